@@ -1,12 +1,13 @@
 //! Utility functions for working with arrow
 
+use std::borrow::Borrow;
 use std::iter::FromIterator;
 use std::sync::Arc;
 
 use arrow::{
     array::{new_null_array, ArrayRef, StringArray},
-    compute::concat_batches,
-    datatypes::SchemaRef,
+    compute::{cast, concat_batches},
+    datatypes::{DataType, SchemaRef},
     error::ArrowError,
     record_batch::RecordBatch,
 };
@@ -24,10 +25,54 @@ where
     RecordBatch::try_from_iter(vec![(field_name, Arc::new(array) as ArrayRef)])
 }
 
+/// How a record batch whose columns differ from an output schema is conformed to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SchemaPolicy {
+    /// Handling of columns whose type differs from their output type.
+    pub type_conflict: TypeConflictPolicy,
+    /// Handling of output columns missing from the batch.
+    pub missing_column: MissingColumnPolicy,
+}
+
+/// Handling of a column whose type differs from its output type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TypeConflictPolicy {
+    /// Return an error.
+    #[default]
+    Error,
+    /// Cast numeric columns to a wider numeric output type that represents all of their values,
+    /// e.g. `Int32` to `Int64` or `Float32` to `Float64`, and return an error for any other
+    /// conflict.
+    WidenNumeric,
+}
+
+/// Handling of an output column missing from a record batch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingColumnPolicy {
+    /// Add the column with all null values.
+    #[default]
+    Null,
+    /// Return an error.
+    Error,
+}
+
 /// Ensures the record batch has the specified schema
+///
+/// Columns missing from the batch are added with all null values, and columns with a different
+/// type than in `output_schema` result in an error.
 pub fn ensure_schema(
     output_schema: &SchemaRef,
     batch: &RecordBatch,
+) -> Result<RecordBatch, ArrowError> {
+    ensure_schema_with_policy(output_schema, batch, SchemaPolicy::default())
+}
+
+/// Ensures the record batch has the specified schema, handling the differences of its columns
+/// according to `policy`.
+pub fn ensure_schema_with_policy(
+    output_schema: &SchemaRef,
+    batch: &RecordBatch,
+    policy: SchemaPolicy,
 ) -> Result<RecordBatch, ArrowError> {
     let batch_schema = batch.schema();
 
@@ -44,35 +89,363 @@ pub fn ensure_schema(
                 .find(|(_, batch_field)| output_field.name() == batch_field.name())
                 .map(|(idx, _)| idx);
 
-            if let Some(batch_field_index) = batch_field_index {
-                // The column available, use it
-                Arc::clone(batch.column(batch_field_index))
-            } else {
-                // the column not available, add it with all null values
-                new_null_array(output_field.data_type(), batch.num_rows())
+            let batch_field_index = match (batch_field_index, policy.missing_column) {
+                (Some(batch_field_index), _) => batch_field_index,
+                (None, MissingColumnPolicy::Null) => {
+                    // the column not available, add it with all null values
+                    return Ok(new_null_array(output_field.data_type(), batch.num_rows()));
+                }
+                (None, MissingColumnPolicy::Error) => {
+                    return Err(ArrowError::SchemaError(format!(
+                        "column '{}' is missing from record batch",
+                        output_field.name()
+                    )));
+                }
+            };
+
+            // The column available, use it
+            let column = batch.column(batch_field_index);
+            let data_type = column.data_type();
+            if data_type == output_field.data_type() {
+                return Ok(Arc::clone(column));
+            }
+            match policy.type_conflict {
+                TypeConflictPolicy::WidenNumeric
+                    if is_numeric_widening(data_type, output_field.data_type()) =>
+                {
+                    cast(column, output_field.data_type())
+                }
+                _ => Err(ArrowError::SchemaError(format!(
+                    "column '{}' has type {} in record batch but {} in output schema",
+                    output_field.name(),
+                    data_type,
+                    output_field.data_type()
+                ))),
             }
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>, _>>()?;
 
     RecordBatch::try_new(Arc::clone(output_schema), batch_output_columns)
 }
 
-/// Merge the record batches into one record batch
-/// and pad null values to columns that are not available in certain batches
+/// Returns true if all values of numeric type `from` are represented exactly by numeric type
+/// `to`.
+fn is_numeric_widening(from: &DataType, to: &DataType) -> bool {
+    use DataType::*;
+
+    matches!(
+        (from, to),
+        (Int8, Int16 | Int32 | Int64)
+            | (Int16, Int32 | Int64)
+            | (Int32, Int64)
+            | (UInt8, UInt16 | UInt32 | UInt64 | Int16 | Int32 | Int64)
+            | (UInt16, UInt32 | UInt64 | Int32 | Int64)
+            | (UInt32, UInt64 | Int64)
+            | (Int8 | Int16 | UInt8 | UInt16, Float32)
+            | (
+                Int8 | Int16 | Int32 | UInt8 | UInt16 | UInt32 | Float32,
+                Float64
+            )
+    )
+}
+
+/// Merge the record batches into record batches of at most `max_rows` rows
+/// and pad null values to columns that are not available in certain batches.
+///
+/// Consecutive small batches are concatenated and batches larger than
+/// `max_rows` are split (without copying), so that merging never needs to
+/// materialise all rows in a single batch. Batches whose columns differ from
+/// `output_schema` are handled according to `policy`, see
+/// [`MergeRecordBatches`] to merge them incrementally.
+///
+/// # Panics
+///
+/// Panics if `max_rows` is zero.
 pub fn merge_record_batches(
     output_schema: &SchemaRef,
     batches: Vec<Arc<RecordBatch>>,
-) -> Result<Option<RecordBatch>, ArrowError> {
-    // Add null values for non-existing columns
-    let batches = batches
-        .iter()
-        .map(|batch| ensure_schema(output_schema, batch.as_ref()))
-        .collect::<Result<Vec<_>, _>>()?;
+    max_rows: usize,
+    policy: SchemaPolicy,
+) -> Result<Vec<RecordBatch>, ArrowError> {
+    MergeRecordBatches::new(Arc::clone(output_schema), batches, max_rows, policy).collect()
+}
+
+/// An iterator merging record batches into record batches of at most
+/// `max_rows` rows of the output schema, see [`merge_record_batches`].
+///
+/// Input batches are only consumed as far as needed to produce the next
+/// output batch. The iterator ends after the first error.
+#[derive(Debug)]
+pub struct MergeRecordBatches<I> {
+    output_schema: SchemaRef,
+    batches: I,
+    max_rows: usize,
+    policy: SchemaPolicy,
+
+    /// The input batch being merged and the offset of its next row.
+    current: Option<(RecordBatch, usize)>,
+
+    /// Whether the input is exhausted or an error was returned.
+    done: bool,
+}
+
+impl<I> MergeRecordBatches<I> {
+    /// Merge `batches` into batches of at most `max_rows` rows of
+    /// `output_schema`, handling differing columns according to `policy`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_rows` is zero.
+    pub fn new<T>(
+        output_schema: SchemaRef,
+        batches: T,
+        max_rows: usize,
+        policy: SchemaPolicy,
+    ) -> Self
+    where
+        T: IntoIterator<IntoIter = I>,
+    {
+        assert!(max_rows > 0, "max_rows must be greater than zero");
+
+        Self {
+            output_schema,
+            batches: batches.into_iter(),
+            max_rows,
+            policy,
+            current: None,
+            done: false,
+        }
+    }
+}
+
+impl<I, B> Iterator for MergeRecordBatches<I>
+where
+    I: Iterator<Item = B>,
+    B: Borrow<RecordBatch>,
+{
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut pending = vec![];
+        let mut pending_rows = 0;
+
+        while !self.done && pending_rows < self.max_rows {
+            let exhausted = self
+                .current
+                .as_ref()
+                .map_or(true, |(batch, offset)| *offset >= batch.num_rows());
+            if exhausted {
+                match self.batches.next() {
+                    Some(batch) => {
+                        match ensure_schema_with_policy(
+                            &self.output_schema,
+                            batch.borrow(),
+                            self.policy,
+                        ) {
+                            Ok(batch) => self.current = Some((batch, 0)),
+                            Err(e) => {
+                                self.done = true;
+                                return Some(Err(e));
+                            }
+                        }
+                    }
+                    None => self.done = true,
+                }
+                continue;
+            }
 
-    // Combine batches
-    if batches.is_empty() {
-        return Ok(None);
+            let (batch, offset) = self.current.as_mut().expect("current batch not exhausted");
+            let len = (batch.num_rows() - *offset).min(self.max_rows - pending_rows);
+            pending.push(batch.slice(*offset, len));
+            pending_rows += len;
+            *offset += len;
+        }
+
+        (pending_rows > 0).then(|| concat_batches(&self.output_schema, &pending))
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{Int32Array, Int64Array},
+        datatypes::{DataType, Field, Schema},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_merge_record_batches() {
+        let batch = |values: Vec<i64>| {
+            Arc::new(
+                RecordBatch::try_from_iter(vec![(
+                    "a",
+                    Arc::new(Int64Array::from(values)) as ArrayRef,
+                )])
+                .unwrap(),
+            )
+        };
+        let batch_b = Arc::new(
+            RecordBatch::try_from_iter(vec![(
+                "b",
+                Arc::new(StringArray::from(vec!["x"])) as ArrayRef,
+            )])
+            .unwrap(),
+        );
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+
+        let merged = merge_record_batches(
+            &output_schema,
+            vec![batch(vec![1, 2]), batch(vec![3, 4, 5, 6, 7]), batch_b],
+            3,
+            SchemaPolicy::default(),
+        )
+        .unwrap();
+
+        let num_rows = merged.iter().map(|b| b.num_rows()).collect::<Vec<_>>();
+        assert_eq!(num_rows, vec![3, 3, 2]);
+        assert!(merged.iter().all(|b| b.schema() == output_schema));
 
-    Ok(Some(concat_batches(output_schema, &batches)?))
+        let expected = vec![
+            "+---+---+",
+            "| a | b |",
+            "+---+---+",
+            "| 1 |   |",
+            "| 2 |   |",
+            "| 3 |   |",
+            "| 4 |   |",
+            "| 5 |   |",
+            "| 6 |   |",
+            "| 7 |   |",
+            "|   | x |",
+            "+---+---+",
+        ];
+        let formatted = crate::display::pretty_format_batches(&merged).unwrap();
+        assert_eq!(formatted.trim().split('\n').collect::<Vec<_>>(), expected);
+
+        assert!(
+            merge_record_batches(&output_schema, vec![], 3, SchemaPolicy::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_merge_record_batches_schema_policy() {
+        let batch_a32 = Arc::new(
+            RecordBatch::try_from_iter(vec![(
+                "a",
+                Arc::new(Int32Array::from(vec![1])) as ArrayRef,
+            )])
+            .unwrap(),
+        );
+        let batch_a64 = Arc::new(
+            RecordBatch::try_from_iter(vec![
+                ("a", Arc::new(Int64Array::from(vec![2])) as ArrayRef),
+                ("b", Arc::new(StringArray::from(vec!["x"])) as ArrayRef),
+            ])
+            .unwrap(),
+        );
+        let batch_a_str = Arc::new(
+            RecordBatch::try_from_iter(vec![(
+                "a",
+                Arc::new(StringArray::from(vec!["3"])) as ArrayRef,
+            )])
+            .unwrap(),
+        );
+        let output_schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let merge = |batches: Vec<Arc<RecordBatch>>, policy| {
+            merge_record_batches(&output_schema, batches, 10, policy)
+        };
+
+        // Type conflicts are errors by default.
+        let err = merge(vec![Arc::clone(&batch_a32)], SchemaPolicy::default()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Schema error: column 'a' has type Int32 in record batch but Int64 in output schema"
+        );
+
+        // Numeric columns are widened on request.
+        let widen = SchemaPolicy {
+            type_conflict: TypeConflictPolicy::WidenNumeric,
+            ..Default::default()
+        };
+        let merged = merge(vec![Arc::clone(&batch_a32), Arc::clone(&batch_a64)], widen).unwrap();
+        let expected = vec![
+            "+---+---+",
+            "| a | b |",
+            "+---+---+",
+            "| 1 |   |",
+            "| 2 | x |",
+            "+---+---+",
+        ];
+        let formatted = crate::display::pretty_format_batches(&merged).unwrap();
+        assert_eq!(formatted.trim().split('\n').collect::<Vec<_>>(), expected);
+
+        // Only numeric types are widened.
+        merge(vec![batch_a_str], widen).unwrap_err();
+
+        // Missing columns are errors on request.
+        let strict = SchemaPolicy {
+            missing_column: MissingColumnPolicy::Error,
+            ..Default::default()
+        };
+        merge(vec![Arc::clone(&batch_a64)], strict).unwrap();
+        let err = merge(vec![batch_a64, batch_a32], strict).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Schema error: column 'b' is missing from record batch"
+        );
+    }
+
+    #[test]
+    fn test_merge_record_batches_iter() {
+        let batch = |values: Vec<i64>| {
+            RecordBatch::try_from_iter(vec![("a", Arc::new(Int64Array::from(values)) as ArrayRef)])
+                .unwrap()
+        };
+        let output_schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, true)]));
+
+        // Input batches are consumed as output batches are requested.
+        let mut consumed = 0;
+        let input = [
+            batch(vec![1, 2]),
+            batch(vec![3, 4]),
+            batch(vec![]),
+            batch(vec![5]),
+        ]
+        .into_iter()
+        .inspect(|_| consumed += 1);
+        let mut merged = MergeRecordBatches::new(
+            Arc::clone(&output_schema),
+            input,
+            3,
+            SchemaPolicy::default(),
+        );
+
+        let got = merged.next().unwrap().unwrap();
+        assert_eq!(got.num_rows(), 3);
+        let got = merged.next().unwrap().unwrap();
+        assert_eq!(got.num_rows(), 2);
+        assert!(merged.next().is_none());
+        drop(merged);
+        assert_eq!(consumed, 4);
+
+        // The iterator ends after an error.
+        let other = Arc::new(Schema::new(vec![Field::new("a", DataType::Utf8, true)]));
+        let mut merged = MergeRecordBatches::new(
+            other,
+            [batch(vec![1]), batch(vec![2])],
+            3,
+            SchemaPolicy::default(),
+        );
+        merged.next().unwrap().unwrap_err();
+        assert!(merged.next().is_none());
+    }
 }
@@ -0,0 +1,77 @@
+//! CLI config for the HTTP access log.
+
+use std::time::Duration;
+
+/// CLI config for the HTTP access log.
+///
+/// The access log is disabled by default. A request is logged if it is slower than the slow
+/// request threshold, if it failed and failed requests are logged, or if it is sampled.
+#[derive(Debug, Clone, Copy, clap::Parser)]
+pub struct AccessLogConfig {
+    /// Fraction of HTTP requests to log, within `[0, 1]`.
+    #[clap(
+        long = "access-log-sample-rate",
+        env = "INFLUXDB_IOX_ACCESS_LOG_SAMPLE_RATE",
+        default_value = "0",
+        value_parser = parse_sample_rate,
+    )]
+    pub sample_rate: f64,
+
+    /// Log every HTTP request taking longer than this to return its response headers.
+    #[clap(
+        long = "access-log-slow-threshold",
+        env = "INFLUXDB_IOX_ACCESS_LOG_SLOW_THRESHOLD",
+        value_parser = humantime::parse_duration,
+    )]
+    pub slow_threshold: Option<Duration>,
+
+    /// Log every HTTP request failing with a client (4xx) or server (5xx) error.
+    #[clap(
+        long = "access-log-errors",
+        env = "INFLUXDB_IOX_ACCESS_LOG_ERRORS",
+        action
+    )]
+    pub log_errors: bool,
+}
+
+/// Parse a sample rate, which must be within `[0, 1]`.
+fn parse_sample_rate(s: &str) -> Result<f64, String> {
+    let rate: f64 = s.parse().map_err(|e| format!("{e}"))?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("{rate} is not within [0, 1]"));
+    }
+    Ok(rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_default() {
+        let config = AccessLogConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(config.sample_rate, 0.0);
+        assert_eq!(config.slow_threshold, None);
+        assert!(!config.log_errors);
+    }
+
+    #[test]
+    fn test_parse() {
+        let config = AccessLogConfig::try_parse_from([
+            "my_binary",
+            "--access-log-sample-rate",
+            "0.01",
+            "--access-log-slow-threshold",
+            "500ms",
+            "--access-log-errors",
+        ])
+        .unwrap();
+        assert_eq!(config.sample_rate, 0.01);
+        assert_eq!(config.slow_threshold, Some(Duration::from_millis(500)));
+        assert!(config.log_errors);
+
+        AccessLogConfig::try_parse_from(["my_binary", "--access-log-sample-rate", "2"])
+            .unwrap_err();
+    }
+}
@@ -0,0 +1,55 @@
+//! Authorization-related configs.
+use std::time::Duration;
+
+/// CLI config for the authorization of requests by the API tokens stored in the catalog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::Parser)]
+pub struct AuthzConfig {
+    /// Require an API token allowing each request, sent in the `authorization` header (e.g.
+    /// `Bearer <token>`, or `Token <token>` for the InfluxDB write API).
+    ///
+    /// Tokens are managed with the `influxdb_iox token` commands, which require a token with
+    /// the admin permission once this is enabled. Create the first admin token with
+    /// `influxdb_iox catalog token create`.
+    ///
+    /// Queries and writes may use tokens scoped to their namespace. The other gRPC APIs,
+    /// including the storage (InfluxRPC) API of the querier, require a token scoped to all
+    /// namespaces.
+    #[clap(long = "authz", env = "INFLUXDB_IOX_AUTHZ", action)]
+    pub authz: bool,
+
+    /// How long the permissions of an API token are cached, e.g. `60s`.
+    ///
+    /// Revoked tokens are accepted for up to this long.
+    #[clap(
+        long = "authz-cache-ttl",
+        env = "INFLUXDB_IOX_AUTHZ_CACHE_TTL",
+        default_value = "60s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub authz_cache_ttl: Duration,
+}
+
+impl AuthzConfig {
+    /// How long the permissions of API tokens are cached, or `None` if authorization is
+    /// disabled.
+    pub fn cache_ttl(&self) -> Option<Duration> {
+        self.authz.then_some(self.authz_cache_ttl)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_default() {
+        let config = AuthzConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(config.cache_ttl(), None);
+
+        let config =
+            AuthzConfig::try_parse_from(["my_binary", "--authz", "--authz-cache-ttl", "5s"])
+                .unwrap();
+        assert_eq!(config.cache_ttl(), Some(Duration::from_secs(5)));
+    }
+}
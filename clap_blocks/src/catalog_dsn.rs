@@ -1,13 +1,15 @@
 //! Catalog-DSN-related configs.
+use crate::object_store::parse_fault_rate;
 use iox_catalog::{
     create_or_get_default_records,
+    fault::{FaultConfig, FaultInjectingCatalog},
     interface::Catalog,
     mem::MemCatalog,
     postgres::{PostgresCatalog, PostgresConnectionOptions},
 };
 use observability_deps::tracing::*;
 use snafu::{OptionExt, ResultExt, Snafu};
-use std::{ops::DerefMut, sync::Arc, time::Duration};
+use std::{ops::DerefMut, path::PathBuf, sync::Arc, time::Duration};
 
 #[derive(Debug, Snafu)]
 #[allow(missing_docs)]
@@ -15,10 +17,28 @@ pub enum Error {
     #[snafu(display("A Postgres connection string in --catalog-dsn is required."))]
     ConnectionStringRequired,
 
+    #[snafu(display("A SQLite connection string in --catalog-dsn is required."))]
+    SqliteConnectionStringRequired,
+
     #[snafu(display("A catalog error occurred: {}", source))]
     Catalog {
         source: iox_catalog::interface::Error,
     },
+
+    #[snafu(display(
+        "The catalog schema is dirty (migration {} failed part way through) and must be repaired \
+         by hand before running `influxdb_iox catalog migrate`",
+        version
+    ))]
+    DirtyCatalogSchema { version: i64 },
+
+    #[snafu(display(
+        "The catalog schema is out of date ({} pending migration(s), first is {}); run \
+         `influxdb_iox catalog migrate` to update it",
+        pending,
+        first_pending
+    ))]
+    CatalogSchemaOutOfDate { pending: usize, first_pending: i64 },
 }
 
 fn default_max_connections() -> &'static str {
@@ -46,7 +66,8 @@ fn default_hotswap_poll_interval_timeout() -> &'static str {
 /// CLI config for catalog DSN.
 #[derive(Debug, Clone, clap::Parser)]
 pub struct CatalogDsnConfig {
-    /// The type of catalog to use. "memory" is only useful for testing purposes.
+    /// The type of catalog to use. "memory" is only useful for testing purposes. "sqlite" is
+    /// meant for single-node deployments and requires the `sqlite` feature.
     #[clap(
         value_enum,
         long = "catalog",
@@ -56,7 +77,8 @@ pub struct CatalogDsnConfig {
     )]
     pub(crate) catalog_type_: CatalogType,
 
-    /// Postgres connection string. Required if catalog is set to postgres.
+    /// Catalog connection string. Required if catalog is set to postgres or sqlite, e.g.
+    /// `postgres://...` or `sqlite:///path/to/catalog.sqlite`.
     #[clap(long = "catalog-dsn", env = "INFLUXDB_IOX_CATALOG_DSN", action)]
     pub dsn: Option<String>,
 
@@ -107,6 +129,32 @@ pub struct CatalogDsnConfig {
         value_parser = humantime::parse_duration,
     )]
     pub hotswap_poll_interval: Duration,
+
+    /// Probability in `[0, 1]` that a catalog operation fails with an injected
+    /// error, for exercising recovery paths in tests.
+    #[clap(
+        long = "catalog-fault-error-rate",
+        env = "INFLUXDB_IOX_CATALOG_FAULT_ERROR_RATE",
+        default_value = "0",
+        value_parser = parse_fault_rate,
+        hide = true
+    )]
+    pub fault_error_rate: f64,
+
+    /// Latency added to every catalog operation, for exercising recovery paths
+    /// in tests.
+    #[clap(
+        long = "catalog-fault-latency",
+        env = "INFLUXDB_IOX_CATALOG_FAULT_LATENCY",
+        value_parser = humantime::parse_duration,
+        hide = true
+    )]
+    pub fault_latency: Option<Duration>,
+
+    /// File the schema of an in-memory catalog is loaded from on startup and saved to on
+    /// shutdown, see [`with_memory_snapshot_path`](Self::with_memory_snapshot_path).
+    #[clap(skip)]
+    pub(crate) memory_snapshot_path: Option<PathBuf>,
 }
 
 /// Catalog type.
@@ -117,6 +165,9 @@ pub enum CatalogType {
 
     /// In-memory.
     Memory,
+
+    /// SQLite.
+    Sqlite,
 }
 
 impl CatalogDsnConfig {
@@ -133,6 +184,9 @@ impl CatalogDsnConfig {
             connect_timeout: PostgresConnectionOptions::DEFAULT_CONNECT_TIMEOUT,
             idle_timeout: PostgresConnectionOptions::DEFAULT_IDLE_TIMEOUT,
             hotswap_poll_interval: PostgresConnectionOptions::DEFAULT_HOTSWAP_POLL_INTERVAL,
+            fault_error_rate: 0.0,
+            fault_latency: None,
+            memory_snapshot_path: None,
         }
     }
 
@@ -148,6 +202,19 @@ impl CatalogDsnConfig {
             connect_timeout: PostgresConnectionOptions::DEFAULT_CONNECT_TIMEOUT,
             idle_timeout: PostgresConnectionOptions::DEFAULT_IDLE_TIMEOUT,
             hotswap_poll_interval: PostgresConnectionOptions::DEFAULT_HOTSWAP_POLL_INTERVAL,
+            fault_error_rate: 0.0,
+            fault_latency: None,
+            memory_snapshot_path: None,
+        }
+    }
+
+    /// Keep the schema of an in-memory catalog in the snapshot file at `path` across restarts.
+    ///
+    /// The catalog only writes the snapshot when it is [shut down](Catalog::shutdown).
+    pub fn with_memory_snapshot_path(self, path: Option<PathBuf>) -> Self {
+        Self {
+            memory_snapshot_path: path,
+            ..self
         }
     }
 
@@ -179,7 +246,13 @@ impl CatalogDsnConfig {
                 ) as Arc<dyn Catalog>
             }
             CatalogType::Memory => {
-                let mem = MemCatalog::new(metrics);
+                let mem = match &self.memory_snapshot_path {
+                    Some(path) => {
+                        info!(path=%path.display(), "loading in-memory catalog snapshot");
+                        MemCatalog::with_snapshot(metrics, path).context(CatalogSnafu)?
+                    }
+                    None => MemCatalog::new(metrics),
+                };
 
                 let mut txn = mem.start_transaction().await.context(CatalogSnafu)?;
                 create_or_get_default_records(1, txn.deref_mut())
@@ -189,8 +262,82 @@ impl CatalogDsnConfig {
 
                 Arc::new(mem) as Arc<dyn Catalog>
             }
+            CatalogType::Sqlite => {
+                let dsn = self
+                    .dsn
+                    .as_ref()
+                    .context(SqliteConnectionStringRequiredSnafu)?;
+                new_sqlite(dsn, self.max_catalog_connections, metrics).await?
+            }
         };
 
+        let faults = self.fault_config();
+        if !faults.is_enabled() {
+            return Ok(catalog);
+        }
+
+        warn!(?faults, "injecting faults into catalog operations");
+        Ok(Arc::new(FaultInjectingCatalog::new(catalog, faults)))
+    }
+
+    /// The faults to inject into catalog operations.
+    pub fn fault_config(&self) -> FaultConfig {
+        let config = FaultConfig::default().with_error_rate(self.fault_error_rate);
+
+        match self.fault_latency {
+            Some(latency) => config.with_latency(latency),
+            None => config,
+        }
+    }
+
+    /// Like [`get_catalog`](Self::get_catalog), but refuses to return a catalog whose schema
+    /// is dirty or has pending migrations.
+    ///
+    /// Servers use this at startup so that they never run against a schema they do not
+    /// understand; migrations are applied explicitly via `influxdb_iox catalog migrate`.
+    pub async fn get_up_to_date_catalog(
+        &self,
+        app_name: &'static str,
+        metrics: Arc<metric::Registry>,
+    ) -> Result<Arc<dyn Catalog>, Error> {
+        let catalog = self.get_catalog(app_name, metrics).await?;
+
+        let status = catalog.migration_status().await.context(CatalogSnafu)?;
+        if let Some(version) = status.dirty {
+            return DirtyCatalogSchemaSnafu { version }.fail();
+        }
+        if let Some(first) = status.pending.first() {
+            return CatalogSchemaOutOfDateSnafu {
+                pending: status.pending.len(),
+                first_pending: first.version,
+            }
+            .fail();
+        }
+
         Ok(catalog)
     }
 }
+
+#[cfg(feature = "sqlite")]
+async fn new_sqlite(
+    dsn: &str,
+    max_conns: u32,
+    metrics: Arc<metric::Registry>,
+) -> Result<Arc<dyn Catalog>, Error> {
+    use iox_catalog::sqlite::{SqliteCatalog, SqliteConnectionOptions};
+
+    let options = SqliteConnectionOptions {
+        dsn: dsn.to_string(),
+        max_conns,
+    };
+    Ok(Arc::new(
+        SqliteCatalog::connect(options, metrics)
+            .await
+            .context(CatalogSnafu)?,
+    ))
+}
+
+#[cfg(not(feature = "sqlite"))]
+async fn new_sqlite(_: &str, _: u32, _: Arc<metric::Registry>) -> Result<Arc<dyn Catalog>, Error> {
+    panic!("SQLite catalog support not enabled, recompile with the sqlite feature enabled")
+}
@@ -84,6 +84,18 @@ macro_rules! gen_compactor_config {
             )]
             pub split_percentage: u16,
 
+            /// Max size of compacted parquet files.
+            /// If set, compacted data estimated to be larger than this is split into more files,
+            /// taking the size and time range of each compacted file into account, e.g. when a
+            /// few large files are compacted with many small ones. Like
+            /// `compaction-max-desired-size-bytes`, this is a target rather than a guarantee.
+            #[clap(
+                long = "compaction-max-output-file-size-bytes",
+                env = "INFLUXDB_IOX_COMPACTION_MAX_OUTPUT_FILE_SIZE_BYTES",
+                action
+            )]
+            pub max_output_file_size_bytes: Option<u64>,
+
             /// Max number of partitions per shard we want to compact per cycle
             /// Default: 1
             #[clap(
@@ -208,6 +220,29 @@ macro_rules! gen_compactor_config {
                 action
             )]
             pub hot_compaction_hours_threshold_2: u64,
+
+            /// Tag columns to write bloom filters for into the parquet files written by the
+            /// compactor, so that queries can skip files that do not hold the value of an equality
+            /// predicate on the column. Only worth it for tags with many distinct values.
+            #[clap(
+                long = "compaction-bloom-filter-columns",
+                env = "INFLUXDB_IOX_COMPACTION_BLOOM_FILTER_COLUMNS",
+                use_value_delimiter = true,
+                action = clap::ArgAction::Append
+            )]
+            pub bloom_filter_columns: Vec<String>,
+
+            /// Tag columns whose values the compactor indexes per partition, next to the parquet
+            /// files of the partition, so that queries can skip the files that do not hold the
+            /// value of an equality predicate on the column without reading them. Only worth it
+            /// for tags with many distinct values.
+            #[clap(
+                long = "compaction-tag-index-columns",
+                env = "INFLUXDB_IOX_COMPACTION_TAG_INDEX_COLUMNS",
+                use_value_delimiter = true,
+                action = clap::ArgAction::Append
+            )]
+            pub tag_index_columns: Vec<String>,
         }
     };
 }
@@ -227,6 +262,7 @@ impl CompactorOnceConfig {
             max_desired_file_size_bytes: self.max_desired_file_size_bytes,
             percentage_max_file_size: self.percentage_max_file_size,
             split_percentage: self.split_percentage,
+            max_output_file_size_bytes: self.max_output_file_size_bytes,
             max_number_partitions_per_shard: self.max_number_partitions_per_shard,
             min_number_recent_ingested_files_per_partition: self
                 .min_number_recent_ingested_files_per_partition,
@@ -239,6 +275,8 @@ impl CompactorOnceConfig {
             minutes_without_new_writes_to_be_cold: self.minutes_without_new_writes_to_be_cold,
             hot_compaction_hours_threshold_1: self.hot_compaction_hours_threshold_1,
             hot_compaction_hours_threshold_2: self.hot_compaction_hours_threshold_2,
+            bloom_filter_columns: self.bloom_filter_columns,
+            tag_index_columns: self.tag_index_columns,
         }
     }
 }
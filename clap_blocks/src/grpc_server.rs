@@ -0,0 +1,131 @@
+//! CLI config for the gRPC server.
+
+use std::time::Duration;
+
+/// CLI config for the HTTP/2 and TCP settings of the gRPC server.
+///
+/// Unset options keep the defaults of the underlying HTTP/2 implementation, whose 64KiB flow
+/// control windows throttle large Flight responses.
+#[derive(Debug, Clone, Copy, clap::Parser)]
+pub struct GrpcServerConfig {
+    /// Interval of the HTTP/2 pings sent to clients to keep idle connections alive, e.g. "30s".
+    ///
+    /// Disabled unless set.
+    #[clap(
+        long = "grpc-keepalive-interval",
+        env = "INFLUXDB_IOX_GRPC_KEEPALIVE_INTERVAL",
+        value_parser = humantime::parse_duration,
+    )]
+    pub keepalive_interval: Option<Duration>,
+
+    /// How long to wait for the acknowledgement of a keepalive ping before closing the
+    /// connection, e.g. "20s".
+    ///
+    /// Only used if `--grpc-keepalive-interval` is set.
+    #[clap(
+        long = "grpc-keepalive-timeout",
+        env = "INFLUXDB_IOX_GRPC_KEEPALIVE_TIMEOUT",
+        value_parser = humantime::parse_duration,
+    )]
+    pub keepalive_timeout: Option<Duration>,
+
+    /// Maximum number of concurrent requests (HTTP/2 streams) per client connection.
+    #[clap(
+        long = "grpc-max-concurrent-streams",
+        env = "INFLUXDB_IOX_GRPC_MAX_CONCURRENT_STREAMS",
+        action
+    )]
+    pub max_concurrent_streams: Option<u32>,
+
+    /// Maximum size of the HTTP/2 frames the server accepts, between 16KiB and 16MiB.
+    #[clap(
+        long = "grpc-max-frame-size",
+        env = "INFLUXDB_IOX_GRPC_MAX_FRAME_SIZE",
+        value_parser = clap::value_parser!(u32).range(16_384..=16_777_215),
+    )]
+    pub max_frame_size: Option<u32>,
+
+    /// Size of the HTTP/2 flow control window of a single request, i.e. how many bytes of a
+    /// message may be in flight.
+    #[clap(
+        long = "grpc-initial-stream-window-size",
+        env = "INFLUXDB_IOX_GRPC_INITIAL_STREAM_WINDOW_SIZE",
+        action
+    )]
+    pub initial_stream_window_size: Option<u32>,
+
+    /// Size of the HTTP/2 flow control window shared by all requests of a connection.
+    #[clap(
+        long = "grpc-initial-connection-window-size",
+        env = "INFLUXDB_IOX_GRPC_INITIAL_CONNECTION_WINDOW_SIZE",
+        action
+    )]
+    pub initial_connection_window_size: Option<u32>,
+
+    /// Disable Nagle's algorithm on client connections, sending small responses without delay.
+    #[clap(
+        long = "grpc-tcp-nodelay",
+        env = "INFLUXDB_IOX_GRPC_TCP_NODELAY",
+        default_value = "true",
+        action = clap::ArgAction::Set,
+    )]
+    pub tcp_nodelay: bool,
+}
+
+impl Default for GrpcServerConfig {
+    fn default() -> Self {
+        Self {
+            keepalive_interval: None,
+            keepalive_timeout: None,
+            max_concurrent_streams: None,
+            max_frame_size: None,
+            initial_stream_window_size: None,
+            initial_connection_window_size: None,
+            tcp_nodelay: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_default() {
+        let config = GrpcServerConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(config.keepalive_interval, None);
+        assert_eq!(config.max_frame_size, None);
+        assert!(config.tcp_nodelay);
+    }
+
+    #[test]
+    fn test_parse() {
+        let config = GrpcServerConfig::try_parse_from([
+            "my_binary",
+            "--grpc-keepalive-interval",
+            "30s",
+            "--grpc-keepalive-timeout",
+            "20s",
+            "--grpc-max-concurrent-streams",
+            "100",
+            "--grpc-max-frame-size",
+            "1048576",
+            "--grpc-initial-stream-window-size",
+            "8388608",
+            "--grpc-tcp-nodelay",
+            "false",
+        ])
+        .unwrap();
+        assert_eq!(config.keepalive_interval, Some(Duration::from_secs(30)));
+        assert_eq!(config.keepalive_timeout, Some(Duration::from_secs(20)));
+        assert_eq!(config.max_concurrent_streams, Some(100));
+        assert_eq!(config.max_frame_size, Some(1_048_576));
+        assert_eq!(config.initial_stream_window_size, Some(8_388_608));
+        assert_eq!(config.initial_connection_window_size, None);
+        assert!(!config.tcp_nodelay);
+
+        GrpcServerConfig::try_parse_from(["my_binary", "--grpc-max-frame-size", "1024"])
+            .unwrap_err();
+    }
+}
@@ -1,5 +1,7 @@
 //! CLI config for catalog ingest lifecycle
 
+use std::num::NonZeroUsize;
+
 /// CLI config for catalog ingest lifecycle
 #[derive(Debug, Clone, clap::Parser)]
 #[allow(missing_copy_implementations)]
@@ -20,25 +22,128 @@ pub struct IngesterConfig {
     )]
     pub shard_index_range_end: i32,
 
+    /// Allow the set of shards this ingester consumes to be changed at runtime
+    /// through the shard assignment gRPC API, instead of only at startup.
+    ///
+    /// When enabled, the ingester connects to all the shards of the write
+    /// buffer topic so that any of them can be assigned to it later. The
+    /// shard index range is only the initial assignment.
+    #[clap(
+        long = "shard-reassignment",
+        env = "INFLUXDB_IOX_SHARD_REASSIGNMENT",
+        action
+    )]
+    pub shard_reassignment: bool,
+
+    /// Run as one of several ingesters consuming the same shards.
+    ///
+    /// All the ingesters buffer the data of the shards, but only the holder
+    /// of a shard's lease in the catalog persists it. The others act as
+    /// standbys, releasing their buffered data once the leader persisted it,
+    /// and take over persistence from their own buffer if the leader fails.
+    #[clap(long = "shard-leases", env = "INFLUXDB_IOX_SHARD_LEASES", action)]
+    pub shard_leases: bool,
+
+    /// Identity of this ingester in the shard leases it holds, unique among
+    /// the ingesters consuming the same shards. Random if not set.
+    #[clap(
+        long = "shard-lease-holder",
+        env = "INFLUXDB_IOX_SHARD_LEASE_HOLDER",
+        action
+    )]
+    pub shard_lease_holder: Option<String>,
+
+    /// Duration of the shard leases, in seconds. A standby takes over a
+    /// shard at most this long after its leader stops renewing the lease.
+    #[clap(
+        long = "shard-lease-duration-seconds",
+        env = "INFLUXDB_IOX_SHARD_LEASE_DURATION_SECONDS",
+        default_value = "30",
+        action
+    )]
+    pub shard_lease_duration_seconds: u64,
+
+    /// Time a standby waits for the shard leader to persist data it buffered,
+    /// in seconds, before persisting the data itself.
+    #[clap(
+        long = "standby-persist-timeout-seconds",
+        env = "INFLUXDB_IOX_STANDBY_PERSIST_TIMEOUT_SECONDS",
+        default_value = "600",
+        action
+    )]
+    pub standby_persist_timeout_seconds: u64,
+
+    /// Record the jobs of this ingester, e.g. the persistence of partitions,
+    /// as operations of this node in the catalog. The operations are served by
+    /// the gRPC operations service, and the jobs running when the ingester
+    /// stopped are reported as interrupted after its restart.
+    ///
+    /// The node name must be unique among the ingesters and stable across
+    /// restarts. Jobs are not recorded if not set.
+    #[clap(long = "job-node", env = "INFLUXDB_IOX_JOB_NODE", action)]
+    pub job_node: Option<String>,
+
+    /// The address the queriers use to reach this ingester, exactly as in
+    /// their shard to ingester mapping.
+    ///
+    /// When set, the ingester records its drain state in the catalog under
+    /// this address and serves the drain gRPC API, which persists all its data
+    /// and hands off its shards before a restart. Queriers stop querying a
+    /// drained ingester, and avoid a draining one if another replica of its
+    /// shards is available.
+    #[clap(
+        long = "advertise-address",
+        env = "INFLUXDB_IOX_INGESTER_ADVERTISE_ADDRESS",
+        action
+    )]
+    pub advertise_address: Option<String>,
+
+    /// Consume the shards assigned to this ingester in the catalog, instead of
+    /// a static shard index range.
+    ///
+    /// The ingesters of a topic register in the catalog under their
+    /// `--advertise-address` and balance its shards among themselves,
+    /// rebalancing when an ingester is added, stops sending heartbeats or is
+    /// drained. The ingester connects to all the shards of the topic, and the
+    /// shard index range is only the initial assignment.
+    ///
+    /// Implies `--shard-leases`, held under the `--advertise-address`. A
+    /// moved shard is only assigned to its new ingester once the previous one
+    /// persisted its data and released its lease.
+    #[clap(
+        long = "catalog-shard-assignment",
+        env = "INFLUXDB_IOX_CATALOG_SHARD_ASSIGNMENT",
+        requires = "advertise_address",
+        conflicts_with = "shard_lease_holder",
+        action
+    )]
+    pub catalog_shard_assignment: bool,
+
     /// The ingester will continue to pull data and buffer it from the write buffer as long as the
     /// ingester buffer is below this size. If the ingester buffer hits this size, ingest from the
     /// write buffer will pause until the ingester buffer goes below this threshold.
+    ///
+    /// If not specified, defaults to half of the memory available to the ingester, i.e. the
+    /// memory limit of its container.
     #[clap(
         long = "pause-ingest-size-bytes",
         env = "INFLUXDB_IOX_PAUSE_INGEST_SIZE_BYTES",
         action
     )]
-    pub pause_ingest_size_bytes: usize,
+    pub pause_ingest_size_bytes: Option<usize>,
 
     /// Once the ingester crosses this threshold of data buffered across all shards, it will
     /// pick the largest partitions and persist them until it falls below this threshold. An
     /// ingester running in a steady state is expected to take up this much memory.
+    ///
+    /// If not specified, defaults to 30% of the memory available to the ingester, i.e. the
+    /// memory limit of its container.
     #[clap(
         long = "persist-memory-threshold-bytes",
         env = "INFLUXDB_IOX_PERSIST_MEMORY_THRESHOLD_BYTES",
         action
     )]
-    pub persist_memory_threshold_bytes: usize,
+    pub persist_memory_threshold_bytes: Option<usize>,
 
     /// If the total bytes written to an individual partition crosses
     /// this size threshold, it will be persisted.  The default value
@@ -84,6 +189,27 @@ pub struct IngesterConfig {
     )]
     pub persist_partition_rows_max: usize,
 
+    /// The maximum number of partitions the ingester persists at the same time.
+    #[clap(
+        long = "persist-max-concurrency",
+        env = "INFLUXDB_IOX_PERSIST_MAX_CONCURRENCY",
+        default_value = "10",
+        action
+    )]
+    pub persist_max_concurrency: NonZeroUsize,
+
+    /// The maximum number of buffered bytes of the partitions the ingester persists at the same
+    /// time, bounding the memory used to build their parquet files. A partition larger than this
+    /// budget is persisted on its own.
+    ///
+    /// If not specified, only the number of concurrent persists is bounded.
+    #[clap(
+        long = "persist-memory-budget-bytes",
+        env = "INFLUXDB_IOX_PERSIST_MEMORY_BUDGET_BYTES",
+        action
+    )]
+    pub persist_memory_budget_bytes: Option<NonZeroUsize>,
+
     /// If the catalog's max sequence number for the partition is no longer available in the write
     /// buffer due to the retention policy, by default the ingester will panic. If this flag is
     /// specified, the ingester will skip any sequence numbers that have not been retained in the
@@ -115,4 +241,92 @@ pub struct IngesterConfig {
         action
     )]
     pub concurrent_request_limit: usize,
+
+    /// Sort the data of a partition on its sort key before building the plan
+    /// that persists it, so that the plan only deduplicates it.
+    ///
+    /// This only affects persistence: the buffered data that queries read is
+    /// not sorted, and is not reported as sorted to the querier.
+    #[clap(long = "presort-persist", env = "INFLUXDB_IOX_PRESORT_PERSIST", action)]
+    pub presort_persist: bool,
+
+    /// Maximum number of rows per row group of the parquet files written when
+    /// persisting.
+    ///
+    /// Should be a multiple of the query batch size (8192).
+    #[clap(
+        long = "persist-row-group-size",
+        env = "INFLUXDB_IOX_PERSIST_ROW_GROUP_SIZE",
+        default_value = "1048576",
+        action
+    )]
+    pub persist_row_group_size: NonZeroUsize,
+
+    /// Best effort maximum size in bytes of the data pages of the parquet
+    /// files written when persisting. Uses the parquet default if not set.
+    #[clap(
+        long = "persist-data-page-size",
+        env = "INFLUXDB_IOX_PERSIST_DATA_PAGE_SIZE",
+        action
+    )]
+    pub persist_data_page_size: Option<usize>,
+
+    /// Compression codec of the parquet files written when persisting.
+    #[clap(
+        value_enum,
+        long = "persist-compression",
+        env = "INFLUXDB_IOX_PERSIST_COMPRESSION",
+        default_value = "zstd",
+        action
+    )]
+    pub persist_compression: ParquetCompression,
+
+    /// Do not dictionary encode tag columns of the parquet files written when
+    /// persisting.
+    #[clap(
+        long = "persist-disable-tag-dictionary",
+        env = "INFLUXDB_IOX_PERSIST_DISABLE_TAG_DICTIONARY",
+        action
+    )]
+    pub persist_disable_tag_dictionary: bool,
+
+    /// Do not dictionary encode field columns of the parquet files written
+    /// when persisting.
+    #[clap(
+        long = "persist-disable-field-dictionary",
+        env = "INFLUXDB_IOX_PERSIST_DISABLE_FIELD_DICTIONARY",
+        action
+    )]
+    pub persist_disable_field_dictionary: bool,
+
+    /// Tag columns to write bloom filters for into the parquet files written
+    /// when persisting, so that queries can skip files that do not hold the
+    /// value of an equality predicate on the column. Only worth it for tags
+    /// with many distinct values.
+    #[clap(
+        long = "persist-bloom-filter-columns",
+        env = "INFLUXDB_IOX_PERSIST_BLOOM_FILTER_COLUMNS",
+        use_value_delimiter = true,
+        action = clap::ArgAction::Append
+    )]
+    pub persist_bloom_filter_columns: Vec<String>,
+}
+
+/// Compression codec of parquet files.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum ParquetCompression {
+    /// No compression.
+    Uncompressed,
+
+    /// Snappy.
+    Snappy,
+
+    /// Gzip.
+    Gzip,
+
+    /// LZ4.
+    Lz4,
+
+    /// Zstandard.
+    Zstd,
 }
@@ -12,11 +12,17 @@
     clippy::todo,
     clippy::dbg_macro
 )]
+pub mod access_log;
+pub mod authz;
 pub mod catalog_dsn;
 pub mod compactor;
+pub mod grpc_server;
 pub mod ingester;
+pub mod metrics_push;
 pub mod object_store;
 pub mod querier;
+pub mod query_gateway;
+pub mod router;
 pub mod run_config;
 pub mod socket_addr;
 pub mod write_buffer;
@@ -0,0 +1,129 @@
+//! CLI config for pushing metrics to a Prometheus pushgateway.
+
+use std::time::Duration;
+
+/// CLI config for periodically pushing the metrics to a Prometheus pushgateway.
+///
+/// This is meant for environments that cannot scrape the `/metrics` endpoint of the server, and
+/// is disabled unless a pushgateway URL is specified.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct MetricsPushConfig {
+    /// URL of a Prometheus pushgateway to periodically push the metrics to, e.g.
+    /// "http://pushgateway:9091".
+    #[clap(
+        long = "metrics-push-gateway-url",
+        env = "INFLUXDB_IOX_METRICS_PUSH_GATEWAY_URL",
+        action
+    )]
+    pub gateway_url: Option<String>,
+
+    /// How often to push the metrics to the pushgateway.
+    #[clap(
+        long = "metrics-push-interval",
+        env = "INFLUXDB_IOX_METRICS_PUSH_INTERVAL",
+        default_value = "15s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub interval: Duration,
+
+    /// The job the metrics are pushed for, which becomes their `job` label.
+    #[clap(
+        long = "metrics-push-job",
+        env = "INFLUXDB_IOX_METRICS_PUSH_JOB",
+        default_value = "influxdb_iox",
+        value_parser = parse_label_value,
+    )]
+    pub job: String,
+
+    /// The instance the metrics are pushed for, which becomes their `instance` label.
+    ///
+    /// Servers pushing to the same pushgateway must set distinct instances, otherwise they
+    /// overwrite each other's metrics.
+    #[clap(
+        long = "metrics-push-instance",
+        env = "INFLUXDB_IOX_METRICS_PUSH_INSTANCE",
+        value_parser = parse_label_value,
+    )]
+    pub instance: Option<String>,
+}
+
+impl Default for MetricsPushConfig {
+    fn default() -> Self {
+        Self {
+            gateway_url: None,
+            interval: Duration::from_secs(15),
+            job: "influxdb_iox".to_string(),
+            instance: None,
+        }
+    }
+}
+
+impl MetricsPushConfig {
+    /// The URL of the metrics group the metrics are pushed to, or `None` if pushing is disabled.
+    pub fn push_url(&self) -> Option<String> {
+        let gateway_url = self.gateway_url.as_ref()?;
+        let mut url = format!(
+            "{}/metrics/job/{}",
+            gateway_url.trim_end_matches('/'),
+            self.job
+        );
+        if let Some(instance) = &self.instance {
+            url.push_str("/instance/");
+            url.push_str(instance);
+        }
+        Some(url)
+    }
+}
+
+/// Parse a grouping label value, which becomes a segment of the push URL.
+fn parse_label_value(s: &str) -> Result<String, String> {
+    if s.is_empty() || s.contains('/') {
+        return Err(format!("{s:?} must be non-empty and must not contain '/'"));
+    }
+    Ok(s.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_default() {
+        let config = MetricsPushConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(config.push_url(), None);
+        assert_eq!(config.interval, Duration::from_secs(15));
+    }
+
+    #[test]
+    fn test_push_url() {
+        let config = MetricsPushConfig::try_parse_from([
+            "my_binary",
+            "--metrics-push-gateway-url",
+            "http://pushgateway:9091/",
+        ])
+        .unwrap();
+        assert_eq!(
+            config.push_url().as_deref(),
+            Some("http://pushgateway:9091/metrics/job/influxdb_iox")
+        );
+
+        let config = MetricsPushConfig::try_parse_from([
+            "my_binary",
+            "--metrics-push-gateway-url",
+            "http://pushgateway:9091",
+            "--metrics-push-job",
+            "router",
+            "--metrics-push-instance",
+            "router-0",
+        ])
+        .unwrap();
+        assert_eq!(
+            config.push_url().as_deref(),
+            Some("http://pushgateway:9091/metrics/job/router/instance/router-0")
+        );
+
+        MetricsPushConfig::try_parse_from(["my_binary", "--metrics-push-instance", "a/b"])
+            .unwrap_err();
+    }
+}
@@ -5,6 +5,7 @@ use object_store::memory::InMemory;
 use object_store::path::Path;
 use object_store::throttle::ThrottledStore;
 use object_store::{throttle::ThrottleConfig, DynObjectStore};
+use object_store_metrics::fault::{FaultConfig, FaultInjectingObjectStore};
 use observability_deps::tracing::{info, warn};
 use snafu::{ResultExt, Snafu};
 use std::sync::Arc;
@@ -203,6 +204,38 @@ pub struct ObjectStoreConfig {
         action
     )]
     pub object_store_connection_limit: NonZeroUsize,
+
+    /// Probability in `[0, 1]` that an object store operation fails with an
+    /// injected error, for exercising recovery paths in tests.
+    #[clap(
+        long = "object-store-fault-error-rate",
+        env = "INFLUXDB_IOX_OBJECT_STORE_FAULT_ERROR_RATE",
+        default_value = "0",
+        value_parser = parse_fault_rate,
+        hide = true
+    )]
+    pub object_store_fault_error_rate: f64,
+
+    /// Probability in `[0, 1]` that an object store put only writes part of
+    /// its payload before failing, for exercising recovery paths in tests.
+    #[clap(
+        long = "object-store-fault-truncation-rate",
+        env = "INFLUXDB_IOX_OBJECT_STORE_FAULT_TRUNCATION_RATE",
+        default_value = "0",
+        value_parser = parse_fault_rate,
+        hide = true
+    )]
+    pub object_store_fault_truncation_rate: f64,
+
+    /// Latency added to every object store operation, for exercising recovery
+    /// paths in tests.
+    #[clap(
+        long = "object-store-fault-latency",
+        env = "INFLUXDB_IOX_OBJECT_STORE_FAULT_LATENCY",
+        value_parser = humantime::parse_duration,
+        hide = true
+    )]
+    pub object_store_fault_latency: Option<Duration>,
 }
 
 impl ObjectStoreConfig {
@@ -229,8 +262,32 @@ impl ObjectStoreConfig {
             google_service_account: Default::default(),
             object_store,
             object_store_connection_limit: NonZeroUsize::new(16).unwrap(),
+            object_store_fault_error_rate: 0.0,
+            object_store_fault_truncation_rate: 0.0,
+            object_store_fault_latency: None,
         }
     }
+
+    /// The faults to inject into object store operations.
+    pub fn fault_config(&self) -> FaultConfig {
+        let config = FaultConfig::default()
+            .with_error_rate(self.object_store_fault_error_rate)
+            .with_truncation_rate(self.object_store_fault_truncation_rate);
+
+        match self.object_store_fault_latency {
+            Some(latency) => config.with_latency(latency),
+            None => config,
+        }
+    }
+}
+
+/// Parse a fault injection probability, which must be within `[0, 1]`.
+pub(crate) fn parse_fault_rate(s: &str) -> Result<f64, String> {
+    let rate: f64 = s.parse().map_err(|e| format!("{e}"))?;
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(format!("{rate} is not within [0, 1]"));
+    }
+    Ok(rate)
 }
 
 /// Object-store type.
@@ -354,6 +411,21 @@ fn new_azure(_: &ObjectStoreConfig) -> Result<Arc<DynObjectStore>, ParseError> {
 
 /// Create config-dependant object store.
 pub fn make_object_store(config: &ObjectStoreConfig) -> Result<Arc<DynObjectStore>, ParseError> {
+    let object_store = new_object_store(config)?;
+
+    let faults = config.fault_config();
+    if !faults.is_enabled() {
+        return Ok(object_store);
+    }
+
+    warn!(?faults, "injecting faults into object store operations");
+    Ok(Arc::new(FaultInjectingObjectStore::new(
+        object_store,
+        faults,
+    )))
+}
+
+fn new_object_store(config: &ObjectStoreConfig) -> Result<Arc<DynObjectStore>, ParseError> {
     if let Some(data_dir) = &config.database_directory {
         if !matches!(&config.object_store, Some(ObjectStoreType::File)) {
             warn!(?data_dir, object_store_type=?config.object_store,
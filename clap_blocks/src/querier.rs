@@ -1,8 +1,16 @@
 //! Querier-related configs.
 use data_types::{IngesterMapping, ShardIndex};
+use object_store_metrics::hedge::HedgeConfig;
 use serde::Deserialize;
 use snafu::{ResultExt, Snafu};
-use std::{collections::HashMap, fs, io, path::PathBuf, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    num::NonZeroUsize,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
 
 #[derive(Debug, Snafu)]
 #[allow(missing_docs)]
@@ -36,14 +44,30 @@ pub enum Error {
         shard_index: ShardIndex,
         name: Arc<str>,
     },
+
+    #[snafu(display("Could not read read policy file `{}`: {source}", file.display()))]
+    ReadPolicyFileReading { source: io::Error, file: PathBuf },
+
+    #[snafu(display("Could not deserialize JSON from read policy file: {source}"))]
+    ReadPolicyDeserializing { source: serde_json::Error },
+
+    #[snafu(display("`--write-slo-namespace` requires `--router-http-address`"))]
+    WriteSloRequiresRouter,
+
+    #[snafu(display("`--read-replica` can not be combined with a shard to ingesters mapping"))]
+    ReadReplicaWithIngesters,
 }
 
+/// Allowed tag values by tag name, by identity, by namespace name. See `--read-policy-file`.
+pub type ReadPolicyConfig = HashMap<String, HashMap<String, BTreeMap<String, Vec<String>>>>;
+
 /// CLI config for querier configuration
 #[derive(Debug, Clone, PartialEq, Eq, clap::Parser)]
 pub struct QuerierConfig {
     /// The number of threads to use for queries.
     ///
-    /// If not specified, defaults to the number of cores on the system
+    /// If not specified, defaults to the number of CPUs available to the querier, i.e. the CPU
+    /// limit of its container.
     #[clap(
         long = "num-query-threads",
         env = "INFLUXDB_IOX_NUM_QUERY_THREADS",
@@ -96,7 +120,14 @@ pub struct QuerierConfig {
     ///       // If this is `null`, queries to this shard will error.
     ///       //
     ///       // default: null
-    ///       "ingester": "i1"
+    ///       "ingester": "i1",
+    ///
+    ///       // Names of further ingesters from the `ingester` mapping that hold a replica of
+    ///       // the unpersisted data of this shard. The querier queries all replicas, uses the
+    ///       // most recent data of each partition and tolerates one replica being unavailable.
+    ///       //
+    ///       // default: []
+    ///       "replicas": []
     ///     },
     ///     "2": {
     ///       "ingester": "i1"
@@ -165,7 +196,14 @@ pub struct QuerierConfig {
     ///       // If this is `null`, queries to this shard will error.
     ///       //
     ///       // default: null
-    ///       "ingester": "i1"
+    ///       "ingester": "i1",
+    ///
+    ///       // Names of further ingesters from the `ingester` mapping that hold a replica of
+    ///       // the unpersisted data of this shard. The querier queries all replicas, uses the
+    ///       // most recent data of each partition and tolerates one replica being unavailable.
+    ///       //
+    ///       // default: []
+    ///       "replicas": []
     ///     },
     ///     "2": {
     ///       "ingester": "i1"
@@ -189,23 +227,74 @@ pub struct QuerierConfig {
     )]
     pub shard_to_ingesters: Option<String>,
 
+    /// Run the querier as a read replica that never contacts the ingesters.
+    ///
+    /// A read replica only serves the data persisted to object storage, so that cheap analytics
+    /// replicas can be isolated from the ingest tier. The Flight responses of a read replica
+    /// carry the newest timestamp of the persisted data read from each table, from which
+    /// clients can tell how stale the results are.
+    ///
+    /// Can not be combined with `--shard-to-ingesters-file` or `--shard-to-ingesters`.
+    #[clap(long = "read-replica", env = "INFLUXDB_IOX_READ_REPLICA", action)]
+    pub read_replica: bool,
+
+    /// Query the ingesters the shards are assigned to in the catalog.
+    ///
+    /// Must be set if the ingesters balance the shards among themselves
+    /// (`--catalog-shard-assignment`), as the ingester of a shard changes whenever they
+    /// rebalance. The ingesters of a shard are the one it is assigned to, and the one holding its
+    /// lease while handing it over.
+    ///
+    /// Can not be combined with `--shard-to-ingesters-file`, `--shard-to-ingesters` or
+    /// `--read-replica`.
+    #[clap(
+        long = "catalog-shard-assignment",
+        env = "INFLUXDB_IOX_CATALOG_SHARD_ASSIGNMENT",
+        conflicts_with_all = &["shard_to_ingesters_file", "shard_to_ingesters", "read_replica"],
+        action
+    )]
+    pub catalog_shard_assignment: bool,
+
+    /// Do not query the ingesters for tables whose partitions were all marked cold by the
+    /// compactor.
+    ///
+    /// A partition is marked cold once it stopped getting writes and was compacted to a single
+    /// file. Writes to a cold table are only visible once the ingesters persisted them.
+    #[clap(
+        long = "skip-ingesters-for-cold-tables",
+        env = "INFLUXDB_IOX_SKIP_INGESTERS_FOR_COLD_TABLES",
+        action
+    )]
+    pub skip_ingesters_for_cold_tables: bool,
+
+    /// Query the ingesters of the shard each table is pinned to in the catalog.
+    ///
+    /// Must be set if the routers pin tables to shards (`--shard-pinning`), as the data of a
+    /// pinned table may be on a different shard than the one the table hashes to.
+    #[clap(long = "shard-pinning", env = "INFLUXDB_IOX_SHARD_PINNING", action)]
+    pub shard_pinning: bool,
+
     /// Size of the RAM cache used to store catalog metadata information in bytes.
+    ///
+    /// If not specified, defaults to 5% of the memory available to the querier (i.e. the memory
+    /// limit of its container), or 128MB if that is unknown.
     #[clap(
         long = "ram-pool-metadata-bytes",
         env = "INFLUXDB_IOX_RAM_POOL_METADATA_BYTES",
-        default_value = "134217728",  // 128MB
         action
     )]
-    pub ram_pool_metadata_bytes: usize,
+    pub ram_pool_metadata_bytes: Option<usize>,
 
     /// Size of the RAM cache used to store data in bytes.
+    ///
+    /// If not specified, defaults to 25% of the memory available to the querier (i.e. the memory
+    /// limit of its container), or 1GB if that is unknown.
     #[clap(
         long = "ram-pool-data-bytes",
         env = "INFLUXDB_IOX_RAM_POOL_DATA_BYTES",
-        default_value = "1073741824",  // 1GB
         action
     )]
-    pub ram_pool_data_bytes: usize,
+    pub ram_pool_data_bytes: Option<usize>,
 
     /// Limit the number of concurrent queries.
     #[clap(
@@ -229,6 +318,92 @@ pub struct QuerierConfig {
     )]
     pub max_table_query_bytes: usize,
 
+    /// Limit the number of parquet file scans across all queries that wait for object store
+    /// data at the same time.
+    ///
+    /// Scans beyond this limit wait for their turn, which is reported via the
+    /// `iox_async_semaphore_acquire_duration{semaphore="object_store_scan_global"}` metric. If
+    /// not set, scans are unlimited.
+    #[clap(
+        long = "max-concurrent-object-store-scans",
+        env = "INFLUXDB_IOX_MAX_CONCURRENT_OBJECT_STORE_SCANS",
+        action
+    )]
+    pub max_concurrent_object_store_scans: Option<NonZeroUsize>,
+
+    /// Limit the number of parquet file scans of a single query that wait for object store data
+    /// at the same time.
+    ///
+    /// Scans beyond this limit wait for their turn, which is reported via the
+    /// `iox_async_semaphore_acquire_duration{semaphore="object_store_scan_query"}` metric. If
+    /// not set, scans are unlimited.
+    #[clap(
+        long = "max-concurrent-object-store-scans-per-query",
+        env = "INFLUXDB_IOX_MAX_CONCURRENT_OBJECT_STORE_SCANS_PER_QUERY",
+        action
+    )]
+    pub max_concurrent_object_store_scans_per_query: Option<NonZeroUsize>,
+
+    /// Hedge parquet file reads that take longer than this percentile of recent read latencies,
+    /// within `(0, 1)` (e.g. `0.95`).
+    ///
+    /// A hedged read issues a second request for the same data to the object store and uses
+    /// whichever response arrives first. The `object_store_hedged_requests` metric counts hedged
+    /// reads by the winning request. If not set, reads are not hedged.
+    #[clap(
+        long = "object-store-hedge-percentile",
+        env = "INFLUXDB_IOX_OBJECT_STORE_HEDGE_PERCENTILE",
+        value_parser = parse_hedge_percentile,
+    )]
+    pub object_store_hedge_percentile: Option<f64>,
+
+    /// Never hedge parquet file reads that took less than this (e.g. `50ms`).
+    #[clap(
+        long = "object-store-hedge-min-delay",
+        env = "INFLUXDB_IOX_OBJECT_STORE_HEDGE_MIN_DELAY",
+        default_value = "10ms",
+        value_parser = humantime::parse_duration,
+    )]
+    pub object_store_hedge_min_delay: Duration,
+
+    /// Cache the logical plans of up to this many queries.
+    ///
+    /// Planning dominates the latency of small queries that are repeated over and over again,
+    /// e.g. by dashboards. Plans are cached by namespace and statement and are dropped when the
+    /// schema of the namespace changes. The hit rate is reported via the `query_plan_cache_get`
+    /// metric. If not set, plans are not cached.
+    #[clap(
+        long = "plan-cache-max-entries",
+        env = "INFLUXDB_IOX_PLAN_CACHE_MAX_ENTRIES",
+        action
+    )]
+    pub plan_cache_max_entries: Option<NonZeroUsize>,
+
+    /// Label the `query_duration` metric with the namespace of each query, for up to this many
+    /// distinct namespaces.
+    ///
+    /// Queries against further namespaces are labelled with the namespace "other", bounding the
+    /// number of metric series. The metric is not labelled by namespace if unset.
+    #[clap(
+        long = "namespace-metric-label-limit",
+        env = "INFLUXDB_IOX_NAMESPACE_METRIC_LABEL_LIMIT",
+        action
+    )]
+    pub namespace_metric_label_limit: Option<usize>,
+
+    /// Reject queries that fill the gaps of more than this many time buckets per series with
+    /// `date_bin_gapfill`.
+    ///
+    /// The number of buckets is the time range of the query divided by the stride of
+    /// `date_bin_gapfill`, and is checked when the query is planned.
+    #[clap(
+        long = "max-gap-fill-buckets",
+        env = "INFLUXDB_IOX_MAX_GAP_FILL_BUCKETS",
+        default_value = "1000000",
+        action
+    )]
+    pub max_gap_fill_buckets: usize,
+
     /// After how many ingester query errors should the querier enter circuit breaker mode?
     ///
     /// The querier normally contacts the ingester for any unpersisted data during query planning.
@@ -250,6 +425,153 @@ pub struct QuerierConfig {
         action
     )]
     pub ingester_circuit_breaker_threshold: u64,
+
+    /// Location prefixes that `CREATE EXTERNAL TABLE` statements may reference.
+    ///
+    /// External tables are created in the `external` schema (e.g.
+    /// `CREATE EXTERNAL TABLE external.ref STORED AS PARQUET LOCATION 'file:///data/ref/'`) and
+    /// can be joined against the IOx tables of the namespace they were created in until the
+    /// querier restarts. Locations are matched against the prefixes by path segment, and
+    /// locations containing `..` are rejected. If empty, external tables are disabled.
+    #[clap(
+        long = "external-table-location-allowlist",
+        env = "INFLUXDB_IOX_EXTERNAL_TABLE_LOCATION_ALLOWLIST",
+        use_value_delimiter = true,
+        action = clap::ArgAction::Append
+    )]
+    pub external_table_location_allowlist: Vec<String>,
+
+    /// HTTP API address of a router (e.g. `http://router:8080`).
+    ///
+    /// If set, `CREATE TABLE <table> AS SELECT ...` and `INSERT INTO <table> SELECT ...`
+    /// statements write their query results to `<table>` of the queried namespace through this
+    /// router. The results must contain a `time` column. Otherwise these statements are rejected.
+    #[clap(
+        long = "router-http-address",
+        env = "INFLUXDB_IOX_ROUTER_HTTP_ADDRESS",
+        action
+    )]
+    pub router_http_address: Option<String>,
+
+    /// Maximum size in bytes of a request writing query results to the router.
+    ///
+    /// Query results are written in requests of up to this size, so it must not exceed the
+    /// `--max-http-request-size` of the router.
+    #[clap(
+        long = "router-max-request-size",
+        env = "INFLUXDB_IOX_ROUTER_MAX_REQUEST_SIZE",
+        default_value = "10485760", // 10 MiB
+        action
+    )]
+    pub router_max_request_size: usize,
+
+    /// Name of the query pool this querier is a member of.
+    ///
+    /// Queries against a namespace are only dispatched to the queriers of the namespace's query
+    /// pool. The querier only registers in the pool if `--query-pool-advertise-address` is set.
+    #[clap(
+        long = "query-pool",
+        env = "INFLUXDB_IOX_QUERY_POOL_NAME",
+        default_value = "iox-shared",
+        action
+    )]
+    pub query_pool_name: String,
+
+    /// gRPC address under which other services can reach this querier (e.g.
+    /// `http://querier-1:8082`).
+    ///
+    /// If set, the querier registers itself in the catalog as a member of its query pool and
+    /// keeps the registration alive with periodic heartbeats.
+    #[clap(
+        long = "query-pool-advertise-address",
+        env = "INFLUXDB_IOX_QUERY_POOL_ADVERTISE_ADDRESS",
+        action
+    )]
+    pub query_pool_advertise_address: Option<String>,
+
+    /// Interval between two query pool heartbeats of this querier, in seconds.
+    #[clap(
+        long = "query-pool-heartbeat-interval-seconds",
+        env = "INFLUXDB_IOX_QUERY_POOL_HEARTBEAT_INTERVAL_SECONDS",
+        default_value = "10",
+        action
+    )]
+    pub query_pool_heartbeat_interval_seconds: u64,
+
+    /// Namespace to write canary points to, measuring the latency of the write path.
+    ///
+    /// If set, the querier periodically writes a point to the `write_slo` table of this
+    /// namespace through the router of `--router-http-address`, and measures the time until
+    /// the point is returned by its queries and until it is persisted. The latencies are
+    /// reported via the `write_slo_queryable_duration` and `write_slo_persisted_duration`
+    /// metrics and the `system.write_slo` table of the namespace. The namespace should be
+    /// dedicated to this purpose.
+    #[clap(
+        long = "write-slo-namespace",
+        env = "INFLUXDB_IOX_WRITE_SLO_NAMESPACE",
+        action
+    )]
+    pub write_slo_namespace: Option<String>,
+
+    /// Interval between two canary points of `--write-slo-namespace`, in seconds.
+    #[clap(
+        long = "write-slo-interval-seconds",
+        env = "INFLUXDB_IOX_WRITE_SLO_INTERVAL_SECONDS",
+        default_value = "60",
+        action
+    )]
+    pub write_slo_interval_seconds: u64,
+
+    /// Time after which a canary point that is not queryable or persisted counts as failed, in
+    /// seconds.
+    #[clap(
+        long = "write-slo-timeout-seconds",
+        env = "INFLUXDB_IOX_WRITE_SLO_TIMEOUT_SECONDS",
+        default_value = "1800",
+        action
+    )]
+    pub write_slo_timeout_seconds: u64,
+
+    /// Account the queries executed against and the bytes scanned from each namespace, adding
+    /// them to the namespace usage in the catalog every this many seconds.
+    ///
+    /// Usage accounting is disabled if unset.
+    #[clap(
+        long = "usage-flush-interval-seconds",
+        env = "INFLUXDB_IOX_USAGE_FLUSH_INTERVAL_SECONDS",
+        action
+    )]
+    pub usage_flush_interval_seconds: Option<u64>,
+
+    /// Path to a JSON file restricting the rows of namespaces that identities may read. For
+    /// example:
+    ///
+    /// ```json
+    /// {
+    ///   // Namespace whose reads are restricted. Namespaces not listed are unrestricted.
+    ///   "shared_ns": {
+    ///     // Identity allowed to read the namespace, mapped to the allowed values of tags.
+    ///     // Only rows whose tags have one of the allowed values are returned; tables without
+    ///     // one of the tags return no rows.
+    ///     "tenant-abc": {
+    ///       "tenant_id": ["abc"]
+    ///     },
+    ///     // An identity without tag values may read all rows.
+    ///     "admin": {}
+    ///   }
+    /// }
+    /// ```
+    ///
+    /// Queries of restricted namespaces without one of the listed identities are rejected. The
+    /// identity of a Flight SQL query is taken from the `iox-identity` gRPC header, which must be
+    /// set by an authenticating gateway. Restricted identities can not use the storage (InfluxRPC)
+    /// API.
+    #[clap(
+        long = "read-policy-file",
+        env = "INFLUXDB_IOX_READ_POLICY_FILE",
+        action
+    )]
+    pub read_policy_file: Option<PathBuf>,
 }
 
 impl QuerierConfig {
@@ -262,8 +584,18 @@ impl QuerierConfig {
     /// Return the querier config's ingester addresses. If `--shard-to-ingesters-file` is used to
     /// specify a JSON file containing shard to ingester address mappings, this returns `Err` if
     /// there are any problems reading, deserializing, or interpreting the file.
+    ///
+    /// A `--read-replica` has no ingester addresses, and the addresses are read from the catalog
+    /// with `--catalog-shard-assignment`.
     pub fn ingester_addresses(&self) -> Result<IngesterAddresses, Error> {
-        if let Some(file) = &self.shard_to_ingesters_file {
+        if self.catalog_shard_assignment {
+            Ok(IngesterAddresses::Catalog)
+        } else if self.read_replica {
+            if self.shard_to_ingesters_file.is_some() || self.shard_to_ingesters.is_some() {
+                return ReadReplicaWithIngestersSnafu.fail();
+            }
+            Ok(IngesterAddresses::None)
+        } else if let Some(file) = &self.shard_to_ingesters_file {
             let contents =
                 fs::read_to_string(file).context(ShardToIngesterFileReadingSnafu { file })?;
             let map = deserialize_shard_ingester_map(&contents)?;
@@ -284,13 +616,13 @@ impl QuerierConfig {
         }
     }
 
-    /// Size of the RAM cache pool for metadata in bytes.
-    pub fn ram_pool_metadata_bytes(&self) -> usize {
+    /// Size of the RAM cache pool for metadata in bytes, if configured.
+    pub fn ram_pool_metadata_bytes(&self) -> Option<usize> {
         self.ram_pool_metadata_bytes
     }
 
-    /// Size of the RAM cache pool for payload in bytes.
-    pub fn ram_pool_data_bytes(&self) -> usize {
+    /// Size of the RAM cache pool for payload in bytes, if configured.
+    pub fn ram_pool_data_bytes(&self) -> Option<usize> {
         self.ram_pool_data_bytes
     }
 
@@ -304,6 +636,118 @@ impl QuerierConfig {
     pub fn max_table_query_bytes(&self) -> usize {
         self.max_table_query_bytes
     }
+
+    /// Maximum number of concurrent object store scans across all queries, if limited.
+    pub fn max_concurrent_object_store_scans(&self) -> Option<NonZeroUsize> {
+        self.max_concurrent_object_store_scans
+    }
+
+    /// Maximum number of concurrent object store scans of a single query, if limited.
+    pub fn max_concurrent_object_store_scans_per_query(&self) -> Option<NonZeroUsize> {
+        self.max_concurrent_object_store_scans_per_query
+    }
+
+    /// Maximum number of cached query plans, or `None` if plans are not cached.
+    pub fn plan_cache_max_entries(&self) -> Option<NonZeroUsize> {
+        self.plan_cache_max_entries
+    }
+
+    /// Maximum number of distinct namespaces the query metrics are labelled with, or `None` if
+    /// they are not labelled by namespace.
+    pub fn namespace_metric_label_limit(&self) -> Option<usize> {
+        self.namespace_metric_label_limit
+    }
+
+    /// Maximum number of time buckets per series a gap-filling query may fill.
+    pub fn max_gap_fill_buckets(&self) -> usize {
+        self.max_gap_fill_buckets
+    }
+
+    /// When to hedge parquet file reads, or `None` if reads are not hedged.
+    pub fn object_store_hedge_config(&self) -> Option<HedgeConfig> {
+        self.object_store_hedge_percentile.map(|percentile| {
+            HedgeConfig::new(percentile).with_min_delay(self.object_store_hedge_min_delay)
+        })
+    }
+
+    /// Location prefixes allowed for external tables. Empty if external tables are disabled.
+    pub fn external_table_location_allowlist(&self) -> &[String] {
+        &self.external_table_location_allowlist
+    }
+
+    /// HTTP API address of the router that query results are written to, if enabled.
+    pub fn router_http_address(&self) -> Option<&str> {
+        self.router_http_address.as_deref()
+    }
+
+    /// Maximum size of a request writing query results to the router.
+    pub fn router_max_request_size(&self) -> usize {
+        self.router_max_request_size
+    }
+
+    /// Name of the query pool this querier is a member of.
+    pub fn query_pool_name(&self) -> &str {
+        &self.query_pool_name
+    }
+
+    /// Address this querier registers in its query pool, if registration is enabled.
+    pub fn query_pool_advertise_address(&self) -> Option<&str> {
+        self.query_pool_advertise_address.as_deref()
+    }
+
+    /// Interval between two query pool heartbeats.
+    pub fn query_pool_heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.query_pool_heartbeat_interval_seconds)
+    }
+
+    /// Namespace and router HTTP address of the write path latency probe, or `None` if the probe
+    /// is disabled.
+    pub fn write_slo_target(&self) -> Result<Option<(&str, &str)>, Error> {
+        match (&self.write_slo_namespace, &self.router_http_address) {
+            (None, _) => Ok(None),
+            (Some(namespace), Some(router_http_address)) => {
+                Ok(Some((namespace.as_str(), router_http_address.as_str())))
+            }
+            (Some(_), None) => WriteSloRequiresRouterSnafu.fail(),
+        }
+    }
+
+    /// Interval between two canary points of the write path latency probe.
+    pub fn write_slo_interval(&self) -> Duration {
+        Duration::from_secs(self.write_slo_interval_seconds)
+    }
+
+    /// Time after which a canary point of the write path latency probe counts as failed.
+    pub fn write_slo_timeout(&self) -> Duration {
+        Duration::from_secs(self.write_slo_timeout_seconds)
+    }
+
+    /// Interval between two flushes of the namespace usage to the catalog, or `None` if usage
+    /// accounting is disabled.
+    pub fn usage_flush_interval(&self) -> Option<Duration> {
+        self.usage_flush_interval_seconds.map(Duration::from_secs)
+    }
+
+    /// Return the read policies of `--read-policy-file`, or an empty config if it is not set.
+    pub fn read_policies(&self) -> Result<ReadPolicyConfig, Error> {
+        match &self.read_policy_file {
+            Some(file) => {
+                let contents =
+                    fs::read_to_string(file).context(ReadPolicyFileReadingSnafu { file })?;
+                serde_json::from_str(&contents).context(ReadPolicyDeserializingSnafu)
+            }
+            None => Ok(ReadPolicyConfig::default()),
+        }
+    }
+}
+
+/// Parse a hedge percentile, which must be within `(0, 1)`.
+fn parse_hedge_percentile(s: &str) -> Result<f64, String> {
+    let percentile: f64 = s.parse().map_err(|e| format!("{e}"))?;
+    if !(percentile > 0.0 && percentile < 1.0) {
+        return Err(format!("{percentile} is not within (0, 1)"));
+    }
+    Ok(percentile)
 }
 
 fn deserialize_shard_ingester_map(
@@ -354,11 +798,25 @@ fn deserialize_shard_ingester_map(
             map.insert(shard_index, IngesterMapping::Ignore);
             continue;
         }
-        match shard_config.ingester {
-            Some(ingester) => match ingester_mapping_by_name.get(&ingester) {
-                Some(ingester_mapping) => {
-                    map.insert(shard_index, ingester_mapping.clone());
+        if shard_config.ingester.is_none() && shard_config.replicas.is_empty() {
+            map.insert(shard_index, IngesterMapping::NotMapped);
+            continue;
+        }
+
+        // Ignored ingesters are not queried, the shard is only ignored if all its replicas are.
+        let mut addrs: Vec<Arc<str>> = vec![];
+        for ingester in shard_config
+            .ingester
+            .into_iter()
+            .chain(shard_config.replicas)
+        {
+            match ingester_mapping_by_name.get(&ingester) {
+                Some(IngesterMapping::Addr(addr)) => {
+                    if !addrs.contains(addr) {
+                        addrs.push(Arc::clone(addr));
+                    }
                 }
+                Some(_) => {}
                 None => {
                     return IngesterNotFoundSnafu {
                         name: Arc::clone(&ingester),
@@ -366,11 +824,15 @@ fn deserialize_shard_ingester_map(
                     }
                     .fail();
                 }
-            },
-            None => {
-                map.insert(shard_index, IngesterMapping::NotMapped);
             }
         }
+
+        let mapping = match addrs.len() {
+            0 => IngesterMapping::Ignore,
+            1 => IngesterMapping::Addr(addrs.remove(0)),
+            _ => IngesterMapping::Replicas(addrs),
+        };
+        map.insert(shard_index, mapping);
     }
 
     Ok(map)
@@ -382,6 +844,9 @@ pub enum IngesterAddresses {
     /// A mapping from shard index to ingesters.
     ByShardIndex(HashMap<ShardIndex, IngesterMapping>),
 
+    /// The ingesters the shards are assigned to in the catalog.
+    Catalog,
+
     /// No connections, meaning only persisted data should be used.
     None,
 }
@@ -410,6 +875,8 @@ pub struct IngesterConfig {
 pub struct ShardConfig {
     ingester: Option<Arc<str>>,
     #[serde(default)]
+    replicas: Vec<Arc<str>>,
+    #[serde(default)]
     ignore: bool,
 }
 
@@ -424,10 +891,186 @@ mod tests {
         let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
 
         assert_eq!(actual.num_query_threads(), None);
+        assert_eq!(actual.ram_pool_metadata_bytes(), None);
+        assert_eq!(actual.ram_pool_data_bytes(), None);
         assert!(matches!(
             actual.ingester_addresses().unwrap(),
             IngesterAddresses::None,
         ));
+        assert!(actual.external_table_location_allowlist().is_empty());
+        assert_eq!(actual.router_http_address(), None);
+        assert_eq!(actual.router_max_request_size(), 10 * 1024 * 1024);
+        assert!(actual.read_policies().unwrap().is_empty());
+        assert_eq!(actual.max_concurrent_object_store_scans(), None);
+        assert_eq!(actual.max_concurrent_object_store_scans_per_query(), None);
+        assert_eq!(actual.object_store_hedge_config(), None);
+        assert_eq!(actual.plan_cache_max_entries(), None);
+        assert_eq!(actual.namespace_metric_label_limit(), None);
+        assert_eq!(actual.max_gap_fill_buckets(), 1_000_000);
+        assert_eq!(actual.write_slo_target().unwrap(), None);
+        assert_eq!(actual.usage_flush_interval(), None);
+    }
+
+    #[test]
+    fn test_write_slo_target() {
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--write-slo-namespace",
+            "canary",
+            "--router-http-address",
+            "http://router:8080",
+        ])
+        .unwrap();
+        assert_eq!(
+            actual.write_slo_target().unwrap(),
+            Some(("canary", "http://router:8080"))
+        );
+        assert_eq!(actual.write_slo_interval(), Duration::from_secs(60));
+
+        let actual =
+            QuerierConfig::try_parse_from(["my_binary", "--write-slo-namespace", "canary"])
+                .unwrap();
+        assert_error!(actual.write_slo_target(), Error::WriteSloRequiresRouter);
+    }
+
+    #[test]
+    fn test_object_store_hedge_config() {
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--object-store-hedge-percentile",
+            "0.95",
+            "--object-store-hedge-min-delay",
+            "50ms",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            actual.object_store_hedge_config(),
+            Some(HedgeConfig::new(0.95).with_min_delay(Duration::from_millis(50)))
+        );
+
+        for percentile in ["0", "1", "1.5"] {
+            assert!(QuerierConfig::try_parse_from([
+                "my_binary",
+                "--object-store-hedge-percentile",
+                percentile,
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_max_concurrent_object_store_scans() {
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--max-concurrent-object-store-scans",
+            "100",
+            "--max-concurrent-object-store-scans-per-query",
+            "10",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            actual.max_concurrent_object_store_scans(),
+            NonZeroUsize::new(100)
+        );
+        assert_eq!(
+            actual.max_concurrent_object_store_scans_per_query(),
+            NonZeroUsize::new(10)
+        );
+
+        assert!(QuerierConfig::try_parse_from([
+            "my_binary",
+            "--max-concurrent-object-store-scans",
+            "0",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_external_table_location_allowlist() {
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--external-table-location-allowlist",
+            "s3://reference/,file:///data/",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            actual.external_table_location_allowlist(),
+            &["s3://reference/".to_string(), "file:///data/".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_read_policy_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("read_policy.json");
+        fs::write(
+            &file,
+            r#"{"shared_ns": {"tenant-abc": {"tenant_id": ["abc"]}, "admin": {}}}"#,
+        )
+        .unwrap();
+
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--read-policy-file",
+            file.to_str().unwrap(),
+        ])
+        .unwrap();
+
+        let expected = ReadPolicyConfig::from([(
+            "shared_ns".to_string(),
+            HashMap::from([
+                (
+                    "tenant-abc".to_string(),
+                    BTreeMap::from([("tenant_id".to_string(), vec!["abc".to_string()])]),
+                ),
+                ("admin".to_string(), BTreeMap::new()),
+            ]),
+        )]);
+        assert_eq!(actual.read_policies().unwrap(), expected);
+
+        fs::write(&file, "not json").unwrap();
+        assert_error!(
+            actual.read_policies(),
+            Error::ReadPolicyDeserializing { .. }
+        );
+    }
+
+    #[test]
+    fn test_read_replica() {
+        let actual = QuerierConfig::try_parse_from(["my_binary", "--read-replica"]).unwrap();
+        assert!(matches!(
+            actual.ingester_addresses().unwrap(),
+            IngesterAddresses::None,
+        ));
+
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--read-replica",
+            "--shard-to-ingesters",
+            r#"{"ingesters": {"i1": {"addr": "http://ingester-1:1234"}}, "shards": {"1": {"ingester": "i1"}}}"#,
+        ])
+        .unwrap();
+        assert_error!(actual.ingester_addresses(), Error::ReadReplicaWithIngesters);
+    }
+
+    #[test]
+    fn test_catalog_shard_assignment() {
+        let actual =
+            QuerierConfig::try_parse_from(["my_binary", "--catalog-shard-assignment"]).unwrap();
+        assert_eq!(
+            actual.ingester_addresses().unwrap(),
+            IngesterAddresses::Catalog
+        );
+
+        QuerierConfig::try_parse_from([
+            "my_binary",
+            "--catalog-shard-assignment",
+            "--read-replica",
+        ])
+        .unwrap_err();
     }
 
     #[test]
@@ -718,4 +1361,86 @@ mod tests {
 
         assert_eq!(map.unwrap(), expected);
     }
+
+    #[test]
+    fn shard_to_ingester_replicas() {
+        let map = deserialize_shard_ingester_map(
+            r#"{
+            "ingesters": {
+                "i1": {
+                  "addr": "http://ingester-1:1234"
+                },
+                "i2": {
+                  "addr": "http://ingester-2:1234"
+                },
+                "i3": {
+                  "ignore": true
+                }
+            },
+            "shards": {
+                "1": {
+                  "ingester": "i1",
+                  "replicas": ["i2"]
+                },
+                "2": {
+                  "replicas": ["i2", "i1", "i2"]
+                },
+                "3": {
+                  "ingester": "i1",
+                  "replicas": ["i3"]
+                },
+                "4": {
+                  "replicas": ["i3"]
+                }
+            }
+        }"#,
+        );
+
+        let expected = [
+            (
+                ShardIndex::new(1),
+                IngesterMapping::Replicas(vec![
+                    "http://ingester-1:1234".into(),
+                    "http://ingester-2:1234".into(),
+                ]),
+            ),
+            (
+                ShardIndex::new(2),
+                IngesterMapping::Replicas(vec![
+                    "http://ingester-2:1234".into(),
+                    "http://ingester-1:1234".into(),
+                ]),
+            ),
+            (
+                ShardIndex::new(3),
+                IngesterMapping::Addr("http://ingester-1:1234".into()),
+            ),
+            (ShardIndex::new(4), IngesterMapping::Ignore),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(map.unwrap(), expected);
+
+        let map = deserialize_shard_ingester_map(
+            r#"{
+            "ingesters": {
+                "i1": {
+                  "addr": "http://ingester-1:1234"
+                }
+            },
+            "shards": {
+                "1": {
+                  "ingester": "i1",
+                  "replicas": ["i2"]
+                }
+            }
+        }"#,
+        );
+        assert_error!(
+            map,
+            Error::IngesterNotFound { shard_index, ref name }
+              if shard_index.get() == 1 && name.as_ref() == "i2"
+        );
+    }
 }
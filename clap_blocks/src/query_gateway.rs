@@ -0,0 +1,195 @@
+//! Query gateway-related configs.
+use serde::Deserialize;
+use snafu::{ResultExt, Snafu};
+use std::{collections::HashMap, fs, io, path::PathBuf, time::Duration};
+
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display("Could not read token file `{}`: {source}", file.display()))]
+    TokenFileReading { source: io::Error, file: PathBuf },
+
+    #[snafu(display("Could not deserialize JSON from token file: {source}"))]
+    TokenDeserializing { source: serde_json::Error },
+}
+
+/// The identity and namespaces of a token. See `--token-file`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TokenConfig {
+    /// The identity queries authenticated with the token run on behalf of.
+    pub identity: String,
+
+    /// The namespaces the token may query. The token may query all namespaces if empty.
+    #[serde(default)]
+    pub namespaces: Vec<String>,
+}
+
+/// CLI config for the query gateway
+#[derive(Debug, Clone, PartialEq, Eq, clap::Parser)]
+pub struct QueryGatewayConfig {
+    /// Path to a JSON file mapping the bearer tokens accepted by the gateway to the identity
+    /// queries run on behalf of. For example:
+    ///
+    /// ```json
+    /// {
+    ///   "secret-token-1": {
+    ///     // Identity sent to the queriers in the `iox-identity` gRPC header.
+    ///     "identity": "tenant-abc",
+    ///     // Namespaces the token may query. All namespaces if empty or missing.
+    ///     "namespaces": ["shared_ns"]
+    ///   }
+    /// }
+    /// ```
+    ///
+    /// If set, queries must send one of the tokens in the `authorization` gRPC header (e.g.
+    /// `Bearer secret-token-1`). Otherwise queries are not authenticated and run without an
+    /// identity.
+    #[clap(long = "token-file", env = "INFLUXDB_IOX_GATEWAY_TOKEN_FILE", action)]
+    pub token_file: Option<PathBuf>,
+
+    /// Limit the number of queries forwarded to the queriers concurrently.
+    #[clap(
+        long = "max-concurrent-queries",
+        env = "INFLUXDB_IOX_GATEWAY_MAX_CONCURRENT_QUERIES",
+        default_value = "64",
+        action
+    )]
+    pub max_concurrent_queries: usize,
+
+    /// Limit the number of queries waiting for one of the `--max-concurrent-queries` slots.
+    ///
+    /// Queries arriving while this many queries are waiting are rejected with
+    /// `RESOURCE_EXHAUSTED`.
+    #[clap(
+        long = "max-queued-queries",
+        env = "INFLUXDB_IOX_GATEWAY_MAX_QUEUED_QUERIES",
+        default_value = "256",
+        action
+    )]
+    pub max_queued_queries: usize,
+
+    /// How long query results are cached, e.g. `30s`.
+    ///
+    /// Identical queries of the same identity within this time are answered from the cache
+    /// without contacting a querier. Results are not cached if this is zero.
+    #[clap(
+        long = "result-cache-ttl",
+        env = "INFLUXDB_IOX_GATEWAY_RESULT_CACHE_TTL",
+        default_value = "0s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub result_cache_ttl: Duration,
+
+    /// Size of the RAM cache used to store query results in bytes.
+    #[clap(
+        long = "result-cache-bytes",
+        env = "INFLUXDB_IOX_GATEWAY_RESULT_CACHE_BYTES",
+        default_value = "1073741824",  // 1GB
+        action
+    )]
+    pub result_cache_bytes: usize,
+
+    /// Results larger than this many bytes are not cached.
+    #[clap(
+        long = "result-cache-max-entry-bytes",
+        env = "INFLUXDB_IOX_GATEWAY_RESULT_CACHE_MAX_ENTRY_BYTES",
+        default_value = "16777216",  // 16MB
+        action
+    )]
+    pub result_cache_max_entry_bytes: usize,
+
+    /// Interval between two query pool heartbeats of the queriers, in seconds.
+    ///
+    /// Queries are only forwarded to queriers that sent a heartbeat within the last few
+    /// intervals. This must match `--query-pool-heartbeat-interval-seconds` of the queriers.
+    #[clap(
+        long = "query-pool-heartbeat-interval-seconds",
+        env = "INFLUXDB_IOX_QUERY_POOL_HEARTBEAT_INTERVAL_SECONDS",
+        default_value = "10",
+        action
+    )]
+    pub query_pool_heartbeat_interval_seconds: u64,
+}
+
+impl QueryGatewayConfig {
+    /// Return the tokens of `--token-file`, or [`None`] if authentication is disabled.
+    pub fn tokens(&self) -> Result<Option<HashMap<String, TokenConfig>>, Error> {
+        match &self.token_file {
+            Some(file) => {
+                let contents = fs::read_to_string(file).context(TokenFileReadingSnafu { file })?;
+                serde_json::from_str(&contents)
+                    .map(Some)
+                    .context(TokenDeserializingSnafu)
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Interval between two query pool heartbeats of the queriers.
+    pub fn query_pool_heartbeat_interval(&self) -> Duration {
+        Duration::from_secs(self.query_pool_heartbeat_interval_seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_defaults() {
+        let config = QueryGatewayConfig::try_parse_from(["my_binary"]).unwrap();
+
+        assert_eq!(config.tokens().unwrap(), None);
+        assert_eq!(config.max_concurrent_queries, 64);
+        assert_eq!(config.result_cache_ttl, Duration::ZERO);
+        assert_eq!(
+            config.query_pool_heartbeat_interval(),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn test_tokens() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(
+            br#"{
+                "t1": {"identity": "alice", "namespaces": ["ns"]},
+                "t2": {"identity": "admin"}
+            }"#,
+        )
+        .unwrap();
+
+        let config = QueryGatewayConfig::try_parse_from([
+            "my_binary",
+            "--token-file",
+            file.path().to_str().unwrap(),
+            "--result-cache-ttl",
+            "30s",
+        ])
+        .unwrap();
+
+        assert_eq!(config.result_cache_ttl, Duration::from_secs(30));
+        assert_eq!(
+            config.tokens().unwrap(),
+            Some(HashMap::from([
+                (
+                    "t1".to_string(),
+                    TokenConfig {
+                        identity: "alice".to_string(),
+                        namespaces: vec!["ns".to_string()],
+                    }
+                ),
+                (
+                    "t2".to_string(),
+                    TokenConfig {
+                        identity: "admin".to_string(),
+                        namespaces: vec![],
+                    }
+                ),
+            ]))
+        );
+    }
+}
@@ -0,0 +1,735 @@
+//! CLI config for the router.
+
+use chrono::format::{Item, StrftimeItems};
+use data_types::{NamespaceNameRules, PartitionTemplate, TemplatePart};
+use serde::{Deserialize, Deserializer};
+use snafu::{ensure, ResultExt, Snafu};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::PathBuf,
+    time::Duration,
+};
+
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display("Could not read DML handler config file `{}`: {source}", file.display()))]
+    DmlHandlerConfigFileReading { source: io::Error, file: PathBuf },
+
+    #[snafu(display("Could not deserialize TOML from DML handler config file: {source}"))]
+    DmlHandlerConfigDeserializing { source: toml::de::Error },
+
+    #[snafu(display("Invalid partitioner time format `{time_format}`"))]
+    PartitionerTimeFormat { time_format: String },
+
+    #[snafu(display(
+        "Invalid rename to `{name}` in the transform rules of namespace `{namespace}`"
+    ))]
+    TransformRename { namespace: String, name: String },
+}
+
+/// CLI config for the creation of namespaces that do not exist when they are
+/// first written to.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct NamespaceAutocreationConfig {
+    /// What to do with writes to namespaces that do not exist.
+    ///
+    /// "enabled" creates the namespace with the "new-namespace" settings
+    /// (the catalog defaults, with infinite retention, if unset). "disabled"
+    /// rejects the write with a 404 response.
+    #[clap(
+        value_enum,
+        long = "namespace-autocreation",
+        env = "INFLUXDB_IOX_NAMESPACE_AUTOCREATION",
+        default_value = "enabled",
+        action
+    )]
+    pub namespace_autocreation: NamespaceAutocreationPolicy,
+
+    /// The retention period, in hours, of automatically created namespaces.
+    ///
+    /// 0 means infinite retention.
+    #[clap(
+        long = "new-namespace-retention-hours",
+        env = "INFLUXDB_IOX_NEW_NAMESPACE_RETENTION_HOURS",
+        default_value = "0",
+        value_parser = clap::value_parser!(u32).range(0..=MAX_RETENTION_HOURS),
+        action
+    )]
+    pub new_namespace_retention_hours: u32,
+
+    /// The maximum number of tables of automatically created namespaces.
+    ///
+    /// Uses the catalog default if not specified.
+    #[clap(
+        long = "new-namespace-max-tables",
+        env = "INFLUXDB_IOX_NEW_NAMESPACE_MAX_TABLES",
+        action
+    )]
+    pub new_namespace_max_tables: Option<i32>,
+
+    /// The maximum number of columns per table of automatically created
+    /// namespaces.
+    ///
+    /// Uses the catalog default if not specified.
+    #[clap(
+        long = "new-namespace-max-columns-per-table",
+        env = "INFLUXDB_IOX_NEW_NAMESPACE_MAX_COLUMNS_PER_TABLE",
+        action
+    )]
+    pub new_namespace_max_columns_per_table: Option<i32>,
+
+    /// The partition template of automatically created namespaces, one part
+    /// per occurrence, e.g. `--new-namespace-partition-template "time(%Y-%m)"
+    /// --new-namespace-partition-template "tag(region)"`.
+    ///
+    /// See the `parts` of the partitioner in "dml-handler-config-file" for
+    /// the syntax of the parts. Namespaces are partitioned by the partitioner
+    /// of the DML handler config if not specified.
+    #[clap(
+        long = "new-namespace-partition-template",
+        env = "INFLUXDB_IOX_NEW_NAMESPACE_PARTITION_TEMPLATE",
+        value_parser = parse_template_part,
+        action = clap::ArgAction::Append
+    )]
+    pub new_namespace_partition_template: Vec<TemplatePart>,
+}
+
+/// Nanoseconds per hour.
+const NANOS_PER_HOUR: i64 = 60 * 60 * 1_000_000_000;
+
+/// The longest retention period, in hours, that fits in an `i64` of
+/// nanoseconds.
+const MAX_RETENTION_HOURS: i64 = i64::MAX / NANOS_PER_HOUR;
+
+impl NamespaceAutocreationConfig {
+    /// Create a config that automatically creates namespaces with the
+    /// catalog defaults.
+    pub fn new_enabled() -> Self {
+        Self {
+            namespace_autocreation: NamespaceAutocreationPolicy::Enabled,
+            new_namespace_retention_hours: 0,
+            new_namespace_max_tables: None,
+            new_namespace_max_columns_per_table: None,
+            new_namespace_partition_template: vec![],
+        }
+    }
+
+    /// The retention period of automatically created namespaces, in
+    /// nanoseconds, or [`None`] for infinite retention.
+    ///
+    /// Retention periods too long to represent in nanoseconds are infinite.
+    pub fn new_namespace_retention_period_ns(&self) -> Option<i64> {
+        match self.new_namespace_retention_hours {
+            0 => None,
+            hours => i64::from(hours).checked_mul(NANOS_PER_HOUR),
+        }
+    }
+
+    /// The partition template of automatically created namespaces, or
+    /// [`None`] to partition them by the partitioner of the DML handler
+    /// config.
+    pub fn new_namespace_partition_template(&self) -> Option<PartitionTemplate> {
+        (!self.new_namespace_partition_template.is_empty()).then(|| PartitionTemplate {
+            parts: self.new_namespace_partition_template.clone(),
+        })
+    }
+}
+
+fn parse_template_part(s: &str) -> Result<TemplatePart, String> {
+    let part = s.parse::<TemplatePart>().map_err(|e| e.to_string())?;
+    match &part {
+        TemplatePart::TimeFormat(time_format) if !is_valid_time_format(time_format) => {
+            Err(format!("invalid time format `{}`", time_format))
+        }
+        _ => Ok(part),
+    }
+}
+
+/// Whether `time_format` is a valid strftime format.
+fn is_valid_time_format(time_format: &str) -> bool {
+    !StrftimeItems::new(time_format).any(|item| matches!(item, Item::Error))
+}
+
+/// Namespace autocreation policy.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum NamespaceAutocreationPolicy {
+    /// Create namespaces that do not exist.
+    Enabled,
+
+    /// Reject writes to namespaces that do not exist.
+    Disabled,
+}
+
+/// CLI config for the restrictions on namespace names.
+#[derive(Debug, Clone, Default, clap::Parser)]
+pub struct NamespaceNameRulesConfig {
+    /// The maximum length of namespace names.
+    ///
+    /// Namespace names can never be longer than 64 characters.
+    #[clap(
+        long = "namespace-name-max-length",
+        env = "INFLUXDB_IOX_NAMESPACE_NAME_MAX_LENGTH",
+        action
+    )]
+    pub namespace_name_max_length: Option<usize>,
+
+    /// If set, namespace names may only contain ASCII alphanumeric characters
+    /// and the characters in this string, e.g. `_-`.
+    ///
+    /// Note that the namespace of a write is `<org>_<bucket>`, with
+    /// non-alphanumeric characters in the org and bucket percent-encoded.
+    #[clap(
+        long = "namespace-name-allowed-chars",
+        env = "INFLUXDB_IOX_NAMESPACE_NAME_ALLOWED_CHARS",
+        action
+    )]
+    pub namespace_name_allowed_chars: Option<String>,
+
+    /// Prefixes namespace names may not start with, e.g.
+    /// `system_,_internal`.
+    #[clap(
+        long = "namespace-name-reserved-prefixes",
+        env = "INFLUXDB_IOX_NAMESPACE_NAME_RESERVED_PREFIXES",
+        use_value_delimiter = true,
+        action = clap::ArgAction::Append
+    )]
+    pub namespace_name_reserved_prefixes: Vec<String>,
+}
+
+impl NamespaceNameRulesConfig {
+    /// The [`NamespaceNameRules`] described by this config.
+    pub fn rules(&self) -> NamespaceNameRules {
+        NamespaceNameRules {
+            max_length: self.namespace_name_max_length,
+            allowed_chars: self.namespace_name_allowed_chars.clone(),
+            reserved_prefixes: self
+                .namespace_name_reserved_prefixes
+                .iter()
+                .filter(|v| !v.is_empty())
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// CLI config for the handling of writes with a column type that conflicts
+/// with the namespace schema.
+#[derive(Debug, Clone, Default, clap::Parser)]
+pub struct SchemaConflictConfig {
+    /// What to do with writes containing a column with a type that conflicts
+    /// with the existing column.
+    ///
+    /// "reject" rejects the whole write. "coerce" converts integer fields
+    /// written to existing float columns into floats, and rejects writes with
+    /// any other conflict. "drop-lines" drops the lines containing a
+    /// conflicting column and applies the rest of the write.
+    #[clap(
+        value_enum,
+        long = "schema-conflict-policy",
+        env = "INFLUXDB_IOX_SCHEMA_CONFLICT_POLICY",
+        default_value = "reject",
+        action
+    )]
+    pub schema_conflict_policy: SchemaConflictPolicy,
+
+    /// Per-namespace schema conflict policies, overriding the
+    /// "schema-conflict-policy" for the given namespaces, e.g.
+    /// `myorg_mybucket=coerce,myorg_other=drop-lines`.
+    #[clap(
+        long = "namespace-schema-conflict-policies",
+        env = "INFLUXDB_IOX_NAMESPACE_SCHEMA_CONFLICT_POLICIES",
+        use_value_delimiter = true,
+        value_parser = parse_namespace_schema_conflict_policy,
+        action = clap::ArgAction::Append
+    )]
+    pub namespace_schema_conflict_policies: Vec<(String, SchemaConflictPolicy)>,
+}
+
+/// Schema conflict policy.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemaConflictPolicy {
+    /// Reject writes with conflicting columns.
+    #[default]
+    Reject,
+
+    /// Coerce integer fields written to float columns.
+    Coerce,
+
+    /// Drop lines with conflicting columns.
+    DropLines,
+}
+
+fn parse_namespace_schema_conflict_policy(
+    s: &str,
+) -> Result<(String, SchemaConflictPolicy), String> {
+    let (namespace, policy) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected <namespace>=<policy>, got {:?}", s))?;
+    let policy = <SchemaConflictPolicy as clap::ValueEnum>::from_str(policy, true)?;
+
+    Ok((namespace.to_string(), policy))
+}
+
+/// CLI config for routing the writes of namespaces to write buffer topics
+/// other than the configured "write-buffer-topic".
+#[derive(Debug, Clone, clap::Parser)]
+pub struct TopicRoutingConfig {
+    /// Additional write buffer topics to route writes to.
+    ///
+    /// Writes for a namespace assigned to one of these topics in the catalog
+    /// are sent to it, and writes for namespaces assigned to the
+    /// "write-buffer-topic" (or not yet observed) to the "write-buffer-topic".
+    /// Writes for a namespace assigned to any other topic are rejected. The
+    /// topics must exist in the catalog, and namespaces are assigned to them
+    /// with `influxdb_iox namespace topic`.
+    #[clap(
+        long = "write-buffer-additional-topics",
+        env = "INFLUXDB_IOX_WRITE_BUFFER_ADDITIONAL_TOPICS",
+        use_value_delimiter = true,
+        action = clap::ArgAction::Append
+    )]
+    pub write_buffer_additional_topics: Vec<String>,
+
+    /// How often, in seconds, to reload the topic assigned to each namespace
+    /// from the catalog.
+    #[clap(
+        long = "namespace-topic-refresh-interval-seconds",
+        env = "INFLUXDB_IOX_NAMESPACE_TOPIC_REFRESH_INTERVAL_SECONDS",
+        default_value = "60",
+        action
+    )]
+    pub namespace_topic_refresh_interval_seconds: u64,
+}
+
+impl Default for TopicRoutingConfig {
+    fn default() -> Self {
+        Self {
+            write_buffer_additional_topics: vec![],
+            namespace_topic_refresh_interval_seconds: 60,
+        }
+    }
+}
+
+impl TopicRoutingConfig {
+    /// The interval between reloads of the namespace topics.
+    pub fn namespace_topic_refresh_interval(&self) -> Duration {
+        Duration::from_secs(self.namespace_topic_refresh_interval_seconds)
+    }
+}
+
+/// CLI config for the composition of the DML handler stack that writes pass
+/// through.
+#[derive(Debug, Clone, Default, clap::Parser)]
+pub struct DmlHandlerConfig {
+    /// Path to a TOML file configuring the DML handlers that writes pass
+    /// through, and their parameters. For example:
+    ///
+    /// ```toml
+    /// # Reject writes containing data outside of the retention period of
+    /// # the namespace. Enabled by default.
+    /// [retention_validator]
+    /// enabled = false
+    ///
+    /// # Partition writes by their time, formatted with this strftime format.
+    /// # Defaults to "%Y-%m-%d" (daily partitions).
+    /// [partitioner]
+    /// time_format = "%Y-%m"
+    /// # Append more parts to the partition key: the sanitized value of a
+    /// # tag, one of N hash buckets of the value of a tag, or a literal.
+    /// parts = ["tag(region)", "bucket(host, 16)", "literal(v2)"]
+    ///
+    /// # Rewrite the writes to a namespace before they are validated. No
+    /// # namespace is rewritten by default.
+    /// [transform.myorg_mybucket]
+    /// rename_measurements = { cpu_total = "cpu" }
+    /// rename_tags = { hostname = "host" }
+    /// drop_fields = ["debug"]
+    /// ```
+    ///
+    /// Handlers and parameters not listed in the file keep their defaults.
+    #[clap(
+        long = "dml-handler-config-file",
+        env = "INFLUXDB_IOX_DML_HANDLER_CONFIG_FILE",
+        action
+    )]
+    pub dml_handler_config_file: Option<PathBuf>,
+}
+
+impl DmlHandlerConfig {
+    /// Return the DML handler chain described by `--dml-handler-config-file`,
+    /// or the default chain if unset.
+    ///
+    /// Returns `Err` if there are any problems reading, deserializing, or
+    /// validating the file.
+    pub fn handler_chain(&self) -> Result<DmlHandlerChainConfig, Error> {
+        match &self.dml_handler_config_file {
+            Some(file) => {
+                let contents =
+                    fs::read_to_string(file).context(DmlHandlerConfigFileReadingSnafu { file })?;
+                DmlHandlerChainConfig::from_toml(&contents)
+            }
+            None => Ok(DmlHandlerChainConfig::default()),
+        }
+    }
+}
+
+/// The composition of the DML handler stack. See `--dml-handler-config-file`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DmlHandlerChainConfig {
+    /// Validation of the write time against the namespace retention period.
+    pub retention_validator: RetentionValidatorConfig,
+
+    /// Partitioning of writes.
+    pub partitioner: PartitionerConfig,
+
+    /// Rules rewriting the writes, keyed by namespace.
+    pub transform: BTreeMap<String, TransformRulesConfig>,
+}
+
+impl DmlHandlerChainConfig {
+    /// Deserialize and validate a handler chain from TOML.
+    pub fn from_toml(contents: &str) -> Result<Self, Error> {
+        let config: Self = toml::from_str(contents).context(DmlHandlerConfigDeserializingSnafu)?;
+
+        let time_formats = config
+            .partitioner
+            .parts
+            .iter()
+            .filter_map(|part| match part {
+                TemplatePart::TimeFormat(time_format) => Some(time_format),
+                _ => None,
+            });
+        for time_format in std::iter::once(&config.partitioner.time_format).chain(time_formats) {
+            ensure!(
+                is_valid_time_format(time_format),
+                PartitionerTimeFormatSnafu { time_format }
+            );
+        }
+
+        for (namespace, rules) in &config.transform {
+            for name in rules
+                .rename_measurements
+                .values()
+                .chain(rules.rename_tags.values())
+            {
+                ensure!(
+                    !name.is_empty() && name != "time",
+                    TransformRenameSnafu { namespace, name }
+                );
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+/// Config of the retention validator of the DML handler stack.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetentionValidatorConfig {
+    /// Reject writes with data outside of the namespace retention period.
+    pub enabled: bool,
+}
+
+impl Default for RetentionValidatorConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Transform rules of a namespace in the DML handler stack.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransformRulesConfig {
+    /// New names of measurements, keyed by their name in the write.
+    pub rename_measurements: BTreeMap<String, String>,
+
+    /// New keys of tags, keyed by their key in the write.
+    pub rename_tags: BTreeMap<String, String>,
+
+    /// Fields to remove from all measurements.
+    pub drop_fields: BTreeSet<String>,
+}
+
+/// Config of the partitioner of the DML handler stack.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PartitionerConfig {
+    /// The strftime format of the write time that forms the partition key.
+    pub time_format: String,
+
+    /// The parts of the partition key after the time, see
+    /// [`TemplatePart`]'s `FromStr` implementation for their syntax.
+    #[serde(deserialize_with = "deserialize_template_parts")]
+    pub parts: Vec<TemplatePart>,
+}
+
+impl PartitionerConfig {
+    /// The template writes are partitioned by.
+    pub fn template(&self) -> PartitionTemplate {
+        PartitionTemplate {
+            parts: std::iter::once(TemplatePart::TimeFormat(self.time_format.clone()))
+                .chain(self.parts.iter().cloned())
+                .collect(),
+        }
+    }
+}
+
+impl Default for PartitionerConfig {
+    fn default() -> Self {
+        Self {
+            time_format: "%Y-%m-%d".to_string(),
+            parts: vec![],
+        }
+    }
+}
+
+fn deserialize_template_parts<'de, D>(deserializer: D) -> Result<Vec<TemplatePart>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|part| part.parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_schema_conflict_config() {
+        let config = SchemaConflictConfig::try_parse_from([
+            "my_binary",
+            "--schema-conflict-policy",
+            "coerce",
+            "--namespace-schema-conflict-policies",
+            "a_b=drop-lines,c_d=reject",
+        ])
+        .unwrap();
+        assert_eq!(config.schema_conflict_policy, SchemaConflictPolicy::Coerce);
+        assert_eq!(
+            config.namespace_schema_conflict_policies,
+            [
+                ("a_b".to_string(), SchemaConflictPolicy::DropLines),
+                ("c_d".to_string(), SchemaConflictPolicy::Reject),
+            ]
+        );
+
+        let config = SchemaConflictConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(config.schema_conflict_policy, SchemaConflictPolicy::Reject);
+        assert!(config.namespace_schema_conflict_policies.is_empty());
+
+        SchemaConflictConfig::try_parse_from([
+            "my_binary",
+            "--namespace-schema-conflict-policies",
+            "a_b",
+        ])
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_namespace_autocreation_config() {
+        let config = NamespaceAutocreationConfig::try_parse_from([
+            "my_binary",
+            "--new-namespace-retention-hours",
+            "24",
+            "--new-namespace-partition-template",
+            "time(%Y-%m)",
+            "--new-namespace-partition-template",
+            "bucket(host, 16)",
+        ])
+        .unwrap();
+        assert_eq!(
+            config.new_namespace_retention_period_ns(),
+            Some(24 * NANOS_PER_HOUR)
+        );
+        assert_eq!(
+            config.new_namespace_partition_template().unwrap().parts,
+            [
+                TemplatePart::TimeFormat("%Y-%m".to_string()),
+                TemplatePart::Bucket(data_types::TagBucket {
+                    tag: "host".to_string(),
+                    buckets: 16
+                }),
+            ]
+        );
+
+        let config = NamespaceAutocreationConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(config.new_namespace_retention_period_ns(), None);
+        assert_eq!(config.new_namespace_partition_template(), None);
+
+        // the longest retention period that fits in nanoseconds is accepted
+        let config = NamespaceAutocreationConfig::try_parse_from([
+            "my_binary",
+            "--new-namespace-retention-hours",
+            &MAX_RETENTION_HOURS.to_string(),
+        ])
+        .unwrap();
+        assert!(config.new_namespace_retention_period_ns().unwrap() > 0);
+
+        for args in [
+            ["--new-namespace-retention-hours", "4294967295"],
+            ["--new-namespace-partition-template", "bananas"],
+            ["--new-namespace-partition-template", "time(%Q)"],
+        ] {
+            NamespaceAutocreationConfig::try_parse_from(std::iter::once("my_binary").chain(args))
+                .unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_topic_routing_config() {
+        let config = TopicRoutingConfig::try_parse_from([
+            "my_binary",
+            "--write-buffer-additional-topics",
+            "noisy,noisier",
+            "--namespace-topic-refresh-interval-seconds",
+            "5",
+        ])
+        .unwrap();
+        assert_eq!(config.write_buffer_additional_topics, ["noisy", "noisier"]);
+        assert_eq!(
+            config.namespace_topic_refresh_interval(),
+            Duration::from_secs(5)
+        );
+
+        let config = TopicRoutingConfig::try_parse_from(["my_binary"]).unwrap();
+        assert!(config.write_buffer_additional_topics.is_empty());
+        assert_eq!(
+            config.namespace_topic_refresh_interval(),
+            TopicRoutingConfig::default().namespace_topic_refresh_interval()
+        );
+    }
+
+    #[test]
+    fn test_dml_handler_chain_config() {
+        let config = DmlHandlerChainConfig::from_toml(
+            r#"
+            [retention_validator]
+            enabled = false
+
+            [partitioner]
+            time_format = "%Y-%m"
+            "#,
+        )
+        .unwrap();
+        assert!(!config.retention_validator.enabled);
+        assert_eq!(config.partitioner.time_format, "%Y-%m");
+
+        // unset handlers keep their defaults
+        let config = DmlHandlerChainConfig::from_toml(
+            r#"
+            [retention_validator]
+            enabled = false
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.partitioner, PartitionerConfig::default());
+        assert_eq!(
+            DmlHandlerChainConfig::from_toml("").unwrap(),
+            DmlHandlerChainConfig::default()
+        );
+
+        // unknown handlers are rejected
+        let err = DmlHandlerChainConfig::from_toml("[quota]\nenabled = true").unwrap_err();
+        assert!(matches!(err, Error::DmlHandlerConfigDeserializing { .. }));
+
+        let err =
+            DmlHandlerChainConfig::from_toml("[partitioner]\ntime_format = \"%Q\"").unwrap_err();
+        assert!(matches!(err, Error::PartitionerTimeFormat { .. }));
+    }
+
+    #[test]
+    fn test_dml_handler_chain_config_partitioner_parts() {
+        let config = DmlHandlerChainConfig::from_toml(
+            r#"
+            [partitioner]
+            parts = ["tag(region)", "bucket(host, 16)", "literal(v2)"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.partitioner.template().parts,
+            vec![
+                TemplatePart::TimeFormat("%Y-%m-%d".to_string()),
+                TemplatePart::TagValue("region".to_string()),
+                TemplatePart::Bucket(data_types::TagBucket {
+                    tag: "host".to_string(),
+                    buckets: 16
+                }),
+                TemplatePart::Literal("v2".to_string()),
+            ]
+        );
+
+        let err = DmlHandlerChainConfig::from_toml("[partitioner]\nparts = [\"bucket(host, 0)\"]")
+            .unwrap_err();
+        assert!(matches!(err, Error::DmlHandlerConfigDeserializing { .. }));
+
+        let err =
+            DmlHandlerChainConfig::from_toml("[partitioner]\nparts = [\"time(%Q)\"]").unwrap_err();
+        assert!(matches!(err, Error::PartitionerTimeFormat { .. }));
+    }
+
+    #[test]
+    fn test_dml_handler_chain_config_transform() {
+        let config = DmlHandlerChainConfig::from_toml(
+            r#"
+            [transform.myorg_mybucket]
+            rename_measurements = { cpu_total = "cpu" }
+            rename_tags = { hostname = "host" }
+            drop_fields = ["debug"]
+
+            [transform.myorg_other]
+            drop_fields = ["debug"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.transform.len(), 2);
+        assert_eq!(
+            config.transform["myorg_mybucket"],
+            TransformRulesConfig {
+                rename_measurements: BTreeMap::from([("cpu_total".into(), "cpu".into())]),
+                rename_tags: BTreeMap::from([("hostname".into(), "host".into())]),
+                drop_fields: BTreeSet::from(["debug".into()]),
+            }
+        );
+        assert!(config.transform["myorg_other"].rename_tags.is_empty());
+
+        let err = DmlHandlerChainConfig::from_toml(
+            "[transform.myorg_mybucket]\nrename_tags = { host = \"time\" }",
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::TransformRename { .. }));
+
+        let err = DmlHandlerChainConfig::from_toml("[transform.myorg_mybucket]\ndrop_tags = []")
+            .unwrap_err();
+        assert!(matches!(err, Error::DmlHandlerConfigDeserializing { .. }));
+    }
+
+    #[test]
+    fn test_dml_handler_config_file() {
+        let config = DmlHandlerConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(
+            config.handler_chain().unwrap(),
+            DmlHandlerChainConfig::default()
+        );
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "[retention_validator]\nenabled = false").unwrap();
+        let config = DmlHandlerConfig::try_parse_from([
+            "my_binary",
+            "--dml-handler-config-file",
+            file.path().to_str().unwrap(),
+        ])
+        .unwrap();
+        assert!(!config.handler_chain().unwrap().retention_validator.enabled);
+    }
+}
@@ -2,7 +2,10 @@
 use trace_exporters::TracingConfig;
 use trogging::cli::LoggingConfig;
 
-use crate::{object_store::ObjectStoreConfig, socket_addr::SocketAddr};
+use crate::{
+    access_log::AccessLogConfig, grpc_server::GrpcServerConfig, metrics_push::MetricsPushConfig,
+    object_store::ObjectStoreConfig, socket_addr::SocketAddr,
+};
 
 /// The default bind address for the HTTP API.
 pub const DEFAULT_API_BIND_ADDR: &str = "127.0.0.1:8080";
@@ -21,6 +24,14 @@ pub struct RunConfig {
     #[clap(flatten)]
     pub(crate) tracing_config: TracingConfig,
 
+    /// HTTP access log options
+    #[clap(flatten)]
+    pub(crate) access_log_config: AccessLogConfig,
+
+    /// metrics pushgateway options
+    #[clap(flatten)]
+    pub(crate) metrics_push_config: MetricsPushConfig,
+
     /// The address on which IOx will serve HTTP API requests.
     #[clap(
         long = "api-bind",
@@ -39,6 +50,10 @@ pub struct RunConfig {
     )]
     pub grpc_bind_address: SocketAddr,
 
+    /// gRPC server options
+    #[clap(flatten)]
+    pub(crate) grpc_server_config: GrpcServerConfig,
+
     /// Maximum size of HTTP requests.
     #[clap(
         long = "max-http-request-size",
@@ -69,6 +84,21 @@ impl RunConfig {
         &mut self.tracing_config
     }
 
+    /// Get a reference to the run config's HTTP access log config.
+    pub fn access_log_config(&self) -> &AccessLogConfig {
+        &self.access_log_config
+    }
+
+    /// Get a reference to the run config's metrics pushgateway config.
+    pub fn metrics_push_config(&self) -> &MetricsPushConfig {
+        &self.metrics_push_config
+    }
+
+    /// Get a reference to the run config's gRPC server config.
+    pub fn grpc_server_config(&self) -> &GrpcServerConfig {
+        &self.grpc_server_config
+    }
+
     /// Get a reference to the run config's logging config.
     pub fn logging_config(&self) -> &LoggingConfig {
         &self.logging_config
@@ -90,16 +120,22 @@ impl RunConfig {
     pub fn new(
         logging_config: LoggingConfig,
         tracing_config: TracingConfig,
+        access_log_config: AccessLogConfig,
+        metrics_push_config: MetricsPushConfig,
         http_bind_address: SocketAddr,
         grpc_bind_address: SocketAddr,
+        grpc_server_config: GrpcServerConfig,
         max_http_request_size: usize,
         object_store_config: ObjectStoreConfig,
     ) -> Self {
         Self {
             logging_config,
             tracing_config,
+            access_log_config,
+            metrics_push_config,
             http_bind_address,
             grpc_bind_address,
+            grpc_server_config,
             max_http_request_size,
             object_store_config,
         }
@@ -85,6 +85,18 @@ impl WriteBufferConfig {
         }
     }
 
+    /// Return a copy of this config connecting to `topic` instead of the
+    /// configured topic.
+    pub fn for_topic(&self, topic: &str) -> Self {
+        Self {
+            type_: self.type_.clone(),
+            connection_string: self.connection_string.clone(),
+            topic: topic.to_string(),
+            connection_config: self.connection_config.clone(),
+            auto_create_topics: self.auto_create_topics,
+        }
+    }
+
     /// Initialize a [`WriteBufferWriting`].
     pub async fn writing(
         &self,
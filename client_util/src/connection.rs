@@ -130,6 +130,11 @@ pub struct Builder {
     headers: Vec<(HeaderName, HeaderValue)>,
     connect_timeout: Duration,
     timeout: Duration,
+    keepalive_interval: Option<Duration>,
+    keepalive_timeout: Option<Duration>,
+    initial_stream_window_size: Option<u32>,
+    initial_connection_window_size: Option<u32>,
+    tcp_nodelay: bool,
 }
 
 impl std::default::Default for Builder {
@@ -139,6 +144,11 @@ impl std::default::Default for Builder {
             connect_timeout: DEFAULT_CONNECT_TIMEOUT,
             timeout: DEFAULT_TIMEOUT,
             headers: Default::default(),
+            keepalive_interval: None,
+            keepalive_timeout: None,
+            initial_stream_window_size: None,
+            initial_connection_window_size: None,
+            tcp_nodelay: true,
         }
     }
 }
@@ -177,10 +187,19 @@ impl Builder {
     where
         D: TryInto<Uri, Error = InvalidUri> + Send,
     {
-        let endpoint = Endpoint::from(dst.try_into()?)
+        let mut endpoint = Endpoint::from(dst.try_into()?)
             .user_agent(&self.user_agent)?
             .connect_timeout(self.connect_timeout)
-            .timeout(self.timeout);
+            .timeout(self.timeout)
+            .initial_stream_window_size(self.initial_stream_window_size)
+            .initial_connection_window_size(self.initial_connection_window_size)
+            .tcp_nodelay(self.tcp_nodelay);
+        if let Some(interval) = self.keepalive_interval {
+            endpoint = endpoint.http2_keep_alive_interval(interval);
+        }
+        if let Some(timeout) = self.keepalive_timeout {
+            endpoint = endpoint.keep_alive_timeout(timeout);
+        }
         Ok(endpoint)
     }
 
@@ -243,6 +262,57 @@ impl Builder {
     pub fn timeout(self, timeout: Duration) -> Self {
         Self { timeout, ..self }
     }
+
+    /// Sends HTTP/2 pings at this interval to keep the gRPC connection
+    /// alive, for example through proxies that close idle connections.
+    pub fn keepalive_interval(self, interval: Duration) -> Self {
+        Self {
+            keepalive_interval: Some(interval),
+            ..self
+        }
+    }
+
+    /// Sets how long to wait for the acknowledgement of a keepalive ping
+    /// before closing the connection.
+    ///
+    /// Only used if a [`keepalive_interval`](Self::keepalive_interval) is
+    /// set.
+    pub fn keepalive_timeout(self, timeout: Duration) -> Self {
+        Self {
+            keepalive_timeout: Some(timeout),
+            ..self
+        }
+    }
+
+    /// Sets the size of the HTTP/2 flow control window of a single gRPC
+    /// request, i.e. how many bytes of a response may be in flight.
+    ///
+    /// Raising it speeds up fetching large Flight responses over high latency
+    /// connections.
+    pub fn initial_stream_window_size(self, size: u32) -> Self {
+        Self {
+            initial_stream_window_size: Some(size),
+            ..self
+        }
+    }
+
+    /// Sets the size of the HTTP/2 flow control window shared by all gRPC
+    /// requests of the connection.
+    pub fn initial_connection_window_size(self, size: u32) -> Self {
+        Self {
+            initial_connection_window_size: Some(size),
+            ..self
+        }
+    }
+
+    /// Sets whether to disable Nagle's algorithm on the TCP connection.
+    /// Enabled by default.
+    pub fn tcp_nodelay(self, tcp_nodelay: bool) -> Self {
+        Self {
+            tcp_nodelay,
+            ..self
+        }
+    }
 }
 
 #[cfg(test)]
@@ -1,11 +1,17 @@
 //! Compact partitions that are cold because they have not gotten writes recently and they're not
 //! fully compacted.
+//!
+//! The full compaction of a cold partition compacts its level 1 files to a single level 2 file,
+//! sorted by the partition's sort key. Partitions left with only level 2 files are then marked cold
+//! in the catalog, which lets the querier skip asking the ingesters for their data.
 
 use crate::{
-    compact::Compactor, compact_candidates_with_memory_budget, compact_in_parallel,
-    parquet_file_combining, parquet_file_lookup, utils::get_candidates_with_retry,
+    compact::{Compactor, PartitionCompactionCandidateWithInfo},
+    compact_candidates_with_memory_budget, compact_in_parallel, parquet_file_combining,
+    parquet_file_lookup,
+    utils::get_candidates_with_retry,
 };
-use data_types::CompactionLevel;
+use data_types::{CompactionLevel, Timestamp};
 use metric::Attributes;
 use observability_deps::tracing::*;
 use snafu::Snafu;
@@ -51,16 +57,18 @@ pub async fn compact(compactor: Arc<Compactor>, do_full_compact: bool) -> usize
     .await;
 
     if do_full_compact {
-        //Compact level 1 files in parallel ("full compaction")
+        //Compact level 1 files in parallel ("full compaction") to one file per partition
         compact_candidates_with_memory_budget(
             Arc::clone(&compactor),
             compaction_type,
             CompactionLevel::FileNonOverlapped,
             compact_in_parallel,
-            true, // split
-            candidates.into(),
+            false, // no split
+            candidates.clone().into(),
         )
         .await;
+
+        mark_cold(&compactor, &candidates).await;
     }
 
     // Done compacting all candidates in the cycle, record its time
@@ -77,6 +85,30 @@ pub async fn compact(compactor: Arc<Compactor>, do_full_compact: bool) -> usize
     n_candidates
 }
 
+/// Mark the fully compacted partitions among `candidates` as cold in the catalog.
+///
+/// Partitions that still have files below level 2, because they did not fit in the memory budget,
+/// are not marked and get compacted again in a later cycle.
+async fn mark_cold(
+    compactor: &Compactor,
+    candidates: &[Arc<PartitionCompactionCandidateWithInfo>],
+) {
+    let now = Timestamp::from(compactor.time_provider.now());
+    let mut repos = compactor.catalog.repositories().await;
+
+    for candidate in candidates {
+        let partition_id = candidate.id();
+        match repos.partitions().mark_cold(partition_id, now).await {
+            Ok(true) => debug!(?partition_id, "marked partition cold"),
+            Ok(false) => debug!(
+                ?partition_id,
+                "partition not fully compacted, not marked cold"
+            ),
+            Err(e) => warn!(%e, ?partition_id, "failed to mark partition cold"),
+        }
+    }
+}
+
 #[derive(Debug, Snafu)]
 #[allow(missing_copy_implementations, missing_docs)]
 pub(crate) enum Error {
@@ -700,6 +732,7 @@ mod tests {
             max_desired_file_size_bytes: 10_000,
             percentage_max_file_size: 30,
             split_percentage: 80,
+            max_output_file_size_bytes: None,
             max_number_partitions_per_shard: 1,
             min_number_recent_ingested_files_per_partition: 1,
             hot_multiple: 4,
@@ -825,12 +858,12 @@ mod tests {
 
         compact(compactor, true).await;
 
-        // Should have 2 non-soft-deleted file:
+        // Should have 1 non-soft-deleted file:
         //
-        // - the 2 level-2 files created after combining all 3 level 1 files created by the first step
+        // - the level-2 file created after combining all 3 level 1 files created by the first step
         //   of compaction to compact remaining level 0 files
         let mut files = catalog.list_by_table_not_to_delete(table.table.id).await;
-        assert_eq!(files.len(), 2, "{files:?}");
+        assert_eq!(files.len(), 1, "{files:?}");
         let files_and_levels: Vec<_> = files
             .iter()
             .map(|f| (f.id.get(), f.compaction_level))
@@ -838,29 +871,23 @@ mod tests {
 
         // The initial files are: L0 1-4, L1 5-6. The first step of cold compaction took files 1-5
         // and compacted them into two l-1 files 7, 8. The second step of cold compaction
-        // took 6, 7, and 8 and combined them all into two files 9 and 10.
-        assert_eq!(
-            files_and_levels,
-            vec![(9, CompactionLevel::Final), (10, CompactionLevel::Final)]
-        );
+        // took 6, 7, and 8 and combined them all into one file 9.
+        assert_eq!(files_and_levels, vec![(9, CompactionLevel::Final)]);
+
+        // The partition is fully compacted, so it is marked cold
+        let partition = catalog
+            .catalog
+            .repositories()
+            .await
+            .partitions()
+            .get_by_id(files[0].partition_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(partition.cold_compacted_at.is_some());
 
         // ------------------------------------------------
         // Verify the parquet file content
-        // first file:
-        let file = files.pop().unwrap();
-        let batches = table.read_parquet_file(file).await;
-        assert_batches_sorted_eq!(
-            &[
-                "+-----------+------+------+------+-----------------------------+",
-                "| field_int | tag1 | tag2 | tag3 | time                        |",
-                "+-----------+------+------+------+-----------------------------+",
-                "| 421       |      | OH   | 21   | 1970-01-01T00:00:00.000091Z |",
-                "| 81601     |      | PA   | 15   | 1970-01-01T00:00:00.000090Z |",
-                "+-----------+------+------+------+-----------------------------+",
-            ],
-            &batches
-        );
-        // second file
         let file = files.pop().unwrap();
         let batches = table.read_parquet_file(file).await;
         assert_batches_sorted_eq!(
@@ -878,7 +905,9 @@ mod tests {
                 "| 20        |      | VT   | 20   | 1970-01-01T00:00:00.000026Z    |",
                 "| 21        |      | OH   | 21   | 1970-01-01T00:00:00.000000025Z |",
                 "| 270       | UT   |      |      | 1970-01-01T00:00:00.000025Z    |",
+                "| 421       |      | OH   | 21   | 1970-01-01T00:00:00.000091Z    |",
                 "| 70        | UT   |      |      | 1970-01-01T00:00:00.000020Z    |",
+                "| 81601     |      | PA   | 15   | 1970-01-01T00:00:00.000090Z    |",
                 "+-----------+------+------+------+--------------------------------+",
             ],
             &batches
@@ -1081,23 +1110,33 @@ mod tests {
         compact(compactor, true).await;
 
         let files = catalog.list_by_table_not_to_delete(table.table.id).await;
-        assert_eq!(files.len(), 3, "{files:?}");
+        assert_eq!(files.len(), 2, "{files:?}");
         let files_and_levels: Vec<_> = files
             .iter()
             .map(|f| (f.id.get(), f.compaction_level))
             .collect();
 
         // Compaction will select files 1 and 2 because adding file 3 would go over the memory
-        // budget. File 3 remains untouched. Files 1 and 2 are compacted and then split into files
-        // 4 and 5 at level 2.
+        // budget. File 3 remains untouched. Files 1 and 2 are compacted into file 4 at level 2.
         assert_eq!(
             files_and_levels,
             vec![
                 (3, CompactionLevel::FileNonOverlapped),
                 (4, CompactionLevel::Final),
-                (5, CompactionLevel::Final),
             ]
         );
+
+        // File 3 is still at level 1, so the partition is not marked cold yet
+        let partition = catalog
+            .catalog
+            .repositories()
+            .await
+            .partitions()
+            .get_by_id(files[0].partition_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(partition.cold_compacted_at, None);
     }
 
     #[tokio::test]
@@ -1256,14 +1295,14 @@ mod tests {
 
         compact(compactor, true).await;
 
-        // Should have 4 non-soft-deleted files:
+        // Should have 3 non-soft-deleted files:
         //
         // - pf4, the level 1 file untouched because it didn't fit in the memory budget
         // - pf6, the level 2 file untouched because it doesn't overlap anything
-        // - two level-2 files created after combining all 3 level 1 files created by the first step
-        //   of compaction to compact remaining level 0 files
+        // - the level-2 file created after combining the level 1 files with the level 2 files they
+        //   overlap
         let mut files = catalog.list_by_table_not_to_delete(table.table.id).await;
-        assert_eq!(files.len(), 4, "{files:?}");
+        assert_eq!(files.len(), 3, "{files:?}");
         let files_and_levels: Vec<_> = files
             .iter()
             .map(|f| (f.id.get(), f.compaction_level))
@@ -1271,33 +1310,19 @@ mod tests {
 
         // File 4 was L1 but didn't fit in the memory budget, so was untouched.
         // File 6 was already L2 and did not overlap with anything, so was untouched.
-        // Cold compaction took files 1, 2, 3, 5 and compacted them into 2 files 7 and 8.
+        // Cold compaction took files 1, 2, 3, 5 and compacted them into file 7.
         assert_eq!(
             files_and_levels,
             vec![
                 (4, CompactionLevel::FileNonOverlapped),
                 (6, CompactionLevel::Final),
                 (7, CompactionLevel::Final),
-                (8, CompactionLevel::Final),
             ]
         );
 
         // ------------------------------------------------
         // Verify the parquet file content
-        // newly created L-2 with largest timestamp
-        let file = files.pop().unwrap();
-        let batches = table.read_parquet_file(file).await;
-        assert_batches_sorted_eq!(
-            &[
-                "+-----------+------+------+------+-----------------------------+",
-                "| field_int | tag1 | tag2 | tag3 | time                        |",
-                "+-----------+------+------+------+-----------------------------+",
-                "| 270       | UT   |      |      | 1970-01-01T00:00:00.000025Z |",
-                "+-----------+------+------+------+-----------------------------+",
-            ],
-            &batches
-        );
-        // newly created L-2 with smallest timestamp
+        // newly created L-2
         let file = files.pop().unwrap();
         let batches = table.read_parquet_file(file).await;
         assert_batches_sorted_eq!(
@@ -1312,6 +1337,7 @@ mod tests {
                 "| 1500      | WA   |      |      | 1970-01-01T00:00:00.000008Z    |",
                 "| 1601      |      | PA   | 15   | 1970-01-01T00:00:00.000000009Z |",
                 "| 21        |      | OH   | 21   | 1970-01-01T00:00:00.000000025Z |",
+                "| 270       | UT   |      |      | 1970-01-01T00:00:00.000025Z    |",
                 "| 70        | UT   |      |      | 1970-01-01T00:00:00.000020Z    |",
                 "+-----------+------+------+------+--------------------------------+",
             ],
@@ -143,6 +143,10 @@ pub struct Compactor {
     ///  . Whether there is a big difference between each cycle or not
     ///  . How well this process  is parallelized
     pub(crate) compaction_cycle_duration: Metric<DurationHistogram>,
+
+    /// Tag columns whose values are indexed in the tag index of each compacted partition, see
+    /// [`parquet_file::tag_index`].
+    pub(crate) tag_index_columns: Vec<String>,
 }
 
 impl Compactor {
@@ -247,9 +251,17 @@ impl Compactor {
             candidate_selection_duration,
             partitions_extra_info_reading_duration,
             compaction_cycle_duration,
+            tag_index_columns: vec![],
         }
     }
 
+    /// Index the values of the tag columns `tag_index_columns` of the compacted files in the tag
+    /// index of their partition.
+    pub fn with_tag_index_columns(mut self, tag_index_columns: Vec<String>) -> Self {
+        self.tag_index_columns = tag_index_columns;
+        self
+    }
+
     /// Access to the TimeProvider
     pub fn time_provider(&self) -> Arc<dyn TimeProvider> {
         Arc::clone(&self.time_provider) as _
@@ -687,6 +699,7 @@ pub mod tests {
             max_desired_file_size_bytes: 10_000,
             percentage_max_file_size: 30,
             split_percentage: 80,
+            max_output_file_size_bytes: None,
             max_number_partitions_per_shard: 1,
             min_number_recent_ingested_files_per_partition: 1,
             hot_multiple: 4,
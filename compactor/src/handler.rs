@@ -113,6 +113,13 @@ pub struct CompactorConfig {
     /// This value must be between (0, 100)
     pub split_percentage: u16,
 
+    /// Max size of compacted parquet files.
+    /// If set, compacted data estimated to be larger than this is split into more files than
+    /// `max_desired_file_size_bytes` alone would lead to, taking the size and time range of each
+    /// compacted file into account. Like `max_desired_file_size_bytes`, this is a target rather
+    /// than a guarantee.
+    pub max_output_file_size_bytes: Option<u64>,
+
     /// Max number of partitions per shard we want to compact per cycle
     pub max_number_partitions_per_shard: usize,
 
@@ -399,7 +399,9 @@ pub(crate) async fn compact_one_partition(
             compactor.config.max_desired_file_size_bytes,
             compactor.config.percentage_max_file_size,
             compactor.config.split_percentage,
+            compactor.config.max_output_file_size_bytes,
             target_level,
+            &compactor.tag_index_columns,
         )
         .await
         .map_err(|e| CompactOnePartitionError::Combining {
@@ -415,6 +417,7 @@ pub(crate) async fn compact_one_partition(
             Arc::clone(&compactor.time_provider),
             &compactor.compaction_input_file_bytes,
             target_level,
+            &compactor.tag_index_columns,
         )
         .await
         .map_err(|e| CompactOnePartitionError::Combining {
@@ -568,6 +571,7 @@ pub mod tests {
             max_desired_file_size_bytes: 100_000_000,
             percentage_max_file_size: 90,
             split_percentage: 100,
+            max_output_file_size_bytes: None,
             max_number_partitions_per_shard: 100,
             min_number_recent_ingested_files_per_partition: 1,
             hot_multiple: 4,
@@ -950,6 +954,7 @@ pub mod tests {
             max_desired_file_size_bytes: 10_000,
             percentage_max_file_size: 30,
             split_percentage: 80,
+            max_output_file_size_bytes: None,
             max_number_partitions_per_shard: 1,
             min_number_recent_ingested_files_per_partition: 1,
             hot_multiple: 4,
@@ -10,11 +10,12 @@ use datafusion::{error::DataFusionError, logical_expr::LogicalPlan};
 use futures::{stream::FuturesOrdered, StreamExt, TryStreamExt};
 use iox_catalog::interface::Catalog;
 use iox_query::{
-    exec::{Executor, ExecutorType},
+    exec::{Executor, ExecutorType, IOxSessionContext},
     frontend::reorg::ReorgPlanner,
+    row_ttl::RowTtl,
     QueryChunk,
 };
-use iox_time::TimeProvider;
+use iox_time::{Time, TimeProvider};
 use metric::{Attributes, Metric, U64Histogram};
 use observability_deps::tracing::*;
 use parquet_file::{
@@ -22,12 +23,14 @@ use parquet_file::{
     metadata::IoxMetadata,
     serialize::CodecError,
     storage::{ParquetStorage, UploadError},
+    tag_index::{collect_tag_values, TagIndex, TagIndexPath, TagValues},
 };
+use predicate::Predicate;
 use schema::{sort::SortKey, Schema};
 use snafu::{ensure, ResultExt, Snafu};
 use std::{
     cmp::{max, min},
-    collections::BTreeMap,
+    collections::{BTreeMap, HashSet},
     future,
     sync::Arc,
 };
@@ -97,8 +100,13 @@ pub(crate) async fn compact_parquet_files(
     // When data is between a "small" and "large" amount, split the compacted files at roughly this
     // percentage in the earlier compacted file, and the remainder in the later compacted file.
     split_percentage: u16,
+    // If set, split the compacted data further so that no compacted file is estimated to be larger
+    // than this, taking the size and time range of each input file into account.
+    max_output_file_size_bytes: Option<u64>,
     // Compaction level the newly created file will have.
     target_level: CompactionLevel,
+    // Tag columns whose values are added to the tag index of the partition.
+    tag_index_columns: &[String],
 ) -> Result<(), Error> {
     let partition_id = partition.id();
 
@@ -138,8 +146,12 @@ pub(crate) async fn compact_parquet_files(
     );
 
     // Collect all the parquet file IDs, to be able to set their catalog records to be
-    // deleted. These should already be unique, no need to dedupe.
-    let original_parquet_file_ids: Vec<_> = files.iter().map(|f| f.id()).collect();
+    // deleted and record them as the inputs of the compacted files, with their compaction
+    // levels. These should already be unique, no need to dedupe.
+    let original_parquet_files: Vec<_> = files
+        .iter()
+        .map(|f| (f.id(), f.compaction_level()))
+        .collect();
 
     // Convert the input files into QueryableParquetChunk for making query plan
     let query_chunks: Vec<_> = files
@@ -177,6 +189,12 @@ pub(crate) async fn compact_parquet_files(
         .iter()
         .map(|c| TimestampMinMax::new(c.min_time(), c.max_time()))
         .collect();
+    // and with the size of each chunk, for estimating the size of the data in a time range.
+    let chunk_sizes: Vec<_> = chunk_times
+        .iter()
+        .copied()
+        .zip(file_sizes.iter().map(|&size| size as u64))
+        .collect();
 
     // Merge schema of the compacting chunks
     let query_chunks: Vec<_> = query_chunks
@@ -199,10 +217,49 @@ pub(crate) async fn compact_parquet_files(
     let (small_cutoff_bytes, large_cutoff_bytes) =
         cutoff_bytes(max_desired_file_size_bytes, percentage_max_file_size);
 
-    let ctx = exec.new_context(ExecutorType::Reorg);
-    let plan = if total_size <= small_cutoff_bytes {
+    let split_times = if total_size <= small_cutoff_bytes {
         // Compact everything into one file
-        ReorgPlanner::new(ctx.child_ctx("ReorgPlanner"))
+        vec![]
+    } else if small_cutoff_bytes < total_size && total_size <= large_cutoff_bytes {
+        // Split compaction into two files, the earlier of split_percentage amount of
+        // max_desired_file_size_bytes, the later of the rest
+        vec![min_time + ((max_time - min_time) * split_percentage as i64) / 100]
+    } else {
+        // Split compaction into multiple files
+        crate::utils::compute_split_time(
+            chunk_times,
+            min_time,
+            max_time,
+            total_size,
+            max_desired_file_size_bytes,
+        )
+    };
+
+    let split_times = match max_output_file_size_bytes {
+        Some(max_output_file_size_bytes) => {
+            let split_times = crate::utils::split_oversized_time_ranges(
+                &chunk_sizes,
+                min_time,
+                max_time,
+                &split_times,
+                max_output_file_size_bytes,
+            );
+            debug!(
+                ?partition_id,
+                num_split_times = split_times.len(),
+                "split times after splitting oversized time ranges"
+            );
+            split_times
+        }
+        None => split_times,
+    };
+
+    let ctx = exec.new_context(ExecutorType::Reorg);
+    let planner = reorg_planner(&ctx, &partition, &merged_schema, time_provider.now());
+    let plan = if split_times.is_empty() || (split_times.len() == 1 && split_times[0] == max_time) {
+        // The split times might not have actually split anything, so in this case, compact
+        // everything into one file
+        planner
             .compact_plan(
                 Arc::from(partition.table.name.clone()),
                 Arc::clone(&merged_schema),
@@ -211,48 +268,20 @@ pub(crate) async fn compact_parquet_files(
             )
             .context(CompactLogicalPlanSnafu)?
     } else {
-        let split_times = if small_cutoff_bytes < total_size && total_size <= large_cutoff_bytes {
-            // Split compaction into two files, the earlier of split_percentage amount of
-            // max_desired_file_size_bytes, the later of the rest
-            vec![min_time + ((max_time - min_time) * split_percentage as i64) / 100]
-        } else {
-            // Split compaction into multiple files
-            crate::utils::compute_split_time(
-                chunk_times,
-                min_time,
-                max_time,
-                total_size,
-                max_desired_file_size_bytes,
+        // split compact query plan
+        planner
+            .split_plan(
+                Arc::from(partition.table.name.clone()),
+                Arc::clone(&merged_schema),
+                query_chunks,
+                sort_key.clone(),
+                split_times,
             )
-        };
-
-        if split_times.is_empty() || (split_times.len() == 1 && split_times[0] == max_time) {
-            // The split times might not have actually split anything, so in this case, compact
-            // everything into one file
-            ReorgPlanner::new(ctx.child_ctx("ReorgPlanner"))
-                .compact_plan(
-                    Arc::from(partition.table.name.clone()),
-                    Arc::clone(&merged_schema),
-                    query_chunks,
-                    sort_key.clone(),
-                )
-                .context(CompactLogicalPlanSnafu)?
-        } else {
-            // split compact query plan
-            ReorgPlanner::new(ctx.child_ctx("ReorgPlanner"))
-                .split_plan(
-                    Arc::from(partition.table.name.clone()),
-                    Arc::clone(&merged_schema),
-                    query_chunks,
-                    sort_key.clone(),
-                    split_times,
-                )
-                .context(CompactLogicalPlanSnafu)?
-        }
+            .context(CompactLogicalPlanSnafu)?
     };
 
-    let compacted_parquet_files = compact_with_plan(
-        store,
+    let (compacted_parquet_files, tag_values): (Vec<_>, Vec<_>) = compact_with_plan(
+        store.clone(),
         exec,
         time_provider,
         plan,
@@ -261,18 +290,30 @@ pub(crate) async fn compact_parquet_files(
         partition_id,
         max_sequence_number,
         target_level,
+        tag_index_columns,
     )
-    .await?;
+    .await?
+    .into_iter()
+    .unzip();
 
-    update_catalog(
-        catalog,
+    let compacted_ids = update_catalog(
+        Arc::clone(&catalog),
         partition_id,
         compacted_parquet_files,
-        &original_parquet_file_ids,
+        &original_parquet_files,
     )
     .await
     .context(CatalogSnafu { partition_id })?;
 
+    update_tag_index(
+        catalog,
+        &store,
+        &partition,
+        compacted_ids.into_iter().zip(tag_values).collect(),
+        tag_index_columns,
+    )
+    .await;
+
     info!(?partition_id, "compaction complete");
 
     let attributes = Attributes::from([("shard_id", format!("{}", partition.shard_id()).into())]);
@@ -300,6 +341,8 @@ pub(crate) async fn compact_final_no_splits(
     compaction_input_file_bytes: &Metric<U64Histogram>,
     // Compaction level the newly created file will have.
     target_level: CompactionLevel,
+    // Tag columns whose values are added to the tag index of the partition.
+    tag_index_columns: &[String],
 ) -> Result<(), Error> {
     let partition_id = partition.id();
 
@@ -318,8 +361,12 @@ pub(crate) async fn compact_final_no_splits(
     debug!(?partition_id, num_files, "compact_final_no_splits");
 
     // Collect all the parquet file IDs, to be able to set their catalog records to be
-    // deleted. These should already be unique, no need to dedupe.
-    let original_parquet_file_ids: Vec<_> = files.iter().map(|f| f.id()).collect();
+    // deleted and record them as the inputs of the compacted files, with their compaction
+    // levels. These should already be unique, no need to dedupe.
+    let original_parquet_files: Vec<_> = files
+        .iter()
+        .map(|f| (f.id(), f.compaction_level()))
+        .collect();
 
     // Convert the input files into QueryableParquetChunk for making query plan
     let query_chunks: Vec<_> = files
@@ -372,7 +419,7 @@ pub(crate) async fn compact_final_no_splits(
 
     let ctx = exec.new_context(ExecutorType::Reorg);
     // Compact everything into one file
-    let plan = ReorgPlanner::new(ctx.child_ctx("ReorgPlanner"))
+    let plan = reorg_planner(&ctx, &partition, &merged_schema, time_provider.now())
         .compact_plan(
             Arc::from(partition.table.name.clone()),
             Arc::clone(&merged_schema),
@@ -381,8 +428,8 @@ pub(crate) async fn compact_final_no_splits(
         )
         .context(CompactLogicalPlanSnafu)?;
 
-    let compacted_parquet_files = compact_with_plan(
-        store,
+    let (compacted_parquet_files, tag_values): (Vec<_>, Vec<_>) = compact_with_plan(
+        store.clone(),
         exec,
         time_provider,
         plan,
@@ -391,18 +438,30 @@ pub(crate) async fn compact_final_no_splits(
         partition_id,
         max_sequence_number,
         target_level,
+        tag_index_columns,
     )
-    .await?;
+    .await?
+    .into_iter()
+    .unzip();
 
-    update_catalog(
-        catalog,
+    let compacted_ids = update_catalog(
+        Arc::clone(&catalog),
         partition_id,
         compacted_parquet_files,
-        &original_parquet_file_ids,
+        &original_parquet_files,
     )
     .await
     .context(CatalogSnafu { partition_id })?;
 
+    update_tag_index(
+        catalog,
+        &store,
+        &partition,
+        compacted_ids.into_iter().zip(tag_values).collect(),
+        tag_index_columns,
+    )
+    .await;
+
     info!(?partition_id, "compaction complete");
 
     let attributes = Attributes::from([("shard_id", format!("{}", partition.shard_id()).into())]);
@@ -414,6 +473,29 @@ pub(crate) async fn compact_final_no_splits(
     Ok(())
 }
 
+/// Returns the planner compacting the files of `partition` with the merged `schema`, dropping the
+/// rows of the partition that expired at time `now` (see [`RowTtl`]).
+fn reorg_planner(
+    ctx: &IOxSessionContext,
+    partition: &PartitionCompactionCandidateWithInfo,
+    schema: &Schema,
+    now: Time,
+) -> ReorgPlanner {
+    let planner = ReorgPlanner::new(ctx.child_ctx("ReorgPlanner"));
+
+    let row_ttl = RowTtl {
+        ttl_ns: partition.namespace.row_ttl_ns,
+        column: partition.namespace.row_ttl_column.as_deref().map(Arc::from),
+    };
+    match row_ttl.filter(schema, now.timestamp_nanos()) {
+        Some(filter) => {
+            debug!(partition_id=?partition.id(), ?filter, "dropping expired rows");
+            planner.with_predicate(Predicate::new().with_expr(filter))
+        }
+        None => planner,
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn compact_with_plan(
     store: ParquetStorage,
@@ -425,7 +507,8 @@ async fn compact_with_plan(
     partition_id: PartitionId,
     max_sequence_number: SequenceNumber,
     target_level: CompactionLevel,
-) -> Result<Vec<ParquetFileParams>, Error> {
+    tag_index_columns: &[String],
+) -> Result<Vec<(ParquetFileParams, TagValues)>, Error> {
     let ctx = exec.new_context(ExecutorType::Reorg);
     let physical_plan = ctx
         .create_physical_plan(&plan)
@@ -453,6 +536,7 @@ async fn compact_with_plan(
             let time_provider = Arc::clone(&time_provider);
             let sort_key = sort_key.clone();
             let partition = Arc::clone(&partition);
+            let tag_index_columns = tag_index_columns.to_vec();
             // run as a separate tokio task so files can be written
             // concurrently.
             tokio::task::spawn(async move {
@@ -462,6 +546,7 @@ async fn compact_with_plan(
                     .await
                     .context(ExecuteCompactPlanSnafu)?;
                 trace!(partition = i, "built result stream for partition");
+                let (data, tag_values) = collect_tag_values(data, &tag_index_columns);
 
                 let meta = IoxMetadata {
                     object_store_id: Uuid::new_v4(),
@@ -494,9 +579,9 @@ async fn compact_with_plan(
                     Err(UploadError::Serialise(CodecError::NoRows)) => {
                         // This MAY be a bug.
                         //
-                        // This also may happen legitimately, though very, very
-                        // rarely. See test_empty_parquet_file_panic for an
-                        // explanation.
+                        // This also may happen legitimately, when all the rows
+                        // expired (see RowTtl), or very, very rarely otherwise.
+                        // See test_empty_parquet_file_panic for an explanation.
                         warn!(
                             ?partition_id,
                             %object_store_id,
@@ -519,7 +604,9 @@ async fn compact_with_plan(
                             .id
                     });
 
-                Ok(Some(parquet_file))
+                let tag_values = tag_values.lock().take();
+
+                Ok(Some((parquet_file, tag_values)))
             })
         })
         // NB: FuturesOrdered allows the futures to run in parallel
@@ -628,19 +715,29 @@ pub(crate) enum CatalogUpdateError {
     FlagForDelete {
         source: iox_catalog::interface::Error,
     },
+
+    #[snafu(display("Error while recording the lineage of a parquet file {}", source))]
+    Lineage {
+        source: iox_catalog::interface::Error,
+    },
 }
 
+/// Create the compacted files in the catalog and flag the original files for deletion.
+///
+/// Returns the IDs of the compacted files, in the order they were given.
 async fn update_catalog(
     catalog: Arc<dyn Catalog>,
     partition_id: PartitionId,
     compacted_parquet_files: Vec<ParquetFileParams>,
-    original_parquet_file_ids: &[ParquetFileId],
-) -> Result<(), CatalogUpdateError> {
+    original_parquet_files: &[(ParquetFileId, CompactionLevel)],
+) -> Result<Vec<ParquetFileId>, CatalogUpdateError> {
     let mut txn = catalog
         .start_transaction()
         .await
         .context(TransactionSnafu)?;
 
+    let mut compacted_ids = Vec::with_capacity(compacted_parquet_files.len());
+
     // Create the new parquet file in the catalog first
     for parquet_file in compacted_parquet_files {
         debug!(
@@ -649,21 +746,91 @@ async fn update_catalog(
             "updating catalog"
         );
 
-        txn.parquet_files()
+        let parquet_file = txn
+            .parquet_files()
             .create(parquet_file)
             .await
             .context(UpdateSnafu)?;
+
+        // Record which files the new file was compacted from
+        txn.parquet_files()
+            .create_lineage(&parquet_file, original_parquet_files)
+            .await
+            .context(LineageSnafu)?;
+
+        compacted_ids.push(parquet_file.id);
     }
 
     // Mark input files for deletion
-    for &original_parquet_file_id in original_parquet_file_ids {
+    for &(original_parquet_file_id, _) in original_parquet_files {
         txn.parquet_files()
             .flag_for_delete(original_parquet_file_id)
             .await
             .context(FlagForDeleteSnafu)?;
     }
 
-    txn.commit().await.context(TransactionCommitSnafu)
+    txn.commit().await.context(TransactionCommitSnafu)?;
+
+    Ok(compacted_ids)
+}
+
+/// Add the `compacted_files` with their tag values to the [`TagIndex`] of `partition`, and drop
+/// the files that are no longer in the catalog (e.g. the inputs of the compaction) from it.
+///
+/// Nothing is done if no `tag_index_columns` are configured. Failures are only logged: files that
+/// the index does not cover are never skipped by queries, so a stale index is still correct.
+async fn update_tag_index(
+    catalog: Arc<dyn Catalog>,
+    store: &ParquetStorage,
+    partition: &PartitionCompactionCandidateWithInfo,
+    compacted_files: Vec<(ParquetFileId, TagValues)>,
+    tag_index_columns: &[String],
+) {
+    if tag_index_columns.is_empty() {
+        return;
+    }
+
+    let partition_id = partition.id();
+    let path = TagIndexPath::new(
+        partition.namespace_id(),
+        partition.table.id,
+        partition.shard_id(),
+        partition_id,
+    );
+    let object_store = store.object_store();
+
+    let mut index = match TagIndex::load(object_store.as_ref(), &path).await {
+        Ok(index) => index.unwrap_or_default(),
+        Err(e) => {
+            warn!(%e, ?partition_id, "cannot read tag index, not updating it");
+            return;
+        }
+    };
+
+    let res = catalog
+        .repositories()
+        .await
+        .parquet_files()
+        .list_by_partition_not_to_delete(partition_id)
+        .await;
+    let files = match res {
+        Ok(files) => files,
+        Err(e) => {
+            warn!(%e, ?partition_id, "cannot list parquet files, not updating tag index");
+            return;
+        }
+    };
+    let files: HashSet<_> = files.into_iter().map(|f| f.id).collect();
+    index.retain_files(|id| files.contains(&id));
+
+    for (id, tag_values) in compacted_files {
+        index.add_file(id, tag_values);
+    }
+
+    match index.store(object_store.as_ref(), &path).await {
+        Ok(()) => debug!(?partition_id, "tag index updated"),
+        Err(e) => warn!(%e, ?partition_id, "cannot write tag index"),
+    }
 }
 
 #[cfg(test)]
@@ -672,11 +839,12 @@ mod tests {
 
     use super::*;
     use arrow_util::{assert_batches_eq, assert_batches_sorted_eq};
-    use data_types::{ColumnType, PartitionParam, ShardId};
+    use data_types::{ColumnType, Namespace, PartitionParam, ShardId};
     use iox_tests::util::{TestCatalog, TestParquetFileBuilder, TestTable};
     use itertools::Itertools;
     use metric::U64HistogramOptions;
     use parquet_file::storage::StorageId;
+    use std::collections::BTreeSet;
     use test_helpers::assert_error;
 
     #[test]
@@ -975,7 +1143,9 @@ mod tests {
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             DEFAULT_SPLIT_PERCENTAGE,
+            None,
             CompactionLevel::FileNonOverlapped,
+            &[],
         )
         .await;
         assert_error!(result, Error::NotEnoughParquetFiles { num_files: 0, .. });
@@ -1016,7 +1186,9 @@ mod tests {
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             DEFAULT_SPLIT_PERCENTAGE,
+            None,
             CompactionLevel::FileNonOverlapped,
+            &[],
         )
         .await
         .unwrap();
@@ -1102,7 +1274,9 @@ mod tests {
                 DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
                 DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
                 DEFAULT_SPLIT_PERCENTAGE,
+                None,
                 CompactionLevel::Final,
+                &[],
             )
             .await
             .unwrap();
@@ -1146,7 +1320,9 @@ mod tests {
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             DEFAULT_SPLIT_PERCENTAGE,
+            None,
             CompactionLevel::FileNonOverlapped,
+            &[],
         )
         .await
         .unwrap();
@@ -1173,6 +1349,34 @@ mod tests {
             ]
         );
 
+        // The compacted file records the files it was compacted from
+        let lineage: Vec<_> = catalog
+            .catalog
+            .repositories()
+            .await
+            .parquet_files()
+            .list_lineage_by_namespace(table.table.namespace_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|l| {
+                (
+                    l.parquet_file_id.get(),
+                    l.input_parquet_file_id.get(),
+                    l.input_compaction_level,
+                )
+            })
+            .collect();
+        assert_eq!(
+            lineage,
+            vec![
+                (7, 1, CompactionLevel::FileNonOverlapped),
+                (7, 2, CompactionLevel::Initial),
+                (7, 3, CompactionLevel::Initial),
+                (7, 4, CompactionLevel::FileNonOverlapped),
+            ]
+        );
+
         // Verify the metrics
         assert_eq!(
             extract_byte_metrics(&compaction_input_file_bytes, shard_id),
@@ -1231,7 +1435,9 @@ mod tests {
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             DEFAULT_SPLIT_PERCENTAGE,
+            None,
             CompactionLevel::FileNonOverlapped,
+            &[],
         )
         .await
         .unwrap();
@@ -1334,7 +1540,9 @@ mod tests {
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             split_percentage,
+            None,
             CompactionLevel::FileNonOverlapped,
+            &[],
         )
         .await
         .unwrap();
@@ -1418,7 +1626,9 @@ mod tests {
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             DEFAULT_SPLIT_PERCENTAGE,
+            None,
             CompactionLevel::FileNonOverlapped,
+            &[],
         )
         .await
         .unwrap();
@@ -1504,6 +1714,122 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn large_input_files_get_split_by_max_output_file_size() {
+        test_helpers::maybe_start_logging();
+
+        let TestSetup {
+            catalog,
+            table,
+            candidate_partition,
+            parquet_files,
+        } = test_setup().await;
+        let compaction_input_file_bytes = metrics();
+
+        compact_parquet_files(
+            parquet_files,
+            candidate_partition,
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store), StorageId::from("iox")),
+            Arc::clone(&catalog.exec),
+            Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
+            &compaction_input_file_bytes,
+            DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
+            DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
+            DEFAULT_SPLIT_PERCENTAGE,
+            Some(60 * 1024 * 1024),
+            CompactionLevel::FileNonOverlapped,
+            &[],
+        )
+        .await
+        .unwrap();
+
+        // Compared to large_input_files_get_split_multiple_times, the earliest time range,
+        // estimated to contain more than 60MB of data, is split in 2, so that the data at 36000
+        // ends up in its own file. The time range of the data at 68000 is also split in 2, but the
+        // later half has no data and therefore no file.
+        let mut files = catalog.list_by_table_not_to_delete(table.table.id).await;
+        assert_eq!(files.len(), 4);
+        let files_and_levels: Vec<_> = files
+            .iter()
+            .map(|f| (f.id.get(), f.compaction_level))
+            .collect();
+        assert_eq!(
+            files_and_levels,
+            vec![
+                (7, CompactionLevel::FileNonOverlapped),
+                (8, CompactionLevel::FileNonOverlapped),
+                (9, CompactionLevel::FileNonOverlapped),
+                (10, CompactionLevel::FileNonOverlapped),
+            ]
+        );
+
+        // The files don't overlap in time
+        for pair in files.windows(2) {
+            assert!(pair[0].max_time < pair[1].min_time, "{pair:?}");
+        }
+
+        // Compacted file with the latest data
+        let file3 = files.pop().unwrap();
+        let batches = table.read_parquet_file(file3).await;
+        assert_batches_sorted_eq!(
+            &[
+                "+-----------+------+------+------+-----------------------------+",
+                "| field_int | tag1 | tag2 | tag3 | time                        |",
+                "+-----------+------+------+------+-----------------------------+",
+                "| 210       |      | OH   | 21   | 1970-01-01T00:00:00.000136Z |",
+                "+-----------+------+------+------+-----------------------------+",
+            ],
+            &batches
+        );
+
+        let file2 = files.pop().unwrap();
+        let batches = table.read_parquet_file(file2).await;
+        assert_batches_sorted_eq!(
+            &[
+                "+-----------+------+------+------+-----------------------------+",
+                "| field_int | tag1 | tag2 | tag3 | time                        |",
+                "+-----------+------+------+------+-----------------------------+",
+                "| 10        | VT   |      |      | 1970-01-01T00:00:00.000068Z |",
+                "+-----------+------+------+------+-----------------------------+",
+            ],
+            &batches
+        );
+
+        let file1 = files.pop().unwrap();
+        let batches = table.read_parquet_file(file1).await;
+        assert_batches_sorted_eq!(
+            &[
+                "+-----------+------+------+------+-----------------------------+",
+                "| field_int | tag1 | tag2 | tag3 | time                        |",
+                "+-----------+------+------+------+-----------------------------+",
+                "| 21        |      | OH   | 21   | 1970-01-01T00:00:00.000036Z |",
+                "+-----------+------+------+------+-----------------------------+",
+            ],
+            &batches
+        );
+
+        // Compacted file with the earliest data
+        let file0 = files.pop().unwrap();
+        let batches = table.read_parquet_file(file0).await;
+        assert_batches_sorted_eq!(
+            &[
+                "+-----------+------+------+------+-----------------------------+",
+                "| field_int | tag1 | tag2 | tag3 | time                        |",
+                "+-----------+------+------+------+-----------------------------+",
+                "| 10        | VT   |      |      | 1970-01-01T00:00:00.000006Z |",
+                "| 10        | VT   |      |      | 1970-01-01T00:00:00.000010Z |",
+                "| 1500      | WA   |      |      | 1970-01-01T00:00:00.000008Z |",
+                "| 1601      |      | PA   | 15   | 1970-01-01T00:00:00.000030Z |",
+                "| 270       | UT   |      |      | 1970-01-01T00:00:00.000025Z |",
+                "| 70        | UT   |      |      | 1970-01-01T00:00:00.000020Z |",
+                "| 99        | OR   |      |      | 1970-01-01T00:00:00.000012Z |",
+                "+-----------+------+------+------+-----------------------------+",
+            ],
+            &batches
+        );
+    }
+
     #[tokio::test]
     async fn compact_final_no_splits_creates_one_level_2_file() {
         test_helpers::maybe_start_logging();
@@ -1532,6 +1858,7 @@ mod tests {
             Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
             &compaction_input_file_bytes,
             CompactionLevel::Final,
+            &[],
         )
         .await
         .unwrap();
@@ -1584,6 +1911,160 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn compact_final_no_splits_updates_tag_index() {
+        test_helpers::maybe_start_logging();
+
+        let TestSetup {
+            catalog,
+            table,
+            candidate_partition,
+            parquet_files,
+        } = test_setup().await;
+        let compaction_input_file_bytes = metrics();
+
+        // an existing index covering one of the compacted files and one that stays
+        let object_store = Arc::clone(&catalog.object_store);
+        let path = TagIndexPath::new(
+            candidate_partition.namespace_id(),
+            candidate_partition.table.id,
+            candidate_partition.shard_id(),
+            candidate_partition.id(),
+        );
+        let tag_values = |column: &str, value: &str| {
+            TagValues::from([(column.to_string(), BTreeSet::from([value.to_string()]))])
+        };
+        let mut index = TagIndex::default();
+        index.add_file(ParquetFileId::new(1), tag_values("tag1", "x"));
+        index.add_file(ParquetFileId::new(2), tag_values("tag1", "y"));
+        index.store(object_store.as_ref(), &path).await.unwrap();
+
+        let level_1_files = parquet_files
+            .into_iter()
+            .filter(|f| f.compaction_level() == CompactionLevel::FileNonOverlapped)
+            .collect();
+
+        compact_final_no_splits(
+            level_1_files,
+            candidate_partition,
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store), StorageId::from("iox")),
+            Arc::clone(&catalog.exec),
+            Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
+            &compaction_input_file_bytes,
+            CompactionLevel::Final,
+            &[
+                "tag1".to_string(),
+                "tag2".to_string(),
+                "field_int".to_string(),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let mut files = catalog.list_by_table_not_to_delete(table.table.id).await;
+        let file = files.pop().unwrap();
+        assert_eq!(file.compaction_level, CompactionLevel::Final);
+        assert_eq!(TagIndexPath::from(&file), path);
+
+        let index = TagIndex::load(object_store.as_ref(), &path)
+            .await
+            .unwrap()
+            .unwrap();
+        let values = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        // the compacted file is indexed for the tag columns
+        assert_eq!(
+            index.may_contain(file.id, "tag1", &values(&["VT"])),
+            Some(true)
+        );
+        assert_eq!(
+            index.may_contain(file.id, "tag1", &values(&["WA"])),
+            Some(false)
+        );
+        assert_eq!(
+            index.may_contain(file.id, "tag2", &values(&["PA"])),
+            Some(true)
+        );
+        assert_eq!(index.may_contain(file.id, "tag3", &values(&["15"])), None);
+        assert_eq!(
+            index.may_contain(file.id, "field_int", &values(&["1"])),
+            None
+        );
+
+        // the input files are dropped from the index, the other files are kept
+        assert_eq!(
+            index.may_contain(ParquetFileId::new(1), "tag1", &values(&["x"])),
+            None
+        );
+        assert_eq!(
+            index.may_contain(ParquetFileId::new(2), "tag1", &values(&["y"])),
+            Some(true)
+        );
+    }
+
+    #[tokio::test]
+    async fn compact_final_no_splits_drops_expired_rows() {
+        test_helpers::maybe_start_logging();
+
+        let TestSetup {
+            catalog,
+            table,
+            candidate_partition,
+            parquet_files,
+        } = test_setup().await;
+        let compaction_input_file_bytes = metrics();
+
+        // rows expire 25us after their timestamp
+        catalog
+            .mock_time_provider()
+            .set(Time::from_timestamp_nanos(40_000));
+        let candidate_partition = Arc::new(PartitionCompactionCandidateWithInfo {
+            namespace: Arc::new(Namespace {
+                row_ttl_ns: Some(25_000),
+                ..(*candidate_partition.namespace).clone()
+            }),
+            ..(*candidate_partition).clone()
+        });
+
+        let level_1_files = parquet_files
+            .into_iter()
+            .filter(|f| f.compaction_level() == CompactionLevel::FileNonOverlapped)
+            .collect();
+
+        compact_final_no_splits(
+            level_1_files,
+            candidate_partition,
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store), StorageId::from("iox")),
+            Arc::clone(&catalog.exec),
+            Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
+            &compaction_input_file_bytes,
+            CompactionLevel::Final,
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let mut files = catalog.list_by_table_not_to_delete(table.table.id).await;
+        let file = files.pop().unwrap();
+        assert_eq!(file.compaction_level, CompactionLevel::Final);
+
+        // the rows older than 15us are dropped
+        let batches = table.read_parquet_file(file).await;
+        assert_batches_sorted_eq!(
+            &[
+                "+-----------+------+------+------+-----------------------------+",
+                "| field_int | tag1 | tag2 | tag3 | time                        |",
+                "+-----------+------+------+------+-----------------------------+",
+                "| 1601      |      | PA   | 15   | 1970-01-01T00:00:00.000030Z |",
+                "| 21        |      | OH   | 21   | 1970-01-01T00:00:00.000036Z |",
+                "+-----------+------+------+------+-----------------------------+",
+            ],
+            &batches
+        );
+    }
+
     #[derive(Debug, PartialEq)]
     struct ExtractedByteMetrics {
         sample_count: u64,
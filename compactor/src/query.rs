@@ -1,5 +1,6 @@
 //! Queryable Compactor Data
 
+use async_trait::async_trait;
 use data_types::{
     ChunkId, ChunkOrder, CompactionLevel, DeletePredicate, PartitionId, SequenceNumber,
     TableSummary, Timestamp, Tombstone,
@@ -134,6 +135,7 @@ impl QueryChunkMeta for QueryableParquetChunk {
     }
 }
 
+#[async_trait]
 impl QueryChunk for QueryableParquetChunk {
     // This function is needed to distinguish the ParquetChunks further if they happen to have the
     // same creation order.
@@ -160,7 +162,7 @@ impl QueryChunk for QueryableParquetChunk {
     /// table that have at least one row that matches `predicate`, if
     /// the predicate can be evaluated entirely on the metadata of
     /// this Chunk. Returns `None` otherwise
-    fn column_names(
+    async fn column_names(
         &self,
         _ctx: IOxSessionContext,
         _predicate: &Predicate,
@@ -174,7 +176,7 @@ impl QueryChunk for QueryableParquetChunk {
     /// on the metadata of this Chunk. Returns `None` otherwise
     ///
     /// The requested columns must all have String type.
-    fn column_values(
+    async fn column_values(
         &self,
         _ctx: IOxSessionContext,
         _column_name: &str,
@@ -259,6 +259,85 @@ pub(crate) fn compute_split_time(
     split_times
 }
 
+/// Add split times to `split_times` so that none of the resulting files is estimated to be
+/// larger than `max_file_size`.
+///
+/// The size of the data up to a time is estimated from the size and time range of the
+/// `chunks`, assuming the data of each chunk is evenly distributed in the chunk's own time
+/// range. Unlike [`compute_split_time`], this accounts for data that is unevenly distributed
+/// over the whole time range, e.g. a large chunk next to many small ones.
+///
+/// `split_times` must be sorted. The returned times are sorted, unique and never include
+/// `max_time`, so an empty result means the data should not be split.
+pub(crate) fn split_oversized_time_ranges(
+    chunks: &[(TimestampMinMax, u64)],
+    min_time: i64,
+    max_time: i64,
+    split_times: &[i64],
+    max_file_size: u64,
+) -> Vec<i64> {
+    let start = min_time.saturating_sub(1);
+    let mut bounds = vec![start];
+    bounds.extend(
+        split_times
+            .iter()
+            .copied()
+            .filter(|&t| start < t && t < max_time),
+    );
+    bounds.push(max_time);
+    bounds.dedup();
+
+    let mut result = vec![];
+    for range in bounds.windows(2) {
+        let (lo, hi) = (range[0], range[1]);
+        let lo_size = estimated_size_up_to(chunks, lo);
+        let size = estimated_size_up_to(chunks, hi) - lo_size;
+
+        // Split the range into equally sized parts rather than leaving a small remainder.
+        let parts = if max_file_size == 0 {
+            1
+        } else {
+            (size / max_file_size as f64).ceil() as u64
+        };
+        for part in 1..parts {
+            let target = lo_size + size * part as f64 / parts as f64;
+
+            // Find the first time in the range up to which the data reaches the target size.
+            let (mut l, mut r) = (lo + 1, hi);
+            while l < r {
+                let mid = l + (r - l) / 2;
+                if estimated_size_up_to(chunks, mid) >= target {
+                    r = mid;
+                } else {
+                    l = mid + 1;
+                }
+            }
+
+            if l < hi && result.last().map_or(true, |&last| last < l) {
+                result.push(l);
+            }
+        }
+
+        if hi < max_time {
+            result.push(hi);
+        }
+    }
+
+    result
+}
+
+/// Estimated size of the data of `chunks` with a time on or before `time`.
+fn estimated_size_up_to(chunks: &[(TimestampMinMax, u64)], time: i64) -> f64 {
+    chunks
+        .iter()
+        .map(|(chunk, size)| {
+            let covered = (time as f64 - chunk.min as f64 + 1.0)
+                / (chunk.max as f64 - chunk.min as f64 + 1.0);
+            *size as f64 * covered.clamp(0.0, 1.0)
+        })
+        .sum()
+}
+
 // time_range_present returns true if the given time range is included in any of the chunks.
 fn time_range_present(chunk_times: &[TimestampMinMax], min_time: i64, max_time: i64) -> bool {
     chunk_times
@@ -409,4 +488,43 @@ mod tests {
         assert_eq!(result.len(), 1);
         assert_eq!(result[0], 34);
     }
+
+    #[test]
+    fn test_split_oversized_time_ranges() {
+        let chunk = |min, max, size| (TimestampMinMax { min, max }, size);
+
+        // evenly distributed data is split into equally sized parts
+        let chunks = vec![chunk(0, 99, 100)];
+        assert_eq!(
+            split_oversized_time_ranges(&chunks, 0, 99, &[], 30),
+            vec![24, 49, 74]
+        );
+
+        // nothing to split
+        assert!(split_oversized_time_ranges(&chunks, 0, 99, &[], 100).is_empty());
+        assert!(split_oversized_time_ranges(&chunks, 0, 99, &[99], 100).is_empty());
+
+        // existing split times resulting in small enough files are kept
+        assert_eq!(
+            split_oversized_time_ranges(&chunks, 0, 99, &[49], 60),
+            vec![49]
+        );
+
+        // only the ranges that are too large are split further
+        assert_eq!(
+            split_oversized_time_ranges(&chunks, 0, 99, &[19], 40),
+            vec![19, 59]
+        );
+
+        // unevenly distributed data is split where the data is
+        let chunks = vec![chunk(0, 9, 90), chunk(10, 99, 10)];
+        assert_eq!(
+            split_oversized_time_ranges(&chunks, 0, 99, &[], 50),
+            vec![5]
+        );
+
+        // data at a single time can't be split
+        let chunks = vec![chunk(5, 5, 100)];
+        assert!(split_oversized_time_ranges(&chunks, 5, 5, &[], 10).is_empty());
+    }
 }
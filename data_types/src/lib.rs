@@ -130,6 +130,12 @@ impl QueryPoolId {
     }
 }
 
+impl std::fmt::Display for QueryPoolId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Unique ID for a `Table`
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type)]
 #[sqlx(transparent)]
@@ -238,6 +244,9 @@ pub enum IngesterMapping {
     Ignore,
     /// The address of the ingester to contact for this shard.
     Addr(Arc<str>),
+    /// The addresses of several ingesters that each hold a replica of the unpersisted data of this
+    /// shard. The querier contacts all of them and tolerates one of them being unavailable.
+    Replicas(Vec<Arc<str>>),
 }
 
 /// Unique ID for a `Partition`
@@ -412,6 +421,48 @@ impl std::fmt::Display for ParquetFileId {
     }
 }
 
+/// Unique ID for an `Operation`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct OperationId(i64);
+
+#[allow(missing_docs)]
+impl OperationId {
+    pub fn new(v: i64) -> Self {
+        Self(v)
+    }
+    pub fn get(&self) -> i64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for OperationId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Unique ID for an `ApiToken`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct ApiTokenId(i64);
+
+#[allow(missing_docs)]
+impl ApiTokenId {
+    pub fn new(v: i64) -> Self {
+        Self(v)
+    }
+    pub fn get(&self) -> i64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for ApiTokenId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Data object for a topic. When Kafka is used as the write buffer, this is the Kafka topic name
 /// plus a catalog-assigned ID.
 #[derive(Debug, Clone, Eq, PartialEq, sqlx::FromRow)]
@@ -431,6 +482,17 @@ pub struct QueryPool {
     pub name: String,
 }
 
+/// Data object for a querier registered as a member of a query pool
+#[derive(Debug, Clone, Eq, PartialEq, sqlx::FromRow)]
+pub struct QuerierRegistration {
+    /// The address clients use to send queries to the querier
+    pub address: String,
+    /// The query pool the querier serves
+    pub query_pool_id: QueryPoolId,
+    /// When the querier last sent a heartbeat
+    pub last_heartbeat: Timestamp,
+}
+
 /// Data object for a namespace
 #[derive(Debug, Clone, Eq, PartialEq, sqlx::FromRow)]
 pub struct Namespace {
@@ -449,6 +511,65 @@ pub struct Namespace {
     pub max_tables: i32,
     /// The maximum number of columns per table in this namespace
     pub max_columns_per_table: i32,
+    /// Incremented by each schema modification, allowing a writer to detect that the schema
+    /// changed since it was read (see [`NamespaceSchema::generation`])
+    pub schema_generation: i64,
+    #[sqlx(default)]
+    /// The time range in ns a query covers if it does not restrict the time. None queries all
+    /// the data.
+    pub default_query_range_ns: Option<i64>,
+    #[sqlx(default)]
+    /// The longest time range in ns a query may cover. None allows queries of any range.
+    pub max_query_range_ns: Option<i64>,
+    #[sqlx(default)]
+    /// The time in ns after which a row expires, counted from its timestamp. None keeps the
+    /// rows until the retention period drops them.
+    pub row_ttl_ns: Option<i64>,
+    #[sqlx(default)]
+    /// The integer field holding the time in ns since the epoch at which each row expires. Rows
+    /// without a value in the field do not expire. None expires no row by a field.
+    pub row_ttl_column: Option<String>,
+    #[sqlx(default)]
+    /// The template the writes to this namespace are partitioned by, in the text form of
+    /// [`PartitionTemplate`]. None partitions them by the template the router is configured with.
+    pub partition_template: Option<String>,
+}
+
+/// Data object for the cumulative usage of a namespace, recorded for billing
+#[derive(Debug, Clone, Copy, Eq, PartialEq, sqlx::FromRow)]
+pub struct NamespaceUsage {
+    /// The namespace the usage is of
+    pub namespace_id: NamespaceId,
+    /// The number of rows written
+    pub rows_written: i64,
+    /// The number of (decompressed) line protocol bytes written
+    pub bytes_written: i64,
+    /// The number of queries executed
+    pub queries_executed: i64,
+    /// The estimated number of bytes of the chunks scanned by queries
+    pub bytes_scanned: i64,
+}
+
+impl NamespaceUsage {
+    /// No usage of namespace `namespace_id`
+    pub fn new(namespace_id: NamespaceId) -> Self {
+        Self {
+            namespace_id,
+            rows_written: 0,
+            bytes_written: 0,
+            queries_executed: 0,
+            bytes_scanned: 0,
+        }
+    }
+
+    /// Add the usage of `other`, which must be of the same namespace
+    pub fn add(&mut self, other: &Self) {
+        debug_assert_eq!(self.namespace_id, other.namespace_id);
+        self.rows_written += other.rows_written;
+        self.bytes_written += other.bytes_written;
+        self.queries_executed += other.queries_executed;
+        self.bytes_scanned += other.bytes_scanned;
+    }
 }
 
 /// Schema collection for a namespace. This is an in-memory object useful for a schema
@@ -468,6 +589,26 @@ pub struct NamespaceSchema {
     /// The retention period in ns.
     /// None represents infinite duration (i.e. never drop data).
     pub retention_period_ns: Option<i64>,
+    /// The time range in ns a query covers if it does not restrict the time.
+    /// None queries all the data.
+    pub default_query_range_ns: Option<i64>,
+    /// The longest time range in ns a query may cover.
+    /// None allows queries of any range.
+    pub max_query_range_ns: Option<i64>,
+    /// The time in ns after which a row expires, counted from its timestamp.
+    /// None keeps the rows until the retention period drops them.
+    pub row_ttl_ns: Option<i64>,
+    /// The integer field holding the time in ns since the epoch at which each row expires.
+    /// None expires no row by a field.
+    pub row_ttl_column: Option<String>,
+    /// The template the writes to this namespace are partitioned by.
+    /// None partitions them by the template the router is configured with.
+    pub partition_template: Option<PartitionTemplate>,
+    /// The [`Namespace::schema_generation`] this schema was built from.
+    ///
+    /// A schema change made on top of this schema only succeeds if the catalog is still at
+    /// this generation.
+    pub generation: i64,
 }
 
 impl NamespaceSchema {
@@ -486,12 +627,19 @@ impl NamespaceSchema {
             query_pool_id,
             max_columns_per_table: max_columns_per_table as usize,
             retention_period_ns,
+            default_query_range_ns: None,
+            max_query_range_ns: None,
+            row_ttl_ns: None,
+            row_ttl_column: None,
+            partition_template: None,
+            generation: 0,
         }
     }
 
     /// Estimated Size in bytes including `self`.
     pub fn size(&self) -> usize {
         std::mem::size_of_val(self)
+            + self.row_ttl_column.as_ref().map_or(0, |c| c.capacity())
             + self
                 .tables
                 .iter()
@@ -578,6 +726,9 @@ pub struct Column {
     pub name: String,
     /// the logical type of the column
     pub column_type: ColumnType,
+    /// when the column was created, `None` for the columns created before
+    /// this was recorded
+    pub created_at: Option<Timestamp>,
 }
 
 impl Column {
@@ -784,6 +935,75 @@ pub struct Shard {
     pub min_unpersisted_sequence_number: SequenceNumber,
 }
 
+/// Data object for the lease granting an ingester leadership of a shard
+#[derive(Debug, Clone, Eq, PartialEq, sqlx::FromRow)]
+pub struct ShardLease {
+    /// The shard the lease is for
+    pub shard_id: ShardId,
+    /// The identity of the ingester holding the lease
+    pub holder: String,
+    /// When the lease expires, unless renewed by its holder
+    pub expires_at: Timestamp,
+    /// Incremented whenever the lease changes hands, so that a holder that lost the lease can be
+    /// told apart from its new holder
+    pub epoch: i64,
+}
+
+/// The drain state of an ingester, coordinating its restart with the queriers.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, sqlx::Type)]
+#[repr(i16)]
+pub enum DrainState {
+    /// The ingester is consuming its shards and answers queries.
+    Active = 1,
+    /// The ingester is persisting its data before handing off its shards. Queriers avoid it if
+    /// another replica of its shards is available.
+    Draining = 2,
+    /// The ingester persisted all its data and consumes no shards, it is safe to terminate.
+    /// Queriers no longer query it.
+    Drained = 3,
+}
+
+impl DrainState {
+    /// The name of the state.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Draining => "draining",
+            Self::Drained => "drained",
+        }
+    }
+}
+
+impl std::fmt::Display for DrainState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Data object for the drain state of an ingester
+#[derive(Debug, Clone, Eq, PartialEq, sqlx::FromRow)]
+pub struct NodeDrain {
+    /// The address queriers use to reach the ingester
+    pub address: String,
+    /// The drain state of the ingester
+    pub state: DrainState,
+    /// When the state was last changed
+    pub updated_at: Timestamp,
+}
+
+/// Data object for an ingester registered as a consumer of the shards of a topic
+#[derive(Debug, Clone, Eq, PartialEq, sqlx::FromRow)]
+pub struct IngesterRegistration {
+    /// The address queriers use to reach the ingester
+    pub address: String,
+    /// The topic whose shards the ingester consumes
+    pub topic_id: TopicId,
+    /// The shards assigned to the ingester, in ascending order
+    pub shard_indexes: Vec<ShardIndex>,
+    /// When the ingester last sent a heartbeat
+    pub last_heartbeat: Timestamp,
+}
+
 /// Defines an partition via an arbitrary string within a table within
 /// a namespace.
 ///
@@ -904,6 +1124,13 @@ pub struct Partition {
     ///
     /// If [`None`] no data has been persisted for this partition.
     pub persisted_sequence_number: Option<SequenceNumber>,
+
+    /// The time the compactor fully compacted this partition after it stopped getting writes.
+    ///
+    /// All the non-deleted parquet files of a cold partition are at
+    /// [`CompactionLevel::Final`]. This is reset to [`None`] when data is persisted to the
+    /// partition again.
+    pub cold_compacted_at: Option<Timestamp>,
 }
 
 impl Partition {
@@ -951,6 +1178,144 @@ pub struct SkippedCompaction {
     pub limit_num_files_first_in_partition: i64,
 }
 
+/// The state of an [`Operation`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, sqlx::Type)]
+#[repr(i16)]
+pub enum OperationStatus {
+    /// The operation is being executed.
+    Running = 0,
+    /// The operation completed successfully.
+    Success = 1,
+    /// The operation failed.
+    Error = 2,
+    /// The operation was cancelled before it completed.
+    Cancelled = 3,
+    /// The process executing the operation stopped before the operation completed.
+    Interrupted = 4,
+}
+
+impl OperationStatus {
+    /// Returns true if the operation completed, successfully or not.
+    pub fn is_done(&self) -> bool {
+        !matches!(self, Self::Running)
+    }
+
+    /// The name of the status, as used in operation filters.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Success => "success",
+            Self::Error => "error",
+            Self::Cancelled => "cancelled",
+            Self::Interrupted => "interrupted",
+        }
+    }
+}
+
+impl std::str::FromStr for OperationStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "running" => Ok(Self::Running),
+            "success" => Ok(Self::Success),
+            "error" => Ok(Self::Error),
+            "cancelled" => Ok(Self::Cancelled),
+            "interrupted" => Ok(Self::Interrupted),
+            _ => Err(format!("invalid operation status: {s}")),
+        }
+    }
+}
+
+/// Data object for a long-running job, e.g. the persistence of a partition, recorded so that
+/// its outcome can be reported after the process executing it restarted.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct Operation {
+    /// the id of the operation
+    pub id: OperationId,
+    /// the kind of job, e.g. "persist"
+    pub kind: String,
+    /// human-readable description of the job
+    pub description: String,
+    /// the identity of the process executing the job
+    pub node: String,
+    /// the state of the job
+    pub status: OperationStatus,
+    /// when the job started
+    pub created_at: Timestamp,
+    /// when the job completed, if it did
+    pub completed_at: Option<Timestamp>,
+}
+
+/// The actions an [`ApiToken`] allows. Each permission includes the permissions below it, i.e.
+/// a token that may write may also read.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, sqlx::Type)]
+#[repr(i16)]
+pub enum ApiTokenPermission {
+    /// Query the data of namespaces.
+    Read = 1,
+    /// Write to and delete from namespaces.
+    Write = 2,
+    /// Manage API tokens.
+    Admin = 3,
+}
+
+impl ApiTokenPermission {
+    /// The name of the permission, as used by the CLI.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl std::fmt::Display for ApiTokenPermission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for ApiTokenPermission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            "admin" => Ok(Self::Admin),
+            _ => Err(format!("invalid API token permission: {s}")),
+        }
+    }
+}
+
+/// Data object for an API token. Only the hash of the token is stored.
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct ApiToken {
+    /// the id of the token
+    pub id: ApiTokenId,
+    /// the SHA-256 hash of the token, hex-encoded
+    pub token_hash: String,
+    /// the namespace the token is scoped to, or `None` for all namespaces
+    pub namespace_id: Option<NamespaceId>,
+    /// the actions the token allows
+    pub permission: ApiTokenPermission,
+    /// human-readable description of the token, e.g. its owner
+    pub description: String,
+    /// when the token was created
+    pub created_at: Timestamp,
+    /// when the token was revoked, if it was
+    pub revoked_at: Option<Timestamp>,
+}
+
+impl ApiToken {
+    /// Returns true if the token was revoked.
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+}
+
 /// Data object for a tombstone.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, sqlx::FromRow)]
 pub struct Tombstone {
@@ -1109,6 +1474,24 @@ impl ParquetFile {
     }
 }
 
+/// Data object recording that a parquet file was compacted from another parquet file, so that
+/// the data of compacted files can be traced back to the files persisted by the ingesters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::FromRow)]
+pub struct ParquetFileLineage {
+    /// the compacted parquet file
+    pub parquet_file_id: ParquetFileId,
+    /// the compaction level of the compacted parquet file when it was created
+    pub compaction_level: CompactionLevel,
+    /// a parquet file the compacted parquet file was compacted from
+    pub input_parquet_file_id: ParquetFileId,
+    /// the compaction level of the input parquet file when it was compacted
+    pub input_compaction_level: CompactionLevel,
+    /// the namespace of the files
+    pub namespace_id: NamespaceId,
+    /// the table of the files
+    pub table_id: TableId,
+}
+
 /// Data for a parquet file to be inserted into the catalog.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParquetFileParams {
@@ -1226,8 +1609,9 @@ impl ChunkOrder {
 
 /// `PartitionTemplate` is used to compute the partition key of each row that
 /// gets written. It can consist of the table name, a column name and its value,
-/// a formatted time, or a string column and regex captures of its value. For
-/// columns that do not appear in the input row, a blank value is output.
+/// a formatted time, a string column and regex captures of its value, the
+/// sanitized value of a tag, a hash bucket of the value of a tag, or a literal.
+/// For columns that do not appear in the input row, a blank value is output.
 ///
 /// The key is constructed in order of the template parts; thus ordering changes
 /// what partition key is generated.
@@ -1237,6 +1621,34 @@ pub struct PartitionTemplate {
     pub parts: Vec<TemplatePart>,
 }
 
+impl std::fmt::Display for PartitionTemplate {
+    /// Write the text form of the template: the text form of each part, one
+    /// per line.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, part) in self.parts.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", part)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for PartitionTemplate {
+    type Err = TemplatePartParseError;
+
+    /// Parse the text form of a template, one [`TemplatePart`] per line.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { parts })
+    }
+}
+
 /// `TemplatePart` specifies what part of a row should be used to compute this
 /// part of a partition key.
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -1255,6 +1667,170 @@ pub enum TemplatePart {
     RegexCapture(RegexCapture),
     /// Applies a `strftime` pattern to some column other than "time"
     StrftimeColumn(StrftimeColumn),
+    /// The value of a named tag, sanitized with [`sanitize_tag_value`]
+    TagValue(String),
+    /// The bucket the value of a named tag hashes to, bounding the number of
+    /// distinct partition keys to the number of buckets
+    Bucket(TagBucket),
+    /// A fixed string, made up of the characters kept by
+    /// [`sanitize_tag_value`]
+    Literal(String),
+}
+
+/// The longest tag value, in bytes, kept in a partition key by a
+/// [`TemplatePart::TagValue`]. Longer values are truncated.
+pub const PARTITION_KEY_MAX_TAG_VALUE_LEN: usize = 64;
+
+/// Sanitize a tag `value` for use in a partition key.
+///
+/// ASCII alphanumerics, `_` and `.` are kept, every other character
+/// (including the `-` partition key parts are joined by) is replaced by `_`.
+/// The result is truncated to [`PARTITION_KEY_MAX_TAG_VALUE_LEN`] characters.
+pub fn sanitize_tag_value(value: &str) -> impl Iterator<Item = char> + '_ {
+    value
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '.' => c,
+            _ => '_',
+        })
+        .take(PARTITION_KEY_MAX_TAG_VALUE_LEN)
+}
+
+/// [`TagBucket`] maps the values of a tag to one of a fixed number of buckets.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TagBucket {
+    /// The name of the tag.
+    pub tag: String,
+    /// The number of buckets, at least 1.
+    pub buckets: u32,
+}
+
+impl TagBucket {
+    /// The bucket in `[0, buckets)` the tag `value` is in.
+    ///
+    /// Uses the 64-bit FNV-1a hash of the value, so that the bucket of a value
+    /// never changes across processes and releases.
+    pub fn bucket(&self, value: &str) -> u32 {
+        const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+
+        let hash = value.bytes().fold(OFFSET_BASIS, |hash, b| {
+            (hash ^ u64::from(b)).wrapping_mul(PRIME)
+        });
+        (hash % u64::from(self.buckets.max(1))) as u32
+    }
+}
+
+/// Errors parsing a [`TemplatePart`].
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum TemplatePartParseError {
+    #[snafu(display("unknown partition template part `{}`", part))]
+    Unknown { part: String },
+
+    #[snafu(display("partition template part `{}` has an empty argument", part))]
+    EmptyArgument { part: String },
+
+    #[snafu(display(
+        "partition template part `{}` must have a bucket count between 1 and {}",
+        part,
+        u32::MAX
+    ))]
+    InvalidBuckets { part: String },
+
+    #[snafu(display(
+        "partition template literal `{}` may only contain ASCII alphanumerics, `_` and `.`",
+        literal
+    ))]
+    InvalidLiteral { literal: String },
+}
+
+impl std::str::FromStr for TemplatePart {
+    type Err = TemplatePartParseError;
+
+    /// Parse a template part written as `table`, `column(name)`,
+    /// `time(format)`, `tag(name)`, `bucket(name, N)` or `literal(text)`.
+    fn from_str(part: &str) -> Result<Self, Self::Err> {
+        let part = part.trim();
+        if part == "table" {
+            return Ok(Self::Table);
+        }
+
+        let (kind, arg) = part
+            .strip_suffix(')')
+            .and_then(|s| s.split_once('('))
+            .ok_or_else(|| TemplatePartParseError::Unknown {
+                part: part.to_string(),
+            })?;
+        let arg = arg.trim();
+        if arg.is_empty() {
+            return Err(TemplatePartParseError::EmptyArgument {
+                part: part.to_string(),
+            });
+        }
+
+        match kind.trim() {
+            "column" => Ok(Self::Column(arg.to_string())),
+            "time" => Ok(Self::TimeFormat(arg.to_string())),
+            "tag" => Ok(Self::TagValue(arg.to_string())),
+            "bucket" => {
+                let (tag, buckets) = arg
+                    .rsplit_once(',')
+                    .map(|(tag, buckets)| (tag.trim(), buckets.trim().parse::<u32>()))
+                    .ok_or_else(|| TemplatePartParseError::InvalidBuckets {
+                        part: part.to_string(),
+                    })?;
+                match buckets {
+                    Ok(buckets) if buckets > 0 && !tag.is_empty() => Ok(Self::Bucket(TagBucket {
+                        tag: tag.to_string(),
+                        buckets,
+                    })),
+                    _ if tag.is_empty() => Err(TemplatePartParseError::EmptyArgument {
+                        part: part.to_string(),
+                    }),
+                    _ => Err(TemplatePartParseError::InvalidBuckets {
+                        part: part.to_string(),
+                    }),
+                }
+            }
+            "literal" => {
+                if !sanitize_tag_value(arg).eq(arg.chars()) {
+                    return Err(TemplatePartParseError::InvalidLiteral {
+                        literal: arg.to_string(),
+                    });
+                }
+                Ok(Self::Literal(arg.to_string()))
+            }
+            _ => Err(TemplatePartParseError::Unknown {
+                part: part.to_string(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for TemplatePart {
+    /// Write the part in the form its [`FromStr`](std::str::FromStr)
+    /// implementation parses.
+    ///
+    /// [`TemplatePart::RegexCapture`] and [`TemplatePart::StrftimeColumn`]
+    /// have no such form, and are written as `regex(column, regex)` and
+    /// `strftime(column, format)` for display only.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Table => write!(f, "table"),
+            Self::Column(column) => write!(f, "column({})", column),
+            Self::TimeFormat(format) => write!(f, "time({})", format),
+            Self::RegexCapture(RegexCapture { column, regex }) => {
+                write!(f, "regex({}, {})", column, regex)
+            }
+            Self::StrftimeColumn(StrftimeColumn { column, format }) => {
+                write!(f, "strftime({}, {})", column, format)
+            }
+            Self::TagValue(tag) => write!(f, "tag({})", tag),
+            Self::Bucket(TagBucket { tag, buckets }) => write!(f, "bucket({}, {})", tag, buckets),
+            Self::Literal(literal) => write!(f, "literal({})", literal),
+        }
+    }
 }
 
 /// `RegexCapture` is for pulling parts of a string column into the partition
@@ -1627,6 +2203,95 @@ impl<'a> std::fmt::Display for NamespaceName<'a> {
     }
 }
 
+/// Violations of the [`NamespaceNameRules`] of a deployment.
+#[derive(Debug, Snafu, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum NamespaceNameRuleViolation {
+    #[snafu(display(
+        "Namespace name {} is longer than the maximum of {} characters",
+        name,
+        max_length
+    ))]
+    TooLong { name: String, max_length: usize },
+
+    #[snafu(display(
+        "Namespace name '{}' contains disallowed character '{}' at character number {}",
+        name,
+        bad_char,
+        bad_char_offset
+    ))]
+    DisallowedChar {
+        name: String,
+        bad_char: char,
+        bad_char_offset: usize,
+    },
+
+    #[snafu(display("Namespace name '{}' uses reserved prefix '{}'", name, prefix))]
+    ReservedPrefix { name: String, prefix: String },
+}
+
+/// Deployment-specific restrictions on namespace names, applied on top of the
+/// [`NamespaceName`] validation.
+///
+/// The default rules allow any valid [`NamespaceName`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamespaceNameRules {
+    /// The maximum length of a namespace name, if lower than the
+    /// [`NamespaceName`] limit.
+    pub max_length: Option<usize>,
+
+    /// If set, namespace names may only contain ASCII alphanumeric characters
+    /// and the characters in this string.
+    pub allowed_chars: Option<String>,
+
+    /// Namespace names may not start with any of these prefixes.
+    pub reserved_prefixes: Vec<String>,
+}
+
+impl NamespaceNameRules {
+    /// Returns an error if `name` violates these rules.
+    pub fn validate(&self, name: &NamespaceName<'_>) -> Result<(), NamespaceNameRuleViolation> {
+        if let Some(max_length) = self.max_length {
+            if name.len() > max_length {
+                return TooLongSnafu {
+                    name: name.as_str(),
+                    max_length,
+                }
+                .fail();
+            }
+        }
+
+        if let Some(allowed) = &self.allowed_chars {
+            if let Some((bad_char_offset, bad_char)) = name
+                .chars()
+                .enumerate()
+                .find(|(_, c)| !c.is_ascii_alphanumeric() && !allowed.contains(*c))
+            {
+                return DisallowedCharSnafu {
+                    name: name.as_str(),
+                    bad_char,
+                    bad_char_offset,
+                }
+                .fail();
+            }
+        }
+
+        if let Some(prefix) = self
+            .reserved_prefixes
+            .iter()
+            .find(|p| name.starts_with(p.as_str()))
+        {
+            return ReservedPrefixSnafu {
+                name: name.as_str(),
+                prefix,
+            }
+            .fail();
+        }
+
+        Ok(())
+    }
+}
+
 /// Column name, statistics which encode type information
 #[derive(Debug, PartialEq, Clone)]
 pub struct ColumnSummary {
@@ -2638,6 +3303,51 @@ mod tests {
         assert_eq!(&*db, "my-example-db_with_underscores and spaces");
     }
 
+    #[test]
+    fn test_namespace_name_rules_default() {
+        let rules = NamespaceNameRules::default();
+        let ns = NamespaceName::new("_internal my-example").unwrap();
+        assert_eq!(rules.validate(&ns), Ok(()));
+    }
+
+    #[test]
+    fn test_namespace_name_rules() {
+        let rules = NamespaceNameRules {
+            max_length: Some(16),
+            allowed_chars: Some("_-".to_string()),
+            reserved_prefixes: vec!["system_".to_string(), "_internal".to_string()],
+        };
+
+        let ns = NamespaceName::new("my-org_bucket").unwrap();
+        assert_eq!(rules.validate(&ns), Ok(()));
+
+        let ns = NamespaceName::new("my_org_with_a_long_name").unwrap();
+        assert!(matches!(
+            rules.validate(&ns),
+            Err(NamespaceNameRuleViolation::TooLong { max_length: 16, .. })
+        ));
+
+        let ns = NamespaceName::new("my%21org_bucket").unwrap();
+        let err = rules.validate(&ns).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Namespace name 'my%21org_bucket' contains disallowed character '%' at character number 2"
+        );
+
+        let ns = NamespaceName::new("system_bucket").unwrap();
+        assert_eq!(
+            rules.validate(&ns),
+            Err(NamespaceNameRuleViolation::ReservedPrefix {
+                name: "system_bucket".to_string(),
+                prefix: "system_".to_string(),
+            })
+        );
+
+        // Reserved prefixes apply to the start of the name only.
+        let ns = NamespaceName::new("my_system_bucket").unwrap();
+        assert_eq!(rules.validate(&ns), Ok(()));
+    }
+
     #[test]
     fn statistics_new_non_null() {
         let actual = StatValues::new_non_null(Some(-1i64), Some(1i64), 3);
@@ -3376,6 +4086,12 @@ mod tests {
             tables: BTreeMap::from([]),
             max_columns_per_table: 4,
             retention_period_ns: None,
+            default_query_range_ns: None,
+            max_query_range_ns: None,
+            row_ttl_ns: None,
+            row_ttl_column: None,
+            partition_template: None,
+            generation: 0,
         };
         let schema2 = NamespaceSchema {
             id: NamespaceId::new(1),
@@ -3384,6 +4100,12 @@ mod tests {
             tables: BTreeMap::from([(String::from("foo"), TableSchema::new(TableId::new(1)))]),
             max_columns_per_table: 4,
             retention_period_ns: None,
+            default_query_range_ns: None,
+            max_query_range_ns: None,
+            row_ttl_ns: None,
+            row_ttl_column: None,
+            partition_template: None,
+            generation: 0,
         };
         assert!(schema1.size() < schema2.size());
     }
@@ -3424,4 +4146,110 @@ mod tests {
         assert_eq!(tr.start(), 1);
         assert_eq!(tr.end(), 1);
     }
+
+    #[test]
+    fn test_template_part_from_str() {
+        assert_eq!(
+            "table".parse::<TemplatePart>().unwrap(),
+            TemplatePart::Table
+        );
+        assert_eq!(
+            "column(region)".parse::<TemplatePart>().unwrap(),
+            TemplatePart::Column("region".to_string())
+        );
+        assert_eq!(
+            "time(%Y-%m)".parse::<TemplatePart>().unwrap(),
+            TemplatePart::TimeFormat("%Y-%m".to_string())
+        );
+        assert_eq!(
+            " tag( region ) ".parse::<TemplatePart>().unwrap(),
+            TemplatePart::TagValue("region".to_string())
+        );
+        assert_eq!(
+            "bucket(host, 16)".parse::<TemplatePart>().unwrap(),
+            TemplatePart::Bucket(TagBucket {
+                tag: "host".to_string(),
+                buckets: 16
+            })
+        );
+        assert_eq!(
+            "literal(v2.1)".parse::<TemplatePart>().unwrap(),
+            TemplatePart::Literal("v2.1".to_string())
+        );
+
+        for (part, want) in [
+            ("bananas", "unknown partition template part `bananas`"),
+            ("regex(a)", "unknown partition template part `regex(a)`"),
+            ("tag()", "has an empty argument"),
+            ("bucket(, 4)", "has an empty argument"),
+            ("bucket(host)", "must have a bucket count"),
+            ("bucket(host, 0)", "must have a bucket count"),
+            ("bucket(host, -1)", "must have a bucket count"),
+            ("literal(a-b)", "may only contain ASCII alphanumerics"),
+        ] {
+            let err = part.parse::<TemplatePart>().unwrap_err();
+            assert_contains!(err.to_string(), want);
+        }
+    }
+
+    #[test]
+    fn test_partition_template_text_round_trip() {
+        let template = PartitionTemplate {
+            parts: vec![
+                TemplatePart::TimeFormat("%Y-%m".to_string()),
+                TemplatePart::Table,
+                TemplatePart::Column("region".to_string()),
+                TemplatePart::TagValue("host".to_string()),
+                TemplatePart::Bucket(TagBucket {
+                    tag: "host".to_string(),
+                    buckets: 16,
+                }),
+                TemplatePart::Literal("v2".to_string()),
+            ],
+        };
+
+        let text = template.to_string();
+        assert_eq!(
+            text,
+            "time(%Y-%m)\ntable\ncolumn(region)\ntag(host)\nbucket(host, 16)\nliteral(v2)"
+        );
+        assert_eq!(text.parse::<PartitionTemplate>().unwrap(), template);
+
+        let err = "time(%Y)\nbananas"
+            .parse::<PartitionTemplate>()
+            .unwrap_err();
+        assert_contains!(err.to_string(), "unknown partition template part `bananas`");
+    }
+
+    #[test]
+    fn test_sanitize_tag_value() {
+        let sanitize = |v: &str| sanitize_tag_value(v).collect::<String>();
+
+        assert_eq!(sanitize("us-west.1_a"), "us_west.1_a");
+        assert_eq!(sanitize("a b/c%d\u{e9}"), "a_b_c_d_");
+        assert_eq!(sanitize(""), "");
+        assert_eq!(
+            sanitize(&"x".repeat(PARTITION_KEY_MAX_TAG_VALUE_LEN * 2)).len(),
+            PARTITION_KEY_MAX_TAG_VALUE_LEN
+        );
+    }
+
+    #[test]
+    fn test_tag_bucket() {
+        let bucket = TagBucket {
+            tag: "host".to_string(),
+            buckets: 16,
+        };
+
+        // The buckets must not change across releases, or the partitions of
+        // existing data would.
+        assert_eq!(bucket.bucket(""), 5);
+        assert_eq!(bucket.bucket("server01"), 5);
+        assert_eq!(bucket.bucket("server02"), 12);
+
+        let all = (0..1000)
+            .map(|i| bucket.bucket(&format!("host-{}", i)))
+            .collect::<BTreeSet<_>>();
+        assert_eq!(all, (0..16).collect());
+    }
 }
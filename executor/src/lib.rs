@@ -35,13 +35,18 @@ struct Task {
 
     #[allow(dead_code)]
     task_ref: Arc<()>,
+
+    /// Counts the task as queued until it starts running.
+    queued_ref: Option<Arc<()>>,
 }
 
 impl Task {
     /// Run task.
     ///
     /// This runs the payload or cancels if the linked [`Job`] is dropped.
-    async fn run(self) {
+    async fn run(mut self) {
+        self.queued_ref.take();
+
         tokio::select! {
             _ = self.cancel.cancelled() => (),
             _ = self.fut => (),
@@ -117,6 +122,9 @@ struct State {
     /// Task counter (uses Arc strong count).
     task_refs: Arc<()>,
 
+    /// Counter of the tasks that did not start running yet (uses Arc strong count).
+    queued_refs: Arc<()>,
+
     /// The inner thread that can be used to join during drop.
     thread: Option<std::thread::JoinHandle<()>>,
 }
@@ -158,6 +166,8 @@ impl DedicatedExecutor {
     /// The worker thread priority is set to low so that such tasks do
     /// not starve other more important tasks (such as answering health checks)
     ///
+    /// The threads of the executor are named after `thread_name`.
+    ///
     /// Follows the example from to stack overflow and spawns a new
     /// thread to install a Tokio runtime "context"
     /// <https://stackoverflow.com/questions/62536566>
@@ -173,42 +183,46 @@ impl DedicatedExecutor {
         let (tx_tasks, rx_tasks) = std::sync::mpsc::channel::<Task>();
         let (tx_shutdown, rx_shutdown) = tokio::sync::oneshot::channel();
 
-        let thread = std::thread::spawn(move || {
-            let runtime = tokio::runtime::Builder::new_multi_thread()
-                .enable_all()
-                .thread_name(&thread_name)
-                .worker_threads(num_threads)
-                .on_thread_start(move || set_current_thread_priority(WORKER_PRIORITY))
-                .build()
-                .expect("Creating tokio runtime");
-
-            runtime.block_on(async move {
-                // Dropping the tokio runtime only waits for tasks to yield not to complete
-                //
-                // We therefore use a RwLock to wait for tasks to complete
-                let join = Arc::new(tokio::sync::RwLock::new(()));
-
-                while let Ok(task) = rx_tasks.recv() {
-                    let join = Arc::clone(&join);
-                    let handle = join.read_owned().await;
-
-                    tokio::task::spawn(async move {
-                        task.run().await;
-                        std::mem::drop(handle);
-                    });
-                }
-
-                // Wait for all tasks to finish
-                let _guard = join.write().await;
-
-                // signal shutdown, but it's OK if the other side is gone
-                tx_shutdown.send(()).ok();
+        let thread = std::thread::Builder::new()
+            .name(thread_name.clone())
+            .spawn(move || {
+                let runtime = tokio::runtime::Builder::new_multi_thread()
+                    .enable_all()
+                    .thread_name(&thread_name)
+                    .worker_threads(num_threads)
+                    .on_thread_start(move || set_current_thread_priority(WORKER_PRIORITY))
+                    .build()
+                    .expect("Creating tokio runtime");
+
+                runtime.block_on(async move {
+                    // Dropping the tokio runtime only waits for tasks to yield not to complete
+                    //
+                    // We therefore use a RwLock to wait for tasks to complete
+                    let join = Arc::new(tokio::sync::RwLock::new(()));
+
+                    while let Ok(task) = rx_tasks.recv() {
+                        let join = Arc::clone(&join);
+                        let handle = join.read_owned().await;
+
+                        tokio::task::spawn(async move {
+                            task.run().await;
+                            std::mem::drop(handle);
+                        });
+                    }
+
+                    // Wait for all tasks to finish
+                    let _guard = join.write().await;
+
+                    // signal shutdown, but it's OK if the other side is gone
+                    tx_shutdown.send(()).ok();
+                })
             })
-        });
+            .expect("Spawning executor thread");
 
         let state = State {
             requests: Some(tx_tasks),
             task_refs: Arc::new(()),
+            queued_refs: Arc::new(()),
             completed_shutdown: rx_shutdown.map_err(Arc::new).boxed().shared(),
             thread: Some(thread),
         };
@@ -242,6 +256,7 @@ impl DedicatedExecutor {
             fut,
             cancel: cancel.clone(),
             task_ref: Arc::clone(&state.task_refs),
+            queued_ref: Some(Arc::clone(&state.queued_refs)),
         };
 
         if let Some(requests) = &mut state.requests {
@@ -258,7 +273,7 @@ impl DedicatedExecutor {
         }
     }
 
-    /// Number of currently active tasks.
+    /// Number of currently active tasks, including the queued ones.
     pub fn tasks(&self) -> usize {
         let state = self.state.lock();
 
@@ -266,6 +281,14 @@ impl DedicatedExecutor {
         Arc::strong_count(&state.task_refs).saturating_sub(1)
     }
 
+    /// Number of tasks that wait for a worker thread, i.e. that did not start running yet.
+    pub fn queued_tasks(&self) -> usize {
+        let state = self.state.lock();
+
+        // the strong count is always `1 + queued jobs` because of the Arc we hold within Self
+        Arc::strong_count(&state.queued_refs).saturating_sub(1)
+    }
+
     /// signals shutdown of this executor and any Clones
     pub fn shutdown(&self) {
         // hang up the channel which will cause the dedicated thread
@@ -607,6 +630,33 @@ mod tests {
         exec.join().await;
     }
 
+    #[tokio::test]
+    async fn queued_tasks() {
+        let barrier = Arc::new(Barrier::new(2));
+
+        // the first task blocks the only worker thread, so the second one has to wait
+        let exec = DedicatedExecutor::new("Test DedicatedExecutor", 1);
+        let task1 = exec.spawn(do_work(11, Arc::clone(&barrier)));
+        let task2 = exec.spawn(async { 42 });
+
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while exec.queued_tasks() != 1 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("Did not find queued task within a second");
+        assert_eq!(exec.tasks(), 2);
+
+        barrier.wait();
+        assert_eq!(task1.await.unwrap(), 11);
+        assert_eq!(task2.await.unwrap(), 42);
+        wait_for_tasks(&exec, 0).await;
+        assert_eq!(exec.queued_tasks(), 0);
+
+        exec.join().await;
+    }
+
     /// Wait for the barrier and then return `result`
     async fn do_work(result: usize, barrier: Arc<Barrier>) -> usize {
         barrier.wait();
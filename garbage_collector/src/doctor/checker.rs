@@ -0,0 +1,60 @@
+use iox_catalog::{doctor, interface::Catalog};
+use observability_deps::tracing::*;
+use snafu::prelude::*;
+use std::{sync::Arc, time::Duration};
+use tokio::{select, time::sleep};
+use tokio_util::sync::CancellationToken;
+
+pub(crate) async fn perform(
+    shutdown: CancellationToken,
+    catalog: Arc<dyn Catalog>,
+    repair: bool,
+    sleep_interval_minutes: u64,
+) -> Result<()> {
+    loop {
+        let mut report = doctor::check(catalog.as_ref())
+            .await
+            .context(CheckingSnafu)?;
+        if repair {
+            doctor::repair(catalog.as_ref(), &mut report)
+                .await
+                .context(RepairingSnafu)?;
+        }
+
+        for issue in &report.repaired {
+            info!(%issue, "repaired catalog inconsistency");
+        }
+        for issue in &report.issues {
+            warn!(%issue, "catalog inconsistency");
+        }
+        info!(
+            issue_count = %report.issues.len(),
+            repaired_count = %report.repaired.len(),
+            "iox_catalog::doctor::check()"
+        );
+
+        select! {
+            _ = shutdown.cancelled() => {
+                break
+            },
+            _ = sleep(Duration::from_secs(60 * sleep_interval_minutes)) => (),
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display("Failed to check the consistency of the catalog"))]
+    Checking {
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display("Failed to repair the catalog"))]
+    Repairing {
+        source: iox_catalog::interface::Error,
+    },
+}
+
+pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,2 @@
+/// Logic for checking the consistency of the catalog
+pub(crate) mod checker;
@@ -18,6 +18,7 @@
 #![allow(clippy::missing_docs_in_private_items)]
 
 use crate::{
+    doctor::checker as doctor_checker,
     objectstore::{checker as os_checker, deleter as os_deleter, lister as os_lister},
     parquetfile::deleter as pf_deleter,
     retention::flagger as retention_flagger,
@@ -33,6 +34,8 @@ use std::{fmt::Debug, sync::Arc, time::Duration};
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 
+/// Logic for checking the consistency of the catalog
+mod doctor;
 /// Logic for listing, checking and deleting files in object storage
 mod objectstore;
 /// Logic for deleting parquet files from the catalog
@@ -55,6 +58,7 @@ pub struct GarbageCollector {
     os_deleter: tokio::task::JoinHandle<Result<(), os_deleter::Error>>,
     pf_deleter: tokio::task::JoinHandle<Result<(), pf_deleter::Error>>,
     retention_flagger: tokio::task::JoinHandle<Result<(), retention_flagger::Error>>,
+    doctor: tokio::task::JoinHandle<Result<(), doctor_checker::Error>>,
 }
 
 impl Debug for GarbageCollector {
@@ -79,6 +83,8 @@ impl GarbageCollector {
             objectstore_sleep_interval_minutes = %sub_config.objectstore_sleep_interval_minutes,
             parquetfile_sleep_interval_minutes = %sub_config.parquetfile_sleep_interval_minutes,
             retention_sleep_interval_minutes = %sub_config.retention_sleep_interval_minutes,
+            doctor_sleep_interval_minutes = %sub_config.doctor_sleep_interval_minutes,
+            doctor_repair = %sub_config.doctor_repair,
             "GarbageCollector starting"
         );
 
@@ -132,10 +138,19 @@ impl GarbageCollector {
         // flag_for_delete_by_retention() on the catalog then sleeps.
         let retention_flagger = tokio::spawn(retention_flagger::perform(
             shutdown.clone(),
-            catalog,
+            Arc::clone(&catalog),
             sub_config.retention_sleep_interval_minutes,
         ));
 
+        // Initialise the catalog consistency checker, which is just one thread that checks the
+        // catalog, optionally repairs it, then sleeps.
+        let doctor = tokio::spawn(doctor_checker::perform(
+            shutdown.clone(),
+            catalog,
+            sub_config.doctor_repair,
+            sub_config.doctor_sleep_interval_minutes,
+        ));
+
         Ok(Self {
             shutdown,
             os_lister,
@@ -143,6 +158,7 @@ impl GarbageCollector {
             os_deleter,
             pf_deleter,
             retention_flagger,
+            doctor,
         })
     }
 
@@ -162,17 +178,20 @@ impl GarbageCollector {
             os_deleter,
             pf_deleter,
             retention_flagger,
+            doctor,
             shutdown: _,
         } = self;
 
-        let (os_lister, os_checker, os_deleter, pf_deleter, retention_flagger) = futures::join!(
+        let (os_lister, os_checker, os_deleter, pf_deleter, retention_flagger, doctor) = futures::join!(
             os_lister,
             os_checker,
             os_deleter,
             pf_deleter,
-            retention_flagger
+            retention_flagger,
+            doctor
         );
 
+        doctor.context(CatalogDoctorPanicSnafu)??;
         retention_flagger.context(ParquetFileDeleterPanicSnafu)??;
         pf_deleter.context(ParquetFileDeleterPanicSnafu)??;
         os_deleter.context(ObjectStoreDeleterPanicSnafu)??;
@@ -271,6 +290,21 @@ pub struct SubConfig {
         env = "INFLUXDB_IOX_GC_RETENTION_SLEEP_INTERVAL_MINUTES"
     )]
     retention_sleep_interval_minutes: u64,
+
+    /// Number of minutes to sleep between checks of the consistency of the catalog.
+    /// Defaults to 60 minutes.
+    #[clap(
+        long,
+        default_value_t = 60,
+        env = "INFLUXDB_IOX_GC_DOCTOR_SLEEP_INTERVAL_MINUTES"
+    )]
+    doctor_sleep_interval_minutes: u64,
+
+    /// If this flag is specified, repair the catalog inconsistencies that can be repaired,
+    /// flagging parquet files of missing partitions for deletion and removing tombstones of
+    /// missing tables or shards. Otherwise they are only logged.
+    #[clap(long, env = "INFLUXDB_IOX_GC_DOCTOR_REPAIR")]
+    doctor_repair: bool,
 }
 
 #[derive(Debug, Snafu)]
@@ -308,6 +342,12 @@ pub enum Error {
     ParquetFileRetentionFlagger { source: retention_flagger::Error },
     #[snafu(display("The parquet file retention flagger task panicked"))]
     ParquetFileRetentionFlaggerPanic { source: tokio::task::JoinError },
+
+    #[snafu(display("The catalog doctor task failed"))]
+    #[snafu(context(false))]
+    CatalogDoctor { source: doctor_checker::Error },
+    #[snafu(display("The catalog doctor task panicked"))]
+    CatalogDoctorPanic { source: tokio::task::JoinError },
 }
 
 #[allow(missing_docs)]
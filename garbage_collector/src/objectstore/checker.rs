@@ -1,7 +1,9 @@
 use chrono::{DateTime, Duration, Utc};
+use data_types::PartitionId;
 use iox_catalog::interface::{Catalog, ParquetFileRepo};
 use object_store::ObjectMeta;
 use observability_deps::tracing::*;
+use parquet_file::tag_index::TAG_INDEX_FILE_NAME;
 use snafu::prelude::*;
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -18,6 +20,14 @@ pub enum Error {
         object_store_id: uuid::Uuid,
     },
 
+    #[snafu(display(
+        "The catalog could not be queried for the files of partition {partition_id}"
+    ))]
+    ListPartitionFiles {
+        source: iox_catalog::interface::Error,
+        partition_id: PartitionId,
+    },
+
     #[snafu(display("The deleter task exited unexpectedly"))]
     DeleterExited {
         source: tokio::sync::mpsc::error::SendError<ObjectMeta>,
@@ -63,7 +73,52 @@ async fn should_delete(
         return Ok(false);
     }
 
-    let file_name = item.location.parts().last().context(FileNameMissingSnafu)?;
+    let parts = item.location.parts().collect::<Vec<_>>();
+    let file_name = parts.last().context(FileNameMissingSnafu)?;
+
+    if file_name.as_ref() == TAG_INDEX_FILE_NAME {
+        // The tag index of a partition is rewritten by the compactor as long as the partition has
+        // files; it is only garbage once none are left.
+        let partition_id = parts
+            .len()
+            .checked_sub(2)
+            .and_then(|i| parts[i].as_ref().parse().ok())
+            .map(PartitionId::new);
+        let partition_id = match partition_id {
+            Some(v) => v,
+            None => {
+                info!(
+                    location = %item.location,
+                    deleting = true,
+                    reason = "tag index of an invalid partition ID",
+                    "Scheduling file for deletion",
+                );
+                return Ok(true);
+            }
+        };
+
+        let files = parquet_files
+            .list_by_partition_not_to_delete(partition_id)
+            .await
+            .context(ListPartitionFilesSnafu { partition_id })?;
+        if !files.is_empty() {
+            info!(
+                location = %item.location,
+                deleting = false,
+                reason = "tag index of a partition with files",
+                "Ignoring object",
+            );
+            return Ok(false);
+        }
+
+        info!(
+            location = %item.location,
+            deleting = true,
+            reason = "tag index of a partition without files",
+            "Scheduling file for deletion",
+        );
+        return Ok(true);
+    }
 
     if let Some(uuid) = file_name.as_ref().strip_suffix(".parquet") {
         if let Ok(object_store_id) = uuid.parse() {
@@ -122,7 +177,7 @@ mod tests {
     use iox_catalog::{interface::Catalog, mem::MemCatalog};
     use object_store::path::Path;
     use once_cell::sync::Lazy;
-    use parquet_file::ParquetFilePath;
+    use parquet_file::{tag_index::TagIndexPath, ParquetFilePath};
     use uuid::Uuid;
 
     static OLDER_TIME: Lazy<DateTime<Utc>> =
@@ -311,6 +366,66 @@ mod tests {
         assert!(should_delete(&item, cutoff, parquet_files).await.unwrap());
     }
 
+    #[tokio::test]
+    async fn dont_delete_old_tag_index_of_partition_with_files() {
+        let (catalog, file_in_catalog) = test_catalog().await;
+        let mut repositories = catalog.repositories().await;
+        let parquet_files = repositories.parquet_files();
+
+        let location = TagIndexPath::from(&file_in_catalog).object_store_path();
+
+        let cutoff = *NEWER_TIME;
+        let last_modified = *OLDER_TIME;
+
+        let item = ObjectMeta {
+            location,
+            last_modified,
+            size: 0,
+        };
+
+        assert!(!should_delete(&item, cutoff, parquet_files).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn delete_old_tag_index_of_partition_without_files() {
+        let (catalog, file_in_catalog) = test_catalog().await;
+        let mut repositories = catalog.repositories().await;
+        let parquet_files = repositories.parquet_files();
+
+        let location = TagIndexPath::from(&file_in_catalog).object_store_path();
+        let item = ObjectMeta {
+            location,
+            last_modified: *OLDER_TIME,
+            size: 0,
+        };
+
+        // Once the last file of the partition is deleted, so is its index.
+        parquet_files
+            .flag_for_delete(file_in_catalog.id)
+            .await
+            .unwrap();
+        assert!(should_delete(&item, *NEWER_TIME, parquet_files)
+            .await
+            .unwrap());
+
+        // An index of a partition that doesn't exist is deleted too.
+        let location = TagIndexPath::new(
+            NamespaceId::new(1),
+            TableId::new(2),
+            ShardId::new(3),
+            PartitionId::new(4242),
+        )
+        .object_store_path();
+        let item = ObjectMeta {
+            location,
+            last_modified: *OLDER_TIME,
+            size: 0,
+        };
+        assert!(should_delete(&item, *NEWER_TIME, parquet_files)
+            .await
+            .unwrap());
+    }
+
     #[tokio::test]
     async fn delete_old_file_with_unparseable_path() {
         let metric_registry = Arc::new(metric::Registry::new());
@@ -18,46 +18,60 @@ fn main() -> Result<()> {
 ///
 /// Creates:
 ///
+/// - `influxdata.iox.authz.v1.rs`
+/// - `influxdata.iox.backup.v1.rs`
 /// - `influxdata.iox.catalog.v1.rs`
 /// - `influxdata.iox.compactor.v1.rs`
 /// - `influxdata.iox.delete.v1.rs`
 /// - `influxdata.iox.ingester.v1.rs`
 /// - `influxdata.iox.namespace.v1.rs`
 /// - `influxdata.iox.object_store.v1.rs`
+/// - `influxdata.iox.operations.v1.rs`
 /// - `influxdata.iox.predicate.v1.rs`
 /// - `influxdata.iox.querier.v1.rs`
 /// - `influxdata.iox.schema.v1.rs`
 /// - `influxdata.iox.sharder.v1.rs`
+/// - `influxdata.iox.usage.v1.rs`
 /// - `influxdata.iox.write.v1.rs`
 /// - `influxdata.iox.write_buffer.v1.rs`
 /// - `influxdata.platform.storage.rs`
 fn generate_grpc_types(root: &Path) -> Result<()> {
+    let authz_path = root.join("influxdata/iox/authz/v1");
+    let backup_path = root.join("influxdata/iox/backup/v1");
     let catalog_path = root.join("influxdata/iox/catalog/v1");
     let compactor_path = root.join("influxdata/iox/compactor/v1");
     let delete_path = root.join("influxdata/iox/delete/v1");
     let ingester_path = root.join("influxdata/iox/ingester/v1");
     let namespace_path = root.join("influxdata/iox/namespace/v1");
     let object_store_path = root.join("influxdata/iox/object_store/v1");
+    let operations_path = root.join("influxdata/iox/operations/v1");
     let predicate_path = root.join("influxdata/iox/predicate/v1");
     let querier_path = root.join("influxdata/iox/querier/v1");
     let schema_path = root.join("influxdata/iox/schema/v1");
     let sharder_path = root.join("influxdata/iox/sharder/v1");
+    let usage_path = root.join("influxdata/iox/usage/v1");
     let write_buffer_path = root.join("influxdata/iox/write_buffer/v1");
     let write_summary_path = root.join("influxdata/iox/write_summary/v1");
     let storage_path = root.join("influxdata/platform/storage");
     let storage_errors_path = root.join("influxdata/platform/errors");
 
     let proto_files = vec![
+        authz_path.join("service.proto"),
+        backup_path.join("backup.proto"),
         catalog_path.join("parquet_file.proto"),
         catalog_path.join("service.proto"),
         compactor_path.join("service.proto"),
         delete_path.join("service.proto"),
+        ingester_path.join("drain.proto"),
         ingester_path.join("parquet_metadata.proto"),
+        ingester_path.join("persist.proto"),
         ingester_path.join("query.proto"),
+        ingester_path.join("shard_assignment.proto"),
         ingester_path.join("write_info.proto"),
         ingester_path.join("write.proto"),
         namespace_path.join("service.proto"),
         object_store_path.join("service.proto"),
+        operations_path.join("operations.proto"),
         predicate_path.join("predicate.proto"),
         querier_path.join("flight.proto"),
         root.join("google/longrunning/operations.proto"),
@@ -67,6 +81,7 @@ fn generate_grpc_types(root: &Path) -> Result<()> {
         root.join("influxdata/pbdata/v1/influxdb_pb_data_protocol.proto"),
         schema_path.join("service.proto"),
         sharder_path.join("sharder.proto"),
+        usage_path.join("service.proto"),
         write_buffer_path.join("write_buffer.proto"),
         write_summary_path.join("write_summary.proto"),
         storage_path.join("predicate.proto"),
@@ -90,6 +105,7 @@ fn generate_grpc_types(root: &Path) -> Result<()> {
         .extern_path(".google.protobuf", "::pbjson_types")
         .btree_map([
             ".influxdata.iox.ingester.v1.IngesterQueryResponseMetadata.unpersisted_partitions",
+            ".influxdata.iox.querier.v1.AppMetadata.persisted_watermarks",
         ]);
 
     let descriptor_path = PathBuf::from(env::var("OUT_DIR").unwrap()).join("proto_descriptor.bin");
@@ -61,14 +61,81 @@ pub mod longrunning {
     include!(concat!(env!("OUT_DIR"), "/google.longrunning.rs"));
     include!(concat!(env!("OUT_DIR"), "/google.longrunning.serde.rs"));
 
+    use super::FieldViolation;
+    use crate::influxdata::iox::operations::v1::{OperationMetadata, OPERATION_METADATA};
+    use crate::protobuf_type_url_eq;
+    use prost::Message;
+
     impl Operation {
         /// Return the IOx operation `id`. This `id` can
         /// be passed to the various APIs in the
-        /// operations client such as `influxdb_iox_client::operations::Client::wait_operation`;
-        pub fn id(&self) -> usize {
-            self.name
-                .parse()
-                .expect("Internal error: id returned from server was not an integer")
+        /// operations client such as `influxdb_iox_client::operations::Client::wait_operation`.
+        pub fn id(&self) -> Result<usize, FieldViolation> {
+            self.name.parse().map_err(|_| FieldViolation {
+                field: "name".to_string(),
+                description: format!("operation name '{}' is not an integer", self.name),
+            })
+        }
+    }
+
+    /// An [`Operation`] of IOx together with its decoded [`OperationMetadata`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct IoxOperation {
+        /// The IOx operation `id`.
+        pub id: usize,
+
+        /// The operation as returned by the server.
+        pub operation: Operation,
+
+        /// The progress and resource usage of the operation.
+        pub metadata: OperationMetadata,
+    }
+
+    impl IoxOperation {
+        /// Returns true if the operation completed, successfully or not.
+        pub fn is_done(&self) -> bool {
+            self.operation.done
+        }
+
+        /// Returns the error of the operation, if it failed.
+        pub fn error(&self) -> Option<&super::rpc::Status> {
+            match &self.operation.result {
+                Some(operation::Result::Error(status)) => Some(status),
+                _ => None,
+            }
+        }
+    }
+
+    impl TryFrom<Operation> for IoxOperation {
+        type Error = FieldViolation;
+
+        fn try_from(operation: Operation) -> Result<Self, Self::Error> {
+            let id = operation.id()?;
+
+            let metadata = operation
+                .metadata
+                .as_ref()
+                .ok_or_else(|| FieldViolation::required("metadata"))?;
+            if !protobuf_type_url_eq(&metadata.type_url, OPERATION_METADATA) {
+                return Err(FieldViolation {
+                    field: "metadata.type_url".to_string(),
+                    description: format!(
+                        "expected {}, got {}",
+                        OPERATION_METADATA, metadata.type_url
+                    ),
+                });
+            }
+            let metadata =
+                OperationMetadata::decode(metadata.value.clone()).map_err(|e| FieldViolation {
+                    field: "metadata.value".to_string(),
+                    description: e.to_string(),
+                })?;
+
+            Ok(Self {
+                id,
+                operation,
+                metadata,
+            })
         }
     }
 }
@@ -233,6 +300,9 @@ pub enum ResourceType {
     DatabaseUuid,
     Job,
     Router,
+    Namespace,
+    Shard,
+    ParquetFile,
     Unknown(String),
 }
 
@@ -246,6 +316,9 @@ impl ResourceType {
             Self::Chunk => "chunk",
             Self::Job => "job",
             Self::Router => "router",
+            Self::Namespace => "namespace",
+            Self::Shard => "shard",
+            Self::ParquetFile => "parquet_file",
             Self::Unknown(unknown) => unknown,
         }
     }
@@ -261,6 +334,9 @@ impl From<String> for ResourceType {
             "chunk" => Self::Chunk,
             "job" => Self::Job,
             "router" => Self::Router,
+            "namespace" => Self::Namespace,
+            "shard" => Self::Shard,
+            "parquet_file" => Self::ParquetFile,
             _ => Self::Unknown(s),
         }
     }
@@ -428,6 +504,12 @@ pub enum PreconditionViolation {
     RouterConfigImmutable,
     /// Configuration is immutable
     DatabaseConfigImmutable,
+    /// Namespace not in required state for operation
+    NamespaceInvalidState(String),
+    /// Shard is not available, e.g. because it is not consumed
+    ShardUnavailable(String),
+    /// Parquet file not in required state for operation, e.g. already deleted
+    ParquetFileInvalidState(String),
     /// An unknown precondition violation
     Unknown {
         category: String,
@@ -446,6 +528,9 @@ impl PreconditionViolation {
             Self::ChunkInvalidState(description) => description.clone(),
             Self::RouterConfigImmutable => "router configuration is not mutable".to_string(),
             Self::DatabaseConfigImmutable => "database configuration is not mutable".to_string(),
+            Self::NamespaceInvalidState(description) => description.clone(),
+            Self::ShardUnavailable(description) => description.clone(),
+            Self::ParquetFileInvalidState(description) => description.clone(),
             Self::Unknown { description, .. } => description.clone(),
         }
     }
@@ -489,6 +574,21 @@ impl From<PreconditionViolation> for rpc::precondition_failure::Violation {
                 subject: "influxdata.com/iox/database".to_string(),
                 description: v.description(),
             },
+            PreconditionViolation::NamespaceInvalidState(_) => Self {
+                r#type: "state".to_string(),
+                subject: "influxdata.com/iox/namespace".to_string(),
+                description: v.description(),
+            },
+            PreconditionViolation::ShardUnavailable(_) => Self {
+                r#type: "available".to_string(),
+                subject: "influxdata.com/iox/shard".to_string(),
+                description: v.description(),
+            },
+            PreconditionViolation::ParquetFileInvalidState(_) => Self {
+                r#type: "state".to_string(),
+                subject: "influxdata.com/iox/parquet_file".to_string(),
+                description: v.description(),
+            },
             PreconditionViolation::Unknown {
                 category,
                 subject,
@@ -522,6 +622,15 @@ impl From<rpc::precondition_failure::Violation> for PreconditionViolation {
             ("config", "influxdata.com/iox/database") => {
                 PreconditionViolation::DatabaseConfigImmutable
             }
+            ("state", "influxdata.com/iox/namespace") => {
+                PreconditionViolation::NamespaceInvalidState(v.description)
+            }
+            ("available", "influxdata.com/iox/shard") => {
+                PreconditionViolation::ShardUnavailable(v.description)
+            }
+            ("state", "influxdata.com/iox/parquet_file") => {
+                PreconditionViolation::ParquetFileInvalidState(v.description)
+            }
             _ => Self::Unknown {
                 category: v.r#type,
                 subject: v.subject,
@@ -761,6 +870,31 @@ mod tests {
         assert_eq!(collected, vec![precondition]);
     }
 
+    #[test]
+    fn test_ng_resources_roundtrip() {
+        for resource_type in [
+            ResourceType::Namespace,
+            ResourceType::Shard,
+            ResourceType::ParquetFile,
+        ] {
+            let not_found = NotFound::new(resource_type, "foo".to_string());
+            let status = tonic::Status::from(not_found.clone());
+            let collected: Vec<_> = decode_not_found(&status).collect();
+            assert_eq!(collected, vec![not_found]);
+        }
+
+        for precondition in [
+            PreconditionViolation::NamespaceInvalidState("namespace".to_string()),
+            PreconditionViolation::ShardUnavailable("shard".to_string()),
+            PreconditionViolation::ParquetFileInvalidState("parquet file".to_string()),
+        ] {
+            let status = tonic::Status::from(precondition.clone());
+            assert_eq!(status.code(), tonic::Code::FailedPrecondition);
+            let collected: Vec<_> = decode_precondition_violation(&status).collect();
+            assert_eq!(collected, vec![precondition]);
+        }
+    }
+
     #[test]
     fn test_multiple() {
         // Should allow encoding multiple violations
@@ -792,4 +926,51 @@ mod tests {
         let collected: Vec<_> = decode_field_violation(&status).collect();
         assert_eq!(collected, violations);
     }
+
+    #[test]
+    fn test_iox_operation() {
+        use crate::influxdata::iox::operations::v1::{OperationMetadata, OPERATION_METADATA};
+        use longrunning::{IoxOperation, Operation};
+
+        let metadata = OperationMetadata {
+            description: "Compact partition 42".to_string(),
+            total_count: 4,
+            pending_count: 1,
+            success_count: 2,
+            error_count: 1,
+            ..Default::default()
+        };
+        let operation = Operation {
+            name: "13".to_string(),
+            metadata: Some(protobuf::Any {
+                type_url: crate::protobuf_type_url(OPERATION_METADATA),
+                value: metadata.encode_to_vec().into(),
+            }),
+            done: false,
+            result: None,
+        };
+
+        let iox_operation = IoxOperation::try_from(operation.clone()).unwrap();
+        assert_eq!(iox_operation.id, 13);
+        assert_eq!(iox_operation.metadata, metadata);
+        assert_eq!(iox_operation.metadata.fraction_completed(), Some(0.75));
+        assert!(!iox_operation.is_done());
+
+        let err = IoxOperation::try_from(Operation {
+            name: "not-a-number".to_string(),
+            ..operation.clone()
+        })
+        .unwrap_err();
+        assert_eq!(err.field, "name");
+
+        let err = IoxOperation::try_from(Operation {
+            metadata: Some(protobuf::Any {
+                type_url: "my_magic/type".to_string(),
+                value: Bytes::new(),
+            }),
+            ..operation
+        })
+        .unwrap_err();
+        assert_eq!(err.field, "metadata.type_url");
+    }
 }
@@ -45,6 +45,26 @@ pub mod influxdata {
     }
 
     pub mod iox {
+        pub mod authz {
+            pub mod v1 {
+                include!(concat!(env!("OUT_DIR"), "/influxdata.iox.authz.v1.rs"));
+                include!(concat!(
+                    env!("OUT_DIR"),
+                    "/influxdata.iox.authz.v1.serde.rs"
+                ));
+            }
+        }
+
+        pub mod backup {
+            pub mod v1 {
+                include!(concat!(env!("OUT_DIR"), "/influxdata.iox.backup.v1.rs"));
+                include!(concat!(
+                    env!("OUT_DIR"),
+                    "/influxdata.iox.backup.v1.serde.rs"
+                ));
+            }
+        }
+
         pub mod catalog {
             pub mod v1 {
                 include!(concat!(env!("OUT_DIR"), "/influxdata.iox.catalog.v1.rs"));
@@ -108,6 +128,34 @@ pub mod influxdata {
             }
         }
 
+        pub mod operations {
+            pub mod v1 {
+                include!(concat!(env!("OUT_DIR"), "/influxdata.iox.operations.v1.rs"));
+                include!(concat!(
+                    env!("OUT_DIR"),
+                    "/influxdata.iox.operations.v1.serde.rs"
+                ));
+
+                /// The name of the [`OperationMetadata`] protobuf type.
+                pub const OPERATION_METADATA: &str =
+                    "influxdata.iox.operations.v1.OperationMetadata";
+
+                impl OperationMetadata {
+                    /// Number of tasks of the operation that completed, successfully or not.
+                    pub fn completed_count(&self) -> u64 {
+                        self.success_count + self.error_count + self.cancelled_count
+                    }
+
+                    /// Fraction of the tasks of the operation that completed, or `None` if the
+                    /// operation has no tasks.
+                    pub fn fraction_completed(&self) -> Option<f64> {
+                        (self.total_count > 0)
+                            .then(|| self.completed_count() as f64 / self.total_count as f64)
+                    }
+                }
+            }
+        }
+
         pub mod predicate {
             pub mod v1 {
                 include!(concat!(env!("OUT_DIR"), "/influxdata.iox.predicate.v1.rs"));
@@ -167,6 +215,16 @@ pub mod influxdata {
             }
         }
 
+        pub mod usage {
+            pub mod v1 {
+                include!(concat!(env!("OUT_DIR"), "/influxdata.iox.usage.v1.rs"));
+                include!(concat!(
+                    env!("OUT_DIR"),
+                    "/influxdata.iox.usage.v1.serde.rs"
+                ));
+            }
+        }
+
         pub mod write_buffer {
             pub mod v1 {
                 include!(concat!(
@@ -266,6 +324,7 @@ pub mod delete_predicate;
 pub mod ingester;
 #[cfg(any(feature = "data_types_conversions", test))]
 pub mod write_info;
+pub mod write_summary;
 
 pub use prost::{DecodeError, EncodeError};
 
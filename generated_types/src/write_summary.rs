@@ -0,0 +1,231 @@
+use crate::influxdata::iox::write_summary::v1 as proto;
+use prost::Message;
+use snafu::{ensure, ResultExt, Snafu};
+use std::{fmt::Display, str::FromStr};
+
+/// The version of the write token format created by [`encode_write_token`].
+///
+/// Tokens are decoded by whichever release the client talks to next, so the version must be bumped
+/// whenever the meaning of an existing [`proto::WriteSummary`] field changes. Adding fields does
+/// not require a new version, since unknown fields are ignored when decoding.
+pub const WRITE_TOKEN_VERSION: u32 = 1;
+
+/// Encodes [`proto::WriteSummary`] as a write token.
+///
+/// The token is the base64 encoded protobuf representation of the summary, stamped with
+/// [`WRITE_TOKEN_VERSION`], and can be decoded with [`decode_write_token`].
+pub fn encode_write_token(summary: &proto::WriteSummary) -> String {
+    let summary = proto::WriteSummary {
+        version: WRITE_TOKEN_VERSION,
+        ..summary.clone()
+    };
+    base64::encode(summary.encode_to_vec())
+}
+
+#[derive(Debug, Snafu)]
+pub enum DecodeWriteTokenError {
+    #[snafu(display("invalid base64: {source}"))]
+    Base64Decode { source: base64::DecodeError },
+
+    #[snafu(display("protobuf decode error: {source}"))]
+    ProtobufDecode { source: prost::DecodeError },
+
+    #[snafu(display("json decode error: {source}"))]
+    JsonDecode { source: serde_json::Error },
+
+    #[snafu(display(
+        "unsupported write token version {version}, at most {WRITE_TOKEN_VERSION} is supported"
+    ))]
+    UnsupportedVersion { version: u32 },
+}
+
+/// Decodes [`proto::WriteSummary`] from a write token created with [`encode_write_token`].
+///
+/// Fields unknown to this release are ignored, so tokens created by newer releases decode as long
+/// as their version is supported. Unversioned tokens, which were base64 encoded JSON, are decoded
+/// with a version of 0.
+pub fn decode_write_token(token: &str) -> Result<proto::WriteSummary, DecodeWriteTokenError> {
+    let data = base64::decode(token).context(Base64DecodeSnafu)?;
+
+    // a protobuf message never starts with `{`, since that is the tag of a group with field
+    // number 15
+    let summary = if data.first() == Some(&b'{') {
+        serde_json::from_slice(&data).context(JsonDecodeSnafu)?
+    } else {
+        proto::WriteSummary::decode(data.as_slice()).context(ProtobufDecodeSnafu)?
+    };
+
+    ensure!(
+        summary.version <= WRITE_TOKEN_VERSION,
+        UnsupportedVersionSnafu {
+            version: summary.version
+        }
+    );
+
+    Ok(summary)
+}
+
+/// A decoded write token, describing the shards and sequence numbers of a write.
+///
+/// Parse a token with [`str::parse`], and encode it again with [`ToString::to_string`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteToken {
+    summary: proto::WriteSummary,
+}
+
+impl WriteToken {
+    /// The version of the token format, 0 for tokens created before the format was versioned.
+    pub fn version(&self) -> u32 {
+        self.summary.version
+    }
+
+    /// The shard indexes the write was sequenced into.
+    pub fn shard_indexes(&self) -> impl Iterator<Item = i32> + '_ {
+        self.summary.shards.iter().map(|shard| shard.shard_index)
+    }
+
+    /// The sequence numbers of the write in the shard with `shard_index`, or `None` if the write
+    /// has no data in that shard.
+    pub fn sequence_numbers(&self, shard_index: i32) -> Option<&[i64]> {
+        self.summary
+            .shards
+            .iter()
+            .find(|shard| shard.shard_index == shard_index)
+            .map(|shard| shard.sequence_numbers.as_slice())
+    }
+
+    /// The summary of the write.
+    pub fn summary(&self) -> &proto::WriteSummary {
+        &self.summary
+    }
+}
+
+impl FromStr for WriteToken {
+    type Err = DecodeWriteTokenError;
+
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        decode_write_token(token).map(Self::from)
+    }
+}
+
+impl Display for WriteToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", encode_write_token(&self.summary))
+    }
+}
+
+impl From<proto::WriteSummary> for WriteToken {
+    fn from(summary: proto::WriteSummary) -> Self {
+        Self { summary }
+    }
+}
+
+impl From<WriteToken> for proto::WriteSummary {
+    fn from(token: WriteToken) -> Self {
+        token.summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_summary() -> proto::WriteSummary {
+        proto::WriteSummary {
+            shards: vec![
+                proto::ShardWrite {
+                    shard_index: 1,
+                    sequence_numbers: vec![2, 3],
+                    partitions: vec![proto::PartitionWrite {
+                        partition_key: "2022-12-14".to_string(),
+                        sequence_number: 2,
+                        row_count: 3,
+                    }],
+                },
+                proto::ShardWrite {
+                    shard_index: 5,
+                    sequence_numbers: vec![7],
+                    partitions: vec![],
+                },
+            ],
+            version: WRITE_TOKEN_VERSION,
+        }
+    }
+
+    #[test]
+    fn token_round_trip() {
+        let summary = test_summary();
+
+        let token = encode_write_token(&summary);
+        assert_eq!(decode_write_token(&token).unwrap(), summary);
+
+        // the version is stamped when encoding
+        let unversioned = proto::WriteSummary {
+            version: 0,
+            ..summary.clone()
+        };
+        let token = encode_write_token(&unversioned);
+        assert_eq!(decode_write_token(&token).unwrap(), summary);
+    }
+
+    #[test]
+    fn token_without_partitions() {
+        // unversioned json tokens created before the partitions were recorded remain readable
+        let token = base64::encode(r#"{"shards":[{"shardIndex":1,"sequenceNumbers":["2"]}]}"#);
+        let summary = decode_write_token(&token).unwrap();
+
+        assert_eq!(
+            summary,
+            proto::WriteSummary {
+                shards: vec![proto::ShardWrite {
+                    shard_index: 1,
+                    sequence_numbers: vec![2],
+                    partitions: vec![],
+                }],
+                version: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn token_with_unknown_fields() {
+        // a newer release may add fields without bumping the version
+        let mut data = test_summary().encode_to_vec();
+        prost::encoding::string::encode(100, &"new field".to_string(), &mut data);
+
+        let token = base64::encode(data);
+        assert_eq!(decode_write_token(&token).unwrap(), test_summary());
+    }
+
+    #[test]
+    fn token_with_unsupported_version() {
+        let summary = proto::WriteSummary {
+            version: WRITE_TOKEN_VERSION + 1,
+            ..test_summary()
+        };
+
+        let token = base64::encode(summary.encode_to_vec());
+        let err = decode_write_token(&token).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unsupported write token version 2, at most 1 is supported"
+        );
+    }
+
+    #[test]
+    fn write_token() {
+        let token: WriteToken = encode_write_token(&test_summary()).parse().unwrap();
+
+        assert_eq!(token.version(), WRITE_TOKEN_VERSION);
+        assert_eq!(token.shard_indexes().collect::<Vec<_>>(), vec![1, 5]);
+        assert_eq!(token.sequence_numbers(1), Some([2, 3].as_slice()));
+        assert_eq!(token.sequence_numbers(5), Some([7].as_slice()));
+        assert_eq!(token.sequence_numbers(2), None);
+
+        let round_trip: WriteToken = token.to_string().parse().unwrap();
+        assert_eq!(round_trip, token);
+        assert_eq!(proto::WriteSummary::from(round_trip), test_summary());
+
+        assert!("foo%%".parse::<WriteToken>().is_err());
+    }
+}
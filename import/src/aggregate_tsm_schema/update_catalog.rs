@@ -455,6 +455,13 @@ mod tests {
             self.requests.write().push(request.into_inner());
             Ok(tonic::Response::new(self.reply_with.clone()))
         }
+
+        async fn get_shard_progress(
+            &self,
+            _request: tonic::Request<GetShardProgressRequest>,
+        ) -> Result<tonic::Response<GetShardProgressResponse>, tonic::Status> {
+            Err(tonic::Status::unimplemented("not used by the import tests"))
+        }
     }
 
     async fn create_test_shard_service(
@@ -503,6 +510,7 @@ mod tests {
         let (connection, _join_handle, _requests) = create_test_shard_service(MapToShardResponse {
             shard_id: 0,
             shard_index: 0,
+            topic: "iox-shared".to_string(),
         })
         .await;
 
@@ -588,6 +596,7 @@ mod tests {
         let (connection, _join_handle, _requests) = create_test_shard_service(MapToShardResponse {
             shard_id: 0,
             shard_index: 0,
+            topic: "iox-shared".to_string(),
         })
         .await;
 
@@ -692,6 +701,7 @@ mod tests {
         let (connection, _join_handle, _requests) = create_test_shard_service(MapToShardResponse {
             shard_id: 0,
             shard_index: 0,
+            topic: "iox-shared".to_string(),
         })
         .await;
 
@@ -772,6 +782,7 @@ mod tests {
         let (connection, _join_handle, _requests) = create_test_shard_service(MapToShardResponse {
             shard_id: 0,
             shard_index: 0,
+            topic: "iox-shared".to_string(),
         })
         .await;
 
@@ -850,6 +861,7 @@ mod tests {
         let (connection, _join_handle, _requests) = create_test_shard_service(MapToShardResponse {
             shard_id: 0,
             shard_index: 0,
+            topic: "iox-shared".to_string(),
         })
         .await;
 
@@ -899,6 +911,7 @@ mod tests {
         let (connection, _join_handle, requests) = create_test_shard_service(MapToShardResponse {
             shard_id: 0,
             shard_index: 0,
+            topic: "iox-shared".to_string(),
         })
         .await;
 
@@ -1261,6 +1274,7 @@ mod tests {
             shard_id: ShardId::new(1),
             table_id: TableId::new(1),
             persisted_sequence_number: None,
+            cold_compacted_at: None,
             partition_key: PartitionKey::from("2022-06-21"),
             sort_key: Vec::new(),
         };
@@ -1308,6 +1322,7 @@ mod tests {
             shard_id: ShardId::new(1),
             table_id: TableId::new(1),
             persisted_sequence_number: None,
+            cold_compacted_at: None,
             partition_key: PartitionKey::from("2022-06-21"),
             // N.B. sort key is already what it will computed to; here we're testing the `adjust_sort_key_columns` code path
             sort_key: vec!["host".to_string(), "arch".to_string(), "time".to_string()],
@@ -1355,6 +1370,7 @@ mod tests {
             shard_id: ShardId::new(1),
             table_id: TableId::new(1),
             persisted_sequence_number: None,
+            cold_compacted_at: None,
             partition_key: PartitionKey::from("2022-06-21"),
             // N.B. is missing host so will need updating
             sort_key: vec!["arch".to_string(), "time".to_string()],
@@ -1404,6 +1420,7 @@ mod tests {
             shard_id: ShardId::new(1),
             table_id: TableId::new(1),
             persisted_sequence_number: None,
+            cold_compacted_at: None,
             partition_key: PartitionKey::from("2022-06-21"),
             // N.B. is missing arch so will need updating
             sort_key: vec!["host".to_string(), "time".to_string()],
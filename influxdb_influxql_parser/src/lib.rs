@@ -20,6 +20,7 @@ use crate::statement::{statement, Statement};
 use nom::combinator::eof;
 use nom::Offset;
 use std::fmt::{Debug, Display, Formatter};
+use std::ops::Range;
 
 #[cfg(test)]
 mod test_util;
@@ -34,6 +35,7 @@ pub mod identifier;
 mod internal;
 mod keywords;
 pub mod literal;
+mod normalize;
 pub mod parameter;
 pub mod select;
 pub mod show;
@@ -55,6 +57,18 @@ pub struct ParseError {
     pos: usize,
 }
 
+impl ParseError {
+    /// A human-readable message indicating the cause of the parse failure.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The byte offset of the input at which parsing failed.
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+}
+
 impl Display for ParseError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} at pos {}", self.message, self.pos)
@@ -88,34 +102,140 @@ pub fn parse_statements(input: &str) -> ParseResult {
             continue;
         }
 
-        match statement(i) {
-            Ok((i1, o)) => {
-                res.push(o);
+        let (i1, o) = parse_statement(input, i)?;
+        res.push(o);
+        i = i1;
+    }
+}
+
+/// A statement parsed by [`parse_statements_with_recovery`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedStatement {
+    /// The parsed statement.
+    pub statement: Statement,
+
+    /// The byte range of the input the statement was parsed from.
+    pub span: Range<usize>,
+}
+
+/// Parse the input into a set of InfluxQL statements, continuing with the next statement
+/// whenever a statement fails to parse.
+///
+/// Unlike [`parse_statements`], which stops at the first error, this returns the statements
+/// which could be parsed along with an error for every statement which could not, so that
+/// interactive clients can report all of them at once.
+pub fn parse_statements_with_recovery(input: &str) -> (Vec<SpannedStatement>, Vec<ParseError>) {
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+    let mut i: &str = input;
+
+    loop {
+        // Consume whitespace from the input
+        i = match ws0(i) {
+            Ok((i1, _)) => i1,
+            _ => unreachable!("ws0 is infallible"),
+        };
+
+        if eof::<_, nom::error::Error<_>>(i).is_ok() {
+            return (statements, errors);
+        }
+
+        if let Ok((i1, _)) = statement_terminator(i) {
+            i = i1;
+            continue;
+        }
+
+        match parse_statement(input, i) {
+            Ok((i1, statement)) => {
+                statements.push(SpannedStatement {
+                    statement,
+                    span: input.offset(i)..input.offset(i1),
+                });
                 i = i1;
             }
-            Err(nom::Err::Failure(InternalError::Syntax {
-                input: pos,
-                message,
-            })) => {
-                return Err(ParseError {
-                    message: message.into(),
-                    pos: input.offset(pos),
-                })
+            Err(e) => {
+                errors.push(e);
+                i = skip_statement(i);
+            }
+        }
+    }
+}
+
+/// Parse the statement at the start of `i`, which is a suffix of `input`.
+fn parse_statement<'a>(input: &str, i: &'a str) -> Result<(&'a str, Statement), ParseError> {
+    match statement(i) {
+        Ok(v) => Ok(v),
+        Err(nom::Err::Failure(InternalError::Syntax {
+            input: pos,
+            message,
+        })) => Err(ParseError {
+            message: message.into(),
+            pos: input.offset(pos),
+        }),
+        // any other error indicates an invalid statement
+        Err(_) => Err(ParseError {
+            message: "invalid SQL statement".into(),
+            pos: input.offset(i),
+        }),
+    }
+}
+
+/// Returns the input following the next statement terminator of `i`, ignoring any terminators
+/// within quoted strings, quoted identifiers and comments.
+fn skip_statement(i: &str) -> &str {
+    // All delimiters are ASCII, which never occurs within a multi-byte UTF-8 sequence, so the
+    // input can be scanned byte by byte.
+    let bytes = i.as_bytes();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b';' => return &i[pos + 1..],
+            q @ (b'\'' | b'"') => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != q {
+                    if bytes[pos] == b'\\' {
+                        pos += 1;
+                    }
+                    pos += 1;
+                }
+            }
+            b'-' if i[pos..].starts_with("--") => {
+                pos += i[pos..].find('\n').unwrap_or(i.len() - pos);
             }
-            // any other error indicates an invalid statement
-            Err(_) => {
-                return Err(ParseError {
-                    message: "invalid SQL statement".into(),
-                    pos: input.offset(i),
-                })
+            b'/' if i[pos..].starts_with("/*") => {
+                pos += i[pos..].find("*/").map_or(i.len() - pos, |n| n + 1);
             }
+            _ => {}
         }
+        pos += 1;
     }
+    ""
+}
+
+/// Parse the input into a set of InfluxQL statements and format them in their canonical form.
+///
+/// Every statement is formatted using its [`Display`] implementation, which writes keywords in
+/// upper case, separates tokens by a single space, quotes identifiers only where necessary and
+/// drops comments. Beforehand, the names of function calls are lower-cased and parentheses that
+/// do not change the precedence of the expression they enclose are removed. Statements are
+/// terminated by a `;` and separated by a new line.
+///
+/// Queries that only differ in these respects are therefore formatted identically, which allows
+/// tooling to display and fingerprint them consistently.
+pub fn format_statements(input: &str) -> Result<String, ParseError> {
+    Ok(parse_statements(input)?
+        .into_iter()
+        .map(|mut s| {
+            normalize::normalize_statement(&mut s);
+            format!("{};", s)
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
 }
 
 #[cfg(test)]
 mod test {
-    use crate::parse_statements;
+    use crate::{format_statements, normalize, parse_statements, parse_statements_with_recovery};
 
     /// Validates that the [`parse_statements`] function
     /// handles statement terminators and errors.
@@ -180,4 +300,107 @@ mod test {
         let got = parse_statements("SHOW MEASUREMENTS;BAD SQL").unwrap_err();
         assert_eq!(format!("{}", got), "invalid SQL statement at pos 18");
     }
+
+    #[test]
+    fn test_parse_statements_with_recovery() {
+        let input = "SHOW DATABASES;BAD SQL;SHOW MEASUREMENTS WITH MEASUREMENT = \";\"; SHOW TAG VALUES WITH KEY !~ foo;\nDROP MEASUREMENT cpu";
+        let (statements, errors) = parse_statements_with_recovery(input);
+
+        let got = statements
+            .iter()
+            .map(|s| (format!("{}", s.statement), &input[s.span.clone()]))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            got,
+            vec![
+                ("SHOW DATABASES".to_string(), "SHOW DATABASES"),
+                (
+                    "SHOW MEASUREMENTS WITH MEASUREMENT = \";\"".to_string(),
+                    "SHOW MEASUREMENTS WITH MEASUREMENT = \";\""
+                ),
+                ("DROP MEASUREMENT cpu".to_string(), "DROP MEASUREMENT cpu"),
+            ]
+        );
+
+        let got = errors
+            .iter()
+            .map(|e| (e.message(), e.pos()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            got,
+            vec![
+                ("invalid SQL statement", 15),
+                (
+                    "invalid WITH KEY clause, expected regular expression following !~",
+                    93
+                ),
+            ]
+        );
+
+        // Terminators within quotes and comments do not end an invalid statement
+        let (statements, errors) =
+            parse_statements_with_recovery("BAD ';' \"a;b\" /* ; */ -- ;\n SQL; SHOW DATABASES");
+        assert_eq!(statements.len(), 1);
+        assert_eq!(format!("{}", statements[0].statement), "SHOW DATABASES");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_format_statements() {
+        let got = format_statements(
+            "select  value,\n usage AS \"the usage\" from cpu where host='a' group by time(5m), \"host\" fill(null) ;\nshow databases",
+        )
+        .unwrap();
+        assert_eq!(
+            got,
+            "SELECT value, usage AS \"the usage\" FROM cpu WHERE host = 'a' GROUP BY TIME(5m), host FILL(NULL);\nSHOW DATABASES;"
+        );
+
+        // Function names are lower-cased and redundant parentheses removed
+        let got = format_statements("SELECT Mean((usage)) FROM cpu").unwrap();
+        assert_eq!(got, "SELECT mean(usage) FROM cpu;");
+
+        // Parentheses required by the precedence of operators are kept
+        for (input, want) in [
+            (
+                "SELECT (a + b) * c, a + (b * c), (a * b) + c FROM cpu",
+                "SELECT (a + b) * c, a + b * c, a * b + c FROM cpu;",
+            ),
+            (
+                "SELECT a - (b - c), (a - b) - c, -(a + b), -(-a) FROM cpu",
+                "SELECT a - (b - c), a - b - c, -(a + b), -(-a) FROM cpu;",
+            ),
+            (
+                "SELECT a FROM cpu WHERE ((a = 1) AND (b = 2 OR c = 3)) OR ((d + 1) > 2)",
+                "SELECT a FROM cpu WHERE a = 1 AND (b = 2 OR c = 3) OR d + 1 > 2;",
+            ),
+            (
+                "DELETE WHERE (a = 1 OR b = 2) AND (c = 3)",
+                "DELETE WHERE (a = 1 OR b = 2) AND c = 3;",
+            ),
+        ] {
+            assert_eq!(format_statements(input).unwrap(), want, "{}", input);
+        }
+
+        // The canonical form parses to the normalized statements, and is therefore stable.
+        for input in [
+            "SELECT COUNT(DISTINCT value), count(*) INTO db.rp.m FROM cpu, /^mem/, (SELECT usage FROM cpu) WHERE time > now() - 1h AND (host = 'a' OR host =~ /b/) GROUP BY TIME(1h, -15m), * FILL(-1.5) ORDER BY TIME DESC LIMIT 1 OFFSET 2 SLIMIT 3 SOFFSET 4 TZ('Australia/Hobart')",
+            "SHOW MEASUREMENTS ON db WITH MEASUREMENT =~ /cpu/ WHERE host = 'a' LIMIT 10 OFFSET 1",
+            "SHOW TAG KEYS ON db FROM cpu WHERE host = 'a' LIMIT 1 OFFSET 2",
+            "SHOW TAG VALUES FROM cpu WITH KEY IN (host, region)",
+            "SHOW TAG VALUES FROM cpu WITH KEY !~ /host/",
+            "SHOW FIELD KEYS FROM cpu",
+            "SHOW RETENTION POLICIES ON db",
+            "EXPLAIN ANALYZE SELECT usage FROM cpu",
+            "DELETE FROM cpu WHERE time < now() - 30d",
+            "DROP MEASUREMENT cpu",
+            "CREATE DATABASE db WITH DURATION 1d REPLICATION 1 SHARD DURATION 1h NAME rp",
+        ] {
+            let mut want = parse_statements(input).unwrap();
+            want.iter_mut().for_each(normalize::normalize_statement);
+            let formatted = format_statements(input).unwrap();
+            assert_eq!(parse_statements(&formatted).unwrap(), want, "{}", formatted);
+            assert_eq!(format_statements(&formatted).unwrap(), formatted);
+        }
+    }
 }
@@ -0,0 +1,208 @@
+//! Normalization of statements to the canonical form written by
+//! [`format_statements`](crate::format_statements).
+
+use crate::delete::DeleteStatement;
+use crate::expression::arithmetic::{BinaryOperator, Expr};
+use crate::expression::conditional::{ConditionalExpression, ConditionalOperator};
+use crate::select::{Dimension, MeasurementSelection, SelectStatement};
+use crate::statement::Statement;
+
+/// Rewrite `statement` to its canonical form, in place.
+///
+/// The names of function calls are lower-cased, and parentheses are removed from all
+/// expressions, except where they are required to preserve the precedence of the operators of
+/// the expression they enclose.
+pub(crate) fn normalize_statement(statement: &mut Statement) {
+    match statement {
+        Statement::Delete(s) => match s.as_mut() {
+            DeleteStatement::FromWhere { condition, .. } => {
+                if let Some(condition) = condition {
+                    normalize_conditional(condition, Position::Any);
+                }
+            }
+            DeleteStatement::Where(condition) => normalize_conditional(condition, Position::Any),
+        },
+        Statement::Explain(s) => normalize_select(&mut s.select),
+        Statement::Select(s) => normalize_select(s),
+        Statement::ShowMeasurements(s) => {
+            if let Some(condition) = &mut s.condition {
+                normalize_conditional(condition, Position::Any);
+            }
+        }
+        Statement::ShowTagKeys(s) => {
+            if let Some(condition) = &mut s.condition {
+                normalize_conditional(condition, Position::Any);
+            }
+        }
+        Statement::ShowTagValues(s) => {
+            if let Some(condition) = &mut s.condition {
+                normalize_conditional(condition, Position::Any);
+            }
+        }
+        Statement::CreateDatabase(_)
+        | Statement::DropMeasurement(_)
+        | Statement::ShowDatabases(_)
+        | Statement::ShowRetentionPolicies(_)
+        | Statement::ShowFieldKeys(_) => {}
+    }
+}
+
+fn normalize_select(s: &mut SelectStatement) {
+    for field in &mut s.fields.contents {
+        normalize_expr(&mut field.expr, Position::Any);
+    }
+
+    for selection in &mut s.from.contents {
+        if let MeasurementSelection::Subquery(s) = selection {
+            normalize_select(s);
+        }
+    }
+
+    if let Some(condition) = &mut s.condition {
+        normalize_conditional(condition, Position::Any);
+    }
+
+    if let Some(group_by) = &mut s.group_by {
+        for dimension in &mut group_by.contents {
+            if let Dimension::Time { interval, offset } = dimension {
+                normalize_expr(interval, Position::Any);
+                if let Some(offset) = offset {
+                    normalize_expr(offset, Position::Any);
+                }
+            }
+        }
+    }
+}
+
+/// The position of an expression within its parent, which determines whether it must be
+/// parenthesized.
+#[derive(Debug, Clone, Copy)]
+enum Position {
+    /// An expression that is not an operand, such as a function argument or a `WHERE` clause.
+    Any,
+    /// The left-hand side of a binary operator with the given precedence.
+    Lhs(u8),
+    /// The right-hand side of a binary operator with the given precedence.
+    Rhs(u8),
+    /// The operand of a unary operator.
+    Unary,
+}
+
+impl Position {
+    /// Returns true if a binary expression with an operator of `precedence` must be
+    /// parenthesized in this position.
+    ///
+    /// All binary operators are left-associative.
+    fn requires_parens(self, precedence: u8) -> bool {
+        match self {
+            Self::Any => false,
+            Self::Lhs(parent) => precedence < parent,
+            Self::Rhs(parent) => precedence <= parent,
+            Self::Unary => true,
+        }
+    }
+}
+
+/// The precedence of an arithmetic operator; operators with a higher precedence bind more
+/// tightly.
+fn arithmetic_precedence(op: BinaryOperator) -> u8 {
+    match op {
+        BinaryOperator::Add
+        | BinaryOperator::Sub
+        | BinaryOperator::BitwiseOr
+        | BinaryOperator::BitwiseXor => 1,
+        BinaryOperator::Mul
+        | BinaryOperator::Div
+        | BinaryOperator::Mod
+        | BinaryOperator::BitwiseAnd => 2,
+    }
+}
+
+/// The precedence of a conditional operator; operators with a higher precedence bind more
+/// tightly.
+fn conditional_precedence(op: ConditionalOperator) -> u8 {
+    match op {
+        ConditionalOperator::Or => 1,
+        ConditionalOperator::And => 2,
+        ConditionalOperator::Eq
+        | ConditionalOperator::NotEq
+        | ConditionalOperator::Lt
+        | ConditionalOperator::LtEq
+        | ConditionalOperator::Gt
+        | ConditionalOperator::GtEq
+        | ConditionalOperator::In => 3,
+        ConditionalOperator::EqRegex | ConditionalOperator::NotEqRegex => 4,
+    }
+}
+
+fn normalize_expr(e: &mut Expr, position: Position) {
+    while let Expr::Nested(inner) = e {
+        let inner = std::mem::replace(inner.as_mut(), Expr::Wildcard(None));
+        *e = inner;
+    }
+
+    let requires_parens = match e {
+        Expr::Binary { lhs, op, rhs } => {
+            let precedence = arithmetic_precedence(*op);
+            normalize_expr(lhs, Position::Lhs(precedence));
+            normalize_expr(rhs, Position::Rhs(precedence));
+            position.requires_parens(precedence)
+        }
+        Expr::UnaryOp(_, operand) => {
+            normalize_expr(operand, Position::Unary);
+            // Two consecutive signs would be written as `--`, which starts a comment.
+            matches!(position, Position::Unary)
+        }
+        Expr::Call { name, args } => {
+            *name = name.to_lowercase();
+            for arg in args {
+                normalize_expr(arg, Position::Any);
+            }
+            false
+        }
+        Expr::Literal(v) => matches!(position, Position::Unary) && v.to_string().starts_with('-'),
+        Expr::VarRef { .. }
+        | Expr::BindParameter(_)
+        | Expr::Wildcard(_)
+        | Expr::Distinct(_)
+        | Expr::Nested(_) => false,
+    };
+
+    if requires_parens {
+        let inner = std::mem::replace(e, Expr::Wildcard(None));
+        *e = Expr::Nested(Box::new(inner));
+    }
+}
+
+fn normalize_conditional(e: &mut ConditionalExpression, position: Position) {
+    while let ConditionalExpression::Grouped(inner) = e {
+        let inner = std::mem::replace(
+            inner.as_mut(),
+            ConditionalExpression::Expr(Box::new(Expr::Wildcard(None))),
+        );
+        *e = inner;
+    }
+
+    let requires_parens = match e {
+        ConditionalExpression::Binary { lhs, op, rhs } => {
+            let precedence = conditional_precedence(*op);
+            normalize_conditional(lhs, Position::Lhs(precedence));
+            normalize_conditional(rhs, Position::Rhs(precedence));
+            position.requires_parens(precedence)
+        }
+        // Arithmetic operators bind more tightly than all conditional operators.
+        ConditionalExpression::Expr(e) => {
+            normalize_expr(e, Position::Any);
+            false
+        }
+        ConditionalExpression::Grouped(_) => false,
+    };
+
+    if requires_parens {
+        let inner = std::mem::replace(
+            e,
+            ConditionalExpression::Expr(Box::new(Expr::Wildcard(None))),
+        );
+        *e = ConditionalExpression::Grouped(Box::new(inner));
+    }
+}
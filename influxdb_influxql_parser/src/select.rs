@@ -4,8 +4,8 @@
 
 use crate::common::{
     limit_clause, offset_clause, order_by_clause, qualified_measurement_name, where_clause, ws0,
-    ws1, LimitClause, OffsetClause, OneOrMore, OrderByClause, Parser, QualifiedMeasurementName,
-    WhereClause,
+    ws1, LimitClause, MeasurementName, OffsetClause, OneOrMore, OrderByClause, Parser,
+    QualifiedMeasurementName, WhereClause,
 };
 use crate::expression::arithmetic::Expr::Wildcard;
 use crate::expression::arithmetic::{
@@ -34,6 +34,12 @@ pub struct SelectStatement {
     /// Expressions returned by the selection.
     pub fields: FieldList,
 
+    /// The measurement the results of the selection are written to, specified as an
+    /// [`INTO` clause][into_clause].
+    ///
+    /// [into_clause]: https://docs.influxdata.com/influxdb/v1.8/query_language/explore-data/#the-into-clause
+    pub into: Option<IntoClause>,
+
     /// A list of measurements or subqueries used as the source data for the selection.
     pub from: FromMeasurementClause,
 
@@ -44,7 +50,7 @@ pub struct SelectStatement {
     pub group_by: Option<GroupByClause>,
 
     /// The [fill clause] specifies the fill behaviour for the selection. If the value is [`None`],
-    /// it is the same behavior as `fill(none)`.
+    /// it is the same behavior as `fill(null)`.
     ///
     /// [fill]: https://docs.influxdata.com/influxdb/v1.8/query_language/explore-data/#group-by-time-intervals-and-fill
     pub fill: Option<FillClause>,
@@ -72,7 +78,13 @@ pub struct SelectStatement {
 
 impl Display for SelectStatement {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(f, "SELECT {} {}", self.fields, self.from)?;
+        write!(f, "SELECT {}", self.fields)?;
+
+        if let Some(into_clause) = &self.into {
+            write!(f, " {}", into_clause)?;
+        }
+
+        write!(f, " {}", self.from)?;
 
         if let Some(where_clause) = &self.condition {
             write!(f, " {}", where_clause)?;
@@ -121,6 +133,7 @@ pub(crate) fn select_statement(i: &str) -> ParseResult<&str, SelectStatement> {
             _, // SELECT
             _, // whitespace
             fields,
+            into,
             from,
             condition,
             group_by,
@@ -136,6 +149,7 @@ pub(crate) fn select_statement(i: &str) -> ParseResult<&str, SelectStatement> {
         keyword("SELECT"),
         ws0,
         field_list,
+        opt(preceded(ws0, into_clause)),
         preceded(ws0, from_clause),
         opt(preceded(ws0, where_clause)),
         opt(preceded(ws0, group_by_clause)),
@@ -152,6 +166,7 @@ pub(crate) fn select_statement(i: &str) -> ParseResult<&str, SelectStatement> {
         remaining,
         SelectStatement {
             fields,
+            into,
             from,
             condition,
             group_by,
@@ -166,6 +181,43 @@ pub(crate) fn select_statement(i: &str) -> ParseResult<&str, SelectStatement> {
     ))
 }
 
+/// Represents the target measurement of an `INTO` clause.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntoClause(pub(crate) QualifiedMeasurementName);
+
+impl_tuple_clause!(IntoClause, QualifiedMeasurementName);
+
+impl Display for IntoClause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "INTO {}", self.0)
+    }
+}
+
+/// Parse an `INTO` clause.
+///
+/// Unlike the `FROM` clause, the target of an `INTO` clause must be a single measurement name
+/// rather than a regular expression.
+///
+/// ```text
+/// into_clause ::= "INTO" qualified_measurement_name
+/// ```
+fn into_clause(i: &str) -> ParseResult<&str, IntoClause> {
+    preceded(
+        pair(keyword("INTO"), ws1),
+        expect(
+            "invalid INTO clause, expected measurement name",
+            map(
+                verify(
+                    "invalid INTO clause, regular expressions are not supported",
+                    qualified_measurement_name,
+                    |n: &QualifiedMeasurementName| matches!(n.name, MeasurementName::Name(_)),
+                ),
+                IntoClause,
+            ),
+        ),
+    )(i)
+}
+
 /// Represents a single measurement selection for a `FROM` clause.
 #[derive(Clone, Debug, PartialEq)]
 pub enum MeasurementSelection {
@@ -354,7 +406,10 @@ fn time_call_expression(i: &str) -> ParseResult<&str, Dimension> {
                     ),
                     opt(preceded(
                         preceded(ws0, char(',')),
-                        preceded(ws0, arithmetic::<TimeCallOffsetArgument>),
+                        expect(
+                            "invalid TIME call, expected a duration, string or now() for the offset",
+                            preceded(ws0, arithmetic::<TimeCallOffsetArgument>),
+                        ),
                     )),
                 ),
                 expect("invalid TIME call, expected ')'", preceded(ws0, char(')'))),
@@ -562,7 +617,10 @@ fn fill_clause(i: &str) -> ParseResult<&str, FillClause> {
                     )),
                 ),
             ),
-            preceded(ws0, char(')')),
+            expect(
+                "invalid FILL clause, expected ')'",
+                preceded(ws0, char(')')),
+            ),
         ),
     )(i)
 }
@@ -680,6 +738,15 @@ mod test {
             r#"SELECT sum(value) FROM foo GROUP BY TIME(5m), host"#
         );
 
+        let (_, got) = select_statement(
+            "SELECT mean(value) INTO telegraf.autogen.cpu_5m FROM cpu GROUP BY time(5m), *",
+        )
+        .unwrap();
+        assert_eq!(
+            format!("{}", got),
+            r#"SELECT mean(value) INTO telegraf.autogen.cpu_5m FROM cpu GROUP BY TIME(5m), *"#
+        );
+
         // Parses TIME() with expressions
         let (_, got) =
             select_statement("SELECT sum(value) FROM foo GROUP BY time(5m * 10), host").unwrap();
@@ -1104,6 +1171,11 @@ mod test {
         let (got, _) = time_call_expression("TIME(5m, now())").unwrap();
         assert_eq!(got, "");
 
+        // Negative offsets shift the window boundaries backwards
+        let (got, got_dim) = time_call_expression("TIME(1h, -15m)").unwrap();
+        assert_eq!(got, "");
+        assert_eq!(got_dim.to_string(), "TIME(1h, -15m)");
+
         // Strings are later evaluated to be datetime-like:
         // https://github.com/influxdata/influxql/blob/1ba470371ec093d57a726b143fe6ccbacf1b452b/ast.go#L3660-L3676
         let (got, _) = time_call_expression("TIME(5m, 'some string')").unwrap();
@@ -1123,11 +1195,14 @@ mod test {
             "invalid TIME call, expected ')'"
         );
 
-        // The offset argument parser does not recognise the 3, therefore it results
-        // in attempting to parse a `)`, and fails.
+        // The offset argument must be a duration, datetime-like string or `now()`.
         assert_expect_error!(
             time_call_expression("TIME(5m, 3)"),
-            "invalid TIME call, expected ')'"
+            "invalid TIME call, expected a duration, string or now() for the offset"
+        );
+        assert_expect_error!(
+            time_call_expression("TIME(5m, )"),
+            "invalid TIME call, expected a duration, string or now() for the offset"
         );
     }
 
@@ -1161,6 +1236,49 @@ mod test {
             fill_clause("FILL(foo)"),
             "invalid FILL option, expected NULL, NONE, PREVIOUS, LINEAR, or a number"
         );
+        assert_expect_error!(
+            fill_clause("FILL(linear"),
+            "invalid FILL clause, expected ')'"
+        );
+    }
+
+    #[test]
+    fn test_into_clause() {
+        let (_, got) = into_clause("INTO cpu_5m").unwrap();
+        assert_eq!(
+            got,
+            IntoClause::new(QualifiedMeasurementName::new("cpu_5m".into()))
+        );
+
+        let (_, got) = into_clause("INTO telegraf.autogen.cpu_5m").unwrap();
+        assert_eq!(
+            got,
+            IntoClause::new(QualifiedMeasurementName::new_db_rp(
+                "cpu_5m".into(),
+                "telegraf".into(),
+                "autogen".into()
+            ))
+        );
+
+        let (_, got) = into_clause("INTO telegraf..cpu_5m").unwrap();
+        assert_eq!(
+            got,
+            IntoClause::new(QualifiedMeasurementName::new_db(
+                "cpu_5m".into(),
+                "telegraf".into()
+            ))
+        );
+
+        // Fallible cases
+
+        assert_expect_error!(
+            into_clause("INTO /cpu/"),
+            "invalid INTO clause, regular expressions are not supported"
+        );
+        assert_expect_error!(
+            into_clause("INTO 'cpu'"),
+            "invalid INTO clause, expected measurement name"
+        );
     }
 
     #[test]
@@ -151,7 +151,7 @@ impl Display for WithKeyClause {
             Self::Eq(v) => write!(f, "= {}", v),
             Self::NotEq(v) => write!(f, "!= {}", v),
             Self::EqRegex(v) => write!(f, "=~ {}", v),
-            Self::NotEqRegex(v) => write!(f, "=! {}", v),
+            Self::NotEqRegex(v) => write!(f, "!~ {}", v),
             Self::In(list) => write!(f, "IN ({})", list),
         }
     }
@@ -193,7 +193,7 @@ fn with_key_clause(i: &str) -> ParseResult<&str, WithKeyClause> {
                     preceded(
                         delimited(ws0, tag("!~"), ws0),
                         expect(
-                            "invalid WITH KEY clause, expected regular expression following =!",
+                            "invalid WITH KEY clause, expected regular expression following !~",
                             regex,
                         ),
                     ),
@@ -304,6 +304,9 @@ mod test {
         let (_, got) = show_tag_values("VALUES WITH KEY IN( foo )").unwrap();
         assert_eq!(format!("{}", got), "SHOW TAG VALUES WITH KEY IN (foo)");
 
+        let (_, got) = show_tag_values("VALUES WITH KEY !~ /foo/").unwrap();
+        assert_eq!(format!("{}", got), "SHOW TAG VALUES WITH KEY !~ /foo/");
+
         // Fallible cases are tested by the various combinator functions
     }
 
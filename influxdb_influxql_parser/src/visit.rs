@@ -32,7 +32,7 @@ use crate::explain::ExplainStatement;
 use crate::expression::arithmetic::Expr;
 use crate::expression::conditional::ConditionalExpression;
 use crate::select::{
-    Dimension, Field, FieldList, FillClause, FromMeasurementClause, GroupByClause,
+    Dimension, Field, FieldList, FillClause, FromMeasurementClause, GroupByClause, IntoClause,
     MeasurementSelection, SLimitClause, SOffsetClause, SelectStatement, TimeZoneClause,
 };
 use crate::show::{OnClause, ShowDatabasesStatement};
@@ -377,6 +377,16 @@ pub trait Visitor: Sized {
         Ok(self)
     }
 
+    /// Invoked before any children of the `INTO` clause are visited.
+    fn pre_visit_into_clause(self, _n: &IntoClause) -> VisitorResult<Recursion<Self>> {
+        Ok(Continue(self))
+    }
+
+    /// Invoked after all children of the `INTO` clause are visited.
+    fn post_visit_into_clause(self, _n: &IntoClause) -> VisitorResult<Self> {
+        Ok(self)
+    }
+
     /// Invoked before any children of the `FILL` clause are visited.
     fn pre_visit_fill_clause(self, _n: &FillClause) -> VisitorResult<Recursion<Self>> {
         Ok(Continue(self))
@@ -627,6 +637,12 @@ impl Visitable for SelectStatement {
 
         let visitor = self.fields.accept(visitor)?;
 
+        let visitor = if let Some(into_clause) = &self.into {
+            into_clause.accept(visitor)
+        } else {
+            Ok(visitor)
+        }?;
+
         let visitor = self.from.accept(visitor)?;
 
         let visitor = if let Some(condition) = &self.condition {
@@ -687,6 +703,19 @@ impl Visitable for SelectStatement {
     }
 }
 
+impl Visitable for IntoClause {
+    fn accept<V: Visitor>(&self, visitor: V) -> VisitorResult<V> {
+        let visitor = match visitor.pre_visit_into_clause(self)? {
+            Continue(visitor) => visitor,
+            Stop(visitor) => return Ok(visitor),
+        };
+
+        let visitor = self.0.accept(visitor)?;
+
+        visitor.post_visit_into_clause(self)
+    }
+}
+
 impl Visitable for TimeZoneClause {
     fn accept<V: Visitor>(&self, visitor: V) -> VisitorResult<V> {
         let visitor = match visitor.pre_visit_timezone_clause(self)? {
@@ -1178,7 +1207,7 @@ mod test {
     use crate::expression::arithmetic::Expr;
     use crate::expression::conditional::ConditionalExpression;
     use crate::select::{
-        Dimension, Field, FieldList, FillClause, FromMeasurementClause, GroupByClause,
+        Dimension, Field, FieldList, FillClause, FromMeasurementClause, GroupByClause, IntoClause,
         MeasurementSelection, SLimitClause, SOffsetClause, SelectStatement, TimeZoneClause,
     };
     use crate::show::{OnClause, ShowDatabasesStatement};
@@ -1527,6 +1556,14 @@ mod test {
             Ok(self.push_post("soffset_clause", n))
         }
 
+        fn pre_visit_into_clause(self, n: &IntoClause) -> VisitorResult<Recursion<Self>> {
+            Ok(Continue(self.push_pre("into_clause", n)))
+        }
+
+        fn post_visit_into_clause(self, n: &IntoClause) -> VisitorResult<Self> {
+            Ok(self.push_post("into_clause", n))
+        }
+
         fn pre_visit_timezone_clause(self, n: &TimeZoneClause) -> VisitorResult<Recursion<Self>> {
             Ok(Continue(self.push_pre("timezone_clause", n)))
         }
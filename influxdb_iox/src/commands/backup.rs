@@ -0,0 +1,273 @@
+//! This module implements the `backup` CLI command
+
+use clap_blocks::{
+    catalog_dsn::CatalogDsnConfig,
+    object_store::{make_object_store, ObjectStoreConfig},
+};
+use data_types::{ShardId, ShardIndex};
+use generated_types::influxdata::iox::backup::v1 as proto;
+use iox_catalog::interface::Catalog;
+use object_store::{local::LocalFileSystem, path::Path, DynObjectStore};
+use parquet_file::ParquetFilePath;
+use prost::Message;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// The name of the object holding the catalog rows of a backup.
+const CATALOG_OBJECT: &str = "namespace.pb";
+
+/// The directory holding the parquet files of a backup.
+const PARQUET_DIR: &str = "parquet";
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Catalog DSN error: {0}")]
+    CatalogDsn(#[from] clap_blocks::catalog_dsn::Error),
+
+    #[error("Cannot parse object store config: {0}")]
+    ObjectStoreParsing(#[from] clap_blocks::object_store::ParseError),
+
+    #[error("Catalog error: {0}")]
+    Catalog(#[from] iox_catalog::interface::Error),
+
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    #[error("Cannot create backup directory: {0}")]
+    BackupDir(#[from] std::io::Error),
+
+    #[error("Namespace {0} not found")]
+    NamespaceNotFound(String),
+
+    #[error("Shard {0} not found")]
+    ShardNotFound(ShardId),
+
+    #[error("Cannot encode backup: {0}")]
+    Encode(#[from] prost::EncodeError),
+}
+
+/// Back up catalog rows and parquet files
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+/// Back up the catalog rows and parquet files of a namespace to a local
+/// directory.
+///
+/// The backup is not a consistent snapshot: compaction and ingest should be
+/// paused for the namespace while the backup is taken.
+#[derive(Debug, clap::Parser)]
+struct Namespace {
+    #[clap(flatten)]
+    catalog_dsn: CatalogDsnConfig,
+
+    #[clap(flatten)]
+    object_store: ObjectStoreConfig,
+
+    /// The namespace to back up
+    #[clap(action)]
+    namespace: String,
+
+    /// The directory to write the backup to
+    #[clap(long, action)]
+    backup_dir: PathBuf,
+}
+
+/// All possible subcommands for backup
+#[derive(Debug, clap::Parser)]
+enum Command {
+    /// Back up a namespace
+    Namespace(Box<Namespace>),
+}
+
+pub async fn command(config: Config) -> Result<(), Error> {
+    match config.command {
+        Command::Namespace(command) => {
+            let metrics = Arc::new(metric::Registry::new());
+            let catalog = command.catalog_dsn.get_catalog("cli", metrics).await?;
+            let object_store = make_object_store(&command.object_store)?;
+            let backup_store = backup_dir_store(&command.backup_dir)?;
+
+            let backup =
+                backup_namespace(&catalog, &object_store, &command.namespace, &backup_store)
+                    .await?;
+
+            println!(
+                "backed up namespace {} with {} tables, {} partitions, {} parquet files and {} \
+                tombstones to {}",
+                backup.name,
+                backup.tables.len(),
+                backup.partitions.len(),
+                backup.parquet_files.len(),
+                backup.tombstones.len(),
+                command.backup_dir.display(),
+            );
+
+            Ok(())
+        }
+    }
+}
+
+/// Returns an object store writing to and reading from `dir`, creating `dir`
+/// if it does not exist.
+///
+/// Backups are never written to the object store of the backed up cluster, as
+/// its garbage collector deletes all objects not referenced by the catalog.
+pub(crate) fn backup_dir_store(dir: &std::path::Path) -> Result<Arc<DynObjectStore>, Error> {
+    std::fs::create_dir_all(dir)?;
+    Ok(Arc::new(LocalFileSystem::new_with_prefix(dir)?))
+}
+
+/// Write the catalog rows of `namespace` and copies of its parquet files in
+/// `object_store` to `backup_store`, returning the written catalog rows.
+///
+/// Parquet files flagged for deletion are not backed up. The catalog rows are
+/// written last, so a backup missing its catalog object is incomplete.
+pub(crate) async fn backup_namespace(
+    catalog: &Arc<dyn Catalog>,
+    object_store: &Arc<DynObjectStore>,
+    namespace: &str,
+    backup_store: &Arc<DynObjectStore>,
+) -> Result<proto::NamespaceBackup, Error> {
+    let mut repos = catalog.repositories().await;
+
+    let namespace = repos
+        .namespaces()
+        .get_by_name(namespace)
+        .await?
+        .ok_or_else(|| Error::NamespaceNotFound(namespace.to_string()))?;
+
+    let shard_indexes: HashMap<ShardId, ShardIndex> = repos
+        .shards()
+        .list()
+        .await?
+        .into_iter()
+        .map(|s| (s.id, s.shard_index))
+        .collect();
+    let shard_index = |shard_id: ShardId| {
+        shard_indexes
+            .get(&shard_id)
+            .map(|i| i.get())
+            .ok_or(Error::ShardNotFound(shard_id))
+    };
+
+    let mut columns: HashMap<_, Vec<_>> = HashMap::new();
+    for c in repos.columns().list_by_namespace_id(namespace.id).await? {
+        columns.entry(c.table_id).or_default().push(proto::Column {
+            id: c.id.get(),
+            name: c.name,
+            column_type: c.column_type as i32,
+        });
+    }
+
+    let tables = repos
+        .tables()
+        .list_by_namespace_id(namespace.id)
+        .await?
+        .into_iter()
+        .map(|t| proto::Table {
+            id: t.id.get(),
+            name: t.name,
+            columns: columns.remove(&t.id).unwrap_or_default(),
+        })
+        .collect();
+
+    let partitions = repos
+        .partitions()
+        .list_by_namespace(namespace.id)
+        .await?
+        .into_iter()
+        .map(|p| {
+            Ok(proto::Partition {
+                id: p.id.get(),
+                table_id: p.table_id.get(),
+                shard_index: shard_index(p.shard_id)?,
+                key: p.partition_key.to_string(),
+                sort_key: p.sort_key,
+                persisted_sequence_number: p.persisted_sequence_number.map(|s| s.get()),
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+
+    let parquet_files = repos
+        .parquet_files()
+        .list_by_namespace_not_to_delete(namespace.id)
+        .await?;
+
+    let tombstones = repos
+        .tombstones()
+        .list_by_namespace(namespace.id)
+        .await?
+        .into_iter()
+        .map(|t| {
+            Ok(proto::Tombstone {
+                table_id: t.table_id.get(),
+                shard_index: shard_index(t.shard_id)?,
+                sequence_number: t.sequence_number.get(),
+                min_time: t.min_time.get(),
+                max_time: t.max_time.get(),
+                serialized_predicate: t.serialized_predicate,
+            })
+        })
+        .collect::<Result<_, Error>>()?;
+
+    drop(repos);
+
+    let mut backup_files = Vec::with_capacity(parquet_files.len());
+    for f in parquet_files {
+        let bytes = object_store
+            .get(&ParquetFilePath::from(&f).object_store_path())
+            .await?
+            .bytes()
+            .await?;
+        backup_store
+            .put(&parquet_path(f.object_store_id), bytes)
+            .await?;
+
+        backup_files.push(proto::ParquetFile {
+            table_id: f.table_id.get(),
+            partition_id: f.partition_id.get(),
+            shard_index: shard_index(f.shard_id)?,
+            object_store_id: f.object_store_id.to_string(),
+            max_sequence_number: f.max_sequence_number.get(),
+            min_time: f.min_time.get(),
+            max_time: f.max_time.get(),
+            file_size_bytes: f.file_size_bytes,
+            row_count: f.row_count,
+            compaction_level: f.compaction_level as i32,
+            created_at: f.created_at.get(),
+            column_set: f.column_set.iter().map(|c| c.get()).collect(),
+        });
+    }
+
+    let backup = proto::NamespaceBackup {
+        name: namespace.name,
+        retention_period_ns: namespace.retention_period_ns,
+        max_tables: namespace.max_tables,
+        max_columns_per_table: namespace.max_columns_per_table,
+        tables,
+        partitions,
+        parquet_files: backup_files,
+        tombstones,
+    };
+
+    let mut buf = Vec::with_capacity(backup.encoded_len());
+    backup.encode(&mut buf)?;
+    backup_store.put(&catalog_path(), buf.into()).await?;
+
+    Ok(backup)
+}
+
+/// The location of the catalog rows in a backup.
+pub(crate) fn catalog_path() -> Path {
+    Path::from(CATALOG_OBJECT)
+}
+
+/// The location of the copy of the parquet file `object_store_id` in a backup.
+pub(crate) fn parquet_path(object_store_id: Uuid) -> Path {
+    Path::from_iter([PARQUET_DIR, &format!("{}.parquet", object_store_id)])
+}
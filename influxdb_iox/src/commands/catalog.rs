@@ -5,6 +5,8 @@ use std::sync::Arc;
 use clap_blocks::catalog_dsn::CatalogDsnConfig;
 use thiserror::Error;
 
+mod doctor;
+mod token;
 mod topic;
 
 #[allow(clippy::enum_variant_names)]
@@ -13,11 +15,23 @@ pub enum Error {
     #[error("Error in topic subcommand: {0}")]
     Topic(#[from] topic::Error),
 
+    #[error("Error in token subcommand: {0}")]
+    Token(#[from] token::Error),
+
+    #[error("Error in doctor subcommand: {0}")]
+    Doctor(#[from] doctor::Error),
+
     #[error("Catalog error: {0}")]
     Catalog(#[from] iox_catalog::interface::Error),
 
     #[error("Catalog DSN error: {0}")]
     CatalogDsn(#[from] clap_blocks::catalog_dsn::Error),
+
+    #[error("Catalog schema is dirty: migration {0} failed part way through")]
+    DirtySchema(i64),
+
+    #[error("Catalog schema has {0} pending migration(s)")]
+    PendingMigrations(usize),
 }
 
 /// Various commands for catalog manipulation
@@ -34,14 +48,42 @@ struct Setup {
     catalog_dsn: CatalogDsnConfig,
 }
 
+/// Apply pending catalog schema migrations
+#[derive(Debug, clap::Parser)]
+struct Migrate {
+    #[clap(flatten)]
+    catalog_dsn: CatalogDsnConfig,
+
+    /// Print the migrations that would be applied without applying them.
+    #[clap(long, action)]
+    dry_run: bool,
+
+    /// Only apply migrations up to and including this version.
+    #[clap(long, action)]
+    target_version: Option<i64>,
+
+    /// Do not migrate; exit with an error if the schema is dirty or has pending migrations.
+    #[clap(long, action, conflicts_with_all = &["dry_run", "target_version"])]
+    dirty_check: bool,
+}
+
 /// All possible subcommands for catalog
 #[derive(Debug, clap::Parser)]
 enum Command {
     /// Run database migrations
     Setup(Setup),
 
+    /// Apply pending catalog schema migrations
+    Migrate(Migrate),
+
     /// Manage topic
     Topic(topic::Config),
+
+    /// Manage API tokens directly in the catalog
+    Token(token::Config),
+
+    /// Check the catalog for inconsistencies, optionally repairing them
+    Doctor(doctor::Config),
 }
 
 pub async fn command(config: Config) -> Result<(), Error> {
@@ -52,9 +94,42 @@ pub async fn command(config: Config) -> Result<(), Error> {
             catalog.setup().await?;
             println!("OK");
         }
+        Command::Migrate(command) => {
+            let metrics = Arc::new(metric::Registry::new());
+            let catalog = command.catalog_dsn.get_catalog("cli", metrics).await?;
+            let status = catalog.migration_status().await?;
+
+            if command.dirty_check {
+                if let Some(version) = status.dirty {
+                    return Err(Error::DirtySchema(version));
+                }
+                if !status.pending.is_empty() {
+                    return Err(Error::PendingMigrations(status.pending.len()));
+                }
+                println!("OK");
+            } else if command.dry_run {
+                let pending = status.pending.iter().filter(|m| {
+                    command
+                        .target_version
+                        .map_or(true, |target| m.version <= target)
+                });
+                for migration in pending {
+                    println!("{} {}", migration.version, migration.description);
+                }
+            } else {
+                catalog.migrate(command.target_version).await?;
+                println!("OK");
+            }
+        }
         Command::Topic(config) => {
             topic::command(config).await?;
         }
+        Command::Token(config) => {
+            token::command(config).await?;
+        }
+        Command::Doctor(config) => {
+            doctor::command(config).await?;
+        }
     }
 
     Ok(())
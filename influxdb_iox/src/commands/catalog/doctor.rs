@@ -0,0 +1,71 @@
+//! This module implements the `catalog doctor` CLI subcommand
+
+use std::{fs::File, path::PathBuf, sync::Arc};
+
+use thiserror::Error;
+
+use clap_blocks::catalog_dsn::CatalogDsnConfig;
+use iox_catalog::doctor;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Error checking catalog: {0}")]
+    CheckCatalogError(#[from] iox_catalog::interface::Error),
+
+    #[error("Catalog DSN error: {0}")]
+    CatalogDsn(#[from] clap_blocks::catalog_dsn::Error),
+
+    #[error("Cannot write report: {0}")]
+    WriteReportError(#[from] std::io::Error),
+
+    #[error("Cannot serialize report: {0}")]
+    SerializeReportError(#[from] serde_json::Error),
+
+    #[error("Catalog has {0} unrepaired issue(s)")]
+    Unhealthy(usize),
+}
+
+/// Check the catalog for records that violate its invariants, such as parquet files of missing
+/// partitions or shards of missing topics
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(flatten)]
+    catalog_dsn: CatalogDsnConfig,
+
+    /// Repair the issues that can be repaired: parquet files of missing partitions are flagged
+    /// for deletion, and tombstones of missing tables or shards are removed.
+    #[clap(long, action)]
+    repair: bool,
+
+    /// Write a JSON report of the checked records and the issues found to this file.
+    #[clap(long, action)]
+    report: Option<PathBuf>,
+}
+
+pub async fn command(config: Config) -> Result<(), Error> {
+    let metrics = Arc::new(metric::Registry::new());
+    let catalog = config.catalog_dsn.get_catalog("cli", metrics).await?;
+
+    let mut report = doctor::check(catalog.as_ref()).await?;
+    if config.repair {
+        doctor::repair(catalog.as_ref(), &mut report).await?;
+    }
+
+    for issue in &report.repaired {
+        println!("repaired: {}", issue);
+    }
+    for issue in &report.issues {
+        println!("{}", issue);
+    }
+
+    if let Some(path) = config.report {
+        serde_json::to_writer_pretty(File::create(path)?, &report)?;
+    }
+
+    if !report.is_healthy() {
+        return Err(Error::Unhealthy(report.issues.len()));
+    }
+    println!("OK");
+    Ok(())
+}
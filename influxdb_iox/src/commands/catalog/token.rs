@@ -0,0 +1,97 @@
+//! This module implements the `catalog token` CLI subcommand
+
+use std::sync::Arc;
+
+use data_types::{ApiTokenPermission, Timestamp};
+use iox_catalog::authz::{generate_token, hash_token};
+use thiserror::Error;
+
+use clap_blocks::catalog_dsn::CatalogDsnConfig;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Error updating catalog: {0}")]
+    UpdateCatalogError(#[from] iox_catalog::interface::Error),
+
+    #[error("Catalog DSN error: {0}")]
+    CatalogDsn(#[from] clap_blocks::catalog_dsn::Error),
+
+    #[error("Namespace {0} not found")]
+    NamespaceNotFound(String),
+}
+
+/// Manage API tokens directly in the catalog
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+/// Create an API token and print its secret, which cannot be retrieved again.
+///
+/// This writes to the catalog directly, e.g. to create the first admin token of a cluster with
+/// authorization enabled. Use `influxdb_iox token create` otherwise.
+#[derive(Debug, clap::Parser)]
+struct Create {
+    #[clap(flatten)]
+    catalog_dsn: CatalogDsnConfig,
+
+    /// The actions the token allows: read, write (includes read) or admin (includes write and
+    /// the management of tokens)
+    #[clap(action, long)]
+    permission: ApiTokenPermission,
+
+    /// The namespace the token is scoped to. Tokens without a namespace are valid for all
+    /// namespaces.
+    #[clap(action, long)]
+    namespace: Option<String>,
+
+    /// Human-readable description of the token, e.g. its owner
+    #[clap(action, long, default_value = "")]
+    description: String,
+}
+
+/// All possible subcommands for token
+#[derive(Debug, clap::Parser)]
+enum Command {
+    Create(Create),
+}
+
+pub async fn command(config: Config) -> Result<(), Error> {
+    match config.command {
+        Command::Create(create) => {
+            let metrics = Arc::new(metric::Registry::new());
+            let catalog = create.catalog_dsn.get_catalog("cli", metrics).await?;
+            let mut repos = catalog.repositories().await;
+
+            let namespace_id = match &create.namespace {
+                Some(name) => Some(
+                    repos
+                        .namespaces()
+                        .get_by_name(name)
+                        .await?
+                        .ok_or_else(|| Error::NamespaceNotFound(name.clone()))?
+                        .id,
+                ),
+                None => None,
+            };
+
+            let token = generate_token();
+            let created_at = Timestamp::from(catalog.time_provider().now());
+            let api_token = repos
+                .api_tokens()
+                .create(
+                    &hash_token(&token),
+                    namespace_id,
+                    create.permission,
+                    &create.description,
+                    created_at,
+                )
+                .await?;
+            eprintln!("created API token {}", api_token.id);
+            println!("{token}");
+            Ok(())
+        }
+    }
+}
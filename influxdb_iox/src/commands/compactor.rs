@@ -86,7 +86,8 @@ pub async fn command(config: Config) -> Result<()> {
             let parquet_store = ParquetStorage::new(object_store, StorageId::from("iox"));
 
             let exec = Arc::new(Executor::new_with_config(ExecutorConfig {
-                num_threads: query_exec_thread_count,
+                num_query_threads: query_exec_thread_count,
+                num_reorg_threads: query_exec_thread_count,
                 target_query_partitions: query_exec_thread_count,
                 object_stores: HashMap::from([(
                     parquet_store.id(),
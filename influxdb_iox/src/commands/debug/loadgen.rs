@@ -0,0 +1,183 @@
+//! This module implements the `loadgen` CLI command
+
+use futures::{stream, StreamExt, TryStreamExt};
+use influxdb_iox_client::{connection::Connection, write};
+use std::{
+    fmt::Write,
+    num::NonZeroUsize,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Client error: {0}")]
+    ClientError(#[from] influxdb_iox_client::error::Error),
+}
+
+/// Write synthetic line protocol to a namespace and report the achieved
+/// throughput and request latencies.
+///
+/// The generated data only depends on the options, so runs with the same
+/// options write the same data and can be compared with each other.
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    /// The namespace into which to write
+    #[clap(action)]
+    namespace: String,
+
+    /// Number of measurements to write to
+    #[clap(action, long, default_value = "1")]
+    measurements: NonZeroUsize,
+
+    /// Number of tag keys of each measurement
+    #[clap(action, long, default_value = "2")]
+    tags: usize,
+
+    /// Number of distinct values of each tag key
+    #[clap(action, long, default_value = "10")]
+    tag_values: NonZeroUsize,
+
+    /// Number of fields of each measurement
+    #[clap(action, long, default_value = "3")]
+    fields: NonZeroUsize,
+
+    /// Number of lines of line protocol per write request
+    #[clap(action, long, default_value = "1000")]
+    batch_size: NonZeroUsize,
+
+    /// Total number of write requests to make
+    #[clap(action, long, default_value = "100")]
+    batches: usize,
+
+    /// Number of write requests in flight at a time
+    #[clap(action, long, short = 'c', default_value = "10")]
+    concurrency: NonZeroUsize,
+}
+
+pub async fn command(connection: Connection, config: Config) -> Result<(), Error> {
+    // Send every batch as a single request, so that the latencies are per batch
+    let client = write::Client::new(connection).with_max_request_payload_size_bytes(None);
+    let namespace = config.namespace.as_str();
+
+    let start = Instant::now();
+    let mut results = stream::iter(0..config.batches)
+        .map(|batch| {
+            let mut client = client.clone();
+            let lp = generate_batch(&config, batch);
+            async move {
+                let request_start = Instant::now();
+                let bytes = client.write_lp(namespace, lp).await?;
+                Ok::<_, Error>((bytes, request_start.elapsed()))
+            }
+        })
+        .buffer_unordered(config.concurrency.get())
+        .try_collect::<Vec<_>>()
+        .await?;
+    let elapsed = start.elapsed();
+
+    let total_bytes: usize = results.iter().map(|(bytes, _)| bytes).sum();
+    let total_lines = config.batches * config.batch_size.get();
+    let secs = elapsed.as_secs_f64();
+    println!(
+        "Wrote {total_lines} lines ({total_bytes} bytes) in {} requests in {elapsed:?}",
+        config.batches
+    );
+    println!(
+        "Throughput: {:.0} lines/sec, {:.2} MB/sec",
+        total_lines as f64 / secs,
+        total_bytes as f64 / (1024.0 * 1024.0) / secs
+    );
+
+    results.sort_unstable_by_key(|(_, latency)| *latency);
+    let latencies: Vec<_> = results.into_iter().map(|(_, latency)| latency).collect();
+    if let Some(max) = latencies.last() {
+        println!(
+            "Latency: p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+            percentile(&latencies, 50),
+            percentile(&latencies, 90),
+            percentile(&latencies, 99),
+            max
+        );
+    }
+
+    Ok(())
+}
+
+/// Generate the line protocol of batch number `batch`.
+///
+/// Consecutive lines cycle through all series, i.e. combinations of
+/// measurement and tag values, and have increasing timestamps, so that no
+/// line overwrites another.
+fn generate_batch(config: &Config, batch: usize) -> String {
+    let batch_size = config.batch_size.get();
+    let mut lp = String::new();
+
+    for line in batch * batch_size..(batch + 1) * batch_size {
+        let mut series = line;
+        write!(lp, "m{}", series % config.measurements).unwrap();
+        series /= config.measurements.get();
+
+        for tag in 0..config.tags {
+            write!(lp, ",tag{tag}=value{}", series % config.tag_values).unwrap();
+            series /= config.tag_values.get();
+        }
+
+        for field in 0..config.fields.get() {
+            let separator = if field == 0 { ' ' } else { ',' };
+            let value = (line * (field + 1)) % 1000;
+            write!(lp, "{separator}field{field}={value}").unwrap();
+        }
+
+        writeln!(lp, " {line}").unwrap();
+    }
+
+    lp
+}
+
+/// Returns the `p`th percentile of the sorted, non-empty `latencies`.
+pub(crate) fn percentile(latencies: &[Duration], p: usize) -> Duration {
+    let index = (latencies.len() * p / 100).min(latencies.len() - 1);
+    latencies[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_generate_batch() {
+        let config = Config::try_parse_from([
+            "loadgen",
+            "ns",
+            "--measurements",
+            "2",
+            "--tags",
+            "1",
+            "--tag-values",
+            "2",
+            "--fields",
+            "2",
+            "--batch-size",
+            "3",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            generate_batch(&config, 1),
+            "m1,tag0=value1 field0=3,field1=6 3\n\
+             m0,tag0=value0 field0=4,field1=8 4\n\
+             m1,tag0=value0 field0=5,field1=10 5\n"
+        );
+    }
+
+    #[test]
+    fn test_percentile() {
+        let latencies: Vec<_> = (1..=10).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&latencies, 50), Duration::from_millis(6));
+        assert_eq!(percentile(&latencies, 99), Duration::from_millis(10));
+        assert_eq!(percentile(&latencies[..1], 90), Duration::from_millis(1));
+    }
+}
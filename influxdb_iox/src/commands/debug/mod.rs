@@ -2,10 +2,14 @@ use futures::Future;
 use influxdb_iox_client::connection::Connection;
 use snafu::prelude::*;
 
+mod loadgen;
 mod parquet_to_lp;
 mod print_cpu;
 mod schema;
 mod skipped_compactions;
+mod verify;
+
+pub(crate) use loadgen::percentile;
 
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -13,6 +17,10 @@ pub enum Error {
     #[snafu(display("Error in schema subcommand: {}", source))]
     Schema { source: schema::Error },
 
+    #[snafu(context(false))]
+    #[snafu(display("Error in loadgen subcommand: {}", source))]
+    Loadgen { source: loadgen::Error },
+
     #[snafu(context(false))]
     #[snafu(display("Error in parquet_to_lp subcommand: {}", source))]
     ParquetToLp { source: parquet_to_lp::Error },
@@ -20,6 +28,10 @@ pub enum Error {
     #[snafu(context(false))]
     #[snafu(display("Error in skipped-compactions subcommand: {}", source))]
     SkippedCompactions { source: skipped_compactions::Error },
+
+    #[snafu(context(false))]
+    #[snafu(display("Error in verify subcommand: {}", source))]
+    Verify { source: verify::Error },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -39,11 +51,17 @@ enum Command {
     /// Interrogate the schema of a namespace
     Schema(schema::Config),
 
+    /// Write synthetic line protocol to benchmark the write path
+    Loadgen(loadgen::Config),
+
     /// Convert IOx Parquet files back into line protocol format
     ParquetToLp(parquet_to_lp::Config),
 
     /// Interrogate skipped compactions
     SkippedCompactions(skipped_compactions::Config),
+
+    /// Compare the data of a namespace on two IOx instances
+    Verify(verify::Config),
 }
 
 pub async fn command<C, CFut>(connection: C, config: Config) -> Result<()>
@@ -57,11 +75,19 @@ where
             let connection = connection().await;
             schema::command(connection, config).await?
         }
+        Command::Loadgen(config) => {
+            let connection = connection().await;
+            loadgen::command(connection, config).await?
+        }
         Command::ParquetToLp(config) => parquet_to_lp::command(config).await?,
         Command::SkippedCompactions(config) => {
             let connection = connection().await;
             skipped_compactions::command(connection, config).await?
         }
+        Command::Verify(config) => {
+            let connection = connection().await;
+            verify::command(connection, config).await?
+        }
     }
 
     Ok(())
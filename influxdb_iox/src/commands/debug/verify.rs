@@ -0,0 +1,269 @@
+//! This module implements the `verify` CLI command
+
+use influxdb_iox_client::{
+    connection::{Builder, Connection},
+    flight::{self, generated_types::ReadInfo},
+    format::QueryOutputFormat,
+    schema::{
+        self,
+        generated_types::{column_schema::ColumnType, TableSchema},
+    },
+};
+use std::{collections::BTreeSet, time::Duration};
+use thiserror::Error;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Error connecting to {host}: {source}")]
+    ConnectionError {
+        host: String,
+        source: influxdb_iox_client::connection::Error,
+    },
+
+    #[error("Client error: {0}")]
+    ClientError(#[from] influxdb_iox_client::error::Error),
+
+    #[error("Error querying: {0}")]
+    Query(#[from] influxdb_iox_client::flight::Error),
+
+    #[error("Error formatting: {0}")]
+    Formatting(#[from] influxdb_iox_client::format::Error),
+
+    #[error("{0} of {1} tables do not match")]
+    Mismatch(usize, usize),
+}
+
+/// Compare the data of a namespace on two IOx instances.
+///
+/// Runs checksum queries against every table of the namespace on the
+/// instance given by `--host` and on `--other-host`, and reports the time
+/// buckets whose results differ. The queries compute, per time bucket, the
+/// number of rows, the number of non-null values, minimum and maximum of each
+/// field, and the sum of each integer field. Float fields are not summed, as
+/// the result depends on the order in which values are added.
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    /// The namespace to compare
+    #[clap(action)]
+    namespace: String,
+
+    /// gRPC address of the IOx instance to compare with
+    #[clap(action, long)]
+    other_host: String,
+
+    /// Compare only these tables, instead of all the tables of the namespace
+    #[clap(action, long = "table")]
+    tables: Vec<String>,
+
+    /// Width of the time buckets to compare
+    #[clap(long, default_value = "1d", value_parser = humantime::parse_duration)]
+    bucket: Duration,
+}
+
+pub async fn command(connection: Connection, config: Config) -> Result<(), Error> {
+    let other_connection =
+        Builder::default()
+            .build(&config.other_host)
+            .await
+            .map_err(|source| Error::ConnectionError {
+                host: config.other_host.clone(),
+                source,
+            })?;
+
+    let schema = schema::Client::new(connection.clone())
+        .get_schema(&config.namespace)
+        .await?;
+    let other_schema = schema::Client::new(other_connection.clone())
+        .get_schema(&config.namespace)
+        .await?;
+
+    let mut client = flight::Client::new(connection);
+    let mut other_client = flight::Client::new(other_connection);
+
+    let tables: Vec<_> = if config.tables.is_empty() {
+        let mut tables: Vec<_> = schema.tables.keys().cloned().collect();
+        tables.sort_unstable();
+        tables
+    } else {
+        config.tables.clone()
+    };
+
+    let mut mismatches = 0;
+    for table in &tables {
+        let table_schema = match (schema.tables.get(table), other_schema.tables.get(table)) {
+            (Some(table_schema), Some(_)) => table_schema,
+            (None, _) | (_, None) => {
+                println!("{table}: MISMATCH, table does not exist on both instances");
+                mismatches += 1;
+                continue;
+            }
+        };
+
+        let query = checksum_query(table, table_schema, config.bucket);
+        let rows = run_query(&mut client, &config.namespace, &query).await?;
+        let other_rows = run_query(&mut other_client, &config.namespace, &query).await?;
+
+        let (missing, extra) = diff_rows(&rows, &other_rows);
+        if missing.is_empty() && extra.is_empty() {
+            println!(
+                "{table}: OK ({} time buckets)",
+                rows.len().saturating_sub(1)
+            );
+            continue;
+        }
+
+        mismatches += 1;
+        println!("{table}: MISMATCH");
+        if let Some(header) = rows.first() {
+            println!("  {header}");
+        }
+        for row in missing {
+            println!("- {row}");
+        }
+        for row in extra {
+            println!("+ {row}");
+        }
+    }
+
+    if mismatches > 0 {
+        return Err(Error::Mismatch(mismatches, tables.len()));
+    }
+    Ok(())
+}
+
+/// Returns the query computing the checksums of `table` per time bucket of
+/// width `bucket`.
+fn checksum_query(table: &str, table_schema: &TableSchema, bucket: Duration) -> String {
+    let mut columns: Vec<_> = table_schema.columns.iter().collect();
+    columns.sort_unstable_by_key(|(name, _)| name.as_str());
+
+    let mut aggregates = vec![r#"COUNT(*) AS "count""#.to_string()];
+    for (name, column) in columns {
+        let column_type = column.column_type();
+        if matches!(
+            column_type,
+            ColumnType::Unspecified | ColumnType::Time | ColumnType::Tag
+        ) {
+            continue;
+        }
+
+        let ident = quote_ident(name);
+        let mut push = |function: &str| {
+            let alias = quote_ident(&format!("{}_{name}", function.to_ascii_lowercase()));
+            aggregates.push(format!("{function}({ident}) AS {alias}"));
+        };
+
+        push("COUNT");
+        if matches!(
+            column_type,
+            ColumnType::I64 | ColumnType::U64 | ColumnType::F64
+        ) {
+            push("MIN");
+            push("MAX");
+        }
+        if matches!(column_type, ColumnType::I64 | ColumnType::U64) {
+            push("SUM");
+        }
+    }
+
+    format!(
+        "SELECT date_bin(INTERVAL '{} seconds', time, TIMESTAMP '1970-01-01T00:00:00Z') AS bucket, {} \
+         FROM {} GROUP BY bucket ORDER BY bucket",
+        bucket.as_secs().max(1),
+        aggregates.join(", "),
+        quote_ident(table)
+    )
+}
+
+/// Quote `ident` as a SQL identifier.
+fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+/// Runs `query` and returns the rows of the result as CSV, including the header.
+async fn run_query(
+    client: &mut flight::Client,
+    namespace: &str,
+    query: &str,
+) -> Result<Vec<String>, Error> {
+    let mut query_results = client
+        .perform_query(ReadInfo {
+            namespace_name: namespace.to_string(),
+            sql_query: query.to_string(),
+            max_unpersisted_staleness_ns: None,
+            additional_namespaces: vec![],
+            params: vec![],
+            include_statistics: false,
+            debug_parquet_file_ids: vec![],
+        })
+        .await?;
+
+    let mut batches = vec![];
+    while let Some(data) = query_results.next().await? {
+        batches.push(data);
+    }
+
+    let csv = QueryOutputFormat::Csv.format(&batches)?;
+    Ok(csv.lines().map(ToString::to_string).collect())
+}
+
+/// Returns the rows only in `left` and the rows only in `right`.
+fn diff_rows<'a>(left: &'a [String], right: &'a [String]) -> (Vec<&'a str>, Vec<&'a str>) {
+    let left_set: BTreeSet<_> = left.iter().map(String::as_str).collect();
+    let right_set: BTreeSet<_> = right.iter().map(String::as_str).collect();
+    (
+        left_set.difference(&right_set).copied().collect(),
+        right_set.difference(&left_set).copied().collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use influxdb_iox_client::schema::generated_types::ColumnSchema;
+
+    #[test]
+    fn test_checksum_query() {
+        let columns = [
+            ("time", ColumnType::Time),
+            ("host", ColumnType::Tag),
+            ("usage", ColumnType::F64),
+            ("count", ColumnType::I64),
+            ("ok", ColumnType::Bool),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(id, (name, column_type))| {
+            (
+                name.to_string(),
+                ColumnSchema {
+                    id: id as i64,
+                    column_type: column_type as i32,
+                },
+            )
+        })
+        .collect();
+        let table_schema = TableSchema { id: 1, columns };
+
+        assert_eq!(
+            checksum_query("cpu", &table_schema, Duration::from_secs(3600)),
+            "SELECT date_bin(INTERVAL '3600 seconds', time, TIMESTAMP '1970-01-01T00:00:00Z') AS bucket, \
+             COUNT(*) AS \"count\", \
+             COUNT(\"count\") AS \"count_count\", MIN(\"count\") AS \"min_count\", \
+             MAX(\"count\") AS \"max_count\", SUM(\"count\") AS \"sum_count\", \
+             COUNT(\"ok\") AS \"count_ok\", \
+             COUNT(\"usage\") AS \"count_usage\", MIN(\"usage\") AS \"min_usage\", \
+             MAX(\"usage\") AS \"max_usage\" \
+             FROM \"cpu\" GROUP BY bucket ORDER BY bucket"
+        );
+    }
+
+    #[test]
+    fn test_diff_rows() {
+        let left = ["bucket,count", "1,2", "2,3"].map(String::from);
+        let right = ["bucket,count", "1,2", "2,4", "3,1"].map(String::from);
+        assert_eq!(diff_rows(&left, &right), (vec!["2,3"], vec!["2,4", "3,1"]));
+        assert_eq!(diff_rows(&left, &left), (vec![], vec![]));
+    }
+}
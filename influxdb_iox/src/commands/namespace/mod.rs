@@ -4,7 +4,10 @@ use influxdb_iox_client::{connection::Connection, namespace};
 use thiserror::Error;
 
 mod create;
+mod query_ranges;
 mod retention;
+mod row_ttl;
+mod topic;
 
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error)]
@@ -34,6 +37,15 @@ enum Command {
 
     /// Update retention of an existing namespace
     Retention(retention::Config),
+
+    /// Update the default and maximum query time ranges of an existing namespace
+    QueryRanges(query_ranges::Config),
+
+    /// Update the expiry of the individual rows of an existing namespace
+    RowTtl(row_ttl::Config),
+
+    /// Assign an existing namespace to a write buffer topic
+    Topic(topic::Config),
 }
 
 pub async fn command(connection: Connection, config: Config) -> Result<(), Error> {
@@ -48,6 +60,15 @@ pub async fn command(connection: Connection, config: Config) -> Result<(), Error
         }
         Command::Retention(config) => {
             retention::command(connection, config).await?;
+        }
+        Command::QueryRanges(config) => {
+            query_ranges::command(connection, config).await?;
+        }
+        Command::RowTtl(config) => {
+            row_ttl::command(connection, config).await?;
+        }
+        Command::Topic(config) => {
+            topic::command(connection, config).await?;
         } // Deliberately not adding _ => so the compiler will direct people here to impl new
           // commands
     }
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use influxdb_iox_client::connection::Connection;
+
+/// Update the time ranges the queries of the specified namespace may cover
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    /// The namespace to update the query ranges for
+    #[clap(action)]
+    namespace: String,
+
+    /// Time range queried by queries that do not restrict the time, e.g. "1d". Queries of all
+    /// the data if not set
+    #[clap(long, value_parser = humantime::parse_duration)]
+    default_range: Option<Duration>,
+
+    /// Longest time range a query may cover, e.g. "30d". Queries of any range are allowed if
+    /// not set
+    #[clap(long, value_parser = humantime::parse_duration)]
+    max_range: Option<Duration>,
+}
+
+pub async fn command(
+    connection: Connection,
+    config: Config,
+) -> Result<(), crate::commands::namespace::Error> {
+    let Config {
+        namespace,
+        default_range,
+        max_range,
+    } = config;
+
+    let to_ns = |d: Duration| d.as_nanos().min(i64::MAX as u128) as i64;
+    let mut client = influxdb_iox_client::namespace::Client::new(connection);
+    let namespace = client
+        .update_namespace_query_ranges(&namespace, default_range.map(to_ns), max_range.map(to_ns))
+        .await?;
+    println!("{}", serde_json::to_string_pretty(&namespace)?);
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use influxdb_iox_client::connection::Connection;
+
+/// Update when the individual rows of the specified namespace expire
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    /// The namespace to update the row expiry for
+    #[clap(action)]
+    namespace: String,
+
+    /// Time after which a row expires, counted from its timestamp, e.g. "1h". Rows do not expire
+    /// by their timestamp if not set
+    #[clap(long, value_parser = humantime::parse_duration)]
+    ttl: Option<Duration>,
+
+    /// Integer field holding the time in nanoseconds since the epoch at which each row expires.
+    /// Rows do not expire by a field if not set
+    #[clap(long)]
+    column: Option<String>,
+}
+
+pub async fn command(
+    connection: Connection,
+    config: Config,
+) -> Result<(), crate::commands::namespace::Error> {
+    let Config {
+        namespace,
+        ttl,
+        column,
+    } = config;
+
+    let to_ns = |d: Duration| d.as_nanos().min(i64::MAX as u128) as i64;
+    let mut client = influxdb_iox_client::namespace::Client::new(connection);
+    let namespace = client
+        .update_namespace_row_ttl(&namespace, ttl.map(to_ns), column)
+        .await?;
+    println!("{}", serde_json::to_string_pretty(&namespace)?);
+
+    Ok(())
+}
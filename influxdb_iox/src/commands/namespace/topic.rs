@@ -0,0 +1,27 @@
+use influxdb_iox_client::connection::Connection;
+
+/// Assign the specified namespace to a write buffer topic
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    /// The namespace to assign
+    #[clap(action)]
+    namespace: String,
+
+    /// The name of the write buffer topic to route the writes of the namespace to. Routers that
+    /// do not write to the topic reject writes to the namespace
+    #[clap(action)]
+    topic: String,
+}
+
+pub async fn command(
+    connection: Connection,
+    config: Config,
+) -> Result<(), crate::commands::namespace::Error> {
+    let Config { namespace, topic } = config;
+
+    let mut client = influxdb_iox_client::namespace::Client::new(connection);
+    let namespace = client.update_namespace_topic(&namespace, &topic).await?;
+    println!("{}", serde_json::to_string_pretty(&namespace)?);
+
+    Ok(())
+}
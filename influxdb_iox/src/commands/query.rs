@@ -3,7 +3,7 @@ use influxdb_iox_client::{
     flight::{self, generated_types::ReadInfo},
     format::QueryOutputFormat,
 };
-use std::str::FromStr;
+use std::{str::FromStr, time::Duration};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -31,6 +31,24 @@ pub struct Config {
     /// Optional format ('pretty', 'json', or 'csv')
     #[clap(short, long, default_value = "pretty", action)]
     format: String,
+
+    /// Tolerate missing unpersisted data older than this (e.g. "5m"), allowing the querier to
+    /// skip the ingesters for queries over older time ranges.
+    #[clap(long, value_parser = humantime::parse_duration)]
+    max_unpersisted_staleness: Option<Duration>,
+
+    /// Other namespaces the query may reference as `"<namespace>".<table>`
+    #[clap(long = "additional-namespace", action)]
+    additional_namespaces: Vec<String>,
+
+    /// Print the execution statistics of the query, e.g. the number of scanned rows, to stderr
+    #[clap(long, action)]
+    statistics: bool,
+
+    /// Debug scan: read the tables only from the parquet file with this ID, without pruning,
+    /// deduplication or ingester data. May be given multiple times.
+    #[clap(long = "debug-parquet-file-id", action)]
+    debug_parquet_file_ids: Vec<i64>,
 }
 
 pub async fn command(connection: Connection, config: Config) -> Result<()> {
@@ -39,6 +57,10 @@ pub async fn command(connection: Connection, config: Config) -> Result<()> {
         namespace,
         format,
         query,
+        max_unpersisted_staleness,
+        additional_namespaces,
+        statistics,
+        debug_parquet_file_ids,
     } = config;
 
     let format = QueryOutputFormat::from_str(&format)?;
@@ -47,6 +69,12 @@ pub async fn command(connection: Connection, config: Config) -> Result<()> {
         .perform_query(ReadInfo {
             namespace_name: namespace,
             sql_query: query,
+            max_unpersisted_staleness_ns: max_unpersisted_staleness
+                .map(|d| d.as_nanos().try_into().unwrap_or(u64::MAX)),
+            additional_namespaces,
+            params: vec![],
+            include_statistics: statistics,
+            debug_parquet_file_ids,
         })
         .await?;
 
@@ -61,5 +89,18 @@ pub async fn command(connection: Connection, config: Config) -> Result<()> {
 
     println!("{}", formatted_result);
 
+    if let Some(statistics) = query_results.statistics() {
+        eprintln!(
+            "parquet bytes read: {}, rows scanned: {}, rows returned: {}, \
+             ingester partitions: {}, metadata cache hits: {}, metadata cache misses: {}",
+            statistics.parquet_bytes_read,
+            statistics.rows_scanned,
+            statistics.rows_returned,
+            statistics.ingester_partitions,
+            statistics.cache_hits,
+            statistics.cache_misses,
+        );
+    }
+
     Ok(())
 }
@@ -0,0 +1,544 @@
+//! This module implements the `restore` CLI command
+
+use crate::commands::backup::{backup_dir_store, catalog_path, parquet_path};
+use clap_blocks::{
+    catalog_dsn::CatalogDsnConfig,
+    object_store::{make_object_store, ObjectStoreConfig},
+};
+use data_types::{
+    ColumnId, ColumnSet, ColumnType, CompactionLevel, Namespace as CatalogNamespace,
+    ParquetFileParams, SequenceNumber, ShardId, ShardIndex, Timestamp,
+};
+use generated_types::influxdata::iox::backup::v1 as proto;
+use iox_catalog::interface::{Catalog, RepoCollection};
+use object_store::DynObjectStore;
+use parquet_file::ParquetFilePath;
+use prost::Message;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use thiserror::Error;
+use uuid::Uuid;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Catalog DSN error: {0}")]
+    CatalogDsn(#[from] clap_blocks::catalog_dsn::Error),
+
+    #[error("Cannot parse object store config: {0}")]
+    ObjectStoreParsing(#[from] clap_blocks::object_store::ParseError),
+
+    #[error("Cannot open backup: {0}")]
+    Backup(#[from] crate::commands::backup::Error),
+
+    #[error("Catalog error: {0}")]
+    Catalog(#[from] iox_catalog::interface::Error),
+
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    #[error("Cannot decode backup: {0}")]
+    Decode(#[from] prost::DecodeError),
+
+    #[error("Invalid backup: {0}")]
+    InvalidBackup(String),
+}
+
+/// Restore catalog rows and parquet files from a backup
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+/// Restore a namespace backup written by `backup namespace` into the catalog
+/// and object store.
+///
+/// All catalog IDs and parquet file object store IDs are rewritten on import,
+/// so a backup can be restored into a fresh catalog, or under a different name
+/// into the catalog it was taken from.
+#[derive(Debug, clap::Parser)]
+struct Namespace {
+    #[clap(flatten)]
+    catalog_dsn: CatalogDsnConfig,
+
+    #[clap(flatten)]
+    object_store: ObjectStoreConfig,
+
+    /// The directory the backup was written to
+    #[clap(long, action)]
+    backup_dir: PathBuf,
+
+    /// Restore the namespace under this name instead of its backed up name
+    #[clap(long, action)]
+    name: Option<String>,
+
+    /// The topic to assign the restored namespace to
+    #[clap(long, default_value = "iox-shared", action)]
+    topic: String,
+
+    /// The query pool to assign the restored namespace to
+    #[clap(long, default_value = "iox-shared", action)]
+    query_pool: String,
+}
+
+/// All possible subcommands for restore
+#[derive(Debug, clap::Parser)]
+enum Command {
+    /// Restore a namespace
+    Namespace(Box<Namespace>),
+}
+
+pub async fn command(config: Config) -> Result<(), Error> {
+    match config.command {
+        Command::Namespace(command) => {
+            let metrics = Arc::new(metric::Registry::new());
+            let catalog = command.catalog_dsn.get_catalog("cli", metrics).await?;
+            let object_store = make_object_store(&command.object_store)?;
+            let backup_store = backup_dir_store(&command.backup_dir)?;
+
+            let namespace = restore_namespace(
+                &catalog,
+                &object_store,
+                &backup_store,
+                command.name.as_deref(),
+                &command.topic,
+                &command.query_pool,
+            )
+            .await?;
+
+            println!(
+                "restored namespace {} with id {} from {}",
+                namespace.name,
+                namespace.id,
+                command.backup_dir.display(),
+            );
+
+            Ok(())
+        }
+    }
+}
+
+/// Restore the namespace backup in `backup_store` into `catalog` and
+/// `object_store`, returning the restored namespace.
+///
+/// The namespace is named `name`, or its backed up name if `name` is [`None`],
+/// and must not exist. Shards are looked up by their backed up shard index in
+/// `topic`, and created if missing.
+///
+/// All catalog rows are written in a single transaction that is committed once
+/// all parquet files have been copied, so a failed restore leaves no catalog
+/// rows behind. Files copied by a failed restore are eventually deleted by the
+/// garbage collector.
+pub(crate) async fn restore_namespace(
+    catalog: &Arc<dyn Catalog>,
+    object_store: &Arc<DynObjectStore>,
+    backup_store: &Arc<DynObjectStore>,
+    name: Option<&str>,
+    topic: &str,
+    query_pool: &str,
+) -> Result<CatalogNamespace, Error> {
+    let bytes = backup_store.get(&catalog_path()).await?.bytes().await?;
+    let backup = proto::NamespaceBackup::decode(bytes)?;
+    let name = name.unwrap_or(&backup.name);
+
+    let mut txn = catalog.start_transaction().await?;
+
+    let topic = txn.topics().create_or_get(topic).await?;
+    let query_pool = txn.query_pools().create_or_get(query_pool).await?;
+
+    txn.namespaces()
+        .create(name, backup.retention_period_ns, topic.id, query_pool.id)
+        .await?;
+    txn.namespaces()
+        .update_table_limit(name, backup.max_tables)
+        .await?;
+    let namespace = txn
+        .namespaces()
+        .update_column_limit(name, backup.max_columns_per_table)
+        .await?;
+
+    let mut shards = HashMap::new();
+    for shard_index in backup
+        .partitions
+        .iter()
+        .map(|p| p.shard_index)
+        .chain(backup.parquet_files.iter().map(|f| f.shard_index))
+        .chain(backup.tombstones.iter().map(|t| t.shard_index))
+    {
+        if !shards.contains_key(&shard_index) {
+            let shard = txn
+                .shards()
+                .create_or_get(&topic, ShardIndex::new(shard_index))
+                .await?;
+            shards.insert(shard_index, shard.id);
+        }
+    }
+    let shard_id = |shard_index: i32| -> ShardId { shards[&shard_index] };
+
+    let mut tables = HashMap::new();
+    let mut columns = HashMap::new();
+    for t in &backup.tables {
+        let table = txn.tables().create_or_get(&t.name, namespace.id).await?;
+        tables.insert(t.id, table.id);
+
+        for c in &t.columns {
+            let column_type = ColumnType::try_from(c.column_type as i16).map_err(|e| {
+                Error::InvalidBackup(format!("invalid type of column {}: {}", c.name, e))
+            })?;
+            let column = txn
+                .columns()
+                .create_or_get(&c.name, table.id, column_type)
+                .await?;
+            columns.insert(c.id, column.id);
+        }
+    }
+
+    let mut partitions = HashMap::new();
+    for p in &backup.partitions {
+        let table_id = lookup(&tables, p.table_id, "table")?;
+        let partition = txn
+            .partitions()
+            .create_or_get(p.key.clone().into(), shard_id(p.shard_index), table_id)
+            .await?;
+        partitions.insert(p.id, (table_id, partition.id));
+
+        if !p.sort_key.is_empty() {
+            let sort_key: Vec<_> = p.sort_key.iter().map(|s| s.as_str()).collect();
+            txn.partitions()
+                .update_sort_key(partition.id, &sort_key)
+                .await?;
+        }
+        if let Some(sequence_number) = p.persisted_sequence_number {
+            txn.partitions()
+                .update_persisted_sequence_number(
+                    partition.id,
+                    SequenceNumber::new(sequence_number),
+                )
+                .await?;
+        }
+    }
+
+    for f in &backup.parquet_files {
+        let (table_id, partition_id) = lookup(&partitions, f.partition_id, "partition")?;
+        let params = ParquetFileParams {
+            shard_id: shard_id(f.shard_index),
+            namespace_id: namespace.id,
+            table_id,
+            partition_id,
+            object_store_id: Uuid::new_v4(),
+            max_sequence_number: SequenceNumber::new(f.max_sequence_number),
+            min_time: Timestamp::new(f.min_time),
+            max_time: Timestamp::new(f.max_time),
+            file_size_bytes: f.file_size_bytes,
+            row_count: f.row_count,
+            compaction_level: CompactionLevel::try_from(f.compaction_level)
+                .map_err(|e| Error::InvalidBackup(e.to_string()))?,
+            created_at: Timestamp::new(f.created_at),
+            column_set: ColumnSet::new(
+                f.column_set
+                    .iter()
+                    .map(|id| lookup(&columns, *id, "column"))
+                    .collect::<Result<Vec<ColumnId>, _>>()?,
+            ),
+        };
+
+        let backup_object_store_id = Uuid::parse_str(&f.object_store_id)
+            .map_err(|e| Error::InvalidBackup(format!("invalid object store id: {}", e)))?;
+        let bytes = backup_store
+            .get(&parquet_path(backup_object_store_id))
+            .await?
+            .bytes()
+            .await?;
+        object_store
+            .put(
+                &ParquetFilePath::new(
+                    params.namespace_id,
+                    params.table_id,
+                    params.shard_id,
+                    params.partition_id,
+                    params.object_store_id,
+                )
+                .object_store_path(),
+                bytes,
+            )
+            .await?;
+
+        txn.parquet_files().create(params).await?;
+    }
+
+    for t in &backup.tombstones {
+        let table_id = lookup(&tables, t.table_id, "table")?;
+        txn.tombstones()
+            .create_or_get(
+                table_id,
+                shard_id(t.shard_index),
+                SequenceNumber::new(t.sequence_number),
+                Timestamp::new(t.min_time),
+                Timestamp::new(t.max_time),
+                &t.serialized_predicate,
+            )
+            .await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(namespace)
+}
+
+/// Look up the restored ID of the row with the backed up ID `id`.
+fn lookup<T: Copy>(ids: &HashMap<i64, T>, id: i64, kind: &str) -> Result<T, Error> {
+    ids.get(&id)
+        .copied()
+        .ok_or_else(|| Error::InvalidBackup(format!("reference to unknown {} {}", kind, id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::backup::backup_namespace;
+    use bytes::Bytes;
+    use data_types::PartitionKey;
+    use iox_catalog::mem::MemCatalog;
+    use object_store::memory::InMemory;
+
+    #[tokio::test]
+    async fn backup_and_restore_namespace() {
+        let source: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Default::default()));
+        let source_store: Arc<DynObjectStore> = Arc::new(InMemory::new());
+
+        let mut repos = source.repositories().await;
+        let topic = repos.topics().create_or_get("topic").await.unwrap();
+        let query_pool = repos.query_pools().create_or_get("pool").await.unwrap();
+        let shard = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(3))
+            .await
+            .unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("ns", Some(42), topic.id, query_pool.id)
+            .await
+            .unwrap();
+        repos
+            .namespaces()
+            .update_table_limit("ns", 7)
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("cpu", namespace.id)
+            .await
+            .unwrap();
+        let tag = repos
+            .columns()
+            .create_or_get("host", table.id, ColumnType::Tag)
+            .await
+            .unwrap();
+        let time = repos
+            .columns()
+            .create_or_get("time", table.id, ColumnType::Time)
+            .await
+            .unwrap();
+        let partition = repos
+            .partitions()
+            .create_or_get("2022-12-01".into(), shard.id, table.id)
+            .await
+            .unwrap();
+        repos
+            .partitions()
+            .update_sort_key(partition.id, &["host", "time"])
+            .await
+            .unwrap();
+        repos
+            .partitions()
+            .update_persisted_sequence_number(partition.id, SequenceNumber::new(10))
+            .await
+            .unwrap();
+        let file = repos
+            .parquet_files()
+            .create(ParquetFileParams {
+                shard_id: shard.id,
+                namespace_id: namespace.id,
+                table_id: table.id,
+                partition_id: partition.id,
+                object_store_id: Uuid::new_v4(),
+                max_sequence_number: SequenceNumber::new(10),
+                min_time: Timestamp::new(1),
+                max_time: Timestamp::new(2),
+                file_size_bytes: 8,
+                row_count: 3,
+                compaction_level: CompactionLevel::FileNonOverlapped,
+                created_at: Timestamp::new(4),
+                column_set: ColumnSet::new([tag.id, time.id]),
+            })
+            .await
+            .unwrap();
+        repos
+            .tombstones()
+            .create_or_get(
+                table.id,
+                shard.id,
+                SequenceNumber::new(11),
+                Timestamp::new(1),
+                Timestamp::new(2),
+                "host=a",
+            )
+            .await
+            .unwrap();
+        drop(repos);
+
+        source_store
+            .put(
+                &ParquetFilePath::from(&file).object_store_path(),
+                Bytes::from_static(b"platanos"),
+            )
+            .await
+            .unwrap();
+
+        let backup_store: Arc<DynObjectStore> = Arc::new(InMemory::new());
+        backup_namespace(&source, &source_store, "ns", &backup_store)
+            .await
+            .unwrap();
+
+        // Restore into a fresh catalog that already has rows, so that none of
+        // the restored IDs match the backed up IDs by accident.
+        let target: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Default::default()));
+        let target_store: Arc<DynObjectStore> = Arc::new(InMemory::new());
+        {
+            let mut repos = target.repositories().await;
+            let topic = repos.topics().create_or_get("other").await.unwrap();
+            let query_pool = repos.query_pools().create_or_get("other").await.unwrap();
+            let namespace = repos
+                .namespaces()
+                .create("other", None, topic.id, query_pool.id)
+                .await
+                .unwrap();
+            repos
+                .tables()
+                .create_or_get("other", namespace.id)
+                .await
+                .unwrap();
+        }
+
+        let restored = restore_namespace(
+            &target,
+            &target_store,
+            &backup_store,
+            Some("restored"),
+            "iox-shared",
+            "iox-shared",
+        )
+        .await
+        .unwrap();
+        assert_eq!(restored.name, "restored");
+        assert_eq!(restored.retention_period_ns, Some(42));
+        assert_eq!(restored.max_tables, 7);
+        assert_ne!(restored.id, namespace.id);
+
+        let mut repos = target.repositories().await;
+        let tables = repos
+            .tables()
+            .list_by_namespace_id(restored.id)
+            .await
+            .unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "cpu");
+        assert_ne!(tables[0].id, table.id);
+
+        let columns = repos
+            .columns()
+            .list_by_table_id(tables[0].id)
+            .await
+            .unwrap();
+        let column_id = |name: &str| columns.iter().find(|c| c.name == name).unwrap().id;
+
+        let partitions = repos
+            .partitions()
+            .list_by_namespace(restored.id)
+            .await
+            .unwrap();
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(
+            partitions[0].partition_key,
+            PartitionKey::from("2022-12-01")
+        );
+        assert_eq!(partitions[0].sort_key, vec!["host", "time"]);
+        assert_eq!(
+            partitions[0].persisted_sequence_number,
+            Some(SequenceNumber::new(10))
+        );
+
+        let files = repos
+            .parquet_files()
+            .list_by_namespace_not_to_delete(restored.id)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        let restored_file = &files[0];
+        assert_eq!(restored_file.partition_id, partitions[0].id);
+        assert_ne!(restored_file.object_store_id, file.object_store_id);
+        assert_eq!(restored_file.row_count, 3);
+        assert_eq!(
+            restored_file.column_set,
+            ColumnSet::new([column_id("host"), column_id("time")])
+        );
+
+        let bytes = target_store
+            .get(&ParquetFilePath::from(restored_file).object_store_path())
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        assert_eq!(bytes.as_ref(), b"platanos");
+
+        let tombstones = repos
+            .tombstones()
+            .list_by_namespace(restored.id)
+            .await
+            .unwrap();
+        assert_eq!(tombstones.len(), 1);
+        assert_eq!(tombstones[0].table_id, tables[0].id);
+        assert_eq!(tombstones[0].serialized_predicate, "host=a");
+    }
+
+    #[tokio::test]
+    async fn restore_existing_namespace_fails() {
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Default::default()));
+        let object_store: Arc<DynObjectStore> = Arc::new(InMemory::new());
+        {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("iox-shared").await.unwrap();
+            let query_pool = repos
+                .query_pools()
+                .create_or_get("iox-shared")
+                .await
+                .unwrap();
+            repos
+                .namespaces()
+                .create("ns", None, topic.id, query_pool.id)
+                .await
+                .unwrap();
+        }
+
+        let backup_store: Arc<DynObjectStore> = Arc::new(InMemory::new());
+        backup_namespace(&catalog, &object_store, "ns", &backup_store)
+            .await
+            .unwrap();
+
+        let err = restore_namespace(
+            &catalog,
+            &object_store,
+            &backup_store,
+            None,
+            "iox-shared",
+            "iox-shared",
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Catalog(iox_catalog::interface::Error::NameExists { .. })
+        ));
+    }
+}
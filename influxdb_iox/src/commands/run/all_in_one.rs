@@ -2,11 +2,18 @@
 
 use super::main;
 use clap_blocks::{
+    access_log::AccessLogConfig,
     catalog_dsn::CatalogDsnConfig,
     compactor::CompactorConfig,
-    ingester::IngesterConfig,
+    grpc_server::GrpcServerConfig,
+    ingester::{IngesterConfig, ParquetCompression},
+    metrics_push::MetricsPushConfig,
     object_store::{make_object_store, ObjectStoreConfig},
     querier::{IngesterAddresses, QuerierConfig},
+    router::{
+        DmlHandlerConfig, NamespaceAutocreationConfig, NamespaceNameRulesConfig,
+        SchemaConflictConfig, TopicRoutingConfig,
+    },
     run_config::RunConfig,
     socket_addr::SocketAddr,
     write_buffer::WriteBufferConfig,
@@ -25,7 +32,7 @@ use ioxd_router::create_router_server_type;
 use object_store::DynObjectStore;
 use observability_deps::tracing::*;
 use parquet_file::storage::{ParquetStorage, StorageId};
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, num::NonZeroUsize, path::PathBuf, sync::Arc, time::Duration};
 use thiserror::Error;
 use trace_exporters::TracingConfig;
 use trogging::cli::LoggingConfig;
@@ -159,6 +166,18 @@ pub struct Config {
     #[clap(flatten)]
     pub(crate) tracing_config: TracingConfig,
 
+    /// HTTP access log options
+    #[clap(flatten)]
+    pub(crate) access_log_config: AccessLogConfig,
+
+    /// metrics pushgateway options
+    #[clap(flatten)]
+    pub(crate) metrics_push_config: MetricsPushConfig,
+
+    /// gRPC server options, shared by all services
+    #[clap(flatten)]
+    pub(crate) grpc_server_config: GrpcServerConfig,
+
     /// Maximum size of HTTP requests.
     #[clap(
         long = "max-http-request-size",
@@ -174,6 +193,16 @@ pub struct Config {
     #[clap(flatten)]
     catalog_dsn: CatalogDsnConfig,
 
+    /// File to keep the schema of the in-memory catalog in across restarts. The namespaces,
+    /// tables and columns are loaded from it on startup and saved to it on shutdown. Ignored if
+    /// `--catalog-dsn` is set.
+    #[clap(
+        long = "catalog-snapshot",
+        env = "INFLUXDB_IOX_CATALOG_SNAPSHOT",
+        action
+    )]
+    pub catalog_snapshot: Option<PathBuf>,
+
     /// The ingester will continue to pull data and buffer it from the write buffer
     /// as long as it is below this size. If it hits this size it will pause
     /// ingest from the write buffer until persistence goes below this threshold.
@@ -323,6 +352,25 @@ pub struct Config {
         action
     )]
     pub querier_max_table_query_bytes: usize,
+
+    /// Number of threads to use for query execution, shared by all services. Defaults to the
+    /// number of available CPUs, i.e. the CPU limit of the container.
+    #[clap(
+        long = "query-exec-thread-count",
+        env = "INFLUXDB_IOX_QUERY_EXEC_THREAD_COUNT",
+        action
+    )]
+    pub query_exec_thread_count: Option<usize>,
+
+    /// Number of threads to use for reorganization tasks, such as compaction and persistence,
+    /// shared by all services. Defaults to the number of available CPUs, i.e. the CPU limit of
+    /// the container.
+    #[clap(
+        long = "reorg-exec-thread-count",
+        env = "INFLUXDB_IOX_REORG_EXEC_THREAD_COUNT",
+        action
+    )]
+    pub reorg_exec_thread_count: Option<usize>,
 }
 
 impl Config {
@@ -331,9 +379,13 @@ impl Config {
         let Self {
             logging_config,
             tracing_config,
+            access_log_config,
+            metrics_push_config,
+            grpc_server_config,
             max_http_request_size,
             object_store_config,
             catalog_dsn,
+            catalog_snapshot,
             pause_ingest_size_bytes,
             persist_memory_threshold_bytes,
             persist_partition_size_threshold_bytes,
@@ -348,6 +400,8 @@ impl Config {
             querier_ram_pool_data_bytes,
             querier_max_concurrent_queries,
             querier_max_table_query_bytes,
+            query_exec_thread_count,
+            reorg_exec_thread_count,
         } = self;
 
         let database_directory = object_store_config.database_directory.clone();
@@ -362,7 +416,7 @@ impl Config {
 
         let write_buffer_config = WriteBufferConfig::new(QUERY_POOL_NAME, database_directory);
         let catalog_dsn = if catalog_dsn.dsn.is_none() {
-            CatalogDsnConfig::new_memory()
+            CatalogDsnConfig::new_memory().with_memory_snapshot_path(catalog_snapshot)
         } else {
             catalog_dsn
         };
@@ -370,8 +424,11 @@ impl Config {
         let router_run_config = RunConfig::new(
             logging_config,
             tracing_config,
+            access_log_config,
+            metrics_push_config,
             router_http_bind_address,
             router_grpc_bind_address,
+            grpc_server_config,
             max_http_request_size,
             object_store_config,
         );
@@ -399,15 +456,32 @@ impl Config {
         let ingester_config = IngesterConfig {
             shard_index_range_start,
             shard_index_range_end,
-            pause_ingest_size_bytes,
-            persist_memory_threshold_bytes,
+            shard_reassignment: false,
+            shard_leases: false,
+            shard_lease_holder: None,
+            shard_lease_duration_seconds: 30,
+            standby_persist_timeout_seconds: 600,
+            job_node: None,
+            advertise_address: None,
+            catalog_shard_assignment: false,
+            pause_ingest_size_bytes: Some(pause_ingest_size_bytes),
+            persist_memory_threshold_bytes: Some(persist_memory_threshold_bytes),
             persist_partition_size_threshold_bytes,
             persist_partition_age_threshold_seconds,
             persist_partition_cold_threshold_seconds,
             skip_to_oldest_available,
             test_flight_do_get_panic: 0,
             concurrent_request_limit: 10,
+            presort_persist: false,
+            persist_row_group_size: NonZeroUsize::new(1024 * 1024).unwrap(),
+            persist_data_page_size: None,
+            persist_compression: ParquetCompression::Zstd,
+            persist_disable_tag_dictionary: false,
+            persist_disable_field_dictionary: false,
+            persist_bloom_filter_columns: vec![],
             persist_partition_rows_max: 500_000,
+            persist_max_concurrency: NonZeroUsize::new(10).unwrap(),
+            persist_memory_budget_bytes: None,
         };
 
         // create a CompactorConfig for the all in one server based on
@@ -420,6 +494,7 @@ impl Config {
             max_desired_file_size_bytes: 30_000,
             percentage_max_file_size: 30,
             split_percentage: 80,
+            max_output_file_size_bytes: None,
             max_number_partitions_per_shard: 1,
             min_number_recent_ingested_files_per_partition: 1,
             hot_multiple: 4,
@@ -430,17 +505,40 @@ impl Config {
             minutes_without_new_writes_to_be_cold: 10,
             hot_compaction_hours_threshold_1: 4,
             hot_compaction_hours_threshold_2: 24,
+            bloom_filter_columns: vec![],
+            tag_index_columns: vec![],
         };
 
         let querier_config = QuerierConfig {
             num_query_threads: None,       // will be ignored
             shard_to_ingesters_file: None, // will be ignored
             shard_to_ingesters: None,      // will be ignored
-            ram_pool_metadata_bytes: querier_ram_pool_metadata_bytes,
-            ram_pool_data_bytes: querier_ram_pool_data_bytes,
+            read_replica: false,
+            skip_ingesters_for_cold_tables: false,
+            shard_pinning: false,
+            ram_pool_metadata_bytes: Some(querier_ram_pool_metadata_bytes),
+            ram_pool_data_bytes: Some(querier_ram_pool_data_bytes),
             max_concurrent_queries: querier_max_concurrent_queries,
             max_table_query_bytes: querier_max_table_query_bytes,
+            max_concurrent_object_store_scans: None,
+            max_concurrent_object_store_scans_per_query: None,
+            object_store_hedge_percentile: None,
+            object_store_hedge_min_delay: Duration::from_millis(10),
+            plan_cache_max_entries: None,
+            namespace_metric_label_limit: None,
+            max_gap_fill_buckets: 1_000_000,
             ingester_circuit_breaker_threshold: u64::MAX, // never for all-in-one-mode
+            external_table_location_allowlist: vec![],
+            router_http_address: None,
+            router_max_request_size: 10 * 1024 * 1024,
+            query_pool_name: QUERY_POOL_NAME.to_string(),
+            query_pool_advertise_address: None,
+            query_pool_heartbeat_interval_seconds: 10,
+            write_slo_namespace: None,
+            write_slo_interval_seconds: 60,
+            write_slo_timeout_seconds: 1800,
+            usage_flush_interval_seconds: None,
+            read_policy_file: None,
         };
 
         SpecializedConfig {
@@ -455,6 +553,9 @@ impl Config {
             ingester_config,
             compactor_config,
             querier_config,
+
+            query_exec_thread_count,
+            reorg_exec_thread_count,
         }
     }
 }
@@ -472,6 +573,9 @@ struct SpecializedConfig {
     ingester_config: IngesterConfig,
     compactor_config: CompactorConfig,
     querier_config: QuerierConfig,
+
+    query_exec_thread_count: Option<usize>,
+    reorg_exec_thread_count: Option<usize>,
 }
 
 pub async fn command(config: Config) -> Result<()> {
@@ -485,6 +589,8 @@ pub async fn command(config: Config) -> Result<()> {
         ingester_config,
         compactor_config,
         querier_config,
+        query_exec_thread_count,
+        reorg_exec_thread_count,
     } = config.specialize();
 
     let metrics = Arc::new(metric::Registry::default());
@@ -515,20 +621,27 @@ pub async fn command(config: Config) -> Result<()> {
     // create common state from the router and use it below
     let common_state = CommonServerState::from_config(router_run_config.clone())?;
 
-    // TODO: make num_threads a parameter (other modes have it
-    // configured by a command line)
-    let num_threads = num_cpus::get();
-    info!(%num_threads, "Creating shared query executor");
+    let query_exec_thread_count =
+        query_exec_thread_count.unwrap_or_else(|| common_state.resources().num_threads());
+    let reorg_exec_thread_count =
+        reorg_exec_thread_count.unwrap_or_else(|| common_state.resources().num_threads());
+    info!(
+        %query_exec_thread_count,
+        %reorg_exec_thread_count,
+        "Creating shared query executor"
+    );
 
     let parquet_store = ParquetStorage::new(Arc::clone(&object_store), StorageId::from("iox"));
     let exec = Arc::new(Executor::new_with_config(ExecutorConfig {
-        num_threads,
-        target_query_partitions: num_threads,
+        num_query_threads: query_exec_thread_count,
+        num_reorg_threads: reorg_exec_thread_count,
+        target_query_partitions: query_exec_thread_count,
         object_stores: HashMap::from([(
             parquet_store.id(),
             Arc::clone(parquet_store.object_store()),
         )]),
     }));
+    exec.register_metrics(&metrics);
 
     info!("starting router");
     let router = create_router_server_type(
@@ -539,6 +652,16 @@ pub async fn command(config: Config) -> Result<()> {
         &write_buffer_config,
         QUERY_POOL_NAME,
         1_000, // max 1,000 concurrent HTTP requests
+        0.0,   // write auditing disabled
+        false, // shard pinning disabled
+        None,  // request metrics not labelled by namespace
+        None,  // usage accounting disabled
+        None,  // authorization disabled
+        &NamespaceAutocreationConfig::new_enabled(),
+        &NamespaceNameRulesConfig::default(),
+        &SchemaConflictConfig::default(),
+        &TopicRoutingConfig::default(),
+        &DmlHandlerConfig::default(),
     )
     .await?;
 
@@ -580,12 +703,13 @@ pub async fn command(config: Config) -> Result<()> {
     let querier = create_querier_server_type(QuerierServerTypeArgs {
         common_state: &common_state,
         metric_registry: Arc::clone(&metrics),
-        catalog,
+        catalog: Arc::clone(&catalog),
         object_store,
         exec,
         time_provider,
         ingester_addresses,
         querier_config,
+        authz_cache_ttl: None,
     })
     .await?;
 
@@ -598,5 +722,8 @@ pub async fn command(config: Config) -> Result<()> {
         Service::create_grpc_only(querier, &querier_run_config),
     ];
 
-    Ok(main::main(common_state, services, metrics).await?)
+    main::main(common_state, services, metrics).await?;
+
+    catalog.shutdown().await?;
+    Ok(())
 }
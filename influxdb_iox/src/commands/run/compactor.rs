@@ -66,14 +66,25 @@ pub struct Config {
     #[clap(flatten)]
     pub(crate) compactor_config: CompactorConfig,
 
-    /// Number of threads to use for the compactor query execution, compaction and persistence.
+    /// Number of threads to use for the compactor query execution.
+    ///
+    /// If not specified, defaults to the number of CPUs available to the compactor, i.e. the CPU
+    /// limit of its container.
     #[clap(
         long = "query-exec-thread-count",
         env = "INFLUXDB_IOX_QUERY_EXEC_THREAD_COUNT",
-        default_value = "4",
         action
     )]
-    pub query_exec_thread_count: usize,
+    pub query_exec_thread_count: Option<usize>,
+
+    /// Number of threads to use for the compactor reorganization tasks, such as compaction and
+    /// persistence. Defaults to `--query-exec-thread-count`.
+    #[clap(
+        long = "reorg-exec-thread-count",
+        env = "INFLUXDB_IOX_REORG_EXEC_THREAD_COUNT",
+        action
+    )]
+    pub reorg_exec_thread_count: Option<usize>,
 }
 
 pub async fn command(config: Config) -> Result<(), Error> {
@@ -83,7 +94,7 @@ pub async fn command(config: Config) -> Result<(), Error> {
     let metric_registry: Arc<metric::Registry> = Default::default();
     let catalog = config
         .catalog_dsn
-        .get_catalog("compactor", Arc::clone(&metric_registry))
+        .get_up_to_date_catalog("compactor", Arc::clone(&metric_registry))
         .await?;
 
     let object_store = make_object_store(config.run_config.object_store_config())
@@ -98,14 +109,21 @@ pub async fn command(config: Config) -> Result<(), Error> {
 
     let parquet_store = ParquetStorage::new(object_store, StorageId::from("iox"));
 
+    let query_exec_thread_count = config
+        .query_exec_thread_count
+        .unwrap_or_else(|| common_state.resources().num_threads());
     let exec = Arc::new(Executor::new_with_config(ExecutorConfig {
-        num_threads: config.query_exec_thread_count,
-        target_query_partitions: config.query_exec_thread_count,
+        num_query_threads: query_exec_thread_count,
+        num_reorg_threads: config
+            .reorg_exec_thread_count
+            .unwrap_or(query_exec_thread_count),
+        target_query_partitions: query_exec_thread_count,
         object_stores: HashMap::from([(
             parquet_store.id(),
             Arc::clone(parquet_store.object_store()),
         )]),
     }));
+    exec.register_metrics(&metric_registry);
     let time_provider = Arc::new(SystemProvider::new());
 
     let server_type = create_compactor_server_type(
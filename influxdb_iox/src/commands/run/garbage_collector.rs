@@ -33,7 +33,7 @@ pub async fn command(config: Config) -> Result<()> {
 
     let catalog = config
         .catalog_dsn
-        .get_catalog("garbage-collector", Arc::clone(&metric_registry))
+        .get_up_to_date_catalog("garbage-collector", Arc::clone(&metric_registry))
         .await?;
 
     let object_store = make_object_store(config.run_config.object_store_config())?;
@@ -5,7 +5,7 @@ use clap_blocks::{
     catalog_dsn::CatalogDsnConfig, ingester::IngesterConfig, run_config::RunConfig,
     write_buffer::WriteBufferConfig,
 };
-use iox_query::exec::Executor;
+use iox_query::exec::{Executor, ExecutorConfig};
 use iox_time::{SystemProvider, TimeProvider};
 use ioxd_common::server_type::{CommonServerState, CommonServerStateError};
 use ioxd_common::Service;
@@ -13,7 +13,7 @@ use ioxd_ingester::create_ingester_server_type;
 use object_store::DynObjectStore;
 use object_store_metrics::ObjectStoreMetrics;
 use observability_deps::tracing::*;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use thiserror::Error;
 
 use super::main;
@@ -65,14 +65,25 @@ pub struct Config {
     #[clap(flatten)]
     pub(crate) ingester_config: IngesterConfig,
 
-    /// Number of threads to use for the ingester query execution, compaction and persistence.
+    /// Number of threads to use for the ingester query execution.
+    ///
+    /// If not specified, defaults to the number of CPUs available to the ingester, i.e. the CPU
+    /// limit of its container.
     #[clap(
         long = "query-exec-thread-count",
         env = "INFLUXDB_IOX_QUERY_EXEC_THREAD_COUNT",
-        default_value = "4",
         action
     )]
-    pub query_exec_thread_count: usize,
+    pub query_exec_thread_count: Option<usize>,
+
+    /// Number of threads to use for the ingester reorganization tasks, such as compaction and
+    /// persistence. Defaults to `--query-exec-thread-count`.
+    #[clap(
+        long = "reorg-exec-thread-count",
+        env = "INFLUXDB_IOX_REORG_EXEC_THREAD_COUNT",
+        action
+    )]
+    pub reorg_exec_thread_count: Option<usize>,
 }
 
 pub async fn command(config: Config) -> Result<()> {
@@ -83,7 +94,7 @@ pub async fn command(config: Config) -> Result<()> {
 
     let catalog = config
         .catalog_dsn
-        .get_catalog("ingester", Arc::clone(&metric_registry))
+        .get_up_to_date_catalog("ingester", Arc::clone(&metric_registry))
         .await?;
 
     let object_store = make_object_store(config.run_config.object_store_config())
@@ -96,7 +107,18 @@ pub async fn command(config: Config) -> Result<()> {
         &metric_registry,
     ));
 
-    let exec = Arc::new(Executor::new(config.query_exec_thread_count));
+    let query_exec_thread_count = config
+        .query_exec_thread_count
+        .unwrap_or_else(|| common_state.resources().num_threads());
+    let exec = Arc::new(Executor::new_with_config(ExecutorConfig {
+        num_query_threads: query_exec_thread_count,
+        num_reorg_threads: config
+            .reorg_exec_thread_count
+            .unwrap_or(query_exec_thread_count),
+        target_query_partitions: query_exec_thread_count,
+        object_stores: HashMap::default(),
+    }));
+    exec.register_metrics(&metric_registry);
     let server_type = create_ingester_server_type(
         &common_state,
         Arc::clone(&metric_registry),
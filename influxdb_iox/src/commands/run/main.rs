@@ -1,7 +1,10 @@
 use std::sync::Arc;
 
 use ioxd_common::Service;
-use ioxd_common::{grpc_listener, http_listener, serve, server_type::CommonServerState};
+use ioxd_common::{
+    grpc_listener, http_listener, metrics_push::spawn_metrics_push, serve,
+    server_type::CommonServerState,
+};
 use observability_deps::tracing::{debug, error, info};
 use panic_logging::SendPanicsToTracing;
 use snafu::{ResultExt, Snafu};
@@ -116,6 +119,15 @@ pub async fn main(
             .register_instrument("jemalloc_metrics", jemalloc::JemallocMetrics::new);
     }
 
+    // Push the metrics to a pushgateway, if configured, until all services
+    // are done serving.
+    let metrics_push_shutdown = CancellationToken::new();
+    let metrics_push = spawn_metrics_push(
+        common_state.run_config().metrics_push_config(),
+        Arc::clone(&metrics),
+        metrics_push_shutdown.clone(),
+    );
+
     // Construct a token to trigger clean shutdown
     let frontend_shutdown = CancellationToken::new();
 
@@ -189,5 +201,10 @@ pub async fn main(
         );
     }
 
+    metrics_push_shutdown.cancel();
+    if let Some(metrics_push) = metrics_push {
+        metrics_push.await.context(JoiningSnafu)?;
+    }
+
     Ok(())
 }
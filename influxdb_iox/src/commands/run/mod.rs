@@ -7,6 +7,7 @@ mod garbage_collector;
 mod ingester;
 mod main;
 mod querier;
+mod query_gateway;
 mod router;
 mod test;
 
@@ -22,6 +23,9 @@ pub enum Error {
     #[snafu(display("Error in querier subcommand: {}", source))]
     QuerierError { source: querier::Error },
 
+    #[snafu(display("Error in query gateway subcommand: {}", source))]
+    QueryGatewayError { source: query_gateway::Error },
+
     #[snafu(display("Error in router subcommand: {}", source))]
     RouterError { source: router::Error },
 
@@ -54,6 +58,7 @@ impl Config {
             Some(Command::Compactor(config)) => config.run_config.logging_config(),
             Some(Command::GarbageCollector(config)) => config.run_config.logging_config(),
             Some(Command::Querier(config)) => config.run_config.logging_config(),
+            Some(Command::QueryGateway(config)) => config.run_config.logging_config(),
             Some(Command::Router(config)) => config.run_config.logging_config(),
             Some(Command::Ingester(config)) => config.run_config.logging_config(),
             Some(Command::AllInOne(config)) => &config.logging_config,
@@ -70,6 +75,9 @@ enum Command {
     /// Run the server in querier mode
     Querier(querier::Config),
 
+    /// Run the server in query gateway mode
+    QueryGateway(query_gateway::Config),
+
     /// Run the server in router mode
     Router(router::Config),
 
@@ -98,6 +106,9 @@ pub async fn command(config: Config) -> Result<()> {
             .await
             .context(GarbageCollectorSnafu),
         Some(Command::Querier(config)) => querier::command(config).await.context(QuerierSnafu),
+        Some(Command::QueryGateway(config)) => query_gateway::command(config)
+            .await
+            .context(QueryGatewaySnafu),
         Some(Command::Router(config)) => router::command(config).await.context(RouterSnafu),
         Some(Command::Ingester(config)) => ingester::command(config).await.context(IngesterSnafu),
         Some(Command::AllInOne(config)) => all_in_one::command(config).await.context(AllInOneSnafu),
@@ -2,8 +2,8 @@
 
 use super::main;
 use clap_blocks::{
-    catalog_dsn::CatalogDsnConfig, object_store::make_object_store, querier::QuerierConfig,
-    run_config::RunConfig,
+    authz::AuthzConfig, catalog_dsn::CatalogDsnConfig, object_store::make_object_store,
+    querier::QuerierConfig, run_config::RunConfig,
 };
 use iox_query::exec::Executor;
 use iox_time::{SystemProvider, TimeProvider};
@@ -66,6 +66,9 @@ pub struct Config {
 
     #[clap(flatten)]
     pub(crate) querier_config: QuerierConfig,
+
+    #[clap(flatten)]
+    pub(crate) authz_config: AuthzConfig,
 }
 
 pub async fn command(config: Config) -> Result<(), Error> {
@@ -76,7 +79,7 @@ pub async fn command(config: Config) -> Result<(), Error> {
 
     let catalog = config
         .catalog_dsn
-        .get_catalog("querier", Arc::clone(&metric_registry))
+        .get_up_to_date_catalog("querier", Arc::clone(&metric_registry))
         .await?;
 
     let object_store = make_object_store(config.run_config.object_store_config())
@@ -91,13 +94,18 @@ pub async fn command(config: Config) -> Result<(), Error> {
     let time_provider = Arc::new(SystemProvider::new());
 
     let num_query_threads = config.querier_config.num_query_threads();
-    let num_threads = num_query_threads.unwrap_or_else(num_cpus::get);
+    let num_threads = num_query_threads.unwrap_or_else(|| common_state.resources().num_threads());
     info!(%num_threads, "using specified number of threads per thread pool");
 
     let ingester_addresses = config.querier_config.ingester_addresses()?;
-    info!(?ingester_addresses, "using ingester addresses");
+    if config.querier_config.read_replica {
+        info!("running as read replica, only serving persisted data");
+    } else {
+        info!(?ingester_addresses, "using ingester addresses");
+    }
 
     let exec = Arc::new(Executor::new(num_threads));
+    exec.register_metrics(&metric_registry);
 
     let server_type = create_querier_server_type(QuerierServerTypeArgs {
         common_state: &common_state,
@@ -108,6 +116,7 @@ pub async fn command(config: Config) -> Result<(), Error> {
         time_provider,
         ingester_addresses,
         querier_config: config.querier_config,
+        authz_cache_ttl: config.authz_config.cache_ttl(),
     })
     .await?;
 
@@ -0,0 +1,83 @@
+//! Implementation of command line option for running the query gateway
+
+use super::main;
+use clap_blocks::{
+    catalog_dsn::CatalogDsnConfig, query_gateway::QueryGatewayConfig, run_config::RunConfig,
+};
+use iox_time::{SystemProvider, TimeProvider};
+use ioxd_common::{
+    server_type::{CommonServerState, CommonServerStateError},
+    Service,
+};
+use ioxd_query_gateway::{create_query_gateway_server_type, QueryGatewayServerTypeArgs};
+use observability_deps::tracing::*;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Run: {0}")]
+    Run(#[from] main::Error),
+
+    #[error("Invalid config: {0}")]
+    InvalidConfigCommon(#[from] CommonServerStateError),
+
+    #[error("Catalog DSN error: {0}")]
+    CatalogDsn(#[from] clap_blocks::catalog_dsn::Error),
+
+    #[error("Query gateway error: {0}")]
+    QueryGateway(#[from] ioxd_query_gateway::Error),
+}
+
+#[derive(Debug, clap::Parser)]
+#[clap(
+    name = "run",
+    about = "Runs in query gateway mode",
+    long_about = "Run the IOx query gateway server.\n\nThe query gateway authenticates Flight \
+    queries, applies admission control and result caching, and forwards queries to the \
+    queriers of the queried namespace's query pool.\n\nThe configuration options below can be \
+    set either with the command line flags or with the specified environment \
+    variable. If there is a file named '.env' in the current working directory, \
+    it is sourced before loading the configuration.
+
+Configuration is loaded from the following sources (highest precedence first):
+        - command line arguments
+        - user set environment variables
+        - .env file contents
+        - pre-configured default values"
+)]
+pub struct Config {
+    #[clap(flatten)]
+    pub(crate) run_config: RunConfig,
+
+    #[clap(flatten)]
+    pub(crate) catalog_dsn: CatalogDsnConfig,
+
+    #[clap(flatten)]
+    pub(crate) query_gateway_config: QueryGatewayConfig,
+}
+
+pub async fn command(config: Config) -> Result<(), Error> {
+    let common_state = CommonServerState::from_config(config.run_config.clone())?;
+
+    let time_provider = Arc::new(SystemProvider::new()) as Arc<dyn TimeProvider>;
+    let metric_registry: Arc<metric::Registry> = Default::default();
+
+    let catalog = config
+        .catalog_dsn
+        .get_up_to_date_catalog("query_gateway", Arc::clone(&metric_registry))
+        .await?;
+
+    let server_type = create_query_gateway_server_type(QueryGatewayServerTypeArgs {
+        common_state: &common_state,
+        metric_registry: Arc::clone(&metric_registry),
+        catalog,
+        time_provider,
+        query_gateway_config: config.query_gateway_config,
+    })?;
+
+    info!("starting query gateway");
+
+    let services = vec![Service::create(server_type, common_state.run_config())];
+    Ok(main::main(common_state, services, metric_registry).await?)
+}
@@ -3,7 +3,14 @@
 use super::main;
 use clap_blocks::object_store::make_object_store;
 use clap_blocks::{
-    catalog_dsn::CatalogDsnConfig, run_config::RunConfig, write_buffer::WriteBufferConfig,
+    authz::AuthzConfig,
+    catalog_dsn::CatalogDsnConfig,
+    router::{
+        DmlHandlerConfig, NamespaceAutocreationConfig, NamespaceNameRulesConfig,
+        SchemaConflictConfig, TopicRoutingConfig,
+    },
+    run_config::RunConfig,
+    write_buffer::WriteBufferConfig,
 };
 use iox_time::{SystemProvider, TimeProvider};
 use ioxd_common::{
@@ -14,9 +21,12 @@ use ioxd_router::create_router_server_type;
 use object_store::DynObjectStore;
 use object_store_metrics::ObjectStoreMetrics;
 use observability_deps::tracing::*;
-use std::sync::Arc;
+use simulate::SimulationConfig;
+use std::{sync::Arc, time::Duration};
 use thiserror::Error;
 
+mod simulate;
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Run: {0}")]
@@ -33,6 +43,9 @@ pub enum Error {
 
     #[error("Catalog DSN error: {0}")]
     CatalogDsn(#[from] clap_blocks::catalog_dsn::Error),
+
+    #[error("Simulation: {0}")]
+    Simulation(#[from] simulate::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -62,6 +75,27 @@ pub struct Config {
     #[clap(flatten)]
     pub(crate) write_buffer_config: WriteBufferConfig,
 
+    #[clap(flatten)]
+    pub(crate) namespace_autocreation_config: NamespaceAutocreationConfig,
+
+    #[clap(flatten)]
+    pub(crate) namespace_name_rules_config: NamespaceNameRulesConfig,
+
+    #[clap(flatten)]
+    pub(crate) schema_conflict_config: SchemaConflictConfig,
+
+    #[clap(flatten)]
+    pub(crate) topic_routing_config: TopicRoutingConfig,
+
+    #[clap(flatten)]
+    pub(crate) authz_config: AuthzConfig,
+
+    #[clap(flatten)]
+    pub(crate) dml_handler_config: DmlHandlerConfig,
+
+    #[clap(flatten)]
+    pub(crate) simulation_config: SimulationConfig,
+
     /// Query pool name to dispatch writes to.
     #[clap(
         long = "query-pool",
@@ -87,16 +121,71 @@ pub struct Config {
         action
     )]
     pub(crate) http_request_limit: usize,
+
+    /// The fraction of accepted writes to record in the write audit log, from
+    /// 0.0 (disabled) to 1.0 (every write).
+    ///
+    /// Sampled writes are logged with one line per table to the
+    /// `write_audit` log target, recording the namespace, table, row count,
+    /// approximate size and the trace ID of the request.
+    #[clap(
+        long = "write-audit-sample-rate",
+        env = "INFLUXDB_IOX_WRITE_AUDIT_SAMPLE_RATE",
+        default_value = "0",
+        action
+    )]
+    pub(crate) write_audit_sample_rate: f64,
+
+    /// Pin each table to the write buffer shard it is first assigned to.
+    ///
+    /// Pins are stored in the catalog and created lazily, with tables that
+    /// already have data pinned to the shard holding it. This allows the
+    /// number of write buffer shards (Kafka partitions) to change without
+    /// moving existing tables to a different shard, while new tables are
+    /// spread across all shards.
+    ///
+    /// The queriers must be run with `--shard-pinning` as well, so that they
+    /// query the ingesters of the shard a table is pinned to.
+    #[clap(long = "shard-pinning", env = "INFLUXDB_IOX_SHARD_PINNING", action)]
+    pub(crate) shard_pinning: bool,
+
+    /// Label the request metrics with the namespace of each request, for up
+    /// to this many distinct namespaces.
+    ///
+    /// Requests to further namespaces are labelled with the namespace
+    /// "other", bounding the number of metric series. Request metrics are not
+    /// labelled by namespace if unset.
+    #[clap(
+        long = "namespace-metric-label-limit",
+        env = "INFLUXDB_IOX_NAMESPACE_METRIC_LABEL_LIMIT",
+        action
+    )]
+    pub(crate) namespace_metric_label_limit: Option<usize>,
+
+    /// Account the rows and bytes written to each namespace, adding them to
+    /// the namespace usage in the catalog every this many seconds.
+    ///
+    /// Usage accounting is disabled if unset.
+    #[clap(
+        long = "usage-flush-interval-seconds",
+        env = "INFLUXDB_IOX_USAGE_FLUSH_INTERVAL_SECONDS",
+        action
+    )]
+    pub(crate) usage_flush_interval_seconds: Option<u64>,
 }
 
 pub async fn command(config: Config) -> Result<()> {
     let common_state = CommonServerState::from_config(config.run_config.clone())?;
+    if config.simulation_config.enabled {
+        return Ok(simulate::command(&config, &common_state).await?);
+    }
+
     let time_provider = Arc::new(SystemProvider::new()) as Arc<dyn TimeProvider>;
     let metrics = Arc::new(metric::Registry::default());
 
     let catalog = config
         .catalog_dsn
-        .get_catalog("router", Arc::clone(&metrics))
+        .get_up_to_date_catalog("router", Arc::clone(&metrics))
         .await?;
 
     let object_store = make_object_store(config.run_config.object_store_config())
@@ -116,6 +205,16 @@ pub async fn command(config: Config) -> Result<()> {
         &config.write_buffer_config,
         &config.query_pool_name,
         config.http_request_limit,
+        config.write_audit_sample_rate,
+        config.shard_pinning,
+        config.namespace_metric_label_limit,
+        config.usage_flush_interval_seconds.map(Duration::from_secs),
+        config.authz_config.cache_ttl(),
+        &config.namespace_autocreation_config,
+        &config.namespace_name_rules_config,
+        &config.schema_conflict_config,
+        &config.topic_routing_config,
+        &config.dml_handler_config,
     )
     .await?;
 
@@ -0,0 +1,681 @@
+//! Simulation mode of the router, replaying recorded writes through the
+//! router handler stack to find the bottleneck of the write path.
+
+use super::Config;
+use crate::commands::debug::percentile;
+use clap_blocks::write_buffer::WriteBufferConfig;
+use data_types::{NamespaceId, TableId};
+use dml::{DmlOperation, DmlWrite};
+use futures::StreamExt;
+use hyper::{Body, Method, Request, StatusCode};
+use iox_catalog::{create_or_get_default_records, interface::Catalog, mem::MemCatalog};
+use iox_time::Time;
+use ioxd_common::{
+    http::error::HttpApiErrorSource,
+    server_type::{CommonServerState, ServerType},
+};
+use ioxd_router::create_router_server_type;
+use metric::{Observation, RawReporter};
+use mutable_batch::MutableBatch;
+use object_store::{memory::InMemory, DynObjectStore};
+use observability_deps::tracing::*;
+use schema::Projection;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write,
+    fs::File,
+    io::{BufRead, BufReader},
+    ops::DerefMut,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tempfile::TempDir;
+use thiserror::Error;
+use tokio::time::Instant;
+use write_buffer::core::WriteBufferReading;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Cannot read write audit log {}: {source}", path.display())]
+    ReadAuditLog {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid write audit record on line {line}: {message}")]
+    InvalidAuditRecord { line: usize, message: String },
+
+    #[error("Catalog error: {0}")]
+    Catalog(#[from] iox_catalog::interface::Error),
+
+    #[error("Catalog DSN error: {0}")]
+    CatalogDsn(#[from] clap_blocks::catalog_dsn::Error),
+
+    #[error("Write buffer error: {0}")]
+    WriteBuffer(#[from] write_buffer::core::WriteBufferError),
+
+    #[error("Cannot read recorded write: {0}")]
+    MutableBatch(#[from] mutable_batch::Error),
+
+    #[error("Cannot convert recorded write to line protocol: {0}")]
+    LineProtocol(#[from] parquet_to_line_protocol::Error),
+
+    #[error("Cannot create temporary write buffer directory: {0}")]
+    TempDir(std::io::Error),
+
+    #[error("Creating router: {0}")]
+    Router(#[from] ioxd_router::Error),
+
+    #[error("No writes to replay")]
+    NoWrites,
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// CLI config of the router simulation mode.
+#[derive(Debug, clap::Parser)]
+pub struct SimulationConfig {
+    /// Replay recorded writes through the router handler stack instead of
+    /// serving requests, and report where the write path spends its time.
+    ///
+    /// The writes are applied to a throwaway in-memory catalog and a
+    /// temporary file write buffer, so neither the configured catalog nor the
+    /// write buffer are modified. Writes are read from the
+    /// `--simulate-audit-log` if set, and from the configured write buffer
+    /// topic otherwise.
+    #[clap(long = "simulate", env = "INFLUXDB_IOX_SIMULATE", action)]
+    pub(crate) enabled: bool,
+
+    /// Replay the writes recorded in this write audit log, see
+    /// `--write-audit-sample-rate`.
+    ///
+    /// The log must be in the logfmt format. Audit records only record the
+    /// namespace, table, row count and byte count of each write, not its
+    /// payload, so the replayed writes are synthesized line protocol with the
+    /// recorded number of rows and approximately the recorded number of bytes.
+    ///
+    /// The synthesized rows have no tags and the same two fields, and all rows
+    /// of a write fall in a single partition: a replayed audit log does NOT
+    /// reproduce the schema validation or partitioning load of the recorded
+    /// writes. Replay from the write buffer to reproduce them.
+    #[clap(
+        long = "simulate-audit-log",
+        env = "INFLUXDB_IOX_SIMULATE_AUDIT_LOG",
+        requires = "enabled",
+        action
+    )]
+    pub(crate) audit_log: Option<PathBuf>,
+
+    /// Multiplier of the speed at which the writes are replayed, e.g. 2 to
+    /// replay an hour of writes in 30 minutes.
+    #[clap(
+        long = "simulate-speed",
+        env = "INFLUXDB_IOX_SIMULATE_SPEED",
+        default_value = "1",
+        value_parser = parse_speed,
+    )]
+    pub(crate) speed: f64,
+}
+
+fn parse_speed(s: &str) -> Result<f64, String> {
+    let speed: f64 = s.parse().map_err(|e| format!("invalid speed: {e}"))?;
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(format!("speed must be positive, got {speed}"));
+    }
+    Ok(speed)
+}
+
+/// DML handler stages that wrap other stages, and therefore never are the
+/// bottleneck themselves.
+const ENCLOSING_STAGES: &[&str] = &["request", "parallel_write"];
+
+/// A recorded write to a single namespace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RecordedWrite {
+    /// When the write was originally applied.
+    time: Time,
+    namespace: String,
+    line_protocol: String,
+}
+
+/// The result of replaying a single write.
+#[derive(Debug)]
+struct Outcome {
+    status: StatusCode,
+    latency: Duration,
+    /// How much later than scheduled the write was sent.
+    lag: Duration,
+}
+
+/// Replay the recorded writes and print a report of the simulation.
+pub(super) async fn command(config: &Config, common_state: &CommonServerState) -> Result<()> {
+    let simulation = &config.simulation_config;
+    let mut writes = match &simulation.audit_log {
+        Some(path) => read_audit_log(path)?,
+        None => read_write_buffer(config).await?,
+    };
+    writes.sort_by_key(|write| write.time);
+    let (first, last) = match (writes.first(), writes.last()) {
+        (Some(first), Some(last)) => (first.time, last.time),
+        _ => return Err(Error::NoWrites),
+    };
+
+    let metrics = Arc::new(metric::Registry::default());
+    let catalog = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+    let mut txn = catalog.start_transaction().await?;
+    let (topic, query_pool, _) = create_or_get_default_records(1, txn.deref_mut()).await?;
+    // Namespaces are created up front, independent of the autocreation
+    // policy.
+    let namespaces: BTreeSet<_> = writes.iter().map(|w| w.namespace.as_str()).collect();
+    for namespace in namespaces {
+        txn.namespaces()
+            .create(namespace, None, topic.id, query_pool.id)
+            .await?;
+    }
+    txn.commit().await?;
+
+    let write_buffer_dir = TempDir::new().map_err(Error::TempDir)?;
+    let write_buffer_config =
+        WriteBufferConfig::new(&topic.name, Some(write_buffer_dir.path().to_path_buf()));
+    let object_store: Arc<DynObjectStore> = Arc::new(InMemory::new());
+
+    // Neither audit the replayed writes nor authorize them, since the
+    // throwaway catalog has no tokens.
+    let router = create_router_server_type(
+        common_state,
+        Arc::clone(&metrics),
+        catalog,
+        object_store,
+        &write_buffer_config,
+        &query_pool.name,
+        config.http_request_limit,
+        0.0,
+        config.shard_pinning,
+        config.namespace_metric_label_limit,
+        config.usage_flush_interval_seconds.map(Duration::from_secs),
+        None,
+        &config.namespace_autocreation_config,
+        &config.namespace_name_rules_config,
+        &config.schema_conflict_config,
+        &config.topic_routing_config,
+        &config.dml_handler_config,
+    )
+    .await?;
+
+    let span = last
+        .checked_duration_since(first)
+        .expect("writes are sorted by time");
+    info!(
+        writes = writes.len(),
+        ?span,
+        speed = simulation.speed,
+        "replaying recorded writes"
+    );
+
+    let start = Instant::now();
+    let mut handles = Vec::with_capacity(writes.len());
+    for write in writes {
+        let offset = write
+            .time
+            .checked_duration_since(first)
+            .expect("writes are sorted by time");
+        let scheduled = start + offset.div_f64(simulation.speed);
+        tokio::time::sleep_until(scheduled).await;
+
+        let router = Arc::clone(&router);
+        handles.push(tokio::spawn(async move {
+            let sent = Instant::now();
+            let status = replay(router.as_ref(), write).await;
+            Outcome {
+                status,
+                latency: sent.elapsed(),
+                lag: sent.duration_since(scheduled),
+            }
+        }));
+    }
+    let mut outcomes = Vec::with_capacity(handles.len());
+    for handle in handles {
+        outcomes.push(handle.await.expect("replay task panicked"));
+    }
+    let elapsed = start.elapsed();
+
+    print_report(&outcomes, span, elapsed, simulation.speed, &metrics);
+
+    router.shutdown();
+    router.join().await;
+    Ok(())
+}
+
+/// Send `write` to the HTTP write API of `router`, returning the response
+/// status.
+async fn replay(router: &dyn ServerType, write: RecordedWrite) -> StatusCode {
+    // Namespaces are named `<org>_<bucket>`, where neither contains an
+    // underscore.
+    let (org, bucket) = match write.namespace.split_once('_') {
+        Some(org_and_bucket) => org_and_bucket,
+        None => {
+            warn!(
+                namespace = %write.namespace,
+                "skipping write to namespace not addressable by org and bucket"
+            );
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!(
+            "http://simulation/api/v2/write?org={org}&bucket={bucket}"
+        ))
+        .body(Body::from(write.line_protocol))
+        .expect("valid request");
+
+    match router.route_http_request(request).await {
+        Ok(response) => response.status(),
+        Err(e) => e.to_http_api_error().response().status(),
+    }
+}
+
+fn print_report(
+    outcomes: &[Outcome],
+    span: Duration,
+    elapsed: Duration,
+    speed: f64,
+    metrics: &metric::Registry,
+) {
+    println!(
+        "Replayed {} writes recorded over {span:?} in {elapsed:?} (target {:?} at {speed}x)",
+        outcomes.len(),
+        span.div_f64(speed)
+    );
+
+    let mut statuses: BTreeMap<StatusCode, usize> = BTreeMap::new();
+    for outcome in outcomes {
+        *statuses.entry(outcome.status).or_default() += 1;
+    }
+    for (status, count) in &statuses {
+        println!("  {status}: {count} writes");
+    }
+
+    let mut latencies: Vec<_> = outcomes.iter().map(|outcome| outcome.latency).collect();
+    latencies.sort_unstable();
+    println!(
+        "Latency: p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+        percentile(&latencies, 50),
+        percentile(&latencies, 90),
+        percentile(&latencies, 99),
+        latencies[latencies.len() - 1],
+    );
+    let max_lag = outcomes
+        .iter()
+        .map(|outcome| outcome.lag)
+        .max()
+        .unwrap_or_default();
+    println!("Writes were sent up to {max_lag:?} later than scheduled");
+
+    let total_latency: Duration = latencies.iter().sum();
+    let stages = stage_durations(metrics);
+    println!("Time spent per stage:");
+    for (stage, (total, count)) in &stages {
+        println!(
+            "  {stage}: {total:?} in {count} calls ({:.1}% of the request latency)",
+            100.0 * total.as_secs_f64() / total_latency.as_secs_f64().max(f64::MIN_POSITIVE)
+        );
+    }
+
+    let bottleneck = stages
+        .iter()
+        .filter(|(stage, _)| !ENCLOSING_STAGES.contains(&stage.as_str()))
+        .max_by_key(|(_, (total, _))| *total);
+    if let Some((stage, _)) = bottleneck {
+        println!("Bottleneck: {stage}");
+    }
+    if let Some(rejected) = statuses.get(&StatusCode::TOO_MANY_REQUESTS) {
+        println!(
+            "{rejected} writes exceeded the limit of concurrent requests (--max-http-requests)"
+        );
+    }
+}
+
+/// Sum the duration and number of the successful calls of each write path
+/// stage: line protocol parsing and each instrumented DML handler.
+fn stage_durations(metrics: &metric::Registry) -> BTreeMap<String, (Duration, u64)> {
+    let mut reporter = RawReporter::default();
+    metrics.report(&mut reporter);
+
+    let mut stages: BTreeMap<String, (Duration, u64)> = BTreeMap::new();
+    if let Some(Observation::DurationHistogram(parse)) = reporter
+        .metric("http_line_protocol_parse_duration")
+        .and_then(|set| set.observations.first())
+        .map(|(_, observation)| observation)
+    {
+        stages.insert(
+            "line_protocol_parse".to_string(),
+            (parse.total, parse.sample_count()),
+        );
+    }
+
+    let handlers = reporter
+        .metric("dml_handler_write_duration")
+        .map(|set| set.observations.as_slice())
+        .unwrap_or_default();
+    for (attributes, observation) in handlers {
+        let attribute = |key: &str| {
+            attributes
+                .iter()
+                .find(|(k, _)| **k == key)
+                .map(|(_, v)| v.as_ref())
+        };
+        let (handler, histogram) = match (attribute("handler"), attribute("result"), observation) {
+            (Some(handler), Some("success"), Observation::DurationHistogram(histogram)) => {
+                (handler, histogram)
+            }
+            _ => continue,
+        };
+
+        // request metrics may be labelled by namespace
+        let stage = stages.entry(handler.to_string()).or_default();
+        stage.0 += histogram.total;
+        stage.1 += histogram.sample_count();
+    }
+
+    stages
+}
+
+/// Read the write audit records of the logfmt log at `path`, skipping all
+/// other log lines.
+fn read_audit_log(path: &Path) -> Result<Vec<RecordedWrite>> {
+    let read_error = |source| Error::ReadAuditLog {
+        path: path.to_path_buf(),
+        source,
+    };
+    let file = File::open(path).map_err(read_error)?;
+
+    let mut writes = vec![];
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(read_error)?;
+        if let Some(write) =
+            parse_audit_record(&line).map_err(|message| Error::InvalidAuditRecord {
+                line: index + 1,
+                message,
+            })?
+        {
+            writes.push(write);
+        }
+    }
+
+    info!(path=%path.display(), writes = writes.len(), "read write audit log");
+    Ok(writes)
+}
+
+/// Parse a write audit record logged by the router, returning `None` for
+/// other log lines.
+fn parse_audit_record(line: &str) -> Result<Option<RecordedWrite>, String> {
+    let fields = parse_logfmt(line);
+    if fields.get("msg").map(String::as_str) != Some("write audit") {
+        return Ok(None);
+    }
+
+    let field = |key: &str| {
+        fields
+            .get(key)
+            .map(String::as_str)
+            .ok_or_else(|| format!("missing field {key}"))
+    };
+    let rows: usize = field("rows")?
+        .parse()
+        .map_err(|e| format!("invalid rows: {e}"))?;
+    let bytes: usize = field("bytes")?
+        .parse()
+        .map_err(|e| format!("invalid bytes: {e}"))?;
+    let time =
+        Time::from_rfc3339(field("timestamp")?).map_err(|e| format!("invalid timestamp: {e}"))?;
+
+    Ok(Some(RecordedWrite {
+        time,
+        namespace: field("namespace")?.to_string(),
+        line_protocol: synthesize_line_protocol(field("table")?, rows, bytes, time),
+    }))
+}
+
+/// Split a logfmt line into its fields, unquoting quoted values.
+fn parse_logfmt(line: &str) -> HashMap<&str, String> {
+    let mut fields = HashMap::new();
+    let mut rest = line.trim_start();
+
+    while let Some((key, value)) = rest.split_once('=') {
+        let mut value_chars = value.char_indices();
+        let (value, remainder) = if value.starts_with('"') {
+            value_chars.next();
+            let mut unquoted = String::new();
+            let mut end = value.len();
+            while let Some((i, c)) = value_chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some((_, escaped)) = value_chars.next() {
+                            unquoted.push(escaped);
+                        }
+                    }
+                    '"' => {
+                        end = i + 1;
+                        break;
+                    }
+                    c => unquoted.push(c),
+                }
+            }
+            (unquoted, &value[end..])
+        } else {
+            let end = value.find(' ').unwrap_or(value.len());
+            (value[..end].to_string(), &value[end..])
+        };
+
+        fields.insert(key, value);
+        rest = remainder.trim_start();
+    }
+
+    fields
+}
+
+/// Number of bytes of a synthesized row besides the padding: an integer field
+/// and the timestamp.
+const SYNTHESIZED_ROW_SIZE: usize = 16;
+
+/// Synthesize `rows` lines of line protocol for `table` with about `bytes`
+/// bytes of data in total, and consecutive timestamps starting at `time`.
+fn synthesize_line_protocol(table: &str, rows: usize, bytes: usize, time: Time) -> String {
+    let table = table
+        .replace(',', "\\,")
+        .replace(' ', "\\ ")
+        .replace('=', "\\=");
+    let padding = "x".repeat((bytes / rows.max(1)).saturating_sub(SYNTHESIZED_ROW_SIZE));
+    let start = time.timestamp_nanos();
+
+    let mut lp = String::new();
+    for row in 0..rows {
+        writeln!(
+            lp,
+            "{table} row={row}i,padding=\"{padding}\" {}",
+            start + row as i64
+        )
+        .unwrap();
+    }
+    lp
+}
+
+/// Read all writes retained in the configured write buffer topic, resolving
+/// the namespace and table names through the configured catalog.
+async fn read_write_buffer(config: &Config) -> Result<Vec<RecordedWrite>> {
+    let metrics = Arc::new(metric::Registry::default());
+    let catalog = config
+        .catalog_dsn
+        .get_catalog("router_simulation", Arc::clone(&metrics))
+        .await?;
+    let mut repos = catalog.repositories().await;
+    let namespaces: HashMap<NamespaceId, String> = repos
+        .namespaces()
+        .list()
+        .await?
+        .into_iter()
+        .map(|namespace| (namespace.id, namespace.name))
+        .collect();
+    let tables: HashMap<TableId, String> = repos
+        .tables()
+        .list()
+        .await?
+        .into_iter()
+        .map(|table| (table.id, table.name))
+        .collect();
+    drop(repos);
+
+    let reader = config
+        .write_buffer_config
+        .reading(Arc::clone(&metrics), None, None)
+        .await?;
+
+    let mut writes = vec![];
+    let mut skipped = 0;
+    for shard_index in reader.shard_indexes() {
+        let high_watermark = reader.fetch_high_watermark(shard_index).await?.get();
+        if high_watermark == 0 {
+            continue;
+        }
+
+        let mut handler = reader.stream_handler(shard_index).await?;
+        handler.reset_to_earliest();
+        let mut stream = handler.stream().await;
+        while let Some(op) = stream.next().await {
+            let op = op?;
+            let sequence_number = op
+                .meta()
+                .sequence()
+                .map(|sequence| sequence.sequence_number.get())
+                .unwrap_or_default();
+
+            if let DmlOperation::Write(write) = op {
+                match recorded_write(&write, &namespaces, &tables)? {
+                    Some(write) => writes.push(write),
+                    None => skipped += 1,
+                }
+            }
+
+            if sequence_number + 1 >= high_watermark {
+                break;
+            }
+        }
+    }
+
+    if skipped > 0 {
+        warn!(
+            skipped,
+            "skipped writes without producer timestamp or to deleted namespaces or tables"
+        );
+    }
+    info!(
+        topic = config.write_buffer_config.topic(),
+        writes = writes.len(),
+        "read write buffer topic"
+    );
+    Ok(writes)
+}
+
+/// Convert `write` to line protocol, returning `None` if its namespace or a
+/// table is unknown or it lacks a producer timestamp.
+fn recorded_write(
+    write: &DmlWrite,
+    namespaces: &HashMap<NamespaceId, String>,
+    tables: &HashMap<TableId, String>,
+) -> Result<Option<RecordedWrite>> {
+    let (time, namespace) = match (
+        write.meta().producer_ts(),
+        namespaces.get(&write.namespace_id()),
+    ) {
+        (Some(time), Some(namespace)) => (time, namespace),
+        _ => return Ok(None),
+    };
+
+    let mut lp = vec![];
+    for (table_id, batch) in write.tables() {
+        let table = match tables.get(table_id) {
+            Some(table) => table,
+            None => return Ok(None),
+        };
+        lp.extend(batch_line_protocol(table, batch)?);
+    }
+
+    Ok(Some(RecordedWrite {
+        time,
+        namespace: namespace.clone(),
+        line_protocol: String::from_utf8(lp).expect("line protocol builder produces UTF-8"),
+    }))
+}
+
+fn batch_line_protocol(table: &str, batch: &MutableBatch) -> Result<Vec<u8>> {
+    let schema = batch.schema(Projection::All)?;
+    let record_batch = batch.to_arrow(Projection::All)?;
+    Ok(parquet_to_line_protocol::convert_batch(
+        table,
+        &schema,
+        &record_batch,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_audit_record() {
+        let line = r#"level=info msg="write audit" namespace=bananas_test table=cpu rows=2 bytes=100 trace_id="Some(\"4d2\")" timestamp=2022-12-20T10:00:00+00:00 target="write_audit" location="router/src/dml_handlers/write_audit.rs:60" time=1671530400000000000"#;
+        let write = parse_audit_record(line).unwrap().unwrap();
+
+        let time = Time::from_rfc3339("2022-12-20T10:00:00+00:00").unwrap();
+        assert_eq!(write.time, time);
+        assert_eq!(write.namespace, "bananas_test");
+        assert_eq!(
+            write.line_protocol,
+            synthesize_line_protocol("cpu", 2, 100, time)
+        );
+
+        let other = r#"level=info msg="starting router" time=1671530400000000000"#;
+        assert_eq!(parse_audit_record(other).unwrap(), None);
+
+        let invalid = r#"level=info msg="write audit" namespace=bananas_test table=cpu rows=2"#;
+        assert_eq!(
+            parse_audit_record(invalid).unwrap_err(),
+            "missing field bytes"
+        );
+    }
+
+    #[test]
+    fn test_parse_logfmt() {
+        let fields = parse_logfmt(r#"a=1 b="two words" c="escaped \"quote\"" d= e=5"#);
+        assert_eq!(fields["a"], "1");
+        assert_eq!(fields["b"], "two words");
+        assert_eq!(fields["c"], r#"escaped "quote""#);
+        assert_eq!(fields["d"], "");
+        assert_eq!(fields["e"], "5");
+    }
+
+    #[test]
+    fn test_synthesize_line_protocol() {
+        let time = Time::from_timestamp_nanos(1_000);
+        let lp = synthesize_line_protocol("my table", 2, 40, time);
+        assert_eq!(
+            lp,
+            "my\\ table row=0i,padding=\"xxxx\" 1000\n\
+             my\\ table row=1i,padding=\"xxxx\" 1001\n"
+        );
+
+        assert_eq!(synthesize_line_protocol("cpu", 0, 100, time), "");
+    }
+
+    #[test]
+    fn test_parse_speed() {
+        assert_eq!(parse_speed("2.5").unwrap(), 2.5);
+        assert!(parse_speed("0").is_err());
+        assert!(parse_speed("-1").is_err());
+        assert!(parse_speed("inf").is_err());
+        assert!(parse_speed("fast").is_err());
+    }
+}
@@ -15,8 +15,11 @@ mod repl_command;
 #[derive(Debug, clap::Parser)]
 pub struct Config {
     // TODO add an option to avoid saving history
+    /// Namespace to query on startup. Can be changed using the
+    /// `USE NAMESPACE` command
+    #[clap(short, long, action)]
+    namespace: Option<String>,
 
-    // TODO add an option to specify the default database (rather than having to set it via USE DATABASE)
     /// Format to use for output. Can be overridden using
     /// `SET FORMAT` command
     ///
@@ -53,6 +56,10 @@ pub async fn command(connection: Connection, config: Config) -> Result<()> {
 
     repl.set_output_format(config.format).context(ReplSnafu)?;
 
+    if let Some(namespace) = config.namespace {
+        repl.use_namespace(namespace);
+    }
+
     repl.run().await.context(ReplSnafu)
 }
 
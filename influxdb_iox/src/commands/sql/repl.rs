@@ -1,4 +1,12 @@
-use std::{borrow::Cow, convert::TryInto, path::PathBuf, sync::Arc, time::Instant};
+use std::{
+    borrow::Cow,
+    convert::TryInto,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Instant,
+};
 
 use arrow::{
     array::{ArrayRef, Int64Array, StringArray},
@@ -45,6 +53,27 @@ pub enum Error {
 
     #[snafu(display("Cannot create REPL: {}", source))]
     ReplCreation { source: ReadlineError },
+
+    #[snafu(display("Error: no query results to save"))]
+    NoResults,
+
+    #[snafu(display(
+        "Cannot save results to '{}': expected a .parquet, .csv or .json file",
+        path.display()
+    ))]
+    UnknownFileFormat { path: PathBuf },
+
+    #[snafu(display("Error saving results to '{}': {}", path.display(), source))]
+    SavingResults {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[snafu(display("Error saving results to '{}': {}", path.display(), source))]
+    SavingParquet {
+        path: PathBuf,
+        source: parquet::errors::ParquetError,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -77,7 +106,8 @@ impl rustyline::validate::Validator for RustylineHelper {
     ) -> rustyline::Result<rustyline::validate::ValidationResult> {
         let input = ctx.input();
 
-        if input.trim_end().ends_with(';') {
+        // Backslash commands are complete without a terminating semicolon
+        if input.trim_end().ends_with(';') || input.trim_start().starts_with('\\') {
             match ReplCommand::try_from(input) {
                 Ok(_) => Ok(rustyline::validate::ValidationResult::Valid(None)),
                 Err(err) => Ok(rustyline::validate::ValidationResult::Invalid(Some(err))),
@@ -173,6 +203,12 @@ pub struct Repl {
 
     /// Formatter to use to format query results
     output_format: QueryOutputFormat,
+
+    /// Whether to print how long each query takes
+    timing: bool,
+
+    /// The results of the last query, which can be saved to a file
+    last_results: Vec<RecordBatch>,
 }
 
 impl Repl {
@@ -204,6 +240,8 @@ impl Repl {
             flight_client,
             query_engine: None,
             output_format,
+            timing: true,
+            last_results: vec![],
         })
     }
 
@@ -236,6 +274,15 @@ impl Repl {
                 ReplCommand::SetFormat { format } => {
                     self.set_output_format(format)?;
                 }
+                ReplCommand::ToggleTiming => {
+                    self.timing = !self.timing;
+                    println!("Timing is {}", if self.timing { "on" } else { "off" });
+                }
+                ReplCommand::SaveResults { path } => {
+                    self.save_results(Path::new(&path))
+                        .map_err(|e| println!("{}", e))
+                        .ok();
+                }
             }
         }
     }
@@ -306,10 +353,62 @@ impl Repl {
         let end = Instant::now();
         self.print_results(&batches)?;
 
+        if self.timing {
+            println!(
+                "Returned {} in {:?}",
+                Self::row_summary(&batches),
+                end - start
+            );
+        } else {
+            println!("Returned {}", Self::row_summary(&batches));
+        }
+
+        self.last_results = batches;
+        Ok(())
+    }
+
+    /// Saves the results of the last query to `path`, in the format given by its extension
+    fn save_results(&self, path: &Path) -> Result<()> {
+        if self.last_results.is_empty() {
+            return Err(Error::NoResults);
+        }
+
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let format = match extension.as_deref() {
+            Some("parquet") => None,
+            Some("csv") => Some(QueryOutputFormat::Csv),
+            Some("json") => Some(QueryOutputFormat::Json),
+            _ => return UnknownFileFormatSnafu { path }.fail(),
+        };
+
+        let file = File::create(path).context(SavingResultsSnafu { path })?;
+        match format {
+            None => {
+                let mut writer =
+                    parquet::arrow::ArrowWriter::try_new(file, self.last_results[0].schema(), None)
+                        .context(SavingParquetSnafu { path })?;
+                for batch in &self.last_results {
+                    writer.write(batch).context(SavingParquetSnafu { path })?;
+                }
+                writer.close().context(SavingParquetSnafu { path })?;
+            }
+            Some(format) => {
+                let formatted = format
+                    .format(&self.last_results)
+                    .context(FormattingResultsSnafu)?;
+                (&file)
+                    .write_all(formatted.as_bytes())
+                    .context(SavingResultsSnafu { path })?;
+            }
+        }
+
         println!(
-            "Returned {} in {:?}",
-            Self::row_summary(&batches),
-            end - start
+            "Saved {} to {}",
+            Self::row_summary(&self.last_results),
+            path.display()
         );
         Ok(())
     }
@@ -326,7 +425,7 @@ impl Repl {
         }
     }
 
-    fn use_namespace(&mut self, db_name: String) {
+    pub fn use_namespace(&mut self, db_name: String) {
         info!(%db_name, "setting current namespace");
         println!("You are now in remote mode, querying namespace {}", db_name);
         self.set_query_engine(QueryEngine::Remote(db_name));
@@ -398,6 +497,11 @@ async fn scrape_query(
         .perform_query(ReadInfo {
             namespace_name: db_name.to_string(),
             sql_query: query.to_string(),
+            max_unpersisted_staleness_ns: None,
+            additional_namespaces: vec![],
+            params: vec![],
+            include_statistics: false,
+            debug_parquet_file_ids: vec![],
         })
         .await
         .context(RunningRemoteQuerySnafu)?;
@@ -8,6 +8,8 @@ pub enum ReplCommand {
     SetFormat { format: String },
     UseNamespace { db_name: String },
     SqlCommand { sql: String },
+    ToggleTiming,
+    SaveResults { path: String },
     Exit,
 }
 
@@ -60,6 +62,11 @@ impl TryFrom<&str> for ReplCommand {
                 warn!(%extra_content, "ignoring tokens after 'help'");
                 Ok(Self::Help)
             }
+            ["\\timing"] => Ok(Self::ToggleTiming),
+            ["save"] => Err("path not specified. Usage: SAVE <path>".to_string()),
+            ["save", _path] => Ok(Self::SaveResults {
+                path: raw_commands[1].to_string(),
+            }),
             ["exit"] => Ok(Self::Exit),
             ["quit"] => Ok(Self::Exit),
             ["use", "namespace"] => {
@@ -102,6 +109,11 @@ USE NAMESPACE <name>: Set the current remote namespace to name
 
 SET FORMAT <format>: Set the output format to Pretty, csv or json
 
+SAVE <path>: Save the results of the last query to a file, as parquet, csv
+  or json depending on the extension of the path
+
+\timing: Toggle printing how long each query takes
+
 [EXIT | QUIT]: Quit this session and exit the program
 
 # Examples: use remote namespace foo
@@ -228,6 +240,30 @@ mod tests {
         assert_eq!("blah".try_into(), expected);
     }
 
+    #[test]
+    fn timing() {
+        let expected = Ok(ReplCommand::ToggleTiming);
+        assert_eq!("\\timing".try_into(), expected);
+        assert_eq!(" \\timing;".try_into(), expected);
+        assert_eq!("\\TIMING".try_into(), expected);
+    }
+
+    #[test]
+    fn save_results() {
+        let expected = Ok(ReplCommand::SaveResults {
+            path: "Results.parquet".to_string(),
+        });
+        assert_eq!("save Results.parquet".try_into(), expected);
+        assert_eq!("SAVE Results.parquet;".try_into(), expected);
+
+        let expected: Result<ReplCommand, String> =
+            Err("path not specified. Usage: SAVE <path>".to_string());
+        assert_eq!("save;".try_into(), expected);
+
+        let expected = sql_cmd("save foo bar");
+        assert_eq!("save foo bar".try_into(), expected);
+    }
+
     #[test]
     fn exit() {
         let expected = Ok(ReplCommand::Exit);
@@ -0,0 +1,94 @@
+//! This module implements the `token` CLI command
+
+use data_types::ApiTokenPermission;
+use influxdb_iox_client::{
+    authz::{self, generated_types::Permission},
+    connection::Connection,
+};
+use thiserror::Error;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("JSON Serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("Client error: {0}")]
+    ClientError(#[from] influxdb_iox_client::error::Error),
+}
+
+/// Manage the API tokens authorizing requests to routers and queriers.
+///
+/// If authorization is enabled, these commands require a token with the admin permission, e.g.
+/// `--header authorization="Bearer <token>"`.
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+/// Create an API token and print its secret, which cannot be retrieved again
+#[derive(Debug, clap::Parser)]
+struct Create {
+    /// The actions the token allows: read, write (includes read) or admin (includes write and
+    /// the management of tokens)
+    #[clap(action, long)]
+    permission: ApiTokenPermission,
+
+    /// The namespace the token is scoped to. Tokens without a namespace are valid for all
+    /// namespaces.
+    #[clap(action, long)]
+    namespace: Option<String>,
+
+    /// Human-readable description of the token, e.g. its owner
+    #[clap(action, long, default_value = "")]
+    description: String,
+}
+
+/// Revoke an API token
+#[derive(Debug, clap::Parser)]
+struct Revoke {
+    /// The ID of the token
+    #[clap(action)]
+    id: i64,
+}
+
+/// All possible subcommands for token
+#[derive(Debug, clap::Parser)]
+enum Command {
+    Create(Create),
+
+    /// List all API tokens, including revoked ones
+    List,
+
+    Revoke(Revoke),
+}
+
+pub async fn command(connection: Connection, config: Config) -> Result<(), Error> {
+    let mut client = authz::Client::new(connection);
+
+    match config.command {
+        Command::Create(create) => {
+            let permission = match create.permission {
+                ApiTokenPermission::Read => Permission::Read,
+                ApiTokenPermission::Write => Permission::Write,
+                ApiTokenPermission::Admin => Permission::Admin,
+            };
+            let (api_token, token) = client
+                .create_token(create.namespace.as_deref(), permission, &create.description)
+                .await?;
+            eprintln!("{}", serde_json::to_string_pretty(&api_token)?);
+            println!("{token}");
+        }
+        Command::List => {
+            let api_tokens = client.list_tokens().await?;
+            println!("{}", serde_json::to_string_pretty(&api_tokens)?);
+        }
+        Command::Revoke(revoke) => {
+            let api_token = client.revoke_token(revoke.id).await?;
+            println!("{}", serde_json::to_string_pretty(&api_token)?);
+        }
+    }
+
+    Ok(())
+}
@@ -2,13 +2,7 @@ use futures::StreamExt;
 use influxdb_iox_client::{connection::Connection, write};
 use observability_deps::tracing::info;
 use snafu::{ensure, OptionExt, ResultExt, Snafu};
-use std::{
-    fs::File,
-    io::{BufReader, Read},
-    num::NonZeroUsize,
-    path::PathBuf,
-    time::Instant,
-};
+use std::{fs::File, io::Read, num::NonZeroUsize, path::PathBuf, time::Instant};
 
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Snafu)]
@@ -59,12 +53,19 @@ pub struct Config {
     #[clap(action, long, short = 'c', default_value = "10")]
     max_concurrent_uploads: usize,
 
+    /// If specified, restricts the number of points (lines of line
+    /// protocol) written per second on average to this many, to limit the
+    /// load a backfill puts on the server
+    #[clap(action, long)]
+    max_points_per_second: Option<NonZeroUsize>,
+
     /// The namespace into which to write
     #[clap(action)]
     namespace: String,
 
     /// File(s) with data to load. Currently supported formats are .lp (line protocol),
-    /// .parquet (IOx created parquet files), and .gz (gzipped line protocol)
+    /// .parquet (IOx created parquet files), and gzipped line protocol, which is
+    /// detected from the file contents regardless of the extension
     #[clap(action)]
     file_names: Vec<PathBuf>,
 }
@@ -77,6 +78,7 @@ pub async fn command(connection: Connection, config: Config) -> Result<()> {
         file_names,
         max_request_payload_size_bytes,
         max_concurrent_uploads,
+        max_points_per_second,
     } = config;
 
     let max_concurrent_uploads =
@@ -84,7 +86,10 @@ pub async fn command(connection: Connection, config: Config) -> Result<()> {
 
     info!(
         num_files = file_names.len(),
-        max_request_payload_size_bytes, max_concurrent_uploads, "Beginning upload"
+        max_request_payload_size_bytes,
+        max_concurrent_uploads,
+        ?max_points_per_second,
+        "Beginning upload"
     );
 
     // first pass is to check that all the files exist and can be
@@ -130,7 +135,8 @@ pub async fn command(connection: Connection, config: Config) -> Result<()> {
 
     let mut client = write::Client::new(connection)
         .with_max_concurrent_uploads(max_concurrent_uploads)
-        .with_max_request_payload_size_bytes(Some(max_request_payload_size_bytes));
+        .with_max_request_payload_size_bytes(Some(max_request_payload_size_bytes))
+        .with_max_points_per_second(max_points_per_second);
 
     let total_bytes = client
         .write_lp_stream(namespace, lp_stream)
@@ -145,11 +151,14 @@ pub async fn command(connection: Connection, config: Config) -> Result<()> {
     Ok(())
 }
 
+/// The magic bytes every gzip file starts with
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
 /// Reads the contents of `file_name into a string
 ///
 /// .parquet files --> iox parquet files (convert to parquet)
-/// .gz  --> treated as gzipped line protocol
-/// .lp (or anything else) --> treated as raw line protocol
+/// gzip contents (regardless of extension) --> treated as gzipped line protocol
+/// anything else --> treated as raw line protocol
 ///
 async fn slurp_file(file_name: PathBuf) -> Result<String> {
     let file_name = &file_name;
@@ -175,27 +184,26 @@ async fn slurp_file(file_name: PathBuf) -> Result<String> {
             );
             Ok(lp_data)
         }
-        // decompress as gz
-        Some(extension) if extension.to_string_lossy() == "gz" => {
-            let mut lp_data = String::new();
-            let reader =
-                BufReader::new(File::open(file_name).context(ReadingFileSnafu { file_name })?);
-
-            flate2::read::GzDecoder::new(reader)
-                .read_to_string(&mut lp_data)
-                .context(GzSnafu { file_name })?;
-
-            info!(
-                ?file_name,
-                file_size_bytes = lp_data.len(),
-                "Buffered line protocol from gzipped line protocol file"
-            );
-            Ok(lp_data)
-        }
-        // anything else, treat as line protocol
         Some(_) | None => {
-            let lp_data =
-                std::fs::read_to_string(file_name).context(ReadingFileSnafu { file_name })?;
+            let data = std::fs::read(file_name).context(ReadingFileSnafu { file_name })?;
+
+            // decompress as gz
+            if data.starts_with(&GZIP_MAGIC) {
+                let mut lp_data = String::new();
+                flate2::read::GzDecoder::new(data.as_slice())
+                    .read_to_string(&mut lp_data)
+                    .context(GzSnafu { file_name })?;
+
+                info!(
+                    ?file_name,
+                    file_size_bytes = lp_data.len(),
+                    "Buffered line protocol from gzipped line protocol file"
+                );
+                return Ok(lp_data);
+            }
+
+            // anything else, treat as line protocol
+            let lp_data = String::from_utf8(data).context(InvalidUtf8Snafu)?;
 
             info!(
                 ?file_name,
@@ -214,6 +222,26 @@ mod test {
 
     use super::*;
 
+    #[tokio::test]
+    async fn slurp_detects_gzip() {
+        use std::io::Write;
+
+        let lp = "m,t=foo f=4\nm,t=bar f=3";
+        let dir = tempfile::tempdir().unwrap();
+
+        // gzipped line protocol is detected without a .gz extension
+        let gz_file = dir.path().join("data.lp");
+        let mut encoder =
+            flate2::write::GzEncoder::new(File::create(&gz_file).unwrap(), Default::default());
+        encoder.write_all(lp.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+        assert_eq!(slurp_file(gz_file).await.unwrap(), lp);
+
+        let lp_file = dir.path().join("data.gz");
+        std::fs::write(&lp_file, lp).unwrap();
+        assert_eq!(slurp_file(lp_file).await.unwrap(), lp);
+    }
+
     #[test]
     fn command_default_is_same_as_client_default() {
         let config = Config::try_parse_from(vec!["my_db", "file1"]).unwrap();
@@ -27,6 +27,7 @@ use std::{
 use tokio::runtime::Runtime;
 
 mod commands {
+    pub mod backup;
     pub mod catalog;
     pub mod compactor;
     pub mod debug;
@@ -35,9 +36,11 @@ mod commands {
     pub mod query;
     pub mod query_ingester;
     pub mod remote;
+    pub mod restore;
     pub mod run;
     pub mod sql;
     pub mod storage;
+    pub mod token;
     pub mod tracing;
     pub mod write;
 }
@@ -204,6 +207,15 @@ enum Command {
 
     /// Various commands for namespace manipulation
     Namespace(commands::namespace::Config),
+
+    /// Back up catalog rows and parquet files
+    Backup(commands::backup::Config),
+
+    /// Restore catalog rows and parquet files from a backup
+    Restore(commands::restore::Config),
+
+    /// Manage the API tokens authorizing requests
+    Token(commands::token::Config),
 }
 
 fn main() -> Result<(), std::io::Error> {
@@ -361,6 +373,28 @@ fn main() -> Result<(), std::io::Error> {
                     std::process::exit(ReturnCode::Failure as _)
                 }
             }
+            Some(Command::Token(config)) => {
+                let _tracing_guard = handle_init_logs(init_simple_logs(log_verbose_count));
+                let connection = connection().await;
+                if let Err(e) = commands::token::command(connection, config).await {
+                    eprintln!("{}", e);
+                    std::process::exit(ReturnCode::Failure as _)
+                }
+            }
+            Some(Command::Backup(config)) => {
+                let _tracing_guard = handle_init_logs(init_simple_logs(log_verbose_count));
+                if let Err(e) = commands::backup::command(config).await {
+                    eprintln!("{}", e);
+                    std::process::exit(ReturnCode::Failure as _)
+                }
+            }
+            Some(Command::Restore(config)) => {
+                let _tracing_guard = handle_init_logs(init_simple_logs(log_verbose_count));
+                if let Err(e) = commands::restore::command(config).await {
+                    eprintln!("{}", e);
+                    std::process::exit(ReturnCode::Failure as _)
+                }
+            }
         }
     });
 
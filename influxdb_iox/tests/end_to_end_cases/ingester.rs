@@ -53,6 +53,7 @@ async fn ingester_flight_api() {
             partition_id,
             status: Some(PartitionStatus {
                 parquet_max_sequence_number: None,
+                max_sequence_number: None,
             })
         },
     );
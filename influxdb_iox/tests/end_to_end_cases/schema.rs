@@ -39,6 +39,14 @@ async fn ingester_schema_client() {
                     column_names.sort_unstable();
 
                     assert_eq!(column_names, &["tag1", "tag2", "time", "val"]);
+
+                    let response = client
+                        .get_table_schema(state.cluster().namespace(), "my_awesome_table")
+                        .await
+                        .expect("successful response");
+                    let column_names: Vec<_> =
+                        response.columns.iter().map(|c| c.name.as_str()).collect();
+                    assert_eq!(column_names, &["tag1", "tag2", "time", "val"]);
                 }
                 .boxed()
             })),
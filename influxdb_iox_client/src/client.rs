@@ -1,3 +1,6 @@
+/// Client for API token management API
+pub mod authz;
+
 /// Client for interacting with a remote catalog
 pub mod catalog;
 
@@ -10,6 +13,9 @@ pub mod delete;
 /// Errors for the client
 pub mod error;
 
+/// Client for exporting query results as CSV or JSON lines
+pub mod export;
+
 #[cfg(feature = "flight")]
 /// Client for query API (based on Arrow flight)
 pub mod flight;
@@ -20,6 +26,9 @@ pub mod health;
 /// Client for namespace API
 pub mod namespace;
 
+/// Client for long-running operations API
+pub mod operations;
+
 /// Client for schema API
 pub mod schema;
 
@@ -0,0 +1,67 @@
+use client_util::connection::GrpcConnection;
+
+use self::generated_types::{authz_service_client::AuthzServiceClient, *};
+use crate::connection::Connection;
+use crate::error::Error;
+use ::generated_types::google::OptionalField;
+
+/// Re-export generated_types
+pub mod generated_types {
+    pub use generated_types::influxdata::iox::authz::v1::*;
+}
+
+/// A basic client for managing API tokens.
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: AuthzServiceClient<GrpcConnection>,
+}
+
+impl Client {
+    /// Creates a new client with the provided connection
+    pub fn new(connection: Connection) -> Self {
+        Self {
+            inner: AuthzServiceClient::new(connection.into_grpc_connection()),
+        }
+    }
+
+    /// Create an API token allowing `permission` on `namespace`, or on all namespaces if
+    /// `None`.
+    ///
+    /// Returns the created token and the secret to authenticate requests with, which cannot
+    /// be retrieved again.
+    pub async fn create_token(
+        &mut self,
+        namespace: Option<&str>,
+        permission: Permission,
+        description: &str,
+    ) -> Result<(ApiToken, String), Error> {
+        let response = self
+            .inner
+            .create_token(CreateTokenRequest {
+                namespace: namespace.map(ToString::to_string),
+                permission: permission.into(),
+                description: description.to_string(),
+            })
+            .await?
+            .into_inner();
+
+        Ok((
+            response.api_token.unwrap_field("api_token")?,
+            response.token,
+        ))
+    }
+
+    /// List all API tokens, including revoked ones
+    pub async fn list_tokens(&mut self) -> Result<Vec<ApiToken>, Error> {
+        let response = self.inner.list_tokens(ListTokensRequest {}).await?;
+
+        Ok(response.into_inner().api_tokens)
+    }
+
+    /// Revoke the API token with the given ID
+    pub async fn revoke_token(&mut self, id: i64) -> Result<ApiToken, Error> {
+        let response = self.inner.revoke_token(RevokeTokenRequest { id }).await?;
+
+        Ok(response.into_inner().api_token.unwrap_field("api_token")?)
+    }
+}
@@ -66,4 +66,21 @@ impl Client {
 
         Ok(response.into_inner().parquet_files)
     }
+
+    /// Get how far the deletes of the table were applied, to tell when they are fully effective
+    pub async fn get_delete_status(
+        &mut self,
+        namespace_name: String,
+        table_name: String,
+    ) -> Result<Vec<DeleteStatus>, Error> {
+        let response = self
+            .inner
+            .get_delete_status(GetDeleteStatusRequest {
+                namespace_name,
+                table_name,
+            })
+            .await?;
+
+        Ok(response.into_inner().deletes)
+    }
 }
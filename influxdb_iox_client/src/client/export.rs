@@ -0,0 +1,108 @@
+use bytes::Bytes;
+use client_util::connection::HttpConnection;
+use futures_util::{stream::BoxStream, StreamExt, TryStreamExt};
+use reqwest::Method;
+
+use crate::{
+    connection::Connection,
+    error::{translate_response, Error},
+};
+
+/// The formats the results of a query can be exported in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// CSV, preceded by an annotation row with the types of the columns.
+    Csv,
+    /// One JSON object per row, separated by newlines.
+    JsonLines,
+}
+
+impl Format {
+    /// The name of the format in the `format` parameter of the query API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::JsonLines => "jsonl",
+        }
+    }
+}
+
+/// A client exporting the results of SQL queries as CSV or JSON lines, using
+/// the HTTP query API of the querier.
+///
+/// Prefer the [flight](crate::flight) client if Arrow can be consumed.
+///
+/// ```no_run
+/// #[tokio::main]
+/// # async fn main() {
+/// use futures_util::TryStreamExt;
+/// use influxdb_iox_client::{
+///     export::{Client, Format},
+///     connection::Builder,
+/// };
+///
+/// let connection = Builder::default()
+///     .build("http://127.0.0.1:8080")
+///     .await
+///     .unwrap();
+///
+/// let mut client = Client::new(connection);
+///
+/// let mut chunks = client
+///     .query("bananas", "select * from cpu", Format::Csv)
+///     .await
+///     .expect("failed to query IOx");
+/// while let Some(chunk) = chunks.try_next().await.expect("failed to read results") {
+///     print!("{}", String::from_utf8_lossy(&chunk));
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: HttpConnection,
+}
+
+impl Client {
+    /// Creates a new client with the provided connection
+    pub fn new(connection: Connection) -> Self {
+        Self {
+            inner: connection.into_http_connection(),
+        }
+    }
+
+    /// Run the SQL query `sql` against `namespace`, returning the results
+    /// encoded in `format` as they are streamed by the querier.
+    ///
+    /// Errors that occur after the querier started sending results end the
+    /// stream with an error.
+    pub async fn query(
+        &mut self,
+        namespace: impl AsRef<str> + Send,
+        sql: impl AsRef<str> + Send,
+        format: Format,
+    ) -> Result<BoxStream<'static, Result<Bytes, Error>>, Error> {
+        let query_url = format!("{}api/v3/query", self.inner.uri());
+
+        let response = self
+            .inner
+            .client()
+            .request(Method::GET, &query_url)
+            .query(&[
+                ("namespace", namespace.as_ref()),
+                ("q", sql.as_ref()),
+                ("format", format.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(Error::client)?;
+
+        // `translate_response` consumes the response to describe errors
+        if !response.status().is_success() {
+            return Err(translate_response(response)
+                .await
+                .expect_err("status is not success"));
+        }
+
+        Ok(response.bytes_stream().map_err(Error::client).boxed())
+    }
+}
@@ -1,4 +1,5 @@
-use ::generated_types::influxdata::iox::querier::v1::{AppMetadata, ReadInfo};
+use ::generated_types::influxdata::iox::querier::v1::{AppMetadata, QueryStatistics, ReadInfo};
+use std::collections::BTreeMap;
 use thiserror::Error;
 
 use arrow::{
@@ -84,7 +85,7 @@ pub enum Error {
 ///     connection::Builder,
 ///     flight::{
 ///         Client,
-///         generated_types::ReadInfo,
+///         generated_types::{query_param, QueryParam, ReadInfo},
 ///     },
 /// };
 ///
@@ -98,7 +99,15 @@ pub enum Error {
 /// let mut query_results = client
 ///     .perform_query(ReadInfo {
 ///         namespace_name: "my_database".to_string(),
-///         sql_query: "select * from cpu_load".to_string(),
+///         sql_query: "select * from cpu_load where time > $start".to_string(),
+///         max_unpersisted_staleness_ns: None,
+///         additional_namespaces: vec![],
+///         params: vec![QueryParam {
+///             name: "start".to_string(),
+///             value: Some(query_param::Value::TimestampValue(1_671_000_000_000_000_000)),
+///         }],
+///         include_statistics: true,
+///         debug_parquet_file_ids: vec![],
 ///     })
 ///     .await
 ///     .expect("query request should work");
@@ -108,6 +117,11 @@ pub enum Error {
 /// while let Some(data) = query_results.next().await.expect("valid batches") {
 ///     batches.push(data);
 /// }
+///
+/// // the statistics are available once all batches were received
+/// if let Some(statistics) = query_results.statistics() {
+///     println!("scanned {} rows", statistics.rows_scanned);
+/// }
 /// # }
 /// ```
 #[derive(Debug)]
@@ -142,6 +156,8 @@ impl Client {
 pub struct PerformQuery {
     inner: LowLevelPerformQuery<AppMetadata>,
     got_schema: bool,
+    statistics: Option<QueryStatistics>,
+    persisted_watermarks: BTreeMap<String, i64>,
 }
 
 impl PerformQuery {
@@ -151,6 +167,8 @@ impl PerformQuery {
         Ok(Self {
             inner,
             got_schema: false,
+            statistics: None,
+            persisted_watermarks: BTreeMap::new(),
         })
     }
 
@@ -160,18 +178,41 @@ impl PerformQuery {
         loop {
             match self.inner.next().await? {
                 None => return Ok(None),
-                Some((LowLevelMessage::Schema(_), _)) => {
+                Some((LowLevelMessage::Schema(_), app_metadata)) => {
                     if self.got_schema {
                         return Err(Error::UnexpectedSchemaChange);
                     }
                     self.got_schema = true;
+                    self.persisted_watermarks = app_metadata.persisted_watermarks;
                 }
                 Some((LowLevelMessage::RecordBatch(batch), _)) => return Ok(Some(batch)),
-                Some((LowLevelMessage::None, _)) => (),
+                Some((LowLevelMessage::None, app_metadata)) => {
+                    if let Some(statistics) = app_metadata.statistics {
+                        self.statistics = Some(statistics);
+                    }
+                }
             }
         }
     }
 
+    /// Returns the execution statistics of the query, if requested via
+    /// [`ReadInfo::include_statistics`].
+    ///
+    /// The statistics are sent after the last `RecordBatch`, so they are only available once
+    /// [`next`](Self::next) returned `None`.
+    pub fn statistics(&self) -> Option<&QueryStatistics> {
+        self.statistics.as_ref()
+    }
+
+    /// Returns the newest timestamp of the persisted data read from each table, by table name,
+    /// if the querier only serves persisted data (a read replica).
+    ///
+    /// The watermarks are sent before the first `RecordBatch`, so they are available once
+    /// [`next`](Self::next) returned for the first time.
+    pub fn persisted_watermarks(&self) -> &BTreeMap<String, i64> {
+        &self.persisted_watermarks
+    }
+
     /// Collect and return all `RecordBatch`es into a `Vec`
     pub async fn collect(&mut self) -> Result<Vec<RecordBatch>, Error> {
         let mut batches = Vec::new();
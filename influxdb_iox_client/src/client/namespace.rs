@@ -25,10 +25,21 @@ impl Client {
     }
 
     /// Get the available namespaces
+    ///
+    /// The namespaces are streamed by the server in pages.
     pub async fn get_namespaces(&mut self) -> Result<Vec<Namespace>, Error> {
-        let response = self.inner.get_namespaces(GetNamespacesRequest {}).await?;
+        let mut pages = self
+            .inner
+            .list_namespaces(ListNamespacesRequest {})
+            .await?
+            .into_inner();
+
+        let mut namespaces = vec![];
+        while let Some(page) = pages.message().await? {
+            namespaces.extend(page.namespaces);
+        }
 
-        Ok(response.into_inner().namespaces)
+        Ok(namespaces)
     }
 
     /// Create a namespace
@@ -64,4 +75,59 @@ impl Client {
 
         Ok(response.into_inner().namespace.unwrap_field("namespace")?)
     }
+
+    /// Update the default and maximum query time ranges of a namespace
+    pub async fn update_namespace_query_ranges(
+        &mut self,
+        namespace: &str,
+        default_query_range_ns: Option<i64>,
+        max_query_range_ns: Option<i64>,
+    ) -> Result<Namespace, Error> {
+        let response = self
+            .inner
+            .update_namespace_query_ranges(UpdateNamespaceQueryRangesRequest {
+                name: namespace.to_string(),
+                default_query_range_ns,
+                max_query_range_ns,
+            })
+            .await?;
+
+        Ok(response.into_inner().namespace.unwrap_field("namespace")?)
+    }
+
+    /// Update the expiry of the individual rows of a namespace
+    pub async fn update_namespace_row_ttl(
+        &mut self,
+        namespace: &str,
+        row_ttl_ns: Option<i64>,
+        row_ttl_column: Option<String>,
+    ) -> Result<Namespace, Error> {
+        let response = self
+            .inner
+            .update_namespace_row_ttl(UpdateNamespaceRowTtlRequest {
+                name: namespace.to_string(),
+                row_ttl_ns,
+                row_ttl_column,
+            })
+            .await?;
+
+        Ok(response.into_inner().namespace.unwrap_field("namespace")?)
+    }
+
+    /// Assign a namespace to the write buffer topic named `topic`
+    pub async fn update_namespace_topic(
+        &mut self,
+        namespace: &str,
+        topic: &str,
+    ) -> Result<Namespace, Error> {
+        let response = self
+            .inner
+            .update_namespace_topic(UpdateNamespaceTopicRequest {
+                name: namespace.to_string(),
+                topic: topic.to_string(),
+            })
+            .await?;
+
+        Ok(response.into_inner().namespace.unwrap_field("namespace")?)
+    }
 }
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use client_util::connection::GrpcConnection;
+
+use self::generated_types::{operations_client::OperationsClient, *};
+use crate::connection::Connection;
+use crate::error::Error;
+
+/// Re-export generated_types
+pub mod generated_types {
+    pub use generated_types::google::longrunning::*;
+    pub use generated_types::influxdata::iox::operations::v1::*;
+}
+
+/// A client for watching and managing long-running IOx operations.
+///
+/// Operations are returned as [`IoxOperation`]s, which carry the decoded
+/// [`OperationMetadata`] of the operation, i.e. its progress.
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: OperationsClient<GrpcConnection>,
+}
+
+impl Client {
+    /// Creates a new client with the provided connection
+    pub fn new(connection: Connection) -> Self {
+        Self {
+            inner: OperationsClient::new(connection.into_grpc_connection()),
+        }
+    }
+
+    /// List the operations matching `filter`.
+    ///
+    /// An empty filter lists all operations. All pages of the server response are collected.
+    pub async fn list_operations(
+        &mut self,
+        filter: impl Into<String> + Send,
+    ) -> Result<Vec<IoxOperation>, Error> {
+        let filter = filter.into();
+        let mut operations = vec![];
+        let mut page_token = String::new();
+
+        loop {
+            let response = self
+                .inner
+                .list_operations(ListOperationsRequest {
+                    name: String::new(),
+                    filter: filter.clone(),
+                    page_size: 0,
+                    page_token,
+                })
+                .await?
+                .into_inner();
+
+            for operation in response.operations {
+                operations.push(operation.try_into()?);
+            }
+
+            if response.next_page_token.is_empty() {
+                return Ok(operations);
+            }
+            page_token = response.next_page_token;
+        }
+    }
+
+    /// Get the current state of the operation `id`.
+    pub async fn get_operation(&mut self, id: usize) -> Result<IoxOperation, Error> {
+        let response = self
+            .inner
+            .get_operation(GetOperationRequest {
+                name: id.to_string(),
+            })
+            .await?;
+
+        Ok(response.into_inner().try_into()?)
+    }
+
+    /// Cancel the operation `id`.
+    ///
+    /// Cancellation is best-effort: the operation may still complete. Use
+    /// [`get_operation`](Self::get_operation) or [`wait_operation`](Self::wait_operation) to
+    /// check its outcome.
+    pub async fn cancel_operation(&mut self, id: usize) -> Result<(), Error> {
+        self.inner
+            .cancel_operation(CancelOperationRequest {
+                name: id.to_string(),
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Wait until the operation `id` is done or `timeout` has passed, returning its latest
+    /// state.
+    ///
+    /// Without a timeout, the server decides how long to wait. Use [`IoxOperation::is_done`] to
+    /// check whether the operation completed.
+    pub async fn wait_operation(
+        &mut self,
+        id: usize,
+        timeout: Option<Duration>,
+    ) -> Result<IoxOperation, Error> {
+        let response = self
+            .inner
+            .wait_operation(WaitOperationRequest {
+                name: id.to_string(),
+                timeout: timeout.map(|timeout| ::generated_types::google::protobuf::Duration {
+                    seconds: timeout.as_secs() as i64,
+                    nanos: timeout.subsec_nanos() as i32,
+                }),
+            })
+            .await?;
+
+        Ok(response.into_inner().try_into()?)
+    }
+}
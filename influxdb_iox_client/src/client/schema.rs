@@ -35,4 +35,22 @@ impl Client {
 
         Ok(response.into_inner().schema.unwrap_field("schema")?)
     }
+
+    /// Get the schema of a single table of a namespace, with the metadata of
+    /// its columns.
+    pub async fn get_table_schema(
+        &mut self,
+        namespace: &str,
+        table: &str,
+    ) -> Result<GetTableSchemaResponse, Error> {
+        let response = self
+            .inner
+            .get_table_schema(GetTableSchemaRequest {
+                namespace: namespace.to_string(),
+                table: table.to_string(),
+            })
+            .await?;
+
+        Ok(response.into_inner())
+    }
 }
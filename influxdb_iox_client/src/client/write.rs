@@ -1,4 +1,4 @@
-use std::{fmt::Debug, num::NonZeroUsize, sync::Arc};
+use std::{fmt::Debug, num::NonZeroUsize, sync::Arc, time::Duration};
 
 use client_util::{connection::HttpConnection, namespace_translation::split_namespace};
 use futures_util::{future::BoxFuture, FutureExt, Stream, StreamExt, TryStreamExt};
@@ -8,6 +8,7 @@ use crate::{
     error::{translate_response, Error},
 };
 use reqwest::{Body, Method};
+use tokio::time::Instant;
 
 /// The default value for the maximum size of each request, in bytes
 pub const DEFAULT_MAX_REQUEST_PAYLOAD_SIZE_BYTES: Option<usize> = Some(1024 * 1024);
@@ -58,6 +59,11 @@ pub struct Client {
 
     /// Makes this many concurrent requests at a time. Defaults to 1
     max_concurrent_uploads: NonZeroUsize,
+
+    /// If `Some`, delays requests so that no more than this many points
+    /// (lines of line protocol) are written per second on average. If
+    /// `None`, does not restrict the rate of writes. Defaults to `None`
+    max_points_per_second: Option<NonZeroUsize>,
 }
 
 impl Client {
@@ -72,6 +78,7 @@ impl Client {
             inner,
             max_request_payload_size_bytes: DEFAULT_MAX_REQUEST_PAYLOAD_SIZE_BYTES,
             max_concurrent_uploads: NonZeroUsize::new(1).unwrap(),
+            max_points_per_second: None,
         }
     }
 
@@ -98,6 +105,16 @@ impl Client {
         }
     }
 
+    /// Restricts the number of points written per second, on average, to
+    /// `max_points_per_second`. If `None`, does not restrict the rate of
+    /// writes. Defaults to `None`.
+    pub fn with_max_points_per_second(self, max_points_per_second: Option<NonZeroUsize>) -> Self {
+        Self {
+            max_points_per_second,
+            ..self
+        }
+    }
+
     /// Write the [LineProtocol] formatted string in `lp_data` to
     /// namespace `namespace`.
     ///
@@ -136,6 +153,7 @@ impl Client {
 
         let max_concurrent_uploads: usize = self.max_concurrent_uploads.into();
         let max_request_payload_size_bytes = self.max_request_payload_size_bytes;
+        let mut rate_limiter = self.max_points_per_second.map(RateLimiter::new);
 
         // make a stream and process in parallel
         let results = sources
@@ -147,6 +165,18 @@ impl Client {
                     max_concurrent_uploads,
                 )
             })
+            // wait until the points of the request may be written
+            .then(move |source| {
+                let due = rate_limiter
+                    .as_mut()
+                    .map(|limiter| limiter.reserve(count_points(&source)));
+                async move {
+                    if let Some(due) = due {
+                        tokio::time::sleep_until(due).await;
+                    }
+                    source
+                }
+            })
             // do the actual write
             .map(|source| {
                 let org_id = org_id.to_string();
@@ -252,6 +282,48 @@ fn split_lp(
 
     tokio_stream::wrappers::ReceiverStream::new(rx)
 }
+
+/// Returns the number of points in the line protocol `lp`, ignoring empty
+/// lines and comments
+fn count_points(lp: &str) -> usize {
+    influxdb_line_protocol::split_lines(lp)
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .count()
+}
+
+/// Schedules requests so that at most `points_per_second` points are
+/// written per second on average
+#[derive(Debug)]
+struct RateLimiter {
+    points_per_second: NonZeroUsize,
+    /// When the first request was scheduled
+    start: Option<Instant>,
+    /// The number of points scheduled so far
+    points: usize,
+}
+
+impl RateLimiter {
+    fn new(points_per_second: NonZeroUsize) -> Self {
+        Self {
+            points_per_second,
+            start: None,
+            points: 0,
+        }
+    }
+
+    /// Schedule a request writing `points` points, returning when it may
+    /// be made: once all previously scheduled points would have been
+    /// written at the target rate
+    fn reserve(&mut self, points: usize) -> Instant {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        let due = start
+            + Duration::from_secs_f64(self.points as f64 / self.points_per_second.get() as f64);
+        self.points += points;
+        due
+    }
+}
+
 #[derive(Debug)]
 struct LineAccumulator {
     current_chunk: String,
@@ -389,6 +461,27 @@ mod tests {
         assert_eq!(num_bytes, 22);
     }
 
+    #[test]
+    fn test_count_points() {
+        assert_eq!(count_points(""), 0);
+        assert_eq!(count_points("m,t=foo f=4"), 1);
+        assert_eq!(
+            count_points("# comment\nm,t=foo f=4\n\n  m,t=bar f=\"a\\nb\"\n"),
+            2
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut limiter = RateLimiter::new(NonZeroUsize::new(10).unwrap());
+
+        // the first request is made immediately, later ones once the
+        // points before them would have been written at 10 points/s
+        let start = limiter.reserve(5);
+        assert_eq!(limiter.reserve(20), start + Duration::from_millis(500));
+        assert_eq!(limiter.reserve(1), start + Duration::from_millis(2500));
+    }
+
     #[derive(Debug, Clone, PartialEq)]
     struct MockRequest {
         org_id: String,
@@ -11,12 +11,44 @@ pub mod generated_types {
         write_info_service_client, write_info_service_server, GetWriteInfoRequest,
         GetWriteInfoResponse, ShardInfo, ShardStatus,
     };
+    pub use generated_types::influxdata::iox::write_summary::v1::{
+        PartitionWrite, ShardWrite, WriteSummary,
+    };
     pub use generated_types::write_info::merge_responses;
+    pub use generated_types::write_summary::{
+        decode_write_token, encode_write_token, DecodeWriteTokenError, WriteToken,
+        WRITE_TOKEN_VERSION,
+    };
 }
 
 /// A basic client for fetching information about write tokens from a
 /// single ingester.
 ///
+/// The write token itself can be parsed into a [`WriteToken`], listing the
+/// sequence numbers of the write in each shard:
+///
+/// ```
+/// use influxdb_iox_client::write_info::generated_types::{
+///     encode_write_token, ShardWrite, WriteSummary, WriteToken,
+/// };
+///
+/// # let token = encode_write_token(&WriteSummary {
+/// #     shards: vec![ShardWrite {
+/// #         shard_index: 1,
+/// #         sequence_numbers: vec![42],
+/// #         partitions: vec![],
+/// #     }],
+/// #     version: 0,
+/// # });
+/// let token: WriteToken = token.parse().expect("valid write token");
+/// for shard_index in token.shard_indexes() {
+///     println!("{shard_index}: {:?}", token.sequence_numbers(shard_index));
+/// }
+/// ```
+///
+/// The full [`WriteSummary`], including the partitions of the write, can be
+/// decoded with [`decode_write_token`].
+///
 /// NOTE: This is an ALPHA / Internal API that is used as part of the
 /// end to end tests.
 ///
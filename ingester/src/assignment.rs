@@ -0,0 +1,511 @@
+//! Shard assignment maintained in the catalog, instead of a static shard range
+//! per ingester.
+//!
+//! Every ingester registers itself in the catalog as a consumer of the shards
+//! of its topic, keyed by the address the queriers use to reach it, and keeps
+//! the registration alive with periodic heartbeats. On every heartbeat, the
+//! ingester also acts as the assignment controller: it balances the shards of
+//! the topic over the live ingesters and records the assignment in their
+//! registrations. Ingesters that stopped sending heartbeats, are draining or
+//! are drained receive no shards, and the registrations of expired ingesters
+//! are removed. An ingester then consumes the shards assigned to it, following
+//! any change.
+//!
+//! The assignment only depends on the catalog, so all the ingesters compute
+//! the same one and it does not matter which of them records it. Shards keep
+//! their ingester unless it is gone or has more than its share, so adding or
+//! removing an ingester only moves the shards needed to rebalance.
+//!
+//! A shard is handed over in two phases, so that it is never consumed by two
+//! ingesters at once: it is first removed from the assignment of its previous
+//! ingester, which persists the data buffered for the shard and releases the
+//! shard lease, and only assigned to the next ingester once the lease is
+//! released, or expired if the previous ingester is gone.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use data_types::{DrainState, IngesterRegistration, ShardIndex, Timestamp, TopicMetadata};
+use iox_catalog::interface::Catalog;
+use iox_time::TimeProvider;
+use observability_deps::tracing::*;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+
+use crate::handler::IngestHandler;
+
+/// The default interval of the heartbeats, which is also the interval at
+/// which the assignment is rebalanced and applied.
+pub const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The number of heartbeat intervals after which an ingester that stopped
+/// sending heartbeats loses its shards.
+pub const HEARTBEAT_EXPIRY_INTERVALS: u32 = 3;
+
+/// The shard assignment of an ingester, see the [module documentation](self).
+pub struct ShardAssignment {
+    address: String,
+    topic: TopicMetadata,
+    catalog: Arc<dyn Catalog>,
+    time_provider: Arc<dyn TimeProvider>,
+    heartbeat_interval: Duration,
+
+    /// The assignment last applied to the ingester.
+    ///
+    /// The shards are only changed when the assignment changes, so that the
+    /// ingester does not consume its shards again after it is drained.
+    applied: Option<BTreeSet<ShardIndex>>,
+}
+
+impl std::fmt::Debug for ShardAssignment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShardAssignment")
+            .field("address", &self.address)
+            .field("topic", &self.topic.name)
+            .field("heartbeat_interval", &self.heartbeat_interval)
+            .field("applied", &self.applied)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ShardAssignment {
+    /// Register the ingester reachable by the queriers at `address` as a
+    /// consumer of the shards of `topic`.
+    pub fn new(
+        address: impl Into<String>,
+        topic: TopicMetadata,
+        catalog: Arc<dyn Catalog>,
+        time_provider: Arc<dyn TimeProvider>,
+    ) -> Self {
+        Self {
+            address: address.into(),
+            topic,
+            catalog,
+            time_provider,
+            heartbeat_interval: DEFAULT_HEARTBEAT_INTERVAL,
+            applied: None,
+        }
+    }
+
+    /// Send a heartbeat every `heartbeat_interval` instead of every
+    /// [`DEFAULT_HEARTBEAT_INTERVAL`].
+    ///
+    /// All the ingesters of a topic MUST use the same interval, since it
+    /// decides when an ingester is considered gone.
+    pub fn with_heartbeat_interval(self, heartbeat_interval: Duration) -> Self {
+        Self {
+            heartbeat_interval,
+            ..self
+        }
+    }
+
+    /// Send heartbeats, rebalance the assignment and apply the shards assigned
+    /// to this ingester to `handler` until `shutdown` is cancelled or the
+    /// handler is dropped.
+    ///
+    /// The registration is kept on shutdown, so that a restarted ingester
+    /// gets its shards back if it returns before its heartbeat expires.
+    pub(crate) async fn run(
+        mut self,
+        handler: Weak<dyn IngestHandler>,
+        shutdown: CancellationToken,
+    ) {
+        info!(
+            address=%self.address,
+            topic=%self.topic.name,
+            "registering ingester for catalog shard assignment"
+        );
+
+        let mut interval = tokio::time::interval(self.heartbeat_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+
+            if let Err(e) = self.rebalance().await {
+                warn!(%e, address=%self.address, "failed to rebalance shard assignment");
+            }
+
+            let handler = match handler.upgrade() {
+                Some(v) => v,
+                None => break,
+            };
+            self.apply(handler.as_ref()).await;
+        }
+
+        info!(address=%self.address, "catalog shard assignment stopped");
+    }
+
+    /// Record a heartbeat of this ingester, then balance the shards of the
+    /// topic over the live ingesters and record their assignments.
+    async fn rebalance(&self) -> Result<(), iox_catalog::interface::Error> {
+        let now = self.time_provider.now();
+        let heartbeat_after = now
+            .checked_sub(self.heartbeat_interval * HEARTBEAT_EXPIRY_INTERVALS)
+            .map(Timestamp::from)
+            .unwrap_or_else(|| Timestamp::new(i64::MIN));
+
+        let mut txn = self.catalog.start_transaction().await?;
+        txn.shards()
+            .record_ingester_heartbeat(self.topic.id, &self.address, Timestamp::from(now))
+            .await?;
+
+        let shards = txn.shards().list_by_topic(&self.topic).await?;
+        let index_of: BTreeMap<_, _> = shards.iter().map(|s| (s.id, s.shard_index)).collect();
+        let shards: Vec<_> = shards.into_iter().map(|s| s.shard_index).collect();
+        let leased: BTreeMap<_, _> = txn
+            .shards()
+            .list_held_leases(self.topic.id)
+            .await?
+            .into_iter()
+            .filter_map(|l| Some((*index_of.get(&l.shard_id)?, l.holder)))
+            .collect();
+        let registrations = txn.shards().list_ingesters(self.topic.id).await?;
+        let inactive: BTreeSet<_> = txn
+            .node_drains()
+            .list()
+            .await?
+            .into_iter()
+            .filter(|d| d.state != DrainState::Active)
+            .map(|d| d.address)
+            .collect();
+
+        let live: BTreeMap<_, _> = registrations
+            .iter()
+            .filter(|r| r.last_heartbeat >= heartbeat_after && !inactive.contains(&r.address))
+            .map(|r| {
+                (
+                    r.address.as_str(),
+                    r.shard_indexes.iter().copied().collect(),
+                )
+            })
+            .collect();
+        let assignment = balance(&shards, &live);
+
+        for IngesterRegistration {
+            address,
+            shard_indexes,
+            last_heartbeat,
+            ..
+        } in &registrations
+        {
+            if *last_heartbeat < heartbeat_after {
+                info!(%address, "removing expired ingester registration");
+                txn.shards().remove_ingester(address).await?;
+                continue;
+            }
+
+            // A shard new to the ingester is withheld while another ingester
+            // holds its lease.
+            let assigned: Vec<_> = assignment
+                .get(address.as_str())
+                .into_iter()
+                .flatten()
+                .copied()
+                .filter(|s| {
+                    shard_indexes.contains(s) || leased.get(s).map_or(true, |h| h == address)
+                })
+                .collect();
+            if *shard_indexes != assigned {
+                info!(
+                    %address,
+                    current=?shard_indexes,
+                    assigned=?assigned,
+                    "assigning shards to ingester"
+                );
+                txn.shards().assign_shards(address, &assigned).await?;
+            }
+        }
+
+        txn.commit().await
+    }
+
+    /// Change the shards consumed by `handler` if the assignment of this
+    /// ingester changed since it was last applied.
+    async fn apply(&mut self, handler: &dyn IngestHandler) {
+        let registration = match self
+            .catalog
+            .repositories()
+            .await
+            .shards()
+            .list_ingesters(self.topic.id)
+            .await
+        {
+            Ok(v) => v.into_iter().find(|r| r.address == self.address),
+            Err(e) => {
+                warn!(%e, address=%self.address, "failed to read shard assignment");
+                return;
+            }
+        };
+
+        // Not registered yet, or the registration expired.
+        let assigned: BTreeSet<_> = match registration {
+            Some(v) => v.shard_indexes.into_iter().collect(),
+            None => return,
+        };
+        if self.applied.as_ref() == Some(&assigned) {
+            return;
+        }
+
+        match handler.set_shard_indexes(assigned.clone()).await {
+            Ok(()) => self.applied = Some(assigned),
+            Err(e) => warn!(%e, address=%self.address, "failed to apply shard assignment"),
+        }
+    }
+}
+
+/// Balance `shards` over the `live` ingesters, given the shards currently
+/// assigned to each of them.
+///
+/// Every ingester receives the same number of shards, give or take one. An
+/// ingester keeps its current shards up to its share, and only the remaining
+/// shards are moved, lowest shard index first.
+fn balance<'a>(
+    shards: &[ShardIndex],
+    live: &BTreeMap<&'a str, BTreeSet<ShardIndex>>,
+) -> BTreeMap<&'a str, BTreeSet<ShardIndex>> {
+    if live.is_empty() {
+        return BTreeMap::new();
+    }
+
+    let share = shards.len() / live.len();
+    let remainder = shards.len() % live.len();
+
+    let mut unassigned: BTreeSet<_> = shards.iter().copied().collect();
+    let mut assignment: BTreeMap<_, _> = live
+        .iter()
+        .enumerate()
+        .map(|(i, (&address, current))| {
+            let target = share + usize::from(i < remainder);
+            let kept: BTreeSet<_> = current
+                .iter()
+                .filter(|s| unassigned.contains(s))
+                .take(target)
+                .copied()
+                .collect();
+            unassigned.retain(|s| !kept.contains(s));
+            (address, (kept, target))
+        })
+        .collect();
+
+    let mut unassigned = unassigned.into_iter();
+    for (assigned, target) in assignment.values_mut() {
+        assigned.extend(unassigned.by_ref().take(*target - assigned.len()));
+    }
+
+    assignment
+        .into_iter()
+        .map(|(address, (assigned, _))| (address, assigned))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use iox_catalog::mem::MemCatalog;
+    use iox_time::{MockProvider, Time};
+
+    use super::*;
+    use crate::test_util::MockIngestHandler;
+
+    fn shard_set(shard_indexes: impl IntoIterator<Item = i32>) -> BTreeSet<ShardIndex> {
+        shard_indexes.into_iter().map(ShardIndex::new).collect()
+    }
+
+    #[test]
+    fn test_balance() {
+        let shards: Vec<_> = shard_set(0..8).into_iter().collect();
+
+        assert!(balance(&shards, &BTreeMap::new()).is_empty());
+
+        // A single ingester consumes all the shards.
+        let live = BTreeMap::from([("a", shard_set([]))]);
+        assert_eq!(
+            balance(&shards, &live),
+            BTreeMap::from([("a", shard_set(0..8))])
+        );
+
+        // A new ingester takes over the shards beyond the share of the
+        // existing one.
+        let live = BTreeMap::from([("a", shard_set(0..8)), ("b", shard_set([]))]);
+        assert_eq!(
+            balance(&shards, &live),
+            BTreeMap::from([("a", shard_set(0..4)), ("b", shard_set(4..8))])
+        );
+
+        // The shards of a removed ingester are spread over the others, which
+        // keep their own.
+        let live = BTreeMap::from([("a", shard_set([0, 1, 2])), ("c", shard_set([6, 7]))]);
+        assert_eq!(
+            balance(&shards, &live),
+            BTreeMap::from([
+                ("a", shard_set([0, 1, 2, 3])),
+                ("c", shard_set([4, 5, 6, 7]))
+            ])
+        );
+
+        // Shards that no longer exist, or are assigned twice, are dropped.
+        let live = BTreeMap::from([
+            ("a", shard_set([0, 1, 9])),
+            ("b", shard_set([1, 2])),
+            ("c", shard_set([])),
+        ]);
+        assert_eq!(
+            balance(&shards, &live),
+            BTreeMap::from([
+                ("a", shard_set([0, 1, 3])),
+                ("b", shard_set([2, 4, 5])),
+                ("c", shard_set([6, 7])),
+            ])
+        );
+
+        // More ingesters than shards.
+        let live = BTreeMap::from([("a", shard_set([])), ("b", shard_set([]))]);
+        assert_eq!(
+            balance(&shards[..1], &live),
+            BTreeMap::from([("a", shard_set([0])), ("b", shard_set([]))])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_assignment() {
+        let catalog: Arc<dyn Catalog> =
+            Arc::new(MemCatalog::new(Arc::new(metric::Registry::default())));
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+
+        let (topic, shards) = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("assignment").await.unwrap();
+            let mut shards = vec![];
+            for shard_index in 0..4 {
+                let shard = repos
+                    .shards()
+                    .create_or_get(&topic, ShardIndex::new(shard_index))
+                    .await
+                    .unwrap();
+                shards.push(shard);
+            }
+            (topic, shards)
+        };
+        let ingester = |address: &str| {
+            let assignment = ShardAssignment::new(
+                address,
+                topic.clone(),
+                Arc::clone(&catalog),
+                Arc::clone(&time_provider) as _,
+            );
+            (assignment, MockIngestHandler::default())
+        };
+        let (mut a, handler_a) = ingester("http://ingester-a:8083");
+        let (mut b, handler_b) = ingester("http://ingester-b:8083");
+
+        // The first ingester is assigned all the shards.
+        a.rebalance().await.unwrap();
+        a.apply(&handler_a).await;
+        assert_eq!(handler_a.shard_indexes(), shard_set(0..4));
+
+        // A second ingester takes over half of them, each once the first one
+        // released its lease.
+        catalog
+            .repositories()
+            .await
+            .shards()
+            .acquire_lease(
+                shards[2].id,
+                "http://ingester-a:8083",
+                Duration::from_secs(60),
+            )
+            .await
+            .unwrap();
+        b.rebalance().await.unwrap();
+        a.apply(&handler_a).await;
+        b.apply(&handler_b).await;
+        assert_eq!(handler_a.shard_indexes(), shard_set(0..2));
+        assert_eq!(handler_b.shard_indexes(), shard_set([3]));
+
+        catalog
+            .repositories()
+            .await
+            .shards()
+            .release_lease(shards[2].id, "http://ingester-a:8083")
+            .await
+            .unwrap();
+        b.rebalance().await.unwrap();
+        b.apply(&handler_b).await;
+        assert_eq!(handler_b.shard_indexes(), shard_set(2..4));
+
+        // A draining ingester loses its shards once the assignment is
+        // rebalanced by any ingester.
+        catalog
+            .repositories()
+            .await
+            .node_drains()
+            .set_state(
+                "http://ingester-b:8083",
+                DrainState::Draining,
+                Timestamp::new(0),
+            )
+            .await
+            .unwrap();
+        handler_b.set_shard_indexes(BTreeSet::new()).await.unwrap();
+        b.apply(&handler_b).await;
+        assert!(
+            handler_b.shard_indexes().is_empty(),
+            "an unchanged assignment is not applied again"
+        );
+        a.rebalance().await.unwrap();
+        a.apply(&handler_a).await;
+        b.apply(&handler_b).await;
+        assert_eq!(handler_a.shard_indexes(), shard_set(0..4));
+        assert!(handler_b.shard_indexes().is_empty());
+
+        // Once active again, it gets its share back.
+        catalog
+            .repositories()
+            .await
+            .node_drains()
+            .set_state(
+                "http://ingester-b:8083",
+                DrainState::Active,
+                Timestamp::new(0),
+            )
+            .await
+            .unwrap();
+        b.rebalance().await.unwrap();
+        b.apply(&handler_b).await;
+        assert_eq!(handler_b.shard_indexes(), shard_set(2..4));
+
+        // An ingester that stopped sending heartbeats loses its shards, and
+        // its registration is removed.
+        time_provider
+            .inc(DEFAULT_HEARTBEAT_INTERVAL * HEARTBEAT_EXPIRY_INTERVALS + Duration::from_secs(1));
+        b.rebalance().await.unwrap();
+        b.apply(&handler_b).await;
+        assert_eq!(handler_b.shard_indexes(), shard_set(0..4));
+
+        let addresses: Vec<_> = catalog
+            .repositories()
+            .await
+            .shards()
+            .list_ingesters(topic.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.address)
+            .collect();
+        assert_eq!(addresses, vec!["http://ingester-b:8083"]);
+
+        // The expired ingester registers again when it returns, and takes
+        // over the shards beyond the share of the other.
+        a.rebalance().await.unwrap();
+        a.apply(&handler_a).await;
+        b.apply(&handler_b).await;
+        assert_eq!(handler_a.shard_indexes(), shard_set(2..4));
+        assert_eq!(handler_b.shard_indexes(), shard_set(0..2));
+    }
+}
@@ -46,6 +46,9 @@ pub(crate) enum Error {
 
     #[snafu(display("Error computing min and max for record batches: {}", source))]
     MinMax { source: iox_query::util::Error },
+
+    #[snafu(display("Error sorting persisting batch: {}", source))]
+    Sort { source: crate::query_adaptor::Error },
 }
 
 /// A specialized `Error` for Ingester's Compact errors
@@ -95,9 +98,37 @@ pub(crate) async fn compact_persisting_batch(
 ) -> Result<CompactedStream> {
     assert!(!batch.record_batches().is_empty());
 
+    let (data_sort_key, catalog_sort_key_update) = compaction_sort_keys(sort_key.as_ref(), &batch);
+
+    // Compact
+    let stream = compact(executor, table_name, Arc::new(batch), data_sort_key.clone()).await?;
+
+    Ok(CompactedStream {
+        stream,
+        catalog_sort_key_update,
+        data_sort_key,
+    })
+}
+
+/// Sort `batch` on the sort key [`compact_persisting_batch`] will use to
+/// compact it, so that the compaction plan does not need to sort it again.
+pub(crate) fn sort_persisting_batch(
+    sort_key: Option<&SortKey>,
+    batch: QueryAdaptor,
+) -> Result<QueryAdaptor> {
+    let (data_sort_key, _) = compaction_sort_keys(sort_key, &batch);
+    batch.sort(data_sort_key).context(SortSnafu)
+}
+
+/// Return the sort key to compact `batch` with, and the sort key the catalog
+/// should be updated to, if any.
+fn compaction_sort_keys(
+    sort_key: Option<&SortKey>,
+    batch: &QueryAdaptor,
+) -> (SortKey, Option<SortKey>) {
     // Get sort key from the catalog or compute it from
     // cardinality.
-    let (data_sort_key, catalog_sort_key_update) = match sort_key {
+    match sort_key {
         Some(sk) => {
             // Remove any columns not present in this data from the
             // sort key that will be used to compact this parquet file
@@ -105,7 +136,7 @@ pub(crate) async fn compact_persisting_batch(
             //
             // If there are any new columns, add them to the end of the sort key in the catalog and
             // return that to be updated in the catalog.
-            adjust_sort_key_columns(&sk, &batch.schema().primary_key())
+            adjust_sort_key_columns(sk, &batch.schema().primary_key())
         }
         None => {
             let sort_key = compute_sort_key(
@@ -116,16 +147,7 @@ pub(crate) async fn compact_persisting_batch(
             // file's metadata, also return the sort key to be stored in the catalog
             (sort_key.clone(), Some(sort_key))
         }
-    };
-
-    // Compact
-    let stream = compact(executor, table_name, Arc::new(batch), data_sort_key.clone()).await?;
-
-    Ok(CompactedStream {
-        stream,
-        catalog_sort_key_update,
-        data_sort_key,
-    })
+    }
 }
 
 /// Compact a given batch without updating the sort key.
@@ -643,6 +665,52 @@ mod tests {
         assert_batches_eq!(&expected, &output_batches);
     }
 
+    #[tokio::test]
+    async fn test_compact_sorted_batches_with_duplicates() {
+        // create many-batches input data
+        let batch = QueryAdaptor::new(PartitionId::new(1), create_batches_with_influxtype().await);
+
+        // sort the data ahead of compaction
+        let sort_key = SortKey::from_columns(["tag1", "time"]);
+        let batch = sort_persisting_batch(Some(&sort_key), batch).unwrap();
+        assert_eq!(batch.record_batches().len(), 1);
+        assert_eq!(batch.sort_key(), Some(&sort_key));
+
+        // compact
+        let exc = Executor::new(1);
+        let CompactedStream {
+            stream,
+            data_sort_key,
+            catalog_sort_key_update,
+        } = compact_persisting_batch(&exc, Some(sort_key.clone()), "test_table".into(), batch)
+            .await
+            .unwrap();
+        assert_eq!(data_sort_key, sort_key);
+        assert!(catalog_sort_key_update.is_none());
+
+        let output_batches = datafusion::physical_plan::common::collect(stream)
+            .await
+            .unwrap();
+
+        // verify compacted data
+        // the sort kept the order of duplicates, so the latest writes win
+        let expected = vec![
+            "+-----------+------+--------------------------------+",
+            "| field_int | tag1 | time                           |",
+            "+-----------+------+--------------------------------+",
+            "| 100       | AL   | 1970-01-01T00:00:00.000000050Z |",
+            "| 70        | CT   | 1970-01-01T00:00:00.000000100Z |",
+            "| 70        | CT   | 1970-01-01T00:00:00.000000500Z |",
+            "| 30        | MT   | 1970-01-01T00:00:00.000000005Z |",
+            "| 1000      | MT   | 1970-01-01T00:00:00.000001Z    |",
+            "| 1000      | MT   | 1970-01-01T00:00:00.000002Z    |",
+            "| 5         | MT   | 1970-01-01T00:00:00.000005Z    |",
+            "| 10        | MT   | 1970-01-01T00:00:00.000007Z    |",
+            "+-----------+------+--------------------------------+",
+        ];
+        assert_batches_eq!(&expected, &output_batches);
+    }
+
     #[tokio::test]
     async fn test_compact_many_batches_different_columns_with_duplicates() {
         // create many-batches input data
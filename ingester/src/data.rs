@@ -1,28 +1,33 @@
 //! Data for the lifecycle of the Ingester
 
 use crate::{
-    compact::{compact_persisting_batch, CompactedStream},
+    compact::{compact_persisting_batch, sort_persisting_batch, CompactedStream},
+    lease::{LeaseFence, ShardLeases, StandbyOutcome},
     lifecycle::LifecycleHandle,
 };
 use async_trait::async_trait;
 use backoff::{Backoff, BackoffConfig};
 use data_types::{
-    CompactionLevel, NamespaceId, PartitionId, SequenceNumber, ShardId, ShardIndex, TableId,
+    CompactionLevel, NamespaceId, ParquetFileParams, PartitionId, SequenceNumber, ShardId,
+    ShardIndex, TableId,
 };
 use dml::DmlOperation;
-use iox_catalog::interface::{get_table_schema_by_id, Catalog};
+use iox_catalog::interface::{get_table_schema_by_id, Catalog, Transaction};
 use iox_query::exec::Executor;
 use iox_time::{SystemProvider, TimeProvider};
 use metric::{Attributes, Metric, U64Histogram, U64HistogramOptions};
 use object_store::DynObjectStore;
 use observability_deps::tracing::*;
+use parking_lot::RwLock;
 use parquet_file::{
     metadata::IoxMetadata,
+    serialize::ParquetWriterOptions,
     storage::{ParquetStorage, StorageId},
 };
 use snafu::{OptionExt, Snafu};
 use std::{
     collections::BTreeMap,
+    ops::ControlFlow,
     sync::Arc,
     time::{Duration, Instant},
 };
@@ -104,10 +109,20 @@ pub struct IngesterData {
     /// The global catalog for schema, parquet files and tombstones
     catalog: Arc<dyn Catalog>,
 
-    /// This map gets set up on initialization of the ingester so it won't ever be modified.
-    /// The content of each ShardData will get changed when more namespaces and tables
-    /// get ingested.
-    shards: BTreeMap<ShardId, ShardData>,
+    /// The shards this ingester buffers data for.
+    ///
+    /// This map is set up on initialization of the ingester, and changes only
+    /// when shards are assigned to, or removed from this ingester at runtime.
+    /// The content of each ShardData will get changed when more namespaces and
+    /// tables get ingested.
+    shards: RwLock<BTreeMap<ShardId, Arc<ShardData>>>,
+
+    /// The resolvers shared by all [`ShardData`] instances, retained to
+    /// initialise the state of shards added at runtime.
+    namespace_name_provider: Arc<dyn NamespaceNameProvider>,
+    table_name_provider: Arc<dyn TableNameProvider>,
+    partition_provider: Arc<dyn PartitionProvider>,
+    metrics: Arc<metric::Registry>,
 
     /// Executor for running queries and compacting and persisting
     exec: Arc<Executor>,
@@ -117,6 +132,13 @@ pub struct IngesterData {
 
     /// Metrics for file size of persisted Parquet files
     persisted_file_size_bytes: Metric<U64Histogram>,
+
+    /// Sort persisting data on its sort key before compacting it.
+    presort_persist: bool,
+
+    /// The shard leases deciding whether this ingester persists the data of a
+    /// shard, if several ingesters consume the same shards.
+    shard_leases: Option<Arc<ShardLeases>>,
 }
 
 impl IngesterData {
@@ -194,14 +216,14 @@ impl IngesterData {
             .map(|(id, index)| {
                 (
                     id,
-                    ShardData::new(
+                    Arc::new(ShardData::new(
                         index,
                         id,
                         Arc::clone(&namespace_name_provider),
                         Arc::clone(&table_name_provider),
                         Arc::clone(&partition_provider),
                         Arc::clone(&metrics),
-                    ),
+                    )),
                 )
             })
             .collect();
@@ -209,27 +231,114 @@ impl IngesterData {
         Ok(Self {
             store: ParquetStorage::new(object_store, StorageId::from("iox")),
             catalog,
-            shards,
+            shards: RwLock::new(shards),
+            namespace_name_provider,
+            table_name_provider,
+            partition_provider,
+            metrics,
             exec,
             backoff_config,
             persisted_file_size_bytes,
+            presort_persist: false,
+            shard_leases: None,
         })
     }
 
+    /// Sort the data of a partition on its sort key before compacting it for
+    /// persistence, instead of sorting it as part of the compaction plan.
+    ///
+    /// The buffered data read by queries is not sorted.
+    pub fn with_presort_persist(self, presort_persist: bool) -> Self {
+        Self {
+            presort_persist,
+            ..self
+        }
+    }
+
+    /// Use `options` for the parquet files written when persisting.
+    pub fn with_parquet_writer_options(self, options: ParquetWriterOptions) -> Self {
+        Self {
+            store: self.store.with_writer_options(options),
+            ..self
+        }
+    }
+
+    /// Only persist the data of the shards whose lease this ingester holds in
+    /// `shard_leases`, and release the data of other shards once their leader
+    /// persisted it.
+    pub(crate) fn with_shard_leases(self, shard_leases: Arc<ShardLeases>) -> Self {
+        Self {
+            shard_leases: Some(shard_leases),
+            ..self
+        }
+    }
+
+    /// Returns true if another ingester is responsible for persisting the data
+    /// of `shard_id`.
+    fn is_standby(&self, shard_id: ShardId) -> bool {
+        self.shard_leases
+            .as_ref()
+            .map_or(false, |l| !l.is_leader(shard_id))
+    }
+
     /// Executor for running queries and compacting and persisting
     pub(crate) fn exec(&self) -> &Arc<Executor> {
         &self.exec
     }
 
     /// Get shard data for specific shard.
-    #[cfg(test)]
-    pub(crate) fn shard(&self, shard_id: ShardId) -> Option<&ShardData> {
-        self.shards.get(&shard_id)
+    pub(crate) fn shard(&self, shard_id: ShardId) -> Option<Arc<ShardData>> {
+        self.shards.read().get(&shard_id).map(Arc::clone)
+    }
+
+    /// Get a snapshot of the shards (ID and data) currently buffered.
+    pub(crate) fn shards(&self) -> Vec<(ShardId, Arc<ShardData>)> {
+        self.shards
+            .read()
+            .iter()
+            .map(|(id, data)| (*id, Arc::clone(data)))
+            .collect()
+    }
+
+    /// Begin buffering operations for the shard identified by `shard_id` and
+    /// `shard_index`, if it is not already buffered.
+    pub(crate) fn add_shard(&self, shard_id: ShardId, shard_index: ShardIndex) {
+        self.shards.write().entry(shard_id).or_insert_with(|| {
+            Arc::new(ShardData::new(
+                shard_index,
+                shard_id,
+                Arc::clone(&self.namespace_name_provider),
+                Arc::clone(&self.table_name_provider),
+                Arc::clone(&self.partition_provider),
+                Arc::clone(&self.metrics),
+            ))
+        });
+    }
+
+    /// Discard the buffered state of the shard identified by `shard_id`,
+    /// returning true if it was buffered.
+    ///
+    /// All data buffered for the shard MUST have been persisted before it is
+    /// removed, otherwise it is lost (until replayed by another ingester).
+    pub(crate) fn remove_shard(&self, shard_id: ShardId) -> bool {
+        self.shards.write().remove(&shard_id).is_some()
     }
 
-    /// Get iterator over shards (ID and data).
-    pub(crate) fn shards(&self) -> impl Iterator<Item = (&ShardId, &ShardData)> {
-        self.shards.iter()
+    /// Acquire the lease of a shard about to be consumed, unless another
+    /// ingester holds it, rather than waiting for the next renewal.
+    pub(crate) async fn acquire_shard_lease(&self, shard_id: ShardId) {
+        if let Some(shard_leases) = &self.shard_leases {
+            shard_leases.acquire(shard_id).await;
+        }
+    }
+
+    /// Release the lease of a removed shard, if this ingester holds it, so
+    /// that the next ingester consuming the shard takes it over without
+    /// waiting for the lease to expire.
+    pub(crate) async fn release_shard_lease(&self, shard_id: ShardId) {
+        if let Some(shard_leases) = &self.shard_leases {
+            shard_leases.release_shard(shard_id).await;
+        }
     }
 
     /// Store the write or delete in the in memory buffer. Deletes will
@@ -245,8 +354,7 @@ impl IngesterData {
         lifecycle_handle: &dyn LifecycleHandle,
     ) -> Result<DmlApplyAction> {
         let shard_data = self
-            .shards
-            .get(&shard_id)
+            .shard(shard_id)
             .context(ShardNotFoundSnafu { shard_id })?;
         shard_data
             .buffer_operation(dml_operation, lifecycle_handle)
@@ -263,9 +371,10 @@ impl IngesterData {
         for shard_index in shard_indexes {
             let shard_data = self
                 .shards
-                .iter()
-                .map(|(_, shard_data)| shard_data)
-                .find(|shard_data| shard_data.shard_index() == shard_index);
+                .read()
+                .values()
+                .find(|shard_data| shard_data.shard_index() == shard_index)
+                .map(Arc::clone);
 
             let progress = match shard_data {
                 Some(shard_data) => shard_data.progress().await,
@@ -318,8 +427,7 @@ impl Persister for IngesterData {
         // lookup the state from the ingester data. If something isn't found,
         // it's unexpected. Crash so someone can take a look.
         let namespace = self
-            .shards
-            .get(&shard_id)
+            .shard(shard_id)
             .and_then(|s| s.namespace(namespace_id))
             .unwrap_or_else(|| panic!("namespace {namespace_id} not in shard {shard_id} state"));
 
@@ -438,6 +546,72 @@ impl Persister for IngesterData {
             }
         };
 
+        // A standby releases the data once the shard leader has persisted it.
+        // If it takes over leadership of the shard in the meantime, or the
+        // leader does not persist the data in time, it persists the data
+        // itself.
+        let standby_outcome = if self.is_standby(shard_id) {
+            let leases = self.shard_leases.as_ref().expect("standby has leases");
+            let max_sequence_number = batch_sequence_number_range.inclusive_max().unwrap();
+            match leases
+                .wait_for_leader(shard_id, partition_id, max_sequence_number)
+                .await
+            {
+                StandbyOutcome::PersistedByLeader => {
+                    partition.lock().mark_persisted(max_sequence_number);
+                    debug!(
+                        %shard_id,
+                        %namespace_id,
+                        %table_id,
+                        %partition_id,
+                        %partition_key,
+                        max_sequence_number=%max_sequence_number.get(),
+                        "released partition data persisted by shard leader"
+                    );
+                    return;
+                }
+                outcome => {
+                    info!(
+                        %shard_id,
+                        %namespace_id,
+                        %table_id,
+                        %partition_id,
+                        %partition_key,
+                        ?outcome,
+                        "persisting partition data buffered as standby"
+                    );
+                    Some(outcome)
+                }
+            }
+        } else {
+            None
+        };
+
+        // Sort the data ahead of compaction, if configured to, so that the
+        // compaction plan can skip sorting it.
+        //
+        // Sorting is only an optimisation - should it fail, the unsorted batch
+        // is persisted instead and the compaction plan sorts it.
+        let batch = if self.presort_persist {
+            match sort_persisting_batch(sort_key.as_ref(), batch.clone()) {
+                Ok(sorted) => sorted,
+                Err(e) => {
+                    warn!(
+                        error=%e,
+                        %shard_id,
+                        %namespace_id,
+                        %table_id,
+                        %partition_id,
+                        %partition_key,
+                        "unable to sort persisting batch, persisting unsorted data"
+                    );
+                    batch
+                }
+            }
+        } else {
+            batch
+        };
+
         // At this point, the table name is necessary, so demand it be resolved
         // if it is not yet available.
         let table_name = table_name.get().await;
@@ -549,61 +723,64 @@ impl Persister for IngesterData {
             );
         }
 
-        // Add the parquet file to the catalog.
-        //
-        // This has the effect of allowing the queriers to "discover" the
-        // parquet file by polling / querying the catalog.
-        Backoff::new(&self.backoff_config)
-            .retry_all_errors("add parquet file to catalog", || async {
-                let mut repos = self.catalog.repositories().await;
-                let parquet_file = repos.parquet_files().create(parquet_file.clone()).await?;
-                debug!(
-                    ?partition_id,
-                    table_id=?parquet_file.table_id,
-                    parquet_file_id=?parquet_file.id,
-                    table_name=%iox_metadata.table_name,
-                    "parquet file written to catalog"
-                );
-                // compiler insisted on getting told the type of the error :shrug:
-                Ok(()) as Result<(), iox_catalog::interface::Error>
-            })
-            .await
-            .expect("retry forever");
-
-        // Update the per-partition persistence watermark, so that new
-        // ingester instances skip the just-persisted ops during replay.
+        // Add the parquet file to the catalog, and update the per-partition
+        // persistence watermark in the same transaction, so that new ingester
+        // instances skip the just-persisted ops during replay.
         //
-        // This could be transactional with the above parquet insert to
-        // maintain catalog consistency, though in practice it is an
-        // unnecessary overhead - the system can tolerate replaying the ops
-        // that lead to this parquet file being generated, and tolerate
-        // creating a parquet file containing duplicate data (remedied by
-        // compaction).
+        // Adding the parquet file has the effect of allowing the queriers to
+        // "discover" it by polling / querying the catalog.
         //
-        // This means it is possible to observe a parquet file with a
-        // max_persisted_sequence_number >
-        // partition.persisted_sequence_number, either in-between these
-        // catalog updates, or for however long it takes a crashed ingester
-        // to restart and replay the ops, and re-persist a file containing
-        // the same (or subset of) data.
+        // The leader of the shard fences the transaction by its lease, so
+        // that a leader that lost its lease without noticing, e.g. after a
+        // long pause, does not persist the data alongside the new leader.
+        // Should the lease be lost, this ingester waits for the data to be
+        // persisted by the new leader as a standby does, leaving the uploaded
+        // parquet file to the object store garbage collector.
         //
-        // The above is also true of the per-shard persist marker that
-        // governs the ingester's replay start point, which is
-        // non-transactionally updated after all partitions have persisted.
-        Backoff::new(&self.backoff_config)
-            .retry_all_errors("set partition persist marker", || async {
-                self.catalog
-                    .repositories()
-                    .await
-                    .partitions()
-                    .update_persisted_sequence_number(
-                        parquet_file.partition_id,
-                        parquet_file.max_sequence_number,
-                    )
-                    .await
-            })
-            .await
-            .expect("retry forever");
+        // The per-shard persist marker that governs the ingester's replay
+        // start point is updated after all partitions have persisted, and may
+        // lag behind the per-partition watermarks for however long it takes
+        // a crashed ingester to restart and replay the ops.
+        let max_sequence_number = iox_metadata.max_sequence_number;
+        let mut fenced = standby_outcome != Some(StandbyOutcome::TimedOut);
+        loop {
+            let leases = match &self.shard_leases {
+                Some(leases) if fenced => leases,
+                _ => {
+                    self.commit_parquet_file(&parquet_file, None).await;
+                    break;
+                }
+            };
+
+            if let Some(fence) = leases.fence(shard_id) {
+                if self.commit_parquet_file(&parquet_file, Some(&fence)).await {
+                    break;
+                }
+                leases.lost(shard_id, fence.epoch);
+            }
+
+            match leases
+                .wait_for_leader(shard_id, partition_id, max_sequence_number)
+                .await
+            {
+                StandbyOutcome::PersistedByLeader => {
+                    partition.lock().mark_persisted(max_sequence_number);
+                    info!(
+                        %object_store_id,
+                        %shard_id,
+                        %namespace_id,
+                        %table_id,
+                        %partition_id,
+                        %partition_key,
+                        max_sequence_number=%max_sequence_number.get(),
+                        "released partition data persisted by new shard leader"
+                    );
+                    return;
+                }
+                StandbyOutcome::Leader => {}
+                StandbyOutcome::TimedOut => fenced = false,
+            }
+        }
 
         // Record metrics
         let attributes = Attributes::from([("shard_id", format!("{}", shard_id).into())]);
@@ -648,18 +825,135 @@ impl Persister for IngesterData {
         shard_id: ShardId,
         sequence_number: SequenceNumber,
     ) {
+        // The replay start point of a shard is maintained by its leader, in a
+        // transaction fenced by its lease.
+        let fence = match &self.shard_leases {
+            Some(leases) => match leases.fence(shard_id) {
+                Some(fence) => Some(fence),
+                None => return,
+            },
+            None => None,
+        };
+
+        let committed = Backoff::new(&self.backoff_config)
+            .retry_with_backoff("updating min_unpersisted_sequence_number", || async {
+                let res = async {
+                    let mut txn = self
+                        .start_fenced_transaction(shard_id, fence.as_ref())
+                        .await?;
+                    let res = txn
+                        .shards()
+                        .update_min_unpersisted_sequence_number(shard_id, sequence_number)
+                        .await;
+                    finish_transaction(txn, res).await
+                }
+                .await;
+                lease_control_flow(res)
+            })
+            .await
+            .expect("retry forever");
+
+        if let (false, Some(leases), Some(fence)) = (committed, &self.shard_leases, fence) {
+            leases.lost(shard_id, fence.epoch);
+        }
+    }
+}
+
+impl IngesterData {
+    /// Add `parquet_file` to the catalog and advance the persist marker of its
+    /// partition, in a transaction fenced by `fence`.
+    ///
+    /// Retries until it succeeds, and returns false if the catalog reports
+    /// the lease of `fence` lost instead.
+    async fn commit_parquet_file(
+        &self,
+        parquet_file: &ParquetFileParams,
+        fence: Option<&LeaseFence>,
+    ) -> bool {
         Backoff::new(&self.backoff_config)
-            .retry_all_errors("updating min_unpersisted_sequence_number", || async {
-                self.catalog
-                    .repositories()
-                    .await
-                    .shards()
-                    .update_min_unpersisted_sequence_number(shard_id, sequence_number)
-                    .await
+            .retry_with_backoff("add parquet file to catalog", || async {
+                let res = async {
+                    let mut txn = self
+                        .start_fenced_transaction(parquet_file.shard_id, fence)
+                        .await?;
+                    let res = async {
+                        let created = txn.parquet_files().create(parquet_file.clone()).await?;
+                        txn.partitions()
+                            .update_persisted_sequence_number(
+                                parquet_file.partition_id,
+                                parquet_file.max_sequence_number,
+                            )
+                            .await?;
+                        Ok::<_, iox_catalog::interface::Error>(created)
+                    }
+                    .await;
+                    finish_transaction(txn, res).await
+                }
+                .await;
+                if let Ok(created) = &res {
+                    debug!(
+                        partition_id=?created.partition_id,
+                        table_id=?created.table_id,
+                        parquet_file_id=?created.id,
+                        "parquet file written to catalog"
+                    );
+                }
+                lease_control_flow(res)
             })
             .await
             .expect("retry forever")
     }
+
+    /// Start a catalog transaction, checking first that the lease of
+    /// `shard_id` is still held in the epoch of `fence`, if any.
+    async fn start_fenced_transaction(
+        &self,
+        shard_id: ShardId,
+        fence: Option<&LeaseFence>,
+    ) -> Result<Box<dyn Transaction>, iox_catalog::interface::Error> {
+        let mut txn = self.catalog.start_transaction().await?;
+        if let Some(fence) = fence {
+            if let Err(e) = txn
+                .shards()
+                .check_lease(shard_id, &fence.holder, fence.epoch)
+                .await
+            {
+                return finish_transaction(txn, Err(e)).await;
+            }
+        }
+        Ok(txn)
+    }
+}
+
+/// Commit `txn` if `res` is ok, abort it otherwise.
+async fn finish_transaction<T>(
+    txn: Box<dyn Transaction>,
+    res: Result<T, iox_catalog::interface::Error>,
+) -> Result<T, iox_catalog::interface::Error> {
+    match res {
+        Ok(v) => {
+            txn.commit().await?;
+            Ok(v)
+        }
+        Err(e) => {
+            if let Err(abort) = txn.abort().await {
+                warn!(error=%abort, "failed to abort catalog transaction");
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Stop retrying a fenced catalog transaction once it succeeded, or the
+/// catalog reported the lease fencing it lost.
+fn lease_control_flow<T>(
+    res: Result<T, iox_catalog::interface::Error>,
+) -> ControlFlow<bool, iox_catalog::interface::Error> {
+    match res {
+        Ok(_) => ControlFlow::Break(true),
+        Err(iox_catalog::interface::Error::LeaseLost { .. }) => ControlFlow::Break(false),
+        Err(e) => ControlFlow::Continue(e),
+    }
 }
 
 /// A successful DML apply operation can perform one of these actions
@@ -676,6 +970,7 @@ pub enum DmlApplyAction {
 mod tests {
     use super::*;
     use crate::{
+        lease::ShardLeaseConfig,
         lifecycle::{LifecycleConfig, LifecycleManager},
         test_util::make_write_op,
     };
@@ -863,7 +1158,7 @@ mod tests {
 
         async fn persist_data(&self, table: &Table) {
             let partition_id = {
-                let sd = self.data.shards.get(&self.shard1.id).unwrap();
+                let sd = self.data.shard(self.shard1.id).unwrap();
                 let n = sd.namespace(self.namespace.id).unwrap();
                 let t = n.table(table.id).unwrap();
                 let p = t
@@ -965,6 +1260,95 @@ mod tests {
         assert_eq!(file_paths.len(), 1);
     }
 
+    #[tokio::test]
+    async fn standby_releases_data_persisted_by_leader() {
+        test_helpers::maybe_start_logging();
+        let ctx = TestContext::new().await;
+        let shard = &ctx.shard1;
+
+        // Another ingester holds the lease of the shard.
+        let leases = |holder: &str| {
+            Arc::new(ShardLeases::new(
+                ShardLeaseConfig::new(Duration::from_secs(60)).with_holder(holder),
+                Arc::clone(&ctx.catalog),
+                Arc::new(SystemProvider::new()),
+            ))
+        };
+        leases("leader").renew(&[shard.id]).await;
+        let standby_leases = leases("standby");
+        standby_leases.renew(&[shard.id]).await;
+
+        let standby = IngesterData::new(
+            Arc::clone(&ctx.object_store),
+            Arc::clone(&ctx.catalog),
+            [(shard.id, shard.shard_index)],
+            Arc::new(Executor::new(1)),
+            BackoffConfig::default(),
+            Arc::new(metric::Registry::new()),
+        )
+        .await
+        .expect("failed to initialise ingester")
+        .with_shard_leases(standby_leases);
+
+        let manager = LifecycleManager::new(
+            LifecycleConfig::new(
+                1000000000,
+                0,
+                0,
+                Duration::from_secs(1),
+                Duration::from_secs(1),
+                1000000,
+            ),
+            Arc::clone(&ctx.metrics),
+            Arc::new(SystemProvider::new()),
+        );
+
+        // Both ingesters buffer the same write, and the leader persists it.
+        let w1 = ctx.arbitrary_write_with_seq_num(&ctx.table1, 1);
+        standby
+            .buffer_operation(shard.id, DmlOperation::Write(w1.clone()), &manager.handle())
+            .await
+            .unwrap();
+        ctx.data
+            .buffer_operation(shard.id, DmlOperation::Write(w1), &manager.handle())
+            .await
+            .unwrap();
+        ctx.persist_data(&ctx.table1).await;
+
+        let partition = standby
+            .shard(shard.id)
+            .unwrap()
+            .namespace(ctx.namespace.id)
+            .unwrap()
+            .table(ctx.table1.id)
+            .unwrap()
+            .get_partition_by_key(&ctx.partition_key)
+            .unwrap();
+        let partition_id = partition.lock().partition_id();
+
+        // The standby releases its data without persisting it again.
+        standby
+            .persist(shard.id, ctx.namespace.id, ctx.table1.id, partition_id)
+            .await;
+
+        let file_paths: Vec<_> = ctx
+            .object_store
+            .list(None)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(file_paths.len(), 1);
+
+        let mut partition = partition.lock();
+        assert!(partition.get_query_data().is_none());
+        assert_eq!(
+            partition.max_persisted_sequence_number(),
+            Some(SequenceNumber::new(1))
+        );
+    }
+
     #[tokio::test]
     async fn persist() {
         test_helpers::maybe_start_logging();
@@ -1008,7 +1392,7 @@ mod tests {
             .with_buffered(SequenceNumber::new(2));
         assert_progress(data, shard1.shard_index, expected_progress).await;
 
-        let sd = data.shards.get(&shard1.id).unwrap();
+        let sd = data.shard(shard1.id).unwrap();
         let n = sd.namespace(namespace.id).unwrap();
         let partition_id;
         {
@@ -1188,7 +1572,7 @@ mod tests {
             .unwrap();
 
         // Get the namespace
-        let sd = data.shards.get(&shard1.id).unwrap();
+        let sd = data.shard(shard1.id).unwrap();
         let n = sd.namespace(namespace.id).unwrap();
 
         let expected_progress = ShardProgress::new().with_buffered(SequenceNumber::new(1));
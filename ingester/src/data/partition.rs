@@ -2,6 +2,7 @@
 
 use std::sync::Arc;
 
+use arrow::record_batch::RecordBatch;
 use data_types::{NamespaceId, PartitionId, PartitionKey, SequenceNumber, ShardId, TableId};
 use mutable_batch::MutableBatch;
 use observability_deps::tracing::*;
@@ -9,7 +10,7 @@ use schema::sort::SortKey;
 use thiserror::Error;
 use write_summary::ShardProgress;
 
-use self::buffer::{traits::Queryable, BufferState, DataBuffer, Persisting};
+use self::buffer::{traits::Queryable, BufferState, DataBuffer, FrozenBuffer, Persisting};
 use crate::{deferred_load::DeferredLoad, query_adaptor::QueryAdaptor};
 
 use super::{sequence_range::SequenceNumberRange, table::TableName};
@@ -24,6 +25,10 @@ pub(crate) enum BufferError {
     #[error("skipped applying already persisted op")]
     SkipPersisted,
 
+    /// The op being applied has already been buffered, and is being replayed.
+    #[error("skipped applying already buffered op")]
+    SkipDuplicate,
+
     /// An error occurred writing the data to the [`MutableBatch`].
     #[error("failed to apply DML op: {0}")]
     BufferError(#[from] mutable_batch::Error),
@@ -49,6 +54,60 @@ impl SortKeyState {
     }
 }
 
+/// The data of a [`PartitionData`] as of the time it was pinned for a query by
+/// [`PartitionData::pin_query_data()`].
+#[derive(Debug)]
+pub(crate) struct PinnedPartition {
+    partition_id: PartitionId,
+
+    /// The data of the persisting buffer, if any.
+    persisting: Vec<Arc<RecordBatch>>,
+
+    /// The buffered data, ordered after `persisting`.
+    buffered: Vec<Arc<FrozenBuffer>>,
+
+    /// The persistence watermark of the partition at the time it was pinned.
+    max_persisted_sequence_number: Option<SequenceNumber>,
+
+    /// The highest [`SequenceNumber`] of the pinned data, if any.
+    max_sequence_number: Option<SequenceNumber>,
+}
+
+impl PinnedPartition {
+    pub(crate) fn partition_id(&self) -> PartitionId {
+        self.partition_id
+    }
+
+    /// Return the [`SequenceNumber`] that formed the (inclusive) persistence
+    /// watermark of the partition when it was pinned.
+    pub(crate) fn max_persisted_sequence_number(&self) -> Option<SequenceNumber> {
+        self.max_persisted_sequence_number
+    }
+
+    /// Return the highest [`SequenceNumber`] of the pinned data, or [`None`]
+    /// if the partition contained no data.
+    pub(crate) fn max_sequence_number(&self) -> Option<SequenceNumber> {
+        self.max_sequence_number
+    }
+
+    /// Return the pinned data, ordered by the [`SequenceNumber`] from which it
+    /// was buffered with, or [`None`] if the partition contained no data.
+    ///
+    /// This generates the [`RecordBatch`] of any buffered data not yet read.
+    pub(crate) fn into_query_data(self) -> Option<QueryAdaptor> {
+        let data = self
+            .persisting
+            .into_iter()
+            .chain(self.buffered.iter().map(|b| b.to_arrow()))
+            .collect::<Vec<_>>();
+
+        if data.is_empty() {
+            return None;
+        }
+        Some(QueryAdaptor::new(self.partition_id, data))
+    }
+}
+
 /// Data of an IOx Partition of a given Table of a Namespace that belongs to a
 /// given Shard
 #[derive(Debug)]
@@ -86,6 +145,14 @@ pub struct PartitionData {
     /// The max_persisted_sequence number for any parquet_file in this
     /// partition.
     max_persisted_sequence_number: Option<SequenceNumber>,
+
+    /// The greatest [`SequenceNumber`] buffered by this partition, including
+    /// writes since moved to the persisting buffer or persisted.
+    ///
+    /// Unlike the sequence number range of [`Self::buffer`], this watermark
+    /// survives persistence, so replayed writes are detected for as long as the
+    /// partition is held in memory.
+    max_buffered_sequence_number: Option<SequenceNumber>,
 }
 
 impl PartitionData {
@@ -112,6 +179,7 @@ impl PartitionData {
             buffer: DataBuffer::default(),
             persisting: None,
             max_persisted_sequence_number,
+            max_buffered_sequence_number: None,
         }
     }
 
@@ -123,10 +191,10 @@ impl PartitionData {
     /// is strictly less than the value of
     /// [`Self::max_persisted_sequence_number()`]).
     ///
-    /// # Panics
-    ///
-    /// This method panics if `sequence_number` is not strictly greater than
-    /// previous calls. This is not enforced for writes before the persist mark.
+    /// This method returns [`BufferError::SkipDuplicate`] if `sequence_number`
+    /// is not strictly greater than the sequence number of a previously
+    /// buffered write, as the write is being replayed (i.e. after a write
+    /// buffer consumer rebalance).
     pub(super) fn buffer_write(
         &mut self,
         mb: MutableBatch,
@@ -144,9 +212,24 @@ impl PartitionData {
             }
         }
 
+        // Skip any ops that have already been buffered.
+        if let Some(max) = self.max_buffered_sequence_number {
+            if sequence_number <= max {
+                warn!(
+                    shard_id=%self.shard_id,
+                    partition_id=%self.partition_id,
+                    op_sequence_number=?sequence_number,
+                    max_buffered_sequence_number=?max,
+                    "skipping duplicate write"
+                );
+                return Err(BufferError::SkipDuplicate);
+            }
+        }
+
         // Buffer the write, which ensures monotonicity of writes within the
         // buffer itself.
         self.buffer.buffer_write(mb, sequence_number)?;
+        self.max_buffered_sequence_number = Some(sequence_number);
 
         trace!(
             shard_id = %self.shard_id,
@@ -165,6 +248,9 @@ impl PartitionData {
 
     /// Return all data for this partition, ordered by the [`SequenceNumber`]
     /// from which it was buffered with.
+    ///
+    /// Queries pin the partition data instead, see [`Self::pin_query_data()`].
+    #[cfg(test)]
     pub(crate) fn get_query_data(&mut self) -> Option<QueryAdaptor> {
         // Extract the buffered data, if any.
         let buffered_data = self.buffer.get_query_data();
@@ -208,8 +294,31 @@ impl PartitionData {
         Some(QueryAdaptor::new(self.partition_id, data))
     }
 
+    /// Pin all data of this partition for a query.
+    ///
+    /// No [`RecordBatch`] is generated while the partition is locked - the buffered writes are frozen, and later
+    /// writes are buffered separately. The returned [`PinnedPartition`] retains
+    /// the pinned data until the query is done with it, even if the partition
+    /// is persisted in the meantime.
+    pub(crate) fn pin_query_data(&mut self) -> PinnedPartition {
+        let persisting = self
+            .persisting
+            .iter()
+            .flat_map(|b| b.get_query_data())
+            .collect();
+        let buffered = self.buffer.pin_query_data();
+
+        PinnedPartition {
+            partition_id: self.partition_id,
+            persisting,
+            buffered,
+            max_persisted_sequence_number: self.max_persisted_sequence_number,
+            max_sequence_number: self.sequence_number_range().inclusive_max(),
+        }
+    }
+
     /// Return the range of [`SequenceNumber`] currently queryable by calling
-    /// [`PartitionData::get_query_data()`].
+    /// [`PartitionData::pin_query_data()`].
     ///
     /// This includes buffered data, snapshots, and currently persisting data.
     pub(super) fn sequence_number_range(&self) -> SequenceNumberRange {
@@ -285,7 +394,9 @@ impl PartitionData {
             "starting persistence on partition in persisting state"
         );
 
-        let persisting = std::mem::take(&mut self.buffer).into_persisting()?;
+        let (buffer, persisting) = std::mem::take(&mut self.buffer).into_persisting();
+        self.buffer = buffer;
+        let persisting = persisting?;
 
         // From this point on, all code MUST be infallible or the buffered data
         // contained within persisting may be dropped.
@@ -578,6 +689,89 @@ mod tests {
         }
     }
 
+    // Pinned data is not affected by writes or persist operations that happen
+    // after it was pinned.
+    #[tokio::test]
+    async fn test_pin_query_data() {
+        let mut p = PartitionData::new(
+            PARTITION_ID,
+            PARTITION_KEY.clone(),
+            ShardId::new(2),
+            NamespaceId::new(3),
+            TableId::new(4),
+            Arc::new(DeferredLoad::new(Duration::from_secs(1), async {
+                TABLE_NAME.clone()
+            })),
+            SortKeyState::Provided(None),
+            None,
+        );
+
+        assert!(p.pin_query_data().into_query_data().is_none());
+
+        let mb = lp_to_mutable_batch(r#"bananas,city=London people=2,pigeons="millions" 10"#).1;
+        p.buffer_write(mb, SequenceNumber::new(1))
+            .expect("write should succeed");
+        let pinned = p.pin_query_data();
+
+        // Write, persist and write again after pinning the data.
+        let mb = lp_to_mutable_batch(r#"bananas,city=Madrid people=4,pigeons="none" 20"#).1;
+        p.buffer_write(mb, SequenceNumber::new(2))
+            .expect("write should succeed");
+        p.mark_persisting().expect("must contain existing data");
+        p.mark_persisted(SequenceNumber::new(2));
+        let mb = lp_to_mutable_batch(r#"bananas,city=Paris people=6,pigeons="few" 30"#).1;
+        p.buffer_write(mb, SequenceNumber::new(3))
+            .expect("write should succeed");
+
+        // The pinned data still reflects the partition at the time it was
+        // pinned.
+        assert_eq!(pinned.partition_id(), PARTITION_ID);
+        assert_eq!(pinned.max_persisted_sequence_number(), None);
+        assert_eq!(pinned.max_sequence_number(), Some(SequenceNumber::new(1)));
+        let data = pinned.into_query_data().expect("must have data");
+        let expected = [
+            "+--------+--------+----------+--------------------------------+",
+            "| city   | people | pigeons  | time                           |",
+            "+--------+--------+----------+--------------------------------+",
+            "| London | 2      | millions | 1970-01-01T00:00:00.000000010Z |",
+            "+--------+--------+----------+--------------------------------+",
+        ];
+        assert_batches_eq!(
+            expected,
+            &*data
+                .record_batches()
+                .iter()
+                .map(Deref::deref)
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+
+        // While pinning the partition again observes the changes.
+        let pinned = p.pin_query_data();
+        assert_eq!(
+            pinned.max_persisted_sequence_number(),
+            Some(SequenceNumber::new(2))
+        );
+        assert_eq!(pinned.max_sequence_number(), Some(SequenceNumber::new(3)));
+        let data = pinned.into_query_data().expect("must have data");
+        let expected = [
+            "+-------+--------+---------+--------------------------------+",
+            "| city  | people | pigeons | time                           |",
+            "+-------+--------+---------+--------------------------------+",
+            "| Paris | 6      | few     | 1970-01-01T00:00:00.000000030Z |",
+            "+-------+--------+---------+--------------------------------+",
+        ];
+        assert_batches_eq!(
+            expected,
+            &*data
+                .record_batches()
+                .iter()
+                .map(Deref::deref)
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+    }
+
     // Test persist operations against the partition, ensuring data is readable
     // both before, during, and after a persist takes place.
     #[tokio::test]
@@ -974,7 +1168,6 @@ mod tests {
 
     // Perform writes with non-monotonic sequence numbers.
     #[tokio::test]
-    #[should_panic(expected = "monotonicity violation")]
     async fn test_non_monotonic_writes() {
         let mut p = PartitionData::new(
             PARTITION_ID,
@@ -989,11 +1182,20 @@ mod tests {
             None,
         );
 
-        // Perform out of order writes.
+        // Perform out of order writes, which are skipped as duplicates.
         let mb = lp_to_mutable_batch(r#"bananas,city=London people=2,pigeons="millions" 10"#).1;
         p.buffer_write(mb.clone(), SequenceNumber::new(2))
             .expect("write should succeed");
-        let _ = p.buffer_write(mb, SequenceNumber::new(1));
+        let err = p
+            .buffer_write(mb.clone(), SequenceNumber::new(1))
+            .expect_err("out of order write should be skipped");
+        assert_matches!(err, BufferError::SkipDuplicate);
+        let err = p
+            .buffer_write(mb, SequenceNumber::new(2))
+            .expect_err("repeated write should be skipped");
+        assert_matches!(err, BufferError::SkipDuplicate);
+
+        assert_eq!(p.get_query_data().unwrap().record_batches().len(), 1);
     }
 
     #[tokio::test]
@@ -1085,13 +1287,9 @@ mod tests {
         p.mark_persisted(SequenceNumber::new(42));
     }
 
-    // Because persisting moves the data out of the "hot" buffer, the sequence
-    // numbers are not validated as being monotonic (the new buffer has no
-    // sequence numbers to compare against).
-    //
-    // Instead this check is performed when marking the persist op as complete.
+    // Persisting moves the data out of the "hot" buffer, but the partition
+    // still detects replayed writes of the persisting data.
     #[tokio::test]
-    #[should_panic(expected = "monotonicity violation")]
     async fn test_non_monotonic_writes_with_persistence() {
         let mut p = PartitionData::new(
             PARTITION_ID,
@@ -1112,22 +1310,18 @@ mod tests {
 
         assert!(p.mark_persisting().is_some());
 
-        // This succeeds due to a new buffer being in place that cannot track
-        // previous sequence numbers.
-        p.buffer_write(mb, SequenceNumber::new(1))
-            .expect("out of order write should succeed");
+        let err = p
+            .buffer_write(mb, SequenceNumber::new(1))
+            .expect_err("out of order write should be skipped");
+        assert_matches!(err, BufferError::SkipDuplicate);
 
-        // The assert on non-monotonic writes moves to here instead.
         p.mark_persisted(SequenceNumber::new(42));
     }
 
-    // As above, the sequence numbers are not tracked between buffer instances.
-    //
     // This test ensures that a partition can tolerate replayed ops prior to the
-    // persist marker when first initialising. However once a partition has
-    // buffered beyond the persist marker, it cannot re-buffer ops after it.
+    // persist marker when first initialising, and skips replayed ops after it
+    // once it has buffered beyond the persist marker.
     #[tokio::test]
-    #[should_panic(expected = "monotonicity violation")]
     async fn test_non_monotonic_writes_after_persistence() {
         let mut p = PartitionData::new(
             PARTITION_ID,
@@ -1149,22 +1343,23 @@ mod tests {
         assert!(p.mark_persisting().is_some());
         p.mark_persisted(SequenceNumber::new(42));
 
-        // This should fail as the write "goes backwards".
         let err = p
             .buffer_write(mb.clone(), SequenceNumber::new(1))
-            .expect_err("out of order write should succeed");
+            .expect_err("out of order write should be skipped");
 
         // This assert ensures replay is tolerated, with the previously
         // persisted ops skipping instead of being applied.
         assert_matches!(err, BufferError::SkipPersisted);
 
-        // Until a write is accepted.
         p.buffer_write(mb.clone(), SequenceNumber::new(100))
-            .expect("out of order write should succeed");
+            .expect("write should succeed");
 
-        // At which point a write between the persist marker and the maximum
-        // applied sequence number is a hard error.
-        let _ = p.buffer_write(mb, SequenceNumber::new(50));
+        // A write between the persist marker and the maximum applied sequence
+        // number is a replayed duplicate.
+        let err = p
+            .buffer_write(mb, SequenceNumber::new(50))
+            .expect_err("out of order write should be skipped");
+        assert_matches!(err, BufferError::SkipDuplicate);
     }
 
     // As above, but with a pre-configured persist marker greater than the
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+#[cfg(test)]
 use arrow::record_batch::RecordBatch;
 use data_types::SequenceNumber;
 use mutable_batch::MutableBatch;
@@ -7,13 +8,17 @@ use mutable_batch::MutableBatch;
 use crate::data::SequenceNumberRange;
 
 mod always_some;
+mod dictionary;
 mod mutable_buffer;
 mod state_machine;
 pub(crate) mod traits;
 
+pub(crate) use mutable_buffer::FrozenBuffer;
 pub(crate) use state_machine::*;
 
-use self::{always_some::AlwaysSome, traits::Queryable};
+#[cfg(test)]
+use self::traits::Queryable;
+use self::{always_some::AlwaysSome, dictionary::PartitionDictionaries};
 
 /// The current state of the [`BufferState`] state machine.
 ///
@@ -27,12 +32,6 @@ enum FsmState {
     Buffering(BufferState<Buffering>),
 }
 
-impl Default for FsmState {
-    fn default() -> Self {
-        Self::Buffering(BufferState::new())
-    }
-}
-
 impl FsmState {
     /// Return the current range of writes in the [`BufferState`] state machine,
     /// if any.
@@ -45,15 +44,35 @@ impl FsmState {
 
 /// A helper wrapper over the [`BufferState`] FSM to abstract the caller from
 /// state transitions during reads and writes from the underlying buffer.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 #[must_use = "DataBuffer should not be dropped unused"]
-pub(crate) struct DataBuffer(AlwaysSome<FsmState>);
+pub(crate) struct DataBuffer {
+    fsm: AlwaysSome<FsmState>,
+
+    /// The tag value dictionaries shared by all buffers of the partition.
+    dictionaries: PartitionDictionaries,
+}
+
+impl Default for DataBuffer {
+    fn default() -> Self {
+        Self::new(PartitionDictionaries::default())
+    }
+}
 
 impl DataBuffer {
+    fn new(dictionaries: PartitionDictionaries) -> Self {
+        Self {
+            fsm: AlwaysSome::new(FsmState::Buffering(BufferState::with_dictionaries(
+                dictionaries.clone(),
+            ))),
+            dictionaries,
+        }
+    }
+
     /// Return the range of [`SequenceNumber`] currently queryable by calling
-    /// [`Self::get_query_data()`].
+    /// [`Self::pin_query_data()`].
     pub(crate) fn sequence_number_range(&self) -> &SequenceNumberRange {
-        self.0.sequence_number_range()
+        self.fsm.sequence_number_range()
     }
 
     /// Buffer the given [`MutableBatch`] in memory, ordered by the specified
@@ -69,7 +88,7 @@ impl DataBuffer {
         sequence_number: SequenceNumber,
     ) -> Result<(), mutable_batch::Error> {
         // Take ownership of the FSM and apply the write.
-        self.0.mutate(|fsm| match fsm {
+        self.fsm.mutate(|fsm| match fsm {
             // Mutable stats simply have the write applied.
             FsmState::Buffering(mut b) => {
                 let ret = b.write(mb, sequence_number);
@@ -80,9 +99,10 @@ impl DataBuffer {
 
     /// Return all data for this buffer, ordered by the [`SequenceNumber`] from
     /// which it was buffered with.
+    #[cfg(test)]
     pub(crate) fn get_query_data(&mut self) -> Vec<Arc<RecordBatch>> {
         // Take ownership of the FSM and return the data within it.
-        self.0.mutate(|fsm| match fsm {
+        self.fsm.mutate(|fsm| match fsm {
             // The buffering state can return data.
             FsmState::Buffering(b) => {
                 let ret = b.get_query_data();
@@ -91,22 +111,41 @@ impl DataBuffer {
         })
     }
 
-    // Deconstruct the [`DataBuffer`] into the underlying FSM in a
-    // [`Persisting`] state, if the buffer contains any data.
-    pub(crate) fn into_persisting(self) -> Option<BufferState<Persisting>> {
-        let p = match self.0.into_inner() {
+    /// Return all data for this buffer, ordered by the [`SequenceNumber`] from
+    /// which it was buffered with, without generating any record batches.
+    ///
+    /// The buffered writes are frozen, and subsequent writes are buffered
+    /// separately, so the returned data never observes them.
+    pub(crate) fn pin_query_data(&mut self) -> Vec<Arc<FrozenBuffer>> {
+        self.fsm.mutate(|fsm| match fsm {
+            FsmState::Buffering(mut b) => {
+                let ret = b.pin();
+                (FsmState::Buffering(b), ret)
+            }
+        })
+    }
+
+    /// Deconstruct the [`DataBuffer`] into the underlying FSM in a
+    /// [`Persisting`] state, if the buffer contains any data.
+    ///
+    /// The returned [`DataBuffer`] is empty and continues to share the tag
+    /// value dictionaries of the persisting data, so that subsequent snapshots
+    /// reuse them.
+    pub(crate) fn into_persisting(self) -> (Self, Option<BufferState<Persisting>>) {
+        let next = Self::new(self.dictionaries);
+        let p = match self.fsm.into_inner() {
             FsmState::Buffering(b) => {
                 // Attempt to snapshot the buffer to an immutable state.
                 match b.snapshot() {
                     Transition::Ok(b) => b.into_persisting(),
                     Transition::Unchanged(_) => {
                         // The buffer contains no data.
-                        return None;
+                        return (next, None);
                     }
                 }
             }
         };
 
-        Some(p)
+        (next, Some(p))
     }
 }
@@ -0,0 +1,222 @@
+//! Tag value dictionaries shared by the snapshots of a partition.
+
+use std::{collections::HashMap, sync::Arc};
+
+use arrow::{
+    array::{ArrayData, ArrayDataBuilder, ArrayRef, DictionaryArray},
+    datatypes::{DataType, Int32Type},
+    record_batch::RecordBatch,
+};
+use arrow_util::dictionary::StringDictionary;
+use mutable_batch::{column::ColumnData, MutableBatch};
+use parking_lot::Mutex;
+use schema::{InfluxColumnType, Projection};
+
+/// The tag value dictionaries of a partition, shared by all of its buffers.
+///
+/// Every tag column of a [`MutableBatch`] carries its own dictionary, which is
+/// copied into a new arrow array each time a snapshot is generated from it.
+/// Instead, the snapshots of a partition are encoded against a single
+/// dictionary per tag column, so that successive snapshots (and the
+/// persisting data queried alongside the buffer) share their dictionary
+/// values rather than each holding a copy.
+///
+/// Values are never removed from a shared dictionary - it grows with the tag
+/// cardinality of the partition.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PartitionDictionaries(Arc<Mutex<HashMap<String, SharedDictionary>>>);
+
+/// The shared dictionary of a single tag column.
+#[derive(Debug, Default)]
+struct SharedDictionary {
+    dictionary: StringDictionary<i32>,
+
+    /// The arrow representation of the values in `dictionary` when it was
+    /// last generated.
+    values: Option<ArrayData>,
+}
+
+impl SharedDictionary {
+    /// Return the arrow representation of the dictionary values, generating
+    /// it only if new values were added since the last call.
+    fn values(&mut self) -> ArrayData {
+        let len = self.dictionary.values().len();
+        match &self.values {
+            Some(values) if values.len() == len => values.clone(),
+            _ => {
+                let values = self.dictionary.values().to_arrow(None).data().clone();
+                self.values = Some(values.clone());
+                values
+            }
+        }
+    }
+}
+
+/// Encodes the tag columns of a single [`MutableBatch`] buffer against the
+/// [`PartitionDictionaries`] of its partition.
+///
+/// The dictionary of a [`MutableBatch`] column is append-only, so the mapping
+/// of its keys to the keys of the shared dictionary is retained and only
+/// extended by the values added since the last snapshot.
+#[derive(Debug, Default)]
+pub(super) struct DictionaryEncoder {
+    shared: PartitionDictionaries,
+
+    /// The shared key of each buffer dictionary key, by tag column name.
+    mappings: HashMap<String, Vec<i32>>,
+}
+
+impl DictionaryEncoder {
+    /// Initialise an encoder for a new, empty buffer.
+    pub(super) fn new(shared: PartitionDictionaries) -> Self {
+        Self {
+            shared,
+            mappings: Default::default(),
+        }
+    }
+
+    /// Convert all the data in `batch` into a [`RecordBatch`], with the tag
+    /// columns referencing the shared dictionaries.
+    ///
+    /// # Panics
+    ///
+    /// If `batch` cannot be converted into a [`RecordBatch`], this method
+    /// panics.
+    pub(super) fn to_arrow(&mut self, batch: &MutableBatch) -> RecordBatch {
+        let schema = batch
+            .schema(Projection::All)
+            .expect("failed to generate buffer schema");
+
+        let mut dictionaries = self.shared.0.lock();
+        let columns = schema
+            .iter()
+            .map(|(influx_type, field)| {
+                let column = batch
+                    .column(field.name())
+                    .expect("schema contains non-existent column");
+
+                match (influx_type, column.data()) {
+                    (InfluxColumnType::Tag, ColumnData::Tag(keys, dictionary, _)) => {
+                        let shared = dictionaries.entry(field.name().clone()).or_default();
+                        let mapping = self.mappings.entry(field.name().clone()).or_default();
+
+                        // Map the buffer dictionary values added since the
+                        // last snapshot.
+                        for value in dictionary.values().iter().skip(mapping.len()) {
+                            mapping.push(shared.dictionary.lookup_value_or_insert(value));
+                        }
+
+                        let data = ArrayDataBuilder::new(DataType::Dictionary(
+                            Box::new(DataType::Int32),
+                            Box::new(DataType::Utf8),
+                        ))
+                        .len(keys.len())
+                        .add_buffer(
+                            keys.iter()
+                                .map(|&key| match key {
+                                    // Null rows carry an invalid key that is
+                                    // masked by the null bitmap.
+                                    key if key < 0 => key,
+                                    key => mapping[key as usize],
+                                })
+                                .collect(),
+                        )
+                        .add_child_data(shared.values())
+                        .null_bit_buffer(Some(column.valid_mask().to_arrow()))
+                        .build()
+                        .expect("valid dictionary array data");
+
+                        Arc::new(DictionaryArray::<Int32Type>::from(data)) as ArrayRef
+                    }
+                    _ => column
+                        .to_arrow()
+                        .expect("failed to convert buffer column to arrow"),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        RecordBatch::try_new(schema.into(), columns).expect("failed to snapshot buffer data")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_util::assert_batches_eq;
+    use mutable_batch_lp::test_helpers::lp_to_mutable_batch;
+
+    use super::*;
+
+    /// Return a pointer to the dictionary values of `column` in `batch`.
+    fn values_ptr(batch: &RecordBatch, column: &str) -> *const u8 {
+        let idx = batch.schema().index_of(column).unwrap();
+        batch.column(idx).data().child_data()[0].buffers()[1].as_ptr()
+    }
+
+    #[test]
+    fn test_snapshots_share_dictionary_values() {
+        let mut encoder = DictionaryEncoder::default();
+
+        let (_, mut mb) = lp_to_mutable_batch("m,t=a,u=x v=1 1\nm,t=b v=2 2");
+        let first = encoder.to_arrow(&mb);
+        let expected = vec![
+            "+---+--------------------------------+---+---+",
+            "| t | time                           | u | v |",
+            "+---+--------------------------------+---+---+",
+            "| a | 1970-01-01T00:00:00.000000001Z | x | 1 |",
+            "| b | 1970-01-01T00:00:00.000000002Z |   | 2 |",
+            "+---+--------------------------------+---+---+",
+        ];
+        assert_batches_eq!(&expected, &[first.clone()]);
+
+        // A write without new tag values reuses the dictionary values.
+        let (_, other) = lp_to_mutable_batch("m,t=b,u=x v=3 3");
+        mb.extend_from(&other).unwrap();
+        let second = encoder.to_arrow(&mb);
+        assert_eq!(second.num_rows(), 3);
+        assert_eq!(values_ptr(&first, "t"), values_ptr(&second, "t"));
+        assert_eq!(values_ptr(&first, "u"), values_ptr(&second, "u"));
+
+        // Only the dictionary of the column with a new value is regenerated.
+        let (_, other) = lp_to_mutable_batch("m,t=c,u=x v=4 4");
+        mb.extend_from(&other).unwrap();
+        let third = encoder.to_arrow(&mb);
+        assert_ne!(values_ptr(&second, "t"), values_ptr(&third, "t"));
+        assert_eq!(values_ptr(&second, "u"), values_ptr(&third, "u"));
+
+        let expected = vec![
+            "+---+--------------------------------+---+---+",
+            "| t | time                           | u | v |",
+            "+---+--------------------------------+---+---+",
+            "| a | 1970-01-01T00:00:00.000000001Z | x | 1 |",
+            "| b | 1970-01-01T00:00:00.000000002Z |   | 2 |",
+            "| b | 1970-01-01T00:00:00.000000003Z | x | 3 |",
+            "| c | 1970-01-01T00:00:00.000000004Z | x | 4 |",
+            "+---+--------------------------------+---+---+",
+        ];
+        assert_batches_eq!(&expected, &[third]);
+    }
+
+    #[test]
+    fn test_new_buffer_rekeys_against_shared_dictionary() {
+        let dictionaries = PartitionDictionaries::default();
+
+        let (_, mb) = lp_to_mutable_batch("m,t=a v=1 1\nm,t=b v=2 2");
+        let first = DictionaryEncoder::new(dictionaries.clone()).to_arrow(&mb);
+
+        // The buffer dictionary of the next buffer assigns different keys to
+        // the same values.
+        let (_, mb) = lp_to_mutable_batch("m,t=b v=3 3\nm,t=a v=4 4");
+        let second = DictionaryEncoder::new(dictionaries).to_arrow(&mb);
+        assert_eq!(values_ptr(&first, "t"), values_ptr(&second, "t"));
+
+        let expected = vec![
+            "+---+--------------------------------+---+",
+            "| t | time                           | v |",
+            "+---+--------------------------------+---+",
+            "| b | 1970-01-01T00:00:00.000000003Z | 3 |",
+            "| a | 1970-01-01T00:00:00.000000004Z | 4 |",
+            "+---+--------------------------------+---+",
+        ];
+        assert_batches_eq!(&expected, &[second]);
+    }
+}
@@ -2,7 +2,10 @@ use std::sync::Arc;
 
 use arrow::record_batch::RecordBatch;
 use mutable_batch::MutableBatch;
-use schema::Projection;
+use once_cell::sync::OnceCell;
+use parking_lot::Mutex;
+
+use super::dictionary::{DictionaryEncoder, PartitionDictionaries};
 
 /// A [`Buffer`] is an internal mutable buffer wrapper over a [`MutableBatch`]
 /// for the [`BufferState`] FSM.
@@ -10,12 +13,29 @@ use schema::Projection;
 /// A [`Buffer`] can contain no writes.
 ///
 /// [`BufferState`]: super::super::BufferState
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub(super) struct Buffer {
     buffer: Option<MutableBatch>,
+
+    /// Encodes the tag columns of `buffer` against the shared dictionaries of
+    /// the partition when generating [`RecordBatch`] from it.
+    encoder: Mutex<DictionaryEncoder>,
+
+    /// The tag value dictionaries of the partition.
+    dictionaries: PartitionDictionaries,
 }
 
 impl Buffer {
+    /// Initialise an empty [`Buffer`] sharing the tag value `dictionaries` of
+    /// its partition.
+    pub(super) fn new(dictionaries: PartitionDictionaries) -> Self {
+        Self {
+            buffer: None,
+            encoder: Mutex::new(DictionaryEncoder::new(dictionaries.clone())),
+            dictionaries,
+        }
+    }
+
     /// Apply `batch` to the in-memory buffer.
     ///
     /// # Data Loss
@@ -31,6 +51,20 @@ impl Buffer {
         Ok(())
     }
 
+    /// Generates a [`RecordBatch`] from the data in this [`Buffer`] without
+    /// consuming it.
+    ///
+    /// If this [`Buffer`] is empty when this method is called, the call is a
+    /// NOP and [`None`] is returned.
+    ///
+    /// # Panics
+    ///
+    /// If generating the [`RecordBatch`] fails, this method panics.
+    pub(super) fn to_arrow(&self) -> Option<RecordBatch> {
+        let buffer = self.buffer.as_ref()?;
+        Some(self.encoder.lock().to_arrow(buffer))
+    }
+
     /// Generates a [`RecordBatch`] from the data in this [`Buffer`].
     ///
     /// If this [`Buffer`] is empty when this method is called, the call is a
@@ -40,18 +74,57 @@ impl Buffer {
     ///
     /// If generating the snapshot fails, this method panics.
     pub(super) fn snapshot(self) -> Option<Arc<RecordBatch>> {
-        Some(Arc::new(
-            self.buffer?
-                .to_arrow(Projection::All)
-                .expect("failed to snapshot buffer data"),
-        ))
+        let buffer = self.buffer?;
+        Some(Arc::new(self.encoder.into_inner().to_arrow(&buffer)))
+    }
+
+    /// Move the data in this [`Buffer`] into an immutable [`FrozenBuffer`],
+    /// leaving this [`Buffer`] empty.
+    ///
+    /// If this [`Buffer`] is empty when this method is called, the call is a
+    /// NOP and [`None`] is returned.
+    pub(super) fn freeze(&mut self) -> Option<FrozenBuffer> {
+        let buffer = self.buffer.take()?;
+        let encoder = std::mem::replace(
+            &mut self.encoder,
+            Mutex::new(DictionaryEncoder::new(self.dictionaries.clone())),
+        );
+
+        Some(FrozenBuffer {
+            buffer,
+            encoder,
+            data: OnceCell::new(),
+        })
     }
 
     pub(super) fn is_empty(&self) -> bool {
         self.buffer.is_none()
     }
+}
 
-    pub(super) fn buffer(&self) -> Option<&MutableBatch> {
-        self.buffer.as_ref()
+/// The data of a [`Buffer`] that no longer accepts writes.
+///
+/// Freezing a buffer is cheap, as the [`RecordBatch`] is only generated when
+/// first read, which need not happen while holding the partition lock. The
+/// [`RecordBatch`] is then shared by all subsequent reads.
+#[derive(Debug)]
+pub(crate) struct FrozenBuffer {
+    buffer: MutableBatch,
+    encoder: Mutex<DictionaryEncoder>,
+    data: OnceCell<Arc<RecordBatch>>,
+}
+
+impl FrozenBuffer {
+    /// Return the data of this [`FrozenBuffer`], generating the
+    /// [`RecordBatch`] on first use.
+    ///
+    /// # Panics
+    ///
+    /// If generating the [`RecordBatch`] fails, this method panics.
+    pub(crate) fn to_arrow(&self) -> Arc<RecordBatch> {
+        Arc::clone(
+            self.data
+                .get_or_init(|| Arc::new(self.encoder.lock().to_arrow(&self.buffer))),
+        )
     }
 }
@@ -14,6 +14,7 @@ pub(crate) use persisting::*;
 
 use crate::data::SequenceNumberRange;
 
+use super::dictionary::PartitionDictionaries;
 use super::traits::{Queryable, Writeable};
 
 /// A result type for fallible transitions.
@@ -78,8 +79,14 @@ pub(crate) struct BufferState<T> {
 impl BufferState<Buffering> {
     /// Initialise a new buffer state machine.
     pub(super) fn new() -> Self {
+        Self::with_dictionaries(PartitionDictionaries::default())
+    }
+
+    /// Initialise a new buffer state machine that shares the tag value
+    /// `dictionaries` of its partition with previous buffers.
+    pub(super) fn with_dictionaries(dictionaries: PartitionDictionaries) -> Self {
         Self {
-            state: Buffering::default(),
+            state: Buffering::new(dictionaries),
             sequence_range: SequenceNumberRange::default(),
         }
     }
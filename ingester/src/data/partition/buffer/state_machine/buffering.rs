@@ -4,18 +4,22 @@ use std::sync::Arc;
 
 use arrow::record_batch::RecordBatch;
 use mutable_batch::MutableBatch;
-use schema::Projection;
 
 use crate::data::partition::buffer::{
-    mutable_buffer::Buffer,
+    dictionary::PartitionDictionaries,
+    mutable_buffer::{Buffer, FrozenBuffer},
     traits::{Queryable, Writeable},
 };
 
 use super::{snapshot::Snapshot, BufferState, Transition};
 
 /// The FSM starting ingest state - a mutable buffer collecting writes.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub(crate) struct Buffering {
+    /// Writes frozen when their data was pinned by a query, ordered before the
+    /// writes in `buffer`.
+    frozen: Vec<Arc<FrozenBuffer>>,
+
     /// The buffer for incoming writes.
     ///
     /// This buffer MAY be empty when no writes have occured since transitioning
@@ -23,6 +27,32 @@ pub(crate) struct Buffering {
     buffer: Buffer,
 }
 
+impl Buffering {
+    /// Initialise an empty [`Buffering`] state, encoding snapshots against the
+    /// tag value `dictionaries` of its partition.
+    pub(super) fn new(dictionaries: PartitionDictionaries) -> Self {
+        Self {
+            frozen: vec![],
+            buffer: Buffer::new(dictionaries),
+        }
+    }
+
+    /// Freeze the buffered writes and return all frozen data of this state.
+    ///
+    /// The returned data is immutable - later writes are buffered separately
+    /// and only become visible to subsequent reads.
+    pub(super) fn pin(&mut self) -> Vec<Arc<FrozenBuffer>> {
+        if let Some(frozen) = self.buffer.freeze() {
+            self.frozen.push(Arc::new(frozen));
+        }
+        self.frozen.clone()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.frozen.is_empty() && self.buffer.is_empty()
+    }
+}
+
 /// Implement on-demand querying of the buffered contents without storing the
 /// generated snapshot.
 ///
@@ -32,17 +62,11 @@ pub(crate) struct Buffering {
 /// context.
 impl Queryable for Buffering {
     fn get_query_data(&self) -> Vec<Arc<RecordBatch>> {
-        let data = self.buffer.buffer().map(|v| {
-            Arc::new(
-                v.to_arrow(Projection::All)
-                    .expect("failed to snapshot buffer data"),
-            )
-        });
-
-        match data {
-            Some(v) => vec![v],
-            None => vec![],
-        }
+        self.frozen
+            .iter()
+            .map(|frozen| frozen.to_arrow())
+            .chain(self.buffer.to_arrow().map(Arc::new))
+            .collect()
     }
 }
 
@@ -57,27 +81,86 @@ impl BufferState<Buffering> {
     ///
     /// This returns [`Transition::Unchanged`] if this buffer contains no data.
     pub(crate) fn snapshot(self) -> Transition<Snapshot, Buffering> {
-        if self.state.buffer.is_empty() {
+        if self.state.is_empty() {
             // It is a logical error to snapshot an empty buffer.
             return Transition::unchanged(self);
         }
 
-        // Generate a snapshot from the buffer.
-        let snap = self
-            .state
-            .buffer
-            .snapshot()
-            .expect("snapshot of non-empty buffer should succeed");
+        // Generate a snapshot from the frozen data and the buffer, preserving
+        // the order of writes.
+        let Buffering { frozen, buffer } = self.state;
+        let snapshots = frozen
+            .iter()
+            .map(|frozen| frozen.to_arrow())
+            .chain(buffer.snapshot())
+            .collect();
 
         // And transition to the WithSnapshot state.
-        Transition::ok(Snapshot::new(vec![snap]), self.sequence_range)
+        Transition::ok(Snapshot::new(snapshots), self.sequence_range)
+    }
+
+    /// Pin the data in this buffer for a query, see [`Buffering::pin()`].
+    pub(crate) fn pin(&mut self) -> Vec<Arc<FrozenBuffer>> {
+        self.state.pin()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use arrow_util::assert_batches_eq;
+    use data_types::SequenceNumber;
+    use mutable_batch_lp::test_helpers::lp_to_mutable_batch;
+
     use super::*;
 
+    #[test]
+    fn test_pinned_data_is_immutable() {
+        let mut b = BufferState::new();
+        assert!(b.pin().is_empty());
+
+        b.write(lp_to_mutable_batch("m,t=a v=1 1").1, SequenceNumber::new(1))
+            .unwrap();
+        let pinned = b.pin();
+        assert_eq!(pinned.len(), 1);
+
+        // Writes after pinning are not visible in the pinned data.
+        b.write(lp_to_mutable_batch("m,t=b v=2 2").1, SequenceNumber::new(2))
+            .unwrap();
+        let expected = vec![
+            "+---+--------------------------------+---+",
+            "| t | time                           | v |",
+            "+---+--------------------------------+---+",
+            "| a | 1970-01-01T00:00:00.000000001Z | 1 |",
+            "+---+--------------------------------+---+",
+        ];
+        assert_batches_eq!(&expected, &[pinned[0].to_arrow().as_ref().clone()]);
+
+        // But they are read after the pinned data, which shares its batch with
+        // the query.
+        let data = b.get_query_data();
+        assert_eq!(data.len(), 2);
+        assert!(Arc::ptr_eq(&data[0], &pinned[0].to_arrow()));
+        let expected = vec![
+            "+---+--------------------------------+---+",
+            "| t | time                           | v |",
+            "+---+--------------------------------+---+",
+            "| a | 1970-01-01T00:00:00.000000001Z | 1 |",
+            "| b | 1970-01-01T00:00:00.000000002Z | 2 |",
+            "+---+--------------------------------+---+",
+        ];
+        assert_batches_eq!(
+            &expected,
+            &data.iter().map(|b| b.as_ref().clone()).collect::<Vec<_>>()
+        );
+
+        // The snapshot contains both, in write order.
+        let snapshot = match b.snapshot() {
+            Transition::Ok(v) => v,
+            Transition::Unchanged(_) => panic!("did not transition to snapshot state"),
+        };
+        assert_eq!(snapshot.get_query_data(), data);
+    }
+
     #[test]
     fn test_empty_buffer_does_not_snapshot() {
         let b = BufferState::new();
@@ -317,6 +317,7 @@ mod tests {
             partition_key: stored_partition_key.clone(),
             sort_key: vec!["dos".to_string(), "bananas".to_string()],
             persisted_sequence_number: Default::default(),
+            cold_compacted_at: Default::default(),
         };
 
         let cache = new_cache(inner, [partition]);
@@ -376,6 +377,7 @@ mod tests {
             partition_key: PARTITION_KEY.into(),
             sort_key: Default::default(),
             persisted_sequence_number: Default::default(),
+            cold_compacted_at: Default::default(),
         };
 
         let cache = new_cache(inner, [partition]);
@@ -420,6 +422,7 @@ mod tests {
             partition_key: PARTITION_KEY.into(),
             sort_key: Default::default(),
             persisted_sequence_number: Default::default(),
+            cold_compacted_at: Default::default(),
         };
 
         let cache = new_cache(inner, [partition]);
@@ -464,6 +467,7 @@ mod tests {
             partition_key: PARTITION_KEY.into(),
             sort_key: Default::default(),
             persisted_sequence_number: Default::default(),
+            cold_compacted_at: Default::default(),
         };
 
         let cache = new_cache(inner, [partition]);
@@ -2,9 +2,11 @@
 
 use std::sync::Arc;
 
-use data_types::{NamespaceId, ShardId, ShardIndex};
+use data_types::{NamespaceId, SequenceNumber, ShardId, ShardIndex};
 use dml::DmlOperation;
 use metric::U64Counter;
+use observability_deps::tracing::warn;
+use parking_lot::Mutex;
 use write_summary::ShardProgress;
 
 use super::{
@@ -47,8 +49,22 @@ pub(crate) struct ShardData {
     /// [`TableData`]: crate::data::table::TableData
     table_name_resolver: Arc<dyn TableNameProvider>,
 
+    /// The greatest [`SequenceNumber`] of the [`DmlOperation`]s applied to
+    /// this shard.
+    ///
+    /// Ops are read from the shard in sequence number order, so an op at or
+    /// below this watermark is a duplicate delivered again by the write buffer
+    /// (i.e. after a consumer rebalance).
+    max_sequence_number: Mutex<Option<SequenceNumber>>,
+
     metrics: Arc<metric::Registry>,
     namespace_count: U64Counter,
+
+    /// Ops skipped because they are at or below [`Self::max_sequence_number`].
+    skipped_duplicate_ops: U64Counter,
+    /// Ops skipped because every partition they write to has already buffered
+    /// or persisted them (i.e. when replaying the shard after a restart).
+    skipped_replayed_ops: U64Counter,
 }
 
 impl ShardData {
@@ -68,15 +84,31 @@ impl ShardData {
             )
             .recorder(&[]);
 
+        let skipped_ops = metrics.register_metric::<U64Counter>(
+            "ingester_skipped_ops",
+            "Number of DML operations skipped because they were already applied",
+        );
+        let skipped_duplicate_ops = skipped_ops.recorder([
+            ("shard_index", shard_index.to_string().into()),
+            ("reason", "shard_watermark".into()),
+        ]);
+        let skipped_replayed_ops = skipped_ops.recorder([
+            ("shard_index", shard_index.to_string().into()),
+            ("reason", "partition_watermark".into()),
+        ]);
+
         Self {
             shard_index,
             shard_id,
             namespaces: Default::default(),
             namespace_name_resolver,
             table_name_resolver,
+            max_sequence_number: Default::default(),
             metrics,
             partition_provider,
             namespace_count,
+            skipped_duplicate_ops,
+            skipped_replayed_ops,
         }
     }
 
@@ -86,6 +118,25 @@ impl ShardData {
         dml_operation: DmlOperation,
         lifecycle_handle: &dyn LifecycleHandle,
     ) -> Result<DmlApplyAction, super::Error> {
+        let sequence_number = dml_operation.meta().sequence().map(|s| s.sequence_number);
+
+        // Skip ops delivered again by the write buffer.
+        if let (Some(sequence_number), Some(max)) =
+            (sequence_number, *self.max_sequence_number.lock())
+        {
+            if sequence_number <= max {
+                warn!(
+                    shard_index=%self.shard_index,
+                    shard_id=%self.shard_id,
+                    op_sequence_number=?sequence_number,
+                    max_sequence_number=?max,
+                    "skipping duplicate op"
+                );
+                self.skipped_duplicate_ops.inc(1);
+                return Ok(DmlApplyAction::Skipped);
+            }
+        }
+
         let namespace_id = dml_operation.namespace_id();
         let namespace_data = self.namespaces.get_or_insert_with(&namespace_id, || {
             // Increase the metric that records the number of namespaces
@@ -102,9 +153,22 @@ impl ShardData {
             ))
         });
 
-        namespace_data
+        let action = namespace_data
             .buffer_operation(dml_operation, lifecycle_handle)
-            .await
+            .await?;
+
+        // Only advance the watermark once the op is applied - the partitions
+        // skip the writes of a failed op that were applied if it is retried.
+        if sequence_number.is_some() {
+            let mut max = self.max_sequence_number.lock();
+            *max = (*max).max(sequence_number);
+        }
+
+        if matches!(action, DmlApplyAction::Skipped) {
+            self.skipped_replayed_ops.inc(1);
+        }
+
+        Ok(action)
     }
 
     /// Gets the namespace data out of the map
@@ -218,4 +282,68 @@ mod tests {
             .fetch();
         assert_eq!(tables, 1);
     }
+
+    #[tokio::test]
+    async fn test_shard_skip_duplicate_ops() {
+        let metrics = Arc::new(metric::Registry::default());
+
+        let partition_provider = Arc::new(MockPartitionProvider::default().with_partition(
+            PartitionData::new(
+                PartitionId::new(0),
+                PartitionKey::from("banana-split"),
+                SHARD_ID,
+                NAMESPACE_ID,
+                TABLE_ID,
+                Arc::new(DeferredLoad::new(Duration::from_secs(1), async {
+                    TableName::from(TABLE_NAME)
+                })),
+                SortKeyState::Provided(None),
+                None,
+            ),
+        ));
+
+        let shard = ShardData::new(
+            SHARD_INDEX,
+            SHARD_ID,
+            Arc::new(MockNamespaceNameProvider::new(NAMESPACE_NAME)),
+            Arc::new(MockTableNameProvider::new(TABLE_NAME)),
+            partition_provider,
+            Arc::clone(&metrics),
+        );
+
+        let op = |sequence_number| {
+            DmlOperation::Write(make_write_op(
+                &PartitionKey::from("banana-split"),
+                SHARD_INDEX,
+                NAMESPACE_ID,
+                TABLE_NAME,
+                TABLE_ID,
+                sequence_number,
+                r#"test_table,city=Medford day="sun",temp=55 22"#,
+            ))
+        };
+
+        for (sequence_number, want_applied) in [(1, true), (2, true), (2, false), (1, false)] {
+            let action = shard
+                .buffer_operation(op(sequence_number), &MockLifecycleHandle::default())
+                .await
+                .expect("buffer op should succeed");
+            assert_eq!(
+                matches!(action, DmlApplyAction::Applied(_)),
+                want_applied,
+                "sequence number {sequence_number}"
+            );
+        }
+
+        let skipped = metrics
+            .get_instrument::<Metric<U64Counter>>("ingester_skipped_ops")
+            .expect("failed to read metric")
+            .get_observer(&Attributes::from(&[
+                ("shard_index", "24"),
+                ("reason", "shard_watermark"),
+            ]))
+            .expect("failed to get observer")
+            .fetch();
+        assert_eq!(skipped, 2);
+    }
 }
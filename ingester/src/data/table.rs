@@ -10,7 +10,7 @@ use parking_lot::{Mutex, RwLock};
 use write_summary::ShardProgress;
 
 use super::{
-    partition::{resolver::PartitionProvider, BufferError, PartitionData},
+    partition::{resolver::PartitionProvider, BufferError, PartitionData, PinnedPartition},
     DmlApplyAction,
 };
 use crate::{arcmap::ArcMap, deferred_load::DeferredLoad, lifecycle::LifecycleHandle};
@@ -106,6 +106,10 @@ pub(crate) struct TableData {
 
     // Map of partition key to its data
     partition_data: RwLock<DoubleRef>,
+
+    /// Excludes writes to the table while a query pins its partitions, see
+    /// [`Self::pin_partitions()`].
+    query_epoch: RwLock<()>,
 }
 
 impl TableData {
@@ -133,6 +137,7 @@ impl TableData {
             namespace_id,
             partition_data: Default::default(),
             partition_provider,
+            query_epoch: Default::default(),
         }
     }
 
@@ -170,10 +175,13 @@ impl TableData {
         let size = batch.size();
         let rows = batch.rows();
         let partition_id = {
+            let _epoch = self.query_epoch.write();
             let mut p = partition_data.lock();
             match p.buffer_write(batch, sequence_number) {
                 Ok(_) => p.partition_id(),
-                Err(BufferError::SkipPersisted) => return Ok(DmlApplyAction::Skipped),
+                Err(BufferError::SkipPersisted | BufferError::SkipDuplicate) => {
+                    return Ok(DmlApplyAction::Skipped)
+                }
                 Err(BufferError::BufferError(e)) => {
                     return Err(super::Error::BufferWrite { source: e })
                 }
@@ -208,6 +216,23 @@ impl TableData {
         self.partition_data.read().by_key.values()
     }
 
+    /// Pin the data of all partitions of this table for a query.
+    ///
+    /// No write is applied to the table while its partitions are pinned, so a
+    /// query observes all partitions as of the same point in the write stream
+    /// of the shard - if it sees a write, it also sees all writes to the table
+    /// with a lower [`SequenceNumber`]. Pinning does not generate any
+    /// [`RecordBatch`], so writes are only blocked briefly.
+    ///
+    /// [`RecordBatch`]: arrow::record_batch::RecordBatch
+    pub(crate) fn pin_partitions(&self) -> Vec<PinnedPartition> {
+        let _epoch = self.query_epoch.read();
+        self.partitions()
+            .into_iter()
+            .map(|p| p.lock().pin_query_data())
+            .collect()
+    }
+
     /// Return the [`PartitionData`] for the specified ID.
     #[allow(unused)]
     pub(crate) fn get_partition(
@@ -0,0 +1,196 @@
+//! Draining an ingester before it is restarted or terminated.
+//!
+//! An ingester records its [`DrainState`] in the catalog, keyed by the address
+//! the queriers use to reach it. When asked to drain, the ingester is marked as
+//! draining, so that queriers prefer other replicas of its shards. It then
+//! persists all the data it buffered and stops consuming its shards, releasing
+//! their leases so that a standby takes over, and is marked as drained. A
+//! drained ingester can be terminated without losing data, and queriers no
+//! longer query it. On startup, the ingester marks itself as active again.
+
+use std::{collections::BTreeSet, sync::Arc};
+
+use backoff::{Backoff, BackoffConfig};
+use data_types::{DrainState, Timestamp};
+use iox_catalog::interface::Catalog;
+use iox_time::TimeProvider;
+use observability_deps::tracing::*;
+use parking_lot::Mutex;
+
+use crate::handler::IngestHandler;
+
+/// The drain state of an ingester, see the [module documentation](self).
+pub struct Drain {
+    address: String,
+    catalog: Arc<dyn Catalog>,
+    handler: Arc<dyn IngestHandler>,
+    time_provider: Arc<dyn TimeProvider>,
+    state: Mutex<DrainState>,
+}
+
+impl std::fmt::Debug for Drain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Drain")
+            .field("address", &self.address)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drain {
+    /// Record the ingester reachable by the queriers at `address` as active,
+    /// draining the shards consumed by `handler` on request.
+    pub async fn new(
+        address: impl Into<String>,
+        catalog: Arc<dyn Catalog>,
+        handler: Arc<dyn IngestHandler>,
+        time_provider: Arc<dyn TimeProvider>,
+    ) -> Result<Self, iox_catalog::interface::Error> {
+        let address = address.into();
+        catalog
+            .repositories()
+            .await
+            .node_drains()
+            .set_state(
+                &address,
+                DrainState::Active,
+                Timestamp::from(time_provider.now()),
+            )
+            .await?;
+        info!(%address, "ingester active");
+
+        Ok(Self {
+            address,
+            catalog,
+            handler,
+            time_provider,
+            state: Mutex::new(DrainState::Active),
+        })
+    }
+
+    /// The current drain state of the ingester.
+    pub fn state(&self) -> DrainState {
+        *self.state.lock()
+    }
+
+    /// Start draining the ingester in the background, unless it is already
+    /// draining or drained. Returns the resulting drain state.
+    pub fn start(self: &Arc<Self>) -> DrainState {
+        {
+            let mut state = self.state.lock();
+            if *state != DrainState::Active {
+                return *state;
+            }
+            *state = DrainState::Draining;
+        }
+
+        let this = Arc::clone(self);
+        tokio::spawn(async move { this.run().await });
+
+        DrainState::Draining
+    }
+
+    async fn run(&self) {
+        info!(address=%self.address, "draining ingester");
+        self.record(DrainState::Draining).await;
+
+        // Persists all the buffered data and stops consuming the shards.
+        if let Err(e) = self.handler.set_shard_indexes(BTreeSet::new()).await {
+            warn!(%e, address=%self.address, "failed to drain ingester");
+            self.record(DrainState::Active).await;
+            *self.state.lock() = DrainState::Active;
+            return;
+        }
+
+        self.record(DrainState::Drained).await;
+        *self.state.lock() = DrainState::Drained;
+        info!(address=%self.address, "ingester drained, safe to terminate");
+    }
+
+    /// Record `state` in the catalog, retrying until it succeeds.
+    async fn record(&self, state: DrainState) {
+        Backoff::new(&BackoffConfig::default())
+            .retry_all_errors("record drain state", || async {
+                self.catalog
+                    .repositories()
+                    .await
+                    .node_drains()
+                    .set_state(
+                        &self.address,
+                        state,
+                        Timestamp::from(self.time_provider.now()),
+                    )
+                    .await
+            })
+            .await
+            .expect("retry forever");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use data_types::ShardIndex;
+    use iox_catalog::mem::MemCatalog;
+    use iox_time::{MockProvider, Time};
+
+    use super::*;
+    use crate::test_util::MockIngestHandler;
+
+    async fn catalog_state(catalog: &dyn Catalog, address: &str) -> Option<DrainState> {
+        catalog
+            .repositories()
+            .await
+            .node_drains()
+            .get_by_address(address)
+            .await
+            .unwrap()
+            .map(|d| d.state)
+    }
+
+    #[tokio::test]
+    async fn test_drain() {
+        let catalog: Arc<dyn Catalog> =
+            Arc::new(MemCatalog::new(Arc::new(metric::Registry::default())));
+        let handler = Arc::new(MockIngestHandler::new([
+            ShardIndex::new(1),
+            ShardIndex::new(2),
+        ]));
+        let address = "http://ingester-1:8083";
+
+        let drain = Arc::new(
+            Drain::new(
+                address,
+                Arc::clone(&catalog),
+                Arc::clone(&handler) as _,
+                Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            )
+            .await
+            .unwrap(),
+        );
+        assert_eq!(drain.state(), DrainState::Active);
+        assert_eq!(
+            catalog_state(catalog.as_ref(), address).await,
+            Some(DrainState::Active)
+        );
+
+        assert_eq!(drain.start(), DrainState::Draining);
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while drain.state() != DrainState::Drained {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("drained");
+
+        assert_eq!(
+            catalog_state(catalog.as_ref(), address).await,
+            Some(DrainState::Drained)
+        );
+        assert!(handler.shard_indexes().is_empty());
+
+        // Draining again has no effect.
+        assert_eq!(drain.start(), DrainState::Drained);
+    }
+}
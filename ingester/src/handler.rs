@@ -1,10 +1,14 @@
 //! Ingest handler
 
-use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Weak},
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use backoff::BackoffConfig;
-use data_types::{Shard, ShardIndex, TopicMetadata};
+use data_types::{PartitionId, Shard, ShardIndex, TopicMetadata};
 use futures::{
     future::{BoxFuture, Shared},
     stream::FuturesUnordered,
@@ -17,9 +21,11 @@ use iox_time::{SystemProvider, TimeProvider};
 use metric::{DurationHistogram, Metric, U64Counter};
 use object_store::DynObjectStore;
 use observability_deps::tracing::*;
+use parking_lot::Mutex;
+use parquet_file::serialize::ParquetWriterOptions;
 use snafu::{ResultExt, Snafu};
 use tokio::{
-    sync::{Semaphore, TryAcquireError},
+    sync::{oneshot, Notify, Semaphore, TryAcquireError},
     task::{JoinError, JoinHandle},
 };
 use tokio_util::sync::CancellationToken;
@@ -28,8 +34,14 @@ use write_buffer::core::WriteBufferReading;
 use write_summary::ShardProgress;
 
 use crate::{
+    assignment::ShardAssignment,
     data::IngesterData,
-    lifecycle::{run_lifecycle_manager, LifecycleConfig, LifecycleManager},
+    job::JobRegistry,
+    lease::{run_lease_manager, ShardLeaseConfig, ShardLeases},
+    lifecycle::{
+        run_lifecycle_manager, BufferedPartition, LifecycleConfig, LifecycleHandleImpl,
+        LifecycleManager,
+    },
     poison::PoisonCabinet,
     querier_handler::{prepare_data_to_querier, IngesterQueryResponse},
     stream_handler::{
@@ -47,6 +59,17 @@ pub enum Error {
     },
     #[snafu(display("error initialising ingester: {}", source))]
     IngesterInit { source: crate::data::InitError },
+    #[snafu(display("Catalog error: {}", source))]
+    Catalog {
+        source: iox_catalog::interface::Error,
+    },
+    #[snafu(display("consumer for shard index {} failed: {}", shard_index, source))]
+    ConsumerFailed {
+        shard_index: ShardIndex,
+        source: Arc<JoinError>,
+    },
+    #[snafu(display("ingester is shutting down"))]
+    ShuttingDown,
 }
 
 /// A specialized `Error` for Catalog errors
@@ -68,6 +91,27 @@ pub trait IngestHandler: Send + Sync {
         shard_indexes: Vec<ShardIndex>,
     ) -> BTreeMap<ShardIndex, ShardProgress>;
 
+    /// Return the indexes of the shards this ingester is consuming from.
+    fn shard_indexes(&self) -> BTreeSet<ShardIndex>;
+
+    /// Consume from exactly the shards in `shard_indexes`.
+    ///
+    /// The consumers of shards no longer assigned to this ingester are stopped,
+    /// and all the data buffered for them is persisted before this call
+    /// returns. Consumers are started for newly assigned shards.
+    async fn set_shard_indexes(&self, shard_indexes: BTreeSet<ShardIndex>) -> Result<()>;
+
+    /// Return the partitions with buffered data that is not being persisted.
+    fn buffered_partitions(&self) -> Vec<BufferedPartition>;
+
+    /// Persist the data buffered for `partition_ids`, irrespective of the
+    /// persistence thresholds.
+    ///
+    /// The returned receiver resolves once the data buffered for the
+    /// partitions has been persisted, and errors if the ingester shuts down
+    /// first.
+    fn persist_partitions(&self, partition_ids: BTreeSet<PartitionId>) -> oneshot::Receiver<()>;
+
     /// Wait until the handler finished  to shutdown.
     ///
     /// Use [`shutdown`](Self::shutdown) to trigger a shutdown.
@@ -85,23 +129,56 @@ fn shared_handle(handle: JoinHandle<()>) -> SharedJoinHandle {
     handle.map_err(Arc::new).boxed().shared()
 }
 
+/// A task consuming the operations of a single shard into the buffer.
+#[derive(Debug)]
+struct ShardConsumer {
+    shard: Shard,
+
+    /// A token that is used to stop this consumer, a child of the ingester
+    /// shutdown token.
+    stop: CancellationToken,
+
+    /// Future that resolves when the consumer exits
+    handle: SharedJoinHandle,
+}
+
 /// Implementation of the `IngestHandler` trait to ingest from shards and manage
 /// persistence and answer queries
 #[derive(Debug)]
 pub struct IngestHandlerImpl<T = SystemProvider> {
     /// Topic assigned to this ingester
-    #[allow(dead_code)]
     topic: TopicMetadata,
 
     /// Future that resolves when the background worker exits
     join_handles: Vec<(String, SharedJoinHandle)>,
 
+    /// Future that resolves when the task applying the shard assignment
+    /// maintained in the catalog exits, if started.
+    shard_assignment: Mutex<Option<SharedJoinHandle>>,
+
+    /// The consumer of each shard this ingester is assigned, keyed by shard
+    /// index.
+    consumers: Mutex<BTreeMap<ShardIndex, ShardConsumer>>,
+
+    /// Notified when consumers are started or stopped.
+    consumers_changed: Notify,
+
+    /// Serialises changes to the set of shards consumed.
+    reassignment: tokio::sync::Mutex<()>,
+
     /// A token that is used to trigger shutdown of the background worker
     shutdown: CancellationToken,
 
     /// The cache and buffered data for the ingester
     data: Arc<IngesterData>,
 
+    /// The state used to start new shard consumers.
+    catalog: Arc<dyn Catalog>,
+    write_buffer: Arc<dyn WriteBufferReading>,
+    lifecycle_handle: LifecycleHandleImpl,
+    metric_registry: Arc<metric::Registry>,
+    skip_to_oldest_available: bool,
+
     time_provider: T,
 
     /// Query execution duration distribution for successes.
@@ -138,30 +215,51 @@ impl IngestHandlerImpl {
         metric_registry: Arc<metric::Registry>,
         skip_to_oldest_available: bool,
         max_requests: usize,
+        presort_persist: bool,
+        parquet_writer_options: ParquetWriterOptions,
+        shard_lease_config: Option<ShardLeaseConfig>,
+        job_node: Option<String>,
     ) -> Result<Self> {
-        let data = Arc::new(
-            IngesterData::new(
-                object_store,
-                catalog,
-                shard_states.clone().into_iter().map(|(idx, s)| (s.id, idx)),
-                exec,
-                BackoffConfig::default(),
-                Arc::clone(&metric_registry),
-            )
-            .await
-            .context(IngesterInitSnafu)?,
-        );
+        let shard_leases = shard_lease_config.map(|config| {
+            Arc::new(ShardLeases::new(
+                config,
+                Arc::clone(&catalog),
+                Arc::new(SystemProvider::new()),
+            ))
+        });
 
-        let ingester_data = Arc::clone(&data);
-        let topic_name = topic.name.clone();
+        let mut data = IngesterData::new(
+            object_store,
+            Arc::clone(&catalog),
+            shard_states.clone().into_iter().map(|(idx, s)| (s.id, idx)),
+            exec,
+            BackoffConfig::default(),
+            Arc::clone(&metric_registry),
+        )
+        .await
+        .context(IngesterInitSnafu)?
+        .with_presort_persist(presort_persist)
+        .with_parquet_writer_options(parquet_writer_options);
+        if let Some(shard_leases) = &shard_leases {
+            data = data.with_shard_leases(Arc::clone(shard_leases));
+        }
+        let data = Arc::new(data);
 
         // start the lifecycle manager
         let persister = Arc::clone(&data);
-        let lifecycle_manager = LifecycleManager::new(
+        let mut lifecycle_manager = LifecycleManager::new(
             lifecycle_config,
             Arc::clone(&metric_registry),
             Arc::new(SystemProvider::new()),
         );
+        if let Some(job_node) = job_node {
+            let time_provider: Arc<dyn TimeProvider> = Arc::new(SystemProvider::new());
+            let job_registry =
+                JobRegistry::new(Arc::clone(&metric_registry), Arc::clone(&time_provider))
+                    .with_operations(Arc::clone(&catalog), job_node, time_provider);
+            job_registry.interrupt_previous_jobs().await;
+            lifecycle_manager = lifecycle_manager.with_job_registry(Arc::new(job_registry));
+        }
         let lifecycle_handle = lifecycle_manager.handle();
         let shutdown = CancellationToken::new();
         let handle = tokio::task::spawn(run_lifecycle_manager(
@@ -175,75 +273,16 @@ impl IngestHandlerImpl {
             lifecycle_config
         );
 
-        let mut join_handles = Vec::with_capacity(shard_states.len() + 1);
-        join_handles.push(("lifecycle manager".to_owned(), shared_handle(handle)));
-
-        for (shard_index, shard) in shard_states {
-            let metric_registry = Arc::clone(&metric_registry);
-
-            // Acquire a write buffer stream and seek it to the last
-            // definitely-already-persisted op
-            let mut op_stream = write_buffer
-                .stream_handler(shard_index)
-                .await
-                .context(WriteBufferSnafu)?;
-            info!(
-                shard_index = shard_index.get(),
-                min_unpersisted_sequence_number = shard.min_unpersisted_sequence_number.get(),
-                "Seek stream",
-            );
-            op_stream
-                .seek(shard.min_unpersisted_sequence_number)
-                .await
-                .context(WriteBufferSnafu)?;
-
-            // Initialise the DmlSink stack.
-            let watermark_fetcher = PeriodicWatermarkFetcher::new(
-                Arc::clone(&write_buffer),
-                shard.shard_index,
-                Duration::from_secs(10),
-                &metric_registry,
-            );
-            // Wrap the IngesterData in a DmlSink adapter
-            let sink = IngestSinkAdaptor::new(
-                Arc::clone(&ingester_data),
-                lifecycle_handle.clone(),
-                shard.id,
-            );
-            // Emit metrics when ops flow through the sink
-            let sink = SinkInstrumentation::new(
-                sink,
-                watermark_fetcher,
-                topic_name.clone(),
-                shard.shard_index,
-                &metric_registry,
-            );
-
-            // Spawn a task to stream in ops from the op_stream and push them
-            // into the sink
-            let handle = tokio::task::spawn({
-                let shutdown = shutdown.child_token();
-                let lifecycle_handle = lifecycle_handle.clone();
-                let topic_name = topic_name.clone();
-                async move {
-                    let handler = SequencedStreamHandler::new(
-                        op_stream,
-                        shard.min_unpersisted_sequence_number,
-                        sink,
-                        lifecycle_handle,
-                        topic_name,
-                        shard.shard_index,
-                        shard.id,
-                        &metric_registry,
-                        skip_to_oldest_available,
-                    );
-
-                    handler.run(shutdown).await
-                }
-            });
+        let mut join_handles = vec![("lifecycle manager".to_owned(), shared_handle(handle))];
 
-            let worker_name = format!("stream handler for shard index {}", shard_index.get());
-            join_handles.push((worker_name, shared_handle(handle)));
+        // Elect the ingester persisting each shard, if several consume it.
+        if let Some(shard_leases) = shard_leases {
+            let handle = tokio::task::spawn(run_lease_manager(
+                shard_leases,
+                Arc::clone(&data),
+                shutdown.clone(),
+            ));
+            join_handles.push(("shard lease manager".to_owned(), shared_handle(handle)));
         }
 
         // Record query duration metrics, broken down by query execution result
@@ -263,19 +302,231 @@ impl IngestHandlerImpl {
             )
             .recorder(&[]);
 
-        Ok(Self {
+        let mut this = Self {
             data,
             topic,
             join_handles,
+            shard_assignment: Default::default(),
+            consumers: Default::default(),
+            consumers_changed: Notify::new(),
+            reassignment: Default::default(),
             shutdown,
+            catalog,
+            write_buffer,
+            lifecycle_handle,
+            metric_registry,
+            skip_to_oldest_available,
             query_duration_success,
             query_duration_error_not_found,
             query_duration_error_other,
             query_request_limit_rejected,
             request_sem: Semaphore::new(max_requests),
             time_provider: Default::default(),
+        };
+
+        for (shard_index, shard) in shard_states {
+            let consumer = this.start_consumer(shard).await?;
+            this.consumers.get_mut().insert(shard_index, consumer);
+        }
+
+        Ok(this)
+    }
+
+    /// Consume the shards assigned to this ingester in the catalog by
+    /// `assignment` instead of its initial shards, following any change until
+    /// shutdown.
+    pub fn start_shard_assignment(self: &Arc<Self>, assignment: ShardAssignment) {
+        let handler: Weak<dyn IngestHandler> = Arc::downgrade(self);
+        let handle = tokio::task::spawn(assignment.run(handler, self.shutdown.clone()));
+        *self.shard_assignment.lock() = Some(shared_handle(handle));
+        self.consumers_changed.notify_waiters();
+    }
+}
+
+impl<T> IngestHandlerImpl<T> {
+    /// Spawn a task consuming the operations of `shard` into the buffer,
+    /// starting at its `min_unpersisted_sequence_number`.
+    ///
+    /// The [`ShardData`] for `shard` MUST already be initialised in the
+    /// [`IngesterData`].
+    ///
+    /// [`ShardData`]: crate::data::shard::ShardData
+    async fn start_consumer(&self, shard: Shard) -> Result<ShardConsumer> {
+        let shard_index = shard.shard_index;
+        let topic_name = self.topic.name.clone();
+        let metric_registry = Arc::clone(&self.metric_registry);
+
+        // Acquire a write buffer stream and seek it to the last
+        // definitely-already-persisted op
+        let mut op_stream = self
+            .write_buffer
+            .stream_handler(shard_index)
+            .await
+            .context(WriteBufferSnafu)?;
+        info!(
+            shard_index = shard_index.get(),
+            min_unpersisted_sequence_number = shard.min_unpersisted_sequence_number.get(),
+            "Seek stream",
+        );
+        op_stream
+            .seek(shard.min_unpersisted_sequence_number)
+            .await
+            .context(WriteBufferSnafu)?;
+
+        // Initialise the DmlSink stack.
+        let watermark_fetcher = PeriodicWatermarkFetcher::new(
+            Arc::clone(&self.write_buffer),
+            shard.shard_index,
+            Duration::from_secs(10),
+            &metric_registry,
+        );
+        // Wrap the IngesterData in a DmlSink adapter
+        let sink = IngestSinkAdaptor::new(
+            Arc::clone(&self.data),
+            self.lifecycle_handle.clone(),
+            shard.id,
+        );
+        // Emit metrics when ops flow through the sink
+        let sink = SinkInstrumentation::new(
+            sink,
+            watermark_fetcher,
+            topic_name.clone(),
+            shard.shard_index,
+            &metric_registry,
+        );
+
+        // Spawn a task to stream in ops from the op_stream and push them
+        // into the sink
+        let stop = self.shutdown.child_token();
+        let handle = tokio::task::spawn({
+            let stop = stop.clone();
+            let lifecycle_handle = self.lifecycle_handle.clone();
+            let skip_to_oldest_available = self.skip_to_oldest_available;
+            let shard = shard.clone();
+            async move {
+                let handler = SequencedStreamHandler::new(
+                    op_stream,
+                    shard.min_unpersisted_sequence_number,
+                    sink,
+                    lifecycle_handle,
+                    topic_name,
+                    shard.shard_index,
+                    shard.id,
+                    &metric_registry,
+                    skip_to_oldest_available,
+                );
+
+                handler.run(stop).await
+            }
+        });
+
+        Ok(ShardConsumer {
+            shard,
+            stop,
+            handle: shared_handle(handle),
         })
     }
+
+    /// Stop consuming `shard_index`, persist all the data buffered for it and
+    /// then discard its buffered state.
+    async fn remove_shard(&self, shard_index: ShardIndex) -> Result<()> {
+        let consumer = match self.consumers.lock().remove(&shard_index) {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        self.consumers_changed.notify_waiters();
+        let shard_id = consumer.shard.id;
+        info!(
+            shard_index = shard_index.get(),
+            %shard_id,
+            "stopping shard consumer"
+        );
+
+        // Stop buffering writes for the shard before persisting it.
+        consumer.stop.cancel();
+        consumer
+            .handle
+            .await
+            .context(ConsumerFailedSnafu { shard_index })?;
+
+        // Wait for the lifecycle manager to persist all the data buffered for
+        // the shard, and advance its min_unpersisted_sequence_number so the
+        // next ingester to consume it does not replay it.
+        self.lifecycle_handle
+            .persist_shard(shard_id)
+            .await
+            .map_err(|_| Error::ShuttingDown)?;
+
+        // Only then hand the shard over to its next consumer.
+        self.data.remove_shard(shard_id);
+        self.data.release_shard_lease(shard_id).await;
+        info!(
+            shard_index = shard_index.get(),
+            %shard_id,
+            "removed shard"
+        );
+
+        Ok(())
+    }
+
+    /// Begin consuming `shard_index`, creating it in the catalog if necessary.
+    async fn add_shard(&self, shard_index: ShardIndex) -> Result<()> {
+        let shard = self
+            .catalog
+            .repositories()
+            .await
+            .shards()
+            .create_or_get(&self.topic, shard_index)
+            .await
+            .context(CatalogSnafu)?;
+        let shard_id = shard.id;
+
+        self.data.add_shard(shard_id, shard_index);
+        self.data.acquire_shard_lease(shard_id).await;
+        let consumer = match self.start_consumer(shard).await {
+            Ok(v) => v,
+            Err(e) => {
+                // Nothing has been buffered for the shard.
+                self.data.remove_shard(shard_id);
+                return Err(e);
+            }
+        };
+
+        self.consumers.lock().insert(shard_index, consumer);
+        self.consumers_changed.notify_waiters();
+        info!(
+            shard_index = shard_index.get(),
+            %shard_id,
+            "added shard"
+        );
+
+        Ok(())
+    }
+
+    /// Return the name, join handle and stop token of every background worker
+    /// and shard consumer.
+    fn workers(&self) -> Vec<(String, SharedJoinHandle, CancellationToken)> {
+        let consumers = self.consumers.lock();
+        let shard_assignment = self.shard_assignment.lock();
+        self.join_handles
+            .iter()
+            .map(|(name, handle)| (name.clone(), handle.clone(), self.shutdown.clone()))
+            .chain(shard_assignment.iter().map(|handle| {
+                (
+                    "shard assignment".to_owned(),
+                    handle.clone(),
+                    self.shutdown.clone(),
+                )
+            }))
+            .chain(consumers.iter().map(|(shard_index, c)| {
+                (
+                    format!("stream handler for shard index {}", shard_index.get()),
+                    c.handle.clone(),
+                    c.stop.clone(),
+                )
+            }))
+            .collect()
+    }
 }
 
 #[async_trait]
@@ -340,23 +591,41 @@ impl IngestHandler for IngestHandlerImpl {
     }
 
     async fn join(&self) {
-        // Need to poll handlers unordered to detect early exists of any worker in the list.
-        let mut unordered: FuturesUnordered<_> = self
-            .join_handles
-            .iter()
-            .cloned()
-            .map(|(name, handle)| async move { handle.await.map(|_| name) })
-            .collect();
-
-        while let Some(e) = unordered.next().await {
-            let name = e.unwrap();
-
-            if !self.shutdown.is_cancelled() {
-                panic!("Background worker '{name}' exited early!");
+        loop {
+            // Register for consumer changes before snapshotting the workers, so
+            // that a consumer started in between is not missed.
+            let changed = self.consumers_changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            // Need to poll handlers unordered to detect early exists of any worker in the list.
+            let mut unordered: FuturesUnordered<_> = self
+                .workers()
+                .into_iter()
+                .map(|(name, handle, stop)| async move { handle.await.map(|_| (name, stop)) })
+                .collect();
+
+            loop {
+                tokio::select! {
+                    e = unordered.next() => match e {
+                        Some(e) => {
+                            let (name, stop) = e.unwrap();
+
+                            // A consumer stopped because its shard was removed
+                            // exits with its stop token cancelled.
+                            if !stop.is_cancelled() {
+                                panic!("Background worker '{name}' exited early!");
+                            }
+                        }
+                        None => {
+                            self.data.exec().join().await;
+                            return;
+                        }
+                    },
+                    _ = &mut changed => break,
+                }
             }
         }
-
-        self.data.exec().join().await;
     }
 
     fn shutdown(&self) {
@@ -371,6 +640,44 @@ impl IngestHandler for IngestHandlerImpl {
     ) -> BTreeMap<ShardIndex, ShardProgress> {
         self.data.progresses(shard_indexes).await
     }
+
+    fn shard_indexes(&self) -> BTreeSet<ShardIndex> {
+        self.consumers.lock().keys().copied().collect()
+    }
+
+    async fn set_shard_indexes(&self, shard_indexes: BTreeSet<ShardIndex>) -> Result<()> {
+        let _guard = self.reassignment.lock().await;
+        if self.shutdown.is_cancelled() {
+            return Err(Error::ShuttingDown);
+        }
+
+        let current = self.shard_indexes();
+        info!(
+            current = ?current,
+            requested = ?shard_indexes,
+            "changing shard assignment"
+        );
+
+        // Persist and release the removed shards before consuming new ones, to
+        // bound the memory used during a rebalance.
+        for &shard_index in current.difference(&shard_indexes) {
+            self.remove_shard(shard_index).await?;
+        }
+        for &shard_index in shard_indexes.difference(&current) {
+            self.add_shard(shard_index).await?;
+        }
+
+        Ok(())
+    }
+
+    fn buffered_partitions(&self) -> Vec<BufferedPartition> {
+        self.lifecycle_handle.buffered_partitions()
+    }
+
+    fn persist_partitions(&self, partition_ids: BTreeSet<PartitionId>) -> oneshot::Receiver<()> {
+        info!(?partition_ids, "persisting partitions on request");
+        self.lifecycle_handle.persist_partitions(partition_ids)
+    }
 }
 
 impl<T> Drop for IngestHandlerImpl<T> {
@@ -380,8 +687,8 @@ impl<T> Drop for IngestHandlerImpl<T> {
             self.shutdown.cancel();
         }
 
-        for (worker_name, handle) in &self.join_handles {
-            if handle.clone().now_or_never().is_none() {
+        for (worker_name, handle, _) in self.workers() {
+            if handle.now_or_never().is_none() {
                 warn!(
                     worker_name = worker_name.as_str(),
                     "IngestHandlerImpl dropped without waiting for worker termination",
@@ -523,6 +830,10 @@ mod tests {
             Arc::clone(&metrics),
             skip_to_oldest_available,
             1,
+            false,
+            ParquetWriterOptions::default(),
+            None,
+            None,
         )
         .await
         .unwrap();
@@ -1,15 +1,23 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use data_types::PartitionId;
-use iox_time::TimeProvider;
+use data_types::{OperationStatus, PartitionId, Timestamp};
+use iox_catalog::interface::Catalog;
+use iox_time::{Time, TimeProvider};
+use observability_deps::tracing::{info, warn};
 use parking_lot::Mutex;
 use tracker::{
     AbstractTaskRegistry, TaskRegistration, TaskRegistry, TaskRegistryWithHistory,
-    TaskRegistryWithMetrics, TaskTracker,
+    TaskRegistryWithMetrics, TaskResult, TaskTracker,
 };
 
 const JOB_HISTORY_SIZE: usize = 1000;
 
+/// How long completed operations are kept in the catalog.
+const OPERATION_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often completed operations past their retention are deleted.
+const OPERATION_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
 #[derive(Debug)]
 pub enum Job {
     Persist { partition_id: PartitionId },
@@ -21,12 +29,19 @@ impl Job {
             Self::Persist { .. } => "persist",
         }
     }
+
+    fn description(&self) -> String {
+        match self {
+            Self::Persist { partition_id } => format!("persist partition {}", partition_id),
+        }
+    }
 }
 
 /// The global job registry
 #[derive(Debug)]
 pub struct JobRegistry {
     inner: Mutex<TaskRegistryWithMetrics<Job, TaskRegistryWithHistory<Job, TaskRegistry<Job>>>>,
+    operations: Option<Arc<JobOperations>>,
 }
 
 impl JobRegistry {
@@ -40,11 +55,50 @@ impl JobRegistry {
             TaskRegistryWithMetrics::new(registry, metric_registry, Box::new(f_attributes));
         Self {
             inner: Mutex::new(registry),
+            operations: None,
+        }
+    }
+
+    /// Record the registered jobs as operations of `node` in `catalog`.
+    ///
+    /// Recording is best-effort: a job whose operation cannot be written to
+    /// the catalog is executed regardless.
+    pub fn with_operations(
+        self,
+        catalog: Arc<dyn Catalog>,
+        node: impl Into<String>,
+        time_provider: Arc<dyn TimeProvider>,
+    ) -> Self {
+        let last_cleanup = Mutex::new(time_provider.now());
+        Self {
+            operations: Some(Arc::new(JobOperations {
+                catalog,
+                node: node.into(),
+                time_provider,
+                last_cleanup,
+            })),
+            ..self
         }
     }
 
     pub fn register(&self, job: Job) -> (TaskTracker<Job>, TaskRegistration) {
-        self.inner.lock().register(job)
+        let (tracker, registration) = self.inner.lock().register(job);
+
+        if let Some(operations) = &self.operations {
+            tokio::task::spawn(Arc::clone(operations).record(tracker.clone()));
+        }
+
+        (tracker, registration)
+    }
+
+    /// Mark the operations this node was executing before it restarted as
+    /// interrupted, as they can no longer complete.
+    ///
+    /// Should be called once on startup, before any job is registered.
+    pub async fn interrupt_previous_jobs(&self) {
+        if let Some(operations) = &self.operations {
+            operations.interrupt_running().await;
+        }
     }
 
     /// Reclaims jobs into the historical archive
@@ -62,3 +116,257 @@ impl JobRegistry {
 fn f_attributes(job: &Job) -> metric::Attributes {
     metric::Attributes::from(&[("name", job.name())])
 }
+
+/// Records the jobs of a [`JobRegistry`] as operations in the catalog, so
+/// that they can be listed, and reported after a restart of the ingester.
+#[derive(Debug)]
+struct JobOperations {
+    catalog: Arc<dyn Catalog>,
+    node: String,
+    time_provider: Arc<dyn TimeProvider>,
+
+    /// When completed operations past their retention were last deleted.
+    last_cleanup: Mutex<Time>,
+}
+
+impl JobOperations {
+    /// Record the operation of the job tracked by `tracker` until it
+    /// completes.
+    async fn record(self: Arc<Self>, tracker: TaskTracker<Job>) {
+        let job = tracker.metadata();
+        let operation = self
+            .catalog
+            .repositories()
+            .await
+            .operations()
+            .create(
+                job.name(),
+                &job.description(),
+                &self.node,
+                Timestamp::from(tracker.start_time()),
+            )
+            .await;
+        let operation = match operation {
+            Ok(operation) => operation,
+            Err(e) => {
+                warn!(error=%e, job=?job, "failed to record job operation in catalog");
+                return;
+            }
+        };
+
+        tracker.join().await;
+
+        let status = match tracker.get_status().result() {
+            Some(TaskResult::Success) => OperationStatus::Success,
+            Some(TaskResult::Cancelled) => OperationStatus::Cancelled,
+            Some(TaskResult::Error | TaskResult::Dropped) | None => OperationStatus::Error,
+        };
+        let now = self.time_provider.now();
+        if let Err(e) = self
+            .catalog
+            .repositories()
+            .await
+            .operations()
+            .complete(operation.id, status, Timestamp::from(now))
+            .await
+        {
+            warn!(error=%e, operation_id=%operation.id, "failed to record job completion in catalog");
+        }
+
+        self.maybe_cleanup(now).await;
+    }
+
+    /// Delete the completed operations past their retention, at most once
+    /// per [`OPERATION_CLEANUP_INTERVAL`].
+    async fn maybe_cleanup(&self, now: Time) {
+        {
+            let mut last_cleanup = self.last_cleanup.lock();
+            if now < *last_cleanup + OPERATION_CLEANUP_INTERVAL {
+                return;
+            }
+            *last_cleanup = now;
+        }
+
+        let older_than = Timestamp::from(now - OPERATION_RETENTION);
+        match self
+            .catalog
+            .repositories()
+            .await
+            .operations()
+            .delete_completed_before(older_than)
+            .await
+        {
+            Ok(deleted) => info!(deleted, "deleted completed job operations"),
+            Err(e) => warn!(error=%e, "failed to delete completed job operations"),
+        }
+    }
+
+    async fn interrupt_running(&self) {
+        let now = Timestamp::from(self.time_provider.now());
+        let interrupted = self
+            .catalog
+            .repositories()
+            .await
+            .operations()
+            .interrupt_running(&self.node, now)
+            .await;
+
+        match interrupted {
+            Ok(interrupted) => {
+                for operation in interrupted {
+                    warn!(
+                        operation_id=%operation.id,
+                        description=%operation.description,
+                        "job interrupted by restart"
+                    );
+                }
+            }
+            Err(e) => {
+                warn!(error=%e, node=%self.node, "failed to interrupt previous job operations")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_types::Operation;
+    use iox_catalog::mem::MemCatalog;
+    use iox_time::MockProvider;
+    use tracker::TrackedFutureExt;
+
+    async fn list_operations(catalog: &Arc<dyn Catalog>) -> Vec<Operation> {
+        catalog
+            .repositories()
+            .await
+            .operations()
+            .list()
+            .await
+            .unwrap()
+    }
+
+    /// Wait until all operations in `catalog` completed, and return them.
+    async fn completed_operations(catalog: &Arc<dyn Catalog>) -> Vec<Operation> {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let operations = list_operations(catalog).await;
+                if !operations.is_empty() && operations.iter().all(|o| o.status.is_done()) {
+                    return operations;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("operations completed")
+    }
+
+    #[tokio::test]
+    async fn test_record_operations() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let registry = JobRegistry::new(Arc::clone(&metrics), Arc::clone(&time_provider) as _)
+            .with_operations(
+                Arc::clone(&catalog),
+                "ingester-1",
+                Arc::clone(&time_provider) as _,
+            );
+
+        let (_tracker, registration) = registry.register(Job::Persist {
+            partition_id: PartitionId::new(1),
+        });
+        tokio::task::spawn(async { Ok::<_, String>(()) }.track(registration))
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        let operations = completed_operations(&catalog).await;
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations[0].kind, "persist");
+        assert_eq!(operations[0].description, "persist partition 1");
+        assert_eq!(operations[0].node, "ingester-1");
+        assert_eq!(operations[0].status, OperationStatus::Success);
+
+        // Completed operations past their retention are deleted once the
+        // cleanup interval passed.
+        time_provider.inc(OPERATION_RETENTION + OPERATION_CLEANUP_INTERVAL);
+        let (_tracker, registration) = registry.register(Job::Persist {
+            partition_id: PartitionId::new(2),
+        });
+        tokio::task::spawn(async { Err::<(), _>("failed".to_string()) }.track(registration))
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap_err();
+
+        let operations = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let operations = completed_operations(&catalog).await;
+                if operations.len() == 1 {
+                    return operations;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("old operation deleted");
+        assert_eq!(operations[0].description, "persist partition 2");
+        assert_eq!(operations[0].status, OperationStatus::Error);
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_previous_jobs() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+
+        let mut repos = catalog.repositories().await;
+        let own = repos
+            .operations()
+            .create(
+                "persist",
+                "persist partition 1",
+                "ingester-1",
+                Timestamp::new(0),
+            )
+            .await
+            .unwrap();
+        let other = repos
+            .operations()
+            .create(
+                "persist",
+                "persist partition 2",
+                "ingester-2",
+                Timestamp::new(0),
+            )
+            .await
+            .unwrap();
+        // release the lock of the in-memory catalog
+        drop(repos);
+
+        // A registry without operations does not touch the catalog.
+        let registry = JobRegistry::new(Arc::clone(&metrics), Arc::clone(&time_provider) as _);
+        registry.interrupt_previous_jobs().await;
+        assert_eq!(
+            list_operations(&catalog).await,
+            vec![own.clone(), other.clone()]
+        );
+
+        time_provider.inc(Duration::from_secs(1));
+        let registry = registry.with_operations(
+            Arc::clone(&catalog),
+            "ingester-1",
+            Arc::clone(&time_provider) as _,
+        );
+        registry.interrupt_previous_jobs().await;
+
+        let own = Operation {
+            status: OperationStatus::Interrupted,
+            completed_at: Some(Timestamp::new(1_000_000_000)),
+            ..own
+        };
+        assert_eq!(list_operations(&catalog).await, vec![own, other]);
+    }
+}
@@ -0,0 +1,531 @@
+//! Shard leases electing which of the ingesters consuming the same shard
+//! persists its data.
+//!
+//! When several ingesters consume a shard, all of them buffer its data but only
+//! the holder of the shard's lease in the catalog (the leader) persists it. The
+//! other ingesters (standbys) release their buffered data once the leader has
+//! persisted it. If the leader fails, its lease expires and a standby takes
+//! over persistence from its own buffer, instead of a replacement ingester
+//! replaying the write buffer.
+//!
+//! The leases expire by the clock of the catalog. Each lease has an epoch,
+//! incremented whenever it changes hands, and the leader commits the parquet
+//! files and sequence numbers it persists in a catalog transaction checking
+//! that it still holds the lease in the epoch it acquired it in. A leader that
+//! lost its lease without noticing, e.g. after a long pause, is thereby fenced
+//! off instead of racing the new leader.
+
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use data_types::{PartitionId, SequenceNumber, ShardId};
+use iox_catalog::interface::Catalog;
+use iox_time::{Time, TimeProvider};
+use observability_deps::tracing::*;
+use parking_lot::Mutex;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::data::IngesterData;
+
+/// The interval at which a standby checks whether the leader has persisted the
+/// data it is waiting for.
+const STANDBY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The default time a standby waits for the leader to persist its data before
+/// persisting it itself.
+const DEFAULT_STANDBY_PERSIST_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Configuration of the shard leases of an ingester.
+#[derive(Debug, Clone)]
+pub struct ShardLeaseConfig {
+    holder: String,
+    lease_duration: Duration,
+    standby_persist_timeout: Duration,
+}
+
+impl ShardLeaseConfig {
+    /// Hold leases for `lease_duration`, renewing them a few times per
+    /// duration. The lease holder identity is random unless set with
+    /// [`Self::with_holder()`].
+    pub fn new(lease_duration: Duration) -> Self {
+        Self {
+            holder: Uuid::new_v4().to_string(),
+            lease_duration,
+            standby_persist_timeout: DEFAULT_STANDBY_PERSIST_TIMEOUT,
+        }
+    }
+
+    /// Identify this ingester as `holder` in the leases it holds.
+    ///
+    /// Every ingester consuming a shard MUST use a distinct identity.
+    pub fn with_holder(self, holder: impl Into<String>) -> Self {
+        Self {
+            holder: holder.into(),
+            ..self
+        }
+    }
+
+    /// Persist data buffered as a standby itself if the leader has not
+    /// persisted it within `standby_persist_timeout`.
+    ///
+    /// The leader persists on its own schedule, and the data of a partition
+    /// the standby snapshots may not be covered by the leader's next persist
+    /// of that partition. Persisting the data in the standby produces a
+    /// parquet file duplicating (some of) the leader's data, which compaction
+    /// deduplicates.
+    pub fn with_standby_persist_timeout(self, standby_persist_timeout: Duration) -> Self {
+        Self {
+            standby_persist_timeout,
+            ..self
+        }
+    }
+
+    /// The identity of this ingester in the leases it holds.
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+}
+
+/// How the data of a partition snapshot taken by a standby became durable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StandbyOutcome {
+    /// The leader persisted the data.
+    PersistedByLeader,
+    /// This ingester became the leader of the shard, and must persist the data.
+    Leader,
+    /// The leader did not persist the data in time, so this ingester must
+    /// persist it.
+    TimedOut,
+}
+
+/// The lease of a shard held by this ingester, fencing the catalog
+/// transactions persisting the data of the shard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LeaseFence {
+    /// The identity of this ingester.
+    pub(crate) holder: String,
+    /// The epoch the lease was acquired in.
+    pub(crate) epoch: i64,
+}
+
+/// A lease held by this ingester.
+#[derive(Debug, Clone, Copy)]
+struct HeldLease {
+    /// When the lease expires at the latest, by the clock of this ingester.
+    expires_at: Time,
+    /// The epoch the lease was acquired in.
+    epoch: i64,
+}
+
+/// The shard leases held by an ingester.
+#[derive(Debug)]
+pub(crate) struct ShardLeases {
+    config: ShardLeaseConfig,
+    catalog: Arc<dyn Catalog>,
+    time_provider: Arc<dyn TimeProvider>,
+
+    /// The shards whose lease this ingester holds.
+    held: Mutex<BTreeMap<ShardId, HeldLease>>,
+}
+
+impl ShardLeases {
+    pub(crate) fn new(
+        config: ShardLeaseConfig,
+        catalog: Arc<dyn Catalog>,
+        time_provider: Arc<dyn TimeProvider>,
+    ) -> Self {
+        Self {
+            config,
+            catalog,
+            time_provider,
+            held: Default::default(),
+        }
+    }
+
+    /// Returns true if this ingester holds an unexpired lease for `shard_id`.
+    ///
+    /// A lease that could not be renewed is considered lost once it expires,
+    /// even if no other ingester took it over.
+    pub(crate) fn is_leader(&self, shard_id: ShardId) -> bool {
+        let now = self.time_provider.now();
+        self.held
+            .lock()
+            .get(&shard_id)
+            .map_or(false, |lease| now < lease.expires_at)
+    }
+
+    /// The fence of the lease of `shard_id`, if this ingester holds it.
+    pub(crate) fn fence(&self, shard_id: ShardId) -> Option<LeaseFence> {
+        let now = self.time_provider.now();
+        self.held
+            .lock()
+            .get(&shard_id)
+            .filter(|lease| now < lease.expires_at)
+            .map(|lease| LeaseFence {
+                holder: self.config.holder.clone(),
+                epoch: lease.epoch,
+            })
+    }
+
+    /// Forget the lease of `shard_id` the catalog reported lost in `epoch`,
+    /// until the next renewal.
+    pub(crate) fn lost(&self, shard_id: ShardId, epoch: i64) {
+        let mut held = self.held.lock();
+        if held.get(&shard_id).map_or(false, |l| l.epoch == epoch) {
+            warn!(
+                %shard_id,
+                holder=%self.config.holder,
+                epoch,
+                "shard lease lost, continuing as standby"
+            );
+            held.remove(&shard_id);
+        }
+    }
+
+    /// Acquire or renew the lease of `shard_id`, if it is not held by another
+    /// ingester.
+    pub(crate) async fn acquire(&self, shard_id: ShardId) {
+        // The catalog expires the lease by its own clock, no earlier than
+        // `lease_duration` after the request is sent.
+        let expires_at = self.time_provider.now() + self.config.lease_duration;
+        let res = self
+            .catalog
+            .repositories()
+            .await
+            .shards()
+            .acquire_lease(shard_id, &self.config.holder, self.config.lease_duration)
+            .await;
+
+        match res {
+            Ok(lease) if lease.holder == self.config.holder => {
+                let held = HeldLease {
+                    expires_at,
+                    epoch: lease.epoch,
+                };
+                let previous = self.held.lock().insert(shard_id, held);
+                if previous.map_or(true, |p| p.epoch != lease.epoch) {
+                    info!(
+                        %shard_id,
+                        holder=%self.config.holder,
+                        epoch=lease.epoch,
+                        "acquired shard lease, persisting as leader"
+                    );
+                }
+            }
+            Ok(lease) => {
+                if self.held.lock().remove(&shard_id).is_some() {
+                    warn!(
+                        %shard_id,
+                        holder=%self.config.holder,
+                        leader=%lease.holder,
+                        "lost shard lease, continuing as standby"
+                    );
+                } else {
+                    debug!(%shard_id, leader=%lease.holder, "shard lease held by leader");
+                }
+            }
+            Err(e) => {
+                // The lease is kept until it expires, and may be renewed
+                // by the next attempt.
+                warn!(%e, %shard_id, "failed to renew shard lease");
+            }
+        }
+    }
+
+    /// Acquire or renew the leases of `shard_ids`, and release the leases held
+    /// for any other shard.
+    pub(crate) async fn renew(&self, shard_ids: &[ShardId]) {
+        for &shard_id in shard_ids {
+            self.acquire(shard_id).await;
+        }
+
+        let released = {
+            let mut held = self.held.lock();
+            let released = held
+                .keys()
+                .filter(|id| !shard_ids.contains(id))
+                .copied()
+                .collect::<Vec<_>>();
+            for shard_id in &released {
+                held.remove(shard_id);
+            }
+            released
+        };
+        for shard_id in released {
+            self.release(shard_id).await;
+        }
+    }
+
+    /// Release the lease of `shard_id`, if held by this ingester.
+    pub(crate) async fn release_shard(&self, shard_id: ShardId) {
+        let held = self.held.lock().remove(&shard_id).is_some();
+        if held {
+            self.release(shard_id).await;
+        }
+    }
+
+    /// Release all the leases held by this ingester, allowing a standby to
+    /// take over without waiting for them to expire.
+    pub(crate) async fn release_all(&self) {
+        let held = std::mem::take(&mut *self.held.lock());
+        for shard_id in held.into_keys() {
+            self.release(shard_id).await;
+        }
+    }
+
+    async fn release(&self, shard_id: ShardId) {
+        info!(%shard_id, holder=%self.config.holder, "releasing shard lease");
+        if let Err(e) = self
+            .catalog
+            .repositories()
+            .await
+            .shards()
+            .release_lease(shard_id, &self.config.holder)
+            .await
+        {
+            warn!(%e, %shard_id, "failed to release shard lease");
+        }
+    }
+
+    /// Wait until the data of `partition_id` up to and including
+    /// `sequence_number`, buffered as a standby for `shard_id`, is durable or
+    /// must be persisted by this ingester.
+    pub(crate) async fn wait_for_leader(
+        &self,
+        shard_id: ShardId,
+        partition_id: PartitionId,
+        sequence_number: SequenceNumber,
+    ) -> StandbyOutcome {
+        let deadline = self.time_provider.now() + self.config.standby_persist_timeout;
+
+        loop {
+            if self.is_leader(shard_id) {
+                return StandbyOutcome::Leader;
+            }
+
+            match self
+                .catalog
+                .repositories()
+                .await
+                .partitions()
+                .get_by_id(partition_id)
+                .await
+            {
+                Ok(Some(p)) if p.persisted_sequence_number >= Some(sequence_number) => {
+                    return StandbyOutcome::PersistedByLeader;
+                }
+                Ok(_) => {}
+                Err(e) => warn!(%e, %partition_id, "failed to fetch partition persist marker"),
+            }
+
+            if self.time_provider.now() >= deadline {
+                return StandbyOutcome::TimedOut;
+            }
+
+            tokio::time::sleep(STANDBY_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Keep the leases of the shards buffered in `data` until `shutdown` is
+/// cancelled, then release them.
+pub(crate) async fn run_lease_manager(
+    leases: Arc<ShardLeases>,
+    data: Arc<IngesterData>,
+    shutdown: CancellationToken,
+) {
+    let renew_interval = leases.config.lease_duration / 3;
+
+    loop {
+        let shard_ids = data
+            .shards()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+        leases.renew(&shard_ids).await;
+
+        tokio::select!(
+            _ = tokio::time::sleep(renew_interval) => {},
+            _ = shutdown.cancelled() => break,
+        );
+    }
+
+    leases.release_all().await;
+    info!("shard lease manager shutdown");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use data_types::PartitionKey;
+    use iox_catalog::mem::MemCatalog;
+    use iox_time::MockProvider;
+
+    struct TestContext {
+        catalog: Arc<dyn Catalog>,
+        time_provider: Arc<MockProvider>,
+        shard_id: ShardId,
+    }
+
+    impl TestContext {
+        async fn new() -> Self {
+            let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+            let catalog: Arc<dyn Catalog> = Arc::new(
+                MemCatalog::new(Arc::new(metric::Registry::default()))
+                    .with_time_provider(Arc::clone(&time_provider) as _),
+            );
+
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("topic").await.unwrap();
+            let shard = repos
+                .shards()
+                .create_or_get(&topic, data_types::ShardIndex::new(0))
+                .await
+                .unwrap();
+            drop(repos);
+
+            Self {
+                catalog,
+                time_provider,
+                shard_id: shard.id,
+            }
+        }
+
+        fn leases(&self, holder: &str) -> ShardLeases {
+            self.leases_with_timeout(holder, Duration::from_secs(60))
+        }
+
+        fn leases_with_timeout(&self, holder: &str, timeout: Duration) -> ShardLeases {
+            ShardLeases::new(
+                ShardLeaseConfig::new(Duration::from_secs(30))
+                    .with_holder(holder)
+                    .with_standby_persist_timeout(timeout),
+                Arc::clone(&self.catalog),
+                Arc::clone(&self.time_provider) as _,
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn test_leader_election() {
+        let ctx = TestContext::new().await;
+        let a = ctx.leases("a");
+        let b = ctx.leases("b");
+
+        a.renew(&[ctx.shard_id]).await;
+        b.renew(&[ctx.shard_id]).await;
+        assert!(a.is_leader(ctx.shard_id));
+        assert!(!b.is_leader(ctx.shard_id));
+        let fence_a = a.fence(ctx.shard_id).unwrap();
+        assert_eq!(fence_a.holder, "a");
+        assert_eq!(b.fence(ctx.shard_id), None);
+
+        // Renewing the lease keeps its epoch.
+        a.renew(&[ctx.shard_id]).await;
+        assert_eq!(a.fence(ctx.shard_id), Some(fence_a.clone()));
+
+        // The leader stops renewing its lease, which expires.
+        ctx.time_provider.inc(Duration::from_secs(30));
+        assert!(!a.is_leader(ctx.shard_id));
+        assert_eq!(a.fence(ctx.shard_id), None);
+
+        // The standby takes over in a new epoch, fencing off the old leader.
+        b.renew(&[ctx.shard_id]).await;
+        assert!(b.is_leader(ctx.shard_id));
+        let fence_b = b.fence(ctx.shard_id).unwrap();
+        assert!(fence_b.epoch > fence_a.epoch);
+        assert_matches!(
+            ctx.catalog
+                .repositories()
+                .await
+                .shards()
+                .check_lease(ctx.shard_id, &fence_a.holder, fence_a.epoch)
+                .await,
+            Err(iox_catalog::interface::Error::LeaseLost { .. })
+        );
+
+        // The old leader notices it lost the lease.
+        a.renew(&[ctx.shard_id]).await;
+        assert!(!a.is_leader(ctx.shard_id));
+
+        // A lease reported lost is forgotten until the next renewal.
+        b.lost(ctx.shard_id, fence_b.epoch);
+        assert!(!b.is_leader(ctx.shard_id));
+        b.renew(&[ctx.shard_id]).await;
+        assert_eq!(b.fence(ctx.shard_id), Some(fence_b));
+
+        // Releasing the lease allows an immediate takeover.
+        b.release_all().await;
+        assert!(!b.is_leader(ctx.shard_id));
+        a.renew(&[ctx.shard_id]).await;
+        assert!(a.is_leader(ctx.shard_id));
+
+        // Leases of shards no longer buffered are released.
+        a.renew(&[]).await;
+        assert!(!a.is_leader(ctx.shard_id));
+        b.renew(&[ctx.shard_id]).await;
+        assert!(b.is_leader(ctx.shard_id));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_leader() {
+        let ctx = TestContext::new().await;
+        let leader = ctx.leases("leader");
+        let standby = ctx.leases("standby");
+        leader.renew(&[ctx.shard_id]).await;
+        standby.renew(&[ctx.shard_id]).await;
+
+        let mut repos = ctx.catalog.repositories().await;
+        let topic = repos.topics().create_or_get("topic").await.unwrap();
+        let pool = repos.query_pools().create_or_get("pool").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("ns", None, topic.id, pool.id)
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("table", namespace.id)
+            .await
+            .unwrap();
+        let partition = repos
+            .partitions()
+            .create_or_get(PartitionKey::from("p"), ctx.shard_id, table.id)
+            .await
+            .unwrap();
+        repos
+            .partitions()
+            .update_persisted_sequence_number(partition.id, SequenceNumber::new(10))
+            .await
+            .unwrap();
+        drop(repos);
+
+        // The leader persisted the data.
+        assert_eq!(
+            standby
+                .wait_for_leader(ctx.shard_id, partition.id, SequenceNumber::new(10))
+                .await,
+            StandbyOutcome::PersistedByLeader
+        );
+
+        // The leader has not persisted the data by the deadline.
+        let impatient = ctx.leases_with_timeout("impatient", Duration::ZERO);
+        assert_eq!(
+            impatient
+                .wait_for_leader(ctx.shard_id, partition.id, SequenceNumber::new(11))
+                .await,
+            StandbyOutcome::TimedOut
+        );
+
+        // The standby took over the lease.
+        ctx.time_provider.inc(Duration::from_secs(30));
+        standby.renew(&[ctx.shard_id]).await;
+        assert_eq!(
+            standby
+                .wait_for_leader(ctx.shard_id, partition.id, SequenceNumber::new(11))
+                .await,
+            StandbyOutcome::Leader
+        );
+    }
+}
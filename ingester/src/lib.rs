@@ -18,10 +18,13 @@
 )]
 
 mod arcmap;
+pub mod assignment;
 pub(crate) mod compact;
 pub mod data;
+pub mod drain;
 pub mod handler;
 mod job;
+pub mod lease;
 pub mod lifecycle;
 mod poison;
 pub mod querier_handler;
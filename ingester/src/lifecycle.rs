@@ -7,13 +7,19 @@
 
 pub mod mock_handle;
 
-use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroUsize,
+    sync::Arc,
+    time::Duration,
+};
 
 use data_types::{NamespaceId, PartitionId, SequenceNumber, ShardId, TableId};
 use iox_time::{Time, TimeProvider};
 use metric::{Metric, U64Counter};
 use observability_deps::tracing::{error, info, trace, warn};
 use parking_lot::Mutex;
+use tokio::sync::{oneshot, Semaphore};
 use tokio_util::sync::CancellationToken;
 use tracker::TrackedFutureExt;
 
@@ -121,6 +127,90 @@ impl LifecycleHandle for LifecycleHandleImpl {
     }
 }
 
+impl LifecycleHandleImpl {
+    /// Persist all the data buffered for `shard_id` on the next evaluation of
+    /// the [`LifecycleManager`], irrespective of the configured thresholds.
+    ///
+    /// The returned receiver resolves once there is no unpersisted data for
+    /// the shard, and its `min_unpersisted_sequence_number` has been updated.
+    ///
+    /// The caller MUST stop buffering writes for `shard_id` before calling
+    /// this method, otherwise the receiver may never resolve.
+    pub(crate) fn persist_shard(&self, shard_id: ShardId) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        self.state
+            .lock()
+            .draining
+            .entry(shard_id)
+            .or_default()
+            .push(tx);
+        rx
+    }
+
+    /// Persist the data buffered for `partition_ids` on the next evaluation
+    /// of the [`LifecycleManager`], irrespective of the configured
+    /// thresholds.
+    ///
+    /// The returned receiver resolves once the data buffered when that
+    /// evaluation started has been persisted. Partitions without buffered
+    /// data are ignored.
+    pub(crate) fn persist_partitions(
+        &self,
+        partition_ids: BTreeSet<PartitionId>,
+    ) -> oneshot::Receiver<()> {
+        let (tx, rx) = oneshot::channel();
+        self.state.lock().forced.push((partition_ids, tx));
+        rx
+    }
+
+    /// Returns the partitions with buffered data that is not being
+    /// persisted.
+    pub(crate) fn buffered_partitions(&self) -> Vec<BufferedPartition> {
+        self.state
+            .lock()
+            .partition_stats
+            .values()
+            .map(|s| BufferedPartition {
+                shard_id: s.shard_id,
+                namespace_id: s.namespace_id,
+                table_id: s.table_id,
+                partition_id: s.partition_id,
+                first_write: s.first_write,
+                last_write: s.last_write,
+                bytes_written: s.bytes_written,
+                rows_written: s.rows_written,
+                first_sequence_number: s.first_sequence_number,
+            })
+            .collect()
+    }
+}
+
+/// A partition with buffered data that is not being persisted, as tracked
+/// by the [`LifecycleManager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferedPartition {
+    /// The shard this partition is under
+    pub shard_id: ShardId,
+    /// The namespace identifier
+    pub namespace_id: NamespaceId,
+    /// The table identifier
+    pub table_id: TableId,
+    /// The partition identifier
+    pub partition_id: PartitionId,
+    /// Time that the partition received its first write since it was last
+    /// persisted.
+    pub first_write: Time,
+    /// Time that the partition received its last write.
+    pub last_write: Time,
+    /// The number of bytes buffered, as estimated by the mutable batch sizes.
+    pub bytes_written: usize,
+    /// The number of rows buffered.
+    pub rows_written: usize,
+    /// The sequence number of the first write since the partition was last
+    /// persisted.
+    pub first_sequence_number: SequenceNumber,
+}
+
 /// The lifecycle manager keeps track of the size and age of partitions across
 /// all shards. It triggers persistence based on keeping total memory usage
 /// around a set amount while ensuring that partitions don't get too old or
@@ -160,6 +250,11 @@ pub(crate) struct LifecycleManager {
     /// Counter tracking the number of times a partition has been evicted for
     /// containing too many rows.
     persist_rows_counter: U64Counter,
+    /// Counter for the removal of the partition's shard from this ingester
+    /// triggering a persist.
+    persist_shard_removed_counter: U64Counter,
+    /// Counter for a persist of the partition being requested.
+    persist_forced_counter: U64Counter,
 }
 
 /// The configuration options for the lifecycle on the ingester.
@@ -196,6 +291,14 @@ pub struct LifecycleConfig {
     /// Reaching this limit pauses ingest while the partition is flushed to
     /// object storage.
     partition_row_max: usize,
+
+    /// The maximum number of partitions persisted at the same time. Unbounded
+    /// if not set.
+    max_concurrent_persists: Option<NonZeroUsize>,
+    /// The maximum number of buffered bytes of the partitions persisted at the
+    /// same time. A partition larger than this budget is persisted on its own.
+    /// Unbounded if not set.
+    persist_memory_budget: Option<NonZeroUsize>,
 }
 
 impl LifecycleConfig {
@@ -219,14 +322,52 @@ impl LifecycleConfig {
             partition_age_threshold,
             partition_cold_threshold,
             partition_row_max,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
         }
     }
+
+    /// Bound the number of partitions, and the number of buffered bytes of
+    /// the partitions, persisted at the same time.
+    pub const fn with_persist_concurrency(
+        mut self,
+        max_concurrent_persists: Option<NonZeroUsize>,
+        persist_memory_budget: Option<NonZeroUsize>,
+    ) -> Self {
+        self.max_concurrent_persists = max_concurrent_persists;
+        self.persist_memory_budget = persist_memory_budget;
+        self
+    }
+}
+
+/// The number of permits a persist memory budget is divided into.
+const PERSIST_MEMORY_BUDGET_PERMITS: u32 = 1024;
+
+/// Returns the number of the [`PERSIST_MEMORY_BUDGET_PERMITS`] permits of
+/// `budget` needed to persist a partition of `bytes` bytes.
+///
+/// Every persist needs at least one permit, and no persist needs more than
+/// the whole budget, so that partitions larger than the budget are persisted
+/// on their own rather than never.
+fn persist_memory_permits(bytes: usize, budget: NonZeroUsize) -> u32 {
+    let budget = budget.get() as u128;
+    let permits = (bytes as u128 * PERSIST_MEMORY_BUDGET_PERMITS as u128 + budget - 1) / budget;
+    permits.clamp(1, PERSIST_MEMORY_BUDGET_PERMITS as u128) as u32
 }
 
 #[derive(Default, Debug)]
 struct LifecycleState {
     total_bytes: usize,
     partition_stats: BTreeMap<PartitionId, PartitionLifecycleStats>,
+
+    /// Shards that must have all their partitions persisted, and the waiters
+    /// to notify once they have been.
+    draining: BTreeMap<ShardId, Vec<oneshot::Sender<()>>>,
+
+    /// Partitions that must be persisted irrespective of the thresholds, in
+    /// the order they were requested, and the waiters to notify once they
+    /// have been.
+    forced: Vec<(BTreeSet<PartitionId>, oneshot::Sender<()>)>,
 }
 
 impl LifecycleState {
@@ -243,6 +384,12 @@ struct LifecycleStats {
     pub total_bytes: usize,
     /// the stats for every partition the lifecycle manager is tracking.
     pub partition_stats: Vec<PartitionLifecycleStats>,
+    /// the shards that must have all their partitions persisted.
+    pub draining: BTreeSet<ShardId>,
+    /// the partitions that must be persisted irrespective of the thresholds.
+    pub forced: BTreeSet<PartitionId>,
+    /// the number of requests the `forced` partitions were collected from.
+    pub forced_requests: usize,
 }
 
 /// The stats for a partition
@@ -290,6 +437,9 @@ impl LifecycleManager {
         let persist_age_counter = persist_counter.recorder(&[("trigger", "age")]);
         let persist_cold_counter = persist_counter.recorder(&[("trigger", "cold")]);
         let persist_rows_counter = persist_counter.recorder(&[("trigger", "rows")]);
+        let persist_shard_removed_counter =
+            persist_counter.recorder(&[("trigger", "shard_removed")]);
+        let persist_forced_counter = persist_counter.recorder(&[("trigger", "forced")]);
 
         let job_registry = Arc::new(JobRegistry::new(
             metric_registry,
@@ -305,6 +455,17 @@ impl LifecycleManager {
             persist_age_counter,
             persist_cold_counter,
             persist_rows_counter,
+            persist_shard_removed_counter,
+            persist_forced_counter,
+        }
+    }
+
+    /// Register the persist jobs in `job_registry`, e.g. one recording them as
+    /// operations in the catalog.
+    pub(crate) fn with_job_registry(self, job_registry: Arc<JobRegistry>) -> Self {
+        Self {
+            job_registry,
+            ..self
         }
     }
 
@@ -317,14 +478,24 @@ impl LifecycleManager {
         }
     }
 
-    /// This will persist any partitions that are over their size or age thresholds and
-    /// persist as many partitions as necessary (largest first) to get below the memory threshold.
-    /// The persist operations are spawned in new tasks and run at the same time, but the
+    /// This will persist any partitions that are over their size or age thresholds, that
+    /// belong to a shard being removed or whose persistence was requested, and persist as many partitions as necessary (largest
+    /// first) to get below the memory threshold.
+    /// The persist operations are spawned in new tasks and run at the same time, bounded by the
+    /// configured maximum number of concurrent persists and persist memory budget, but the
     /// function waits for all to return before completing.
+    ///
+    /// Each partition is persisted at most once per call, and a call only returns once all its
+    /// persists completed, so the persists of a partition never overlap and complete in the
+    /// order they were started. The min unpersisted sequence number of a shard is only advanced
+    /// once all of its partitions selected for persistence have been persisted.
     pub async fn maybe_persist<P: Persister>(&mut self, persister: &Arc<P>) {
         let LifecycleStats {
             mut total_bytes,
             partition_stats,
+            draining,
+            forced,
+            forced_requests,
         } = self.stats();
 
         // get anything over the threshold size or age to persist
@@ -432,7 +603,45 @@ impl LifecycleManager {
                 self.persist_size_counter.inc(1);
             }
 
-            aged_out || sized_out || is_cold || exceeded_max_rows
+            // If this partition's shard is being removed from this ingester,
+            // flush it.
+            let shard_removed = draining.contains(&s.shard_id);
+            if shard_removed {
+                info!(
+                    shard_id=%s.shard_id,
+                    partition_id=%s.partition_id,
+                    first_write=%s.first_write,
+                    last_write=%s.last_write,
+                    bytes_written=s.bytes_written,
+                    rows_written=s.rows_written,
+                    first_sequence_number=?s.first_sequence_number,
+                    "partition shard is being removed, persisting"
+                );
+                self.persist_shard_removed_counter.inc(1);
+            }
+
+            // If persisting this partition was requested, flush it.
+            let persist_requested = forced.contains(&s.partition_id);
+            if persist_requested {
+                info!(
+                    shard_id=%s.shard_id,
+                    partition_id=%s.partition_id,
+                    first_write=%s.first_write,
+                    last_write=%s.last_write,
+                    bytes_written=s.bytes_written,
+                    rows_written=s.rows_written,
+                    first_sequence_number=?s.first_sequence_number,
+                    "partition persist requested, persisting"
+                );
+                self.persist_forced_counter.inc(1);
+            }
+
+            aged_out
+                || sized_out
+                || is_cold
+                || exceeded_max_rows
+                || shard_removed
+                || persist_requested
         });
 
         // keep track of what we'll be evicting to see what else to drop
@@ -483,6 +692,17 @@ impl LifecycleManager {
                 .or_insert(s.first_sequence_number);
         }
 
+        let concurrency_limit = self
+            .config
+            .max_concurrent_persists
+            .map(|n| Arc::new(Semaphore::new(n.get())));
+        let memory_budget = self.config.persist_memory_budget.map(|budget| {
+            (
+                budget,
+                Arc::new(Semaphore::new(PERSIST_MEMORY_BUDGET_PERMITS as usize)),
+            )
+        });
+
         let persist_tasks: Vec<_> = to_persist
             .into_iter()
             .map(|s| {
@@ -511,7 +731,32 @@ impl LifecycleManager {
                 });
 
                 let state = Arc::clone(&self.state);
+                let concurrency_limit = concurrency_limit.clone();
+                let memory_budget = memory_budget.clone();
                 tokio::task::spawn(async move {
+                    // Acquire the memory budget first, so that a large
+                    // partition waiting for it does not hold a concurrency
+                    // permit that a smaller partition could use.
+                    let _memory_permit = match memory_budget {
+                        Some((budget, sem)) => Some(
+                            sem.acquire_many_owned(persist_memory_permits(
+                                partition_memory_usage,
+                                budget,
+                            ))
+                            .await
+                            .expect("semaphore is never closed"),
+                        ),
+                        None => None,
+                    };
+                    let _permit = match concurrency_limit {
+                        Some(sem) => Some(
+                            sem.acquire_owned()
+                                .await
+                                .expect("semaphore is never closed"),
+                        ),
+                        None => None,
+                    };
+
                     persister
                         .persist(s.shard_id, s.namespace_id, s.table_id, s.partition_id)
                         .await;
@@ -566,6 +811,34 @@ impl LifecycleManager {
                     .await;
             }
         }
+
+        // Notify the waiters of any shards being removed that all their data
+        // has now been persisted.
+        if !draining.is_empty() {
+            let mut s = self.state.lock();
+            for shard_id in draining {
+                if s.partition_stats.values().any(|p| p.shard_id == shard_id) {
+                    // Data was buffered for the shard after the stats snapshot
+                    // was taken - persist it in the next pass.
+                    continue;
+                }
+                info!(%shard_id, "persisted all data for removed shard");
+                for tx in s.draining.remove(&shard_id).unwrap_or_default() {
+                    // The waiter may have gone away, which is fine.
+                    let _ = tx.send(());
+                }
+            }
+        }
+
+        // Notify the waiters of the requested persists that the partitions
+        // they asked for have been persisted.
+        if forced_requests > 0 {
+            let waiters: Vec<_> = self.state.lock().forced.drain(..forced_requests).collect();
+            for (_, tx) in waiters {
+                // The waiter may have gone away, which is fine.
+                let _ = tx.send(());
+            }
+        }
     }
 
     /// Returns a point in time snapshot of the lifecycle state.
@@ -576,6 +849,13 @@ impl LifecycleManager {
         LifecycleStats {
             total_bytes: s.total_bytes,
             partition_stats,
+            draining: s.draining.keys().copied().collect(),
+            forced: s
+                .forced
+                .iter()
+                .flat_map(|(partition_ids, _)| partition_ids.iter().copied())
+                .collect(),
+            forced_requests: s.forced.len(),
         }
     }
 
@@ -774,6 +1054,8 @@ mod tests {
             partition_age_threshold: Duration::from_nanos(0),
             partition_cold_threshold: Duration::from_secs(500),
             partition_row_max: 100,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
         };
         let TestLifecycleManger {
             m, time_provider, ..
@@ -838,6 +1120,8 @@ mod tests {
             partition_age_threshold: Duration::from_nanos(0),
             partition_cold_threshold: Duration::from_secs(500),
             partition_row_max: 100,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
         };
         let partition_id = PartitionId::new(1);
         let TestLifecycleManger { mut m, .. } = TestLifecycleManger::new(config);
@@ -894,6 +1178,8 @@ mod tests {
             partition_age_threshold: Duration::from_nanos(0),
             partition_cold_threshold: Duration::from_secs(500),
             partition_row_max: 10,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
         };
         let partition_id = PartitionId::new(1);
         let TestLifecycleManger { mut m, .. } = TestLifecycleManger::new(config);
@@ -973,6 +1259,8 @@ mod tests {
             partition_age_threshold: Duration::from_nanos(0),
             partition_cold_threshold: Duration::from_secs(500),
             partition_row_max: 100,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
         };
         let partition_id = PartitionId::new(1);
         let TestLifecycleManger { mut m, .. } = TestLifecycleManger::new(config);
@@ -1036,6 +1324,8 @@ mod tests {
             partition_age_threshold: Duration::from_nanos(5),
             partition_cold_threshold: Duration::from_secs(500),
             partition_row_max: 100,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
         };
         let TestLifecycleManger {
             mut m,
@@ -1107,6 +1397,8 @@ mod tests {
             partition_age_threshold: Duration::from_nanos(5),
             partition_cold_threshold: Duration::from_secs(500),
             partition_row_max: 100,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
         };
         let TestLifecycleManger {
             mut m,
@@ -1188,6 +1480,8 @@ mod tests {
             partition_age_threshold: Duration::from_millis(100),
             partition_cold_threshold: Duration::from_secs(500),
             partition_row_max: 100,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
         };
         let TestLifecycleManger {
             mut m,
@@ -1263,6 +1557,8 @@ mod tests {
             partition_age_threshold: Duration::from_millis(1000),
             partition_cold_threshold: Duration::from_secs(500),
             partition_row_max: 100,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
         };
         let shard_id = ShardId::new(1);
         let TestLifecycleManger {
@@ -1359,6 +1655,8 @@ mod tests {
             partition_age_threshold: Duration::from_millis(1000),
             partition_cold_threshold: Duration::from_secs(500),
             partition_row_max: 100,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
         };
         let shard_id = ShardId::new(1);
         let TestLifecycleManger {
@@ -1425,6 +1723,8 @@ mod tests {
             partition_age_threshold: Duration::from_secs(1000),
             partition_cold_threshold: Duration::from_secs(5),
             partition_row_max: 100,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
         };
         let TestLifecycleManger {
             mut m,
@@ -1486,6 +1786,267 @@ mod tests {
         assert_eq!(cold_counter, 1);
     }
 
+    #[tokio::test]
+    async fn persists_removed_shard() {
+        let config = LifecycleConfig {
+            pause_ingest_size: 500,
+            persist_memory_threshold: 500,
+            partition_size_threshold: 500,
+            partition_age_threshold: Duration::from_secs(1000),
+            partition_cold_threshold: Duration::from_secs(1000),
+            partition_row_max: 100,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
+        };
+        let TestLifecycleManger {
+            mut m,
+            metric_registry,
+            ..
+        } = TestLifecycleManger::new(config);
+        let h = m.handle();
+        let persister = Arc::new(TestPersister::default());
+        let removed = ShardId::new(1);
+        let retained = ShardId::new(2);
+
+        h.log_write(
+            PartitionId::new(1),
+            removed,
+            NamespaceId::new(91),
+            TableId::new(92),
+            SequenceNumber::new(1),
+            10,
+            1,
+        );
+        h.log_write(
+            PartitionId::new(2),
+            retained,
+            NamespaceId::new(91),
+            TableId::new(92),
+            SequenceNumber::new(2),
+            6,
+            1,
+        );
+
+        // A shard with no buffered data is drained immediately.
+        let mut empty = h.persist_shard(ShardId::new(3));
+        let mut done = h.persist_shard(removed);
+        assert!(done.try_recv().is_err());
+
+        m.maybe_persist(&persister).await;
+
+        done.await.expect("shard drain must complete");
+        empty.try_recv().expect("empty shard drain must complete");
+        assert!(persister.persist_called_for(PartitionId::new(1)));
+        assert!(!persister.persist_called_for(PartitionId::new(2)));
+        assert_eq!(
+            persister.update_min_calls(),
+            vec![(removed, SequenceNumber::new(1))]
+        );
+
+        let stats = m.stats();
+        assert_eq!(stats.total_bytes, 6);
+        assert_eq!(stats.partition_stats.len(), 1);
+        assert!(stats.draining.is_empty());
+
+        let counter = get_counter(&metric_registry, "shard_removed");
+        assert_eq!(counter, 1);
+    }
+
+    #[tokio::test]
+    async fn persists_requested_partitions() {
+        let config = LifecycleConfig {
+            pause_ingest_size: 500,
+            persist_memory_threshold: 500,
+            partition_size_threshold: 500,
+            partition_age_threshold: Duration::from_secs(1000),
+            partition_cold_threshold: Duration::from_secs(1000),
+            partition_row_max: 100,
+            max_concurrent_persists: None,
+            persist_memory_budget: None,
+        };
+        let TestLifecycleManger {
+            mut m,
+            metric_registry,
+            ..
+        } = TestLifecycleManger::new(config);
+        let h = m.handle();
+        let persister = Arc::new(TestPersister::default());
+        let shard_id = ShardId::new(1);
+
+        for (partition_id, sequence_number) in [(1, 1), (2, 2)] {
+            h.log_write(
+                PartitionId::new(partition_id),
+                shard_id,
+                NamespaceId::new(91),
+                TableId::new(92),
+                SequenceNumber::new(sequence_number),
+                10,
+                1,
+            );
+        }
+        let buffered = h.buffered_partitions();
+        assert_eq!(buffered.len(), 2);
+        assert_eq!(buffered[0].partition_id, PartitionId::new(1));
+        assert_eq!(buffered[0].bytes_written, 10);
+        assert_eq!(buffered[0].rows_written, 1);
+
+        // Partitions without buffered data are ignored.
+        let mut done =
+            h.persist_partitions(BTreeSet::from([PartitionId::new(1), PartitionId::new(3)]));
+        assert!(done.try_recv().is_err());
+
+        m.maybe_persist(&persister).await;
+
+        done.await.expect("requested persist must complete");
+        assert!(persister.persist_called_for(PartitionId::new(1)));
+        assert!(!persister.persist_called_for(PartitionId::new(2)));
+        assert_eq!(
+            persister.update_min_calls(),
+            vec![(shard_id, SequenceNumber::new(2))]
+        );
+
+        let buffered = h.buffered_partitions();
+        assert_eq!(buffered.len(), 1);
+        assert_eq!(buffered[0].partition_id, PartitionId::new(2));
+
+        let stats = m.stats();
+        assert_eq!(stats.total_bytes, 10);
+        assert!(stats.forced.is_empty());
+        assert_eq!(stats.forced_requests, 0);
+
+        let counter = get_counter(&metric_registry, "forced");
+        assert_eq!(counter, 1);
+    }
+
+    /// This persister records the maximum number of persists in flight at
+    /// the same time.
+    #[derive(Default)]
+    struct ConcurrencyPersister {
+        inner: TestPersister,
+        in_flight: Mutex<(usize, usize)>,
+    }
+
+    impl ConcurrencyPersister {
+        fn max_in_flight(&self) -> usize {
+            self.in_flight.lock().1
+        }
+    }
+
+    #[async_trait]
+    impl Persister for ConcurrencyPersister {
+        async fn persist(
+            &self,
+            shard_id: ShardId,
+            namespace_id: NamespaceId,
+            table_id: TableId,
+            partition_id: PartitionId,
+        ) {
+            {
+                let mut in_flight = self.in_flight.lock();
+                in_flight.0 += 1;
+                in_flight.1 = in_flight.1.max(in_flight.0);
+            }
+            // Give the other persist tasks a chance to start.
+            for _ in 0..10 {
+                tokio::task::yield_now().await;
+            }
+            self.inner
+                .persist(shard_id, namespace_id, table_id, partition_id)
+                .await;
+            self.in_flight.lock().0 -= 1;
+        }
+
+        async fn update_min_unpersisted_sequence_number(
+            &self,
+            shard_id: ShardId,
+            sequence_number: SequenceNumber,
+        ) {
+            self.inner
+                .update_min_unpersisted_sequence_number(shard_id, sequence_number)
+                .await
+        }
+    }
+
+    /// Log a write of `bytes` bytes to each of the partitions 1 to 5 and
+    /// persist them with a [`ConcurrencyPersister`].
+    async fn persist_five_partitions(
+        config: LifecycleConfig,
+        bytes: usize,
+    ) -> Arc<ConcurrencyPersister> {
+        let TestLifecycleManger { mut m, .. } = TestLifecycleManger::new(config);
+        let shard_id = ShardId::new(1);
+        let h = m.handle();
+        for i in 1..=5 {
+            h.log_write(
+                PartitionId::new(i),
+                shard_id,
+                NamespaceId::new(91),
+                TableId::new(92),
+                SequenceNumber::new(i),
+                bytes,
+                1,
+            );
+        }
+
+        let persister = Arc::new(ConcurrencyPersister::default());
+        m.maybe_persist(&persister).await;
+
+        for i in 1..=5 {
+            assert!(persister.inner.persist_called_for(PartitionId::new(i)));
+        }
+        assert_eq!(
+            persister.inner.update_min_calls(),
+            vec![(shard_id, SequenceNumber::new(5))]
+        );
+        assert_eq!(m.stats().total_bytes, 0);
+
+        persister
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn persists_bounded_by_max_concurrent_persists() {
+        let config = LifecycleConfig::new(
+            100,
+            50,
+            5,
+            Duration::from_secs(100),
+            Duration::from_secs(500),
+            100,
+        )
+        .with_persist_concurrency(NonZeroUsize::new(2), None);
+
+        let persister = persist_five_partitions(config, 10).await;
+        assert!(persister.max_in_flight() <= 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn persists_bounded_by_memory_budget() {
+        // Only one partition of 10 bytes fits in the budget at a time.
+        let config = LifecycleConfig::new(
+            100,
+            50,
+            5,
+            Duration::from_secs(100),
+            Duration::from_secs(500),
+            100,
+        )
+        .with_persist_concurrency(None, NonZeroUsize::new(15));
+
+        let persister = persist_five_partitions(config, 10).await;
+        assert_eq!(persister.max_in_flight(), 1);
+    }
+
+    #[test]
+    fn test_persist_memory_permits() {
+        let budget = NonZeroUsize::new(2048).unwrap();
+        assert_eq!(persist_memory_permits(0, budget), 1);
+        assert_eq!(persist_memory_permits(1, budget), 1);
+        assert_eq!(persist_memory_permits(3, budget), 2);
+        assert_eq!(persist_memory_permits(1024, budget), 512);
+        assert_eq!(persist_memory_permits(2048, budget), 1024);
+        assert_eq!(persist_memory_permits(usize::MAX, budget), 1024);
+    }
+
     struct TestLifecycleManger {
         m: LifecycleManager,
         time_provider: Arc<MockProvider>,
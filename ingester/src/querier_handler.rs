@@ -9,12 +9,14 @@ use datafusion::physical_plan::SendableRecordBatchStream;
 use datafusion_util::MemoryStream;
 use futures::{Stream, StreamExt, TryStreamExt};
 use generated_types::ingester::IngesterQueryRequest;
+use iox_query::QueryChunk;
 use observability_deps::tracing::*;
+use predicate::{Predicate, PredicateMatch};
 use schema::{merge::SchemaMerger, Projection};
 use snafu::{ensure, Snafu};
 use trace::span::{Span, SpanRecorder};
 
-use crate::data::IngesterData;
+use crate::{data::IngesterData, query_adaptor::QueryAdaptor};
 
 /// Number of table data read locks that shall be acquired in parallel
 const CONCURRENT_TABLE_DATA_LOCKS: usize = 10;
@@ -57,6 +59,9 @@ pub(crate) type SnapshotStream =
 pub struct PartitionStatus {
     /// Max sequence number persisted
     pub parquet_max_sequence_number: Option<SequenceNumber>,
+
+    /// Max sequence number of the unpersisted data
+    pub max_sequence_number: Option<SequenceNumber>,
 }
 
 /// Response data for a single partition.
@@ -304,78 +309,85 @@ pub async fn prepare_data_to_querier(
         },
     );
 
-    // acquire locks and read table data in parallel
+    // pin the table data in parallel, deferring the generation of record
+    // batches until the partitions are read so that no lock is held meanwhile
     let unpersisted_partitions: Vec<_> = futures::stream::iter(table_refs)
-        .map(|table_data| async move {
-            table_data
-                .partitions()
-                .into_iter()
-                .map(|p| {
-                    let mut p = p.lock();
-                    (
-                        p.partition_id(),
-                        p.get_query_data(),
-                        p.max_persisted_sequence_number(),
-                    )
-                })
-                .collect::<Vec<_>>()
-        })
+        .map(|table_data| async move { table_data.pin_partitions() })
         // Note: the order doesn't matter
         .buffer_unordered(CONCURRENT_TABLE_DATA_LOCKS)
         .concat()
         .await;
 
     let request = Arc::clone(request);
-    let partitions = futures::stream::iter(unpersisted_partitions.into_iter().map(
-        move |(partition_id, data, max_persisted_sequence_number)| {
-            let snapshots = match data {
-                None => Box::pin(futures::stream::empty()) as SnapshotStream,
-
-                Some(batch) => {
-                    assert_eq!(partition_id, batch.partition_id());
-
-                    // Project the data if necessary
-                    let columns = request
-                        .columns
-                        .iter()
-                        .map(String::as_str)
-                        .collect::<Vec<_>>();
-                    let selection = if columns.is_empty() {
-                        Projection::All
-                    } else {
-                        Projection::Some(columns.as_ref())
-                    };
-
-                    let snapshots = batch.project_selection(selection).into_iter().map(|batch| {
-                        // Create a stream from the batch.
-                        Ok(Box::pin(MemoryStream::new(vec![batch])) as SendableRecordBatchStream)
-                    });
+    let partitions = futures::stream::iter(unpersisted_partitions.into_iter().map(move |pinned| {
+        let partition_id = pinned.partition_id();
+        let max_persisted_sequence_number = pinned.max_persisted_sequence_number();
+        let max_sequence_number = pinned.max_sequence_number();
 
-                    Box::pin(futures::stream::iter(snapshots)) as SnapshotStream
-                }
-            };
-
-            // NOTE: the partition persist watermark MUST always be provided to
-            // the querier for any partition that has performed (or is aware of)
-            // a persist operation.
-            //
-            // This allows the querier to use the per-partition persist marker
-            // when planning queries.
-            Ok(IngesterQueryPartition::new(
-                snapshots,
-                partition_id,
-                PartitionStatus {
-                    parquet_max_sequence_number: max_persisted_sequence_number,
-                },
-            ))
-        },
-    ));
+        // Skip the data of partitions whose statistics show that no row matches the predicate.
+        let data = pinned
+            .into_query_data()
+            .filter(|batch| !prune_by_metadata(batch, request.predicate.as_ref()));
+
+        let snapshots = match data {
+            None => Box::pin(futures::stream::empty()) as SnapshotStream,
+
+            Some(batch) => {
+                assert_eq!(partition_id, batch.partition_id());
+
+                // Project the data if necessary
+                let columns = request
+                    .columns
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<_>>();
+                let selection = if columns.is_empty() {
+                    Projection::All
+                } else {
+                    Projection::Some(columns.as_ref())
+                };
+
+                let snapshots = batch.project_selection(selection).into_iter().map(|batch| {
+                    // Create a stream from the batch.
+                    Ok(Box::pin(MemoryStream::new(vec![batch])) as SendableRecordBatchStream)
+                });
+
+                Box::pin(futures::stream::iter(snapshots)) as SnapshotStream
+            }
+        };
+
+        // NOTE: the partition persist watermark MUST always be provided to
+        // the querier for any partition that has performed (or is aware of)
+        // a persist operation.
+        //
+        // This allows the querier to use the per-partition persist marker
+        // when planning queries.
+        Ok(IngesterQueryPartition::new(
+            snapshots,
+            partition_id,
+            PartitionStatus {
+                parquet_max_sequence_number: max_persisted_sequence_number,
+                max_sequence_number,
+            },
+        ))
+    }));
 
     span_recorder.ok("done");
 
     Ok(IngesterQueryResponse::new(Box::pin(partitions)))
 }
 
+/// Returns true if the statistics of `batch` show that no row can match `predicate`.
+fn prune_by_metadata(batch: &QueryAdaptor, predicate: Option<&Predicate>) -> bool {
+    match predicate {
+        Some(predicate) => matches!(
+            batch.apply_predicate_to_metadata(predicate),
+            Ok(PredicateMatch::Zero)
+        ),
+        None => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::task::{Context, Poll};
@@ -388,7 +400,6 @@ mod tests {
         prelude::{col, lit},
     };
     use mutable_batch_lp::test_helpers::lp_to_mutable_batch;
-    use predicate::Predicate;
 
     use super::*;
     use crate::test_util::make_ingester_data;
@@ -425,6 +436,7 @@ mod tests {
                 PartitionId::new(2),
                 PartitionStatus {
                     parquet_max_sequence_number: None,
+                    max_sequence_number: None,
                 },
             )),
             Err(ArrowError::IoError("some io error".into())),
@@ -433,6 +445,7 @@ mod tests {
                 PartitionId::new(1),
                 PartitionStatus {
                     parquet_max_sequence_number: None,
+                    max_sequence_number: None,
                 },
             )),
         ])));
@@ -443,6 +456,7 @@ mod tests {
                 partition_id: PartitionId::new(2),
                 status: PartitionStatus {
                     parquet_max_sequence_number: None,
+                    max_sequence_number: None,
                 },
             }),
             Ok(FlatIngesterQueryResponse::StartSnapshot { schema: schema_1 }),
@@ -458,6 +472,7 @@ mod tests {
                 partition_id: PartitionId::new(1),
                 status: PartitionStatus {
                     parquet_max_sequence_number: None,
+                    max_sequence_number: None,
                 },
             }),
         ];
@@ -602,6 +617,28 @@ mod tests {
             assert_batches_sorted_eq!(&expected, &result);
         }
 
+        // partitions are pruned using their statistics
+        let pred = Predicate::default().with_range(1_000, 2_000);
+        let request = Arc::new(IngesterQueryRequest::new(
+            ns_id,
+            table_id,
+            vec![],
+            Some(pred),
+        ));
+        for scenario in &scenarios {
+            let messages: Vec<_> = prepare_data_to_querier(scenario, &request, None)
+                .await
+                .unwrap()
+                .flatten()
+                .try_collect()
+                .await
+                .unwrap();
+            assert!(!messages.is_empty());
+            assert!(messages
+                .iter()
+                .all(|msg| matches!(msg, FlatIngesterQueryResponse::StartPartition { .. })));
+        }
+
         // test "table not found" handling
         let request = Arc::new(IngesterQueryRequest::new(
             ns_id,
@@ -3,19 +3,24 @@
 
 use std::{any::Any, sync::Arc};
 
-use arrow::record_batch::RecordBatch;
+use arrow::{
+    array::{ArrayRef, UInt32Array},
+    compute::{concat_batches, lexsort_to_indices, take, SortColumn},
+    record_batch::RecordBatch,
+};
 use arrow_util::util::ensure_schema;
+use async_trait::async_trait;
 use data_types::{ChunkId, ChunkOrder, DeletePredicate, PartitionId, TableSummary};
 use datafusion::error::DataFusionError;
 use iox_query::{
     exec::{stringset::StringSet, IOxSessionContext},
-    util::{compute_timenanosecond_min_max, create_basic_summary},
+    util::compute_table_summary,
     QueryChunk, QueryChunkData, QueryChunkMeta,
 };
 use once_cell::sync::OnceCell;
 use predicate::Predicate;
 use schema::{merge::merge_record_batch_schemas, sort::SortKey, Projection, Schema};
-use snafu::Snafu;
+use snafu::{ResultExt, Snafu};
 
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Snafu)]
@@ -32,6 +37,9 @@ pub enum Error {
 
     #[snafu(display("Internal error filtering record batch: {}", source))]
     FilterBatch { source: arrow::error::ArrowError },
+
+    #[snafu(display("Internal error sorting record batch: {}", source))]
+    SortBatch { source: arrow::error::ArrowError },
 }
 
 /// A specialized `Error` for Ingester's Query errors
@@ -64,6 +72,9 @@ pub(crate) struct QueryAdaptor {
 
     /// An interned table summary.
     summary: OnceCell<Arc<TableSummary>>,
+
+    /// The sort key of `data`, if it is a single [`RecordBatch`] sorted on it.
+    sort_key: Option<SortKey>,
 }
 
 impl QueryAdaptor {
@@ -88,9 +99,57 @@ impl QueryAdaptor {
             id: ChunkId::new(),
             schema: OnceCell::default(),
             summary: OnceCell::default(),
+            sort_key: None,
         }
     }
 
+    /// Sort the data on `sort_key`, returning a [`QueryAdaptor`] over a single
+    /// sorted [`RecordBatch`] that reports `sort_key` as its sort key.
+    ///
+    /// Rows with equal sort key values keep their relative order, preserving
+    /// the order of writes for deduplication.
+    pub(crate) fn sort(self, sort_key: SortKey) -> Result<Self> {
+        let schema = self.schema().as_arrow();
+        let batches = self
+            .data
+            .iter()
+            .map(|b| ensure_schema(&schema, b))
+            .collect::<Result<Vec<_>, _>>()
+            .context(ConcatBatchesSnafu)?;
+        let batch = concat_batches(&schema, &batches).context(ConcatBatchesSnafu)?;
+
+        // The row position breaks ties, making the sort stable.
+        let row_positions = UInt32Array::from_iter_values(0..batch.num_rows() as u32);
+        let sort_columns = sort_key
+            .iter()
+            .filter_map(|(column_name, options)| {
+                Some(SortColumn {
+                    values: Arc::clone(batch.column(schema.index_of(column_name).ok()?)),
+                    options: Some(*options),
+                })
+            })
+            .chain(std::iter::once(SortColumn {
+                values: Arc::new(row_positions) as ArrayRef,
+                options: None,
+            }))
+            .collect::<Vec<_>>();
+
+        let indices = lexsort_to_indices(&sort_columns, None).context(SortBatchSnafu)?;
+        let columns = batch
+            .columns()
+            .iter()
+            .map(|column| take(column.as_ref(), &indices, None))
+            .collect::<Result<Vec<_>, _>>()
+            .context(SortBatchSnafu)?;
+        let batch = RecordBatch::try_new(schema, columns).context(SortBatchSnafu)?;
+
+        Ok(Self {
+            data: vec![Arc::new(batch)],
+            sort_key: Some(sort_key),
+            ..self
+        })
+    }
+
     pub(crate) fn project_selection(&self, selection: Projection<'_>) -> Vec<RecordBatch> {
         // Project the column selection across all RecordBatch
         self.data
@@ -132,13 +191,9 @@ impl QueryAdaptor {
 impl QueryChunkMeta for QueryAdaptor {
     fn summary(&self) -> Arc<TableSummary> {
         Arc::clone(self.summary.get_or_init(|| {
-            let ts_min_max = compute_timenanosecond_min_max(self.data.iter().map(|b| b.as_ref()))
-                .expect("Should have time range");
-
-            Arc::new(create_basic_summary(
-                self.data.iter().map(|b| b.num_rows()).sum::<usize>() as u64,
+            Arc::new(compute_table_summary(
                 &self.schema(),
-                ts_min_max,
+                self.data.iter().map(|b| b.as_ref()),
             ))
         }))
     }
@@ -159,7 +214,8 @@ impl QueryChunkMeta for QueryAdaptor {
     }
 
     fn sort_key(&self) -> Option<&SortKey> {
-        None // Ingester data is not sorted
+        // Ingester data is only sorted once explicitly sorted for persistence
+        self.sort_key.as_ref()
     }
 
     fn delete_predicates(&self) -> &[Arc<DeletePredicate>] {
@@ -167,6 +223,7 @@ impl QueryChunkMeta for QueryAdaptor {
     }
 }
 
+#[async_trait]
 impl QueryChunk for QueryAdaptor {
     fn id(&self) -> ChunkId {
         self.id
@@ -184,7 +241,7 @@ impl QueryChunk for QueryAdaptor {
     /// table that have at least one row that matches `predicate`, if
     /// the predicate can be evaluated entirely on the metadata of
     /// this Chunk. Returns `None` otherwise
-    fn column_names(
+    async fn column_names(
         &self,
         _ctx: IOxSessionContext,
         _predicate: &Predicate,
@@ -198,7 +255,7 @@ impl QueryChunk for QueryAdaptor {
     /// on the metadata of this Chunk. Returns `None` otherwise
     ///
     /// The requested columns must all have String type.
-    fn column_values(
+    async fn column_values(
         &self,
         _ctx: IOxSessionContext,
         _column_name: &str,
@@ -1,6 +1,7 @@
 //! gRPC service implementations for `ingester`.
 
 use crate::{
+    drain::Drain,
     handler::IngestHandler,
     querier_handler::{FlatIngesterQueryResponse, FlatIngesterQueryResponseStream},
 };
@@ -10,23 +11,33 @@ use arrow_flight::{
     Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
     HandshakeRequest, HandshakeResponse, IpcMessage, PutResult, SchemaAsIpc, SchemaResult, Ticket,
 };
-use data_types::{NamespaceId, TableId};
+use data_types::{DrainState, NamespaceId, PartitionId, PartitionKey, ShardIndex, TableId};
 use flatbuffers::FlatBufferBuilder;
 use futures::Stream;
+use generated_types::google::{
+    longrunning::operations_server::{Operations, OperationsServer},
+    PreconditionViolation,
+};
 use generated_types::influxdata::iox::{
     catalog::v1::*,
     ingester::v1::{
         self as proto,
+        drain_service_server::{DrainService, DrainServiceServer},
+        persist_service_server::{PersistService, PersistServiceServer},
+        shard_assignment_service_server::{ShardAssignmentService, ShardAssignmentServiceServer},
         write_info_service_server::{WriteInfoService, WriteInfoServiceServer},
     },
 };
 use iox_catalog::interface::Catalog;
 use observability_deps::tracing::*;
+use parking_lot::Mutex;
 use pin_project::pin_project;
 use prost::Message;
 use service_grpc_catalog::CatalogService;
+use service_grpc_operations::OperationsService;
 use snafu::{ResultExt, Snafu};
 use std::{
+    collections::{BTreeMap, BTreeSet},
     pin::Pin,
     sync::{
         atomic::{AtomicU64, Ordering},
@@ -34,6 +45,7 @@ use std::{
     },
     task::Poll,
 };
+use tokio::sync::oneshot;
 use tonic::{Request, Response, Streaming};
 use trace::{ctx::SpanContext, span::SpanExt};
 use write_summary::WriteSummary;
@@ -48,6 +60,9 @@ pub struct GrpcDelegate<I: IngestHandler> {
     ///
     /// Every panic will decrease the counter until it reaches zero. At zero, no panics will occur.
     test_flight_do_get_panic: Arc<AtomicU64>,
+
+    /// The drain state of the ingester, if it can be drained.
+    drain: Option<Arc<Drain>>,
 }
 
 impl<I: IngestHandler + Send + Sync + 'static> GrpcDelegate<I> {
@@ -61,6 +76,15 @@ impl<I: IngestHandler + Send + Sync + 'static> GrpcDelegate<I> {
             catalog,
             ingest_handler,
             test_flight_do_get_panic,
+            drain: None,
+        }
+    }
+
+    /// Serve the drain gRPC service, draining the ingester through `drain`.
+    pub fn with_drain(self, drain: Arc<Drain>) -> Self {
+        Self {
+            drain: Some(drain),
+            ..self
         }
     }
 
@@ -79,6 +103,31 @@ impl<I: IngestHandler + Send + Sync + 'static> GrpcDelegate<I> {
         ))
     }
 
+    /// Acquire a ShardAssignment gRPC service implementation.
+    pub fn shard_assignment_service(
+        &self,
+    ) -> ShardAssignmentServiceServer<impl ShardAssignmentService> {
+        ShardAssignmentServiceServer::new(ShardAssignmentServiceImpl::new(Arc::clone(
+            &self.ingest_handler,
+        ) as _))
+    }
+
+    /// Acquire a Drain gRPC service implementation.
+    ///
+    /// The service fails all requests unless the ingester can be drained, see
+    /// [`Self::with_drain()`].
+    pub fn drain_service(&self) -> DrainServiceServer<impl DrainService> {
+        DrainServiceServer::new(DrainServiceImpl::new(self.drain.clone()))
+    }
+
+    /// Acquire a Persist gRPC service implementation.
+    pub fn persist_service(&self) -> PersistServiceServer<impl PersistService> {
+        PersistServiceServer::new(PersistServiceImpl::new(
+            Arc::clone(&self.catalog),
+            Arc::clone(&self.ingest_handler) as _,
+        ))
+    }
+
     /// Acquire a [`CatalogService`] gRPC service implementation.
     ///
     /// [`CatalogService`]: generated_types::influxdata::iox::catalog::v1::catalog_service_server::CatalogService.
@@ -90,6 +139,12 @@ impl<I: IngestHandler + Send + Sync + 'static> GrpcDelegate<I> {
             &self.catalog,
         )))
     }
+
+    /// Acquire an [`OperationsService`] gRPC service implementation, serving
+    /// the job operations recorded in the catalog.
+    pub fn operations_service(&self) -> OperationsServer<impl Operations> {
+        OperationsServer::new(OperationsService::new(Arc::clone(&self.catalog)))
+    }
 }
 
 /// Implementation of write info
@@ -139,6 +194,330 @@ impl WriteInfoService for WriteInfoServiceImpl {
     }
 }
 
+/// Implementation of shard assignment
+struct ShardAssignmentServiceImpl {
+    handler: Arc<dyn IngestHandler + Send + Sync + 'static>,
+}
+
+impl ShardAssignmentServiceImpl {
+    pub fn new(handler: Arc<dyn IngestHandler + Send + Sync + 'static>) -> Self {
+        Self { handler }
+    }
+
+    fn shard_indexes(&self) -> Vec<i32> {
+        self.handler
+            .shard_indexes()
+            .into_iter()
+            .map(|v| v.get())
+            .collect()
+    }
+}
+
+#[tonic::async_trait]
+impl ShardAssignmentService for ShardAssignmentServiceImpl {
+    async fn get_shard_assignment(
+        &self,
+        _request: Request<proto::GetShardAssignmentRequest>,
+    ) -> Result<Response<proto::GetShardAssignmentResponse>, tonic::Status> {
+        Ok(tonic::Response::new(proto::GetShardAssignmentResponse {
+            shard_indexes: self.shard_indexes(),
+        }))
+    }
+
+    async fn set_shard_assignment(
+        &self,
+        request: Request<proto::SetShardAssignmentRequest>,
+    ) -> Result<Response<proto::SetShardAssignmentResponse>, tonic::Status> {
+        let proto::SetShardAssignmentRequest { shard_indexes } = request.into_inner();
+
+        if let Some(v) = shard_indexes.iter().find(|&&v| v < 0) {
+            return Err(tonic::Status::invalid_argument(format!(
+                "invalid shard index {v}"
+            )));
+        }
+
+        self.handler
+            .set_shard_indexes(shard_indexes.into_iter().map(ShardIndex::new).collect())
+            .await
+            .map_err(|e| {
+                warn!(error=%e, "failed to change shard assignment");
+                match e {
+                    crate::handler::Error::ShuttingDown => {
+                        tonic::Status::unavailable(e.to_string())
+                    }
+                    crate::handler::Error::ConsumerFailed { .. } => {
+                        PreconditionViolation::ShardUnavailable(e.to_string()).into()
+                    }
+                    _ => tonic::Status::internal(e.to_string()),
+                }
+            })?;
+
+        Ok(tonic::Response::new(proto::SetShardAssignmentResponse {
+            shard_indexes: self.shard_indexes(),
+        }))
+    }
+}
+
+/// Implementation of drain
+struct DrainServiceImpl {
+    drain: Option<Arc<Drain>>,
+}
+
+impl DrainServiceImpl {
+    pub fn new(drain: Option<Arc<Drain>>) -> Self {
+        Self { drain }
+    }
+
+    fn drain(&self) -> Result<&Arc<Drain>, tonic::Status> {
+        self.drain.as_ref().ok_or_else(|| {
+            tonic::Status::failed_precondition(
+                "ingester can not be drained without an advertise address",
+            )
+        })
+    }
+}
+
+fn drain_state_to_proto(state: DrainState) -> proto::DrainState {
+    match state {
+        DrainState::Active => proto::DrainState::Active,
+        DrainState::Draining => proto::DrainState::Draining,
+        DrainState::Drained => proto::DrainState::Drained,
+    }
+}
+
+#[tonic::async_trait]
+impl DrainService for DrainServiceImpl {
+    async fn drain(
+        &self,
+        _request: Request<proto::DrainRequest>,
+    ) -> Result<Response<proto::DrainResponse>, tonic::Status> {
+        let state = self.drain()?.start();
+
+        Ok(tonic::Response::new(proto::DrainResponse {
+            state: drain_state_to_proto(state).into(),
+        }))
+    }
+
+    async fn get_drain_state(
+        &self,
+        _request: Request<proto::GetDrainStateRequest>,
+    ) -> Result<Response<proto::GetDrainStateResponse>, tonic::Status> {
+        let state = self.drain()?.state();
+
+        Ok(tonic::Response::new(proto::GetDrainStateResponse {
+            state: drain_state_to_proto(state).into(),
+            safe_to_terminate: state == DrainState::Drained,
+        }))
+    }
+}
+
+/// The number of persists whose status is kept.
+const PERSIST_HISTORY_SIZE: usize = 1000;
+
+/// Implementation of persist
+struct PersistServiceImpl {
+    catalog: Arc<dyn Catalog>,
+    handler: Arc<dyn IngestHandler + Send + Sync + 'static>,
+
+    persists: Mutex<PersistHistory>,
+}
+
+/// The most recent [`PERSIST_HISTORY_SIZE`] requested persists.
+#[derive(Default)]
+struct PersistHistory {
+    /// The requested persists by ID.
+    persists: BTreeMap<u64, PersistState>,
+
+    /// The ID of the next requested persist.
+    next_id: u64,
+}
+
+impl PersistHistory {
+    /// Record a persist that completes when `rx` resolves, returning its ID.
+    fn push(&mut self, rx: oneshot::Receiver<()>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.persists.insert(id, PersistState::Persisting(rx));
+        if self.persists.len() > PERSIST_HISTORY_SIZE {
+            let oldest = *self.persists.keys().next().expect("not empty");
+            self.persists.remove(&oldest);
+        }
+
+        id
+    }
+}
+
+/// The state of a requested persist.
+enum PersistState {
+    /// Resolves once the data has been persisted.
+    Persisting(oneshot::Receiver<()>),
+    Complete,
+    Failed,
+}
+
+impl PersistState {
+    fn status(&mut self) -> proto::PersistStatus {
+        if let Self::Persisting(rx) = self {
+            match rx.try_recv() {
+                Ok(()) => *self = Self::Complete,
+                Err(oneshot::error::TryRecvError::Empty) => {}
+                Err(oneshot::error::TryRecvError::Closed) => *self = Self::Failed,
+            }
+        }
+
+        match self {
+            Self::Persisting(_) => proto::PersistStatus::Persisting,
+            Self::Complete => proto::PersistStatus::Complete,
+            Self::Failed => proto::PersistStatus::Failed,
+        }
+    }
+}
+
+impl PersistServiceImpl {
+    pub fn new(
+        catalog: Arc<dyn Catalog>,
+        handler: Arc<dyn IngestHandler + Send + Sync + 'static>,
+    ) -> Self {
+        Self {
+            catalog,
+            handler,
+            persists: Default::default(),
+        }
+    }
+
+    /// Resolve the buffered partitions of the table, or of the partition of
+    /// the table, named in `request`.
+    async fn buffered_partitions(
+        &self,
+        request: &proto::PersistRequest,
+    ) -> Result<BTreeSet<PartitionId>, tonic::Status> {
+        let mut repos = self.catalog.repositories().await;
+
+        let namespace = repos
+            .namespaces()
+            .get_by_name(&request.namespace_name)
+            .await
+            .map_err(|e| tonic::Status::unknown(e.to_string()))?
+            .ok_or_else(|| {
+                tonic::Status::not_found(format!("namespace {} not found", request.namespace_name))
+            })?;
+        let table = repos
+            .tables()
+            .get_by_namespace_and_name(namespace.id, &request.table_name)
+            .await
+            .map_err(|e| tonic::Status::unknown(e.to_string()))?
+            .ok_or_else(|| {
+                tonic::Status::not_found(format!("table {} not found", request.table_name))
+            })?;
+
+        // A partition key identifies one partition per shard.
+        let partition_ids = if request.partition_key.is_empty() {
+            None
+        } else {
+            let key = PartitionKey::from(request.partition_key.as_str());
+            let partition_ids: BTreeSet<_> = repos
+                .partitions()
+                .list_by_table_id(table.id)
+                .await
+                .map_err(|e| tonic::Status::unknown(e.to_string()))?
+                .into_iter()
+                .filter(|p| p.partition_key == key)
+                .map(|p| p.id)
+                .collect();
+            if partition_ids.is_empty() {
+                return Err(tonic::Status::not_found(format!(
+                    "partition {} not found",
+                    request.partition_key
+                )));
+            }
+            Some(partition_ids)
+        };
+
+        Ok(self
+            .handler
+            .buffered_partitions()
+            .into_iter()
+            .filter(|p| p.table_id == table.id)
+            .map(|p| p.partition_id)
+            .filter(|id| partition_ids.as_ref().map_or(true, |ids| ids.contains(id)))
+            .collect())
+    }
+}
+
+#[tonic::async_trait]
+impl PersistService for PersistServiceImpl {
+    async fn list_buffered_partitions(
+        &self,
+        _request: Request<proto::ListBufferedPartitionsRequest>,
+    ) -> Result<Response<proto::ListBufferedPartitionsResponse>, tonic::Status> {
+        let partitions = self
+            .handler
+            .buffered_partitions()
+            .into_iter()
+            .map(|p| proto::BufferedPartition {
+                shard_id: p.shard_id.get(),
+                namespace_id: p.namespace_id.get(),
+                table_id: p.table_id.get(),
+                partition_id: p.partition_id.get(),
+                bytes: p.bytes_written as u64,
+                rows: p.rows_written as u64,
+                first_write_time_ns: p.first_write.timestamp_nanos(),
+                last_write_time_ns: p.last_write.timestamp_nanos(),
+                first_sequence_number: p.first_sequence_number.get(),
+            })
+            .collect();
+
+        Ok(tonic::Response::new(
+            proto::ListBufferedPartitionsResponse { partitions },
+        ))
+    }
+
+    async fn persist(
+        &self,
+        request: Request<proto::PersistRequest>,
+    ) -> Result<Response<proto::PersistResponse>, tonic::Status> {
+        let request = request.into_inner();
+        let partition_ids = self.buffered_partitions(&request).await?;
+
+        let rx = self.handler.persist_partitions(partition_ids.clone());
+
+        let persist_id = self.persists.lock().push(rx);
+        info!(
+            persist_id,
+            namespace_name = %request.namespace_name,
+            table_name = %request.table_name,
+            partition_key = %request.partition_key,
+            ?partition_ids,
+            "persist requested"
+        );
+
+        Ok(tonic::Response::new(proto::PersistResponse {
+            persist_id,
+            partition_ids: partition_ids.into_iter().map(|id| id.get()).collect(),
+        }))
+    }
+
+    async fn get_persist_status(
+        &self,
+        request: Request<proto::GetPersistStatusRequest>,
+    ) -> Result<Response<proto::GetPersistStatusResponse>, tonic::Status> {
+        let proto::GetPersistStatusRequest { persist_id } = request.into_inner();
+
+        let status = self
+            .persists
+            .lock()
+            .persists
+            .get_mut(&persist_id)
+            .map(PersistState::status)
+            .ok_or_else(|| tonic::Status::not_found(format!("persist {persist_id} not found")))?;
+
+        Ok(tonic::Response::new(proto::GetPersistStatusResponse {
+            status: status.into(),
+        }))
+    }
+}
+
 #[derive(Debug, Snafu)]
 #[allow(missing_docs)]
 pub enum Error {
@@ -425,6 +804,7 @@ impl Stream for GetStream {
                             parquet_max_sequence_number: status
                                 .parquet_max_sequence_number
                                 .map(|x| x.get()),
+                            max_sequence_number: status.max_sequence_number.map(|x| x.get()),
                         }),
                     };
                     prost::Message::encode(&app_metadata, &mut bytes)
@@ -502,6 +882,7 @@ mod tests {
                     partition_id: PartitionId::new(1),
                     status: PartitionStatus {
                         parquet_max_sequence_number: None,
+                        max_sequence_number: None,
                     },
                 }),
                 Ok(FlatIngesterQueryResponse::StartSnapshot { schema }),
@@ -514,6 +895,7 @@ mod tests {
                         partition_id: 1,
                         status: Some(proto::PartitionStatus {
                             parquet_max_sequence_number: None,
+                            max_sequence_number: None,
                         }),
                     },
                 }),
@@ -538,6 +920,7 @@ mod tests {
                     partition_id: PartitionId::new(1),
                     status: PartitionStatus {
                         parquet_max_sequence_number: None,
+                        max_sequence_number: None,
                     },
                 }),
                 Err(ArrowError::IoError("foo".into())),
@@ -545,6 +928,7 @@ mod tests {
                     partition_id: PartitionId::new(1),
                     status: PartitionStatus {
                         parquet_max_sequence_number: None,
+                        max_sequence_number: None,
                     },
                 }),
             ],
@@ -555,6 +939,7 @@ mod tests {
                         partition_id: 1,
                         status: Some(proto::PartitionStatus {
                             parquet_max_sequence_number: None,
+                            max_sequence_number: None,
                         }),
                     },
                 }),
@@ -564,6 +949,33 @@ mod tests {
         .await;
     }
 
+    #[test]
+    fn test_persist_history() {
+        let mut history = PersistHistory::default();
+
+        let (tx_complete, rx) = oneshot::channel();
+        let complete = history.push(rx);
+        let (tx_failed, rx) = oneshot::channel();
+        let failed = history.push(rx);
+        assert_ne!(complete, failed);
+
+        let mut status = |id| history.persists.get_mut(&id).unwrap().status();
+        assert_eq!(status(complete), proto::PersistStatus::Persisting);
+
+        tx_complete.send(()).unwrap();
+        drop(tx_failed);
+        assert_eq!(status(complete), proto::PersistStatus::Complete);
+        assert_eq!(status(complete), proto::PersistStatus::Complete);
+        assert_eq!(status(failed), proto::PersistStatus::Failed);
+
+        // only the most recent persists are kept
+        for _ in 0..PERSIST_HISTORY_SIZE {
+            history.push(oneshot::channel().1);
+        }
+        assert_eq!(history.persists.len(), PERSIST_HISTORY_SIZE);
+        assert!(!history.persists.contains_key(&complete));
+    }
+
     #[tokio::test]
     async fn test_get_stream_dictionary_batches() {
         let batch = lp_to_mutable_batch("table,x=\"foo\",y=\"bar\" z=1 0")
@@ -417,7 +417,7 @@ something clever.",
                         shard_id=%self.shard_id,
                         false,
                         ?op_sequence_number,
-                        "did not apply dml operation (op was already persisted previously)"
+                        "did not apply dml operation (op was already buffered or persisted previously)"
                     );
                     false
                 }
@@ -3,23 +3,35 @@
 #![allow(missing_docs)]
 #![cfg(test)]
 
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    time::Duration,
+};
 
 use arrow::record_batch::RecordBatch;
 use arrow_util::assert_batches_eq;
+use async_trait::async_trait;
 use data_types::{
-    NamespaceId, PartitionKey, Sequence, SequenceNumber, ShardId, ShardIndex, TableId,
+    NamespaceId, PartitionId, PartitionKey, Sequence, SequenceNumber, ShardId, ShardIndex, TableId,
 };
 use dml::{DmlMeta, DmlOperation, DmlWrite};
+use generated_types::ingester::IngesterQueryRequest;
 use iox_catalog::{interface::Catalog, mem::MemCatalog};
 use iox_query::test::{raw_data, TestChunk};
 use iox_time::{SystemProvider, Time};
 use mutable_batch_lp::lines_to_batches;
 use object_store::memory::InMemory;
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+use trace::span::Span;
+use write_summary::ShardProgress;
 
 use crate::{
     data::IngesterData,
-    lifecycle::{LifecycleConfig, LifecycleManager},
+    handler::IngestHandler,
+    lifecycle::{BufferedPartition, LifecycleConfig, LifecycleManager},
+    querier_handler::IngesterQueryResponse,
 };
 
 pub(crate) async fn create_one_row_record_batch_with_influxtype() -> Vec<Arc<RecordBatch>> {
@@ -743,3 +755,60 @@ pub(crate) async fn populate_catalog(
         .id;
     (shard_id, ns_id, table_id)
 }
+
+/// An [`IngestHandler`] that only tracks the shards it consumes.
+#[derive(Debug, Default)]
+pub(crate) struct MockIngestHandler {
+    shard_indexes: Mutex<BTreeSet<ShardIndex>>,
+}
+
+impl MockIngestHandler {
+    /// A handler consuming `shard_indexes`.
+    pub(crate) fn new(shard_indexes: impl IntoIterator<Item = ShardIndex>) -> Self {
+        Self {
+            shard_indexes: Mutex::new(shard_indexes.into_iter().collect()),
+        }
+    }
+}
+
+#[async_trait]
+impl IngestHandler for MockIngestHandler {
+    async fn query(
+        &self,
+        _request: IngesterQueryRequest,
+        _span: Option<Span>,
+    ) -> Result<IngesterQueryResponse, crate::querier_handler::Error> {
+        unimplemented!()
+    }
+
+    async fn progresses(
+        &self,
+        _shard_indexes: Vec<ShardIndex>,
+    ) -> BTreeMap<ShardIndex, ShardProgress> {
+        unimplemented!()
+    }
+
+    fn shard_indexes(&self) -> BTreeSet<ShardIndex> {
+        self.shard_indexes.lock().clone()
+    }
+
+    async fn set_shard_indexes(
+        &self,
+        shard_indexes: BTreeSet<ShardIndex>,
+    ) -> crate::handler::Result<()> {
+        *self.shard_indexes.lock() = shard_indexes;
+        Ok(())
+    }
+
+    fn buffered_partitions(&self) -> Vec<BufferedPartition> {
+        unimplemented!()
+    }
+
+    fn persist_partitions(&self, _partition_ids: BTreeSet<PartitionId>) -> oneshot::Receiver<()> {
+        unimplemented!()
+    }
+
+    async fn join(&self) {}
+
+    fn shutdown(&self) {}
+}
@@ -1,4 +1,9 @@
-use std::{collections::HashMap, num::NonZeroU32, sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeSet, HashMap},
+    num::NonZeroU32,
+    sync::Arc,
+    time::Duration,
+};
 
 use data_types::{
     Namespace, NamespaceId, NamespaceSchema, PartitionKey, QueryPoolId, Sequence, SequenceNumber,
@@ -19,6 +24,7 @@ use metric::{Attributes, Metric, MetricObserver};
 use mutable_batch_lp::lines_to_batches;
 use object_store::DynObjectStore;
 use observability_deps::tracing::*;
+use parquet_file::serialize::ParquetWriterOptions;
 use test_helpers::{maybe_start_logging, timeout::FutureTimeout};
 use write_buffer::{
     core::WriteBufferReading,
@@ -109,6 +115,10 @@ impl TestContext {
             Arc::clone(&metrics),
             true,
             1,
+            false,
+            ParquetWriterOptions::default(),
+            None,
+            None,
         )
         .await
         .unwrap();
@@ -164,6 +174,10 @@ impl TestContext {
             Arc::clone(&self.metrics),
             true,
             1,
+            false,
+            ParquetWriterOptions::default(),
+            None,
+            None,
         )
         .await
         .unwrap();
@@ -356,6 +370,21 @@ impl TestContext {
         .await;
     }
 
+    /// Change the set of shards the ingester consumes to `shard_indexes`.
+    pub async fn set_shard_indexes(
+        &self,
+        shard_indexes: impl IntoIterator<Item = ShardIndex>,
+    ) -> Result<(), ingester::handler::Error> {
+        self.ingester
+            .set_shard_indexes(shard_indexes.into_iter().collect())
+            .await
+    }
+
+    /// Return the set of shards the ingester is consuming.
+    pub fn shard_indexes(&self) -> BTreeSet<ShardIndex> {
+        self.ingester.shard_indexes()
+    }
+
     /// Submit a query to the ingester's public query interface.
     pub async fn query(
         &self,
@@ -7,6 +7,7 @@ use data_types::{Partition, PartitionKey, SequenceNumber};
 use generated_types::ingester::IngesterQueryRequest;
 use iox_time::{SystemProvider, TimeProvider};
 use metric::{DurationHistogram, U64Counter, U64Gauge};
+use write_summary::ShardProgress;
 
 // Write data to an ingester through the write buffer interface, utilise the
 // progress API to wait for it to become readable, and finally query the data
@@ -419,3 +420,93 @@ async fn test_seek_dropped_offset() {
         .fetch();
     assert!(metric > 0);
 }
+
+// Ensure removing a shard from an ingester at runtime persists the data
+// buffered for it, and that consuming the shard again resumes from the
+// persisted offset.
+#[tokio::test]
+async fn test_shard_reassignment() {
+    let mut ctx = TestContext::new().await;
+
+    let ns = ctx.ensure_namespace("test_namespace", None).await;
+    let partition_key = PartitionKey::from("1970-01-01");
+    let w1 = ctx
+        .write_lp(
+            "test_namespace",
+            "bananas greatness=\"unbounded\" 10",
+            partition_key.clone(),
+            0,
+        )
+        .await;
+    ctx.wait_for_readable(w1).await;
+
+    // Remove the only shard from the ingester.
+    ctx.set_shard_indexes([])
+        .await
+        .expect("removing shard should succeed");
+    assert!(ctx.shard_indexes().is_empty());
+
+    // The buffered data was persisted before the shard was released, and
+    // the ingester no longer reports progress for it.
+    let files = ctx
+        .catalog()
+        .repositories()
+        .await
+        .parquet_files()
+        .list_by_shard_greater_than(ctx.shard_id(), SequenceNumber::new(-1))
+        .await
+        .expect("query failed");
+    assert_eq!(files.len(), 1);
+    assert_eq!(ctx.progress().await, ShardProgress::new());
+    assert_matches!(
+        ctx.query(IngesterQueryRequest {
+            namespace_id: ns.id,
+            table_id: ctx.table_id("test_namespace", "bananas").await,
+            columns: vec![],
+            predicate: None,
+        })
+        .await,
+        Err(ingester::querier_handler::Error::NamespaceNotFound { .. })
+    );
+
+    // Assign the shard back to the ingester and write more data.
+    ctx.set_shard_indexes([TEST_SHARD_INDEX])
+        .await
+        .expect("adding shard should succeed");
+    assert_eq!(
+        ctx.shard_indexes().into_iter().collect::<Vec<_>>(),
+        [TEST_SHARD_INDEX]
+    );
+    let w2 = ctx
+        .write_lp(
+            "test_namespace",
+            "bananas greatness=\"amazing\" 20",
+            partition_key,
+            1,
+        )
+        .await;
+    ctx.wait_for_readable(w2).await;
+
+    // Only the unpersisted write is buffered, the first write was not
+    // replayed.
+    let data = ctx
+        .query(IngesterQueryRequest {
+            namespace_id: ns.id,
+            table_id: ctx.table_id("test_namespace", "bananas").await,
+            columns: vec![],
+            predicate: None,
+        })
+        .await
+        .expect("query should succeed")
+        .into_record_batches()
+        .await;
+
+    let expected = vec![
+        "+-----------+--------------------------------+",
+        "| greatness | time                           |",
+        "+-----------+--------------------------------+",
+        "| amazing   | 1970-01-01T00:00:00.000000020Z |",
+        "+-----------+--------------------------------+",
+    ];
+    assert_batches_sorted_eq!(&expected, &data);
+}
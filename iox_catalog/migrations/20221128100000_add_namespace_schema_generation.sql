@@ -0,0 +1,2 @@
+ALTER TABLE IF EXISTS namespace
+    ADD COLUMN IF NOT EXISTS schema_generation BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS querier_registration (
+    address VARCHAR NOT NULL,
+    query_pool_id BIGINT NOT NULL REFERENCES query_pool (id),
+    last_heartbeat BIGINT NOT NULL,
+    PRIMARY KEY (address)
+);
+
+CREATE INDEX IF NOT EXISTS querier_registration_query_pool_idx ON querier_registration (query_pool_id);
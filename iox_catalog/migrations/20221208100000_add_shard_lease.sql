@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS shard_lease (
+    shard_id BIGINT NOT NULL REFERENCES shard (id),
+    holder VARCHAR NOT NULL,
+    expires_at BIGINT NOT NULL,
+    PRIMARY KEY (shard_id)
+);
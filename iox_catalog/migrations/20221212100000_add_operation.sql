@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS operation (
+    id BIGINT GENERATED ALWAYS AS IDENTITY,
+    kind VARCHAR NOT NULL,
+    description VARCHAR NOT NULL,
+    node VARCHAR NOT NULL,
+    status SMALLINT NOT NULL,
+    created_at BIGINT NOT NULL,
+    completed_at BIGINT,
+    PRIMARY KEY (id)
+);
+
+CREATE INDEX IF NOT EXISTS operation_node_status_idx ON operation (node, status);
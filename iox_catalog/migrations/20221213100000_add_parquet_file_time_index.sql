@@ -0,0 +1,3 @@
+-- Add index for query to select the parquet files of a table within a time range
+CREATE INDEX IF NOT EXISTS parquet_file_table_time_idx ON parquet_file (table_id, max_time, min_time)
+    WHERE to_delete IS NULL;
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS namespace_usage (
+    namespace_id BIGINT NOT NULL REFERENCES namespace (id),
+    rows_written BIGINT NOT NULL DEFAULT 0,
+    bytes_written BIGINT NOT NULL DEFAULT 0,
+    queries_executed BIGINT NOT NULL DEFAULT 0,
+    bytes_scanned BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (namespace_id)
+);
@@ -0,0 +1,14 @@
+-- The inputs of compacted parquet files. There are deliberately no foreign keys to parquet_file,
+-- so that the lineage of a file can be traced back to the ingester-produced files even after the
+-- files in between have been deleted.
+CREATE TABLE IF NOT EXISTS parquet_file_lineage (
+    parquet_file_id BIGINT NOT NULL,
+    compaction_level SMALLINT NOT NULL,
+    input_parquet_file_id BIGINT NOT NULL,
+    input_compaction_level SMALLINT NOT NULL,
+    namespace_id BIGINT NOT NULL REFERENCES namespace (id),
+    table_id BIGINT NOT NULL REFERENCES table_name (id),
+    PRIMARY KEY (parquet_file_id, input_parquet_file_id)
+);
+
+CREATE INDEX IF NOT EXISTS parquet_file_lineage_namespace_idx ON parquet_file_lineage (namespace_id);
@@ -0,0 +1,13 @@
+-- API tokens are stored as the SHA-256 hash of the token, the token itself is only known to the
+-- client it was issued to.
+CREATE TABLE IF NOT EXISTS api_token (
+    id BIGINT GENERATED ALWAYS AS IDENTITY,
+    token_hash VARCHAR NOT NULL,
+    namespace_id BIGINT REFERENCES namespace (id),
+    permission SMALLINT NOT NULL,
+    description VARCHAR NOT NULL,
+    created_at BIGINT NOT NULL,
+    revoked_at BIGINT,
+    PRIMARY KEY (id),
+    CONSTRAINT api_token_hash_unique UNIQUE (token_hash)
+);
@@ -0,0 +1,8 @@
+-- The highest sequence number of the writes of each producer acknowledged by the routers, so that
+-- retries of acknowledged writes are not applied twice.
+CREATE TABLE IF NOT EXISTS producer_sequence (
+    namespace_id BIGINT NOT NULL REFERENCES namespace (id),
+    producer_id VARCHAR NOT NULL,
+    sequence_number BIGINT NOT NULL,
+    PRIMARY KEY (namespace_id, producer_id)
+);
@@ -0,0 +1,7 @@
+-- The time range a query of the namespace covers if it does not restrict the time, and the longest
+-- time range a query may cover, in nanoseconds. NULL means unrestricted.
+ALTER TABLE IF EXISTS namespace
+    ADD COLUMN IF NOT EXISTS default_query_range_ns BIGINT DEFAULT NULL;
+
+ALTER TABLE IF EXISTS namespace
+    ADD COLUMN IF NOT EXISTS max_query_range_ns BIGINT DEFAULT NULL;
@@ -0,0 +1,4 @@
+-- The time a column was created, in nanoseconds since the epoch. NULL for the columns created
+-- before this was recorded.
+ALTER TABLE IF EXISTS column_name
+    ADD COLUMN IF NOT EXISTS created_at BIGINT DEFAULT NULL;
@@ -0,0 +1,5 @@
+-- The time a partition was fully compacted by the compactor after it stopped getting writes, in
+-- nanoseconds since the epoch. NULL while the partition is not cold, and reset to NULL when data
+-- is persisted to it again.
+ALTER TABLE IF EXISTS partition
+    ADD COLUMN IF NOT EXISTS cold_compacted_at BIGINT DEFAULT NULL;
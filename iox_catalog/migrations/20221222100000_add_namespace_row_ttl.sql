@@ -0,0 +1,7 @@
+-- The time in nanoseconds after which a row of the namespace expires, counted from its timestamp,
+-- and the name of the field holding the time at which each row expires. NULL expires no row.
+ALTER TABLE IF EXISTS namespace
+    ADD COLUMN IF NOT EXISTS row_ttl_ns BIGINT DEFAULT NULL;
+
+ALTER TABLE IF EXISTS namespace
+    ADD COLUMN IF NOT EXISTS row_ttl_column VARCHAR DEFAULT NULL;
@@ -0,0 +1,11 @@
+-- The ingesters consuming the shards of a topic, keyed by the address queriers use to reach them,
+-- and the shards assigned to each of them.
+CREATE TABLE IF NOT EXISTS ingester_registration (
+    address VARCHAR NOT NULL,
+    topic_id BIGINT NOT NULL REFERENCES topic (id),
+    shard_indexes INT[] NOT NULL,
+    last_heartbeat BIGINT NOT NULL,
+    PRIMARY KEY (address)
+);
+
+CREATE INDEX IF NOT EXISTS ingester_registration_topic_idx ON ingester_registration (topic_id);
@@ -0,0 +1,4 @@
+-- The template the writes to the namespace are partitioned by, one template part per line. NULL
+-- partitions them by the template the router is configured with.
+ALTER TABLE IF EXISTS namespace
+    ADD COLUMN IF NOT EXISTS partition_template VARCHAR DEFAULT NULL;
@@ -0,0 +1,3 @@
+-- Incremented whenever the lease of the shard changes hands, fencing off a holder that lost it.
+ALTER TABLE IF EXISTS shard_lease
+    ADD COLUMN IF NOT EXISTS epoch BIGINT NOT NULL DEFAULT 0;
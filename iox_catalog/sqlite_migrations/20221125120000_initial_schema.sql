@@ -0,0 +1,146 @@
+-- The complete catalog schema for SQLite.
+--
+-- This mirrors the state of the Postgres schema after all of the migrations in `../migrations`
+-- have been applied. Later changes to the Postgres schema need a matching migration here.
+
+CREATE TABLE IF NOT EXISTS topic (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL,
+    CONSTRAINT topic_name_unique UNIQUE (name)
+);
+
+CREATE TABLE IF NOT EXISTS query_pool (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL,
+    CONSTRAINT query_pool_name_unique UNIQUE (name)
+);
+
+CREATE TABLE IF NOT EXISTS namespace (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR NOT NULL,
+    topic_id INTEGER NOT NULL REFERENCES topic (id),
+    query_pool_id INTEGER NOT NULL REFERENCES query_pool (id),
+    max_tables INTEGER NOT NULL DEFAULT 10000,
+    max_columns_per_table INTEGER NOT NULL DEFAULT 200,
+    retention_period_ns INTEGER DEFAULT NULL,
+    CONSTRAINT namespace_name_unique UNIQUE (name)
+);
+
+CREATE TABLE IF NOT EXISTS table_name (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    namespace_id INTEGER NOT NULL REFERENCES namespace (id),
+    name VARCHAR NOT NULL,
+    CONSTRAINT table_name_unique UNIQUE (namespace_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS table_name_namespace_idx ON table_name (namespace_id);
+
+CREATE TABLE IF NOT EXISTS column_name (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    table_id INTEGER NOT NULL REFERENCES table_name (id),
+    name VARCHAR NOT NULL,
+    column_type SMALLINT NOT NULL,
+    CONSTRAINT column_name_unique UNIQUE (table_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS column_name_table_idx ON column_name (table_id);
+
+CREATE TABLE IF NOT EXISTS shard (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    topic_id INTEGER NOT NULL REFERENCES topic (id),
+    shard_index INTEGER NOT NULL,
+    min_unpersisted_sequence_number INTEGER NOT NULL,
+    CONSTRAINT shard_unique UNIQUE (topic_id, shard_index)
+);
+
+CREATE TABLE IF NOT EXISTS partition (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    shard_id INTEGER NOT NULL REFERENCES shard (id),
+    table_id INTEGER NOT NULL REFERENCES table_name (id),
+    partition_key VARCHAR NOT NULL,
+    -- JSON array of column names
+    sort_key TEXT NOT NULL DEFAULT '[]',
+    persisted_sequence_number INTEGER DEFAULT NULL,
+    to_delete INTEGER DEFAULT NULL,
+    CONSTRAINT partition_key_unique UNIQUE (table_id, partition_key)
+);
+
+CREATE TABLE IF NOT EXISTS parquet_file (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    shard_id INTEGER NOT NULL REFERENCES shard (id),
+    namespace_id INTEGER NOT NULL REFERENCES namespace (id),
+    table_id INTEGER NOT NULL REFERENCES table_name (id),
+    partition_id INTEGER NOT NULL REFERENCES partition (id),
+    object_store_id BLOB NOT NULL,
+    max_sequence_number INTEGER NOT NULL,
+    min_time INTEGER NOT NULL,
+    max_time INTEGER NOT NULL,
+    to_delete INTEGER DEFAULT NULL,
+    file_size_bytes INTEGER NOT NULL,
+    row_count INTEGER NOT NULL,
+    compaction_level SMALLINT NOT NULL,
+    created_at INTEGER NOT NULL,
+    -- JSON array of column IDs
+    column_set TEXT NOT NULL,
+    CONSTRAINT parquet_location_unique UNIQUE (object_store_id)
+);
+
+CREATE INDEX IF NOT EXISTS parquet_file_table_idx ON parquet_file (table_id);
+CREATE INDEX IF NOT EXISTS parquet_file_partition_idx ON parquet_file (partition_id);
+CREATE INDEX IF NOT EXISTS parquet_file_deleted_at_idx ON parquet_file (to_delete);
+CREATE INDEX IF NOT EXISTS parquet_file_shard_compaction_delete_created_idx
+    ON parquet_file (shard_id, compaction_level, to_delete, created_at);
+
+CREATE TABLE IF NOT EXISTS tombstone (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    table_id INTEGER NOT NULL REFERENCES table_name (id),
+    shard_id INTEGER NOT NULL REFERENCES shard (id),
+    sequence_number INTEGER NOT NULL,
+    min_time INTEGER NOT NULL,
+    max_time INTEGER NOT NULL,
+    serialized_predicate TEXT NOT NULL,
+    CONSTRAINT tombstone_unique UNIQUE (table_id, shard_id, sequence_number)
+);
+
+CREATE TABLE IF NOT EXISTS processed_tombstone (
+    tombstone_id INTEGER NOT NULL REFERENCES tombstone (id),
+    parquet_file_id INTEGER NOT NULL REFERENCES parquet_file (id) ON DELETE CASCADE,
+    PRIMARY KEY (tombstone_id, parquet_file_id)
+);
+
+CREATE TABLE IF NOT EXISTS skipped_compactions (
+    partition_id INTEGER REFERENCES partition (id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    skipped_at INTEGER NOT NULL,
+    num_files INTEGER DEFAULT NULL,
+    limit_num_files INTEGER DEFAULT NULL,
+    limit_num_files_first_in_partition INTEGER DEFAULT NULL,
+    estimated_bytes INTEGER DEFAULT NULL,
+    limit_bytes INTEGER DEFAULT NULL,
+    PRIMARY KEY (partition_id)
+);
+
+CREATE TABLE IF NOT EXISTS billing_summary (
+    namespace_id INTEGER NOT NULL REFERENCES namespace (id),
+    total_file_size_bytes INTEGER NOT NULL,
+    PRIMARY KEY (namespace_id)
+);
+
+CREATE TRIGGER IF NOT EXISTS update_billing
+    AFTER INSERT ON parquet_file
+BEGIN
+    INSERT INTO billing_summary (namespace_id, total_file_size_bytes)
+    VALUES (NEW.namespace_id, NEW.file_size_bytes)
+    ON CONFLICT (namespace_id) DO UPDATE
+    SET total_file_size_bytes = billing_summary.total_file_size_bytes + NEW.file_size_bytes
+    WHERE billing_summary.namespace_id = NEW.namespace_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS decrement_summary
+    AFTER UPDATE ON parquet_file
+    WHEN OLD.to_delete IS NULL AND NEW.to_delete IS NOT NULL
+BEGIN
+    UPDATE billing_summary
+    SET total_file_size_bytes = billing_summary.total_file_size_bytes - OLD.file_size_bytes
+    WHERE billing_summary.namespace_id = OLD.namespace_id;
+END;
@@ -0,0 +1,2 @@
+ALTER TABLE namespace
+    ADD COLUMN schema_generation INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS table_shard_pin (
+    table_id INTEGER NOT NULL REFERENCES table_name (id) ON DELETE CASCADE,
+    shard_id INTEGER NOT NULL REFERENCES shard (id),
+    PRIMARY KEY (table_id)
+);
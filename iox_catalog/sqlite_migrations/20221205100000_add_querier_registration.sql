@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS querier_registration (
+    address TEXT NOT NULL,
+    query_pool_id INTEGER NOT NULL REFERENCES query_pool (id),
+    last_heartbeat INTEGER NOT NULL,
+    PRIMARY KEY (address)
+);
+
+CREATE INDEX IF NOT EXISTS querier_registration_query_pool_idx ON querier_registration (query_pool_id);
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS shard_lease (
+    shard_id INTEGER NOT NULL REFERENCES shard (id),
+    holder TEXT NOT NULL,
+    expires_at INTEGER NOT NULL,
+    PRIMARY KEY (shard_id)
+);
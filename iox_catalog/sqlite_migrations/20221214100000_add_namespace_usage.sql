@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS namespace_usage (
+    namespace_id INTEGER NOT NULL REFERENCES namespace (id),
+    rows_written INTEGER NOT NULL DEFAULT 0,
+    bytes_written INTEGER NOT NULL DEFAULT 0,
+    queries_executed INTEGER NOT NULL DEFAULT 0,
+    bytes_scanned INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (namespace_id)
+);
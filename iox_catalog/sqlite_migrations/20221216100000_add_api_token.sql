@@ -0,0 +1,12 @@
+-- API tokens are stored as the SHA-256 hash of the token, the token itself is only known to the
+-- client it was issued to.
+CREATE TABLE IF NOT EXISTS api_token (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    token_hash TEXT NOT NULL,
+    namespace_id INTEGER REFERENCES namespace (id),
+    permission INTEGER NOT NULL,
+    description TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    revoked_at INTEGER,
+    CONSTRAINT api_token_hash_unique UNIQUE (token_hash)
+);
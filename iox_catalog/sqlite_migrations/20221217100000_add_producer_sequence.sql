@@ -0,0 +1,8 @@
+-- The highest sequence number of the writes of each producer acknowledged by the routers, so that
+-- retries of acknowledged writes are not applied twice.
+CREATE TABLE IF NOT EXISTS producer_sequence (
+    namespace_id INTEGER NOT NULL REFERENCES namespace (id),
+    producer_id TEXT NOT NULL,
+    sequence_number INTEGER NOT NULL,
+    PRIMARY KEY (namespace_id, producer_id)
+);
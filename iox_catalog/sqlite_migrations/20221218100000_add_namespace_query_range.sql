@@ -0,0 +1,7 @@
+-- The time range a query of the namespace covers if it does not restrict the time, and the longest
+-- time range a query may cover, in nanoseconds. NULL means unrestricted.
+ALTER TABLE namespace
+    ADD COLUMN default_query_range_ns INTEGER DEFAULT NULL;
+
+ALTER TABLE namespace
+    ADD COLUMN max_query_range_ns INTEGER DEFAULT NULL;
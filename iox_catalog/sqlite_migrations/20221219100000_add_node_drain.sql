@@ -0,0 +1,8 @@
+-- The drain state of ingesters, keyed by the address queriers use to reach them. Ingesters that
+-- have no row are active.
+CREATE TABLE IF NOT EXISTS node_drain (
+    address TEXT NOT NULL,
+    state INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (address)
+);
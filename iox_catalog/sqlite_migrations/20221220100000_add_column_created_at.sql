@@ -0,0 +1,4 @@
+-- The time a column was created, in nanoseconds since the epoch. NULL for the columns created
+-- before this was recorded.
+ALTER TABLE column_name
+    ADD COLUMN created_at INTEGER DEFAULT NULL;
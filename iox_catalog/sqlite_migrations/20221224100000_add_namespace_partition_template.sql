@@ -0,0 +1,4 @@
+-- The template the writes to the namespace are partitioned by, one template part per line. NULL
+-- partitions them by the template the router is configured with.
+ALTER TABLE namespace
+    ADD COLUMN partition_template TEXT DEFAULT NULL;
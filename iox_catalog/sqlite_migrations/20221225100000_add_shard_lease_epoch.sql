@@ -0,0 +1,3 @@
+-- Incremented whenever the lease of the shard changes hands, fencing off a holder that lost it.
+ALTER TABLE shard_lease
+    ADD COLUMN epoch INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,400 @@
+//! Authorization of requests by API tokens.
+//!
+//! API tokens allow a [permission](ApiTokenPermission) on one or all namespaces. Only the
+//! SHA-256 hash of a token is stored in the catalog, the token itself is returned once, when it
+//! is created.
+//!
+//! Routers and queriers check the tokens of requests with an [`Authorizer`], which caches the
+//! grant of each token for a configurable time, so that the catalog is not read on every
+//! request. A revoked token is therefore rejected once its cached grant expires. Only the grants
+//! of valid tokens are cached, so that requests with made up tokens can not grow the cache.
+//!
+//! The description of a token identifies its owner, e.g. to the read policies of queriers.
+
+use crate::interface::{Catalog, Error};
+use data_types::{ApiToken, ApiTokenPermission};
+use iox_time::{Time, TimeProvider};
+use parking_lot::Mutex;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fmt::Write, sync::Arc, time::Duration};
+use thiserror::Error;
+
+/// Number of random bytes of a generated token.
+const TOKEN_BYTES: usize = 32;
+
+/// Reasons for rejecting a request.
+#[derive(Debug, Error)]
+#[allow(missing_docs)]
+pub enum AuthzError {
+    #[error("missing API token")]
+    MissingToken,
+
+    #[error("invalid API token")]
+    InvalidToken,
+
+    #[error("API token does not allow {permission} access to {}", .namespace.as_deref().unwrap_or("all namespaces"))]
+    PermissionDenied {
+        namespace: Option<String>,
+        permission: ApiTokenPermission,
+    },
+
+    #[error("failed to look up API token: {0}")]
+    Catalog(#[from] Error),
+}
+
+/// Generate a new random API token.
+pub fn generate_token() -> String {
+    let mut bytes = [0; TOKEN_BYTES];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex(&bytes)
+}
+
+/// The hash of `token`, as stored in the catalog.
+pub fn hash_token(token: &str) -> String {
+    hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        write!(s, "{b:02x}").unwrap();
+        s
+    })
+}
+
+/// Extract the token from the value of an `Authorization` header, accepting both the
+/// `Bearer <token>` and the InfluxDB `Token <token>` schemes.
+pub fn token_from_header(value: &str) -> Option<&str> {
+    value
+        .strip_prefix("Bearer ")
+        .or_else(|| value.strip_prefix("Token "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
+
+/// What a valid token allows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Grant {
+    /// The name of the namespace the token is scoped to, or `None` for all namespaces.
+    namespace: Option<String>,
+    permission: ApiTokenPermission,
+    /// The description of the token, identifying its owner.
+    description: String,
+}
+
+impl Grant {
+    fn allows(&self, namespace: Option<&str>, permission: ApiTokenPermission) -> bool {
+        let namespace_allowed = match (&self.namespace, namespace) {
+            (None, _) => true,
+            (Some(granted), Some(namespace)) => granted == namespace,
+            (Some(_), None) => false,
+        };
+        namespace_allowed && self.permission >= permission
+    }
+}
+
+/// Authorizes requests by their API token, caching the grants of tokens read from the catalog.
+#[derive(Debug)]
+pub struct Authorizer {
+    catalog: Arc<dyn Catalog>,
+    time_provider: Arc<dyn TimeProvider>,
+
+    /// How long a grant is cached for.
+    ttl: Duration,
+
+    /// The grants of valid tokens by token hash, with the time they were read.
+    cache: Mutex<HashMap<String, (Time, Grant)>>,
+}
+
+impl Authorizer {
+    /// Authorize requests with the tokens in `catalog`, caching their grants for `ttl`.
+    pub fn new(
+        catalog: Arc<dyn Catalog>,
+        time_provider: Arc<dyn TimeProvider>,
+        ttl: Duration,
+    ) -> Self {
+        Self {
+            catalog,
+            time_provider,
+            ttl,
+            cache: Default::default(),
+        }
+    }
+
+    /// Return an error unless `token` allows `permission` on `namespace`.
+    ///
+    /// Requests that do not target a namespace, such as the management of tokens, pass `None`
+    /// and are only allowed by tokens scoped to all namespaces.
+    pub async fn authorize(
+        &self,
+        token: Option<&str>,
+        namespace: Option<&str>,
+        permission: ApiTokenPermission,
+    ) -> Result<(), AuthzError> {
+        let grant = self.valid_grant(token).await?;
+
+        if !grant.allows(namespace, permission) {
+            return Err(AuthzError::PermissionDenied {
+                namespace: namespace.map(ToString::to_string),
+                permission,
+            });
+        }
+        Ok(())
+    }
+
+    /// The identity of the owner of `token`, i.e. the description of the token.
+    ///
+    /// Unlike an identity claimed by a request, this identity can not be spoofed without the
+    /// token, so it is used for row-level read policies when authorization is enabled.
+    pub async fn identity(&self, token: Option<&str>) -> Result<String, AuthzError> {
+        Ok(self.valid_grant(token).await?.description)
+    }
+
+    /// The grant of `token`, or an error if it is missing or invalid.
+    async fn valid_grant(&self, token: Option<&str>) -> Result<Grant, AuthzError> {
+        let token = token.ok_or(AuthzError::MissingToken)?;
+        self.grant(&hash_token(token))
+            .await?
+            .ok_or(AuthzError::InvalidToken)
+    }
+
+    /// The grant of the token with `hash`, or `None` if the token is invalid.
+    ///
+    /// Invalid tokens are not cached: their hashes are chosen by the requests, so caching them
+    /// would allow growing the cache without bound.
+    async fn grant(&self, hash: &str) -> Result<Option<Grant>, AuthzError> {
+        let now = self.time_provider.now();
+        let is_fresh = |read_at: Time| {
+            now.checked_duration_since(read_at)
+                .map_or(true, |age| age < self.ttl)
+        };
+
+        if let Some((read_at, grant)) = self.cache.lock().get(hash) {
+            if is_fresh(*read_at) {
+                return Ok(Some(grant.clone()));
+            }
+        }
+
+        let grant = self.load(hash).await?;
+
+        let mut cache = self.cache.lock();
+        cache.retain(|_, (read_at, _)| is_fresh(*read_at));
+        match &grant {
+            Some(grant) => {
+                cache.insert(hash.to_string(), (now, grant.clone()));
+            }
+            None => {
+                cache.remove(hash);
+            }
+        }
+
+        Ok(grant)
+    }
+
+    async fn load(&self, hash: &str) -> Result<Option<Grant>, Error> {
+        let mut repos = self.catalog.repositories().await;
+
+        let token = match repos.api_tokens().get_by_hash(hash).await? {
+            Some(token) if !token.is_revoked() => token,
+            _ => return Ok(None),
+        };
+        let ApiToken {
+            namespace_id,
+            permission,
+            description,
+            ..
+        } = token;
+
+        let namespace = match namespace_id {
+            Some(id) => match repos.namespaces().get_by_id(id).await? {
+                Some(namespace) => Some(namespace.name),
+                // the namespace no longer exists
+                None => return Ok(None),
+            },
+            None => None,
+        };
+
+        Ok(Some(Grant {
+            namespace,
+            permission,
+            description,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::MemCatalog;
+    use assert_matches::assert_matches;
+    use data_types::Timestamp;
+    use iox_time::MockProvider;
+
+    #[test]
+    fn test_token_from_header() {
+        assert_eq!(token_from_header("Bearer abc"), Some("abc"));
+        assert_eq!(token_from_header("Token abc"), Some("abc"));
+        assert_eq!(token_from_header("Basic abc"), None);
+        assert_eq!(token_from_header("Bearer "), None);
+    }
+
+    #[test]
+    fn test_generate_token() {
+        let token = generate_token();
+        assert_eq!(token.len(), 2 * TOKEN_BYTES);
+        assert_ne!(token, generate_token());
+
+        let hash = hash_token(&token);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, hash_token(&token));
+        assert_eq!(
+            hash_token("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_authorize() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+        let time = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let (writer, admin) = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("foo").await.unwrap();
+            let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+            let namespace = repos
+                .namespaces()
+                .create("ns", None, topic.id, pool.id)
+                .await
+                .unwrap();
+
+            let writer = generate_token();
+            let admin = generate_token();
+            repos
+                .api_tokens()
+                .create(
+                    &hash_token(&writer),
+                    Some(namespace.id),
+                    ApiTokenPermission::Write,
+                    "writer",
+                    Timestamp::new(0),
+                )
+                .await
+                .unwrap();
+            repos
+                .api_tokens()
+                .create(
+                    &hash_token(&admin),
+                    None,
+                    ApiTokenPermission::Admin,
+                    "admin",
+                    Timestamp::new(0),
+                )
+                .await
+                .unwrap();
+            (writer, admin)
+        };
+
+        let authz = Authorizer::new(
+            Arc::clone(&catalog),
+            Arc::clone(&time) as _,
+            Duration::from_secs(10),
+        );
+
+        // A write token allows reading and writing its namespace only.
+        for permission in [ApiTokenPermission::Read, ApiTokenPermission::Write] {
+            authz
+                .authorize(Some(&writer), Some("ns"), permission)
+                .await
+                .unwrap();
+        }
+        assert_matches!(
+            authz
+                .authorize(Some(&writer), Some("other"), ApiTokenPermission::Read)
+                .await,
+            Err(AuthzError::PermissionDenied { .. })
+        );
+        assert_matches!(
+            authz
+                .authorize(Some(&writer), None, ApiTokenPermission::Admin)
+                .await,
+            Err(AuthzError::PermissionDenied { .. })
+        );
+
+        // An admin token allows everything.
+        authz
+            .authorize(Some(&admin), Some("other"), ApiTokenPermission::Write)
+            .await
+            .unwrap();
+        authz
+            .authorize(Some(&admin), None, ApiTokenPermission::Admin)
+            .await
+            .unwrap();
+
+        assert_matches!(
+            authz
+                .authorize(None, Some("ns"), ApiTokenPermission::Read)
+                .await,
+            Err(AuthzError::MissingToken)
+        );
+        assert_matches!(
+            authz
+                .authorize(Some("bananas"), Some("ns"), ApiTokenPermission::Read)
+                .await,
+            Err(AuthzError::InvalidToken)
+        );
+        assert_matches!(
+            authz.identity(Some("bananas")).await,
+            Err(AuthzError::InvalidToken)
+        );
+        assert_eq!(authz.identity(Some(&writer)).await.unwrap(), "writer");
+
+        // Invalid tokens are not cached, only the grants of the two valid tokens are.
+        assert_eq!(authz.cache.lock().len(), 2);
+        {
+            let mut repos = catalog.repositories().await;
+            repos
+                .api_tokens()
+                .create(
+                    &hash_token("bananas"),
+                    None,
+                    ApiTokenPermission::Read,
+                    "bananas",
+                    Timestamp::new(0),
+                )
+                .await
+                .unwrap();
+        }
+        authz
+            .authorize(Some("bananas"), Some("ns"), ApiTokenPermission::Read)
+            .await
+            .unwrap();
+
+        // Revoked tokens are rejected once their cached grant expires.
+        {
+            let mut repos = catalog.repositories().await;
+            let token = repos
+                .api_tokens()
+                .get_by_hash(&hash_token(&writer))
+                .await
+                .unwrap()
+                .unwrap();
+            repos
+                .api_tokens()
+                .revoke(token.id, Timestamp::new(1))
+                .await
+                .unwrap();
+        }
+        authz
+            .authorize(Some(&writer), Some("ns"), ApiTokenPermission::Write)
+            .await
+            .unwrap();
+        time.inc(Duration::from_secs(10));
+        assert_matches!(
+            authz
+                .authorize(Some(&writer), Some("ns"), ApiTokenPermission::Write)
+                .await,
+            Err(AuthzError::InvalidToken)
+        );
+    }
+}
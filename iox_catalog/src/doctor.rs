@@ -0,0 +1,494 @@
+//! Checks of the invariants of the catalog that its schema does not enforce.
+//!
+//! [`check`] reads the catalog and reports every [`Issue`] it finds in a [`Report`], which can
+//! be written out as JSON. Some issues can be fixed by [`repair`]; the others are left to an
+//! operator, as there is no way to tell which of the conflicting records is the right one.
+
+use crate::interface::{Catalog, Result};
+use data_types::{
+    ColumnType, ParquetFileId, SequenceNumber, TableId, Tombstone, TombstoneId, TopicId,
+};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt::Display,
+};
+
+/// The name of the column holding the timestamps of a table.
+const TIME_COLUMN_NAME: &str = "time";
+
+/// A violated invariant of the catalog.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Issue {
+    /// A parquet file references a partition that does not exist.
+    OrphanParquetFile {
+        /// The parquet file.
+        parquet_file_id: i64,
+        /// The table of the parquet file.
+        table_id: i64,
+        /// The missing partition.
+        partition_id: i64,
+    },
+
+    /// A table has several columns of the same name with different types.
+    ConflictingColumnTypes {
+        /// The table of the columns.
+        table_id: i64,
+        /// The name of the columns.
+        column_name: String,
+        /// The types of the columns.
+        column_types: Vec<String>,
+    },
+
+    /// The time column of a table does not have the time type, or another column does.
+    InvalidTimeColumn {
+        /// The table of the column.
+        table_id: i64,
+        /// The column.
+        column_id: i64,
+        /// The name of the column.
+        column_name: String,
+        /// The type of the column.
+        column_type: String,
+    },
+
+    /// A shard references a topic that does not exist.
+    ShardWithoutTopic {
+        /// The shard.
+        shard_id: i64,
+        /// The missing topic.
+        topic_id: i64,
+    },
+
+    /// A tombstone references a table or shard that does not exist.
+    DanglingTombstone {
+        /// The tombstone.
+        tombstone_id: i64,
+        /// The table of the tombstone.
+        table_id: i64,
+        /// The shard of the tombstone.
+        shard_id: i64,
+    },
+}
+
+impl Issue {
+    /// Whether [`repair`] fixes this issue.
+    ///
+    /// Orphan parquet files are flagged for deletion, and dangling tombstones removed.
+    pub fn is_repairable(&self) -> bool {
+        matches!(
+            self,
+            Self::OrphanParquetFile { .. } | Self::DanglingTombstone { .. }
+        )
+    }
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OrphanParquetFile {
+                parquet_file_id,
+                table_id,
+                partition_id,
+            } => write!(
+                f,
+                "parquet file {parquet_file_id} of table {table_id} references missing \
+                 partition {partition_id}"
+            ),
+            Self::ConflictingColumnTypes {
+                table_id,
+                column_name,
+                column_types,
+            } => write!(
+                f,
+                "column {column_name} of table {table_id} has conflicting types {}",
+                column_types.join(", ")
+            ),
+            Self::InvalidTimeColumn {
+                table_id,
+                column_id,
+                column_name,
+                column_type,
+            } => write!(
+                f,
+                "column {column_name} ({column_id}) of table {table_id} has invalid type \
+                 {column_type}"
+            ),
+            Self::ShardWithoutTopic { shard_id, topic_id } => {
+                write!(f, "shard {shard_id} references missing topic {topic_id}")
+            }
+            Self::DanglingTombstone {
+                tombstone_id,
+                table_id,
+                shard_id,
+            } => write!(
+                f,
+                "tombstone {tombstone_id} references missing table {table_id} or shard \
+                 {shard_id}"
+            ),
+        }
+    }
+}
+
+/// The number of records of each kind read by [`check`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[allow(missing_docs)]
+pub struct Checked {
+    pub topics: usize,
+    pub shards: usize,
+    pub tables: usize,
+    pub columns: usize,
+    pub partitions: usize,
+    pub parquet_files: usize,
+    pub tombstones: usize,
+}
+
+/// The result of [`check`], and of [`repair`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    /// The number of records checked.
+    pub checked: Checked,
+
+    /// The issues found that are not repaired.
+    pub issues: Vec<Issue>,
+
+    /// The issues found that were repaired.
+    pub repaired: Vec<Issue>,
+}
+
+impl Report {
+    /// Whether no issues are left unrepaired.
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Check the invariants of `catalog`.
+///
+/// Only parquet files that are not flagged for deletion are checked.
+pub async fn check(catalog: &dyn Catalog) -> Result<Report> {
+    let mut repos = catalog.repositories().await;
+    let mut report = Report::default();
+
+    let topics = repos.topics().list().await?;
+    let shards = repos.shards().list().await?;
+    let tables = repos.tables().list().await?;
+    let columns = repos.columns().list().await?;
+    report.checked.topics = topics.len();
+    report.checked.shards = shards.len();
+    report.checked.tables = tables.len();
+    report.checked.columns = columns.len();
+
+    let topic_ids: HashSet<TopicId> = topics.iter().map(|t| t.id).collect();
+    for shard in &shards {
+        if !topic_ids.contains(&shard.topic_id) {
+            report.issues.push(Issue::ShardWithoutTopic {
+                shard_id: shard.id.get(),
+                topic_id: shard.topic_id.get(),
+            });
+        }
+    }
+
+    let mut column_types: BTreeMap<(TableId, &str), BTreeSet<ColumnType>> = BTreeMap::new();
+    for column in &columns {
+        column_types
+            .entry((column.table_id, column.name.as_str()))
+            .or_default()
+            .insert(column.column_type);
+
+        if (column.name == TIME_COLUMN_NAME) != (column.column_type == ColumnType::Time) {
+            report.issues.push(Issue::InvalidTimeColumn {
+                table_id: column.table_id.get(),
+                column_id: column.id.get(),
+                column_name: column.name.clone(),
+                column_type: column.column_type.to_string(),
+            });
+        }
+    }
+    for ((table_id, column_name), types) in column_types {
+        if types.len() > 1 {
+            report.issues.push(Issue::ConflictingColumnTypes {
+                table_id: table_id.get(),
+                column_name: column_name.to_string(),
+                column_types: types.iter().map(ToString::to_string).collect(),
+            });
+        }
+    }
+
+    for table in &tables {
+        let partitions = repos.partitions().list_by_table_id(table.id).await?;
+        let files = repos
+            .parquet_files()
+            .list_by_table_not_to_delete(table.id)
+            .await?;
+        report.checked.partitions += partitions.len();
+        report.checked.parquet_files += files.len();
+
+        let partition_ids: HashSet<_> = partitions.iter().map(|p| p.id).collect();
+        for file in files {
+            if !partition_ids.contains(&file.partition_id) {
+                report.issues.push(Issue::OrphanParquetFile {
+                    parquet_file_id: file.id.get(),
+                    table_id: file.table_id.get(),
+                    partition_id: file.partition_id.get(),
+                });
+            }
+        }
+    }
+
+    // Tombstones are listed both by table and by shard, to find those referencing a missing
+    // table as well as those referencing a missing shard.
+    let mut tombstones: BTreeMap<TombstoneId, Tombstone> = BTreeMap::new();
+    for table in &tables {
+        for tombstone in repos.tombstones().list_by_table(table.id).await? {
+            tombstones.insert(tombstone.id, tombstone);
+        }
+    }
+    for shard in &shards {
+        let by_shard = repos
+            .tombstones()
+            .list_tombstones_by_shard_greater_than(shard.id, SequenceNumber::new(-1))
+            .await?;
+        for tombstone in by_shard {
+            tombstones.insert(tombstone.id, tombstone);
+        }
+    }
+    report.checked.tombstones = tombstones.len();
+
+    let table_ids: HashSet<_> = tables.iter().map(|t| t.id).collect();
+    let shard_ids: HashSet<_> = shards.iter().map(|s| s.id).collect();
+    for tombstone in tombstones.values() {
+        if !table_ids.contains(&tombstone.table_id) || !shard_ids.contains(&tombstone.shard_id) {
+            report.issues.push(Issue::DanglingTombstone {
+                tombstone_id: tombstone.id.get(),
+                table_id: tombstone.table_id.get(),
+                shard_id: tombstone.shard_id.get(),
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+/// Repair the [repairable](Issue::is_repairable) issues of `report`, moving them to
+/// [`Report::repaired`].
+pub async fn repair(catalog: &dyn Catalog, report: &mut Report) -> Result<()> {
+    let (repairable, issues) = std::mem::take(&mut report.issues)
+        .into_iter()
+        .partition::<Vec<_>, _>(Issue::is_repairable);
+    report.issues = issues;
+
+    let mut repos = catalog.repositories().await;
+
+    let tombstone_ids: Vec<_> = repairable
+        .iter()
+        .filter_map(|issue| match issue {
+            Issue::DanglingTombstone { tombstone_id, .. } => Some(TombstoneId::new(*tombstone_id)),
+            _ => None,
+        })
+        .collect();
+    if !tombstone_ids.is_empty() {
+        if let Err(e) = repos.tombstones().remove(&tombstone_ids).await {
+            report.issues.extend(repairable);
+            return Err(e);
+        }
+    }
+
+    for issue in repairable {
+        if let Issue::OrphanParquetFile {
+            parquet_file_id, ..
+        } = issue
+        {
+            if let Err(e) = repos
+                .parquet_files()
+                .flag_for_delete(ParquetFileId::new(parquet_file_id))
+                .await
+            {
+                report.issues.push(issue);
+                return Err(e);
+            }
+        }
+        report.repaired.push(issue);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::MemCatalog;
+    use data_types::{
+        ColumnId, ColumnSet, CompactionLevel, ParquetFileParams, PartitionId, ShardIndex,
+        Timestamp, TopicMetadata,
+    };
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_check_and_repair() {
+        let catalog = MemCatalog::new(Arc::new(metric::Registry::default()));
+        let mut repos = catalog.repositories().await;
+
+        let topic = repos.topics().create_or_get("iox-shared").await.unwrap();
+        let pool = repos
+            .query_pools()
+            .create_or_get("iox-shared")
+            .await
+            .unwrap();
+        let shard = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(1))
+            .await
+            .unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("ns", None, topic.id, pool.id)
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("cpu", namespace.id)
+            .await
+            .unwrap();
+        repos
+            .columns()
+            .create_or_get("time", table.id, ColumnType::Time)
+            .await
+            .unwrap();
+        let partition = repos
+            .partitions()
+            .create_or_get("2022-01-01".into(), shard.id, table.id)
+            .await
+            .unwrap();
+        let file_params = ParquetFileParams {
+            shard_id: shard.id,
+            namespace_id: namespace.id,
+            table_id: table.id,
+            partition_id: partition.id,
+            object_store_id: Uuid::new_v4(),
+            max_sequence_number: SequenceNumber::new(1),
+            min_time: Timestamp::new(1),
+            max_time: Timestamp::new(10),
+            file_size_bytes: 1337,
+            row_count: 0,
+            compaction_level: CompactionLevel::Initial,
+            created_at: Timestamp::new(1),
+            column_set: ColumnSet::new([ColumnId::new(1)]),
+        };
+        repos
+            .parquet_files()
+            .create(file_params.clone())
+            .await
+            .unwrap();
+        repos
+            .tombstones()
+            .create_or_get(
+                table.id,
+                shard.id,
+                SequenceNumber::new(1),
+                Timestamp::new(1),
+                Timestamp::new(10),
+                "",
+            )
+            .await
+            .unwrap();
+        drop(repos);
+
+        let report = check(&catalog).await.unwrap();
+        assert!(report.is_healthy(), "{report:?}");
+        assert_eq!(
+            report.checked,
+            Checked {
+                topics: 1,
+                shards: 1,
+                tables: 1,
+                columns: 1,
+                partitions: 1,
+                parquet_files: 1,
+                tombstones: 1,
+            }
+        );
+
+        // The in-memory catalog does not enforce foreign keys, so it can be made inconsistent.
+        let mut repos = catalog.repositories().await;
+        let orphan = repos
+            .parquet_files()
+            .create(ParquetFileParams {
+                partition_id: PartitionId::new(42),
+                object_store_id: Uuid::new_v4(),
+                ..file_params
+            })
+            .await
+            .unwrap();
+        repos
+            .columns()
+            .create_or_get("ts", table.id, ColumnType::Time)
+            .await
+            .unwrap();
+        let shard_without_topic = repos
+            .shards()
+            .create_or_get(
+                &TopicMetadata {
+                    id: TopicId::new(42),
+                    name: "gone".to_string(),
+                },
+                ShardIndex::new(1),
+            )
+            .await
+            .unwrap();
+        let tombstone = repos
+            .tombstones()
+            .create_or_get(
+                TableId::new(42),
+                shard.id,
+                SequenceNumber::new(2),
+                Timestamp::new(1),
+                Timestamp::new(10),
+                "",
+            )
+            .await
+            .unwrap();
+        drop(repos);
+
+        let mut report = check(&catalog).await.unwrap();
+        let orphan_issue = Issue::OrphanParquetFile {
+            parquet_file_id: orphan.id.get(),
+            table_id: table.id.get(),
+            partition_id: 42,
+        };
+        let tombstone_issue = Issue::DanglingTombstone {
+            tombstone_id: tombstone.id.get(),
+            table_id: 42,
+            shard_id: shard.id.get(),
+        };
+        let time_issue = Issue::InvalidTimeColumn {
+            table_id: table.id.get(),
+            column_id: 2,
+            column_name: "ts".to_string(),
+            column_type: "time".to_string(),
+        };
+        let shard_issue = Issue::ShardWithoutTopic {
+            shard_id: shard_without_topic.id.get(),
+            topic_id: 42,
+        };
+        assert_eq!(
+            report.issues,
+            vec![
+                shard_issue.clone(),
+                time_issue.clone(),
+                orphan_issue.clone(),
+                tombstone_issue.clone()
+            ]
+        );
+
+        repair(&catalog, &mut report).await.unwrap();
+        assert_eq!(report.issues, vec![shard_issue.clone(), time_issue.clone()]);
+        assert_eq!(report.repaired, vec![orphan_issue, tombstone_issue]);
+
+        let report = check(&catalog).await.unwrap();
+        assert_eq!(report.issues, vec![shard_issue, time_issue]);
+        assert!(report.repaired.is_empty());
+    }
+}
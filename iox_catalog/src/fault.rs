@@ -0,0 +1,559 @@
+//! Fault injection for catalog implementations, used to exercise the recovery
+//! paths of catalog users in tests.
+
+use crate::interface::{
+    sealed::TransactionFinalize, ApiTokenRepo, Catalog, ColumnRepo, Error, MigrationStatus,
+    NamespaceRepo, NodeDrainRepo, OperationRepo, ParquetFileRepo, PartitionRepo,
+    ProcessedTombstoneRepo, QueryPoolRepo, RepoCollection, Result, ShardRepo, TableRepo,
+    TombstoneRepo, TopicMetadataRepo, Transaction,
+};
+use async_trait::async_trait;
+use data_types::{
+    ApiToken, ApiTokenId, ApiTokenPermission, Column, ColumnType, ColumnTypeCount, CompactionLevel,
+    DrainState, IngesterRegistration, Namespace, NamespaceId, NamespaceUsage, NodeDrain, Operation,
+    OperationId, OperationStatus, ParquetFile, ParquetFileId, ParquetFileLineage,
+    ParquetFileParams, Partition, PartitionId, PartitionKey, PartitionParam, PartitionTemplate,
+    ProcessedTombstone, QuerierRegistration, QueryPool, QueryPoolId, SequenceNumber, Shard,
+    ShardId, ShardIndex, ShardLease, SkippedCompaction, Table, TableId, TablePartition, Timestamp,
+    Tombstone, TombstoneId, TopicId, TopicMetadata,
+};
+use iox_time::TimeProvider;
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use uuid::Uuid;
+
+/// The faults a [`FaultInjectingCatalog`] injects into catalog operations.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FaultConfig {
+    error_rate: f64,
+    latency: Option<Duration>,
+    seed: Option<u64>,
+}
+
+impl FaultConfig {
+    /// Fail each operation with probability `error_rate`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `[0, 1]`.
+    pub fn with_error_rate(self, error_rate: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&error_rate),
+            "fault error rate must be within [0, 1]"
+        );
+        Self { error_rate, ..self }
+    }
+
+    /// Delay each operation by `latency` before it is executed.
+    pub fn with_latency(self, latency: Duration) -> Self {
+        Self {
+            latency: Some(latency),
+            ..self
+        }
+    }
+
+    /// Seed the random number generator deciding which operations fail, making
+    /// the sequence of injected errors reproducible.
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Returns true if this config injects any faults.
+    pub fn is_enabled(&self) -> bool {
+        self.error_rate > 0.0 || self.latency.is_some()
+    }
+}
+
+/// Shared fault injection state of a [`FaultInjectingCatalog`] and the
+/// repositories it hands out.
+#[derive(Debug)]
+struct Faults {
+    config: FaultConfig,
+    rng: Mutex<StdRng>,
+}
+
+impl Faults {
+    fn new(config: FaultConfig) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        Self {
+            config,
+            rng: Mutex::new(rng),
+        }
+    }
+
+    /// Apply the configured latency, then decide whether `operation` fails.
+    async fn inject(&self, operation: &'static str) -> Result<()> {
+        if let Some(latency) = self.config.latency {
+            tokio::time::sleep(latency).await;
+        }
+
+        if self.rng.lock().gen_bool(self.config.error_rate) {
+            return Err(Error::InjectedFault { operation });
+        }
+
+        Ok(())
+    }
+}
+
+/// A [`Catalog`] decorator that injects latency and errors into the operations
+/// of the wrapped catalog.
+///
+/// Faults are injected into every repository operation (both inside and
+/// outside of transactions), when starting a transaction and when committing
+/// one. An injected error is returned before the operation reaches the inner
+/// catalog, so a failed commit leaves the transaction uncommitted.
+///
+/// Schema setup and migrations are passed through unchanged.
+#[derive(Debug)]
+pub struct FaultInjectingCatalog {
+    inner: Arc<dyn Catalog>,
+    faults: Arc<Faults>,
+}
+
+impl FaultInjectingCatalog {
+    /// Wrap `inner`, injecting the faults described by `config`.
+    pub fn new(inner: Arc<dyn Catalog>, config: FaultConfig) -> Self {
+        Self {
+            inner,
+            faults: Arc::new(Faults::new(config)),
+        }
+    }
+}
+
+#[async_trait]
+impl Catalog for FaultInjectingCatalog {
+    async fn setup(&self) -> Result<(), Error> {
+        self.inner.setup().await
+    }
+
+    async fn migration_status(&self) -> Result<MigrationStatus, Error> {
+        self.inner.migration_status().await
+    }
+
+    async fn migrate(&self, target_version: Option<i64>) -> Result<(), Error> {
+        self.inner.migrate(target_version).await
+    }
+
+    async fn start_transaction(&self) -> Result<Box<dyn Transaction>, Error> {
+        self.faults.inject("txn_start").await?;
+        let inner = self.inner.start_transaction().await?;
+        Ok(Box::new(FaultInjectingRepos {
+            inner,
+            faults: Arc::clone(&self.faults),
+        }))
+    }
+
+    async fn repositories(&self) -> Box<dyn RepoCollection> {
+        Box::new(FaultInjectingRepos {
+            inner: self.inner.repositories().await,
+            faults: Arc::clone(&self.faults),
+        })
+    }
+
+    fn metrics(&self) -> Arc<metric::Registry> {
+        self.inner.metrics()
+    }
+
+    fn time_provider(&self) -> Arc<dyn TimeProvider> {
+        self.inner.time_provider()
+    }
+
+    async fn shutdown(&self) -> Result<(), Error> {
+        self.inner.shutdown().await
+    }
+}
+
+/// The repositories (or transaction) handed out by a [`FaultInjectingCatalog`].
+#[derive(Debug)]
+struct FaultInjectingRepos<T: ?Sized> {
+    inner: Box<T>,
+    faults: Arc<Faults>,
+}
+
+impl<T> RepoCollection for FaultInjectingRepos<T>
+where
+    T: RepoCollection + ?Sized,
+{
+    fn topics(&mut self) -> &mut dyn TopicMetadataRepo {
+        self
+    }
+
+    fn query_pools(&mut self) -> &mut dyn QueryPoolRepo {
+        self
+    }
+
+    fn namespaces(&mut self) -> &mut dyn NamespaceRepo {
+        self
+    }
+
+    fn tables(&mut self) -> &mut dyn TableRepo {
+        self
+    }
+
+    fn columns(&mut self) -> &mut dyn ColumnRepo {
+        self
+    }
+
+    fn shards(&mut self) -> &mut dyn ShardRepo {
+        self
+    }
+
+    fn partitions(&mut self) -> &mut dyn PartitionRepo {
+        self
+    }
+
+    fn tombstones(&mut self) -> &mut dyn TombstoneRepo {
+        self
+    }
+
+    fn parquet_files(&mut self) -> &mut dyn ParquetFileRepo {
+        self
+    }
+
+    fn processed_tombstones(&mut self) -> &mut dyn ProcessedTombstoneRepo {
+        self
+    }
+
+    fn operations(&mut self) -> &mut dyn OperationRepo {
+        self
+    }
+
+    fn api_tokens(&mut self) -> &mut dyn ApiTokenRepo {
+        self
+    }
+
+    fn node_drains(&mut self) -> &mut dyn NodeDrainRepo {
+        self
+    }
+}
+
+#[async_trait]
+impl TransactionFinalize for FaultInjectingRepos<dyn Transaction> {
+    async fn commit_inplace(&mut self) -> Result<(), Error> {
+        self.faults.inject("txn_commit").await?;
+        self.inner.commit_inplace().await
+    }
+
+    async fn abort_inplace(&mut self) -> Result<(), Error> {
+        self.inner.abort_inplace().await
+    }
+}
+
+/// Emit a trait impl for `impl_trait` that injects faults before delegating
+/// calls to the `repo` of the inner repository collection.
+///
+/// Like the metrics `decorate!()` macro, all methods of the trait MUST be
+/// listed or the impl will not compile.
+macro_rules! decorate {
+    (
+        impl_trait = $trait:ident,
+        repo = $repo:ident,
+        methods = [$(
+            $op:literal = $method:ident(
+                &mut self $(,)?
+                $($arg:ident : $t:ty),*
+            ) -> Result<$out:ty>;
+        )+]
+    ) => {
+        #[async_trait]
+        impl<T> $trait for FaultInjectingRepos<T>
+        where
+            T: RepoCollection + ?Sized,
+        {
+            $(
+                async fn $method(&mut self, $($arg : $t),*) -> Result<$out> {
+                    self.faults.inject($op).await?;
+                    self.inner.$repo().$method($($arg),*).await
+                }
+            )+
+        }
+    };
+}
+
+decorate!(
+    impl_trait = TopicMetadataRepo,
+    repo = topics,
+    methods = [
+        "topic_create_or_get" = create_or_get(&mut self, name: &str) -> Result<TopicMetadata>;
+        "topic_get_by_name" = get_by_name(&mut self, name: &str) -> Result<Option<TopicMetadata>>;
+        "topic_list" = list(&mut self) -> Result<Vec<TopicMetadata>>;
+    ]
+);
+
+decorate!(
+    impl_trait = QueryPoolRepo,
+    repo = query_pools,
+    methods = [
+        "query_create_or_get" = create_or_get(&mut self, name: &str) -> Result<QueryPool>;
+        "query_record_querier_heartbeat" = record_querier_heartbeat(&mut self, query_pool_id: QueryPoolId, address: &str, at: Timestamp) -> Result<QuerierRegistration>;
+        "query_list_queriers" = list_queriers(&mut self, query_pool_id: QueryPoolId, heartbeat_after: Timestamp) -> Result<Vec<QuerierRegistration>>;
+        "query_remove_querier" = remove_querier(&mut self, address: &str) -> Result<()>;
+    ]
+);
+
+decorate!(
+    impl_trait = NamespaceRepo,
+    repo = namespaces,
+    methods = [
+        "namespace_create" = create(&mut self, name: &str, retention_period_ns: Option<i64>, topic_id: TopicId, query_pool_id: QueryPoolId) -> Result<Namespace>;
+        "namespace_update_retention_period" = update_retention_period(&mut self, name: &str, retention_period_ns: Option<i64>) -> Result<Namespace>;
+        "namespace_update_query_ranges" = update_query_ranges(&mut self, name: &str, default_query_range_ns: Option<i64>, max_query_range_ns: Option<i64>) -> Result<Namespace>;
+        "namespace_update_row_ttl" = update_row_ttl(&mut self, name: &str, row_ttl_ns: Option<i64>, row_ttl_column: Option<String>) -> Result<Namespace>;
+        "namespace_update_partition_template" = update_partition_template(&mut self, name: &str, partition_template: Option<&PartitionTemplate>) -> Result<Namespace>;
+        "namespace_list" = list(&mut self) -> Result<Vec<Namespace>>;
+        "namespace_list_paged" = list_paged(&mut self, after: Option<NamespaceId>, limit: usize) -> Result<Vec<Namespace>>;
+        "namespace_get_by_id" = get_by_id(&mut self, id: NamespaceId) -> Result<Option<Namespace>>;
+        "namespace_get_by_name" = get_by_name(&mut self, name: &str) -> Result<Option<Namespace>>;
+        "namespace_update_table_limit" = update_table_limit(&mut self, name: &str, new_max: i32) -> Result<Namespace>;
+        "namespace_update_column_limit" = update_column_limit(&mut self, name: &str, new_max: i32) -> Result<Namespace>;
+        "namespace_update_topic" = update_topic(&mut self, name: &str, topic_id: TopicId) -> Result<Namespace>;
+        "namespace_increment_schema_generation" = increment_schema_generation(&mut self, id: NamespaceId, expected: i64) -> Result<Namespace>;
+        "namespace_add_usage" = add_usage(&mut self, usage: &NamespaceUsage) -> Result<()>;
+        "namespace_get_usage" = get_usage(&mut self, id: NamespaceId) -> Result<Option<NamespaceUsage>>;
+        "namespace_list_usage" = list_usage(&mut self) -> Result<Vec<NamespaceUsage>>;
+        "namespace_get_producer_sequence" = get_producer_sequence(&mut self, id: NamespaceId, producer_id: &str) -> Result<Option<i64>>;
+        "namespace_compare_and_swap_producer_sequence" = compare_and_swap_producer_sequence(&mut self, id: NamespaceId, producer_id: &str, current: Option<i64>, new: Option<i64>) -> Result<bool>;
+    ]
+);
+
+decorate!(
+    impl_trait = TableRepo,
+    repo = tables,
+    methods = [
+        "table_create_or_get" = create_or_get(&mut self, name: &str, namespace_id: NamespaceId) -> Result<Table>;
+        "table_get_by_id" = get_by_id(&mut self, table_id: TableId) -> Result<Option<Table>>;
+        "table_get_by_namespace_and_name" = get_by_namespace_and_name(&mut self, namespace_id: NamespaceId, name: &str) -> Result<Option<Table>>;
+        "table_list_by_namespace_id" = list_by_namespace_id(&mut self, namespace_id: NamespaceId) -> Result<Vec<Table>>;
+        "table_list" = list(&mut self) -> Result<Vec<Table>>;
+        "table_get_shard_pin" = get_shard_pin(&mut self, table_id: TableId) -> Result<Option<ShardId>>;
+        "table_create_or_get_shard_pin" = create_or_get_shard_pin(&mut self, table_id: TableId, shard_id: ShardId) -> Result<ShardId>;
+    ]
+);
+
+decorate!(
+    impl_trait = ColumnRepo,
+    repo = columns,
+    methods = [
+        "column_create_or_get" = create_or_get(&mut self, name: &str, table_id: TableId, column_type: ColumnType) -> Result<Column>;
+        "column_list_by_namespace_id" = list_by_namespace_id(&mut self, namespace_id: NamespaceId) -> Result<Vec<Column>>;
+        "column_list_by_table_id" = list_by_table_id(&mut self, table_id: TableId) -> Result<Vec<Column>>;
+        "column_create_or_get_many_unchecked" = create_or_get_many_unchecked(&mut self, table_id: TableId, columns: HashMap<&str, ColumnType>) -> Result<Vec<Column>>;
+        "column_list" = list(&mut self) -> Result<Vec<Column>>;
+        "column_list_type_count_by_table_id" = list_type_count_by_table_id(&mut self, table_id: TableId) -> Result<Vec<ColumnTypeCount>>;
+    ]
+);
+
+decorate!(
+    impl_trait = ShardRepo,
+    repo = shards,
+    methods = [
+        "shard_create_or_get" = create_or_get(&mut self, topic: &TopicMetadata, shard_index: ShardIndex) -> Result<Shard>;
+        "shard_get_by_topic_id_and_shard_index" = get_by_topic_id_and_shard_index(&mut self, topic_id: TopicId, shard_index: ShardIndex) -> Result<Option<Shard>>;
+        "shard_list" = list(&mut self) -> Result<Vec<Shard>>;
+        "shard_list_by_topic" = list_by_topic(&mut self, topic: &TopicMetadata) -> Result<Vec<Shard>>;
+        "shard_update_min_unpersisted_sequence_number" = update_min_unpersisted_sequence_number(&mut self, shard_id: ShardId, sequence_number: SequenceNumber) -> Result<()>;
+        "shard_acquire_lease" = acquire_lease(&mut self, shard_id: ShardId, holder: &str, duration: Duration) -> Result<ShardLease>;
+        "shard_release_lease" = release_lease(&mut self, shard_id: ShardId, holder: &str) -> Result<()>;
+        "shard_check_lease" = check_lease(&mut self, shard_id: ShardId, holder: &str, epoch: i64) -> Result<()>;
+        "shard_list_held_leases" = list_held_leases(&mut self, topic_id: TopicId) -> Result<Vec<ShardLease>>;
+        "shard_record_ingester_heartbeat" = record_ingester_heartbeat(&mut self, topic_id: TopicId, address: &str, at: Timestamp) -> Result<IngesterRegistration>;
+        "shard_list_ingesters" = list_ingesters(&mut self, topic_id: TopicId) -> Result<Vec<IngesterRegistration>>;
+        "shard_assign_shards" = assign_shards(&mut self, address: &str, shard_indexes: &[ShardIndex]) -> Result<()>;
+        "shard_remove_ingester" = remove_ingester(&mut self, address: &str) -> Result<()>;
+    ]
+);
+
+decorate!(
+    impl_trait = PartitionRepo,
+    repo = partitions,
+    methods = [
+        "partition_create_or_get" = create_or_get(&mut self, key: PartitionKey, shard_id: ShardId, table_id: TableId) -> Result<Partition>;
+        "partition_get_by_id" = get_by_id(&mut self, partition_id: PartitionId) -> Result<Option<Partition>>;
+        "partition_list_by_shard" = list_by_shard(&mut self, shard_id: ShardId) -> Result<Vec<Partition>>;
+        "partition_list_by_namespace" = list_by_namespace(&mut self, namespace_id: NamespaceId) -> Result<Vec<Partition>>;
+        "partition_list_by_table_id" = list_by_table_id(&mut self, table_id: TableId) -> Result<Vec<Partition>>;
+        "partition_update_sort_key" = update_sort_key(&mut self, partition_id: PartitionId, sort_key: &[&str]) -> Result<Partition>;
+        "partition_record_skipped_compaction" = record_skipped_compaction(&mut self, partition_id: PartitionId, reason: &str, num_files: usize, limit_num_files: usize, limit_num_files_first_in_partition: usize, estimated_bytes: u64, limit_bytes: u64) -> Result<()>;
+        "partition_list_skipped_compactions" = list_skipped_compactions(&mut self) -> Result<Vec<SkippedCompaction>>;
+        "partition_delete_skipped_compactions" = delete_skipped_compactions(&mut self, partition_id: PartitionId) -> Result<Option<SkippedCompaction>>;
+        "partition_update_persisted_sequence_number" = update_persisted_sequence_number(&mut self, partition_id: PartitionId, sequence_number: SequenceNumber) -> Result<()>;
+        "partition_mark_cold" = mark_cold(&mut self, partition_id: PartitionId, cold_compacted_at: Timestamp) -> Result<bool>;
+        "partition_most_recent_n" = most_recent_n(&mut self, n: usize, shards: &[ShardId]) -> Result<Vec<Partition>>;
+    ]
+);
+
+decorate!(
+    impl_trait = TombstoneRepo,
+    repo = tombstones,
+    methods = [
+        "tombstone_create_or_get" = create_or_get( &mut self, table_id: TableId, shard_id: ShardId, sequence_number: SequenceNumber, min_time: Timestamp, max_time: Timestamp, predicate: &str) -> Result<Tombstone>;
+        "tombstone_list_by_namespace" = list_by_namespace(&mut self, namespace_id: NamespaceId) -> Result<Vec<Tombstone>>;
+        "tombstone_list_by_table" = list_by_table(&mut self, table_id: TableId) -> Result<Vec<Tombstone>>;
+        "tombstone_get_by_id" = get_by_id(&mut self, id: TombstoneId) -> Result<Option<Tombstone>>;
+        "tombstone_list_tombstones_by_shard_greater_than" = list_tombstones_by_shard_greater_than(&mut self, shard_id: ShardId, sequence_number: SequenceNumber) -> Result<Vec<Tombstone>>;
+        "tombstone_remove" = remove(&mut self, tombstone_ids: &[TombstoneId]) -> Result<()>;
+        "tombstone_list_tombstones_for_time_range" = list_tombstones_for_time_range(&mut self, shard_id: ShardId, table_id: TableId, sequence_number: SequenceNumber, min_time: Timestamp, max_time: Timestamp) -> Result<Vec<Tombstone>>;
+    ]
+);
+
+decorate!(
+    impl_trait = ParquetFileRepo,
+    repo = parquet_files,
+    methods = [
+        "parquet_create" = create( &mut self, parquet_file_params: ParquetFileParams) -> Result<ParquetFile>;
+        "parquet_flag_for_delete" = flag_for_delete(&mut self, id: ParquetFileId) -> Result<()>;
+        "parquet_flag_for_delete_by_retention" = flag_for_delete_by_retention(&mut self) -> Result<Vec<ParquetFileId>>;
+        "parquet_list_by_shard_greater_than" = list_by_shard_greater_than(&mut self, shard_id: ShardId, sequence_number: SequenceNumber) -> Result<Vec<ParquetFile>>;
+        "parquet_list_by_namespace_not_to_delete" = list_by_namespace_not_to_delete(&mut self, namespace_id: NamespaceId) -> Result<Vec<ParquetFile>>;
+        "parquet_list_by_table_not_to_delete" = list_by_table_not_to_delete(&mut self, table_id: TableId) -> Result<Vec<ParquetFile>>;
+        "parquet_list_by_table_and_time_range" = list_by_table_and_time_range(&mut self, table_id: TableId, min_time: Timestamp, max_time: Timestamp) -> Result<Vec<ParquetFile>>;
+        "parquet_delete_old" = delete_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>>;
+        "parquet_delete_old_ids_only" = delete_old_ids_only(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFileId>>;
+        "parquet_list_by_partition_not_to_delete" = list_by_partition_not_to_delete(&mut self, partition_id: PartitionId) -> Result<Vec<ParquetFile>>;
+        "parquet_level_0" = level_0(&mut self, shard_id: ShardId) -> Result<Vec<ParquetFile>>;
+        "parquet_level_1" = level_1(&mut self, table_partition: TablePartition, min_time: Timestamp, max_time: Timestamp) -> Result<Vec<ParquetFile>>;
+        "parquet_update_compaction_level" = update_compaction_level(&mut self, parquet_file_ids: &[ParquetFileId], compaction_level: CompactionLevel) -> Result<Vec<ParquetFileId>>;
+        "parquet_exist" = exist(&mut self, id: ParquetFileId) -> Result<bool>;
+        "parquet_count" = count(&mut self) -> Result<i64>;
+        "parquet_count_by_overlaps_with_level_0" = count_by_overlaps_with_level_0(&mut self, table_id: TableId, shard_id: ShardId, min_time: Timestamp, max_time: Timestamp, sequence_number: SequenceNumber) -> Result<i64>;
+        "parquet_count_by_overlaps_with_level_1" = count_by_overlaps_with_level_1(&mut self, table_id: TableId, shard_id: ShardId, min_time: Timestamp, max_time: Timestamp) -> Result<i64>;
+        "parquet_get_by_object_store_id" = get_by_object_store_id(&mut self, object_store_id: Uuid) -> Result<Option<ParquetFile>>;
+        "parquet_create_lineage" = create_lineage(&mut self, parquet_file: &ParquetFile, inputs: &[(ParquetFileId, CompactionLevel)]) -> Result<()>;
+        "parquet_list_lineage_by_namespace" = list_lineage_by_namespace(&mut self, namespace_id: NamespaceId) -> Result<Vec<ParquetFileLineage>>;
+        "recent_highest_throughput_partitions" = recent_highest_throughput_partitions(&mut self, shard_id: ShardId, time_in_the_past: Timestamp, min_num_files: usize, num_partitions: usize) -> Result<Vec<PartitionParam>>;
+        "most_cold_files_partitions" = most_cold_files_partitions(&mut self, shard_id: ShardId, time_in_the_past: Timestamp, num_partitions: usize) -> Result<Vec<PartitionParam>>;
+    ]
+);
+
+decorate!(
+    impl_trait = ProcessedTombstoneRepo,
+    repo = processed_tombstones,
+    methods = [
+        "processed_tombstone_create" = create(&mut self, parquet_file_id: ParquetFileId, tombstone_id: TombstoneId) -> Result<ProcessedTombstone>;
+        "processed_tombstone_exist" = exist(&mut self, parquet_file_id: ParquetFileId, tombstone_id: TombstoneId) -> Result<bool>;
+        "processed_tombstone_count" = count(&mut self) -> Result<i64>;
+        "processed_tombstone_count_by_tombstone_id" = count_by_tombstone_id(&mut self, tombstone_id: TombstoneId) -> Result<i64>;
+    ]
+);
+
+decorate!(
+    impl_trait = OperationRepo,
+    repo = operations,
+    methods = [
+        "operation_create" = create(&mut self, kind: &str, description: &str, node: &str, created_at: Timestamp) -> Result<Operation>;
+        "operation_get_by_id" = get_by_id(&mut self, id: OperationId) -> Result<Option<Operation>>;
+        "operation_list" = list(&mut self) -> Result<Vec<Operation>>;
+        "operation_complete" = complete(&mut self, id: OperationId, status: OperationStatus, completed_at: Timestamp) -> Result<()>;
+        "operation_interrupt_running" = interrupt_running(&mut self, node: &str, at: Timestamp) -> Result<Vec<Operation>>;
+        "operation_delete_completed_before" = delete_completed_before(&mut self, older_than: Timestamp) -> Result<u64>;
+    ]
+);
+
+decorate!(
+    impl_trait = ApiTokenRepo,
+    repo = api_tokens,
+    methods = [
+        "api_token_create" = create(&mut self, token_hash: &str, namespace_id: Option<NamespaceId>, permission: ApiTokenPermission, description: &str, created_at: Timestamp) -> Result<ApiToken>;
+        "api_token_get_by_hash" = get_by_hash(&mut self, token_hash: &str) -> Result<Option<ApiToken>>;
+        "api_token_list" = list(&mut self) -> Result<Vec<ApiToken>>;
+        "api_token_revoke" = revoke(&mut self, id: ApiTokenId, revoked_at: Timestamp) -> Result<Option<ApiToken>>;
+    ]
+);
+
+decorate!(
+    impl_trait = NodeDrainRepo,
+    repo = node_drains,
+    methods = [
+        "node_drain_set_state" = set_state(&mut self, address: &str, state: DrainState, updated_at: Timestamp) -> Result<NodeDrain>;
+        "node_drain_get_by_address" = get_by_address(&mut self, address: &str) -> Result<Option<NodeDrain>>;
+        "node_drain_list" = list(&mut self) -> Result<Vec<NodeDrain>>;
+    ]
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::MemCatalog;
+    use assert_matches::assert_matches;
+
+    fn catalog(config: FaultConfig) -> FaultInjectingCatalog {
+        let metrics = Arc::new(metric::Registry::default());
+        FaultInjectingCatalog::new(Arc::new(MemCatalog::new(metrics)), config)
+    }
+
+    #[tokio::test]
+    async fn test_no_faults() {
+        let catalog = catalog(FaultConfig::default());
+
+        let mut txn = catalog.start_transaction().await.unwrap();
+        txn.topics().create_or_get("bananas").await.unwrap();
+        txn.commit().await.unwrap();
+
+        let topic = catalog
+            .repositories()
+            .await
+            .topics()
+            .get_by_name("bananas")
+            .await
+            .unwrap();
+        assert!(topic.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_all_operations_fail() {
+        let catalog = catalog(FaultConfig::default().with_error_rate(1.0));
+
+        let err = catalog
+            .repositories()
+            .await
+            .topics()
+            .create_or_get("bananas")
+            .await
+            .unwrap_err();
+        assert_matches!(
+            err,
+            Error::InjectedFault {
+                operation: "topic_create_or_get"
+            }
+        );
+
+        let err = catalog.start_transaction().await.unwrap_err();
+        assert_matches!(
+            err,
+            Error::InjectedFault {
+                operation: "txn_start"
+            }
+        );
+
+        // Setup is never faulted.
+        catalog.setup().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_seeded_faults_are_reproducible() {
+        let config = FaultConfig::default().with_error_rate(0.5).with_seed(42);
+
+        let mut outcomes = vec![];
+        for _ in 0..2 {
+            let catalog = catalog(config);
+            let mut repos = catalog.repositories().await;
+            let mut results = vec![];
+            for _ in 0..32 {
+                results.push(repos.topics().get_by_name("bananas").await.is_ok());
+            }
+            outcomes.push(results);
+        }
+
+        assert_eq!(outcomes[0], outcomes[1]);
+        assert!(outcomes[0].contains(&true));
+        assert!(outcomes[0].contains(&false));
+    }
+}
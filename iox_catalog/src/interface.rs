@@ -2,18 +2,24 @@
 
 use async_trait::async_trait;
 use data_types::{
-    Column, ColumnSchema, ColumnType, ColumnTypeCount, CompactionLevel, Namespace, NamespaceId,
-    NamespaceSchema, ParquetFile, ParquetFileId, ParquetFileParams, Partition, PartitionId,
-    PartitionKey, PartitionParam, ProcessedTombstone, QueryPool, QueryPoolId, SequenceNumber,
-    Shard, ShardId, ShardIndex, SkippedCompaction, Table, TableId, TablePartition, TableSchema,
-    Timestamp, Tombstone, TombstoneId, TopicId, TopicMetadata,
+    ApiToken, ApiTokenId, ApiTokenPermission, Column, ColumnId, ColumnSchema, ColumnType,
+    ColumnTypeCount, CompactionLevel, DrainState, IngesterRegistration, Namespace, NamespaceId,
+    NamespaceSchema, NamespaceUsage, NodeDrain, Operation, OperationId, OperationStatus,
+    ParquetFile, ParquetFileId, ParquetFileLineage, ParquetFileParams, Partition, PartitionId,
+    PartitionKey, PartitionParam, PartitionTemplate, ProcessedTombstone, QuerierRegistration,
+    QueryPool, QueryPoolId, SequenceNumber, Shard, ShardId, ShardIndex, ShardLease,
+    SkippedCompaction, Table, TableId, TablePartition, TableSchema, Timestamp, Tombstone,
+    TombstoneId, TopicId, TopicMetadata,
 };
+use futures::Stream;
 use iox_time::TimeProvider;
-use snafu::{OptionExt, Snafu};
+use snafu::{OptionExt, ResultExt, Snafu};
 use std::{
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     fmt::Debug,
+    path::PathBuf,
     sync::Arc,
+    time::Duration,
 };
 use uuid::Uuid;
 
@@ -50,12 +56,42 @@ pub enum Error {
     #[snafu(display("namespace {} not found", id))]
     NamespaceNotFoundById { id: NamespaceId },
 
+    #[snafu(display("namespace {} has an invalid partition template: {}", id, source))]
+    InvalidPartitionTemplate {
+        id: NamespaceId,
+        source: data_types::TemplatePartParseError,
+    },
+
+    #[snafu(display(
+        "namespace {} schema was modified concurrently: expected generation {}, found {}",
+        namespace_id,
+        expected,
+        actual
+    ))]
+    PreconditionViolation {
+        namespace_id: NamespaceId,
+        expected: i64,
+        actual: i64,
+    },
+
     #[snafu(display("table {} not found", id))]
     TableNotFound { id: TableId },
 
     #[snafu(display("partition {} not found", id))]
     PartitionNotFound { id: PartitionId },
 
+    #[snafu(display(
+        "the lease of shard {} is no longer held by {} in epoch {}",
+        shard_id,
+        holder,
+        epoch
+    ))]
+    LeaseLost {
+        shard_id: ShardId,
+        holder: String,
+        epoch: i64,
+    },
+
     #[snafu(display(
         "couldn't create column {} in table {}; limit reached on namespace",
         column_name,
@@ -79,6 +115,9 @@ pub enum Error {
     #[snafu(display("parquet file with object_store_id {} already exists", object_store_id))]
     FileExists { object_store_id: Uuid },
 
+    #[snafu(display("an API token with the same hash already exists"))]
+    ApiTokenExists,
+
     #[snafu(display("parquet file with id {} does not exist. Foreign key violation", id))]
     FileNotFound { id: i64 },
 
@@ -129,17 +168,65 @@ pub enum Error {
 
     #[snafu(display("could not delete skipped compactions: {source}"))]
     CouldNotDeleteSkippedCompactions { source: sqlx::Error },
+
+    #[snafu(display("injected fault in catalog operation {operation}"))]
+    InjectedFault { operation: &'static str },
+
+    #[snafu(display("catalog snapshot {}: {}", path.display(), source))]
+    Snapshot {
+        source: Box<dyn std::error::Error + Send + Sync>,
+        path: PathBuf,
+    },
 }
 
 /// A specialized `Error` for Catalog errors
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// A schema migration embedded in this binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationInfo {
+    /// Version of the migration.
+    pub version: i64,
+
+    /// Description of the migration.
+    pub description: String,
+}
+
+/// State of the catalog schema compared to the migrations embedded in this binary.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationStatus {
+    /// Migrations that have been applied to the catalog, in order.
+    pub applied: Vec<i64>,
+
+    /// Migrations that have not been applied to the catalog yet, in order.
+    pub pending: Vec<MigrationInfo>,
+
+    /// Version of a migration that failed part way through, leaving the schema dirty.
+    pub dirty: Option<i64>,
+}
+
+impl MigrationStatus {
+    /// Returns true if all migrations were applied successfully.
+    pub fn is_up_to_date(&self) -> bool {
+        self.pending.is_empty() && self.dirty.is_none()
+    }
+}
+
 /// Methods for working with the catalog.
 #[async_trait]
 pub trait Catalog: Send + Sync + Debug {
     /// Setup catalog for usage and apply possible migrations.
     async fn setup(&self) -> Result<(), Error>;
 
+    /// Get the state of the catalog schema compared to the migrations embedded in this binary.
+    async fn migration_status(&self) -> Result<MigrationStatus, Error>;
+
+    /// Apply all pending migrations up to and including `target_version`, or all pending
+    /// migrations if `target_version` is `None`.
+    ///
+    /// Fails without applying anything if the schema is dirty.
+    async fn migrate(&self, target_version: Option<i64>) -> Result<(), Error>;
+
     /// Creates a new [`Transaction`].
     ///
     /// Creating transactions is potentially expensive. Holding one consumes resources. The number
@@ -156,6 +243,11 @@ pub trait Catalog: Send + Sync + Debug {
 
     /// Gets the time provider associated with this catalog.
     fn time_provider(&self) -> Arc<dyn TimeProvider>;
+
+    /// Flushes any state the catalog only keeps in memory, called before the process exits.
+    async fn shutdown(&self) -> Result<(), Error> {
+        Ok(())
+    }
 }
 
 /// Secret module for [sealed traits].
@@ -263,6 +355,15 @@ pub trait RepoCollection: Send + Sync + Debug {
 
     /// Repository for [processed tombstones](data_types::ProcessedTombstone).
     fn processed_tombstones(&mut self) -> &mut dyn ProcessedTombstoneRepo;
+
+    /// Repository for [operations](data_types::Operation).
+    fn operations(&mut self) -> &mut dyn OperationRepo;
+
+    /// Repository for [API tokens](data_types::ApiToken).
+    fn api_tokens(&mut self) -> &mut dyn ApiTokenRepo;
+
+    /// Repository for [drain states](data_types::NodeDrain) of ingesters.
+    fn node_drains(&mut self) -> &mut dyn NodeDrainRepo;
 }
 
 /// Functions for working with topics in the catalog.
@@ -273,6 +374,9 @@ pub trait TopicMetadataRepo: Send + Sync {
 
     /// Gets the topic by its unique name
     async fn get_by_name(&mut self, name: &str) -> Result<Option<TopicMetadata>>;
+
+    /// List all topics.
+    async fn list(&mut self) -> Result<Vec<TopicMetadata>>;
 }
 
 /// Functions for working with query pools in the catalog.
@@ -280,6 +384,28 @@ pub trait TopicMetadataRepo: Send + Sync {
 pub trait QueryPoolRepo: Send + Sync {
     /// Creates the query pool in the catalog or gets the existing record by name.
     async fn create_or_get(&mut self, name: &str) -> Result<QueryPool>;
+
+    /// Record a heartbeat sent at `at` by the querier reachable at `address`, registering it as
+    /// a member of the query pool `query_pool_id`.
+    ///
+    /// A querier is a member of at most one pool; a heartbeat for another pool moves it.
+    async fn record_querier_heartbeat(
+        &mut self,
+        query_pool_id: QueryPoolId,
+        address: &str,
+        at: Timestamp,
+    ) -> Result<QuerierRegistration>;
+
+    /// List the queriers of the query pool `query_pool_id` that sent a heartbeat at or after
+    /// `heartbeat_after`, ordered by address.
+    async fn list_queriers(
+        &mut self,
+        query_pool_id: QueryPoolId,
+        heartbeat_after: Timestamp,
+    ) -> Result<Vec<QuerierRegistration>>;
+
+    /// Remove the registration of the querier reachable at `address`, if any.
+    async fn remove_querier(&mut self, address: &str) -> Result<()>;
 }
 
 /// Functions for working with namespaces in the catalog
@@ -303,9 +429,46 @@ pub trait NamespaceRepo: Send + Sync {
         retention_period_ns: Option<i64>,
     ) -> Result<Namespace>;
 
+    /// Update the default and maximum query time ranges of a namespace (see
+    /// [`Namespace::default_query_range_ns`] and [`Namespace::max_query_range_ns`])
+    async fn update_query_ranges(
+        &mut self,
+        name: &str,
+        default_query_range_ns: Option<i64>,
+        max_query_range_ns: Option<i64>,
+    ) -> Result<Namespace>;
+
+    /// Update the row expiry of a namespace (see [`Namespace::row_ttl_ns`] and
+    /// [`Namespace::row_ttl_column`])
+    async fn update_row_ttl(
+        &mut self,
+        name: &str,
+        row_ttl_ns: Option<i64>,
+        row_ttl_column: Option<String>,
+    ) -> Result<Namespace>;
+
+    /// Update the template the writes to a namespace are partitioned by (see
+    /// [`Namespace::partition_template`])
+    async fn update_partition_template(
+        &mut self,
+        name: &str,
+        partition_template: Option<&PartitionTemplate>,
+    ) -> Result<Namespace>;
+
     /// List all namespaces.
     async fn list(&mut self) -> Result<Vec<Namespace>>;
 
+    /// List at most `limit` namespaces with an ID greater than `after` (or from the start if
+    /// `None`), in ascending ID order.
+    ///
+    /// Pass the ID of the last namespace returned as `after` to fetch the next page; a page
+    /// shorter than `limit` is the last one.
+    async fn list_paged(
+        &mut self,
+        after: Option<NamespaceId>,
+        limit: usize,
+    ) -> Result<Vec<Namespace>>;
+
     /// Gets the namespace by its ID.
     async fn get_by_id(&mut self, id: NamespaceId) -> Result<Option<Namespace>>;
 
@@ -317,6 +480,57 @@ pub trait NamespaceRepo: Send + Sync {
 
     /// Update the limit on the number of columns that can exist per table in a given namespace.
     async fn update_column_limit(&mut self, name: &str, new_max: i32) -> Result<Namespace>;
+
+    /// Move the namespace to the write buffer topic `topic_id`.
+    ///
+    /// Routers serving the new topic send subsequent writes for the namespace to it once they
+    /// observe the change.
+    async fn update_topic(&mut self, name: &str, topic_id: TopicId) -> Result<Namespace>;
+
+    /// Increment the [`Namespace::schema_generation`] of namespace `id`, but only if it is
+    /// currently `expected`.
+    ///
+    /// This is the compare-and-swap step of a schema change: a writer that built its changes on
+    /// top of a [`NamespaceSchema`] at generation `expected` calls this once they are applied,
+    /// and learns through [`Error::PreconditionViolation`] that another writer changed the
+    /// schema in the meantime (and its view of the schema is therefore incomplete).
+    ///
+    /// Updating the retention period, column limit or topic also increments the generation.
+    async fn increment_schema_generation(
+        &mut self,
+        id: NamespaceId,
+        expected: i64,
+    ) -> Result<Namespace>;
+
+    /// Add `usage` to the cumulative usage of its namespace.
+    async fn add_usage(&mut self, usage: &NamespaceUsage) -> Result<()>;
+
+    /// Get the cumulative usage of namespace `id`, or `None` if none was recorded.
+    async fn get_usage(&mut self, id: NamespaceId) -> Result<Option<NamespaceUsage>>;
+
+    /// List the cumulative usage of all namespaces with recorded usage, in ascending namespace
+    /// ID order.
+    async fn list_usage(&mut self) -> Result<Vec<NamespaceUsage>>;
+
+    /// Get the highest sequence number of the writes of producer `producer_id` to namespace `id`
+    /// reserved so far, or `None` if none was recorded.
+    async fn get_producer_sequence(
+        &mut self,
+        id: NamespaceId,
+        producer_id: &str,
+    ) -> Result<Option<i64>>;
+
+    /// Atomically replace the sequence number of producer `producer_id` to namespace `id` by
+    /// `new` if it is `current`, where `None` is no recorded sequence number.
+    ///
+    /// Returns false, changing nothing, if the recorded sequence number is not `current`.
+    async fn compare_and_swap_producer_sequence(
+        &mut self,
+        id: NamespaceId,
+        producer_id: &str,
+        current: Option<i64>,
+        new: Option<i64>,
+    ) -> Result<bool>;
 }
 
 /// Functions for working with tables in the catalog
@@ -340,6 +554,20 @@ pub trait TableRepo: Send + Sync {
 
     /// List all tables.
     async fn list(&mut self) -> Result<Vec<Table>>;
+
+    /// Get the shard the writes for table `table_id` are pinned to, if any.
+    async fn get_shard_pin(&mut self, table_id: TableId) -> Result<Option<ShardId>>;
+
+    /// Pin the writes for table `table_id` to `shard_id`, unless the table is already pinned to
+    /// a shard, returning the shard the table is pinned to.
+    ///
+    /// An existing pin is never changed, so that concurrent callers resolving the pin for the
+    /// same table all converge on the first pin created.
+    async fn create_or_get_shard_pin(
+        &mut self,
+        table_id: TableId,
+        shard_id: ShardId,
+    ) -> Result<ShardId>;
 }
 
 /// Functions for working with columns in the catalog
@@ -415,6 +643,62 @@ pub trait ShardRepo: Send + Sync {
         shard: ShardId,
         sequence_number: SequenceNumber,
     ) -> Result<()>;
+
+    /// Acquire or renew the lease of `shard_id` for `holder` for `duration`, if the lease is not
+    /// held, already held by `holder`, or has expired.
+    ///
+    /// The expiry is computed from the clock of the catalog, so that the ingesters competing for
+    /// the lease do not depend on their own clocks agreeing. The epoch of the lease is
+    /// incremented whenever it is acquired while not held, and kept when it is renewed.
+    ///
+    /// Returns the lease of the shard after the attempt, which is held by another ingester if
+    /// the attempt failed.
+    async fn acquire_lease(
+        &mut self,
+        shard_id: ShardId,
+        holder: &str,
+        duration: Duration,
+    ) -> Result<ShardLease>;
+
+    /// Release the lease of `shard_id`, if it is held by `holder`.
+    ///
+    /// The lease expires immediately, but keeps its epoch.
+    async fn release_lease(&mut self, shard_id: ShardId, holder: &str) -> Result<()>;
+
+    /// Check that `holder` still holds the lease of `shard_id` it acquired in `epoch`, returning
+    /// [`Error::LeaseLost`] otherwise.
+    ///
+    /// Called in the transaction persisting the data of the shard, the check fences off a
+    /// leader whose lease was taken over: the lease can not be taken over before the
+    /// transaction completes.
+    async fn check_lease(&mut self, shard_id: ShardId, holder: &str, epoch: i64) -> Result<()>;
+
+    /// List the leases of the shards of the topic `topic_id` that are currently held, by the
+    /// clock of the catalog.
+    async fn list_held_leases(&mut self, topic_id: TopicId) -> Result<Vec<ShardLease>>;
+
+    /// Record a heartbeat sent at `at` by the ingester reachable at `address`, registering it as
+    /// a consumer of the shards of the topic `topic_id`.
+    ///
+    /// A new registration has no shards assigned. The shards assigned to an existing one are
+    /// kept, unless the heartbeat moves it to another topic.
+    async fn record_ingester_heartbeat(
+        &mut self,
+        topic_id: TopicId,
+        address: &str,
+        at: Timestamp,
+    ) -> Result<IngesterRegistration>;
+
+    /// List the ingesters registered as consumers of the shards of the topic `topic_id`,
+    /// including those that stopped sending heartbeats, ordered by address.
+    async fn list_ingesters(&mut self, topic_id: TopicId) -> Result<Vec<IngesterRegistration>>;
+
+    /// Assign `shard_indexes` to the ingester reachable at `address`, replacing its previous
+    /// assignment. Does nothing if the ingester is not registered.
+    async fn assign_shards(&mut self, address: &str, shard_indexes: &[ShardIndex]) -> Result<()>;
+
+    /// Remove the registration of the ingester reachable at `address`, if any.
+    async fn remove_ingester(&mut self, address: &str) -> Result<()>;
 }
 
 /// Functions for working with IOx partitions in the catalog. Note that these are how IOx splits up
@@ -477,13 +761,24 @@ pub trait PartitionRepo: Send + Sync {
     /// Update the per-partition persistence watermark.
     ///
     /// The given `sequence_number` is the inclusive maximum [`SequenceNumber`]
-    /// of the most recently persisted data for this partition.
+    /// of the most recently persisted data for this partition. This also
+    /// clears the time the partition was marked cold, if any.
     async fn update_persisted_sequence_number(
         &mut self,
         partition_id: PartitionId,
         sequence_number: SequenceNumber,
     ) -> Result<()>;
 
+    /// Mark the partition as cold, fully compacted at `cold_compacted_at`.
+    ///
+    /// The partition is only marked if all its non-deleted parquet files are at
+    /// [`CompactionLevel::Final`]. Returns whether it was marked.
+    async fn mark_cold(
+        &mut self,
+        partition_id: PartitionId,
+        cold_compacted_at: Timestamp,
+    ) -> Result<bool>;
+
     /// Return the N most recently created partitions for the specified shards.
     async fn most_recent_n(&mut self, n: usize, shards: &[ShardId]) -> Result<Vec<Partition>>;
 }
@@ -573,6 +868,16 @@ pub trait ParquetFileRepo: Send + Sync {
     /// [`to_delete`](ParquetFile::to_delete).
     async fn list_by_table_not_to_delete(&mut self, table_id: TableId) -> Result<Vec<ParquetFile>>;
 
+    /// List all parquet files within a given table that are NOT marked as
+    /// [`to_delete`](ParquetFile::to_delete) and that overlap the time range from `min_time` to
+    /// `max_time` (both inclusive).
+    async fn list_by_table_and_time_range(
+        &mut self,
+        table_id: TableId,
+        min_time: Timestamp,
+        max_time: Timestamp,
+    ) -> Result<Vec<ParquetFile>>;
+
     /// Delete all parquet files that were marked to be deleted earlier than the specified time.
     /// Returns the deleted records.
     async fn delete_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>>;
@@ -667,6 +972,22 @@ pub trait ParquetFileRepo: Send + Sync {
         &mut self,
         object_store_id: Uuid,
     ) -> Result<Option<ParquetFile>>;
+
+    /// Record that `parquet_file` was compacted from the parquet files `inputs`, given with their
+    /// compaction levels at the time of the compaction.
+    async fn create_lineage(
+        &mut self,
+        parquet_file: &ParquetFile,
+        inputs: &[(ParquetFileId, CompactionLevel)],
+    ) -> Result<()>;
+
+    /// List the lineage of the compacted parquet files of namespace `namespace_id`, including the
+    /// lineage of files that have since been deleted, ordered by parquet file ID and input
+    /// parquet file ID.
+    async fn list_lineage_by_namespace(
+        &mut self,
+        namespace_id: NamespaceId,
+    ) -> Result<Vec<ParquetFileLineage>>;
 }
 
 /// Functions for working with processed tombstone pointers in the catalog
@@ -693,6 +1014,91 @@ pub trait ProcessedTombstoneRepo: Send + Sync {
     async fn count_by_tombstone_id(&mut self, tombstone_id: TombstoneId) -> Result<i64>;
 }
 
+/// Functions for working with operations in the catalog
+#[async_trait]
+pub trait OperationRepo: Send + Sync {
+    /// Record that `node` started a job of `kind` at `created_at`. The operation is running
+    /// until it is completed.
+    async fn create(
+        &mut self,
+        kind: &str,
+        description: &str,
+        node: &str,
+        created_at: Timestamp,
+    ) -> Result<Operation>;
+
+    /// Get the operation with the given id
+    async fn get_by_id(&mut self, id: OperationId) -> Result<Option<Operation>>;
+
+    /// List all operations, ordered by id
+    async fn list(&mut self) -> Result<Vec<Operation>>;
+
+    /// Record that the running operation `id` completed with `status` at `completed_at`.
+    ///
+    /// Operations that already completed are left unchanged.
+    async fn complete(
+        &mut self,
+        id: OperationId,
+        status: OperationStatus,
+        completed_at: Timestamp,
+    ) -> Result<()>;
+
+    /// Mark the running operations of `node` as interrupted at `at`, returning them.
+    ///
+    /// Called by a process on startup, as the jobs it was executing before a restart can no
+    /// longer complete.
+    async fn interrupt_running(&mut self, node: &str, at: Timestamp) -> Result<Vec<Operation>>;
+
+    /// Delete the operations that completed before `older_than`, returning the number of
+    /// deleted operations.
+    async fn delete_completed_before(&mut self, older_than: Timestamp) -> Result<u64>;
+}
+
+/// Functions for working with API tokens in the catalog
+#[async_trait]
+pub trait ApiTokenRepo: Send + Sync {
+    /// Record a token with the given hash, allowing `permission` on `namespace_id`, or on all
+    /// namespaces if `None`.
+    async fn create(
+        &mut self,
+        token_hash: &str,
+        namespace_id: Option<NamespaceId>,
+        permission: ApiTokenPermission,
+        description: &str,
+        created_at: Timestamp,
+    ) -> Result<ApiToken>;
+
+    /// Get the token with the given hash, whether it is revoked or not
+    async fn get_by_hash(&mut self, token_hash: &str) -> Result<Option<ApiToken>>;
+
+    /// List all tokens, ordered by id
+    async fn list(&mut self) -> Result<Vec<ApiToken>>;
+
+    /// Revoke the token `id` at `revoked_at`, returning it, or `None` if it does not exist.
+    ///
+    /// Tokens that are already revoked are left unchanged.
+    async fn revoke(&mut self, id: ApiTokenId, revoked_at: Timestamp) -> Result<Option<ApiToken>>;
+}
+
+/// Functions for working with the drain states of ingesters in the catalog
+#[async_trait]
+pub trait NodeDrainRepo: Send + Sync {
+    /// Set the drain state of the ingester reachable at `address` to `state` at `updated_at`.
+    async fn set_state(
+        &mut self,
+        address: &str,
+        state: DrainState,
+        updated_at: Timestamp,
+    ) -> Result<NodeDrain>;
+
+    /// Get the drain state of the ingester reachable at `address`, or `None` if it never
+    /// recorded one.
+    async fn get_by_address(&mut self, address: &str) -> Result<Option<NodeDrain>>;
+
+    /// List the drain states of all ingesters, ordered by address
+    async fn list(&mut self) -> Result<Vec<NodeDrain>>;
+}
+
 /// Gets the namespace schema including all tables and columns.
 pub async fn get_schema_by_id<R>(id: NamespaceId, repos: &mut R) -> Result<NamespaceSchema>
 where
@@ -729,13 +1135,28 @@ where
     let columns = repos.columns().list_by_namespace_id(namespace.id).await?;
     let tables = repos.tables().list_by_namespace_id(namespace.id).await?;
 
-    let mut namespace = NamespaceSchema::new(
-        namespace.id,
-        namespace.topic_id,
-        namespace.query_pool_id,
-        namespace.max_columns_per_table,
-        namespace.retention_period_ns,
-    );
+    let partition_template = namespace
+        .partition_template
+        .as_deref()
+        .map(str::parse::<PartitionTemplate>)
+        .transpose()
+        .context(InvalidPartitionTemplateSnafu { id: namespace.id })?;
+
+    let mut namespace = NamespaceSchema {
+        default_query_range_ns: namespace.default_query_range_ns,
+        max_query_range_ns: namespace.max_query_range_ns,
+        row_ttl_ns: namespace.row_ttl_ns,
+        row_ttl_column: namespace.row_ttl_column,
+        partition_template,
+        generation: namespace.schema_generation,
+        ..NamespaceSchema::new(
+            namespace.id,
+            namespace.topic_id,
+            namespace.query_pool_id,
+            namespace.max_columns_per_table,
+            namespace.retention_period_ns,
+        )
+    };
 
     let mut table_id_to_schema = BTreeMap::new();
     for t in tables {
@@ -874,12 +1295,68 @@ pub async fn list_schemas(
                 v.retention_period_ns,
             );
             ns.tables = joined.remove(&v.id)?;
+            ns.generation = v.schema_generation;
             Some((v, ns))
         });
 
     Ok(iter)
 }
 
+/// Stream every [`NamespaceSchema`] in the catalog, fetching `page_size` namespaces at a time.
+///
+/// Unlike [`list_schemas`], which loads every column and table in the catalog into memory at
+/// once, this holds at most one page of namespaces and the schema being built. The price is two
+/// extra queries per namespace, and the result is not a point-in-time snapshot across
+/// namespaces.
+///
+/// Namespaces without any tables are included, with an empty schema.
+///
+/// # Panics
+///
+/// Panics if `page_size` is 0.
+pub async fn list_schemas_paged(
+    catalog: &dyn Catalog,
+    page_size: usize,
+) -> impl Stream<Item = Result<(Namespace, NamespaceSchema)>> + Send {
+    assert!(page_size > 0, "page size must be non-zero");
+
+    struct State {
+        repos: Box<dyn RepoCollection>,
+        page: VecDeque<Namespace>,
+        after: Option<NamespaceId>,
+        exhausted: bool,
+    }
+
+    let state = State {
+        repos: catalog.repositories().await,
+        page: VecDeque::new(),
+        after: None,
+        exhausted: false,
+    };
+
+    futures::stream::try_unfold(state, move |mut state| async move {
+        loop {
+            if let Some(namespace) = state.page.pop_front() {
+                let schema = get_schema_internal(namespace.clone(), &mut *state.repos).await?;
+                return Ok(Some(((namespace, schema), state)));
+            }
+
+            if state.exhausted {
+                return Ok(None);
+            }
+
+            let page = state
+                .repos
+                .namespaces()
+                .list_paged(state.after, page_size)
+                .await?;
+            state.exhausted = page.len() < page_size;
+            state.after = page.last().map(|n| n.id).or(state.after);
+            state.page = page.into();
+        }
+    })
+}
+
 #[cfg(test)]
 pub(crate) mod test_helpers {
     use crate::{validate_or_insert_schema, DEFAULT_MAX_COLUMNS_PER_TABLE, DEFAULT_MAX_TABLES};
@@ -887,7 +1364,8 @@ pub(crate) mod test_helpers {
     use super::*;
     use ::test_helpers::{assert_contains, tracing::TracingCapture};
     use assert_matches::assert_matches;
-    use data_types::{ColumnId, ColumnSet, CompactionLevel};
+    use data_types::{ColumnSet, CompactionLevel};
+    use futures::TryStreamExt;
     use metric::{Attributes, DurationHistogram, Metric};
     use std::{
         ops::{Add, DerefMut},
@@ -899,10 +1377,20 @@ pub(crate) mod test_helpers {
         test_setup(Arc::clone(&catalog)).await;
         test_topic(Arc::clone(&catalog)).await;
         test_query_pool(Arc::clone(&catalog)).await;
+        test_query_pool_queriers(Arc::clone(&catalog)).await;
         test_namespace(Arc::clone(&catalog)).await;
+        test_namespace_schema_generation(Arc::clone(&catalog)).await;
+        test_namespace_query_ranges(Arc::clone(&catalog)).await;
+        test_namespace_row_ttl(Arc::clone(&catalog)).await;
+        test_namespace_partition_template(Arc::clone(&catalog)).await;
+        test_namespace_usage(Arc::clone(&catalog)).await;
+        test_producer_sequence(Arc::clone(&catalog)).await;
         test_table(Arc::clone(&catalog)).await;
+        test_table_shard_pin(Arc::clone(&catalog)).await;
         test_column(Arc::clone(&catalog)).await;
         test_shards(Arc::clone(&catalog)).await;
+        test_shard_lease(Arc::clone(&catalog)).await;
+        test_ingester_registrations(Arc::clone(&catalog)).await;
         test_partition(Arc::clone(&catalog)).await;
         test_tombstone(Arc::clone(&catalog)).await;
         test_tombstones_by_parquet_file(Arc::clone(&catalog)).await;
@@ -912,11 +1400,18 @@ pub(crate) mod test_helpers {
         test_most_cold_files_partitions(Arc::clone(&catalog)).await;
         test_recent_highest_throughput_partitions(Arc::clone(&catalog)).await;
         test_update_to_compaction_level_1(Arc::clone(&catalog)).await;
+        test_parquet_file_lineage(Arc::clone(&catalog)).await;
         test_processed_tombstones(Arc::clone(&catalog)).await;
+        test_operations(Arc::clone(&catalog)).await;
+        test_api_tokens(Arc::clone(&catalog)).await;
+        test_node_drains(Arc::clone(&catalog)).await;
+        test_partition_mark_cold(Arc::clone(&catalog)).await;
         test_list_by_partiton_not_to_delete(Arc::clone(&catalog)).await;
         test_txn_isolation(Arc::clone(&catalog)).await;
         test_txn_drop(Arc::clone(&catalog)).await;
         test_list_schemas(Arc::clone(&catalog)).await;
+        test_list_paged(Arc::clone(&catalog)).await;
+        test_list_schemas_paged(Arc::clone(&catalog)).await;
 
         let metrics = catalog.metrics();
         assert_metric_hit(&metrics, "topic_create_or_get");
@@ -928,6 +1423,22 @@ pub(crate) mod test_helpers {
         assert_metric_hit(&metrics, "partition_create_or_get");
         assert_metric_hit(&metrics, "tombstone_create_or_get");
         assert_metric_hit(&metrics, "parquet_create");
+        assert_metric_hit(&metrics, "txn_abort");
+
+        // test_namespace creates a duplicate namespace
+        let errors = metrics
+            .get_instrument::<Metric<DurationHistogram>>("catalog_op_duration")
+            .expect("failed to read metric")
+            .get_observer(&Attributes::from(&[
+                ("op", "namespace_create"),
+                ("result", "error"),
+            ]))
+            .expect("errors should be recorded")
+            .fetch();
+        assert!(
+            errors.sample_count() > 0,
+            "metric did not record any errors"
+        );
     }
 
     async fn test_setup(catalog: Arc<dyn Catalog>) {
@@ -948,6 +1459,9 @@ pub(crate) mod test_helpers {
         assert_eq!(k3, k);
         let k3 = topic_repo.get_by_name("asdf").await.unwrap();
         assert!(k3.is_none());
+
+        let topics = topic_repo.list().await.unwrap();
+        assert!(topics.contains(&k));
     }
 
     async fn test_query_pool(catalog: Arc<dyn Catalog>) {
@@ -961,6 +1475,90 @@ pub(crate) mod test_helpers {
         assert_eq!(q, q2);
     }
 
+    async fn test_query_pool_queriers(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let pool_a = repos.query_pools().create_or_get("pool_a").await.unwrap();
+        let pool_b = repos.query_pools().create_or_get("pool_b").await.unwrap();
+        let query_repo = repos.query_pools();
+
+        assert!(query_repo
+            .list_queriers(pool_a.id, Timestamp::new(0))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let q1 = query_repo
+            .record_querier_heartbeat(pool_a.id, "http://querier-1:8082", Timestamp::new(10))
+            .await
+            .unwrap();
+        assert_eq!(q1.address, "http://querier-1:8082");
+        assert_eq!(q1.query_pool_id, pool_a.id);
+        assert_eq!(q1.last_heartbeat, Timestamp::new(10));
+        let q2 = query_repo
+            .record_querier_heartbeat(pool_a.id, "http://querier-2:8082", Timestamp::new(20))
+            .await
+            .unwrap();
+        let q3 = query_repo
+            .record_querier_heartbeat(pool_b.id, "http://querier-3:8082", Timestamp::new(20))
+            .await
+            .unwrap();
+
+        let listed = query_repo
+            .list_queriers(pool_a.id, Timestamp::new(0))
+            .await
+            .unwrap();
+        assert_eq!(listed, vec![q1.clone(), q2.clone()]);
+
+        // Queriers that have not sent a recent heartbeat are excluded.
+        let listed = query_repo
+            .list_queriers(pool_a.id, Timestamp::new(15))
+            .await
+            .unwrap();
+        assert_eq!(listed, vec![q2.clone()]);
+
+        // A heartbeat refreshes the registration.
+        let q1 = query_repo
+            .record_querier_heartbeat(pool_a.id, "http://querier-1:8082", Timestamp::new(30))
+            .await
+            .unwrap();
+        let listed = query_repo
+            .list_queriers(pool_a.id, Timestamp::new(15))
+            .await
+            .unwrap();
+        assert_eq!(listed, vec![q1, q2.clone()]);
+
+        // A heartbeat for another pool moves the querier.
+        let moved = query_repo
+            .record_querier_heartbeat(pool_b.id, "http://querier-1:8082", Timestamp::new(40))
+            .await
+            .unwrap();
+        let listed = query_repo
+            .list_queriers(pool_a.id, Timestamp::new(0))
+            .await
+            .unwrap();
+        assert_eq!(listed, vec![q2]);
+        let listed = query_repo
+            .list_queriers(pool_b.id, Timestamp::new(0))
+            .await
+            .unwrap();
+        assert_eq!(listed, vec![moved, q3.clone()]);
+
+        query_repo
+            .remove_querier("http://querier-1:8082")
+            .await
+            .unwrap();
+        // Removing an unregistered querier is a no-op.
+        query_repo
+            .remove_querier("http://querier-1:8082")
+            .await
+            .unwrap();
+        let listed = query_repo
+            .list_queriers(pool_b.id, Timestamp::new(0))
+            .await
+            .unwrap();
+        assert_eq!(listed, vec![q3]);
+    }
+
     async fn test_namespace(catalog: Arc<dyn Catalog>) {
         let mut repos = catalog.repositories().await;
         let topic = repos.topics().create_or_get("foo").await.unwrap();
@@ -1098,52 +1696,499 @@ pub(crate) mod test_helpers {
             .expect("namespace should be updateable");
     }
 
-    async fn test_table(catalog: Arc<dyn Catalog>) {
+    async fn test_namespace_schema_generation(catalog: Arc<dyn Catalog>) {
         let mut repos = catalog.repositories().await;
         let topic = repos.topics().create_or_get("foo").await.unwrap();
         let pool = repos.query_pools().create_or_get("foo").await.unwrap();
         let namespace = repos
             .namespaces()
-            .create("namespace_table_test", None, topic.id, pool.id)
+            .create("test_namespace_generation", None, topic.id, pool.id)
             .await
             .unwrap();
+        assert_eq!(namespace.schema_generation, 0);
 
-        // test we can create or get a table
-        let t = repos
-            .tables()
-            .create_or_get("test_table", namespace.id)
+        let bumped = repos
+            .namespaces()
+            .increment_schema_generation(namespace.id, 0)
+            .await
+            .expect("generation should match");
+        assert_eq!(bumped.schema_generation, 1);
+
+        // A second writer that also read generation 0 loses the race.
+        let err = repos
+            .namespaces()
+            .increment_schema_generation(namespace.id, 0)
+            .await
+            .expect_err("stale generation should be rejected");
+        assert_matches!(
+            err,
+            Error::PreconditionViolation {
+                namespace_id,
+                expected: 0,
+                actual: 1,
+            } if namespace_id == namespace.id
+        );
+
+        // Changes to the schema-visible namespace settings also move the generation on.
+        let modified = repos
+            .namespaces()
+            .update_retention_period(&namespace.name, Some(42))
             .await
             .unwrap();
-        let tt = repos
-            .tables()
-            .create_or_get("test_table", namespace.id)
+        assert_eq!(modified.schema_generation, 2);
+        let modified = repos
+            .namespaces()
+            .update_column_limit(&namespace.name, 42)
             .await
             .unwrap();
-        assert!(t.id > TableId::new(0));
-        assert_eq!(t, tt);
+        assert_eq!(modified.schema_generation, 3);
 
-        // get by id
-        assert_eq!(t, repos.tables().get_by_id(t.id).await.unwrap().unwrap());
-        assert!(repos
-            .tables()
-            .get_by_id(TableId::new(i64::MAX))
+        let other_topic = repos.topics().create_or_get("bar").await.unwrap();
+        let modified = repos
+            .namespaces()
+            .update_topic(&namespace.name, other_topic.id)
             .await
-            .unwrap()
-            .is_none());
+            .unwrap();
+        assert_eq!(modified.topic_id, other_topic.id);
+        assert_eq!(modified.schema_generation, 4);
 
-        let tables = repos
-            .tables()
-            .list_by_namespace_id(namespace.id)
+        let schema = get_schema_by_id(namespace.id, repos.deref_mut())
             .await
             .unwrap();
-        assert_eq!(vec![t.clone()], tables);
+        assert_eq!(schema.generation, 4);
+        assert_eq!(schema.topic_id, other_topic.id);
 
-        // test we can create a table of the same name in a different namespace
-        let namespace2 = repos
+        let err = repos
             .namespaces()
-            .create("two", None, topic.id, pool.id)
+            .update_topic("does_not_exist", other_topic.id)
             .await
-            .unwrap();
+            .expect_err("namespace does not exist");
+        assert_matches!(err, Error::NamespaceNotFoundByName { .. });
+
+        let err = repos
+            .namespaces()
+            .increment_schema_generation(NamespaceId::new(i64::MAX), 0)
+            .await
+            .expect_err("namespace does not exist");
+        assert_matches!(err, Error::NamespaceNotFoundById { .. });
+    }
+
+    async fn test_namespace_query_ranges(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("namespace_query_ranges_test", None, topic.id, pool.id)
+            .await
+            .unwrap();
+        assert_eq!(namespace.default_query_range_ns, None);
+        assert_eq!(namespace.max_query_range_ns, None);
+
+        let modified = repos
+            .namespaces()
+            .update_query_ranges(&namespace.name, Some(3_600_000_000_000), Some(42))
+            .await
+            .unwrap();
+        assert_eq!(modified.default_query_range_ns, Some(3_600_000_000_000));
+        assert_eq!(modified.max_query_range_ns, Some(42));
+        assert_eq!(modified.schema_generation, namespace.schema_generation + 1);
+
+        let schema = get_schema_by_name(&namespace.name, repos.deref_mut())
+            .await
+            .unwrap();
+        assert_eq!(schema.default_query_range_ns, Some(3_600_000_000_000));
+        assert_eq!(schema.max_query_range_ns, Some(42));
+
+        let modified = repos
+            .namespaces()
+            .update_query_ranges(&namespace.name, None, None)
+            .await
+            .unwrap();
+        assert_eq!(modified.default_query_range_ns, None);
+        assert_eq!(modified.max_query_range_ns, None);
+
+        let err = repos
+            .namespaces()
+            .update_query_ranges("namespace_query_ranges_unknown", Some(1), None)
+            .await
+            .expect_err("namespace should not exist");
+        assert_matches!(err, Error::NamespaceNotFoundByName { .. });
+    }
+
+    async fn test_namespace_row_ttl(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("namespace_row_ttl_test", None, topic.id, pool.id)
+            .await
+            .unwrap();
+        assert_eq!(namespace.row_ttl_ns, None);
+        assert_eq!(namespace.row_ttl_column, None);
+
+        let modified = repos
+            .namespaces()
+            .update_row_ttl(
+                &namespace.name,
+                Some(60_000_000_000),
+                Some("expires".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(modified.row_ttl_ns, Some(60_000_000_000));
+        assert_eq!(modified.row_ttl_column.as_deref(), Some("expires"));
+        assert_eq!(modified.schema_generation, namespace.schema_generation + 1);
+
+        let schema = get_schema_by_name(&namespace.name, repos.deref_mut())
+            .await
+            .unwrap();
+        assert_eq!(schema.row_ttl_ns, Some(60_000_000_000));
+        assert_eq!(schema.row_ttl_column.as_deref(), Some("expires"));
+
+        let modified = repos
+            .namespaces()
+            .update_row_ttl(&namespace.name, None, None)
+            .await
+            .unwrap();
+        assert_eq!(modified.row_ttl_ns, None);
+        assert_eq!(modified.row_ttl_column, None);
+
+        let err = repos
+            .namespaces()
+            .update_row_ttl("namespace_row_ttl_unknown", Some(1), None)
+            .await
+            .expect_err("namespace should not exist");
+        assert_matches!(err, Error::NamespaceNotFoundByName { .. });
+    }
+
+    async fn test_namespace_partition_template(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("namespace_partition_template_test", None, topic.id, pool.id)
+            .await
+            .unwrap();
+        assert_eq!(namespace.partition_template, None);
+
+        let template = PartitionTemplate {
+            parts: vec![
+                data_types::TemplatePart::TimeFormat("%Y-%m".to_string()),
+                data_types::TemplatePart::TagValue("region".to_string()),
+            ],
+        };
+        let modified = repos
+            .namespaces()
+            .update_partition_template(&namespace.name, Some(&template))
+            .await
+            .unwrap();
+        assert_eq!(
+            modified.partition_template.as_deref(),
+            Some("time(%Y-%m)\ntag(region)")
+        );
+        assert_eq!(modified.schema_generation, namespace.schema_generation + 1);
+
+        let schema = get_schema_by_name(&namespace.name, repos.deref_mut())
+            .await
+            .unwrap();
+        assert_eq!(schema.partition_template, Some(template));
+
+        let modified = repos
+            .namespaces()
+            .update_partition_template(&namespace.name, None)
+            .await
+            .unwrap();
+        assert_eq!(modified.partition_template, None);
+
+        let err = repos
+            .namespaces()
+            .update_partition_template("namespace_partition_template_unknown", None)
+            .await
+            .expect_err("namespace should not exist");
+        assert_matches!(err, Error::NamespaceNotFoundByName { .. });
+    }
+
+    async fn test_namespace_usage(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace_1 = repos
+            .namespaces()
+            .create("namespace_usage_test_1", None, topic.id, pool.id)
+            .await
+            .unwrap();
+        let namespace_2 = repos
+            .namespaces()
+            .create("namespace_usage_test_2", None, topic.id, pool.id)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repos.namespaces().get_usage(namespace_1.id).await.unwrap(),
+            None
+        );
+
+        // Usage is added up.
+        let usage = NamespaceUsage {
+            rows_written: 10,
+            bytes_written: 100,
+            ..NamespaceUsage::new(namespace_1.id)
+        };
+        repos.namespaces().add_usage(&usage).await.unwrap();
+        let usage = NamespaceUsage {
+            rows_written: 1,
+            queries_executed: 2,
+            bytes_scanned: 1000,
+            ..NamespaceUsage::new(namespace_1.id)
+        };
+        repos.namespaces().add_usage(&usage).await.unwrap();
+        let usage_1 = NamespaceUsage {
+            rows_written: 11,
+            bytes_written: 100,
+            queries_executed: 2,
+            bytes_scanned: 1000,
+            ..NamespaceUsage::new(namespace_1.id)
+        };
+        assert_eq!(
+            repos.namespaces().get_usage(namespace_1.id).await.unwrap(),
+            Some(usage_1)
+        );
+
+        let usage_2 = NamespaceUsage {
+            queries_executed: 1,
+            ..NamespaceUsage::new(namespace_2.id)
+        };
+        repos.namespaces().add_usage(&usage_2).await.unwrap();
+
+        let listed: Vec<_> = repos
+            .namespaces()
+            .list_usage()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|u| [namespace_1.id, namespace_2.id].contains(&u.namespace_id))
+            .collect();
+        assert_eq!(listed, vec![usage_1, usage_2]);
+
+        // Usage can only be recorded for existing namespaces.
+        repos
+            .namespaces()
+            .add_usage(&NamespaceUsage::new(NamespaceId::new(i64::MAX)))
+            .await
+            .unwrap_err();
+    }
+
+    async fn test_producer_sequence(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace_1 = repos
+            .namespaces()
+            .create(
+                "namespace_producer_sequence_test_1",
+                None,
+                topic.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let namespace_2 = repos
+            .namespaces()
+            .create(
+                "namespace_producer_sequence_test_2",
+                None,
+                topic.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            repos
+                .namespaces()
+                .get_producer_sequence(namespace_1.id, "gateway")
+                .await
+                .unwrap(),
+            None
+        );
+
+        for (current, new, swapped) in [
+            (None, Some(5), true),
+            // The sequence number is only swapped if it is the current one.
+            (None, Some(6), false),
+            (Some(4), Some(6), false),
+            (Some(5), Some(7), true),
+            (Some(5), None, false),
+            (None, None, false),
+        ] {
+            let got = repos
+                .namespaces()
+                .compare_and_swap_producer_sequence(namespace_1.id, "gateway", current, new)
+                .await
+                .unwrap();
+            assert_eq!(got, swapped, "{current:?} -> {new:?}");
+        }
+        assert_eq!(
+            repos
+                .namespaces()
+                .get_producer_sequence(namespace_1.id, "gateway")
+                .await
+                .unwrap(),
+            Some(7)
+        );
+
+        // Sequence numbers are per producer and namespace.
+        assert!(repos
+            .namespaces()
+            .compare_and_swap_producer_sequence(namespace_2.id, "gateway", None, Some(1))
+            .await
+            .unwrap());
+        assert_eq!(
+            repos
+                .namespaces()
+                .get_producer_sequence(namespace_1.id, "other")
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            repos
+                .namespaces()
+                .get_producer_sequence(namespace_2.id, "gateway")
+                .await
+                .unwrap(),
+            Some(1)
+        );
+
+        // Removing the sequence number.
+        assert!(repos
+            .namespaces()
+            .compare_and_swap_producer_sequence(namespace_2.id, "gateway", Some(1), None)
+            .await
+            .unwrap());
+        assert!(repos
+            .namespaces()
+            .compare_and_swap_producer_sequence(namespace_2.id, "gateway", None, None)
+            .await
+            .unwrap());
+
+        // Sequence numbers can only be recorded for existing namespaces.
+        repos
+            .namespaces()
+            .compare_and_swap_producer_sequence(
+                NamespaceId::new(i64::MAX),
+                "gateway",
+                None,
+                Some(1),
+            )
+            .await
+            .unwrap_err();
+    }
+
+    async fn test_table_shard_pin(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("namespace_table_shard_pin_test", None, topic.id, pool.id)
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let shard_1 = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(1))
+            .await
+            .unwrap();
+        let shard_2 = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(2))
+            .await
+            .unwrap();
+
+        assert_eq!(repos.tables().get_shard_pin(table.id).await.unwrap(), None);
+
+        let pinned = repos
+            .tables()
+            .create_or_get_shard_pin(table.id, shard_1.id)
+            .await
+            .unwrap();
+        assert_eq!(pinned, shard_1.id);
+
+        // An existing pin is never changed.
+        let pinned = repos
+            .tables()
+            .create_or_get_shard_pin(table.id, shard_2.id)
+            .await
+            .unwrap();
+        assert_eq!(pinned, shard_1.id);
+        assert_eq!(
+            repos.tables().get_shard_pin(table.id).await.unwrap(),
+            Some(shard_1.id)
+        );
+
+        // Other tables are unaffected.
+        let other = repos
+            .tables()
+            .create_or_get("other_table", namespace.id)
+            .await
+            .unwrap();
+        assert_eq!(repos.tables().get_shard_pin(other.id).await.unwrap(), None);
+    }
+
+    async fn test_table(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("namespace_table_test", None, topic.id, pool.id)
+            .await
+            .unwrap();
+
+        // test we can create or get a table
+        let t = repos
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let tt = repos
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        assert!(t.id > TableId::new(0));
+        assert_eq!(t, tt);
+
+        // get by id
+        assert_eq!(t, repos.tables().get_by_id(t.id).await.unwrap().unwrap());
+        assert!(repos
+            .tables()
+            .get_by_id(TableId::new(i64::MAX))
+            .await
+            .unwrap()
+            .is_none());
+
+        let tables = repos
+            .tables()
+            .list_by_namespace_id(namespace.id)
+            .await
+            .unwrap();
+        assert_eq!(vec![t.clone()], tables);
+
+        // test we can create a table of the same name in a different namespace
+        let namespace2 = repos
+            .namespaces()
+            .create("two", None, topic.id, pool.id)
+            .await
+            .unwrap();
         assert_ne!(namespace, namespace2);
         let test_table = repos
             .tables()
@@ -1437,6 +2482,178 @@ pub(crate) mod test_helpers {
         assert!(shard.is_none());
     }
 
+    async fn test_ingester_registrations(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic_a = repos.topics().create_or_get("ingesters_a").await.unwrap();
+        let topic_b = repos.topics().create_or_get("ingesters_b").await.unwrap();
+        let shards = repos.shards();
+
+        // A new registration has no shards assigned.
+        let i1 = shards
+            .record_ingester_heartbeat(topic_a.id, "http://ingester-1:8083", Timestamp::new(10))
+            .await
+            .unwrap();
+        assert_eq!(i1.address, "http://ingester-1:8083");
+        assert_eq!(i1.topic_id, topic_a.id);
+        assert!(i1.shard_indexes.is_empty());
+        assert_eq!(i1.last_heartbeat, Timestamp::new(10));
+        let i0 = shards
+            .record_ingester_heartbeat(topic_a.id, "http://ingester-0:8083", Timestamp::new(20))
+            .await
+            .unwrap();
+        shards
+            .record_ingester_heartbeat(topic_b.id, "http://ingester-2:8083", Timestamp::new(20))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            shards.list_ingesters(topic_a.id).await.unwrap(),
+            vec![i0.clone(), i1]
+        );
+
+        // Shards are assigned in ascending order, and kept by heartbeats.
+        shards
+            .assign_shards(
+                "http://ingester-1:8083",
+                &[ShardIndex::new(3), ShardIndex::new(1)],
+            )
+            .await
+            .unwrap();
+        let i1 = shards
+            .record_ingester_heartbeat(topic_a.id, "http://ingester-1:8083", Timestamp::new(30))
+            .await
+            .unwrap();
+        assert_eq!(
+            i1.shard_indexes,
+            vec![ShardIndex::new(1), ShardIndex::new(3)]
+        );
+        assert_eq!(i1.last_heartbeat, Timestamp::new(30));
+        assert_eq!(
+            shards.list_ingesters(topic_a.id).await.unwrap(),
+            vec![i0, i1]
+        );
+
+        // Assigning shards to an unregistered ingester does nothing.
+        shards
+            .assign_shards("http://ingester-3:8083", &[ShardIndex::new(2)])
+            .await
+            .unwrap();
+        assert_eq!(shards.list_ingesters(topic_a.id).await.unwrap().len(), 2);
+
+        // A heartbeat for another topic moves the ingester and drops its shards.
+        let i1 = shards
+            .record_ingester_heartbeat(topic_b.id, "http://ingester-1:8083", Timestamp::new(40))
+            .await
+            .unwrap();
+        assert_eq!(i1.topic_id, topic_b.id);
+        assert!(i1.shard_indexes.is_empty());
+        assert_eq!(shards.list_ingesters(topic_a.id).await.unwrap().len(), 1);
+
+        shards
+            .remove_ingester("http://ingester-1:8083")
+            .await
+            .unwrap();
+        shards
+            .remove_ingester("http://ingester-1:8083")
+            .await
+            .unwrap();
+        let addresses: Vec<_> = shards
+            .list_ingesters(topic_b.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|i| i.address)
+            .collect();
+        assert_eq!(addresses, vec!["http://ingester-2:8083"]);
+    }
+
+    async fn test_shard_lease(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("lease_test").await.unwrap();
+        let shard = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(1))
+            .await
+            .unwrap();
+        let shards = repos.shards();
+        let minute = Duration::from_secs(60);
+
+        // An unheld lease is acquired.
+        let lease = shards
+            .acquire_lease(shard.id, "ingester-1", minute)
+            .await
+            .unwrap();
+        assert_eq!(lease.shard_id, shard.id);
+        assert_eq!(lease.holder, "ingester-1");
+        assert_eq!(lease.epoch, 1);
+        let expires_at = lease.expires_at;
+        shards.check_lease(shard.id, "ingester-1", 1).await.unwrap();
+        assert_eq!(
+            shards.list_held_leases(topic.id).await.unwrap(),
+            vec![lease.clone()]
+        );
+
+        // Another ingester cannot take over a lease before it expires.
+        let lease = shards
+            .acquire_lease(shard.id, "ingester-2", minute)
+            .await
+            .unwrap();
+        assert_eq!(lease.holder, "ingester-1");
+        assert_eq!(lease.expires_at, expires_at);
+        assert_matches!(
+            shards.check_lease(shard.id, "ingester-2", 1).await,
+            Err(Error::LeaseLost { .. })
+        );
+
+        // The holder renews its lease, in the same epoch. Renewing it for no time lets it
+        // expire.
+        let lease = shards
+            .acquire_lease(shard.id, "ingester-1", Duration::ZERO)
+            .await
+            .unwrap();
+        assert_eq!(lease.holder, "ingester-1");
+        assert_eq!(lease.epoch, 1);
+        assert_matches!(
+            shards.check_lease(shard.id, "ingester-1", 1).await,
+            Err(Error::LeaseLost { .. })
+        );
+
+        // An expired lease is taken over, in a new epoch.
+        let lease = shards
+            .acquire_lease(shard.id, "ingester-2", minute)
+            .await
+            .unwrap();
+        assert_eq!(lease.holder, "ingester-2");
+        assert_eq!(lease.epoch, 2);
+        shards.check_lease(shard.id, "ingester-2", 2).await.unwrap();
+
+        // Only the holder releases a lease.
+        shards.release_lease(shard.id, "ingester-1").await.unwrap();
+        let lease = shards
+            .acquire_lease(shard.id, "ingester-1", minute)
+            .await
+            .unwrap();
+        assert_eq!(lease.holder, "ingester-2");
+
+        // A released lease is acquired in a new epoch, even by its previous holder.
+        shards.release_lease(shard.id, "ingester-2").await.unwrap();
+        assert!(shards.list_held_leases(topic.id).await.unwrap().is_empty());
+        assert_matches!(
+            shards.check_lease(shard.id, "ingester-2", 2).await,
+            Err(Error::LeaseLost { .. })
+        );
+        let lease = shards
+            .acquire_lease(shard.id, "ingester-2", minute)
+            .await
+            .unwrap();
+        assert_eq!(lease.holder, "ingester-2");
+        assert_eq!(lease.epoch, 3);
+        assert_matches!(
+            shards.check_lease(shard.id, "ingester-2", 2).await,
+            Err(Error::LeaseLost { .. })
+        );
+    }
+
     async fn test_partition(catalog: Arc<dyn Catalog>) {
         let mut repos = catalog.repositories().await;
         let topic = repos.topics().create_or_get("foo").await.unwrap();
@@ -2252,6 +3469,32 @@ pub(crate) mod test_helpers {
             .unwrap();
         assert_eq!(files, vec![other_file.clone()]);
 
+        // test list_by_table_and_time_range, other_file covers 50 to 60
+        for (min_time, max_time, expected) in [
+            (0, 49, vec![]),
+            (0, 50, vec![other_file.clone()]),
+            (55, 56, vec![other_file.clone()]),
+            (60, 100, vec![other_file.clone()]),
+            (61, 100, vec![]),
+        ] {
+            let files = repos
+                .parquet_files()
+                .list_by_table_and_time_range(
+                    other_table.id,
+                    Timestamp::new(min_time),
+                    Timestamp::new(max_time),
+                )
+                .await
+                .unwrap();
+            assert_eq!(files, expected, "min_time={min_time} max_time={max_time}");
+        }
+        let files = repos
+            .parquet_files()
+            .list_by_table_and_time_range(table.id, Timestamp::new(0), Timestamp::new(100))
+            .await
+            .unwrap();
+        assert_eq!(files, vec![]);
+
         // test list_by_namespace_not_to_delete
         let namespace2 = repos
             .namespaces()
@@ -3790,32 +5033,178 @@ pub(crate) mod test_helpers {
             .unwrap();
         repos
             .parquet_files()
-            .update_compaction_level(&[level1_file.id], CompactionLevel::FileNonOverlapped)
+            .update_compaction_level(&[level1_file.id], CompactionLevel::FileNonOverlapped)
+            .await
+            .unwrap();
+        level1_file.compaction_level = CompactionLevel::FileNonOverlapped;
+
+        let other_partition_params = ParquetFileParams {
+            partition_id: partition2.id,
+            object_store_id: Uuid::new_v4(),
+            ..parquet_file_params.clone()
+        };
+        let _partition2_file = repos
+            .parquet_files()
+            .create(other_partition_params)
+            .await
+            .unwrap();
+
+        let files = repos
+            .parquet_files()
+            .list_by_partition_not_to_delete(partition.id)
+            .await
+            .unwrap();
+        // not asserting against a vector literal to guard against flakiness due to uncertain
+        // ordering of SQL query in postgres impl
+        assert_eq!(files.len(), 2);
+        assert_matches!(files.iter().find(|f| f.id == parquet_file.id), Some(_));
+        assert_matches!(files.iter().find(|f| f.id == level1_file.id), Some(_));
+    }
+
+    async fn test_parquet_file_lineage(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create(
+                "namespace_parquet_file_lineage_test",
+                None,
+                topic.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let other_namespace = repos
+            .namespaces()
+            .create(
+                "namespace_parquet_file_lineage_test_other",
+                None,
+                topic.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("lineage_table", namespace.id)
+            .await
+            .unwrap();
+        let shard = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(1000))
+            .await
+            .unwrap();
+        let partition = repos
+            .partitions()
+            .create_or_get("one".into(), shard.id, table.id)
+            .await
+            .unwrap();
+
+        let parquet_file_params = ParquetFileParams {
+            shard_id: shard.id,
+            namespace_id: namespace.id,
+            table_id: partition.table_id,
+            partition_id: partition.id,
+            object_store_id: Uuid::new_v4(),
+            max_sequence_number: SequenceNumber::new(140),
+            min_time: Timestamp::new(1),
+            max_time: Timestamp::new(10),
+            file_size_bytes: 1337,
+            row_count: 0,
+            compaction_level: CompactionLevel::Initial,
+            created_at: Timestamp::new(1),
+            column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+        };
+        let input_1 = repos
+            .parquet_files()
+            .create(parquet_file_params.clone())
+            .await
+            .unwrap();
+        let input_2 = repos
+            .parquet_files()
+            .create(ParquetFileParams {
+                object_store_id: Uuid::new_v4(),
+                ..parquet_file_params.clone()
+            })
+            .await
+            .unwrap();
+        let compacted = repos
+            .parquet_files()
+            .create(ParquetFileParams {
+                object_store_id: Uuid::new_v4(),
+                compaction_level: CompactionLevel::FileNonOverlapped,
+                ..parquet_file_params.clone()
+            })
+            .await
+            .unwrap();
+
+        // Files persisted by the ingester have no lineage.
+        let lineage = repos
+            .parquet_files()
+            .list_lineage_by_namespace(namespace.id)
+            .await
+            .unwrap();
+        assert!(lineage.is_empty());
+
+        let inputs = [
+            (input_2.id, CompactionLevel::Initial),
+            (input_1.id, CompactionLevel::Initial),
+        ];
+        repos
+            .parquet_files()
+            .create_lineage(&compacted, &inputs)
+            .await
+            .unwrap();
+        // Recording the same lineage again is a no-op.
+        repos
+            .parquet_files()
+            .create_lineage(&compacted, &inputs)
+            .await
+            .unwrap();
+
+        let expected: Vec<_> = [input_1.id, input_2.id]
+            .into_iter()
+            .map(|input_parquet_file_id| ParquetFileLineage {
+                parquet_file_id: compacted.id,
+                compaction_level: CompactionLevel::FileNonOverlapped,
+                input_parquet_file_id,
+                input_compaction_level: CompactionLevel::Initial,
+                namespace_id: namespace.id,
+                table_id: table.id,
+            })
+            .collect();
+        let lineage = repos
+            .parquet_files()
+            .list_lineage_by_namespace(namespace.id)
+            .await
+            .unwrap();
+        assert_eq!(lineage, expected);
+
+        // The lineage outlives the files.
+        repos
+            .parquet_files()
+            .flag_for_delete(input_1.id)
             .await
             .unwrap();
-        level1_file.compaction_level = CompactionLevel::FileNonOverlapped;
-
-        let other_partition_params = ParquetFileParams {
-            partition_id: partition2.id,
-            object_store_id: Uuid::new_v4(),
-            ..parquet_file_params.clone()
-        };
-        let _partition2_file = repos
+        let older_than = Timestamp::new(
+            (catalog.time_provider().now() + Duration::from_secs(100)).timestamp_nanos(),
+        );
+        let deleted_files = repos.parquet_files().delete_old(older_than).await.unwrap();
+        assert!(deleted_files.iter().any(|f| f.id == input_1.id));
+        let lineage = repos
             .parquet_files()
-            .create(other_partition_params)
+            .list_lineage_by_namespace(namespace.id)
             .await
             .unwrap();
+        assert_eq!(lineage, expected);
 
-        let files = repos
+        let lineage = repos
             .parquet_files()
-            .list_by_partition_not_to_delete(partition.id)
+            .list_lineage_by_namespace(other_namespace.id)
             .await
             .unwrap();
-        // not asserting against a vector literal to guard against flakiness due to uncertain
-        // ordering of SQL query in postgres impl
-        assert_eq!(files.len(), 2);
-        assert_matches!(files.iter().find(|f| f.id == parquet_file.id), Some(_));
-        assert_matches!(files.iter().find(|f| f.id == level1_file.id), Some(_));
+        assert!(lineage.is_empty());
     }
 
     async fn test_update_to_compaction_level_1(catalog: Arc<dyn Catalog>) {
@@ -4120,6 +5509,392 @@ pub(crate) mod test_helpers {
         assert_eq!(count, 0);
     }
 
+    async fn test_operations(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let operations = repos.operations();
+
+        let op1 = operations
+            .create(
+                "persist",
+                "persist partition 1",
+                "ingester-1",
+                Timestamp::new(10),
+            )
+            .await
+            .unwrap();
+        assert_eq!(op1.kind, "persist");
+        assert_eq!(op1.description, "persist partition 1");
+        assert_eq!(op1.node, "ingester-1");
+        assert_eq!(op1.status, OperationStatus::Running);
+        assert_eq!(op1.created_at, Timestamp::new(10));
+        assert_eq!(op1.completed_at, None);
+        let op2 = operations
+            .create(
+                "persist",
+                "persist partition 2",
+                "ingester-1",
+                Timestamp::new(20),
+            )
+            .await
+            .unwrap();
+        let op3 = operations
+            .create(
+                "persist",
+                "persist partition 3",
+                "ingester-2",
+                Timestamp::new(20),
+            )
+            .await
+            .unwrap();
+        assert!(op1.id < op2.id);
+        assert!(op2.id < op3.id);
+
+        assert_eq!(
+            operations.get_by_id(op1.id).await.unwrap(),
+            Some(op1.clone())
+        );
+        assert_eq!(
+            operations
+                .get_by_id(OperationId::new(op3.id.get() + 1))
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            operations.list().await.unwrap(),
+            vec![op1.clone(), op2.clone(), op3.clone()]
+        );
+
+        operations
+            .complete(op1.id, OperationStatus::Success, Timestamp::new(30))
+            .await
+            .unwrap();
+        let op1 = operations.get_by_id(op1.id).await.unwrap().unwrap();
+        assert_eq!(op1.status, OperationStatus::Success);
+        assert_eq!(op1.completed_at, Some(Timestamp::new(30)));
+
+        // completed operations are not changed
+        operations
+            .complete(op1.id, OperationStatus::Error, Timestamp::new(40))
+            .await
+            .unwrap();
+        assert_eq!(
+            operations.get_by_id(op1.id).await.unwrap(),
+            Some(op1.clone())
+        );
+
+        // only the running operations of the node are interrupted
+        let interrupted = operations
+            .interrupt_running("ingester-1", Timestamp::new(50))
+            .await
+            .unwrap();
+        let op2 = Operation {
+            status: OperationStatus::Interrupted,
+            completed_at: Some(Timestamp::new(50)),
+            ..op2
+        };
+        assert_eq!(interrupted, vec![op2.clone()]);
+        assert!(operations
+            .interrupt_running("ingester-1", Timestamp::new(60))
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            operations.list().await.unwrap(),
+            vec![op1.clone(), op2.clone(), op3.clone()]
+        );
+
+        // running operations are never deleted
+        let deleted = operations
+            .delete_completed_before(Timestamp::new(50))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(
+            operations.list().await.unwrap(),
+            vec![op2.clone(), op3.clone()]
+        );
+        let deleted = operations
+            .delete_completed_before(Timestamp::new(100))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(operations.list().await.unwrap(), vec![op3.clone()]);
+
+        // ids are not reused
+        let op4 = operations
+            .create(
+                "persist",
+                "persist partition 4",
+                "ingester-1",
+                Timestamp::new(70),
+            )
+            .await
+            .unwrap();
+        assert!(op4.id > op3.id);
+
+        operations
+            .complete(op3.id, OperationStatus::Success, Timestamp::new(80))
+            .await
+            .unwrap();
+        operations
+            .complete(op4.id, OperationStatus::Success, Timestamp::new(80))
+            .await
+            .unwrap();
+        operations
+            .delete_completed_before(Timestamp::new(100))
+            .await
+            .unwrap();
+        assert!(operations.list().await.unwrap().is_empty());
+    }
+
+    async fn test_api_tokens(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("namespace_api_token_test", None, topic.id, pool.id)
+            .await
+            .unwrap();
+
+        let tokens = repos.api_tokens();
+        let t1 = tokens
+            .create(
+                "hash1",
+                Some(namespace.id),
+                ApiTokenPermission::Write,
+                "writer",
+                Timestamp::new(10),
+            )
+            .await
+            .unwrap();
+        assert_eq!(t1.token_hash, "hash1");
+        assert_eq!(t1.namespace_id, Some(namespace.id));
+        assert_eq!(t1.permission, ApiTokenPermission::Write);
+        assert_eq!(t1.description, "writer");
+        assert_eq!(t1.created_at, Timestamp::new(10));
+        assert_eq!(t1.revoked_at, None);
+        let t2 = tokens
+            .create(
+                "hash2",
+                None,
+                ApiTokenPermission::Admin,
+                "admin",
+                Timestamp::new(20),
+            )
+            .await
+            .unwrap();
+        assert!(t1.id < t2.id);
+
+        // token hashes are unique
+        let err = tokens
+            .create(
+                "hash1",
+                None,
+                ApiTokenPermission::Read,
+                "reader",
+                Timestamp::new(30),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ApiTokenExists));
+
+        assert_eq!(tokens.get_by_hash("hash1").await.unwrap(), Some(t1.clone()));
+        assert_eq!(tokens.get_by_hash("hash3").await.unwrap(), None);
+        assert_eq!(tokens.list().await.unwrap(), vec![t1.clone(), t2.clone()]);
+
+        let revoked = tokens
+            .revoke(t1.id, Timestamp::new(40))
+            .await
+            .unwrap()
+            .expect("token exists");
+        let t1 = ApiToken {
+            revoked_at: Some(Timestamp::new(40)),
+            ..t1
+        };
+        assert_eq!(revoked, t1);
+        assert!(t1.is_revoked());
+
+        // revoked tokens are not changed
+        assert_eq!(
+            tokens.revoke(t1.id, Timestamp::new(50)).await.unwrap(),
+            Some(t1.clone())
+        );
+        assert_eq!(tokens.get_by_hash("hash1").await.unwrap(), Some(t1.clone()));
+        assert_eq!(
+            tokens
+                .revoke(ApiTokenId::new(t2.id.get() + 1), Timestamp::new(50))
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(tokens.list().await.unwrap(), vec![t1, t2]);
+    }
+
+    async fn test_node_drains(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let drains = repos.node_drains();
+
+        assert_eq!(
+            drains
+                .get_by_address("http://ingester-1:8083")
+                .await
+                .unwrap(),
+            None
+        );
+
+        let d1 = drains
+            .set_state(
+                "http://ingester-1:8083",
+                DrainState::Active,
+                Timestamp::new(10),
+            )
+            .await
+            .unwrap();
+        assert_eq!(d1.address, "http://ingester-1:8083");
+        assert_eq!(d1.state, DrainState::Active);
+        assert_eq!(d1.updated_at, Timestamp::new(10));
+        let d2 = drains
+            .set_state(
+                "http://ingester-0:8083",
+                DrainState::Draining,
+                Timestamp::new(20),
+            )
+            .await
+            .unwrap();
+
+        // setting the state again updates it
+        let d1 = drains
+            .set_state(
+                "http://ingester-1:8083",
+                DrainState::Drained,
+                Timestamp::new(30),
+            )
+            .await
+            .unwrap();
+        assert_eq!(d1.state, DrainState::Drained);
+        assert_eq!(d1.updated_at, Timestamp::new(30));
+
+        assert_eq!(
+            drains
+                .get_by_address("http://ingester-1:8083")
+                .await
+                .unwrap(),
+            Some(d1.clone())
+        );
+        assert_eq!(drains.list().await.unwrap(), vec![d2, d1]);
+    }
+
+    async fn test_partition_mark_cold(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create(
+                "namespace_partition_mark_cold_test",
+                None,
+                topic.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("cold_table", namespace.id)
+            .await
+            .unwrap();
+        let shard = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(1001))
+            .await
+            .unwrap();
+        let partition = repos
+            .partitions()
+            .create_or_get("one".into(), shard.id, table.id)
+            .await
+            .unwrap();
+        assert_eq!(partition.cold_compacted_at, None);
+
+        let parquet_file_params = ParquetFileParams {
+            shard_id: shard.id,
+            namespace_id: namespace.id,
+            table_id: partition.table_id,
+            partition_id: partition.id,
+            object_store_id: Uuid::new_v4(),
+            max_sequence_number: SequenceNumber::new(140),
+            min_time: Timestamp::new(1),
+            max_time: Timestamp::new(10),
+            file_size_bytes: 1337,
+            row_count: 0,
+            compaction_level: CompactionLevel::FileNonOverlapped,
+            created_at: Timestamp::new(1),
+            column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+        };
+        let level_1_file = repos
+            .parquet_files()
+            .create(parquet_file_params.clone())
+            .await
+            .unwrap();
+        let level_2_params = ParquetFileParams {
+            object_store_id: Uuid::new_v4(),
+            compaction_level: CompactionLevel::Final,
+            ..parquet_file_params
+        };
+        repos.parquet_files().create(level_2_params).await.unwrap();
+
+        // a partition with files below the final level is not cold
+        let marked = repos
+            .partitions()
+            .mark_cold(partition.id, Timestamp::new(100))
+            .await
+            .unwrap();
+        assert!(!marked);
+
+        // ... which deleted files do not count towards
+        repos
+            .parquet_files()
+            .flag_for_delete(level_1_file.id)
+            .await
+            .unwrap();
+        let marked = repos
+            .partitions()
+            .mark_cold(partition.id, Timestamp::new(100))
+            .await
+            .unwrap();
+        assert!(marked);
+        let got = repos
+            .partitions()
+            .get_by_id(partition.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(got.cold_compacted_at, Some(Timestamp::new(100)));
+
+        // persisting data to the partition makes it hot again
+        repos
+            .partitions()
+            .update_persisted_sequence_number(partition.id, SequenceNumber::new(150))
+            .await
+            .unwrap();
+        let got = repos
+            .partitions()
+            .get_by_id(partition.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(got.cold_compacted_at, None);
+
+        // marking an unknown partition has no effect
+        let marked = repos
+            .partitions()
+            .mark_cold(PartitionId::new(i64::MAX), Timestamp::new(100))
+            .await
+            .unwrap();
+        assert!(!marked);
+    }
+
     async fn test_txn_isolation(catalog: Arc<dyn Catalog>) {
         let barrier = Arc::new(tokio::sync::Barrier::new(2));
 
@@ -4253,6 +6028,54 @@ pub(crate) mod test_helpers {
         assert!(got.contains(&ns2), "{:#?}\n\nwant{:#?}", got, &ns2);
     }
 
+    async fn test_list_paged(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+
+        // walking the pages yields exactly what list() does, in ID order
+        let mut want = repos.namespaces().list().await.unwrap();
+        want.sort_by_key(|n| n.id);
+        let mut got = vec![];
+        let mut after = None;
+        loop {
+            let page = repos.namespaces().list_paged(after, 2).await.unwrap();
+            assert!(page.len() <= 2);
+            after = page.last().map(|n| n.id).or(after);
+            let last_page = page.len() < 2;
+            got.extend(page);
+            if last_page {
+                break;
+            }
+        }
+        assert_eq!(got, want);
+    }
+
+    async fn test_list_schemas_paged(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+
+        let ns1 = populate_namespace(
+            repos.deref_mut(),
+            "ns_paged1",
+            "cpu,tag=1 field=1i\nanother,tag=1 field=1.0",
+        )
+        .await;
+        let ns2 = populate_namespace(repos.deref_mut(), "ns_paged2", "mem,tag=1 field=1u").await;
+        let namespaces = repos.namespaces().list().await.unwrap();
+
+        // Otherwise the in-mem catalog deadlocks.... (but not postgres)
+        drop(repos);
+
+        // a page size that does not divide the number of namespaces
+        let got = list_schemas_paged(&*catalog, 3)
+            .await
+            .try_collect::<Vec<_>>()
+            .await
+            .expect("should be able to list the schemas");
+
+        assert_eq!(got.len(), namespaces.len());
+        assert!(got.contains(&ns1), "{:#?}\n\nwant{:#?}", got, &ns1);
+        assert!(got.contains(&ns2), "{:#?}\n\nwant{:#?}", got, &ns2);
+    }
+
     fn assert_metric_hit(metrics: &metric::Registry, name: &'static str) {
         let histogram = metrics
             .get_instrument::<Metric<DurationHistogram>>("catalog_op_duration")
@@ -36,10 +36,18 @@ pub const DEFAULT_MAX_COLUMNS_PER_TABLE: i32 = 200;
 pub const DEFAULT_RETENTION_PERIOD: Option<i64> = None;
 
 /// A string value representing an infinite retention policy.
+pub mod authz;
+pub mod doctor;
+pub mod fault;
 pub mod interface;
 pub mod mem;
 pub mod metrics;
 pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod usage;
+
+mod migrate;
 
 /// An [`crate::interface::Error`] scoped to a single table for schema validation errors.
 #[derive(Debug, Error)]
@@ -98,6 +106,77 @@ where
     }
 }
 
+/// The changes [`validate_or_insert_schema`] would make to the schema of a
+/// single table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableSchemaChanges {
+    /// True if the table does not exist yet.
+    pub new_table: bool,
+    /// The columns that do not exist yet, and the type they would be created
+    /// with.
+    pub new_columns: BTreeMap<String, ColumnType>,
+}
+
+/// Validate `tables` against `schema` in the same way as
+/// [`validate_or_insert_schema`], but return the tables and columns that
+/// would be created instead of creating them.
+///
+/// Only tables that would change are included in the returned map.
+///
+/// As the catalog is not consulted, columns added to the catalog after
+/// `schema` was read are reported as new, and the catalog-enforced table and
+/// column limits are not checked.
+pub fn validate_schema<'a, T>(
+    tables: T,
+    schema: &NamespaceSchema,
+) -> Result<BTreeMap<String, TableSchemaChanges>, TableScopedError>
+where
+    T: IntoIterator<Item = (&'a str, &'a MutableBatch)>,
+{
+    let mut changes = BTreeMap::new();
+
+    for (table_name, batch) in tables {
+        let table = schema.tables.get(table_name);
+        let mut table_changes = TableSchemaChanges {
+            new_table: table.is_none(),
+            ..Default::default()
+        };
+
+        // All new tables get a time column.
+        if table.is_none() {
+            table_changes
+                .new_columns
+                .insert(TIME_COLUMN.to_string(), ColumnType::Time);
+        }
+
+        for (name, col) in batch.columns() {
+            match table.and_then(|t| t.columns.get(name.as_str())) {
+                Some(existing) if existing.matches_type(col.influx_type()) => {}
+                Some(existing) => {
+                    let e = ColumnTypeMismatchSnafu {
+                        name,
+                        existing: existing.column_type,
+                        new: col.influx_type(),
+                    }
+                    .build();
+                    return Err(TableScopedError(table_name.to_string(), e));
+                }
+                None => {
+                    table_changes
+                        .new_columns
+                        .insert(name.clone(), ColumnType::from(col.influx_type()));
+                }
+            }
+        }
+
+        if table_changes.new_table || !table_changes.new_columns.is_empty() {
+            changes.insert(table_name.to_string(), table_changes);
+        }
+    }
+
+    Ok(changes)
+}
+
 // &mut Cow is used to avoid a copy, so allow it
 #[allow(clippy::ptr_arg)]
 async fn validate_mutable_batch<R>(
@@ -293,17 +372,40 @@ mod tests {
                             let writes = mutable_batch_lp::lines_to_batches(lp.as_str(), 42)
                                 .expect("failed to build test writes from LP");
 
+                            // The changes a dry run predicts must be exactly those
+                            // made by the real validation.
+                            let predicted = validate_schema(writes.iter().map(|(k, v)| (k.as_str(), v)), &schema);
+
                             let got = validate_or_insert_schema(writes.iter().map(|(k, v)| (k.as_str(), v)), &schema, txn.deref_mut())
                                 .await;
 
                             match got {
                                 Err(TableScopedError(_, Error::ColumnTypeMismatch{ .. })) => {
+                                    assert!(predicted.is_err(), "dry run did not predict conflict");
                                     observed_conflict = true;
                                     schema
                                 },
                                 Err(e) => panic!("unexpected error: {}", e),
-                                Ok(Some(new_schema)) => new_schema,
-                                Ok(None) => schema,
+                                Ok(Some(new_schema)) => {
+                                    let predicted = predicted.expect("dry run predicted conflict");
+                                    for (table_name, table) in &new_schema.tables {
+                                        let old = schema.tables.get(table_name);
+                                        let added = table
+                                            .columns
+                                            .iter()
+                                            .filter(|(name, _)| old.map_or(true, |t| !t.columns.contains_key(*name)))
+                                            .map(|(name, c)| (name.clone(), c.column_type))
+                                            .collect::<BTreeMap<_, _>>();
+                                        let want = predicted.get(table_name).cloned().unwrap_or_default();
+                                        assert_eq!(want.new_table, old.is_none());
+                                        assert_eq!(want.new_columns, added);
+                                    }
+                                    new_schema
+                                },
+                                Ok(None) => {
+                                    assert!(predicted.expect("dry run predicted conflict").is_empty());
+                                    schema
+                                },
                             }
                         };
                     )+
@@ -1,33 +1,42 @@
 //! This module implements an in-memory implementation of the iox_catalog interface. It can be
 //! used for testing or for an IOx designed to run without catalog persistence.
+//!
+//! The schema of a [`MemCatalog`] can optionally be kept across restarts in a snapshot file, see
+//! [`MemCatalog::with_snapshot`].
+
+mod snapshot;
 
 use crate::{
     interface::{
-        sealed::TransactionFinalize, Catalog, ColumnRepo, ColumnTypeMismatchSnafu, Error,
-        NamespaceRepo, ParquetFileRepo, PartitionRepo, ProcessedTombstoneRepo, QueryPoolRepo,
-        RepoCollection, Result, ShardRepo, TableRepo, TombstoneRepo, TopicMetadataRepo,
-        Transaction,
+        sealed::TransactionFinalize, ApiTokenRepo, Catalog, ColumnRepo, ColumnTypeMismatchSnafu,
+        Error, MigrationStatus, NamespaceRepo, NodeDrainRepo, OperationRepo, ParquetFileRepo,
+        PartitionRepo, ProcessedTombstoneRepo, QueryPoolRepo, RepoCollection, Result, ShardRepo,
+        TableRepo, TombstoneRepo, TopicMetadataRepo, Transaction,
     },
     metrics::MetricDecorator,
     DEFAULT_MAX_COLUMNS_PER_TABLE, DEFAULT_MAX_TABLES,
 };
 use async_trait::async_trait;
 use data_types::{
-    Column, ColumnId, ColumnType, ColumnTypeCount, CompactionLevel, Namespace, NamespaceId,
-    ParquetFile, ParquetFileId, ParquetFileParams, Partition, PartitionId, PartitionKey,
-    PartitionParam, ProcessedTombstone, QueryPool, QueryPoolId, SequenceNumber, Shard, ShardId,
-    ShardIndex, SkippedCompaction, Table, TableId, TablePartition, Timestamp, Tombstone,
-    TombstoneId, TopicId, TopicMetadata,
+    ApiToken, ApiTokenId, ApiTokenPermission, Column, ColumnId, ColumnType, ColumnTypeCount,
+    CompactionLevel, DrainState, IngesterRegistration, Namespace, NamespaceId, NamespaceUsage,
+    NodeDrain, Operation, OperationId, OperationStatus, ParquetFile, ParquetFileId,
+    ParquetFileLineage, ParquetFileParams, Partition, PartitionId, PartitionKey, PartitionParam,
+    PartitionTemplate, ProcessedTombstone, QuerierRegistration, QueryPool, QueryPoolId,
+    SequenceNumber, Shard, ShardId, ShardIndex, ShardLease, SkippedCompaction, Table, TableId,
+    TablePartition, Timestamp, Tombstone, TombstoneId, TopicId, TopicMetadata,
 };
 use iox_time::{SystemProvider, TimeProvider};
 use observability_deps::tracing::warn;
 use snafu::ensure;
 use sqlx::types::Uuid;
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     convert::TryFrom,
     fmt::Formatter,
+    path::PathBuf,
     sync::Arc,
+    time::Duration,
 };
 use tokio::sync::{Mutex, OwnedMutexGuard};
 
@@ -37,6 +46,7 @@ pub struct MemCatalog {
     metrics: Arc<metric::Registry>,
     collections: Arc<Mutex<MemCollections>>,
     time_provider: Arc<dyn TimeProvider>,
+    snapshot_path: Option<PathBuf>,
 }
 
 impl MemCatalog {
@@ -46,13 +56,45 @@ impl MemCatalog {
             metrics,
             collections: Default::default(),
             time_provider: Arc::new(SystemProvider::new()),
+            snapshot_path: None,
+        }
+    }
+
+    /// Create a `MemCatalog` that keeps its schema in the snapshot file at `path`.
+    ///
+    /// The topics, query pools, namespaces, tables and columns are loaded from the file if it
+    /// exists, and written to it when the catalog is [shut down](Catalog::shutdown). Shards,
+    /// partitions, parquet files and all other records start out empty, so data that is not
+    /// replayed from the write buffer after a restart is no longer queryable.
+    pub fn with_snapshot(
+        metrics: Arc<metric::Registry>,
+        path: impl Into<PathBuf>,
+    ) -> Result<Self, Error> {
+        let path = path.into();
+        let collections = snapshot::load(&path)?;
+
+        Ok(Self {
+            metrics,
+            collections: Arc::new(Mutex::new(collections)),
+            time_provider: Arc::new(SystemProvider::new()),
+            snapshot_path: Some(path),
+        })
+    }
+
+    /// Use `time_provider` as the clock of the catalog, e.g. to expire shard leases in tests.
+    pub fn with_time_provider(self, time_provider: Arc<dyn TimeProvider>) -> Self {
+        Self {
+            time_provider,
+            ..self
         }
     }
 }
 
 impl std::fmt::Debug for MemCatalog {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("MemCatalog").finish_non_exhaustive()
+        f.debug_struct("MemCatalog")
+            .field("snapshot_path", &self.snapshot_path)
+            .finish_non_exhaustive()
     }
 }
 
@@ -60,15 +102,26 @@ impl std::fmt::Debug for MemCatalog {
 struct MemCollections {
     topics: Vec<TopicMetadata>,
     query_pools: Vec<QueryPool>,
+    querier_registrations: BTreeMap<String, QuerierRegistration>,
     namespaces: Vec<Namespace>,
     tables: Vec<Table>,
     columns: Vec<Column>,
     shards: Vec<Shard>,
     partitions: Vec<Partition>,
+    table_shard_pins: HashMap<TableId, ShardId>,
+    shard_leases: HashMap<ShardId, ShardLease>,
+    ingester_registrations: BTreeMap<String, IngesterRegistration>,
+    namespace_usage: BTreeMap<NamespaceId, NamespaceUsage>,
+    producer_sequences: BTreeMap<(NamespaceId, String), i64>,
     skipped_compactions: Vec<SkippedCompaction>,
     tombstones: Vec<Tombstone>,
     parquet_files: Vec<ParquetFile>,
+    parquet_file_lineage: Vec<ParquetFileLineage>,
     processed_tombstones: Vec<ProcessedTombstone>,
+    operations: Vec<Operation>,
+    last_operation_id: i64,
+    api_tokens: Vec<ApiToken>,
+    node_drains: BTreeMap<String, NodeDrain>,
 }
 
 #[derive(Debug)]
@@ -118,6 +171,16 @@ impl Catalog for MemCatalog {
         Ok(())
     }
 
+    async fn migration_status(&self) -> Result<MigrationStatus, Error> {
+        // there is no schema to migrate
+        Ok(MigrationStatus::default())
+    }
+
+    async fn migrate(&self, _target_version: Option<i64>) -> Result<(), Error> {
+        // nothing to do
+        Ok(())
+    }
+
     async fn start_transaction(&self) -> Result<Box<dyn Transaction>, Error> {
         let guard = Arc::clone(&self.collections).lock_owned().await;
         let stage = guard.clone();
@@ -152,6 +215,13 @@ impl Catalog for MemCatalog {
     fn time_provider(&self) -> Arc<dyn TimeProvider> {
         Arc::clone(&self.time_provider)
     }
+
+    async fn shutdown(&self) -> Result<(), Error> {
+        match &self.snapshot_path {
+            Some(path) => snapshot::save(path, &*self.collections.lock().await),
+            None => Ok(()),
+        }
+    }
 }
 
 #[async_trait]
@@ -229,6 +299,18 @@ impl RepoCollection for MemTxn {
     fn processed_tombstones(&mut self) -> &mut dyn ProcessedTombstoneRepo {
         self
     }
+
+    fn operations(&mut self) -> &mut dyn OperationRepo {
+        self
+    }
+
+    fn api_tokens(&mut self) -> &mut dyn ApiTokenRepo {
+        self
+    }
+
+    fn node_drains(&mut self) -> &mut dyn NodeDrainRepo {
+        self
+    }
 }
 
 #[async_trait]
@@ -257,6 +339,12 @@ impl TopicMetadataRepo for MemTxn {
         let topic = stage.topics.iter().find(|t| t.name == name).cloned();
         Ok(topic)
     }
+
+    async fn list(&mut self) -> Result<Vec<TopicMetadata>> {
+        let stage = self.stage();
+
+        Ok(stage.topics.clone())
+    }
 }
 
 #[async_trait]
@@ -278,6 +366,47 @@ impl QueryPoolRepo for MemTxn {
 
         Ok(pool.clone())
     }
+
+    async fn record_querier_heartbeat(
+        &mut self,
+        query_pool_id: QueryPoolId,
+        address: &str,
+        at: Timestamp,
+    ) -> Result<QuerierRegistration> {
+        let stage = self.stage();
+
+        let registration = QuerierRegistration {
+            address: address.to_string(),
+            query_pool_id,
+            last_heartbeat: at,
+        };
+        stage
+            .querier_registrations
+            .insert(address.to_string(), registration.clone());
+
+        Ok(registration)
+    }
+
+    async fn list_queriers(
+        &mut self,
+        query_pool_id: QueryPoolId,
+        heartbeat_after: Timestamp,
+    ) -> Result<Vec<QuerierRegistration>> {
+        let stage = self.stage();
+
+        Ok(stage
+            .querier_registrations
+            .values()
+            .filter(|r| r.query_pool_id == query_pool_id && r.last_heartbeat >= heartbeat_after)
+            .cloned()
+            .collect())
+    }
+
+    async fn remove_querier(&mut self, address: &str) -> Result<()> {
+        let stage = self.stage();
+        stage.querier_registrations.remove(address);
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -305,6 +434,12 @@ impl NamespaceRepo for MemTxn {
             max_tables: DEFAULT_MAX_TABLES,
             max_columns_per_table: DEFAULT_MAX_COLUMNS_PER_TABLE,
             retention_period_ns,
+            schema_generation: 0,
+            default_query_range_ns: None,
+            max_query_range_ns: None,
+            row_ttl_ns: None,
+            row_ttl_column: None,
+            partition_template: None,
         };
         stage.namespaces.push(namespace);
         Ok(stage.namespaces.last().unwrap().clone())
@@ -316,6 +451,23 @@ impl NamespaceRepo for MemTxn {
         Ok(stage.namespaces.clone())
     }
 
+    async fn list_paged(
+        &mut self,
+        after: Option<NamespaceId>,
+        limit: usize,
+    ) -> Result<Vec<Namespace>> {
+        let stage = self.stage();
+
+        // IDs are assigned in insertion order
+        Ok(stage
+            .namespaces
+            .iter()
+            .filter(|n| after.map_or(true, |after| n.id > after))
+            .take(limit)
+            .cloned()
+            .collect())
+    }
+
     async fn get_by_id(&mut self, id: NamespaceId) -> Result<Option<Namespace>> {
         let stage = self.stage();
 
@@ -346,6 +498,21 @@ impl NamespaceRepo for MemTxn {
         match stage.namespaces.iter_mut().find(|n| n.name == name) {
             Some(n) => {
                 n.max_columns_per_table = new_max;
+                n.schema_generation += 1;
+                Ok(n.clone())
+            }
+            None => Err(Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    async fn update_topic(&mut self, name: &str, topic_id: TopicId) -> Result<Namespace> {
+        let stage = self.stage();
+        match stage.namespaces.iter_mut().find(|n| n.name == name) {
+            Some(n) => {
+                n.topic_id = topic_id;
+                n.schema_generation += 1;
                 Ok(n.clone())
             }
             None => Err(Error::NamespaceNotFoundByName {
@@ -363,6 +530,65 @@ impl NamespaceRepo for MemTxn {
         match stage.namespaces.iter_mut().find(|n| n.name == name) {
             Some(n) => {
                 n.retention_period_ns = retention_period_ns;
+                n.schema_generation += 1;
+                Ok(n.clone())
+            }
+            None => Err(Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    async fn update_query_ranges(
+        &mut self,
+        name: &str,
+        default_query_range_ns: Option<i64>,
+        max_query_range_ns: Option<i64>,
+    ) -> Result<Namespace> {
+        let stage = self.stage();
+        match stage.namespaces.iter_mut().find(|n| n.name == name) {
+            Some(n) => {
+                n.default_query_range_ns = default_query_range_ns;
+                n.max_query_range_ns = max_query_range_ns;
+                n.schema_generation += 1;
+                Ok(n.clone())
+            }
+            None => Err(Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    async fn update_row_ttl(
+        &mut self,
+        name: &str,
+        row_ttl_ns: Option<i64>,
+        row_ttl_column: Option<String>,
+    ) -> Result<Namespace> {
+        let stage = self.stage();
+        match stage.namespaces.iter_mut().find(|n| n.name == name) {
+            Some(n) => {
+                n.row_ttl_ns = row_ttl_ns;
+                n.row_ttl_column = row_ttl_column;
+                n.schema_generation += 1;
+                Ok(n.clone())
+            }
+            None => Err(Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    async fn update_partition_template(
+        &mut self,
+        name: &str,
+        partition_template: Option<&PartitionTemplate>,
+    ) -> Result<Namespace> {
+        let stage = self.stage();
+        match stage.namespaces.iter_mut().find(|n| n.name == name) {
+            Some(n) => {
+                n.partition_template = partition_template.map(ToString::to_string);
+                n.schema_generation += 1;
                 Ok(n.clone())
             }
             None => Err(Error::NamespaceNotFoundByName {
@@ -370,6 +596,91 @@ impl NamespaceRepo for MemTxn {
             }),
         }
     }
+
+    async fn increment_schema_generation(
+        &mut self,
+        id: NamespaceId,
+        expected: i64,
+    ) -> Result<Namespace> {
+        let stage = self.stage();
+        match stage.namespaces.iter_mut().find(|n| n.id == id) {
+            Some(n) if n.schema_generation == expected => {
+                n.schema_generation += 1;
+                Ok(n.clone())
+            }
+            Some(n) => Err(Error::PreconditionViolation {
+                namespace_id: id,
+                expected,
+                actual: n.schema_generation,
+            }),
+            None => Err(Error::NamespaceNotFoundById { id }),
+        }
+    }
+
+    async fn add_usage(&mut self, usage: &NamespaceUsage) -> Result<()> {
+        let stage = self.stage();
+        let id = usage.namespace_id;
+        if !stage.namespaces.iter().any(|n| n.id == id) {
+            return Err(Error::NamespaceNotFoundById { id });
+        }
+
+        stage
+            .namespace_usage
+            .entry(id)
+            .or_insert_with(|| NamespaceUsage::new(id))
+            .add(usage);
+
+        Ok(())
+    }
+
+    async fn get_usage(&mut self, id: NamespaceId) -> Result<Option<NamespaceUsage>> {
+        let stage = self.stage();
+
+        Ok(stage.namespace_usage.get(&id).copied())
+    }
+
+    async fn list_usage(&mut self) -> Result<Vec<NamespaceUsage>> {
+        let stage = self.stage();
+
+        Ok(stage.namespace_usage.values().copied().collect())
+    }
+
+    async fn get_producer_sequence(
+        &mut self,
+        id: NamespaceId,
+        producer_id: &str,
+    ) -> Result<Option<i64>> {
+        let stage = self.stage();
+
+        Ok(stage
+            .producer_sequences
+            .get(&(id, producer_id.to_string()))
+            .copied())
+    }
+
+    async fn compare_and_swap_producer_sequence(
+        &mut self,
+        id: NamespaceId,
+        producer_id: &str,
+        current: Option<i64>,
+        new: Option<i64>,
+    ) -> Result<bool> {
+        let stage = self.stage();
+        if !stage.namespaces.iter().any(|n| n.id == id) {
+            return Err(Error::NamespaceNotFoundById { id });
+        }
+
+        let key = (id, producer_id.to_string());
+        if stage.producer_sequences.get(&key).copied() != current {
+            return Ok(false);
+        }
+        match new {
+            Some(new) => stage.producer_sequences.insert(key, new),
+            None => stage.producer_sequences.remove(&key),
+        };
+
+        Ok(true)
+    }
 }
 
 #[async_trait]
@@ -461,6 +772,20 @@ impl TableRepo for MemTxn {
         let stage = self.stage();
         Ok(stage.tables.clone())
     }
+
+    async fn get_shard_pin(&mut self, table_id: TableId) -> Result<Option<ShardId>> {
+        let stage = self.stage();
+        Ok(stage.table_shard_pins.get(&table_id).copied())
+    }
+
+    async fn create_or_get_shard_pin(
+        &mut self,
+        table_id: TableId,
+        shard_id: ShardId,
+    ) -> Result<ShardId> {
+        let stage = self.stage();
+        Ok(*stage.table_shard_pins.entry(table_id).or_insert(shard_id))
+    }
 }
 
 #[async_trait]
@@ -471,6 +796,7 @@ impl ColumnRepo for MemTxn {
         table_id: TableId,
         column_type: ColumnType,
     ) -> Result<Column> {
+        let created_at = Timestamp::from(self.time_provider.now());
         let stage = self.stage();
 
         // this block is just to ensure the mem impl correctly creates ColumnCreateLimitError in
@@ -532,6 +858,7 @@ impl ColumnRepo for MemTxn {
                     table_id,
                     name: name.to_string(),
                     column_type,
+                    created_at: Some(created_at),
                 };
                 stage.columns.push(column);
                 stage.columns.last().unwrap()
@@ -550,6 +877,7 @@ impl ColumnRepo for MemTxn {
         // check column limits when inserting many columns because it's complicated and expensive,
         // and for testing purposes the in-memory catalog needs to match its functionality.
 
+        let created_at = Timestamp::from(self.time_provider.now());
         let stage = self.stage();
 
         let out: Vec<_> = columns
@@ -577,6 +905,7 @@ impl ColumnRepo for MemTxn {
                             table_id,
                             name: column_name.to_string(),
                             column_type,
+                            created_at: Some(created_at),
                         };
                         stage.columns.push(new_column);
                         Ok(stage.columns.last().unwrap().clone())
@@ -733,6 +1062,137 @@ impl ShardRepo for MemTxn {
 
         Ok(())
     }
+
+    async fn acquire_lease(
+        &mut self,
+        shard_id: ShardId,
+        holder: &str,
+        duration: Duration,
+    ) -> Result<ShardLease> {
+        let now = self.time_provider.now();
+        let expires_at = Timestamp::from(now + duration);
+        let now = Timestamp::from(now);
+        let stage = self.stage();
+
+        let lease = stage
+            .shard_leases
+            .entry(shard_id)
+            .or_insert_with(|| ShardLease {
+                shard_id,
+                holder: holder.to_string(),
+                expires_at: Timestamp::new(0),
+                epoch: 0,
+            });
+        if lease.holder == holder && lease.expires_at > now {
+            lease.expires_at = expires_at;
+        } else if lease.expires_at <= now {
+            lease.holder = holder.to_string();
+            lease.expires_at = expires_at;
+            lease.epoch += 1;
+        }
+
+        Ok(lease.clone())
+    }
+
+    async fn release_lease(&mut self, shard_id: ShardId, holder: &str) -> Result<()> {
+        let stage = self.stage();
+
+        if let Some(lease) = stage
+            .shard_leases
+            .get_mut(&shard_id)
+            .filter(|l| l.holder == holder)
+        {
+            lease.expires_at = Timestamp::new(0);
+        }
+
+        Ok(())
+    }
+
+    async fn check_lease(&mut self, shard_id: ShardId, holder: &str, epoch: i64) -> Result<()> {
+        let now = Timestamp::from(self.time_provider.now());
+        let stage = self.stage();
+
+        match stage.shard_leases.get(&shard_id) {
+            Some(l) if l.holder == holder && l.epoch == epoch && l.expires_at > now => Ok(()),
+            _ => Err(Error::LeaseLost {
+                shard_id,
+                holder: holder.to_string(),
+                epoch,
+            }),
+        }
+    }
+
+    async fn list_held_leases(&mut self, topic_id: TopicId) -> Result<Vec<ShardLease>> {
+        let now = Timestamp::from(self.time_provider.now());
+        let stage = self.stage();
+
+        let mut leases: Vec<_> = stage
+            .shards
+            .iter()
+            .filter(|s| s.topic_id == topic_id)
+            .filter_map(|s| stage.shard_leases.get(&s.id))
+            .filter(|l| l.expires_at > now)
+            .cloned()
+            .collect();
+        leases.sort_by_key(|l| l.shard_id);
+        Ok(leases)
+    }
+
+    async fn record_ingester_heartbeat(
+        &mut self,
+        topic_id: TopicId,
+        address: &str,
+        at: Timestamp,
+    ) -> Result<IngesterRegistration> {
+        let stage = self.stage();
+
+        let shard_indexes = stage
+            .ingester_registrations
+            .get(address)
+            .filter(|r| r.topic_id == topic_id)
+            .map(|r| r.shard_indexes.clone())
+            .unwrap_or_default();
+        let registration = IngesterRegistration {
+            address: address.to_string(),
+            topic_id,
+            shard_indexes,
+            last_heartbeat: at,
+        };
+        stage
+            .ingester_registrations
+            .insert(address.to_string(), registration.clone());
+
+        Ok(registration)
+    }
+
+    async fn list_ingesters(&mut self, topic_id: TopicId) -> Result<Vec<IngesterRegistration>> {
+        let stage = self.stage();
+
+        Ok(stage
+            .ingester_registrations
+            .values()
+            .filter(|r| r.topic_id == topic_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn assign_shards(&mut self, address: &str, shard_indexes: &[ShardIndex]) -> Result<()> {
+        let stage = self.stage();
+
+        if let Some(registration) = stage.ingester_registrations.get_mut(address) {
+            let mut shard_indexes = shard_indexes.to_vec();
+            shard_indexes.sort();
+            registration.shard_indexes = shard_indexes;
+        }
+
+        Ok(())
+    }
+
+    async fn remove_ingester(&mut self, address: &str) -> Result<()> {
+        let stage = self.stage();
+        stage.ingester_registrations.remove(address);
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -758,6 +1218,7 @@ impl PartitionRepo for MemTxn {
                         partition_key: key,
                         sort_key: vec![],
                         persisted_sequence_number: None,
+                        cold_compacted_at: None,
                     };
                     stage.partitions.push(p);
                     stage.partitions.last().unwrap()
@@ -909,12 +1370,33 @@ impl PartitionRepo for MemTxn {
         match stage.partitions.iter_mut().find(|p| p.id == partition_id) {
             Some(p) => {
                 p.persisted_sequence_number = Some(sequence_number);
+                p.cold_compacted_at = None;
                 Ok(())
             }
             None => Err(Error::PartitionNotFound { id: partition_id }),
         }
     }
 
+    async fn mark_cold(
+        &mut self,
+        partition_id: PartitionId,
+        cold_compacted_at: Timestamp,
+    ) -> Result<bool> {
+        let stage = self.stage();
+        let fully_compacted = stage.parquet_files.iter().all(|f| {
+            f.partition_id != partition_id
+                || f.to_delete.is_some()
+                || f.compaction_level == CompactionLevel::Final
+        });
+        match stage.partitions.iter_mut().find(|p| p.id == partition_id) {
+            Some(p) if fully_compacted => {
+                p.cold_compacted_at = Some(cold_compacted_at);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
     async fn most_recent_n(&mut self, n: usize, shards: &[ShardId]) -> Result<Vec<Partition>> {
         let stage = self.stage();
         Ok(stage
@@ -1197,6 +1679,28 @@ impl ParquetFileRepo for MemTxn {
         Ok(parquet_files)
     }
 
+    async fn list_by_table_and_time_range(
+        &mut self,
+        table_id: TableId,
+        min_time: Timestamp,
+        max_time: Timestamp,
+    ) -> Result<Vec<ParquetFile>> {
+        let stage = self.stage();
+
+        let parquet_files: Vec<_> = stage
+            .parquet_files
+            .iter()
+            .filter(|f| {
+                table_id == f.table_id
+                    && f.to_delete.is_none()
+                    && f.min_time <= max_time
+                    && f.max_time >= min_time
+            })
+            .cloned()
+            .collect();
+        Ok(parquet_files)
+    }
+
     async fn delete_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>> {
         let stage = self.stage();
 
@@ -1521,6 +2025,56 @@ impl ParquetFileRepo for MemTxn {
             .find(|f| f.object_store_id.eq(&object_store_id))
             .cloned())
     }
+
+    async fn create_lineage(
+        &mut self,
+        parquet_file: &ParquetFile,
+        inputs: &[(ParquetFileId, CompactionLevel)],
+    ) -> Result<()> {
+        let stage = self.stage();
+
+        if !stage.tables.iter().any(|t| t.id == parquet_file.table_id) {
+            return Err(Error::TableNotFound {
+                id: parquet_file.table_id,
+            });
+        }
+
+        for &(input_parquet_file_id, input_compaction_level) in inputs {
+            if stage.parquet_file_lineage.iter().any(|l| {
+                l.parquet_file_id == parquet_file.id
+                    && l.input_parquet_file_id == input_parquet_file_id
+            }) {
+                continue;
+            }
+            stage.parquet_file_lineage.push(ParquetFileLineage {
+                parquet_file_id: parquet_file.id,
+                compaction_level: parquet_file.compaction_level,
+                input_parquet_file_id,
+                input_compaction_level,
+                namespace_id: parquet_file.namespace_id,
+                table_id: parquet_file.table_id,
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn list_lineage_by_namespace(
+        &mut self,
+        namespace_id: NamespaceId,
+    ) -> Result<Vec<ParquetFileLineage>> {
+        let stage = self.stage();
+
+        let mut lineage: Vec<_> = stage
+            .parquet_file_lineage
+            .iter()
+            .filter(|l| l.namespace_id == namespace_id)
+            .copied()
+            .collect();
+        lineage.sort_by_key(|l| (l.parquet_file_id, l.input_parquet_file_id));
+
+        Ok(lineage)
+    }
 }
 
 #[async_trait]
@@ -1604,6 +2158,191 @@ impl ProcessedTombstoneRepo for MemTxn {
     }
 }
 
+#[async_trait]
+impl OperationRepo for MemTxn {
+    async fn create(
+        &mut self,
+        kind: &str,
+        description: &str,
+        node: &str,
+        created_at: Timestamp,
+    ) -> Result<Operation> {
+        let stage = self.stage();
+
+        // ids are never reused, even if the operation with the last id was deleted
+        stage.last_operation_id += 1;
+        let operation = Operation {
+            id: OperationId::new(stage.last_operation_id),
+            kind: kind.to_string(),
+            description: description.to_string(),
+            node: node.to_string(),
+            status: OperationStatus::Running,
+            created_at,
+            completed_at: None,
+        };
+        stage.operations.push(operation.clone());
+
+        Ok(operation)
+    }
+
+    async fn get_by_id(&mut self, id: OperationId) -> Result<Option<Operation>> {
+        let stage = self.stage();
+
+        Ok(stage.operations.iter().find(|o| o.id == id).cloned())
+    }
+
+    async fn list(&mut self) -> Result<Vec<Operation>> {
+        let stage = self.stage();
+
+        Ok(stage.operations.clone())
+    }
+
+    async fn complete(
+        &mut self,
+        id: OperationId,
+        status: OperationStatus,
+        completed_at: Timestamp,
+    ) -> Result<()> {
+        let stage = self.stage();
+
+        if let Some(operation) = stage
+            .operations
+            .iter_mut()
+            .find(|o| o.id == id && o.status == OperationStatus::Running)
+        {
+            operation.status = status;
+            operation.completed_at = Some(completed_at);
+        }
+
+        Ok(())
+    }
+
+    async fn interrupt_running(&mut self, node: &str, at: Timestamp) -> Result<Vec<Operation>> {
+        let stage = self.stage();
+
+        let mut interrupted = vec![];
+        for operation in stage
+            .operations
+            .iter_mut()
+            .filter(|o| o.node == node && o.status == OperationStatus::Running)
+        {
+            operation.status = OperationStatus::Interrupted;
+            operation.completed_at = Some(at);
+            interrupted.push(operation.clone());
+        }
+
+        Ok(interrupted)
+    }
+
+    async fn delete_completed_before(&mut self, older_than: Timestamp) -> Result<u64> {
+        let stage = self.stage();
+
+        let before = stage.operations.len();
+        stage.operations.retain(|o| match o.completed_at {
+            Some(completed_at) => completed_at >= older_than,
+            None => true,
+        });
+
+        Ok((before - stage.operations.len()) as u64)
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepo for MemTxn {
+    async fn create(
+        &mut self,
+        token_hash: &str,
+        namespace_id: Option<NamespaceId>,
+        permission: ApiTokenPermission,
+        description: &str,
+        created_at: Timestamp,
+    ) -> Result<ApiToken> {
+        let stage = self.stage();
+
+        if let Some(id) = namespace_id {
+            if !stage.namespaces.iter().any(|n| n.id == id) {
+                return Err(Error::NamespaceNotFoundById { id });
+            }
+        }
+        if stage.api_tokens.iter().any(|t| t.token_hash == token_hash) {
+            return Err(Error::ApiTokenExists);
+        }
+
+        let token = ApiToken {
+            id: ApiTokenId::new(stage.api_tokens.len() as i64 + 1),
+            token_hash: token_hash.to_string(),
+            namespace_id,
+            permission,
+            description: description.to_string(),
+            created_at,
+            revoked_at: None,
+        };
+        stage.api_tokens.push(token.clone());
+
+        Ok(token)
+    }
+
+    async fn get_by_hash(&mut self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let stage = self.stage();
+
+        Ok(stage
+            .api_tokens
+            .iter()
+            .find(|t| t.token_hash == token_hash)
+            .cloned())
+    }
+
+    async fn list(&mut self) -> Result<Vec<ApiToken>> {
+        let stage = self.stage();
+
+        Ok(stage.api_tokens.clone())
+    }
+
+    async fn revoke(&mut self, id: ApiTokenId, revoked_at: Timestamp) -> Result<Option<ApiToken>> {
+        let stage = self.stage();
+
+        Ok(stage.api_tokens.iter_mut().find(|t| t.id == id).map(|t| {
+            if t.revoked_at.is_none() {
+                t.revoked_at = Some(revoked_at);
+            }
+            t.clone()
+        }))
+    }
+}
+
+#[async_trait]
+impl NodeDrainRepo for MemTxn {
+    async fn set_state(
+        &mut self,
+        address: &str,
+        state: DrainState,
+        updated_at: Timestamp,
+    ) -> Result<NodeDrain> {
+        let stage = self.stage();
+
+        let drain = NodeDrain {
+            address: address.to_string(),
+            state,
+            updated_at,
+        };
+        stage.node_drains.insert(address.to_string(), drain.clone());
+
+        Ok(drain)
+    }
+
+    async fn get_by_address(&mut self, address: &str) -> Result<Option<NodeDrain>> {
+        let stage = self.stage();
+
+        Ok(stage.node_drains.get(address).cloned())
+    }
+
+    async fn list(&mut self) -> Result<Vec<NodeDrain>> {
+        let stage = self.stage();
+
+        Ok(stage.node_drains.values().cloned().collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1614,4 +2353,65 @@ mod tests {
         let metrics = Arc::new(metric::Registry::default());
         crate::interface::test_helpers::test_catalog(Arc::new(MemCatalog::new(metrics))).await;
     }
+
+    #[tokio::test]
+    async fn test_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("catalog.json");
+        let metrics = Arc::new(metric::Registry::default());
+
+        // a missing snapshot starts an empty catalog
+        let catalog = MemCatalog::with_snapshot(Arc::clone(&metrics), &path).unwrap();
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("iox-shared").await.unwrap();
+        let pool = repos
+            .query_pools()
+            .create_or_get("iox-shared")
+            .await
+            .unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("ns", None, topic.id, pool.id)
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("cpu", namespace.id)
+            .await
+            .unwrap();
+        let column = repos
+            .columns()
+            .create_or_get("usage", table.id, ColumnType::F64)
+            .await
+            .unwrap();
+        drop(repos);
+        catalog.shutdown().await.unwrap();
+
+        let catalog = MemCatalog::with_snapshot(metrics, &path).unwrap();
+        let mut repos = catalog.repositories().await;
+        assert_eq!(
+            repos.namespaces().get_by_name("ns").await.unwrap(),
+            Some(namespace.clone())
+        );
+        assert_eq!(
+            repos
+                .tables()
+                .list_by_namespace_id(namespace.id)
+                .await
+                .unwrap(),
+            vec![table.clone()]
+        );
+        assert_eq!(
+            repos.columns().list_by_table_id(table.id).await.unwrap(),
+            vec![column]
+        );
+
+        // new records do not reuse the IDs of the loaded ones
+        let other = repos
+            .tables()
+            .create_or_get("mem", namespace.id)
+            .await
+            .unwrap();
+        assert_ne!(other.id, table.id);
+    }
 }
@@ -0,0 +1,256 @@
+//! JSON snapshot of the schema held by a [`MemCatalog`](super::MemCatalog).
+
+use super::MemCollections;
+use crate::interface::Error;
+use data_types::{
+    Column, ColumnId, ColumnType, Namespace, NamespaceId, QueryPool, QueryPoolId, Table, TableId,
+    Timestamp, TopicId, TopicMetadata,
+};
+use serde::{Deserialize, Serialize};
+use std::{io::ErrorKind, path::Path};
+
+/// The version of the snapshot format written by [`save`].
+const VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Snapshot {
+    version: u32,
+    topics: Vec<TopicSnapshot>,
+    query_pools: Vec<QueryPoolSnapshot>,
+    namespaces: Vec<NamespaceSnapshot>,
+    tables: Vec<TableSnapshot>,
+    columns: Vec<ColumnSnapshot>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TopicSnapshot {
+    id: i64,
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct QueryPoolSnapshot {
+    id: i64,
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct NamespaceSnapshot {
+    id: i64,
+    name: String,
+    retention_period_ns: Option<i64>,
+    topic_id: i64,
+    query_pool_id: i64,
+    max_tables: i32,
+    max_columns_per_table: i32,
+    schema_generation: i64,
+    default_query_range_ns: Option<i64>,
+    max_query_range_ns: Option<i64>,
+    #[serde(default)]
+    row_ttl_ns: Option<i64>,
+    #[serde(default)]
+    row_ttl_column: Option<String>,
+    #[serde(default)]
+    partition_template: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TableSnapshot {
+    id: i64,
+    namespace_id: i64,
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ColumnSnapshot {
+    id: i64,
+    table_id: i64,
+    name: String,
+    column_type: i16,
+    #[serde(default)]
+    created_at: Option<i64>,
+}
+
+/// Load the collections from the snapshot at `path`, or empty collections if there is none.
+pub(super) fn load(path: &Path) -> Result<MemCollections, Error> {
+    let err = |source: Box<dyn std::error::Error + Send + Sync>| Error::Snapshot {
+        source,
+        path: path.to_path_buf(),
+    };
+
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(MemCollections::default()),
+        Err(e) => return Err(err(e.into())),
+    };
+    let snapshot: Snapshot = serde_json::from_slice(&data).map_err(|e| err(e.into()))?;
+
+    if snapshot.version != VERSION {
+        return Err(err(
+            format!("unsupported version {}", snapshot.version).into()
+        ));
+    }
+
+    // The IDs are assigned from the number of records, so the loaded records must be exactly
+    // the ones numbered from 1 for new records not to reuse their IDs.
+    let ids = [
+        snapshot.topics.iter().map(|t| t.id).collect::<Vec<_>>(),
+        snapshot.query_pools.iter().map(|p| p.id).collect(),
+        snapshot.namespaces.iter().map(|n| n.id).collect(),
+        snapshot.tables.iter().map(|t| t.id).collect(),
+        snapshot.columns.iter().map(|c| c.id).collect(),
+    ];
+    for ids in ids {
+        if let Some((idx, id)) = ids
+            .into_iter()
+            .enumerate()
+            .find(|(idx, id)| *id != *idx as i64 + 1)
+        {
+            return Err(err(
+                format!("unexpected ID {} at position {}", id, idx + 1).into()
+            ));
+        }
+    }
+
+    let columns = snapshot
+        .columns
+        .into_iter()
+        .map(|c| {
+            Ok(Column {
+                id: ColumnId::new(c.id),
+                table_id: TableId::new(c.table_id),
+                column_type: ColumnType::try_from(c.column_type)
+                    .map_err(|_| format!("unknown type {} of column {}", c.column_type, c.name))?,
+                name: c.name,
+                created_at: c.created_at.map(Timestamp::new),
+            })
+        })
+        .collect::<Result<_, String>>()
+        .map_err(|e| err(e.into()))?;
+
+    Ok(MemCollections {
+        topics: snapshot
+            .topics
+            .into_iter()
+            .map(|t| TopicMetadata {
+                id: TopicId::new(t.id),
+                name: t.name,
+            })
+            .collect(),
+        query_pools: snapshot
+            .query_pools
+            .into_iter()
+            .map(|p| QueryPool {
+                id: QueryPoolId::new(p.id),
+                name: p.name,
+            })
+            .collect(),
+        namespaces: snapshot
+            .namespaces
+            .into_iter()
+            .map(|n| Namespace {
+                id: NamespaceId::new(n.id),
+                name: n.name,
+                retention_period_ns: n.retention_period_ns,
+                topic_id: TopicId::new(n.topic_id),
+                query_pool_id: QueryPoolId::new(n.query_pool_id),
+                max_tables: n.max_tables,
+                max_columns_per_table: n.max_columns_per_table,
+                schema_generation: n.schema_generation,
+                default_query_range_ns: n.default_query_range_ns,
+                max_query_range_ns: n.max_query_range_ns,
+                row_ttl_ns: n.row_ttl_ns,
+                row_ttl_column: n.row_ttl_column,
+                partition_template: n.partition_template,
+            })
+            .collect(),
+        tables: snapshot
+            .tables
+            .into_iter()
+            .map(|t| Table {
+                id: TableId::new(t.id),
+                namespace_id: NamespaceId::new(t.namespace_id),
+                name: t.name,
+            })
+            .collect(),
+        columns,
+        ..Default::default()
+    })
+}
+
+/// Write the schema held in `collections` to the snapshot at `path`.
+///
+/// The snapshot is written to a temporary file first, so that an interrupted write does not
+/// replace the previous snapshot.
+pub(super) fn save(path: &Path, collections: &MemCollections) -> Result<(), Error> {
+    let err = |source: Box<dyn std::error::Error + Send + Sync>| Error::Snapshot {
+        source,
+        path: path.to_path_buf(),
+    };
+
+    let snapshot = Snapshot {
+        version: VERSION,
+        topics: collections
+            .topics
+            .iter()
+            .map(|t| TopicSnapshot {
+                id: t.id.get(),
+                name: t.name.clone(),
+            })
+            .collect(),
+        query_pools: collections
+            .query_pools
+            .iter()
+            .map(|p| QueryPoolSnapshot {
+                id: p.id.get(),
+                name: p.name.clone(),
+            })
+            .collect(),
+        namespaces: collections
+            .namespaces
+            .iter()
+            .map(|n| NamespaceSnapshot {
+                id: n.id.get(),
+                name: n.name.clone(),
+                retention_period_ns: n.retention_period_ns,
+                topic_id: n.topic_id.get(),
+                query_pool_id: n.query_pool_id.get(),
+                max_tables: n.max_tables,
+                max_columns_per_table: n.max_columns_per_table,
+                schema_generation: n.schema_generation,
+                default_query_range_ns: n.default_query_range_ns,
+                max_query_range_ns: n.max_query_range_ns,
+                row_ttl_ns: n.row_ttl_ns,
+                row_ttl_column: n.row_ttl_column.clone(),
+                partition_template: n.partition_template.clone(),
+            })
+            .collect(),
+        tables: collections
+            .tables
+            .iter()
+            .map(|t| TableSnapshot {
+                id: t.id.get(),
+                namespace_id: t.namespace_id.get(),
+                name: t.name.clone(),
+            })
+            .collect(),
+        columns: collections
+            .columns
+            .iter()
+            .map(|c| ColumnSnapshot {
+                id: c.id.get(),
+                table_id: c.table_id.get(),
+                name: c.name.clone(),
+                column_type: c.column_type as i16,
+                created_at: c.created_at.map(|t| t.get()),
+            })
+            .collect(),
+    };
+
+    let data = serde_json::to_vec_pretty(&snapshot).map_err(|e| err(e.into()))?;
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, data).map_err(|e| err(e.into()))?;
+    std::fs::rename(&tmp, path).map_err(|e| err(e.into()))?;
+
+    Ok(())
+}
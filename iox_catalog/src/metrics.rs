@@ -1,29 +1,32 @@
 //! Metric instrumentation for catalog implementations.
 
 use crate::interface::{
-    sealed::TransactionFinalize, ColumnRepo, NamespaceRepo, ParquetFileRepo, PartitionRepo,
-    ProcessedTombstoneRepo, QueryPoolRepo, RepoCollection, Result, ShardRepo, TableRepo,
-    TombstoneRepo, TopicMetadataRepo,
+    sealed::TransactionFinalize, ApiTokenRepo, ColumnRepo, NamespaceRepo, NodeDrainRepo,
+    OperationRepo, ParquetFileRepo, PartitionRepo, ProcessedTombstoneRepo, QueryPoolRepo,
+    RepoCollection, Result, ShardRepo, TableRepo, TombstoneRepo, TopicMetadataRepo,
 };
 use async_trait::async_trait;
 use data_types::{
-    Column, ColumnType, ColumnTypeCount, CompactionLevel, Namespace, NamespaceId, ParquetFile,
-    ParquetFileId, ParquetFileParams, Partition, PartitionId, PartitionKey, PartitionParam,
-    ProcessedTombstone, QueryPool, QueryPoolId, SequenceNumber, Shard, ShardId, ShardIndex,
-    SkippedCompaction, Table, TableId, TablePartition, Timestamp, Tombstone, TombstoneId, TopicId,
-    TopicMetadata,
+    ApiToken, ApiTokenId, ApiTokenPermission, Column, ColumnType, ColumnTypeCount, CompactionLevel,
+    DrainState, IngesterRegistration, Namespace, NamespaceId, NamespaceUsage, NodeDrain, Operation,
+    OperationId, OperationStatus, ParquetFile, ParquetFileId, ParquetFileLineage,
+    ParquetFileParams, Partition, PartitionId, PartitionKey, PartitionParam, PartitionTemplate,
+    ProcessedTombstone, QuerierRegistration, QueryPool, QueryPoolId, SequenceNumber, Shard,
+    ShardId, ShardIndex, ShardLease, SkippedCompaction, Table, TableId, TablePartition, Timestamp,
+    Tombstone, TombstoneId, TopicId, TopicMetadata,
 };
-use iox_time::{SystemProvider, TimeProvider};
+use iox_time::{SystemProvider, Time, TimeProvider};
 use metric::{DurationHistogram, Metric};
-use std::{collections::HashMap, fmt::Debug, sync::Arc};
+use std::{collections::HashMap, fmt::Debug, sync::Arc, time::Duration};
 use uuid::Uuid;
 
 /// Decorates a implementation of the catalog's [`RepoCollection`] (and the
 /// transactional variant) with instrumentation that emits latency histograms
-/// for each method.
+/// for each method, including transaction commits and aborts.
 ///
 /// Values are recorded under the `catalog_op_duration` metric, labelled by
-/// operation name and result (success/error).
+/// operation name and result (success/error). The sample count of the "error"
+/// histogram for an operation is its error count.
 #[derive(Debug)]
 pub struct MetricDecorator<T, P = SystemProvider> {
     inner: T,
@@ -42,6 +45,31 @@ impl<T> MetricDecorator<T> {
     }
 }
 
+impl<T, P> MetricDecorator<T, P>
+where
+    P: TimeProvider,
+{
+    /// Record the duration of `op`, started at `start`, and whether it
+    /// succeeded.
+    fn record<O>(&self, op: &'static str, start: Time, res: &Result<O>) {
+        let observer: Metric<DurationHistogram> = self
+            .metrics
+            .register_metric("catalog_op_duration", "catalog call duration");
+
+        // Avoid exploding if time goes backwards - simply drop the
+        // measurement if it happens.
+        if let Some(delta) = self.time_provider.now().checked_duration_since(start) {
+            let tag = match res {
+                Ok(_) => "success",
+                Err(_) => "error",
+            };
+            observer
+                .recorder(&[("op", op), ("result", tag)])
+                .record(delta);
+        }
+    }
+}
+
 impl<T, P> RepoCollection for MetricDecorator<T, P>
 where
     T: TopicMetadataRepo
@@ -54,6 +82,9 @@ where
         + TombstoneRepo
         + ProcessedTombstoneRepo
         + ParquetFileRepo
+        + OperationRepo
+        + ApiTokenRepo
+        + NodeDrainRepo
         + Debug,
     P: TimeProvider,
 {
@@ -96,6 +127,18 @@ where
     fn processed_tombstones(&mut self) -> &mut dyn ProcessedTombstoneRepo {
         self
     }
+
+    fn operations(&mut self) -> &mut dyn OperationRepo {
+        self
+    }
+
+    fn api_tokens(&mut self) -> &mut dyn ApiTokenRepo {
+        self
+    }
+
+    fn node_drains(&mut self) -> &mut dyn NodeDrainRepo {
+        self
+    }
 }
 
 #[async_trait]
@@ -105,10 +148,16 @@ where
     P: TimeProvider,
 {
     async fn commit_inplace(&mut self) -> Result<(), super::interface::Error> {
-        self.inner.commit_inplace().await
+        let t = self.time_provider.now();
+        let res = self.inner.commit_inplace().await;
+        self.record("txn_commit", t, &res);
+        res
     }
     async fn abort_inplace(&mut self) -> Result<(), super::interface::Error> {
-        self.inner.abort_inplace().await
+        let t = self.time_provider.now();
+        let res = self.inner.abort_inplace().await;
+        self.record("txn_abort", t, &res);
+        res
     }
 }
 
@@ -150,24 +199,9 @@ macro_rules! decorate {
 
             $(
                 async fn $method(&mut self, $($arg : $t),*) -> Result<$out> {
-                    let observer: Metric<DurationHistogram> = self.metrics.register_metric(
-                        "catalog_op_duration",
-                        "catalog call duration",
-                    );
-
                     let t = self.time_provider.now();
                     let res = self.inner.$method($($arg),*).await;
-
-                    // Avoid exploding if time goes backwards - simply drop the
-                    // measurement if it happens.
-                    if let Some(delta) = self.time_provider.now().checked_duration_since(t) {
-                        let tag = match &res {
-                            Ok(_) => "success",
-                            Err(_) => "error",
-                        };
-                        observer.recorder(&[("op", $metric), ("result", tag)]).record(delta);
-                    }
-
+                    self.record($metric, t, &res);
                     res
                 }
             )+
@@ -180,6 +214,7 @@ decorate!(
     methods = [
         "topic_create_or_get" = create_or_get(&mut self, name: &str) -> Result<TopicMetadata>;
         "topic_get_by_name" = get_by_name(&mut self, name: &str) -> Result<Option<TopicMetadata>>;
+        "topic_list" = list(&mut self) -> Result<Vec<TopicMetadata>>;
     ]
 );
 
@@ -187,6 +222,9 @@ decorate!(
     impl_trait = QueryPoolRepo,
     methods = [
         "query_create_or_get" = create_or_get(&mut self, name: &str) -> Result<QueryPool>;
+        "query_record_querier_heartbeat" = record_querier_heartbeat(&mut self, query_pool_id: QueryPoolId, address: &str, at: Timestamp) -> Result<QuerierRegistration>;
+        "query_list_queriers" = list_queriers(&mut self, query_pool_id: QueryPoolId, heartbeat_after: Timestamp) -> Result<Vec<QuerierRegistration>>;
+        "query_remove_querier" = remove_querier(&mut self, address: &str) -> Result<()>;
     ]
 );
 
@@ -195,11 +233,22 @@ decorate!(
     methods = [
         "namespace_create" = create(&mut self, name: &str, retention_period_ns: Option<i64>, topic_id: TopicId, query_pool_id: QueryPoolId) -> Result<Namespace>;
         "namespace_update_retention_period" = update_retention_period(&mut self, name: &str, retention_period_ns: Option<i64>) -> Result<Namespace>;
+        "namespace_update_query_ranges" = update_query_ranges(&mut self, name: &str, default_query_range_ns: Option<i64>, max_query_range_ns: Option<i64>) -> Result<Namespace>;
+        "namespace_update_row_ttl" = update_row_ttl(&mut self, name: &str, row_ttl_ns: Option<i64>, row_ttl_column: Option<String>) -> Result<Namespace>;
+        "namespace_update_partition_template" = update_partition_template(&mut self, name: &str, partition_template: Option<&PartitionTemplate>) -> Result<Namespace>;
         "namespace_list" = list(&mut self) -> Result<Vec<Namespace>>;
+        "namespace_list_paged" = list_paged(&mut self, after: Option<NamespaceId>, limit: usize) -> Result<Vec<Namespace>>;
         "namespace_get_by_id" = get_by_id(&mut self, id: NamespaceId) -> Result<Option<Namespace>>;
         "namespace_get_by_name" = get_by_name(&mut self, name: &str) -> Result<Option<Namespace>>;
         "namespace_update_table_limit" = update_table_limit(&mut self, name: &str, new_max: i32) -> Result<Namespace>;
         "namespace_update_column_limit" = update_column_limit(&mut self, name: &str, new_max: i32) -> Result<Namespace>;
+        "namespace_update_topic" = update_topic(&mut self, name: &str, topic_id: TopicId) -> Result<Namespace>;
+        "namespace_increment_schema_generation" = increment_schema_generation(&mut self, id: NamespaceId, expected: i64) -> Result<Namespace>;
+        "namespace_add_usage" = add_usage(&mut self, usage: &NamespaceUsage) -> Result<()>;
+        "namespace_get_usage" = get_usage(&mut self, id: NamespaceId) -> Result<Option<NamespaceUsage>>;
+        "namespace_list_usage" = list_usage(&mut self) -> Result<Vec<NamespaceUsage>>;
+        "namespace_get_producer_sequence" = get_producer_sequence(&mut self, id: NamespaceId, producer_id: &str) -> Result<Option<i64>>;
+        "namespace_compare_and_swap_producer_sequence" = compare_and_swap_producer_sequence(&mut self, id: NamespaceId, producer_id: &str, current: Option<i64>, new: Option<i64>) -> Result<bool>;
     ]
 );
 
@@ -211,6 +260,8 @@ decorate!(
         "table_get_by_namespace_and_name" = get_by_namespace_and_name(&mut self, namespace_id: NamespaceId, name: &str) -> Result<Option<Table>>;
         "table_list_by_namespace_id" = list_by_namespace_id(&mut self, namespace_id: NamespaceId) -> Result<Vec<Table>>;
         "table_list" = list(&mut self) -> Result<Vec<Table>>;
+        "table_get_shard_pin" = get_shard_pin(&mut self, table_id: TableId) -> Result<Option<ShardId>>;
+        "table_create_or_get_shard_pin" = create_or_get_shard_pin(&mut self, table_id: TableId, shard_id: ShardId) -> Result<ShardId>;
     ]
 );
 
@@ -234,6 +285,14 @@ decorate!(
         "shard_list" = list(&mut self) -> Result<Vec<Shard>>;
         "shard_list_by_topic" = list_by_topic(&mut self, topic: &TopicMetadata) -> Result<Vec<Shard>>;
         "shard_update_min_unpersisted_sequence_number" = update_min_unpersisted_sequence_number(&mut self, shard_id: ShardId, sequence_number: SequenceNumber) -> Result<()>;
+        "shard_acquire_lease" = acquire_lease(&mut self, shard_id: ShardId, holder: &str, duration: Duration) -> Result<ShardLease>;
+        "shard_release_lease" = release_lease(&mut self, shard_id: ShardId, holder: &str) -> Result<()>;
+        "shard_check_lease" = check_lease(&mut self, shard_id: ShardId, holder: &str, epoch: i64) -> Result<()>;
+        "shard_list_held_leases" = list_held_leases(&mut self, topic_id: TopicId) -> Result<Vec<ShardLease>>;
+        "shard_record_ingester_heartbeat" = record_ingester_heartbeat(&mut self, topic_id: TopicId, address: &str, at: Timestamp) -> Result<IngesterRegistration>;
+        "shard_list_ingesters" = list_ingesters(&mut self, topic_id: TopicId) -> Result<Vec<IngesterRegistration>>;
+        "shard_assign_shards" = assign_shards(&mut self, address: &str, shard_indexes: &[ShardIndex]) -> Result<()>;
+        "shard_remove_ingester" = remove_ingester(&mut self, address: &str) -> Result<()>;
     ]
 );
 
@@ -250,6 +309,7 @@ decorate!(
         "partition_list_skipped_compactions" = list_skipped_compactions(&mut self) -> Result<Vec<SkippedCompaction>>;
         "partition_delete_skipped_compactions" = delete_skipped_compactions(&mut self, partition_id: PartitionId) -> Result<Option<SkippedCompaction>>;
         "partition_update_persisted_sequence_number" = update_persisted_sequence_number(&mut self, partition_id: PartitionId, sequence_number: SequenceNumber) -> Result<()>;
+        "partition_mark_cold" = mark_cold(&mut self, partition_id: PartitionId, cold_compacted_at: Timestamp) -> Result<bool>;
         "partition_most_recent_n" = most_recent_n(&mut self, n: usize, shards: &[ShardId]) -> Result<Vec<Partition>>;
     ]
 );
@@ -276,6 +336,7 @@ decorate!(
         "parquet_list_by_shard_greater_than" = list_by_shard_greater_than(&mut self, shard_id: ShardId, sequence_number: SequenceNumber) -> Result<Vec<ParquetFile>>;
         "parquet_list_by_namespace_not_to_delete" = list_by_namespace_not_to_delete(&mut self, namespace_id: NamespaceId) -> Result<Vec<ParquetFile>>;
         "parquet_list_by_table_not_to_delete" = list_by_table_not_to_delete(&mut self, table_id: TableId) -> Result<Vec<ParquetFile>>;
+        "parquet_list_by_table_and_time_range" = list_by_table_and_time_range(&mut self, table_id: TableId, min_time: Timestamp, max_time: Timestamp) -> Result<Vec<ParquetFile>>;
         "parquet_delete_old" = delete_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>>;
         "parquet_delete_old_ids_only" = delete_old_ids_only(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFileId>>;
         "parquet_list_by_partition_not_to_delete" = list_by_partition_not_to_delete(&mut self, partition_id: PartitionId) -> Result<Vec<ParquetFile>>;
@@ -287,6 +348,8 @@ decorate!(
         "parquet_count_by_overlaps_with_level_0" = count_by_overlaps_with_level_0(&mut self, table_id: TableId, shard_id: ShardId, min_time: Timestamp, max_time: Timestamp, sequence_number: SequenceNumber) -> Result<i64>;
         "parquet_count_by_overlaps_with_level_1" = count_by_overlaps_with_level_1(&mut self, table_id: TableId, shard_id: ShardId, min_time: Timestamp, max_time: Timestamp) -> Result<i64>;
         "parquet_get_by_object_store_id" = get_by_object_store_id(&mut self, object_store_id: Uuid) -> Result<Option<ParquetFile>>;
+        "parquet_create_lineage" = create_lineage(&mut self, parquet_file: &ParquetFile, inputs: &[(ParquetFileId, CompactionLevel)]) -> Result<()>;
+        "parquet_list_lineage_by_namespace" = list_lineage_by_namespace(&mut self, namespace_id: NamespaceId) -> Result<Vec<ParquetFileLineage>>;
         "recent_highest_throughput_partitions" = recent_highest_throughput_partitions(&mut self, shard_id: ShardId, time_in_the_past: Timestamp, min_num_files: usize, num_partitions: usize) -> Result<Vec<PartitionParam>>;
         "most_cold_files_partitions" =  most_cold_files_partitions(&mut self, shard_id: ShardId, time_in_the_past: Timestamp, num_partitions: usize) -> Result<Vec<PartitionParam>>;
     ]
@@ -301,3 +364,34 @@ decorate!(
         "processed_tombstone_count_by_tombstone_id" = count_by_tombstone_id(&mut self, tombstone_id: TombstoneId) -> Result<i64>;
     ]
 );
+
+decorate!(
+    impl_trait = OperationRepo,
+    methods = [
+        "operation_create" = create(&mut self, kind: &str, description: &str, node: &str, created_at: Timestamp) -> Result<Operation>;
+        "operation_get_by_id" = get_by_id(&mut self, id: OperationId) -> Result<Option<Operation>>;
+        "operation_list" = list(&mut self) -> Result<Vec<Operation>>;
+        "operation_complete" = complete(&mut self, id: OperationId, status: OperationStatus, completed_at: Timestamp) -> Result<()>;
+        "operation_interrupt_running" = interrupt_running(&mut self, node: &str, at: Timestamp) -> Result<Vec<Operation>>;
+        "operation_delete_completed_before" = delete_completed_before(&mut self, older_than: Timestamp) -> Result<u64>;
+    ]
+);
+
+decorate!(
+    impl_trait = ApiTokenRepo,
+    methods = [
+        "api_token_create" = create(&mut self, token_hash: &str, namespace_id: Option<NamespaceId>, permission: ApiTokenPermission, description: &str, created_at: Timestamp) -> Result<ApiToken>;
+        "api_token_get_by_hash" = get_by_hash(&mut self, token_hash: &str) -> Result<Option<ApiToken>>;
+        "api_token_list" = list(&mut self) -> Result<Vec<ApiToken>>;
+        "api_token_revoke" = revoke(&mut self, id: ApiTokenId, revoked_at: Timestamp) -> Result<Option<ApiToken>>;
+    ]
+);
+
+decorate!(
+    impl_trait = NodeDrainRepo,
+    methods = [
+        "node_drain_set_state" = set_state(&mut self, address: &str, state: DrainState, updated_at: Timestamp) -> Result<NodeDrain>;
+        "node_drain_get_by_address" = get_by_address(&mut self, address: &str) -> Result<Option<NodeDrain>>;
+        "node_drain_list" = list(&mut self) -> Result<Vec<NodeDrain>>;
+    ]
+);
@@ -0,0 +1,112 @@
+//! Helpers for inspecting and applying the sqlx migrations embedded in the catalog backends.
+
+use crate::interface::{MigrationInfo, MigrationStatus};
+use observability_deps::tracing::info;
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use std::collections::HashMap;
+
+/// Compare the migrations recorded by `conn` with the ones embedded in `migrator`.
+///
+/// `conn` must already contain the sqlx migrations table.
+pub(crate) async fn status<C>(
+    conn: &mut C,
+    migrator: &Migrator,
+) -> Result<MigrationStatus, MigrateError>
+where
+    C: Migrate + Send,
+{
+    let dirty = conn.dirty_version().await?;
+    let applied = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| m.version)
+        .collect::<Vec<_>>();
+
+    Ok(MigrationStatus {
+        pending: pending(migrator, &applied),
+        applied,
+        dirty,
+    })
+}
+
+/// All migrations in `migrator` that are not part of `applied`, in order.
+pub(crate) fn pending(migrator: &Migrator, applied: &[i64]) -> Vec<MigrationInfo> {
+    migrator
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration() && !applied.contains(&m.version))
+        .map(|m| MigrationInfo {
+            version: m.version,
+            description: m.description.to_string(),
+        })
+        .collect()
+}
+
+/// Apply all pending migrations of `migrator` up to and including `target_version` (or all of
+/// them if `None`).
+///
+/// Fails without applying anything if the schema is dirty or an applied migration no longer
+/// matches the embedded one.
+pub(crate) async fn run<C>(
+    conn: &mut C,
+    migrator: &Migrator,
+    target_version: Option<i64>,
+) -> Result<(), MigrateError>
+where
+    C: Migrate + Send,
+{
+    conn.ensure_migrations_table().await?;
+    conn.lock().await?;
+
+    let result = run_locked(conn, migrator, target_version).await;
+
+    conn.unlock().await?;
+
+    result
+}
+
+async fn run_locked<C>(
+    conn: &mut C,
+    migrator: &Migrator,
+    target_version: Option<i64>,
+) -> Result<(), MigrateError>
+where
+    C: Migrate + Send,
+{
+    if let Some(version) = conn.dirty_version().await? {
+        return Err(MigrateError::Dirty(version));
+    }
+
+    let applied = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| (m.version, m.checksum))
+        .collect::<HashMap<_, _>>();
+
+    for migration in migrator.iter() {
+        if migration.migration_type.is_down_migration() {
+            continue;
+        }
+        if target_version.map_or(false, |target| migration.version > target) {
+            break;
+        }
+
+        match applied.get(&migration.version) {
+            Some(checksum) if *checksum != migration.checksum => {
+                return Err(MigrateError::VersionMismatch(migration.version));
+            }
+            Some(_) => {}
+            None => {
+                info!(
+                    version = migration.version,
+                    description = %migration.description,
+                    "applying catalog migration"
+                );
+                conn.apply(migration).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -2,21 +2,24 @@
 
 use crate::{
     interface::{
-        self, sealed::TransactionFinalize, Catalog, ColumnRepo, ColumnTypeMismatchSnafu, Error,
-        NamespaceRepo, ParquetFileRepo, PartitionRepo, ProcessedTombstoneRepo, QueryPoolRepo,
+        self, sealed::TransactionFinalize, ApiTokenRepo, Catalog, ColumnRepo,
+        ColumnTypeMismatchSnafu, Error, MigrationStatus, NamespaceRepo, NodeDrainRepo,
+        OperationRepo, ParquetFileRepo, PartitionRepo, ProcessedTombstoneRepo, QueryPoolRepo,
         RepoCollection, Result, ShardRepo, TableRepo, TombstoneRepo, TopicMetadataRepo,
         Transaction,
     },
     metrics::MetricDecorator,
-    DEFAULT_MAX_COLUMNS_PER_TABLE, DEFAULT_MAX_TABLES,
+    migrate, DEFAULT_MAX_COLUMNS_PER_TABLE, DEFAULT_MAX_TABLES,
 };
 use async_trait::async_trait;
 use data_types::{
-    Column, ColumnType, ColumnTypeCount, CompactionLevel, Namespace, NamespaceId, ParquetFile,
-    ParquetFileId, ParquetFileParams, Partition, PartitionId, PartitionKey, PartitionParam,
-    ProcessedTombstone, QueryPool, QueryPoolId, SequenceNumber, Shard, ShardId, ShardIndex,
-    SkippedCompaction, Table, TableId, TablePartition, Timestamp, Tombstone, TombstoneId, TopicId,
-    TopicMetadata,
+    ApiToken, ApiTokenId, ApiTokenPermission, Column, ColumnType, ColumnTypeCount, CompactionLevel,
+    DrainState, IngesterRegistration, Namespace, NamespaceId, NamespaceUsage, NodeDrain, Operation,
+    OperationId, OperationStatus, ParquetFile, ParquetFileId, ParquetFileLineage,
+    ParquetFileParams, Partition, PartitionId, PartitionKey, PartitionParam, PartitionTemplate,
+    ProcessedTombstone, QuerierRegistration, QueryPool, QueryPoolId, SequenceNumber, Shard,
+    ShardId, ShardIndex, ShardLease, SkippedCompaction, Table, TableId, TablePartition, Timestamp,
+    Tombstone, TombstoneId, TopicId, TopicMetadata,
 };
 use iox_time::{SystemProvider, TimeProvider};
 use observability_deps::tracing::{debug, info, warn};
@@ -295,6 +298,57 @@ impl Catalog for PostgresCatalog {
         Ok(())
     }
 
+    async fn migration_status(&self) -> Result<MigrationStatus, Error> {
+        // Do not create the migrations table (or schema) just to look at it.
+        let migrations_table_exists = sqlx::query(
+            r#"
+SELECT EXISTS (
+    SELECT 1 FROM information_schema.tables
+    WHERE table_schema = $1 AND table_name = '_sqlx_migrations'
+);
+            "#,
+        )
+        .bind(&self.schema_name) // $1
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?
+        .get::<bool, _>(0);
+
+        if !migrations_table_exists {
+            return Ok(MigrationStatus {
+                pending: migrate::pending(&MIGRATOR, &[]),
+                ..Default::default()
+            });
+        }
+
+        let mut conn = (&self.pool)
+            .acquire()
+            .await
+            .map_err(|e| Error::SqlxError { source: e })?;
+
+        migrate::status(&mut *conn, &MIGRATOR)
+            .await
+            .map_err(|e| Error::Setup { source: e.into() })
+    }
+
+    async fn migrate(&self, target_version: Option<i64>) -> Result<(), Error> {
+        // See `setup` for why the schema is created first.
+        let create_schema_query = format!("CREATE SCHEMA IF NOT EXISTS {};", &self.schema_name);
+        self.pool
+            .execute(sqlx::query(&create_schema_query))
+            .await
+            .map_err(|e| Error::Setup { source: e })?;
+
+        let mut conn = (&self.pool)
+            .acquire()
+            .await
+            .map_err(|e| Error::SqlxError { source: e })?;
+
+        migrate::run(&mut *conn, &MIGRATOR, target_version)
+            .await
+            .map_err(|e| Error::Setup { source: e.into() })
+    }
+
     async fn start_transaction(&self) -> Result<Box<dyn Transaction>, Error> {
         let transaction = self
             .pool
@@ -519,6 +573,18 @@ impl RepoCollection for PostgresTxn {
     fn processed_tombstones(&mut self) -> &mut dyn ProcessedTombstoneRepo {
         self
     }
+
+    fn operations(&mut self) -> &mut dyn OperationRepo {
+        self
+    }
+
+    fn api_tokens(&mut self) -> &mut dyn ApiTokenRepo {
+        self
+    }
+
+    fn node_drains(&mut self) -> &mut dyn NodeDrainRepo {
+        self
+    }
 }
 
 #[async_trait]
@@ -561,6 +627,13 @@ WHERE name = $1;
 
         Ok(Some(topic))
     }
+
+    async fn list(&mut self) -> Result<Vec<TopicMetadata>> {
+        sqlx::query_as::<_, TopicMetadata>(r#"SELECT * FROM topic;"#)
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
 }
 
 #[async_trait]
@@ -582,6 +655,71 @@ RETURNING *;
 
         Ok(rec)
     }
+
+    async fn record_querier_heartbeat(
+        &mut self,
+        query_pool_id: QueryPoolId,
+        address: &str,
+        at: Timestamp,
+    ) -> Result<QuerierRegistration> {
+        sqlx::query_as::<_, QuerierRegistration>(
+            r#"
+INSERT INTO querier_registration ( address, query_pool_id, last_heartbeat )
+VALUES ( $1, $2, $3 )
+ON CONFLICT ( address )
+DO UPDATE SET query_pool_id = excluded.query_pool_id, last_heartbeat = excluded.last_heartbeat
+RETURNING *;
+        "#,
+        )
+        .bind(address) // $1
+        .bind(query_pool_id) // $2
+        .bind(at) // $3
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
+
+    async fn list_queriers(
+        &mut self,
+        query_pool_id: QueryPoolId,
+        heartbeat_after: Timestamp,
+    ) -> Result<Vec<QuerierRegistration>> {
+        sqlx::query_as::<_, QuerierRegistration>(
+            r#"
+SELECT *
+FROM querier_registration
+WHERE query_pool_id = $1
+  AND last_heartbeat >= $2
+ORDER BY address;
+        "#,
+        )
+        .bind(query_pool_id) // $1
+        .bind(heartbeat_after) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn remove_querier(&mut self, address: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+DELETE FROM querier_registration
+WHERE address = $1;
+        "#,
+        )
+        .bind(address) // $1
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -638,6 +776,27 @@ FROM namespace;
         Ok(rec)
     }
 
+    async fn list_paged(
+        &mut self,
+        after: Option<NamespaceId>,
+        limit: usize,
+    ) -> Result<Vec<Namespace>> {
+        sqlx::query_as::<_, Namespace>(
+            r#"
+SELECT *
+FROM namespace
+WHERE id > $1
+ORDER BY id
+LIMIT $2;
+            "#,
+        )
+        .bind(after.map_or(i64::MIN, |id| id.get())) // $1
+        .bind(limit as i64) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
     async fn get_by_id(&mut self, id: NamespaceId) -> Result<Option<Namespace>> {
         let rec = sqlx::query_as::<_, Namespace>(
             r#"
@@ -708,7 +867,7 @@ RETURNING *;
         let rec = sqlx::query_as::<_, Namespace>(
             r#"
 UPDATE namespace
-SET max_columns_per_table = $1
+SET max_columns_per_table = $1, schema_generation = schema_generation + 1
 WHERE name = $2
 RETURNING *;
         "#,
@@ -728,13 +887,43 @@ RETURNING *;
         Ok(namespace)
     }
 
+    async fn update_topic(&mut self, name: &str, topic_id: TopicId) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET topic_id = $1, schema_generation = schema_generation + 1
+WHERE name = $2
+RETURNING *;
+        "#,
+        )
+        .bind(topic_id) // $1
+        .bind(name) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ if is_fk_violation(&e) => Error::ForeignKeyViolation { source: e },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
     async fn update_retention_period(
         &mut self,
         name: &str,
         retention_period_ns: Option<i64>,
     ) -> Result<Namespace> {
         let rec = sqlx::query_as::<_, Namespace>(
-            r#"UPDATE namespace SET retention_period_ns = $1 WHERE name = $2 RETURNING *;"#,
+            r#"
+UPDATE namespace
+SET retention_period_ns = $1, schema_generation = schema_generation + 1
+WHERE name = $2
+RETURNING *;
+        "#,
         )
         .bind(retention_period_ns) // $1
         .bind(name) // $2
@@ -750,6 +939,253 @@ RETURNING *;
 
         Ok(namespace)
     }
+
+    async fn update_query_ranges(
+        &mut self,
+        name: &str,
+        default_query_range_ns: Option<i64>,
+        max_query_range_ns: Option<i64>,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET default_query_range_ns = $1, max_query_range_ns = $2,
+    schema_generation = schema_generation + 1
+WHERE name = $3
+RETURNING *;
+        "#,
+        )
+        .bind(default_query_range_ns) // $1
+        .bind(max_query_range_ns) // $2
+        .bind(name) // $3
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn update_row_ttl(
+        &mut self,
+        name: &str,
+        row_ttl_ns: Option<i64>,
+        row_ttl_column: Option<String>,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET row_ttl_ns = $1, row_ttl_column = $2,
+    schema_generation = schema_generation + 1
+WHERE name = $3
+RETURNING *;
+        "#,
+        )
+        .bind(row_ttl_ns) // $1
+        .bind(row_ttl_column) // $2
+        .bind(name) // $3
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn update_partition_template(
+        &mut self,
+        name: &str,
+        partition_template: Option<&PartitionTemplate>,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET partition_template = $1,
+    schema_generation = schema_generation + 1
+WHERE name = $2
+RETURNING *;
+        "#,
+        )
+        .bind(partition_template.map(ToString::to_string)) // $1
+        .bind(name) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn increment_schema_generation(
+        &mut self,
+        id: NamespaceId,
+        expected: i64,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET schema_generation = schema_generation + 1
+WHERE id = $1 AND schema_generation = $2
+RETURNING *;
+        "#,
+        )
+        .bind(id) // $1
+        .bind(expected) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        match rec {
+            Ok(namespace) => Ok(namespace),
+            // Either the namespace does not exist, or its generation moved on.
+            Err(sqlx::Error::RowNotFound) => match NamespaceRepo::get_by_id(self, id).await? {
+                Some(namespace) => Err(Error::PreconditionViolation {
+                    namespace_id: id,
+                    expected,
+                    actual: namespace.schema_generation,
+                }),
+                None => Err(Error::NamespaceNotFoundById { id }),
+            },
+            Err(e) => Err(Error::SqlxError { source: e }),
+        }
+    }
+
+    async fn add_usage(&mut self, usage: &NamespaceUsage) -> Result<()> {
+        sqlx::query(
+            r#"
+INSERT INTO namespace_usage
+    ( namespace_id, rows_written, bytes_written, queries_executed, bytes_scanned )
+VALUES ( $1, $2, $3, $4, $5 )
+ON CONFLICT ( namespace_id )
+DO UPDATE SET
+    rows_written = namespace_usage.rows_written + excluded.rows_written,
+    bytes_written = namespace_usage.bytes_written + excluded.bytes_written,
+    queries_executed = namespace_usage.queries_executed + excluded.queries_executed,
+    bytes_scanned = namespace_usage.bytes_scanned + excluded.bytes_scanned;
+        "#,
+        )
+        .bind(usage.namespace_id) // $1
+        .bind(usage.rows_written) // $2
+        .bind(usage.bytes_written) // $3
+        .bind(usage.queries_executed) // $4
+        .bind(usage.bytes_scanned) // $5
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_usage(&mut self, id: NamespaceId) -> Result<Option<NamespaceUsage>> {
+        sqlx::query_as::<_, NamespaceUsage>(
+            r#"SELECT * FROM namespace_usage WHERE namespace_id = $1;"#,
+        )
+        .bind(id) // $1
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_usage(&mut self) -> Result<Vec<NamespaceUsage>> {
+        sqlx::query_as::<_, NamespaceUsage>(
+            r#"SELECT * FROM namespace_usage ORDER BY namespace_id;"#,
+        )
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn get_producer_sequence(
+        &mut self,
+        id: NamespaceId,
+        producer_id: &str,
+    ) -> Result<Option<i64>> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+SELECT sequence_number FROM producer_sequence
+WHERE namespace_id = $1 AND producer_id = $2;
+        "#,
+        )
+        .bind(id) // $1
+        .bind(producer_id) // $2
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn compare_and_swap_producer_sequence(
+        &mut self,
+        id: NamespaceId,
+        producer_id: &str,
+        current: Option<i64>,
+        new: Option<i64>,
+    ) -> Result<bool> {
+        let query = match (current, new) {
+            (None, None) => {
+                return Ok(self.get_producer_sequence(id, producer_id).await?.is_none());
+            }
+            (None, Some(new)) => sqlx::query(
+                r#"
+INSERT INTO producer_sequence ( namespace_id, producer_id, sequence_number )
+VALUES ( $1, $2, $3 )
+ON CONFLICT ( namespace_id, producer_id ) DO NOTHING;
+        "#,
+            )
+            .bind(id) // $1
+            .bind(producer_id) // $2
+            .bind(new), // $3
+            (Some(current), Some(new)) => sqlx::query(
+                r#"
+UPDATE producer_sequence
+SET sequence_number = $3
+WHERE namespace_id = $1 AND producer_id = $2 AND sequence_number = $4;
+        "#,
+            )
+            .bind(id) // $1
+            .bind(producer_id) // $2
+            .bind(new) // $3
+            .bind(current), // $4
+            (Some(current), None) => sqlx::query(
+                r#"
+DELETE FROM producer_sequence
+WHERE namespace_id = $1 AND producer_id = $2 AND sequence_number = $3;
+        "#,
+            )
+            .bind(id) // $1
+            .bind(producer_id) // $2
+            .bind(current), // $3
+        };
+
+        let res = query.execute(&mut self.inner).await.map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        Ok(res.rows_affected() == 1)
+    }
 }
 
 #[async_trait]
@@ -870,6 +1306,48 @@ WHERE namespace_id = $1;
 
         Ok(rec)
     }
+
+    async fn get_shard_pin(&mut self, table_id: TableId) -> Result<Option<ShardId>> {
+        sqlx::query_scalar::<_, ShardId>(
+            r#"
+SELECT shard_id
+FROM table_shard_pin
+WHERE table_id = $1;
+            "#,
+        )
+        .bind(table_id) // $1
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn create_or_get_shard_pin(
+        &mut self,
+        table_id: TableId,
+        shard_id: ShardId,
+    ) -> Result<ShardId> {
+        // The no-op update on conflict returns the existing pin, which is never changed.
+        sqlx::query_scalar::<_, ShardId>(
+            r#"
+INSERT INTO table_shard_pin ( table_id, shard_id )
+VALUES ( $1, $2 )
+ON CONFLICT ( table_id )
+DO UPDATE SET table_id = table_shard_pin.table_id
+RETURNING shard_id;
+            "#,
+        )
+        .bind(table_id) // $1
+        .bind(shard_id) // $2
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
 }
 
 #[async_trait]
@@ -882,8 +1360,8 @@ impl ColumnRepo for PostgresTxn {
     ) -> Result<Column> {
         let rec = sqlx::query_as::<_, Column>(
             r#"
-INSERT INTO column_name ( name, table_id, column_type )
-SELECT $1, table_id, $3 FROM (
+INSERT INTO column_name ( name, table_id, column_type, created_at )
+SELECT $1, table_id, $3, $4 FROM (
     SELECT max_columns_per_table, namespace.id, table_name.id as table_id, COUNT(column_name.*) AS count
     FROM namespace LEFT JOIN table_name ON namespace.id = table_name.namespace_id
                    LEFT JOIN column_name ON table_name.id = column_name.table_id
@@ -898,6 +1376,7 @@ RETURNING *;
         .bind(name) // $1
         .bind(table_id) // $2
         .bind(column_type) // $3
+        .bind(Timestamp::from(self.time_provider.now())) // $4
         .fetch_one(&mut self.inner)
         .await
         .map_err(|e| match e {
@@ -984,8 +1463,8 @@ WHERE table_id = $1;
         // - <https://github.com/influxdata/idpe/issues/16298>
         let out = sqlx::query_as::<_, Column>(
             r#"
-INSERT INTO column_name ( name, table_id, column_type )
-SELECT name, $1, column_type
+INSERT INTO column_name ( name, table_id, column_type, created_at )
+SELECT name, $1, column_type, $4
 FROM UNNEST($2, $3) as a(name, column_type)
 ORDER BY name
 ON CONFLICT ON CONSTRAINT column_name_unique
@@ -996,6 +1475,7 @@ RETURNING *;
         .bind(table_id) // $1
         .bind(&v_name) // $2
         .bind(&v_column_type) // $3
+        .bind(Timestamp::from(self.time_provider.now())) // $4
         .fetch_all(&mut self.inner)
         .await
         .map_err(|e| {
@@ -1104,28 +1584,219 @@ WHERE topic_id = $1
             .map_err(|e| Error::SqlxError { source: e })
     }
 
-    async fn list_by_topic(&mut self, topic: &TopicMetadata) -> Result<Vec<Shard>> {
-        sqlx::query_as::<_, Shard>(r#"SELECT * FROM shard WHERE topic_id = $1;"#)
-            .bind(topic.id) // $1
-            .fetch_all(&mut self.inner)
-            .await
-            .map_err(|e| Error::SqlxError { source: e })
+    async fn list_by_topic(&mut self, topic: &TopicMetadata) -> Result<Vec<Shard>> {
+        sqlx::query_as::<_, Shard>(r#"SELECT * FROM shard WHERE topic_id = $1;"#)
+            .bind(topic.id) // $1
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn update_min_unpersisted_sequence_number(
+        &mut self,
+        shard_id: ShardId,
+        sequence_number: SequenceNumber,
+    ) -> Result<()> {
+        let _ = sqlx::query(
+            r#"
+UPDATE shard
+SET min_unpersisted_sequence_number = $1
+WHERE id = $2;
+                "#,
+        )
+        .bind(sequence_number.get()) // $1
+        .bind(shard_id) // $2
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+
+    async fn acquire_lease(
+        &mut self,
+        shard_id: ShardId,
+        holder: &str,
+        duration: Duration,
+    ) -> Result<ShardLease> {
+        // The lease is only taken over if it is held by `holder` or has expired, by the clock of
+        // the database. The epoch is kept when an unexpired lease is renewed.
+        sqlx::query(
+            r#"
+INSERT INTO shard_lease ( shard_id, holder, expires_at, epoch )
+VALUES ( $1, $2, (EXTRACT(EPOCH FROM now()) * 1000000000)::BIGINT + $3, 1 )
+ON CONFLICT ( shard_id )
+DO UPDATE SET
+    holder = excluded.holder,
+    expires_at = excluded.expires_at,
+    epoch = CASE
+        WHEN shard_lease.holder = excluded.holder
+            AND shard_lease.expires_at > (EXTRACT(EPOCH FROM now()) * 1000000000)::BIGINT
+        THEN shard_lease.epoch
+        ELSE shard_lease.epoch + 1
+    END
+WHERE shard_lease.holder = excluded.holder
+    OR shard_lease.expires_at <= (EXTRACT(EPOCH FROM now()) * 1000000000)::BIGINT;
+        "#,
+        )
+        .bind(shard_id) // $1
+        .bind(holder) // $2
+        .bind(duration.as_nanos() as i64) // $3
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        sqlx::query_as::<_, ShardLease>(r#"SELECT * FROM shard_lease WHERE shard_id = $1;"#)
+            .bind(shard_id) // $1
+            .fetch_one(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn release_lease(&mut self, shard_id: ShardId, holder: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+UPDATE shard_lease
+SET expires_at = 0
+WHERE shard_id = $1 AND holder = $2;
+        "#,
+        )
+        .bind(shard_id) // $1
+        .bind(holder) // $2
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+
+    async fn check_lease(&mut self, shard_id: ShardId, holder: &str, epoch: i64) -> Result<()> {
+        // The row lock blocks a takeover of the lease until the transaction of the caller
+        // completes.
+        let lease = sqlx::query_as::<_, ShardLease>(
+            r#"
+SELECT * FROM shard_lease
+WHERE shard_id = $1 AND holder = $2 AND epoch = $3 AND expires_at > (EXTRACT(EPOCH FROM now()) * 1000000000)::BIGINT
+FOR SHARE;
+        "#,
+        )
+        .bind(shard_id) // $1
+        .bind(holder) // $2
+        .bind(epoch) // $3
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        match lease {
+            Some(_) => Ok(()),
+            None => Err(Error::LeaseLost {
+                shard_id,
+                holder: holder.to_string(),
+                epoch,
+            }),
+        }
+    }
+
+    async fn list_held_leases(&mut self, topic_id: TopicId) -> Result<Vec<ShardLease>> {
+        sqlx::query_as::<_, ShardLease>(
+            r#"
+SELECT shard_lease.* FROM shard_lease
+INNER JOIN shard ON shard.id = shard_lease.shard_id
+WHERE shard.topic_id = $1 AND shard_lease.expires_at > (EXTRACT(EPOCH FROM now()) * 1000000000)::BIGINT
+ORDER BY shard_lease.shard_id;
+        "#,
+        )
+        .bind(topic_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn record_ingester_heartbeat(
+        &mut self,
+        topic_id: TopicId,
+        address: &str,
+        at: Timestamp,
+    ) -> Result<IngesterRegistration> {
+        sqlx::query_as::<_, IngesterRegistration>(
+            r#"
+INSERT INTO ingester_registration ( address, topic_id, shard_indexes, last_heartbeat )
+VALUES ( $1, $2, '{}', $3 )
+ON CONFLICT ( address )
+DO UPDATE SET
+    shard_indexes = CASE
+        WHEN ingester_registration.topic_id = excluded.topic_id
+        THEN ingester_registration.shard_indexes
+        ELSE '{}'
+    END,
+    topic_id = excluded.topic_id,
+    last_heartbeat = excluded.last_heartbeat
+RETURNING *;
+        "#,
+        )
+        .bind(address) // $1
+        .bind(topic_id) // $2
+        .bind(at) // $3
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
+
+    async fn list_ingesters(&mut self, topic_id: TopicId) -> Result<Vec<IngesterRegistration>> {
+        sqlx::query_as::<_, IngesterRegistration>(
+            r#"
+SELECT *
+FROM ingester_registration
+WHERE topic_id = $1
+ORDER BY address;
+        "#,
+        )
+        .bind(topic_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn assign_shards(&mut self, address: &str, shard_indexes: &[ShardIndex]) -> Result<()> {
+        let mut shard_indexes = shard_indexes.to_vec();
+        shard_indexes.sort();
+
+        sqlx::query(
+            r#"
+UPDATE ingester_registration
+SET shard_indexes = $1
+WHERE address = $2;
+        "#,
+        )
+        .bind(shard_indexes.iter().map(|v| v.get()).collect::<Vec<_>>()) // $1
+        .bind(address) // $2
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
     }
 
-    async fn update_min_unpersisted_sequence_number(
-        &mut self,
-        shard_id: ShardId,
-        sequence_number: SequenceNumber,
-    ) -> Result<()> {
-        let _ = sqlx::query(
+    async fn remove_ingester(&mut self, address: &str) -> Result<()> {
+        sqlx::query(
             r#"
-UPDATE shard
-SET min_unpersisted_sequence_number = $1
-WHERE id = $2;
-                "#,
+DELETE FROM ingester_registration
+WHERE address = $1;
+        "#,
         )
-        .bind(sequence_number.get()) // $1
-        .bind(shard_id) // $2
+        .bind(address) // $1
         .execute(&mut self.inner)
         .await
         .map_err(|e| Error::SqlxError { source: e })?;
@@ -1344,7 +2015,7 @@ RETURNING *
         let _ = sqlx::query(
             r#"
 UPDATE partition
-SET persisted_sequence_number = $1
+SET persisted_sequence_number = $1, cold_compacted_at = NULL
 WHERE id = $2;
                 "#,
         )
@@ -1357,6 +2028,34 @@ WHERE id = $2;
         Ok(())
     }
 
+    async fn mark_cold(
+        &mut self,
+        partition_id: PartitionId,
+        cold_compacted_at: Timestamp,
+    ) -> Result<bool> {
+        let res = sqlx::query(
+            r#"
+UPDATE partition
+SET cold_compacted_at = $1
+WHERE id = $2
+  AND NOT EXISTS (
+    SELECT 1 FROM parquet_file
+    WHERE parquet_file.partition_id = $2
+      AND parquet_file.to_delete IS NULL
+      AND parquet_file.compaction_level != $3
+  );
+                "#,
+        )
+        .bind(cold_compacted_at) // $1
+        .bind(partition_id) // $2
+        .bind(CompactionLevel::Final) // $3
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
     async fn most_recent_n(&mut self, n: usize, shards: &[ShardId]) -> Result<Vec<Partition>> {
         sqlx::query_as(
             r#"SELECT * FROM partition WHERE shard_id IN (SELECT UNNEST($1)) ORDER BY id DESC LIMIT $2;"#,
@@ -1732,6 +2431,36 @@ WHERE table_id = $1 AND to_delete IS NULL;
         .map_err(|e| Error::SqlxError { source: e })
     }
 
+    async fn list_by_table_and_time_range(
+        &mut self,
+        table_id: TableId,
+        min_time: Timestamp,
+        max_time: Timestamp,
+    ) -> Result<Vec<ParquetFile>> {
+        // Deliberately doesn't use `SELECT *` to avoid the performance hit of fetching the large
+        // `parquet_metadata` column!!
+        //
+        // Served by `parquet_file_table_time_idx`.
+        sqlx::query_as::<_, ParquetFile>(
+            r#"
+SELECT id, shard_id, namespace_id, table_id, partition_id, object_store_id,
+       max_sequence_number, min_time, max_time, to_delete, file_size_bytes,
+       row_count, compaction_level, created_at, column_set
+FROM parquet_file
+WHERE table_id = $1
+  AND to_delete IS NULL
+  AND max_time >= $2
+  AND min_time <= $3;
+             "#,
+        )
+        .bind(table_id) // $1
+        .bind(min_time) // $2
+        .bind(max_time) // $3
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
     async fn delete_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>> {
         sqlx::query_as::<_, ParquetFile>(
             r#"
@@ -2068,6 +2797,63 @@ WHERE object_store_id = $1;
 
         Ok(Some(parquet_file))
     }
+
+    async fn create_lineage(
+        &mut self,
+        parquet_file: &ParquetFile,
+        inputs: &[(ParquetFileId, CompactionLevel)],
+    ) -> Result<()> {
+        let (v_input_id, v_input_level): (Vec<i64>, Vec<i16>) = inputs
+            .iter()
+            .map(|&(id, level)| (id.get(), level as i16))
+            .unzip();
+
+        sqlx::query(
+            r#"
+INSERT INTO parquet_file_lineage
+    ( parquet_file_id, compaction_level, input_parquet_file_id, input_compaction_level,
+      namespace_id, table_id )
+SELECT $1, $2, input_id, input_level, $3, $4
+FROM UNNEST($5, $6) as a(input_id, input_level)
+ON CONFLICT DO NOTHING;
+            "#,
+        )
+        .bind(parquet_file.id) // $1
+        .bind(parquet_file.compaction_level) // $2
+        .bind(parquet_file.namespace_id) // $3
+        .bind(parquet_file.table_id) // $4
+        .bind(&v_input_id) // $5
+        .bind(&v_input_level) // $6
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        Ok(())
+    }
+
+    async fn list_lineage_by_namespace(
+        &mut self,
+        namespace_id: NamespaceId,
+    ) -> Result<Vec<ParquetFileLineage>> {
+        sqlx::query_as::<_, ParquetFileLineage>(
+            r#"
+SELECT *
+FROM parquet_file_lineage
+WHERE namespace_id = $1
+ORDER BY parquet_file_id, input_parquet_file_id;
+             "#,
+        )
+        .bind(namespace_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
 }
 
 #[async_trait]
@@ -2147,6 +2933,249 @@ WHERE parquet_file_id = $1
     }
 }
 
+#[async_trait]
+impl OperationRepo for PostgresTxn {
+    async fn create(
+        &mut self,
+        kind: &str,
+        description: &str,
+        node: &str,
+        created_at: Timestamp,
+    ) -> Result<Operation> {
+        sqlx::query_as::<_, Operation>(
+            r#"
+INSERT INTO operation ( kind, description, node, status, created_at )
+VALUES ( $1, $2, $3, $4, $5 )
+RETURNING *;
+        "#,
+        )
+        .bind(kind) // $1
+        .bind(description) // $2
+        .bind(node) // $3
+        .bind(OperationStatus::Running) // $4
+        .bind(created_at) // $5
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn get_by_id(&mut self, id: OperationId) -> Result<Option<Operation>> {
+        let rec = sqlx::query_as::<_, Operation>(r#"SELECT * FROM operation WHERE id = $1;"#)
+            .bind(id) // $1
+            .fetch_one(&mut self.inner)
+            .await;
+
+        if let Err(sqlx::Error::RowNotFound) = rec {
+            return Ok(None);
+        }
+
+        let operation = rec.map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(Some(operation))
+    }
+
+    async fn list(&mut self) -> Result<Vec<Operation>> {
+        sqlx::query_as::<_, Operation>(r#"SELECT * FROM operation ORDER BY id;"#)
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn complete(
+        &mut self,
+        id: OperationId,
+        status: OperationStatus,
+        completed_at: Timestamp,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+UPDATE operation
+SET status = $1, completed_at = $2
+WHERE id = $3
+  AND status = $4;
+        "#,
+        )
+        .bind(status) // $1
+        .bind(completed_at) // $2
+        .bind(id) // $3
+        .bind(OperationStatus::Running) // $4
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+
+    async fn interrupt_running(&mut self, node: &str, at: Timestamp) -> Result<Vec<Operation>> {
+        let mut interrupted = sqlx::query_as::<_, Operation>(
+            r#"
+UPDATE operation
+SET status = $1, completed_at = $2
+WHERE node = $3
+  AND status = $4
+RETURNING *;
+        "#,
+        )
+        .bind(OperationStatus::Interrupted) // $1
+        .bind(at) // $2
+        .bind(node) // $3
+        .bind(OperationStatus::Running) // $4
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        // RETURNING does not guarantee any order
+        interrupted.sort_by_key(|o| o.id);
+
+        Ok(interrupted)
+    }
+
+    async fn delete_completed_before(&mut self, older_than: Timestamp) -> Result<u64> {
+        let deleted = sqlx::query(
+            r#"
+DELETE FROM operation
+WHERE completed_at < $1;
+        "#,
+        )
+        .bind(older_than) // $1
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(deleted.rows_affected())
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepo for PostgresTxn {
+    async fn create(
+        &mut self,
+        token_hash: &str,
+        namespace_id: Option<NamespaceId>,
+        permission: ApiTokenPermission,
+        description: &str,
+        created_at: Timestamp,
+    ) -> Result<ApiToken> {
+        sqlx::query_as::<_, ApiToken>(
+            r#"
+INSERT INTO api_token ( token_hash, namespace_id, permission, description, created_at )
+VALUES ( $1, $2, $3, $4, $5 )
+RETURNING *;
+        "#,
+        )
+        .bind(token_hash) // $1
+        .bind(namespace_id) // $2
+        .bind(permission) // $3
+        .bind(description) // $4
+        .bind(created_at) // $5
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                Error::ApiTokenExists
+            } else if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
+
+    async fn get_by_hash(&mut self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let rec =
+            sqlx::query_as::<_, ApiToken>(r#"SELECT * FROM api_token WHERE token_hash = $1;"#)
+                .bind(token_hash) // $1
+                .fetch_one(&mut self.inner)
+                .await;
+
+        if let Err(sqlx::Error::RowNotFound) = rec {
+            return Ok(None);
+        }
+
+        let token = rec.map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(Some(token))
+    }
+
+    async fn list(&mut self) -> Result<Vec<ApiToken>> {
+        sqlx::query_as::<_, ApiToken>(r#"SELECT * FROM api_token ORDER BY id;"#)
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn revoke(&mut self, id: ApiTokenId, revoked_at: Timestamp) -> Result<Option<ApiToken>> {
+        let rec = sqlx::query_as::<_, ApiToken>(
+            r#"
+UPDATE api_token
+SET revoked_at = COALESCE(revoked_at, $1)
+WHERE id = $2
+RETURNING *;
+        "#,
+        )
+        .bind(revoked_at) // $1
+        .bind(id) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        if let Err(sqlx::Error::RowNotFound) = rec {
+            return Ok(None);
+        }
+
+        let token = rec.map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(Some(token))
+    }
+}
+
+#[async_trait]
+impl NodeDrainRepo for PostgresTxn {
+    async fn set_state(
+        &mut self,
+        address: &str,
+        state: DrainState,
+        updated_at: Timestamp,
+    ) -> Result<NodeDrain> {
+        sqlx::query_as::<_, NodeDrain>(
+            r#"
+INSERT INTO node_drain ( address, state, updated_at )
+VALUES ( $1, $2, $3 )
+ON CONFLICT ( address )
+DO UPDATE SET state = excluded.state, updated_at = excluded.updated_at
+RETURNING *;
+        "#,
+        )
+        .bind(address) // $1
+        .bind(state) // $2
+        .bind(updated_at) // $3
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn get_by_address(&mut self, address: &str) -> Result<Option<NodeDrain>> {
+        let rec = sqlx::query_as::<_, NodeDrain>(r#"SELECT * FROM node_drain WHERE address = $1;"#)
+            .bind(address) // $1
+            .fetch_one(&mut self.inner)
+            .await;
+
+        if let Err(sqlx::Error::RowNotFound) = rec {
+            return Ok(None);
+        }
+
+        let drain = rec.map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(Some(drain))
+    }
+
+    async fn list(&mut self) -> Result<Vec<NodeDrain>> {
+        sqlx::query_as::<_, NodeDrain>(r#"SELECT * FROM node_drain ORDER BY address;"#)
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+}
+
 /// The error code returned by Postgres for a unique constraint violation.
 ///
 /// See <https://www.postgresql.org/docs/9.2/errcodes-appendix.html>
@@ -2325,6 +3354,25 @@ mod tests {
         crate::interface::test_helpers::test_catalog(postgres).await;
     }
 
+    #[tokio::test]
+    async fn test_migration_status() {
+        // If running an integration test on your laptop, this requires that you have Postgres
+        // running and that you've done the sqlx migrations. See the README in this crate for
+        // info to set it up.
+        maybe_skip_integration!();
+
+        let postgres = setup_db().await;
+
+        // setup_db applied every migration
+        let status = postgres.migration_status().await.unwrap();
+        assert!(status.is_up_to_date(), "{:?}", status);
+        assert!(!status.applied.is_empty());
+
+        // migrating an up to date schema is a no-op
+        postgres.migrate(None).await.unwrap();
+        assert_eq!(postgres.migration_status().await.unwrap(), status);
+    }
+
     #[tokio::test]
     async fn test_tombstone_create_or_get_idempotent() {
         // If running an integration test on your laptop, this requires that you have Postgres
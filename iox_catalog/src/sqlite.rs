@@ -0,0 +1,3122 @@
+//! A SQLite backed implementation of the Catalog
+//!
+//! This is intended for single-node deployments (e.g. all-in-one mode) that do not want to run a
+//! Postgres server. Its schema lives in `sqlite_migrations` and mirrors the Postgres schema, with
+//! array columns stored as JSON text.
+
+use crate::{
+    interface::{
+        self, sealed::TransactionFinalize, ApiTokenRepo, Catalog, ColumnRepo,
+        ColumnTypeMismatchSnafu, Error, MigrationStatus, NamespaceRepo, NodeDrainRepo,
+        OperationRepo, ParquetFileRepo, PartitionRepo, ProcessedTombstoneRepo, QueryPoolRepo,
+        RepoCollection, Result, ShardRepo, TableRepo, TombstoneRepo, TopicMetadataRepo,
+        Transaction,
+    },
+    metrics::MetricDecorator,
+    migrate, DEFAULT_MAX_COLUMNS_PER_TABLE, DEFAULT_MAX_TABLES,
+};
+use async_trait::async_trait;
+use data_types::{
+    ApiToken, ApiTokenId, ApiTokenPermission, Column, ColumnId, ColumnSet, ColumnType,
+    ColumnTypeCount, CompactionLevel, DrainState, IngesterRegistration, Namespace, NamespaceId,
+    NamespaceUsage, NodeDrain, Operation, OperationId, OperationStatus, ParquetFile, ParquetFileId,
+    ParquetFileLineage, ParquetFileParams, Partition, PartitionId, PartitionKey, PartitionParam,
+    PartitionTemplate, ProcessedTombstone, QuerierRegistration, QueryPool, QueryPoolId,
+    SequenceNumber, Shard, ShardId, ShardIndex, ShardLease, SkippedCompaction, Table, TableId,
+    TablePartition, Timestamp, Tombstone, TombstoneId, TopicId, TopicMetadata,
+};
+use iox_time::{SystemProvider, TimeProvider};
+use observability_deps::tracing::{debug, info, warn};
+use snafu::prelude::*;
+use sqlx::{
+    migrate::Migrator,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+    types::{Json, Uuid},
+    ConnectOptions, Executor, Pool, Row, Sqlite,
+};
+use std::{collections::HashMap, str::FromStr, sync::Arc, time::Duration};
+
+static MIGRATOR: Migrator = sqlx::migrate!("./sqlite_migrations");
+
+/// Maximum number of files deleted by [`ParquetFileRepo::delete_old_ids_only].
+const MAX_PARQUET_FILES_DELETED_ONCE: i64 = 1_000;
+
+/// SQLite connection options.
+#[derive(Debug, Clone)]
+pub struct SqliteConnectionOptions {
+    /// DSN, e.g. `sqlite:///var/lib/iox/catalog.sqlite` or `sqlite::memory:`.
+    ///
+    /// The database file is created if it does not exist.
+    pub dsn: String,
+
+    /// Maximum number of concurrent connections.
+    pub max_conns: u32,
+}
+
+impl SqliteConnectionOptions {
+    /// Default value for [`max_conns`](Self::max_conns).
+    pub const DEFAULT_MAX_CONNS: u32 = 10;
+}
+
+impl Default for SqliteConnectionOptions {
+    fn default() -> Self {
+        Self {
+            dsn: String::from("sqlite::memory:"),
+            max_conns: Self::DEFAULT_MAX_CONNS,
+        }
+    }
+}
+
+/// SQLite catalog.
+#[derive(Debug)]
+pub struct SqliteCatalog {
+    metrics: Arc<metric::Registry>,
+    pool: Pool<Sqlite>,
+    time_provider: Arc<dyn TimeProvider>,
+}
+
+// struct to get return value from "select count(id) ..." query
+#[derive(sqlx::FromRow)]
+struct Count {
+    count: i64,
+}
+
+impl SqliteCatalog {
+    /// Connect to the catalog store.
+    pub async fn connect(
+        options: SqliteConnectionOptions,
+        metrics: Arc<metric::Registry>,
+    ) -> Result<Self> {
+        let mut connect_options = SqliteConnectOptions::from_str(&options.dsn)
+            .map_err(|e| Error::SqlxError { source: e })?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .foreign_keys(true);
+        // the default is INFO, which is frankly surprising.
+        connect_options.log_statements(log::LevelFilter::Trace);
+
+        // An in-memory database only lives as long as at least one connection to it is open, so
+        // never let the pool close its last connection.
+        let pool = SqlitePoolOptions::new()
+            .min_connections(1)
+            .max_connections(options.max_conns)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(connect_options)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })?;
+
+        info!(dsn = %options.dsn, "connected to SQLite catalog");
+
+        Ok(Self {
+            pool,
+            metrics,
+            time_provider: Arc::new(SystemProvider::new()),
+        })
+    }
+}
+
+/// transaction for [`SqliteCatalog`].
+#[derive(Debug)]
+pub struct SqliteTxn {
+    inner: SqliteTxnInner,
+    time_provider: Arc<dyn TimeProvider>,
+}
+
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+enum SqliteTxnInner {
+    Txn(Option<sqlx::Transaction<'static, Sqlite>>),
+    Oneshot(Pool<Sqlite>),
+}
+
+impl<'c> Executor<'c> for &'c mut SqliteTxnInner {
+    type Database = Sqlite;
+
+    #[allow(clippy::type_complexity)]
+    fn fetch_many<'e, 'q: 'e, E: 'q>(
+        self,
+        query: E,
+    ) -> futures::stream::BoxStream<
+        'e,
+        Result<
+            sqlx::Either<
+                <Self::Database as sqlx::Database>::QueryResult,
+                <Self::Database as sqlx::Database>::Row,
+            >,
+            sqlx::Error,
+        >,
+    >
+    where
+        'c: 'e,
+        E: sqlx::Execute<'q, Self::Database>,
+    {
+        match self {
+            SqliteTxnInner::Txn(txn) => txn.as_mut().expect("Not yet finalized").fetch_many(query),
+            SqliteTxnInner::Oneshot(pool) => pool.fetch_many(query),
+        }
+    }
+
+    fn fetch_optional<'e, 'q: 'e, E: 'q>(
+        self,
+        query: E,
+    ) -> futures::future::BoxFuture<
+        'e,
+        Result<Option<<Self::Database as sqlx::Database>::Row>, sqlx::Error>,
+    >
+    where
+        'c: 'e,
+        E: sqlx::Execute<'q, Self::Database>,
+    {
+        match self {
+            SqliteTxnInner::Txn(txn) => txn
+                .as_mut()
+                .expect("Not yet finalized")
+                .fetch_optional(query),
+            SqliteTxnInner::Oneshot(pool) => pool.fetch_optional(query),
+        }
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [<Self::Database as sqlx::Database>::TypeInfo],
+    ) -> futures::future::BoxFuture<
+        'e,
+        Result<<Self::Database as sqlx::database::HasStatement<'q>>::Statement, sqlx::Error>,
+    >
+    where
+        'c: 'e,
+    {
+        match self {
+            SqliteTxnInner::Txn(txn) => txn
+                .as_mut()
+                .expect("Not yet finalized")
+                .prepare_with(sql, parameters),
+            SqliteTxnInner::Oneshot(pool) => pool.prepare_with(sql, parameters),
+        }
+    }
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> futures::future::BoxFuture<'e, Result<sqlx::Describe<Self::Database>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        match self {
+            SqliteTxnInner::Txn(txn) => txn.as_mut().expect("Not yet finalized").describe(sql),
+            SqliteTxnInner::Oneshot(pool) => pool.describe(sql),
+        }
+    }
+}
+
+impl Drop for SqliteTxn {
+    fn drop(&mut self) {
+        if let SqliteTxnInner::Txn(Some(_)) = self.inner {
+            warn!("Dropping SqliteTxn w/o finalizing (commit or abort)");
+
+            // SQLx ensures that the inner transaction enqueues a rollback when it is dropped, so
+            // we don't need to spawn a task here to call `rollback` manually.
+        }
+    }
+}
+
+#[async_trait]
+impl TransactionFinalize for SqliteTxn {
+    async fn commit_inplace(&mut self) -> Result<(), Error> {
+        match &mut self.inner {
+            SqliteTxnInner::Txn(txn) => txn
+                .take()
+                .expect("Not yet finalized")
+                .commit()
+                .await
+                .map_err(|e| Error::SqlxError { source: e }),
+            SqliteTxnInner::Oneshot(_) => {
+                panic!("cannot commit oneshot");
+            }
+        }
+    }
+
+    async fn abort_inplace(&mut self) -> Result<(), Error> {
+        match &mut self.inner {
+            SqliteTxnInner::Txn(txn) => txn
+                .take()
+                .expect("Not yet finalized")
+                .rollback()
+                .await
+                .map_err(|e| Error::SqlxError { source: e }),
+            SqliteTxnInner::Oneshot(_) => {
+                panic!("cannot abort oneshot");
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Catalog for SqliteCatalog {
+    async fn setup(&self) -> Result<(), Error> {
+        MIGRATOR
+            .run(&self.pool)
+            .await
+            .map_err(|e| Error::Setup { source: e.into() })?;
+
+        Ok(())
+    }
+
+    async fn migration_status(&self) -> Result<MigrationStatus, Error> {
+        // Do not create the migrations table just to look at it.
+        let migrations_table_exists = sqlx::query(
+            r#"
+SELECT EXISTS (
+    SELECT 1 FROM sqlite_master
+    WHERE type = 'table' AND name = '_sqlx_migrations'
+);
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?
+        .get::<bool, _>(0);
+
+        if !migrations_table_exists {
+            return Ok(MigrationStatus {
+                pending: migrate::pending(&MIGRATOR, &[]),
+                ..Default::default()
+            });
+        }
+
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .map_err(|e| Error::SqlxError { source: e })?;
+
+        migrate::status(&mut *conn, &MIGRATOR)
+            .await
+            .map_err(|e| Error::Setup { source: e.into() })
+    }
+
+    async fn migrate(&self, target_version: Option<i64>) -> Result<(), Error> {
+        let mut conn = self
+            .pool
+            .acquire()
+            .await
+            .map_err(|e| Error::SqlxError { source: e })?;
+
+        migrate::run(&mut *conn, &MIGRATOR, target_version)
+            .await
+            .map_err(|e| Error::Setup { source: e.into() })
+    }
+
+    async fn start_transaction(&self) -> Result<Box<dyn Transaction>, Error> {
+        let transaction = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(Box::new(MetricDecorator::new(
+            SqliteTxn {
+                inner: SqliteTxnInner::Txn(Some(transaction)),
+                time_provider: Arc::clone(&self.time_provider),
+            },
+            Arc::clone(&self.metrics),
+        )))
+    }
+
+    async fn repositories(&self) -> Box<dyn RepoCollection> {
+        Box::new(MetricDecorator::new(
+            SqliteTxn {
+                inner: SqliteTxnInner::Oneshot(self.pool.clone()),
+                time_provider: Arc::clone(&self.time_provider),
+            },
+            Arc::clone(&self.metrics),
+        ))
+    }
+
+    fn metrics(&self) -> Arc<metric::Registry> {
+        Arc::clone(&self.metrics)
+    }
+
+    fn time_provider(&self) -> Arc<dyn TimeProvider> {
+        Arc::clone(&self.time_provider)
+    }
+}
+
+#[async_trait]
+impl RepoCollection for SqliteTxn {
+    fn topics(&mut self) -> &mut dyn TopicMetadataRepo {
+        self
+    }
+
+    fn query_pools(&mut self) -> &mut dyn QueryPoolRepo {
+        self
+    }
+
+    fn namespaces(&mut self) -> &mut dyn NamespaceRepo {
+        self
+    }
+
+    fn tables(&mut self) -> &mut dyn TableRepo {
+        self
+    }
+
+    fn columns(&mut self) -> &mut dyn ColumnRepo {
+        self
+    }
+
+    fn shards(&mut self) -> &mut dyn ShardRepo {
+        self
+    }
+
+    fn partitions(&mut self) -> &mut dyn PartitionRepo {
+        self
+    }
+
+    fn tombstones(&mut self) -> &mut dyn TombstoneRepo {
+        self
+    }
+
+    fn parquet_files(&mut self) -> &mut dyn ParquetFileRepo {
+        self
+    }
+
+    fn processed_tombstones(&mut self) -> &mut dyn ProcessedTombstoneRepo {
+        self
+    }
+
+    fn operations(&mut self) -> &mut dyn OperationRepo {
+        self
+    }
+
+    fn api_tokens(&mut self) -> &mut dyn ApiTokenRepo {
+        self
+    }
+
+    fn node_drains(&mut self) -> &mut dyn NodeDrainRepo {
+        self
+    }
+}
+
+#[async_trait]
+impl TopicMetadataRepo for SqliteTxn {
+    async fn create_or_get(&mut self, name: &str) -> Result<TopicMetadata> {
+        let rec = sqlx::query_as::<_, TopicMetadata>(
+            r#"
+INSERT INTO topic ( name )
+VALUES ( $1 )
+ON CONFLICT ( name )
+DO UPDATE SET name = topic.name
+RETURNING *;
+        "#,
+        )
+        .bind(name) // $1
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(rec)
+    }
+
+    async fn get_by_name(&mut self, name: &str) -> Result<Option<TopicMetadata>> {
+        sqlx::query_as::<_, TopicMetadata>(
+            r#"
+SELECT *
+FROM topic
+WHERE name = $1;
+        "#,
+        )
+        .bind(name) // $1
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list(&mut self) -> Result<Vec<TopicMetadata>> {
+        sqlx::query_as::<_, TopicMetadata>(r#"SELECT * FROM topic;"#)
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+}
+
+#[async_trait]
+impl QueryPoolRepo for SqliteTxn {
+    async fn create_or_get(&mut self, name: &str) -> Result<QueryPool> {
+        let rec = sqlx::query_as::<_, QueryPool>(
+            r#"
+INSERT INTO query_pool ( name )
+VALUES ( $1 )
+ON CONFLICT ( name )
+DO UPDATE SET name = query_pool.name
+RETURNING *;
+        "#,
+        )
+        .bind(name) // $1
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(rec)
+    }
+
+    async fn record_querier_heartbeat(
+        &mut self,
+        query_pool_id: QueryPoolId,
+        address: &str,
+        at: Timestamp,
+    ) -> Result<QuerierRegistration> {
+        sqlx::query_as::<_, QuerierRegistration>(
+            r#"
+INSERT INTO querier_registration ( address, query_pool_id, last_heartbeat )
+VALUES ( $1, $2, $3 )
+ON CONFLICT ( address )
+DO UPDATE SET query_pool_id = excluded.query_pool_id, last_heartbeat = excluded.last_heartbeat
+RETURNING *;
+        "#,
+        )
+        .bind(address) // $1
+        .bind(query_pool_id) // $2
+        .bind(at) // $3
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
+
+    async fn list_queriers(
+        &mut self,
+        query_pool_id: QueryPoolId,
+        heartbeat_after: Timestamp,
+    ) -> Result<Vec<QuerierRegistration>> {
+        sqlx::query_as::<_, QuerierRegistration>(
+            r#"
+SELECT *
+FROM querier_registration
+WHERE query_pool_id = $1
+  AND last_heartbeat >= $2
+ORDER BY address;
+        "#,
+        )
+        .bind(query_pool_id) // $1
+        .bind(heartbeat_after) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn remove_querier(&mut self, address: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+DELETE FROM querier_registration
+WHERE address = $1;
+        "#,
+        )
+        .bind(address) // $1
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl NamespaceRepo for SqliteTxn {
+    async fn create(
+        &mut self,
+        name: &str,
+        retention_period_ns: Option<i64>,
+        topic_id: TopicId,
+        query_pool_id: QueryPoolId,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+INSERT INTO namespace ( name, topic_id, query_pool_id, retention_period_ns )
+VALUES ( $1, $2, $3, $4 )
+RETURNING *;
+            "#,
+        )
+        .bind(name) // $1
+        .bind(topic_id) // $2
+        .bind(query_pool_id) // $3
+        .bind(retention_period_ns) // $4
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                Error::NameExists {
+                    name: name.to_string(),
+                }
+            } else if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        // Ensure the column default values match the code values.
+        debug_assert_eq!(rec.max_tables, DEFAULT_MAX_TABLES);
+        debug_assert_eq!(rec.max_columns_per_table, DEFAULT_MAX_COLUMNS_PER_TABLE);
+
+        Ok(rec)
+    }
+
+    async fn list(&mut self) -> Result<Vec<Namespace>> {
+        sqlx::query_as::<_, Namespace>("SELECT * FROM namespace;")
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_paged(
+        &mut self,
+        after: Option<NamespaceId>,
+        limit: usize,
+    ) -> Result<Vec<Namespace>> {
+        sqlx::query_as::<_, Namespace>(
+            r#"
+SELECT *
+FROM namespace
+WHERE id > $1
+ORDER BY id
+LIMIT $2;
+            "#,
+        )
+        .bind(after.map_or(i64::MIN, |id| id.get())) // $1
+        .bind(limit as i64) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn get_by_id(&mut self, id: NamespaceId) -> Result<Option<Namespace>> {
+        sqlx::query_as::<_, Namespace>("SELECT * FROM namespace WHERE id = $1;")
+            .bind(id) // $1
+            .fetch_optional(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn get_by_name(&mut self, name: &str) -> Result<Option<Namespace>> {
+        sqlx::query_as::<_, Namespace>("SELECT * FROM namespace WHERE name = $1;")
+            .bind(name) // $1
+            .fetch_optional(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn update_table_limit(&mut self, name: &str, new_max: i32) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET max_tables = $1
+WHERE name = $2
+RETURNING *;
+        "#,
+        )
+        .bind(new_max) // $1
+        .bind(name) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn update_column_limit(&mut self, name: &str, new_max: i32) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET max_columns_per_table = $1, schema_generation = schema_generation + 1
+WHERE name = $2
+RETURNING *;
+        "#,
+        )
+        .bind(new_max) // $1
+        .bind(name) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn update_topic(&mut self, name: &str, topic_id: TopicId) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET topic_id = $1, schema_generation = schema_generation + 1
+WHERE name = $2
+RETURNING *;
+        "#,
+        )
+        .bind(topic_id) // $1
+        .bind(name) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ if is_fk_violation(&e) => Error::ForeignKeyViolation { source: e },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn update_retention_period(
+        &mut self,
+        name: &str,
+        retention_period_ns: Option<i64>,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET retention_period_ns = $1, schema_generation = schema_generation + 1
+WHERE name = $2
+RETURNING *;
+        "#,
+        )
+        .bind(retention_period_ns) // $1
+        .bind(name) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn update_query_ranges(
+        &mut self,
+        name: &str,
+        default_query_range_ns: Option<i64>,
+        max_query_range_ns: Option<i64>,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET default_query_range_ns = $1, max_query_range_ns = $2,
+    schema_generation = schema_generation + 1
+WHERE name = $3
+RETURNING *;
+        "#,
+        )
+        .bind(default_query_range_ns) // $1
+        .bind(max_query_range_ns) // $2
+        .bind(name) // $3
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn update_row_ttl(
+        &mut self,
+        name: &str,
+        row_ttl_ns: Option<i64>,
+        row_ttl_column: Option<String>,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET row_ttl_ns = $1, row_ttl_column = $2,
+    schema_generation = schema_generation + 1
+WHERE name = $3
+RETURNING *;
+        "#,
+        )
+        .bind(row_ttl_ns) // $1
+        .bind(row_ttl_column) // $2
+        .bind(name) // $3
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn update_partition_template(
+        &mut self,
+        name: &str,
+        partition_template: Option<&PartitionTemplate>,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET partition_template = $1,
+    schema_generation = schema_generation + 1
+WHERE name = $2
+RETURNING *;
+        "#,
+        )
+        .bind(partition_template.map(ToString::to_string)) // $1
+        .bind(name) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn increment_schema_generation(
+        &mut self,
+        id: NamespaceId,
+        expected: i64,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET schema_generation = schema_generation + 1
+WHERE id = $1 AND schema_generation = $2
+RETURNING *;
+        "#,
+        )
+        .bind(id) // $1
+        .bind(expected) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        match rec {
+            Ok(namespace) => Ok(namespace),
+            // Either the namespace does not exist, or its generation moved on.
+            Err(sqlx::Error::RowNotFound) => match NamespaceRepo::get_by_id(self, id).await? {
+                Some(namespace) => Err(Error::PreconditionViolation {
+                    namespace_id: id,
+                    expected,
+                    actual: namespace.schema_generation,
+                }),
+                None => Err(Error::NamespaceNotFoundById { id }),
+            },
+            Err(e) => Err(Error::SqlxError { source: e }),
+        }
+    }
+
+    async fn add_usage(&mut self, usage: &NamespaceUsage) -> Result<()> {
+        sqlx::query(
+            r#"
+INSERT INTO namespace_usage
+    ( namespace_id, rows_written, bytes_written, queries_executed, bytes_scanned )
+VALUES ( $1, $2, $3, $4, $5 )
+ON CONFLICT ( namespace_id )
+DO UPDATE SET
+    rows_written = namespace_usage.rows_written + excluded.rows_written,
+    bytes_written = namespace_usage.bytes_written + excluded.bytes_written,
+    queries_executed = namespace_usage.queries_executed + excluded.queries_executed,
+    bytes_scanned = namespace_usage.bytes_scanned + excluded.bytes_scanned;
+        "#,
+        )
+        .bind(usage.namespace_id) // $1
+        .bind(usage.rows_written) // $2
+        .bind(usage.bytes_written) // $3
+        .bind(usage.queries_executed) // $4
+        .bind(usage.bytes_scanned) // $5
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        Ok(())
+    }
+
+    async fn get_usage(&mut self, id: NamespaceId) -> Result<Option<NamespaceUsage>> {
+        sqlx::query_as::<_, NamespaceUsage>(
+            r#"SELECT * FROM namespace_usage WHERE namespace_id = $1;"#,
+        )
+        .bind(id) // $1
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_usage(&mut self) -> Result<Vec<NamespaceUsage>> {
+        sqlx::query_as::<_, NamespaceUsage>(
+            r#"SELECT * FROM namespace_usage ORDER BY namespace_id;"#,
+        )
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn get_producer_sequence(
+        &mut self,
+        id: NamespaceId,
+        producer_id: &str,
+    ) -> Result<Option<i64>> {
+        sqlx::query_scalar::<_, i64>(
+            r#"
+SELECT sequence_number FROM producer_sequence
+WHERE namespace_id = $1 AND producer_id = $2;
+        "#,
+        )
+        .bind(id) // $1
+        .bind(producer_id) // $2
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn compare_and_swap_producer_sequence(
+        &mut self,
+        id: NamespaceId,
+        producer_id: &str,
+        current: Option<i64>,
+        new: Option<i64>,
+    ) -> Result<bool> {
+        let query = match (current, new) {
+            (None, None) => {
+                return Ok(self.get_producer_sequence(id, producer_id).await?.is_none());
+            }
+            (None, Some(new)) => sqlx::query(
+                r#"
+INSERT INTO producer_sequence ( namespace_id, producer_id, sequence_number )
+VALUES ( $1, $2, $3 )
+ON CONFLICT ( namespace_id, producer_id ) DO NOTHING;
+        "#,
+            )
+            .bind(id) // $1
+            .bind(producer_id) // $2
+            .bind(new), // $3
+            (Some(current), Some(new)) => sqlx::query(
+                r#"
+UPDATE producer_sequence
+SET sequence_number = $3
+WHERE namespace_id = $1 AND producer_id = $2 AND sequence_number = $4;
+        "#,
+            )
+            .bind(id) // $1
+            .bind(producer_id) // $2
+            .bind(new) // $3
+            .bind(current), // $4
+            (Some(current), None) => sqlx::query(
+                r#"
+DELETE FROM producer_sequence
+WHERE namespace_id = $1 AND producer_id = $2 AND sequence_number = $3;
+        "#,
+            )
+            .bind(id) // $1
+            .bind(producer_id) // $2
+            .bind(current), // $3
+        };
+
+        let res = query.execute(&mut self.inner).await.map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        Ok(res.rows_affected() == 1)
+    }
+}
+
+#[async_trait]
+impl TableRepo for SqliteTxn {
+    async fn create_or_get(&mut self, name: &str, namespace_id: NamespaceId) -> Result<Table> {
+        // See the Postgres implementation for how this enforces the table limit without a racy
+        // select-then-insert. SQLite requires the `WHERE` on the `SELECT` to parse the upsert.
+        let rec = sqlx::query_as::<_, Table>(
+            r#"
+INSERT INTO table_name ( name, namespace_id )
+SELECT $1, id FROM (
+    SELECT namespace.id AS id, max_tables, COUNT(table_name.id) AS count
+    FROM namespace LEFT JOIN table_name ON namespace.id = table_name.namespace_id
+    WHERE namespace.id = $2
+    GROUP BY namespace.max_tables, table_name.namespace_id, namespace.id
+) AS get_count WHERE count < max_tables
+ON CONFLICT ( namespace_id, name )
+DO UPDATE SET name = table_name.name
+RETURNING *;
+        "#,
+        )
+        .bind(name) // $1
+        .bind(namespace_id) // $2
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::TableCreateLimitError {
+                table_name: name.to_string(),
+                namespace_id,
+            },
+            _ => {
+                if is_fk_violation(&e) {
+                    Error::ForeignKeyViolation { source: e }
+                } else {
+                    Error::SqlxError { source: e }
+                }
+            }
+        })?;
+
+        Ok(rec)
+    }
+
+    async fn get_by_id(&mut self, table_id: TableId) -> Result<Option<Table>> {
+        sqlx::query_as::<_, Table>("SELECT * FROM table_name WHERE id = $1;")
+            .bind(table_id) // $1
+            .fetch_optional(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn get_by_namespace_and_name(
+        &mut self,
+        namespace_id: NamespaceId,
+        name: &str,
+    ) -> Result<Option<Table>> {
+        sqlx::query_as::<_, Table>(
+            r#"
+SELECT *
+FROM table_name
+WHERE namespace_id = $1 AND name = $2;
+            "#,
+        )
+        .bind(namespace_id) // $1
+        .bind(name) // $2
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_by_namespace_id(&mut self, namespace_id: NamespaceId) -> Result<Vec<Table>> {
+        sqlx::query_as::<_, Table>("SELECT * FROM table_name WHERE namespace_id = $1;")
+            .bind(namespace_id) // $1
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list(&mut self) -> Result<Vec<Table>> {
+        sqlx::query_as::<_, Table>("SELECT * FROM table_name;")
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn get_shard_pin(&mut self, table_id: TableId) -> Result<Option<ShardId>> {
+        sqlx::query_scalar::<_, ShardId>(
+            r#"
+SELECT shard_id
+FROM table_shard_pin
+WHERE table_id = $1;
+            "#,
+        )
+        .bind(table_id) // $1
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn create_or_get_shard_pin(
+        &mut self,
+        table_id: TableId,
+        shard_id: ShardId,
+    ) -> Result<ShardId> {
+        // The no-op update on conflict returns the existing pin, which is never changed.
+        sqlx::query_scalar::<_, ShardId>(
+            r#"
+INSERT INTO table_shard_pin ( table_id, shard_id )
+VALUES ( $1, $2 )
+ON CONFLICT ( table_id )
+DO UPDATE SET table_id = table_shard_pin.table_id
+RETURNING shard_id;
+            "#,
+        )
+        .bind(table_id) // $1
+        .bind(shard_id) // $2
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl ColumnRepo for SqliteTxn {
+    async fn create_or_get(
+        &mut self,
+        name: &str,
+        table_id: TableId,
+        column_type: ColumnType,
+    ) -> Result<Column> {
+        let rec = sqlx::query_as::<_, Column>(
+            r#"
+INSERT INTO column_name ( name, table_id, column_type, created_at )
+SELECT $1, table_id, $3, $4 FROM (
+    SELECT max_columns_per_table, namespace.id, table_name.id as table_id, COUNT(column_name.id) AS count
+    FROM namespace LEFT JOIN table_name ON namespace.id = table_name.namespace_id
+                   LEFT JOIN column_name ON table_name.id = column_name.table_id
+    WHERE table_name.id = $2
+    GROUP BY namespace.max_columns_per_table, namespace.id, table_name.id
+) AS get_count WHERE count < max_columns_per_table
+ON CONFLICT ( table_id, name )
+DO UPDATE SET name = column_name.name
+RETURNING *;
+        "#,
+        )
+        .bind(name) // $1
+        .bind(table_id) // $2
+        .bind(column_type) // $3
+        .bind(Timestamp::from(self.time_provider.now())) // $4
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::ColumnCreateLimitError {
+                column_name: name.to_string(),
+                table_id,
+            },
+            _ => {
+                if is_fk_violation(&e) {
+                    Error::ForeignKeyViolation { source: e }
+                } else {
+                    Error::SqlxError { source: e }
+                }
+            }
+        })?;
+
+        ensure!(
+            rec.column_type == column_type,
+            ColumnTypeMismatchSnafu {
+                name,
+                existing: rec.column_type,
+                new: column_type,
+            }
+        );
+
+        Ok(rec)
+    }
+
+    async fn list_by_namespace_id(&mut self, namespace_id: NamespaceId) -> Result<Vec<Column>> {
+        sqlx::query_as::<_, Column>(
+            r#"
+SELECT column_name.* FROM table_name
+INNER JOIN column_name on column_name.table_id = table_name.id
+WHERE table_name.namespace_id = $1;
+            "#,
+        )
+        .bind(namespace_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_by_table_id(&mut self, table_id: TableId) -> Result<Vec<Column>> {
+        sqlx::query_as::<_, Column>("SELECT * FROM column_name WHERE table_id = $1;")
+            .bind(table_id) // $1
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list(&mut self) -> Result<Vec<Column>> {
+        sqlx::query_as::<_, Column>("SELECT * FROM column_name;")
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn create_or_get_many_unchecked(
+        &mut self,
+        table_id: TableId,
+        columns: HashMap<&str, ColumnType>,
+    ) -> Result<Vec<Column>> {
+        // SQLite has no array parameters to UNNEST, so upsert the columns one at a time. Writes
+        // are serialised by SQLite, so the Postgres deadlock concerns around ordering don't
+        // apply.
+        let created_at = Timestamp::from(self.time_provider.now());
+        let mut out = Vec::with_capacity(columns.len());
+        for (&name, &column_type) in &columns {
+            let existing = sqlx::query_as::<_, Column>(
+                r#"
+INSERT INTO column_name ( name, table_id, column_type, created_at )
+VALUES ( $1, $2, $3, $4 )
+ON CONFLICT ( table_id, name )
+DO UPDATE SET name = column_name.name
+RETURNING *;
+                "#,
+            )
+            .bind(name) // $1
+            .bind(table_id) // $2
+            .bind(column_type) // $3
+            .bind(created_at) // $4
+            .fetch_one(&mut self.inner)
+            .await
+            .map_err(|e| {
+                if is_fk_violation(&e) {
+                    Error::ForeignKeyViolation { source: e }
+                } else {
+                    Error::SqlxError { source: e }
+                }
+            })?;
+
+            ensure!(
+                existing.column_type == column_type,
+                ColumnTypeMismatchSnafu {
+                    name: &existing.name,
+                    existing: existing.column_type,
+                    new: column_type,
+                }
+            );
+
+            out.push(existing);
+        }
+
+        Ok(out)
+    }
+
+    async fn list_type_count_by_table_id(
+        &mut self,
+        table_id: TableId,
+    ) -> Result<Vec<ColumnTypeCount>> {
+        sqlx::query_as::<_, ColumnTypeCount>(
+            r#"
+select column_type as col_type, count(1) as count from column_name where table_id = $1 group by 1;
+            "#,
+        )
+        .bind(table_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+}
+
+#[async_trait]
+impl ShardRepo for SqliteTxn {
+    async fn create_or_get(
+        &mut self,
+        topic: &TopicMetadata,
+        shard_index: ShardIndex,
+    ) -> Result<Shard> {
+        sqlx::query_as::<_, Shard>(
+            r#"
+INSERT INTO shard
+    ( topic_id, shard_index, min_unpersisted_sequence_number )
+VALUES
+    ( $1, $2, 0 )
+ON CONFLICT ( topic_id, shard_index )
+DO UPDATE SET topic_id = shard.topic_id
+RETURNING *;
+        "#,
+        )
+        .bind(topic.id) // $1
+        .bind(shard_index) // $2
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
+
+    async fn get_by_topic_id_and_shard_index(
+        &mut self,
+        topic_id: TopicId,
+        shard_index: ShardIndex,
+    ) -> Result<Option<Shard>> {
+        sqlx::query_as::<_, Shard>(
+            r#"
+SELECT *
+FROM shard
+WHERE topic_id = $1
+  AND shard_index = $2;
+        "#,
+        )
+        .bind(topic_id) // $1
+        .bind(shard_index) // $2
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list(&mut self) -> Result<Vec<Shard>> {
+        sqlx::query_as::<_, Shard>(r#"SELECT * FROM shard;"#)
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_by_topic(&mut self, topic: &TopicMetadata) -> Result<Vec<Shard>> {
+        sqlx::query_as::<_, Shard>(r#"SELECT * FROM shard WHERE topic_id = $1;"#)
+            .bind(topic.id) // $1
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn update_min_unpersisted_sequence_number(
+        &mut self,
+        shard_id: ShardId,
+        sequence_number: SequenceNumber,
+    ) -> Result<()> {
+        let _ = sqlx::query(
+            r#"
+UPDATE shard
+SET min_unpersisted_sequence_number = $1
+WHERE id = $2;
+                "#,
+        )
+        .bind(sequence_number.get()) // $1
+        .bind(shard_id) // $2
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+
+    async fn acquire_lease(
+        &mut self,
+        shard_id: ShardId,
+        holder: &str,
+        duration: Duration,
+    ) -> Result<ShardLease> {
+        // The lease is only taken over if it is held by `holder` or has expired, by the clock of
+        // the database. The epoch is kept when an unexpired lease is renewed.
+        sqlx::query(
+            r#"
+INSERT INTO shard_lease ( shard_id, holder, expires_at, epoch )
+VALUES ( $1, $2, CAST((julianday('now') - 2440587.5) * 86400000000000 AS INTEGER) + $3, 1 )
+ON CONFLICT ( shard_id )
+DO UPDATE SET
+    holder = excluded.holder,
+    expires_at = excluded.expires_at,
+    epoch = CASE
+        WHEN shard_lease.holder = excluded.holder
+            AND shard_lease.expires_at > CAST((julianday('now') - 2440587.5) * 86400000000000 AS INTEGER)
+        THEN shard_lease.epoch
+        ELSE shard_lease.epoch + 1
+    END
+WHERE shard_lease.holder = excluded.holder
+    OR shard_lease.expires_at <= CAST((julianday('now') - 2440587.5) * 86400000000000 AS INTEGER);
+        "#,
+        )
+        .bind(shard_id) // $1
+        .bind(holder) // $2
+        .bind(duration.as_nanos() as i64) // $3
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        sqlx::query_as::<_, ShardLease>(r#"SELECT * FROM shard_lease WHERE shard_id = $1;"#)
+            .bind(shard_id) // $1
+            .fetch_one(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn release_lease(&mut self, shard_id: ShardId, holder: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+UPDATE shard_lease
+SET expires_at = 0
+WHERE shard_id = $1 AND holder = $2;
+        "#,
+        )
+        .bind(shard_id) // $1
+        .bind(holder) // $2
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+
+    async fn check_lease(&mut self, shard_id: ShardId, holder: &str, epoch: i64) -> Result<()> {
+        // SQLite serialises write transactions, so the lease can not be taken over before the
+        // transaction of the caller completes.
+        let lease = sqlx::query_as::<_, ShardLease>(
+            r#"
+SELECT * FROM shard_lease
+WHERE shard_id = $1 AND holder = $2 AND epoch = $3 AND expires_at > CAST((julianday('now') - 2440587.5) * 86400000000000 AS INTEGER);
+        "#,
+        )
+        .bind(shard_id) // $1
+        .bind(holder) // $2
+        .bind(epoch) // $3
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        match lease {
+            Some(_) => Ok(()),
+            None => Err(Error::LeaseLost {
+                shard_id,
+                holder: holder.to_string(),
+                epoch,
+            }),
+        }
+    }
+
+    async fn list_held_leases(&mut self, topic_id: TopicId) -> Result<Vec<ShardLease>> {
+        sqlx::query_as::<_, ShardLease>(
+            r#"
+SELECT shard_lease.* FROM shard_lease
+INNER JOIN shard ON shard.id = shard_lease.shard_id
+WHERE shard.topic_id = $1 AND shard_lease.expires_at > CAST((julianday('now') - 2440587.5) * 86400000000000 AS INTEGER)
+ORDER BY shard_lease.shard_id;
+        "#,
+        )
+        .bind(topic_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn record_ingester_heartbeat(
+        &mut self,
+        topic_id: TopicId,
+        address: &str,
+        at: Timestamp,
+    ) -> Result<IngesterRegistration> {
+        let rec = sqlx::query_as::<_, IngesterRegistrationPod>(
+            r#"
+INSERT INTO ingester_registration ( address, topic_id, shard_indexes, last_heartbeat )
+VALUES ( $1, $2, '[]', $3 )
+ON CONFLICT ( address )
+DO UPDATE SET
+    shard_indexes = CASE
+        WHEN ingester_registration.topic_id = excluded.topic_id
+        THEN ingester_registration.shard_indexes
+        ELSE '[]'
+    END,
+    topic_id = excluded.topic_id,
+    last_heartbeat = excluded.last_heartbeat
+RETURNING *;
+        "#,
+        )
+        .bind(address) // $1
+        .bind(topic_id) // $2
+        .bind(at) // $3
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        Ok(rec.into())
+    }
+
+    async fn list_ingesters(&mut self, topic_id: TopicId) -> Result<Vec<IngesterRegistration>> {
+        Ok(sqlx::query_as::<_, IngesterRegistrationPod>(
+            r#"
+SELECT *
+FROM ingester_registration
+WHERE topic_id = $1
+ORDER BY address;
+        "#,
+        )
+        .bind(topic_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?
+        .into_iter()
+        .map(Into::into)
+        .collect())
+    }
+
+    async fn assign_shards(&mut self, address: &str, shard_indexes: &[ShardIndex]) -> Result<()> {
+        let mut shard_indexes = shard_indexes.to_vec();
+        shard_indexes.sort();
+
+        sqlx::query(
+            r#"
+UPDATE ingester_registration
+SET shard_indexes = $1
+WHERE address = $2;
+        "#,
+        )
+        .bind(Json(
+            shard_indexes.iter().map(|v| v.get()).collect::<Vec<_>>(),
+        )) // $1
+        .bind(address) // $2
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+
+    async fn remove_ingester(&mut self, address: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+DELETE FROM ingester_registration
+WHERE address = $1;
+        "#,
+        )
+        .bind(address) // $1
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+}
+
+/// [`IngesterRegistration`] as stored in SQLite, with the shard indexes as a JSON array.
+#[derive(Debug, sqlx::FromRow)]
+struct IngesterRegistrationPod {
+    address: String,
+    topic_id: TopicId,
+    shard_indexes: Json<Vec<i32>>,
+    last_heartbeat: Timestamp,
+}
+
+impl From<IngesterRegistrationPod> for IngesterRegistration {
+    fn from(value: IngesterRegistrationPod) -> Self {
+        Self {
+            address: value.address,
+            topic_id: value.topic_id,
+            shard_indexes: value
+                .shard_indexes
+                .0
+                .into_iter()
+                .map(ShardIndex::new)
+                .collect(),
+            last_heartbeat: value.last_heartbeat,
+        }
+    }
+}
+
+/// [`Partition`] as stored in SQLite, with the sort key as a JSON array.
+#[derive(Debug, sqlx::FromRow)]
+struct PartitionPod {
+    id: PartitionId,
+    shard_id: ShardId,
+    table_id: TableId,
+    partition_key: String,
+    sort_key: Json<Vec<String>>,
+    persisted_sequence_number: Option<SequenceNumber>,
+    cold_compacted_at: Option<Timestamp>,
+}
+
+impl From<PartitionPod> for Partition {
+    fn from(value: PartitionPod) -> Self {
+        Self {
+            id: value.id,
+            shard_id: value.shard_id,
+            table_id: value.table_id,
+            partition_key: PartitionKey::from(value.partition_key),
+            sort_key: value.sort_key.0,
+            persisted_sequence_number: value.persisted_sequence_number,
+            cold_compacted_at: value.cold_compacted_at,
+        }
+    }
+}
+
+#[async_trait]
+impl PartitionRepo for SqliteTxn {
+    async fn create_or_get(
+        &mut self,
+        key: PartitionKey,
+        shard_id: ShardId,
+        table_id: TableId,
+    ) -> Result<Partition> {
+        let v = sqlx::query_as::<_, PartitionPod>(
+            r#"
+INSERT INTO partition
+    ( partition_key, shard_id, table_id, sort_key )
+VALUES
+    ( $1, $2, $3, '[]' )
+ON CONFLICT ( table_id, partition_key )
+DO UPDATE SET partition_key = partition.partition_key
+RETURNING *;
+        "#,
+        )
+        .bind(key.to_string()) // $1
+        .bind(shard_id) // $2
+        .bind(table_id) // $3
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        // If the partition_key_unique constraint was hit because there was an
+        // existing record for (table_id, partition_key) ensure the partition
+        // key in the DB is mapped to the same shard_id the caller
+        // requested.
+        assert_eq!(
+            v.shard_id, shard_id,
+            "attempted to overwrite partition with different shard ID"
+        );
+
+        Ok(v.into())
+    }
+
+    async fn get_by_id(&mut self, partition_id: PartitionId) -> Result<Option<Partition>> {
+        let rec = sqlx::query_as::<_, PartitionPod>(r#"SELECT * FROM partition WHERE id = $1;"#)
+            .bind(partition_id) // $1
+            .fetch_optional(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(rec.map(Into::into))
+    }
+
+    async fn list_by_shard(&mut self, shard_id: ShardId) -> Result<Vec<Partition>> {
+        Ok(
+            sqlx::query_as::<_, PartitionPod>(r#"SELECT * FROM partition WHERE shard_id = $1;"#)
+                .bind(shard_id) // $1
+                .fetch_all(&mut self.inner)
+                .await
+                .map_err(|e| Error::SqlxError { source: e })?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        )
+    }
+
+    async fn list_by_namespace(&mut self, namespace_id: NamespaceId) -> Result<Vec<Partition>> {
+        Ok(sqlx::query_as::<_, PartitionPod>(
+            r#"
+SELECT partition.*
+FROM table_name
+INNER JOIN partition on partition.table_id = table_name.id
+WHERE table_name.namespace_id = $1;
+            "#,
+        )
+        .bind(namespace_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?
+        .into_iter()
+        .map(Into::into)
+        .collect())
+    }
+
+    async fn list_by_table_id(&mut self, table_id: TableId) -> Result<Vec<Partition>> {
+        Ok(
+            sqlx::query_as::<_, PartitionPod>(r#"SELECT * FROM partition WHERE table_id = $1;"#)
+                .bind(table_id) // $1
+                .fetch_all(&mut self.inner)
+                .await
+                .map_err(|e| Error::SqlxError { source: e })?
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        )
+    }
+
+    async fn update_sort_key(
+        &mut self,
+        partition_id: PartitionId,
+        sort_key: &[&str],
+    ) -> Result<Partition> {
+        let rec = sqlx::query_as::<_, PartitionPod>(
+            r#"
+UPDATE partition
+SET sort_key = $1
+WHERE id = $2
+RETURNING *;
+        "#,
+        )
+        .bind(Json(sort_key)) // $1
+        .bind(partition_id) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let partition: Partition = rec
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => Error::PartitionNotFound { id: partition_id },
+                _ => Error::SqlxError { source: e },
+            })?
+            .into();
+
+        debug!(
+            ?partition_id,
+            input_sort_key=?sort_key,
+            partition_after_catalog_update=?partition,
+            "Partition after updating sort key"
+        );
+
+        Ok(partition)
+    }
+
+    async fn record_skipped_compaction(
+        &mut self,
+        partition_id: PartitionId,
+        reason: &str,
+        num_files: usize,
+        limit_num_files: usize,
+        limit_num_files_first_in_partition: usize,
+        estimated_bytes: u64,
+        limit_bytes: u64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+INSERT INTO skipped_compactions
+    ( partition_id, reason, num_files, limit_num_files, limit_num_files_first_in_partition, estimated_bytes, limit_bytes, skipped_at )
+VALUES
+    ( $1, $2, $3, $4, $5, $6, $7, CAST(strftime('%s', 'now') AS INTEGER) )
+ON CONFLICT ( partition_id )
+DO UPDATE
+SET
+reason = excluded.reason,
+num_files = excluded.num_files,
+limit_num_files = excluded.limit_num_files,
+limit_num_files_first_in_partition = excluded.limit_num_files_first_in_partition,
+estimated_bytes = excluded.estimated_bytes,
+limit_bytes = excluded.limit_bytes,
+skipped_at = excluded.skipped_at;
+        "#,
+        )
+        .bind(partition_id) // $1
+        .bind(reason)
+        .bind(num_files as i64)
+        .bind(limit_num_files as i64)
+        .bind(limit_num_files_first_in_partition as i64)
+        .bind(estimated_bytes as i64)
+        .bind(limit_bytes as i64)
+        .execute(&mut self.inner)
+        .await
+        .context(interface::CouldNotRecordSkippedCompactionSnafu { partition_id })?;
+        Ok(())
+    }
+
+    async fn list_skipped_compactions(&mut self) -> Result<Vec<SkippedCompaction>> {
+        sqlx::query_as::<_, SkippedCompaction>("SELECT * FROM skipped_compactions;")
+            .fetch_all(&mut self.inner)
+            .await
+            .context(interface::CouldNotListSkippedCompactionsSnafu)
+    }
+
+    async fn delete_skipped_compactions(
+        &mut self,
+        partition_id: PartitionId,
+    ) -> Result<Option<SkippedCompaction>> {
+        sqlx::query_as::<_, SkippedCompaction>(
+            r#"
+DELETE FROM skipped_compactions
+WHERE partition_id = $1
+RETURNING *;
+        "#,
+        )
+        .bind(partition_id) // $1
+        .fetch_optional(&mut self.inner)
+        .await
+        .context(interface::CouldNotDeleteSkippedCompactionsSnafu)
+    }
+
+    async fn update_persisted_sequence_number(
+        &mut self,
+        partition_id: PartitionId,
+        sequence_number: SequenceNumber,
+    ) -> Result<()> {
+        let _ = sqlx::query(
+            r#"
+UPDATE partition
+SET persisted_sequence_number = $1, cold_compacted_at = NULL
+WHERE id = $2;
+                "#,
+        )
+        .bind(sequence_number.get()) // $1
+        .bind(partition_id) // $2
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+
+    async fn mark_cold(
+        &mut self,
+        partition_id: PartitionId,
+        cold_compacted_at: Timestamp,
+    ) -> Result<bool> {
+        let res = sqlx::query(
+            r#"
+UPDATE partition
+SET cold_compacted_at = $1
+WHERE id = $2
+  AND NOT EXISTS (
+    SELECT 1 FROM parquet_file
+    WHERE parquet_file.partition_id = $2
+      AND parquet_file.to_delete IS NULL
+      AND parquet_file.compaction_level != $3
+  );
+                "#,
+        )
+        .bind(cold_compacted_at) // $1
+        .bind(partition_id) // $2
+        .bind(CompactionLevel::Final) // $3
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(res.rows_affected() > 0)
+    }
+
+    async fn most_recent_n(&mut self, n: usize, shards: &[ShardId]) -> Result<Vec<Partition>> {
+        Ok(sqlx::query_as::<_, PartitionPod>(
+            r#"
+SELECT *
+FROM partition
+WHERE shard_id IN (SELECT value FROM json_each($1))
+ORDER BY id DESC
+LIMIT $2;
+            "#,
+        )
+        .bind(Json(shards.iter().map(|v| v.get()).collect::<Vec<_>>())) // $1
+        .bind(n as i64) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?
+        .into_iter()
+        .map(Into::into)
+        .collect())
+    }
+}
+
+#[async_trait]
+impl TombstoneRepo for SqliteTxn {
+    async fn create_or_get(
+        &mut self,
+        table_id: TableId,
+        shard_id: ShardId,
+        sequence_number: SequenceNumber,
+        min_time: Timestamp,
+        max_time: Timestamp,
+        predicate: &str,
+    ) -> Result<Tombstone> {
+        let v = sqlx::query_as::<_, Tombstone>(
+            r#"
+INSERT INTO tombstone
+    ( table_id, shard_id, sequence_number, min_time, max_time, serialized_predicate )
+VALUES
+    ( $1, $2, $3, $4, $5, $6 )
+ON CONFLICT ( table_id, shard_id, sequence_number )
+DO UPDATE SET table_id = tombstone.table_id
+RETURNING *;
+        "#,
+        )
+        .bind(table_id) // $1
+        .bind(shard_id) // $2
+        .bind(sequence_number) // $3
+        .bind(min_time) // $4
+        .bind(max_time) // $5
+        .bind(predicate) // $6
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        // If tombstone_unique is hit, a record with (table_id, shard_id,
+        // sequence_number) already exists.
+        //
+        // Ensure the caller does not falsely believe they have created the
+        // record with the provided values if the DB row contains different
+        // values.
+        assert_eq!(
+            v.min_time, min_time,
+            "attempted to overwrite min_time in tombstone record"
+        );
+        assert_eq!(
+            v.max_time, max_time,
+            "attempted to overwrite max_time in tombstone record"
+        );
+        assert_eq!(
+            v.serialized_predicate, predicate,
+            "attempted to overwrite predicate in tombstone record"
+        );
+
+        Ok(v)
+    }
+
+    async fn list_by_namespace(&mut self, namespace_id: NamespaceId) -> Result<Vec<Tombstone>> {
+        sqlx::query_as::<_, Tombstone>(
+            r#"
+SELECT tombstone.*
+FROM table_name
+INNER JOIN tombstone on tombstone.table_id = table_name.id
+WHERE table_name.namespace_id = $1;
+            "#,
+        )
+        .bind(namespace_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_by_table(&mut self, table_id: TableId) -> Result<Vec<Tombstone>> {
+        sqlx::query_as::<_, Tombstone>(
+            r#"
+SELECT *
+FROM tombstone
+WHERE table_id = $1
+ORDER BY id;
+            "#,
+        )
+        .bind(table_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn get_by_id(&mut self, id: TombstoneId) -> Result<Option<Tombstone>> {
+        sqlx::query_as::<_, Tombstone>("SELECT * FROM tombstone WHERE id = $1;")
+            .bind(id) // $1
+            .fetch_optional(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_tombstones_by_shard_greater_than(
+        &mut self,
+        shard_id: ShardId,
+        sequence_number: SequenceNumber,
+    ) -> Result<Vec<Tombstone>> {
+        sqlx::query_as::<_, Tombstone>(
+            r#"
+SELECT *
+FROM tombstone
+WHERE shard_id = $1
+  AND sequence_number > $2
+ORDER BY id;
+            "#,
+        )
+        .bind(shard_id) // $1
+        .bind(sequence_number) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn remove(&mut self, tombstone_ids: &[TombstoneId]) -> Result<()> {
+        let ids: Vec<_> = tombstone_ids.iter().map(|t| t.get()).collect();
+
+        // Remove processed tombstones first
+        sqlx::query(
+            r#"
+DELETE
+FROM processed_tombstone
+WHERE tombstone_id IN (SELECT value FROM json_each($1));
+            "#,
+        )
+        .bind(Json(&ids)) // $1
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        // Remove tombstones
+        sqlx::query(
+            r#"
+DELETE
+FROM tombstone
+WHERE id IN (SELECT value FROM json_each($1));
+            "#,
+        )
+        .bind(Json(&ids)) // $1
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+
+    async fn list_tombstones_for_time_range(
+        &mut self,
+        shard_id: ShardId,
+        table_id: TableId,
+        sequence_number: SequenceNumber,
+        min_time: Timestamp,
+        max_time: Timestamp,
+    ) -> Result<Vec<Tombstone>> {
+        sqlx::query_as::<_, Tombstone>(
+            r#"
+SELECT *
+FROM tombstone
+WHERE shard_id = $1
+  AND table_id = $2
+  AND sequence_number > $3
+  AND ((min_time <= $4 AND max_time >= $4)
+        OR (min_time > $4 AND min_time <= $5))
+ORDER BY id;
+            "#,
+        )
+        .bind(shard_id) // $1
+        .bind(table_id) // $2
+        .bind(sequence_number) // $3
+        .bind(min_time) // $4
+        .bind(max_time) // $5
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+}
+
+/// [`ParquetFile`] as stored in SQLite, with the column set as a JSON array.
+#[derive(Debug, sqlx::FromRow)]
+struct ParquetFilePod {
+    id: ParquetFileId,
+    shard_id: ShardId,
+    namespace_id: NamespaceId,
+    table_id: TableId,
+    partition_id: PartitionId,
+    object_store_id: Uuid,
+    max_sequence_number: SequenceNumber,
+    min_time: Timestamp,
+    max_time: Timestamp,
+    to_delete: Option<Timestamp>,
+    file_size_bytes: i64,
+    row_count: i64,
+    compaction_level: CompactionLevel,
+    created_at: Timestamp,
+    column_set: Json<Vec<i64>>,
+}
+
+impl From<ParquetFilePod> for ParquetFile {
+    fn from(value: ParquetFilePod) -> Self {
+        Self {
+            id: value.id,
+            shard_id: value.shard_id,
+            namespace_id: value.namespace_id,
+            table_id: value.table_id,
+            partition_id: value.partition_id,
+            object_store_id: value.object_store_id,
+            max_sequence_number: value.max_sequence_number,
+            min_time: value.min_time,
+            max_time: value.max_time,
+            to_delete: value.to_delete,
+            file_size_bytes: value.file_size_bytes,
+            row_count: value.row_count,
+            compaction_level: value.compaction_level,
+            created_at: value.created_at,
+            column_set: ColumnSet::new(value.column_set.0.into_iter().map(ColumnId::new)),
+        }
+    }
+}
+
+fn parquet_files(pods: Vec<ParquetFilePod>) -> Vec<ParquetFile> {
+    pods.into_iter().map(Into::into).collect()
+}
+
+#[async_trait]
+impl ParquetFileRepo for SqliteTxn {
+    async fn create(&mut self, parquet_file_params: ParquetFileParams) -> Result<ParquetFile> {
+        let ParquetFileParams {
+            shard_id,
+            namespace_id,
+            table_id,
+            partition_id,
+            object_store_id,
+            max_sequence_number,
+            min_time,
+            max_time,
+            file_size_bytes,
+            row_count,
+            compaction_level,
+            created_at,
+            column_set,
+        } = parquet_file_params;
+
+        let rec = sqlx::query_as::<_, ParquetFilePod>(
+            r#"
+INSERT INTO parquet_file (
+    shard_id, table_id, partition_id, object_store_id,
+    max_sequence_number, min_time, max_time, file_size_bytes,
+    row_count, compaction_level, created_at, namespace_id, column_set )
+VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13 )
+RETURNING *;
+        "#,
+        )
+        .bind(shard_id) // $1
+        .bind(table_id) // $2
+        .bind(partition_id) // $3
+        .bind(object_store_id) // $4
+        .bind(max_sequence_number) // $5
+        .bind(min_time) // $6
+        .bind(max_time) // $7
+        .bind(file_size_bytes) // $8
+        .bind(row_count) // $9
+        .bind(compaction_level) // $10
+        .bind(created_at) // $11
+        .bind(namespace_id) // $12
+        .bind(Json(column_set.iter().map(|c| c.get()).collect::<Vec<_>>())) // $13
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                Error::FileExists { object_store_id }
+            } else if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })?;
+
+        Ok(rec.into())
+    }
+
+    async fn flag_for_delete(&mut self, id: ParquetFileId) -> Result<()> {
+        let marked_at = Timestamp::from(self.time_provider.now());
+
+        let _ = sqlx::query(r#"UPDATE parquet_file SET to_delete = $1 WHERE id = $2;"#)
+            .bind(marked_at) // $1
+            .bind(id) // $2
+            .execute(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+
+    async fn flag_for_delete_by_retention(&mut self) -> Result<Vec<ParquetFileId>> {
+        let flagged_at = Timestamp::from(self.time_provider.now());
+        // TODO - include check of table retention period once implemented
+        //
+        // A NULL retention period yields a NULL comparison, so files in namespaces with infinite
+        // retention are never flagged.
+        let flagged = sqlx::query(
+            r#"
+UPDATE parquet_file
+SET to_delete = $1
+WHERE to_delete IS NULL
+  AND max_time < $1 - (
+      SELECT retention_period_ns FROM namespace WHERE namespace.id = parquet_file.namespace_id
+  )
+RETURNING id;
+            "#,
+        )
+        .bind(flagged_at) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        let flagged = flagged.into_iter().map(|row| row.get("id")).collect();
+        Ok(flagged)
+    }
+
+    async fn list_by_shard_greater_than(
+        &mut self,
+        shard_id: ShardId,
+        sequence_number: SequenceNumber,
+    ) -> Result<Vec<ParquetFile>> {
+        sqlx::query_as::<_, ParquetFilePod>(
+            r#"
+SELECT *
+FROM parquet_file
+WHERE shard_id = $1
+  AND max_sequence_number > $2
+ORDER BY id;
+            "#,
+        )
+        .bind(shard_id) // $1
+        .bind(sequence_number) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map(parquet_files)
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_by_namespace_not_to_delete(
+        &mut self,
+        namespace_id: NamespaceId,
+    ) -> Result<Vec<ParquetFile>> {
+        sqlx::query_as::<_, ParquetFilePod>(
+            r#"
+SELECT parquet_file.*
+FROM parquet_file
+INNER JOIN table_name on table_name.id = parquet_file.table_id
+WHERE table_name.namespace_id = $1
+  AND parquet_file.to_delete IS NULL;
+             "#,
+        )
+        .bind(namespace_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map(parquet_files)
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_by_table_not_to_delete(&mut self, table_id: TableId) -> Result<Vec<ParquetFile>> {
+        sqlx::query_as::<_, ParquetFilePod>(
+            r#"
+SELECT *
+FROM parquet_file
+WHERE table_id = $1 AND to_delete IS NULL;
+             "#,
+        )
+        .bind(table_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map(parquet_files)
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_by_table_and_time_range(
+        &mut self,
+        table_id: TableId,
+        min_time: Timestamp,
+        max_time: Timestamp,
+    ) -> Result<Vec<ParquetFile>> {
+        sqlx::query_as::<_, ParquetFilePod>(
+            r#"
+SELECT *
+FROM parquet_file
+WHERE table_id = $1
+  AND to_delete IS NULL
+  AND max_time >= $2
+  AND min_time <= $3;
+             "#,
+        )
+        .bind(table_id) // $1
+        .bind(min_time) // $2
+        .bind(max_time) // $3
+        .fetch_all(&mut self.inner)
+        .await
+        .map(parquet_files)
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn delete_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>> {
+        sqlx::query_as::<_, ParquetFilePod>(
+            r#"
+DELETE FROM parquet_file
+WHERE to_delete < $1
+RETURNING *;
+             "#,
+        )
+        .bind(older_than) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map(parquet_files)
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn delete_old_ids_only(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFileId>> {
+        let deleted = sqlx::query(
+            r#"
+DELETE FROM parquet_file
+WHERE id IN (
+    SELECT id
+    FROM parquet_file
+    WHERE to_delete < $1
+    LIMIT $2
+)
+RETURNING id;
+             "#,
+        )
+        .bind(older_than) // $1
+        .bind(MAX_PARQUET_FILES_DELETED_ONCE) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        let deleted = deleted.into_iter().map(|row| row.get("id")).collect();
+        Ok(deleted)
+    }
+
+    async fn level_0(&mut self, shard_id: ShardId) -> Result<Vec<ParquetFile>> {
+        // this intentionally limits the returned files to 10,000 as it is used to make
+        // a decision on the highest priority partitions. If compaction has never been
+        // run this could end up returning millions of results and taking too long to run.
+        sqlx::query_as::<_, ParquetFilePod>(
+            r#"
+SELECT *
+FROM parquet_file
+WHERE parquet_file.shard_id = $1
+  AND parquet_file.compaction_level = $2
+  AND parquet_file.to_delete IS NULL
+  LIMIT 1000;
+        "#,
+        )
+        .bind(shard_id) // $1
+        .bind(CompactionLevel::Initial) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map(parquet_files)
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn level_1(
+        &mut self,
+        table_partition: TablePartition,
+        min_time: Timestamp,
+        max_time: Timestamp,
+    ) -> Result<Vec<ParquetFile>> {
+        sqlx::query_as::<_, ParquetFilePod>(
+            r#"
+SELECT *
+FROM parquet_file
+WHERE parquet_file.shard_id = $1
+  AND parquet_file.table_id = $2
+  AND parquet_file.partition_id = $3
+  AND parquet_file.compaction_level = $4
+  AND parquet_file.to_delete IS NULL
+  AND ((parquet_file.min_time <= $5 AND parquet_file.max_time >= $5)
+      OR (parquet_file.min_time > $5 AND parquet_file.min_time <= $6));
+        "#,
+        )
+        .bind(table_partition.shard_id) // $1
+        .bind(table_partition.table_id) // $2
+        .bind(table_partition.partition_id) // $3
+        .bind(CompactionLevel::FileNonOverlapped) // $4
+        .bind(min_time) // $5
+        .bind(max_time) // $6
+        .fetch_all(&mut self.inner)
+        .await
+        .map(parquet_files)
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn recent_highest_throughput_partitions(
+        &mut self,
+        shard_id: ShardId,
+        time_in_the_past: Timestamp,
+        min_num_files: usize,
+        num_partitions: usize,
+    ) -> Result<Vec<PartitionParam>> {
+        let min_num_files = min_num_files as i32;
+        let num_partitions = num_partitions as i32;
+
+        sqlx::query_as::<_, PartitionParam>(
+            r#"
+SELECT parquet_file.partition_id, parquet_file.table_id, parquet_file.shard_id,
+       parquet_file.namespace_id, count(parquet_file.id)
+FROM parquet_file
+LEFT OUTER JOIN skipped_compactions ON parquet_file.partition_id = skipped_compactions.partition_id
+WHERE compaction_level = $5
+AND   to_delete is null
+AND   shard_id = $1
+AND   created_at > $2
+AND   skipped_compactions.partition_id IS NULL
+GROUP BY 1, 2, 3, 4
+HAVING count(id) >= $3
+ORDER BY 5 DESC
+LIMIT $4;
+            "#,
+        )
+        .bind(shard_id) // $1
+        .bind(time_in_the_past) //$2
+        .bind(min_num_files) // $3
+        .bind(num_partitions) // $4
+        .bind(CompactionLevel::Initial) // $5
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn most_cold_files_partitions(
+        &mut self,
+        shard_id: ShardId,
+        time_in_the_past: Timestamp,
+        num_partitions: usize,
+    ) -> Result<Vec<PartitionParam>> {
+        let num_partitions = num_partitions as i32;
+
+        // This query returns partitions with most L0+L1 files and all L0 files (both deleted and non deleted) are either created
+        // before the given time ($2) or not available (removed by garbage collector)
+        sqlx::query_as::<_, PartitionParam>(
+            r#"
+SELECT parquet_file.partition_id, parquet_file.shard_id, parquet_file.namespace_id,
+       parquet_file.table_id,
+       count(case when to_delete is null then 1 end) total_count,
+       max(case when compaction_level= $4 then parquet_file.created_at end)
+FROM   parquet_file
+LEFT OUTER JOIN skipped_compactions ON parquet_file.partition_id = skipped_compactions.partition_id
+WHERE  (compaction_level = $4 OR compaction_level = $5)
+AND    shard_id = $1
+AND    skipped_compactions.partition_id IS NULL
+GROUP BY 1, 2, 3, 4
+HAVING count(case when to_delete is null then 1 end) > 0
+       AND ( max(case when compaction_level= $4 then parquet_file.created_at end) < $2  OR
+             max(case when compaction_level= $4 then parquet_file.created_at end) is null)
+ORDER BY total_count DESC
+LIMIT $3;
+            "#,
+        )
+        .bind(shard_id) // $1
+        .bind(time_in_the_past) // $2
+        .bind(num_partitions) // $3
+        .bind(CompactionLevel::Initial) // $4
+        .bind(CompactionLevel::FileNonOverlapped) // $5
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_by_partition_not_to_delete(
+        &mut self,
+        partition_id: PartitionId,
+    ) -> Result<Vec<ParquetFile>> {
+        sqlx::query_as::<_, ParquetFilePod>(
+            r#"
+SELECT *
+FROM parquet_file
+WHERE parquet_file.partition_id = $1
+  AND parquet_file.to_delete IS NULL;
+        "#,
+        )
+        .bind(partition_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map(parquet_files)
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn update_compaction_level(
+        &mut self,
+        parquet_file_ids: &[ParquetFileId],
+        compaction_level: CompactionLevel,
+    ) -> Result<Vec<ParquetFileId>> {
+        let ids: Vec<_> = parquet_file_ids.iter().map(|p| p.get()).collect();
+        let updated = sqlx::query(
+            r#"
+UPDATE parquet_file
+SET compaction_level = $1
+WHERE id IN (SELECT value FROM json_each($2))
+RETURNING id;
+        "#,
+        )
+        .bind(compaction_level) // $1
+        .bind(Json(&ids)) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        let updated = updated.into_iter().map(|row| row.get("id")).collect();
+        Ok(updated)
+    }
+
+    async fn exist(&mut self, id: ParquetFileId) -> Result<bool> {
+        let read_result = sqlx::query_as::<_, Count>(
+            r#"SELECT count(1) as count FROM parquet_file WHERE id = $1;"#,
+        )
+        .bind(id) // $1
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(read_result.count > 0)
+    }
+
+    async fn count(&mut self) -> Result<i64> {
+        let read_result =
+            sqlx::query_as::<_, Count>(r#"SELECT count(1) as count FROM parquet_file;"#)
+                .fetch_one(&mut self.inner)
+                .await
+                .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(read_result.count)
+    }
+
+    async fn count_by_overlaps_with_level_0(
+        &mut self,
+        table_id: TableId,
+        shard_id: ShardId,
+        min_time: Timestamp,
+        max_time: Timestamp,
+        sequence_number: SequenceNumber,
+    ) -> Result<i64> {
+        let read_result = sqlx::query_as::<_, Count>(
+            r#"
+SELECT count(1) as count
+FROM parquet_file
+WHERE table_id = $1
+  AND shard_id = $2
+  AND max_sequence_number < $3
+  AND parquet_file.to_delete IS NULL
+  AND compaction_level = $6
+  AND ((parquet_file.min_time <= $4 AND parquet_file.max_time >= $4)
+  OR (parquet_file.min_time > $4 AND parquet_file.min_time <= $5));
+            "#,
+        )
+        .bind(table_id) // $1
+        .bind(shard_id) // $2
+        .bind(sequence_number) // $3
+        .bind(min_time) // $4
+        .bind(max_time) // $5
+        .bind(CompactionLevel::Initial) // $6
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(read_result.count)
+    }
+
+    async fn count_by_overlaps_with_level_1(
+        &mut self,
+        table_id: TableId,
+        shard_id: ShardId,
+        min_time: Timestamp,
+        max_time: Timestamp,
+    ) -> Result<i64> {
+        let read_result = sqlx::query_as::<_, Count>(
+            r#"
+SELECT count(1) as count
+FROM parquet_file
+WHERE table_id = $1
+  AND shard_id = $2
+  AND parquet_file.to_delete IS NULL
+  AND compaction_level = $5
+  AND ((parquet_file.min_time <= $3 AND parquet_file.max_time >= $3)
+  OR (parquet_file.min_time > $3 AND parquet_file.min_time <= $4));
+            "#,
+        )
+        .bind(table_id) // $1
+        .bind(shard_id) // $2
+        .bind(min_time) // $3
+        .bind(max_time) // $4
+        .bind(CompactionLevel::FileNonOverlapped) // $5
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(read_result.count)
+    }
+
+    async fn get_by_object_store_id(
+        &mut self,
+        object_store_id: Uuid,
+    ) -> Result<Option<ParquetFile>> {
+        let rec = sqlx::query_as::<_, ParquetFilePod>(
+            r#"
+SELECT *
+FROM parquet_file
+WHERE object_store_id = $1;
+             "#,
+        )
+        .bind(object_store_id) // $1
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(rec.map(Into::into))
+    }
+
+    async fn create_lineage(
+        &mut self,
+        parquet_file: &ParquetFile,
+        inputs: &[(ParquetFileId, CompactionLevel)],
+    ) -> Result<()> {
+        // SQLite has no array parameters to UNNEST, so insert the inputs one at a time.
+        for &(input_parquet_file_id, input_compaction_level) in inputs {
+            sqlx::query(
+                r#"
+INSERT INTO parquet_file_lineage
+    ( parquet_file_id, compaction_level, input_parquet_file_id, input_compaction_level,
+      namespace_id, table_id )
+VALUES ( $1, $2, $3, $4, $5, $6 )
+ON CONFLICT DO NOTHING;
+                "#,
+            )
+            .bind(parquet_file.id) // $1
+            .bind(parquet_file.compaction_level) // $2
+            .bind(input_parquet_file_id) // $3
+            .bind(input_compaction_level) // $4
+            .bind(parquet_file.namespace_id) // $5
+            .bind(parquet_file.table_id) // $6
+            .execute(&mut self.inner)
+            .await
+            .map_err(|e| {
+                if is_fk_violation(&e) {
+                    Error::ForeignKeyViolation { source: e }
+                } else {
+                    Error::SqlxError { source: e }
+                }
+            })?;
+        }
+
+        Ok(())
+    }
+
+    async fn list_lineage_by_namespace(
+        &mut self,
+        namespace_id: NamespaceId,
+    ) -> Result<Vec<ParquetFileLineage>> {
+        sqlx::query_as::<_, ParquetFileLineage>(
+            r#"
+SELECT *
+FROM parquet_file_lineage
+WHERE namespace_id = $1
+ORDER BY parquet_file_id, input_parquet_file_id;
+             "#,
+        )
+        .bind(namespace_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+}
+
+#[async_trait]
+impl ProcessedTombstoneRepo for SqliteTxn {
+    async fn create(
+        &mut self,
+        parquet_file_id: ParquetFileId,
+        tombstone_id: TombstoneId,
+    ) -> Result<ProcessedTombstone> {
+        sqlx::query_as::<_, ProcessedTombstone>(
+            r#"
+INSERT INTO processed_tombstone ( tombstone_id, parquet_file_id )
+VALUES ( $1, $2 )
+RETURNING *;
+        "#,
+        )
+        .bind(tombstone_id) // $1
+        .bind(parquet_file_id) // $2
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                Error::ProcessTombstoneExists {
+                    tombstone_id: tombstone_id.get(),
+                    parquet_file_id: parquet_file_id.get(),
+                }
+            } else if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
+
+    async fn exist(
+        &mut self,
+        parquet_file_id: ParquetFileId,
+        tombstone_id: TombstoneId,
+    ) -> Result<bool> {
+        let read_result = sqlx::query_as::<_, Count>(
+            r#"
+SELECT count(1) as count
+FROM processed_tombstone
+WHERE parquet_file_id = $1
+  AND tombstone_id = $2;
+            "#,
+        )
+        .bind(parquet_file_id) // $1
+        .bind(tombstone_id) // $2
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(read_result.count > 0)
+    }
+
+    async fn count(&mut self) -> Result<i64> {
+        let read_result =
+            sqlx::query_as::<_, Count>(r#"SELECT count(1) as count FROM processed_tombstone;"#)
+                .fetch_one(&mut self.inner)
+                .await
+                .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(read_result.count)
+    }
+
+    async fn count_by_tombstone_id(&mut self, tombstone_id: TombstoneId) -> Result<i64> {
+        let read_result = sqlx::query_as::<_, Count>(
+            r#"SELECT count(1) as count FROM processed_tombstone WHERE tombstone_id = $1;"#,
+        )
+        .bind(tombstone_id) // $1
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(read_result.count)
+    }
+}
+
+#[async_trait]
+impl OperationRepo for SqliteTxn {
+    async fn create(
+        &mut self,
+        kind: &str,
+        description: &str,
+        node: &str,
+        created_at: Timestamp,
+    ) -> Result<Operation> {
+        sqlx::query_as::<_, Operation>(
+            r#"
+INSERT INTO operation ( kind, description, node, status, created_at )
+VALUES ( $1, $2, $3, $4, $5 )
+RETURNING *;
+        "#,
+        )
+        .bind(kind) // $1
+        .bind(description) // $2
+        .bind(node) // $3
+        .bind(OperationStatus::Running) // $4
+        .bind(created_at) // $5
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn get_by_id(&mut self, id: OperationId) -> Result<Option<Operation>> {
+        let rec = sqlx::query_as::<_, Operation>(r#"SELECT * FROM operation WHERE id = $1;"#)
+            .bind(id) // $1
+            .fetch_one(&mut self.inner)
+            .await;
+
+        if let Err(sqlx::Error::RowNotFound) = rec {
+            return Ok(None);
+        }
+
+        let operation = rec.map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(Some(operation))
+    }
+
+    async fn list(&mut self) -> Result<Vec<Operation>> {
+        sqlx::query_as::<_, Operation>(r#"SELECT * FROM operation ORDER BY id;"#)
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn complete(
+        &mut self,
+        id: OperationId,
+        status: OperationStatus,
+        completed_at: Timestamp,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+UPDATE operation
+SET status = $1, completed_at = $2
+WHERE id = $3
+  AND status = $4;
+        "#,
+        )
+        .bind(status) // $1
+        .bind(completed_at) // $2
+        .bind(id) // $3
+        .bind(OperationStatus::Running) // $4
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+
+    async fn interrupt_running(&mut self, node: &str, at: Timestamp) -> Result<Vec<Operation>> {
+        let mut interrupted = sqlx::query_as::<_, Operation>(
+            r#"
+UPDATE operation
+SET status = $1, completed_at = $2
+WHERE node = $3
+  AND status = $4
+RETURNING *;
+        "#,
+        )
+        .bind(OperationStatus::Interrupted) // $1
+        .bind(at) // $2
+        .bind(node) // $3
+        .bind(OperationStatus::Running) // $4
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        // RETURNING does not guarantee any order
+        interrupted.sort_by_key(|o| o.id);
+
+        Ok(interrupted)
+    }
+
+    async fn delete_completed_before(&mut self, older_than: Timestamp) -> Result<u64> {
+        let deleted = sqlx::query(
+            r#"
+DELETE FROM operation
+WHERE completed_at < $1;
+        "#,
+        )
+        .bind(older_than) // $1
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(deleted.rows_affected())
+    }
+}
+
+#[async_trait]
+impl ApiTokenRepo for SqliteTxn {
+    async fn create(
+        &mut self,
+        token_hash: &str,
+        namespace_id: Option<NamespaceId>,
+        permission: ApiTokenPermission,
+        description: &str,
+        created_at: Timestamp,
+    ) -> Result<ApiToken> {
+        sqlx::query_as::<_, ApiToken>(
+            r#"
+INSERT INTO api_token ( token_hash, namespace_id, permission, description, created_at )
+VALUES ( $1, $2, $3, $4, $5 )
+RETURNING *;
+        "#,
+        )
+        .bind(token_hash) // $1
+        .bind(namespace_id) // $2
+        .bind(permission) // $3
+        .bind(description) // $4
+        .bind(created_at) // $5
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                Error::ApiTokenExists
+            } else if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
+
+    async fn get_by_hash(&mut self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let rec =
+            sqlx::query_as::<_, ApiToken>(r#"SELECT * FROM api_token WHERE token_hash = $1;"#)
+                .bind(token_hash) // $1
+                .fetch_one(&mut self.inner)
+                .await;
+
+        if let Err(sqlx::Error::RowNotFound) = rec {
+            return Ok(None);
+        }
+
+        let token = rec.map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(Some(token))
+    }
+
+    async fn list(&mut self) -> Result<Vec<ApiToken>> {
+        sqlx::query_as::<_, ApiToken>(r#"SELECT * FROM api_token ORDER BY id;"#)
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn revoke(&mut self, id: ApiTokenId, revoked_at: Timestamp) -> Result<Option<ApiToken>> {
+        let rec = sqlx::query_as::<_, ApiToken>(
+            r#"
+UPDATE api_token
+SET revoked_at = COALESCE(revoked_at, $1)
+WHERE id = $2
+RETURNING *;
+        "#,
+        )
+        .bind(revoked_at) // $1
+        .bind(id) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        if let Err(sqlx::Error::RowNotFound) = rec {
+            return Ok(None);
+        }
+
+        let token = rec.map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(Some(token))
+    }
+}
+
+#[async_trait]
+impl NodeDrainRepo for SqliteTxn {
+    async fn set_state(
+        &mut self,
+        address: &str,
+        state: DrainState,
+        updated_at: Timestamp,
+    ) -> Result<NodeDrain> {
+        sqlx::query_as::<_, NodeDrain>(
+            r#"
+INSERT INTO node_drain ( address, state, updated_at )
+VALUES ( $1, $2, $3 )
+ON CONFLICT ( address )
+DO UPDATE SET state = excluded.state, updated_at = excluded.updated_at
+RETURNING *;
+        "#,
+        )
+        .bind(address) // $1
+        .bind(state) // $2
+        .bind(updated_at) // $3
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn get_by_address(&mut self, address: &str) -> Result<Option<NodeDrain>> {
+        let rec = sqlx::query_as::<_, NodeDrain>(r#"SELECT * FROM node_drain WHERE address = $1;"#)
+            .bind(address) // $1
+            .fetch_one(&mut self.inner)
+            .await;
+
+        if let Err(sqlx::Error::RowNotFound) = rec {
+            return Ok(None);
+        }
+
+        let drain = rec.map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(Some(drain))
+    }
+
+    async fn list(&mut self) -> Result<Vec<NodeDrain>> {
+        sqlx::query_as::<_, NodeDrain>(r#"SELECT * FROM node_drain ORDER BY address;"#)
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+}
+
+/// The extended result codes returned by SQLite for a unique or primary key constraint
+/// violation.
+///
+/// See <https://www.sqlite.org/rescode.html>
+const SQLITE_CONSTRAINT_UNIQUE: &str = "2067";
+const SQLITE_CONSTRAINT_PRIMARYKEY: &str = "1555";
+
+/// Returns true if `e` is a unique constraint violation error.
+fn is_unique_violation(e: &sqlx::Error) -> bool {
+    if let sqlx::Error::Database(inner) = e {
+        if let Some(code) = inner.code() {
+            if code == SQLITE_CONSTRAINT_UNIQUE || code == SQLITE_CONSTRAINT_PRIMARYKEY {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// The extended result code returned by SQLite for a foreign key constraint violation.
+const SQLITE_CONSTRAINT_FOREIGNKEY: &str = "787";
+
+fn is_fk_violation(e: &sqlx::Error) -> bool {
+    if let sqlx::Error::Database(inner) = e {
+        if let Some(code) = inner.code() {
+            if code == SQLITE_CONSTRAINT_FOREIGNKEY {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_or_get_default_records;
+    use std::ops::DerefMut;
+    use tempfile::TempDir;
+
+    /// Create a catalog in a fresh database file.
+    ///
+    /// File-backed (rather than `sqlite::memory:`) so that concurrent transactions see WAL
+    /// snapshot isolation, like they would in production. The returned [`TempDir`] must be kept
+    /// alive for as long as the catalog is used.
+    async fn setup_db() -> (SqliteCatalog, TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let options = SqliteConnectionOptions {
+            dsn: format!("sqlite://{}", dir.path().join("catalog.sqlite").display()),
+            ..Default::default()
+        };
+        let metrics = Arc::new(metric::Registry::default());
+
+        let catalog = SqliteCatalog::connect(options, metrics).await.unwrap();
+        catalog.setup().await.unwrap();
+
+        (catalog, dir)
+    }
+
+    #[tokio::test]
+    async fn test_catalog() {
+        let (sqlite, _dir) = setup_db().await;
+        let sqlite: Arc<dyn Catalog> = Arc::new(sqlite);
+
+        crate::interface::test_helpers::test_catalog(sqlite).await;
+    }
+
+    #[tokio::test]
+    async fn test_migration_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = SqliteConnectionOptions {
+            dsn: format!("sqlite://{}", dir.path().join("catalog.sqlite").display()),
+            ..Default::default()
+        };
+        let sqlite = SqliteCatalog::connect(options, Default::default())
+            .await
+            .unwrap();
+
+        // nothing applied yet, and looking does not create the migrations table
+        let status = sqlite.migration_status().await.unwrap();
+        assert!(status.applied.is_empty());
+        assert_eq!(status.pending.len(), MIGRATOR.iter().count());
+        assert_eq!(sqlite.migration_status().await.unwrap(), status);
+
+        sqlite.migrate(None).await.unwrap();
+        let status = sqlite.migration_status().await.unwrap();
+        assert!(status.is_up_to_date(), "{:?}", status);
+        assert_eq!(status.applied.len(), MIGRATOR.iter().count());
+    }
+
+    #[tokio::test]
+    async fn test_in_memory() {
+        let sqlite = SqliteCatalog::connect(Default::default(), Default::default())
+            .await
+            .unwrap();
+        sqlite.setup().await.unwrap();
+
+        // every pooled connection must see the same in-memory database
+        let mut txn = sqlite.start_transaction().await.unwrap();
+        let (topic, _, shards) = create_or_get_default_records(2, txn.deref_mut())
+            .await
+            .unwrap();
+        txn.commit().await.unwrap();
+
+        let mut repos = sqlite.repositories().await;
+        assert_eq!(
+            repos.topics().get_by_name(&topic.name).await.unwrap(),
+            Some(topic)
+        );
+        assert_eq!(repos.shards().list().await.unwrap().len(), shards.len());
+    }
+
+    #[tokio::test]
+    async fn test_sort_key_and_column_set_round_trip() {
+        let (sqlite, _dir) = setup_db().await;
+        let mut repos = sqlite.repositories().await;
+
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("ns", None, topic.id, pool.id)
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("t", namespace.id)
+            .await
+            .unwrap();
+        let shard = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(1))
+            .await
+            .unwrap();
+        let partition = repos
+            .partitions()
+            .create_or_get("p".into(), shard.id, table.id)
+            .await
+            .unwrap();
+        assert!(partition.sort_key.is_empty());
+
+        let partition = repos
+            .partitions()
+            .update_sort_key(partition.id, &["tag", "time"])
+            .await
+            .unwrap();
+        assert_eq!(partition.sort_key, vec!["tag", "time"]);
+        let fetched = repos
+            .partitions()
+            .get_by_id(partition.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched, partition);
+
+        let params = ParquetFileParams {
+            shard_id: shard.id,
+            namespace_id: namespace.id,
+            table_id: table.id,
+            partition_id: partition.id,
+            object_store_id: Uuid::new_v4(),
+            max_sequence_number: SequenceNumber::new(1),
+            min_time: Timestamp::new(1),
+            max_time: Timestamp::new(2),
+            file_size_bytes: 1337,
+            row_count: 0,
+            compaction_level: CompactionLevel::Initial,
+            created_at: Timestamp::new(1),
+            column_set: ColumnSet::new([ColumnId::new(3), ColumnId::new(1)]),
+        };
+        let file = repos.parquet_files().create(params.clone()).await.unwrap();
+        assert_eq!(file.column_set, params.column_set);
+        assert_eq!(
+            repos
+                .parquet_files()
+                .get_by_object_store_id(params.object_store_id)
+                .await
+                .unwrap(),
+            Some(file)
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Accounting of the usage of namespaces for billing.
+//!
+//! Routers and queriers record the usage they serve in a [`UsageAccumulator`], which adds it to
+//! the cumulative [`NamespaceUsage`] in the catalog periodically, so that the catalog is not
+//! written to on every request.
+
+use crate::interface::{Catalog, Error, Result};
+use data_types::{NamespaceId, NamespaceUsage};
+use observability_deps::tracing::{info, warn};
+use parking_lot::Mutex;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+
+/// Accumulates the usage of namespaces until it is flushed to the catalog.
+#[derive(Debug, Default)]
+pub struct UsageAccumulator {
+    /// Usage recorded since the last flush.
+    pending: Mutex<HashMap<NamespaceId, NamespaceUsage>>,
+
+    /// Cumulative usage in the catalog as of the last flush.
+    totals: Mutex<HashMap<NamespaceId, NamespaceUsage>>,
+}
+
+impl UsageAccumulator {
+    /// Record a write of `rows` rows and `bytes` bytes to namespace `namespace_id`.
+    pub fn record_write(&self, namespace_id: NamespaceId, rows: u64, bytes: u64) {
+        self.record(namespace_id, |usage| {
+            usage.rows_written += rows as i64;
+            usage.bytes_written += bytes as i64;
+        });
+    }
+
+    /// Record a query executed against namespace `namespace_id`.
+    pub fn record_query(&self, namespace_id: NamespaceId) {
+        self.record(namespace_id, |usage| usage.queries_executed += 1);
+    }
+
+    /// Record a scan of `bytes` bytes of namespace `namespace_id` by a query.
+    pub fn record_scan(&self, namespace_id: NamespaceId, bytes: u64) {
+        self.record(namespace_id, |usage| usage.bytes_scanned += bytes as i64);
+    }
+
+    fn record(&self, namespace_id: NamespaceId, f: impl FnOnce(&mut NamespaceUsage)) {
+        let mut pending = self.pending.lock();
+        f(pending
+            .entry(namespace_id)
+            .or_insert_with(|| NamespaceUsage::new(namespace_id)));
+    }
+
+    /// The cumulative usage of namespace `namespace_id` in the catalog as of the last flush.
+    pub fn total(&self, namespace_id: NamespaceId) -> Option<NamespaceUsage> {
+        self.totals.lock().get(&namespace_id).copied()
+    }
+
+    /// Add the usage recorded since the last flush to the catalog, and reload the cumulative
+    /// usage of all namespaces.
+    ///
+    /// Usage that could not be added is kept for the next flush, except for the usage of
+    /// namespaces that no longer exist.
+    pub async fn flush(&self, catalog: &dyn Catalog) -> Result<()> {
+        let pending = std::mem::take(&mut *self.pending.lock());
+
+        let mut repos = catalog.repositories().await;
+        let mut result = Ok(());
+        for usage in pending.into_values() {
+            match repos.namespaces().add_usage(&usage).await {
+                Ok(()) => {}
+                Err(Error::ForeignKeyViolation { .. } | Error::NamespaceNotFoundById { .. }) => {
+                    warn!(namespace_id=%usage.namespace_id, "dropping usage of unknown namespace");
+                }
+                Err(e) => {
+                    self.record(usage.namespace_id, |pending| pending.add(&usage));
+                    result = Err(e);
+                }
+            }
+        }
+        result?;
+
+        let totals = repos.namespaces().list_usage().await?;
+        *self.totals.lock() = totals.into_iter().map(|u| (u.namespace_id, u)).collect();
+
+        Ok(())
+    }
+
+    /// Flush the recorded usage to `catalog` every `interval` until `shutdown` is cancelled,
+    /// flushing it a last time on shutdown.
+    pub async fn run(
+        self: Arc<Self>,
+        catalog: Arc<dyn Catalog>,
+        interval: Duration,
+        shutdown: CancellationToken,
+    ) {
+        info!(?interval, "flushing namespace usage to the catalog");
+
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = ticker.tick() => {}
+            }
+            if let Err(e) = self.flush(catalog.as_ref()).await {
+                warn!(%e, "failed to flush namespace usage");
+            }
+        }
+
+        if let Err(e) = self.flush(catalog.as_ref()).await {
+            warn!(%e, "failed to flush namespace usage on shutdown");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::MemCatalog;
+
+    #[tokio::test]
+    async fn test_flush() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+        let namespace = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("foo").await.unwrap();
+            let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+            repos
+                .namespaces()
+                .create("ns", None, topic.id, pool.id)
+                .await
+                .unwrap()
+        };
+
+        let usage = UsageAccumulator::default();
+        usage.record_write(namespace.id, 10, 100);
+        usage.record_write(namespace.id, 1, 10);
+        usage.record_query(namespace.id);
+        usage.record_scan(namespace.id, 1000);
+        assert_eq!(usage.total(namespace.id), None);
+
+        usage.flush(catalog.as_ref()).await.unwrap();
+        let want = NamespaceUsage {
+            rows_written: 11,
+            bytes_written: 110,
+            queries_executed: 1,
+            bytes_scanned: 1000,
+            ..NamespaceUsage::new(namespace.id)
+        };
+        assert_eq!(usage.total(namespace.id), Some(want));
+
+        // Nothing is added twice.
+        usage.flush(catalog.as_ref()).await.unwrap();
+        assert_eq!(usage.total(namespace.id), Some(want));
+
+        // Usage of an unknown namespace is dropped.
+        let unknown = NamespaceId::new(42);
+        usage.record_query(unknown);
+        usage.flush(catalog.as_ref()).await.unwrap();
+        assert!(usage.pending.lock().is_empty());
+        assert_eq!(usage.total(unknown), None);
+    }
+}
@@ -355,9 +355,10 @@ impl InnerPointsWriter {
 
                     let meta = IoxMetadata::external(crate::now_ns(), &*measurement);
 
-                    let (data, _parquet_file_meta) = serialize::to_parquet_bytes(stream, &meta)
-                        .await
-                        .context(ParquetSerializationSnafu)?;
+                    let (data, _parquet_file_meta) =
+                        serialize::to_parquet_bytes(stream, &meta, &Default::default())
+                            .await
+                            .context(ParquetSerializationSnafu)?;
                     let data = Bytes::from(data);
 
                     let mut filename = dir_path.clone();
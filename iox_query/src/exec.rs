@@ -4,7 +4,12 @@
 pub(crate) mod context;
 pub mod field;
 pub mod fieldlist;
+mod gapfill;
+mod metrics;
 mod non_null_checker;
+mod params;
+pub mod persisted_watermarks;
+pub mod query_statistics;
 mod query_tracing;
 mod schema_pivot;
 pub mod seriesset;
@@ -19,6 +24,7 @@ use std::{collections::HashMap, sync::Arc};
 
 use datafusion::{
     self,
+    catalog::schema::SchemaProvider,
     execution::{
         context::SessionState,
         runtime_env::{RuntimeConfig, RuntimeEnv},
@@ -28,16 +34,27 @@ use datafusion::{
     prelude::SessionContext,
 };
 
-pub use context::{IOxSessionConfig, IOxSessionContext, SessionContextIOxExt};
+pub use context::{
+    sql_permission, IOxSessionConfig, IOxSessionContext, PlanCache, SessionContextIOxExt,
+    TableWriter,
+};
+pub use gapfill::DEFAULT_MAX_GAP_FILL_BUCKETS;
+pub use persisted_watermarks::PersistedWatermarks;
+pub use query_statistics::{QueryStatistics, QueryStatisticsSummary};
 use schema_pivot::SchemaPivotNode;
 
-use self::{non_null_checker::NonNullCheckerNode, split::StreamSplitNode};
+use self::{
+    metrics::ExecutorMetrics, non_null_checker::NonNullCheckerNode, split::StreamSplitNode,
+};
 
 /// Configuration for an Executor
 #[derive(Debug, Clone)]
 pub struct ExecutorConfig {
-    /// Number of threads per thread pool
-    pub num_threads: usize,
+    /// Number of threads of the pool for queries
+    pub num_query_threads: usize,
+
+    /// Number of threads of the pool for system / reorganization tasks
+    pub num_reorg_threads: usize,
 
     /// Target parallelism for query execution
     pub target_query_partitions: usize,
@@ -55,24 +72,34 @@ pub struct DedicatedExecutors {
     /// compact
     reorg_exec: DedicatedExecutor,
 
-    /// Number of threads per thread pool
-    num_threads: usize,
+    /// Number of threads of the query thread pool
+    num_query_threads: usize,
+
+    /// Number of threads of the reorg thread pool
+    num_reorg_threads: usize,
 }
 
 impl DedicatedExecutors {
-    pub fn new(num_threads: usize) -> Self {
-        let query_exec = DedicatedExecutor::new("IOx Query Executor Thread", num_threads);
-        let reorg_exec = DedicatedExecutor::new("IOx Reorg Executor Thread", num_threads);
+    /// Creates the thread pools for queries and reorganization tasks, each with its own tokio
+    /// runtime so that long running reorganizations (e.g. compactions) cannot starve queries.
+    pub fn new(num_query_threads: usize, num_reorg_threads: usize) -> Self {
+        let query_exec = DedicatedExecutor::new("IOx Query Executor Thread", num_query_threads);
+        let reorg_exec = DedicatedExecutor::new("IOx Reorg Executor Thread", num_reorg_threads);
 
         Self {
             query_exec,
             reorg_exec,
-            num_threads,
+            num_query_threads,
+            num_reorg_threads,
         }
     }
 
-    pub fn num_threads(&self) -> usize {
-        self.num_threads
+    pub fn num_query_threads(&self) -> usize {
+        self.num_query_threads
+    }
+
+    pub fn num_reorg_threads(&self) -> usize {
+        self.num_reorg_threads
     }
 }
 
@@ -108,14 +135,18 @@ impl Executor {
     /// with num_threads
     pub fn new(num_threads: usize) -> Self {
         Self::new_with_config(ExecutorConfig {
-            num_threads,
+            num_query_threads: num_threads,
+            num_reorg_threads: num_threads,
             target_query_partitions: num_threads,
             object_stores: HashMap::default(),
         })
     }
 
     pub fn new_with_config(config: ExecutorConfig) -> Self {
-        let executors = Arc::new(DedicatedExecutors::new(config.num_threads));
+        let executors = Arc::new(DedicatedExecutors::new(
+            config.num_query_threads,
+            config.num_reorg_threads,
+        ));
         Self::new_with_config_and_executors(config, executors)
     }
 
@@ -124,12 +155,13 @@ impl Executor {
     /// This is mostly useful if you wanna keep the executors (because they are quiet expensive to create) but need a fresh IOx runtime.
     ///
     /// # Panic
-    /// Panics if the number of threads in `executors` is different from `config`.
+    /// Panics if the number of threads of the pools in `executors` is different from `config`.
     pub fn new_with_config_and_executors(
         config: ExecutorConfig,
         executors: Arc<DedicatedExecutors>,
     ) -> Self {
-        assert_eq!(config.num_threads, executors.num_threads);
+        assert_eq!(config.num_query_threads, executors.num_query_threads);
+        assert_eq!(config.num_reorg_threads, executors.num_reorg_threads);
 
         let runtime_config = RuntimeConfig::new();
 
@@ -184,6 +216,15 @@ impl Executor {
         }
     }
 
+    /// Report the number of queued and active tasks of the thread pools to `registry`, as the
+    /// `iox_executor_tasks` metric.
+    pub fn register_metrics(&self, registry: &metric::Registry) {
+        let executors = Arc::clone(&self.executors);
+        registry.register_instrument("iox_executor_tasks", move || {
+            ExecutorMetrics::new(executors)
+        });
+    }
+
     /// Initializes shutdown.
     pub fn shutdown(&self) {
         self.executors.query_exec.shutdown();
@@ -312,18 +353,36 @@ pub fn make_stream_split(input: LogicalPlan, split_exprs: Vec<Expr>) -> LogicalP
 pub trait ExecutionContextProvider {
     /// Returns a new execution context suitable for running queries
     fn new_query_context(&self, span_ctx: Option<trace::ctx::SpanContext>) -> IOxSessionContext;
+
+    /// Returns the user tables as a DataFusion schema so that they can be made addressable from a
+    /// query context created by another provider (see
+    /// [`IOxSessionContext::register_namespace_schema`]).
+    ///
+    /// Returns `None` if cross-namespace queries are not supported.
+    fn user_schema_provider(&self) -> Option<Arc<dyn SchemaProvider>> {
+        None
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use arrow::{
-        array::{ArrayRef, Int64Array, StringArray},
+        array::{
+            ArrayRef, Float64Array, Int64Array, StringArray, TimestampNanosecondArray, UInt64Array,
+        },
         datatypes::{DataType, Field, Schema, SchemaRef},
     };
+    use arrow_util::assert_batches_eq;
+    use data_types::ApiTokenPermission;
     use datafusion::{
+        catalog::schema::MemorySchemaProvider,
         datasource::{provider_as_source, MemTable},
+        error::DataFusionError,
         logical_expr::LogicalPlanBuilder,
+        physical_plan::SendableRecordBatchStream,
+        scalar::ScalarValue,
     };
+    use futures::TryStreamExt;
     use stringset::StringSet;
 
     use super::*;
@@ -509,6 +568,413 @@ mod tests {
         exec.join().await;
     }
 
+    #[tokio::test]
+    async fn external_table_disabled_by_default() {
+        let exec = Executor::new(1);
+        let ctx = exec.new_context(ExecutorType::Query);
+
+        let err = ctx
+            .prepare_sql("CREATE EXTERNAL TABLE t STORED AS CSV LOCATION '/tmp/t.csv'")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, DataFusionError::NotImplemented(_)),
+            "unexpected error: {err}"
+        );
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn external_table_location_allowlist() {
+        let dir = test_helpers::tmp_dir().unwrap();
+        let path = dir.path().join("data.csv");
+        std::fs::write(&path, "a,b\n1,foo\n2,bar\n").unwrap();
+        let prefix = format!("{}/", dir.path().display());
+
+        let exec = Executor::new(1);
+        let ctx = exec
+            .new_context(ExecutorType::Query)
+            .with_external_table_locations(vec![prefix]);
+
+        let err = ctx
+            .prepare_sql("CREATE EXTERNAL TABLE t STORED AS CSV LOCATION '/etc/passwd'")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, DataFusionError::Plan(_)),
+            "unexpected error: {err}"
+        );
+
+        // neither escaping the prefix nor sharing its name is allowed
+        for location in [
+            format!("{}/../etc/passwd", dir.path().display()),
+            format!("{}/%2e%2e/etc/passwd", dir.path().display()),
+            format!("{}-other/data.csv", dir.path().display()),
+        ] {
+            let sql = format!("CREATE EXTERNAL TABLE t STORED AS CSV LOCATION '{location}'");
+            let err = ctx.prepare_sql(&sql).await.unwrap_err();
+            assert!(
+                matches!(err, DataFusionError::Plan(_)),
+                "unexpected error for {location}: {err}"
+            );
+        }
+
+        let sql = format!(
+            "CREATE EXTERNAL TABLE t STORED AS CSV WITH HEADER ROW LOCATION '{}'",
+            path.display()
+        );
+        let plan = ctx.prepare_sql(&sql).await.unwrap();
+        assert!(ctx.collect(plan).await.unwrap().is_empty());
+
+        let plan = ctx
+            .prepare_sql("SELECT count(*) AS n FROM t")
+            .await
+            .unwrap();
+        let batches = ctx.collect(plan).await.unwrap();
+        let n = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .value(0);
+        assert_eq!(n, 2);
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn write_query_results() {
+        let exec = Executor::new(1);
+        let ctx = exec.new_context(ExecutorType::Query);
+
+        let sql = "CREATE TABLE t AS SELECT 1 AS a";
+        let err = ctx.prepare_sql(sql).await.unwrap_err();
+        assert!(
+            matches!(err, DataFusionError::NotImplemented(_)),
+            "unexpected error: {err}"
+        );
+
+        let writer = Arc::new(RecordingTableWriter::default());
+        let ctx = exec
+            .new_context(ExecutorType::Query)
+            .with_table_writer(Arc::clone(&writer) as _)
+            .with_api_token(Some("token"));
+
+        let plan = ctx.prepare_sql(sql).await.unwrap();
+        let batches = ctx.collect(plan).await.unwrap();
+        let count = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap()
+            .value(0);
+        assert_eq!(count, 1);
+
+        let plan = ctx
+            .prepare_sql("INSERT INTO t SELECT 2 AS a UNION ALL SELECT 3 AS a")
+            .await
+            .unwrap();
+        ctx.collect(plan).await.unwrap();
+
+        let writes = writer.writes.lock().unwrap();
+        assert_eq!(
+            writes.as_slice(),
+            &[
+                ("t".to_string(), 1, Some("token".to_string())),
+                ("t".to_string(), 2, Some("token".to_string()))
+            ]
+        );
+
+        exec.join().await;
+    }
+
+    #[test]
+    fn test_sql_permission() {
+        for (sql, permission) in [
+            ("SELECT 1", ApiTokenPermission::Read),
+            ("EXPLAIN SELECT * FROM t", ApiTokenPermission::Read),
+            ("not sql", ApiTokenPermission::Read),
+            ("INSERT INTO t SELECT 1 AS a", ApiTokenPermission::Write),
+            ("CREATE TABLE t AS SELECT 1 AS a", ApiTokenPermission::Write),
+            (
+                "SELECT 1; INSERT INTO t SELECT 1 AS a",
+                ApiTokenPermission::Write,
+            ),
+            (
+                "CREATE EXTERNAL TABLE t STORED AS CSV LOCATION '/tmp/t.csv'",
+                ApiTokenPermission::Admin,
+            ),
+        ] {
+            assert_eq!(sql_permission(sql), permission, "{sql}");
+        }
+    }
+
+    #[tokio::test]
+    async fn gap_fill() {
+        let exec = Executor::new(1);
+        let ctx = exec.new_context(ExecutorType::Query);
+
+        // the rows of host a are split across partitions
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("host", DataType::Utf8, false),
+            Field::new("time", schema::TIME_DATA_TYPE(), false),
+            Field::new("usage", DataType::Float64, false),
+        ]));
+        let batch = |host: &str, minutes: &[i64], usage: &[f64]| {
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    to_string_array(&vec![host; minutes.len()]),
+                    Arc::new(TimestampNanosecondArray::from_iter_values(
+                        minutes.iter().map(|m| m * 60_000_000_000),
+                    )),
+                    Arc::new(Float64Array::from(usage.to_vec())),
+                ],
+            )
+            .unwrap()
+        };
+        let partitions = vec![
+            vec![batch("a", &[1], &[1.0]), batch("b", &[0], &[10.0])],
+            vec![batch("a", &[3, 3], &[2.0, 4.0])],
+        ];
+        let table = MemTable::try_new(Arc::clone(&schema), partitions).unwrap();
+        ctx.inner().register_table("cpu", Arc::new(table)).unwrap();
+
+        let sql = "SELECT host, date_bin_gapfill(INTERVAL '1 minute', time) AS minute, \
+            avg(usage) AS usage, locf(avg(usage)) AS locf_usage \
+            FROM cpu \
+            WHERE time >= '1970-01-01T00:00:00Z' AND time < '1970-01-01T00:04:00Z' \
+            GROUP BY host, minute \
+            ORDER BY host, minute";
+        let plan = ctx.prepare_sql(sql).await.unwrap();
+        let batches = ctx.collect(plan).await.unwrap();
+
+        let expected = vec![
+            "+------+---------------------+-------+------------+",
+            "| host | minute              | usage | locf_usage |",
+            "+------+---------------------+-------+------------+",
+            "| a    | 1970-01-01T00:00:00 |       |            |",
+            "| a    | 1970-01-01T00:01:00 | 1     | 1          |",
+            "| a    | 1970-01-01T00:02:00 |       | 1          |",
+            "| a    | 1970-01-01T00:03:00 | 3     | 3          |",
+            "| b    | 1970-01-01T00:00:00 | 10    | 10         |",
+            "| b    | 1970-01-01T00:01:00 |       | 10         |",
+            "| b    | 1970-01-01T00:02:00 |       | 10         |",
+            "| b    | 1970-01-01T00:03:00 |       | 10         |",
+            "+------+---------------------+-------+------------+",
+        ];
+        assert_batches_eq!(&expected, &batches);
+
+        // the time range to fill must be bounded
+        let err = ctx
+            .prepare_sql(
+                "SELECT date_bin_gapfill(INTERVAL '1 minute', time) AS minute, count(*) \
+                FROM cpu WHERE time >= '1970-01-01T00:00:00Z' GROUP BY minute",
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, DataFusionError::Plan(_)),
+            "unexpected error: {err}"
+        );
+
+        // locf and interpolate are only allowed around the aggregates of a gap-filled aggregation
+        for sql in [
+            "SELECT locf(usage) FROM cpu",
+            "SELECT host, date_bin_gapfill(INTERVAL '1 minute', time) AS minute, \
+            locf(avg(usage)) * 2 \
+            FROM cpu \
+            WHERE time >= '1970-01-01T00:00:00Z' AND time < '1970-01-01T00:04:00Z' \
+            GROUP BY host, minute",
+            "SELECT host, date_bin_gapfill(INTERVAL '1 minute', time) AS minute, locf(minute) \
+            FROM cpu \
+            WHERE time >= '1970-01-01T00:00:00Z' AND time < '1970-01-01T00:04:00Z' \
+            GROUP BY host, minute",
+        ] {
+            let err = ctx.prepare_sql(sql).await.unwrap_err();
+            assert!(
+                matches!(&err, DataFusionError::Plan(msg) if msg.contains("locf")),
+                "unexpected error: {err}"
+            );
+        }
+
+        // the number of buckets to fill is limited
+        let ctx = ctx.with_max_gap_fill_buckets(3);
+        let err = ctx.prepare_sql(sql).await.unwrap_err();
+        assert!(
+            matches!(&err, DataFusionError::Plan(msg) if msg.contains("4 buckets")),
+            "unexpected error: {err}"
+        );
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn cached_logical_plans() {
+        let exec = Executor::new(1);
+        let cache = Arc::new(RecordingPlanCache::default());
+        let ctx = exec
+            .new_context(ExecutorType::Query)
+            .with_plan_cache(Arc::clone(&cache) as _);
+
+        // queries that only differ in formatting share their plan
+        ctx.prepare_sql("SELECT 1 AS a").await.unwrap();
+        ctx.prepare_sql("select 1  as a -- again").await.unwrap();
+        assert_eq!(cache.statements(), vec!["SELECT 1 AS a".to_string()]);
+        assert_eq!(*cache.hits.lock().unwrap(), 1);
+
+        // parameter values are bound after the plan is taken from the cache
+        let params = HashMap::from([("v".to_string(), ScalarValue::Int64(Some(2)))]);
+        let plan = ctx
+            .prepare_sql_with_params("SELECT $v AS a", &params)
+            .await
+            .unwrap();
+        ctx.collect(plan).await.unwrap();
+        let params = HashMap::from([("v".to_string(), ScalarValue::Int64(Some(3)))]);
+        let plan = ctx
+            .prepare_sql_with_params("SELECT $v AS a", &params)
+            .await
+            .unwrap();
+        let batches = ctx.collect(plan).await.unwrap();
+        let a = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap()
+            .value(0);
+        assert_eq!(a, 3);
+        assert_eq!(*cache.hits.lock().unwrap(), 2);
+
+        // other statements are not cached
+        ctx.prepare_sql("SELECT 1; SELECT 2").await.unwrap_err();
+        ctx.prepare_sql("CREATE TABLE t AS SELECT 1 AS a")
+            .await
+            .unwrap_err();
+        assert_eq!(cache.statements().len(), 2);
+
+        // the cache is bypassed once another namespace is registered
+        ctx.register_namespace_schema("other", Arc::new(MemorySchemaProvider::new()))
+            .unwrap();
+        ctx.prepare_sql("SELECT 1 AS a").await.unwrap();
+        assert_eq!(*cache.hits.lock().unwrap(), 2);
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn executor_metrics() {
+        let exec = Executor::new_with_config(ExecutorConfig {
+            num_query_threads: 1,
+            num_reorg_threads: 2,
+            target_query_partitions: 1,
+            object_stores: HashMap::default(),
+        });
+        let registry = metric::Registry::new();
+        exec.register_metrics(&registry);
+
+        // block the only query thread, so that the next query task is queued
+        let barrier = Arc::new(std::sync::Barrier::new(2));
+        let query_exec = exec.executor(ExecutorType::Query);
+        let blocking = query_exec.spawn({
+            let barrier = Arc::clone(&barrier);
+            async move {
+                barrier.wait();
+            }
+        });
+        let queued = query_exec.spawn(async {});
+
+        tokio::time::timeout(std::time::Duration::from_secs(1), async {
+            while query_exec.queued_tasks() != 1 || query_exec.tasks() != 2 {
+                tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+            }
+        })
+        .await
+        .expect("Did not find queued task within a second");
+
+        let mut reporter = metric::RawReporter::default();
+        registry.report(&mut reporter);
+        let tasks = reporter.metric("iox_executor_tasks").unwrap();
+        for (executor, state, expected) in [
+            ("query", "queued", 1),
+            ("query", "active", 1),
+            ("reorg", "queued", 0),
+            ("reorg", "active", 0),
+        ] {
+            assert_eq!(
+                tasks.observation(&[("executor", executor), ("state", state)]),
+                Some(&metric::Observation::U64Gauge(expected)),
+                "{executor} {state}",
+            );
+        }
+
+        barrier.wait();
+        blocking.await.unwrap();
+        queued.await.unwrap();
+
+        exec.join().await;
+    }
+
+    /// [`PlanCache`] that keeps all plans and counts its hits.
+    #[derive(Debug, Default)]
+    struct RecordingPlanCache {
+        plans: std::sync::Mutex<HashMap<String, LogicalPlan>>,
+        hits: std::sync::Mutex<usize>,
+    }
+
+    impl RecordingPlanCache {
+        fn statements(&self) -> Vec<String> {
+            let mut statements: Vec<_> = self.plans.lock().unwrap().keys().cloned().collect();
+            statements.sort();
+            statements
+        }
+    }
+
+    impl PlanCache for RecordingPlanCache {
+        fn get(&self, statement: &str) -> Option<LogicalPlan> {
+            let plan = self.plans.lock().unwrap().get(statement).cloned();
+            if plan.is_some() {
+                *self.hits.lock().unwrap() += 1;
+            }
+            plan
+        }
+
+        fn put(&self, statement: &str, plan: LogicalPlan) {
+            self.plans
+                .lock()
+                .unwrap()
+                .insert(statement.to_string(), plan);
+        }
+    }
+
+    /// [`TableWriter`] that records the table name and row count of each write.
+    #[derive(Debug, Default)]
+    struct RecordingTableWriter {
+        writes: std::sync::Mutex<Vec<(String, u64, Option<String>)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TableWriter for RecordingTableWriter {
+        async fn write(
+            &self,
+            table_name: &str,
+            batches: SendableRecordBatchStream,
+            api_token: Option<&str>,
+        ) -> datafusion::error::Result<u64> {
+            let rows = batches
+                .try_fold(0, |rows, batch| async move {
+                    Ok(rows + batch.num_rows() as u64)
+                })
+                .await?;
+            self.writes.lock().unwrap().push((
+                table_name.to_string(),
+                rows,
+                api_token.map(ToString::to_string),
+            ));
+            Ok(rows)
+        }
+    }
+
     /// return a set for testing
     fn to_set(strs: &[&str]) -> StringSetRef {
         StringSetRef::new(strs.iter().map(|s| s.to_string()).collect::<StringSet>())
@@ -2,7 +2,13 @@
 //! DataFusion
 
 use super::{
-    non_null_checker::NonNullCheckerNode, seriesset::series::Either, split::StreamSplitNode,
+    gapfill::{plan_gap_fill, GapFillExec, GapFillNode, DEFAULT_MAX_GAP_FILL_BUCKETS},
+    non_null_checker::NonNullCheckerNode,
+    params::{bind_params, prepare_statement},
+    persisted_watermarks::PersistedWatermarks,
+    query_statistics::QueryStatistics,
+    seriesset::series::Either,
+    split::StreamSplitNode,
 };
 use crate::{
     exec::{
@@ -22,11 +28,17 @@ use crate::{
         seriesset::{SeriesSetPlan, SeriesSetPlans},
         stringset::StringSetPlan,
     },
+    provider::{ParquetMetadataProvider, ParquetMetadataSource, ScanLimits},
+};
+use arrow::{
+    array::UInt64Array,
+    datatypes::{DataType, Field, Schema as ArrowSchema},
+    record_batch::RecordBatch,
 };
-use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
+use data_types::{ApiTokenPermission, ParquetFileId};
 use datafusion::{
-    catalog::catalog::CatalogProvider,
+    catalog::{catalog::CatalogProvider, schema::SchemaProvider},
     execution::{
         context::{QueryPlanner, SessionState, TaskContext},
         runtime_env::RuntimeEnv,
@@ -35,17 +47,33 @@ use datafusion::{
     physical_plan::{
         coalesce_partitions::CoalescePartitionsExec,
         displayable,
+        empty::EmptyExec,
+        memory::MemoryExec,
         planner::{DefaultPhysicalPlanner, ExtensionPlanner},
         EmptyRecordBatchStream, ExecutionPlan, PhysicalPlanner, SendableRecordBatchStream,
     },
     prelude::*,
+    scalar::ScalarValue,
+    sql::{
+        parser::{DFParser, Statement as DFStatement},
+        sqlparser::ast::Statement,
+    },
 };
 use datafusion_util::config::{iox_session_config, DEFAULT_CATALOG};
 use executor::DedicatedExecutor;
 use futures::TryStreamExt;
 use observability_deps::tracing::debug;
-use query_functions::selectors::register_selector_aggregates;
-use std::{convert::TryInto, fmt, sync::Arc};
+use query_functions::{
+    register_timeseries_functions, selectors::register_selector_aggregates,
+    sketches::register_sketch_aggregates,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    fmt,
+    sync::Arc,
+    time::Duration,
+};
 use trace::{
     ctx::SpanContext,
     span::{MetaValue, Span, SpanExt, SpanRecorder},
@@ -67,8 +95,8 @@ impl QueryPlanner for IOxQueryPlanner {
         logical_plan: &LogicalPlan,
         session_state: &SessionState,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        // Teach the default physical planner how to plan SchemaPivot
-        // and StreamSplit nodes.
+        // Teach the default physical planner how to plan SchemaPivot,
+        // StreamSplit and GapFill nodes.
         let physical_planner =
             DefaultPhysicalPlanner::with_extension_planners(vec![Arc::new(IOxExtensionPlanner {})]);
         // Delegate most work of physical planning to the default physical planner
@@ -135,6 +163,36 @@ impl ExtensionPlanner for IOxExtensionPlanner {
                 Arc::clone(&physical_inputs[0]),
                 split_exprs,
             )) as Arc<dyn ExecutionPlan>)
+        } else if let Some(gap_fill) = any.downcast_ref::<GapFillNode>() {
+            assert_eq!(
+                logical_inputs.len(),
+                1,
+                "Inconsistent number of logical inputs"
+            );
+            assert_eq!(
+                physical_inputs.len(),
+                1,
+                "Inconsistent number of physical inputs"
+            );
+
+            let params = gap_fill.exec_params(
+                logical_inputs[0].schema(),
+                physical_inputs[0].schema(),
+                session_state.max_gap_fill_buckets(),
+                |e| {
+                    planner.create_physical_expr(
+                        e,
+                        logical_inputs[0].schema(),
+                        &physical_inputs[0].schema(),
+                        session_state,
+                    )
+                },
+            )?;
+
+            Some(
+                Arc::new(GapFillExec::new(Arc::clone(&physical_inputs[0]), params))
+                    as Arc<dyn ExecutionPlan>,
+            )
         } else {
             None
         };
@@ -209,6 +267,8 @@ impl IOxSessionConfig {
             .with_query_planner(Arc::new(IOxQueryPlanner {}));
 
         let state = register_selector_aggregates(state);
+        let state = register_timeseries_functions(state);
+        let state = register_sketch_aggregates(state);
 
         let inner = SessionContext::with_state(state);
 
@@ -302,13 +362,35 @@ impl IOxSessionContext {
     pub async fn prepare_sql(&self, sql: &str) -> Result<Arc<dyn ExecutionPlan>> {
         let ctx = self.child_ctx("prepare_sql");
         debug!(text=%sql, "planning SQL query");
-        let logical_plan = ctx.inner.create_logical_plan(sql)?;
+
+        // DataFusion cannot plan `INSERT`, so handle it before creating the logical plan
+        if let Some(writer) = ctx.table_writer() {
+            if let Some((table_name, input)) = ctx.plan_insert(sql)? {
+                return ctx.write_query_results(writer, &table_name, &input).await;
+            }
+        }
+
+        let logical_plan = ctx.create_logical_plan(sql)?;
         debug!(plan=%logical_plan.display_graphviz(), "logical plan");
 
         // Handle unsupported SQL
         match &logical_plan {
-            LogicalPlan::CreateMemoryTable(_) => {
-                return Err(Error::NotImplemented("CreateMemoryTable".to_string()));
+            LogicalPlan::CreateMemoryTable(cmd) => {
+                let writer = ctx
+                    .table_writer()
+                    .ok_or_else(|| Error::NotImplemented("CreateMemoryTable".to_string()))?;
+
+                if ctx.inner.table(cmd.name.as_str()).is_ok() {
+                    if cmd.if_not_exists {
+                        return Ok(Arc::new(EmptyExec::new(
+                            false,
+                            Arc::new(ArrowSchema::empty()),
+                        )));
+                    }
+                    return Err(Error::Plan(format!("Table '{}' already exists", cmd.name)));
+                }
+
+                return ctx.write_query_results(writer, &cmd.name, &cmd.input).await;
             }
             LogicalPlan::DropTable(_) => {
                 return Err(Error::NotImplemented("DropTable".to_string()));
@@ -319,17 +401,189 @@ impl IOxSessionContext {
             LogicalPlan::CreateView(_) => {
                 return Err(Error::NotImplemented("CreateView".to_string()));
             }
+            LogicalPlan::CreateExternalTable(cmd) => {
+                return ctx.create_external_table(sql, &cmd.location).await;
+            }
             _ => (),
         }
 
         ctx.create_physical_plan(&logical_plan).await
     }
 
+    /// Prepare a SQL statement that references the named parameters `$<name>` for execution,
+    /// binding the parameters to the values in `params`.
+    ///
+    /// Without parameters, this is the same as [`prepare_sql`](Self::prepare_sql).
+    pub async fn prepare_sql_with_params(
+        &self,
+        sql: &str,
+        params: &HashMap<String, ScalarValue>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if params.is_empty() {
+            return self.prepare_sql(sql).await;
+        }
+
+        let ctx = self.child_ctx("prepare_sql_with_params");
+        let (statement, values) = prepare_statement(sql, params)?;
+        debug!(text=%statement, ?values, "planning SQL query with parameters");
+
+        let input = match ctx.create_logical_plan(&statement)? {
+            LogicalPlan::Prepare(prepare) => prepare.input,
+            plan => {
+                return Err(Error::Internal(format!(
+                    "expected a prepared statement, got {plan:?}"
+                )))
+            }
+        };
+
+        // Only queries can have parameters
+        match input.as_ref() {
+            LogicalPlan::CreateMemoryTable(_)
+            | LogicalPlan::CreateExternalTable(_)
+            | LogicalPlan::CreateView(_)
+            | LogicalPlan::DropTable(_)
+            | LogicalPlan::DropView(_) => {
+                return Err(Error::NotImplemented(
+                    "Query parameters in DDL statements".to_string(),
+                ));
+            }
+            _ => (),
+        }
+
+        let logical_plan = bind_params(&input, &values)?;
+        debug!(plan=%logical_plan.display_graphviz(), "logical plan");
+
+        ctx.create_physical_plan(&logical_plan).await
+    }
+
+    /// Create the logical plan of `sql`, taking it from the [`PlanCache`] of the query if
+    /// possible.
+    ///
+    /// Only the plans of single queries (including prepared ones) are cached. The plans are not
+    /// optimized, so that they are independent of the data of the query.
+    fn create_logical_plan(&self, sql: &str) -> Result<LogicalPlan> {
+        let cache = match self.plan_cache() {
+            Some(cache) => cache,
+            None => return self.inner.create_logical_plan(sql),
+        };
+        let statement = match normalize_query(sql) {
+            Some(statement) => statement,
+            None => return self.inner.create_logical_plan(sql),
+        };
+
+        if let Some(plan) = cache.get(&statement) {
+            debug!(%statement, "using cached logical plan");
+            return Ok(plan);
+        }
+
+        let plan = self.inner.create_logical_plan(sql)?;
+        cache.put(&statement, plan.clone());
+        Ok(plan)
+    }
+
+    /// Run a `CREATE EXTERNAL TABLE` statement if its location is allow-listed (see
+    /// [`with_external_table_locations`](Self::with_external_table_locations)).
+    ///
+    /// DataFusion creates the table and registers it with the schema named in the statement.
+    /// Returns an empty plan.
+    async fn create_external_table(
+        &self,
+        sql: &str,
+        location: &str,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let allowed = self
+            .inner
+            .state
+            .read()
+            .config
+            .get_extension::<ExternalTableLocations>();
+        let allowed = match allowed {
+            Some(allowed) if !allowed.0.is_empty() => allowed,
+            _ => return Err(Error::NotImplemented("CreateExternalTable".to_string())),
+        };
+
+        if !allowed.allows(location) {
+            return Err(Error::Plan(format!(
+                "External table location '{location}' is not allowed"
+            )));
+        }
+
+        debug!(%location, "creating external table");
+        self.inner.sql(sql).await?;
+
+        Ok(Arc::new(EmptyExec::new(
+            false,
+            Arc::new(ArrowSchema::empty()),
+        )))
+    }
+
+    /// If `sql` is a single `INSERT INTO <table> SELECT ...` statement, returns the table name and
+    /// the logical plan of the query whose results should be inserted.
+    fn plan_insert(&self, sql: &str) -> Result<Option<(String, LogicalPlan)>> {
+        let mut statements = DFParser::parse_sql(sql)?;
+        if statements.len() != 1 {
+            return Ok(None);
+        }
+
+        match statements.pop_front() {
+            Some(DFStatement::Statement(statement)) => match *statement {
+                Statement::Insert {
+                    table_name,
+                    columns,
+                    source,
+                    ..
+                } => {
+                    if !columns.is_empty() {
+                        return Err(Error::NotImplemented(
+                            "INSERT with explicit column list".to_string(),
+                        ));
+                    }
+                    let input = self.inner.create_logical_plan(&source.to_string())?;
+                    Ok(Some((table_name.to_string(), input)))
+                }
+                _ => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Run `input` and stream its results to `writer` to be appended to `table_name`.
+    ///
+    /// Returns a plan producing a single row containing the number of rows written.
+    pub(crate) async fn write_query_results(
+        &self,
+        writer: Arc<dyn TableWriter>,
+        table_name: &str,
+        input: &LogicalPlan,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let physical_plan = self.create_physical_plan(input).await?;
+        let batches = self.execute_stream(physical_plan).await?;
+
+        debug!(%table_name, "writing query results");
+        let api_token = self.api_token();
+        let count = writer
+            .write(table_name, batches, api_token.as_deref())
+            .await?;
+
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "count",
+            DataType::UInt64,
+            false,
+        )]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(UInt64Array::from(vec![count]))],
+        )?;
+
+        Ok(Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None)?))
+    }
+
     /// Prepare (optimize + plan) a pre-created [`LogicalPlan`] for execution
     pub async fn create_physical_plan(&self, plan: &LogicalPlan) -> Result<Arc<dyn ExecutionPlan>> {
         let mut ctx = self.child_ctx("create_physical_plan");
         debug!(text=%plan.display_indent_schema(), "create_physical_plan: initial plan");
-        let physical_plan = ctx.inner.create_physical_plan(plan).await?;
+        let plan = plan_gap_fill(plan)?;
+        let physical_plan = ctx.inner.create_physical_plan(&plan).await?;
 
         ctx.recorder.event("physical plan");
         debug!(text=%displayable(physical_plan.as_ref()).indent(), "create_physical_plan: plan to run");
@@ -625,6 +879,265 @@ impl IOxSessionContext {
         self.recorder.span()
     }
 
+    /// Declare that the caller accepts results missing unpersisted data that is older than
+    /// `now - staleness`.
+    ///
+    /// Table providers may use this hint to avoid contacting the ingesters when the query only
+    /// touches time ranges outside of this window. `None` leaves the context unchanged.
+    pub fn with_max_unpersisted_staleness(self, staleness: Option<Duration>) -> Self {
+        if let Some(staleness) = staleness {
+            let mut state = self.inner.state.write();
+            state.config = state
+                .config
+                .clone()
+                .with_extension(Arc::new(MaxUnpersistedStaleness(staleness)));
+        }
+        self
+    }
+
+    /// Debug scan: read the tables only from the parquet files with the IDs `files`.
+    ///
+    /// Table providers then scan the raw rows of these files, without pruning or deduplication.
+    /// `None` leaves the context unchanged.
+    pub fn with_debug_parquet_files(self, files: Option<HashSet<ParquetFileId>>) -> Self {
+        if let Some(files) = files {
+            let mut state = self.inner.state.write();
+            state.config = state
+                .config
+                .clone()
+                .with_extension(Arc::new(DebugParquetFiles(files)));
+        }
+        self
+    }
+
+    /// Run the query on behalf of `identity`, the authenticated caller.
+    ///
+    /// Table providers may use the identity to restrict the rows the query can read. `None` leaves
+    /// the context unchanged.
+    pub fn with_identity(self, identity: Option<&str>) -> Self {
+        if let Some(identity) = identity {
+            let mut state = self.inner.state.write();
+            state.config = state
+                .config
+                .clone()
+                .with_extension(Arc::new(QueryIdentity(Arc::from(identity))));
+        }
+        self
+    }
+
+    /// Run the query with `api_token`, the API token of the caller.
+    ///
+    /// The token is passed on to the [`TableWriter`] of the query, so that the results of the
+    /// query are written with the permissions of the caller. `None` leaves the context unchanged.
+    pub fn with_api_token(self, api_token: Option<&str>) -> Self {
+        if let Some(api_token) = api_token {
+            let mut state = self.inner.state.write();
+            state.config = state
+                .config
+                .clone()
+                .with_extension(Arc::new(QueryApiToken(Arc::from(api_token))));
+        }
+        self
+    }
+
+    /// Reject queries that would fill the gaps of more than `max_buckets` time buckets per series
+    /// with `date_bin_gapfill`, instead of [`DEFAULT_MAX_GAP_FILL_BUCKETS`].
+    pub fn with_max_gap_fill_buckets(self, max_buckets: usize) -> Self {
+        {
+            let mut state = self.inner.state.write();
+            state.config = state
+                .config
+                .clone()
+                .with_extension(Arc::new(MaxGapFillBuckets(max_buckets)));
+        }
+        self
+    }
+
+    /// Limit the object store scans of this query to `limits`.
+    ///
+    /// `None` leaves scans unlimited.
+    pub fn with_scan_limits(self, limits: Option<ScanLimits>) -> Self {
+        if let Some(limits) = limits {
+            let mut state = self.inner.state.write();
+            state.config = state.config.clone().with_extension(Arc::new(limits));
+        }
+        self
+    }
+
+    /// Take the metadata of the parquet files scanned by this query from `source`.
+    ///
+    /// `None` leaves scans to fetch and decode the footer of every file.
+    pub fn with_parquet_metadata(self, source: Option<Arc<dyn ParquetMetadataSource>>) -> Self {
+        if let Some(source) = source {
+            let mut state = self.inner.state.write();
+            state.config = state
+                .config
+                .clone()
+                .with_extension(Arc::new(ParquetMetadataProvider(source)));
+        }
+        self
+    }
+
+    /// Record the execution statistics of this query in `statistics`.
+    ///
+    /// `None` leaves the statistics unrecorded.
+    pub fn with_statistics(self, statistics: Option<Arc<QueryStatistics>>) -> Self {
+        if let Some(statistics) = statistics {
+            let mut state = self.inner.state.write();
+            state.config = state.config.clone().with_extension(statistics);
+        }
+        self
+    }
+
+    /// Record the watermarks of the persisted data this query reads in `watermarks`.
+    ///
+    /// `None` leaves the watermarks unrecorded.
+    pub fn with_persisted_watermarks(self, watermarks: Option<Arc<PersistedWatermarks>>) -> Self {
+        if let Some(watermarks) = watermarks {
+            let mut state = self.inner.state.write();
+            state.config = state.config.clone().with_extension(watermarks);
+        }
+        self
+    }
+
+    /// Allow `CREATE EXTERNAL TABLE` statements whose location starts with one of `prefixes`.
+    ///
+    /// External tables are disabled unless at least one prefix is given. Prefixes are matched by
+    /// path segment, so e.g. `s3://bucket` does not also match `s3://bucket-other/`, and
+    /// locations with `..` segments are rejected.
+    pub fn with_external_table_locations(self, prefixes: Vec<String>) -> Self {
+        {
+            let mut state = self.inner.state.write();
+            state.config = state
+                .config
+                .clone()
+                .with_extension(Arc::new(ExternalTableLocations(prefixes)));
+        }
+        self
+    }
+
+    /// Enable `CREATE TABLE ... AS SELECT` and `INSERT INTO ... SELECT` statements, which hand
+    /// their query results to `writer`.
+    ///
+    /// Both statements are rejected unless a writer is set.
+    pub fn with_table_writer(self, writer: Arc<dyn TableWriter>) -> Self {
+        {
+            let mut state = self.inner.state.write();
+            state.config = state
+                .config
+                .clone()
+                .with_extension(Arc::new(TableWriterExtension(writer)));
+        }
+        self
+    }
+
+    /// Cache the logical plans of queries in `cache`, keyed by their normalized statement.
+    ///
+    /// The cache is bypassed once another namespace is registered (see
+    /// [`register_namespace_schema`](Self::register_namespace_schema)), since the plans then
+    /// depend on more than the tables the cache was set up for.
+    pub fn with_plan_cache(self, cache: Arc<dyn PlanCache>) -> Self {
+        {
+            let mut state = self.inner.state.write();
+            state.config = state
+                .config
+                .clone()
+                .with_extension(Arc::new(PlanCacheExtension(Some(cache))));
+        }
+        self
+    }
+
+    /// Returns the cache set via [`with_plan_cache`](Self::with_plan_cache), if any.
+    fn plan_cache(&self) -> Option<Arc<dyn PlanCache>> {
+        self.inner
+            .state
+            .read()
+            .config
+            .get_extension::<PlanCacheExtension>()
+            .and_then(|ext| ext.0.clone())
+    }
+
+    /// Returns the writer set via [`with_table_writer`](Self::with_table_writer), if any.
+    pub(crate) fn table_writer(&self) -> Option<Arc<dyn TableWriter>> {
+        self.inner
+            .state
+            .read()
+            .config
+            .get_extension::<TableWriterExtension>()
+            .map(|ext| Arc::clone(&ext.0))
+    }
+
+    /// Make the tables of another namespace addressable as `"<name>".<table>` in this context.
+    ///
+    /// Fails if the context has no default catalog, if the catalog does not support registering
+    /// schemas, or if a schema with the same name already exists.
+    pub fn register_namespace_schema(
+        &self,
+        name: &str,
+        schema: Arc<dyn SchemaProvider>,
+    ) -> Result<()> {
+        let catalog = self.inner.catalog(DEFAULT_CATALOG).ok_or_else(|| {
+            Error::Plan(format!(
+                "Cannot register namespace '{name}': no default catalog"
+            ))
+        })?;
+
+        if catalog.schema(name).is_some() {
+            return Err(Error::Plan(format!(
+                "Cannot register namespace '{name}': schema already exists"
+            )));
+        }
+
+        catalog.register_schema(name, schema)?;
+
+        // Plans may now reference the tables of the other namespace
+        let mut state = self.inner.state.write();
+        state.config = state
+            .config
+            .clone()
+            .with_extension(Arc::new(PlanCacheExtension(None)));
+
+        Ok(())
+    }
+
+    /// Returns the maximum unpersisted staleness set via
+    /// [`with_max_unpersisted_staleness`](Self::with_max_unpersisted_staleness), if any.
+    pub fn max_unpersisted_staleness(&self) -> Option<Duration> {
+        self.inner.state.read().max_unpersisted_staleness()
+    }
+
+    /// Returns the files set via [`with_debug_parquet_files`](Self::with_debug_parquet_files), if
+    /// any.
+    pub fn debug_parquet_files(&self) -> Option<HashSet<ParquetFileId>> {
+        self.inner.state.read().debug_parquet_files()
+    }
+
+    /// Returns the identity set via [`with_identity`](Self::with_identity), if any.
+    pub fn identity(&self) -> Option<Arc<str>> {
+        self.inner.state.read().identity()
+    }
+
+    /// Returns the API token set via [`with_api_token`](Self::with_api_token), if any.
+    pub fn api_token(&self) -> Option<Arc<str>> {
+        self.inner
+            .state
+            .read()
+            .config
+            .get_extension::<QueryApiToken>()
+            .map(|api_token| Arc::clone(&api_token.0))
+    }
+
+    /// Returns the statistics set via [`with_statistics`](Self::with_statistics), if any.
+    pub fn statistics(&self) -> Option<Arc<QueryStatistics>> {
+        self.inner.state.read().statistics()
+    }
+
+    /// Returns the watermarks set via
+    /// [`with_persisted_watermarks`](Self::with_persisted_watermarks), if any.
+    pub fn persisted_watermarks(&self) -> Option<Arc<PersistedWatermarks>> {
+        self.inner.state.read().persisted_watermarks()
+    }
+
     /// Number of currently active tasks.
     pub fn tasks(&self) -> usize {
         self.exec.as_ref().map(|e| e.tasks()).unwrap_or_default()
@@ -638,6 +1151,208 @@ pub trait SessionContextIOxExt {
 
     /// Get span context
     fn span_ctx(&self) -> Option<SpanContext>;
+
+    /// Get the maximum staleness of unpersisted data the query tolerates, if any.
+    fn max_unpersisted_staleness(&self) -> Option<Duration>;
+
+    /// Get the parquet files a debug scan reads from, if the query is one.
+    fn debug_parquet_files(&self) -> Option<HashSet<ParquetFileId>>;
+
+    /// Get the identity the query runs on behalf of, if any.
+    fn identity(&self) -> Option<Arc<str>>;
+
+    /// Get the maximum number of time buckets per series `date_bin_gapfill` may fill.
+    fn max_gap_fill_buckets(&self) -> usize;
+
+    /// Get the recorder of the execution statistics of the query, if any.
+    fn statistics(&self) -> Option<Arc<QueryStatistics>>;
+
+    /// Get the recorder of the watermarks of the persisted data the query reads, if any.
+    fn persisted_watermarks(&self) -> Option<Arc<PersistedWatermarks>>;
+}
+
+/// Destination for the results of `CREATE TABLE ... AS SELECT` and `INSERT INTO ... SELECT`
+/// statements.
+#[async_trait]
+pub trait TableWriter: fmt::Debug + Send + Sync + 'static {
+    /// Append the batches of `batches` to table `table_name`, creating the table if required.
+    ///
+    /// The batches are produced while the query runs, so implementations should write them as
+    /// they arrive instead of collecting all of them first.
+    ///
+    /// `table_name` is the name as written in the statement and may be qualified with a schema.
+    /// `api_token` is the API token of the caller, see [`IOxSessionContext::with_api_token`].
+    /// Returns the number of rows written.
+    async fn write(
+        &self,
+        table_name: &str,
+        batches: SendableRecordBatchStream,
+        api_token: Option<&str>,
+    ) -> Result<u64>;
+}
+
+/// Cache of the logical plans of queries, see
+/// [`IOxSessionContext::with_plan_cache`].
+///
+/// A cached plan references the table providers it was planned against. An implementation must
+/// therefore only return plans that were created for the same tables and table schemas.
+pub trait PlanCache: fmt::Debug + Send + Sync + 'static {
+    /// Get the plan of the normalized `statement`, if cached.
+    fn get(&self, statement: &str) -> Option<LogicalPlan>;
+
+    /// Cache the `plan` of the normalized `statement`.
+    fn put(&self, statement: &str, plan: LogicalPlan);
+}
+
+/// Session extension holding the [`PlanCache`] of the query, or `None` if it is bypassed.
+#[derive(Debug)]
+struct PlanCacheExtension(Option<Arc<dyn PlanCache>>);
+
+/// Normalize the SQL text of a single query (or a prepared query), so that queries that only
+/// differ in whitespace, comments or keyword case map to the same statement.
+///
+/// Returns `None` for anything else, including statements that fail to parse.
+fn normalize_query(sql: &str) -> Option<String> {
+    let mut statements = DFParser::parse_sql(sql).ok()?;
+    if statements.len() != 1 {
+        return None;
+    }
+
+    match statements.pop_front()? {
+        DFStatement::Statement(statement) => match statement.as_ref() {
+            Statement::Query(_) => Some(statement.to_string()),
+            Statement::Prepare {
+                statement: prepared,
+                ..
+            } if matches!(prepared.as_ref(), Statement::Query(_)) => Some(statement.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The permission an API token needs to run `sql` with [`IOxSessionContext::prepare_sql`].
+///
+/// `INSERT` and `CREATE TABLE` statements write to the namespace, and `CREATE EXTERNAL TABLE`
+/// reads object store locations outside of it, so they need [`ApiTokenPermission::Write`] and
+/// [`ApiTokenPermission::Admin`]. Other statements only read the namespace.
+///
+/// Statements that fail to parse need [`ApiTokenPermission::Read`], as they fail to plan too.
+pub fn sql_permission(sql: &str) -> ApiTokenPermission {
+    let statements = match DFParser::parse_sql(sql) {
+        Ok(statements) => statements,
+        Err(_) => return ApiTokenPermission::Read,
+    };
+
+    statements
+        .iter()
+        .map(|statement| match statement {
+            DFStatement::CreateExternalTable(_) => ApiTokenPermission::Admin,
+            DFStatement::Statement(statement) => match statement.as_ref() {
+                Statement::Insert { .. }
+                | Statement::CreateTable { .. }
+                | Statement::CreateView { .. }
+                | Statement::Drop { .. } => ApiTokenPermission::Write,
+                _ => ApiTokenPermission::Read,
+            },
+            _ => ApiTokenPermission::Read,
+        })
+        .max()
+        .unwrap_or(ApiTokenPermission::Read)
+}
+
+/// Session extension holding the [`TableWriter`] of the query.
+#[derive(Debug)]
+struct TableWriterExtension(Arc<dyn TableWriter>);
+
+/// Session extension holding the location prefixes allowed for external tables.
+#[derive(Debug, Clone)]
+struct ExternalTableLocations(Vec<String>);
+
+impl ExternalTableLocations {
+    /// Returns true if `location` is within one of the allowed prefixes.
+    ///
+    /// Locations are compared by their scheme, authority and path segments rather than as
+    /// strings, so that `s3://bucket/` does not match `s3://bucket-other/`. Empty and `.`
+    /// segments are ignored, and locations with `..` segments are rejected as they could
+    /// resolve to a path outside of the prefix.
+    fn allows(&self, location: &str) -> bool {
+        let location = match LocationComponents::parse(location) {
+            Some(location) => location,
+            None => return false,
+        };
+
+        self.0.iter().any(|prefix| {
+            LocationComponents::parse(prefix)
+                .map(|prefix| location.starts_with(&prefix))
+                .unwrap_or(false)
+        })
+    }
+}
+
+/// The normalized components of the location of an external table.
+#[derive(Debug, PartialEq, Eq)]
+struct LocationComponents<'a> {
+    /// Lower-cased scheme and authority, empty for plain paths.
+    origin: String,
+
+    /// Path segments.
+    segments: Vec<&'a str>,
+}
+
+impl<'a> LocationComponents<'a> {
+    /// Split `location` into its components, or return `None` if its path has `..` segments.
+    fn parse(location: &'a str) -> Option<Self> {
+        let (origin, path) = match location.split_once("://") {
+            Some((scheme, rest)) => {
+                let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+                (format!("{scheme}://{authority}").to_lowercase(), path)
+            }
+            None => (String::new(), location),
+        };
+
+        // URL parsers treat backslashes as separators and percent-encoded dots as dots
+        let mut segments = vec![];
+        for segment in path.split(|c| c == '/' || c == '\\') {
+            match segment.to_lowercase().replace("%2e", ".").as_str() {
+                "" | "." => {}
+                ".." => return None,
+                _ => segments.push(segment),
+            }
+        }
+
+        Some(Self { origin, segments })
+    }
+
+    /// Returns true if `self` is `prefix` or a location within it.
+    fn starts_with(&self, prefix: &Self) -> bool {
+        self.origin == prefix.origin && self.segments.starts_with(&prefix.segments)
+    }
+}
+
+/// Session extension holding the query's tolerance for missing unpersisted data.
+#[derive(Debug, Clone, Copy)]
+struct MaxUnpersistedStaleness(Duration);
+
+/// Session extension holding the parquet files of a debug scan.
+#[derive(Debug, Clone)]
+struct DebugParquetFiles(HashSet<ParquetFileId>);
+
+/// Session extension holding the identity the query runs on behalf of.
+#[derive(Debug, Clone)]
+struct QueryIdentity(Arc<str>);
+
+/// Session extension holding the maximum number of buckets `date_bin_gapfill` may fill.
+#[derive(Debug, Clone, Copy)]
+struct MaxGapFillBuckets(usize);
+
+/// Session extension holding the API token of the caller.
+struct QueryApiToken(Arc<str>);
+
+impl fmt::Debug for QueryApiToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("QueryApiToken").field(&"<redacted>").finish()
+    }
 }
 
 impl SessionContextIOxExt for SessionState {
@@ -652,4 +1367,36 @@ impl SessionContextIOxExt for SessionState {
             .get_extension::<Option<Span>>()
             .and_then(|span| span.as_ref().as_ref().map(|span| span.ctx.clone()))
     }
+
+    fn max_unpersisted_staleness(&self) -> Option<Duration> {
+        self.config
+            .get_extension::<MaxUnpersistedStaleness>()
+            .map(|staleness| staleness.0)
+    }
+
+    fn debug_parquet_files(&self) -> Option<HashSet<ParquetFileId>> {
+        self.config
+            .get_extension::<DebugParquetFiles>()
+            .map(|files| files.0.clone())
+    }
+
+    fn identity(&self) -> Option<Arc<str>> {
+        self.config
+            .get_extension::<QueryIdentity>()
+            .map(|identity| Arc::clone(&identity.0))
+    }
+
+    fn max_gap_fill_buckets(&self) -> usize {
+        self.config
+            .get_extension::<MaxGapFillBuckets>()
+            .map_or(DEFAULT_MAX_GAP_FILL_BUCKETS, |max_buckets| max_buckets.0)
+    }
+
+    fn statistics(&self) -> Option<Arc<QueryStatistics>> {
+        self.config.get_extension::<QueryStatistics>()
+    }
+
+    fn persisted_watermarks(&self) -> Option<Arc<PersistedWatermarks>> {
+        self.config.get_extension::<PersistedWatermarks>()
+    }
 }
@@ -0,0 +1,1095 @@
+//! This module contains code for the "GapFill" DataFusion extension
+//! plan node, which fills the gaps of aggregations grouping by
+//! `date_bin_gapfill`.
+//!
+//! A query such as
+//!
+//! ```sql
+//! SELECT host, date_bin_gapfill(INTERVAL '1 minute', time) AS minute, avg(usage)
+//! FROM cpu
+//! WHERE time >= '2022-01-01T00:00:00Z' AND time < '2022-01-01T00:05:00Z'
+//! GROUP BY host, minute
+//! ```
+//!
+//! only produces rows for the minutes a host has data for. [`plan_gap_fill`]
+//! rewrites the aggregation into
+//!
+//! ```text
+//! GapFill: series=[host], time=minute, ...
+//!   Sort: host, minute
+//!     Aggregate: groupBy=[[host, date_bin_gapfill(...)]], aggr=[[AVG(usage)]]
+//! ```
+//!
+//! and the GapFill node inserts a row with NULL aggregates for every bucket
+//! of the time range of the `WHERE` clause that a series has no row for,
+//! regardless of how the rows of the series are split into batches. Each
+//! series is produced in its own record batch, so `locf` and `interpolate`
+//! in the projection above never mix the values of different series. These
+//! functions depend on the order of their input, so they are rejected
+//! anywhere else than around an aggregate in such a projection.
+//!
+//! Queries filling more buckets per series than [`DEFAULT_MAX_GAP_FILL_BUCKETS`],
+//! or the maximum configured for the session, are rejected when they are planned.
+
+use std::{
+    any::Any,
+    collections::HashSet,
+    fmt::{self, Debug},
+    sync::Arc,
+};
+
+use arrow::{
+    array::{as_primitive_array, new_null_array, ArrayRef, TimestampNanosecondArray, UInt32Array},
+    compute::{concat_batches, take},
+    datatypes::{SchemaRef, TimestampNanosecondType},
+    error::Result as ArrowResult,
+    record_batch::RecordBatch,
+};
+use datafusion::{
+    common::{DFSchema, DFSchemaRef},
+    error::{DataFusionError as Error, Result},
+    execution::context::TaskContext,
+    logical_expr::{
+        cast,
+        expr_visitor::{ExprVisitable, ExpressionVisitor, Recursion},
+        utils::{expr_to_columns, from_plan},
+        BinaryExpr, Expr, Extension, LogicalPlan, LogicalPlanBuilder, Operator,
+        UserDefinedLogicalNode,
+    },
+    optimizer::utils::split_conjunction,
+    physical_plan::{
+        expressions::PhysicalSortExpr,
+        metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet},
+        ColumnarValue, DisplayFormatType, Distribution, ExecutionPlan, Partitioning, PhysicalExpr,
+        SendableRecordBatchStream, Statistics,
+    },
+    prelude::lit_timestamp_nano,
+    scalar::ScalarValue,
+};
+use datafusion_util::{watch::WatchedTask, AdapterStream};
+use observability_deps::tracing::debug;
+use query_functions::{DATE_BIN_GAPFILL_UDF_NAME, INTERPOLATE_UDF_NAME, LOCF_UDF_NAME};
+use schema::TIME_DATA_TYPE;
+use tokio::sync::mpsc;
+use tokio_stream::StreamExt;
+
+/// Nanoseconds in a day, for strides given in days.
+const NANOS_PER_DAY: i64 = 86_400_000_000_000;
+
+/// The maximum number of buckets a GapFill node may fill per series, unless configured otherwise
+/// with [`crate::exec::IOxSessionContext::with_max_gap_fill_buckets`].
+pub const DEFAULT_MAX_GAP_FILL_BUCKETS: usize = 1_000_000;
+
+/// Rewrite the aggregations of `plan` that group by `date_bin_gapfill` to fill the gaps of their
+/// output, see the [module documentation](self).
+///
+/// Returns an error if such an aggregation groups by more than one `date_bin_gapfill` or if its
+/// input does not set both a lower and an upper bound on the binned time, and if `locf` or
+/// `interpolate` are used anywhere else than around an aggregate of the projection of a gap-filled
+/// aggregation.
+pub(crate) fn plan_gap_fill(plan: &LogicalPlan) -> Result<LogicalPlan> {
+    let plan = rewrite_gap_fill(plan)?;
+    check_fill_functions(&plan)?;
+    Ok(plan)
+}
+
+/// Wrap the aggregations of `plan` that group by `date_bin_gapfill` into [`GapFillNode`]s.
+fn rewrite_gap_fill(plan: &LogicalPlan) -> Result<LogicalPlan> {
+    if !uses_gap_fill(plan) {
+        return Ok(plan.clone());
+    }
+
+    match plan {
+        // the plan to explain is planned separately by DataFusion
+        LogicalPlan::Explain(_) | LogicalPlan::Analyze(_) => return Ok(plan.clone()),
+        _ => (),
+    }
+
+    let inputs = plan
+        .inputs()
+        .into_iter()
+        .map(rewrite_gap_fill)
+        .collect::<Result<Vec<_>>>()?;
+    let plan = from_plan(plan, &plan.expressions(), &inputs)?;
+
+    gap_fill_aggregate(plan)
+}
+
+/// Check that `locf` and `interpolate` are only applied directly to the aggregates of gap-filled
+/// aggregations, in the projection right above their [`GapFillNode`].
+///
+/// These functions fill the NULLs of a column from the preceding and following rows, so they are
+/// only correct on the output of a GapFill node, which has each series in its own batch, sorted
+/// by time.
+fn check_fill_functions(plan: &LogicalPlan) -> Result<()> {
+    match plan {
+        // the plan to explain is planned separately by DataFusion
+        LogicalPlan::Explain(_) | LogicalPlan::Analyze(_) => return Ok(()),
+        LogicalPlan::Projection(projection) => {
+            let gap_fill = match projection.input.as_ref() {
+                LogicalPlan::Extension(Extension { node }) => {
+                    node.as_any().downcast_ref::<GapFillNode>()
+                }
+                _ => None,
+            };
+            for expr in &projection.expr {
+                let expr = match expr {
+                    Expr::Alias(expr, _) => expr.as_ref(),
+                    expr => expr,
+                };
+                match (fill_function_args(expr), gap_fill) {
+                    (Some((_, [arg])), Some(gap_fill)) if gap_fill.is_aggregate(arg) => {}
+                    (Some((name, _)), Some(_)) => {
+                        return Err(Error::Plan(format!(
+                            "{name} must be applied to an aggregate of the \
+                             {DATE_BIN_GAPFILL_UDF_NAME} aggregation"
+                        )))
+                    }
+                    _ => check_no_fill_functions(expr)?,
+                }
+            }
+        }
+        plan => {
+            for expr in plan.expressions() {
+                check_no_fill_functions(&expr)?;
+            }
+        }
+    }
+
+    plan.inputs().into_iter().try_for_each(check_fill_functions)
+}
+
+/// Returns an error if `expr` calls `locf` or `interpolate`.
+fn check_no_fill_functions(expr: &Expr) -> Result<()> {
+    let finder = expr.accept(FillFunctionFinder::default())?;
+    match finder.0 {
+        Some(name) => Err(Error::Plan(format!(
+            "{name} may only be applied to an aggregate in the SELECT list of an aggregation \
+             grouping by {DATE_BIN_GAPFILL_UDF_NAME}"
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// The name and arguments of `expr` if it is a call of `locf` or `interpolate`.
+fn fill_function_args(expr: &Expr) -> Option<(&str, &[Expr])> {
+    match expr {
+        Expr::ScalarUDF { fun, args }
+            if fun.name == LOCF_UDF_NAME || fun.name == INTERPOLATE_UDF_NAME =>
+        {
+            Some((&fun.name, args))
+        }
+        _ => None,
+    }
+}
+
+/// Finds the name of the first `locf` or `interpolate` called by an expression.
+#[derive(Debug, Default)]
+struct FillFunctionFinder(Option<String>);
+
+impl ExpressionVisitor for FillFunctionFinder {
+    fn pre_visit(mut self, expr: &Expr) -> Result<Recursion<Self>> {
+        match fill_function_args(expr) {
+            Some((name, _)) => {
+                self.0 = Some(name.to_string());
+                Ok(Recursion::Stop(self))
+            }
+            None => Ok(Recursion::Continue(self)),
+        }
+    }
+}
+
+/// Returns true if an aggregation of `plan` groups by `date_bin_gapfill`.
+fn uses_gap_fill(plan: &LogicalPlan) -> bool {
+    match plan {
+        LogicalPlan::Aggregate(aggregate)
+            if aggregate
+                .group_expr
+                .iter()
+                .any(|e| date_bin_gapfill_args(e).is_some()) =>
+        {
+            true
+        }
+        _ => plan.inputs().into_iter().any(uses_gap_fill),
+    }
+}
+
+/// The arguments of `expr` if it is a call of `date_bin_gapfill`.
+fn date_bin_gapfill_args(expr: &Expr) -> Option<&[Expr]> {
+    match expr {
+        Expr::ScalarUDF { fun, args } if fun.name == DATE_BIN_GAPFILL_UDF_NAME => Some(args),
+        Expr::Alias(expr, _) => date_bin_gapfill_args(expr),
+        _ => None,
+    }
+}
+
+/// Wrap `plan` into a [`GapFillNode`] if it is an aggregation grouping by `date_bin_gapfill`.
+fn gap_fill_aggregate(plan: LogicalPlan) -> Result<LogicalPlan> {
+    let aggregate = match &plan {
+        LogicalPlan::Aggregate(aggregate) => aggregate,
+        _ => return Ok(plan),
+    };
+
+    let mut gap_fill = None;
+    for (idx, expr) in aggregate.group_expr.iter().enumerate() {
+        if let Some(args) = date_bin_gapfill_args(expr) {
+            if gap_fill.replace((idx, args)).is_some() {
+                return Err(Error::Plan(format!(
+                    "{DATE_BIN_GAPFILL_UDF_NAME} may only be used once in a GROUP BY"
+                )));
+            }
+        }
+    }
+    let (time_idx, args) = match gap_fill {
+        Some(gap_fill) => gap_fill,
+        None => return Ok(plan),
+    };
+    if args.len() < 2 {
+        return Err(Error::Plan(format!(
+            "{DATE_BIN_GAPFILL_UDF_NAME} expects 2 or 3 arguments, got {}",
+            args.len()
+        )));
+    }
+
+    let stride = args[0].clone();
+    let origin = match args.get(2) {
+        Some(origin) => cast(origin.clone(), TIME_DATA_TYPE()),
+        None => lit_timestamp_nano(0),
+    };
+    let range = TimeRange::new(aggregate.input.as_ref(), &args[1]);
+    if range.lower.is_empty() || range.upper.is_empty() {
+        return Err(Error::Plan(format!(
+            "{DATE_BIN_GAPFILL_UDF_NAME} requires the WHERE clause to set a lower and an upper \
+            bound on the time"
+        )));
+    }
+
+    // The output of an aggregation are its group columns followed by its aggregates
+    let num_group = aggregate.group_expr.len();
+    let columns = plan
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| Expr::Column(field.qualified_column()))
+        .collect::<Vec<_>>();
+    let time_expr = columns[time_idx].clone();
+    let series_exprs = columns[..num_group]
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx != time_idx)
+        .map(|(_, expr)| expr.clone())
+        .collect::<Vec<_>>();
+    let aggr_exprs = columns[num_group..].to_vec();
+
+    let sort_exprs = series_exprs
+        .iter()
+        .chain(std::iter::once(&time_expr))
+        .map(|expr| Expr::Sort {
+            expr: Box::new(expr.clone()),
+            asc: true,
+            nulls_first: false,
+        })
+        .collect::<Vec<_>>();
+    let input = LogicalPlanBuilder::from(plan).sort(sort_exprs)?.build()?;
+
+    let node = Arc::new(GapFillNode {
+        input,
+        series_exprs,
+        time_expr,
+        aggr_exprs,
+        stride,
+        origin,
+        range,
+    });
+    Ok(LogicalPlan::Extension(Extension { node }))
+}
+
+/// The bounds on the binned time set by the filters of the input of an aggregation.
+///
+/// The bounds are kept as expressions, cast to timestamps, so that DataFusion simplifies them
+/// (e.g. `now() - INTERVAL '1 hour'`) before they are evaluated by the physical planner.
+#[derive(Debug, Clone, Default)]
+struct TimeRange {
+    /// Lower bounds, the greatest of which applies.
+    lower: Vec<Expr>,
+    /// Upper bounds, the least of which applies.
+    upper: Vec<Expr>,
+    /// Whether the corresponding upper bound is inclusive.
+    upper_inclusive: Vec<bool>,
+}
+
+impl TimeRange {
+    /// Collect the bounds on `time` set by the filters of `plan` and its single inputs.
+    fn new(plan: &LogicalPlan, time: &Expr) -> Self {
+        let mut range = Self::default();
+        let mut plan = plan;
+        loop {
+            match plan {
+                LogicalPlan::Filter(_) | LogicalPlan::TableScan(_) => {
+                    for expr in plan.expressions() {
+                        for expr in split_conjunction(&expr) {
+                            range.add(expr, time);
+                        }
+                    }
+                }
+                LogicalPlan::Projection(_) => {}
+                _ => break,
+            }
+
+            match plan.inputs().as_slice() {
+                [input] => plan = input,
+                _ => break,
+            }
+        }
+        range
+    }
+
+    /// Add the bounds set by the predicate `expr`, if any.
+    fn add(&mut self, expr: &Expr, time: &Expr) {
+        match expr {
+            Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+                // normalise to `time <op> bound`
+                let (op, bound) = if is_time(left, time) && is_constant(right) {
+                    (*op, right)
+                } else if is_time(right, time) && is_constant(left) {
+                    match swap(*op) {
+                        Some(op) => (op, left),
+                        None => return,
+                    }
+                } else {
+                    return;
+                };
+
+                match op {
+                    Operator::Gt | Operator::GtEq => self.add_lower(bound),
+                    Operator::Lt => self.add_upper(bound, false),
+                    Operator::LtEq => self.add_upper(bound, true),
+                    Operator::Eq => {
+                        self.add_lower(bound);
+                        self.add_upper(bound, true);
+                    }
+                    _ => {}
+                }
+            }
+            Expr::Between {
+                expr,
+                negated: false,
+                low,
+                high,
+            } if is_time(expr, time) => {
+                if is_constant(low) {
+                    self.add_lower(low);
+                }
+                if is_constant(high) {
+                    self.add_upper(high, true);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn add_lower(&mut self, bound: &Expr) {
+        self.lower.push(cast(bound.clone(), TIME_DATA_TYPE()));
+    }
+
+    fn add_upper(&mut self, bound: &Expr, inclusive: bool) {
+        self.upper.push(cast(bound.clone(), TIME_DATA_TYPE()));
+        self.upper_inclusive.push(inclusive);
+    }
+}
+
+/// Returns the operator `op'` such that `a op b` is equivalent to `b op' a`, if it is a
+/// comparison.
+fn swap(op: Operator) -> Option<Operator> {
+    match op {
+        Operator::Gt => Some(Operator::Lt),
+        Operator::GtEq => Some(Operator::LtEq),
+        Operator::Lt => Some(Operator::Gt),
+        Operator::LtEq => Some(Operator::GtEq),
+        Operator::Eq => Some(Operator::Eq),
+        _ => None,
+    }
+}
+
+/// Returns true if `expr` is the binned time expression `time`.
+fn is_time(expr: &Expr, time: &Expr) -> bool {
+    match (expr, time) {
+        (Expr::Column(c), Expr::Column(t)) => {
+            c.name == t.name
+                && (c.relation.is_none() || t.relation.is_none() || c.relation == t.relation)
+        }
+        _ => expr == time,
+    }
+}
+
+/// Returns true if `expr` does not reference any column.
+fn is_constant(expr: &Expr) -> bool {
+    let mut columns = HashSet::new();
+    expr_to_columns(expr, &mut columns).is_ok() && columns.is_empty()
+}
+
+/// Implements the GapFill operation as described in this module's documentation
+pub struct GapFillNode {
+    /// The aggregation, sorted by the series and time columns
+    input: LogicalPlan,
+    /// The group columns of the aggregation other than the time column
+    series_exprs: Vec<Expr>,
+    /// The `date_bin_gapfill` column of the aggregation
+    time_expr: Expr,
+    /// The aggregate columns of the aggregation
+    aggr_exprs: Vec<Expr>,
+    /// The stride argument of `date_bin_gapfill`
+    stride: Expr,
+    /// The origin argument of `date_bin_gapfill`
+    origin: Expr,
+    /// The time range to fill
+    range: TimeRange,
+}
+
+impl GapFillNode {
+    /// Returns true if `expr` is one of the aggregate columns of the node.
+    fn is_aggregate(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Column(column) => self
+                .aggr_exprs
+                .iter()
+                .any(|aggr| matches!(aggr, Expr::Column(aggr) if aggr.name == column.name)),
+            _ => false,
+        }
+    }
+
+    /// Evaluate the arguments of `date_bin_gapfill` and the time range to fill, using
+    /// `create_physical_expr` to plan the expressions against `input_schema`, the schema of the
+    /// input of the node.
+    ///
+    /// Returns a plan error if the time range has more than `max_buckets` buckets.
+    pub(crate) fn exec_params<F>(
+        &self,
+        input_schema: &DFSchema,
+        physical_input_schema: SchemaRef,
+        max_buckets: usize,
+        create_physical_expr: F,
+    ) -> Result<GapFillParams>
+    where
+        F: Fn(&Expr) -> Result<Arc<dyn PhysicalExpr>>,
+    {
+        let empty = RecordBatch::new_empty(physical_input_schema);
+        let evaluate = |expr: &Expr| -> Result<ScalarValue> {
+            match create_physical_expr(expr)?.evaluate(&empty)? {
+                ColumnarValue::Scalar(value) => Ok(value),
+                ColumnarValue::Array(_) => Err(Error::Plan(format!(
+                    "{DATE_BIN_GAPFILL_UDF_NAME}: {expr} is not a constant"
+                ))),
+            }
+        };
+        let evaluate_timestamp = |expr: &Expr| -> Result<i64> {
+            match evaluate(expr)? {
+                ScalarValue::TimestampNanosecond(Some(v), _) => Ok(v),
+                value => Err(Error::Plan(format!(
+                    "{DATE_BIN_GAPFILL_UDF_NAME}: expected a timestamp, got {value}"
+                ))),
+            }
+        };
+        let column_index = |expr: &Expr| -> Result<usize> {
+            match expr {
+                Expr::Column(column) => Ok(input_schema.index_of_column(column)?),
+                expr => Err(Error::Internal(format!(
+                    "GapFill: expected a column, got {expr}"
+                ))),
+            }
+        };
+
+        let stride = stride_ns(&evaluate(&self.stride)?)?;
+        let origin = evaluate_timestamp(&self.origin)?;
+
+        let mut lower = None;
+        for expr in &self.range.lower {
+            let v = evaluate_timestamp(expr)?;
+            lower = Some(lower.map_or(v, |l: i64| l.max(v)));
+        }
+        let mut upper = None;
+        for (expr, inclusive) in self.range.upper.iter().zip(&self.range.upper_inclusive) {
+            let mut v = evaluate_timestamp(expr)?;
+            if !inclusive {
+                v = v.saturating_sub(1);
+            }
+            upper = Some(upper.map_or(v, |u: i64| u.min(v)));
+        }
+        let (lower, upper) = match (lower, upper) {
+            (Some(lower), Some(upper)) => (lower, upper),
+            _ => return Err(Error::Internal("GapFill: missing time range".to_string())),
+        };
+
+        let (first, last) = (bin(lower, stride, origin), bin(upper, stride, origin));
+        let num_buckets = bucket_count(first, last, stride);
+        if num_buckets > max_buckets as u128 {
+            return Err(Error::Plan(format!(
+                "{DATE_BIN_GAPFILL_UDF_NAME}: the time range has {num_buckets} buckets, more than \
+                 the maximum of {max_buckets}; narrow the time range or widen the stride"
+            )));
+        }
+
+        Ok(GapFillParams {
+            series: self
+                .series_exprs
+                .iter()
+                .map(column_index)
+                .collect::<Result<_>>()?,
+            time: column_index(&self.time_expr)?,
+            stride,
+            first,
+            last,
+        })
+    }
+}
+
+/// The stride of `date_bin_gapfill` in nanoseconds.
+fn stride_ns(value: &ScalarValue) -> Result<i64> {
+    let ns = match value {
+        ScalarValue::IntervalDayTime(Some(v)) => {
+            let days = (*v >> 32) as i32 as i64;
+            let millis = *v as i32 as i64;
+            days * NANOS_PER_DAY + millis * 1_000_000
+        }
+        ScalarValue::IntervalMonthDayNano(Some(v)) => {
+            let months = (*v >> 96) as i32;
+            let days = (*v >> 64) as i32 as i64;
+            let nanos = *v as i64;
+            if months != 0 {
+                return Err(Error::NotImplemented(format!(
+                    "{DATE_BIN_GAPFILL_UDF_NAME} with a stride of months"
+                )));
+            }
+            days * NANOS_PER_DAY + nanos
+        }
+        value => {
+            return Err(Error::Plan(format!(
+                "{DATE_BIN_GAPFILL_UDF_NAME}: expected an interval, got {value}"
+            )))
+        }
+    };
+
+    if ns <= 0 {
+        return Err(Error::Plan(format!(
+            "{DATE_BIN_GAPFILL_UDF_NAME}: the stride must be positive"
+        )));
+    }
+    Ok(ns)
+}
+
+/// The start of the bucket of `date_bin(stride, t, origin)` containing `t`.
+fn bin(t: i64, stride: i64, origin: i64) -> i64 {
+    origin + (t - origin).div_euclid(stride) * stride
+}
+
+/// The number of buckets from the bucket starting at `first` to the one starting at `last`.
+fn bucket_count(first: i64, last: i64, stride: i64) -> u128 {
+    if last < first {
+        return 0;
+    }
+    (last as i128 - first as i128) as u128 / stride as u128 + 1
+}
+
+impl Debug for GapFillNode {
+    /// Use explain format for the Debug format.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_for_explain(f)
+    }
+}
+
+impl UserDefinedLogicalNode for GapFillNode {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn inputs(&self) -> Vec<&LogicalPlan> {
+        vec![&self.input]
+    }
+
+    /// The output has the schema of the aggregation
+    fn schema(&self) -> &DFSchemaRef {
+        self.input.schema()
+    }
+
+    /// The columns of the aggregation (so that datafusion does not
+    /// optimize them away) followed by the constants of the node
+    fn expressions(&self) -> Vec<Expr> {
+        self.series_exprs
+            .iter()
+            .chain(std::iter::once(&self.time_expr))
+            .chain(&self.aggr_exprs)
+            .chain([&self.stride, &self.origin])
+            .chain(&self.range.lower)
+            .chain(&self.range.upper)
+            .cloned()
+            .collect()
+    }
+
+    /// For example: `GapFill: series=[cpu.host], time=minute, stride=...`
+    fn fmt_for_explain(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "GapFill: series={:?}, time={}, stride={}, origin={}, lower={:?}, upper={:?}",
+            self.series_exprs,
+            self.time_expr,
+            self.stride,
+            self.origin,
+            self.range.lower,
+            self.range.upper
+        )
+    }
+
+    fn from_template(
+        &self,
+        exprs: &[Expr],
+        inputs: &[LogicalPlan],
+    ) -> Arc<dyn UserDefinedLogicalNode> {
+        assert_eq!(inputs.len(), 1, "GapFill: input sizes inconistent");
+        assert_eq!(
+            exprs.len(),
+            self.expressions().len(),
+            "GapFill: expression sizes inconistent"
+        );
+
+        let mut exprs = exprs.iter().cloned();
+        let mut next = |n: usize| exprs.by_ref().take(n).collect::<Vec<_>>();
+        let series_exprs = next(self.series_exprs.len());
+        let time_expr = next(1).remove(0);
+        let aggr_exprs = next(self.aggr_exprs.len());
+        let stride = next(1).remove(0);
+        let origin = next(1).remove(0);
+        let range = TimeRange {
+            lower: next(self.range.lower.len()),
+            upper: next(self.range.upper.len()),
+            upper_inclusive: self.range.upper_inclusive.clone(),
+        };
+
+        Arc::new(Self {
+            input: inputs[0].clone(),
+            series_exprs,
+            time_expr,
+            aggr_exprs,
+            stride,
+            origin,
+            range,
+        })
+    }
+}
+
+// ------ The implementation of GapFill code follows -----
+
+/// The parameters of [`GapFillExec`], evaluated by the physical planner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GapFillParams {
+    /// Indexes of the series columns in the input
+    pub series: Vec<usize>,
+    /// Index of the time column in the input
+    pub time: usize,
+    /// Width of a bucket, in ns
+    pub stride: i64,
+    /// Start of the first bucket to fill
+    pub first: i64,
+    /// Start of the last bucket to fill
+    pub last: i64,
+}
+
+/// Physical operator that implements the GapFill operation. Its input
+/// must be sorted by the series columns, then time.
+pub struct GapFillExec {
+    input: Arc<dyn ExecutionPlan>,
+    params: GapFillParams,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl GapFillExec {
+    pub fn new(input: Arc<dyn ExecutionPlan>, params: GapFillParams) -> Self {
+        Self {
+            input,
+            params,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+impl Debug for GapFillExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GapFillExec")
+    }
+}
+
+impl ExecutionPlan for GapFillExec {
+    fn as_any(&self) -> &(dyn std::any::Any + 'static) {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    /// All rows of a series must be seen by the same partition
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::SinglePartition
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(Self::new(
+                Arc::clone(&children[0]),
+                self.params.clone(),
+            ))),
+            _ => Err(Error::Internal(
+                "GapFillExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    /// Execute one partition and return an iterator over RecordBatch
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        debug!(partition, "Start GapFillExec::execute");
+        if self.output_partitioning().partition_count() <= partition {
+            return Err(Error::Internal(format!(
+                "GapFillExec invalid partition {}",
+                partition
+            )));
+        }
+
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        let input_stream = self.input.execute(partition, context)?;
+
+        let (tx, rx) = mpsc::channel(1);
+
+        let fut = fill_gaps(
+            input_stream,
+            self.schema(),
+            self.params.clone(),
+            baseline_metrics,
+            tx.clone(),
+        );
+
+        // A second task watches the output of the worker task and
+        // reports errors
+        let handle = WatchedTask::new(fut, vec![tx], "gap_fill");
+
+        debug!(partition, "End GapFillExec::execute");
+        Ok(AdapterStream::adapt(self.schema(), rx, handle))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                let GapFillParams {
+                    series,
+                    time,
+                    stride,
+                    first,
+                    last,
+                } = &self.params;
+                write!(
+                    f,
+                    "GapFillExec: series={series:?}, time={time}, stride={stride}, \
+                    first={first}, last={last}"
+                )
+            }
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        // don't know anything about the statistics
+        Statistics::default()
+    }
+}
+
+/// Fill the gaps of each series of `input_stream`, sending the filled rows of each series as one
+/// batch to `tx`.
+async fn fill_gaps(
+    mut input_stream: SendableRecordBatchStream,
+    schema: SchemaRef,
+    params: GapFillParams,
+    baseline_metrics: BaselineMetrics,
+    tx: mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> ArrowResult<()> {
+    // the series key and the rows of the series being read
+    let mut current: Option<Vec<ScalarValue>> = None;
+    let mut pending: Vec<RecordBatch> = vec![];
+
+    while let Some(batch) = input_stream.next().await.transpose()? {
+        let timer = baseline_metrics.elapsed_compute().timer();
+        let mut filled = vec![];
+
+        let mut start = 0;
+        for row in 0..batch.num_rows() {
+            let key = params
+                .series
+                .iter()
+                .map(|idx| ScalarValue::try_from_array(batch.column(*idx), row))
+                .collect::<Result<Vec<_>>>()?;
+            if current.as_ref() == Some(&key) {
+                continue;
+            }
+
+            // a new series starts
+            if row > start {
+                pending.push(batch.slice(start, row - start));
+            }
+            if current.is_some() {
+                filled.push(fill_series(&schema, &params, &pending)?);
+                pending.clear();
+            }
+            current = Some(key);
+            start = row;
+        }
+        if batch.num_rows() > start {
+            pending.push(batch.slice(start, batch.num_rows() - start));
+        }
+
+        std::mem::drop(timer);
+        for batch in filled {
+            // ignore errors on sending (means receiver hung up)
+            if tx.send(Ok(batch)).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    let filled = if current.is_some() {
+        fill_series(&schema, &params, &pending)?
+    } else if params.series.is_empty() {
+        // without series columns, the single series exists even without rows
+        fill_empty(&schema, &params)?
+    } else {
+        return Ok(());
+    };
+
+    // ignore errors on sending (means receiver hung up)
+    tx.send(Ok(filled)).await.ok();
+    Ok(())
+}
+
+/// The start of each bucket to fill.
+fn buckets(params: &GapFillParams) -> impl Iterator<Item = i64> + '_ {
+    std::iter::successors(Some(params.first), |b| b.checked_add(params.stride))
+        .take_while(|b| *b <= params.last)
+}
+
+/// Merge the rows of a series, sorted by time, with a row for each bucket it has no row for.
+fn fill_series(
+    schema: &SchemaRef,
+    params: &GapFillParams,
+    batches: &[RecordBatch],
+) -> ArrowResult<RecordBatch> {
+    let batch = concat_batches(schema, batches)?;
+    let times = as_primitive_array::<TimestampNanosecondType>(batch.column(params.time));
+
+    // for each output row, the input row or `None` for a filled row, and its time
+    let mut indices: Vec<Option<u32>> = Vec::with_capacity(batch.num_rows());
+    let mut out_times: Vec<Option<i64>> = Vec::with_capacity(batch.num_rows());
+    let mut buckets = buckets(params).peekable();
+    for (row, time) in times.iter().enumerate() {
+        // NULL times are sorted last
+        while let Some(bucket) = buckets.next_if(|b| time.map_or(true, |t| *b < t)) {
+            indices.push(None);
+            out_times.push(Some(bucket));
+        }
+        buckets.next_if(|b| Some(*b) == time);
+        indices.push(Some(row as u32));
+        out_times.push(time);
+    }
+    for bucket in buckets {
+        indices.push(None);
+        out_times.push(Some(bucket));
+    }
+
+    // all rows of the series have the same series values
+    let first_row = UInt32Array::from(vec![0; indices.len()]);
+    let indices = UInt32Array::from(indices);
+    let columns = batch
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(idx, column)| {
+            if idx == params.time {
+                Ok(Arc::new(TimestampNanosecondArray::from(out_times.clone())) as ArrayRef)
+            } else if params.series.contains(&idx) {
+                take(column.as_ref(), &first_row, None)
+            } else {
+                take(column.as_ref(), &indices, None)
+            }
+        })
+        .collect::<ArrowResult<Vec<_>>>()?;
+
+    RecordBatch::try_new(Arc::clone(schema), columns)
+}
+
+/// The filled rows of a series without any row.
+fn fill_empty(schema: &SchemaRef, params: &GapFillParams) -> ArrowResult<RecordBatch> {
+    let times = buckets(params).map(Some).collect::<Vec<_>>();
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| {
+            if idx == params.time {
+                Arc::new(TimestampNanosecondArray::from(times.clone())) as ArrayRef
+            } else {
+                new_null_array(field.data_type(), times.len())
+            }
+        })
+        .collect();
+
+    RecordBatch::try_new(Arc::clone(schema), columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::{
+        array::{Float64Array, StringArray},
+        datatypes::{DataType, Field, Schema},
+    };
+    use arrow_util::assert_batches_eq;
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion_util::test_collect;
+
+    const MINUTE: i64 = 60_000_000_000;
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("host", DataType::Utf8, true),
+            Field::new("minute", TIME_DATA_TYPE(), true),
+            Field::new("usage", DataType::Float64, true),
+        ]))
+    }
+
+    fn batch(rows: &[(&str, i64, f64)]) -> RecordBatch {
+        RecordBatch::try_new(
+            schema(),
+            vec![
+                Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r.0))),
+                Arc::new(TimestampNanosecondArray::from_iter_values(
+                    rows.iter().map(|r| r.1 * MINUTE),
+                )),
+                Arc::new(Float64Array::from_iter_values(rows.iter().map(|r| r.2))),
+            ],
+        )
+        .unwrap()
+    }
+
+    async fn fill(series: Vec<usize>, batches: Vec<RecordBatch>) -> Vec<RecordBatch> {
+        let params = GapFillParams {
+            series,
+            time: 1,
+            stride: MINUTE,
+            first: 0,
+            last: 4 * MINUTE,
+        };
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema(), None).unwrap());
+        test_collect(Arc::new(GapFillExec::new(input, params))).await
+    }
+
+    #[tokio::test]
+    async fn test_fill_across_batches() {
+        // the rows of host a are split across two batches
+        let batches = vec![
+            batch(&[("a", 1, 1.0)]),
+            batch(&[("a", 3, 3.0), ("b", 0, 10.0)]),
+        ];
+
+        let results = fill(vec![0], batches).await;
+
+        // one batch per series
+        assert_eq!(results.len(), 2);
+        let expected = vec![
+            "+------+---------------------+-------+",
+            "| host | minute              | usage |",
+            "+------+---------------------+-------+",
+            "| a    | 1970-01-01T00:00:00 |       |",
+            "| a    | 1970-01-01T00:01:00 | 1     |",
+            "| a    | 1970-01-01T00:02:00 |       |",
+            "| a    | 1970-01-01T00:03:00 | 3     |",
+            "| a    | 1970-01-01T00:04:00 |       |",
+            "| b    | 1970-01-01T00:00:00 | 10    |",
+            "| b    | 1970-01-01T00:01:00 |       |",
+            "| b    | 1970-01-01T00:02:00 |       |",
+            "| b    | 1970-01-01T00:03:00 |       |",
+            "| b    | 1970-01-01T00:04:00 |       |",
+            "+------+---------------------+-------+",
+        ];
+        assert_batches_eq!(&expected, &results);
+    }
+
+    #[tokio::test]
+    async fn test_fill_without_series() {
+        let results = fill(vec![], vec![batch(&[("a", 2, 2.0)])]).await;
+        let expected = vec![
+            "+------+---------------------+-------+",
+            "| host | minute              | usage |",
+            "+------+---------------------+-------+",
+            "|      | 1970-01-01T00:00:00 |       |",
+            "|      | 1970-01-01T00:01:00 |       |",
+            "| a    | 1970-01-01T00:02:00 | 2     |",
+            "|      | 1970-01-01T00:03:00 |       |",
+            "|      | 1970-01-01T00:04:00 |       |",
+            "+------+---------------------+-------+",
+        ];
+        assert_batches_eq!(&expected, &results);
+
+        // the buckets are produced even without input rows
+        let results = fill(vec![], vec![]).await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].num_rows(), 5);
+    }
+
+    #[test]
+    fn test_bin() {
+        assert_eq!(bin(0, MINUTE, 0), 0);
+        assert_eq!(bin(MINUTE + 1, MINUTE, 0), MINUTE);
+        assert_eq!(bin(-1, MINUTE, 0), -MINUTE);
+        assert_eq!(bin(MINUTE, MINUTE, 30), 30);
+    }
+
+    #[test]
+    fn test_bucket_count() {
+        assert_eq!(bucket_count(0, 0, MINUTE), 1);
+        assert_eq!(bucket_count(0, 4 * MINUTE, MINUTE), 5);
+        assert_eq!(bucket_count(MINUTE, 0, MINUTE), 0);
+        assert_eq!(bucket_count(i64::MIN, i64::MAX - 1, 2), 1 << 63);
+    }
+
+    #[test]
+    fn test_stride_ns() {
+        assert_eq!(
+            stride_ns(&ScalarValue::IntervalDayTime(Some((1 << 32) + 1))).unwrap(),
+            NANOS_PER_DAY + 1_000_000
+        );
+        assert_eq!(
+            stride_ns(&ScalarValue::IntervalMonthDayNano(Some(MINUTE as i128))).unwrap(),
+            MINUTE
+        );
+        assert!(stride_ns(&ScalarValue::IntervalMonthDayNano(Some(1 << 96))).is_err());
+        assert!(stride_ns(&ScalarValue::IntervalMonthDayNano(Some(0))).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+//! Metrics of the thread pools of an [`Executor`](super::Executor).
+
+use std::{any::Any, sync::Arc};
+
+use executor::DedicatedExecutor;
+use metric::{Attributes, MetricKind, Observation, Reporter};
+
+use super::DedicatedExecutors;
+
+/// A `metric::Instrument` that reports the tasks of the thread pools of [`DedicatedExecutors`] as
+/// a u64 gauge called "iox_executor_tasks", by `executor` (`query` or `reorg`) and `state`:
+///
+/// - `queued`: tasks that wait for a thread of the pool
+/// - `active`: tasks that are running
+#[derive(Debug, Clone)]
+pub(super) struct ExecutorMetrics {
+    executors: Arc<DedicatedExecutors>,
+    query_queued: Attributes,
+    query_active: Attributes,
+    reorg_queued: Attributes,
+    reorg_active: Attributes,
+}
+
+impl ExecutorMetrics {
+    pub(super) fn new(executors: Arc<DedicatedExecutors>) -> Self {
+        Self {
+            executors,
+            query_queued: Attributes::from(&[("executor", "query"), ("state", "queued")]),
+            query_active: Attributes::from(&[("executor", "query"), ("state", "active")]),
+            reorg_queued: Attributes::from(&[("executor", "reorg"), ("state", "queued")]),
+            reorg_active: Attributes::from(&[("executor", "reorg"), ("state", "active")]),
+        }
+    }
+}
+
+impl metric::Instrument for ExecutorMetrics {
+    fn report(&self, reporter: &mut dyn Reporter) {
+        reporter.start_metric(
+            "iox_executor_tasks",
+            "Number of queued and active tasks of the executor thread pools",
+            MetricKind::U64Gauge,
+        );
+
+        report_tasks(
+            reporter,
+            &self.executors.query_exec,
+            &self.query_queued,
+            &self.query_active,
+        );
+        report_tasks(
+            reporter,
+            &self.executors.reorg_exec,
+            &self.reorg_queued,
+            &self.reorg_active,
+        );
+
+        reporter.finish_metric();
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn report_tasks(
+    reporter: &mut dyn Reporter,
+    exec: &DedicatedExecutor,
+    queued: &Attributes,
+    active: &Attributes,
+) {
+    let num_queued = exec.queued_tasks();
+    let num_active = exec.tasks().saturating_sub(num_queued);
+
+    reporter.report_observation(queued, Observation::U64Gauge(num_queued as u64));
+    reporter.report_observation(active, Observation::U64Gauge(num_active as u64));
+}
@@ -0,0 +1,280 @@
+//! Named parameters (`$name`) of SQL queries.
+//!
+//! DataFusion only plans numbered placeholders (`$1`) of `PREPARE` statements, whose parameter
+//! types are declared by the statement. A query with named parameters is therefore rewritten to
+//! number its parameters, wrapped into a `PREPARE` statement declaring the types of the parameter
+//! values, and the placeholders of the resulting plan are replaced by the values.
+
+use std::{collections::HashMap, sync::Arc};
+
+use datafusion::{
+    arrow::datatypes::{DataType, TimeUnit},
+    error::{DataFusionError, Result},
+    logical_expr::{
+        expr_rewriter::{ExprRewritable, ExprRewriter},
+        utils::from_plan,
+        Expr, LogicalPlan, Subquery,
+    },
+    scalar::ScalarValue,
+};
+
+/// Name of the `PREPARE` statement wrapping a query with parameters.
+const STATEMENT_NAME: &str = "iox_query";
+
+/// Wrap `sql` into a `PREPARE` statement, with its named parameters replaced by numbered ones.
+///
+/// Returns the statement and the values of the numbered parameters, in order. Parameters in
+/// `params` that are not referenced by `sql` are ignored.
+pub(crate) fn prepare_statement(
+    sql: &str,
+    params: &HashMap<String, ScalarValue>,
+) -> Result<(String, Vec<ScalarValue>)> {
+    let (sql, values) = number_params(sql, params)?;
+
+    let types = values
+        .iter()
+        .map(sql_type)
+        .collect::<Result<Vec<_>>>()?
+        .join(", ");
+
+    Ok((
+        format!("PREPARE {STATEMENT_NAME}({types}) AS {sql}"),
+        values,
+    ))
+}
+
+/// Replace the named parameters of `sql` by numbered ones.
+///
+/// Parameters in string literals, quoted identifiers and comments are left untouched.
+fn number_params(
+    sql: &str,
+    params: &HashMap<String, ScalarValue>,
+) -> Result<(String, Vec<ScalarValue>)> {
+    let mut names: Vec<&str> = vec![];
+    let mut values = vec![];
+    let mut out = String::with_capacity(sql.len());
+
+    let mut chars = sql.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                out.push(c);
+                for (_, next) in chars.by_ref() {
+                    out.push(next);
+                    if next == c {
+                        break;
+                    }
+                }
+            }
+            '-' if matches!(chars.peek(), Some((_, '-'))) => {
+                out.push(c);
+                for (_, next) in chars.by_ref() {
+                    out.push(next);
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if matches!(chars.peek(), Some((_, '*'))) => {
+                out.push(c);
+                let mut prev = None;
+                for (_, next) in chars.by_ref() {
+                    out.push(next);
+                    if prev == Some('*') && next == '/' {
+                        break;
+                    }
+                    prev = Some(next);
+                }
+            }
+            '$' => {
+                let mut end = start + 1;
+                while let Some((i, next)) = chars.peek() {
+                    if !(next.is_ascii_alphanumeric() || *next == '_') {
+                        break;
+                    }
+                    end = i + next.len_utf8();
+                    chars.next();
+                }
+
+                let name = &sql[start + 1..end];
+                if name.is_empty() {
+                    out.push(c);
+                    continue;
+                }
+                if name.starts_with(|c: char| c.is_ascii_digit()) {
+                    return Err(DataFusionError::Plan(format!(
+                        "Numbered query parameters are not supported, use named parameters instead of ${name}"
+                    )));
+                }
+
+                let position = match names.iter().position(|n| *n == name) {
+                    Some(position) => position,
+                    None => {
+                        let value = params.get(name).ok_or_else(|| {
+                            DataFusionError::Plan(format!("No value for query parameter ${name}"))
+                        })?;
+                        names.push(name);
+                        values.push(value.clone());
+                        names.len() - 1
+                    }
+                };
+                out.push_str(&format!("${}", position + 1));
+            }
+            _ => out.push(c),
+        }
+    }
+
+    Ok((out, values))
+}
+
+/// The SQL type of a parameter `value`, as declared by the `PREPARE` statement.
+fn sql_type(value: &ScalarValue) -> Result<&'static str> {
+    match value.get_datatype() {
+        DataType::Boolean => Ok("BOOLEAN"),
+        DataType::Int64 => Ok("BIGINT"),
+        DataType::UInt64 => Ok("BIGINT UNSIGNED"),
+        DataType::Float64 => Ok("DOUBLE"),
+        DataType::Utf8 => Ok("VARCHAR"),
+        DataType::Timestamp(TimeUnit::Nanosecond, None) => Ok("TIMESTAMP"),
+        data_type => Err(DataFusionError::NotImplemented(format!(
+            "Query parameters of type {data_type}"
+        ))),
+    }
+}
+
+/// Replace the numbered placeholders of `plan` by the corresponding `values`.
+pub(crate) fn bind_params(plan: &LogicalPlan, values: &[ScalarValue]) -> Result<LogicalPlan> {
+    let inputs = plan
+        .inputs()
+        .into_iter()
+        .map(|input| bind_params(input, values))
+        .collect::<Result<Vec<_>>>()?;
+
+    let exprs = plan
+        .expressions()
+        .into_iter()
+        .map(|expr| expr.rewrite(&mut ParamBinder { values }))
+        .collect::<Result<Vec<_>>>()?;
+
+    from_plan(plan, &exprs, &inputs)
+}
+
+/// Replaces numbered placeholders by their value, including those of subqueries.
+struct ParamBinder<'a> {
+    values: &'a [ScalarValue],
+}
+
+impl ParamBinder<'_> {
+    fn bind_subquery(&self, subquery: Subquery) -> Result<Subquery> {
+        Ok(Subquery {
+            subquery: Arc::new(bind_params(&subquery.subquery, self.values)?),
+        })
+    }
+}
+
+impl ExprRewriter for ParamBinder<'_> {
+    fn mutate(&mut self, expr: Expr) -> Result<Expr> {
+        match expr {
+            Expr::Placeholder { id, .. } => id
+                .strip_prefix('$')
+                .and_then(|n| n.parse::<usize>().ok())
+                .and_then(|n| n.checked_sub(1))
+                .and_then(|idx| self.values.get(idx))
+                .map(|value| Expr::Literal(value.clone()))
+                .ok_or_else(|| DataFusionError::Internal(format!("No value for placeholder {id}"))),
+            Expr::ScalarSubquery(subquery) => {
+                Ok(Expr::ScalarSubquery(self.bind_subquery(subquery)?))
+            }
+            Expr::Exists { subquery, negated } => Ok(Expr::Exists {
+                subquery: self.bind_subquery(subquery)?,
+                negated,
+            }),
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => Ok(Expr::InSubquery {
+                expr,
+                subquery: self.bind_subquery(subquery)?,
+                negated,
+            }),
+            expr => Ok(expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> HashMap<String, ScalarValue> {
+        HashMap::from([
+            (
+                "start".to_string(),
+                ScalarValue::TimestampNanosecond(Some(1), None),
+            ),
+            ("region".to_string(), ScalarValue::Utf8(Some("west".into()))),
+            ("limit".to_string(), ScalarValue::Int64(Some(10))),
+        ])
+    }
+
+    #[test]
+    fn test_prepare_statement() {
+        let (statement, values) = prepare_statement(
+            "SELECT * FROM cpu WHERE time > $start AND region = $region OR time < $start",
+            &params(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            statement,
+            "PREPARE iox_query(TIMESTAMP, VARCHAR) AS \
+            SELECT * FROM cpu WHERE time > $1 AND region = $2 OR time < $1"
+        );
+        assert_eq!(
+            values,
+            vec![
+                ScalarValue::TimestampNanosecond(Some(1), None),
+                ScalarValue::Utf8(Some("west".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quoted_params() {
+        let (statement, values) = prepare_statement(
+            "SELECT '$region', \"$region\" -- $region\n FROM cpu /* $region */ LIMIT $limit",
+            &params(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            statement,
+            "PREPARE iox_query(BIGINT) AS \
+            SELECT '$region', \"$region\" -- $region\n FROM cpu /* $region */ LIMIT $1"
+        );
+        assert_eq!(values, vec![ScalarValue::Int64(Some(10))]);
+    }
+
+    #[test]
+    fn test_invalid_params() {
+        let err = prepare_statement("SELECT $missing", &params()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: No value for query parameter $missing"
+        );
+
+        let err = prepare_statement("SELECT $1", &params()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Numbered query parameters are not supported, use named parameters instead of $1"
+        );
+
+        let params = HashMap::from([("p".to_string(), ScalarValue::Int32(Some(1)))]);
+        let err = prepare_statement("SELECT $p", &params).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "This feature is not implemented: Query parameters of type Int32"
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! Staleness watermarks of queries that only read persisted data, returned to the client to
+//! describe how recent the results are.
+
+use std::collections::BTreeMap;
+
+use parking_lot::Mutex;
+
+/// Collects the newest timestamp of the persisted data read from each table by a query.
+///
+/// Set for a query via
+/// [`IOxSessionContext::with_persisted_watermarks`](super::IOxSessionContext::with_persisted_watermarks).
+/// Only table providers that never read unpersisted data record a watermark, so the results
+/// of a query are missing at most the data written after the watermarks of its tables.
+#[derive(Debug, Default)]
+pub struct PersistedWatermarks {
+    watermarks: Mutex<BTreeMap<String, i64>>,
+}
+
+impl PersistedWatermarks {
+    /// Record that the persisted data of `table_name` scanned by the query reaches up to the
+    /// timestamp `max_time`.
+    ///
+    /// A table scanned more than once keeps its newest watermark.
+    pub fn record(&self, table_name: &str, max_time: i64) {
+        let mut watermarks = self.watermarks.lock();
+        match watermarks.get_mut(table_name) {
+            Some(watermark) => *watermark = (*watermark).max(max_time),
+            None => {
+                watermarks.insert(table_name.to_string(), max_time);
+            }
+        }
+    }
+
+    /// The watermarks recorded so far, by table name.
+    pub fn watermarks(&self) -> BTreeMap<String, i64> {
+        self.watermarks.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let watermarks = PersistedWatermarks::default();
+        assert!(watermarks.watermarks().is_empty());
+
+        watermarks.record("cpu", 10);
+        watermarks.record("mem", 20);
+        watermarks.record("cpu", 30);
+        watermarks.record("cpu", 5);
+
+        assert_eq!(
+            watermarks.watermarks(),
+            BTreeMap::from([("cpu".to_string(), 30), ("mem".to_string(), 20)])
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Execution statistics of a single query, returned to the client to describe the query cost.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use datafusion::physical_plan::{metrics::MetricValue, ExecutionPlan};
+
+/// Name of the DataFusion metric counting the bytes read from parquet files.
+const BYTES_SCANNED_METRIC: &str = "bytes_scanned";
+
+/// Collects the statistics of a query while it is planned and executed.
+///
+/// Set for a query via
+/// [`IOxSessionContext::with_statistics`](super::IOxSessionContext::with_statistics). The
+/// statistics DataFusion records anyway are taken from the executed plan by
+/// [`summary`](Self::summary), the others are recorded by the components taking part in the
+/// query.
+#[derive(Debug, Default)]
+pub struct QueryStatistics {
+    rows_returned: AtomicU64,
+    ingester_partitions: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+impl QueryStatistics {
+    /// Record `rows` rows returned to the client.
+    pub fn record_rows_returned(&self, rows: u64) {
+        self.rows_returned.fetch_add(rows, Ordering::Relaxed);
+    }
+
+    /// Record that data of `partitions` ingester partitions was read.
+    pub fn record_ingester_partitions(&self, partitions: u64) {
+        self.ingester_partitions
+            .fetch_add(partitions, Ordering::Relaxed);
+    }
+
+    /// Record a lookup of the metadata of a parquet file in a cache.
+    pub fn record_cache_lookup(&self, hit: bool) {
+        let counter = if hit {
+            &self.cache_hits
+        } else {
+            &self.cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Summarise the statistics of the query executed as `plan`.
+    pub fn summary(&self, plan: &dyn ExecutionPlan) -> QueryStatisticsSummary {
+        let mut summary = QueryStatisticsSummary {
+            rows_returned: self.rows_returned.load(Ordering::Relaxed),
+            ingester_partitions: self.ingester_partitions.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+            ..Default::default()
+        };
+        add_plan_metrics(plan, &mut summary);
+        summary
+    }
+}
+
+/// The statistics of an executed query, see [`QueryStatistics::summary`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct QueryStatisticsSummary {
+    /// Bytes read from parquet files.
+    pub parquet_bytes_read: u64,
+
+    /// Rows produced by the leaves of the plan, i.e. read from parquet files and ingester data.
+    pub rows_scanned: u64,
+
+    /// Rows returned to the client.
+    pub rows_returned: u64,
+
+    /// Ingester partitions the data was read from.
+    pub ingester_partitions: u64,
+
+    /// Parquet files whose metadata was cached.
+    pub cache_hits: u64,
+
+    /// Parquet files whose metadata was not cached.
+    pub cache_misses: u64,
+}
+
+/// Add the metrics recorded by DataFusion while executing `plan` to `summary`.
+fn add_plan_metrics(plan: &dyn ExecutionPlan, summary: &mut QueryStatisticsSummary) {
+    let children = plan.children();
+
+    if let Some(metrics) = plan.metrics() {
+        for metric in metrics.iter() {
+            match metric.value() {
+                MetricValue::Count { name, count } if name == BYTES_SCANNED_METRIC => {
+                    summary.parquet_bytes_read += count.value() as u64;
+                }
+                MetricValue::OutputRows(count) if children.is_empty() => {
+                    summary.rows_scanned += count.value() as u64;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    for child in children {
+        add_plan_metrics(child.as_ref(), summary);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::{
+        array::Int64Array,
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use datafusion::physical_plan::{collect, filter::FilterExec, memory::MemoryExec};
+    use datafusion::prelude::{col, lit, SessionContext};
+
+    use crate::util::df_physical_expr;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_summary() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int64Array::from(vec![1, 2, 3]))],
+        )
+        .unwrap();
+        let input = Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap());
+        let predicate = df_physical_expr(input.as_ref(), col("a").gt(lit(1_i64))).unwrap();
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(FilterExec::try_new(predicate, input).unwrap());
+
+        let batches = collect(Arc::clone(&plan), SessionContext::new().task_ctx())
+            .await
+            .unwrap();
+
+        let stats = QueryStatistics::default();
+        stats.record_rows_returned(batches.iter().map(|b| b.num_rows() as u64).sum());
+        stats.record_ingester_partitions(2);
+        stats.record_cache_lookup(true);
+        stats.record_cache_lookup(false);
+        stats.record_cache_lookup(true);
+
+        // the rows output by the filter are not counted as scanned, and the memory source
+        // records no metrics
+        assert_eq!(
+            stats.summary(plan.as_ref()),
+            QueryStatisticsSummary {
+                parquet_bytes_read: 0,
+                rows_scanned: 0,
+                rows_returned: 2,
+                ingester_partitions: 2,
+                cache_hits: 2,
+                cache_misses: 1,
+            }
+        );
+    }
+}
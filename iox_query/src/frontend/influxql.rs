@@ -0,0 +1,1496 @@
+//! Planning of InfluxQL queries.
+//!
+//! [`InfluxQLQueryPlanner`] lowers a single InfluxQL `SELECT` statement to a DataFusion
+//! [`LogicalPlan`] over the tables of the default schema of the query context. The output has a
+//! `time` column, followed by the tags of the `GROUP BY` clause and the fields of the selection.
+//!
+//! If the `FROM` clause names several measurements or a regular expression, the measurements are
+//! queried as one: the output starts with an `iox::measurement` column holding the measurement
+//! name, and columns that a measurement lacks are NULL for it. A regular expression is matched
+//! against the table names when the query is planned, and queries selecting more than
+//! [`DEFAULT_MAX_MEASUREMENTS`] measurements, or the maximum set with
+//! [`InfluxQLQueryPlanner::with_max_measurements`], are rejected.
+//!
+//! Supported are:
+//!
+//! * measurement names and regular expressions in the `FROM` clause,
+//! * fields, tags, literals and arithmetic in the selection, and `*` for all columns,
+//! * the aggregates `count`, `sum`, `mean`, `min` and `max` of a single field,
+//! * comparisons, regular expression matches, `AND` and `OR` in the `WHERE` clause, where time
+//!   may be compared to timestamps, integers, `now()` and durations,
+//! * `GROUP BY time(<interval>[, <offset>])` and tags, including `*` for all tags,
+//! * `fill(null|none|previous|linear|<number>)`, using the gap-filling of
+//!   [`date_bin_gapfill`](query_functions::DATE_BIN_GAPFILL_UDF_NAME),
+//! * `ORDER BY time [ASC|DESC]`, and `LIMIT` and `OFFSET` unless grouping by tags,
+//! * `INTO <measurement>`, if the query context has a [`TableWriter`].
+//!
+//! The results of a query with an `INTO` clause are written to the measurement through the
+//! [`TableWriter`] of the query context, instead of being returned, and the query returns the
+//! number of rows written. Like the results of `INSERT INTO ... SELECT`, the `time` column becomes
+//! the timestamp, the tags of the `GROUP BY` clause and other dictionary encoded columns become
+//! tags, and all other columns become fields. Rows without any field value are not written, as in
+//! InfluxDB 1.x.
+//!
+//! [`TableWriter`]: crate::exec::TableWriter
+//!
+//! As in InfluxDB 1.x, filling requires a lower bound on the time in the `WHERE` clause, and
+//! fills up to the current time if there is no upper bound.
+//!
+//! Other statements and clauses, such as `SLIMIT`, `tz()`, subqueries and selectors, are rejected
+//! with a [`DataFusionError::NotImplemented`](Error::NotImplemented) error.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use arrow::{compute::kernels::cast_utils::string_to_timestamp_nanos, datatypes::DataType};
+use datafusion::{
+    datasource::{provider_as_source, TableProvider},
+    error::{DataFusionError as Error, Result},
+    logical_expr::{
+        and, binary_expr, cast, or, BinaryExpr, BuiltinScalarFunction, LogicalPlan,
+        LogicalPlanBuilder, Operator,
+    },
+    optimizer::utils::split_conjunction,
+    physical_plan::ExecutionPlan,
+    prelude::{avg, count, lit, lit_timestamp_nano, max, min, sum, Expr},
+    scalar::ScalarValue,
+};
+use datafusion_util::{
+    config::{DEFAULT_CATALOG, DEFAULT_SCHEMA},
+    AsExpr,
+};
+use influxdb_influxql_parser::{
+    common::{MeasurementName, OrderByClause, QualifiedMeasurementName},
+    expression::{
+        arithmetic::{BinaryOperator, Expr as IqlExpr, UnaryOperator, VarRefDataType},
+        conditional::{ConditionalExpression, ConditionalOperator},
+    },
+    literal::{Literal, Number},
+    parse_statements,
+    select::{Dimension, FillClause, MeasurementSelection, SelectStatement},
+    statement::Statement,
+};
+use observability_deps::tracing::debug;
+use query_functions::{
+    regex_match_expr, regex_not_match_expr, registry, DATE_BIN_GAPFILL_UDF_NAME,
+    INTERPOLATE_UDF_NAME, LOCF_UDF_NAME,
+};
+use regex::Regex;
+use schema::TIME_COLUMN_NAME;
+
+use crate::exec::context::IOxSessionContext;
+
+/// The maximum number of measurements a query may select, unless configured otherwise with
+/// [`InfluxQLQueryPlanner::with_max_measurements`].
+pub const DEFAULT_MAX_MEASUREMENTS: usize = 100;
+
+/// The name of the output column holding the measurement name, if a query selects several
+/// measurements.
+pub const MEASUREMENT_COLUMN_NAME: &str = "iox::measurement";
+
+/// This struct can create plans for running InfluxQL queries against databases
+#[derive(Debug)]
+pub struct InfluxQLQueryPlanner {
+    /// The maximum number of measurements a query may select.
+    max_measurements: usize,
+}
+
+impl Default for InfluxQLQueryPlanner {
+    fn default() -> Self {
+        Self {
+            max_measurements: DEFAULT_MAX_MEASUREMENTS,
+        }
+    }
+}
+
+impl InfluxQLQueryPlanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject queries whose `FROM` clause selects more than `max_measurements` measurements,
+    /// instead of [`DEFAULT_MAX_MEASUREMENTS`].
+    pub fn with_max_measurements(self, max_measurements: usize) -> Self {
+        Self { max_measurements }
+    }
+
+    /// Plan an InfluxQL `SELECT` statement against the tables registered with `ctx`, and return
+    /// a DataFusion physical execution plan that runs on the query executor.
+    ///
+    /// See the [module documentation](self) for the supported subset of InfluxQL.
+    pub async fn query(
+        &self,
+        query: &str,
+        ctx: &IOxSessionContext,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let ctx = ctx.child_ctx("influxql query");
+        debug!(text=%query, "planning InfluxQL query");
+
+        let select = parse_select(query)?;
+        let plan = SelectPlanner::new(&ctx, now_ns(), self.max_measurements).plan(&select)?;
+        debug!(plan=%plan.display_graphviz(), "logical plan");
+
+        match &select.into {
+            Some(into) => {
+                let writer = ctx
+                    .table_writer()
+                    .ok_or_else(|| Error::NotImplemented("InfluxQL INTO clause".to_string()))?;
+                let table_name = into_table_name(into)?;
+                ctx.write_query_results(writer, &table_name, &plan).await
+            }
+            None => ctx.create_physical_plan(&plan).await,
+        }
+    }
+}
+
+/// The name of the table the `INTO` clause writes to, quoted as a SQL identifier as expected by
+/// [`TableWriter::write`](crate::exec::TableWriter::write).
+fn into_table_name(into: &QualifiedMeasurementName) -> Result<String> {
+    match into {
+        QualifiedMeasurementName {
+            database: None,
+            retention_policy: None,
+            name: MeasurementName::Name(name),
+        } => Ok(format!("\"{}\"", name.replace('"', "\"\""))),
+        _ => Err(Error::NotImplemented(
+            "InfluxQL INTO clause with a database or retention policy".to_string(),
+        )),
+    }
+}
+
+/// Parse `query`, which must be a single `SELECT` statement.
+fn parse_select(query: &str) -> Result<SelectStatement> {
+    let mut statements =
+        parse_statements(query).map_err(|e| Error::Plan(format!("invalid InfluxQL: {e}")))?;
+    if statements.len() != 1 {
+        return Err(Error::Plan(format!(
+            "expected a single InfluxQL statement, got {}",
+            statements.len()
+        )));
+    }
+
+    match statements.remove(0) {
+        Statement::Select(select) => Ok(*select),
+        statement => Err(Error::NotImplemented(format!(
+            "InfluxQL statement: {statement}"
+        ))),
+    }
+}
+
+/// The current time, in nanoseconds since the epoch.
+fn now_ns() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or_default()
+}
+
+/// Lowers a `SELECT` statement to a [`LogicalPlan`].
+struct SelectPlanner<'a> {
+    ctx: &'a IOxSessionContext,
+
+    /// The value of `now()`, in nanoseconds since the epoch.
+    now: i64,
+
+    /// The maximum number of measurements the `FROM` clause may select.
+    max_measurements: usize,
+}
+
+impl<'a> SelectPlanner<'a> {
+    fn new(ctx: &'a IOxSessionContext, now: i64, max_measurements: usize) -> Self {
+        Self {
+            ctx,
+            now,
+            max_measurements,
+        }
+    }
+
+    fn plan(&self, select: &SelectStatement) -> Result<LogicalPlan> {
+        if select.series_limit.is_some() || select.series_offset.is_some() {
+            return Err(Error::NotImplemented(
+                "InfluxQL SLIMIT and SOFFSET clauses".to_string(),
+            ));
+        }
+        if select.timezone.is_some() {
+            return Err(Error::NotImplemented("InfluxQL tz() clause".to_string()));
+        }
+
+        let measurements = self.measurements(select)?;
+        let first = match measurements.first() {
+            Some(first) => first,
+            // a regular expression matching no measurement selects nothing
+            None => return LogicalPlanBuilder::empty(false).build(),
+        };
+        let group_by = self.group_by(select, first)?;
+        if !group_by.tags.is_empty() && (select.limit.is_some() || select.offset.is_some()) {
+            return Err(Error::NotImplemented(
+                "InfluxQL LIMIT and OFFSET clauses with GROUP BY tags".to_string(),
+            ));
+        }
+
+        // The results of INTO are written to a single measurement
+        let with_measurement = select.into.is_none()
+            && (measurements.len() > 1
+                || std::iter::once(select.from.first())
+                    .chain(select.from.rest())
+                    .any(|selection| {
+                        matches!(
+                            selection,
+                            MeasurementSelection::Name(QualifiedMeasurementName {
+                                name: MeasurementName::Regex(_),
+                                ..
+                            })
+                        )
+                    }));
+
+        let (mut builder, written) =
+            self.plan_measurement(select, first, &group_by, with_measurement)?;
+        for measurement in &measurements[1..] {
+            let (plan, _) =
+                self.plan_measurement(select, measurement, &group_by, with_measurement)?;
+            builder = builder.union(plan.build()?)?;
+        }
+
+        let asc = !matches!(select.order_by, Some(OrderByClause::Descending));
+        let sort_exprs = with_measurement
+            .then_some(MEASUREMENT_COLUMN_NAME)
+            .into_iter()
+            .chain(group_by.tags.iter().map(|tag| tag.as_str()))
+            .map(|column| column.as_sort_expr())
+            .chain(std::iter::once(Expr::Sort {
+                expr: Box::new(TIME_COLUMN_NAME.as_expr()),
+                asc,
+                nulls_first: false,
+            }))
+            .collect::<Vec<_>>();
+        let builder = builder.sort(sort_exprs)?;
+
+        let builder = if select.limit.is_some() || select.offset.is_some() {
+            let skip = select.offset.map(|offset| *offset as usize).unwrap_or(0);
+            let fetch = select.limit.map(|limit| *limit as usize);
+            builder.limit(skip, fetch)?
+        } else {
+            builder
+        };
+
+        // Rows without field values cannot be written
+        let builder = match select.into {
+            Some(_) => builder.filter(written)?,
+            None => builder,
+        };
+
+        builder.build()
+    }
+
+    /// Plan the selection from `measurement`, without sorting it.
+    ///
+    /// Returns the plan and a predicate on its output that is true if any field is not NULL.
+    fn plan_measurement(
+        &self,
+        select: &SelectStatement,
+        measurement: &Measurement,
+        group_by: &GroupBy,
+        with_measurement: bool,
+    ) -> Result<(LogicalPlanBuilder, Expr)> {
+        let fields = Fields::new(self, select, measurement, group_by, with_measurement)?;
+        if fields.aggregates.is_empty() && (group_by.time.is_some() || select.fill.is_some()) {
+            return Err(Error::Plan(
+                "GROUP BY time() and fill() require at least one aggregate function".to_string(),
+            ));
+        }
+        let any_not_null = fields.any_not_null();
+
+        let condition = select
+            .condition
+            .as_ref()
+            .map(|condition| self.condition(condition, measurement))
+            .transpose()?;
+
+        let builder = LogicalPlanBuilder::scan(
+            measurement.name.as_str(),
+            provider_as_source(Arc::clone(&measurement.provider)),
+            None,
+        )?;
+
+        let builder = if fields.aggregates.is_empty() {
+            let builder = match condition {
+                Some(condition) => builder.filter(condition)?,
+                None => builder,
+            };
+            let tags = group_by
+                .tags
+                .iter()
+                .map(|tag| Ok(measurement.column(tag)?.alias(tag)))
+                .collect::<Result<Vec<_>>>()?;
+            builder.project(fields.output(tags))?
+        } else {
+            let fill = select.fill.unwrap_or(FillClause::Null);
+            self.aggregate(builder, condition, fields, measurement, group_by, fill)?
+        };
+
+        Ok((builder, any_not_null))
+    }
+
+    /// Aggregate the rows of `builder` matching `condition` by the time buckets and tags of
+    /// `group_by`, filling the empty time buckets as specified by `fill`.
+    ///
+    /// The plan is
+    ///
+    /// ```text
+    /// Projection: time, tags, fields
+    ///   Projection: time, tags, fill(aggregates)
+    ///     Aggregate: groupBy=[tags, date_bin_gapfill(interval, time, offset) AS time]
+    ///       Filter: condition
+    /// ```
+    ///
+    /// `date_bin` replaces `date_bin_gapfill` for `fill(none)`.
+    fn aggregate(
+        &self,
+        builder: LogicalPlanBuilder,
+        condition: Option<Expr>,
+        fields: Fields,
+        measurement: &Measurement,
+        group_by: &GroupBy,
+        fill: FillClause,
+    ) -> Result<LogicalPlanBuilder> {
+        let range = TimeRange::new(condition.as_ref());
+        let mut condition = condition;
+        let gap_fill = group_by.time.is_some() && fill != FillClause::None;
+
+        let time_bucket = match group_by.time {
+            Some((interval, offset)) => {
+                let args = vec![
+                    lit(ScalarValue::IntervalMonthDayNano(Some(interval as i128))),
+                    TIME_COLUMN_NAME.as_expr(),
+                    lit_timestamp_nano(offset),
+                ];
+                let bucket = if gap_fill {
+                    if range.lower.is_none() {
+                        return Err(Error::Plan(
+                            "GROUP BY time() requires a lower bound on time in the WHERE clause, \
+                             unless fill(none) is used"
+                                .to_string(),
+                        ));
+                    }
+                    if range.upper.is_none() {
+                        let upper = TIME_COLUMN_NAME
+                            .as_expr()
+                            .lt_eq(lit_timestamp_nano(self.now));
+                        condition = Some(match condition {
+                            Some(condition) => and(condition, upper),
+                            None => upper,
+                        });
+                    }
+                    registry().udf(DATE_BIN_GAPFILL_UDF_NAME)?.call(args)
+                } else {
+                    Expr::ScalarFunction {
+                        fun: BuiltinScalarFunction::DateBin,
+                        args,
+                    }
+                };
+                Some(bucket.alias(TIME_COLUMN_NAME))
+            }
+            None => None,
+        };
+
+        let builder = match condition {
+            Some(condition) => builder.filter(condition)?,
+            None => builder,
+        };
+
+        let group_exprs = group_by
+            .tags
+            .iter()
+            .map(|tag| Ok(measurement.column(tag)?.alias(tag)))
+            .chain(time_bucket.clone().map(Ok))
+            .collect::<Result<Vec<_>>>()?;
+        let num_group = group_exprs.len();
+        let builder = builder.aggregate(group_exprs, fields.aggregates.clone())?;
+
+        // The output of an aggregation are its group columns followed by its aggregates
+        let schema = Arc::clone(builder.schema());
+        let columns = schema
+            .fields()
+            .iter()
+            .map(|field| Expr::Column(field.qualified_column()))
+            .collect::<Vec<_>>();
+
+        let time = match time_bucket {
+            Some(_) => columns[num_group - 1].clone(),
+            None => lit_timestamp_nano(range.lower.unwrap_or(0)).alias(TIME_COLUMN_NAME),
+        };
+        let mut exprs = vec![time];
+        exprs.extend(columns[..group_by.tags.len()].iter().cloned());
+        for (idx, column) in columns[num_group..].iter().enumerate() {
+            let column = column.clone();
+            let filled = match fill {
+                _ if !gap_fill => column,
+                FillClause::Null | FillClause::None => column,
+                FillClause::Previous => registry().udf(LOCF_UDF_NAME)?.call(vec![column]),
+                FillClause::Linear => registry().udf(INTERPOLATE_UDF_NAME)?.call(vec![column]),
+                FillClause::Value(value) => {
+                    let value = match value {
+                        Number::Integer(v) => lit(v),
+                        Number::Float(v) => lit(v),
+                    };
+                    let data_type = schema.field(num_group + idx).data_type().clone();
+                    Expr::ScalarFunction {
+                        fun: BuiltinScalarFunction::Coalesce,
+                        args: vec![column, cast(value, data_type)],
+                    }
+                }
+            };
+            exprs.push(filled.alias(&aggregate_name(idx)));
+        }
+
+        let tags = group_by.tags.iter().map(|tag| tag.as_str().as_expr());
+        builder.project(exprs)?.project(fields.output(tags))
+    }
+
+    /// Resolve the measurements of the `FROM` clause to tables, sorted by name.
+    ///
+    /// Regular expressions are matched against the names of the tables of the default schema.
+    fn measurements(&self, select: &SelectStatement) -> Result<Vec<Measurement>> {
+        let schema = self
+            .ctx
+            .inner()
+            .catalog(DEFAULT_CATALOG)
+            .and_then(|catalog| catalog.schema(DEFAULT_SCHEMA))
+            .ok_or_else(|| Error::Plan(format!("schema '{DEFAULT_SCHEMA}' not found")))?;
+
+        let mut names = BTreeSet::new();
+        for selection in std::iter::once(select.from.first()).chain(select.from.rest()) {
+            match selection {
+                MeasurementSelection::Subquery(_) => {
+                    return Err(Error::NotImplemented("InfluxQL subqueries".to_string()))
+                }
+                MeasurementSelection::Name(QualifiedMeasurementName {
+                    database: None,
+                    retention_policy: None,
+                    name,
+                }) => match name {
+                    MeasurementName::Name(name) => {
+                        names.insert(name.as_str().to_string());
+                    }
+                    MeasurementName::Regex(regex) => {
+                        let regex = Regex::new(regex.as_str()).map_err(|e| {
+                            Error::Plan(format!(
+                                "invalid regular expression /{}/: {e}",
+                                regex.as_str()
+                            ))
+                        })?;
+                        names.extend(
+                            schema
+                                .table_names()
+                                .into_iter()
+                                .filter(|name| regex.is_match(name)),
+                        );
+                    }
+                },
+                MeasurementSelection::Name(_) => {
+                    return Err(Error::NotImplemented(
+                        "InfluxQL measurements qualified with a database or retention policy"
+                            .to_string(),
+                    ))
+                }
+            }
+        }
+
+        if names.len() > self.max_measurements {
+            return Err(Error::Plan(format!(
+                "the FROM clause selects {} measurements, more than the maximum of {}; \
+                 use a more specific regular expression",
+                names.len(),
+                self.max_measurements
+            )));
+        }
+
+        let tables = names
+            .into_iter()
+            .map(|name| match schema.table(&name) {
+                Some(provider) => Ok((name, provider)),
+                None => Err(Error::Plan(format!("measurement '{name}' not found"))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut column_types = BTreeMap::new();
+        for (_, provider) in &tables {
+            for field in provider.schema().fields() {
+                column_types
+                    .entry(field.name().clone())
+                    .or_insert_with(|| field.data_type().clone());
+            }
+        }
+        let column_types = Arc::new(column_types);
+
+        Ok(tables
+            .into_iter()
+            .map(|(name, provider)| Measurement {
+                name,
+                provider,
+                column_types: Arc::clone(&column_types),
+            })
+            .collect())
+    }
+
+    /// Resolve the `GROUP BY` clause of `select` against the tags of the measurements of the query,
+    /// as known to `measurement`.
+    fn group_by(&self, select: &SelectStatement, measurement: &Measurement) -> Result<GroupBy> {
+        let mut group_by = GroupBy::default();
+        let dimensions = match &select.group_by {
+            Some(dimensions) => dimensions,
+            None => return Ok(group_by),
+        };
+
+        let mut tags = vec![];
+        for dimension in std::iter::once(dimensions.first()).chain(dimensions.rest()) {
+            match dimension {
+                Dimension::Time { interval, offset } => {
+                    if group_by.time.is_some() {
+                        return Err(Error::Plan(
+                            "GROUP BY may only contain a single time() call".to_string(),
+                        ));
+                    }
+                    let interval = self.time_value(interval)?;
+                    if interval <= 0 {
+                        return Err(Error::Plan(
+                            "GROUP BY time() interval must be positive".to_string(),
+                        ));
+                    }
+                    let offset = match offset {
+                        Some(offset) => self.time_value(offset)?,
+                        None => 0,
+                    };
+                    group_by.time = Some((interval, offset));
+                }
+                Dimension::Tag(tag) => tags.push(tag.as_str().to_string()),
+                Dimension::Wildcard => tags.extend(measurement.tags()),
+                Dimension::Regex(_) => {
+                    return Err(Error::NotImplemented(
+                        "InfluxQL regular expressions in the GROUP BY clause".to_string(),
+                    ))
+                }
+            }
+        }
+
+        tags.sort();
+        tags.dedup();
+        group_by.tags = tags;
+        Ok(group_by)
+    }
+
+    /// Lower the `WHERE` clause `condition` for `measurement`.
+    ///
+    /// Comparisons of the time are lowered to `time <op> <timestamp>`, so that [`TimeRange`]
+    /// can find the bounds they set.
+    fn condition(
+        &self,
+        condition: &ConditionalExpression,
+        measurement: &Measurement,
+    ) -> Result<Expr> {
+        let (lhs, op, rhs) = match condition {
+            ConditionalExpression::Expr(expr) => return self.expr(expr, measurement, None),
+            ConditionalExpression::Grouped(condition) => {
+                return self.condition(condition, measurement)
+            }
+            ConditionalExpression::Binary { lhs, op, rhs } => (lhs, *op, rhs),
+        };
+
+        match op {
+            ConditionalOperator::And => {
+                return Ok(and(
+                    self.condition(lhs, measurement)?,
+                    self.condition(rhs, measurement)?,
+                ))
+            }
+            ConditionalOperator::Or => {
+                return Ok(or(
+                    self.condition(lhs, measurement)?,
+                    self.condition(rhs, measurement)?,
+                ))
+            }
+            ConditionalOperator::In => {
+                return Err(Error::NotImplemented("InfluxQL IN operator".to_string()))
+            }
+            _ => {}
+        }
+
+        let (lhs, rhs) = (operand(lhs)?, operand(rhs)?);
+        match op {
+            ConditionalOperator::EqRegex | ConditionalOperator::NotEqRegex => {
+                let pattern = match rhs {
+                    IqlExpr::Literal(Literal::Regex(regex)) => regex.as_str().to_string(),
+                    _ => {
+                        return Err(Error::Plan(format!(
+                            "expected a regular expression on the right of {op}, got {rhs}"
+                        )))
+                    }
+                };
+                let input = self.expr(lhs, measurement, None)?;
+                Ok(if op == ConditionalOperator::EqRegex {
+                    regex_match_expr(input, pattern)
+                } else {
+                    regex_not_match_expr(input, pattern)
+                })
+            }
+            op => {
+                let op = comparison_operator(op);
+                if is_time(lhs) {
+                    Ok(binary_expr(
+                        TIME_COLUMN_NAME.as_expr(),
+                        op,
+                        lit_timestamp_nano(self.time_value(rhs)?),
+                    ))
+                } else if is_time(rhs) {
+                    Ok(binary_expr(
+                        TIME_COLUMN_NAME.as_expr(),
+                        swap(op),
+                        lit_timestamp_nano(self.time_value(lhs)?),
+                    ))
+                } else {
+                    Ok(binary_expr(
+                        self.expr(lhs, measurement, None)?,
+                        op,
+                        self.expr(rhs, measurement, None)?,
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Evaluate the constant time expression `expr` to nanoseconds since the epoch.
+    fn time_value(&self, expr: &IqlExpr) -> Result<i64> {
+        let overflow = || Error::Plan(format!("time expression out of range: {expr}"));
+        match expr {
+            IqlExpr::Literal(Literal::Duration(duration)) => Ok(**duration),
+            IqlExpr::Literal(Literal::Unsigned(value)) => {
+                i64::try_from(*value).map_err(|_| overflow())
+            }
+            IqlExpr::Literal(Literal::String(value)) => string_to_timestamp_nanos(value)
+                .map_err(|e| Error::Plan(format!("invalid time '{value}': {e}"))),
+            IqlExpr::Call { name, args } if name.eq_ignore_ascii_case("now") && args.is_empty() => {
+                Ok(self.now)
+            }
+            IqlExpr::UnaryOp(UnaryOperator::Plus, expr) | IqlExpr::Nested(expr) => {
+                self.time_value(expr)
+            }
+            IqlExpr::UnaryOp(UnaryOperator::Minus, expr) => {
+                self.time_value(expr)?.checked_neg().ok_or_else(overflow)
+            }
+            IqlExpr::Binary {
+                lhs,
+                op: BinaryOperator::Add,
+                rhs,
+            } => self
+                .time_value(lhs)?
+                .checked_add(self.time_value(rhs)?)
+                .ok_or_else(overflow),
+            IqlExpr::Binary {
+                lhs,
+                op: BinaryOperator::Sub,
+                rhs,
+            } => self
+                .time_value(lhs)?
+                .checked_sub(self.time_value(rhs)?)
+                .ok_or_else(overflow),
+            _ => Err(Error::Plan(format!("invalid time expression: {expr}"))),
+        }
+    }
+
+    /// Lower the arithmetic expression `expr` for `measurement`.
+    ///
+    /// The aggregates it calls are appended to `aggregates` and replaced by the columns of their
+    /// results, see [`aggregate_name`]. Aggregates are rejected if `aggregates` is `None`.
+    fn expr(
+        &self,
+        expr: &IqlExpr,
+        measurement: &Measurement,
+        mut aggregates: Option<&mut Vec<Expr>>,
+    ) -> Result<Expr> {
+        match expr {
+            IqlExpr::VarRef { name, data_type } => {
+                let column = measurement.column(name.as_str())?;
+                Ok(match data_type {
+                    Some(VarRefDataType::Float) => cast(column, DataType::Float64),
+                    Some(VarRefDataType::Integer) => cast(column, DataType::Int64),
+                    _ => column,
+                })
+            }
+            IqlExpr::Literal(literal) => match literal {
+                Literal::Unsigned(value) => Ok(match i64::try_from(*value) {
+                    Ok(value) => lit(value),
+                    Err(_) => lit(*value),
+                }),
+                Literal::Float(value) => Ok(lit(*value)),
+                Literal::String(value) => Ok(lit(value.clone())),
+                Literal::Boolean(value) => Ok(lit(*value)),
+                Literal::Duration(duration) => Ok(lit(**duration)),
+                Literal::Regex(_) => Err(Error::Plan(format!(
+                    "regular expressions are only supported on the right of =~ and !~, got {expr}"
+                ))),
+            },
+            IqlExpr::UnaryOp(UnaryOperator::Plus, expr) | IqlExpr::Nested(expr) => {
+                self.expr(expr, measurement, aggregates)
+            }
+            IqlExpr::UnaryOp(UnaryOperator::Minus, expr) => Ok(Expr::Negative(Box::new(
+                self.expr(expr, measurement, aggregates)?,
+            ))),
+            IqlExpr::Binary { lhs, op, rhs } => {
+                let op = match op {
+                    BinaryOperator::Add => Operator::Plus,
+                    BinaryOperator::Sub => Operator::Minus,
+                    BinaryOperator::Mul => Operator::Multiply,
+                    BinaryOperator::Div => Operator::Divide,
+                    BinaryOperator::Mod => Operator::Modulo,
+                    BinaryOperator::BitwiseAnd
+                    | BinaryOperator::BitwiseOr
+                    | BinaryOperator::BitwiseXor => {
+                        return Err(Error::NotImplemented(format!("InfluxQL operator {op}")))
+                    }
+                };
+                let lhs = self.expr(lhs, measurement, aggregates.as_deref_mut())?;
+                let rhs = self.expr(rhs, measurement, aggregates)?;
+                Ok(binary_expr(lhs, op, rhs))
+            }
+            IqlExpr::Call { name, args } => {
+                let aggregate: fn(Expr) -> Expr = match name.to_lowercase().as_str() {
+                    "count" => count,
+                    "sum" => sum,
+                    "mean" => avg,
+                    "min" => min,
+                    "max" => max,
+                    _ => return Err(Error::NotImplemented(format!("InfluxQL function {name}()"))),
+                };
+                let aggregates = aggregates.ok_or_else(|| {
+                    Error::Plan(format!("aggregate function {name}() is not allowed here"))
+                })?;
+                let arg = match args.as_slice() {
+                    [IqlExpr::Wildcard(_)] => {
+                        return Err(Error::NotImplemented(format!(
+                            "InfluxQL wildcard argument of {name}()"
+                        )))
+                    }
+                    [arg] => self.expr(arg, measurement, None)?,
+                    _ => {
+                        return Err(Error::Plan(format!(
+                            "{name}() expects 1 argument, got {}",
+                            args.len()
+                        )))
+                    }
+                };
+                aggregates.push(aggregate(arg));
+                Ok(aggregate_name(aggregates.len() - 1).as_str().as_expr())
+            }
+            IqlExpr::Wildcard(_) => Err(Error::NotImplemented(
+                "InfluxQL wildcards in expressions".to_string(),
+            )),
+            IqlExpr::Distinct(_) => Err(Error::NotImplemented("InfluxQL DISTINCT".to_string())),
+            IqlExpr::BindParameter(_) => Err(Error::NotImplemented(
+                "InfluxQL bind parameters".to_string(),
+            )),
+        }
+    }
+}
+
+/// A measurement of the `FROM` clause.
+struct Measurement {
+    name: String,
+    provider: Arc<dyn TableProvider>,
+
+    /// The types of the columns of all measurements of the query, by name.
+    column_types: Arc<BTreeMap<String, DataType>>,
+}
+
+impl Measurement {
+    /// The sorted names of the columns of all measurements of the query other than the time.
+    fn columns(&self) -> Vec<String> {
+        self.column_types
+            .keys()
+            .filter(|name| *name != TIME_COLUMN_NAME)
+            .cloned()
+            .collect()
+    }
+
+    /// The sorted names of the tags of all measurements of the query, their dictionary encoded
+    /// string columns.
+    fn tags(&self) -> Vec<String> {
+        self.column_types
+            .iter()
+            .filter(|(_, data_type)| {
+                matches!(data_type, DataType::Dictionary(key, value)
+                    if key.as_ref() == &DataType::Int32 && value.as_ref() == &DataType::Utf8)
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Column `name` of the measurement, or a NULL of the type of the column in the other
+    /// measurements of the query if the measurement lacks it.
+    fn column(&self, name: &str) -> Result<Expr> {
+        if self.provider.schema().field_with_name(name).is_err() {
+            if let Some(data_type) = self.column_types.get(name) {
+                return Ok(lit(ScalarValue::try_from(data_type)?));
+            }
+        }
+        Ok(name.as_expr())
+    }
+}
+
+/// The dimensions of a `GROUP BY` clause.
+#[derive(Debug, Default)]
+struct GroupBy {
+    /// The interval and offset of `time()`, in nanoseconds.
+    time: Option<(i64, i64)>,
+
+    /// The sorted tags.
+    tags: Vec<String>,
+}
+
+/// The selection of a `SELECT` statement.
+struct Fields {
+    /// The name of the measurement, if output in the [`MEASUREMENT_COLUMN_NAME`] column.
+    measurement: Option<String>,
+
+    /// The lowered fields, aliased to their output names.
+    exprs: Vec<Expr>,
+
+    /// The output names of the fields.
+    names: Vec<String>,
+
+    /// The aggregates the fields call, replaced in `exprs` by the columns of their results.
+    aggregates: Vec<Expr>,
+}
+
+impl Fields {
+    fn new(
+        planner: &SelectPlanner<'_>,
+        select: &SelectStatement,
+        measurement: &Measurement,
+        group_by: &GroupBy,
+        with_measurement: bool,
+    ) -> Result<Self> {
+        let fields = std::iter::once(select.fields.first())
+            .chain(select.fields.rest())
+            .filter(|field| !is_time(&field.expr))
+            .collect::<Vec<_>>();
+
+        let is_aggregate = fields.iter().any(|field| calls_function(&field.expr));
+        if is_aggregate && fields.iter().any(|field| references_column(&field.expr)) {
+            return Err(Error::Plan(
+                "mixing aggregate and non-aggregate queries is not supported".to_string(),
+            ));
+        }
+
+        // The output columns before the fields
+        let mut names = [MEASUREMENT_COLUMN_NAME, TIME_COLUMN_NAME]
+            .into_iter()
+            .map(str::to_string)
+            .chain(group_by.tags.iter().cloned())
+            .collect::<HashSet<_>>();
+
+        let mut exprs = vec![];
+        let mut field_names = vec![];
+        let mut aggregates = vec![];
+        for field in fields {
+            match &field.expr {
+                IqlExpr::Wildcard(None) => {
+                    for column in measurement.columns() {
+                        let name = unique_name(&mut names, column.clone());
+                        exprs.push(measurement.column(&column)?.alias(&name));
+                        field_names.push(name);
+                    }
+                }
+                IqlExpr::Wildcard(Some(_)) => {
+                    return Err(Error::NotImplemented(
+                        "InfluxQL wildcards with a data type".to_string(),
+                    ))
+                }
+                expr => {
+                    let name = match &field.alias {
+                        Some(alias) => alias.as_str().to_string(),
+                        None => field_name(expr).unwrap_or_else(|| expr.to_string()),
+                    };
+                    let name = unique_name(&mut names, name);
+                    exprs.push(
+                        planner
+                            .expr(expr, measurement, Some(&mut aggregates))?
+                            .alias(&name),
+                    );
+                    field_names.push(name);
+                }
+            }
+        }
+
+        Ok(Self {
+            measurement: with_measurement.then(|| measurement.name.clone()),
+            exprs,
+            names: field_names,
+            aggregates,
+        })
+    }
+
+    /// A predicate on the output that is true if any field is not NULL.
+    fn any_not_null(&self) -> Expr {
+        self.names
+            .iter()
+            .map(|name| name.as_str().as_expr().is_not_null())
+            .reduce(or)
+            .unwrap_or_else(|| lit(false))
+    }
+
+    /// The output columns: the name of the measurement if requested, the time, `tags` and the
+    /// fields.
+    fn output(&self, tags: impl IntoIterator<Item = Expr>) -> Vec<Expr> {
+        self.measurement
+            .iter()
+            .map(|name| lit(name.as_str()).alias(MEASUREMENT_COLUMN_NAME))
+            .chain(std::iter::once(TIME_COLUMN_NAME.as_expr()))
+            .chain(tags)
+            .chain(self.exprs.iter().cloned())
+            .collect()
+    }
+}
+
+/// The bounds set on the time by the conjuncts of a lowered `WHERE` clause, in nanoseconds since
+/// the epoch.
+#[derive(Debug, Default)]
+struct TimeRange {
+    lower: Option<i64>,
+    upper: Option<i64>,
+}
+
+impl TimeRange {
+    fn new(condition: Option<&Expr>) -> Self {
+        let mut range = Self::default();
+        let condition = match condition {
+            Some(condition) => condition,
+            None => return range,
+        };
+
+        for expr in split_conjunction(condition) {
+            let (op, time) = match expr {
+                Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+                    match (left.as_ref(), right.as_ref()) {
+                        (
+                            Expr::Column(column),
+                            Expr::Literal(ScalarValue::TimestampNanosecond(Some(time), _)),
+                        ) if column.name == TIME_COLUMN_NAME => (*op, *time),
+                        _ => continue,
+                    }
+                }
+                _ => continue,
+            };
+
+            if matches!(op, Operator::Gt | Operator::GtEq | Operator::Eq) {
+                range.lower = Some(range.lower.map_or(time, |lower| lower.max(time)));
+            }
+            if matches!(op, Operator::Lt | Operator::LtEq | Operator::Eq) {
+                range.upper = Some(range.upper.map_or(time, |upper| upper.min(time)));
+            }
+        }
+        range
+    }
+}
+
+/// The name of the column of the result of the aggregate at `idx`.
+fn aggregate_name(idx: usize) -> String {
+    format!("__agg_{idx}")
+}
+
+/// Make `name` unique among `names`, by appending `_1`, `_2`, ... like InfluxQL, and add it.
+fn unique_name(names: &mut HashSet<String>, name: String) -> String {
+    if names.insert(name.clone()) {
+        return name;
+    }
+    (1..)
+        .map(|n| format!("{name}_{n}"))
+        .find(|candidate| names.insert(candidate.clone()))
+        .expect("unbounded range")
+}
+
+/// The InfluxQL name of field `expr`: the name of the function or column, joined with `_` for
+/// binary expressions.
+fn field_name(expr: &IqlExpr) -> Option<String> {
+    match expr {
+        IqlExpr::VarRef { name, .. } => Some(name.as_str().to_string()),
+        IqlExpr::Call { name, .. } => Some(name.to_lowercase()),
+        IqlExpr::UnaryOp(_, expr) | IqlExpr::Nested(expr) => field_name(expr),
+        IqlExpr::Binary { lhs, rhs, .. } => match (field_name(lhs), field_name(rhs)) {
+            (Some(lhs), Some(rhs)) => Some(format!("{lhs}_{rhs}")),
+            (lhs, rhs) => lhs.or(rhs),
+        },
+        _ => None,
+    }
+}
+
+/// Returns true if `expr` calls a function.
+fn calls_function(expr: &IqlExpr) -> bool {
+    match expr {
+        IqlExpr::Call { .. } => true,
+        IqlExpr::UnaryOp(_, expr) | IqlExpr::Nested(expr) => calls_function(expr),
+        IqlExpr::Binary { lhs, rhs, .. } => calls_function(lhs) || calls_function(rhs),
+        _ => false,
+    }
+}
+
+/// Returns true if `expr` references a column outside of function calls.
+fn references_column(expr: &IqlExpr) -> bool {
+    match expr {
+        IqlExpr::VarRef { .. } | IqlExpr::Wildcard(_) | IqlExpr::Distinct(_) => true,
+        IqlExpr::UnaryOp(_, expr) | IqlExpr::Nested(expr) => references_column(expr),
+        IqlExpr::Binary { lhs, rhs, .. } => references_column(lhs) || references_column(rhs),
+        _ => false,
+    }
+}
+
+/// Returns true if `expr` is the time column.
+fn is_time(expr: &IqlExpr) -> bool {
+    matches!(expr, IqlExpr::VarRef { name, .. } if name.as_str() == TIME_COLUMN_NAME)
+}
+
+/// The arithmetic expression an operand of a comparison must be.
+fn operand(condition: &ConditionalExpression) -> Result<&IqlExpr> {
+    match condition {
+        ConditionalExpression::Expr(expr) => Ok(expr.as_ref()),
+        condition => Err(Error::Plan(format!(
+            "expected an expression to compare, got {condition}"
+        ))),
+    }
+}
+
+/// The DataFusion operator of comparison `op`.
+fn comparison_operator(op: ConditionalOperator) -> Operator {
+    match op {
+        ConditionalOperator::Eq => Operator::Eq,
+        ConditionalOperator::NotEq => Operator::NotEq,
+        ConditionalOperator::Lt => Operator::Lt,
+        ConditionalOperator::LtEq => Operator::LtEq,
+        ConditionalOperator::Gt => Operator::Gt,
+        ConditionalOperator::GtEq => Operator::GtEq,
+        ConditionalOperator::EqRegex
+        | ConditionalOperator::NotEqRegex
+        | ConditionalOperator::In
+        | ConditionalOperator::And
+        | ConditionalOperator::Or => unreachable!("not a comparison: {op}"),
+    }
+}
+
+/// Returns the operator `op'` such that `a op b` is equivalent to `b op' a`.
+fn swap(op: Operator) -> Operator {
+    match op {
+        Operator::Lt => Operator::Gt,
+        Operator::LtEq => Operator::GtEq,
+        Operator::Gt => Operator::Lt,
+        Operator::GtEq => Operator::LtEq,
+        op => op,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{ArrayRef, DictionaryArray, Float64Array, TimestampNanosecondArray},
+        datatypes::{Field, Int32Type, Schema},
+        record_batch::RecordBatch,
+    };
+    use arrow_util::assert_batches_eq;
+    use datafusion::{datasource::MemTable, physical_plan::SendableRecordBatchStream};
+    use futures::TryStreamExt;
+
+    use super::*;
+    use crate::exec::{Executor, ExecutorType, TableWriter};
+
+    /// Register table `cpu` with the usage of hosts `a` and `b` at full minutes.
+    fn register_cpu(ctx: &IOxSessionContext) {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "host",
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+                false,
+            ),
+            Field::new(TIME_COLUMN_NAME, schema::TIME_DATA_TYPE(), false),
+            Field::new("usage", DataType::Float64, false),
+        ]));
+        let batch = |host: &str, minutes: &[i64], usage: &[f64]| {
+            let hosts: DictionaryArray<Int32Type> = vec![host; minutes.len()].into_iter().collect();
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(hosts) as ArrayRef,
+                    Arc::new(TimestampNanosecondArray::from_iter_values(
+                        minutes.iter().map(|m| m * 60_000_000_000),
+                    )),
+                    Arc::new(Float64Array::from(usage.to_vec())),
+                ],
+            )
+            .unwrap()
+        };
+        let partitions = vec![
+            vec![batch("a", &[1], &[1.0]), batch("b", &[0], &[10.0])],
+            vec![batch("a", &[3], &[3.0])],
+        ];
+        let table = MemTable::try_new(schema, partitions).unwrap();
+        ctx.inner().register_table("cpu", Arc::new(table)).unwrap();
+    }
+
+    /// Register table `mem` with the free memory of host `a` at minute 2.
+    fn register_mem(ctx: &IOxSessionContext) {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "host",
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+                false,
+            ),
+            Field::new(TIME_COLUMN_NAME, schema::TIME_DATA_TYPE(), false),
+            Field::new("free", DataType::Float64, false),
+        ]));
+        let hosts: DictionaryArray<Int32Type> = vec!["a"].into_iter().collect();
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(hosts) as ArrayRef,
+                Arc::new(TimestampNanosecondArray::from_iter_values([
+                    120_000_000_000,
+                ])),
+                Arc::new(Float64Array::from(vec![5.0])),
+            ],
+        )
+        .unwrap();
+        let table = MemTable::try_new(schema, vec![vec![batch]]).unwrap();
+        ctx.inner().register_table("mem", Arc::new(table)).unwrap();
+    }
+
+    /// [`TableWriter`] that keeps the table names and batches of all writes.
+    #[derive(Debug, Default)]
+    struct RecordingTableWriter {
+        writes: std::sync::Mutex<Vec<(String, Vec<RecordBatch>)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl TableWriter for RecordingTableWriter {
+        async fn write(
+            &self,
+            table_name: &str,
+            batches: SendableRecordBatchStream,
+            _api_token: Option<&str>,
+        ) -> Result<u64> {
+            let batches = batches.try_collect::<Vec<_>>().await?;
+            let rows = batches.iter().map(|batch| batch.num_rows() as u64).sum();
+            self.writes
+                .lock()
+                .unwrap()
+                .push((table_name.to_string(), batches));
+            Ok(rows)
+        }
+    }
+
+    async fn run(ctx: &IOxSessionContext, query: &str) -> Result<Vec<RecordBatch>> {
+        let plan = InfluxQLQueryPlanner::new().query(query, ctx).await?;
+        ctx.collect(plan).await
+    }
+
+    #[tokio::test]
+    async fn test_group_by_time_and_fill() {
+        let exec = Executor::new(1);
+        let ctx = exec.new_context(ExecutorType::Query);
+        register_cpu(&ctx);
+
+        let range = "time >= '1970-01-01T00:00:00Z' AND time < '1970-01-01T00:04:00Z'";
+
+        // empty buckets are NULL by default
+        let batches = run(
+            &ctx,
+            &format!(
+                "SELECT sum(usage) AS total FROM cpu WHERE host = 'b' AND {range} GROUP BY time(1m)"
+            ),
+        )
+        .await
+        .unwrap();
+        let expected = vec![
+            "+---------------------+-------+",
+            "| time                | total |",
+            "+---------------------+-------+",
+            "| 1970-01-01T00:00:00 | 10    |",
+            "| 1970-01-01T00:01:00 |       |",
+            "| 1970-01-01T00:02:00 |       |",
+            "| 1970-01-01T00:03:00 |       |",
+            "+---------------------+-------+",
+        ];
+        assert_batches_eq!(&expected, &batches);
+
+        let batches = run(
+            &ctx,
+            &format!(
+                "SELECT mean(usage) FROM cpu WHERE {range} GROUP BY time(1m), host fill(previous)"
+            ),
+        )
+        .await
+        .unwrap();
+        let expected = vec![
+            "+---------------------+------+------+",
+            "| time                | host | mean |",
+            "+---------------------+------+------+",
+            "| 1970-01-01T00:00:00 | a    |      |",
+            "| 1970-01-01T00:01:00 | a    | 1    |",
+            "| 1970-01-01T00:02:00 | a    | 1    |",
+            "| 1970-01-01T00:03:00 | a    | 3    |",
+            "| 1970-01-01T00:00:00 | b    | 10   |",
+            "| 1970-01-01T00:01:00 | b    | 10   |",
+            "| 1970-01-01T00:02:00 | b    | 10   |",
+            "| 1970-01-01T00:03:00 | b    | 10   |",
+            "+---------------------+------+------+",
+        ];
+        assert_batches_eq!(&expected, &batches);
+
+        let batches = run(
+            &ctx,
+            &format!("SELECT max(usage) FROM cpu WHERE {range} GROUP BY time(1m) fill(linear)"),
+        )
+        .await
+        .unwrap();
+        let expected = vec![
+            "+---------------------+-----+",
+            "| time                | max |",
+            "+---------------------+-----+",
+            "| 1970-01-01T00:00:00 | 10  |",
+            "| 1970-01-01T00:01:00 | 1   |",
+            "| 1970-01-01T00:02:00 | 2   |",
+            "| 1970-01-01T00:03:00 | 3   |",
+            "+---------------------+-----+",
+        ];
+        assert_batches_eq!(&expected, &batches);
+
+        let batches = run(
+            &ctx,
+            &format!("SELECT count(usage) FROM cpu WHERE {range} GROUP BY time(1m), * fill(5)"),
+        )
+        .await
+        .unwrap();
+        let expected = vec![
+            "+---------------------+------+-------+",
+            "| time                | host | count |",
+            "+---------------------+------+-------+",
+            "| 1970-01-01T00:00:00 | a    | 5     |",
+            "| 1970-01-01T00:01:00 | a    | 1     |",
+            "| 1970-01-01T00:02:00 | a    | 5     |",
+            "| 1970-01-01T00:03:00 | a    | 1     |",
+            "| 1970-01-01T00:00:00 | b    | 1     |",
+            "| 1970-01-01T00:01:00 | b    | 5     |",
+            "| 1970-01-01T00:02:00 | b    | 5     |",
+            "| 1970-01-01T00:03:00 | b    | 5     |",
+            "+---------------------+------+-------+",
+        ];
+        assert_batches_eq!(&expected, &batches);
+
+        // the offset shifts the buckets, fill(none) needs no time range
+        let batches = run(
+            &ctx,
+            "SELECT mean(usage) FROM cpu GROUP BY time(2m, 1m) fill(none)",
+        )
+        .await
+        .unwrap();
+        let expected = vec![
+            "+---------------------+------+",
+            "| time                | mean |",
+            "+---------------------+------+",
+            "| 1969-12-31T23:59:00 | 10   |",
+            "| 1970-01-01T00:01:00 | 1    |",
+            "| 1970-01-01T00:03:00 | 3    |",
+            "+---------------------+------+",
+        ];
+        assert_batches_eq!(&expected, &batches);
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn test_raw_fields() {
+        let exec = Executor::new(1);
+        let ctx = exec.new_context(ExecutorType::Query);
+        register_cpu(&ctx);
+
+        let batches = run(
+            &ctx,
+            "SELECT usage, usage * 2 FROM cpu WHERE host =~ /^a/ ORDER BY time DESC LIMIT 1",
+        )
+        .await
+        .unwrap();
+        let expected = vec![
+            "+---------------------+-------+---------+",
+            "| time                | usage | usage_1 |",
+            "+---------------------+-------+---------+",
+            "| 1970-01-01T00:03:00 | 3     | 6       |",
+            "+---------------------+-------+---------+",
+        ];
+        assert_batches_eq!(&expected, &batches);
+
+        let batches = run(&ctx, "SELECT * FROM cpu WHERE time < 60000000000")
+            .await
+            .unwrap();
+        let expected = vec![
+            "+---------------------+------+-------+",
+            "| time                | host | usage |",
+            "+---------------------+------+-------+",
+            "| 1970-01-01T00:00:00 | b    | 10    |",
+            "+---------------------+------+-------+",
+        ];
+        assert_batches_eq!(&expected, &batches);
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn test_measurements() {
+        let exec = Executor::new(1);
+        let ctx = exec.new_context(ExecutorType::Query);
+        register_cpu(&ctx);
+        register_mem(&ctx);
+
+        // columns a measurement lacks are NULL
+        let batches = run(
+            &ctx,
+            "SELECT usage, free FROM /^(cpu|mem)$/ WHERE host = 'a'",
+        )
+        .await
+        .unwrap();
+        let expected = vec![
+            "+------------------+---------------------+-------+------+",
+            "| iox::measurement | time                | usage | free |",
+            "+------------------+---------------------+-------+------+",
+            "| cpu              | 1970-01-01T00:01:00 | 1     |      |",
+            "| cpu              | 1970-01-01T00:03:00 | 3     |      |",
+            "| mem              | 1970-01-01T00:02:00 |       | 5    |",
+            "+------------------+---------------------+-------+------+",
+        ];
+        assert_batches_eq!(&expected, &batches);
+
+        let batches = run(&ctx, "SELECT max(usage) FROM mem, cpu GROUP BY host")
+            .await
+            .unwrap();
+        let expected = vec![
+            "+------------------+---------------------+------+-----+",
+            "| iox::measurement | time                | host | max |",
+            "+------------------+---------------------+------+-----+",
+            "| cpu              | 1970-01-01T00:00:00 | a    | 3   |",
+            "| cpu              | 1970-01-01T00:00:00 | b    | 10  |",
+            "| mem              | 1970-01-01T00:00:00 | a    |     |",
+            "+------------------+---------------------+------+-----+",
+        ];
+        assert_batches_eq!(&expected, &batches);
+
+        // a regular expression matching no measurement selects nothing
+        let batches = run(&ctx, "SELECT usage FROM /^disk/").await.unwrap();
+        assert!(batches.iter().all(|batch| batch.num_rows() == 0));
+
+        let err = InfluxQLQueryPlanner::new()
+            .with_max_measurements(1)
+            .query("SELECT usage FROM /^(cpu|mem)$/", &ctx)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, Error::Plan(msg) if msg.contains("more than the maximum of 1")),
+            "unexpected error: {err}"
+        );
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn test_into() {
+        let exec = Executor::new(1);
+        let ctx = exec.new_context(ExecutorType::Query);
+        register_cpu(&ctx);
+
+        let query = "SELECT mean(usage) INTO \"cpu.1m\" FROM cpu \
+            WHERE time >= '1970-01-01T00:00:00Z' AND time < '1970-01-01T00:04:00Z' \
+            GROUP BY time(1m), host";
+
+        // writing requires a table writer
+        let err = run(&ctx, query).await.unwrap_err();
+        assert!(
+            matches!(err, Error::NotImplemented(_)),
+            "unexpected error: {err}"
+        );
+
+        let writer = Arc::new(RecordingTableWriter::default());
+        let ctx = ctx.with_table_writer(Arc::clone(&writer) as _);
+        let batches = run(&ctx, query).await.unwrap();
+        let expected = vec![
+            "+-------+",
+            "| count |",
+            "+-------+",
+            "| 3     |",
+            "+-------+",
+        ];
+        assert_batches_eq!(&expected, &batches);
+
+        // the empty buckets are not written
+        let writes = writer.writes.lock().unwrap();
+        let (table_name, batches) = &writes[0];
+        assert_eq!(table_name, r#""cpu.1m""#);
+        let expected = vec![
+            "+---------------------+------+------+",
+            "| time                | host | mean |",
+            "+---------------------+------+------+",
+            "| 1970-01-01T00:01:00 | a    | 1    |",
+            "| 1970-01-01T00:03:00 | a    | 3    |",
+            "| 1970-01-01T00:00:00 | b    | 10   |",
+            "+---------------------+------+------+",
+        ];
+        assert_batches_eq!(&expected, batches);
+        drop(writes);
+
+        let err = run(&ctx, "SELECT usage INTO telegraf.autogen.cpu_copy FROM cpu")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::NotImplemented(_)),
+            "unexpected error: {err}"
+        );
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn test_errors() {
+        let exec = Executor::new(1);
+        let ctx = exec.new_context(ExecutorType::Query);
+        register_cpu(&ctx);
+
+        for query in [
+            "SELECT mean(usage), usage FROM cpu",
+            "SELECT mean(usage) FROM cpu GROUP BY time(1m)",
+            "SELECT usage FROM cpu GROUP BY time(1m)",
+            "SELECT usage FROM unknown",
+            "SELECT usage FROM cpu, unknown",
+            "SELECT usage FROM /(/",
+            "SELECT usage FROM cpu; SELECT usage FROM cpu",
+        ] {
+            let err = run(&ctx, query).await.unwrap_err();
+            assert!(
+                matches!(err, Error::Plan(_)),
+                "unexpected error for {query}: {err}"
+            );
+        }
+
+        for query in [
+            "SHOW MEASUREMENTS",
+            "SELECT usage FROM cpu SLIMIT 1",
+            "SELECT usage FROM cpu tz('Europe/Paris')",
+            "SELECT usage FROM (SELECT usage FROM cpu)",
+            "SELECT first(usage) FROM cpu",
+            "SELECT usage FROM telegraf.autogen.cpu",
+        ] {
+            let err = run(&ctx, query).await.unwrap_err();
+            assert!(
+                matches!(err, Error::NotImplemented(_)),
+                "unexpected error for {query}: {err}"
+            );
+        }
+
+        exec.join().await;
+    }
+}
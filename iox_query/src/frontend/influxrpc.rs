@@ -19,7 +19,7 @@ use datafusion::{
     common::DFSchemaRef,
     error::DataFusionError,
     logical_expr::{utils::exprlist_to_columns, ExprSchemable, LogicalPlan, LogicalPlanBuilder},
-    prelude::{when, Column, Expr},
+    prelude::{count, lit, when, Column, Expr},
 };
 use datafusion_util::AsExpr;
 use futures::{Stream, StreamExt, TryStreamExt};
@@ -415,6 +415,7 @@ impl InfluxRpcPlanner {
                                 predicate,
                                 selection,
                             )
+                            .await
                             .context(FindingColumnNamesSnafu)?;
 
                         match maybe_names {
@@ -586,6 +587,7 @@ impl InfluxRpcPlanner {
 
                     let maybe_values = chunk
                         .column_values(ctx, tag_name, predicate)
+                        .await
                         .context(FindingColumnValuesSnafu)?;
 
                     match maybe_values {
@@ -788,6 +790,119 @@ impl InfluxRpcPlanner {
         Ok(SeriesSetPlans::new(plans))
     }
 
+    /// Returns one plan per table that counts the series (distinct tag sets, regardless of the
+    /// field) that [`read_filter`](Self::read_filter) would return for `predicate`.
+    ///
+    /// Each plan produces a single row with a single `Int64` column holding the count for its
+    /// table. Only the tag and the selected field columns are read, and no series is
+    /// materialized.
+    pub async fn read_series_cardinality(
+        &self,
+        namespace: Arc<dyn QueryNamespace>,
+        rpc_predicate: InfluxRpcPredicate,
+    ) -> Result<Vec<LogicalPlan>> {
+        let ctx = self.ctx.child_ctx("planning_read_series_cardinality");
+        debug!(?rpc_predicate, "planning read_series_cardinality");
+
+        let table_predicates = rpc_predicate
+            .table_predicates(namespace.as_meta())
+            .context(CreatingPredicatesSnafu)?;
+
+        let plans = create_plans(
+            namespace,
+            &table_predicates,
+            ctx,
+            |ctx, table_name, predicate, chunks, schema| {
+                Self::read_series_cardinality_plan(
+                    ctx.child_ctx("read_series_cardinality plan"),
+                    table_name,
+                    schema,
+                    predicate,
+                    chunks,
+                )
+            },
+        )
+        .await?;
+
+        Ok(plans.into_iter().flatten().collect())
+    }
+
+    /// Creates a plan counting the series of a table, or `None` if no field of the table is
+    /// selected by `predicate`, and it therefore has no series.
+    ///
+    /// A series exists for each distinct tag set with a non-null value in any of the selected
+    /// fields. The created plan looks like:
+    ///
+    ///    Aggregate(count)
+    ///      Distinct
+    ///        Projection(tag_columns)
+    ///          Filter(field1 IS NOT NULL OR field2 IS NOT NULL ...)
+    ///            Projection(tag_columns, field_columns)
+    ///              Filter(predicate)
+    ///                Scan
+    fn read_series_cardinality_plan(
+        ctx: IOxSessionContext,
+        table_name: &str,
+        schema: Arc<Schema>,
+        predicate: &Predicate,
+        chunks: Vec<Arc<dyn QueryChunk>>,
+    ) -> Result<Option<LogicalPlan>> {
+        let scan_and_filter = ScanPlanBuilder::new(
+            Arc::from(table_name),
+            schema,
+            ctx.child_ctx("scan_and_filter planning"),
+        )
+        .with_predicate(predicate)
+        .with_chunks(chunks)
+        .build()?;
+
+        let schema = scan_and_filter.schema();
+        let fields: Vec<_> = filtered_fields_iter(&schema, predicate).collect();
+        let has_field = match fields
+            .iter()
+            .map(|f| f.name.as_expr().is_not_null())
+            .reduce(|a, b| a.or(b))
+        {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        let tags: Vec<Expr> = schema
+            .tags_iter()
+            .map(|field| field.name().as_expr())
+            .collect();
+        // A table without tags has a single series, which is counted once it has any row.
+        let series_key = if tags.is_empty() {
+            vec![lit(true).alias("series")]
+        } else {
+            tags.clone()
+        };
+
+        let plan = scan_and_filter
+            .plan_builder
+            .project(
+                tags.into_iter()
+                    .chain(fields.into_iter().map(|f| f.expr))
+                    .collect::<Vec<_>>(),
+            )
+            .context(BuildingPlanSnafu)?
+            .filter(has_field)
+            .context(BuildingPlanSnafu)?
+            .project(series_key)
+            .context(BuildingPlanSnafu)?
+            .distinct()
+            .context(BuildingPlanSnafu)?
+            .aggregate(
+                Vec::<Expr>::new(),
+                vec![count(lit(1)).alias("series_count")],
+            )
+            .context(BuildingPlanSnafu)?
+            .build()
+            .context(BuildingPlanSnafu)?;
+
+        Ok(Some(plan))
+    }
+
     /// Creates one or more GroupedSeriesSet plans that produces an
     /// output table with rows grouped according to group_columns and
     /// an aggregate function which is applied to each *series* (aka
@@ -7,6 +7,7 @@ use datafusion::{
     prelude::{col, lit_timestamp_nano},
 };
 use observability_deps::tracing::debug;
+use predicate::Predicate;
 use schema::{sort::SortKey, Schema, TIME_COLUMN_NAME};
 
 use crate::{
@@ -15,13 +16,16 @@ use crate::{
 };
 use snafu::{ResultExt, Snafu};
 
-use super::common::ScanPlanBuilder;
+use super::common::{ScanPlan, ScanPlanBuilder};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display("Chunk schema not compatible for compact plan: {}", source))]
     ChunkSchemaNotCompatible { source: schema::merge::Error },
 
+    #[snafu(display("Reorg planner got invalid output sort key: {}", source))]
+    InvalidSortKey { source: schema::sort::Error },
+
     #[snafu(display("Reorg planner got error building plan: {}", source))]
     BuildingPlan {
         source: datafusion::error::DataFusionError,
@@ -55,11 +59,44 @@ impl From<datafusion::error::DataFusionError> for Error {
 #[derive(Debug)]
 pub struct ReorgPlanner {
     ctx: IOxSessionContext,
+    predicate: Option<Predicate>,
 }
 
 impl ReorgPlanner {
     pub fn new(ctx: IOxSessionContext) -> Self {
-        Self { ctx }
+        Self {
+            ctx,
+            predicate: None,
+        }
+    }
+
+    /// Only keep the rows matching `predicate` in the output of the plans, e.g. to drop the
+    /// expired rows of a table. The rows are filtered after the deduplication.
+    pub fn with_predicate(mut self, predicate: Predicate) -> Self {
+        self.predicate = Some(predicate);
+        self
+    }
+
+    /// Creates the scan of `chunks`, sorted on `output_sort_key` and filtered by the predicate,
+    /// if any.
+    fn scan_plan<I>(
+        &self,
+        table_name: Arc<str>,
+        schema: Arc<Schema>,
+        chunks: I,
+        output_sort_key: SortKey,
+        ctx: IOxSessionContext,
+    ) -> Result<ScanPlan>
+    where
+        I: IntoIterator<Item = Arc<dyn QueryChunk>>,
+    {
+        let mut builder = ScanPlanBuilder::new(table_name, schema, ctx)
+            .with_chunks(chunks)
+            .with_output_sort_key(output_sort_key);
+        if let Some(predicate) = &self.predicate {
+            builder = builder.with_predicate(predicate);
+        }
+        builder.build().context(BuildingScanSnafu)
     }
 
     /// Creates an execution plan for the COMPACT operations which does the following:
@@ -67,11 +104,13 @@ impl ReorgPlanner {
     /// 1. Merges chunks together into a single stream
     /// 2. Deduplicates via PK as necessary
     /// 3. Sorts the result according to the requested `output_sort_key`
+    /// 4. Filters the result by the predicate, if any
     ///
     /// The plan looks like:
     ///
-    /// (Sort on output_sort_key)
-    ///   (Scan chunks) <-- any needed deduplication happens here
+    /// (Filter on predicate) [optional]
+    ///   (Sort on output_sort_key)
+    ///     (Scan chunks) <-- any needed deduplication happens here
     pub fn compact_plan<I>(
         &self,
         table_name: Arc<str>,
@@ -82,12 +121,17 @@ impl ReorgPlanner {
     where
         I: IntoIterator<Item = Arc<dyn QueryChunk>>,
     {
-        let scan_plan =
-            ScanPlanBuilder::new(table_name, schema, self.ctx.child_ctx("compact_plan"))
-                .with_chunks(chunks)
-                .with_output_sort_key(output_sort_key)
-                .build()
-                .context(BuildingScanSnafu)?;
+        output_sort_key
+            .validate(&schema)
+            .context(InvalidSortKeySnafu)?;
+
+        let scan_plan = self.scan_plan(
+            table_name,
+            schema,
+            chunks,
+            output_sort_key,
+            self.ctx.child_ctx("compact_plan"),
+        )?;
 
         let plan = scan_plan.plan_builder.build()?;
 
@@ -102,14 +146,16 @@ impl ReorgPlanner {
     /// 1. Merges chunks together into a single stream
     /// 2. Deduplicates via PK as necessary
     /// 3. Sorts the result according to the requested output_sort_key
-    /// 4. Splits the stream on value of the `time` column: Those
+    /// 4. Filters the result by the predicate, if any
+    /// 5. Splits the stream on value of the `time` column: Those
     ///    rows that are on or before the time and those that are after
     ///
     /// The plan looks like:
     ///
     /// (Split on Time)
-    ///   (Sort on output_sort)
-    ///     (Scan chunks) <-- any needed deduplication happens here
+    ///   (Filter on predicate) [optional]
+    ///     (Sort on output_sort)
+    ///       (Scan chunks) <-- any needed deduplication happens here
     ///
     /// The output execution plan has n "output streams" (DataFusion partition):
     /// Stream 0: Rows that have `time` *on or before* the `split_times[0]`
@@ -163,11 +209,17 @@ impl ReorgPlanner {
             panic!("Split plan does not accept empty split_times");
         }
 
-        let scan_plan = ScanPlanBuilder::new(table_name, schema, self.ctx.child_ctx("split_plan"))
-            .with_chunks(chunks)
-            .with_output_sort_key(output_sort_key)
-            .build()
-            .context(BuildingScanSnafu)?;
+        output_sort_key
+            .validate(&schema)
+            .context(InvalidSortKeySnafu)?;
+
+        let scan_plan = self.scan_plan(
+            table_name,
+            schema,
+            chunks,
+            output_sort_key,
+            self.ctx.child_ctx("split_plan"),
+        )?;
 
         let mut split_exprs = Vec::with_capacity(split_times.len());
         // time <= split_times[0]
@@ -203,6 +255,7 @@ impl ReorgPlanner {
 #[cfg(test)]
 mod test {
     use arrow_util::assert_batches_eq;
+    use datafusion::prelude::lit;
     use datafusion_util::{test_collect, test_collect_partition};
     use schema::merge::SchemaMerger;
     use schema::sort::SortKeyBuilder;
@@ -411,6 +464,85 @@ mod test {
         executor.join().await;
     }
 
+    #[tokio::test]
+    async fn test_compact_plan_with_predicate() {
+        test_helpers::maybe_start_logging();
+
+        let (schema, chunks) = get_test_chunks().await;
+
+        let sort_key = SortKeyBuilder::with_capacity(2)
+            .with_col_opts("tag1", true, true)
+            .with_col_opts(TIME_COLUMN_NAME, false, false)
+            .build();
+
+        let predicate = Predicate::new().with_expr(col("field_int").gt(lit(60i64)));
+        let compact_plan = ReorgPlanner::new(IOxSessionContext::with_testing())
+            .with_predicate(predicate)
+            .compact_plan(Arc::from("t"), schema, chunks, sort_key)
+            .expect("created compact plan");
+
+        let executor = Executor::new(1);
+        let physical_plan = executor
+            .new_context(ExecutorType::Reorg)
+            .create_physical_plan(&compact_plan)
+            .await
+            .unwrap();
+
+        let batches = test_collect(physical_plan).await;
+
+        // the rows not matching the predicate are dropped, the others still sorted
+        let expected = vec![
+            "+-----------+------------+------+--------------------------------+",
+            "| field_int | field_int2 | tag1 | time                           |",
+            "+-----------+------------+------+--------------------------------+",
+            "| 1000      | 1000       | WA   | 1970-01-01T00:00:00.000028Z    |",
+            "| 70        | 70         | UT   | 1970-01-01T00:00:00.000220Z    |",
+            "| 1000      |            | MT   | 1970-01-01T00:00:00.000001Z    |",
+            "| 70        |            | CT   | 1970-01-01T00:00:00.000000100Z |",
+            "| 100       |            | AL   | 1970-01-01T00:00:00.000000050Z |",
+            "+-----------+------------+------+--------------------------------+",
+        ];
+
+        assert_batches_eq!(&expected, &batches);
+
+        executor.join().await;
+    }
+
+    #[tokio::test]
+    async fn test_plan_invalid_sort_key() {
+        test_helpers::maybe_start_logging();
+
+        let (schema, chunks) = get_test_chunks().await;
+
+        for column in ["field_int", "tag2"] {
+            let sort_key = SortKeyBuilder::with_capacity(2)
+                .with_col("tag1")
+                .with_col(column)
+                .build();
+
+            let err = ReorgPlanner::new(IOxSessionContext::with_testing())
+                .compact_plan(
+                    Arc::from("t"),
+                    Arc::clone(&schema),
+                    chunks.clone(),
+                    sort_key.clone(),
+                )
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidSortKey { .. }), "{}", err);
+
+            let err = ReorgPlanner::new(IOxSessionContext::with_testing())
+                .split_plan(
+                    Arc::from("t"),
+                    Arc::clone(&schema),
+                    chunks.clone(),
+                    sort_key,
+                    vec![1000],
+                )
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidSortKey { .. }), "{}", err);
+        }
+    }
+
     #[tokio::test]
     async fn test_split_plan() {
         test_helpers::maybe_start_logging();
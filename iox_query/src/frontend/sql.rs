@@ -1,7 +1,7 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::exec::context::IOxSessionContext;
-use datafusion::{error::Result, physical_plan::ExecutionPlan};
+use datafusion::{error::Result, physical_plan::ExecutionPlan, scalar::ScalarValue};
 
 /// This struct can create plans for running SQL queries against databases
 #[derive(Debug, Default)]
@@ -21,4 +21,15 @@ impl SqlQueryPlanner {
     ) -> Result<Arc<dyn ExecutionPlan>> {
         ctx.prepare_sql(query).await
     }
+
+    /// Plan a SQL query that references the named parameters `$<name>`, binding them to the
+    /// values in `params`.
+    pub async fn query_with_params(
+        &self,
+        query: &str,
+        params: &HashMap<String, ScalarValue>,
+        ctx: &IOxSessionContext,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        ctx.prepare_sql_with_params(query, params).await
+    }
 }
@@ -30,6 +30,8 @@ pub mod frontend;
 pub mod plan;
 pub mod provider;
 pub mod pruning;
+pub mod query_range;
+pub mod row_ttl;
 pub mod statistics;
 pub mod util;
 
@@ -204,6 +206,7 @@ impl QueryChunkData {
 }
 
 /// Collection of data that shares the same partition key
+#[async_trait]
 pub trait QueryChunk: QueryChunkMeta + Debug + Send + Sync + 'static {
     /// returns the Id of this chunk. Ids are unique within a
     /// particular partition.
@@ -230,7 +233,10 @@ pub trait QueryChunk: QueryChunkMeta + Debug + Send + Sync + 'static {
     /// table that have at least one row that matches `predicate`, if
     /// the predicate can be evaluated entirely on the metadata of
     /// this Chunk. Returns `None` otherwise
-    fn column_names(
+    ///
+    /// Implementations may perform IO, e.g. to read the metadata of a chunk
+    /// from object store on demand.
+    async fn column_names(
         &self,
         ctx: IOxSessionContext,
         predicate: &Predicate,
@@ -242,7 +248,7 @@ pub trait QueryChunk: QueryChunkMeta + Debug + Send + Sync + 'static {
     /// on the metadata of this Chunk. Returns `None` otherwise
     ///
     /// The requested columns must all have String type.
-    fn column_values(
+    async fn column_values(
         &self,
         ctx: IOxSessionContext,
         column_name: &str,
@@ -38,11 +38,16 @@ use snafu::{ResultExt, Snafu};
 mod adapter;
 mod deduplicate;
 pub mod overlap;
+mod parquet_metadata;
 mod physical;
 mod record_batch_exec;
+mod scan_limit;
 use self::overlap::group_potential_duplicates;
 pub use deduplicate::{DeduplicateExec, RecordBatchDeduplicator};
+pub(crate) use parquet_metadata::ParquetMetadataProvider;
+pub use parquet_metadata::{fetch_parquet_metadata, ParquetMetadataSource};
 pub(crate) use physical::chunks_to_physical_nodes;
+pub use scan_limit::ScanLimits;
 
 #[cfg(test)]
 pub(crate) use record_batch_exec::RecordBatchesExec;
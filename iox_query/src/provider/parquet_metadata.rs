@@ -0,0 +1,261 @@
+//! Reading parquet files with metadata from a [`ParquetMetadataSource`], e.g. a cache.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use data_types::ParquetFileId;
+use datafusion::{
+    error::{DataFusionError, Result},
+    physical_plan::{
+        file_format::{FileMeta, ParquetFileMetrics, ParquetFileReaderFactory},
+        metrics::ExecutionPlanMetricsSet,
+    },
+};
+use futures::{future::BoxFuture, FutureExt, TryFutureExt};
+use object_store::{ObjectMeta, ObjectStore};
+use parquet::{
+    arrow::async_reader::AsyncFileReader,
+    errors::ParquetError,
+    file::{
+        footer::{decode_footer, decode_metadata},
+        metadata::ParquetMetaData,
+        FOOTER_SIZE,
+    },
+};
+use std::{fmt::Debug, ops::Range, sync::Arc};
+
+use crate::exec::QueryStatistics;
+
+/// Source of the decoded metadata (i.e. the footer, including the row group statistics) of
+/// parquet files.
+///
+/// Set for a query via
+/// [`IOxSessionContext::with_parquet_metadata`](crate::exec::IOxSessionContext::with_parquet_metadata).
+/// Scans of parquet files with a known [`ParquetFileId`] then take the metadata from this source
+/// instead of fetching and decoding the footer of the file.
+#[async_trait]
+pub trait ParquetMetadataSource: Debug + Send + Sync + 'static {
+    /// Get the metadata of the parquet file `id` stored at `object_meta`.
+    ///
+    /// Returns `None` if the metadata is not available, e.g. because the file does not exist.
+    async fn metadata(
+        &self,
+        id: ParquetFileId,
+        object_meta: &ObjectMeta,
+    ) -> Option<Arc<ParquetMetaData>>;
+
+    /// Same as [`metadata`](Self::metadata), but also returns whether the metadata was cached.
+    ///
+    /// Sources that do not cache the metadata report every lookup as not cached.
+    async fn metadata_with_status(
+        &self,
+        id: ParquetFileId,
+        object_meta: &ObjectMeta,
+    ) -> (Option<Arc<ParquetMetaData>>, bool) {
+        (self.metadata(id, object_meta).await, false)
+    }
+}
+
+/// Session extension that carries the [`ParquetMetadataSource`] of a query.
+#[derive(Debug, Clone)]
+pub(crate) struct ParquetMetadataProvider(pub(crate) Arc<dyn ParquetMetadataSource>);
+
+/// Fetch and decode the metadata of the parquet file stored at `object_meta`.
+///
+/// Only the footer of the file is read. Returns `None` if the file does not exist.
+pub async fn fetch_parquet_metadata(
+    store: &dyn ObjectStore,
+    object_meta: &ObjectMeta,
+) -> Result<Option<ParquetMetaData>> {
+    let size = object_meta.size;
+    if size < FOOTER_SIZE {
+        return Err(DataFusionError::Execution(format!(
+            "parquet file {} is too small: {} bytes",
+            object_meta.location, size
+        )));
+    }
+
+    let footer = match get_range(store, object_meta, size - FOOTER_SIZE..size).await? {
+        Some(footer) => footer,
+        None => return Ok(None),
+    };
+    let footer: &[u8; FOOTER_SIZE] = footer.as_ref().try_into().map_err(|_| {
+        DataFusionError::Execution(format!(
+            "invalid footer of parquet file {}",
+            object_meta.location
+        ))
+    })?;
+    let metadata_len = decode_footer(footer)?;
+    if metadata_len + FOOTER_SIZE > size {
+        return Err(DataFusionError::Execution(format!(
+            "parquet file {} is too small for its {} bytes of metadata",
+            object_meta.location, metadata_len
+        )));
+    }
+
+    let metadata_start = size - FOOTER_SIZE - metadata_len;
+    match get_range(store, object_meta, metadata_start..size - FOOTER_SIZE).await? {
+        Some(data) => Ok(Some(decode_metadata(&data)?)),
+        None => Ok(None),
+    }
+}
+
+/// Read `range` of the file at `object_meta`, returning `None` if the file does not exist.
+async fn get_range(
+    store: &dyn ObjectStore,
+    object_meta: &ObjectMeta,
+    range: Range<usize>,
+) -> Result<Option<Bytes>> {
+    match store.get_range(&object_meta.location, range).await {
+        Ok(data) => Ok(Some(data)),
+        Err(object_store::Error::NotFound { .. }) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Creates readers that take the parquet metadata from a [`ParquetMetadataSource`].
+///
+/// The file data is read from `store`. Files without a [`ParquetFileId`] in the
+/// [extensions](datafusion::datasource::listing::PartitionedFile::extensions) fall back to
+/// fetching their footer. The metadata lookups are recorded in `statistics`, if set.
+#[derive(Debug)]
+pub(crate) struct CachedMetadataReaderFactory {
+    store: Arc<dyn ObjectStore>,
+    source: Arc<dyn ParquetMetadataSource>,
+    statistics: Option<Arc<QueryStatistics>>,
+}
+
+impl CachedMetadataReaderFactory {
+    pub(crate) fn new(
+        store: Arc<dyn ObjectStore>,
+        source: Arc<dyn ParquetMetadataSource>,
+        statistics: Option<Arc<QueryStatistics>>,
+    ) -> Self {
+        Self {
+            store,
+            source,
+            statistics,
+        }
+    }
+}
+
+impl ParquetFileReaderFactory for CachedMetadataReaderFactory {
+    fn create_reader(
+        &self,
+        partition_index: usize,
+        file_meta: FileMeta,
+        _metadata_size_hint: Option<usize>,
+        metrics: &ExecutionPlanMetricsSet,
+    ) -> Result<Box<dyn AsyncFileReader + Send>> {
+        let metrics =
+            ParquetFileMetrics::new(partition_index, file_meta.location().as_ref(), metrics);
+        let parquet_file_id = file_meta
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.downcast_ref::<ParquetFileId>())
+            .copied();
+
+        Ok(Box::new(CachedMetadataReader {
+            store: Arc::clone(&self.store),
+            source: Arc::clone(&self.source),
+            statistics: self.statistics.clone(),
+            object_meta: file_meta.object_meta,
+            parquet_file_id,
+            metrics,
+        }))
+    }
+}
+
+/// Reader created by [`CachedMetadataReaderFactory`].
+struct CachedMetadataReader {
+    store: Arc<dyn ObjectStore>,
+    source: Arc<dyn ParquetMetadataSource>,
+    statistics: Option<Arc<QueryStatistics>>,
+    object_meta: ObjectMeta,
+    parquet_file_id: Option<ParquetFileId>,
+    metrics: ParquetFileMetrics,
+}
+
+impl AsyncFileReader for CachedMetadataReader {
+    fn get_bytes(&mut self, range: Range<usize>) -> BoxFuture<'_, parquet::errors::Result<Bytes>> {
+        self.metrics.bytes_scanned.add(range.end - range.start);
+
+        self.store
+            .get_range(&self.object_meta.location, range)
+            .map_err(|e| ParquetError::General(format!("AsyncFileReader::get_bytes error: {}", e)))
+            .boxed()
+    }
+
+    fn get_metadata(&mut self) -> BoxFuture<'_, parquet::errors::Result<Arc<ParquetMetaData>>> {
+        async move {
+            let metadata = match self.parquet_file_id {
+                Some(id) => {
+                    let (metadata, cached) = self
+                        .source
+                        .metadata_with_status(id, &self.object_meta)
+                        .await;
+                    if let Some(statistics) = &self.statistics {
+                        statistics.record_cache_lookup(cached);
+                    }
+                    metadata
+                }
+                None => fetch_parquet_metadata(self.store.as_ref(), &self.object_meta)
+                    .await
+                    .map_err(|e| ParquetError::General(e.to_string()))?
+                    .map(Arc::new),
+            };
+
+            metadata.ok_or_else(|| {
+                ParquetError::General(format!(
+                    "metadata of parquet file {} not available",
+                    self.object_meta.location
+                ))
+            })
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::{
+        array::Int64Array,
+        datatypes::{DataType, Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use object_store::{memory::InMemory, path::Path};
+    use parquet::arrow::ArrowWriter;
+
+    #[tokio::test]
+    async fn test_fetch_parquet_metadata() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let batch =
+            RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(vec![1, 2, 3]))]).unwrap();
+        let mut data = vec![];
+        let mut writer = ArrowWriter::try_new(&mut data, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let store = InMemory::new();
+        let location = Path::from("file.parquet");
+        let object_meta = ObjectMeta {
+            location: location.clone(),
+            last_modified: Default::default(),
+            size: data.len(),
+        };
+
+        // missing files have no metadata
+        assert!(fetch_parquet_metadata(&store, &object_meta)
+            .await
+            .unwrap()
+            .is_none());
+
+        store.put(&location, Bytes::from(data)).await.unwrap();
+        let metadata = fetch_parquet_metadata(&store, &object_meta)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(metadata.file_metadata().num_rows(), 3);
+        assert_eq!(metadata.num_row_groups(), 1);
+    }
+}
@@ -1,6 +1,14 @@
 //! Implementation of a DataFusion PhysicalPlan node across partition chunks
 
-use crate::{provider::record_batch_exec::RecordBatchesExec, QueryChunk, QueryChunkData};
+use crate::{
+    exec::QueryStatistics,
+    provider::{
+        parquet_metadata::{CachedMetadataReaderFactory, ParquetMetadataProvider},
+        record_batch_exec::RecordBatchesExec,
+        scan_limit::{ScanLimitExec, ScanLimits},
+    },
+    QueryChunk, QueryChunkData,
+};
 use arrow::{datatypes::SchemaRef, record_batch::RecordBatch};
 use data_types::TableSummary;
 use datafusion::{
@@ -13,10 +21,11 @@ use datafusion::{
         ExecutionPlan, Statistics,
     },
 };
-use object_store::ObjectMeta;
+use observability_deps::tracing::warn;
 use predicate::Predicate;
 use schema::Schema;
 use std::{
+    any::Any,
     collections::{hash_map::Entry, HashMap},
     sync::Arc,
 };
@@ -30,6 +39,9 @@ use std::{
 ///
 /// Parquet chunks will be turned into a [`ParquetExec`] per store, each of them with
 /// [`target_partitions`](datafusion::execution::context::SessionConfig::target_partitions) file groups.
+/// If the session carries [`ScanLimits`], each of these nodes is wrapped into a [`ScanLimitExec`].
+/// If the session carries a [`ParquetMetadataSource`](crate::provider::ParquetMetadataSource),
+/// the nodes take the metadata of files with a known ID from it.
 ///
 /// If this function creates more than one physical node, they will be combined using an [`UnionExec`]. Otherwise, a
 /// single node will be returned directly.
@@ -52,7 +64,8 @@ pub fn chunks_to_physical_nodes(
     }
 
     let mut record_batch_chunks: Vec<(SchemaRef, Vec<RecordBatch>, Arc<TableSummary>)> = vec![];
-    let mut parquet_chunks: HashMap<String, (ObjectStoreUrl, Vec<ObjectMeta>)> = HashMap::new();
+    let mut parquet_chunks: HashMap<String, (ObjectStoreUrl, Vec<PartitionedFile>)> =
+        HashMap::new();
 
     for chunk in &chunks {
         match chunk.data() {
@@ -61,15 +74,20 @@ pub fn chunks_to_physical_nodes(
             }
             QueryChunkData::Parquet(parquet_input) => {
                 let url_str = parquet_input.object_store_url.as_str().to_owned();
+                let file = PartitionedFile {
+                    object_meta: parquet_input.object_meta,
+                    partition_values: vec![],
+                    range: None,
+                    extensions: parquet_input
+                        .parquet_file_id
+                        .map(|id| Arc::new(id) as Arc<dyn Any + Send + Sync>),
+                };
                 match parquet_chunks.entry(url_str) {
                     Entry::Occupied(mut o) => {
-                        o.get_mut().1.push(parquet_input.object_meta);
+                        o.get_mut().1.push(file);
                     }
                     Entry::Vacant(v) => {
-                        v.insert((
-                            parquet_input.object_store_url,
-                            vec![parquet_input.object_meta],
-                        ));
+                        v.insert((parquet_input.object_store_url, vec![file]));
                     }
                 }
             }
@@ -86,16 +104,29 @@ pub fn chunks_to_physical_nodes(
     let mut parquet_chunks: Vec<_> = parquet_chunks.into_iter().collect();
     parquet_chunks.sort_by_key(|(url_str, _)| url_str.clone());
     let target_partitions = context.session_config().target_partitions;
-    for (_url_str, (url, chunks)) in parquet_chunks {
-        let file_groups = distribute(
-            chunks.into_iter().map(|object_meta| PartitionedFile {
-                object_meta,
-                partition_values: vec![],
-                range: None,
-                extensions: None,
-            }),
-            target_partitions,
-        );
+    let scan_limits = context
+        .session_config()
+        .get_extension::<ScanLimits>()
+        .filter(|limits| !limits.is_unlimited());
+    let metadata_provider = context
+        .session_config()
+        .get_extension::<ParquetMetadataProvider>();
+    for (_url_str, (url, files)) in parquet_chunks {
+        let reader_factory = metadata_provider.as_ref().and_then(|provider| {
+            match context.runtime_env().object_store(&url) {
+                Ok(store) => Some(CachedMetadataReaderFactory::new(
+                    store,
+                    Arc::clone(&provider.0),
+                    context.session_config().get_extension::<QueryStatistics>(),
+                )),
+                Err(e) => {
+                    // the scan itself will fail with the same error
+                    warn!(%e, %url, "cannot get object store for parquet metadata lookup");
+                    None
+                }
+            }
+        });
+        let file_groups = distribute(files, target_partitions);
         let base_config = FileScanConfig {
             object_store_url: url,
             file_schema: iox_schema.as_arrow(),
@@ -106,11 +137,18 @@ pub fn chunks_to_physical_nodes(
             table_partition_cols: vec![],
             config_options: context.session_config().config_options(),
         };
-        output_nodes.push(Arc::new(ParquetExec::new(
-            base_config,
-            predicate.filter_expr(),
-            None,
-        )));
+        let mut parquet_exec = ParquetExec::new(base_config, predicate.filter_expr(), None);
+        if let Some(reader_factory) = reader_factory {
+            parquet_exec = parquet_exec.with_parquet_file_reader_factory(Arc::new(reader_factory));
+        }
+        let parquet_exec = Arc::new(parquet_exec);
+        match &scan_limits {
+            Some(limits) => output_nodes.push(Arc::new(ScanLimitExec::new(
+                parquet_exec,
+                limits.as_ref().clone(),
+            ))),
+            None => output_nodes.push(parquet_exec),
+        }
     }
 
     assert!(!output_nodes.is_empty());
@@ -0,0 +1,300 @@
+//! Implementation of a DataFusion PhysicalPlan node that limits concurrent object store scans
+
+use arrow::{datatypes::SchemaRef, error::Result as ArrowResult, record_batch::RecordBatch};
+use datafusion::{
+    execution::context::TaskContext,
+    physical_plan::{
+        expressions::PhysicalSortExpr, DisplayFormatType, Distribution, ExecutionPlan,
+        Partitioning, RecordBatchStream, SendableRecordBatchStream, Statistics,
+    },
+};
+use futures::{future::BoxFuture, FutureExt, Stream, StreamExt};
+use std::{
+    fmt,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tracker::{InstrumentedAsyncOwnedSemaphorePermit, InstrumentedAsyncSemaphore};
+
+/// Semaphores bounding the number of object store scans that are in flight at the same time.
+///
+/// Set for a query via
+/// [`IOxSessionContext::with_scan_limits`](crate::exec::IOxSessionContext::with_scan_limits).
+/// Every scan of parquet files then holds a permit of both semaphores while it waits for data.
+#[derive(Debug, Clone, Default)]
+pub struct ScanLimits {
+    /// Semaphore shared by all queries, if any.
+    global: Option<Arc<InstrumentedAsyncSemaphore>>,
+
+    /// Semaphore of this query, if any.
+    per_query: Option<Arc<InstrumentedAsyncSemaphore>>,
+}
+
+impl ScanLimits {
+    /// Create new limits from a `global` semaphore shared by all queries and a `per_query`
+    /// semaphore that is only used by a single query.
+    pub fn new(
+        global: Option<Arc<InstrumentedAsyncSemaphore>>,
+        per_query: Option<Arc<InstrumentedAsyncSemaphore>>,
+    ) -> Self {
+        Self { global, per_query }
+    }
+
+    /// Returns true if no scans are limited.
+    pub fn is_unlimited(&self) -> bool {
+        self.global.is_none() && self.per_query.is_none()
+    }
+
+    /// Wait until a scan may start.
+    ///
+    /// The per-query permit is acquired first so that a query that exhausted its own limit does
+    /// not hold on to global permits that other queries could use.
+    async fn acquire(self) -> ScanPermits {
+        let per_query = match &self.per_query {
+            Some(semaphore) => Some(acquire(semaphore).await),
+            None => None,
+        };
+        let global = match &self.global {
+            Some(semaphore) => Some(acquire(semaphore).await),
+            None => None,
+        };
+
+        ScanPermits {
+            _per_query: per_query,
+            _global: global,
+        }
+    }
+}
+
+async fn acquire(
+    semaphore: &Arc<InstrumentedAsyncSemaphore>,
+) -> InstrumentedAsyncOwnedSemaphorePermit {
+    semaphore
+        .acquire_owned(None)
+        .await
+        .expect("scan semaphores are never closed")
+}
+
+/// Permits held by a scan that waits for data.
+#[derive(Debug)]
+struct ScanPermits {
+    _per_query: Option<InstrumentedAsyncOwnedSemaphorePermit>,
+    _global: Option<InstrumentedAsyncOwnedSemaphorePermit>,
+}
+
+/// Limits the number of concurrent scans of its input according to [`ScanLimits`].
+///
+/// Each output partition holds permits only while it waits for the next record batch of its
+/// input and releases them as soon as the batch is returned. Partitions whose output is not
+/// consumed, e.g. because a merge waits for another partition, therefore never block other
+/// scans.
+#[derive(Debug)]
+pub(crate) struct ScanLimitExec {
+    input: Arc<dyn ExecutionPlan>,
+    limits: ScanLimits,
+}
+
+impl ScanLimitExec {
+    pub fn new(input: Arc<dyn ExecutionPlan>, limits: ScanLimits) -> Self {
+        Self { input, limits }
+    }
+}
+
+impl ExecutionPlan for ScanLimitExec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::UnspecifiedDistribution
+    }
+
+    fn maintains_input_order(&self) -> bool {
+        true
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> datafusion::error::Result<Arc<dyn ExecutionPlan>> {
+        assert_eq!(children.len(), 1, "ScanLimitExec has exactly one input");
+
+        Ok(Arc::new(Self::new(
+            Arc::clone(&children[0]),
+            self.limits.clone(),
+        )))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> datafusion::error::Result<SendableRecordBatchStream> {
+        let inner = self.input.execute(partition, context)?;
+
+        Ok(Box::pin(ScanLimitStream {
+            inner,
+            limits: self.limits.clone(),
+            state: State::Idle,
+        }))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(f, "ScanLimitExec"),
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.input.statistics()
+    }
+}
+
+/// State of a [`ScanLimitStream`].
+enum State {
+    /// No record batch was requested yet.
+    Idle,
+
+    /// Waiting for the permits to request the next record batch.
+    Acquiring(BoxFuture<'static, ScanPermits>),
+
+    /// Waiting for the next record batch of the input.
+    Scanning(ScanPermits),
+
+    /// The input is exhausted.
+    Done,
+}
+
+/// Stream of [`ScanLimitExec`].
+struct ScanLimitStream {
+    inner: SendableRecordBatchStream,
+    limits: ScanLimits,
+    state: State,
+}
+
+impl RecordBatchStream for ScanLimitStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+impl Stream for ScanLimitStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            match &mut this.state {
+                State::Idle => {
+                    this.state = State::Acquiring(this.limits.clone().acquire().boxed());
+                }
+                State::Acquiring(fut) => {
+                    let permits = futures::ready!(fut.poll_unpin(cx));
+                    this.state = State::Scanning(permits);
+                }
+                State::Scanning(_) => {
+                    let item = futures::ready!(this.inner.poll_next_unpin(cx));
+
+                    // release the permits before the batch is handed out
+                    this.state = match item {
+                        Some(_) => State::Idle,
+                        None => State::Done,
+                    };
+                    return Poll::Ready(item);
+                }
+                State::Done => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::{
+        array::Int64Array,
+        datatypes::{DataType, Field, Schema},
+    };
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion_util::test_execute_partition;
+    use tracker::AsyncSemaphoreMetrics;
+
+    #[tokio::test]
+    async fn test_permits_are_released_between_batches() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int64Array::from(vec![1, 2]))],
+        )
+        .unwrap();
+        let input = Arc::new(
+            MemoryExec::try_new(
+                &[vec![batch.clone(), batch.clone()], vec![batch]],
+                schema,
+                None,
+            )
+            .unwrap(),
+        );
+
+        let metrics = Arc::new(AsyncSemaphoreMetrics::new_unregistered());
+        let global = Arc::new(metrics.new_semaphore(1));
+        let per_query = Arc::new(metrics.new_semaphore(1));
+        let exec: Arc<dyn ExecutionPlan> = Arc::new(ScanLimitExec::new(
+            input,
+            ScanLimits::new(Some(Arc::clone(&global)), Some(Arc::clone(&per_query))),
+        ));
+
+        // Both partitions share a single permit but can be read interleaved because no permit is
+        // held while a batch is not requested.
+        let mut stream_1 = test_execute_partition(Arc::clone(&exec), 0).await;
+        let mut stream_2 = test_execute_partition(exec, 1).await;
+        assert_eq!(stream_1.next().await.unwrap().unwrap().num_rows(), 2);
+        assert_eq!(global.available_permits(), 1);
+        assert_eq!(stream_2.next().await.unwrap().unwrap().num_rows(), 2);
+        assert!(stream_2.next().await.is_none());
+        assert_eq!(stream_1.next().await.unwrap().unwrap().num_rows(), 2);
+        assert!(stream_1.next().await.is_none());
+
+        assert_eq!(global.available_permits(), 1);
+        assert_eq!(per_query.available_permits(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scans_wait_for_permits() {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)]));
+        let input = Arc::new(MemoryExec::try_new(&[vec![]], schema, None).unwrap());
+
+        let metrics = Arc::new(AsyncSemaphoreMetrics::new_unregistered());
+        let global = Arc::new(metrics.new_semaphore(1));
+        let exec = Arc::new(ScanLimitExec::new(
+            input,
+            ScanLimits::new(Some(Arc::clone(&global)), None),
+        ));
+
+        let permit = global.acquire(None).await.unwrap();
+        let mut stream = test_execute_partition(exec, 0).await;
+        let mut next = stream.next();
+        assert!(futures::poll!(&mut next).is_pending());
+
+        drop(permit);
+        assert!(next.await.is_none());
+    }
+}
@@ -0,0 +1,300 @@
+//! Limits of the time range covered by queries.
+//!
+//! A namespace may set a default time range, queried by queries that do not restrict the time,
+//! and a maximum time range, so that a query can not accidentally scan all of its data.
+
+use std::time::Duration;
+
+use datafusion::{
+    logical_expr::{BinaryExpr, Operator},
+    optimizer::utils::split_conjunction,
+    prelude::{col, lit_timestamp_nano, Expr},
+    scalar::ScalarValue,
+};
+use schema::TIME_COLUMN_NAME;
+use snafu::Snafu;
+
+/// Errors returned for queries violating the [`QueryRanges`] of a namespace.
+#[derive(Debug, Snafu, PartialEq, Eq)]
+pub enum QueryRangeError {
+    #[snafu(display(
+        "query of table {table} must restrict the time to a range of at most {:?}",
+        Duration::from_nanos(*max_range_ns as u64)
+    ))]
+    Unbounded { table: String, max_range_ns: i64 },
+
+    #[snafu(display(
+        "query of table {table} covers a time range of {:?}, exceeding the maximum of {:?}",
+        Duration::from_nanos(*range_ns as u64),
+        Duration::from_nanos(*max_range_ns as u64)
+    ))]
+    TooLong {
+        table: String,
+        range_ns: i64,
+        max_range_ns: i64,
+    },
+}
+
+/// The time ranges the queries of a namespace may cover.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryRanges {
+    /// The time range in ns ending now queried by queries that do not set a lower bound on the
+    /// time, if any.
+    pub default_range_ns: Option<i64>,
+
+    /// The longest time range in ns a query may cover, if any.
+    pub max_range_ns: Option<i64>,
+}
+
+impl QueryRanges {
+    /// Check the time range selected by `filters` of a query of `table` at time `now_ns`.
+    ///
+    /// Returns the filter restricting the query to the default time range if `filters` set no
+    /// lower bound on the time. A query without an upper bound covers the time range up to now.
+    pub fn check(
+        &self,
+        table: &str,
+        filters: &[Expr],
+        now_ns: i64,
+    ) -> Result<Option<Expr>, QueryRangeError> {
+        if self.default_range_ns.is_none() && self.max_range_ns.is_none() {
+            return Ok(None);
+        }
+
+        let (mut lower, upper) = time_bounds(filters);
+
+        let mut default_filter = None;
+        if let (None, Some(default_range_ns)) = (lower, self.default_range_ns) {
+            let start = upper.unwrap_or(now_ns).saturating_sub(default_range_ns);
+            default_filter = Some(col(TIME_COLUMN_NAME).gt_eq(lit_timestamp_nano(start)));
+            lower = Some(start);
+        }
+
+        if let Some(max_range_ns) = self.max_range_ns {
+            let lower = match lower {
+                Some(lower) => lower,
+                None => {
+                    return Err(QueryRangeError::Unbounded {
+                        table: table.to_string(),
+                        max_range_ns,
+                    })
+                }
+            };
+
+            let range_ns = upper.unwrap_or(now_ns).saturating_sub(lower);
+            if range_ns > max_range_ns {
+                return Err(QueryRangeError::TooLong {
+                    table: table.to_string(),
+                    range_ns,
+                    max_range_ns,
+                });
+            }
+        }
+
+        Ok(default_filter)
+    }
+}
+
+/// Returns the lower and upper bound on the time set by the conjunction of `filters`, if any.
+///
+/// Only comparisons of the time column with timestamp literals are taken into account, so the
+/// bounds may be looser than the ones the filters actually set.
+fn time_bounds(filters: &[Expr]) -> (Option<i64>, Option<i64>) {
+    let mut lower: Option<i64> = None;
+    let mut upper: Option<i64> = None;
+    let mut add_lower = |v: i64| lower = Some(lower.map_or(v, |l| l.max(v)));
+    let mut add_upper = |v: i64| upper = Some(upper.map_or(v, |u| u.min(v)));
+
+    for expr in filters.iter().flat_map(split_conjunction) {
+        match expr {
+            Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+                // normalise to `time <op> literal`
+                let (op, v) = match (time_column(left), timestamp(right)) {
+                    (true, Some(v)) => (*op, v),
+                    _ => match (timestamp(left), time_column(right), swap(*op)) {
+                        (Some(v), true, Some(op)) => (op, v),
+                        _ => continue,
+                    },
+                };
+
+                match op {
+                    Operator::Gt | Operator::GtEq => add_lower(v),
+                    Operator::Lt | Operator::LtEq => add_upper(v),
+                    Operator::Eq => {
+                        add_lower(v);
+                        add_upper(v);
+                    }
+                    _ => {}
+                }
+            }
+            Expr::Between {
+                expr,
+                negated: false,
+                low,
+                high,
+            } if time_column(expr) => {
+                if let Some(v) = timestamp(low) {
+                    add_lower(v);
+                }
+                if let Some(v) = timestamp(high) {
+                    add_upper(v);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    (lower, upper)
+}
+
+/// Returns the operator `op'` such that `a op b` is equivalent to `b op' a`, if it is a
+/// comparison.
+fn swap(op: Operator) -> Option<Operator> {
+    match op {
+        Operator::Gt => Some(Operator::Lt),
+        Operator::GtEq => Some(Operator::LtEq),
+        Operator::Lt => Some(Operator::Gt),
+        Operator::LtEq => Some(Operator::GtEq),
+        Operator::Eq => Some(Operator::Eq),
+        _ => None,
+    }
+}
+
+fn time_column(expr: &Expr) -> bool {
+    matches!(expr, Expr::Column(c) if c.name == TIME_COLUMN_NAME)
+}
+
+fn timestamp(expr: &Expr) -> Option<i64> {
+    match expr {
+        Expr::Literal(ScalarValue::TimestampNanosecond(v, _)) => *v,
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::prelude::lit;
+
+    use super::*;
+
+    const HOUR: i64 = 3_600_000_000_000;
+    const NOW: i64 = 100 * HOUR;
+
+    fn time() -> Expr {
+        col(TIME_COLUMN_NAME)
+    }
+
+    fn time_between(low: i64, high: i64) -> Expr {
+        Expr::Between {
+            expr: Box::new(time()),
+            negated: false,
+            low: Box::new(lit_timestamp_nano(low)),
+            high: Box::new(lit_timestamp_nano(high)),
+        }
+    }
+
+    #[test]
+    fn test_time_bounds() {
+        assert_eq!(time_bounds(&[]), (None, None));
+        assert_eq!(
+            time_bounds(&[time().gt(lit_timestamp_nano(1))]),
+            (Some(1), None)
+        );
+        assert_eq!(
+            time_bounds(&[lit_timestamp_nano(5).gt_eq(time())]),
+            (None, Some(5))
+        );
+        assert_eq!(
+            time_bounds(&[time()
+                .gt_eq(lit_timestamp_nano(1))
+                .and(time().gt(lit_timestamp_nano(3)))
+                .and(col("host").eq(lit("a")))]),
+            (Some(3), None)
+        );
+        assert_eq!(
+            time_bounds(&[time_between(2, 8), time().lt(lit_timestamp_nano(6)),]),
+            (Some(2), Some(6))
+        );
+        assert_eq!(
+            time_bounds(&[time().eq(lit_timestamp_nano(4))]),
+            (Some(4), Some(4))
+        );
+
+        // disjunctions do not bound the time
+        assert_eq!(
+            time_bounds(&[time()
+                .gt(lit_timestamp_nano(1))
+                .or(col("host").eq(lit("a")))]),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn test_unrestricted() {
+        let ranges = QueryRanges::default();
+        assert_eq!(ranges.check("cpu", &[], NOW), Ok(None));
+    }
+
+    #[test]
+    fn test_default_range() {
+        let ranges = QueryRanges {
+            default_range_ns: Some(HOUR),
+            max_range_ns: None,
+        };
+
+        assert_eq!(
+            ranges.check("cpu", &[], NOW),
+            Ok(Some(time().gt_eq(lit_timestamp_nano(NOW - HOUR))))
+        );
+
+        // the default range ends at the upper bound of the query
+        let filters = [time().lt(lit_timestamp_nano(10 * HOUR))];
+        assert_eq!(
+            ranges.check("cpu", &filters, NOW),
+            Ok(Some(time().gt_eq(lit_timestamp_nano(9 * HOUR))))
+        );
+
+        // queries with a lower bound are not restricted
+        let filters = [time().gt(lit_timestamp_nano(0))];
+        assert_eq!(ranges.check("cpu", &filters, NOW), Ok(None));
+    }
+
+    #[test]
+    fn test_max_range() {
+        let ranges = QueryRanges {
+            default_range_ns: None,
+            max_range_ns: Some(2 * HOUR),
+        };
+
+        assert_eq!(
+            ranges.check("cpu", &[], NOW),
+            Err(QueryRangeError::Unbounded {
+                table: "cpu".to_string(),
+                max_range_ns: 2 * HOUR,
+            })
+        );
+
+        let filters = [time().gt_eq(lit_timestamp_nano(NOW - 3 * HOUR))];
+        assert_eq!(
+            ranges.check("cpu", &filters, NOW),
+            Err(QueryRangeError::TooLong {
+                table: "cpu".to_string(),
+                range_ns: 3 * HOUR,
+                max_range_ns: 2 * HOUR,
+            })
+        );
+
+        let filters = [time_between(0, 2 * HOUR)];
+        assert_eq!(ranges.check("cpu", &filters, NOW), Ok(None));
+
+        // the default range is subject to the maximum range
+        let ranges = QueryRanges {
+            default_range_ns: Some(HOUR),
+            ..ranges
+        };
+        assert_eq!(
+            ranges.check("cpu", &[], NOW),
+            Ok(Some(time().gt_eq(lit_timestamp_nano(NOW - HOUR))))
+        );
+    }
+}
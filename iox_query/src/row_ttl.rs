@@ -0,0 +1,150 @@
+//! Expiry of individual rows.
+//!
+//! A namespace may expire rows sooner than its retention period drops them: a fixed time after
+//! their timestamp, or at the time held in an integer field of each row. The querier filters out
+//! the expired rows and the compactor drops them from the files it writes.
+
+use std::sync::Arc;
+
+use datafusion::prelude::{col, lit, lit_timestamp_nano, Expr};
+use schema::{InfluxColumnType, InfluxFieldType, Schema, TIME_COLUMN_NAME};
+
+/// The expiry of the rows of a namespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RowTtl {
+    /// The time in ns after which a row expires, counted from its timestamp, if any.
+    pub ttl_ns: Option<i64>,
+
+    /// The integer field holding the time in ns since the epoch at which each row expires, if
+    /// any. Rows without a value in the field do not expire by it.
+    pub column: Option<Arc<str>>,
+}
+
+impl RowTtl {
+    /// Whether rows may expire at all.
+    pub fn is_set(&self) -> bool {
+        self.ttl_ns.is_some() || self.column.is_some()
+    }
+
+    /// Returns the columns of a table with `schema` that the [filter](Self::filter) reads.
+    pub fn columns(&self, schema: &Schema) -> Vec<&str> {
+        self.ttl_ns
+            .map(|_| TIME_COLUMN_NAME)
+            .into_iter()
+            .chain(self.expiry_column(schema).map(|(name, _)| name))
+            .collect()
+    }
+
+    /// Returns the filter keeping the rows of a table with `schema` that did not expire at time
+    /// `now_ns`, or [`None`] if none of them can expire.
+    ///
+    /// The expiry field is ignored for tables that do not have it as an integer field.
+    pub fn filter(&self, schema: &Schema, now_ns: i64) -> Option<Expr> {
+        let ttl_filter = self.ttl_ns.map(|ttl_ns| {
+            col(TIME_COLUMN_NAME).gt(lit_timestamp_nano(now_ns.saturating_sub(ttl_ns)))
+        });
+
+        let column_filter = self.expiry_column(schema).map(|(name, field_type)| {
+            let now = match field_type {
+                InfluxFieldType::UInteger => lit(now_ns.max(0) as u64),
+                _ => lit(now_ns),
+            };
+            col(name).is_null().or(col(name).gt(now))
+        });
+
+        ttl_filter
+            .into_iter()
+            .chain(column_filter)
+            .reduce(Expr::and)
+    }
+
+    /// Returns the expiry field and its type, if the table with `schema` has it as an integer
+    /// field.
+    fn expiry_column<'a>(&'a self, schema: &Schema) -> Option<(&'a str, InfluxFieldType)> {
+        let name = self.column.as_deref()?;
+        let idx = schema.find_index_of(name)?;
+        match schema.field(idx).0 {
+            InfluxColumnType::Field(
+                field_type @ (InfluxFieldType::Integer | InfluxFieldType::UInteger),
+            ) => Some((name, field_type)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schema::builder::SchemaBuilder;
+
+    use super::*;
+
+    const NOW: i64 = 1_000;
+
+    fn schema() -> Schema {
+        SchemaBuilder::new()
+            .tag("host")
+            .influx_field("expires", InfluxFieldType::Integer)
+            .influx_field("load", InfluxFieldType::Float)
+            .timestamp()
+            .build()
+            .unwrap()
+    }
+
+    fn new_row_ttl(ttl_ns: Option<i64>, column: Option<&str>) -> RowTtl {
+        RowTtl {
+            ttl_ns,
+            column: column.map(Arc::from),
+        }
+    }
+
+    #[test]
+    fn test_unset() {
+        let row_ttl = RowTtl::default();
+        assert!(!row_ttl.is_set());
+        assert!(row_ttl.columns(&schema()).is_empty());
+        assert_eq!(row_ttl.filter(&schema(), NOW), None);
+    }
+
+    #[test]
+    fn test_ttl() {
+        let row_ttl = new_row_ttl(Some(100), None);
+        assert!(row_ttl.is_set());
+        assert_eq!(row_ttl.columns(&schema()), vec![TIME_COLUMN_NAME]);
+        assert_eq!(
+            row_ttl.filter(&schema(), NOW),
+            Some(col(TIME_COLUMN_NAME).gt(lit_timestamp_nano(900)))
+        );
+    }
+
+    #[test]
+    fn test_column() {
+        let expires = || col("expires");
+        let column_filter = expires().is_null().or(expires().gt(lit(NOW)));
+
+        let row_ttl = new_row_ttl(None, Some("expires"));
+        assert_eq!(row_ttl.columns(&schema()), vec!["expires"]);
+        assert_eq!(row_ttl.filter(&schema(), NOW), Some(column_filter.clone()));
+
+        let both = RowTtl {
+            ttl_ns: Some(100),
+            ..row_ttl
+        };
+        assert_eq!(both.columns(&schema()), vec![TIME_COLUMN_NAME, "expires"]);
+        assert_eq!(
+            both.filter(&schema(), NOW),
+            Some(
+                col(TIME_COLUMN_NAME)
+                    .gt(lit_timestamp_nano(900))
+                    .and(column_filter)
+            )
+        );
+
+        // fields that do not exist or are not integers are ignored
+        for column in ["unknown", "load", "host"] {
+            let row_ttl = new_row_ttl(None, Some(column));
+            assert!(row_ttl.is_set());
+            assert!(row_ttl.columns(&schema()).is_empty());
+            assert_eq!(row_ttl.filter(&schema(), NOW), None);
+        }
+    }
+}
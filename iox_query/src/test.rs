@@ -977,6 +977,7 @@ impl fmt::Display for TestChunk {
     }
 }
 
+#[async_trait]
 impl QueryChunk for TestChunk {
     fn id(&self) -> ChunkId {
         self.id
@@ -1011,7 +1012,7 @@ impl QueryChunk for TestChunk {
         Ok(PredicateMatch::Unknown)
     }
 
-    fn column_values(
+    async fn column_values(
         &self,
         _ctx: IOxSessionContext,
         _column_name: &str,
@@ -1021,7 +1022,7 @@ impl QueryChunk for TestChunk {
         Ok(None)
     }
 
-    fn column_names(
+    async fn column_names(
         &self,
         _ctx: IOxSessionContext,
         predicate: &Predicate,
@@ -7,9 +7,15 @@ use std::{
 };
 
 use arrow::{
-    array::TimestampNanosecondArray,
-    compute::SortOptions,
-    datatypes::{DataType, Schema as ArrowSchema},
+    array::{
+        as_boolean_array, as_primitive_array, as_string_array, Array, ArrayRef,
+        TimestampNanosecondArray,
+    },
+    compute::{cast, SortOptions},
+    datatypes::{
+        DataType, Float64Type, Int64Type, Schema as ArrowSchema, TimeUnit, TimestampNanosecondType,
+        UInt64Type,
+    },
     record_batch::RecordBatch,
 };
 
@@ -354,6 +360,114 @@ pub fn create_basic_summary(
     TableSummary { columns }
 }
 
+/// Compute a [`TableSummary`] with the min/max values and null counts of all columns of `batches`.
+///
+/// Contrary to [`create_basic_summary`], the statistics are exact and can be used to prune the
+/// batches by predicate. Columns of `schema` that are missing from a batch count as NULL for all
+/// rows of that batch.
+pub fn compute_table_summary<'a>(
+    schema: &Schema,
+    batches: impl IntoIterator<Item = &'a RecordBatch>,
+) -> TableSummary {
+    let mut columns = schema
+        .iter()
+        .map(|(t, field)| {
+            let (influxdb_type, stats) = match t {
+                InfluxColumnType::Tag => (
+                    InfluxDbType::Tag,
+                    Statistics::String(StatValues::new_empty()),
+                ),
+                InfluxColumnType::Timestamp => (
+                    InfluxDbType::Timestamp,
+                    Statistics::I64(StatValues::new_empty()),
+                ),
+                InfluxColumnType::Field(field_type) => (
+                    InfluxDbType::Field,
+                    match field_type {
+                        InfluxFieldType::Integer => Statistics::I64(StatValues::new_empty()),
+                        InfluxFieldType::UInteger => Statistics::U64(StatValues::new_empty()),
+                        InfluxFieldType::Float => Statistics::F64(StatValues::new_empty()),
+                        InfluxFieldType::Boolean => Statistics::Bool(StatValues::new_empty()),
+                        InfluxFieldType::String => Statistics::String(StatValues::new_empty()),
+                    },
+                ),
+            };
+
+            ColumnSummary {
+                name: field.name().clone(),
+                influxdb_type,
+                stats,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for batch in batches {
+        let batch_schema = batch.schema();
+        for column in &mut columns {
+            match batch_schema.index_of(&column.name) {
+                Ok(idx) => update_statistics(&mut column.stats, batch.column(idx)),
+                Err(_) => column.stats.update_for_nulls(batch.num_rows() as u64),
+            }
+        }
+    }
+
+    TableSummary { columns }
+}
+
+/// Update `stats` with the values of `array`.
+///
+/// Arrays whose type does not match the statistics are treated as all NULL.
+fn update_statistics(stats: &mut Statistics, array: &ArrayRef) {
+    match (stats, array.data_type()) {
+        (Statistics::I64(stats), DataType::Int64) => {
+            as_primitive_array::<Int64Type>(array)
+                .iter()
+                .flatten()
+                .for_each(|v| stats.update(&v));
+            stats.update_for_nulls(array.null_count() as u64);
+        }
+        (Statistics::I64(stats), DataType::Timestamp(TimeUnit::Nanosecond, _)) => {
+            as_primitive_array::<TimestampNanosecondType>(array)
+                .iter()
+                .flatten()
+                .for_each(|v| stats.update(&v));
+            stats.update_for_nulls(array.null_count() as u64);
+        }
+        (Statistics::U64(stats), DataType::UInt64) => {
+            as_primitive_array::<UInt64Type>(array)
+                .iter()
+                .flatten()
+                .for_each(|v| stats.update(&v));
+            stats.update_for_nulls(array.null_count() as u64);
+        }
+        (Statistics::F64(stats), DataType::Float64) => {
+            as_primitive_array::<Float64Type>(array)
+                .iter()
+                .flatten()
+                .for_each(|v| stats.update(&v));
+            stats.update_for_nulls(array.null_count() as u64);
+        }
+        (Statistics::Bool(stats), DataType::Boolean) => {
+            as_boolean_array(array)
+                .iter()
+                .flatten()
+                .for_each(|v| stats.update(&v));
+            stats.update_for_nulls(array.null_count() as u64);
+        }
+        (Statistics::String(stats), DataType::Utf8 | DataType::Dictionary(_, _)) => {
+            // Dictionaries may contain values that are no longer referenced, so only look at
+            // the values of the rows.
+            let array = cast(array, &DataType::Utf8).expect("string dictionary cast to utf8");
+            as_string_array(&array)
+                .iter()
+                .flatten()
+                .for_each(|v| stats.update(v));
+            stats.update_for_nulls(array.null_count() as u64);
+        }
+        (stats, _) => stats.update_for_nulls(array.len() as u64),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use arrow::datatypes::DataType;
@@ -671,6 +785,87 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_compute_table_summary() {
+        let schema = SchemaBuilder::new()
+            .tag("tag")
+            .influx_field("field_float", InfluxFieldType::Float)
+            .timestamp()
+            .build()
+            .unwrap();
+
+        let tag: arrow::array::DictionaryArray<arrow::datatypes::Int32Type> =
+            vec![Some("b"), None, Some("a")].into_iter().collect();
+        let batch1 = RecordBatch::try_from_iter(vec![
+            ("tag", Arc::new(tag) as ArrayRef),
+            (
+                "field_float",
+                Arc::new(arrow::array::Float64Array::from(vec![
+                    Some(1.5),
+                    Some(-2.0),
+                    None,
+                ])) as ArrayRef,
+            ),
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from(vec![20, 10, 30])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        // second batch has no float column
+        let tag: arrow::array::DictionaryArray<arrow::datatypes::Int32Type> =
+            vec![Some("c")].into_iter().collect();
+        let batch2 = RecordBatch::try_from_iter(vec![
+            ("tag", Arc::new(tag) as ArrayRef),
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from(vec![5])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let actual = compute_table_summary(&schema, [&batch1, &batch2]);
+        let expected = TableSummary {
+            columns: vec![
+                ColumnSummary {
+                    name: String::from("tag"),
+                    influxdb_type: InfluxDbType::Tag,
+                    stats: Statistics::String(StatValues {
+                        min: Some(String::from("a")),
+                        max: Some(String::from("c")),
+                        total_count: 4,
+                        null_count: Some(1),
+                        distinct_count: None,
+                    }),
+                },
+                ColumnSummary {
+                    name: String::from("field_float"),
+                    influxdb_type: InfluxDbType::Field,
+                    stats: Statistics::F64(StatValues {
+                        min: Some(-2.0),
+                        max: Some(1.5),
+                        total_count: 4,
+                        null_count: Some(2),
+                        distinct_count: None,
+                    }),
+                },
+                ColumnSummary {
+                    name: String::from("time"),
+                    influxdb_type: InfluxDbType::Timestamp,
+                    stats: Statistics::I64(StatValues {
+                        min: Some(5),
+                        max: Some(30),
+                        total_count: 4,
+                        null_count: Some(0),
+                        distinct_count: None,
+                    }),
+                },
+            ],
+        };
+        assert_eq!(actual, expected);
+    }
+
     fn full_schema() -> Schema {
         SchemaBuilder::new()
             .tag("tag")
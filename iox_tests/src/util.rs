@@ -28,6 +28,7 @@ use once_cell::sync::Lazy;
 use parquet_file::{
     chunk::ParquetChunk,
     metadata::IoxMetadata,
+    serialize::ParquetWriterOptions,
     storage::{ParquetStorage, StorageId},
 };
 use schema::{
@@ -39,7 +40,7 @@ use uuid::Uuid;
 
 /// Global executor used by all test catalogs.
 static GLOBAL_EXEC: Lazy<Arc<DedicatedExecutors>> =
-    Lazy::new(|| Arc::new(DedicatedExecutors::new(1)));
+    Lazy::new(|| Arc::new(DedicatedExecutors::new(1, 1)));
 
 /// Common retention period used throughout tests
 pub const TEST_RETENTION_PERIOD_NS: Option<i64> = Some(3_600 * 1_000_000_000);
@@ -83,7 +84,8 @@ impl TestCatalog {
         let time_provider = Arc::new(MockProvider::new(Time::from_timestamp(0, 0).unwrap()));
         let exec = Arc::new(Executor::new_with_config_and_executors(
             ExecutorConfig {
-                num_threads: exec.num_threads(),
+                num_query_threads: exec.num_query_threads(),
+                num_reorg_threads: exec.num_reorg_threads(),
                 target_query_partitions,
                 object_stores: HashMap::from([(
                     parquet_store.id(),
@@ -565,6 +567,7 @@ impl TestPartition {
             to_delete,
             object_store_id,
             row_count,
+            bloom_filter_columns,
         } = builder;
 
         let record_batch = record_batch.expect("A record batch is required");
@@ -604,7 +607,11 @@ impl TestPartition {
             ParquetStorage::new(
                 Arc::clone(&self.catalog.object_store),
                 StorageId::from("iox"),
-            ),
+            )
+            .with_writer_options(ParquetWriterOptions {
+                bloom_filter_columns,
+                ..Default::default()
+            }),
             &metadata,
             record_batch.clone(),
         )
@@ -624,6 +631,7 @@ impl TestPartition {
             to_delete,
             object_store_id: Some(object_store_id),
             row_count: None, // will be computed from the record batch again
+            bloom_filter_columns: vec![],
         };
 
         let result = self.create_parquet_file_catalog_record(builder).await;
@@ -735,6 +743,7 @@ pub struct TestParquetFileBuilder {
     to_delete: bool,
     object_store_id: Option<Uuid>,
     row_count: Option<usize>,
+    bloom_filter_columns: Vec<String>,
 }
 
 impl Default for TestParquetFileBuilder {
@@ -753,6 +762,7 @@ impl Default for TestParquetFileBuilder {
             to_delete: false,
             object_store_id: None,
             row_count: None,
+            bloom_filter_columns: vec![],
         }
     }
 }
@@ -833,6 +843,12 @@ impl TestParquetFileBuilder {
         self.size_override = Some(size_override);
         self
     }
+
+    /// Specify the tag columns to write bloom filters for into the parquet file.
+    pub fn with_bloom_filter_columns(mut self, columns: &[&str]) -> Self {
+        self.bloom_filter_columns = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
 }
 
 async fn update_catalog_sort_key_if_needed(
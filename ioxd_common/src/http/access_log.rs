@@ -0,0 +1,163 @@
+//! Structured access log of the HTTP server.
+
+use std::time::{Duration, Instant};
+
+use clap_blocks::access_log::AccessLogConfig;
+use data_types::org_and_bucket_to_namespace;
+use http_body::Body as _;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use observability_deps::tracing::info;
+use serde::Deserialize;
+use trace_http::ctx::RequestLogContext;
+
+/// Selects the HTTP requests to log, see [`AccessLogConfig`].
+///
+/// The default access log logs nothing.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AccessLog {
+    sample_rate: f64,
+    slow_threshold: Option<Duration>,
+    log_errors: bool,
+}
+
+impl AccessLog {
+    pub fn new(config: &AccessLogConfig) -> Self {
+        Self {
+            sample_rate: config.sample_rate,
+            slow_threshold: config.slow_threshold,
+            log_errors: config.log_errors,
+        }
+    }
+
+    /// Start the entry of `request`, before its handler consumes it.
+    pub(crate) fn start(&self, request: &Request<Body>) -> AccessLogEntry {
+        AccessLogEntry {
+            access_log: *self,
+            start_instant: Instant::now(),
+            method: request.method().clone(),
+            // IOx routes have no path parameters, so the path is the route template
+            route: request.uri().path().to_string(),
+            request_bytes: request
+                .headers()
+                .get(hyper::header::CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok()),
+            namespace: namespace(request),
+            trace_id: request
+                .extensions()
+                .get::<RequestLogContext>()
+                .map(|ctx| format!("{:x}", ctx.ctx().trace_id.get())),
+        }
+    }
+
+    fn should_log(&self, status: StatusCode, latency: Duration) -> bool {
+        matches!(self.slow_threshold, Some(threshold) if latency >= threshold)
+            || (self.log_errors && (status.is_client_error() || status.is_server_error()))
+            || (self.sample_rate > 0.0 && rand::random::<f64>() < self.sample_rate)
+    }
+}
+
+/// The fields of a request known before it is handled.
+#[derive(Debug)]
+pub(crate) struct AccessLogEntry {
+    access_log: AccessLog,
+    start_instant: Instant,
+    method: Method,
+    route: String,
+    request_bytes: Option<u64>,
+    namespace: Option<String>,
+    trace_id: Option<String>,
+}
+
+impl AccessLogEntry {
+    /// Log the request answered by `response`, if it is selected.
+    ///
+    /// The latency of the request is the time until its response headers are returned.
+    pub(crate) fn finish(self, response: &Response<Body>) {
+        let latency = self.start_instant.elapsed();
+        let status = response.status();
+
+        if !self.access_log.should_log(status, latency) {
+            return;
+        }
+
+        info!(
+            method=%self.method,
+            route=%self.route,
+            status=status.as_u16(),
+            ?latency,
+            request_bytes=self.request_bytes,
+            response_bytes=response.body().size_hint().exact(),
+            namespace=self.namespace.as_deref(),
+            trace_id=self.trace_id.as_deref(),
+            "HTTP request",
+        );
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OrgBucket {
+    org: String,
+    bucket: String,
+}
+
+/// Returns the namespace of the org and bucket in the query of `request`, if any.
+fn namespace(request: &Request<Body>) -> Option<String> {
+    let query = request.uri().query()?;
+    let OrgBucket { org, bucket } = serde_urlencoded::from_str(query).ok()?;
+    org_and_bucket_to_namespace(org, bucket)
+        .ok()
+        .map(|namespace| namespace.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_log() {
+        let latency = Duration::from_millis(10);
+
+        let access_log = AccessLog::default();
+        assert!(!access_log.should_log(StatusCode::OK, latency));
+        assert!(!access_log.should_log(StatusCode::BAD_REQUEST, latency));
+
+        let access_log = AccessLog {
+            slow_threshold: Some(Duration::from_millis(10)),
+            log_errors: true,
+            ..Default::default()
+        };
+        assert!(access_log.should_log(StatusCode::OK, latency));
+        assert!(!access_log.should_log(StatusCode::OK, Duration::from_millis(9)));
+        assert!(access_log.should_log(StatusCode::BAD_REQUEST, Duration::ZERO));
+        assert!(access_log.should_log(StatusCode::SERVICE_UNAVAILABLE, Duration::ZERO));
+
+        let access_log = AccessLog {
+            sample_rate: 1.0,
+            ..Default::default()
+        };
+        assert!(access_log.should_log(StatusCode::OK, Duration::ZERO));
+    }
+
+    #[test]
+    fn test_entry() {
+        let request = Request::post("http://localhost/api/v2/write?org=myorg&bucket=b&precision=s")
+            .header("content-length", "42")
+            .body(Body::empty())
+            .unwrap();
+        let entry = AccessLog::default().start(&request);
+
+        assert_eq!(entry.method, Method::POST);
+        assert_eq!(entry.route, "/api/v2/write");
+        assert_eq!(entry.request_bytes, Some(42));
+        assert_eq!(entry.namespace.as_deref(), Some("myorg_b"));
+        assert_eq!(entry.trace_id, None);
+
+        let request = Request::get("http://localhost/health")
+            .body(Body::empty())
+            .unwrap();
+        let entry = AccessLog::default().start(&request);
+        assert_eq!(entry.request_bytes, None);
+        assert_eq!(entry.namespace, None);
+    }
+}
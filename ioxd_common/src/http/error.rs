@@ -101,6 +101,12 @@ pub struct HttpApiError {
 
     /// Human-readable message.
     msg: String,
+
+    /// Service-specific, machine-readable error code that is more precise than [`Self::code`].
+    error_code: Option<&'static str>,
+
+    /// Machine-readable details of the failure.
+    details: Option<serde_json::Value>,
 }
 
 impl HttpApiError {
@@ -109,18 +115,39 @@ impl HttpApiError {
         Self {
             code: code.into(),
             msg: msg.into(),
+            error_code: None,
+            details: None,
         }
     }
 
+    /// Add a service-specific `error_code` and the `details` of the failure, returned in the
+    /// `error_code` and `details` fields of the response body.
+    ///
+    /// Unlike the message, the error code is stable and meant for clients to branch on.
+    pub fn with_error_code(
+        mut self,
+        error_code: &'static str,
+        details: Option<serde_json::Value>,
+    ) -> Self {
+        self.error_code = Some(error_code);
+        self.details = details;
+        self
+    }
+
     /// Generate response body for this error.
     fn body(&self) -> Body {
-        let json = serde_json::json!({
+        let mut json = serde_json::json!({
             "code": self.code.as_text().to_string(),
             "message": self.msg.clone(),
-        })
-        .to_string();
+        });
+        if let Some(error_code) = self.error_code {
+            json["error_code"] = error_code.into();
+        }
+        if let Some(details) = &self.details {
+            json["details"] = details.clone();
+        }
 
-        Body::from(json)
+        Body::from(json.to_string())
     }
 
     /// Generate response for this error.
@@ -186,3 +213,36 @@ pub trait HttpApiErrorSource: std::error::Error {
     /// Create [`HttpApiError`].
     fn to_http_api_error(&self) -> HttpApiError;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body_json(e: HttpApiError) -> serde_json::Value {
+        let body = hyper::body::to_bytes(e.response().into_body())
+            .await
+            .unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_error_code() {
+        let e = HttpApiError::new(StatusCode::BAD_REQUEST, "bananas");
+        assert_eq!(
+            body_json(e).await,
+            serde_json::json!({"code": "invalid", "message": "bananas"})
+        );
+
+        let e = HttpApiError::new(StatusCode::BAD_REQUEST, "bananas")
+            .with_error_code("line_protocol_parse", Some(serde_json::json!({"line": 2})));
+        assert_eq!(
+            body_json(e).await,
+            serde_json::json!({
+                "code": "invalid",
+                "message": "bananas",
+                "error_code": "line_protocol_parse",
+                "details": {"line": 2},
+            })
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use std::{convert::Infallible, num::NonZeroI32, sync::Arc};
 
 use hyper::{
+    header::CONTENT_TYPE,
     http::HeaderValue,
     server::conn::{AddrIncoming, AddrStream},
     Body, Method, Request, Response,
@@ -13,7 +14,10 @@ use tower::Layer;
 use trace_http::{ctx::TraceHeaderParser, tower::TraceLayer};
 
 use crate::{
-    http::error::{HttpApiError, HttpApiErrorExt, HttpApiErrorSource},
+    http::{
+        access_log::AccessLog,
+        error::{HttpApiError, HttpApiErrorExt, HttpApiErrorSource},
+    },
     server_type::ServerType,
 };
 
@@ -23,6 +27,7 @@ mod heappy;
 #[cfg(feature = "pprof")]
 mod pprof;
 
+pub mod access_log;
 pub mod error;
 pub mod metrics;
 pub mod utils;
@@ -92,17 +97,18 @@ pub async fn serve(
     server_type: Arc<dyn ServerType>,
     shutdown: CancellationToken,
     trace_header_parser: TraceHeaderParser,
+    access_log: AccessLog,
 ) -> Result<(), hyper::Error> {
     let metric_registry = server_type.metric_registry();
     let trace_collector = server_type.trace_collector();
 
-    let trace_layer = TraceLayer::new(trace_header_parser, metric_registry, trace_collector, false);
+    let trace_layer = TraceLayer::new(trace_header_parser, Some(metric_registry), trace_collector);
 
     hyper::Server::builder(addr)
         .serve(hyper::service::make_service_fn(|_conn: &AddrStream| {
             let server_type = Arc::clone(&server_type);
             let service = hyper::service::service_fn(move |request: Request<_>| {
-                route_request(Arc::clone(&server_type), request)
+                route_request(Arc::clone(&server_type), access_log, request)
             });
 
             let service = trace_layer.layer(service);
@@ -114,12 +120,15 @@ pub async fn serve(
 
 async fn route_request(
     server_type: Arc<dyn ServerType>,
+    access_log: AccessLog,
     mut req: Request<Body>,
 ) -> Result<Response<Body>, Infallible> {
     // we don't need the authorization header and we don't want to accidentally log it.
     req.headers_mut().remove("authorization");
     debug!(request = ?req,"Processing request");
 
+    let access_log_entry = access_log.start(&req);
+
     let method = req.method().clone();
     let uri = req.uri().clone();
     let content_length = req.headers().get("content-length").cloned();
@@ -137,10 +146,10 @@ async fn route_request(
     };
 
     // TODO: Move logging to TraceLayer
-    match response {
+    let response = match response {
         Ok(response) => {
             debug!(?response, "Successfully processed request");
-            Ok(response)
+            response
         }
         Err(error) => {
             let error: HttpApiError = error.to_http_api_error();
@@ -149,9 +158,12 @@ async fn route_request(
             } else {
                 debug!(%error, %method, %uri, ?content_length, "Error while handling request");
             }
-            Ok(error.response())
+            error.response()
         }
-    }
+    };
+
+    access_log_entry.finish(&response);
+    Ok(response)
 }
 
 fn health() -> Result<Response<Body>, ApplicationError> {
@@ -164,7 +176,10 @@ fn handle_metrics(server_type: &dyn ServerType) -> Result<Response<Body>, Applic
     let mut reporter = metric_exporters::PrometheusTextEncoder::new(&mut body);
     server_type.metric_registry().report(&mut reporter);
 
-    Ok(Response::new(Body::from(body)))
+    Ok(Response::builder()
+        .header(CONTENT_TYPE, metric_exporters::PROMETHEUS_TEXT_CONTENT_TYPE)
+        .body(Body::from(body))
+        .unwrap())
 }
 
 async fn pprof_home(req: Request<Body>) -> Result<Response<Body>, ApplicationError> {
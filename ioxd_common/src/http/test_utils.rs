@@ -12,7 +12,10 @@ use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 use trace::RingBufferTraceCollector;
 
-use crate::{http::serve, server_type::ServerType};
+use crate::{
+    http::{access_log::AccessLog, serve},
+    server_type::ServerType,
+};
 
 /// checks a http response against expected results
 pub async fn check_response(
@@ -114,6 +117,7 @@ where
                 server_type_captured,
                 CancellationToken::new(),
                 trace_header_parser,
+                AccessLog::default(),
             )
             .await
             .unwrap();
@@ -183,6 +187,10 @@ where
         .await
         .unwrap();
 
+    assert_eq!(
+        response.headers()[CONTENT_TYPE],
+        metric_exporters::PROMETHEUS_TEXT_CONTENT_TYPE
+    );
     let data = response.text().await.unwrap();
 
     assert!(data.contains("\nmy_metric_total{tag=\"value\"} 20\n"));
@@ -1,4 +1,6 @@
 pub mod http;
+pub mod metrics_push;
+pub mod resources;
 pub mod rpc;
 pub mod server_type;
 mod service;
@@ -123,12 +125,16 @@ pub async fn serve(
                 .traces_jaeger_debug_name,
         );
 
+    let access_log =
+        http::access_log::AccessLog::new(common_state.run_config().access_log_config());
+
     // Construct and start up gRPC server
     let grpc_server = rpc::serve(
         grpc_listener,
         Arc::clone(&server_type),
         trace_header_parser.clone(),
         frontend_shutdown.clone(),
+        *common_state.run_config().grpc_server_config(),
     )
     .fuse();
     info!(?server_type, "gRPC server listening");
@@ -143,6 +149,7 @@ pub async fn serve(
                 captured_server_type,
                 captured_shutdown,
                 trace_header_parser,
+                access_log,
             )
             .await?
         } else {
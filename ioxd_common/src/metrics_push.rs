@@ -0,0 +1,134 @@
+//! Periodic push of the metrics to a Prometheus pushgateway, for environments that cannot scrape
+//! the `/metrics` endpoint.
+
+use std::sync::Arc;
+
+use clap_blocks::metrics_push::MetricsPushConfig;
+use observability_deps::tracing::{info, warn};
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+/// Push the metrics of `registry` to the pushgateway configured by `config` until `shutdown` is
+/// cancelled, pushing them a last time on shutdown.
+///
+/// Returns `None` if no pushgateway is configured.
+pub fn spawn_metrics_push(
+    config: &MetricsPushConfig,
+    registry: Arc<metric::Registry>,
+    shutdown: CancellationToken,
+) -> Option<JoinHandle<()>> {
+    let url = config.push_url()?;
+    let period = config.interval;
+    info!(%url, ?period, "pushing metrics to pushgateway");
+
+    let client = reqwest::Client::builder()
+        .timeout(period)
+        .build()
+        .expect("failed to create pushgateway client");
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.cancelled() => break,
+            }
+            if let Err(e) = push_metrics(&client, &url, &registry).await {
+                warn!(%e, %url, "failed to push metrics to pushgateway");
+            }
+        }
+
+        if let Err(e) = push_metrics(&client, &url, &registry).await {
+            warn!(%e, %url, "failed to push metrics to pushgateway on shutdown");
+        }
+    }))
+}
+
+/// Replace the metrics of the group at `url` by the current metrics of `registry`.
+async fn push_metrics(
+    client: &reqwest::Client,
+    url: &str,
+    registry: &metric::Registry,
+) -> Result<(), reqwest::Error> {
+    let mut body: Vec<u8> = Default::default();
+    let mut reporter = metric_exporters::PrometheusTextEncoder::new(&mut body);
+    registry.report(&mut reporter);
+
+    client
+        .put(url)
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            metric_exporters::PROMETHEUS_TEXT_CONTENT_TYPE,
+        )
+        .body(body)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Body, Request, Response, Server,
+    };
+    use metric::U64Counter;
+    use std::{convert::Infallible, time::Duration};
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_push_metrics() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let make_service = make_service_fn(move |_| {
+            let tx = tx.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let tx = tx.clone();
+                    async move {
+                        let method = req.method().clone();
+                        let path = req.uri().path().to_string();
+                        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                        tx.send((method, path, body)).unwrap();
+                        Ok::<_, Infallible>(Response::new(Body::empty()))
+                    }
+                }))
+            }
+        });
+        let server = Server::bind(&"127.0.0.1:0".parse().unwrap()).serve(make_service);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        let registry = Arc::new(metric::Registry::default());
+        registry
+            .register_metric::<U64Counter>("bananas", "a counter")
+            .recorder(&[])
+            .inc(42);
+
+        let config = MetricsPushConfig {
+            gateway_url: Some(format!("http://{addr}")),
+            interval: Duration::from_secs(3600),
+            instance: Some("router-0".to_string()),
+            ..Default::default()
+        };
+        let shutdown = CancellationToken::new();
+        let handle = spawn_metrics_push(&config, registry, shutdown.clone()).unwrap();
+
+        // The first push happens immediately, and the last one on shutdown.
+        for _ in 0..2 {
+            let (method, path, body) = rx.recv().await.unwrap();
+            assert_eq!(method, hyper::Method::PUT);
+            assert_eq!(path, "/metrics/job/influxdb_iox/instance/router-0");
+            let body = String::from_utf8(body.to_vec()).unwrap();
+            assert!(body.contains("# TYPE bananas_total counter\nbananas_total 42\n"));
+            shutdown.cancel();
+        }
+        handle.await.unwrap();
+
+        assert!(spawn_metrics_push(&Default::default(), Default::default(), shutdown).is_none());
+    }
+}
@@ -0,0 +1,167 @@
+//! Detection of the CPU and memory available to the server.
+//!
+//! Inside a container, the host totals overstate the available resources, so the limits of the
+//! cgroup (v1 or v2) of the process take precedence over them.
+
+use std::path::Path;
+
+use observability_deps::tracing::info;
+
+/// Root of the cgroup hierarchy as mounted inside a container.
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// cgroup v1 reports "no limit" as a huge page-aligned value (`PAGE_COUNTER_MAX * PAGE_SIZE`),
+/// so any memory limit above this is treated as unlimited.
+const UNLIMITED_MEMORY_BYTES: u64 = 1 << 62;
+
+/// The CPU and memory available to the server, used to size thread pools, buffers and caches
+/// that are not configured explicitly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceLimits {
+    /// Number of CPUs, possibly fractional if limited by a CPU quota.
+    cpus: f64,
+
+    /// Memory in bytes, `None` if unknown.
+    memory_bytes: Option<usize>,
+}
+
+impl ResourceLimits {
+    /// Detect the limits of the cgroup of the process, falling back to the host totals.
+    pub fn detect() -> Self {
+        let host_cpus = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1) as f64;
+        let host_memory_bytes = read("/proc/meminfo").and_then(|s| parse_meminfo_total(&s));
+
+        let root = Path::new(CGROUP_ROOT);
+        let (cgroup_cpus, cgroup_memory_bytes) = if root.join("cgroup.controllers").exists() {
+            (
+                read(root.join("cpu.max")).and_then(|s| parse_cpu_max(&s)),
+                read(root.join("memory.max")).and_then(|s| parse_memory_limit(&s)),
+            )
+        } else {
+            (
+                read(root.join("cpu/cpu.cfs_quota_us"))
+                    .zip(read(root.join("cpu/cpu.cfs_period_us")))
+                    .and_then(|(quota, period)| parse_cfs_quota(&quota, &period)),
+                read(root.join("memory/memory.limit_in_bytes"))
+                    .and_then(|s| parse_memory_limit(&s)),
+            )
+        };
+
+        let limits = Self {
+            cpus: cgroup_cpus.map_or(host_cpus, |cpus| cpus.min(host_cpus)),
+            memory_bytes: match (cgroup_memory_bytes, host_memory_bytes) {
+                (Some(cgroup), Some(host)) => Some(cgroup.min(host)),
+                (cgroup, host) => cgroup.or(host),
+            },
+        };
+        info!(
+            cpus = limits.cpus,
+            memory_bytes = ?limits.memory_bytes,
+            "detected resource limits"
+        );
+        limits
+    }
+
+    /// Number of threads that keep all available CPUs busy.
+    pub fn num_threads(&self) -> usize {
+        (self.cpus.ceil() as usize).max(1)
+    }
+
+    /// Available memory in bytes, `None` if unknown.
+    pub fn memory_bytes(&self) -> Option<usize> {
+        self.memory_bytes
+    }
+
+    /// The given `fraction` of the available memory in bytes, `None` if unknown.
+    pub fn memory_fraction(&self, fraction: f64) -> Option<usize> {
+        self.memory_bytes
+            .map(|bytes| (bytes as f64 * fraction) as usize)
+    }
+}
+
+fn read(path: impl AsRef<Path>) -> Option<String> {
+    std::fs::read_to_string(path).ok()
+}
+
+/// Parse the CPU limit from the cgroup v2 `cpu.max` file, formatted as `$QUOTA $PERIOD` with a
+/// quota of `max` if unlimited.
+fn parse_cpu_max(s: &str) -> Option<f64> {
+    let mut parts = s.split_whitespace();
+    let quota = parts.next()?;
+    let period = parts.next()?;
+    parse_cfs_quota(quota, period)
+}
+
+/// Parse the CPU limit from the CFS quota and period in microseconds, with a negative quota if
+/// unlimited.
+fn parse_cfs_quota(quota: &str, period: &str) -> Option<f64> {
+    let quota = quota.trim().parse::<i64>().ok()?;
+    let period = period.trim().parse::<i64>().ok()?;
+    (quota > 0 && period > 0).then(|| quota as f64 / period as f64)
+}
+
+/// Parse a cgroup memory limit in bytes, which is `max` (v2) or a huge value (v1) if unlimited.
+fn parse_memory_limit(s: &str) -> Option<usize> {
+    let bytes = s.trim().parse::<u64>().ok()?;
+    (bytes < UNLIMITED_MEMORY_BYTES)
+        .then(|| usize::try_from(bytes).ok())
+        .flatten()
+}
+
+/// Parse the total memory of the host from `/proc/meminfo`.
+fn parse_meminfo_total(s: &str) -> Option<usize> {
+    let line = s.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kib = line
+        .trim_start_matches("MemTotal:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<usize>()
+        .ok()?;
+    kib.checked_mul(1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_limits() {
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+        assert_eq!(parse_cpu_max("250000 100000\n"), Some(2.5));
+        assert_eq!(parse_cpu_max("garbage"), None);
+
+        assert_eq!(parse_cfs_quota("-1\n", "100000\n"), None);
+        assert_eq!(parse_cfs_quota("50000\n", "100000\n"), Some(0.5));
+    }
+
+    #[test]
+    fn test_parse_memory_limits() {
+        assert_eq!(parse_memory_limit("max\n"), None);
+        assert_eq!(parse_memory_limit("9223372036854771712\n"), None);
+        assert_eq!(parse_memory_limit("1073741824\n"), Some(1073741824));
+
+        let meminfo = "MemTotal:       16333412 kB\nMemFree:         1021472 kB\n";
+        assert_eq!(parse_meminfo_total(meminfo), Some(16333412 * 1024));
+        assert_eq!(parse_meminfo_total("MemFree: 1 kB"), None);
+    }
+
+    #[test]
+    fn test_sizing() {
+        let limits = ResourceLimits {
+            cpus: 0.5,
+            memory_bytes: Some(1000),
+        };
+        assert_eq!(limits.num_threads(), 1);
+        assert_eq!(limits.memory_fraction(0.25), Some(250));
+
+        let limits = ResourceLimits {
+            cpus: 2.5,
+            memory_bytes: None,
+        };
+        assert_eq!(limits.num_threads(), 3);
+        assert_eq!(limits.memory_fraction(0.25), None);
+    }
+}
@@ -0,0 +1,454 @@
+//! Per-RPC metrics of the gRPC services of a server.
+
+use bytes::Buf;
+use futures::ready;
+use http::{HeaderMap, Request, Response};
+use http_body::{Body as HttpBody, SizeHint};
+use hyper::Body;
+use metric::{
+    Attributes, DurationHistogram, Metric, U64Counter, U64Histogram, U64HistogramOptions,
+};
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Instant,
+};
+use tonic::Code;
+use tower::{Layer, Service};
+
+/// Bucket thresholds of the message size histograms, in bytes.
+const SIZE_BUCKETS: [u64; 8] = [
+    1 << 10,
+    1 << 13,
+    1 << 16,
+    1 << 19,
+    1 << 22,
+    1 << 25,
+    1 << 28,
+    u64::MAX,
+];
+
+/// `RpcMetricsLayer` implements `tower::Layer` and records the following metrics for every RPC,
+/// labelled with its gRPC `service` and `method`:
+///
+/// - `grpc_rpc_requests`: the number of RPCs, by status `code`
+/// - `grpc_rpc_duration`: the distribution of the latencies of RPCs until their status is
+///   returned, by status `code`
+/// - `grpc_rpc_request_bytes`: the distribution of the total size of the request messages of RPCs
+/// - `grpc_rpc_response_bytes`: the distribution of the total size of the response messages of
+///   RPCs
+///
+/// RPCs whose response is dropped before returning a status, e.g. because the client went away,
+/// are recorded with the `Cancelled` code.
+#[derive(Debug, Clone)]
+pub struct RpcMetricsLayer {
+    metrics: Arc<RpcMetrics>,
+}
+
+impl RpcMetricsLayer {
+    pub fn new(metric_registry: &metric::Registry) -> Self {
+        Self {
+            metrics: Arc::new(RpcMetrics::new(metric_registry)),
+        }
+    }
+}
+
+impl<S> Layer<S> for RpcMetricsLayer {
+    type Service = RpcMetricsService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RpcMetricsService {
+            service,
+            metrics: Arc::clone(&self.metrics),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RpcMetrics {
+    requests: Metric<U64Counter>,
+    duration: Metric<DurationHistogram>,
+    request_bytes: Metric<U64Histogram>,
+    response_bytes: Metric<U64Histogram>,
+}
+
+impl RpcMetrics {
+    fn new(registry: &metric::Registry) -> Self {
+        let size_options = || U64HistogramOptions::new(SIZE_BUCKETS);
+
+        Self {
+            requests: registry.register_metric("grpc_rpc_requests", "accumulated total RPCs"),
+            duration: registry
+                .register_metric("grpc_rpc_duration", "distribution of RPC latencies"),
+            request_bytes: registry.register_metric_with_options(
+                "grpc_rpc_request_bytes",
+                "distribution of the total size of the request messages of an RPC",
+                size_options,
+            ),
+            response_bytes: registry.register_metric_with_options(
+                "grpc_rpc_response_bytes",
+                "distribution of the total size of the response messages of an RPC",
+                size_options,
+            ),
+        }
+    }
+}
+
+/// `RpcMetricsService` wraps an inner `tower::Service` and records the metrics of the RPCs it
+/// serves
+#[derive(Debug, Clone)]
+pub struct RpcMetricsService<S> {
+    service: S,
+    metrics: Arc<RpcMetrics>,
+}
+
+impl<S, ResBody> Service<Request<Body>> for RpcMetricsService<S>
+where
+    S: Service<Request<Body>, Response = Response<ResBody>>,
+    ResBody: HttpBody,
+{
+    type Response = Response<RpcMetricsBody<ResBody>>;
+    type Error = S::Error;
+    type Future = RpcMetricsFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let recorder = RpcRecorder::new(Arc::clone(&self.metrics), request.uri().path());
+        let request = count_request_bytes(request, Arc::clone(&recorder.request_bytes));
+
+        RpcMetricsFuture {
+            recorder: Some(recorder),
+            inner: self.service.call(request),
+        }
+    }
+}
+
+/// Forward the body of `request` through a new body, adding the size of its data to `bytes`.
+///
+/// The inner service requires a [`hyper::Body`], which cannot be wrapped, so the data is copied
+/// from the original body into a channel body by a separate task.
+fn count_request_bytes(request: Request<Body>, bytes: Arc<AtomicU64>) -> Request<Body> {
+    let (parts, mut body) = request.into_parts();
+    let (mut sender, counted_body) = Body::channel();
+
+    tokio::spawn(async move {
+        while let Some(data) = body.data().await {
+            match data {
+                Ok(data) => {
+                    bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+                    if sender.send_data(data).await.is_err() {
+                        // the inner service dropped the request body
+                        return;
+                    }
+                }
+                Err(_) => {
+                    sender.abort();
+                    return;
+                }
+            }
+        }
+
+        // gRPC clients don't send trailers, but forward them for completeness
+        match body.trailers().await {
+            Ok(Some(trailers)) => {
+                if sender.send_trailers(trailers).await.is_err() {
+                    sender.abort();
+                }
+            }
+            Ok(None) => {}
+            Err(_) => sender.abort(),
+        }
+    });
+
+    Request::from_parts(parts, counted_body)
+}
+
+/// `RpcMetricsFuture` wraps the future returned by the inner `tower::Service` and instruments
+/// the body of its response
+#[pin_project]
+#[derive(Debug)]
+pub struct RpcMetricsFuture<F> {
+    recorder: Option<RpcRecorder>,
+    #[pin]
+    inner: F,
+}
+
+impl<F, ResBody, Error> Future for RpcMetricsFuture<F>
+where
+    F: Future<Output = Result<Response<ResBody>, Error>>,
+    ResBody: HttpBody,
+{
+    type Output = Result<Response<RpcMetricsBody<ResBody>>, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let projected = self.project();
+        let result = ready!(projected.inner.poll(cx));
+        let mut recorder = projected
+            .recorder
+            .take()
+            .expect("future polled after completion");
+
+        match result {
+            Ok(response) => {
+                // RPCs that fail before returning any message return their status in the headers
+                recorder.set_code(response.headers());
+                Poll::Ready(Ok(response.map(|inner| RpcMetricsBody { recorder, inner })))
+            }
+            Err(e) => {
+                recorder.code = Some(Code::Unknown);
+                Poll::Ready(Err(e))
+            }
+        }
+    }
+}
+
+/// `RpcMetricsBody` wraps the body of a response, recording the metrics of the RPC once it is
+/// dropped
+#[pin_project]
+#[derive(Debug)]
+pub struct RpcMetricsBody<B> {
+    recorder: RpcRecorder,
+    #[pin]
+    inner: B,
+}
+
+impl<B: HttpBody> HttpBody for RpcMetricsBody<B> {
+    type Data = B::Data;
+    type Error = B::Error;
+
+    fn poll_data(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let projected = self.project();
+        let result = ready!(projected.inner.poll_data(cx));
+
+        match &result {
+            Some(Ok(data)) => projected.recorder.response_bytes += data.remaining() as u64,
+            Some(Err(_)) => projected.recorder.code = Some(Code::Unknown),
+            None => {}
+        }
+
+        Poll::Ready(result)
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        let projected = self.project();
+        let result = ready!(projected.inner.poll_trailers(cx));
+
+        match &result {
+            Ok(Some(trailers)) => projected.recorder.set_code(trailers),
+            Ok(None) => {}
+            Err(_) => projected.recorder.code = Some(Code::Unknown),
+        }
+
+        Poll::Ready(result)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Records the metrics of a single RPC when dropped
+#[derive(Debug)]
+struct RpcRecorder {
+    metrics: Arc<RpcMetrics>,
+    /// The service and method of the RPC
+    attributes: Attributes,
+    start_instant: Instant,
+    /// Updated by the task forwarding the request body
+    request_bytes: Arc<AtomicU64>,
+    response_bytes: u64,
+    /// The status code of the RPC, or None if it did not return one yet
+    code: Option<Code>,
+}
+
+impl RpcRecorder {
+    fn new(metrics: Arc<RpcMetrics>, path: &str) -> Self {
+        // gRPC request paths are `/<service>/<method>`
+        let (service, method) = path
+            .strip_prefix('/')
+            .and_then(|path| path.split_once('/'))
+            .unwrap_or(("unknown", "unknown"));
+
+        Self {
+            metrics,
+            attributes: Attributes::from([
+                ("service", service.to_string().into()),
+                ("method", method.to_string().into()),
+            ]),
+            start_instant: Instant::now(),
+            request_bytes: Default::default(),
+            response_bytes: 0,
+            code: None,
+        }
+    }
+
+    /// Sets the status code of the RPC from the `grpc-status` header, if any
+    fn set_code(&mut self, headers: &HeaderMap) {
+        if let Some(status) = headers.get("grpc-status") {
+            self.code = Some(Code::from_bytes(status.as_bytes()));
+        }
+    }
+}
+
+impl Drop for RpcRecorder {
+    fn drop(&mut self) {
+        let mut attributes = self.attributes.clone();
+
+        self.metrics
+            .request_bytes
+            .recorder(attributes.clone())
+            .record(self.request_bytes.load(Ordering::Relaxed));
+        self.metrics
+            .response_bytes
+            .recorder(attributes.clone())
+            .record(self.response_bytes);
+
+        attributes.insert(
+            "code",
+            format!("{:?}", self.code.unwrap_or(Code::Cancelled)),
+        );
+        self.metrics.requests.recorder(attributes.clone()).inc(1);
+        self.metrics
+            .duration
+            .recorder(attributes)
+            .record(self.start_instant.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+    use metric::{Observation, RawReporter};
+    use std::convert::Infallible;
+    use tower::ServiceExt;
+
+    const PATH: &str = "/influxdata.iox.test.v1.Test/Echo";
+
+    #[tokio::test]
+    async fn test_ok() {
+        let registry = metric::Registry::new();
+        let service = RpcMetricsLayer::new(&registry).layer(tower::service_fn(
+            |request: Request<Body>| async move {
+                let data = hyper::body::to_bytes(request.into_body()).await.unwrap();
+
+                let (mut sender, body) = Body::channel();
+                tokio::spawn(async move {
+                    sender.send_data(data.clone()).await.unwrap();
+                    sender.send_data(data).await.unwrap();
+
+                    let mut trailers = HeaderMap::new();
+                    trailers.insert("grpc-status", HeaderValue::from_static("0"));
+                    sender.send_trailers(trailers).await.unwrap();
+                });
+                Ok::<_, Infallible>(Response::new(body))
+            },
+        ));
+
+        let response = service.oneshot(request("hello")).await.unwrap();
+        let mut body = response.into_body();
+        while body.data().await.is_some() {}
+        body.trailers().await.unwrap();
+        drop(body);
+
+        assert_eq!(requests(&registry, "Ok"), 1);
+        assert_eq!(sizes(&registry, "grpc_rpc_request_bytes"), (1, 5));
+        assert_eq!(sizes(&registry, "grpc_rpc_response_bytes"), (1, 10));
+    }
+
+    #[tokio::test]
+    async fn test_error_in_headers() {
+        let registry = metric::Registry::new();
+        let service = RpcMetricsLayer::new(&registry).layer(tower::service_fn(
+            |_request: Request<Body>| async move {
+                let response = Response::builder()
+                    .header("grpc-status", "3")
+                    .body(Body::empty())
+                    .unwrap();
+                Ok::<_, Infallible>(response)
+            },
+        ));
+
+        let response = service.oneshot(request("")).await.unwrap();
+        drop(response);
+
+        assert_eq!(requests(&registry, "InvalidArgument"), 1);
+        assert_eq!(requests(&registry, "Cancelled"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled() {
+        let registry = metric::Registry::new();
+        let service = RpcMetricsLayer::new(&registry).layer(tower::service_fn(
+            |_request: Request<Body>| async move {
+                Ok::<_, Infallible>(Response::new(Body::from("partial")))
+            },
+        ));
+
+        let response = service.oneshot(request("")).await.unwrap();
+        drop(response);
+
+        assert_eq!(requests(&registry, "Cancelled"), 1);
+    }
+
+    fn request(body: &'static str) -> Request<Body> {
+        Request::builder()
+            .uri(format!("http://localhost{PATH}"))
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    fn requests(registry: &metric::Registry, code: &'static str) -> u64 {
+        registry
+            .get_instrument::<Metric<U64Counter>>("grpc_rpc_requests")
+            .unwrap()
+            .get_observer(&Attributes::from(&[
+                ("service", "influxdata.iox.test.v1.Test"),
+                ("method", "Echo"),
+                ("code", code),
+            ]))
+            .map(|observer| observer.fetch())
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of observations and their total of a size histogram.
+    fn sizes(registry: &metric::Registry, name: &'static str) -> (u64, u64) {
+        let mut reporter = RawReporter::default();
+        registry.report(&mut reporter);
+
+        match reporter
+            .metric(name)
+            .unwrap()
+            .observation(&[
+                ("service", "influxdata.iox.test.v1.Test"),
+                ("method", "Echo"),
+            ])
+            .unwrap()
+        {
+            Observation::U64Histogram(histogram) => (
+                histogram.buckets.iter().map(|b| b.count).sum(),
+                histogram.total,
+            ),
+            observation => panic!("unexpected observation: {observation:?}"),
+        }
+    }
+}
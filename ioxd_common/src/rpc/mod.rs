@@ -1,6 +1,7 @@
 use std::any::Any;
 use std::sync::Arc;
 
+use clap_blocks::grpc_server::GrpcServerConfig;
 use tokio::net::TcpListener;
 use tokio_util::sync::CancellationToken;
 use tonic::{body::BoxBody, transport::NamedService, Code};
@@ -9,6 +10,8 @@ use trace_http::ctx::TraceHeaderParser;
 
 use crate::server_type::{RpcError, ServerType};
 
+pub mod metrics;
+
 /// Returns the name of the gRPC service S.
 pub fn service_name<S: NamedService>(_: &S) -> &'static str {
     S::NAME
@@ -19,6 +22,7 @@ pub struct RpcBuilderInput {
     pub socket: TcpListener,
     pub trace_header_parser: TraceHeaderParser,
     pub shutdown: CancellationToken,
+    pub config: GrpcServerConfig,
 }
 
 #[derive(Debug)]
@@ -27,6 +31,7 @@ pub struct RpcBuilder<T> {
     pub health_reporter: HealthReporter,
     pub shutdown: CancellationToken,
     pub socket: TcpListener,
+    pub tcp_nodelay: bool,
 }
 
 /// Adds a gRPC service to the builder, and registers it with the
@@ -47,6 +52,7 @@ macro_rules! add_service {
                     mut health_reporter,
                     shutdown,
                     socket,
+                    tcp_nodelay,
                 } = $builder;
                 let service = $svc;
 
@@ -62,6 +68,7 @@ macro_rules! add_service {
                     health_reporter,
                     shutdown,
                     socket,
+                    tcp_nodelay,
                 }
             }
         };
@@ -82,6 +89,7 @@ macro_rules! setup_builder {
             socket,
             trace_header_parser,
             shutdown,
+            config,
         } = $input;
 
         let (health_reporter, health_service) =
@@ -93,13 +101,21 @@ macro_rules! setup_builder {
             .build()
             .expect("gRPC reflection data broken");
 
-        let builder = $crate::reexport::tonic::transport::Server::builder();
+        let builder = $crate::reexport::tonic::transport::Server::builder()
+            .http2_keepalive_interval(config.keepalive_interval)
+            .http2_keepalive_timeout(config.keepalive_timeout)
+            .max_concurrent_streams(config.max_concurrent_streams)
+            .max_frame_size(config.max_frame_size)
+            .initial_stream_window_size(config.initial_stream_window_size)
+            .initial_connection_window_size(config.initial_connection_window_size);
         let builder = builder
             .layer($crate::reexport::trace_http::tower::TraceLayer::new(
                 trace_header_parser,
-                $server_type.metric_registry(),
+                None,
                 $server_type.trace_collector(),
-                true,
+            ))
+            .layer($crate::rpc::metrics::RpcMetricsLayer::new(
+                &$server_type.metric_registry(),
             ))
             .layer(
                 $crate::reexport::tower_http::catch_panic::CatchPanicLayer::custom(
@@ -112,6 +128,7 @@ macro_rules! setup_builder {
             health_reporter,
             shutdown,
             socket,
+            tcp_nodelay: config.tcp_nodelay,
         };
 
         add_service!(builder, health_service);
@@ -129,17 +146,25 @@ macro_rules! setup_builder {
 #[macro_export]
 macro_rules! serve_builder {
     ($builder:ident) => {{
-        use $crate::reexport::tokio_stream::wrappers::TcpListenerStream;
+        use $crate::reexport::tokio_stream::{wrappers::TcpListenerStream, StreamExt};
         use $crate::rpc::RpcBuilder;
 
         let RpcBuilder {
             inner,
             shutdown,
             socket,
+            tcp_nodelay,
             ..
         } = $builder;
 
-        let stream = TcpListenerStream::new(socket);
+        // the server only applies its TCP settings to connections it accepts itself
+        let stream = TcpListenerStream::new(socket).map(move |stream| {
+            stream.map(|stream| {
+                // fails only if the connection is already closed, which the server notices
+                stream.set_nodelay(tcp_nodelay).ok();
+                stream
+            })
+        });
         inner
             .serve_with_incoming_shutdown(stream, shutdown.cancelled())
             .await?;
@@ -173,11 +198,13 @@ pub async fn serve(
     server_type: Arc<dyn ServerType>,
     trace_header_parser: TraceHeaderParser,
     shutdown: CancellationToken,
+    config: GrpcServerConfig,
 ) -> Result<(), RpcError> {
     let builder_input = RpcBuilderInput {
         socket,
         trace_header_parser,
         shutdown,
+        config,
     };
 
     server_type.server_grpc(builder_input).await
@@ -5,6 +5,8 @@ use trace::TraceCollector;
 
 use clap_blocks::run_config::RunConfig;
 
+use crate::resources::ResourceLimits;
+
 #[derive(Debug, Snafu)]
 pub enum CommonServerStateError {
     #[snafu(display("Cannot create tracing pipeline: {}", source))]
@@ -16,6 +18,7 @@ pub enum CommonServerStateError {
 pub struct CommonServerState {
     run_config: RunConfig,
     trace_exporter: Option<Arc<trace_exporters::export::AsyncExporter>>,
+    resources: ResourceLimits,
 }
 
 impl CommonServerState {
@@ -25,6 +28,7 @@ impl CommonServerState {
         Ok(Self {
             run_config,
             trace_exporter,
+            resources: ResourceLimits::detect(),
         })
     }
 
@@ -41,6 +45,11 @@ impl CommonServerState {
         &self.run_config
     }
 
+    /// The CPU and memory available to the server.
+    pub fn resources(&self) -> &ResourceLimits {
+        &self.resources
+    }
+
     pub fn trace_exporter(&self) -> Option<Arc<trace_exporters::export::AsyncExporter>> {
         self.trace_exporter.clone()
     }
@@ -18,7 +18,7 @@ use ioxd_common::{
     setup_builder,
 };
 use metric::Registry;
-use parquet_file::storage::ParquetStorage;
+use parquet_file::{serialize::ParquetWriterOptions, storage::ParquetStorage};
 use std::{
     fmt::{Debug, Display},
     sync::Arc,
@@ -204,6 +204,7 @@ pub async fn build_compactor_from_config(
         max_desired_file_size_bytes,
         percentage_max_file_size,
         split_percentage,
+        max_output_file_size_bytes,
         max_number_partitions_per_shard,
         min_number_recent_ingested_files_per_partition,
         hot_multiple,
@@ -214,13 +215,21 @@ pub async fn build_compactor_from_config(
         minutes_without_new_writes_to_be_cold,
         hot_compaction_hours_threshold_1,
         hot_compaction_hours_threshold_2,
+        bloom_filter_columns,
+        tag_index_columns,
         ..
     } = compactor_config;
 
+    let parquet_store = parquet_store.with_writer_options(ParquetWriterOptions {
+        bloom_filter_columns,
+        ..Default::default()
+    });
+
     let compactor_config = compactor::handler::CompactorConfig {
         max_desired_file_size_bytes,
         percentage_max_file_size,
         split_percentage,
+        max_output_file_size_bytes,
         max_number_partitions_per_shard,
         min_number_recent_ingested_files_per_partition,
         hot_multiple,
@@ -242,5 +251,6 @@ pub async fn build_compactor_from_config(
         backoff::BackoffConfig::default(),
         compactor_config,
         metric_registry,
-    ))
+    )
+    .with_tag_index_columns(tag_index_columns))
 }
@@ -1,9 +1,15 @@
 use async_trait::async_trait;
-use clap_blocks::{ingester::IngesterConfig, write_buffer::WriteBufferConfig};
+use clap_blocks::{
+    ingester::{IngesterConfig, ParquetCompression},
+    write_buffer::WriteBufferConfig,
+};
 use data_types::ShardIndex;
 use hyper::{Body, Request, Response};
 use ingester::{
+    assignment::ShardAssignment,
+    drain::Drain,
     handler::{IngestHandler, IngestHandlerImpl},
+    lease::ShardLeaseConfig,
     lifecycle::LifecycleConfig,
     server::{grpc::GrpcDelegate, http::HttpDelegate, IngesterServer},
 };
@@ -12,6 +18,7 @@ use iox_query::exec::Executor;
 use ioxd_common::{
     add_service,
     http::error::{HttpApiError, HttpApiErrorCode, HttpApiErrorSource},
+    resources::ResourceLimits,
     rpc::RpcBuilderInput,
     serve_builder,
     server_type::{CommonServerState, RpcError, ServerType},
@@ -19,6 +26,7 @@ use ioxd_common::{
 };
 use metric::Registry;
 use object_store::DynObjectStore;
+use parquet_file::serialize::{Compression, ParquetWriterOptions};
 use std::{
     collections::BTreeMap,
     fmt::{Debug, Display},
@@ -44,6 +52,12 @@ pub enum Error {
 
     #[error("error initializing write buffer {0}")]
     WriteBuffer(#[from] write_buffer::core::WriteBufferError),
+
+    #[error("cannot detect the available memory, {0} must be set")]
+    UnknownMemoryLimit(&'static str),
+
+    #[error("--catalog-shard-assignment requires --advertise-address")]
+    NoAdvertiseAddress,
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -51,6 +65,9 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 pub struct IngesterServerType<I: IngestHandler> {
     server: IngesterServer<I>,
     trace_collector: Option<Arc<dyn TraceCollector>>,
+
+    /// Serve the shard assignment gRPC API.
+    shard_reassignment: bool,
 }
 
 impl<I: IngestHandler> std::fmt::Debug for IngesterServerType<I> {
@@ -64,6 +81,16 @@ impl<I: IngestHandler> IngesterServerType<I> {
         Self {
             server,
             trace_collector: common_state.trace_collector(),
+            shard_reassignment: false,
+        }
+    }
+
+    /// Serve the shard assignment gRPC API, allowing the shards consumed to be
+    /// changed at runtime.
+    pub fn with_shard_reassignment(self, shard_reassignment: bool) -> Self {
+        Self {
+            shard_reassignment,
+            ..self
         }
     }
 }
@@ -95,6 +122,12 @@ impl<I: IngestHandler + Sync + Send + Debug + 'static> ServerType for IngesterSe
         add_service!(builder, self.server.grpc().flight_service());
         add_service!(builder, self.server.grpc().write_info_service());
         add_service!(builder, self.server.grpc().catalog_service());
+        add_service!(builder, self.server.grpc().operations_service());
+        if self.shard_reassignment {
+            add_service!(builder, self.server.grpc().shard_assignment_service());
+        }
+        add_service!(builder, self.server.grpc().drain_service());
+        add_service!(builder, self.server.grpc().persist_service());
 
         serve_builder!(builder);
 
@@ -138,6 +171,14 @@ impl HttpApiErrorSource for IoxHttpError {
     }
 }
 
+/// Fraction of the available memory the ingester buffers before pausing ingest, unless
+/// configured explicitly.
+const PAUSE_INGEST_MEMORY_FRACTION: f64 = 0.5;
+
+/// Fraction of the available memory the ingester buffers before persisting, unless configured
+/// explicitly.
+const PERSIST_MEMORY_FRACTION: f64 = 0.3;
+
 /// Instantiate an ingester server type
 pub async fn create_ingester_server_type(
     common_state: &CommonServerState,
@@ -170,23 +211,56 @@ pub async fn create_ingester_server_type(
     }
     txn.commit().await?;
 
+    let shard_assignment = if ingester_config.catalog_shard_assignment {
+        let address = ingester_config
+            .advertise_address
+            .as_ref()
+            .ok_or(Error::NoAdvertiseAddress)?;
+        Some(ShardAssignment::new(
+            address,
+            topic.clone(),
+            Arc::clone(&catalog),
+            catalog.time_provider(),
+        ))
+    } else {
+        None
+    };
+
     let trace_collector = common_state.trace_collector();
 
+    // Connect to all shards if the assignment may change at runtime.
+    let read_shards = (!ingester_config.shard_reassignment
+        && !ingester_config.catalog_shard_assignment)
+        .then_some(shard_range);
     let write_buffer = write_buffer_config
         .reading(
             Arc::clone(&metric_registry),
-            Some(shard_range),
+            read_shards,
             trace_collector.clone(),
         )
         .await?;
 
     let lifecycle_config = LifecycleConfig::new(
-        ingester_config.pause_ingest_size_bytes,
-        ingester_config.persist_memory_threshold_bytes,
+        memory_threshold(
+            ingester_config.pause_ingest_size_bytes,
+            common_state.resources(),
+            PAUSE_INGEST_MEMORY_FRACTION,
+            "--pause-ingest-size-bytes",
+        )?,
+        memory_threshold(
+            ingester_config.persist_memory_threshold_bytes,
+            common_state.resources(),
+            PERSIST_MEMORY_FRACTION,
+            "--persist-memory-threshold-bytes",
+        )?,
         ingester_config.persist_partition_size_threshold_bytes,
         Duration::from_secs(ingester_config.persist_partition_age_threshold_seconds),
         Duration::from_secs(ingester_config.persist_partition_cold_threshold_seconds),
         ingester_config.persist_partition_rows_max,
+    )
+    .with_persist_concurrency(
+        Some(ingester_config.persist_max_concurrency),
+        ingester_config.persist_memory_budget_bytes,
     );
     let grpc_catalog = Arc::clone(&catalog);
     let ingest_handler = Arc::new(
@@ -201,18 +275,99 @@ pub async fn create_ingester_server_type(
             Arc::clone(&metric_registry),
             ingester_config.skip_to_oldest_available,
             ingester_config.concurrent_request_limit,
+            ingester_config.presort_persist,
+            parquet_writer_options(&ingester_config),
+            shard_lease_config(&ingester_config),
+            ingester_config.job_node.clone(),
         )
         .await?,
     );
     let http = HttpDelegate::new(Arc::clone(&ingest_handler));
-    let grpc = GrpcDelegate::new(
-        grpc_catalog,
+    let mut grpc = GrpcDelegate::new(
+        Arc::clone(&grpc_catalog),
         Arc::clone(&ingest_handler),
         Arc::new(AtomicU64::new(ingester_config.test_flight_do_get_panic)),
     );
+    if let Some(address) = &ingester_config.advertise_address {
+        let time_provider = grpc_catalog.time_provider();
+        let drain = Drain::new(
+            address,
+            grpc_catalog,
+            Arc::clone(&ingest_handler) as _,
+            time_provider,
+        )
+        .await?;
+        grpc = grpc.with_drain(Arc::new(drain));
+    }
+    // Start after the ingester is recorded as active, so it is assigned shards.
+    if let Some(shard_assignment) = shard_assignment {
+        ingest_handler.start_shard_assignment(shard_assignment);
+    }
 
     let ingester = IngesterServer::new(metric_registry, http, grpc, ingest_handler);
-    let server_type = Arc::new(IngesterServerType::new(ingester, common_state));
+    let server_type = Arc::new(
+        IngesterServerType::new(ingester, common_state)
+            .with_shard_reassignment(ingester_config.shard_reassignment),
+    );
 
     Ok(server_type)
 }
+
+/// The `configured` memory threshold in bytes, or the given `fraction` of the available memory.
+fn memory_threshold(
+    configured: Option<usize>,
+    resources: &ResourceLimits,
+    fraction: f64,
+    option: &'static str,
+) -> Result<usize> {
+    configured
+        .or_else(|| resources.memory_fraction(fraction))
+        .ok_or(Error::UnknownMemoryLimit(option))
+}
+
+/// Build the shard lease config of the ingester, if it runs alongside other
+/// ingesters consuming the same shards.
+///
+/// With the catalog shard assignment, the leases hand the shards over between
+/// ingesters and are held under the address the assignment knows the
+/// ingester by.
+fn shard_lease_config(config: &IngesterConfig) -> Option<ShardLeaseConfig> {
+    if !config.shard_leases && !config.catalog_shard_assignment {
+        return None;
+    }
+
+    let lease_config =
+        ShardLeaseConfig::new(Duration::from_secs(config.shard_lease_duration_seconds))
+            .with_standby_persist_timeout(Duration::from_secs(
+                config.standby_persist_timeout_seconds,
+            ));
+    let holder = if config.catalog_shard_assignment {
+        config.advertise_address.as_ref()
+    } else {
+        config.shard_lease_holder.as_ref()
+    };
+    Some(match holder {
+        Some(holder) => lease_config.with_holder(holder),
+        None => lease_config,
+    })
+}
+
+/// Build the options for the parquet files the ingester persists.
+fn parquet_writer_options(config: &IngesterConfig) -> ParquetWriterOptions {
+    let compression = match config.persist_compression {
+        ParquetCompression::Uncompressed => Compression::UNCOMPRESSED,
+        ParquetCompression::Snappy => Compression::SNAPPY,
+        ParquetCompression::Gzip => Compression::GZIP,
+        ParquetCompression::Lz4 => Compression::LZ4,
+        ParquetCompression::Zstd => Compression::ZSTD,
+    };
+
+    ParquetWriterOptions {
+        max_row_group_size: config.persist_row_group_size.get(),
+        data_page_size: config.persist_data_page_size,
+        compression,
+        tag_dictionary: !config.persist_disable_tag_dictionary,
+        field_dictionary: !config.persist_disable_field_dictionary,
+        bloom_filter_columns: config.persist_bloom_filter_columns.clone(),
+    }
+}
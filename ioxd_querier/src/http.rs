@@ -0,0 +1,450 @@
+//! The HTTP query API of the querier, for integrations that cannot consume
+//! Arrow Flight.
+//!
+//! `GET /api/v3/query?namespace=<namespace>&q=<sql>&format=<format>` runs the
+//! SQL query `q` against `namespace` and streams the results with chunked
+//! transfer encoding as they are produced, in one of the [`ExportFormat`]s.
+//!
+//! Errors that occur after the response started are reported by ending the
+//! response early, without the terminating chunk.
+
+use std::{str::FromStr, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, StringArray},
+    compute::cast,
+    csv::WriterBuilder,
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    error::ArrowError,
+    json::LineDelimitedWriter,
+    record_batch::RecordBatch,
+};
+use bytes::Bytes;
+use data_types::ApiTokenPermission;
+use datafusion::physical_plan::ExecutionPlan;
+use futures::{stream, StreamExt, TryStreamExt};
+use hyper::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    Body, Method, Request, Response,
+};
+use iox_catalog::authz::{token_from_header, Authorizer, AuthzError};
+use iox_query::{exec::ExecutionContextProvider, QueryNamespace};
+use ioxd_common::http::error::{HttpApiError, HttpApiErrorCode, HttpApiErrorSource};
+use observability_deps::tracing::{debug, info};
+use serde::Deserialize;
+use service_common::{planner::Planner, QueryNamespaceProvider};
+use thiserror::Error;
+use trace::{ctx::SpanContext, span::SpanExt};
+
+/// Errors of the HTTP query API.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("not found")]
+    NoHandler,
+
+    #[error("invalid query string: {0}")]
+    InvalidQueryString(#[from] serde_urlencoded::de::Error),
+
+    #[error("unknown format {0}, expected one of 'csv' or 'jsonl'")]
+    UnknownFormat(String),
+
+    #[error("namespace {0} not found")]
+    NamespaceNotFound(String),
+
+    #[error("authorization failure: {0}")]
+    Unauthorized(#[from] AuthzError),
+
+    #[error("error planning query: {0}")]
+    Planning(service_common::planner::Error),
+
+    #[error("error running query: {0}")]
+    Query(datafusion::error::DataFusionError),
+}
+
+impl HttpApiErrorSource for Error {
+    fn to_http_api_error(&self) -> HttpApiError {
+        let code = match self {
+            Self::NoHandler | Self::NamespaceNotFound(_) => HttpApiErrorCode::NotFound,
+            Self::InvalidQueryString(_) | Self::UnknownFormat(_) | Self::Planning(_) => {
+                HttpApiErrorCode::Invalid
+            }
+            Self::Unauthorized(AuthzError::MissingToken | AuthzError::InvalidToken) => {
+                HttpApiErrorCode::Unauthorized
+            }
+            Self::Unauthorized(AuthzError::PermissionDenied { .. }) => HttpApiErrorCode::Forbidden,
+            Self::Unauthorized(AuthzError::Catalog(_)) | Self::Query(_) => {
+                HttpApiErrorCode::InternalError
+            }
+        };
+        HttpApiError::new(code, self.to_string())
+    }
+}
+
+/// The formats query results are streamed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Annotated CSV: a `#datatype` row with the type of each column and a
+    /// header row precede the rows of data. Every row starts with the
+    /// annotation column, which is empty in the header and data rows.
+    ///
+    /// ```text
+    /// #datatype,string,double,dateTime:RFC3339
+    /// ,region,usage,time
+    /// ,us-west,0.64,2022-12-01T00:00:00.000000000Z
+    /// ```
+    Csv,
+
+    /// One JSON object per row, separated by newlines.
+    ///
+    /// ```text
+    /// {"region":"us-west","usage":0.64,"time":"2022-12-01T00:00:00"}
+    /// ```
+    JsonLines,
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "jsonl" => Ok(Self::JsonLines),
+            _ => Err(Error::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+impl ExportFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::JsonLines => "application/jsonl",
+        }
+    }
+
+    /// The data preceding the first batch of a response with `schema`.
+    fn header(&self, schema: &Schema) -> Bytes {
+        match self {
+            Self::Csv => {
+                let types = schema.fields().iter().map(|f| csv_type(f.data_type()));
+                let names = schema.fields().iter().map(|f| csv_escape(f.name()));
+                let datatype = std::iter::once("#datatype".to_string())
+                    .chain(types.map(ToString::to_string))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let header = std::iter::once(String::new())
+                    .chain(names)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                Bytes::from(format!("{datatype}\n{header}\n"))
+            }
+            Self::JsonLines => Bytes::new(),
+        }
+    }
+
+    /// Encode the rows of `batch`.
+    fn encode(&self, batch: &RecordBatch) -> Result<Bytes, ArrowError> {
+        let batch = plain_batch(batch)?;
+        let mut bytes = vec![];
+        match self {
+            Self::Csv => {
+                // Prepend the empty annotation column.
+                let annotation =
+                    Arc::new(StringArray::from(vec![None::<&str>; batch.num_rows()])) as ArrayRef;
+                let fields = std::iter::once(Field::new("", DataType::Utf8, true))
+                    .chain(batch.schema().fields().iter().cloned())
+                    .collect();
+                let columns = std::iter::once(annotation)
+                    .chain(batch.columns().iter().cloned())
+                    .collect();
+                let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?;
+
+                let mut writer = WriterBuilder::new()
+                    .has_headers(false)
+                    .with_timestamp_format("%Y-%m-%dT%H:%M:%S%.9fZ".to_string())
+                    .build(&mut bytes);
+                writer.write(&batch)?;
+            }
+            Self::JsonLines => {
+                let mut writer = LineDelimitedWriter::new(&mut bytes);
+                writer.write_batches(&[batch])?;
+                writer.finish()?;
+            }
+        }
+        Ok(Bytes::from(bytes))
+    }
+}
+
+/// The annotated CSV type of columns of `data_type`.
+fn csv_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => "long",
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => "unsignedLong",
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => "double",
+        DataType::Boolean => "boolean",
+        DataType::Timestamp(_, _) => "dateTime:RFC3339",
+        DataType::Dictionary(_, value) => csv_type(value),
+        _ => "string",
+    }
+}
+
+/// Quote `value` for use as a CSV field if needed.
+fn csv_escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Unpack the dictionary encoded columns of `batch`, which the CSV and JSON
+/// writers do not support.
+fn plain_batch(batch: &RecordBatch) -> Result<RecordBatch, ArrowError> {
+    if !batch
+        .schema()
+        .fields()
+        .iter()
+        .any(|f| matches!(f.data_type(), DataType::Dictionary(_, _)))
+    {
+        return Ok(batch.clone());
+    }
+
+    let schema: SchemaRef = Arc::new(Schema::new(
+        batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| match f.data_type() {
+                DataType::Dictionary(_, value) => {
+                    Field::new(f.name(), value.as_ref().clone(), f.is_nullable())
+                }
+                _ => f.clone(),
+            })
+            .collect(),
+    ));
+    let columns = batch
+        .columns()
+        .iter()
+        .zip(schema.fields())
+        .map(|(c, f)| cast(c, f.data_type()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    RecordBatch::try_new(schema, columns)
+}
+
+/// The query string of a query request.
+#[derive(Debug, Deserialize)]
+struct QueryParams {
+    namespace: String,
+    q: String,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+/// Serves the HTTP query API for the namespaces of `server`.
+#[derive(Debug)]
+pub struct HttpQueryDelegate<S> {
+    server: Arc<S>,
+
+    /// Authorizes the queries, if authorization is enabled.
+    authorizer: Option<Arc<Authorizer>>,
+}
+
+impl<S> HttpQueryDelegate<S>
+where
+    S: QueryNamespaceProvider,
+{
+    /// Create the HTTP query API, requiring an API token allowing to read the
+    /// queried namespace if `authorizer` is set.
+    pub fn new(server: Arc<S>, authorizer: Option<Arc<Authorizer>>) -> Self {
+        Self { server, authorizer }
+    }
+
+    /// Route `req` to its handler.
+    pub async fn route(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        match (req.method(), req.uri().path()) {
+            (&Method::GET | &Method::POST, "/api/v3/query") => self.query(req).await,
+            _ => Err(Error::NoHandler),
+        }
+    }
+
+    async fn query(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let span_ctx: Option<SpanContext> = req.extensions().get().cloned();
+        let params: QueryParams = serde_urlencoded::from_str(req.uri().query().unwrap_or(""))?;
+        let format = match &params.format {
+            Some(format) => format.parse()?,
+            None => ExportFormat::Csv,
+        };
+
+        if let Some(authorizer) = &self.authorizer {
+            let token = req
+                .headers()
+                .get(AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(token_from_header);
+            authorizer
+                .authorize(token, Some(&params.namespace), ApiTokenPermission::Read)
+                .await?;
+        }
+
+        let db = self
+            .server
+            .db(&params.namespace, span_ctx.child_span("get namespace"))
+            .await
+            .ok_or_else(|| Error::NamespaceNotFound(params.namespace.clone()))?;
+
+        let permit = self
+            .server
+            .acquire_semaphore(span_ctx.child_span("query rate limit semaphore"))
+            .await;
+        info!(namespace=%params.namespace, sql_query=%params.q, ?format, "Running SQL via HTTP");
+
+        let ctx = db.new_query_context(span_ctx);
+        let mut query_completed_token = db.record_query(&ctx, "sql", Box::new(params.q.clone()));
+        let physical_plan = Planner::new(&ctx)
+            .sql(params.q)
+            .await
+            .map_err(Error::Planning)?;
+        let schema = physical_plan.schema();
+        let batches = ctx
+            .execute_stream(physical_plan)
+            .await
+            .map_err(Error::Query)?;
+
+        let header = stream::once(async move { Ok(format.header(&schema)) });
+        let rows = batches.and_then(move |batch| async move { format.encode(&batch) });
+        let done = stream::once(async move {
+            // Keep the query permit until the query completed.
+            drop(permit);
+            query_completed_token.set_success();
+            debug!("Completed SQL query via HTTP");
+            Ok(Bytes::new())
+        });
+        let body = header
+            .chain(rows)
+            .chain(done)
+            // A failed query must not be marked successful.
+            .scan(false, |failed, res: Result<Bytes, ArrowError>| {
+                let item = (!*failed).then(|| {
+                    *failed = res.is_err();
+                    res
+                });
+                futures::future::ready(item)
+            });
+
+        Ok(Response::builder()
+            .header(CONTENT_TYPE, format.content_type())
+            .body(Body::wrap_stream(body))
+            .expect("valid response"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iox_query::test::TestChunk;
+    use service_common::test_util::TestDatabaseStore;
+
+    use super::*;
+
+    async fn test_delegate() -> HttpQueryDelegate<TestDatabaseStore> {
+        let test_storage = Arc::new(TestDatabaseStore::new());
+        test_storage.db_or_create("my_db").await.add_chunk(
+            "my_partition_key",
+            Arc::new(
+                TestChunk::new("h2o")
+                    .with_tag_column("state")
+                    .with_i64_field_column("temp")
+                    .with_time_column()
+                    .with_one_row_of_data(),
+            ),
+        );
+
+        HttpQueryDelegate::new(test_storage, None)
+    }
+
+    async fn query(
+        delegate: &HttpQueryDelegate<TestDatabaseStore>,
+        query_string: &str,
+    ) -> Result<(String, String), Error> {
+        let req = Request::builder()
+            .uri(format!(
+                "https://bananas.example/api/v3/query?{query_string}"
+            ))
+            .body(Body::empty())
+            .unwrap();
+        let response = delegate.route(req).await?;
+        let content_type = response.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        Ok((content_type, String::from_utf8(body.to_vec()).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_query_csv() {
+        let delegate = test_delegate().await;
+
+        let (content_type, body) = query(
+            &delegate,
+            "namespace=my_db&q=SELECT%20state%2C%20temp%2C%20time%20FROM%20h2o&format=csv",
+        )
+        .await
+        .unwrap();
+        assert_eq!(content_type, "text/csv; charset=utf-8");
+        assert_eq!(
+            body,
+            "#datatype,string,long,dateTime:RFC3339\n\
+             ,state,temp,time\n\
+             ,MA,1000,1970-01-01T00:00:00.000001000Z\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_json_lines() {
+        let delegate = test_delegate().await;
+
+        let (content_type, body) = query(
+            &delegate,
+            "namespace=my_db&q=SELECT%20state%2C%20temp%20FROM%20h2o&format=jsonl",
+        )
+        .await
+        .unwrap();
+        assert_eq!(content_type, "application/jsonl");
+        let rows = body
+            .lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(rows, [serde_json::json!({"state": "MA", "temp": 1000})]);
+    }
+
+    #[tokio::test]
+    async fn test_query_errors() {
+        let delegate = test_delegate().await;
+
+        let err = query(&delegate, "namespace=other_db&q=SELECT%201")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NamespaceNotFound(_)));
+
+        let err = query(&delegate, "namespace=my_db&q=SELECT%201&format=parquet")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::UnknownFormat(_)));
+
+        let err = query(&delegate, "namespace=my_db").await.unwrap_err();
+        assert!(matches!(err, Error::InvalidQueryString(_)));
+
+        let err = query(&delegate, "namespace=my_db&q=SELECT%20*%20FROM%20o2")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Planning(_)));
+    }
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("usage"), "usage");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
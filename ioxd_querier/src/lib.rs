@@ -1,12 +1,12 @@
 use async_trait::async_trait;
-use clap_blocks::querier::{IngesterAddresses, QuerierConfig};
+use clap_blocks::querier::{IngesterAddresses, QuerierConfig, ReadPolicyConfig};
 use hyper::{Body, Request, Response};
-use iox_catalog::interface::Catalog;
+use iox_catalog::{authz::Authorizer, interface::Catalog, usage::UsageAccumulator};
 use iox_query::exec::{Executor, ExecutorType};
 use iox_time::TimeProvider;
 use ioxd_common::{
     add_service,
-    http::error::{HttpApiError, HttpApiErrorCode, HttpApiErrorSource},
+    http::error::HttpApiErrorSource,
     rpc::RpcBuilderInput,
     serve_builder,
     server_type::{CommonServerState, RpcError, ServerType},
@@ -14,23 +14,30 @@ use ioxd_common::{
 };
 use metric::Registry;
 use object_store::DynObjectStore;
+use object_store_metrics::hedge::HedgedObjectStore;
 use querier::{
-    create_ingester_connections_by_shard, QuerierCatalogCache, QuerierDatabase, QuerierHandler,
-    QuerierHandlerImpl, QuerierServer,
+    create_ingester_connections_by_catalog, create_ingester_connections_by_shard,
+    NamespaceReadPolicy, QuerierCatalogCache, QuerierDatabase, QuerierHandler, QuerierHandlerImpl,
+    QuerierServer, QueryPoolMembership, ReadPolicies, WriteSloProbe,
 };
-use std::{
-    fmt::{Debug, Display},
-    sync::Arc,
+use service_grpc_authz::rpc::{
+    AuthorizedService, CATALOG_SERVICE_PERMISSIONS, NAMESPACE_SERVICE_PERMISSIONS,
+    OBJECT_STORE_SERVICE_PERMISSIONS, SCHEMA_SERVICE_PERMISSIONS, STORAGE_SERVICE_PERMISSIONS,
+    USAGE_SERVICE_PERMISSIONS, WRITE_INFO_SERVICE_PERMISSIONS,
 };
+use std::{fmt::Debug, sync::Arc, time::Duration};
 use thiserror::Error;
 use tokio::runtime::Handle;
 use trace::TraceCollector;
 
+mod http;
 mod rpc;
 
 pub struct QuerierServerType<C: QuerierHandler> {
     database: Arc<QuerierDatabase>,
     server: QuerierServer<C>,
+    http: http::HttpQueryDelegate<QuerierDatabase>,
+    authorizer: Option<Arc<Authorizer>>,
     trace_collector: Option<Arc<dyn TraceCollector>>,
 }
 
@@ -44,11 +51,15 @@ impl<C: QuerierHandler> QuerierServerType<C> {
     pub fn new(
         server: QuerierServer<C>,
         database: Arc<QuerierDatabase>,
+        authorizer: Option<Arc<Authorizer>>,
         common_state: &CommonServerState,
     ) -> Self {
+        let http = http::HttpQueryDelegate::new(Arc::clone(&database), authorizer.clone());
         Self {
             server,
             database,
+            http,
+            authorizer,
             trace_collector: common_state.trace_collector(),
         }
     }
@@ -66,36 +77,84 @@ impl<C: QuerierHandler + std::fmt::Debug + 'static> ServerType for QuerierServer
         self.trace_collector.as_ref().map(Arc::clone)
     }
 
-    /// Just return "not found".
+    /// Route the HTTP query API, see [`http`].
     async fn route_http_request(
         &self,
-        _req: Request<Body>,
+        req: Request<Body>,
     ) -> Result<Response<Body>, Box<dyn HttpApiErrorSource>> {
-        Err(Box::new(IoxHttpError::NotFound))
+        self.http
+            .route(req)
+            .await
+            .map_err(|e| Box::new(e) as Box<dyn HttpApiErrorSource>)
     }
 
     /// Configure the gRPC services.
+    ///
+    /// The Flight service authorizes its queries by namespace itself, the RPCs to the other
+    /// services require an API token allowing the permission of their method on all namespaces.
     async fn server_grpc(self: Arc<Self>, builder_input: RpcBuilderInput) -> Result<(), RpcError> {
         let builder = setup_builder!(builder_input, self);
+        let authorizer = &self.authorizer;
         add_service!(
             builder,
-            rpc::query::make_flight_server(Arc::clone(&self.database))
+            rpc::query::make_flight_server(Arc::clone(&self.database), authorizer.clone())
         );
         add_service!(
             builder,
-            rpc::query::make_storage_server(Arc::clone(&self.database))
+            AuthorizedService::new(
+                rpc::query::make_storage_server(Arc::clone(&self.database)),
+                authorizer.clone(),
+                STORAGE_SERVICE_PERMISSIONS,
+            )
         );
         add_service!(
             builder,
-            rpc::namespace::namespace_service(Arc::clone(&self.database))
+            AuthorizedService::new(
+                rpc::namespace::namespace_service(Arc::clone(&self.database)),
+                authorizer.clone(),
+                NAMESPACE_SERVICE_PERMISSIONS,
+            )
         );
         add_service!(
             builder,
-            rpc::write_info::write_info_service(Arc::clone(&self.database))
+            AuthorizedService::new(
+                rpc::write_info::write_info_service(Arc::clone(&self.database)),
+                authorizer.clone(),
+                WRITE_INFO_SERVICE_PERMISSIONS,
+            )
+        );
+        add_service!(
+            builder,
+            AuthorizedService::new(
+                self.server.handler().schema_service(),
+                authorizer.clone(),
+                SCHEMA_SERVICE_PERMISSIONS,
+            )
+        );
+        add_service!(
+            builder,
+            AuthorizedService::new(
+                self.server.handler().catalog_service(),
+                authorizer.clone(),
+                CATALOG_SERVICE_PERMISSIONS,
+            )
+        );
+        add_service!(
+            builder,
+            AuthorizedService::new(
+                self.server.handler().object_store_service(),
+                authorizer.clone(),
+                OBJECT_STORE_SERVICE_PERMISSIONS,
+            )
+        );
+        add_service!(
+            builder,
+            AuthorizedService::new(
+                self.server.handler().usage_service(),
+                authorizer.clone(),
+                USAGE_SERVICE_PERMISSIONS,
+            )
         );
-        add_service!(builder, self.server.handler().schema_service());
-        add_service!(builder, self.server.handler().catalog_service());
-        add_service!(builder, self.server.handler().object_store_service());
 
         serve_builder!(builder);
 
@@ -111,34 +170,6 @@ impl<C: QuerierHandler + std::fmt::Debug + 'static> ServerType for QuerierServer
     }
 }
 
-/// Simple error struct, we're not really providing an HTTP interface for the compactor.
-#[derive(Debug)]
-pub enum IoxHttpError {
-    NotFound,
-}
-
-impl IoxHttpError {
-    fn status_code(&self) -> HttpApiErrorCode {
-        match self {
-            IoxHttpError::NotFound => HttpApiErrorCode::NotFound,
-        }
-    }
-}
-
-impl Display for IoxHttpError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
-
-impl std::error::Error for IoxHttpError {}
-
-impl HttpApiErrorSource for IoxHttpError {
-    fn to_http_api_error(&self) -> HttpApiError {
-        HttpApiError::new(self.status_code(), self.to_string())
-    }
-}
-
 /// Arguments required to create a [`ServerType`] for the querier.
 #[derive(Debug)]
 pub struct QuerierServerTypeArgs<'a> {
@@ -150,25 +181,74 @@ pub struct QuerierServerTypeArgs<'a> {
     pub time_provider: Arc<dyn TimeProvider>,
     pub ingester_addresses: IngesterAddresses,
     pub querier_config: QuerierConfig,
+    /// How long the permissions of API tokens are cached, or `None` if queries are not
+    /// authorized.
+    pub authz_cache_ttl: Option<Duration>,
 }
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("querier error: {0}")]
     Querier(#[from] querier::QuerierDatabaseError),
+
+    #[error("invalid querier config: {0}")]
+    Config(#[from] clap_blocks::querier::Error),
 }
 
+/// Fraction of the available memory used for the metadata cache, unless configured explicitly.
+const RAM_POOL_METADATA_MEMORY_FRACTION: f64 = 0.05;
+
+/// Size of the metadata cache if neither configured nor the available memory is known.
+const DEFAULT_RAM_POOL_METADATA_BYTES: usize = 134_217_728; // 128MB
+
+/// Fraction of the available memory used for the data cache, unless configured explicitly.
+const RAM_POOL_DATA_MEMORY_FRACTION: f64 = 0.25;
+
+/// Size of the data cache if neither configured nor the available memory is known.
+const DEFAULT_RAM_POOL_DATA_BYTES: usize = 1_073_741_824; // 1GB
+
 /// Instantiate a querier server
 pub async fn create_querier_server_type(
     args: QuerierServerTypeArgs<'_>,
 ) -> Result<Arc<dyn ServerType>, Error> {
+    // Hedge the parquet file reads of the catalog cache if configured.
+    let parquet_object_store = match args.querier_config.object_store_hedge_config() {
+        Some(hedge_config) => Arc::new(HedgedObjectStore::new(
+            Arc::clone(&args.object_store),
+            hedge_config,
+            Arc::clone(&args.time_provider),
+            &args.metric_registry,
+        )) as Arc<DynObjectStore>,
+        None => Arc::clone(&args.object_store),
+    };
+
+    let resources = args.common_state.resources();
+    let ram_pool_metadata_bytes = args
+        .querier_config
+        .ram_pool_metadata_bytes()
+        .or_else(|| resources.memory_fraction(RAM_POOL_METADATA_MEMORY_FRACTION))
+        .unwrap_or(DEFAULT_RAM_POOL_METADATA_BYTES);
+    let ram_pool_data_bytes = args
+        .querier_config
+        .ram_pool_data_bytes()
+        .or_else(|| resources.memory_fraction(RAM_POOL_DATA_MEMORY_FRACTION))
+        .unwrap_or(DEFAULT_RAM_POOL_DATA_BYTES);
+
+    let authorizer = args.authz_cache_ttl.map(|ttl| {
+        Arc::new(Authorizer::new(
+            Arc::clone(&args.catalog),
+            Arc::clone(&args.time_provider),
+            ttl,
+        ))
+    });
+
     let catalog_cache = Arc::new(QuerierCatalogCache::new(
         Arc::clone(&args.catalog),
         args.time_provider,
         Arc::clone(&args.metric_registry),
-        Arc::clone(&args.object_store),
-        args.querier_config.ram_pool_metadata_bytes(),
-        args.querier_config.ram_pool_data_bytes(),
+        parquet_object_store,
+        ram_pool_metadata_bytes,
+        ram_pool_data_bytes,
         &Handle::current(),
     ));
 
@@ -193,29 +273,91 @@ pub async fn create_querier_server_type(
             Arc::clone(&catalog_cache),
             args.querier_config.ingester_circuit_breaker_threshold,
         )),
+        IngesterAddresses::Catalog => Some(create_ingester_connections_by_catalog(
+            Arc::clone(&catalog_cache),
+            args.querier_config.ingester_circuit_breaker_threshold,
+        )),
     };
 
-    let database = Arc::new(
-        QuerierDatabase::new(
-            catalog_cache,
-            Arc::clone(&args.metric_registry),
-            args.exec,
-            ingester_connection,
-            args.querier_config.max_concurrent_queries(),
-            args.querier_config.max_table_query_bytes(),
-        )
-        .await?,
-    );
-    let querier_handler = Arc::new(QuerierHandlerImpl::new(
+    let read_policies = read_policies(args.querier_config.read_policies()?);
+    let usage_flush_interval = args.querier_config.usage_flush_interval();
+    let usage = usage_flush_interval.map(|_| Arc::new(UsageAccumulator::default()));
+
+    let mut database = QuerierDatabase::new(
+        catalog_cache,
+        Arc::clone(&args.metric_registry),
+        args.exec,
+        ingester_connection,
+        args.querier_config.max_concurrent_queries(),
+        args.querier_config.max_table_query_bytes(),
+        args.querier_config
+            .external_table_location_allowlist()
+            .to_vec(),
+        args.querier_config
+            .router_http_address()
+            .map(ToOwned::to_owned),
+    )
+    .await?
+    .with_router_max_request_size(args.querier_config.router_max_request_size())
+    .with_read_policies(read_policies)
+    .with_scan_limits(
+        args.querier_config.max_concurrent_object_store_scans(),
+        args.querier_config
+            .max_concurrent_object_store_scans_per_query(),
+    )
+    .with_plan_cache(args.querier_config.plan_cache_max_entries())
+    .with_namespace_metric_labels(args.querier_config.namespace_metric_label_limit())
+    .with_max_gap_fill_buckets(args.querier_config.max_gap_fill_buckets())
+    .with_skip_ingesters_for_cold_tables(args.querier_config.skip_ingesters_for_cold_tables)
+    .with_shard_pinning(args.querier_config.shard_pinning);
+    if let Some(usage) = &usage {
+        database = database.with_usage(Arc::clone(usage));
+    }
+    let database = Arc::new(database);
+    let mut querier_handler = QuerierHandlerImpl::new(
         args.catalog,
         Arc::clone(&database),
         Arc::clone(&args.object_store),
-    ));
+    );
+    if let Some(address) = args.querier_config.query_pool_advertise_address() {
+        querier_handler = querier_handler.with_query_pool_membership(QueryPoolMembership::new(
+            args.querier_config.query_pool_name(),
+            address,
+            args.querier_config.query_pool_heartbeat_interval(),
+        ));
+    }
+    if let Some((namespace, router_http_address)) = args.querier_config.write_slo_target()? {
+        querier_handler = querier_handler.with_write_slo_probe(WriteSloProbe::new(
+            namespace,
+            router_http_address,
+            args.querier_config.write_slo_interval(),
+            args.querier_config.write_slo_timeout(),
+            &args.metric_registry,
+        ));
+    }
+    if let Some((usage, interval)) = usage.zip(usage_flush_interval) {
+        querier_handler = querier_handler.with_usage_flush(usage, interval);
+    }
+    let querier_handler = Arc::new(querier_handler);
 
     let querier = QuerierServer::new(args.metric_registry, querier_handler);
     Ok(Arc::new(QuerierServerType::new(
         querier,
         database,
+        authorizer,
         args.common_state,
     )))
 }
+
+fn read_policies(config: ReadPolicyConfig) -> ReadPolicies {
+    config.into_iter().fold(
+        ReadPolicies::default(),
+        |policies, (namespace, identities)| {
+            let policy = identities.into_iter().fold(
+                NamespaceReadPolicy::default(),
+                |policy, (identity, tag_values)| policy.with_identity(&identity, tag_values),
+            );
+            policies.with_namespace(&namespace, policy)
+        },
+    )
+}
@@ -6,6 +6,7 @@
 //! support `show namespaces` in the REPL.
 
 use data_types::Namespace;
+use futures::{stream::BoxStream, StreamExt};
 use generated_types::influxdata::iox::namespace::v1 as proto;
 use querier::QuerierDatabase;
 use std::sync::Arc;
@@ -36,6 +37,10 @@ fn namespace_to_proto(namespace: Namespace) -> proto::Namespace {
         id: namespace.id.get(),
         name: namespace.name,
         retention_period_ns: namespace.retention_period_ns,
+        default_query_range_ns: namespace.default_query_range_ns,
+        max_query_range_ns: namespace.max_query_range_ns,
+        row_ttl_ns: namespace.row_ttl_ns,
+        row_ttl_column: namespace.row_ttl_column,
     }
 }
 
@@ -56,6 +61,24 @@ impl proto::namespace_service_server::NamespaceService for NamespaceServiceImpl
         }))
     }
 
+    type ListNamespacesStream =
+        BoxStream<'static, Result<proto::ListNamespacesResponse, tonic::Status>>;
+
+    async fn list_namespaces(
+        &self,
+        _request: tonic::Request<proto::ListNamespacesRequest>,
+    ) -> Result<tonic::Response<Self::ListNamespacesStream>, tonic::Status> {
+        // The querier holds the namespaces in memory already, so they are sent as a single page.
+        let namespaces = self.server.namespaces().await;
+        let page = proto::ListNamespacesResponse {
+            namespaces: namespaces.into_iter().map(namespace_to_proto).collect(),
+        };
+
+        Ok(tonic::Response::new(
+            futures::stream::once(async move { Ok(page) }).boxed(),
+        ))
+    }
+
     async fn create_namespace(
         &self,
         _request: tonic::Request<proto::CreateNamespaceRequest>,
@@ -73,6 +96,33 @@ impl proto::namespace_service_server::NamespaceService for NamespaceServiceImpl
             "use router instances to manage namespaces",
         ))
     }
+
+    async fn update_namespace_query_ranges(
+        &self,
+        _request: tonic::Request<proto::UpdateNamespaceQueryRangesRequest>,
+    ) -> Result<tonic::Response<proto::UpdateNamespaceQueryRangesResponse>, tonic::Status> {
+        Err(tonic::Status::unimplemented(
+            "use router instances to manage namespaces",
+        ))
+    }
+
+    async fn update_namespace_row_ttl(
+        &self,
+        _request: tonic::Request<proto::UpdateNamespaceRowTtlRequest>,
+    ) -> Result<tonic::Response<proto::UpdateNamespaceRowTtlResponse>, tonic::Status> {
+        Err(tonic::Status::unimplemented(
+            "use router instances to manage namespaces",
+        ))
+    }
+
+    async fn update_namespace_topic(
+        &self,
+        _request: tonic::Request<proto::UpdateNamespaceTopicRequest>,
+    ) -> Result<tonic::Response<proto::UpdateNamespaceTopicResponse>, tonic::Status> {
+        Err(tonic::Status::unimplemented(
+            "use router instances to manage namespaces",
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -108,6 +158,8 @@ mod tests {
                 Some(create_ingester_connection_for_testing()),
                 QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
                 usize::MAX,
+                vec![],
+                None,
             )
             .await
             .unwrap(),
@@ -144,6 +196,8 @@ mod tests {
                 Some(create_ingester_connection_for_testing()),
                 QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
                 usize::MAX,
+                vec![],
+                None,
             )
             .await
             .unwrap(),
@@ -162,11 +216,19 @@ mod tests {
                         id: 1,
                         name: "namespace2".to_string(),
                         retention_period_ns: TEST_RETENTION_PERIOD_NS,
+                        default_query_range_ns: None,
+                        max_query_range_ns: None,
+                        row_ttl_ns: None,
+                        row_ttl_column: None,
                     },
                     proto::Namespace {
                         id: 2,
                         name: "namespace1".to_string(),
                         retention_period_ns: TEST_RETENTION_PERIOD_NS,
+                        default_query_range_ns: None,
+                        max_query_range_ns: None,
+                        row_ttl_ns: None,
+                        row_ttl_column: None,
                     },
                 ]
             }
@@ -4,10 +4,14 @@ use arrow_flight::flight_service_server::{
     FlightService as Flight, FlightServiceServer as FlightServer,
 };
 use generated_types::storage_server::{Storage, StorageServer};
+use iox_catalog::authz::Authorizer;
 use querier::QuerierDatabase;
 
-pub fn make_flight_server(server: Arc<QuerierDatabase>) -> FlightServer<impl Flight> {
-    service_grpc_flight::make_server(server)
+pub fn make_flight_server(
+    server: Arc<QuerierDatabase>,
+    authorizer: Option<Arc<Authorizer>>,
+) -> FlightServer<impl Flight> {
+    service_grpc_flight::make_server(server, authorizer)
 }
 
 pub fn make_storage_server(server: Arc<QuerierDatabase>) -> StorageServer<impl Storage> {
@@ -0,0 +1,113 @@
+//! Admission control bounding the queries forwarded to the queriers.
+
+use metric::U64Counter;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use trace::span::Span;
+use tracker::{
+    AsyncSemaphoreMetrics, InstrumentedAsyncOwnedSemaphorePermit, InstrumentedAsyncSemaphore,
+};
+
+/// Admits a bounded number of concurrent queries, queueing a bounded number of
+/// further queries and rejecting the rest.
+#[derive(Debug)]
+pub struct AdmissionControl {
+    semaphore: Arc<InstrumentedAsyncSemaphore>,
+    queued: AtomicUsize,
+    max_queued: usize,
+    rejected: U64Counter,
+}
+
+impl AdmissionControl {
+    /// Admit up to `max_concurrent` queries at a time, with up to `max_queued`
+    /// queries waiting for admission.
+    pub fn new(
+        max_concurrent: usize,
+        max_queued: usize,
+        metric_registry: &metric::Registry,
+    ) -> Self {
+        let semaphore_metrics = Arc::new(AsyncSemaphoreMetrics::new(
+            metric_registry,
+            &[("semaphore", "query_gateway_admission")],
+        ));
+        let semaphore = Arc::new(semaphore_metrics.new_semaphore(max_concurrent));
+
+        let rejected = metric_registry
+            .register_metric::<U64Counter>(
+                "query_gateway_rejected_queries",
+                "Number of queries rejected because too many queries were waiting for admission",
+            )
+            .recorder(&[]);
+
+        Self {
+            semaphore,
+            queued: AtomicUsize::new(0),
+            max_queued,
+            rejected,
+        }
+    }
+
+    /// Wait for admission, or return [`None`] if too many queries are waiting
+    /// already.
+    ///
+    /// The query is admitted until the returned permit is dropped.
+    pub async fn admit(&self, span: Option<Span>) -> Option<InstrumentedAsyncOwnedSemaphorePermit> {
+        let queued = self.queued.fetch_add(1, Ordering::AcqRel);
+        let _guard = QueuedGuard(&self.queued);
+
+        if queued >= self.max_queued && self.semaphore.available_permits() == 0 {
+            self.rejected.inc(1);
+            return None;
+        }
+
+        Some(
+            self.semaphore
+                .acquire_owned(span)
+                .await
+                .expect("semaphore is never closed"),
+        )
+    }
+}
+
+/// Removes a query from the queue when dropped.
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl<'a> Drop for QueuedGuard<'a> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metric::{Attributes, Metric};
+
+    #[tokio::test]
+    async fn test_admit_and_reject() {
+        let metrics = metric::Registry::default();
+        let admission = AdmissionControl::new(1, 1, &metrics);
+
+        let permit = admission.admit(None).await.expect("admitted");
+
+        // The second query waits for the first one to complete.
+        let mut waiting = Box::pin(admission.admit(None));
+        assert!(futures::poll!(&mut waiting).is_pending());
+
+        // The third query exceeds the queue.
+        assert!(admission.admit(None).await.is_none());
+
+        drop(permit);
+        assert!(waiting.await.is_some());
+
+        let rejected = metrics
+            .get_instrument::<Metric<U64Counter>>("query_gateway_rejected_queries")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch();
+        assert_eq!(rejected, 1);
+    }
+}
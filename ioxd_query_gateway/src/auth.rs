@@ -0,0 +1,184 @@
+//! Authentication of gateway requests.
+
+use clap_blocks::query_gateway::TokenConfig;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+use thiserror::Error;
+use tonic::metadata::MetadataMap;
+
+/// gRPC metadata key carrying the bearer token of a request.
+const AUTHORIZATION_HEADER: &str = "authorization";
+
+/// Reasons for rejecting a request.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum AuthError {
+    #[error("missing bearer token")]
+    MissingToken,
+
+    #[error("invalid bearer token")]
+    InvalidToken,
+
+    #[error("not allowed to query namespace {0}")]
+    NamespaceNotAllowed(String),
+}
+
+impl From<AuthError> for tonic::Status {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::MissingToken | AuthError::InvalidToken => {
+                Self::unauthenticated(e.to_string())
+            }
+            AuthError::NamespaceNotAllowed(_) => Self::permission_denied(e.to_string()),
+        }
+    }
+}
+
+/// The caller of an authenticated request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    identity: Option<Arc<str>>,
+
+    /// The namespaces the caller may query, or [`None`] for all namespaces.
+    namespaces: Option<Arc<HashSet<String>>>,
+}
+
+impl Principal {
+    /// The identity queries of the caller run on behalf of, if any.
+    pub fn identity(&self) -> Option<&Arc<str>> {
+        self.identity.as_ref()
+    }
+
+    /// Return an error if the caller may not query `namespace`.
+    pub fn check_namespace(&self, namespace: &str) -> Result<(), AuthError> {
+        match &self.namespaces {
+            Some(namespaces) if !namespaces.contains(namespace) => {
+                Err(AuthError::NamespaceNotAllowed(namespace.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Authenticates requests by their bearer token.
+#[derive(Debug, Default)]
+pub struct Authenticator {
+    /// The principals by token, or [`None`] if authentication is disabled.
+    tokens: Option<HashMap<String, Principal>>,
+}
+
+impl Authenticator {
+    /// Accept the bearer tokens in `tokens`.
+    ///
+    /// A [`Default`] authenticator accepts all requests without an identity.
+    pub fn new(tokens: HashMap<String, TokenConfig>) -> Self {
+        let tokens = tokens
+            .into_iter()
+            .map(|(token, config)| {
+                let namespaces = (!config.namespaces.is_empty())
+                    .then(|| Arc::new(config.namespaces.into_iter().collect()));
+                let principal = Principal {
+                    identity: Some(Arc::from(config.identity)),
+                    namespaces,
+                };
+                (token, principal)
+            })
+            .collect();
+
+        Self {
+            tokens: Some(tokens),
+        }
+    }
+
+    /// Return the caller of the request with the given `metadata`.
+    pub fn authenticate(&self, metadata: &MetadataMap) -> Result<Principal, AuthError> {
+        let tokens = match &self.tokens {
+            Some(tokens) => tokens,
+            None => {
+                return Ok(Principal {
+                    identity: None,
+                    namespaces: None,
+                })
+            }
+        };
+
+        let token = metadata
+            .get(AUTHORIZATION_HEADER)
+            .ok_or(AuthError::MissingToken)?
+            .to_str()
+            .map_err(|_| AuthError::InvalidToken)?;
+        let token = token
+            .strip_prefix("Bearer ")
+            .ok_or(AuthError::InvalidToken)?;
+
+        tokens.get(token).cloned().ok_or(AuthError::InvalidToken)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(authorization: Option<&str>) -> MetadataMap {
+        let mut metadata = MetadataMap::new();
+        if let Some(v) = authorization {
+            metadata.insert(AUTHORIZATION_HEADER, v.parse().unwrap());
+        }
+        metadata
+    }
+
+    #[test]
+    fn test_disabled() {
+        let principal = Authenticator::default()
+            .authenticate(&metadata(None))
+            .unwrap();
+        assert_eq!(principal.identity(), None);
+        assert_eq!(principal.check_namespace("ns"), Ok(()));
+    }
+
+    #[test]
+    fn test_tokens() {
+        let auth = Authenticator::new(HashMap::from([
+            (
+                "t1".to_string(),
+                TokenConfig {
+                    identity: "alice".to_string(),
+                    namespaces: vec!["ns".to_string()],
+                },
+            ),
+            (
+                "t2".to_string(),
+                TokenConfig {
+                    identity: "admin".to_string(),
+                    namespaces: vec![],
+                },
+            ),
+        ]));
+
+        let alice = auth.authenticate(&metadata(Some("Bearer t1"))).unwrap();
+        assert_eq!(alice.identity().map(|i| i.as_ref()), Some("alice"));
+        assert_eq!(alice.check_namespace("ns"), Ok(()));
+        assert_eq!(
+            alice.check_namespace("other"),
+            Err(AuthError::NamespaceNotAllowed("other".to_string()))
+        );
+
+        let admin = auth.authenticate(&metadata(Some("Bearer t2"))).unwrap();
+        assert_eq!(admin.check_namespace("other"), Ok(()));
+
+        assert_eq!(
+            auth.authenticate(&metadata(None)),
+            Err(AuthError::MissingToken)
+        );
+        assert_eq!(
+            auth.authenticate(&metadata(Some("t1"))),
+            Err(AuthError::InvalidToken)
+        );
+        assert_eq!(
+            auth.authenticate(&metadata(Some("Bearer t3"))),
+            Err(AuthError::InvalidToken)
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Backends executing the queries forwarded by the gateway.
+
+use arrow_flight::{flight_service_client::FlightServiceClient, FlightData, Ticket};
+use async_trait::async_trait;
+use futures::{stream::BoxStream, StreamExt};
+use querier::{QueryPoolDispatchError, QueryPoolDispatcher};
+use std::fmt::Debug;
+
+/// A stream of flight messages.
+pub type FlightDataStream = BoxStream<'static, Result<FlightData, tonic::Status>>;
+
+/// Executes flight queries forwarded by the gateway.
+#[async_trait]
+pub trait QueryBackend: Debug + Send + Sync + 'static {
+    /// Perform the flight `request` against `namespace`, returning the flight
+    /// messages of the result.
+    async fn do_get(
+        &self,
+        namespace: &str,
+        request: tonic::Request<Ticket>,
+    ) -> Result<FlightDataStream, tonic::Status>;
+}
+
+/// Forwards queries to the live queriers of the query pool of the queried
+/// namespace.
+#[derive(Debug)]
+pub struct QueryPoolBackend {
+    dispatcher: QueryPoolDispatcher,
+}
+
+impl QueryPoolBackend {
+    /// Forward queries to the queriers chosen by `dispatcher`.
+    pub fn new(dispatcher: QueryPoolDispatcher) -> Self {
+        Self { dispatcher }
+    }
+}
+
+#[async_trait]
+impl QueryBackend for QueryPoolBackend {
+    async fn do_get(
+        &self,
+        namespace: &str,
+        request: tonic::Request<Ticket>,
+    ) -> Result<FlightDataStream, tonic::Status> {
+        let connection = self
+            .dispatcher
+            .connect(namespace)
+            .await
+            .map_err(dispatch_error_to_status)?;
+
+        let mut client = FlightServiceClient::new(connection.into_grpc_connection());
+        let stream = client.do_get(request).await?.into_inner();
+
+        Ok(stream.boxed())
+    }
+}
+
+fn dispatch_error_to_status(e: QueryPoolDispatchError) -> tonic::Status {
+    let msg = e.to_string();
+    match e {
+        QueryPoolDispatchError::NamespaceNotFound(_) => tonic::Status::not_found(msg),
+        QueryPoolDispatchError::NoQueriers { .. } | QueryPoolDispatchError::Connect { .. } => {
+            tonic::Status::unavailable(msg)
+        }
+        QueryPoolDispatchError::Catalog(_) | QueryPoolDispatchError::Query(_) => {
+            tonic::Status::internal(msg)
+        }
+    }
+}
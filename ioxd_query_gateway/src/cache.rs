@@ -0,0 +1,328 @@
+//! Cache for the results of gateway queries.
+
+use arrow_flight::FlightData;
+use bytes::Bytes;
+use cache_system::{
+    backend::{
+        policy::{
+            lru::{LruPolicy, ResourcePool},
+            ttl::{TtlPolicy, TtlProvider},
+            PolicyBackend,
+        },
+        CacheBackend,
+    },
+    resource_consumption::{FunctionEstimator, Resource},
+};
+use futures::{stream::BoxStream, Stream, StreamExt};
+use iox_time::TimeProvider;
+use metric::U64Counter;
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    mem::size_of_val,
+    ops::{Add, Sub},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+const CACHE_ID: &str = "query_gateway_results";
+
+/// Identifies the result of a query.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CacheKey {
+    /// The identity the query runs on behalf of, as read policies make results
+    /// depend on it.
+    pub identity: Option<Arc<str>>,
+
+    /// The encoded flight ticket of the query.
+    pub ticket: Bytes,
+}
+
+/// The flight messages of a query result.
+type CachedResult = Arc<Vec<FlightData>>;
+
+/// Caches the results of queries for a fixed time.
+///
+/// The least recently used results are evicted once the cached results exceed
+/// the size limit.
+#[derive(Debug)]
+pub struct ResultCache {
+    backend: Mutex<PolicyBackend<CacheKey, CachedResult>>,
+    max_entry_bytes: usize,
+    hits: U64Counter,
+    misses: U64Counter,
+}
+
+impl ResultCache {
+    /// Cache results of up to `max_entry_bytes` for `ttl`, up to a total of
+    /// `max_bytes`.
+    pub fn new(
+        ttl: Duration,
+        max_bytes: usize,
+        max_entry_bytes: usize,
+        time_provider: Arc<dyn TimeProvider>,
+        metric_registry: Arc<metric::Registry>,
+    ) -> Self {
+        let mut backend = PolicyBackend::new(Box::new(HashMap::new()), time_provider);
+        backend.add_policy(TtlPolicy::new(
+            Arc::new(ConstantTtlProvider(ttl)),
+            CACHE_ID,
+            &metric_registry,
+        ));
+        backend.add_policy(LruPolicy::new(
+            Arc::new(ResourcePool::new(
+                CACHE_ID,
+                RamSize(max_bytes),
+                Arc::clone(&metric_registry),
+            )),
+            CACHE_ID,
+            Arc::new(FunctionEstimator::new(|k: &CacheKey, v: &CachedResult| {
+                RamSize(
+                    size_of_val(k)
+                        + k.identity.as_ref().map(|i| i.len()).unwrap_or_default()
+                        + k.ticket.len()
+                        + v.iter().map(flight_data_size).sum::<usize>(),
+                )
+            })),
+        ));
+
+        let requests = metric_registry.register_metric::<U64Counter>(
+            "query_gateway_result_cache_requests",
+            "Number of queries looked up in the result cache",
+        );
+        let hits = requests.recorder(&[("result", "hit")]);
+        let misses = requests.recorder(&[("result", "miss")]);
+
+        Self {
+            backend: Mutex::new(backend),
+            max_entry_bytes,
+            hits,
+            misses,
+        }
+    }
+
+    /// Return the cached result of `key`, if any.
+    pub fn get(
+        &self,
+        key: &CacheKey,
+    ) -> Option<BoxStream<'static, Result<FlightData, tonic::Status>>> {
+        match self.backend.lock().get(key) {
+            Some(result) => {
+                self.hits.inc(1);
+                let messages = (0..result.len()).map(move |i| Ok(result[i].clone()));
+                Some(futures::stream::iter(messages).boxed())
+            }
+            None => {
+                self.misses.inc(1);
+                None
+            }
+        }
+    }
+
+    /// Forward `stream` and cache its messages under `key` once it completed
+    /// successfully.
+    pub fn cache_stream(
+        self: Arc<Self>,
+        key: CacheKey,
+        stream: BoxStream<'static, Result<FlightData, tonic::Status>>,
+    ) -> CachingStream {
+        CachingStream {
+            inner: stream,
+            key: Some(key),
+            messages: Vec::new(),
+            bytes: 0,
+            cache: self,
+        }
+    }
+
+    fn put(&self, key: CacheKey, messages: Vec<FlightData>) {
+        self.backend.lock().set(key, Arc::new(messages));
+    }
+}
+
+/// A stream of flight messages that are cached once the stream completed.
+///
+/// Nothing is cached if the stream fails, if it is dropped before completion,
+/// or if its messages exceed the maximum entry size of the cache.
+pub struct CachingStream {
+    inner: BoxStream<'static, Result<FlightData, tonic::Status>>,
+    /// The cache key, or [`None`] if the result is not cached.
+    key: Option<CacheKey>,
+    messages: Vec<FlightData>,
+    bytes: usize,
+    cache: Arc<ResultCache>,
+}
+
+impl Stream for CachingStream {
+    type Item = Result<FlightData, tonic::Status>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let item = futures::ready!(this.inner.poll_next_unpin(cx));
+
+        match &item {
+            Some(Ok(data)) if this.key.is_some() => {
+                this.bytes += flight_data_size(data);
+                if this.bytes <= this.cache.max_entry_bytes {
+                    this.messages.push(data.clone());
+                } else {
+                    this.key = None;
+                    this.messages = Vec::new();
+                }
+            }
+            Some(Ok(_)) => {}
+            Some(Err(_)) => {
+                this.key = None;
+                this.messages = Vec::new();
+            }
+            None => {
+                if let Some(key) = this.key.take() {
+                    this.cache.put(key, std::mem::take(&mut this.messages));
+                }
+            }
+        }
+
+        Poll::Ready(item)
+    }
+}
+
+fn flight_data_size(data: &FlightData) -> usize {
+    size_of_val(data) + data.data_header.len() + data.data_body.len() + data.app_metadata.len()
+}
+
+/// Expires all results after the same time.
+#[derive(Debug)]
+struct ConstantTtlProvider(Duration);
+
+impl TtlProvider for ConstantTtlProvider {
+    type K = CacheKey;
+    type V = CachedResult;
+
+    fn expires_in(&self, _k: &Self::K, _v: &Self::V) -> Option<Duration> {
+        Some(self.0)
+    }
+}
+
+/// Bytes of RAM used by cached results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd)]
+struct RamSize(usize);
+
+impl Resource for RamSize {
+    fn zero() -> Self {
+        Self(0)
+    }
+
+    fn unit() -> &'static str {
+        "bytes"
+    }
+}
+
+impl From<RamSize> for u64 {
+    fn from(s: RamSize) -> Self {
+        s.0 as Self
+    }
+}
+
+impl Add for RamSize {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self(self.0.checked_add(rhs.0).expect("overflow"))
+    }
+}
+
+impl Sub for RamSize {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self(self.0.checked_sub(rhs.0).expect("underflow"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+    use iox_time::{MockProvider, Time};
+
+    fn message(body: &[u8]) -> FlightData {
+        FlightData {
+            data_body: body.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    fn key(ticket: &'static [u8]) -> CacheKey {
+        CacheKey {
+            identity: None,
+            ticket: Bytes::from_static(ticket),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cache_completed_streams() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let cache = Arc::new(ResultCache::new(
+            Duration::from_secs(10),
+            usize::MAX,
+            1024,
+            Arc::clone(&time_provider) as _,
+            Arc::new(metric::Registry::default()),
+        ));
+
+        assert!(cache.get(&key(b"q1")).is_none());
+
+        let messages = vec![message(b"schema"), message(b"batch")];
+        let stream = futures::stream::iter(messages.clone().into_iter().map(Ok)).boxed();
+        let forwarded: Vec<_> = Arc::clone(&cache)
+            .cache_stream(key(b"q1"), stream)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(forwarded, messages);
+
+        let cached: Vec<_> = cache
+            .get(&key(b"q1"))
+            .expect("cached")
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(cached, messages);
+
+        // Results expire after the TTL.
+        time_provider.inc(Duration::from_secs(10));
+        assert!(cache.get(&key(b"q1")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_skip_failed_and_large_streams() {
+        let cache = Arc::new(ResultCache::new(
+            Duration::from_secs(10),
+            usize::MAX,
+            1024,
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            Arc::new(metric::Registry::default()),
+        ));
+
+        let stream =
+            futures::stream::iter([Ok(message(b"schema")), Err(tonic::Status::internal("boom"))])
+                .boxed();
+        let res: Result<Vec<_>, _> = Arc::clone(&cache)
+            .cache_stream(key(b"failed"), stream)
+            .try_collect()
+            .await;
+        assert!(res.is_err());
+        assert!(cache.get(&key(b"failed")).is_none());
+
+        let stream = futures::stream::iter([Ok(message(&[0; 2048]))]).boxed();
+        let res: Vec<_> = Arc::clone(&cache)
+            .cache_stream(key(b"large"), stream)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(res.len(), 1);
+        assert!(cache.get(&key(b"large")).is_none());
+    }
+}
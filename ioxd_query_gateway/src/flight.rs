@@ -0,0 +1,456 @@
+//! The Arrow Flight service of the gateway.
+
+use crate::{
+    admission::AdmissionControl,
+    auth::Authenticator,
+    backend::{FlightDataStream, QueryBackend},
+    cache::{CacheKey, ResultCache},
+};
+use arrow_flight::{
+    flight_service_server::{FlightService as Flight, FlightServiceServer as FlightServer},
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PutResult, SchemaResult, Ticket,
+};
+use bytes::Bytes;
+use futures::{stream::BoxStream, StreamExt};
+use generated_types::influxdata::iox::querier::v1 as proto;
+use observability_deps::tracing::{debug, info};
+use prost::Message;
+use serde::Deserialize;
+use service_grpc_flight::IDENTITY_HEADER;
+use std::sync::Arc;
+use tonic::{metadata::MetadataValue, Request, Response, Streaming};
+use trace::{ctx::SpanContext, span::SpanExt};
+use trace_exporters::DEFAULT_JAEGER_TRACE_CONTEXT_HEADER_NAME;
+use trace_http::ctx::format_jaeger_trace_context;
+
+/// Body of a JSON ticket, as sent by the Go clients.
+#[derive(Debug, Deserialize)]
+struct JsonReadInfo {
+    namespace_name: String,
+    sql_query: String,
+    #[serde(default)]
+    max_unpersisted_staleness_ns: Option<u64>,
+    #[serde(default)]
+    additional_namespaces: Vec<String>,
+    #[serde(default)]
+    params: Vec<proto::QueryParam>,
+    #[serde(default)]
+    include_statistics: bool,
+    #[serde(default)]
+    debug_parquet_file_ids: Vec<i64>,
+}
+
+/// Decode a protobuf or JSON encoded [`proto::ReadInfo`] ticket.
+fn decode_ticket(ticket: &[u8]) -> Result<proto::ReadInfo, tonic::Status> {
+    if let Ok(read_info) = proto::ReadInfo::decode(ticket) {
+        return Ok(read_info);
+    }
+
+    let read_info: JsonReadInfo = serde_json::from_slice(ticket)
+        .map_err(|e| tonic::Status::invalid_argument(format!("Invalid ticket: {e}")))?;
+
+    Ok(proto::ReadInfo {
+        namespace_name: read_info.namespace_name,
+        sql_query: read_info.sql_query,
+        max_unpersisted_staleness_ns: read_info.max_unpersisted_staleness_ns,
+        additional_namespaces: read_info.additional_namespaces,
+        params: read_info.params,
+        include_statistics: read_info.include_statistics,
+        debug_parquet_file_ids: read_info.debug_parquet_file_ids,
+    })
+}
+
+/// Authenticates flight queries, admits a bounded number of them and answers
+/// them from the result cache or from a [`QueryBackend`].
+#[derive(Debug)]
+pub struct QueryGateway {
+    authenticator: Authenticator,
+    admission: AdmissionControl,
+    cache: Option<Arc<ResultCache>>,
+    backend: Arc<dyn QueryBackend>,
+}
+
+impl QueryGateway {
+    /// Forward queries authenticated by `authenticator` and admitted by
+    /// `admission` to `backend`, caching results in `cache` if set.
+    pub fn new(
+        authenticator: Authenticator,
+        admission: AdmissionControl,
+        cache: Option<Arc<ResultCache>>,
+        backend: Arc<dyn QueryBackend>,
+    ) -> Self {
+        Self {
+            authenticator,
+            admission,
+            cache,
+            backend,
+        }
+    }
+}
+
+/// Returns the Arrow Flight service of `gateway`.
+pub fn make_server(gateway: Arc<QueryGateway>) -> FlightServer<impl Flight> {
+    FlightServer::from_arc(gateway)
+}
+
+type TonicStream<T> = BoxStream<'static, Result<T, tonic::Status>>;
+
+#[tonic::async_trait]
+impl Flight for QueryGateway {
+    type HandshakeStream = TonicStream<HandshakeResponse>;
+    type ListFlightsStream = TonicStream<FlightInfo>;
+    type DoGetStream = FlightDataStream;
+    type DoPutStream = TonicStream<PutResult>;
+    type DoActionStream = TonicStream<arrow_flight::Result>;
+    type ListActionsStream = TonicStream<ActionType>;
+    type DoExchangeStream = TonicStream<FlightData>;
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, tonic::Status> {
+        Err(tonic::Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, tonic::Status> {
+        let span_ctx: Option<SpanContext> = request.extensions().get().cloned();
+        let principal = self.authenticator.authenticate(request.metadata())?;
+
+        let read_info = decode_ticket(&request.into_inner().ticket)?;
+        principal.check_namespace(&read_info.namespace_name)?;
+        for namespace in &read_info.additional_namespaces {
+            principal.check_namespace(namespace)?;
+        }
+
+        // Forward JSON tickets as protobuf, so that equal queries share a
+        // cache entry regardless of their encoding.
+        let ticket = Bytes::from(read_info.encode_to_vec());
+        let key = CacheKey {
+            identity: principal.identity().cloned(),
+            ticket: ticket.clone(),
+        };
+
+        // The statistics describe a single execution of the query, and debug scans look at the
+        // current files, so neither is answered from the cache.
+        let cache = self.cache.as_ref().filter(|_| {
+            !read_info.include_statistics && read_info.debug_parquet_file_ids.is_empty()
+        });
+
+        if let Some(stream) = cache.and_then(|cache| cache.get(&key)) {
+            debug!(
+                namespace_name=%read_info.namespace_name,
+                sql_query=%read_info.sql_query,
+                identity=?principal.identity(),
+                "answering query from result cache"
+            );
+            return Ok(Response::new(stream));
+        }
+
+        let permit = self
+            .admission
+            .admit(span_ctx.child_span("query gateway admission"))
+            .await
+            .ok_or_else(|| tonic::Status::resource_exhausted("too many queued queries"))?;
+
+        info!(
+            namespace_name=%read_info.namespace_name,
+            sql_query=%read_info.sql_query,
+            identity=?principal.identity(),
+            "forwarding query to querier"
+        );
+
+        let mut request = Request::new(Ticket {
+            ticket: ticket.to_vec(),
+        });
+        // The identity header of the client request is never forwarded, only
+        // the authenticated identity.
+        if let Some(identity) = principal.identity() {
+            let value = MetadataValue::try_from(identity.as_ref())
+                .map_err(|_| tonic::Status::internal("identity is not a valid header value"))?;
+            request.metadata_mut().insert(IDENTITY_HEADER, value);
+        }
+        if let Some(span_ctx) = &span_ctx {
+            let value = MetadataValue::try_from(format_jaeger_trace_context(span_ctx))
+                .expect("trace context is a valid header value");
+            request
+                .metadata_mut()
+                .insert(DEFAULT_JAEGER_TRACE_CONTEXT_HEADER_NAME, value);
+        }
+
+        let stream = self
+            .backend
+            .do_get(&read_info.namespace_name, request)
+            .await?;
+        let stream = match cache {
+            Some(cache) => Arc::clone(cache).cache_stream(key, stream).boxed(),
+            None => stream,
+        };
+
+        // Keep the query admitted until its results are streamed.
+        let stream = stream.map(move |data| {
+            let _permit = &permit;
+            data
+        });
+
+        Ok(Response::new(stream.boxed()))
+    }
+
+    async fn handshake(
+        &self,
+        request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, tonic::Status> {
+        let request = request
+            .into_inner()
+            .message()
+            .await?
+            .ok_or_else(|| tonic::Status::invalid_argument("empty handshake"))?;
+        let response = HandshakeResponse {
+            protocol_version: request.protocol_version,
+            payload: request.payload,
+        };
+        let output = futures::stream::iter(std::iter::once(Ok(response)));
+        Ok(Response::new(output.boxed()))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, tonic::Status> {
+        Err(tonic::Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn get_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, tonic::Status> {
+        Err(tonic::Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, tonic::Status> {
+        Err(tonic::Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, tonic::Status> {
+        Err(tonic::Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, tonic::Status> {
+        Err(tonic::Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, tonic::Status> {
+        Err(tonic::Status::unimplemented("Not yet implemented"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use clap_blocks::query_gateway::TokenConfig;
+    use futures::TryStreamExt;
+    use iox_time::{MockProvider, Time};
+    use parking_lot::Mutex;
+    use std::{collections::HashMap, time::Duration};
+
+    /// Records the forwarded requests and returns a single message.
+    #[derive(Debug, Default)]
+    struct MockBackend {
+        requests: Mutex<Vec<(String, proto::ReadInfo, Option<String>)>>,
+    }
+
+    #[async_trait]
+    impl QueryBackend for MockBackend {
+        async fn do_get(
+            &self,
+            namespace: &str,
+            request: Request<Ticket>,
+        ) -> Result<FlightDataStream, tonic::Status> {
+            let identity = request
+                .metadata()
+                .get(IDENTITY_HEADER)
+                .map(|v| v.to_str().unwrap().to_string());
+            let read_info =
+                proto::ReadInfo::decode(request.into_inner().ticket.as_slice()).unwrap();
+            self.requests
+                .lock()
+                .push((namespace.to_string(), read_info, identity));
+
+            let data = FlightData {
+                data_body: b"result".to_vec(),
+                ..Default::default()
+            };
+            Ok(futures::stream::iter([Ok(data)]).boxed())
+        }
+    }
+
+    fn gateway(backend: &Arc<MockBackend>, cache_ttl: Option<Duration>) -> QueryGateway {
+        let metrics = Arc::new(metric::Registry::default());
+        let authenticator = Authenticator::new(HashMap::from([(
+            "t1".to_string(),
+            TokenConfig {
+                identity: "alice".to_string(),
+                namespaces: vec!["ns".to_string()],
+            },
+        )]));
+        let cache = cache_ttl.map(|ttl| {
+            Arc::new(ResultCache::new(
+                ttl,
+                usize::MAX,
+                usize::MAX,
+                Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+                Arc::clone(&metrics),
+            ))
+        });
+
+        QueryGateway::new(
+            authenticator,
+            AdmissionControl::new(1, 1, &metrics),
+            cache,
+            Arc::clone(backend) as _,
+        )
+    }
+
+    fn request(ticket: Vec<u8>, token: Option<&str>) -> Request<Ticket> {
+        let mut request = Request::new(Ticket { ticket });
+        if let Some(token) = token {
+            request
+                .metadata_mut()
+                .insert("authorization", format!("Bearer {token}").parse().unwrap());
+        }
+        // Clients can not choose their identity.
+        request
+            .metadata_mut()
+            .insert(IDENTITY_HEADER, "admin".parse().unwrap());
+        request
+    }
+
+    fn read_info(namespace: &str) -> proto::ReadInfo {
+        proto::ReadInfo {
+            namespace_name: namespace.to_string(),
+            sql_query: "SELECT 1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    async fn do_get(
+        gateway: &QueryGateway,
+        request: Request<Ticket>,
+    ) -> Result<Vec<FlightData>, tonic::Status> {
+        gateway
+            .do_get(request)
+            .await?
+            .into_inner()
+            .try_collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_forward_authenticated_queries() {
+        let backend = Arc::new(MockBackend::default());
+        let gateway = gateway(&backend, None);
+
+        let res = do_get(
+            &gateway,
+            request(read_info("ns").encode_to_vec(), Some("t1")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(res.len(), 1);
+
+        // JSON tickets are forwarded as protobuf.
+        let json = br#"{"namespace_name": "ns", "sql_query": "SELECT 1"}"#.to_vec();
+        do_get(&gateway, request(json, Some("t1"))).await.unwrap();
+
+        let forwarded = ("ns".to_string(), read_info("ns"), Some("alice".to_string()));
+        assert_eq!(*backend.requests.lock(), vec![forwarded.clone(), forwarded]);
+
+        let err = do_get(&gateway, request(read_info("ns").encode_to_vec(), None))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+
+        let err = do_get(
+            &gateway,
+            request(read_info("other").encode_to_vec(), Some("t1")),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+        let mut other = read_info("ns");
+        other.additional_namespaces = vec!["other".to_string()];
+        let err = do_get(&gateway, request(other.encode_to_vec(), Some("t1")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+        let err = do_get(&gateway, request(b"invalid".to_vec(), Some("t1")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        assert_eq!(backend.requests.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cache_results() {
+        let backend = Arc::new(MockBackend::default());
+        let gateway = gateway(&backend, Some(Duration::from_secs(60)));
+
+        let first = do_get(
+            &gateway,
+            request(read_info("ns").encode_to_vec(), Some("t1")),
+        )
+        .await
+        .unwrap();
+        let second = do_get(
+            &gateway,
+            request(read_info("ns").encode_to_vec(), Some("t1")),
+        )
+        .await
+        .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(backend.requests.lock().len(), 1);
+
+        // queries returning statistics are always run
+        let read_info = proto::ReadInfo {
+            include_statistics: true,
+            ..read_info("ns")
+        };
+        for _ in 0..2 {
+            do_get(&gateway, request(read_info.encode_to_vec(), Some("t1")))
+                .await
+                .unwrap();
+        }
+        assert_eq!(backend.requests.lock().len(), 3);
+
+        // ... and so are debug scans
+        let read_info = proto::ReadInfo {
+            debug_parquet_file_ids: vec![1],
+            ..read_info("ns")
+        };
+        for _ in 0..2 {
+            do_get(&gateway, request(read_info.encode_to_vec(), Some("t1")))
+                .await
+                .unwrap();
+        }
+        assert_eq!(backend.requests.lock().len(), 5);
+    }
+}
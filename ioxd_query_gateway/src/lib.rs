@@ -0,0 +1,193 @@
+//! The query gateway server type.
+//!
+//! The query gateway is the front door for queries: it terminates the Flight
+//! query API, authenticates queries, bounds the number of queries in flight,
+//! answers repeated queries from a result cache and forwards all other queries
+//! to a querier of the queried namespace's query pool.
+
+use async_trait::async_trait;
+use clap_blocks::query_gateway::QueryGatewayConfig;
+use hyper::{Body, Request, Response};
+use iox_catalog::interface::Catalog;
+use iox_time::TimeProvider;
+use ioxd_common::{
+    add_service,
+    http::error::{HttpApiError, HttpApiErrorCode, HttpApiErrorSource},
+    rpc::RpcBuilderInput,
+    serve_builder,
+    server_type::{CommonServerState, RpcError, ServerType},
+    setup_builder,
+};
+use metric::Registry;
+use querier::{QueryPoolDispatcher, QUERY_POOL_HEARTBEAT_EXPIRY_INTERVALS};
+use std::{
+    fmt::{Debug, Display},
+    sync::Arc,
+};
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+use trace::TraceCollector;
+
+mod admission;
+mod auth;
+mod backend;
+mod cache;
+mod flight;
+
+pub use admission::AdmissionControl;
+pub use auth::Authenticator;
+pub use backend::{QueryBackend, QueryPoolBackend};
+pub use cache::ResultCache;
+pub use flight::QueryGateway;
+
+pub struct QueryGatewayServerType {
+    gateway: Arc<QueryGateway>,
+    metric_registry: Arc<Registry>,
+    shutdown: CancellationToken,
+    trace_collector: Option<Arc<dyn TraceCollector>>,
+}
+
+impl std::fmt::Debug for QueryGatewayServerType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "QueryGateway")
+    }
+}
+
+impl QueryGatewayServerType {
+    pub fn new(
+        gateway: Arc<QueryGateway>,
+        metric_registry: Arc<Registry>,
+        common_state: &CommonServerState,
+    ) -> Self {
+        Self {
+            gateway,
+            metric_registry,
+            shutdown: CancellationToken::new(),
+            trace_collector: common_state.trace_collector(),
+        }
+    }
+}
+
+#[async_trait]
+impl ServerType for QueryGatewayServerType {
+    /// Return the [`metric::Registry`] used by the query gateway.
+    fn metric_registry(&self) -> Arc<Registry> {
+        Arc::clone(&self.metric_registry)
+    }
+
+    /// Returns the trace collector for query gateway traces.
+    fn trace_collector(&self) -> Option<Arc<dyn TraceCollector>> {
+        self.trace_collector.as_ref().map(Arc::clone)
+    }
+
+    /// Just return "not found".
+    async fn route_http_request(
+        &self,
+        _req: Request<Body>,
+    ) -> Result<Response<Body>, Box<dyn HttpApiErrorSource>> {
+        Err(Box::new(IoxHttpError::NotFound))
+    }
+
+    /// Configure the gRPC services.
+    async fn server_grpc(self: Arc<Self>, builder_input: RpcBuilderInput) -> Result<(), RpcError> {
+        let builder = setup_builder!(builder_input, self);
+        add_service!(builder, flight::make_server(Arc::clone(&self.gateway)));
+        serve_builder!(builder);
+
+        Ok(())
+    }
+
+    async fn join(self: Arc<Self>) {
+        self.shutdown.cancelled().await;
+    }
+
+    fn shutdown(&self) {
+        self.shutdown.cancel();
+    }
+}
+
+/// Simple error struct, we're not really providing an HTTP interface for the query gateway.
+#[derive(Debug)]
+pub enum IoxHttpError {
+    NotFound,
+}
+
+impl IoxHttpError {
+    fn status_code(&self) -> HttpApiErrorCode {
+        match self {
+            IoxHttpError::NotFound => HttpApiErrorCode::NotFound,
+        }
+    }
+}
+
+impl Display for IoxHttpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for IoxHttpError {}
+
+impl HttpApiErrorSource for IoxHttpError {
+    fn to_http_api_error(&self) -> HttpApiError {
+        HttpApiError::new(self.status_code(), self.to_string())
+    }
+}
+
+/// Arguments required to create a [`ServerType`] for the query gateway.
+#[derive(Debug)]
+pub struct QueryGatewayServerTypeArgs<'a> {
+    pub common_state: &'a CommonServerState,
+    pub metric_registry: Arc<metric::Registry>,
+    pub catalog: Arc<dyn Catalog>,
+    pub time_provider: Arc<dyn TimeProvider>,
+    pub query_gateway_config: QueryGatewayConfig,
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid query gateway config: {0}")]
+    Config(#[from] clap_blocks::query_gateway::Error),
+}
+
+/// Instantiate a query gateway server
+pub fn create_query_gateway_server_type(
+    args: QueryGatewayServerTypeArgs<'_>,
+) -> Result<Arc<dyn ServerType>, Error> {
+    let config = args.query_gateway_config;
+
+    let authenticator = match config.tokens()? {
+        Some(tokens) => Authenticator::new(tokens),
+        None => Authenticator::default(),
+    };
+
+    let admission = AdmissionControl::new(
+        config.max_concurrent_queries,
+        config.max_queued_queries,
+        &args.metric_registry,
+    );
+
+    let cache = (!config.result_cache_ttl.is_zero()).then(|| {
+        Arc::new(ResultCache::new(
+            config.result_cache_ttl,
+            config.result_cache_bytes,
+            config.result_cache_max_entry_bytes,
+            Arc::clone(&args.time_provider),
+            Arc::clone(&args.metric_registry),
+        ))
+    });
+
+    let dispatcher = QueryPoolDispatcher::new(
+        args.catalog,
+        config.query_pool_heartbeat_interval() * QUERY_POOL_HEARTBEAT_EXPIRY_INTERVALS,
+    );
+    let backend = Arc::new(QueryPoolBackend::new(dispatcher));
+
+    let gateway = Arc::new(QueryGateway::new(authenticator, admission, cache, backend));
+
+    Ok(Arc::new(QueryGatewayServerType::new(
+        gateway,
+        args.metric_registry,
+        args.common_state,
+    )))
+}
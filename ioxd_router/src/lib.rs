@@ -1,9 +1,17 @@
 use async_trait::async_trait;
-use clap_blocks::write_buffer::WriteBufferConfig;
-use data_types::{NamespaceName, PartitionTemplate, TemplatePart};
+use clap_blocks::{
+    router::{
+        DmlHandlerConfig, NamespaceAutocreationConfig, NamespaceAutocreationPolicy,
+        NamespaceNameRulesConfig, SchemaConflictConfig,
+        SchemaConflictPolicy as SchemaConflictPolicyConfig, TopicRoutingConfig,
+    },
+    write_buffer::WriteBufferConfig,
+};
+use data_types::{NamespaceName, TopicMetadata};
+use futures::{pin_mut, TryStreamExt};
 use hashbrown::HashMap;
 use hyper::{Body, Request, Response};
-use iox_catalog::interface::Catalog;
+use iox_catalog::{authz::Authorizer, interface::Catalog, usage::UsageAccumulator};
 use ioxd_common::{
     add_service,
     http::error::{HttpApiError, HttpApiErrorSource},
@@ -12,19 +20,26 @@ use ioxd_common::{
     server_type::{CommonServerState, RpcError, ServerType},
     setup_builder,
 };
-use metric::Registry;
+use metric::{CardinalityLimiter, Registry};
 use mutable_batch::MutableBatch;
 use object_store::DynObjectStore;
 use observability_deps::tracing::info;
 use router::{
     dml_handlers::{
-        DmlHandler, DmlHandlerChainExt, FanOutAdaptor, InstrumentationDecorator, Partitioner,
-        RetentionValidator, SchemaValidator, ShardedWriteBuffer, WriteSummaryAdapter,
+        DmlHandler, DmlHandlerChainExt, DryRunValidator, FanOutAdaptor, InstrumentationDecorator,
+        LogWriteAuditSink, Partitioner, RetentionValidator, SchemaConflictPolicy, SchemaValidator,
+        ShardPinner, ShardedWriteBuffer, TopicRouter, TransformRules, Transformer, WriteAuditor,
+        WriteSummaryAdapter,
     },
     namespace_cache::{
         metrics::InstrumentedCache, MemoryNamespaceCache, NamespaceCache, ShardedCache,
     },
-    namespace_resolver::{NamespaceAutocreation, NamespaceResolver, NamespaceSchemaResolver},
+    namespace_resolver::{
+        MissingNamespaceAction, NamespaceAutocreation, NamespaceResolver, NamespaceSchemaResolver,
+        NamespaceTemplate,
+    },
+    namespace_topics::{NamespaceTopics, NamespaceTopicsRefresher},
+    producer_sequence::ProducerSequences,
     server::{
         grpc::{sharder::ShardService, GrpcDelegate},
         http::HttpDelegate,
@@ -32,13 +47,15 @@ use router::{
     },
     shard::Shard,
 };
-use sharder::{JumpHash, Sharder};
+use sharder::{PinnedSharder, Sharder};
 use std::{
     collections::BTreeSet,
     fmt::{Debug, Display},
-    sync::Arc,
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 use thiserror::Error;
+use tokio::task::JoinHandle;
 use tokio_util::sync::CancellationToken;
 use trace::TraceCollector;
 use write_summary::WriteSummary;
@@ -62,6 +79,18 @@ pub enum Error {
 
     #[error("Failed to init shard grpc service: {0}")]
     ShardServiceInit(iox_catalog::interface::Error),
+
+    #[error("Write audit sample rate must be within [0.0, 1.0], got {0}")]
+    WriteAuditSampleRate(f64),
+
+    #[error("Invalid namespace in schema conflict policies: {0}")]
+    SchemaConflictNamespace(data_types::NamespaceNameError),
+
+    #[error("Invalid namespace in transform rules: {0}")]
+    TransformNamespace(data_types::NamespaceNameError),
+
+    #[error("Invalid DML handler config: {0}")]
+    DmlHandlerConfig(#[from] clap_blocks::router::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -70,6 +99,13 @@ pub struct RouterServerType<D, N, S> {
     server: RouterServer<D, N, S>,
     shutdown: CancellationToken,
     trace_collector: Option<Arc<dyn TraceCollector>>,
+
+    /// Reloads the namespace topics for the lifetime of the server.
+    _namespace_topics_refresher: Option<NamespaceTopicsRefresher>,
+
+    /// Flushes the namespace usage to the catalog until the server shuts
+    /// down, if usage accounting is enabled.
+    usage_flusher: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl<D, N, S> RouterServerType<D, N, S> {
@@ -78,8 +114,31 @@ impl<D, N, S> RouterServerType<D, N, S> {
             server,
             shutdown: CancellationToken::new(),
             trace_collector: common_state.trace_collector(),
+            _namespace_topics_refresher: None,
+            usage_flusher: Default::default(),
+        }
+    }
+
+    /// Keep `refresher` running until the server is dropped.
+    pub fn with_namespace_topics_refresher(self, refresher: NamespaceTopicsRefresher) -> Self {
+        Self {
+            _namespace_topics_refresher: Some(refresher),
+            ..self
         }
     }
+
+    /// Flush `usage` to `catalog` every `interval` until the server shuts
+    /// down.
+    pub fn with_usage_flush(
+        self,
+        usage: Arc<UsageAccumulator>,
+        catalog: Arc<dyn Catalog>,
+        interval: Duration,
+    ) -> Self {
+        let handle = tokio::spawn(usage.run(catalog, interval, self.shutdown.clone()));
+        *self.usage_flusher.lock().expect("mutex poisoned") = Some(handle);
+        self
+    }
 }
 
 impl<D, N, S> std::fmt::Debug for RouterServerType<D, N, S> {
@@ -130,6 +189,8 @@ where
         add_service!(builder, self.server.grpc().object_store_service());
         add_service!(builder, self.server.grpc().shard_service());
         add_service!(builder, self.server.grpc().namespace_service());
+        add_service!(builder, self.server.grpc().usage_service());
+        add_service!(builder, self.server.grpc().authz_service());
         serve_builder!(builder);
 
         Ok(())
@@ -137,6 +198,11 @@ where
 
     async fn join(self: Arc<Self>) {
         self.shutdown.cancelled().await;
+
+        let usage_flusher = self.usage_flusher.lock().expect("mutex poisoned").take();
+        if let Some(handle) = usage_flusher {
+            handle.await.expect("usage flusher panicked");
+        }
     }
 
     fn shutdown(&self) {
@@ -161,6 +227,15 @@ impl std::error::Error for IoxHttpErrorAdaptor {}
 impl HttpApiErrorSource for IoxHttpErrorAdaptor {
     fn to_http_api_error(&self) -> HttpApiError {
         HttpApiError::new(self.0.as_status_code(), self.to_string())
+            .with_error_code(self.0.error_code().as_str(), self.0.details())
+    }
+}
+
+fn schema_conflict_policy(policy: SchemaConflictPolicyConfig) -> SchemaConflictPolicy {
+    match policy {
+        SchemaConflictPolicyConfig::Reject => SchemaConflictPolicy::Reject,
+        SchemaConflictPolicyConfig::Coerce => SchemaConflictPolicy::CoerceToWiderType,
+        SchemaConflictPolicyConfig::DropLines => SchemaConflictPolicy::DropConflictingLines,
     }
 }
 
@@ -173,7 +248,23 @@ pub async fn create_router_server_type(
     write_buffer_config: &WriteBufferConfig,
     query_pool_name: &str,
     request_limit: usize,
+    write_audit_sample_rate: f64,
+    shard_pinning: bool,
+    namespace_metric_label_limit: Option<usize>,
+    usage_flush_interval: Option<Duration>,
+    authz_cache_ttl: Option<Duration>,
+    namespace_autocreation_config: &NamespaceAutocreationConfig,
+    namespace_name_rules_config: &NamespaceNameRulesConfig,
+    schema_conflict_config: &SchemaConflictConfig,
+    topic_routing_config: &TopicRoutingConfig,
+    dml_handler_config: &DmlHandlerConfig,
 ) -> Result<Arc<dyn ServerType>> {
+    if !(0.0..=1.0).contains(&write_audit_sample_rate) {
+        return Err(Error::WriteAuditSampleRate(write_audit_sample_rate));
+    }
+    let handler_chain = dml_handler_config.handler_chain()?;
+    info!(?handler_chain, "configured DML handler chain");
+
     // Initialise the sharded write buffer and instrument it with DML handler
     // metrics.
     let (write_buffer, sharder) = init_write_buffer(
@@ -182,8 +273,6 @@ pub async fn create_router_server_type(
         common_state.trace_collector(),
     )
     .await?;
-    let write_buffer =
-        InstrumentationDecorator::new("sharded_write_buffer", &metrics, write_buffer);
 
     // Initialise an instrumented namespace cache to be shared with the schema
     // validator, and namespace auto-creator that reports cache hit/miss/update
@@ -199,51 +288,116 @@ pub async fn create_router_server_type(
         .await
         .expect("namespace cache pre-warming failed");
 
-    // Initialise and instrument the schema validator
-    let schema_validator =
-        SchemaValidator::new(Arc::clone(&catalog), Arc::clone(&ns_cache), &metrics);
+    // Initialise and instrument the schema validator, resolving schema
+    // conflicts according to the configured policies.
+    let conflict_policy = schema_conflict_policy(schema_conflict_config.schema_conflict_policy);
+    let namespace_conflict_policies = schema_conflict_config
+        .namespace_schema_conflict_policies
+        .iter()
+        .map(|(namespace, policy)| {
+            let namespace = NamespaceName::try_from(namespace.clone())
+                .map_err(Error::SchemaConflictNamespace)?;
+            Ok((namespace, schema_conflict_policy(*policy)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut schema_validator =
+        SchemaValidator::new(Arc::clone(&catalog), Arc::clone(&ns_cache), &metrics)
+            .with_conflict_policy(conflict_policy);
+    for (namespace, policy) in &namespace_conflict_policies {
+        schema_validator =
+            schema_validator.with_namespace_conflict_policy(namespace.clone(), *policy);
+    }
+    info!(
+        ?schema_conflict_config,
+        "configured schema conflict policies"
+    );
     let schema_validator =
         InstrumentationDecorator::new("schema_validator", &metrics, schema_validator);
 
+    // Rewrite the writes to the namespaces with transform rules before they are
+    // validated (a NOP for all other namespaces).
+    let mut transformer = Transformer::default();
+    for (namespace, rules) in &handler_chain.transform {
+        let namespace =
+            NamespaceName::try_from(namespace.clone()).map_err(Error::TransformNamespace)?;
+        transformer = transformer.with_namespace_rules(
+            namespace,
+            TransformRules {
+                rename_measurements: rules.rename_measurements.clone(),
+                rename_tags: rules.rename_tags.clone(),
+                drop_fields: rules.drop_fields.clone(),
+            },
+        );
+    }
+    let dry_run_transformer = transformer.clone();
+    let transformer = InstrumentationDecorator::new("transformer", &metrics, transformer);
+
     // Add a retention validator into handler stack to reject data outside the retention period
-    let retention_validator = RetentionValidator::new(Arc::clone(&catalog), Arc::clone(&ns_cache));
+    // (a NOP when disabled).
+    let retention_validator = RetentionValidator::new(Arc::clone(&catalog), Arc::clone(&ns_cache))
+        .with_enabled(handler_chain.retention_validator.enabled);
     let retention_validator =
         InstrumentationDecorator::new("retention_validator", &metrics, retention_validator);
 
-    // Add a write partitioner into the handler stack that splits by the date
-    // portion of the write's timestamp.
-    let partitioner = Partitioner::new(PartitionTemplate {
-        parts: vec![TemplatePart::TimeFormat("%Y-%m-%d".to_owned())],
-    });
+    // Add a write partitioner into the handler stack that splits by the
+    // configured (by default, the date) portion of the write's timestamp,
+    // followed by any additional configured parts, or by the partition
+    // template of the namespace if it has one.
+    let partition_template = handler_chain.partitioner.template();
+    let partitioner =
+        Partitioner::new(partition_template.clone()).with_namespace_cache(Arc::clone(&ns_cache));
     let partitioner = InstrumentationDecorator::new("partitioner", &metrics, partitioner);
 
     // Initialise the Namespace ID lookup + cache
     let namespace_resolver =
         NamespaceSchemaResolver::new(Arc::clone(&catalog), Arc::clone(&ns_cache));
 
-    ////////////////////////////////////////////////////////////////////////////
-    //
-    // THIS CODE IS FOR TESTING ONLY.
-    //
-    // The source of truth for the topics & query pools will be read from
-    // the DB, rather than CLI args for a prod deployment.
-    //
-    ////////////////////////////////////////////////////////////////////////////
-    //
-    // Look up the topic ID needed to populate namespace creation
-    // requests.
-    //
-    // This code / auto-creation is for architecture testing purposes only - a
-    // prod deployment would expect namespaces to be explicitly created and this
-    // layer would be removed.
+    // Look up the topic & query pool IDs assigned to namespaces created by
+    // the router.
     let schema_catalog = Arc::clone(&catalog);
+    let usage_catalog = Arc::clone(&catalog);
+    // Authorize writes & token management by the API tokens in the catalog,
+    // if enabled.
+    let authorizer = authz_cache_ttl.map(|ttl| {
+        Arc::new(Authorizer::new(
+            Arc::clone(&catalog),
+            catalog.time_provider(),
+            ttl,
+        ))
+    });
+    // Skip the retries of writes already acknowledged to their producer.
+    let producer_sequences = ProducerSequences::new(Arc::clone(&catalog));
     let mut txn = catalog.start_transaction().await?;
-    let topic_id = txn
+    let topic = txn
         .topics()
         .get_by_name(write_buffer_config.topic())
         .await?
-        .map(|v| v.id)
         .unwrap_or_else(|| panic!("no topic named {} in catalog", write_buffer_config.topic()));
+    let topic_id = topic.id;
+    let catalog_shards = txn.shards().list_by_topic(&topic).await?;
+
+    // Connect to the additional write buffer topics namespaces may be routed
+    // to.
+    let mut topic_write_buffers = vec![(topic_id, write_buffer)];
+    let mut topic_sharders = vec![(topic, Arc::clone(&sharder), catalog_shards)];
+    for name in &topic_routing_config.write_buffer_additional_topics {
+        let additional_topic =
+            txn.topics()
+                .get_by_name(name)
+                .await?
+                .ok_or_else(|| Error::TopicCatalogLookup {
+                    topic_name: name.clone(),
+                })?;
+        let additional_shards = txn.shards().list_by_topic(&additional_topic).await?;
+        let (write_buffer, sharder) = init_write_buffer(
+            &write_buffer_config.for_topic(name),
+            Arc::clone(&metrics),
+            common_state.trace_collector(),
+        )
+        .await?;
+        topic_write_buffers.push((additional_topic.id, write_buffer));
+        topic_sharders.push((additional_topic, sharder, additional_shards));
+    }
     let query_id = txn
         .query_pools()
         .create_or_get(query_pool_name)
@@ -258,24 +412,82 @@ pub async fn create_router_server_type(
         });
     txn.commit().await?;
 
+    // Route the writes of each namespace to the topic it is assigned to in the
+    // catalog, reloading the assignments periodically.
+    //
+    // The assignments are reloaded even when writing to a single topic, so
+    // that writes to a namespace moved to a topic this router does not serve
+    // are rejected rather than written to the default topic.
+    let namespace_topics = Arc::new(NamespaceTopics::new(topic_id));
+    namespace_topics.refresh(&*catalog).await?;
+    info!(
+        additional_topics = ?topic_routing_config.write_buffer_additional_topics,
+        "configured namespace topic routing"
+    );
+    let namespace_topics_refresher = NamespaceTopicsRefresher::new(
+        Arc::clone(&namespace_topics),
+        Arc::clone(&catalog),
+        topic_routing_config.namespace_topic_refresh_interval(),
+    );
+    let write_buffer = TopicRouter::new(Arc::clone(&namespace_topics), topic_write_buffers);
+    let write_buffer =
+        InstrumentationDecorator::new("sharded_write_buffer", &metrics, write_buffer);
+
+    // Apply the configured policy for writes to namespaces that do not
+    // exist.
+    let missing_namespace_action = match namespace_autocreation_config.namespace_autocreation {
+        NamespaceAutocreationPolicy::Enabled => {
+            MissingNamespaceAction::AutoCreate(NamespaceTemplate {
+                retention_period_ns: namespace_autocreation_config
+                    .new_namespace_retention_period_ns(),
+                max_tables: namespace_autocreation_config.new_namespace_max_tables,
+                max_columns_per_table: namespace_autocreation_config
+                    .new_namespace_max_columns_per_table,
+                partition_template: namespace_autocreation_config
+                    .new_namespace_partition_template(),
+            })
+        }
+        NamespaceAutocreationPolicy::Disabled => MissingNamespaceAction::Reject,
+    };
+    info!(
+        ?missing_namespace_action,
+        "configured namespace autocreation"
+    );
     let namespace_resolver = NamespaceAutocreation::new(
         namespace_resolver,
         Arc::clone(&ns_cache),
         Arc::clone(&catalog),
         topic_id,
         query_id,
-        None,
+        missing_namespace_action,
     );
+
+    // Pin the tables written to onto their shard, so that a change in the
+    // number of shards does not move existing tables (a NOP when disabled).
     //
-    ////////////////////////////////////////////////////////////////////////////
+    // Each topic has its own shards, so the tables of a namespace are pinned
+    // to the shards of the topic it is assigned to.
+    let shard_pinner = TopicRouter::new(
+        Arc::clone(&namespace_topics),
+        topic_sharders.iter().map(|(topic, sharder, shards)| {
+            let pinner =
+                ShardPinner::new(Arc::clone(&catalog), Arc::clone(sharder), shards.clone())
+                    .with_enabled(shard_pinning);
+            (topic.id, pinner)
+        }),
+    );
+    info!(shard_pinning, "configured shard pinning");
+    let shard_pinner = InstrumentationDecorator::new("shard_pinner", &metrics, shard_pinner);
 
     let parallel_write = WriteSummaryAdapter::new(FanOutAdaptor::new(write_buffer));
 
     // Build the chain of DML handlers that forms the request processing
     // pipeline, starting with the namespace creator (for testing purposes) and
     // write partitioner that yields a set of partitioned batches.
-    let handler_stack = retention_validator
+    let handler_stack = transformer
+        .and_then(retention_validator)
         .and_then(schema_validator)
+        .and_then(shard_pinner)
         .and_then(partitioner)
         // Once writes have been partitioned, they are processed in parallel.
         //
@@ -289,36 +501,97 @@ pub async fn create_router_server_type(
             parallel_write,
         ));
 
-    // Record the overall request handling latency
-    let handler_stack = InstrumentationDecorator::new("request", &metrics, handler_stack);
+    // Log a sample of the accepted writes for usage attribution (a NOP when
+    // the sample rate is 0).
+    let handler_stack =
+        WriteAuditor::new(handler_stack, LogWriteAuditSink, write_audit_sample_rate);
+
+    // Record the overall request handling latency, by namespace if enabled.
+    let mut handler_stack = InstrumentationDecorator::new("request", &metrics, handler_stack);
+    if let Some(limit) = namespace_metric_label_limit {
+        info!(limit, "labelling request metrics by namespace");
+        handler_stack =
+            handler_stack.with_namespace_labels(Arc::new(CardinalityLimiter::new(limit)));
+    }
+
+    // Dry-run writes are validated, partitioned and sharded in the same way as
+    // the handler stack, but never applied.
+    let mut dry_run = DryRunValidator::new(
+        Arc::clone(&catalog),
+        partition_template,
+        Arc::clone(&sharder) as _,
+    )
+    .with_topic_sharders(topic_sharders.iter().map(|(topic, sharder, _)| {
+        let sharder: Arc<dyn Sharder<MutableBatch, Item = Arc<Shard>>> = Arc::clone(sharder);
+        (topic.id, sharder)
+    }))
+    .with_retention_validation(handler_chain.retention_validator.enabled)
+    .with_transformer(dry_run_transformer)
+    .with_conflict_policy(conflict_policy);
+    for (namespace, policy) in namespace_conflict_policies {
+        dry_run = dry_run.with_namespace_conflict_policy(namespace, policy);
+    }
 
-    // Initialise the shard-mapping gRPC service.
-    let shard_service = init_shard_service(sharder, write_buffer_config, catalog).await?;
+    // Initialise the shard-mapping gRPC service, mapping the tables of each
+    // namespace to the shards of its topic.
+    let shard_service = init_shard_service(
+        topic_sharders
+            .into_iter()
+            .map(|(topic, sharder, _)| (topic, sharder)),
+        write_buffer_config,
+        catalog,
+        Arc::clone(&metrics),
+        common_state.trace_collector(),
+    )
+    .await?
+    .with_namespace_topics(namespace_topics);
+
+    // Namespace naming restrictions, applied to writes & deletes, and to
+    // explicit namespace creation requests.
+    let namespace_name_rules = namespace_name_rules_config.rules();
+
+    // Account the rows and bytes written to each namespace, if enabled.
+    let usage = usage_flush_interval.map(|_| Arc::new(UsageAccumulator::default()));
 
     // Initialise the API delegates
-    let http = HttpDelegate::new(
+    let mut http = HttpDelegate::new(
         common_state.run_config().max_http_request_size,
         request_limit,
         namespace_resolver,
         handler_stack,
         &metrics,
-    );
-    let grpc = GrpcDelegate::new(
+    )
+    .with_dry_run(dry_run)
+    .with_namespace_name_rules(namespace_name_rules.clone())
+    .with_producer_sequences(producer_sequences);
+    if let Some(usage) = &usage {
+        http = http.with_usage(Arc::clone(usage));
+    }
+    let mut grpc = GrpcDelegate::new(
         topic_id,
         query_id,
         schema_catalog,
         object_store,
         shard_service,
-    );
+    )
+    .with_namespace_name_rules(namespace_name_rules);
+    if let Some(authorizer) = authorizer {
+        http = http.with_authorizer(Arc::clone(&authorizer));
+        grpc = grpc.with_authorizer(authorizer);
+    }
 
     let router_server = RouterServer::new(http, grpc, metrics, common_state.trace_collector());
-    let server_type = Arc::new(RouterServerType::new(router_server, common_state));
-    Ok(server_type)
+    let mut server_type = RouterServerType::new(router_server, common_state)
+        .with_namespace_topics_refresher(namespace_topics_refresher);
+    if let Some((usage, interval)) = usage.zip(usage_flush_interval) {
+        server_type = server_type.with_usage_flush(usage, usage_catalog, interval);
+    }
+    Ok(Arc::new(server_type))
 }
 
 /// Initialise the [`ShardedWriteBuffer`] with one shard per Kafka partition,
-/// using [`JumpHash`] to shard operations by their destination namespace &
-/// table name.
+/// using a [`PinnedSharder`] to shard operations by their destination
+/// namespace & table name.
 ///
 /// Returns both the DML handler and the sharder it uses.
 async fn init_write_buffer(
@@ -326,8 +599,8 @@ async fn init_write_buffer(
     metrics: Arc<metric::Registry>,
     trace_collector: Option<Arc<dyn TraceCollector>>,
 ) -> Result<(
-    ShardedWriteBuffer<Arc<JumpHash<Arc<Shard>>>>,
-    Arc<JumpHash<Arc<Shard>>>,
+    ShardedWriteBuffer<Arc<PinnedSharder<Arc<Shard>>>>,
+    Arc<PinnedSharder<Arc<Shard>>>,
 )> {
     let write_buffer = Arc::new(
         write_buffer_config
@@ -354,7 +627,9 @@ async fn init_write_buffer(
     }
 
     // Initialise the sharder that maps (table, namespace, payload) to shards.
-    let sharder = Arc::new(JumpHash::new(
+    //
+    // Until tables are pinned, this maps them exactly as a JumpHash would.
+    let sharder = Arc::new(PinnedSharder::new(
         shards
             .into_iter()
             .map(|shard_index| Shard::new(shard_index, Arc::clone(&write_buffer), &metrics))
@@ -364,32 +639,47 @@ async fn init_write_buffer(
     Ok((ShardedWriteBuffer::new(Arc::clone(&sharder)), sharder))
 }
 
+/// Initialise the [`ShardService`] with the sharder of each topic in
+/// `topic_sharders`, the first of which is the default topic.
 async fn init_shard_service<S>(
-    sharder: S,
+    topic_sharders: impl IntoIterator<Item = (TopicMetadata, S)>,
     write_buffer_config: &WriteBufferConfig,
     catalog: Arc<dyn Catalog>,
+    metrics: Arc<metric::Registry>,
+    trace_collector: Option<Arc<dyn TraceCollector>>,
 ) -> Result<ShardService<S>>
 where
     S: Send + Sync,
 {
-    // Get the TopicMetadata from the catalog for the configured topic.
-    let topic = catalog
-        .repositories()
-        .await
-        .topics()
-        .get_by_name(write_buffer_config.topic())
-        .await?
-        .ok_or_else(|| Error::TopicCatalogLookup {
-            topic_name: write_buffer_config.topic().to_string(),
-        })?;
+    let mut service: Option<ShardService<S>> = None;
+    for (topic, sharder) in topic_sharders {
+        // The shard progress is reported using the high watermark of the
+        // write buffer topic.
+        let write_buffer = write_buffer_config
+            .for_topic(&topic.name)
+            .reading(Arc::clone(&metrics), None, trace_collector.clone())
+            .await?;
+
+        service = Some(
+            match service {
+                None => ShardService::new(sharder, topic, Arc::clone(&catalog), write_buffer).await,
+                Some(service) => service.with_topic(sharder, topic, write_buffer).await,
+            }
+            .map_err(Error::ShardServiceInit)?,
+        );
+    }
 
-    // Initialise the sharder
-    ShardService::new(sharder, topic, catalog)
-        .await
-        .map_err(Error::ShardServiceInit)
+    Ok(service.expect("default topic sharder exists"))
 }
 
+/// The number of namespaces fetched from the catalog at a time when pre-warming the schema
+/// cache.
+const PRE_WARM_PAGE_SIZE: usize = 1_000;
+
 /// Pre-populate `cache` with the all existing schemas in `catalog`.
+///
+/// Schemas are loaded [`PRE_WARM_PAGE_SIZE`] namespaces at a time, so that startup does not
+/// need to hold the entire catalog in memory at once.
 async fn pre_warm_schema_cache<T>(
     cache: &T,
     catalog: &dyn Catalog,
@@ -397,14 +687,15 @@ async fn pre_warm_schema_cache<T>(
 where
     T: NamespaceCache,
 {
-    iox_catalog::interface::list_schemas(catalog)
-        .await?
-        .for_each(|(ns, schema)| {
-            let name = NamespaceName::try_from(ns.name)
-                .expect("cannot convert existing namespace string to a `NamespaceName` instance");
+    let schemas = iox_catalog::interface::list_schemas_paged(catalog, PRE_WARM_PAGE_SIZE).await;
+    pin_mut!(schemas);
 
-            cache.put_schema(name, schema);
-        });
+    while let Some((ns, schema)) = schemas.try_next().await? {
+        let name = NamespaceName::try_from(ns.name)
+            .expect("cannot convert existing namespace string to a `NamespaceName` instance");
+
+        cache.put_schema(name, schema);
+    }
 
     Ok(())
 }
@@ -0,0 +1,68 @@
+use parking_lot::Mutex;
+use std::{borrow::Cow, collections::HashSet};
+
+/// The attribute value recorded by a [`CardinalityLimiter`] once its limit is reached
+pub const OTHER_ATTRIBUTE_VALUE: &str = "other";
+
+/// Bounds the number of distinct values of an attribute, such as the namespace of a request
+///
+/// The first `max_values` distinct values are recorded as is, any further value is aggregated
+/// into [`OTHER_ATTRIBUTE_VALUE`]. This bounds the number of observations reported for a
+/// `Metric`, regardless of the number of distinct values seen by the process
+///
+/// ```
+/// use ::metric::{CardinalityLimiter, OTHER_ATTRIBUTE_VALUE};
+///
+/// let limiter = CardinalityLimiter::new(1);
+///
+/// assert_eq!(limiter.value("bananas"), "bananas");
+/// assert_eq!(limiter.value("platanos"), OTHER_ATTRIBUTE_VALUE);
+/// assert_eq!(limiter.value("bananas"), "bananas");
+/// ```
+#[derive(Debug)]
+pub struct CardinalityLimiter {
+    max_values: usize,
+    values: Mutex<HashSet<String>>,
+}
+
+impl CardinalityLimiter {
+    /// Create a limiter recording up to `max_values` distinct values
+    pub fn new(max_values: usize) -> Self {
+        Self {
+            max_values,
+            values: Default::default(),
+        }
+    }
+
+    /// Returns the attribute value to record for `value`
+    pub fn value(&self, value: &str) -> Cow<'static, str> {
+        let mut values = self.values.lock();
+        if values.contains(value) {
+            return Cow::Owned(value.to_string());
+        }
+        if values.len() < self.max_values {
+            values.insert(value.to_string());
+            return Cow::Owned(value.to_string());
+        }
+        Cow::Borrowed(OTHER_ATTRIBUTE_VALUE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit() {
+        let limiter = CardinalityLimiter::new(2);
+
+        assert_eq!(limiter.value("a"), "a");
+        assert_eq!(limiter.value("b"), "b");
+        assert_eq!(limiter.value("c"), OTHER_ATTRIBUTE_VALUE);
+        assert_eq!(limiter.value("a"), "a");
+        assert_eq!(limiter.value("b"), "b");
+
+        let limiter = CardinalityLimiter::new(0);
+        assert_eq!(limiter.value("a"), OTHER_ATTRIBUTE_VALUE);
+    }
+}
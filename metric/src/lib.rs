@@ -118,6 +118,7 @@ use std::borrow::Cow;
 use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
 
+mod cardinality;
 mod counter;
 mod cumulative;
 mod duration;
@@ -126,6 +127,7 @@ mod histogram;
 mod metric;
 
 pub use crate::metric::*;
+pub use cardinality::*;
 pub use counter::*;
 pub use cumulative::*;
 pub use duration::*;
@@ -18,6 +18,9 @@ use prometheus::{
     Encoder, TextEncoder,
 };
 
+/// The content type of the prometheus text exposition format written by [`PrometheusTextEncoder`]
+pub const PROMETHEUS_TEXT_CONTENT_TYPE: &str = prometheus::TEXT_FORMAT;
+
 /// A `metric::Reporter` that writes data in the prometheus text exposition format
 ///
 /// In order to comply with the prometheus naming best-practices, certain metrics may have
@@ -44,6 +44,9 @@ pub enum Error {
 
     #[snafu(display("Internal MUB error constructing Arrow Array: {}", source))]
     CreatingArrowArray { source: ArrowError },
+
+    #[snafu(display("Cannot coerce column of type {} to a float field", column_type))]
+    UnsupportedCoercion { column_type: InfluxColumnType },
 }
 
 /// A specialized `Error` for [`Column`] errors
@@ -149,6 +152,33 @@ impl Column {
         &self.data
     }
 
+    /// Convert this integer field column into a float field column, widening
+    /// each value and the column statistics to `f64`.
+    ///
+    /// Integers with a magnitude greater than 2^53 lose precision.
+    pub(crate) fn coerce_to_f64(&mut self) -> Result<()> {
+        let (data, stats) = match (&self.influx_type, &self.data) {
+            (InfluxColumnType::Field(InfluxFieldType::Integer), ColumnData::I64(data, stats)) => (
+                data.iter().map(|v| *v as f64).collect::<Vec<_>>(),
+                widen_stats(stats, |v| v as f64),
+            ),
+            (InfluxColumnType::Field(InfluxFieldType::UInteger), ColumnData::U64(data, stats)) => (
+                data.iter().map(|v| *v as f64).collect::<Vec<_>>(),
+                widen_stats(stats, |v| v as f64),
+            ),
+            _ => {
+                return UnsupportedCoercionSnafu {
+                    column_type: self.influx_type,
+                }
+                .fail()
+            }
+        };
+
+        self.influx_type = InfluxColumnType::Field(InfluxFieldType::Float);
+        self.data = ColumnData::F64(data, stats);
+        Ok(())
+    }
+
     /// Ensures that the total length of this column is `len` rows,
     /// padding it with trailing NULLs if necessary
     pub(crate) fn push_nulls_to_len(&mut self, len: usize) {
@@ -307,3 +337,17 @@ impl Column {
         Ok(data)
     }
 }
+
+/// Convert the `min` / `max` of `stats` using `f`, retaining the counts.
+fn widen_stats<T, U>(stats: &StatValues<T>, f: impl Fn(T) -> U) -> StatValues<U>
+where
+    T: Copy,
+{
+    StatValues {
+        min: stats.min.map(&f),
+        max: stats.max.map(&f),
+        total_count: stats.total_count,
+        null_count: stats.null_count,
+        distinct_count: stats.distinct_count,
+    }
+}
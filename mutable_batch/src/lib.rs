@@ -24,7 +24,7 @@ use hashbrown::HashMap;
 use iox_time::Time;
 use schema::Projection;
 use schema::{builder::SchemaBuilder, Schema, TIME_COLUMN_NAME};
-use snafu::{OptionExt, ResultExt, Snafu};
+use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use std::{collections::BTreeSet, ops::Range};
 
 pub mod column;
@@ -51,6 +51,9 @@ pub enum Error {
     #[snafu(display("Column not found: {}", column))]
     ColumnNotFound { column: String },
 
+    #[snafu(display("Column already exists: {}", column))]
+    ColumnExists { column: String },
+
     #[snafu(context(false))]
     WriterError { source: writer::Error },
 }
@@ -200,6 +203,55 @@ impl MutableBatch {
         Ok(&self.columns[*idx])
     }
 
+    /// Convert the integer field `column` into a float field.
+    ///
+    /// Integers with a magnitude greater than 2^53 lose precision.
+    pub fn coerce_column_to_f64(&mut self, column: &str) -> Result<()> {
+        let idx = *self
+            .column_names
+            .get(column)
+            .context(ColumnNotFoundSnafu { column })?;
+
+        self.columns[idx]
+            .coerce_to_f64()
+            .context(ColumnSnafu { column })
+    }
+
+    /// Remove `column` from this batch, returning it.
+    ///
+    /// The row count is unchanged, even if no columns remain.
+    pub fn drop_column(&mut self, column: &str) -> Result<Column> {
+        let idx = self
+            .column_names
+            .remove(column)
+            .context(ColumnNotFoundSnafu { column })?;
+
+        // Shift the index of all columns after the removed one.
+        for v in self.column_names.values_mut() {
+            if *v > idx {
+                *v -= 1;
+            }
+        }
+
+        Ok(self.columns.remove(idx))
+    }
+
+    /// Rename `column` to `new_name`, which must not be the name of another
+    /// column of this batch.
+    pub fn rename_column(&mut self, column: &str, new_name: &str) -> Result<()> {
+        ensure!(
+            !self.column_names.contains_key(new_name),
+            ColumnExistsSnafu { column: new_name }
+        );
+        let idx = self
+            .column_names
+            .remove(column)
+            .context(ColumnNotFoundSnafu { column })?;
+        self.column_names.insert(new_name.to_string(), idx);
+
+        Ok(())
+    }
+
     /// Return the approximate memory size of the batch, in bytes.
     ///
     /// This includes `Self`.
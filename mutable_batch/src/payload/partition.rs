@@ -7,7 +7,7 @@ use crate::{
     MutableBatch,
 };
 use chrono::{format::StrftimeItems, TimeZone, Utc};
-use data_types::{PartitionTemplate, TemplatePart};
+use data_types::{sanitize_tag_value, PartitionTemplate, TagBucket, TemplatePart};
 use schema::TIME_COLUMN_NAME;
 use std::ops::Range;
 
@@ -33,6 +33,9 @@ enum Template<'a> {
     Column(&'a Column, &'a str),
     MissingColumn(&'a str),
     TimeFormat(&'a [i64], StrftimeItems<'a>),
+    TagValue(&'a Column, &'a str),
+    Bucket(&'a Column, &'a TagBucket),
+    Literal(&'a str),
 }
 
 impl<'a> Template<'a> {
@@ -68,10 +71,47 @@ impl<'a> Template<'a> {
                     .format_with_items(format.clone());
                 write!(out, "{}", formatted)
             }
+            Template::TagValue(col, tag_name) => match tag_value(col, idx) {
+                Some(value) => {
+                    out.write_str(tag_name)?;
+                    out.write_char('_')?;
+                    sanitize_tag_value(value).try_for_each(|c| out.write_char(c))
+                }
+                None => out.write_str(tag_name),
+            },
+            Template::Bucket(col, bucket) => match tag_value(col, idx) {
+                Some(value) => write!(out, "{}_bucket_{}", bucket.tag, bucket.bucket(value)),
+                None => out.write_str(&bucket.tag),
+            },
+            Template::Literal(literal) => out.write_str(literal),
         }
     }
 }
 
+/// Returns the value of the tag `col` in row `idx`, if any
+fn tag_value(col: &Column, idx: usize) -> Option<&str> {
+    match &col.data {
+        ColumnData::Tag(col_data, dictionary, _) if col.valid.get(idx) => {
+            dictionary.lookup_id(col_data[idx])
+        }
+        _ => None,
+    }
+}
+
+/// Returns the [`Template`] rendering the tag `name` with `f`, or a
+/// [`Template::MissingColumn`] if the batch has no such tag.
+fn tag_template<'a>(
+    batch: &'a MutableBatch,
+    name: &'a str,
+    f: impl FnOnce(&'a Column) -> Template<'a>,
+) -> Template<'a> {
+    match batch.column(name) {
+        Ok(col) if matches!(col.data, ColumnData::Tag(..)) => f(col),
+        // A field with the same name as the tag is ignored.
+        _ => Template::MissingColumn(name),
+    }
+}
+
 /// Returns an iterator of partition keys for the given table batch
 fn partition_keys<'a>(
     batch: &'a MutableBatch,
@@ -96,6 +136,13 @@ fn partition_keys<'a>(
             TemplatePart::TimeFormat(fmt) => Template::TimeFormat(time, StrftimeItems::new(fmt)),
             TemplatePart::RegexCapture(_) => unimplemented!(),
             TemplatePart::StrftimeColumn(_) => unimplemented!(),
+            TemplatePart::TagValue(name) => {
+                tag_template(batch, name, |col| Template::TagValue(col, name))
+            }
+            TemplatePart::Bucket(bucket) => {
+                tag_template(batch, &bucket.tag, |col| Template::Bucket(col, bucket))
+            }
+            TemplatePart::Literal(literal) => Template::Literal(literal),
         })
         .collect();
 
@@ -234,4 +281,57 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn test_partition_tag_parts() {
+        let mut batch = MutableBatch::new();
+        let mut writer = Writer::new(&mut batch, 4);
+
+        writer
+            .write_time("time", vec![1, 2, 3, 4].into_iter())
+            .unwrap();
+
+        writer
+            .write_tag(
+                "host",
+                Some(&[0b00001011]),
+                vec!["server01", "us-west/a b", "server02"].into_iter(),
+            )
+            .unwrap();
+
+        writer
+            .write_f64("f64", None, vec![2., 4.5, 6., 3.].into_iter())
+            .unwrap();
+
+        let template = PartitionTemplate {
+            parts: vec![
+                TemplatePart::Literal("v2".to_string()),
+                TemplatePart::TagValue("host".to_string()),
+                TemplatePart::Bucket(TagBucket {
+                    tag: "host".to_string(),
+                    buckets: 16,
+                }),
+                // fields are not tags
+                TemplatePart::TagValue("f64".to_string()),
+                TemplatePart::Bucket(TagBucket {
+                    tag: "bananas".to_string(),
+                    buckets: 16,
+                }),
+            ],
+        };
+
+        writer.commit();
+
+        let keys: Vec<_> = partition_keys(&batch, "foo", &template).collect();
+
+        assert_eq!(
+            keys,
+            vec![
+                "v2-host_server01-host_bucket_5-f64-bananas".to_string(),
+                "v2-host_us_west_a_b-host_bucket_5-f64-bananas".to_string(),
+                "v2-host-host-f64-bananas".to_string(),
+                "v2-host_server02-host_bucket_12-f64-bananas".to_string(),
+            ]
+        )
+    }
 }
@@ -0,0 +1,118 @@
+use arrow_util::assert_batches_eq;
+use data_types::{StatValues, Statistics};
+use mutable_batch::{writer::Writer, MutableBatch};
+use schema::{InfluxColumnType, InfluxFieldType, Projection};
+
+fn test_batch() -> MutableBatch {
+    let mut batch = MutableBatch::new();
+    let mut writer = Writer::new(&mut batch, 3);
+
+    writer
+        .write_tag("tag1", None, vec!["v1", "v2", "v1"].into_iter())
+        .unwrap();
+    writer
+        .write_i64("i64", Some(&[0b00000101]), vec![-1, 7].into_iter())
+        .unwrap();
+    writer
+        .write_u64("u64", None, vec![1, 2, 3].into_iter())
+        .unwrap();
+    writer
+        .write_time("time", vec![1, 2, 3].into_iter())
+        .unwrap();
+    writer.commit();
+
+    batch
+}
+
+#[test]
+fn test_coerce_column_to_f64() {
+    let mut batch = test_batch();
+
+    batch.coerce_column_to_f64("i64").unwrap();
+    batch.coerce_column_to_f64("u64").unwrap();
+
+    let column = batch.column("i64").unwrap();
+    assert_eq!(
+        column.influx_type(),
+        InfluxColumnType::Field(InfluxFieldType::Float)
+    );
+    assert_eq!(
+        column.stats(),
+        Statistics::F64(StatValues {
+            min: Some(-1.0),
+            max: Some(7.0),
+            total_count: 3,
+            null_count: Some(1),
+            distinct_count: None,
+        })
+    );
+
+    assert_eq!(
+        batch.column("u64").unwrap().influx_type(),
+        InfluxColumnType::Field(InfluxFieldType::Float)
+    );
+
+    let expected_data = &[
+        "+-----+------+--------------------------------+-----+",
+        "| i64 | tag1 | time                           | u64 |",
+        "+-----+------+--------------------------------+-----+",
+        "| -1  | v1   | 1970-01-01T00:00:00.000000001Z | 1   |",
+        "|     | v2   | 1970-01-01T00:00:00.000000002Z | 2   |",
+        "| 7   | v1   | 1970-01-01T00:00:00.000000003Z | 3   |",
+        "+-----+------+--------------------------------+-----+",
+    ];
+    assert_batches_eq!(expected_data, &[batch.to_arrow(Projection::All).unwrap()]);
+
+    // Only integer fields can be coerced.
+    assert!(batch.coerce_column_to_f64("i64").is_err());
+    assert!(batch.coerce_column_to_f64("tag1").is_err());
+    assert!(batch.coerce_column_to_f64("time").is_err());
+    assert!(batch.coerce_column_to_f64("missing").is_err());
+}
+
+#[test]
+fn test_drop_column() {
+    let mut batch = test_batch();
+
+    let dropped = batch.drop_column("i64").unwrap();
+    assert_eq!(
+        dropped.influx_type(),
+        InfluxColumnType::Field(InfluxFieldType::Integer)
+    );
+    assert!(batch.drop_column("i64").is_err());
+
+    assert_eq!(batch.rows(), 3);
+    assert_eq!(
+        batch.column_names().into_iter().collect::<Vec<_>>(),
+        ["tag1", "time", "u64"]
+    );
+
+    let expected_data = &[
+        "+------+--------------------------------+-----+",
+        "| tag1 | time                           | u64 |",
+        "+------+--------------------------------+-----+",
+        "| v1   | 1970-01-01T00:00:00.000000001Z | 1   |",
+        "| v2   | 1970-01-01T00:00:00.000000002Z | 2   |",
+        "| v1   | 1970-01-01T00:00:00.000000003Z | 3   |",
+        "+------+--------------------------------+-----+",
+    ];
+    assert_batches_eq!(expected_data, &[batch.to_arrow(Projection::All).unwrap()]);
+}
+
+#[test]
+fn test_rename_column() {
+    let mut batch = test_batch();
+
+    batch.rename_column("tag1", "host").unwrap();
+    assert!(batch.rename_column("tag1", "tag2").is_err());
+    assert!(batch.rename_column("host", "u64").is_err());
+
+    assert_eq!(
+        batch.column_names().into_iter().collect::<Vec<_>>(),
+        ["host", "i64", "time", "u64"]
+    );
+    assert_eq!(
+        batch.column("host").unwrap().influx_type(),
+        InfluxColumnType::Tag
+    );
+}
@@ -14,7 +14,7 @@
 )]
 
 use hashbrown::{hash_map::Entry, HashMap, HashSet};
-use influxdb_line_protocol::{parse_lines, FieldValue, ParsedLine};
+use influxdb_line_protocol::{parse_lines, split_lines, FieldValue, ParsedLine};
 use mutable_batch::writer::Writer;
 use mutable_batch::MutableBatch;
 use snafu::{ResultExt, Snafu};
@@ -37,6 +37,12 @@ pub enum Error {
 
     #[snafu(display("timestamp overflows i64"))]
     TimestampOverflow,
+
+    #[snafu(display("line protocol is not valid utf8: {}", source))]
+    NonUtf8 { source: std::str::Utf8Error },
+
+    #[snafu(display("max payload size ({} bytes) exceeded", max_bytes))]
+    SizeExceeded { max_bytes: usize },
 }
 
 /// Result type for line protocol conversion
@@ -99,9 +105,13 @@ impl LinesConverter {
     ///   * same name for tag and field, different type :
     ///     [`mutable_batch::writer::Error::TypeMismatch`]
     ///
+    /// Line numbers in errors are relative to all the line protocol written to
+    /// this [`LinesConverter`].
     pub fn write_lp(&mut self, lines: &str) -> Result<()> {
-        for (line_idx, maybe_line) in parse_lines(lines).enumerate() {
-            let mut line = maybe_line.context(LineProtocolSnafu { line: line_idx + 1 })?;
+        for maybe_line in parse_lines(lines) {
+            // All previous lines were written successfully.
+            let line_number = self.stats.num_lines + 1;
+            let mut line = maybe_line.context(LineProtocolSnafu { line: line_number })?;
 
             if let Some(t) = line.timestamp.as_mut() {
                 *t = t
@@ -123,7 +133,7 @@ impl LinesConverter {
             // TODO: Reuse writer
             let mut writer = Writer::new(batch, 1);
             write_line(&mut writer, &line, self.default_time)
-                .context(WriteSnafu { line: line_idx + 1 })?;
+                .context(WriteSnafu { line: line_number })?;
             writer.commit();
         }
         Ok(())
@@ -139,6 +149,91 @@ impl LinesConverter {
     }
 }
 
+/// Incrementally converts a line protocol payload received in arbitrarily
+/// sized chunks (such as the chunks of a HTTP request body) to a set of
+/// [`MutableBatch`], without buffering the whole payload.
+///
+/// Complete lines are converted as soon as they are received, and only the
+/// trailing incomplete line of each chunk is buffered.
+#[derive(Debug)]
+pub struct StreamingLinesConverter {
+    converter: LinesConverter,
+    /// Bytes received but not yet converted - an incomplete line, which may
+    /// end in an incomplete UTF-8 character.
+    pending: Vec<u8>,
+    /// The total number of bytes received.
+    bytes: usize,
+    /// The maximum number of bytes accepted.
+    max_bytes: usize,
+}
+
+impl StreamingLinesConverter {
+    /// Create a new [`StreamingLinesConverter`], writing to `converter` and
+    /// rejecting payloads of more than `max_bytes` bytes.
+    pub fn new(converter: LinesConverter, max_bytes: usize) -> Self {
+        Self {
+            converter,
+            pending: Vec::new(),
+            bytes: 0,
+            max_bytes,
+        }
+    }
+
+    /// Write the next chunk of the payload.
+    ///
+    /// Returns [`Error::SizeExceeded`] as soon as the payload exceeds the
+    /// configured maximum size.
+    pub fn write_chunk(&mut self, chunk: &[u8]) -> Result<()> {
+        self.bytes += chunk.len();
+        if self.bytes > self.max_bytes {
+            return SizeExceededSnafu {
+                max_bytes: self.max_bytes,
+            }
+            .fail();
+        }
+
+        self.pending.extend_from_slice(chunk);
+
+        // A chunk may end in the middle of a UTF-8 character, which is
+        // completed by the next chunk.
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(v) => v,
+            Err(e) if e.error_len().is_none() => {
+                std::str::from_utf8(&self.pending[..e.valid_up_to()]).expect("valid utf8 prefix")
+            }
+            Err(e) => return Err(Error::NonUtf8 { source: e }),
+        };
+
+        // Newlines within quoted field values do not end a line, so the line
+        // protocol line splitting rules are used to find the (possibly
+        // empty) incomplete last line.
+        let incomplete = split_lines(valid).last().map_or(0, str::len);
+        let complete = valid.len() - incomplete;
+        if complete > 0 {
+            self.converter.write_lp(&valid[..complete])?;
+            self.pending.drain(..complete);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of bytes written so far.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    /// Convert any remaining buffered data and consume this
+    /// [`StreamingLinesConverter`], returning the [`MutableBatch`] and the
+    /// [`PayloadStatistics`] for the written data.
+    pub fn finish(mut self) -> Result<(HashMap<String, MutableBatch>, PayloadStatistics)> {
+        if !self.pending.is_empty() {
+            let lines = std::str::from_utf8(&self.pending).context(NonUtf8Snafu)?;
+            self.converter.write_lp(lines)?;
+        }
+        self.converter.finish()
+    }
+}
+
 /// Converts the provided lines of line protocol to a set of [`MutableBatch`]
 /// keyed by measurement name
 pub fn lines_to_batches(lines: &str, default_time: i64) -> Result<HashMap<String, MutableBatch>> {
@@ -325,7 +420,7 @@ pub mod test_helpers {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use arrow_util::assert_batches_eq;
+    use arrow_util::{assert_batches_eq, display::pretty_format_batches};
     use assert_matches::assert_matches;
     use schema::Projection;
 
@@ -369,6 +464,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_streaming() {
+        let lp = "cpu,tag1=v1 val=2i,str=\"a\nb\" 0\nmem,tag1=\u{1F34C} ival=3i 1\n\ncpu,tag1=v2 val=3i 2";
+        let (want, _) = lines_to_batches_stats(lp, 5).unwrap();
+
+        // Every possible chunk size, splitting lines, quoted newlines and
+        // multi-byte characters across chunks.
+        for chunk_size in 1..=lp.len() {
+            let mut converter = StreamingLinesConverter::new(LinesConverter::new(5), lp.len());
+            for chunk in lp.as_bytes().chunks(chunk_size) {
+                converter.write_chunk(chunk).unwrap();
+            }
+            assert_eq!(converter.bytes(), lp.len());
+
+            let (got, stats) = converter.finish().unwrap();
+            assert_eq!(stats.num_lines, 3);
+            assert_eq!(got.len(), want.len());
+            for (table, batch) in &want {
+                let format = |batch: &MutableBatch| {
+                    pretty_format_batches(&[batch.to_arrow(Projection::All).unwrap()]).unwrap()
+                };
+                assert_eq!(
+                    format(batch),
+                    format(&got[table]),
+                    "chunk size {}",
+                    chunk_size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_streaming_errors() {
+        // Line numbers are relative to the whole payload.
+        let mut converter = StreamingLinesConverter::new(LinesConverter::new(5), 1024);
+        converter.write_chunk(b"cpu val=1i 0\n").unwrap();
+        let err = converter
+            .write_chunk(b"cpu val=2i 1\ncpu val=2.0 2\n")
+            .unwrap_err();
+        assert_matches!(err, Error::Write { line: 3, .. });
+
+        let mut converter = StreamingLinesConverter::new(LinesConverter::new(5), 20);
+        converter.write_chunk(b"cpu val=1i 0\n").unwrap();
+        let err = converter.write_chunk(b"cpu val=2i 1\n").unwrap_err();
+        assert_matches!(err, Error::SizeExceeded { max_bytes: 20 });
+
+        let mut converter = StreamingLinesConverter::new(LinesConverter::new(5), 1024);
+        let err = converter.write_chunk(b"cpu val=1i 0\xff\n").unwrap_err();
+        assert_matches!(err, Error::NonUtf8 { .. });
+
+        // An incomplete character at the end of the payload.
+        let mut converter = StreamingLinesConverter::new(LinesConverter::new(5), 1024);
+        converter.write_chunk(b"cpu val=1i 0\n\xf0\x9f").unwrap();
+        assert_matches!(converter.finish(), Err(Error::NonUtf8 { .. }));
+
+        let converter = StreamingLinesConverter::new(LinesConverter::new(5), 1024);
+        assert_matches!(converter.finish(), Err(Error::EmptyPayload));
+    }
+
     #[test]
     fn test_nulls_string_and_float() {
         let lp = r#"m f0="cat" 1639612800000000000
@@ -0,0 +1,300 @@
+//! Fault injection for [`ObjectStore`] implementations, used to exercise the
+//! recovery paths of object store users in tests.
+
+use std::{fmt::Display, ops::Range, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::{
+    path::Path, DynObjectStore, GetResult, ListResult, MultipartId, ObjectMeta, ObjectStore, Result,
+};
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::io::AsyncWrite;
+
+/// The name reported as the store of injected [`object_store::Error`]s.
+const STORE: &str = "fault_injection";
+
+/// The faults a [`FaultInjectingObjectStore`] injects into object store
+/// operations.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FaultConfig {
+    error_rate: f64,
+    truncation_rate: f64,
+    latency: Option<Duration>,
+    seed: Option<u64>,
+}
+
+impl FaultConfig {
+    /// Fail each operation with probability `error_rate`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `error_rate` is not within `[0, 1]`.
+    pub fn with_error_rate(self, error_rate: f64) -> Self {
+        assert_probability(error_rate);
+        Self { error_rate, ..self }
+    }
+
+    /// Truncate the payload of each [`ObjectStore::put()`] with probability
+    /// `truncation_rate`.
+    ///
+    /// A truncated put writes a random prefix of the payload to the inner store
+    /// and then returns an error, leaving a partially written object behind.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `truncation_rate` is not within `[0, 1]`.
+    pub fn with_truncation_rate(self, truncation_rate: f64) -> Self {
+        assert_probability(truncation_rate);
+        Self {
+            truncation_rate,
+            ..self
+        }
+    }
+
+    /// Delay each operation by `latency` before it is executed.
+    pub fn with_latency(self, latency: Duration) -> Self {
+        Self {
+            latency: Some(latency),
+            ..self
+        }
+    }
+
+    /// Seed the random number generator deciding which operations fail, making
+    /// the sequence of injected faults reproducible.
+    pub fn with_seed(self, seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..self
+        }
+    }
+
+    /// Returns true if this config injects any faults.
+    pub fn is_enabled(&self) -> bool {
+        self.error_rate > 0.0 || self.truncation_rate > 0.0 || self.latency.is_some()
+    }
+}
+
+fn assert_probability(p: f64) {
+    assert!((0.0..=1.0).contains(&p), "fault rate must be within [0, 1]");
+}
+
+/// The error returned by operations failed by a [`FaultInjectingObjectStore`].
+#[derive(Debug)]
+struct InjectedFault {
+    operation: &'static str,
+}
+
+impl Display for InjectedFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "injected fault in object store operation {}",
+            self.operation
+        )
+    }
+}
+
+impl std::error::Error for InjectedFault {}
+
+impl From<InjectedFault> for object_store::Error {
+    fn from(source: InjectedFault) -> Self {
+        Self::Generic {
+            store: STORE,
+            source: Box::new(source),
+        }
+    }
+}
+
+/// An [`ObjectStore`] decorator that injects latency, errors and truncated
+/// writes into the operations of the wrapped store.
+///
+/// Injected errors are returned before the operation reaches the inner store,
+/// with the exception of truncated puts (see
+/// [`FaultConfig::with_truncation_rate()`]). Streams returned by
+/// [`ObjectStore::get()`] and [`ObjectStore::list()`] are passed through
+/// unchanged once the call succeeds.
+#[derive(Debug)]
+pub struct FaultInjectingObjectStore {
+    inner: Arc<DynObjectStore>,
+    config: FaultConfig,
+    rng: Mutex<StdRng>,
+}
+
+impl FaultInjectingObjectStore {
+    /// Wrap `inner`, injecting the faults described by `config`.
+    pub fn new(inner: Arc<DynObjectStore>, config: FaultConfig) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        Self {
+            inner,
+            config,
+            rng: Mutex::new(rng),
+        }
+    }
+
+    /// Apply the configured latency, then decide whether `operation` fails.
+    async fn inject(&self, operation: &'static str) -> Result<()> {
+        if let Some(latency) = self.config.latency {
+            tokio::time::sleep(latency).await;
+        }
+
+        if self.rng.lock().gen_bool(self.config.error_rate) {
+            return Err(InjectedFault { operation }.into());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the length to truncate a payload of `len` bytes to, if the
+    /// write should be truncated.
+    fn truncate_to(&self, len: usize) -> Option<usize> {
+        let mut rng = self.rng.lock();
+        (len > 0 && rng.gen_bool(self.config.truncation_rate)).then(|| rng.gen_range(0..len))
+    }
+}
+
+impl Display for FaultInjectingObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "FaultInjecting({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for FaultInjectingObjectStore {
+    async fn put(&self, location: &Path, bytes: Bytes) -> Result<()> {
+        self.inject("put").await?;
+
+        if let Some(len) = self.truncate_to(bytes.len()) {
+            self.inner.put(location, bytes.slice(..len)).await?;
+            return Err(InjectedFault {
+                operation: "put_truncated",
+            }
+            .into());
+        }
+
+        self.inner.put(location, bytes).await
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        self.inject("put_multipart").await?;
+        self.inner.put_multipart(location).await
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        self.inject("abort_multipart").await?;
+        self.inner.abort_multipart(location, multipart_id).await
+    }
+
+    async fn get(&self, location: &Path) -> Result<GetResult> {
+        self.inject("get").await?;
+        self.inner.get(location).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.inject("get_range").await?;
+        self.inner.get_range(location, range).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inject("head").await?;
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inject("delete").await?;
+        self.inner.delete(location).await
+    }
+
+    async fn list(&self, prefix: Option<&Path>) -> Result<BoxStream<'_, Result<ObjectMeta>>> {
+        self.inject("list").await?;
+        self.inner.list(prefix).await
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inject("list_with_delimiter").await?;
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inject("copy").await?;
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inject("copy_if_not_exists").await?;
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use object_store::memory::InMemory;
+
+    fn store(config: FaultConfig) -> (Arc<DynObjectStore>, FaultInjectingObjectStore) {
+        let inner: Arc<DynObjectStore> = Arc::new(InMemory::new());
+        let store = FaultInjectingObjectStore::new(Arc::clone(&inner), config);
+        (inner, store)
+    }
+
+    #[tokio::test]
+    async fn test_no_faults() {
+        let (_inner, store) = store(FaultConfig::default());
+        let path = Path::from("bananas");
+
+        store.put(&path, Bytes::from_static(b"42")).await.unwrap();
+        let got = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(got.as_ref(), b"42");
+    }
+
+    #[tokio::test]
+    async fn test_all_operations_fail() {
+        let (inner, store) = store(FaultConfig::default().with_error_rate(1.0));
+        let path = Path::from("bananas");
+
+        let err = store
+            .put(&path, Bytes::from_static(b"42"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            object_store::Error::Generic { store: STORE, .. }
+        ));
+        assert!(store.head(&path).await.is_err());
+
+        // The failed put never reached the inner store.
+        assert!(matches!(
+            inner.head(&path).await,
+            Err(object_store::Error::NotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_truncated_put() {
+        let (inner, store) = store(FaultConfig::default().with_truncation_rate(1.0));
+        let path = Path::from("bananas");
+
+        let err = store
+            .put(&path, Bytes::from_static(b"platanos"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            object_store::Error::Generic { store: STORE, .. }
+        ));
+
+        // A strict prefix of the payload was written.
+        let got = inner.get(&path).await.unwrap().bytes().await.unwrap();
+        assert!(got.len() < b"platanos".len());
+        assert!(b"platanos".starts_with(&got));
+    }
+}
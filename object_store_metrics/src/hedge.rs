@@ -0,0 +1,433 @@
+//! Hedged reads for [`ObjectStore`] implementations, cutting the long tail of
+//! read latencies.
+
+use std::{
+    collections::VecDeque, fmt::Display, future::Future, ops::Range, sync::Arc, time::Duration,
+};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{
+    future::{select, Either},
+    stream::BoxStream,
+    StreamExt,
+};
+use iox_time::TimeProvider;
+use metric::U64Counter;
+use object_store::{
+    path::Path, DynObjectStore, GetResult, ListResult, MultipartId, ObjectMeta, ObjectStore, Result,
+};
+use parking_lot::Mutex;
+use tokio::io::AsyncWrite;
+
+/// Number of recent read latencies the hedge delay is computed from.
+const LATENCY_WINDOW: usize = 1_000;
+
+/// Number of read latencies required before reads are hedged.
+const MIN_SAMPLES: usize = 100;
+
+/// When a [`HedgedObjectStore`] hedges reads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HedgeConfig {
+    percentile: f64,
+    min_delay: Duration,
+}
+
+impl HedgeConfig {
+    /// Hedge reads that take longer than the `percentile` of recent read
+    /// latencies, e.g. `0.95` hedges the slowest 5% of reads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `percentile` is not within `(0, 1)`.
+    pub fn new(percentile: f64) -> Self {
+        assert!(
+            percentile > 0.0 && percentile < 1.0,
+            "hedge percentile must be within (0, 1)"
+        );
+
+        Self {
+            percentile,
+            min_delay: Duration::ZERO,
+        }
+    }
+
+    /// Never hedge reads before they took `min_delay`, bounding the number of
+    /// duplicate requests when the store is fast.
+    pub fn with_min_delay(self, min_delay: Duration) -> Self {
+        Self { min_delay, ..self }
+    }
+}
+
+/// Recent latencies of one kind of read.
+#[derive(Debug, Default)]
+struct LatencyWindow {
+    latencies: Mutex<VecDeque<Duration>>,
+}
+
+impl LatencyWindow {
+    fn record(&self, latency: Duration) {
+        let mut latencies = self.latencies.lock();
+        if latencies.len() == LATENCY_WINDOW {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    /// The `percentile` of the recorded latencies, or [`None`] if too few
+    /// latencies were recorded.
+    fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut latencies: Vec<_> = {
+            let latencies = self.latencies.lock();
+            if latencies.len() < MIN_SAMPLES {
+                return None;
+            }
+            latencies.iter().copied().collect()
+        };
+
+        let idx = ((latencies.len() as f64 * percentile) as usize).min(latencies.len() - 1);
+        let (_, latency, _) = latencies.select_nth_unstable(idx);
+        Some(*latency)
+    }
+}
+
+/// Hedging state and metrics of one kind of read.
+#[derive(Debug)]
+struct HedgedOp {
+    latencies: LatencyWindow,
+    primary_wins: U64Counter,
+    hedge_wins: U64Counter,
+}
+
+impl HedgedOp {
+    fn new(registry: &metric::Registry, op: &'static str) -> Self {
+        let hedged = registry.register_metric::<U64Counter>(
+            "object_store_hedged_requests",
+            "Number of object store reads that issued a duplicate request, by the request that completed first",
+        );
+
+        Self {
+            latencies: Default::default(),
+            primary_wins: hedged.recorder(&[("op", op), ("winner", "primary")]),
+            hedge_wins: hedged.recorder(&[("op", op), ("winner", "hedge")]),
+        }
+    }
+}
+
+/// An [`ObjectStore`] decorator that hedges slow reads.
+///
+/// A [`ObjectStore::get()`] or [`ObjectStore::get_range()`] that has not
+/// completed after the configured percentile of recent read latencies issues a
+/// second, identical request to the inner store. The result of whichever
+/// request succeeds first is returned and the other request is dropped. If
+/// the first request to complete fails, the result of the other one is
+/// returned instead.
+///
+/// Reads are not hedged until enough latencies were observed to estimate the
+/// percentile.
+///
+/// Gets are only complete once their entire payload was read, so hedged gets
+/// buffer the payload and return it as a single chunk.
+///
+/// All other operations are passed through unchanged.
+#[derive(Debug)]
+pub struct HedgedObjectStore {
+    inner: Arc<DynObjectStore>,
+    config: HedgeConfig,
+    time_provider: Arc<dyn TimeProvider>,
+    get: HedgedOp,
+    get_range: HedgedOp,
+}
+
+impl HedgedObjectStore {
+    /// Wrap `inner`, hedging reads as described by `config`.
+    pub fn new(
+        inner: Arc<DynObjectStore>,
+        config: HedgeConfig,
+        time_provider: Arc<dyn TimeProvider>,
+        registry: &metric::Registry,
+    ) -> Self {
+        Self {
+            inner,
+            config,
+            time_provider,
+            get: HedgedOp::new(registry, "get"),
+            get_range: HedgedOp::new(registry, "get_range"),
+        }
+    }
+
+    /// Run the read issued by `f`, issuing it a second time if the first read
+    /// is slow.
+    async fn hedged<F, Fut, T>(&self, op: &HedgedOp, f: F) -> Result<T>
+    where
+        F: Fn() -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T>> + Send,
+        T: Send,
+    {
+        let t_start = self.time_provider.now();
+        let delay = op
+            .latencies
+            .percentile(self.config.percentile)
+            .map(|delay| delay.max(self.config.min_delay));
+
+        let primary = Box::pin(f());
+        let res = match delay {
+            None => primary.await,
+            Some(delay) => match select(primary, self.time_provider.sleep(delay)).await {
+                Either::Left((res, _sleep)) => res,
+                Either::Right(((), primary)) => {
+                    let hedge = Box::pin(f());
+                    match select(primary, hedge).await {
+                        Either::Left((Ok(v), _hedge)) => {
+                            op.primary_wins.inc(1);
+                            Ok(v)
+                        }
+                        Either::Right((Ok(v), _primary)) => {
+                            op.hedge_wins.inc(1);
+                            Ok(v)
+                        }
+                        Either::Left((Err(_), hedge)) => {
+                            op.hedge_wins.inc(1);
+                            hedge.await
+                        }
+                        Either::Right((Err(_), primary)) => {
+                            op.primary_wins.inc(1);
+                            primary.await
+                        }
+                    }
+                }
+            },
+        };
+
+        if res.is_ok() {
+            if let Some(latency) = self.time_provider.now().checked_duration_since(t_start) {
+                op.latencies.record(latency);
+            }
+        }
+
+        res
+    }
+}
+
+impl Display for HedgedObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Hedged({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for HedgedObjectStore {
+    async fn put(&self, location: &Path, bytes: Bytes) -> Result<()> {
+        self.inner.put(location, bytes).await
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        self.inner.put_multipart(location).await
+    }
+
+    async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+        self.inner.abort_multipart(location, multipart_id).await
+    }
+
+    async fn get(&self, location: &Path) -> Result<GetResult> {
+        let bytes = self
+            .hedged(&self.get, || async {
+                self.inner.get(location).await?.bytes().await
+            })
+            .await?;
+
+        Ok(GetResult::Stream(
+            futures::stream::once(async { Ok(bytes) }).boxed(),
+        ))
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.hedged(&self.get_range, || {
+            self.inner.get_range(location, range.clone())
+        })
+        .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    async fn list(&self, prefix: Option<&Path>) -> Result<BoxStream<'_, Result<ObjectMeta>>> {
+        self.inner.list(prefix).await
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iox_time::{MockProvider, Time};
+    use metric::{Attributes, Metric};
+    use object_store::memory::InMemory;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Store whose first `get_range` hangs until `time_provider` passed
+    /// `stall` while all later calls complete immediately.
+    #[derive(Debug)]
+    struct StallingStore {
+        inner: InMemory,
+        time_provider: Arc<MockProvider>,
+        stall: Duration,
+        calls: AtomicUsize,
+        stall_call: AtomicUsize,
+    }
+
+    impl Display for StallingStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Stalling")
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for StallingStore {
+        async fn put(&self, location: &Path, bytes: Bytes) -> Result<()> {
+            self.inner.put(location, bytes).await
+        }
+
+        async fn put_multipart(
+            &self,
+            location: &Path,
+        ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+            self.inner.put_multipart(location).await
+        }
+
+        async fn abort_multipart(&self, location: &Path, multipart_id: &MultipartId) -> Result<()> {
+            self.inner.abort_multipart(location, multipart_id).await
+        }
+
+        async fn get(&self, location: &Path) -> Result<GetResult> {
+            self.inner.get(location).await
+        }
+
+        async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call == self.stall_call.load(Ordering::SeqCst) {
+                self.time_provider.sleep(self.stall).await;
+            }
+            self.inner.get_range(location, range).await
+        }
+
+        async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+            self.inner.head(location).await
+        }
+
+        async fn delete(&self, location: &Path) -> Result<()> {
+            self.inner.delete(location).await
+        }
+
+        async fn list(&self, prefix: Option<&Path>) -> Result<BoxStream<'_, Result<ObjectMeta>>> {
+            self.inner.list(prefix).await
+        }
+
+        async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+
+    fn hedged_requests(registry: &metric::Registry, op: &'static str, winner: &'static str) -> u64 {
+        registry
+            .get_instrument::<Metric<U64Counter>>("object_store_hedged_requests")
+            .unwrap()
+            .get_observer(&Attributes::from(&[("op", op), ("winner", winner)]))
+            .unwrap()
+            .fetch()
+    }
+
+    #[test]
+    fn test_latency_percentile() {
+        let window = LatencyWindow::default();
+        for ms in 1..MIN_SAMPLES as u64 {
+            window.record(Duration::from_millis(ms));
+        }
+        assert_eq!(window.percentile(0.9), None);
+
+        window.record(Duration::from_millis(MIN_SAMPLES as u64));
+        assert_eq!(window.percentile(0.9), Some(Duration::from_millis(91)));
+    }
+
+    #[tokio::test]
+    async fn test_get_passes_through() {
+        let registry = metric::Registry::default();
+        let store = HedgedObjectStore::new(
+            Arc::new(InMemory::new()),
+            HedgeConfig::new(0.5),
+            Arc::new(MockProvider::new(Time::from_timestamp_nanos(0))),
+            &registry,
+        );
+        let path = Path::from("bananas");
+
+        store.put(&path, Bytes::from_static(b"42")).await.unwrap();
+        let got = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(got.as_ref(), b"42");
+        assert!(store.get(&Path::from("missing")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slow_read_is_hedged() {
+        let registry = metric::Registry::default();
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let inner = Arc::new(StallingStore {
+            inner: InMemory::new(),
+            time_provider: Arc::clone(&time_provider),
+            stall: Duration::from_secs(60),
+            calls: AtomicUsize::new(0),
+            stall_call: AtomicUsize::new(usize::MAX),
+        });
+        let store = HedgedObjectStore::new(
+            Arc::clone(&inner) as _,
+            HedgeConfig::new(0.5).with_min_delay(Duration::from_secs(1)),
+            Arc::clone(&time_provider) as _,
+            &registry,
+        );
+        let path = Path::from("bananas");
+        store.put(&path, Bytes::from_static(b"42")).await.unwrap();
+
+        // warm up the latency window with instant reads
+        for _ in 0..MIN_SAMPLES {
+            store.get_range(&path, 0..1).await.unwrap();
+        }
+
+        // the next read stalls and is hedged once the minimum delay passed
+        inner
+            .stall_call
+            .store(inner.calls.load(Ordering::SeqCst), Ordering::SeqCst);
+        let mut read = Box::pin(store.get_range(&path, 0..2));
+        assert!(futures::poll!(&mut read).is_pending());
+
+        time_provider.inc(Duration::from_secs(1));
+        assert_eq!(read.await.unwrap().as_ref(), b"42");
+        assert_eq!(hedged_requests(&registry, "get_range", "hedge"), 1);
+        assert_eq!(hedged_requests(&registry, "get_range", "primary"), 0);
+    }
+}
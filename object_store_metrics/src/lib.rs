@@ -22,6 +22,8 @@ use tokio::io::AsyncWrite;
 
 #[cfg(test)]
 mod dummy;
+pub mod fault;
+pub mod hedge;
 
 /// An instrumentation decorator, wrapping an underlying [`ObjectStore`]
 /// implementation and recording bytes transferred and call latency.
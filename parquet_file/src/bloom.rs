@@ -0,0 +1,435 @@
+//! Bloom filters of the tag columns of parquet files.
+//!
+//! The parquet writer in use can not write the bloom filters of the parquet format, so the filters
+//! of the tag columns listed in [`ParquetWriterOptions::bloom_filter_columns`] are stored in the
+//! key-value metadata of the file footer instead, as base64 encoded bitset under the key returned
+//! by [`bloom_filter_key`]. They use the split block layout and the XXH64 hash of the plain encoded
+//! values of the parquet format, so the same filters can move into the file body once the writer
+//! supports it.
+//!
+//! A filter tells whether a file may contain a value of its column: false positives are possible,
+//! false negatives are not. The querier uses them to skip files that can not match an equality
+//! predicate on the column.
+//!
+//! [`ParquetWriterOptions::bloom_filter_columns`]: crate::serialize::ParquetWriterOptions::bloom_filter_columns
+
+use std::{collections::HashSet, sync::Arc};
+
+use arrow::{
+    array::{as_dictionary_array, as_string_array, Array, StringArray},
+    datatypes::{DataType, Int32Type, Schema as ArrowSchema},
+    record_batch::RecordBatch,
+};
+use parquet::file::metadata::{FileMetaData, KeyValue};
+use schema::{InfluxColumnType, Schema};
+
+/// Prefix of the keys of the bloom filters in the key-value metadata of a parquet file.
+pub const BLOOM_FILTER_KEY_PREFIX: &str = "IOX:bloom_filter:";
+
+/// Targeted false positive probability of the written filters.
+pub const BLOOM_FILTER_FPP: f64 = 0.01;
+
+/// Maximum size in bytes of a written filter.
+///
+/// Columns with more distinct values than fit get a filter with a higher false positive
+/// probability.
+pub const BLOOM_FILTER_MAX_BYTES: usize = 1024 * 1024;
+
+/// Size in bytes of a block of a split block bloom filter.
+const BLOCK_BYTES: usize = 32;
+
+/// Salts of the bit positions set within a block, as defined by the parquet format.
+const SALT: [u32; 8] = [
+    0x47b6137b, 0x44974d91, 0x8824ad5b, 0xa2b7289d, 0x705495c7, 0x2df1424b, 0x9efc4947, 0x5c6bfb31,
+];
+
+/// Returns the key of the bloom filter of `column` in the key-value metadata of a parquet file.
+pub fn bloom_filter_key(column: &str) -> String {
+    format!("{}{}", BLOOM_FILTER_KEY_PREFIX, column)
+}
+
+/// A split block bloom filter over string values, see the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    blocks: Vec<[u32; 8]>,
+}
+
+impl BloomFilter {
+    /// Create an empty filter sized for `ndv` distinct values at [`BLOOM_FILTER_FPP`], capped at
+    /// [`BLOOM_FILTER_MAX_BYTES`].
+    pub fn with_ndv(ndv: usize) -> Self {
+        let num_bits = -8.0 * ndv as f64 / (1.0 - BLOOM_FILTER_FPP.powf(1.0 / 8.0)).ln();
+        let num_bytes = ((num_bits / 8.0).ceil() as usize)
+            .clamp(BLOCK_BYTES, BLOOM_FILTER_MAX_BYTES)
+            .next_power_of_two();
+
+        Self {
+            blocks: vec![[0; 8]; num_bytes / BLOCK_BYTES],
+        }
+    }
+
+    /// Decode a filter from its bitset, as returned by [`to_bytes`](Self::to_bytes).
+    ///
+    /// Returns [`None`] if `bytes` is not a bitset of whole blocks.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.is_empty() || bytes.len() % BLOCK_BYTES != 0 {
+            return None;
+        }
+
+        let blocks = bytes
+            .chunks_exact(BLOCK_BYTES)
+            .map(|block| {
+                let mut words = [0; 8];
+                for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+                    *word = u32::from_le_bytes(bytes.try_into().expect("4 bytes"));
+                }
+                words
+            })
+            .collect();
+        Some(Self { blocks })
+    }
+
+    /// Read the filter of `column` from the key-value metadata of a parquet file, if it has one.
+    pub fn from_metadata(metadata: &FileMetaData, column: &str) -> Option<Self> {
+        let key = bloom_filter_key(column);
+        let value = metadata
+            .key_value_metadata()?
+            .iter()
+            .find(|kv| kv.key == key)?
+            .value
+            .as_ref()?;
+        Self::from_bytes(&base64::decode(value).ok()?)
+    }
+
+    /// Encode the bitset of the filter, i.e. its blocks as little endian words.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.blocks
+            .iter()
+            .flatten()
+            .flat_map(|word| word.to_le_bytes())
+            .collect()
+    }
+
+    /// Add `value` to the filter.
+    pub fn insert(&mut self, value: &str) {
+        self.insert_hash(xxh64(value.as_bytes()));
+    }
+
+    /// Whether the filter may contain `value`.
+    pub fn contains(&self, value: &str) -> bool {
+        self.contains_hash(xxh64(value.as_bytes()))
+    }
+
+    /// Size of the bitset of the filter in bytes.
+    pub fn size(&self) -> usize {
+        self.blocks.len() * BLOCK_BYTES
+    }
+
+    fn insert_hash(&mut self, hash: u64) {
+        let idx = self.block_index(hash);
+        let mask = mask(hash as u32);
+        for (word, mask) in self.blocks[idx].iter_mut().zip(mask) {
+            *word |= mask;
+        }
+    }
+
+    fn contains_hash(&self, hash: u64) -> bool {
+        let idx = self.block_index(hash);
+        let mask = mask(hash as u32);
+        self.blocks[idx]
+            .iter()
+            .zip(mask)
+            .all(|(word, mask)| word & mask != 0)
+    }
+
+    fn block_index(&self, hash: u64) -> usize {
+        (((hash >> 32) * self.blocks.len() as u64) >> 32) as usize
+    }
+}
+
+/// Returns the bit set in each word of a block for the lower 32 bits of a hash.
+fn mask(key: u32) -> [u32; 8] {
+    let mut mask = [0; 8];
+    for (bit, salt) in mask.iter_mut().zip(SALT) {
+        *bit = 1 << (key.wrapping_mul(salt) >> 27);
+    }
+    mask
+}
+
+/// Collects the distinct values of tag columns over the record batches of a parquet file and
+/// builds their bloom filters.
+#[derive(Debug)]
+pub(crate) struct BloomFilterBuilder {
+    /// Name, index and hashes of the distinct values of each column.
+    columns: Vec<(String, usize, HashSet<u64>)>,
+}
+
+impl BloomFilterBuilder {
+    /// Create a builder for the tag columns of `schema` listed in `columns`.
+    ///
+    /// Other columns are ignored.
+    pub(crate) fn new(schema: &Arc<ArrowSchema>, columns: &[String]) -> Self {
+        let columns = tag_columns(schema, columns)
+            .into_iter()
+            .map(|(name, idx)| (name, idx, HashSet::new()))
+            .collect();
+
+        Self { columns }
+    }
+
+    /// Add the values of `batch`.
+    pub(crate) fn update(&mut self, batch: &RecordBatch) {
+        for (_, idx, hashes) in &mut self.columns {
+            for_each_value(batch.column(*idx).as_ref(), |value| {
+                hashes.insert(xxh64(value.as_bytes()));
+            });
+        }
+    }
+
+    /// Build the filters of the columns that had values, as key-value metadata entries.
+    pub(crate) fn finish(self) -> Vec<KeyValue> {
+        self.columns
+            .into_iter()
+            .filter(|(_, _, hashes)| !hashes.is_empty())
+            .map(|(name, _, hashes)| {
+                let mut filter = BloomFilter::with_ndv(hashes.len());
+                for hash in hashes {
+                    filter.insert_hash(hash);
+                }
+                KeyValue {
+                    key: bloom_filter_key(&name),
+                    value: Some(base64::encode(filter.to_bytes())),
+                }
+            })
+            .collect()
+    }
+}
+
+const PRIME64_1: u64 = 0x9E3779B185EBCA87;
+const PRIME64_2: u64 = 0xC2B2AE3D27D4EB4F;
+const PRIME64_3: u64 = 0x165667B19E3779F9;
+const PRIME64_4: u64 = 0x85EBCA77C2B2AE63;
+const PRIME64_5: u64 = 0x27D4EB2F165667C5;
+
+/// Returns the names and indexes of the tag columns of `schema` that are listed in `columns`.
+pub(crate) fn tag_columns(schema: &Arc<ArrowSchema>, columns: &[String]) -> Vec<(String, usize)> {
+    match Schema::try_from(Arc::clone(schema)) {
+        Ok(schema) => columns
+            .iter()
+            .filter_map(|name| {
+                let idx = schema.find_index_of(name)?;
+                match schema.field(idx).0 {
+                    InfluxColumnType::Tag => Some((name.clone(), idx)),
+                    _ => None,
+                }
+            })
+            .collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Calls `f` with the non-null values of a string or string dictionary `array`.
+///
+/// For dictionaries, `f` is called once for each value that the keys reference.
+pub(crate) fn for_each_value(array: &dyn Array, mut f: impl FnMut(&str)) {
+    match array.data_type() {
+        DataType::Dictionary(key, _) if key.as_ref() == &DataType::Int32 => {
+            let dictionary = as_dictionary_array::<Int32Type>(array);
+            let values = match dictionary.values().as_any().downcast_ref::<StringArray>() {
+                Some(values) => values,
+                None => return,
+            };
+
+            let mut seen = vec![false; values.len()];
+            for key in dictionary.keys().iter().flatten() {
+                let key = key as usize;
+                if !seen[key] && values.is_valid(key) {
+                    seen[key] = true;
+                    f(values.value(key));
+                }
+            }
+        }
+        DataType::Utf8 => as_string_array(array).iter().flatten().for_each(f),
+        _ => {}
+    }
+}
+
+/// XXH64 hash of `data` with seed 0, as used by the bloom filters of the parquet format.
+fn xxh64(data: &[u8]) -> u64 {
+    fn round(acc: u64, input: u64) -> u64 {
+        acc.wrapping_add(input.wrapping_mul(PRIME64_2))
+            .rotate_left(31)
+            .wrapping_mul(PRIME64_1)
+    }
+
+    fn merge_round(acc: u64, val: u64) -> u64 {
+        (acc ^ round(0, val))
+            .wrapping_mul(PRIME64_1)
+            .wrapping_add(PRIME64_4)
+    }
+
+    fn read_u64(data: &[u8]) -> u64 {
+        u64::from_le_bytes(data[..8].try_into().expect("8 bytes"))
+    }
+
+    fn read_u32(data: &[u8]) -> u32 {
+        u32::from_le_bytes(data[..4].try_into().expect("4 bytes"))
+    }
+
+    let mut remaining = data;
+    let mut hash = if data.len() >= 32 {
+        let mut v1 = PRIME64_1.wrapping_add(PRIME64_2);
+        let mut v2 = PRIME64_2;
+        let mut v3 = 0;
+        let mut v4 = 0u64.wrapping_sub(PRIME64_1);
+
+        while remaining.len() >= 32 {
+            v1 = round(v1, read_u64(remaining));
+            v2 = round(v2, read_u64(&remaining[8..]));
+            v3 = round(v3, read_u64(&remaining[16..]));
+            v4 = round(v4, read_u64(&remaining[24..]));
+            remaining = &remaining[32..];
+        }
+
+        let hash = v1
+            .rotate_left(1)
+            .wrapping_add(v2.rotate_left(7))
+            .wrapping_add(v3.rotate_left(12))
+            .wrapping_add(v4.rotate_left(18));
+        [v1, v2, v3, v4].into_iter().fold(hash, merge_round)
+    } else {
+        PRIME64_5
+    };
+    hash = hash.wrapping_add(data.len() as u64);
+
+    while remaining.len() >= 8 {
+        hash ^= round(0, read_u64(remaining));
+        hash = hash
+            .rotate_left(27)
+            .wrapping_mul(PRIME64_1)
+            .wrapping_add(PRIME64_4);
+        remaining = &remaining[8..];
+    }
+
+    if remaining.len() >= 4 {
+        hash ^= (read_u32(remaining) as u64).wrapping_mul(PRIME64_1);
+        hash = hash
+            .rotate_left(23)
+            .wrapping_mul(PRIME64_2)
+            .wrapping_add(PRIME64_3);
+        remaining = &remaining[4..];
+    }
+
+    for byte in remaining {
+        hash ^= (*byte as u64).wrapping_mul(PRIME64_5);
+        hash = hash.rotate_left(11).wrapping_mul(PRIME64_1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME64_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME64_3);
+    hash ^ (hash >> 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{ArrayRef, BooleanArray, DictionaryArray, TimestampNanosecondArray},
+        compute::filter_record_batch,
+    };
+    use schema::builder::SchemaBuilder;
+
+    use super::*;
+
+    #[test]
+    fn test_xxh64() {
+        assert_eq!(xxh64(b""), 0xEF46DB3751D8E999);
+        assert_eq!(xxh64(b"abc"), 0x44BC2CF5AD770999);
+    }
+
+    #[test]
+    fn test_filter() {
+        let values: Vec<_> = (0..1_000).map(|i| format!("host-{}", i)).collect();
+
+        let mut filter = BloomFilter::with_ndv(values.len());
+        assert_eq!(filter.size(), 2048);
+        for value in &values {
+            filter.insert(value);
+        }
+        for value in &values {
+            assert!(filter.contains(value));
+        }
+
+        let false_positives = (1_000..11_000)
+            .filter(|i| filter.contains(&format!("host-{}", i)))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+
+        let decoded = BloomFilter::from_bytes(&filter.to_bytes()).unwrap();
+        assert_eq!(decoded, filter);
+    }
+
+    #[test]
+    fn test_filter_size() {
+        assert_eq!(BloomFilter::with_ndv(0).size(), BLOCK_BYTES);
+        assert_eq!(
+            BloomFilter::with_ndv(100_000_000).size(),
+            BLOOM_FILTER_MAX_BYTES
+        );
+
+        assert_eq!(BloomFilter::from_bytes(&[]), None);
+        assert_eq!(BloomFilter::from_bytes(&[0; 33]), None);
+    }
+
+    #[test]
+    fn test_builder() {
+        let schema = SchemaBuilder::new()
+            .tag("dict")
+            .tag("plain")
+            .tag("other")
+            .timestamp()
+            .build()
+            .unwrap()
+            .as_arrow();
+
+        let dict: DictionaryArray<Int32Type> = vec!["a", "unused", "b"].into_iter().collect();
+        let plain = StringArray::from(vec![Some("x"), None, Some("y")]);
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![
+                Arc::new(dict) as ArrayRef,
+                Arc::new(plain),
+                Arc::new(StringArray::from(vec!["o", "o", "o"])),
+                Arc::new(TimestampNanosecondArray::from(vec![1, 2, 3])),
+            ],
+        )
+        .unwrap();
+
+        // the dictionary still holds the value of the filtered row
+        let batch =
+            filter_record_batch(&batch, &BooleanArray::from(vec![true, false, true])).unwrap();
+
+        // `time` is no tag and `missing` does not exist
+        let columns = ["dict", "plain", "time", "missing"].map(String::from);
+        let mut builder = BloomFilterBuilder::new(&schema, &columns);
+        builder.update(&batch);
+        let kvs = builder.finish();
+
+        let keys: Vec<_> = kvs.iter().map(|kv| kv.key.as_str()).collect();
+        assert_eq!(keys, ["IOX:bloom_filter:dict", "IOX:bloom_filter:plain"]);
+
+        let decode = |kv: &KeyValue| {
+            BloomFilter::from_bytes(&base64::decode(kv.value.as_ref().unwrap()).unwrap()).unwrap()
+        };
+
+        let dict = decode(&kvs[0]);
+        assert!(dict.contains("a"));
+        assert!(dict.contains("b"));
+        assert!(!dict.contains("unused"));
+
+        let plain = decode(&kvs[1]);
+        assert!(plain.contains("x"));
+        assert!(plain.contains("y"));
+    }
+}
@@ -86,7 +86,10 @@ impl ParquetChunk {
     /// [`ParquetExec`]: datafusion::physical_plan::file_format::ParquetExec
     pub fn parquet_exec_input(&self) -> ParquetExecInput {
         let path: ParquetFilePath = self.parquet_file.as_ref().into();
-        self.store.parquet_exec_input(&path, self.file_size_bytes())
+        ParquetExecInput {
+            parquet_file_id: Some(self.parquet_file.id),
+            ..self.store.parquet_exec_input(&path, self.file_size_bytes())
+        }
     }
 
     /// The total number of rows in all row groups in this chunk.
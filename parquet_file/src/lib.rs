@@ -15,10 +15,12 @@
 )]
 #![allow(clippy::missing_docs_in_private_items)]
 
+pub mod bloom;
 pub mod chunk;
 pub mod metadata;
 pub mod serialize;
 pub mod storage;
+pub mod tag_index;
 
 use data_types::{NamespaceId, ParquetFile, PartitionId, ShardId, TableId};
 use object_store::path::Path;
@@ -4,20 +4,32 @@
 
 use std::{io::Write, sync::Arc};
 
-use arrow::error::ArrowError;
+use arrow::{datatypes::Schema as ArrowSchema, error::ArrowError};
 use datafusion::physical_plan::SendableRecordBatchStream;
 use datafusion_util::config::BATCH_SIZE;
 use futures::{pin_mut, TryStreamExt};
 use observability_deps::tracing::{debug, trace, warn};
+pub use parquet::basic::Compression;
 use parquet::{
     arrow::ArrowWriter,
-    basic::Compression,
     errors::ParquetError,
-    file::{metadata::KeyValue, properties::WriterProperties},
+    file::{
+        metadata::KeyValue,
+        properties::{EnabledStatistics, WriterProperties},
+    },
+    schema::types::ColumnPath,
 };
+use schema::InfluxColumnType;
 use thiserror::Error;
+use thrift::protocol::{TCompactOutputProtocol, TOutputProtocol};
 
-use crate::metadata::{IoxMetadata, METADATA_KEY};
+use crate::{
+    bloom::BloomFilterBuilder,
+    metadata::{IoxMetadata, METADATA_KEY},
+};
+
+/// Magic bytes at the end of a parquet file.
+const PARQUET_MAGIC: &[u8] = b"PAR1";
 
 /// Parquet row group write size
 pub const ROW_GROUP_WRITE_SIZE: usize = 1024 * 1024;
@@ -27,6 +39,47 @@ pub const ROW_GROUP_WRITE_SIZE: usize = 1024 * 1024;
 #[allow(clippy::assertions_on_constants)]
 const _: () = assert!(ROW_GROUP_WRITE_SIZE % BATCH_SIZE == 0);
 
+/// Options for the parquet files written by [`to_parquet()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParquetWriterOptions {
+    /// Maximum number of rows per row group.
+    ///
+    /// Should be a multiple of [`BATCH_SIZE`] so that reading and writing
+    /// work well together.
+    pub max_row_group_size: usize,
+
+    /// Best effort maximum size of a data page in bytes, or [`None`] to use
+    /// the parquet default.
+    pub data_page_size: Option<usize>,
+
+    /// Compression codec of the column chunks.
+    pub compression: Compression,
+
+    /// Dictionary encode tag columns.
+    pub tag_dictionary: bool,
+
+    /// Dictionary encode field columns.
+    pub field_dictionary: bool,
+
+    /// Tag columns to write [bloom filters](crate::bloom) for.
+    ///
+    /// Only written by [`to_parquet_bytes()`].
+    pub bloom_filter_columns: Vec<String>,
+}
+
+impl Default for ParquetWriterOptions {
+    fn default() -> Self {
+        Self {
+            max_row_group_size: ROW_GROUP_WRITE_SIZE,
+            data_page_size: None,
+            compression: Compression::ZSTD,
+            tag_dictionary: true,
+            field_dictionary: true,
+            bloom_filter_columns: vec![],
+        }
+    }
+}
+
 /// [`RecordBatch`] to Parquet serialisation errors.
 ///
 /// [`RecordBatch`]: arrow::record_batch::RecordBatch
@@ -60,6 +113,10 @@ pub enum CodecError {
     /// Attempting to clone a handle to the provided write sink failed.
     #[error("failed to obtain writer handle clone: {0}")]
     CloneSink(std::io::Error),
+
+    /// Rewriting the footer of the parquet file failed.
+    #[error("failed to write parquet footer: {0}")]
+    Footer(#[from] thrift::Error),
 }
 
 /// An IOx-specific, streaming [`RecordBatch`] to parquet file encoder.
@@ -92,7 +149,22 @@ pub enum CodecError {
 pub async fn to_parquet<W>(
     batches: SendableRecordBatchStream,
     meta: &IoxMetadata,
+    options: &ParquetWriterOptions,
+    sink: W,
+) -> Result<parquet::format::FileMetaData, CodecError>
+where
+    W: Write + Send,
+{
+    encode(batches, meta, options, sink, None).await
+}
+
+/// Implementation of [`to_parquet()`], additionally passing all batches to `bloom_filters`.
+async fn encode<W>(
+    batches: SendableRecordBatchStream,
+    meta: &IoxMetadata,
+    options: &ParquetWriterOptions,
     sink: W,
+    mut bloom_filters: Option<&mut BloomFilterBuilder>,
 ) -> Result<parquet::format::FileMetaData, CodecError>
 where
     W: Write + Send,
@@ -105,7 +177,7 @@ where
     pin_mut!(stream);
 
     // Serialize the IoxMetadata to the protobuf bytes.
-    let props = writer_props(meta)?;
+    let props = writer_props(meta, &schema, options)?;
     let write_batch_size = props.write_batch_size();
     let max_row_group_size = props.max_row_group_size();
 
@@ -115,6 +187,9 @@ where
 
     let mut num_batches = 0;
     while let Some(batch) = stream.try_next().await? {
+        if let Some(bloom_filters) = bloom_filters.as_deref_mut() {
+            bloom_filters.update(&batch);
+        }
         writer.write(&batch)?;
         num_batches += 1;
     }
@@ -139,9 +214,14 @@ where
 
 /// A helper function that calls [`to_parquet()`], serialising the parquet file
 /// into an in-memory buffer and returning the resulting bytes.
+///
+/// The [bloom filters](crate::bloom) of the
+/// [`ParquetWriterOptions::bloom_filter_columns`] are added to the key=value
+/// metadata of the file.
 pub async fn to_parquet_bytes(
     batches: SendableRecordBatchStream,
     meta: &IoxMetadata,
+    options: &ParquetWriterOptions,
 ) -> Result<(Vec<u8>, parquet::format::FileMetaData), CodecError> {
     let mut bytes = vec![];
 
@@ -153,7 +233,14 @@ pub async fn to_parquet_bytes(
     );
 
     // Serialize the record batches into the in-memory buffer
-    let meta = to_parquet(batches, meta, &mut bytes).await?;
+    let mut bloom_filters =
+        BloomFilterBuilder::new(&batches.schema(), &options.bloom_filter_columns);
+    let mut meta = encode(batches, meta, options, &mut bytes, Some(&mut bloom_filters)).await?;
+
+    let bloom_filters = bloom_filters.finish();
+    if !bloom_filters.is_empty() {
+        append_key_value_metadata(&mut bytes, &mut meta, bloom_filters)?;
+    }
     bytes.shrink_to_fit();
 
     trace!(?partition_id, ?meta, "generated parquet file metadata");
@@ -161,17 +248,78 @@ pub async fn to_parquet_bytes(
     Ok((bytes, meta))
 }
 
+/// Add `key_value_metadata` to the footer `meta` of the parquet file in `bytes`
+/// and replace the footer of the file with it.
+///
+/// This is needed for metadata that is only known once all data is written.
+fn append_key_value_metadata(
+    bytes: &mut Vec<u8>,
+    meta: &mut parquet::format::FileMetaData,
+    key_value_metadata: Vec<KeyValue>,
+) -> Result<(), thrift::Error> {
+    // A parquet file ends with the footer, the footer length as 4 byte little
+    // endian integer and the magic bytes.
+    let footer_len_start = bytes.len() - 8;
+    let footer_len = u32::from_le_bytes(
+        bytes[footer_len_start..footer_len_start + 4]
+            .try_into()
+            .expect("4 bytes"),
+    ) as usize;
+    bytes.truncate(footer_len_start - footer_len);
+    let footer_start = bytes.len();
+
+    meta.key_value_metadata
+        .get_or_insert_with(Vec::new)
+        .extend(key_value_metadata);
+    {
+        let mut protocol = TCompactOutputProtocol::new(&mut *bytes);
+        meta.write_to_out_protocol(&mut protocol)?;
+        protocol.flush()?;
+    }
+
+    let footer_len = (bytes.len() - footer_start) as u32;
+    bytes.extend_from_slice(&footer_len.to_le_bytes());
+    bytes.extend_from_slice(PARQUET_MAGIC);
+
+    Ok(())
+}
+
 /// Helper to construct [`WriterProperties`] for the [`ArrowWriter`],
 /// serialising the given [`IoxMetadata`] and embedding it as a key=value
 /// property keyed by [`METADATA_KEY`].
-fn writer_props(meta: &IoxMetadata) -> Result<WriterProperties, prost::EncodeError> {
-    let builder = WriterProperties::builder()
+///
+/// Column statistics are written per page so that readers can prune both row
+/// groups and pages.
+fn writer_props(
+    meta: &IoxMetadata,
+    schema: &Arc<ArrowSchema>,
+    options: &ParquetWriterOptions,
+) -> Result<WriterProperties, prost::EncodeError> {
+    let mut builder = WriterProperties::builder()
         .set_key_value_metadata(Some(vec![KeyValue {
             key: METADATA_KEY.to_string(),
             value: Some(meta.to_base64()?),
         }]))
-        .set_compression(Compression::ZSTD)
-        .set_max_row_group_size(ROW_GROUP_WRITE_SIZE);
+        .set_compression(options.compression)
+        .set_max_row_group_size(options.max_row_group_size)
+        .set_statistics_enabled(EnabledStatistics::Page);
+
+    if let Some(data_page_size) = options.data_page_size {
+        builder = builder.set_data_pagesize_limit(data_page_size);
+    }
+
+    // Columns without IOx metadata keep the default dictionary encoding.
+    if let Ok(schema) = schema::Schema::try_from(Arc::clone(schema)) {
+        for (influx_type, field) in schema.iter() {
+            let dictionary = match influx_type {
+                InfluxColumnType::Tag => options.tag_dictionary,
+                InfluxColumnType::Field(_) => options.field_dictionary,
+                InfluxColumnType::Timestamp => continue,
+            };
+            builder = builder
+                .set_column_dictionary_enabled(ColumnPath::from(field.name().as_str()), dictionary);
+        }
+    }
 
     Ok(builder.build())
 }
@@ -179,9 +327,10 @@ fn writer_props(meta: &IoxMetadata) -> Result<WriterProperties, prost::EncodeErr
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::metadata::IoxParquetMetaData;
+    use crate::{bloom::BloomFilter, metadata::IoxParquetMetaData};
     use arrow::{
-        array::{ArrayRef, StringArray},
+        array::{ArrayRef, DictionaryArray, StringArray, TimestampNanosecondArray},
+        datatypes::Int32Type,
         record_batch::RecordBatch,
     };
     use bytes::Bytes;
@@ -189,29 +338,18 @@ mod tests {
     use datafusion::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
     use datafusion_util::MemoryStream;
     use iox_time::Time;
+    use parquet::format::{CompressionCodec, Encoding};
+    use schema::{builder::SchemaBuilder, InfluxFieldType};
     use std::sync::Arc;
 
     #[tokio::test]
     async fn test_encode_stream() {
-        let meta = IoxMetadata {
-            object_store_id: Default::default(),
-            creation_timestamp: Time::from_timestamp_nanos(42),
-            namespace_id: NamespaceId::new(1),
-            namespace_name: "bananas".into(),
-            shard_id: ShardId::new(2),
-            table_id: TableId::new(3),
-            table_name: "platanos".into(),
-            partition_id: PartitionId::new(4),
-            partition_key: "potato".into(),
-            max_sequence_number: SequenceNumber::new(11),
-            compaction_level: CompactionLevel::FileNonOverlapped,
-            sort_key: None,
-        };
+        let meta = test_meta();
 
         let batch = RecordBatch::try_from_iter([("a", to_string_array(&["value"]))]).unwrap();
         let stream = Box::pin(MemoryStream::new(vec![batch.clone()]));
 
-        let (bytes, _file_meta) = to_parquet_bytes(stream, &meta)
+        let (bytes, _file_meta) = to_parquet_bytes(stream, &meta, &Default::default())
             .await
             .expect("should serialize");
 
@@ -244,6 +382,139 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_writer_options() {
+        let schema = SchemaBuilder::new()
+            .tag("tag")
+            .influx_field("field", InfluxFieldType::String)
+            .timestamp()
+            .build()
+            .unwrap()
+            .as_arrow();
+        let tag: DictionaryArray<Int32Type> = vec!["a", "b", "a", "b", "a"].into_iter().collect();
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(tag),
+                to_string_array(&["x", "x", "x", "y", "y"]),
+                Arc::new(TimestampNanosecondArray::from(vec![1, 2, 3, 4, 5])),
+            ],
+        )
+        .unwrap();
+        let stream = Box::pin(MemoryStream::new(vec![batch]));
+
+        let options = ParquetWriterOptions {
+            max_row_group_size: 2,
+            compression: Compression::SNAPPY,
+            tag_dictionary: false,
+            ..Default::default()
+        };
+        let (_bytes, file_meta) = to_parquet_bytes(stream, &test_meta(), &options)
+            .await
+            .expect("should serialize");
+
+        assert_eq!(file_meta.row_groups.len(), 3);
+        for row_group in &file_meta.row_groups {
+            for column in &row_group.columns {
+                let column_meta = column.meta_data.as_ref().unwrap();
+                assert_eq!(column_meta.codec, CompressionCodec::SNAPPY);
+                assert!(column_meta.statistics.is_some());
+
+                let dictionary_encoded = column_meta
+                    .encodings
+                    .iter()
+                    .any(|e| *e == Encoding::RLE_DICTIONARY || *e == Encoding::PLAIN_DICTIONARY);
+                match column_meta.path_in_schema[0].as_str() {
+                    "tag" => assert!(!dictionary_encoded),
+                    "field" => assert!(dictionary_encoded),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bloom_filters() {
+        let schema = SchemaBuilder::new()
+            .tag("tag")
+            .tag("other")
+            .timestamp()
+            .build()
+            .unwrap()
+            .as_arrow();
+        let tag: DictionaryArray<Int32Type> = vec!["a", "b", "a"].into_iter().collect();
+        let other: DictionaryArray<Int32Type> = vec!["x", "x", "x"].into_iter().collect();
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(tag),
+                Arc::new(other),
+                Arc::new(TimestampNanosecondArray::from(vec![1, 2, 3])),
+            ],
+        )
+        .unwrap();
+        let stream = Box::pin(MemoryStream::new(vec![batch.clone()]));
+
+        let options = ParquetWriterOptions {
+            bloom_filter_columns: vec!["tag".to_string()],
+            ..Default::default()
+        };
+        let (bytes, file_meta) = to_parquet_bytes(stream, &test_meta(), &options)
+            .await
+            .expect("should serialize");
+        let keys: Vec<_> = file_meta
+            .key_value_metadata
+            .unwrap()
+            .into_iter()
+            .map(|kv| kv.key)
+            .collect();
+        assert!(keys.contains(&METADATA_KEY.to_string()));
+        assert!(keys.contains(&"IOX:bloom_filter:tag".to_string()));
+
+        // the rewritten footer is read back, with the IOx metadata and the data
+        let bytes = Bytes::from(bytes);
+        let iox_parquet_meta = IoxParquetMetaData::from_file_bytes(bytes.clone())
+            .expect("should decode")
+            .expect("should contain metadata")
+            .decode()
+            .expect("should decode IOx metadata")
+            .read_iox_metadata_new()
+            .expect("should read IOxMetadata");
+        assert_eq!(iox_parquet_meta, test_meta());
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(bytes).expect("should init builder");
+        let metadata = builder.metadata().file_metadata();
+        let filter = BloomFilter::from_metadata(metadata, "tag").expect("should have filter");
+        assert!(filter.contains("a"));
+        assert!(filter.contains("b"));
+        assert!(!filter.contains("c"));
+        assert_eq!(BloomFilter::from_metadata(metadata, "other"), None);
+
+        let num_rows: usize = builder
+            .build()
+            .expect("should create reader")
+            .map(|batch| batch.expect("should be OK batch").num_rows())
+            .sum();
+        assert_eq!(num_rows, batch.num_rows());
+    }
+
+    fn test_meta() -> IoxMetadata {
+        IoxMetadata {
+            object_store_id: Default::default(),
+            creation_timestamp: Time::from_timestamp_nanos(42),
+            namespace_id: NamespaceId::new(1),
+            namespace_name: "bananas".into(),
+            shard_id: ShardId::new(2),
+            table_id: TableId::new(3),
+            table_name: "platanos".into(),
+            partition_id: PartitionId::new(4),
+            partition_key: "potato".into(),
+            max_sequence_number: SequenceNumber::new(11),
+            compaction_level: CompactionLevel::FileNonOverlapped,
+            sort_key: None,
+        }
+    }
+
     fn to_string_array(strs: &[&str]) -> ArrayRef {
         let array: StringArray = strs.iter().map(|s| Some(*s)).collect();
         Arc::new(array)
@@ -3,7 +3,7 @@
 
 use crate::{
     metadata::{IoxMetadata, IoxParquetMetaData},
-    serialize::{self, CodecError},
+    serialize::{self, CodecError, ParquetWriterOptions},
     ParquetFilePath,
 };
 use arrow::{
@@ -11,6 +11,7 @@ use arrow::{
     record_batch::RecordBatch,
 };
 use bytes::Bytes;
+use data_types::ParquetFileId;
 use datafusion::{
     datasource::{listing::PartitionedFile, object_store::ObjectStoreUrl},
     error::DataFusionError,
@@ -87,6 +88,11 @@ pub struct ParquetExecInput {
 
     /// Object metadata.
     pub object_meta: ObjectMeta,
+
+    /// Catalog ID of the file, if known.
+    ///
+    /// This allows readers to look up cached metadata of the file.
+    pub parquet_file_id: Option<ParquetFileId>,
 }
 
 impl ParquetExecInput {
@@ -155,13 +161,28 @@ pub struct ParquetStorage {
 
     /// Storage ID to hook it into DataFusion.
     id: StorageId,
+
+    /// Options for the parquet files written by [`Self::upload()`].
+    writer_options: ParquetWriterOptions,
 }
 
 impl ParquetStorage {
     /// Initialise a new [`ParquetStorage`] using `object_store` as the
     /// persistence layer.
     pub fn new(object_store: Arc<DynObjectStore>, id: StorageId) -> Self {
-        Self { object_store, id }
+        Self {
+            object_store,
+            id,
+            writer_options: ParquetWriterOptions::default(),
+        }
+    }
+
+    /// Use `writer_options` for the parquet files written by [`Self::upload()`].
+    pub fn with_writer_options(self, writer_options: ParquetWriterOptions) -> Self {
+        Self {
+            writer_options,
+            ..self
+        }
     }
 
     /// Get underlying object store.
@@ -211,7 +232,8 @@ impl ParquetStorage {
         //
         // This is not a huge concern, as the resulting parquet files are
         // currently smallish on average.
-        let (data, parquet_file_meta) = serialize::to_parquet_bytes(batches, meta).await?;
+        let (data, parquet_file_meta) =
+            serialize::to_parquet_bytes(batches, meta, &self.writer_options).await?;
 
         // Read the IOx-specific parquet metadata from the file metadata
         let parquet_meta =
@@ -274,6 +296,7 @@ impl ParquetStorage {
                 last_modified: Default::default(),
                 size: file_size,
             },
+            parquet_file_id: None,
         }
     }
 }
@@ -0,0 +1,509 @@
+//! Secondary index from the values of tag columns to the parquet files of a partition.
+//!
+//! The compactor writes the index of a partition next to its parquet files in the object store
+//! and updates it whenever it compacts files of the partition. The querier uses it to skip the
+//! files that hold none of the values a predicate selects, without reading their footers.
+//!
+//! Parquet files are immutable and their catalog IDs are never reused, so an index is correct
+//! for all files it covers, no matter how old it is. Files that it does not cover, e.g. files
+//! written after it or by the ingesters, must never be skipped.
+//!
+//! The garbage collector deletes the index of a partition once the partition has no files left.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    mem::size_of,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use arrow::{datatypes::SchemaRef, error::Result as ArrowResult, record_batch::RecordBatch};
+use bytes::Bytes;
+use data_types::{NamespaceId, ParquetFile, ParquetFileId, PartitionId, ShardId, TableId};
+use datafusion::physical_plan::{RecordBatchStream, SendableRecordBatchStream};
+use futures::{Stream, StreamExt};
+use generated_types::influxdata::iox::ingester::v1 as proto;
+use object_store::{path::Path, DynObjectStore, Error as ObjectStoreError};
+use observability_deps::tracing::warn;
+use parking_lot::Mutex;
+use prost::Message;
+
+use crate::bloom::{for_each_value, tag_columns};
+
+/// Name of the object holding the index, in the directory of the parquet files of a partition.
+pub const TAG_INDEX_FILE_NAME: &str = "tag_index.pb";
+
+/// The distinct values of each indexed tag column of a parquet file.
+pub type TagValues = BTreeMap<String, BTreeSet<String>>;
+
+/// Location of the [`TagIndex`] of a partition within the object store.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct TagIndexPath {
+    namespace_id: NamespaceId,
+    table_id: TableId,
+    shard_id: ShardId,
+    partition_id: PartitionId,
+}
+
+impl TagIndexPath {
+    /// Create the index path of a partition.
+    pub fn new(
+        namespace_id: NamespaceId,
+        table_id: TableId,
+        shard_id: ShardId,
+        partition_id: PartitionId,
+    ) -> Self {
+        Self {
+            namespace_id,
+            table_id,
+            shard_id,
+            partition_id,
+        }
+    }
+
+    /// Get object-store path.
+    pub fn object_store_path(&self) -> Path {
+        let Self {
+            namespace_id,
+            table_id,
+            shard_id,
+            partition_id,
+        } = self;
+
+        Path::from_iter([
+            namespace_id.to_string().as_str(),
+            table_id.to_string().as_str(),
+            shard_id.to_string().as_str(),
+            partition_id.to_string().as_str(),
+            TAG_INDEX_FILE_NAME,
+        ])
+    }
+}
+
+impl From<&ParquetFile> for TagIndexPath {
+    fn from(f: &ParquetFile) -> Self {
+        Self::new(f.namespace_id, f.table_id, f.shard_id, f.partition_id)
+    }
+}
+
+/// The index of a tag column.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct IndexedColumn {
+    /// The files whose values are indexed.
+    files: BTreeSet<ParquetFileId>,
+
+    /// The indexed files holding each value.
+    values: BTreeMap<String, BTreeSet<ParquetFileId>>,
+}
+
+/// Index from the values of tag columns to the parquet files of a partition holding them, see
+/// the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagIndex {
+    columns: BTreeMap<String, IndexedColumn>,
+}
+
+impl TagIndex {
+    /// Index the `values` of a parquet file.
+    ///
+    /// The file is covered for exactly the columns listed in `values`, even if they have no
+    /// values in the file.
+    pub fn add_file(&mut self, file: ParquetFileId, values: TagValues) {
+        for (name, values) in values {
+            let column = self.columns.entry(name).or_default();
+            column.files.insert(file);
+            for value in values {
+                column.values.entry(value).or_default().insert(file);
+            }
+        }
+    }
+
+    /// Remove all files from the index for which `f` returns `false`.
+    pub fn retain_files(&mut self, mut f: impl FnMut(ParquetFileId) -> bool) {
+        for column in self.columns.values_mut() {
+            column.files.retain(|file| f(*file));
+            let files = &column.files;
+            column.values.retain(|_, holding| {
+                holding.retain(|file| files.contains(file));
+                !holding.is_empty()
+            });
+        }
+        self.columns.retain(|_, column| !column.files.is_empty());
+    }
+
+    /// Whether the indexed files are empty.
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    /// Whether a parquet file may hold any of the `values` in `column`, or [`None`] if the index
+    /// does not cover the column of the file.
+    pub fn may_contain(
+        &self,
+        file: ParquetFileId,
+        column: &str,
+        values: &[String],
+    ) -> Option<bool> {
+        let column = self.columns.get(column)?;
+        if !column.files.contains(&file) {
+            return None;
+        }
+
+        Some(values.iter().any(|value| {
+            column
+                .values
+                .get(value)
+                .map(|files| files.contains(&file))
+                .unwrap_or_default()
+        }))
+    }
+
+    /// Estimated memory size in bytes.
+    pub fn size(&self) -> usize {
+        size_of::<Self>()
+            + self
+                .columns
+                .iter()
+                .map(|(name, column)| {
+                    name.capacity()
+                        + size_of::<IndexedColumn>()
+                        + column.files.len() * size_of::<ParquetFileId>()
+                        + column
+                            .values
+                            .iter()
+                            .map(|(value, files)| {
+                                value.capacity() + files.len() * size_of::<ParquetFileId>()
+                            })
+                            .sum::<usize>()
+                })
+                .sum::<usize>()
+    }
+
+    /// Encode the index.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let ids = |files: &BTreeSet<ParquetFileId>| -> Vec<i64> {
+            files.iter().map(|id| id.get()).collect()
+        };
+
+        proto::TagIndex {
+            columns: self
+                .columns
+                .iter()
+                .map(|(name, column)| proto::TagIndexColumn {
+                    name: name.clone(),
+                    parquet_file_ids: ids(&column.files),
+                    values: column
+                        .values
+                        .iter()
+                        .map(|(value, files)| proto::TagIndexValue {
+                            value: value.clone(),
+                            parquet_file_ids: ids(files),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+        .encode_to_vec()
+    }
+
+    /// Decode an index written by [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(data: &[u8]) -> Result<Self, prost::DecodeError> {
+        let ids = |ids: Vec<i64>| -> BTreeSet<ParquetFileId> {
+            ids.into_iter().map(ParquetFileId::new).collect()
+        };
+
+        let columns = proto::TagIndex::decode(data)?
+            .columns
+            .into_iter()
+            .map(|column| {
+                let values = column
+                    .values
+                    .into_iter()
+                    .map(|value| (value.value, ids(value.parquet_file_ids)))
+                    .collect();
+                let column_index = IndexedColumn {
+                    files: ids(column.parquet_file_ids),
+                    values,
+                };
+                (column.name, column_index)
+            })
+            .collect();
+
+        Ok(Self { columns })
+    }
+
+    /// Read the index at `path`, or [`None`] if there is none.
+    ///
+    /// An index that cannot be decoded is logged and treated as missing.
+    pub async fn load(
+        object_store: &DynObjectStore,
+        path: &TagIndexPath,
+    ) -> Result<Option<Self>, ObjectStoreError> {
+        let path = path.object_store_path();
+        let data = match object_store.get(&path).await {
+            Ok(get_result) => get_result.bytes().await?,
+            Err(ObjectStoreError::NotFound { .. }) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        match Self::from_bytes(&data) {
+            Ok(index) => Ok(Some(index)),
+            Err(e) => {
+                warn!(%e, %path, "cannot decode tag index");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Write the index to `path`.
+    pub async fn store(
+        &self,
+        object_store: &DynObjectStore,
+        path: &TagIndexPath,
+    ) -> Result<(), ObjectStoreError> {
+        object_store
+            .put(&path.object_store_path(), Bytes::from(self.to_bytes()))
+            .await
+    }
+}
+
+/// Collects the distinct values of tag columns from record batches.
+#[derive(Debug)]
+pub struct TagValueCollector {
+    /// Name, index and distinct values of each column.
+    columns: Vec<(String, usize, BTreeSet<String>)>,
+}
+
+impl TagValueCollector {
+    /// Create a collector for the tag columns of `schema` listed in `columns`.
+    ///
+    /// Other columns are ignored.
+    pub fn new(schema: &SchemaRef, columns: &[String]) -> Self {
+        let columns = tag_columns(schema, columns)
+            .into_iter()
+            .map(|(name, idx)| (name, idx, BTreeSet::new()))
+            .collect();
+
+        Self { columns }
+    }
+
+    /// Add the values of `batch`.
+    pub fn update(&mut self, batch: &RecordBatch) {
+        for (_, idx, values) in &mut self.columns {
+            for_each_value(batch.column(*idx).as_ref(), |value| {
+                if !values.contains(value) {
+                    values.insert(value.to_string());
+                }
+            });
+        }
+    }
+
+    /// Returns the values collected so far and starts over.
+    pub fn take(&mut self) -> TagValues {
+        self.columns
+            .iter_mut()
+            .map(|(name, _, values)| (name.clone(), std::mem::take(values)))
+            .collect()
+    }
+}
+
+/// Passes the batches of `stream` through unchanged while collecting the values of the tag
+/// columns listed in `columns`.
+///
+/// The values are complete once the returned stream is exhausted.
+pub fn collect_tag_values(
+    stream: SendableRecordBatchStream,
+    columns: &[String],
+) -> (SendableRecordBatchStream, Arc<Mutex<TagValueCollector>>) {
+    let collector = Arc::new(Mutex::new(TagValueCollector::new(
+        &stream.schema(),
+        columns,
+    )));
+    let stream = Box::pin(CollectingStream {
+        inner: stream,
+        collector: Arc::clone(&collector),
+    });
+
+    (stream, collector)
+}
+
+/// Stream returned by [`collect_tag_values`].
+struct CollectingStream {
+    inner: SendableRecordBatchStream,
+    collector: Arc<Mutex<TagValueCollector>>,
+}
+
+impl std::fmt::Debug for CollectingStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CollectingStream")
+            .field("collector", &self.collector)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Stream for CollectingStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let res = self.inner.poll_next_unpin(cx);
+        if let Poll::Ready(Some(Ok(batch))) = &res {
+            self.collector.lock().update(batch);
+        }
+        res
+    }
+}
+
+impl RecordBatchStream for CollectingStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{ArrayRef, DictionaryArray, StringArray, TimestampNanosecondArray};
+    use arrow::datatypes::Int32Type;
+    use datafusion_util::stream_from_batches;
+    use futures::TryStreamExt;
+    use object_store::memory::InMemory;
+    use schema::builder::SchemaBuilder;
+
+    use super::*;
+
+    fn values(columns: &[(&str, &[&str])]) -> TagValues {
+        columns
+            .iter()
+            .map(|(name, values)| {
+                (
+                    name.to_string(),
+                    values.iter().map(|value| value.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_path() {
+        let path = TagIndexPath::new(
+            NamespaceId::new(1),
+            TableId::new(2),
+            ShardId::new(3),
+            PartitionId::new(4),
+        );
+        assert_eq!(path.object_store_path().to_string(), "1/2/3/4/tag_index.pb");
+    }
+
+    #[test]
+    fn test_index() {
+        let f1 = ParquetFileId::new(1);
+        let f2 = ParquetFileId::new(2);
+        let f3 = ParquetFileId::new(3);
+
+        let mut index = TagIndex::default();
+        assert!(index.is_empty());
+        index.add_file(f1, values(&[("host", &["a", "b"]), ("region", &["west"])]));
+        index.add_file(f2, values(&[("host", &["c"])]));
+        index.add_file(f3, values(&[("host", &[])]));
+
+        assert_eq!(index.may_contain(f1, "host", &strings(&["a"])), Some(true));
+        assert_eq!(
+            index.may_contain(f1, "host", &strings(&["c", "b"])),
+            Some(true)
+        );
+        assert_eq!(index.may_contain(f1, "host", &strings(&["c"])), Some(false));
+        assert_eq!(index.may_contain(f2, "host", &strings(&["c"])), Some(true));
+        assert_eq!(index.may_contain(f3, "host", &strings(&["a"])), Some(false));
+
+        // columns and files that are not covered may hold any value
+        assert_eq!(index.may_contain(f2, "region", &strings(&["west"])), None);
+        assert_eq!(index.may_contain(f1, "other", &strings(&["x"])), None);
+        assert_eq!(
+            index.may_contain(ParquetFileId::new(4), "host", &strings(&["a"])),
+            None
+        );
+
+        let decoded = TagIndex::from_bytes(&index.to_bytes()).unwrap();
+        assert_eq!(decoded, index);
+
+        index.retain_files(|file| file != f1);
+        assert_eq!(index.may_contain(f1, "host", &strings(&["a"])), None);
+        assert_eq!(index.may_contain(f2, "host", &strings(&["c"])), Some(true));
+        assert_eq!(index.columns.len(), 1);
+        assert_eq!(index.columns["host"].values.len(), 1);
+
+        index.retain_files(|_| false);
+        assert!(index.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_load_store() {
+        let object_store = InMemory::new();
+        let path = TagIndexPath::new(
+            NamespaceId::new(1),
+            TableId::new(2),
+            ShardId::new(3),
+            PartitionId::new(4),
+        );
+        assert_eq!(TagIndex::load(&object_store, &path).await.unwrap(), None);
+
+        let mut index = TagIndex::default();
+        index.add_file(ParquetFileId::new(1), values(&[("host", &["a"])]));
+        index.store(&object_store, &path).await.unwrap();
+        assert_eq!(
+            TagIndex::load(&object_store, &path).await.unwrap(),
+            Some(index)
+        );
+
+        // broken indexes are treated as missing
+        object_store
+            .put(&path.object_store_path(), Bytes::from_static(b"\xff\xff"))
+            .await
+            .unwrap();
+        assert_eq!(TagIndex::load(&object_store, &path).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_collect_tag_values() {
+        let schema = SchemaBuilder::new()
+            .tag("host")
+            .influx_field("name", schema::InfluxFieldType::String)
+            .timestamp()
+            .build()
+            .unwrap()
+            .as_arrow();
+
+        let batch = |hosts: Vec<Option<&str>>, names: Vec<&str>| {
+            let times: Vec<_> = (0..hosts.len() as i64).collect();
+            let host: DictionaryArray<Int32Type> = hosts.into_iter().collect();
+            RecordBatch::try_new(
+                Arc::clone(&schema),
+                vec![
+                    Arc::new(host) as ArrayRef,
+                    Arc::new(StringArray::from(names)),
+                    Arc::new(TimestampNanosecondArray::from(times)),
+                ],
+            )
+            .unwrap()
+        };
+        let batches = vec![
+            Arc::new(batch(vec![Some("a"), None], vec!["x", "y"])),
+            Arc::new(batch(vec![Some("b"), Some("a")], vec!["x", "z"])),
+        ];
+
+        let (stream, collector) = collect_tag_values(
+            stream_from_batches(Arc::clone(&schema), batches),
+            &strings(&["host", "name", "unknown"]),
+        );
+        let batches: Vec<_> = stream.try_collect().await.unwrap();
+        assert_eq!(batches.len(), 2);
+
+        let mut collector = collector.lock();
+        assert_eq!(collector.take(), values(&[("host", &["a", "b"])]));
+        assert_eq!(collector.take(), values(&[("host", &[])]));
+    }
+}
@@ -1,7 +1,7 @@
 //! Code that can convert between parquet files and line protocol
 
 use datafusion::{
-    arrow::datatypes::SchemaRef as ArrowSchemaRef,
+    arrow::{datatypes::SchemaRef as ArrowSchemaRef, record_batch::RecordBatch},
     config::ConfigOptions,
     datasource::{
         file_format::{parquet::ParquetFormat, FileFormat},
@@ -81,6 +81,17 @@ pub enum Error {
     IO { source: std::io::Error },
 }
 
+/// Converts `batch`, whose columns are described (in order) by `iox_schema`, into line protocol
+/// for measurement `measurement_name`.
+pub fn convert_batch(
+    measurement_name: &str,
+    iox_schema: &Schema,
+    batch: &RecordBatch,
+) -> Result<Vec<u8>, Error> {
+    convert_to_lines(measurement_name, iox_schema, batch)
+        .map_err(|message| Error::Conversion { message })
+}
+
 /// Converts a parquet file that was written by IOx from the local
 /// file system path specified to line protocol and writes those bytes
 /// to `output`, returning the writer on success
@@ -10,17 +10,20 @@ use tokio::runtime::Handle;
 
 use self::{
     namespace::NamespaceCache, object_store::ObjectStoreCache, parquet_file::ParquetFileCache,
-    partition::PartitionCache, processed_tombstones::ProcessedTombstonesCache,
-    projected_schema::ProjectedSchemaCache, ram::RamSize, tombstones::TombstoneCache,
+    parquet_metadata::ParquetMetadataCache, partition::PartitionCache,
+    processed_tombstones::ProcessedTombstonesCache, projected_schema::ProjectedSchemaCache,
+    ram::RamSize, tag_index::TagIndexCache, tombstones::TombstoneCache,
 };
 
 pub mod namespace;
 pub mod object_store;
 pub mod parquet_file;
+pub mod parquet_metadata;
 pub mod partition;
 pub mod processed_tombstones;
 pub mod projected_schema;
 mod ram;
+pub mod tag_index;
 pub mod tombstones;
 
 #[cfg(test)]
@@ -53,6 +56,12 @@ pub struct CatalogCache {
     /// Object store cache.
     object_store_cache: ObjectStoreCache,
 
+    /// Parquet metadata cache.
+    parquet_metadata_cache: Arc<ParquetMetadataCache>,
+
+    /// Tag index cache.
+    tag_index_cache: TagIndexCache,
+
     /// Metric registry
     metric_registry: Arc<metric::Registry>,
 
@@ -176,14 +185,31 @@ impl CatalogCache {
             Arc::clone(&ram_pool_metadata),
             testing,
         );
+        // tag indexes are rewritten, so they must not be read through the object store cache
+        let tag_index_cache = TagIndexCache::new(
+            backoff_config.clone(),
+            Arc::clone(&object_store),
+            Arc::clone(&time_provider),
+            &metric_registry,
+            Arc::clone(&ram_pool_metadata),
+            testing,
+        );
         let object_store_cache = ObjectStoreCache::new(
-            backoff_config,
+            backoff_config.clone(),
             object_store,
             Arc::clone(&time_provider),
             &metric_registry,
             Arc::clone(&ram_pool_data),
             testing,
         );
+        let parquet_metadata_cache = Arc::new(ParquetMetadataCache::new(
+            backoff_config,
+            Arc::clone(object_store_cache.object_store()),
+            Arc::clone(&time_provider),
+            &metric_registry,
+            Arc::clone(&ram_pool_metadata),
+            testing,
+        ));
 
         Self {
             catalog,
@@ -194,6 +220,8 @@ impl CatalogCache {
             tombstone_cache,
             projected_schema_cache,
             object_store_cache,
+            parquet_metadata_cache,
+            tag_index_cache,
             metric_registry,
             time_provider,
         }
@@ -250,6 +278,16 @@ impl CatalogCache {
         &self.object_store_cache
     }
 
+    /// Parquet metadata cache.
+    pub(crate) fn parquet_metadata(&self) -> &Arc<ParquetMetadataCache> {
+        &self.parquet_metadata_cache
+    }
+
+    /// Tag index cache.
+    pub(crate) fn tag_index(&self) -> &TagIndexCache {
+        &self.tag_index_cache
+    }
+
     /// Parquet store that points to the cached object store.
     pub fn parquet_store(&self) -> ParquetStorage {
         ParquetStorage::new(
@@ -13,8 +13,9 @@ use cache_system::{
     loader::{metrics::MetricsLoader, FunctionLoader},
     resource_consumption::FunctionEstimator,
 };
-use data_types::{ColumnId, NamespaceId, NamespaceSchema, TableId, TableSchema};
+use data_types::{ColumnId, NamespaceId, NamespaceSchema, TableId, TableSchema, TopicId};
 use iox_catalog::interface::{get_schema_by_name, Catalog};
+use iox_query::{query_range::QueryRanges, row_ttl::RowTtl};
 use iox_time::TimeProvider;
 use schema::Schema;
 use std::{
@@ -243,6 +244,14 @@ impl From<TableSchema> for CachedTable {
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CachedNamespace {
     pub id: NamespaceId,
+    /// Write buffer topic the writes of the namespace are routed to.
+    pub topic_id: TopicId,
+    /// Schema generation of the namespace, see [`NamespaceSchema::generation`].
+    pub generation: i64,
+    /// Time ranges the queries of the namespace may cover.
+    pub query_ranges: QueryRanges,
+    /// Expiry of the rows of the namespace.
+    pub row_ttl: RowTtl,
     pub tables: HashMap<Arc<str>, Arc<CachedTable>>,
 }
 
@@ -250,6 +259,7 @@ impl CachedNamespace {
     /// RAM-bytes EXCLUDING `self`.
     fn size(&self) -> usize {
         self.tables.capacity() * size_of::<(Arc<str>, Arc<CachedTable>)>()
+            + self.row_ttl.column.as_ref().map_or(0, |c| c.len())
             + self
                 .tables
                 .iter()
@@ -270,7 +280,20 @@ impl From<NamespaceSchema> for CachedNamespace {
             .collect();
         tables.shrink_to_fit();
 
-        Self { id: ns.id, tables }
+        Self {
+            id: ns.id,
+            topic_id: ns.topic_id,
+            generation: ns.generation,
+            query_ranges: QueryRanges {
+                default_range_ns: ns.default_query_range_ns,
+                max_range_ns: ns.max_query_range_ns,
+            },
+            row_ttl: RowTtl {
+                ttl_ns: ns.row_ttl_ns,
+                column: ns.row_ttl_column.map(Arc::from),
+            },
+            tables,
+        }
     }
 }
 
@@ -319,6 +342,10 @@ mod tests {
             .unwrap();
         let expected_ns_1 = CachedNamespace {
             id: ns1.namespace.id,
+            topic_id: ns1.topic.id,
+            generation: 0,
+            query_ranges: QueryRanges::default(),
+            row_ttl: RowTtl::default(),
             tables: HashMap::from([
                 (
                     Arc::from("table1"),
@@ -369,6 +396,10 @@ mod tests {
             .unwrap();
         let expected_ns_2 = CachedNamespace {
             id: ns2.namespace.id,
+            topic_id: ns2.topic.id,
+            generation: 0,
+            query_ranges: QueryRanges::default(),
+            row_ttl: RowTtl::default(),
             tables: HashMap::from([(
                 Arc::from("table1"),
                 Arc::new(CachedTable {
@@ -11,7 +11,7 @@ use cache_system::{
     loader::{metrics::MetricsLoader, FunctionLoader},
     resource_consumption::FunctionEstimator,
 };
-use data_types::{ParquetFile, SequenceNumber, TableId};
+use data_types::{ParquetFile, SequenceNumber, TableId, Timestamp, TimestampRange};
 use iox_catalog::interface::Catalog;
 use iox_time::TimeProvider;
 use observability_deps::tracing::debug;
@@ -23,6 +23,12 @@ use super::ram::RamSize;
 
 const CACHE_ID: &str = "parquet_file";
 
+/// Granularity of the time ranges that files are listed for.
+///
+/// Matches the default (daily) partitioning, so that queries over recent data keep hitting the
+/// same cache entry throughout the day.
+const TIME_BUCKET_NANOS: i64 = 24 * 60 * 60 * 1_000_000_000;
+
 #[derive(Debug, Snafu)]
 #[allow(missing_copy_implementations, missing_docs)]
 pub enum Error {
@@ -32,19 +38,96 @@ pub enum Error {
     },
 }
 
+/// Time range (both ends inclusive) aligned to whole [time buckets](TIME_BUCKET_NANOS).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeBuckets {
+    min: Timestamp,
+    max: Timestamp,
+}
+
+impl TimeBuckets {
+    /// Buckets that cover `range`, or `None` if `range` is (nearly) unbounded.
+    fn covering(range: TimestampRange) -> Option<Self> {
+        if range.contains_nearly_all() {
+            return None;
+        }
+
+        // the end of the range is exclusive
+        let last = (range.end() - 1).max(range.start());
+        let min = range
+            .start()
+            .div_euclid(TIME_BUCKET_NANOS)
+            .checked_mul(TIME_BUCKET_NANOS)
+            .unwrap_or(i64::MIN);
+        let max = (last.div_euclid(TIME_BUCKET_NANOS) + 1)
+            .checked_mul(TIME_BUCKET_NANOS)
+            .map(|end| end - 1)
+            .unwrap_or(i64::MAX);
+
+        Some(Self {
+            min: Timestamp::new(min),
+            max: Timestamp::new(max),
+        })
+    }
+
+    /// Returns true if `file` has data within these buckets.
+    pub fn overlaps(&self, file: &ParquetFile) -> bool {
+        file.min_time <= self.max && file.max_time >= self.min
+    }
+}
+
+/// Returns true if files listed for `listed` include all files for `requested`.
+///
+/// `None` stands for all files of a table.
+fn covers(listed: Option<TimeBuckets>, requested: Option<TimeBuckets>) -> bool {
+    match (listed, requested) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(listed), Some(requested)) => {
+            listed.min <= requested.min && listed.max >= requested.max
+        }
+    }
+}
+
+/// Smallest time range that covers both `a` and `b`.
+fn union(a: Option<TimeBuckets>, b: Option<TimeBuckets>) -> Option<TimeBuckets> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(TimeBuckets {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }),
+        _ => None,
+    }
+}
+
 /// Holds catalog information about a parquet file
 #[derive(Debug)]
 pub struct CachedParquetFiles {
     /// Parquet catalog information
     pub files: Arc<Vec<Arc<ParquetFile>>>,
+
+    /// Time range the files were listed for, `None` if these are all files of the table.
+    pub time_range: Option<TimeBuckets>,
+
+    /// Parquet sequence number that caused this entry to be loaded.
+    ///
+    /// All files up to this sequence number were persisted at that point, even if none of them
+    /// fall into `time_range`.
+    loaded_for: Option<SequenceNumber>,
 }
 
 impl CachedParquetFiles {
-    fn new(parquet_files: Vec<ParquetFile>) -> Self {
+    fn new(
+        parquet_files: Vec<ParquetFile>,
+        time_range: Option<TimeBuckets>,
+        loaded_for: Option<SequenceNumber>,
+    ) -> Self {
         let files: Vec<_> = parquet_files.into_iter().map(Arc::new).collect();
 
         Self {
             files: Arc::new(files),
+            time_range,
+            loaded_for,
         }
     }
 
@@ -80,7 +163,7 @@ type CacheT = Box<
     dyn Cache<
         K = TableId,
         V = Arc<CachedParquetFiles>,
-        GetExtra = ((), Option<Span>),
+        GetExtra = ((Option<TimeBuckets>, Option<SequenceNumber>), Option<Span>),
         PeekExtra = ((), Option<Span>),
     >,
 >;
@@ -106,40 +189,51 @@ impl ParquetFileCache {
         ram_pool: Arc<ResourcePool<RamSize>>,
         testing: bool,
     ) -> Self {
-        let loader = FunctionLoader::new(move |table_id: TableId, _extra: ()| {
-            let catalog = Arc::clone(&catalog);
-            let backoff_config = backoff_config.clone();
-
-            async move {
-                Backoff::new(&backoff_config)
-                    .retry_all_errors("get parquet_files", || async {
-                        // TODO refreshing all parquet files for the
-                        // entire table is likely to be quite wasteful
-                        // for large tables.
-                        //
-                        // Some this code could be more efficeint:
-                        //
-                        // 1. incrementally fetch only NEW parquet
-                        // files that aren't already in the cache
-                        //
-                        // 2. track time ranges needed for queries and
-                        // limit files fetched to what is actually
-                        // needed
-                        let parquet_files: Vec<_> = catalog
-                            .repositories()
-                            .await
-                            .parquet_files()
-                            .list_by_table_not_to_delete(table_id)
-                            .await
-                            .context(CatalogSnafu)?;
-
-                        Ok(Arc::new(CachedParquetFiles::new(parquet_files)))
-                            as std::result::Result<_, Error>
-                    })
-                    .await
-                    .expect("retry forever")
-            }
-        });
+        let loader = FunctionLoader::new(
+            move |table_id: TableId,
+                  (time_range, loaded_for): (Option<TimeBuckets>, Option<SequenceNumber>)| {
+                let catalog = Arc::clone(&catalog);
+                let backoff_config = backoff_config.clone();
+
+                async move {
+                    Backoff::new(&backoff_config)
+                        .retry_all_errors("get parquet_files", || async {
+                            // TODO refreshing all parquet files for the
+                            // entire table (or time range) is likely to
+                            // be quite wasteful for large tables.
+                            //
+                            // This could incrementally fetch only NEW
+                            // parquet files that aren't already in the
+                            // cache
+                            let mut repos = catalog.repositories().await;
+                            let parquet_files: Vec<_> = match time_range {
+                                Some(time_range) => repos
+                                    .parquet_files()
+                                    .list_by_table_and_time_range(
+                                        table_id,
+                                        time_range.min,
+                                        time_range.max,
+                                    )
+                                    .await
+                                    .context(CatalogSnafu)?,
+                                None => repos
+                                    .parquet_files()
+                                    .list_by_table_not_to_delete(table_id)
+                                    .await
+                                    .context(CatalogSnafu)?,
+                            };
+
+                            Ok(Arc::new(CachedParquetFiles::new(
+                                parquet_files,
+                                time_range,
+                                loaded_for,
+                            ))) as std::result::Result<_, Error>
+                        })
+                        .await
+                        .expect("retry forever")
+                }
+            },
+        );
         let loader = Arc::new(MetricsLoader::new(
             loader,
             CACHE_ID,
@@ -178,6 +272,11 @@ impl ParquetFileCache {
 
     /// Get list of cached parquet files, by table id
     ///
+    /// If `time_range` is given, the result may be limited to files that overlap it. Files are
+    /// listed for whole [time buckets](TIME_BUCKET_NANOS) and the time range of the cache entry
+    /// only grows until the entry expires, so that queries over different time ranges of the same
+    /// table do not evict each other.
+    ///
     /// # Expiration
     /// Clear the parquet file cache if the cache does not contain any
     /// files that have the specified `max_parquet_sequence_number`.
@@ -198,45 +297,68 @@ impl ParquetFileCache {
     pub async fn get(
         &self,
         table_id: TableId,
+        time_range: Option<TimestampRange>,
         max_parquet_sequence_number: Option<SequenceNumber>,
         span: Option<Span>,
     ) -> Arc<CachedParquetFiles> {
-        self.remove_if_handle
-            .remove_if_and_get(
-                &self.cache,
-                table_id,
-                |cached_file| {
-                    if let Some(max_parquet_sequence_number) = max_parquet_sequence_number {
-                        let max_cached = cached_file.max_parquet_sequence_number();
-
-                        let expire = if let Some(max_cached) = max_cached {
-                            max_cached < max_parquet_sequence_number
-                        } else {
-                            // a max sequence was provided but there were no
-                            // files in the cache. Means we need to refresh
-                            true
-                        };
-
-                        debug!(
-                            expire,
-                            ?max_cached,
-                            max_parquet_sequence_number = max_parquet_sequence_number.get(),
-                            table_id = table_id.get(),
-                            "expire parquet file cache",
-                        );
-
-                        expire
-                    } else {
-                        false
-                    }
-                },
-                ((), span),
-            )
-            .await
+        let requested = time_range.and_then(TimeBuckets::covering);
+        let is_stale = |cached_file: &CachedParquetFiles| {
+            if let Some(max_parquet_sequence_number) = max_parquet_sequence_number {
+                let max_cached = cached_file
+                    .max_parquet_sequence_number()
+                    .max(cached_file.loaded_for);
+
+                let expire = if let Some(max_cached) = max_cached {
+                    max_cached < max_parquet_sequence_number
+                } else {
+                    // a max sequence was provided but there were no
+                    // files in the cache. Means we need to refresh
+                    true
+                };
+
+                debug!(
+                    expire,
+                    ?max_cached,
+                    max_parquet_sequence_number = max_parquet_sequence_number.get(),
+                    table_id = table_id.get(),
+                    "expire parquet file cache",
+                );
+
+                expire
+            } else {
+                false
+            }
+        };
+
+        loop {
+            // extend the time range of a still valid entry instead of replacing it
+            let time_range = match self.cache.peek(table_id, ((), span.clone())).await {
+                Some(cached_file) if !is_stale(cached_file.as_ref()) => {
+                    union(cached_file.time_range, requested)
+                }
+                _ => requested,
+            };
+
+            let cached_file = self
+                .remove_if_handle
+                .remove_if_and_get(
+                    &self.cache,
+                    table_id,
+                    |cached_file| {
+                        is_stale(cached_file.as_ref()) || !covers(cached_file.time_range, requested)
+                    },
+                    ((time_range, max_parquet_sequence_number), span.clone()),
+                )
+                .await;
+
+            // a concurrent load may have been for a different time range
+            if covers(cached_file.time_range, requested) {
+                return cached_file;
+            }
+        }
     }
 
     /// Mark the entry for table_id as expired (and needs a refresh)
-    #[cfg(test)]
     pub fn expire(&self, table_id: TableId) {
         self.remove_if_handle.remove_if(&table_id, |_| true);
     }
@@ -256,6 +378,7 @@ mod tests {
     use crate::cache::{ram::test_util::test_ram_pool, test_util::assert_histogram_metric_count};
 
     const METRIC_NAME: &str = "parquet_list_by_table_not_to_delete";
+    const RANGE_METRIC_NAME: &str = "parquet_list_by_table_and_time_range";
     const TABLE1_LINE_PROTOCOL: &str = "table1 foo=1 11";
     const TABLE2_LINE_PROTOCOL: &str = "table2 foo=1 11";
 
@@ -266,7 +389,7 @@ mod tests {
         let tfile = partition.create_parquet_file(builder).await;
 
         let cache = make_cache(&catalog);
-        let cached_files = cache.get(table.table.id, None, None).await.vec();
+        let cached_files = cache.get(table.table.id, None, None, None).await.vec();
 
         assert_eq!(cached_files.len(), 1);
         let expected_parquet_file = &tfile.parquet_file;
@@ -274,7 +397,7 @@ mod tests {
 
         // validate a second request doens't result in a catalog request
         assert_histogram_metric_count(&catalog.metric_registry, METRIC_NAME, 1);
-        cache.get(table.table.id, None, None).await;
+        cache.get(table.table.id, None, None, None).await;
         assert_histogram_metric_count(&catalog.metric_registry, METRIC_NAME, 1);
     }
 
@@ -293,12 +416,12 @@ mod tests {
 
         let cache = make_cache(&catalog);
 
-        let cached_files = cache.get(table1.table.id, None, None).await.vec();
+        let cached_files = cache.get(table1.table.id, None, None, None).await.vec();
         assert_eq!(cached_files.len(), 1);
         let expected_parquet_file = &tfile1.parquet_file;
         assert_eq!(cached_files[0].as_ref(), expected_parquet_file);
 
-        let cached_files = cache.get(table2.table.id, None, None).await.vec();
+        let cached_files = cache.get(table2.table.id, None, None, None).await.vec();
         assert_eq!(cached_files.len(), 1);
         let expected_parquet_file = &tfile2.parquet_file;
         assert_eq!(cached_files[0].as_ref(), expected_parquet_file);
@@ -314,7 +437,7 @@ mod tests {
         let different_catalog = TestCatalog::new();
         let cache = make_cache(&different_catalog);
 
-        let cached_files = cache.get(table.table.id, None, None).await.vec();
+        let cached_files = cache.get(table.table.id, None, None, None).await.vec();
         assert!(cached_files.is_empty());
     }
 
@@ -325,19 +448,19 @@ mod tests {
         partition.create_parquet_file(builder).await;
         let table_id = table.table.id;
 
-        let single_file_size = 216;
-        let two_file_size = 400;
+        let single_file_size = 256;
+        let two_file_size = 440;
         assert!(single_file_size < two_file_size);
 
         let cache = make_cache(&catalog);
-        let cached_files = cache.get(table_id, None, None).await;
+        let cached_files = cache.get(table_id, None, None, None).await;
         assert_eq!(cached_files.size(), single_file_size);
 
         // add a second file, and force the cache to find it
         let builder = TestParquetFileBuilder::default().with_line_protocol(TABLE1_LINE_PROTOCOL);
         partition.create_parquet_file(builder).await;
         cache.expire(table_id);
-        let cached_files = cache.get(table_id, None, None).await;
+        let cached_files = cache.get(table_id, None, None, None).await;
         assert_eq!(cached_files.size(), two_file_size);
     }
 
@@ -366,7 +489,7 @@ mod tests {
         let cache = make_cache(&catalog);
         let table_id = table.table.id;
         assert_eq!(
-            cache.get(table_id, None, None).await.ids(),
+            cache.get(table_id, None, None, None).await.ids(),
             ids(&[&tfile1_2, &tfile1_3])
         );
 
@@ -375,7 +498,7 @@ mod tests {
         assert_histogram_metric_count(&catalog.metric_registry, METRIC_NAME, 1);
         assert_eq!(
             cache
-                .get(table_id, Some(sequence_number_2), None)
+                .get(table_id, None, Some(sequence_number_2), None)
                 .await
                 .ids(),
             ids(&[&tfile1_2, &tfile1_3])
@@ -385,7 +508,7 @@ mod tests {
         // simulate request with no sequence number
         // should not expire anything
         assert_eq!(
-            cache.get(table_id, None, None).await.ids(),
+            cache.get(table_id, None, None, None).await.ids(),
             ids(&[&tfile1_2, &tfile1_3])
         );
         assert_histogram_metric_count(&catalog.metric_registry, METRIC_NAME, 1);
@@ -399,7 +522,7 @@ mod tests {
         let tfile1_10 = partition.create_parquet_file(builder).await;
         // cache doesn't have tfile1_10
         assert_eq!(
-            cache.get(table_id, None, None).await.ids(),
+            cache.get(table_id, None, None, None).await.ids(),
             ids(&[&tfile1_2, &tfile1_3])
         );
 
@@ -407,7 +530,7 @@ mod tests {
         // now cache has tfile!_10 (yay!)
         assert_eq!(
             cache
-                .get(table_id, Some(sequence_number_10), None)
+                .get(table_id, None, Some(sequence_number_10), None)
                 .await
                 .ids(),
             ids(&[&tfile1_2, &tfile1_3, &tfile1_10])
@@ -422,15 +545,15 @@ mod tests {
         let table_id = table.table.id;
 
         // no parquet files, sould be none
-        assert!(cache.get(table_id, None, None).await.files.is_empty());
+        assert!(cache.get(table_id, None, None, None).await.files.is_empty());
         assert_histogram_metric_count(&catalog.metric_registry, METRIC_NAME, 1);
 
         // second request should be cached
-        assert!(cache.get(table_id, None, None).await.files.is_empty());
+        assert!(cache.get(table_id, None, None, None).await.files.is_empty());
         assert_histogram_metric_count(&catalog.metric_registry, METRIC_NAME, 1);
 
         // Calls to expire if there is no known persisted file, should still be cached
-        assert!(cache.get(table_id, None, None).await.files.is_empty());
+        assert!(cache.get(table_id, None, None, None).await.files.is_empty());
         assert_histogram_metric_count(&catalog.metric_registry, METRIC_NAME, 1);
 
         // make a new parquet file
@@ -443,13 +566,13 @@ mod tests {
         let tfile = partition.create_parquet_file(builder).await;
 
         // cache is stale
-        assert!(cache.get(table_id, None, None).await.files.is_empty());
+        assert!(cache.get(table_id, None, None, None).await.files.is_empty());
         assert_histogram_metric_count(&catalog.metric_registry, METRIC_NAME, 1);
 
         // Now call to expire with knowledge of new file, will cause a cache refresh
         assert_eq!(
             cache
-                .get(table_id, Some(sequence_number_1), None)
+                .get(table_id, None, Some(sequence_number_1), None)
                 .await
                 .ids(),
             ids(&[&tfile])
@@ -457,6 +580,158 @@ mod tests {
         assert_histogram_metric_count(&catalog.metric_registry, METRIC_NAME, 2);
     }
 
+    #[tokio::test]
+    async fn test_time_range() {
+        let (catalog, table, partition) = make_catalog().await;
+        let table_id = table.table.id;
+        let day = TIME_BUCKET_NANOS;
+
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol(TABLE1_LINE_PROTOCOL)
+            .with_min_time(0)
+            .with_max_time(100);
+        let tfile_day0 = partition.create_parquet_file(builder).await;
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol(TABLE1_LINE_PROTOCOL)
+            .with_min_time(2 * day + 1)
+            .with_max_time(2 * day + 100);
+        let tfile_day2 = partition.create_parquet_file(builder).await;
+
+        let cache = make_cache(&catalog);
+
+        // only the file of the first day is listed
+        let range = TimestampRange::new(10, 20);
+        assert_eq!(
+            cache.get(table_id, Some(range), None, None).await.ids(),
+            ids(&[&tfile_day0])
+        );
+        assert_histogram_metric_count(&catalog.metric_registry, RANGE_METRIC_NAME, 1);
+
+        // other ranges within the same day are served from the cache
+        let range = TimestampRange::new(50, day);
+        assert_eq!(
+            cache.get(table_id, Some(range), None, None).await.ids(),
+            ids(&[&tfile_day0])
+        );
+        assert_histogram_metric_count(&catalog.metric_registry, RANGE_METRIC_NAME, 1);
+
+        // a different day extends the time range of the entry
+        let range = TimestampRange::new(2 * day, 2 * day + 10);
+        let cached_files = cache.get(table_id, Some(range), None, None).await;
+        assert_eq!(cached_files.ids(), ids(&[&tfile_day0, &tfile_day2]));
+        assert_histogram_metric_count(&catalog.metric_registry, RANGE_METRIC_NAME, 2);
+
+        // so both days are now cached
+        let range = TimestampRange::new(10, 20);
+        assert!(Arc::ptr_eq(
+            &cache.get(table_id, Some(range), None, None).await,
+            &cached_files
+        ));
+        assert_histogram_metric_count(&catalog.metric_registry, RANGE_METRIC_NAME, 2);
+
+        // queries without a time range list all files
+        assert_eq!(
+            cache.get(table_id, None, None, None).await.ids(),
+            ids(&[&tfile_day0, &tfile_day2])
+        );
+        assert_histogram_metric_count(&catalog.metric_registry, METRIC_NAME, 1);
+    }
+
+    #[tokio::test]
+    async fn test_time_range_max_persisted_sequence_number() {
+        let (catalog, table, partition) = make_catalog().await;
+        let table_id = table.table.id;
+        let sequence_number_2 = SequenceNumber::new(2);
+        let sequence_number_3 = SequenceNumber::new(3);
+
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol(TABLE1_LINE_PROTOCOL)
+            .with_max_seq(sequence_number_2.get())
+            .with_min_time(0)
+            .with_max_time(100);
+        let tfile = partition.create_parquet_file(builder).await;
+
+        // newer file outside of the queried time range
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol(TABLE1_LINE_PROTOCOL)
+            .with_max_seq(sequence_number_3.get())
+            .with_min_time(2 * TIME_BUCKET_NANOS)
+            .with_max_time(2 * TIME_BUCKET_NANOS + 100);
+        partition.create_parquet_file(builder).await;
+
+        let cache = make_cache(&catalog);
+        let range = Some(TimestampRange::new(10, 20));
+        assert_eq!(
+            cache
+                .get(table_id, range, Some(sequence_number_3), None)
+                .await
+                .ids(),
+            ids(&[&tfile])
+        );
+        assert_histogram_metric_count(&catalog.metric_registry, RANGE_METRIC_NAME, 1);
+
+        // the entry is known to be up-to-date even though it does not contain sequence number 3
+        assert_eq!(
+            cache
+                .get(table_id, range, Some(sequence_number_3), None)
+                .await
+                .ids(),
+            ids(&[&tfile])
+        );
+        assert_histogram_metric_count(&catalog.metric_registry, RANGE_METRIC_NAME, 1);
+    }
+
+    #[test]
+    fn test_time_buckets() {
+        let day = TIME_BUCKET_NANOS;
+
+        assert_eq!(
+            TimeBuckets::covering(TimestampRange::new(10, 20)),
+            Some(TimeBuckets {
+                min: Timestamp::new(0),
+                max: Timestamp::new(day - 1),
+            })
+        );
+        assert_eq!(
+            TimeBuckets::covering(TimestampRange::new(-10, day + 1)),
+            Some(TimeBuckets {
+                min: Timestamp::new(-day),
+                max: Timestamp::new(2 * day - 1),
+            })
+        );
+
+        // exclusive end
+        assert_eq!(
+            TimeBuckets::covering(TimestampRange::new(0, day)),
+            Some(TimeBuckets {
+                min: Timestamp::new(0),
+                max: Timestamp::new(day - 1),
+            })
+        );
+
+        // half-open ranges
+        assert_eq!(
+            TimeBuckets::covering(TimestampRange::new(day, i64::MAX)),
+            Some(TimeBuckets {
+                min: Timestamp::new(day),
+                max: Timestamp::new(i64::MAX),
+            })
+        );
+        assert_eq!(
+            TimeBuckets::covering(TimestampRange::new(i64::MIN, 0)),
+            Some(TimeBuckets {
+                min: Timestamp::new(i64::MIN),
+                max: Timestamp::new(-1),
+            })
+        );
+
+        // all time
+        assert_eq!(
+            TimeBuckets::covering(TimestampRange::new(i64::MIN, i64::MAX)),
+            None
+        );
+    }
+
     fn ids(files: &[&TestParquetFile]) -> HashSet<ParquetFileId> {
         files.iter().map(|f| f.parquet_file.id).collect()
     }
@@ -0,0 +1,426 @@
+//! Cache for decoded parquet file metadata.
+use std::{
+    collections::{HashMap, HashSet},
+    mem::{size_of, size_of_val},
+    sync::{Arc, Weak},
+};
+
+use async_trait::async_trait;
+use backoff::{Backoff, BackoffConfig};
+use cache_system::{
+    backend::policy::{
+        lru::{LruPolicy, ResourcePool},
+        remove_if::{RemoveIfHandle, RemoveIfPolicy},
+        PolicyBackend,
+    },
+    cache::{driver::CacheDriver, metrics::CacheWithMetrics, Cache, CacheGetStatus},
+    loader::{metrics::MetricsLoader, FunctionLoader},
+    resource_consumption::FunctionEstimator,
+};
+use data_types::{ParquetFile, ParquetFileId, TableId};
+use datafusion::error::DataFusionError;
+use iox_query::provider::{fetch_parquet_metadata, ParquetMetadataSource};
+use iox_time::TimeProvider;
+use object_store::{Error as ObjectStoreError, ObjectMeta, ObjectStore};
+use observability_deps::tracing::{debug, warn};
+use parking_lot::Mutex;
+use parquet::file::metadata::{ParquetMetaData, RowGroupMetaData};
+use trace::span::Span;
+
+use super::{parquet_file::CachedParquetFiles, ram::RamSize};
+
+const CACHE_ID: &str = "parquet_metadata";
+
+type CacheT = Box<
+    dyn Cache<
+        K = ParquetFileId,
+        V = Option<Arc<ParquetMetaData>>,
+        GetExtra = (ObjectMeta, Option<Span>),
+        PeekExtra = ((), Option<Span>),
+    >,
+>;
+
+/// Cache for the decoded metadata (i.e. the footer, including the row group statistics) of
+/// parquet files, keyed by the catalog ID of the file.
+///
+/// Queries over the same files therefore neither fetch nor decode the footers again. Files that
+/// do not exist in the object store are cached as `None`.
+///
+/// Entries of files that are no longer listed for their table, i.e. that were marked for deletion
+/// in the catalog, are removed via [`expire_deleted`](Self::expire_deleted).
+#[derive(Debug)]
+pub struct ParquetMetadataCache {
+    cache: CacheT,
+
+    /// Handle that allows clearing entries for existing cache entries
+    remove_if_handle: RemoveIfHandle<ParquetFileId, Option<Arc<ParquetMetaData>>>,
+
+    /// Files of each table as seen by [`expire_deleted`](Self::expire_deleted).
+    table_files: Mutex<HashMap<TableId, TableFiles>>,
+}
+
+/// Files of a table known to [`ParquetMetadataCache::expire_deleted`].
+#[derive(Debug, Default)]
+struct TableFiles {
+    /// The [`CachedParquetFiles`] that were seen last.
+    last_seen: Weak<CachedParquetFiles>,
+
+    /// All files listed so far that were not found to be deleted.
+    files: HashMap<ParquetFileId, Arc<ParquetFile>>,
+}
+
+impl ParquetMetadataCache {
+    /// Create new empty cache.
+    ///
+    /// Metadata is read from `object_store`.
+    pub fn new(
+        backoff_config: BackoffConfig,
+        object_store: Arc<dyn ObjectStore>,
+        time_provider: Arc<dyn TimeProvider>,
+        metric_registry: &metric::Registry,
+        ram_pool: Arc<ResourcePool<RamSize>>,
+        testing: bool,
+    ) -> Self {
+        let loader = FunctionLoader::new(
+            move |parquet_file_id: ParquetFileId, object_meta: ObjectMeta| {
+                let backoff_config = backoff_config.clone();
+                let object_store = Arc::clone(&object_store);
+
+                async move {
+                    Backoff::new(&backoff_config)
+                        .retry_all_errors::<_, _, _, ObjectStoreError>(
+                            "get parquet metadata from object store",
+                            || async {
+                                match fetch_parquet_metadata(object_store.as_ref(), &object_meta)
+                                    .await
+                                {
+                                    Ok(metadata) => Ok(metadata.map(Arc::new)),
+                                    Err(DataFusionError::ObjectStore(e)) => Err(e),
+                                    Err(e) => {
+                                        // retrying does not help for broken files
+                                        warn!(
+                                            %e,
+                                            parquet_file_id=parquet_file_id.get(),
+                                            "cannot decode parquet metadata",
+                                        );
+                                        Ok(None)
+                                    }
+                                }
+                            },
+                        )
+                        .await
+                        .expect("retry forever")
+                }
+            },
+        );
+        let loader = Arc::new(MetricsLoader::new(
+            loader,
+            CACHE_ID,
+            Arc::clone(&time_provider),
+            metric_registry,
+            testing,
+        ));
+
+        let mut backend = PolicyBackend::new(Box::new(HashMap::new()), Arc::clone(&time_provider));
+        let (policy_constructor, remove_if_handle) =
+            RemoveIfPolicy::create_constructor_and_handle(CACHE_ID, metric_registry);
+        backend.add_policy(policy_constructor);
+        backend.add_policy(LruPolicy::new(
+            Arc::clone(&ram_pool),
+            CACHE_ID,
+            Arc::new(FunctionEstimator::new(
+                |k: &ParquetFileId, v: &Option<Arc<ParquetMetaData>>| {
+                    RamSize(
+                        size_of_val(k)
+                            + size_of_val(v)
+                            + v.as_ref().map(|v| metadata_size(v)).unwrap_or_default(),
+                    )
+                },
+            )),
+        ));
+
+        let cache = CacheDriver::new(loader, backend);
+        let cache = Box::new(CacheWithMetrics::new(
+            cache,
+            CACHE_ID,
+            time_provider,
+            metric_registry,
+        ));
+
+        Self {
+            cache,
+            remove_if_handle,
+            table_files: Default::default(),
+        }
+    }
+
+    /// Get the metadata of the parquet file `parquet_file_id` stored at `object_meta`.
+    ///
+    /// Returns `None` if the file does not exist.
+    pub async fn get(
+        &self,
+        parquet_file_id: ParquetFileId,
+        object_meta: ObjectMeta,
+        span: Option<Span>,
+    ) -> Option<Arc<ParquetMetaData>> {
+        self.cache.get(parquet_file_id, (object_meta, span)).await
+    }
+
+    /// Remove the metadata of files of `table_id` that are no longer part of `files`.
+    ///
+    /// This shall be called with the current result of the
+    /// [`ParquetFileCache`](super::parquet_file::ParquetFileCache), which only lists files that
+    /// are not marked for deletion. Files outside of the time range of `files` are kept. Files are
+    /// only compared if `files` changed since the last call for the same table.
+    pub fn expire_deleted(&self, table_id: TableId, files: &Arc<CachedParquetFiles>) {
+        let mut table_files = self.table_files.lock();
+        let table_files = table_files.entry(table_id).or_default();
+        if table_files.last_seen.ptr_eq(&Arc::downgrade(files)) {
+            return;
+        }
+
+        let listed: HashSet<_> = files.files.iter().map(|file| file.id).collect();
+        table_files.files.retain(|id, file| {
+            let covered = files
+                .time_range
+                .map(|time_range| time_range.overlaps(file))
+                .unwrap_or(true);
+            if !covered || listed.contains(id) {
+                return true;
+            }
+
+            debug!(
+                parquet_file_id = id.get(),
+                table_id = table_id.get(),
+                "expire parquet metadata of deleted file",
+            );
+            self.remove_if_handle.remove_if(id, |_| true);
+            false
+        });
+
+        table_files
+            .files
+            .extend(files.files.iter().map(|file| (file.id, Arc::clone(file))));
+        table_files.last_seen = Arc::downgrade(files);
+    }
+}
+
+#[async_trait]
+impl ParquetMetadataSource for ParquetMetadataCache {
+    async fn metadata(
+        &self,
+        id: ParquetFileId,
+        object_meta: &ObjectMeta,
+    ) -> Option<Arc<ParquetMetaData>> {
+        self.get(id, object_meta.clone(), None).await
+    }
+
+    async fn metadata_with_status(
+        &self,
+        id: ParquetFileId,
+        object_meta: &ObjectMeta,
+    ) -> (Option<Arc<ParquetMetaData>>, bool) {
+        let (metadata, status) = self
+            .cache
+            .get_with_status(id, (object_meta.clone(), None))
+            .await;
+        (metadata, status == CacheGetStatus::Hit)
+    }
+}
+
+/// Estimate the memory consumption of decoded parquet metadata, excluding `Self`.
+fn metadata_size(metadata: &ParquetMetaData) -> usize {
+    let row_groups = metadata
+        .row_groups()
+        .iter()
+        .map(|row_group| {
+            size_of::<RowGroupMetaData>()
+                + row_group
+                    .columns()
+                    .iter()
+                    .map(|column| {
+                        size_of_val(column)
+                            + column
+                                .statistics()
+                                .filter(|stats| stats.has_min_max_set())
+                                .map(|stats| stats.min_bytes().len() + stats.max_bytes().len())
+                                .unwrap_or_default()
+                    })
+                    .sum::<usize>()
+        })
+        .sum::<usize>();
+
+    // includes the bloom filters of tag columns
+    let key_value_metadata = metadata
+        .file_metadata()
+        .key_value_metadata()
+        .map(|kvs| {
+            kvs.iter()
+                .map(|kv| {
+                    size_of_val(kv)
+                        + kv.key.capacity()
+                        + kv.value.as_ref().map(|v| v.capacity()).unwrap_or_default()
+                })
+                .sum::<usize>()
+        })
+        .unwrap_or_default();
+
+    size_of::<ParquetMetaData>() + row_groups + key_value_metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_types::{ColumnType, TimestampRange};
+    use iox_tests::util::{TestCatalog, TestParquetFileBuilder};
+    use metric::{Attributes, Metric, U64Counter};
+    use parquet_file::ParquetFilePath;
+
+    use crate::cache::{parquet_file::ParquetFileCache, ram::test_util::test_ram_pool};
+
+    #[tokio::test]
+    async fn test_metadata() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let table = ns.create_table("table").await;
+        table.create_column("foo", ColumnType::F64).await;
+        table.create_column("time", ColumnType::Time).await;
+        let partition = table
+            .with_shard(&ns.create_shard(1).await)
+            .create_partition("k")
+            .await;
+        let builder = TestParquetFileBuilder::default().with_line_protocol("table foo=1 11");
+        let file = partition.create_parquet_file(builder).await.parquet_file;
+
+        let cache = make_cache(&catalog);
+        let object_meta = object_meta(&file);
+
+        let metadata = cache.get(file.id, object_meta.clone(), None).await.unwrap();
+        assert_eq!(metadata.file_metadata().num_rows(), 1);
+        assert_load_count(&catalog, 1);
+
+        // second request is cached
+        let metadata_2 = cache.get(file.id, object_meta.clone(), None).await.unwrap();
+        assert!(Arc::ptr_eq(&metadata, &metadata_2));
+        assert_load_count(&catalog, 1);
+
+        // queries learn whether the metadata was cached
+        let (metadata_3, cached) = cache.metadata_with_status(file.id, &object_meta).await;
+        assert!(Arc::ptr_eq(&metadata, &metadata_3.unwrap()));
+        assert!(cached);
+    }
+
+    #[tokio::test]
+    async fn test_missing_file() {
+        let catalog = TestCatalog::new();
+        let cache = make_cache(&catalog);
+
+        let parquet_file_id = ParquetFileId::new(1);
+        let object_meta = ObjectMeta {
+            location: "missing.parquet".into(),
+            last_modified: Default::default(),
+            size: 100,
+        };
+        assert!(cache
+            .get(parquet_file_id, object_meta.clone(), None)
+            .await
+            .is_none());
+        assert_load_count(&catalog, 1);
+
+        // "not found" is cached as well
+        assert!(cache
+            .get(parquet_file_id, object_meta, None)
+            .await
+            .is_none());
+        assert_load_count(&catalog, 1);
+    }
+
+    #[tokio::test]
+    async fn test_expire_deleted() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let table = ns.create_table("table").await;
+        table.create_column("foo", ColumnType::F64).await;
+        table.create_column("time", ColumnType::Time).await;
+        let partition = table
+            .with_shard(&ns.create_shard(1).await)
+            .create_partition("k")
+            .await;
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("table foo=1 11")
+            .with_min_time(0)
+            .with_max_time(100);
+        let tfile_1 = partition.create_parquet_file(builder.clone()).await;
+        let tfile_2 = partition.create_parquet_file(builder.clone()).await;
+        let day = 24 * 60 * 60 * 1_000_000_000;
+        let builder = builder.with_min_time(2 * day).with_max_time(2 * day + 100);
+        let tfile_3 = partition.create_parquet_file(builder).await;
+        let table_id = table.table.id;
+
+        let cache = make_cache(&catalog);
+        for tfile in [&tfile_1, &tfile_2, &tfile_3] {
+            let file = &tfile.parquet_file;
+            cache.get(file.id, object_meta(file), None).await.unwrap();
+        }
+        assert_load_count(&catalog, 3);
+
+        let file_cache = ParquetFileCache::new(
+            catalog.catalog(),
+            BackoffConfig::default(),
+            catalog.time_provider(),
+            &catalog.metric_registry(),
+            test_ram_pool(),
+            true,
+        );
+        let files = file_cache.get(table_id, None, None, None).await;
+        assert_eq!(files.files.len(), 3);
+        cache.expire_deleted(table_id, &files);
+
+        // the second and third file were marked for deletion, but only the time range of the
+        // first two files is listed again
+        tfile_2.flag_for_delete().await;
+        tfile_3.flag_for_delete().await;
+        file_cache.expire(table_id);
+        let files = file_cache
+            .get(table_id, Some(TimestampRange::new(0, 100)), None, None)
+            .await;
+        assert_eq!(files.files.len(), 1);
+        cache.expire_deleted(table_id, &files);
+
+        for (tfile, load_count) in [(&tfile_1, 3), (&tfile_2, 4), (&tfile_3, 4)] {
+            let file = &tfile.parquet_file;
+            cache.get(file.id, object_meta(file), None).await.unwrap();
+            assert_load_count(&catalog, load_count);
+        }
+    }
+
+    fn make_cache(catalog: &TestCatalog) -> ParquetMetadataCache {
+        ParquetMetadataCache::new(
+            BackoffConfig::default(),
+            catalog.object_store(),
+            catalog.time_provider(),
+            &catalog.metric_registry(),
+            test_ram_pool(),
+            true,
+        )
+    }
+
+    fn object_meta(file: &data_types::ParquetFile) -> ObjectMeta {
+        ObjectMeta {
+            location: ParquetFilePath::from(file).object_store_path(),
+            last_modified: Default::default(),
+            size: file.file_size_bytes as usize,
+        }
+    }
+
+    fn assert_load_count(catalog: &TestCatalog, count: u64) {
+        let actual = catalog
+            .metric_registry()
+            .get_instrument::<Metric<U64Counter>>("cache_load_function_calls")
+            .expect("failed to read metric")
+            .get_observer(&Attributes::from(&[("name", CACHE_ID), ("status", "new")]))
+            .expect("failed to get observer")
+            .fetch();
+        assert_eq!(actual, count);
+    }
+}
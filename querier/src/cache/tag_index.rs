@@ -0,0 +1,210 @@
+//! Cache for the tag indexes of partitions.
+use std::{collections::HashMap, mem::size_of_val, sync::Arc, time::Duration};
+
+use backoff::{Backoff, BackoffConfig};
+use cache_system::{
+    backend::policy::{
+        lru::{LruPolicy, ResourcePool},
+        ttl::{OptionalValueTtlProvider, TtlPolicy},
+        PolicyBackend,
+    },
+    cache::{driver::CacheDriver, metrics::CacheWithMetrics, Cache},
+    loader::{metrics::MetricsLoader, FunctionLoader},
+    resource_consumption::FunctionEstimator,
+};
+use data_types::PartitionId;
+use iox_time::TimeProvider;
+use object_store::{Error as ObjectStoreError, ObjectStore};
+use parquet_file::tag_index::{TagIndex, TagIndexPath};
+use trace::span::Span;
+
+use super::ram::RamSize;
+
+/// Duration to keep the index of a partition.
+///
+/// The compactor rewrites the index whenever it compacts the partition. An outdated index is still
+/// correct, it merely does not cover the latest files.
+pub const TTL_EXISTING: Duration = Duration::from_secs(60);
+
+/// Duration to keep the knowledge that a partition has no index.
+pub const TTL_NON_EXISTING: Duration = Duration::from_secs(60);
+
+const CACHE_ID: &str = "tag_index";
+
+type CacheT = Box<
+    dyn Cache<
+        K = PartitionId,
+        V = Option<Arc<TagIndex>>,
+        GetExtra = (TagIndexPath, Option<Span>),
+        PeekExtra = ((), Option<Span>),
+    >,
+>;
+
+/// Cache for the [tag indexes](parquet_file::tag_index) of partitions, keyed by partition ID.
+///
+/// Partitions without an index are cached as `None`.
+#[derive(Debug)]
+pub struct TagIndexCache {
+    cache: CacheT,
+}
+
+impl TagIndexCache {
+    /// Create new empty cache.
+    ///
+    /// Indexes are read from `object_store`, which must not cache objects since indexes are
+    /// rewritten.
+    pub fn new(
+        backoff_config: BackoffConfig,
+        object_store: Arc<dyn ObjectStore>,
+        time_provider: Arc<dyn TimeProvider>,
+        metric_registry: &metric::Registry,
+        ram_pool: Arc<ResourcePool<RamSize>>,
+        testing: bool,
+    ) -> Self {
+        let loader = FunctionLoader::new(move |_partition_id: PartitionId, path: TagIndexPath| {
+            let backoff_config = backoff_config.clone();
+            let object_store = Arc::clone(&object_store);
+
+            async move {
+                Backoff::new(&backoff_config)
+                    .retry_all_errors::<_, _, _, ObjectStoreError>(
+                        "get tag index from object store",
+                        || async {
+                            Ok(TagIndex::load(object_store.as_ref(), &path)
+                                .await?
+                                .map(Arc::new))
+                        },
+                    )
+                    .await
+                    .expect("retry forever")
+            }
+        });
+        let loader = Arc::new(MetricsLoader::new(
+            loader,
+            CACHE_ID,
+            Arc::clone(&time_provider),
+            metric_registry,
+            testing,
+        ));
+
+        let mut backend = PolicyBackend::new(Box::new(HashMap::new()), Arc::clone(&time_provider));
+        backend.add_policy(TtlPolicy::new(
+            Arc::new(OptionalValueTtlProvider::new(
+                Some(TTL_NON_EXISTING),
+                Some(TTL_EXISTING),
+            )),
+            CACHE_ID,
+            metric_registry,
+        ));
+        backend.add_policy(LruPolicy::new(
+            Arc::clone(&ram_pool),
+            CACHE_ID,
+            Arc::new(FunctionEstimator::new(
+                |k: &PartitionId, v: &Option<Arc<TagIndex>>| {
+                    RamSize(
+                        size_of_val(k)
+                            + size_of_val(v)
+                            + v.as_ref().map(|v| v.size()).unwrap_or_default(),
+                    )
+                },
+            )),
+        ));
+
+        let cache = CacheDriver::new(loader, backend);
+        let cache = Box::new(CacheWithMetrics::new(
+            cache,
+            CACHE_ID,
+            time_provider,
+            metric_registry,
+        ));
+
+        Self { cache }
+    }
+
+    /// Get the index of the partition `partition_id` stored at `path`.
+    ///
+    /// Returns `None` if the partition has no index.
+    pub async fn get(
+        &self,
+        partition_id: PartitionId,
+        path: TagIndexPath,
+        span: Option<Span>,
+    ) -> Option<Arc<TagIndex>> {
+        self.cache.get(partition_id, (path, span)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_types::{NamespaceId, ParquetFileId, ShardId, TableId};
+    use iox_tests::util::TestCatalog;
+    use metric::{Attributes, Metric, U64Counter};
+    use parquet_file::tag_index::TagValues;
+    use std::collections::BTreeSet;
+
+    use crate::cache::ram::test_util::test_ram_pool;
+
+    #[tokio::test]
+    async fn test_tag_index() {
+        let catalog = TestCatalog::new();
+        let cache = TagIndexCache::new(
+            BackoffConfig::default(),
+            catalog.object_store(),
+            catalog.time_provider(),
+            &catalog.metric_registry(),
+            test_ram_pool(),
+            true,
+        );
+
+        let partition_id = PartitionId::new(4);
+        let path = TagIndexPath::new(
+            NamespaceId::new(1),
+            TableId::new(2),
+            ShardId::new(3),
+            partition_id,
+        );
+
+        // a missing index is cached
+        assert!(cache.get(partition_id, path, None).await.is_none());
+        assert_load_count(&catalog, 1);
+
+        let mut index = TagIndex::default();
+        index.add_file(
+            ParquetFileId::new(1),
+            TagValues::from([("host".to_string(), BTreeSet::from(["a".to_string()]))]),
+        );
+        index
+            .store(catalog.object_store().as_ref(), &path)
+            .await
+            .unwrap();
+        assert!(cache.get(partition_id, path, None).await.is_none());
+        assert_load_count(&catalog, 1);
+
+        // ... until it expires
+        catalog.mock_time_provider().inc(TTL_NON_EXISTING);
+        let cached = cache.get(partition_id, path, None).await.unwrap();
+        assert_eq!(cached.as_ref(), &index);
+        assert_load_count(&catalog, 2);
+
+        // so is an existing one
+        let cached_2 = cache.get(partition_id, path, None).await.unwrap();
+        assert!(Arc::ptr_eq(&cached, &cached_2));
+        assert_load_count(&catalog, 2);
+
+        catalog.mock_time_provider().inc(TTL_EXISTING);
+        cache.get(partition_id, path, None).await.unwrap();
+        assert_load_count(&catalog, 3);
+    }
+
+    fn assert_load_count(catalog: &TestCatalog, count: u64) {
+        let actual = catalog
+            .metric_registry()
+            .get_instrument::<Metric<U64Counter>>("cache_load_function_calls")
+            .expect("failed to read metric")
+            .get_observer(&Attributes::from(&[("name", CACHE_ID), ("status", "new")]))
+            .expect("failed to get observer")
+            .fetch();
+        assert_eq!(actual, count);
+    }
+}
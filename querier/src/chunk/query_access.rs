@@ -1,4 +1,5 @@
 use crate::chunk::QuerierChunk;
+use async_trait::async_trait;
 use data_types::{ChunkId, ChunkOrder, DeletePredicate, PartitionId, TableSummary};
 use datafusion::error::DataFusionError;
 use iox_query::{
@@ -35,6 +36,7 @@ impl QueryChunkMeta for QuerierChunk {
     }
 }
 
+#[async_trait]
 impl QueryChunk for QuerierChunk {
     fn id(&self) -> ChunkId {
         self.meta().chunk_id
@@ -44,7 +46,7 @@ impl QueryChunk for QuerierChunk {
         false
     }
 
-    fn column_names(
+    async fn column_names(
         &self,
         mut ctx: IOxSessionContext,
         predicate: &Predicate,
@@ -62,7 +64,7 @@ impl QueryChunk for QuerierChunk {
         Ok(self.parquet_chunk.column_names(columns))
     }
 
-    fn column_values(
+    async fn column_values(
         &self,
         mut ctx: IOxSessionContext,
         column_name: &str,
@@ -0,0 +1,173 @@
+//! Tables whose partitions were all marked cold by the compactor.
+//!
+//! A cold partition did not get writes for a while and was fully compacted, so the ingesters hold
+//! no data for it. Queries against a table whose partitions are all cold can skip the ingesters.
+//! Data written to such a table is only visible once it is persisted, which makes the partition
+//! hot again.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use data_types::{TableId, Timestamp};
+use iox_time::Time;
+use observability_deps::tracing::{debug, warn};
+use parking_lot::Mutex;
+
+use crate::cache::CatalogCache;
+
+/// How long the cold state of a table read from the catalog is used before it is read again.
+pub(crate) const COLD_TABLES_TTL: Duration = Duration::from_secs(10);
+
+/// The cold state of a table, as last read from the catalog.
+#[derive(Debug, Clone, Copy)]
+struct ColdState {
+    read_at: Time,
+
+    /// The latest time one of the partitions of the table was marked cold, if they all are.
+    cold_since: Option<Timestamp>,
+}
+
+/// Cache of the tables whose partitions are all cold, see the [module documentation](self).
+#[derive(Debug)]
+pub struct ColdTables {
+    catalog_cache: Arc<CatalogCache>,
+    tables: Mutex<HashMap<TableId, ColdState>>,
+}
+
+impl ColdTables {
+    /// Create a new cache, reading the partitions from the catalog of `catalog_cache`.
+    pub fn new(catalog_cache: Arc<CatalogCache>) -> Self {
+        Self {
+            catalog_cache,
+            tables: Default::default(),
+        }
+    }
+
+    /// Whether all partitions of the table are cold, reading them from the catalog if they are
+    /// older than [`COLD_TABLES_TTL`].
+    ///
+    /// A table without partitions, or whose partitions can not be read, is not cold. When a table
+    /// becomes cold, its cached parquet files are expired, since the ingesters no longer tell when
+    /// they change.
+    pub(crate) async fn is_cold(&self, table_id: TableId) -> bool {
+        let now = self.catalog_cache.time_provider().now();
+        let previous = self.tables.lock().get(&table_id).copied();
+
+        if let Some(state) = previous {
+            if now < state.read_at + COLD_TABLES_TTL {
+                return state.cold_since.is_some();
+            }
+        }
+
+        let res = self
+            .catalog_cache
+            .catalog()
+            .repositories()
+            .await
+            .partitions()
+            .list_by_table_id(table_id)
+            .await;
+        let partitions = match res {
+            Ok(partitions) => partitions,
+            Err(e) => {
+                warn!(%e, ?table_id, "failed to read the partitions of the table");
+                return false;
+            }
+        };
+
+        let cold_since = if partitions.is_empty() {
+            None
+        } else {
+            partitions
+                .iter()
+                .map(|p| p.cold_compacted_at)
+                .collect::<Option<Vec<_>>>()
+                .and_then(|times| times.into_iter().max())
+        };
+
+        if cold_since.is_some() && cold_since != previous.and_then(|state| state.cold_since) {
+            debug!(?table_id, ?cold_since, "table became cold");
+            self.catalog_cache.parquet_file().expire(table_id);
+        }
+
+        self.tables.lock().insert(
+            table_id,
+            ColdState {
+                read_at: now,
+                cold_since,
+            },
+        );
+        cold_since.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use data_types::{PartitionId, SequenceNumber};
+    use iox_tests::util::TestCatalog;
+    use tokio::runtime::Handle;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_is_cold() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let shard = ns.create_shard(1).await;
+        let table = ns.create_table("table").await;
+        let table_id = table.table.id;
+
+        let catalog_cache = Arc::new(CatalogCache::new_testing(
+            catalog.catalog(),
+            catalog.time_provider(),
+            catalog.metric_registry(),
+            catalog.object_store(),
+            &Handle::current(),
+        ));
+        let cold_tables = ColdTables::new(catalog_cache);
+
+        // a table without partitions is not cold
+        assert!(!cold_tables.is_cold(table_id).await);
+
+        let p1 = table.with_shard(&shard).create_partition("k1").await;
+        let p1 = p1.partition.id;
+        assert!(mark_cold(&catalog, p1).await);
+
+        // the state is cached
+        assert!(!cold_tables.is_cold(table_id).await);
+        catalog.mock_time_provider().inc(COLD_TABLES_TTL);
+        assert!(cold_tables.is_cold(table_id).await);
+
+        // the table is not cold if one of its partitions is not
+        let p2 = table.with_shard(&shard).create_partition("k2").await;
+        let p2 = p2.partition.id;
+        catalog.mock_time_provider().inc(COLD_TABLES_TTL);
+        assert!(!cold_tables.is_cold(table_id).await);
+
+        assert!(mark_cold(&catalog, p2).await);
+        catalog.mock_time_provider().inc(COLD_TABLES_TTL);
+        assert!(cold_tables.is_cold(table_id).await);
+
+        // ... or once data is persisted to it again
+        catalog
+            .catalog
+            .repositories()
+            .await
+            .partitions()
+            .update_persisted_sequence_number(p1, SequenceNumber::new(1))
+            .await
+            .unwrap();
+        catalog.mock_time_provider().inc(COLD_TABLES_TTL);
+        assert!(!cold_tables.is_cold(table_id).await);
+    }
+
+    async fn mark_cold(catalog: &TestCatalog, partition_id: PartitionId) -> bool {
+        catalog
+            .catalog
+            .repositories()
+            .await
+            .partitions()
+            .mark_cold(partition_id, Timestamp::new(1))
+            .await
+            .unwrap()
+    }
+}
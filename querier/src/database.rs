@@ -1,18 +1,27 @@
 //! Database for the querier that contains all namespaces.
 
 use crate::{
-    cache::CatalogCache, chunk::ChunkAdapter, ingester::IngesterConnection,
-    namespace::QuerierNamespace, query_log::QueryLog, table::PruneMetrics,
+    cache::CatalogCache, chunk::ChunkAdapter, cold_tables::ColdTables,
+    external_tables::ExternalTables, ingester::IngesterConnection, namespace::QuerierNamespace,
+    plan_cache::QueryPlanCache, query_log::QueryLog, query_metrics::QueryMetrics,
+    read_policy::ReadPolicies, scan_limit::ScanLimiter, shard_pins::ShardPins, table::PruneMetrics,
+    table_writer::DEFAULT_MAX_REQUEST_SIZE, write_slo::WriteSloLog,
 };
 use async_trait::async_trait;
 use backoff::{Backoff, BackoffConfig};
-use data_types::{Namespace, ShardIndex};
-use iox_catalog::interface::Catalog;
-use iox_query::exec::Executor;
+use data_types::{Namespace, ShardIndex, TopicId};
+use iox_catalog::{interface::Catalog, usage::UsageAccumulator};
+use iox_query::exec::{Executor, DEFAULT_MAX_GAP_FILL_BUCKETS};
+use observability_deps::tracing::warn;
+use parking_lot::RwLock;
 use service_common::QueryNamespaceProvider;
 use sharder::JumpHash;
 use snafu::Snafu;
-use std::{collections::BTreeSet, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashMap},
+    num::NonZeroUsize,
+    sync::Arc,
+};
 use trace::span::{Span, SpanRecorder};
 use tracker::{
     AsyncSemaphoreMetrics, InstrumentedAsyncOwnedSemaphorePermit, InstrumentedAsyncSemaphore,
@@ -49,7 +58,6 @@ pub struct QuerierDatabase {
     chunk_adapter: Arc<ChunkAdapter>,
 
     /// Metric registry
-    #[allow(dead_code)]
     metric_registry: Arc<metric::Registry>,
 
     /// Executor for queries.
@@ -61,6 +69,15 @@ pub struct QuerierDatabase {
     /// Query log.
     query_log: Arc<QueryLog>,
 
+    /// Metrics of the completed queries.
+    query_metrics: Arc<QueryMetrics>,
+
+    /// Log of the write path latency probes.
+    write_slo_log: Arc<WriteSloLog>,
+
+    /// Accumulator of the usage of the namespaces, if usage accounting is enabled.
+    usage: Option<Arc<UsageAccumulator>>,
+
     /// Semaphore that limits the number of namespaces in used at the time by the query subsystem.
     ///
     /// This should be a 1-to-1 relation to the number of active queries.
@@ -68,14 +85,42 @@ pub struct QuerierDatabase {
     /// If the same namespace is requested twice for different queries, it is counted twice.
     query_execution_semaphore: Arc<InstrumentedAsyncSemaphore>,
 
-    /// Sharder to determine which ingesters to query for a particular table and namespace.
-    sharder: Arc<JumpHash<Arc<ShardIndex>>>,
+    /// Sharder of each write buffer topic, to determine which ingesters to query for a particular
+    /// table and namespace.
+    sharders: RwLock<HashMap<TopicId, Arc<JumpHash<Arc<ShardIndex>>>>>,
 
     /// Max combined chunk size for all chunks returned to the query subsystem by a single table.
     max_table_query_bytes: usize,
 
     /// Chunk prune metrics.
     prune_metrics: Arc<PruneMetrics>,
+
+    /// External tables of the namespaces.
+    external_tables: Arc<ExternalTables>,
+
+    /// HTTP API address of the router that query results are written to, if enabled.
+    router_http_address: Option<Arc<str>>,
+
+    /// Maximum size of a request writing query results to the router.
+    router_max_request_size: usize,
+
+    /// Row-level read policies of the namespaces.
+    read_policies: ReadPolicies,
+
+    /// Limits for concurrent object store scans.
+    scan_limiter: Arc<ScanLimiter>,
+
+    /// Cache of the logical plans of queries, if enabled.
+    plan_cache: Option<Arc<QueryPlanCache>>,
+
+    /// Tables whose partitions are all cold, if the ingesters are skipped for them.
+    cold_tables: Option<Arc<ColdTables>>,
+
+    /// Shards the tables are pinned to, if the router pins tables.
+    shard_pins: Option<Arc<ShardPins>>,
+
+    /// Maximum number of time buckets per series a gap-filling query may fill.
+    max_gap_fill_buckets: usize,
 }
 
 #[async_trait]
@@ -110,6 +155,8 @@ impl QuerierDatabase {
         ingester_connection: Option<Arc<dyn IngesterConnection>>,
         max_concurrent_queries: usize,
         max_table_query_bytes: usize,
+        external_table_location_allowlist: Vec<String>,
+        router_http_address: Option<String>,
     ) -> Result<Self, Error> {
         assert!(
             max_concurrent_queries <= Self::MAX_CONCURRENT_QUERIES_MAX,
@@ -125,6 +172,7 @@ impl QuerierDatabase {
             Arc::clone(&metric_registry),
         ));
         let query_log = Arc::new(QueryLog::new(QUERY_LOG_SIZE, catalog_cache.time_provider()));
+        let query_metrics = Arc::new(QueryMetrics::new(&metric_registry, None));
         let semaphore_metrics = Arc::new(AsyncSemaphoreMetrics::new(
             &metric_registry,
             &[("semaphore", "query_execution")],
@@ -132,11 +180,14 @@ impl QuerierDatabase {
         let query_execution_semaphore =
             Arc::new(semaphore_metrics.new_semaphore(max_concurrent_queries));
 
-        let sharder = Arc::new(
-            create_sharder(catalog_cache.catalog().as_ref(), backoff_config.clone()).await?,
-        );
+        let sharders =
+            create_sharders(catalog_cache.catalog().as_ref(), backoff_config.clone()).await;
+        if sharders.is_empty() {
+            return Err(Error::NoShards);
+        }
 
         let prune_metrics = Arc::new(PruneMetrics::new(&metric_registry));
+        let external_tables = Arc::new(ExternalTables::new(external_table_location_allowlist));
 
         Ok(Self {
             backoff_config,
@@ -146,19 +197,119 @@ impl QuerierDatabase {
             exec,
             ingester_connection,
             query_log,
+            query_metrics,
+            write_slo_log: Arc::new(WriteSloLog::default()),
+            usage: None,
             query_execution_semaphore,
-            sharder,
+            sharders: RwLock::new(sharders),
             max_table_query_bytes,
             prune_metrics,
+            external_tables,
+            router_http_address: router_http_address.map(Arc::from),
+            router_max_request_size: DEFAULT_MAX_REQUEST_SIZE,
+            read_policies: ReadPolicies::default(),
+            scan_limiter: Arc::new(ScanLimiter::default()),
+            plan_cache: None,
+            cold_tables: None,
+            shard_pins: None,
+            max_gap_fill_buckets: DEFAULT_MAX_GAP_FILL_BUCKETS,
         })
     }
 
+    /// Write query results to the router in requests of at most `max_request_size` bytes, which
+    /// must not exceed the maximum HTTP request size of the router.
+    pub fn with_router_max_request_size(self, max_request_size: usize) -> Self {
+        Self {
+            router_max_request_size: max_request_size,
+            ..self
+        }
+    }
+
+    /// Restrict reads from namespaces according to `read_policies`.
+    pub fn with_read_policies(self, read_policies: ReadPolicies) -> Self {
+        Self {
+            read_policies,
+            ..self
+        }
+    }
+
+    /// Limit the number of concurrent object store scans to `max_scans` across all queries and to
+    /// `max_scans_per_query` for each query. `None` leaves the respective scans unlimited.
+    pub fn with_scan_limits(
+        self,
+        max_scans: Option<NonZeroUsize>,
+        max_scans_per_query: Option<NonZeroUsize>,
+    ) -> Self {
+        let scan_limiter = Arc::new(ScanLimiter::new(
+            max_scans,
+            max_scans_per_query,
+            &self.metric_registry,
+        ));
+        Self {
+            scan_limiter,
+            ..self
+        }
+    }
+
+    /// Reject queries that would fill the gaps of more than `max_buckets` time buckets per series
+    /// with `date_bin_gapfill`.
+    pub fn with_max_gap_fill_buckets(self, max_buckets: usize) -> Self {
+        Self {
+            max_gap_fill_buckets: max_buckets,
+            ..self
+        }
+    }
+
+    /// Cache the logical plans of up to `max_entries` queries, see [`QueryPlanCache`]. `None`
+    /// disables the cache.
+    pub fn with_plan_cache(self, max_entries: Option<NonZeroUsize>) -> Self {
+        let plan_cache = max_entries
+            .map(|max_entries| Arc::new(QueryPlanCache::new(max_entries, &self.metric_registry)));
+        Self { plan_cache, ..self }
+    }
+
+    /// Label the query metrics with the namespace of each query, for up to `max_namespaces`
+    /// distinct namespaces, see [`QueryMetrics`]. `None` does not label them by namespace.
+    pub fn with_namespace_metric_labels(self, max_namespaces: Option<usize>) -> Self {
+        let query_metrics = Arc::new(QueryMetrics::new(&self.metric_registry, max_namespaces));
+        Self {
+            query_metrics,
+            ..self
+        }
+    }
+
+    /// Account the queries executed against and the bytes scanned from each namespace in `usage`.
+    pub fn with_usage(self, usage: Arc<UsageAccumulator>) -> Self {
+        Self {
+            usage: Some(usage),
+            ..self
+        }
+    }
+
+    /// Do not query the ingesters for tables whose partitions were all marked cold by the
+    /// compactor. Recent writes to such a table are not visible until they are persisted.
+    pub fn with_skip_ingesters_for_cold_tables(self, skip: bool) -> Self {
+        let cold_tables = skip.then(|| Arc::new(ColdTables::new(Arc::clone(&self.catalog_cache))));
+        Self {
+            cold_tables,
+            ..self
+        }
+    }
+
+    /// Query the ingesters of the shard a table is pinned to, if the router pinned it (see the
+    /// router's `--shard-pinning`), instead of the shard the table hashes to.
+    pub fn with_shard_pinning(self, enabled: bool) -> Self {
+        let shard_pins = enabled.then(|| Arc::new(ShardPins::new(Arc::clone(&self.catalog_cache))));
+        Self { shard_pins, ..self }
+    }
+
     /// Get namespace if it exists.
     ///
     /// This will await the internal namespace semaphore. Existence of namespaces is checked AFTER
     /// a semaphore permit was acquired since this lowers the chance that we obtain stale data.
     pub async fn namespace(&self, name: &str, span: Option<Span>) -> Option<Arc<QuerierNamespace>> {
         let span_recorder = SpanRecorder::new(span);
+        let read_policy = self.read_policies.get(name);
         let name = Arc::from(name.to_owned());
         let ns = self
             .catalog_cache
@@ -170,6 +321,19 @@ impl QuerierDatabase {
                 span_recorder.child_span("cache GET namespace schema"),
             )
             .await?;
+        // The data of the namespace is on the shards of the topic it is assigned to.
+        let sharder = match self.sharder(ns.topic_id).await {
+            Some(sharder) => sharder,
+            None => {
+                warn!(
+                    namespace=%name,
+                    topic_id=%ns.topic_id,
+                    "namespace assigned to a topic without shards"
+                );
+                return None;
+            }
+        };
+        let external_tables = self.external_tables.namespace(ns.id);
         Some(Arc::new(QuerierNamespace::new(
             Arc::clone(&self.chunk_adapter),
             ns,
@@ -177,12 +341,41 @@ impl QuerierDatabase {
             Arc::clone(&self.exec),
             self.ingester_connection.clone(),
             Arc::clone(&self.query_log),
-            Arc::clone(&self.sharder),
+            Arc::clone(&self.query_metrics),
+            Arc::clone(&self.write_slo_log),
+            self.usage.clone(),
+            sharder,
             self.max_table_query_bytes,
             Arc::clone(&self.prune_metrics),
+            external_tables,
+            self.router_http_address.clone(),
+            self.router_max_request_size,
+            read_policy,
+            Arc::clone(&self.scan_limiter),
+            self.plan_cache.clone(),
+            self.cold_tables.clone(),
+            self.shard_pins.clone(),
+            self.max_gap_fill_buckets,
         )))
     }
 
+    /// The sharder of the shards of the topic, reloading the shards of all topics if the topic is
+    /// not known, such as a topic created since the shards were last loaded.
+    async fn sharder(&self, topic_id: TopicId) -> Option<Arc<JumpHash<Arc<ShardIndex>>>> {
+        if let Some(sharder) = self.sharders.read().get(&topic_id) {
+            return Some(Arc::clone(sharder));
+        }
+
+        let sharders = create_sharders(
+            self.catalog_cache.catalog().as_ref(),
+            self.backoff_config.clone(),
+        )
+        .await;
+        let sharder = sharders.get(&topic_id).map(Arc::clone);
+        *self.sharders.write() = sharders;
+        sharder
+    }
+
     /// Return all namespaces this querier knows about
     pub async fn namespaces(&self) -> Vec<Namespace> {
         let catalog = &self.catalog_cache.catalog();
@@ -203,12 +396,18 @@ impl QuerierDatabase {
     pub(crate) fn exec(&self) -> &Executor {
         &self.exec
     }
+
+    /// Log of the write path latency probes.
+    pub(crate) fn write_slo_log(&self) -> &WriteSloLog {
+        &self.write_slo_log
+    }
 }
 
-pub async fn create_sharder(
+/// Create a sharder over the shards of each write buffer topic with at least one shard.
+pub async fn create_sharders(
     catalog: &dyn Catalog,
     backoff_config: BackoffConfig,
-) -> Result<JumpHash<Arc<ShardIndex>>, Error> {
+) -> HashMap<TopicId, Arc<JumpHash<Arc<ShardIndex>>>> {
     let shards = Backoff::new(&backoff_config)
         .retry_all_errors("get shards", || async {
             catalog.repositories().await.shards().list().await
@@ -216,22 +415,26 @@ pub async fn create_sharder(
         .await
         .expect("retry forever");
 
-    // Construct the (ordered) set of shard indexes.
+    // Construct the (ordered) set of shard indexes of each topic.
     //
     // The sort order must be deterministic in order for all nodes to shard to
     // the same indexes, therefore we type assert the returned set is of the
     // ordered variety.
-    let shard_indexes: BTreeSet<_> = shards
-        //             ^ don't change this to an unordered set
-        .into_iter()
-        .map(|shard| shard.shard_index)
-        .collect();
-
-    if shard_indexes.is_empty() {
-        return Err(Error::NoShards);
+    let mut shard_indexes: HashMap<TopicId, BTreeSet<ShardIndex>> = HashMap::new();
+    for shard in shards {
+        shard_indexes
+            .entry(shard.topic_id)
+            .or_default()
+            .insert(shard.shard_index);
     }
 
-    Ok(JumpHash::new(shard_indexes.into_iter().map(Arc::new)))
+    shard_indexes
+        .into_iter()
+        .map(|(topic_id, shard_indexes)| {
+            let sharder = JumpHash::new(shard_indexes.into_iter().map(Arc::new));
+            (topic_id, Arc::new(sharder))
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -263,6 +466,8 @@ mod tests {
             Some(create_ingester_connection_for_testing()),
             QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX.saturating_add(1),
             usize::MAX,
+            vec![],
+            None,
         )
         .await
         .unwrap();
@@ -288,6 +493,8 @@ mod tests {
                 Some(create_ingester_connection_for_testing()),
                 QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
                 usize::MAX,
+                vec![],
+                None,
             )
             .await,
             Error::NoShards
@@ -314,6 +521,8 @@ mod tests {
             Some(create_ingester_connection_for_testing()),
             QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
             usize::MAX,
+            vec![],
+            None,
         )
         .await
         .unwrap();
@@ -344,6 +553,8 @@ mod tests {
             Some(create_ingester_connection_for_testing()),
             QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
             usize::MAX,
+            vec![],
+            None,
         )
         .await
         .unwrap();
@@ -357,4 +568,61 @@ mod tests {
         assert_eq!(namespaces[0].name, "ns1");
         assert_eq!(namespaces[1].name, "ns2");
     }
+
+    #[tokio::test]
+    async fn test_namespace_topic() {
+        let catalog = TestCatalog::new();
+        // QuerierDatabase::new returns an error if there are no shards in the catalog
+        catalog.create_shard(0).await;
+
+        let catalog_cache = Arc::new(CatalogCache::new_testing(
+            catalog.catalog(),
+            catalog.time_provider(),
+            catalog.metric_registry(),
+            catalog.object_store(),
+            &Handle::current(),
+        ));
+        let db = QuerierDatabase::new(
+            catalog_cache,
+            catalog.metric_registry(),
+            catalog.exec(),
+            Some(create_ingester_connection_for_testing()),
+            QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
+            usize::MAX,
+            vec![],
+            None,
+        )
+        .await
+        .unwrap();
+
+        let ns = catalog.create_namespace_1hr_retention("ns1").await;
+        let noisy = {
+            let mut repos = catalog.catalog().repositories().await;
+            let noisy = repos.topics().create_or_get("noisy").await.unwrap();
+            repos
+                .namespaces()
+                .update_topic(&ns.namespace.name, noisy.id)
+                .await
+                .unwrap();
+            noisy
+        };
+
+        // The topic of the namespace has no shards.
+        assert!(db.namespace("ns1", None).await.is_none());
+
+        // The shards of a topic created since startup are loaded when first queried.
+        catalog
+            .catalog()
+            .repositories()
+            .await
+            .shards()
+            .create_or_get(&noisy, ShardIndex::new(3))
+            .await
+            .unwrap();
+        assert!(db.namespace("ns1", None).await.is_some());
+        assert_eq!(
+            db.sharder(noisy.id).await.unwrap().shards(),
+            [Arc::new(ShardIndex::new(3))]
+        );
+    }
 }
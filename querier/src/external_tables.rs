@@ -0,0 +1,177 @@
+//! Ad-hoc external tables created via `CREATE EXTERNAL TABLE`.
+
+use data_types::NamespaceId;
+use datafusion::{
+    catalog::schema::SchemaProvider, datasource::TableProvider, error::DataFusionError,
+};
+use parking_lot::{Mutex, RwLock};
+use std::{any::Any, collections::HashMap, sync::Arc};
+
+/// Name of the schema that holds external tables.
+pub const EXTERNAL_SCHEMA: &str = "external";
+
+/// External tables of the namespaces of a querier.
+///
+/// Tables are created with `CREATE EXTERNAL TABLE external.<name> ... LOCATION '<url>'` and are
+/// kept until the querier restarts. Each namespace has its own tables, which are only visible to
+/// the queries against that namespace. Only locations within one of the allow-listed prefixes
+/// are accepted (this check happens during planning, see
+/// [`IOxSessionContext::with_external_table_locations`](iox_query::exec::IOxSessionContext::with_external_table_locations)).
+/// If no prefixes are configured, external tables are disabled.
+#[derive(Debug, Default)]
+pub struct ExternalTables {
+    /// Location prefixes that external tables may reference.
+    allowed_locations: Vec<String>,
+
+    /// Tables of the namespaces that have been queried.
+    namespaces: Mutex<HashMap<NamespaceId, Arc<NamespaceExternalTables>>>,
+}
+
+impl ExternalTables {
+    /// Create an empty registry accepting the given location prefixes.
+    pub fn new(allowed_locations: Vec<String>) -> Self {
+        Self {
+            allowed_locations,
+            namespaces: Default::default(),
+        }
+    }
+
+    /// Get the external tables of the namespace with the given ID.
+    pub fn namespace(&self, namespace_id: NamespaceId) -> Arc<NamespaceExternalTables> {
+        // there is nothing to keep if external tables are disabled
+        if self.allowed_locations.is_empty() {
+            return Arc::new(NamespaceExternalTables::default());
+        }
+
+        let mut namespaces = self.namespaces.lock();
+        let tables = namespaces.entry(namespace_id).or_insert_with(|| {
+            Arc::new(NamespaceExternalTables::new(self.allowed_locations.clone()))
+        });
+        Arc::clone(tables)
+    }
+}
+
+/// External tables of a single namespace, the `external` schema of its queries.
+#[derive(Debug, Default)]
+pub struct NamespaceExternalTables {
+    /// Location prefixes that external tables may reference.
+    allowed_locations: Vec<String>,
+
+    /// Registered tables.
+    tables: RwLock<HashMap<String, Arc<dyn TableProvider>>>,
+}
+
+impl NamespaceExternalTables {
+    /// Create an empty schema accepting the given location prefixes.
+    fn new(allowed_locations: Vec<String>) -> Self {
+        Self {
+            allowed_locations,
+            tables: Default::default(),
+        }
+    }
+
+    /// Location prefixes that external tables may reference.
+    pub fn allowed_locations(&self) -> &[String] {
+        &self.allowed_locations
+    }
+}
+
+impl SchemaProvider for NamespaceExternalTables {
+    fn as_any(&self) -> &dyn Any {
+        self as &dyn Any
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.tables.read().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    fn table(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
+        self.tables.read().get(name).map(Arc::clone)
+    }
+
+    fn register_table(
+        &self,
+        name: String,
+        table: Arc<dyn TableProvider>,
+    ) -> Result<Option<Arc<dyn TableProvider>>, DataFusionError> {
+        if self.allowed_locations.is_empty() {
+            return Err(DataFusionError::NotImplemented(
+                "External tables are disabled".to_string(),
+            ));
+        }
+
+        let mut tables = self.tables.write();
+        if tables.contains_key(&name) {
+            return Err(DataFusionError::Plan(format!(
+                "External table '{name}' already exists"
+            )));
+        }
+        tables.insert(name, table);
+        Ok(None)
+    }
+
+    fn deregister_table(
+        &self,
+        name: &str,
+    ) -> Result<Option<Arc<dyn TableProvider>>, DataFusionError> {
+        Ok(self.tables.write().remove(name))
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.tables.read().contains_key(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::Schema;
+    use datafusion::datasource::empty::EmptyTable;
+
+    #[test]
+    fn test_register() {
+        let tables = NamespaceExternalTables::new(vec!["file:///data/".to_string()]);
+        assert!(tables.table_names().is_empty());
+
+        let table = Arc::new(EmptyTable::new(Arc::new(Schema::empty())));
+        tables
+            .register_table("t".to_string(), Arc::clone(&table) as _)
+            .unwrap();
+        assert_eq!(tables.table_names(), vec!["t".to_string()]);
+        assert!(tables.table_exist("t"));
+        assert!(tables.table("t").is_some());
+
+        // no silent replacement
+        tables
+            .register_table("t".to_string(), Arc::clone(&table) as _)
+            .unwrap_err();
+
+        assert!(tables.deregister_table("t").unwrap().is_some());
+        assert!(!tables.table_exist("t"));
+    }
+
+    #[test]
+    fn test_namespaces() {
+        let tables = ExternalTables::new(vec!["file:///data/".to_string()]);
+        let table = Arc::new(EmptyTable::new(Arc::new(Schema::empty())));
+        tables
+            .namespace(NamespaceId::new(1))
+            .register_table("t".to_string(), table)
+            .unwrap();
+
+        assert!(tables.namespace(NamespaceId::new(1)).table_exist("t"));
+        assert!(!tables.namespace(NamespaceId::new(2)).table_exist("t"));
+    }
+
+    #[test]
+    fn test_disabled() {
+        let tables = ExternalTables::default();
+        let table = Arc::new(EmptyTable::new(Arc::new(Schema::empty())));
+        tables
+            .namespace(NamespaceId::new(1))
+            .register_table("t".to_string(), table)
+            .unwrap_err();
+    }
+}
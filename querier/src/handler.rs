@@ -6,23 +6,28 @@ use futures::{
     stream::FuturesUnordered,
     FutureExt, StreamExt, TryFutureExt,
 };
+use generated_types::influxdata::iox::usage::v1::usage_service_server::UsageServiceServer;
 use influxdb_iox_client::{
     catalog::generated_types::catalog_service_server::CatalogServiceServer,
     schema::generated_types::schema_service_server::SchemaServiceServer,
     store::generated_types::object_store_service_server::ObjectStoreServiceServer,
 };
-use iox_catalog::interface::Catalog;
+use iox_catalog::{interface::Catalog, usage::UsageAccumulator};
 use object_store::ObjectStore;
 use observability_deps::tracing::warn;
 use service_grpc_catalog::CatalogService;
 use service_grpc_object_store::ObjectStoreService;
 use service_grpc_schema::SchemaService;
-use std::sync::Arc;
+use service_grpc_usage::UsageService;
+use std::{sync::Arc, time::Duration};
 use thiserror::Error;
 use tokio::task::{JoinError, JoinHandle};
 use tokio_util::sync::CancellationToken;
 
-use crate::{database::QuerierDatabase, poison::PoisonCabinet};
+use crate::{
+    database::QuerierDatabase, poison::PoisonCabinet, query_pool::QueryPoolMembership,
+    write_slo::WriteSloProbe,
+};
 
 #[derive(Debug, Error)]
 #[allow(missing_copy_implementations, missing_docs)]
@@ -40,6 +45,9 @@ pub trait QuerierHandler: Send + Sync {
     /// Acquire an [`ObjectStoreServiceServer`] gRPC service implementation.
     fn object_store_service(&self) -> ObjectStoreServiceServer<ObjectStoreService>;
 
+    /// Acquire a [`UsageServiceServer`] gRPC service implementation.
+    fn usage_service(&self) -> UsageServiceServer<UsageService>;
+
     /// Wait until the handler finished  to shutdown.
     ///
     /// Use [`shutdown`](Self::shutdown) to trigger a shutdown.
@@ -53,7 +61,6 @@ pub trait QuerierHandler: Send + Sync {
 type SharedJoinHandle = Shared<BoxFuture<'static, Result<(), Arc<JoinError>>>>;
 
 /// Convert a [`JoinHandle`] into a [`SharedJoinHandle`].
-#[allow(dead_code)]
 fn shared_handle(handle: JoinHandle<()>) -> SharedJoinHandle {
     handle.map_err(Arc::new).boxed().shared()
 }
@@ -101,6 +108,36 @@ impl QuerierHandlerImpl {
             poison_cabinet,
         }
     }
+
+    /// Register this querier as a member of a query pool, keeping the registration alive until
+    /// shutdown.
+    pub fn with_query_pool_membership(mut self, membership: QueryPoolMembership) -> Self {
+        let handle = tokio::spawn(membership.run(Arc::clone(&self.catalog), self.shutdown.clone()));
+        self.join_handles
+            .push((String::from("query pool membership"), shared_handle(handle)));
+        self
+    }
+
+    /// Measure the latency of the write path with canary points until shutdown.
+    pub fn with_write_slo_probe(mut self, probe: WriteSloProbe) -> Self {
+        let handle = tokio::spawn(probe.run(
+            Arc::clone(&self.database),
+            Arc::clone(&self.catalog),
+            self.shutdown.clone(),
+        ));
+        self.join_handles
+            .push((String::from("write SLO probe"), shared_handle(handle)));
+        self
+    }
+
+    /// Flush the usage of the namespaces to the catalog every `interval` until shutdown.
+    pub fn with_usage_flush(mut self, usage: Arc<UsageAccumulator>, interval: Duration) -> Self {
+        let handle =
+            tokio::spawn(usage.run(Arc::clone(&self.catalog), interval, self.shutdown.clone()));
+        self.join_handles
+            .push((String::from("usage flush"), shared_handle(handle)));
+        self
+    }
 }
 
 #[async_trait]
@@ -120,6 +157,10 @@ impl QuerierHandler for QuerierHandlerImpl {
         ))
     }
 
+    fn usage_service(&self) -> UsageServiceServer<UsageService> {
+        UsageServiceServer::new(UsageService::new(Arc::clone(&self.catalog)))
+    }
+
     async fn join(&self) {
         // Need to poll handlers unordered to detect early exists of any worker in the list.
         let mut unordered: FuturesUnordered<_> = self
@@ -225,6 +266,8 @@ mod tests {
                     Some(create_ingester_connection_for_testing()),
                     QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
                     usize::MAX,
+                    vec![],
+                    None,
                 )
                 .await
                 .unwrap(),
@@ -0,0 +1,148 @@
+//! Drain states of the ingesters, recorded in the catalog by ingesters that are
+//! drained before a restart.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use data_types::DrainState;
+use iox_catalog::interface::Catalog;
+use iox_time::{Time, TimeProvider};
+use observability_deps::tracing::warn;
+use tokio::sync::Mutex;
+
+/// How long the drain states read from the catalog are used before they are
+/// read again.
+pub(crate) const DRAIN_STATES_TTL: Duration = Duration::from_secs(10);
+
+/// The drain states of the ingesters, by address.
+pub(crate) type DrainStateMap = HashMap<Arc<str>, DrainState>;
+
+/// Cache of the drain states of the ingesters.
+#[derive(Debug)]
+pub(crate) struct DrainStates {
+    catalog: Arc<dyn Catalog>,
+    time_provider: Arc<dyn TimeProvider>,
+    cached: Mutex<Option<(Time, Arc<DrainStateMap>)>>,
+}
+
+impl DrainStates {
+    pub(crate) fn new(catalog: Arc<dyn Catalog>, time_provider: Arc<dyn TimeProvider>) -> Self {
+        Self {
+            catalog,
+            time_provider,
+            cached: Default::default(),
+        }
+    }
+
+    /// Get the drain states of the ingesters, reading them from the catalog
+    /// if they are older than [`DRAIN_STATES_TTL`].
+    ///
+    /// If the catalog can not be read, the previous states are used, and no
+    /// ingester is considered to drain if there are none.
+    pub(crate) async fn get(&self) -> Arc<DrainStateMap> {
+        let mut cached = self.cached.lock().await;
+        let now = self.time_provider.now();
+
+        if let Some((read_at, states)) = cached.as_ref() {
+            if now < *read_at + DRAIN_STATES_TTL {
+                return Arc::clone(states);
+            }
+        }
+
+        let res = self.catalog.repositories().await.node_drains().list().await;
+        let states = match res {
+            Ok(drains) => Arc::new(
+                drains
+                    .into_iter()
+                    .map(|d| (Arc::from(d.address), d.state))
+                    .collect(),
+            ),
+            Err(e) => {
+                warn!(%e, "failed to read the drain states of the ingesters");
+                cached
+                    .as_ref()
+                    .map(|(_, states)| Arc::clone(states))
+                    .unwrap_or_default()
+            }
+        };
+
+        *cached = Some((now, Arc::clone(&states)));
+        states
+    }
+}
+
+/// Select the replicas in `addrs` to query given the drain `states`.
+///
+/// Drained ingesters persisted all their data and are never queried. Draining
+/// ingesters are only queried if no other replica is active.
+pub(crate) fn routable_replicas(addrs: &[Arc<str>], states: &DrainStateMap) -> Vec<Arc<str>> {
+    let state = |addr: &Arc<str>| states.get(addr).copied().unwrap_or(DrainState::Active);
+
+    let addrs = addrs
+        .iter()
+        .filter(|addr| state(addr) != DrainState::Drained)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if addrs.iter().any(|addr| state(addr) == DrainState::Active) {
+        addrs
+            .into_iter()
+            .filter(|addr| state(addr) == DrainState::Active)
+            .collect()
+    } else {
+        addrs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routable_replicas() {
+        let addrs = |s: &[&str]| -> Vec<Arc<str>> { s.iter().map(|&addr| addr.into()).collect() };
+        let states = |s: &[(&str, DrainState)]| -> DrainStateMap {
+            s.iter()
+                .map(|(addr, state)| (Arc::from(*addr), *state))
+                .collect()
+        };
+
+        let all = addrs(&["a", "b", "c"]);
+        assert_eq!(routable_replicas(&all, &states(&[])), all);
+
+        // draining replicas are avoided if others are active
+        assert_eq!(
+            routable_replicas(&all, &states(&[("a", DrainState::Draining)])),
+            addrs(&["b", "c"])
+        );
+        assert_eq!(
+            routable_replicas(
+                &all,
+                &states(&[
+                    ("a", DrainState::Draining),
+                    ("b", DrainState::Drained),
+                    ("c", DrainState::Active)
+                ])
+            ),
+            addrs(&["c"])
+        );
+
+        // ... but queried if they are the only ones left
+        assert_eq!(
+            routable_replicas(
+                &all,
+                &states(&[
+                    ("a", DrainState::Draining),
+                    ("b", DrainState::Drained),
+                    ("c", DrainState::Drained)
+                ])
+            ),
+            addrs(&["a"])
+        );
+
+        // drained ingesters are never queried
+        assert_eq!(
+            routable_replicas(&all[..1], &states(&[("a", DrainState::Drained)])),
+            addrs(&[])
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use self::{
     circuit_breaker::CircuitBreakerFlightClient,
+    drain::{routable_replicas, DrainStates},
     flight_client::{Error as FlightClientError, FlightClient, FlightClientImpl, FlightError},
+    owners::ShardOwners,
     test_util::MockIngesterConnection,
 };
 use crate::cache::CatalogCache;
@@ -10,10 +12,10 @@ use backoff::{Backoff, BackoffConfig, BackoffError};
 use client_util::connection;
 use data_types::{
     ChunkId, ChunkOrder, IngesterMapping, NamespaceId, PartitionId, SequenceNumber, ShardId,
-    ShardIndex, TableId, TableSummary, TimestampMinMax,
+    ShardIndex, TableId, TableSummary, TimestampMinMax, TopicId,
 };
 use datafusion::error::DataFusionError;
-use futures::{stream::FuturesUnordered, TryStreamExt};
+use futures::{stream::FuturesUnordered, StreamExt, TryStreamExt};
 use generated_types::{
     influxdata::iox::ingester::v1::GetWriteInfoResponse,
     ingester::{encode_proto_predicate_as_base64, IngesterQueryRequest},
@@ -24,7 +26,7 @@ use influxdb_iox_client::flight::{
 };
 use iox_query::{
     exec::{stringset::StringSet, IOxSessionContext},
-    util::{compute_timenanosecond_min_max, create_basic_summary},
+    util::{compute_table_summary, compute_timenanosecond_min_max},
     QueryChunk, QueryChunkData, QueryChunkMeta,
 };
 use iox_time::{Time, TimeProvider};
@@ -35,14 +37,16 @@ use schema::{sort::SortKey, Projection, Schema};
 use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use std::{
     any::Any,
-    collections::{HashMap, HashSet},
+    collections::{hash_map::Entry, HashMap, HashSet},
     sync::Arc,
     time::Duration,
 };
 use trace::span::{Span, SpanRecorder};
 
 mod circuit_breaker;
+mod drain;
 pub(crate) mod flight_client;
+mod owners;
 pub(crate) mod test_util;
 
 #[derive(Debug, Snafu)]
@@ -135,6 +139,19 @@ pub enum Error {
         "Shard index {shard_index} was neither mapped to an ingester nor marked ignore"
     ))]
     ShardNotMapped { shard_index: ShardIndex },
+
+    #[snafu(display(
+        "Could not resolve the ingesters of the shards of topic {topic_id}: {source}"
+    ))]
+    ShardOwners {
+        topic_id: TopicId,
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display("Could not resolve the ingesters owning shards: {source}"))]
+    ShardOwnerAddresses {
+        source: iox_catalog::interface::Error,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -158,6 +175,24 @@ pub fn create_ingester_connections_by_shard(
     ))
 }
 
+/// Create a new set of connections to the ingesters the shards are assigned to in the catalog,
+/// see [`IngesterConnectionImpl::by_catalog`].
+pub fn create_ingester_connections_by_catalog(
+    catalog_cache: Arc<CatalogCache>,
+    open_circuit_after_n_errors: u64,
+) -> Arc<dyn IngesterConnection> {
+    Arc::new(IngesterConnectionImpl::by_catalog(
+        catalog_cache,
+        BackoffConfig {
+            init_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+            base: 3.0,
+            deadline: Some(Duration::from_secs(10)),
+        },
+        open_circuit_after_n_errors,
+    ))
+}
+
 /// Create a new ingester suitable for testing
 pub fn create_ingester_connection_for_testing() -> Arc<dyn IngesterConnection> {
     Arc::new(MockIngesterConnection::new())
@@ -175,6 +210,7 @@ pub trait IngesterConnection: std::fmt::Debug + Send + Sync + 'static {
     #[allow(clippy::too_many_arguments)]
     async fn partitions(
         &self,
+        topic_id: TopicId,
         shard_indexes: &[ShardIndex],
         namespace_id: NamespaceId,
         table_id: TableId,
@@ -316,6 +352,9 @@ impl<'a> Drop for ObserveIngesterRequest<'a> {
 pub struct IngesterConnectionImpl {
     shard_to_ingesters: HashMap<ShardIndex, IngesterMapping>,
     unique_ingester_addresses: HashSet<Arc<str>>,
+    /// The owners of the shards, if resolved from the catalog rather than `shard_to_ingesters`.
+    shard_owners: Option<ShardOwners>,
+    drain_states: DrainStates,
     flight_client: Arc<dyn FlightClient>,
     catalog_cache: Arc<CatalogCache>,
     metrics: Arc<IngesterConnectionMetrics>,
@@ -327,17 +366,21 @@ impl IngesterConnectionImpl {
     ///
     /// ```json
     /// {
+    ///   "ingesters": {
+    ///     "i0": {"addr": "http://ingester-0:8082"},
+    ///     "i1": {"addr": "http://ingester-1:8082"},
+    ///     "i3": {"addr": "http://ingester-3:8082"}
+    ///   },
     ///   "shards": {
-    ///     "0": {
-    ///       "ingesters": [
-    ///         {"addr": "http://ingester-0:8082"},
-    ///         {"addr": "http://ingester-3:8082"}
-    ///       ]
-    ///     },
-    ///     "1": { "ingesters": [{"addr": "http://ingester-1:8082"}]},
+    ///     "0": {"ingester": "i0", "replicas": ["i3"]},
+    ///     "1": {"ingester": "i1"}
     ///   }
     /// }
     /// ```
+    ///
+    /// The partitions of shards with replicas are read from all of them, see
+    /// [`IngesterMapping::Replicas`]. Ingesters that are drained, or draining while another
+    /// replica is active, according to their drain state in the catalog are not queried.
     pub fn by_shard(
         shard_to_ingesters: HashMap<ShardIndex, IngesterMapping>,
         catalog_cache: Arc<CatalogCache>,
@@ -359,6 +402,26 @@ impl IngesterConnectionImpl {
         )
     }
 
+    /// Create a new set of connections to the ingesters owning the shards according to the
+    /// shard assignment the ingesters maintain in the catalog, i.e. the ingester each shard is
+    /// assigned to and the ingester holding its lease.
+    pub fn by_catalog(
+        catalog_cache: Arc<CatalogCache>,
+        backoff_config: BackoffConfig,
+        open_circuit_after_n_errors: u64,
+    ) -> Self {
+        let shard_owners = ShardOwners::new(catalog_cache.catalog(), catalog_cache.time_provider());
+        Self {
+            shard_owners: Some(shard_owners),
+            ..Self::by_shard(
+                HashMap::new(),
+                catalog_cache,
+                backoff_config,
+                open_circuit_after_n_errors,
+            )
+        }
+    }
+
     /// Create new set of connections with specific flight client implementation.
     ///
     /// This is helpful for testing, i.e. when the flight client should not be backed by normal
@@ -372,18 +435,21 @@ impl IngesterConnectionImpl {
         let unique_ingester_addresses: HashSet<_> = shard_to_ingesters
             .values()
             .flat_map(|v| match v {
-                IngesterMapping::Addr(addr) => Some(addr),
-                _ => None,
+                IngesterMapping::Addr(addr) => vec![Arc::clone(addr)],
+                IngesterMapping::Replicas(addrs) => addrs.clone(),
+                IngesterMapping::Ignore | IngesterMapping::NotMapped => vec![],
             })
-            .cloned()
             .collect();
 
         let metric_registry = catalog_cache.metric_registry();
         let metrics = Arc::new(IngesterConnectionMetrics::new(&metric_registry));
+        let drain_states = DrainStates::new(catalog_cache.catalog(), catalog_cache.time_provider());
 
         Self {
             shard_to_ingesters,
             unique_ingester_addresses,
+            shard_owners: None,
+            drain_states,
             flight_client,
             catalog_cache,
             metrics,
@@ -635,7 +701,8 @@ impl IngesterStreamDecoder {
                     status.parquet_max_sequence_number.map(SequenceNumber::new),
                     None,
                     partition_sort_key,
-                );
+                )
+                .with_max_sequence_number(status.max_sequence_number.map(SequenceNumber::new));
                 self.current_partition = Some(partition);
             }
             LowLevelMessage::Schema(schema) => {
@@ -712,6 +779,7 @@ impl IngesterConnection for IngesterConnectionImpl {
     /// Retrieve chunks from the ingester for the particular table, shard, and predicate
     async fn partitions(
         &self,
+        topic_id: TopicId,
         shard_indexes: &[ShardIndex],
         namespace_id: NamespaceId,
         table_id: TableId,
@@ -785,57 +853,136 @@ impl IngesterConnection for IngesterConnectionImpl {
 
         // Look up the ingesters needed for the shard. Collect into a HashSet to avoid making
         // multiple requests to the same ingester if that ingester is responsible for multiple
-        // shard_indexes relevant to this query.
+        // shard_indexes relevant to this query. The replica sets are remembered to check that
+        // enough of their ingesters answered.
+        let drain_states = self.drain_states.get().await;
         let mut relevant_ingester_addresses = HashSet::new();
+        let mut replica_sets = HashSet::new();
+
+        let shard_owners = match &self.shard_owners {
+            Some(shard_owners) => Some(
+                shard_owners
+                    .get(topic_id)
+                    .await
+                    .context(ShardOwnersSnafu { topic_id })?,
+            ),
+            None => None,
+        };
 
         for shard_index in shard_indexes {
-            match self.shard_to_ingesters.get(shard_index) {
-                None => {
-                    return NoIngesterFoundForShardSnafu {
-                        shard_index: *shard_index,
+            let addrs = match &shard_owners {
+                // A shard without owner is not consumed by any ingester, i.e. its data is
+                // persisted.
+                Some(shard_owners) => routable_replicas(
+                    shard_owners
+                        .get(shard_index)
+                        .map(Vec::as_slice)
+                        .unwrap_or_default(),
+                    &drain_states,
+                ),
+                None => match self.shard_to_ingesters.get(shard_index) {
+                    None => {
+                        return NoIngesterFoundForShardSnafu {
+                            shard_index: *shard_index,
+                        }
+                        .fail()
                     }
-                    .fail()
-                }
-                Some(mapping) => match mapping {
-                    IngesterMapping::Addr(addr) => {
-                        relevant_ingester_addresses.insert(Arc::clone(addr));
+                    Some(IngesterMapping::Addr(addr)) => {
+                        routable_replicas(&[Arc::clone(addr)], &drain_states)
                     }
-                    IngesterMapping::Ignore => (),
-                    IngesterMapping::NotMapped => {
+                    Some(IngesterMapping::Replicas(addrs)) => {
+                        routable_replicas(addrs, &drain_states)
+                    }
+                    Some(IngesterMapping::Ignore) => continue,
+                    Some(IngesterMapping::NotMapped) => {
                         return ShardNotMappedSnafu {
                             shard_index: *shard_index,
                         }
                         .fail()
                     }
                 },
+            };
+
+            // There is no ingester left to query if all the ingesters of the shard are drained,
+            // i.e. its data is persisted.
+            if !addrs.is_empty() {
+                relevant_ingester_addresses.extend(addrs.iter().cloned());
+                replica_sets.insert(addrs);
             }
         }
 
-        let mut ingester_partitions: Vec<IngesterPartition> = relevant_ingester_addresses
-            .into_iter()
-            .map(move |ingester_address| measured_ingester_request(ingester_address))
-            .collect::<FuturesUnordered<_>>()
-            .try_collect::<Vec<_>>()
-            .await
-            .map_err(|e| {
+        let mut results: HashMap<Arc<str>, Result<Vec<IngesterPartition>>> =
+            relevant_ingester_addresses
+                .into_iter()
+                .map(|ingester_address| {
+                    let request = measured_ingester_request(Arc::clone(&ingester_address));
+                    async move {
+                        let res = request.await.map_err(|e| match e {
+                            BackoffError::DeadlineExceeded { source, .. } => source,
+                        });
+                        (ingester_address, res)
+                    }
+                })
+                .collect::<FuturesUnordered<_>>()
+                .collect()
+                .await;
+
+        // Every replica set may miss one of its ingesters, a single ingester must answer.
+        for replica_set in &replica_sets {
+            let failed: Vec<_> = replica_set
+                .iter()
+                .filter(|addr| matches!(results.get(*addr), Some(Err(_))))
+                .collect();
+            let tolerated = usize::from(replica_set.len() > 1);
+
+            if failed.len() > tolerated {
                 span_recorder.error("failed");
-                match e {
-                    BackoffError::DeadlineExceeded { source, .. } => source,
+                return match results.remove(failed[0]) {
+                    Some(Err(e)) => Err(e),
+                    _ => unreachable!("failed ingester has an error"),
+                };
+            }
+
+            for addr in failed {
+                if let Some(Err(e)) = results.get(addr) {
+                    warn!(
+                        ingester_address=addr.as_ref(),
+                        %e,
+                        "Ingester replica failed, using the other replicas"
+                    );
                 }
-            })?
-            // We have a Vec<Vec<..>> flatten to Vec<_>
+            }
+        }
+
+        // Sort by address so that the replica kept for equal sequence numbers is deterministic.
+        let mut results: Vec<_> = results
             .into_iter()
-            .flatten()
+            .filter_map(|(addr, res)| res.ok().map(|partitions| (addr, partitions)))
             .collect();
+        results.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let ingester_partitions = merge_replica_partitions(
+            results
+                .into_iter()
+                .flat_map(|(_addr, partitions)| partitions)
+                .collect(),
+        );
 
-        ingester_partitions.sort_by_key(|p| p.partition_id);
         span_recorder.ok("done");
         Ok(ingester_partitions)
     }
 
     async fn get_write_info(&self, write_token: &str) -> Result<GetWriteInfoResponse> {
-        let responses = self
-            .unique_ingester_addresses
+        let mut ingester_addresses = self.unique_ingester_addresses.clone();
+        if let Some(shard_owners) = &self.shard_owners {
+            let owners = shard_owners
+                .addresses()
+                .await
+                .context(ShardOwnerAddressesSnafu)?;
+            ingester_addresses.extend(owners);
+        }
+
+        let responses = ingester_addresses
             .iter()
             .map(|ingester_address| execute_get_write_infos(ingester_address, write_token))
             .collect::<FuturesUnordered<_>>()
@@ -850,6 +997,34 @@ impl IngesterConnection for IngesterConnectionImpl {
     }
 }
 
+/// Merge the partitions returned by the replicas of the ingesters.
+///
+/// Replicas return the same partition, possibly at different progress. Of every partition, the
+/// one containing the most recent writes (i.e. with the highest sequence number) is kept, using
+/// the persisted sequence number to break ties. The result is sorted by partition ID.
+fn merge_replica_partitions(partitions: Vec<IngesterPartition>) -> Vec<IngesterPartition> {
+    let mut merged: HashMap<PartitionId, IngesterPartition> = HashMap::new();
+
+    for partition in partitions {
+        match merged.entry(partition.partition_id) {
+            Entry::Occupied(mut o) => {
+                let progress =
+                    |p: &IngesterPartition| (p.max_sequence_number, p.parquet_max_sequence_number);
+                if progress(&partition) > progress(o.get()) {
+                    o.insert(partition);
+                }
+            }
+            Entry::Vacant(v) => {
+                v.insert(partition);
+            }
+        }
+    }
+
+    let mut merged: Vec<_> = merged.into_values().collect();
+    merged.sort_by_key(|p| p.partition_id);
+    merged
+}
+
 async fn execute_get_write_infos(
     ingester_address: &str,
     write_token: &str,
@@ -890,6 +1065,10 @@ pub struct IngesterPartition {
     /// persisted for this partition
     parquet_max_sequence_number: Option<SequenceNumber>,
 
+    /// Maximum sequence number of the writes the ingester has
+    /// buffered for this partition, if known
+    max_sequence_number: Option<SequenceNumber>,
+
     /// Maximum sequence number of tombstone that the ingester has
     /// persisted for this partition
     tombstone_max_sequence_number: Option<SequenceNumber>,
@@ -916,12 +1095,24 @@ impl IngesterPartition {
             partition_id,
             shard_id,
             parquet_max_sequence_number,
+            max_sequence_number: None,
             tombstone_max_sequence_number,
             partition_sort_key,
             chunks: vec![],
         }
     }
 
+    /// Set the maximum sequence number of the buffered writes
+    pub(crate) fn with_max_sequence_number(
+        self,
+        max_sequence_number: Option<SequenceNumber>,
+    ) -> Self {
+        Self {
+            max_sequence_number,
+            ..self
+        }
+    }
+
     /// Try to add a new chunk to this partition.
     pub(crate) fn try_add_chunk(
         mut self,
@@ -948,12 +1139,7 @@ impl IngesterPartition {
         // TODO: may want to ask the Ingester to send this value instead of computing it here.
         let ts_min_max = compute_timenanosecond_min_max(&batches).expect("Should have time range");
 
-        let row_count = batches.iter().map(|batch| batch.num_rows()).sum::<usize>() as u64;
-        let summary = Arc::new(create_basic_summary(
-            row_count,
-            &expected_schema,
-            ts_min_max,
-        ));
+        let summary = Arc::new(compute_table_summary(&expected_schema, &batches));
 
         let chunk = IngesterChunk {
             chunk_id,
@@ -1088,6 +1274,7 @@ impl QueryChunkMeta for IngesterChunk {
     }
 }
 
+#[async_trait]
 impl QueryChunk for IngesterChunk {
     fn id(&self) -> ChunkId {
         self.chunk_id
@@ -1098,7 +1285,7 @@ impl QueryChunk for IngesterChunk {
         true
     }
 
-    fn column_names(
+    async fn column_names(
         &self,
         _ctx: IOxSessionContext,
         _predicate: &Predicate,
@@ -1108,7 +1295,7 @@ impl QueryChunk for IngesterChunk {
         Ok(None)
     }
 
-    fn column_values(
+    async fn column_values(
         &self,
         _ctx: IOxSessionContext,
         _column_name: &str,
@@ -1203,6 +1390,7 @@ mod tests {
         datatypes::Int32Type,
     };
     use assert_matches::assert_matches;
+    use data_types::{DrainState, Timestamp};
     use generated_types::influxdata::iox::ingester::v1::PartitionStatus;
     use influxdb_iox_client::flight::generated_types::IngesterQueryResponseMetadata;
     use iox_tests::util::TestCatalog;
@@ -1321,6 +1509,7 @@ mod tests {
                             partition_id: 1,
                             status: Some(PartitionStatus {
                                 parquet_max_sequence_number: None,
+                                max_sequence_number: None,
                             }),
                         },
                     ))],
@@ -1376,6 +1565,7 @@ mod tests {
                                 partition_id: 1,
                                 status: Some(PartitionStatus {
                                     parquet_max_sequence_number: None,
+                                    max_sequence_number: None,
                                 }),
                             },
                         )),
@@ -1385,6 +1575,7 @@ mod tests {
                                 partition_id: 2,
                                 status: Some(PartitionStatus {
                                     parquet_max_sequence_number: None,
+                                    max_sequence_number: None,
                                 }),
                             },
                         )),
@@ -1394,6 +1585,7 @@ mod tests {
                                 partition_id: 1,
                                 status: Some(PartitionStatus {
                                     parquet_max_sequence_number: None,
+                                    max_sequence_number: None,
                                 }),
                             },
                         )),
@@ -1473,6 +1665,7 @@ mod tests {
                                     partition_id: 1,
                                     status: Some(PartitionStatus {
                                         parquet_max_sequence_number: Some(11),
+                                        max_sequence_number: None,
                                     }),
                                 },
                             )),
@@ -1502,6 +1695,7 @@ mod tests {
                                     partition_id: 2,
                                     status: Some(PartitionStatus {
                                         parquet_max_sequence_number: Some(21),
+                                        max_sequence_number: None,
                                     }),
                                 },
                             )),
@@ -1526,6 +1720,7 @@ mod tests {
                                     partition_id: 3,
                                     status: Some(PartitionStatus {
                                         parquet_max_sequence_number: Some(31),
+                                        max_sequence_number: None,
                                     }),
                                 },
                             )),
@@ -1705,6 +1900,7 @@ mod tests {
                                     partition_id: 1,
                                     status: Some(PartitionStatus {
                                         parquet_max_sequence_number: Some(11),
+                                        max_sequence_number: None,
                                     }),
                                 },
                             )),
@@ -1748,6 +1944,228 @@ mod tests {
         assert_eq!(p1.chunks.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_flight_replicas_most_recent_wins() {
+        let mock_flight_client = Arc::new(
+            MockFlightClient::new([
+                (
+                    "addr1",
+                    Ok(MockQueryData {
+                        results: vec![
+                            partition_announcement(1, Some(5)),
+                            partition_announcement(2, Some(3)),
+                        ],
+                    }),
+                ),
+                (
+                    "addr2",
+                    Ok(MockQueryData {
+                        results: vec![partition_announcement(1, Some(7))],
+                    }),
+                ),
+            ])
+            .await,
+        );
+        let ingester_conn = mock_flight_client.ingester_conn_with_replicas().await;
+
+        let partitions = get_partitions(&ingester_conn, &[1]).await.unwrap();
+        assert_eq!(partitions.len(), 2);
+
+        // the replica that is further ahead wins
+        let p1 = &partitions[0];
+        assert_eq!(p1.partition_id.get(), 1);
+        assert_eq!(p1.ingester().as_ref(), "addr2");
+        assert_eq!(p1.max_sequence_number, Some(SequenceNumber::new(7)));
+
+        // partitions known to one replica only are kept
+        let p2 = &partitions[1];
+        assert_eq!(p2.partition_id.get(), 2);
+        assert_eq!(p2.ingester().as_ref(), "addr1");
+        assert_eq!(p2.max_sequence_number, Some(SequenceNumber::new(3)));
+    }
+
+    #[tokio::test]
+    async fn test_flight_replica_down() {
+        let mock_flight_client = Arc::new(
+            MockFlightClient::new([
+                (
+                    "addr1",
+                    Err(FlightClientError::Flight {
+                        source: FlightError::GrpcError(tonic::Status::unavailable("down")),
+                    }),
+                ),
+                (
+                    "addr2",
+                    Ok(MockQueryData {
+                        results: vec![partition_announcement(1, Some(7))],
+                    }),
+                ),
+            ])
+            .await,
+        );
+        let ingester_conn = mock_flight_client.ingester_conn_with_replicas().await;
+
+        let partitions = get_partitions(&ingester_conn, &[1]).await.unwrap();
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(partitions[0].ingester().as_ref(), "addr2");
+    }
+
+    #[tokio::test]
+    async fn test_flight_all_replicas_down() {
+        let mock_flight_client = Arc::new(
+            MockFlightClient::new([
+                (
+                    "addr1",
+                    Err(FlightClientError::Flight {
+                        source: FlightError::GrpcError(tonic::Status::unavailable("down")),
+                    }),
+                ),
+                (
+                    "addr2",
+                    Err(FlightClientError::Flight {
+                        source: FlightError::GrpcError(tonic::Status::unavailable("down")),
+                    }),
+                ),
+            ])
+            .await,
+        );
+        let ingester_conn = mock_flight_client.ingester_conn_with_replicas().await;
+
+        let err = get_partitions(&ingester_conn, &[1]).await.unwrap_err();
+        assert_matches!(err, Error::RemoteQuery { .. });
+    }
+
+    #[tokio::test]
+    async fn test_flight_draining_replica() {
+        let mock_flight_client = Arc::new(
+            MockFlightClient::new([
+                (
+                    "addr1",
+                    Ok(MockQueryData {
+                        results: vec![partition_announcement(1, Some(7))],
+                    }),
+                ),
+                (
+                    "addr2",
+                    Ok(MockQueryData {
+                        results: vec![partition_announcement(1, Some(5))],
+                    }),
+                ),
+            ])
+            .await,
+        );
+        let ingester_conn = mock_flight_client.ingester_conn_with_replicas().await;
+        mock_flight_client
+            .set_drain_state("addr1", DrainState::Draining)
+            .await;
+
+        // the draining replica is not queried, even though it is further ahead
+        let partitions = get_partitions(&ingester_conn, &[1]).await.unwrap();
+        assert_eq!(partitions.len(), 1);
+        assert_eq!(partitions[0].ingester().as_ref(), "addr2");
+    }
+
+    #[tokio::test]
+    async fn test_flight_drained_ingester() {
+        let mock_flight_client = Arc::new(
+            MockFlightClient::new([(
+                "addr1",
+                Err(FlightClientError::Flight {
+                    source: FlightError::GrpcError(tonic::Status::unavailable("terminated")),
+                }),
+            )])
+            .await,
+        );
+        let ingester_conn = mock_flight_client.ingester_conn().await;
+        mock_flight_client
+            .set_drain_state("addr1", DrainState::Drained)
+            .await;
+
+        // the drained ingester persisted all its data and is not queried
+        let partitions = get_partitions(&ingester_conn, &[1]).await.unwrap();
+        assert!(partitions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_flight_catalog_shard_owners() {
+        let mock_flight_client = Arc::new(
+            MockFlightClient::new([
+                (
+                    "addr1",
+                    Ok(MockQueryData {
+                        results: vec![partition_announcement(1, Some(7))],
+                    }),
+                ),
+                (
+                    "addr2",
+                    Ok(MockQueryData {
+                        results: vec![partition_announcement(2, Some(5))],
+                    }),
+                ),
+            ])
+            .await,
+        );
+        let ingester_conn = mock_flight_client.ingester_conn_by_catalog();
+        let topic_id = mock_flight_client.topic_id;
+
+        // No ingester owns the shard, its data is persisted.
+        let partitions = get_topic_partitions(&ingester_conn, topic_id, &[1], None)
+            .await
+            .unwrap();
+        assert!(partitions.is_empty());
+
+        // Shard 1 is assigned to one ingester, while another one still holds the lease of
+        // shard 0 it is handing over.
+        {
+            let mut repos = mock_flight_client.catalog.catalog().repositories().await;
+            repos
+                .shards()
+                .record_ingester_heartbeat(topic_id, "addr1", Timestamp::new(0))
+                .await
+                .unwrap();
+            repos
+                .shards()
+                .assign_shards("addr1", &[ShardIndex::new(1)])
+                .await
+                .unwrap();
+            repos
+                .shards()
+                .acquire_lease(
+                    mock_flight_client.shard_ids[0],
+                    "addr2",
+                    Duration::from_secs(60),
+                )
+                .await
+                .unwrap();
+        }
+        mock_flight_client
+            .catalog
+            .mock_time_provider()
+            .inc(Duration::from_secs(1));
+
+        let partitions = get_topic_partitions(&ingester_conn, topic_id, &[0, 1], None)
+            .await
+            .unwrap();
+        let ingesters: Vec<_> = partitions.iter().map(|p| p.ingester().as_ref()).collect();
+        assert_eq!(ingesters, ["addr1", "addr2"]);
+    }
+
+    fn partition_announcement(
+        partition_id: i64,
+        max_sequence_number: Option<i64>,
+    ) -> Result<(LowLevelMessage, IngesterQueryResponseMetadata), FlightError> {
+        Ok((
+            LowLevelMessage::None,
+            IngesterQueryResponseMetadata {
+                partition_id,
+                status: Some(PartitionStatus {
+                    parquet_max_sequence_number: None,
+                    max_sequence_number,
+                }),
+            },
+        ))
+    }
+
     async fn get_partitions(
         ingester_conn: &IngesterConnectionImpl,
         shard_indexes: &[i32],
@@ -1759,12 +2177,22 @@ mod tests {
         ingester_conn: &IngesterConnectionImpl,
         shard_indexes: &[i32],
         span: Option<Span>,
+    ) -> Result<Vec<IngesterPartition>, Error> {
+        get_topic_partitions(ingester_conn, TopicId::new(1), shard_indexes, span).await
+    }
+
+    async fn get_topic_partitions(
+        ingester_conn: &IngesterConnectionImpl,
+        topic_id: TopicId,
+        shard_indexes: &[i32],
+        span: Option<Span>,
     ) -> Result<Vec<IngesterPartition>, Error> {
         let columns = vec![String::from("col")];
         let schema = schema();
         let shard_indexes: Vec<_> = shard_indexes.iter().copied().map(ShardIndex::new).collect();
         ingester_conn
             .partitions(
+                topic_id,
                 &shard_indexes,
                 NamespaceId::new(1),
                 TableId::new(2),
@@ -1813,6 +2241,8 @@ mod tests {
     #[derive(Debug)]
     struct MockFlightClient {
         catalog: Arc<TestCatalog>,
+        topic_id: TopicId,
+        shard_ids: [ShardId; 2],
         responses: Mutex<HashMap<String, Result<MockQueryData, FlightClientError>>>,
     }
 
@@ -1833,6 +2263,8 @@ mod tests {
 
             Self {
                 catalog,
+                topic_id: ns.topic.id,
+                shard_ids: [s0.shard.id, s1.shard.id],
                 responses: Mutex::new(
                     responses
                         .into_iter()
@@ -1842,6 +2274,17 @@ mod tests {
             }
         }
 
+        async fn set_drain_state(&self, address: &str, state: DrainState) {
+            self.catalog
+                .catalog()
+                .repositories()
+                .await
+                .node_drains()
+                .set_state(address, state, Timestamp::new(0))
+                .await
+                .unwrap();
+        }
+
         // Assign one shard per address, sorted consistently.
         // Don't assign any addresses to shard index 0 to test error case
         async fn ingester_conn(self: &Arc<Self>) -> IngesterConnectionImpl {
@@ -1859,6 +2302,40 @@ mod tests {
                 })
                 .collect();
 
+            self.ingester_conn_with_mapping(shard_to_ingesters)
+        }
+
+        // Assign all addresses as replicas to shard index 1.
+        async fn ingester_conn_with_replicas(self: &Arc<Self>) -> IngesterConnectionImpl {
+            let ingester_addresses: BTreeSet<_> =
+                self.responses.lock().await.keys().cloned().collect();
+
+            let replicas = ingester_addresses
+                .iter()
+                .map(|addr| Arc::from(addr.as_str()))
+                .collect();
+
+            self.ingester_conn_with_mapping(HashMap::from([(
+                ShardIndex::new(1),
+                IngesterMapping::Replicas(replicas),
+            )]))
+        }
+
+        // Resolve the owners of the shards from the catalog.
+        fn ingester_conn_by_catalog(self: &Arc<Self>) -> IngesterConnectionImpl {
+            IngesterConnectionImpl {
+                shard_owners: Some(ShardOwners::new(
+                    self.catalog.catalog(),
+                    self.catalog.time_provider(),
+                )),
+                ..self.ingester_conn_with_mapping(HashMap::new())
+            }
+        }
+
+        fn ingester_conn_with_mapping(
+            self: &Arc<Self>,
+            shard_to_ingesters: HashMap<ShardIndex, IngesterMapping>,
+        ) -> IngesterConnectionImpl {
             IngesterConnectionImpl::by_shard_with_flight_client(
                 shard_to_ingesters,
                 Arc::clone(self) as _,
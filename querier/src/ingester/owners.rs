@@ -0,0 +1,203 @@
+//! Ingesters owning the shards of a topic, resolved from the shard assignment
+//! the ingesters maintain in the catalog instead of a static shard to ingester
+//! mapping.
+//!
+//! The owners of a shard are the ingester it is assigned to, and the ingester
+//! holding its lease. While a shard is handed over, the previous ingester
+//! keeps its lease until it persisted the data buffered for the shard, and
+//! the shard is only assigned to the next ingester once the lease is
+//! released, so that the unpersisted data of the shard is always buffered by
+//! one of its owners.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use data_types::{ShardIndex, TopicId};
+use iox_catalog::interface::{Catalog, Error as CatalogError};
+use iox_time::{Time, TimeProvider};
+use observability_deps::tracing::warn;
+use tokio::sync::Mutex;
+
+/// How long the owners of the shards of a topic read from the catalog are
+/// used before they are read again.
+///
+/// Right after a shard was handed over, its new ingester may be missed for up
+/// to this long.
+pub(crate) const SHARD_OWNERS_TTL: Duration = Duration::from_secs(1);
+
+/// The addresses of the ingesters owning each shard of a topic.
+pub(crate) type ShardOwnerMap = HashMap<ShardIndex, Vec<Arc<str>>>;
+
+/// Cache of the owners of the shards of each topic.
+#[derive(Debug)]
+pub(crate) struct ShardOwners {
+    catalog: Arc<dyn Catalog>,
+    time_provider: Arc<dyn TimeProvider>,
+    cached: Mutex<HashMap<TopicId, (Time, Arc<ShardOwnerMap>)>>,
+}
+
+impl ShardOwners {
+    pub(crate) fn new(catalog: Arc<dyn Catalog>, time_provider: Arc<dyn TimeProvider>) -> Self {
+        Self {
+            catalog,
+            time_provider,
+            cached: Default::default(),
+        }
+    }
+
+    /// Get the owners of the shards of the topic `topic_id`, reading them
+    /// from the catalog if they are older than [`SHARD_OWNERS_TTL`].
+    ///
+    /// If the catalog can not be read, the previous owners are used.
+    pub(crate) async fn get(&self, topic_id: TopicId) -> Result<Arc<ShardOwnerMap>, CatalogError> {
+        let mut cached = self.cached.lock().await;
+        let now = self.time_provider.now();
+
+        if let Some((read_at, owners)) = cached.get(&topic_id) {
+            if now < *read_at + SHARD_OWNERS_TTL {
+                return Ok(Arc::clone(owners));
+            }
+        }
+
+        match self.load(topic_id).await {
+            Ok(owners) => {
+                let owners = Arc::new(owners);
+                cached.insert(topic_id, (now, Arc::clone(&owners)));
+                Ok(owners)
+            }
+            Err(e) => match cached.get(&topic_id) {
+                Some((_, owners)) => {
+                    warn!(%e, ?topic_id, "failed to read shard owners, using previous owners");
+                    Ok(Arc::clone(owners))
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    /// Get the addresses of the ingesters owning a shard of any topic.
+    pub(crate) async fn addresses(&self) -> Result<HashSet<Arc<str>>, CatalogError> {
+        let topics = self.catalog.repositories().await.topics().list().await?;
+
+        let mut addresses = HashSet::new();
+        for topic in topics {
+            let owners = self.get(topic.id).await?;
+            addresses.extend(owners.values().flatten().cloned());
+        }
+        Ok(addresses)
+    }
+
+    async fn load(&self, topic_id: TopicId) -> Result<ShardOwnerMap, CatalogError> {
+        let mut repos = self.catalog.repositories().await;
+        let registrations = repos.shards().list_ingesters(topic_id).await?;
+        let leases = repos.shards().list_held_leases(topic_id).await?;
+        let shard_indexes: HashMap<_, _> = repos
+            .shards()
+            .list()
+            .await?
+            .into_iter()
+            .filter(|s| s.topic_id == topic_id)
+            .map(|s| (s.id, s.shard_index))
+            .collect();
+
+        let mut owners = ShardOwnerMap::new();
+        for registration in registrations {
+            let address: Arc<str> = Arc::from(registration.address);
+            for shard_index in registration.shard_indexes {
+                owners
+                    .entry(shard_index)
+                    .or_default()
+                    .push(Arc::clone(&address));
+            }
+        }
+        for lease in leases {
+            if let Some(shard_index) = shard_indexes.get(&lease.shard_id) {
+                let shard_owners = owners.entry(*shard_index).or_default();
+                if !shard_owners.iter().any(|a| **a == *lease.holder) {
+                    shard_owners.push(Arc::from(lease.holder));
+                }
+            }
+        }
+
+        Ok(owners)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use data_types::Timestamp;
+    use iox_tests::util::TestCatalog;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shard_owners() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let shard_0 = ns.create_shard(0).await;
+        let shard_1 = ns.create_shard(1).await;
+        let topic_id = ns.topic.id;
+
+        let owners = ShardOwners::new(catalog.catalog(), catalog.time_provider());
+        assert!(owners.get(topic_id).await.unwrap().is_empty());
+
+        {
+            let mut repos = catalog.catalog().repositories().await;
+            let shards = repos.shards();
+            shards
+                .record_ingester_heartbeat(topic_id, "http://ingester-a:8083", Timestamp::new(0))
+                .await
+                .unwrap();
+            shards
+                .assign_shards("http://ingester-a:8083", &[ShardIndex::new(0)])
+                .await
+                .unwrap();
+            // The previous ingester of shard 1 still holds its lease.
+            shards
+                .acquire_lease(
+                    shard_1.shard.id,
+                    "http://ingester-b:8083",
+                    Duration::from_secs(60),
+                )
+                .await
+                .unwrap();
+            // The lease of shard 0 is held by the ingester it is assigned to.
+            shards
+                .acquire_lease(
+                    shard_0.shard.id,
+                    "http://ingester-a:8083",
+                    Duration::from_secs(60),
+                )
+                .await
+                .unwrap();
+        }
+
+        // The owners are cached.
+        assert!(owners.get(topic_id).await.unwrap().is_empty());
+
+        catalog.mock_time_provider().inc(SHARD_OWNERS_TTL);
+        let expected = ShardOwnerMap::from([
+            (
+                ShardIndex::new(0),
+                vec![Arc::from("http://ingester-a:8083")],
+            ),
+            (
+                ShardIndex::new(1),
+                vec![Arc::from("http://ingester-b:8083")],
+            ),
+        ]);
+        assert_eq!(*owners.get(topic_id).await.unwrap(), expected);
+
+        let addresses = owners.addresses().await.unwrap();
+        assert_eq!(
+            addresses,
+            HashSet::from([
+                Arc::from("http://ingester-a:8083"),
+                Arc::from("http://ingester-b:8083")
+            ])
+        );
+    }
+}
@@ -3,6 +3,7 @@ use async_trait::async_trait;
 use data_types::NamespaceId;
 use data_types::ShardIndex;
 use data_types::TableId;
+use data_types::TopicId;
 use generated_types::influxdata::iox::ingester::v1::GetWriteInfoResponse;
 use iox_query::util::create_basic_summary;
 use parking_lot::Mutex;
@@ -34,6 +35,7 @@ impl MockIngesterConnection {
 impl IngesterConnection for MockIngesterConnection {
     async fn partitions(
         &self,
+        _topic_id: TopicId,
         _shard_indexes: &[ShardIndex],
         _namespace_id: NamespaceId,
         _table_id: TableId,
@@ -13,22 +13,33 @@
 
 mod cache;
 mod chunk;
+mod cold_tables;
 mod database;
+mod external_tables;
 mod handler;
 mod ingester;
 mod namespace;
+mod plan_cache;
 mod poison;
 mod query_log;
+mod query_metrics;
+mod query_pool;
+mod read_policy;
+mod scan_limit;
 mod server;
+mod shard_pins;
 mod system_tables;
 mod table;
+mod table_writer;
 mod tombstone;
+mod write_slo;
 
 pub use cache::CatalogCache as QuerierCatalogCache;
 pub use database::{Error as QuerierDatabaseError, QuerierDatabase};
 pub use handler::{QuerierHandler, QuerierHandlerImpl};
 pub use ingester::{
-    create_ingester_connection_for_testing, create_ingester_connections_by_shard,
+    create_ingester_connection_for_testing, create_ingester_connections_by_catalog,
+    create_ingester_connections_by_shard,
     flight_client::{
         Error as IngesterFlightClientError, FlightClient as IngesterFlightClient,
         QueryData as IngesterFlightClientQueryData,
@@ -36,4 +47,10 @@ pub use ingester::{
     Error as IngesterError, IngesterConnection, IngesterConnectionImpl, IngesterPartition,
 };
 pub use namespace::QuerierNamespace;
+pub use query_pool::{
+    DispatchError as QueryPoolDispatchError, QueryPoolDispatcher, QueryPoolMembership,
+    HEARTBEAT_EXPIRY_INTERVALS as QUERY_POOL_HEARTBEAT_EXPIRY_INTERVALS,
+};
+pub use read_policy::{NamespaceReadPolicy, ReadPolicies};
 pub use server::QuerierServer;
+pub use write_slo::WriteSloProbe;
@@ -3,12 +3,22 @@
 use crate::{
     cache::{namespace::CachedNamespace, CatalogCache},
     chunk::ChunkAdapter,
+    cold_tables::ColdTables,
+    external_tables::NamespaceExternalTables,
     ingester::IngesterConnection,
+    plan_cache::{NamespacePlans, QueryPlanCache},
     query_log::QueryLog,
+    query_metrics::QueryMetrics,
+    read_policy::NamespaceReadPolicy,
+    scan_limit::ScanLimiter,
+    shard_pins::ShardPins,
     table::{PruneMetrics, QuerierTable, QuerierTableArgs},
+    table_writer::DEFAULT_MAX_REQUEST_SIZE,
+    write_slo::WriteSloLog,
 };
 use data_types::{NamespaceId, ShardIndex};
-use iox_query::exec::Executor;
+use iox_catalog::usage::UsageAccumulator;
+use iox_query::exec::{Executor, DEFAULT_MAX_GAP_FILL_BUCKETS};
 use sharder::JumpHash;
 use std::{collections::HashMap, sync::Arc};
 
@@ -46,6 +56,35 @@ pub struct QuerierNamespace {
 
     /// Query log.
     query_log: Arc<QueryLog>,
+
+    /// Metrics of the completed queries.
+    query_metrics: Arc<QueryMetrics>,
+
+    /// Log of the write path latency probes.
+    write_slo_log: Arc<WriteSloLog>,
+
+    /// Accumulator of the usage of this namespace, if usage accounting is enabled.
+    usage: Option<Arc<UsageAccumulator>>,
+
+    /// External tables of this namespace.
+    external_tables: Arc<NamespaceExternalTables>,
+
+    /// HTTP API address of the router that query results are written to, if enabled.
+    router_http_address: Option<Arc<str>>,
+
+    /// Maximum size of a request writing query results to the router.
+    router_max_request_size: usize,
+
+    /// Read policy of this namespace, if reads are restricted.
+    read_policy: Option<Arc<NamespaceReadPolicy>>,
+
+    /// Limits for concurrent object store scans.
+    scan_limiter: Arc<ScanLimiter>,
+
+    plan_cache: Option<Arc<NamespacePlans>>,
+
+    /// Maximum number of time buckets per series a gap-filling query may fill.
+    max_gap_fill_buckets: usize,
 }
 
 impl QuerierNamespace {
@@ -58,9 +97,21 @@ impl QuerierNamespace {
         exec: Arc<Executor>,
         ingester_connection: Option<Arc<dyn IngesterConnection>>,
         query_log: Arc<QueryLog>,
+        query_metrics: Arc<QueryMetrics>,
+        write_slo_log: Arc<WriteSloLog>,
+        usage: Option<Arc<UsageAccumulator>>,
         sharder: Arc<JumpHash<Arc<ShardIndex>>>,
         max_table_query_bytes: usize,
         prune_metrics: Arc<PruneMetrics>,
+        external_tables: Arc<NamespaceExternalTables>,
+        router_http_address: Option<Arc<str>>,
+        router_max_request_size: usize,
+        read_policy: Option<Arc<NamespaceReadPolicy>>,
+        scan_limiter: Arc<ScanLimiter>,
+        plan_cache: Option<Arc<QueryPlanCache>>,
+        cold_tables: Option<Arc<ColdTables>>,
+        shard_pins: Option<Arc<ShardPins>>,
+        max_gap_fill_buckets: usize,
     ) -> Self {
         let tables: HashMap<_, _> = ns
             .tables
@@ -70,6 +121,7 @@ impl QuerierNamespace {
                     sharder: Arc::clone(&sharder),
                     namespace_id: ns.id,
                     namespace_name: Arc::clone(&name),
+                    topic_id: ns.topic_id,
                     table_id: cached_table.id,
                     table_name: Arc::clone(table_name),
                     schema: Arc::clone(&cached_table.schema),
@@ -78,6 +130,12 @@ impl QuerierNamespace {
                     exec: Arc::clone(&exec),
                     max_query_bytes: max_table_query_bytes,
                     prune_metrics: Arc::clone(&prune_metrics),
+                    read_policy: read_policy.clone(),
+                    query_ranges: ns.query_ranges,
+                    row_ttl: ns.row_ttl.clone(),
+                    usage: usage.clone(),
+                    cold_tables: cold_tables.clone(),
+                    shard_pins: shard_pins.clone(),
                 }));
 
                 (Arc::clone(table_name), table)
@@ -85,6 +143,7 @@ impl QuerierNamespace {
             .collect();
 
         let id = ns.id;
+        let plan_cache = plan_cache.map(|cache| Arc::new(cache.namespace(ns)));
 
         Self {
             id,
@@ -93,6 +152,16 @@ impl QuerierNamespace {
             exec,
             catalog_cache: Arc::clone(chunk_adapter.catalog_cache()),
             query_log,
+            query_metrics,
+            write_slo_log,
+            usage,
+            external_tables,
+            router_http_address,
+            router_max_request_size,
+            read_policy,
+            scan_limiter,
+            plan_cache,
+            max_gap_fill_buckets,
         }
     }
 
@@ -107,10 +176,12 @@ impl QuerierNamespace {
         ingester_connection: Option<Arc<dyn IngesterConnection>>,
         sharder: Arc<JumpHash<Arc<ShardIndex>>>,
         max_table_query_bytes: usize,
+        read_policy: Option<Arc<NamespaceReadPolicy>>,
     ) -> Self {
         let time_provider = catalog_cache.time_provider();
         let chunk_adapter = Arc::new(ChunkAdapter::new(catalog_cache, metric_registry));
         let query_log = Arc::new(QueryLog::new(10, time_provider));
+        let query_metrics = Arc::new(QueryMetrics::new(&chunk_adapter.metric_registry(), None));
         let prune_metrics = Arc::new(PruneMetrics::new(&chunk_adapter.metric_registry()));
 
         Self::new(
@@ -120,9 +191,21 @@ impl QuerierNamespace {
             exec,
             ingester_connection,
             query_log,
+            query_metrics,
+            Arc::new(WriteSloLog::default()),
+            None,
             sharder,
             max_table_query_bytes,
             prune_metrics,
+            Arc::new(NamespaceExternalTables::default()),
+            None,
+            DEFAULT_MAX_REQUEST_SIZE,
+            read_policy,
+            Arc::new(ScanLimiter::default()),
+            None,
+            None,
+            None,
+            DEFAULT_MAX_GAP_FILL_BUCKETS,
         )
     }
 
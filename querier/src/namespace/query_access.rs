@@ -1,10 +1,13 @@
 //! This module contains implementations of [`iox_query`] interfaces for [QuerierNamespace].
 
 use crate::{
+    external_tables::{NamespaceExternalTables, EXTERNAL_SCHEMA},
     namespace::QuerierNamespace,
     query_log::QueryLog,
     system_tables::{SystemSchemaProvider, SYSTEM_SCHEMA},
     table::QuerierTable,
+    table_writer::RouterTableWriter,
+    write_slo::WriteSloLog,
 };
 use async_trait::async_trait;
 use data_types::NamespaceId;
@@ -14,11 +17,13 @@ use datafusion::{
     error::DataFusionError,
 };
 use datafusion_util::config::DEFAULT_SCHEMA;
+use iox_catalog::{interface::Catalog, usage::UsageAccumulator};
 use iox_query::{
     exec::{ExecutionContextProvider, ExecutorType, IOxSessionContext},
     QueryChunk, QueryCompletedToken, QueryNamespace, QueryText,
 };
 use observability_deps::tracing::{debug, trace};
+use parking_lot::RwLock;
 use predicate::{rpc_predicate::QueryNamespaceMeta, Predicate};
 use schema::Schema;
 use std::{any::Any, collections::HashMap, sync::Arc};
@@ -56,11 +61,27 @@ impl QueryNamespace for QuerierNamespace {
             }
         };
 
+        // Chunks are handed out unfiltered, so restricted identities are limited to SQL queries,
+        // whose table scans apply the row filter.
+        if table
+            .row_filter(ctx.identity())
+            .map_err(|e| DataFusionError::Plan(e.to_string()))?
+            .is_some()
+        {
+            return Err(DataFusionError::Plan(format!(
+                "namespace {} restricts the rows this identity may read, which is only supported \
+                for SQL queries",
+                self.name
+            )));
+        }
+
         let mut chunks = table
             .chunks(
                 predicate,
                 ctx.span().map(|span| span.child("querier table chunks")),
                 projection,
+                ctx.max_unpersisted_staleness(),
+                ctx.persisted_watermarks().as_deref(),
             )
             .await?;
 
@@ -89,9 +110,19 @@ impl QueryNamespace for QuerierNamespace {
         // When the query token is dropped the query entry's completion time
         // will be set.
         let query_log = Arc::clone(&self.query_log);
+        let query_metrics = Arc::clone(&self.query_metrics);
+        let namespace = Arc::clone(&self.name);
         let trace_id = ctx.span().map(|s| s.ctx.trace_id);
         let entry = query_log.push(self.id, query_type, query_text, trace_id);
-        QueryCompletedToken::new(move |success| query_log.set_completed(entry, success))
+        if let Some(usage) = &self.usage {
+            usage.record_query(self.id);
+        }
+        QueryCompletedToken::new(move |success| {
+            query_log.set_completed(Arc::clone(&entry), success);
+            if let Some(duration) = entry.query_completed_duration() {
+                query_metrics.record(&namespace, &entry.query_type, success, duration);
+            }
+        })
     }
 
     fn as_meta(&self) -> &dyn QueryNamespaceMeta {
@@ -103,19 +134,43 @@ pub struct QuerierCatalogProvider {
     /// Namespace ID.
     namespace_id: NamespaceId,
 
+    /// Namespace name.
+    namespace_name: Arc<str>,
+
     /// A snapshot of all tables.
     tables: Arc<HashMap<Arc<str>, Arc<QuerierTable>>>,
 
     /// Query log.
     query_log: Arc<QueryLog>,
+
+    /// Log of the write path latency probes.
+    write_slo_log: Arc<WriteSloLog>,
+
+    /// Accumulator of the usage of the namespace, if usage accounting is enabled.
+    usage: Option<Arc<UsageAccumulator>>,
+
+    /// Catalog, read by the system tables that are not cached.
+    catalog: Arc<dyn Catalog>,
+
+    /// External tables of the namespace.
+    external_tables: Arc<NamespaceExternalTables>,
+
+    /// Schemas of other namespaces registered for cross-namespace queries, keyed by namespace name.
+    other_namespaces: RwLock<HashMap<String, Arc<dyn SchemaProvider>>>,
 }
 
 impl QuerierCatalogProvider {
     fn from_namespace(namespace: &QuerierNamespace) -> Self {
         Self {
             namespace_id: namespace.id,
+            namespace_name: Arc::clone(&namespace.name),
             tables: Arc::clone(&namespace.tables),
             query_log: Arc::clone(&namespace.query_log),
+            write_slo_log: Arc::clone(&namespace.write_slo_log),
+            usage: namespace.usage.clone(),
+            catalog: namespace.catalog_cache.catalog(),
+            external_tables: Arc::clone(&namespace.external_tables),
+            other_namespaces: Default::default(),
         }
     }
 }
@@ -126,7 +181,15 @@ impl CatalogProvider for QuerierCatalogProvider {
     }
 
     fn schema_names(&self) -> Vec<String> {
-        vec![DEFAULT_SCHEMA.to_string(), SYSTEM_SCHEMA.to_string()]
+        let mut names = vec![
+            DEFAULT_SCHEMA.to_string(),
+            SYSTEM_SCHEMA.to_string(),
+            EXTERNAL_SCHEMA.to_string(),
+        ];
+        let mut others: Vec<_> = self.other_namespaces.read().keys().cloned().collect();
+        others.sort();
+        names.extend(others);
+        names
     }
 
     fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>> {
@@ -136,9 +199,34 @@ impl CatalogProvider for QuerierCatalogProvider {
             })),
             SYSTEM_SCHEMA => Some(Arc::new(SystemSchemaProvider::new(
                 Arc::clone(&self.query_log),
+                Arc::clone(&self.write_slo_log),
+                self.usage.clone(),
+                Arc::clone(&self.catalog),
                 self.namespace_id,
+                Arc::clone(&self.namespace_name),
+                self.tables
+                    .values()
+                    .map(|table| (table.id(), Arc::clone(table.table_name())))
+                    .collect(),
             ))),
-            _ => None,
+            EXTERNAL_SCHEMA => Some(Arc::clone(&self.external_tables) as _),
+            _ => self.other_namespaces.read().get(name).map(Arc::clone),
+        }
+    }
+
+    fn register_schema(
+        &self,
+        name: &str,
+        schema: Arc<dyn SchemaProvider>,
+    ) -> Result<Option<Arc<dyn SchemaProvider>>, DataFusionError> {
+        match name {
+            DEFAULT_SCHEMA | SYSTEM_SCHEMA | EXTERNAL_SCHEMA => Err(DataFusionError::Plan(
+                format!("Cannot register namespace as reserved schema '{name}'"),
+            )),
+            _ => Ok(self
+                .other_namespaces
+                .write()
+                .insert(name.to_string(), schema)),
         }
     }
 }
@@ -185,29 +273,60 @@ impl SchemaProvider for UserSchemaProvider {
 
 impl ExecutionContextProvider for QuerierNamespace {
     fn new_query_context(&self, span_ctx: Option<SpanContext>) -> IOxSessionContext {
-        self.exec
+        let ctx = self
+            .exec
             .new_execution_config(ExecutorType::Query)
             .with_default_catalog(Arc::new(QuerierCatalogProvider::from_namespace(self)) as _)
             .with_span_context(span_ctx)
             .build()
+            .with_external_table_locations(self.external_tables.allowed_locations().to_vec())
+            .with_scan_limits(self.scan_limiter.query_limits())
+            .with_max_gap_fill_buckets(self.max_gap_fill_buckets)
+            .with_parquet_metadata(Some(Arc::clone(self.catalog_cache.parquet_metadata()) as _));
+
+        let ctx = match &self.plan_cache {
+            Some(plan_cache) => ctx.with_plan_cache(Arc::clone(plan_cache) as _),
+            None => ctx,
+        };
+
+        match &self.router_http_address {
+            Some(router_http_address) => ctx.with_table_writer(Arc::new(RouterTableWriter::new(
+                Arc::clone(router_http_address),
+                Arc::clone(&self.name),
+                self.router_max_request_size,
+            ))),
+            None => ctx,
+        }
+    }
+
+    fn user_schema_provider(&self) -> Option<Arc<dyn SchemaProvider>> {
+        Some(Arc::new(UserSchemaProvider {
+            tables: Arc::clone(&self.tables),
+        }))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::namespace::test_util::{
-        clear_parquet_cache, querier_namespace, querier_namespace_with_limit,
+    use crate::{
+        namespace::test_util::{
+            clear_parquet_cache, querier_namespace, querier_namespace_with_limit,
+            querier_namespace_with_read_policy,
+        },
+        NamespaceReadPolicy,
     };
     use arrow::record_batch::RecordBatch;
     use arrow_util::assert_batches_sorted_eq;
-    use data_types::ColumnType;
+    use data_types::{ColumnType, ParquetFileId};
     use datafusion::common::DataFusionError;
     use iox_query::frontend::sql::SqlQueryPlanner;
     use iox_tests::util::{TestCatalog, TestParquetFileBuilder};
+    use iox_time::Time;
     use metric::{Observation, RawReporter};
     use regex::Regex;
     use snafu::{ResultExt, Snafu};
+    use test_helpers::assert_contains;
     use trace::{span::SpanStatus, RingBufferTraceCollector};
 
     #[tokio::test]
@@ -635,6 +754,414 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_cross_namespace_query() {
+        test_helpers::maybe_start_logging();
+
+        let catalog = TestCatalog::new();
+
+        let ns1 = catalog.create_namespace_1hr_retention("ns1").await;
+        let ns2 = catalog.create_namespace_1hr_retention("ns2").await;
+
+        let shard1 = ns1.create_shard(1).await;
+        let table_cpu = ns1.create_table("cpu").await;
+        table_cpu.create_column("host", ColumnType::Tag).await;
+        table_cpu.create_column("time", ColumnType::Time).await;
+        table_cpu.create_column("load", ColumnType::F64).await;
+        let partition_cpu = table_cpu.with_shard(&shard1).create_partition("a").await;
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("cpu,host=a load=1 11")
+            .with_max_seq(1)
+            .with_min_time(11)
+            .with_max_time(11);
+        partition_cpu.create_parquet_file(builder).await;
+
+        let shard2 = ns2.create_shard(1).await;
+        let table_events = ns2.create_table("events").await;
+        table_events.create_column("host", ColumnType::Tag).await;
+        table_events.create_column("time", ColumnType::Time).await;
+        table_events.create_column("msg", ColumnType::String).await;
+        let partition_events = table_events.with_shard(&shard2).create_partition("a").await;
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("events,host=a msg=\"reboot\" 11")
+            .with_max_seq(1)
+            .with_min_time(11)
+            .with_max_time(11);
+        partition_events.create_parquet_file(builder).await;
+
+        let querier_ns1 = Arc::new(querier_namespace(&ns1).await);
+        let querier_ns2 = Arc::new(querier_namespace(&ns2).await);
+
+        let ctx = querier_ns1.new_query_context(None);
+        ctx.register_namespace_schema("ns2", querier_ns2.user_schema_provider().unwrap())
+            .unwrap();
+
+        let physical_plan = SqlQueryPlanner::default()
+            .query(
+                "SELECT cpu.host, cpu.load, events.msg FROM cpu JOIN \"ns2\".events ON cpu.host = events.host",
+                &ctx,
+            )
+            .await
+            .unwrap();
+        let results = ctx.collect(physical_plan).await.unwrap();
+        assert_batches_sorted_eq!(
+            &[
+                "+------+------+--------+",
+                "| host | load | msg    |",
+                "+------+------+--------+",
+                "| a    | 1    | reboot |",
+                "+------+------+--------+",
+            ],
+            &results
+        );
+
+        // reserved and already registered names are rejected
+        ctx.register_namespace_schema("system", querier_ns2.user_schema_provider().unwrap())
+            .unwrap_err();
+        ctx.register_namespace_schema("ns2", querier_ns2.user_schema_provider().unwrap())
+            .unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_read_policy() {
+        test_helpers::maybe_start_logging();
+
+        let catalog = TestCatalog::new();
+
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let shard = ns.create_shard(1).await;
+
+        let table_cpu = ns.create_table("cpu").await;
+        table_cpu.create_column("host", ColumnType::Tag).await;
+        table_cpu.create_column("region", ColumnType::Tag).await;
+        table_cpu.create_column("time", ColumnType::Time).await;
+        table_cpu.create_column("load", ColumnType::F64).await;
+        let partition_cpu = table_cpu.with_shard(&shard).create_partition("a").await;
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("cpu,host=a,region=eu load=1 11\ncpu,host=b,region=us load=2 22")
+            .with_max_seq(1)
+            .with_min_time(11)
+            .with_max_time(22);
+        partition_cpu.create_parquet_file(builder).await;
+
+        let table_mem = ns.create_table("mem").await;
+        table_mem.create_column("host", ColumnType::Tag).await;
+        table_mem.create_column("time", ColumnType::Time).await;
+        table_mem.create_column("perc", ColumnType::F64).await;
+        let partition_mem = table_mem.with_shard(&shard).create_partition("a").await;
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("mem,host=a perc=50 11")
+            .with_max_seq(1)
+            .with_min_time(11)
+            .with_max_time(11);
+        partition_mem.create_parquet_file(builder).await;
+
+        let policy = NamespaceReadPolicy::default()
+            .with_identity(
+                "alice",
+                [("region".to_string(), vec!["eu".to_string()])].into(),
+            )
+            .with_identity("admin", Default::default());
+        let querier_namespace = Arc::new(querier_namespace_with_read_policy(&ns, policy).await);
+
+        // the filtered tag is dropped again if it is not selected
+        let results = run_with_identity(
+            &querier_namespace,
+            "SELECT host, load FROM cpu",
+            Some("alice"),
+        )
+        .await
+        .unwrap();
+        assert_batches_sorted_eq!(
+            &[
+                "+------+------+",
+                "| host | load |",
+                "+------+------+",
+                "| a    | 1    |",
+                "+------+------+",
+            ],
+            &results
+        );
+
+        // tables without the filtered tag return no rows
+        let results = run_with_identity(&querier_namespace, "SELECT * FROM mem", Some("alice"))
+            .await
+            .unwrap();
+        assert_batches_sorted_eq!(&["++", "++"], &results);
+
+        let results = run_with_identity(
+            &querier_namespace,
+            "SELECT host, load FROM cpu",
+            Some("admin"),
+        )
+        .await
+        .unwrap();
+        assert_batches_sorted_eq!(
+            &[
+                "+------+------+",
+                "| host | load |",
+                "+------+------+",
+                "| a    | 1    |",
+                "| b    | 2    |",
+                "+------+------+",
+            ],
+            &results
+        );
+
+        let err = run_with_identity(&querier_namespace, "SELECT * FROM cpu", Some("mallory"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot build plan: Error during planning: identity mallory may not read namespace ns",
+        );
+
+        let err = run_with_identity(&querier_namespace, "SELECT * FROM cpu", None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot build plan: Error during planning: namespace ns requires an identity",
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_ranges() {
+        test_helpers::maybe_start_logging();
+
+        let catalog = TestCatalog::new();
+        catalog
+            .mock_time_provider()
+            .set(Time::from_timestamp_nanos(30));
+
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let shard = ns.create_shard(1).await;
+
+        let table = ns.create_table("cpu").await;
+        table.create_column("host", ColumnType::Tag).await;
+        table.create_column("time", ColumnType::Time).await;
+        table.create_column("load", ColumnType::F64).await;
+        let partition = table.with_shard(&shard).create_partition("a").await;
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("cpu,host=a load=1 11\ncpu,host=b load=2 22")
+            .with_max_seq(1)
+            .with_min_time(11)
+            .with_max_time(22);
+        partition.create_parquet_file(builder).await;
+
+        catalog
+            .catalog
+            .repositories()
+            .await
+            .namespaces()
+            .update_query_ranges("ns", Some(10), Some(15))
+            .await
+            .unwrap();
+        let querier_namespace = Arc::new(querier_namespace(&ns).await);
+
+        // queries without a lower bound on the time cover the default range
+        assert_query(
+            &querier_namespace,
+            "SELECT host, load FROM cpu",
+            &[
+                "+------+------+",
+                "| host | load |",
+                "+------+------+",
+                "| b    | 2    |",
+                "+------+------+",
+            ],
+        )
+        .await;
+
+        assert_query(
+            &querier_namespace,
+            "SELECT host, load FROM cpu WHERE time >= to_timestamp(11)",
+            &[
+                "+------+------+",
+                "| host | load |",
+                "+------+------+",
+                "| a    | 1    |",
+                "| b    | 2    |",
+                "+------+------+",
+            ],
+        )
+        .await;
+
+        let err = run_res(
+            &querier_namespace,
+            "SELECT host, load FROM cpu WHERE time >= to_timestamp(0)",
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert_contains!(
+            err.to_string(),
+            "query of table cpu covers a time range of 30ns, exceeding the maximum of 15ns"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_row_ttl() {
+        test_helpers::maybe_start_logging();
+
+        let catalog = TestCatalog::new();
+        catalog
+            .mock_time_provider()
+            .set(Time::from_timestamp_nanos(30));
+
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let shard = ns.create_shard(1).await;
+
+        let table = ns.create_table("cpu").await;
+        table.create_column("host", ColumnType::Tag).await;
+        table.create_column("time", ColumnType::Time).await;
+        table.create_column("load", ColumnType::F64).await;
+        table.create_column("expires", ColumnType::I64).await;
+        let partition = table.with_shard(&shard).create_partition("a").await;
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol(
+                "cpu,host=a load=1 11\n\
+                 cpu,host=b load=2,expires=25i 22\n\
+                 cpu,host=c load=3,expires=40i 23",
+            )
+            .with_max_seq(1)
+            .with_min_time(11)
+            .with_max_time(23);
+        partition.create_parquet_file(builder).await;
+
+        // rows expire at the time of their expiry field, if they have one
+        catalog
+            .catalog
+            .repositories()
+            .await
+            .namespaces()
+            .update_row_ttl("ns", None, Some("expires".to_string()))
+            .await
+            .unwrap();
+        let querier_namespace = Arc::new(querier_namespace(&ns).await);
+        assert_query(
+            &querier_namespace,
+            "SELECT host, load FROM cpu",
+            &[
+                "+------+------+",
+                "| host | load |",
+                "+------+------+",
+                "| a    | 1    |",
+                "| c    | 3    |",
+                "+------+------+",
+            ],
+        )
+        .await;
+
+        // ... and a fixed time after their timestamp
+        catalog
+            .catalog
+            .repositories()
+            .await
+            .namespaces()
+            .update_row_ttl("ns", Some(15), Some("expires".to_string()))
+            .await
+            .unwrap();
+        let querier_namespace = Arc::new(querier_namespace(&ns).await);
+        assert_query(
+            &querier_namespace,
+            "SELECT host, load FROM cpu",
+            &[
+                "+------+------+",
+                "| host | load |",
+                "+------+------+",
+                "| c    | 3    |",
+                "+------+------+",
+            ],
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_debug_parquet_files() {
+        test_helpers::maybe_start_logging();
+
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let shard = ns.create_shard(1).await;
+
+        let table = ns.create_table("cpu").await;
+        table.create_column("host", ColumnType::Tag).await;
+        table.create_column("time", ColumnType::Time).await;
+        table.create_column("load", ColumnType::F64).await;
+        let partition = table.with_shard(&shard).create_partition("a").await;
+
+        // the second file overwrites the row of host `a`
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("cpu,host=a load=1 11\ncpu,host=b load=2 11")
+            .with_max_seq(1)
+            .with_min_time(11)
+            .with_max_time(11);
+        let file1 = partition.create_parquet_file(builder).await.parquet_file;
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("cpu,host=a load=3 11")
+            .with_max_seq(2)
+            .with_min_time(11)
+            .with_max_time(11);
+        let file2 = partition.create_parquet_file(builder).await.parquet_file;
+
+        let querier_namespace = Arc::new(querier_namespace(&ns).await);
+        assert_query(
+            &querier_namespace,
+            "SELECT host, load FROM cpu",
+            &[
+                "+------+------+",
+                "| host | load |",
+                "+------+------+",
+                "| a    | 3    |",
+                "| b    | 2    |",
+                "+------+------+",
+            ],
+        )
+        .await;
+
+        // a debug scan reads the raw rows of the requested files
+        let results = run_with_debug_parquet_files(
+            &querier_namespace,
+            "SELECT host, load FROM cpu",
+            [file1.id],
+        )
+        .await
+        .unwrap();
+        assert_batches_sorted_eq!(
+            &[
+                "+------+------+",
+                "| host | load |",
+                "+------+------+",
+                "| a    | 1    |",
+                "| b    | 2    |",
+                "+------+------+",
+            ],
+            &results
+        );
+
+        // ... without deduplication
+        let results = run_with_debug_parquet_files(
+            &querier_namespace,
+            "SELECT host, load FROM cpu",
+            [file1.id, file2.id],
+        )
+        .await
+        .unwrap();
+        assert_batches_sorted_eq!(
+            &[
+                "+------+------+",
+                "| host | load |",
+                "+------+------+",
+                "| a    | 1    |",
+                "| a    | 3    |",
+                "| b    | 2    |",
+                "+------+------+",
+            ],
+            &results
+        );
+    }
+
     async fn assert_query(
         querier_namespace: &Arc<QuerierNamespace>,
         sql: &str,
@@ -706,4 +1233,34 @@ mod tests {
 
         ctx.collect(physical_plan).await.context(RunSnafu)
     }
+
+    async fn run_with_identity(
+        querier_namespace: &Arc<QuerierNamespace>,
+        sql: &str,
+        identity: Option<&str>,
+    ) -> Result<Vec<RecordBatch>, RunError> {
+        let planner = SqlQueryPlanner::default();
+        let ctx = querier_namespace
+            .new_query_context(None)
+            .with_identity(identity);
+
+        let physical_plan = planner.query(sql, &ctx).await.context(BuildSnafu)?;
+
+        ctx.collect(physical_plan).await.context(RunSnafu)
+    }
+
+    async fn run_with_debug_parquet_files(
+        querier_namespace: &Arc<QuerierNamespace>,
+        sql: &str,
+        files: impl IntoIterator<Item = ParquetFileId>,
+    ) -> Result<Vec<RecordBatch>, RunError> {
+        let planner = SqlQueryPlanner::default();
+        let ctx = querier_namespace
+            .new_query_context(None)
+            .with_debug_parquet_files(Some(files.into_iter().collect()));
+
+        let physical_plan = planner.query(sql, &ctx).await.context(BuildSnafu)?;
+
+        ctx.collect(physical_plan).await.context(RunSnafu)
+    }
 }
@@ -1,6 +1,7 @@
 use super::QuerierNamespace;
 use crate::{
-    cache::namespace::CachedNamespace, create_ingester_connection_for_testing, QuerierCatalogCache,
+    cache::namespace::CachedNamespace, create_ingester_connection_for_testing, NamespaceReadPolicy,
+    QuerierCatalogCache,
 };
 use data_types::{ShardIndex, TableId};
 use iox_catalog::interface::get_schema_by_name;
@@ -19,6 +20,22 @@ pub async fn querier_namespace(ns: &Arc<TestNamespace>) -> QuerierNamespace {
 pub async fn querier_namespace_with_limit(
     ns: &Arc<TestNamespace>,
     max_table_query_bytes: usize,
+) -> QuerierNamespace {
+    build_querier_namespace(ns, max_table_query_bytes, None).await
+}
+
+/// Create [`QuerierNamespace`] for testing with a read policy.
+pub async fn querier_namespace_with_read_policy(
+    ns: &Arc<TestNamespace>,
+    read_policy: NamespaceReadPolicy,
+) -> QuerierNamespace {
+    build_querier_namespace(ns, usize::MAX, Some(Arc::new(read_policy))).await
+}
+
+async fn build_querier_namespace(
+    ns: &Arc<TestNamespace>,
+    max_table_query_bytes: usize,
+    read_policy: Option<Arc<NamespaceReadPolicy>>,
 ) -> QuerierNamespace {
     let mut repos = ns.catalog.catalog.repositories().await;
     let schema = get_schema_by_name(&ns.namespace.name, repos.as_mut())
@@ -58,6 +75,7 @@ pub async fn querier_namespace_with_limit(
         Some(create_ingester_connection_for_testing()),
         sharder,
         max_table_query_bytes,
+        read_policy,
     )
 }
 
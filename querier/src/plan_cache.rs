@@ -0,0 +1,319 @@
+//! Cache of the logical plans of queries.
+
+use crate::cache::namespace::CachedNamespace;
+use data_types::NamespaceId;
+use datafusion::logical_expr::LogicalPlan;
+use iox_query::exec::PlanCache;
+use metric::U64Counter;
+use parking_lot::Mutex;
+use std::{cmp::Ordering, collections::HashMap, num::NonZeroUsize, sync::Arc};
+
+/// Caches the logical plans of the queries of all namespaces.
+///
+/// Planning dominates the latency of small queries that are issued over and over again, e.g. by
+/// dashboards. Plans are cached by namespace and normalized statement, and are only used for
+/// queries against the same schema generation of the namespace. Once a newer generation of a
+/// namespace is seen, the plans of the older generation are dropped. Since not every schema change
+/// bumps the generation (e.g. columns created directly in the catalog), a cached plan is also only
+/// used if the schema of the namespace is unchanged.
+///
+/// Up to `max_entries` plans are kept, evicting the least recently used one. Lookups are counted
+/// by the `query_plan_cache_get` metric, by `status` (`hit` or `miss`).
+#[derive(Debug)]
+pub struct QueryPlanCache {
+    /// Maximum number of cached plans across all namespaces.
+    max_entries: usize,
+
+    state: Mutex<State>,
+
+    hits: U64Counter,
+    misses: U64Counter,
+}
+
+impl QueryPlanCache {
+    /// Create an empty cache holding up to `max_entries` plans.
+    pub fn new(max_entries: NonZeroUsize, metric_registry: &metric::Registry) -> Self {
+        let metric = metric_registry.register_metric::<U64Counter>(
+            "query_plan_cache_get",
+            "Lookups of the logical plans of queries in the plan cache",
+        );
+
+        Self {
+            max_entries: max_entries.get(),
+            state: Default::default(),
+            hits: metric.recorder(&[("status", "hit")]),
+            misses: metric.recorder(&[("status", "miss")]),
+        }
+    }
+
+    /// The plans of the queries against `namespace`.
+    pub(crate) fn namespace(self: &Arc<Self>, namespace: Arc<CachedNamespace>) -> NamespacePlans {
+        NamespacePlans {
+            cache: Arc::clone(self),
+            namespace,
+        }
+    }
+
+    fn get(&self, namespace: &Arc<CachedNamespace>, statement: &str) -> Option<LogicalPlan> {
+        let mut state = self.state.lock();
+        state.clock += 1;
+        let clock = state.clock;
+
+        let plan = state.plans(namespace).and_then(|plans| {
+            let entry = plans.get_mut(statement)?;
+            if !Arc::ptr_eq(&entry.namespace, namespace) && entry.namespace != *namespace {
+                return None;
+            }
+            entry.last_used = clock;
+            Some(entry.plan.clone())
+        });
+
+        match plan {
+            Some(_) => self.hits.inc(1),
+            None => self.misses.inc(1),
+        }
+        plan
+    }
+
+    fn put(&self, namespace: &Arc<CachedNamespace>, statement: &str, plan: LogicalPlan) {
+        let mut state = self.state.lock();
+        state.clock += 1;
+        let entry = Entry {
+            namespace: Arc::clone(namespace),
+            plan,
+            last_used: state.clock,
+        };
+
+        let added = match state.plans(namespace) {
+            Some(plans) => plans.insert(statement.to_string(), entry).is_none(),
+            // planned against an outdated schema
+            None => return,
+        };
+        if added {
+            state.len += 1;
+        }
+
+        while state.len > self.max_entries {
+            state.evict_least_recently_used();
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.state.lock().len
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Cached plans by namespace.
+    namespaces: HashMap<NamespaceId, GenerationPlans>,
+
+    /// Total number of cached plans.
+    len: usize,
+
+    /// Incremented on every access, to find the least recently used plan.
+    clock: u64,
+}
+
+impl State {
+    /// The cached plans of `namespace` by statement.
+    ///
+    /// Drops the cached plans of older generations of the namespace. Returns `None` if plans of a
+    /// newer generation are cached.
+    fn plans(&mut self, namespace: &CachedNamespace) -> Option<&mut HashMap<String, Entry>> {
+        let cached = self
+            .namespaces
+            .entry(namespace.id)
+            .or_insert_with(|| GenerationPlans {
+                generation: namespace.generation,
+                plans: Default::default(),
+            });
+
+        match cached.generation.cmp(&namespace.generation) {
+            Ordering::Less => {
+                self.len -= cached.plans.len();
+                cached.plans.clear();
+                cached.generation = namespace.generation;
+                Some(&mut cached.plans)
+            }
+            Ordering::Equal => Some(&mut cached.plans),
+            Ordering::Greater => None,
+        }
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let oldest = self
+            .namespaces
+            .iter()
+            .flat_map(|(id, cached)| {
+                cached
+                    .plans
+                    .iter()
+                    .map(move |(statement, entry)| (entry.last_used, *id, statement))
+            })
+            .min_by_key(|(last_used, _, _)| *last_used)
+            .map(|(_, id, statement)| (id, statement.clone()));
+
+        if let Some((id, statement)) = oldest {
+            if let Some(cached) = self.namespaces.get_mut(&id) {
+                cached.plans.remove(&statement);
+                self.len -= 1;
+            }
+        }
+    }
+}
+
+/// The cached plans of a single schema generation of a namespace.
+#[derive(Debug)]
+struct GenerationPlans {
+    generation: i64,
+    plans: HashMap<String, Entry>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// Schema of the namespace the plan was created for.
+    namespace: Arc<CachedNamespace>,
+    plan: LogicalPlan,
+    last_used: u64,
+}
+
+/// The [`PlanCache`] of the queries against a namespace.
+#[derive(Debug)]
+pub(crate) struct NamespacePlans {
+    cache: Arc<QueryPlanCache>,
+    namespace: Arc<CachedNamespace>,
+}
+
+impl PlanCache for NamespacePlans {
+    fn get(&self, statement: &str) -> Option<LogicalPlan> {
+        self.cache.get(&self.namespace, statement)
+    }
+
+    fn put(&self, statement: &str, plan: LogicalPlan) {
+        self.cache.put(&self.namespace, statement, plan)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::namespace::CachedTable;
+    use data_types::{TableId, TopicId};
+    use datafusion::logical_expr::LogicalPlanBuilder;
+    use metric::{Attributes, Metric};
+    use schema::SchemaBuilder;
+
+    fn namespace(id: i64, generation: i64) -> Arc<CachedNamespace> {
+        Arc::new(CachedNamespace {
+            id: NamespaceId::new(id),
+            topic_id: TopicId::new(1),
+            generation,
+            query_ranges: Default::default(),
+            row_ttl: Default::default(),
+            tables: HashMap::new(),
+        })
+    }
+
+    fn plan() -> LogicalPlan {
+        LogicalPlanBuilder::empty(false).build().unwrap()
+    }
+
+    fn metric_count(registry: &metric::Registry, status: &'static str) -> u64 {
+        registry
+            .get_instrument::<Metric<U64Counter>>("query_plan_cache_get")
+            .unwrap()
+            .get_observer(&Attributes::from(&[("status", status)]))
+            .unwrap()
+            .fetch()
+    }
+
+    #[test]
+    fn test_get_put() {
+        let registry = metric::Registry::default();
+        let cache = Arc::new(QueryPlanCache::new(
+            NonZeroUsize::new(10).unwrap(),
+            &registry,
+        ));
+
+        let ns1 = cache.namespace(namespace(1, 0));
+        let ns2 = cache.namespace(namespace(2, 0));
+
+        assert!(ns1.get("SELECT 1").is_none());
+        ns1.put("SELECT 1", plan());
+        assert!(ns1.get("SELECT 1").is_some());
+
+        // plans are specific to their namespace
+        assert!(ns2.get("SELECT 1").is_none());
+
+        // the same schema fetched again shares the plans
+        assert!(cache.namespace(namespace(1, 0)).get("SELECT 1").is_some());
+
+        assert_eq!(metric_count(&registry, "hit"), 2);
+        assert_eq!(metric_count(&registry, "miss"), 2);
+    }
+
+    #[test]
+    fn test_schema_change() {
+        let registry = metric::Registry::default();
+        let cache = Arc::new(QueryPlanCache::new(
+            NonZeroUsize::new(10).unwrap(),
+            &registry,
+        ));
+
+        let old = cache.namespace(namespace(1, 0));
+        old.put("SELECT 1", plan());
+        old.put("SELECT 2", plan());
+        assert_eq!(cache.len(), 2);
+
+        // a newer generation drops the plans of older ones
+        let new = cache.namespace(namespace(1, 1));
+        assert!(new.get("SELECT 1").is_none());
+        assert_eq!(cache.len(), 0);
+
+        // queries against the outdated schema are not cached anymore
+        old.put("SELECT 1", plan());
+        assert!(old.get("SELECT 1").is_none());
+        assert_eq!(cache.len(), 0);
+
+        // schema changes without a new generation are detected as well
+        new.put("SELECT 1", plan());
+        let mut changed = namespace(1, 1).as_ref().clone();
+        changed.tables.insert(
+            Arc::from("t"),
+            Arc::new(CachedTable {
+                id: TableId::new(1),
+                schema: Arc::new(SchemaBuilder::new().timestamp().build().unwrap()),
+                column_id_map: HashMap::new(),
+            }),
+        );
+        let changed = cache.namespace(Arc::new(changed));
+        assert!(changed.get("SELECT 1").is_none());
+        changed.put("SELECT 1", plan());
+        assert!(changed.get("SELECT 1").is_some());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_eviction() {
+        let registry = metric::Registry::default();
+        let cache = Arc::new(QueryPlanCache::new(
+            NonZeroUsize::new(2).unwrap(),
+            &registry,
+        ));
+        let ns1 = cache.namespace(namespace(1, 0));
+        let ns2 = cache.namespace(namespace(2, 0));
+
+        ns1.put("SELECT 1", plan());
+        ns2.put("SELECT 2", plan());
+        assert!(ns1.get("SELECT 1").is_some());
+
+        // evicts the least recently used plan across all namespaces
+        ns1.put("SELECT 3", plan());
+        assert_eq!(cache.len(), 2);
+        assert!(ns2.get("SELECT 2").is_none());
+        assert!(ns1.get("SELECT 1").is_some());
+        assert!(ns1.get("SELECT 3").is_some());
+    }
+}
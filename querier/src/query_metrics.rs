@@ -0,0 +1,110 @@
+//! Metrics of the queries run against the namespaces.
+
+use metric::{Attributes, CardinalityLimiter, DurationHistogram, Metric};
+use std::{borrow::Cow, time::Duration};
+
+/// Records the duration of the completed queries, by query type and result, and optionally by
+/// namespace.
+#[derive(Debug)]
+pub struct QueryMetrics {
+    duration: Metric<DurationHistogram>,
+
+    /// Bounds the distinct namespaces the metrics are labelled with, if enabled.
+    namespace_labels: Option<CardinalityLimiter>,
+}
+
+impl QueryMetrics {
+    /// Create the metrics, labelled with the namespace of each query for up to
+    /// `max_namespace_labels` distinct namespaces. Queries against further namespaces are
+    /// labelled as `other`. `None` does not label the metrics by namespace.
+    pub fn new(metric_registry: &metric::Registry, max_namespace_labels: Option<usize>) -> Self {
+        let duration =
+            metric_registry.register_metric("query_duration", "Duration of the completed queries");
+
+        Self {
+            duration,
+            namespace_labels: max_namespace_labels.map(CardinalityLimiter::new),
+        }
+    }
+
+    /// Record a completed query.
+    pub fn record(&self, namespace: &str, query_type: &str, success: bool, duration: Duration) {
+        let result = if success { "success" } else { "error" };
+        let mut attributes = Attributes::from([
+            ("query_type", Cow::Owned(query_type.to_string())),
+            ("result", Cow::Borrowed(result)),
+        ]);
+        if let Some(limiter) = &self.namespace_labels {
+            attributes.insert("namespace", limiter.value(namespace));
+        }
+
+        self.duration.recorder(attributes).record(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_count(
+        metric_registry: &metric::Registry,
+        attributes: impl Into<Attributes>,
+    ) -> Option<u64> {
+        metric_registry
+            .get_instrument::<Metric<DurationHistogram>>("query_duration")
+            .unwrap()
+            .get_observer(&attributes.into())
+            .map(|observer| observer.fetch().sample_count())
+    }
+
+    #[test]
+    fn test_namespace_labels() {
+        let metric_registry = metric::Registry::default();
+        let metrics = QueryMetrics::new(&metric_registry, Some(1));
+
+        metrics.record("ns1", "sql", true, Duration::from_millis(1));
+        metrics.record("ns2", "sql", true, Duration::from_millis(1));
+        metrics.record("ns1", "sql", false, Duration::from_millis(1));
+
+        let attributes = |namespace, result| {
+            Attributes::from(&[
+                ("query_type", "sql"),
+                ("result", result),
+                ("namespace", namespace),
+            ])
+        };
+        assert_eq!(
+            sample_count(&metric_registry, attributes("ns1", "success")),
+            Some(1)
+        );
+        assert_eq!(
+            sample_count(&metric_registry, attributes("other", "success")),
+            Some(1)
+        );
+        assert_eq!(
+            sample_count(&metric_registry, attributes("ns1", "error")),
+            Some(1)
+        );
+        assert_eq!(
+            sample_count(&metric_registry, attributes("ns2", "success")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_no_namespace_labels() {
+        let metric_registry = metric::Registry::default();
+        let metrics = QueryMetrics::new(&metric_registry, None);
+
+        metrics.record("ns1", "read_filter", true, Duration::from_millis(1));
+        metrics.record("ns2", "read_filter", true, Duration::from_millis(1));
+
+        assert_eq!(
+            sample_count(
+                &metric_registry,
+                &[("query_type", "read_filter"), ("result", "success")]
+            ),
+            Some(2)
+        );
+    }
+}
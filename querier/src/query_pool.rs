@@ -0,0 +1,350 @@
+//! Query pool membership of queriers, and dispatch of namespace queries to the
+//! queriers of the namespace's query pool.
+//!
+//! A querier configured with a [`QueryPoolMembership`] registers itself in the
+//! catalog as a member of its query pool and keeps the registration alive with
+//! periodic heartbeats. A [`QueryPoolDispatcher`] sends each query to one of
+//! the queriers of the queried namespace's pool that sent a heartbeat
+//! recently.
+
+use backoff::{Backoff, BackoffConfig};
+use data_types::{QuerierRegistration, QueryPoolId, Timestamp};
+use influxdb_iox_client::{
+    connection::{Builder, Connection},
+    flight::{self, generated_types::ReadInfo, PerformQuery},
+};
+use iox_catalog::interface::Catalog;
+use observability_deps::tracing::*;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+
+/// The number of heartbeat intervals after which a querier that stopped
+/// sending heartbeats no longer receives queries.
+pub const HEARTBEAT_EXPIRY_INTERVALS: u32 = 3;
+
+/// The membership of a querier in a query pool.
+#[derive(Debug, Clone)]
+pub struct QueryPoolMembership {
+    query_pool_name: String,
+    address: String,
+    heartbeat_interval: Duration,
+}
+
+impl QueryPoolMembership {
+    /// Register the querier reachable at `address` as a member of the query
+    /// pool `query_pool_name`, sending a heartbeat every `heartbeat_interval`.
+    pub fn new(
+        query_pool_name: impl Into<String>,
+        address: impl Into<String>,
+        heartbeat_interval: Duration,
+    ) -> Self {
+        Self {
+            query_pool_name: query_pool_name.into(),
+            address: address.into(),
+            heartbeat_interval,
+        }
+    }
+
+    /// Send heartbeats until `shutdown` is cancelled, then remove the
+    /// registration so that the querier stops receiving queries immediately.
+    pub(crate) async fn run(self, catalog: Arc<dyn Catalog>, shutdown: CancellationToken) {
+        let query_pool_id = Backoff::new(&BackoffConfig::default())
+            .retry_all_errors("get query pool", || async {
+                catalog
+                    .repositories()
+                    .await
+                    .query_pools()
+                    .create_or_get(&self.query_pool_name)
+                    .await
+            })
+            .await
+            .expect("retry forever")
+            .id;
+
+        info!(
+            query_pool_name=%self.query_pool_name,
+            %query_pool_id,
+            address=%self.address,
+            "registering querier in query pool"
+        );
+
+        let mut interval = tokio::time::interval(self.heartbeat_interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => {}
+            }
+
+            let now = Timestamp::from(catalog.time_provider().now());
+            if let Err(e) = catalog
+                .repositories()
+                .await
+                .query_pools()
+                .record_querier_heartbeat(query_pool_id, &self.address, now)
+                .await
+            {
+                warn!(%e, address=%self.address, "failed to record query pool heartbeat");
+            }
+        }
+
+        if let Err(e) = catalog
+            .repositories()
+            .await
+            .query_pools()
+            .remove_querier(&self.address)
+            .await
+        {
+            warn!(%e, address=%self.address, "failed to remove query pool registration");
+        }
+    }
+}
+
+/// Errors dispatching a query with a [`QueryPoolDispatcher`].
+#[derive(Debug, Error)]
+#[allow(missing_docs)]
+pub enum DispatchError {
+    #[error("catalog error: {0}")]
+    Catalog(#[from] iox_catalog::interface::Error),
+
+    #[error("namespace {0} not found")]
+    NamespaceNotFound(String),
+
+    #[error("no live queriers in query pool {query_pool_id} of namespace {namespace}")]
+    NoQueriers {
+        namespace: String,
+        query_pool_id: QueryPoolId,
+    },
+
+    #[error("error connecting to querier {address}: {source}")]
+    Connect {
+        address: String,
+        source: influxdb_iox_client::connection::Error,
+    },
+
+    #[error("query error: {0}")]
+    Query(#[from] flight::Error),
+}
+
+/// Dispatches namespace queries to the live queriers of the namespace's query
+/// pool.
+///
+/// Queriers are chosen round-robin. If a querier cannot be connected to, the
+/// next one is tried; errors returned by the query itself are not retried.
+#[derive(Debug)]
+pub struct QueryPoolDispatcher {
+    catalog: Arc<dyn Catalog>,
+    heartbeat_expiry: Duration,
+    next: AtomicUsize,
+}
+
+impl QueryPoolDispatcher {
+    /// Dispatch to queriers that sent a heartbeat within `heartbeat_expiry`.
+    ///
+    /// This should be [`HEARTBEAT_EXPIRY_INTERVALS`] times the heartbeat
+    /// interval of the queriers.
+    pub fn new(catalog: Arc<dyn Catalog>, heartbeat_expiry: Duration) -> Self {
+        Self {
+            catalog,
+            heartbeat_expiry,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Return the live queriers of the query pool of `namespace`.
+    pub async fn queriers(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<QuerierRegistration>, DispatchError> {
+        let mut repos = self.catalog.repositories().await;
+
+        let query_pool_id = repos
+            .namespaces()
+            .get_by_name(namespace)
+            .await?
+            .ok_or_else(|| DispatchError::NamespaceNotFound(namespace.to_string()))?
+            .query_pool_id;
+
+        let now = self.catalog.time_provider().now();
+        let heartbeat_after = now
+            .checked_sub(self.heartbeat_expiry)
+            .map(Timestamp::from)
+            .unwrap_or_else(|| Timestamp::new(i64::MIN));
+
+        let queriers = repos
+            .query_pools()
+            .list_queriers(query_pool_id, heartbeat_after)
+            .await?;
+        if queriers.is_empty() {
+            return Err(DispatchError::NoQueriers {
+                namespace: namespace.to_string(),
+                query_pool_id,
+            });
+        }
+
+        Ok(queriers)
+    }
+
+    /// Perform `request` on one of the live queriers of the query pool of the
+    /// requested namespace.
+    pub async fn perform_query(&self, request: ReadInfo) -> Result<PerformQuery, DispatchError> {
+        let connection = self.connect(&request.namespace_name).await?;
+        let mut client = flight::Client::new(connection);
+        Ok(client.perform_query(request).await?)
+    }
+
+    /// Connect to one of the live queriers of the query pool of `namespace`.
+    pub async fn connect(&self, namespace: &str) -> Result<Connection, DispatchError> {
+        let queriers = self.queriers(namespace).await?;
+
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut last_error = None;
+        for i in 0..queriers.len() {
+            let address = &queriers[(start + i) % queriers.len()].address;
+            match Builder::default().build(address.as_str()).await {
+                Ok(connection) => return Ok(connection),
+                Err(source) => {
+                    warn!(%source, %address, "failed to connect to querier, trying next");
+                    last_error = Some(DispatchError::Connect {
+                        address: address.clone(),
+                        source,
+                    });
+                }
+            }
+        }
+
+        Err(last_error.expect("queriers is not empty"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use iox_catalog::mem::MemCatalog;
+
+    #[tokio::test]
+    async fn test_membership_heartbeats_and_deregisters() {
+        let catalog: Arc<dyn Catalog> =
+            Arc::new(MemCatalog::new(Arc::new(metric::Registry::default())));
+        let shutdown = CancellationToken::new();
+
+        let membership =
+            QueryPoolMembership::new("pool", "http://querier:8082", Duration::from_millis(10));
+        let handle = tokio::spawn(membership.run(Arc::clone(&catalog), shutdown.clone()));
+
+        let pool = catalog
+            .repositories()
+            .await
+            .query_pools()
+            .create_or_get("pool")
+            .await
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let queriers = catalog
+                    .repositories()
+                    .await
+                    .query_pools()
+                    .list_queriers(pool.id, Timestamp::new(i64::MIN))
+                    .await
+                    .unwrap();
+                if !queriers.is_empty() {
+                    assert_eq!(queriers[0].address, "http://querier:8082");
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("querier should register");
+
+        shutdown.cancel();
+        handle.await.unwrap();
+
+        let queriers = catalog
+            .repositories()
+            .await
+            .query_pools()
+            .list_queriers(pool.id, Timestamp::new(i64::MIN))
+            .await
+            .unwrap();
+        assert!(queriers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher_queriers() {
+        let catalog: Arc<dyn Catalog> =
+            Arc::new(MemCatalog::new(Arc::new(metric::Registry::default())));
+
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("topic").await.unwrap();
+        let pool_a = repos.query_pools().create_or_get("pool_a").await.unwrap();
+        let pool_b = repos.query_pools().create_or_get("pool_b").await.unwrap();
+        repos
+            .namespaces()
+            .create("ns_a", None, topic.id, pool_a.id)
+            .await
+            .unwrap();
+        repos
+            .namespaces()
+            .create("ns_b", None, topic.id, pool_b.id)
+            .await
+            .unwrap();
+
+        let now = Timestamp::from(catalog.time_provider().now());
+        let stale = repos
+            .query_pools()
+            .record_querier_heartbeat(pool_a.id, "http://stale:8082", Timestamp::new(0))
+            .await
+            .unwrap();
+        let live_a = repos
+            .query_pools()
+            .record_querier_heartbeat(pool_a.id, "http://a:8082", now)
+            .await
+            .unwrap();
+        let live_b = repos
+            .query_pools()
+            .record_querier_heartbeat(pool_b.id, "http://b:8082", now)
+            .await
+            .unwrap();
+        drop(repos);
+
+        let dispatcher = QueryPoolDispatcher::new(Arc::clone(&catalog), Duration::from_secs(3600));
+        assert_eq!(dispatcher.queriers("ns_a").await.unwrap(), vec![live_a]);
+        assert_eq!(dispatcher.queriers("ns_b").await.unwrap(), vec![live_b]);
+
+        // An expiry reaching back beyond the epoch considers every querier live.
+        let dispatcher = QueryPoolDispatcher::new(Arc::clone(&catalog), Duration::MAX);
+        let queriers = dispatcher.queriers("ns_a").await.unwrap();
+        assert_eq!(queriers.len(), 2);
+        assert!(queriers.contains(&stale));
+
+        assert_matches!(
+            dispatcher.queriers("missing").await,
+            Err(DispatchError::NamespaceNotFound(_))
+        );
+
+        catalog
+            .repositories()
+            .await
+            .query_pools()
+            .remove_querier("http://b:8082")
+            .await
+            .unwrap();
+        assert_matches!(
+            dispatcher.queriers("ns_b").await,
+            Err(DispatchError::NoQueriers { .. })
+        );
+    }
+}
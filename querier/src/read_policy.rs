@@ -0,0 +1,194 @@
+//! Row-level read policies restricting the rows of a namespace an identity may read.
+//!
+//! A namespace with a [`NamespaceReadPolicy`] can only be queried with an identity (see
+//! [`IOxSessionContext::with_identity`](iox_query::exec::IOxSessionContext::with_identity))
+//! listed in the policy. Every table scan of such a query only returns rows whose tags have one of
+//! the values allowed for the identity. The restriction is added to the physical plan of the scan,
+//! so it cannot be bypassed by the query.
+
+use datafusion::logical_expr::{lit, Expr};
+use datafusion_util::AsExpr;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+use thiserror::Error;
+
+/// Reasons for denying an identity access to a namespace.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum AccessDenied {
+    #[error("namespace {namespace} requires an identity")]
+    NoIdentity { namespace: Arc<str> },
+
+    #[error("identity {identity} may not read namespace {namespace}")]
+    UnknownIdentity {
+        namespace: Arc<str>,
+        identity: Arc<str>,
+    },
+}
+
+/// Read policies of all namespaces, keyed by namespace name.
+#[derive(Debug, Default, Clone)]
+pub struct ReadPolicies {
+    namespaces: HashMap<Arc<str>, Arc<NamespaceReadPolicy>>,
+}
+
+impl ReadPolicies {
+    /// Restrict reads from `namespace` to `policy`.
+    pub fn with_namespace(mut self, namespace: &str, policy: NamespaceReadPolicy) -> Self {
+        self.namespaces
+            .insert(Arc::from(namespace), Arc::new(policy));
+        self
+    }
+
+    /// Returns the policy of `namespace`, or [`None`] if reads from `namespace` are unrestricted.
+    pub(crate) fn get(&self, namespace: &str) -> Option<Arc<NamespaceReadPolicy>> {
+        self.namespaces.get(namespace).map(Arc::clone)
+    }
+
+    /// Returns true if no namespace is restricted.
+    pub fn is_empty(&self) -> bool {
+        self.namespaces.is_empty()
+    }
+}
+
+/// The identities allowed to read a namespace, and the rows each of them may read.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NamespaceReadPolicy {
+    identities: HashMap<Arc<str>, RowFilter>,
+}
+
+impl NamespaceReadPolicy {
+    /// Allow `identity` to read the rows whose tags have one of the values in `tag_values`, which
+    /// maps tag names to their allowed values.
+    ///
+    /// Rows of tables without one of the tags are never returned. An identity with no tag values
+    /// may read all rows.
+    pub fn with_identity(
+        mut self,
+        identity: &str,
+        tag_values: BTreeMap<String, Vec<String>>,
+    ) -> Self {
+        self.identities
+            .insert(Arc::from(identity), RowFilter { tag_values });
+        self
+    }
+
+    /// Returns the filter for the rows of `namespace` that `identity` may read, or [`None`] if
+    /// `identity` may read all rows.
+    pub(crate) fn row_filter(
+        &self,
+        namespace: &Arc<str>,
+        identity: Option<Arc<str>>,
+    ) -> Result<Option<&RowFilter>, AccessDenied> {
+        let identity = identity.ok_or_else(|| AccessDenied::NoIdentity {
+            namespace: Arc::clone(namespace),
+        })?;
+
+        let filter =
+            self.identities
+                .get(&identity)
+                .ok_or_else(|| AccessDenied::UnknownIdentity {
+                    namespace: Arc::clone(namespace),
+                    identity: Arc::clone(&identity),
+                })?;
+
+        Ok((!filter.tag_values.is_empty()).then_some(filter))
+    }
+}
+
+/// The allowed values of the tags of the rows an identity may read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RowFilter {
+    tag_values: BTreeMap<String, Vec<String>>,
+}
+
+impl RowFilter {
+    /// The names of the filtered tags.
+    pub(crate) fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tag_values.keys().map(|tag| tag.as_str())
+    }
+
+    /// The filter as a conjunction of one `IN` list per tag.
+    pub(crate) fn expr(&self) -> Expr {
+        self.tag_values
+            .iter()
+            .map(|(tag, values)| {
+                tag.as_str()
+                    .as_expr()
+                    .in_list(values.iter().map(|v| lit(v.as_str())).collect(), false)
+            })
+            .reduce(Expr::and)
+            .expect("row filter has at least one tag")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datafusion::prelude::col;
+
+    fn policy() -> NamespaceReadPolicy {
+        NamespaceReadPolicy::default()
+            .with_identity(
+                "alice",
+                BTreeMap::from([
+                    ("region".to_string(), vec!["eu".to_string()]),
+                    (
+                        "tenant_id".to_string(),
+                        vec!["abc".to_string(), "def".to_string()],
+                    ),
+                ]),
+            )
+            .with_identity("admin", BTreeMap::new())
+    }
+
+    #[test]
+    fn test_row_filter() {
+        let namespace = Arc::from("ns");
+        let policy = policy();
+
+        let filter = policy
+            .row_filter(&namespace, Some(Arc::from("alice")))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            filter.tags().collect::<Vec<_>>(),
+            vec!["region", "tenant_id"]
+        );
+        assert_eq!(
+            filter.expr(),
+            col("region")
+                .in_list(vec![lit("eu")], false)
+                .and(col("tenant_id").in_list(vec![lit("abc"), lit("def")], false))
+        );
+
+        assert_eq!(
+            policy.row_filter(&namespace, Some(Arc::from("admin"))),
+            Ok(None)
+        );
+
+        assert_eq!(
+            policy.row_filter(&namespace, Some(Arc::from("mallory"))),
+            Err(AccessDenied::UnknownIdentity {
+                namespace: Arc::clone(&namespace),
+                identity: Arc::from("mallory"),
+            })
+        );
+        assert_eq!(
+            policy.row_filter(&namespace, None),
+            Err(AccessDenied::NoIdentity {
+                namespace: Arc::clone(&namespace),
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_policies() {
+        let policies = ReadPolicies::default().with_namespace("ns", policy());
+        assert!(!policies.is_empty());
+        assert_eq!(policies.get("ns").as_deref(), Some(&policy()));
+        assert_eq!(policies.get("other"), None);
+    }
+}
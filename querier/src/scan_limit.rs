@@ -0,0 +1,112 @@
+//! Limits for concurrent object store scans.
+
+use iox_query::provider::ScanLimits;
+use std::{num::NonZeroUsize, sync::Arc};
+use tracker::{AsyncSemaphoreMetrics, InstrumentedAsyncSemaphore};
+
+/// Hands out the [`ScanLimits`] of the queries of this querier.
+///
+/// A query that fans out over many parquet files would otherwise issue a request to the object
+/// store for every file at once. The number of scans waiting for object store data is bounded
+/// globally and per query. The time scans wait for their turn is reported via the
+/// `iox_async_semaphore_acquire_duration` metric of the `object_store_scan_global` and
+/// `object_store_scan_query` semaphores.
+#[derive(Debug, Default)]
+pub struct ScanLimiter {
+    /// Semaphore shared by all queries.
+    global: Option<Arc<InstrumentedAsyncSemaphore>>,
+
+    /// Metrics and permits for the semaphore of each query.
+    per_query: Option<(Arc<AsyncSemaphoreMetrics>, usize)>,
+}
+
+impl ScanLimiter {
+    /// Allow up to `max_scans` concurrent scans in total and up to `max_scans_per_query` for each
+    /// query. `None` leaves the respective scans unlimited.
+    pub fn new(
+        max_scans: Option<NonZeroUsize>,
+        max_scans_per_query: Option<NonZeroUsize>,
+        metric_registry: &metric::Registry,
+    ) -> Self {
+        let global = max_scans.map(|max_scans| {
+            let metrics = Arc::new(AsyncSemaphoreMetrics::new(
+                metric_registry,
+                &[("semaphore", "object_store_scan_global")],
+            ));
+            Arc::new(metrics.new_semaphore(max_scans.get()))
+        });
+
+        let per_query = max_scans_per_query.map(|max_scans_per_query| {
+            let metrics = Arc::new(AsyncSemaphoreMetrics::new(
+                metric_registry,
+                &[("semaphore", "object_store_scan_query")],
+            ));
+            (metrics, max_scans_per_query.get())
+        });
+
+        Self { global, per_query }
+    }
+
+    /// Limits for a new query, or `None` if scans are unlimited.
+    pub fn query_limits(&self) -> Option<ScanLimits> {
+        if self.global.is_none() && self.per_query.is_none() {
+            return None;
+        }
+
+        let per_query = self
+            .per_query
+            .as_ref()
+            .map(|(metrics, permits)| Arc::new(metrics.new_semaphore(*permits)));
+
+        Some(ScanLimits::new(self.global.clone(), per_query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metric::{Attributes, Metric, U64Gauge};
+
+    #[test]
+    fn test_query_limits() {
+        let metric_registry = metric::Registry::default();
+
+        let limiter = ScanLimiter::default();
+        assert!(limiter.query_limits().is_none());
+
+        let limiter = ScanLimiter::new(
+            NonZeroUsize::new(10),
+            NonZeroUsize::new(2),
+            &metric_registry,
+        );
+        let limits_1 = limiter.query_limits().unwrap();
+        let limits_2 = limiter.query_limits().unwrap();
+        assert!(!limits_1.is_unlimited());
+
+        // every query has its own semaphore
+        assert_eq!(
+            permits_total(&metric_registry, "object_store_scan_global"),
+            10
+        );
+        assert_eq!(
+            permits_total(&metric_registry, "object_store_scan_query"),
+            4
+        );
+
+        drop(limits_1);
+        drop(limits_2);
+        assert_eq!(
+            permits_total(&metric_registry, "object_store_scan_query"),
+            0
+        );
+    }
+
+    fn permits_total(metric_registry: &metric::Registry, semaphore: &'static str) -> u64 {
+        metric_registry
+            .get_instrument::<Metric<U64Gauge>>("iox_async_semaphore_permits_total")
+            .unwrap()
+            .get_observer(&Attributes::from(&[("semaphore", semaphore)]))
+            .unwrap()
+            .fetch()
+    }
+}
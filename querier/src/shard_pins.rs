@@ -0,0 +1,159 @@
+//! Shards the router pinned tables to.
+//!
+//! With shard pinning enabled, the router writes each table to the shard it is pinned to in the
+//! catalog rather than to the shard the table hashes to, so the data of a table stays on one
+//! shard when the number of shards changes. The querier must then ask the ingesters of the
+//! pinned shard for the unpersisted data of the table.
+//!
+//! A pin to a shard of a topic other than the one the namespace of the table is assigned to is
+//! stale: the namespace was moved, and the router shards the writes of the table across the
+//! shards of the new topic.
+
+use std::{collections::HashMap, sync::Arc};
+
+use data_types::{ShardId, ShardIndex, TableId, TopicId};
+use iox_catalog::interface::Error as CatalogError;
+use observability_deps::tracing::{debug, warn};
+use parking_lot::RwLock;
+
+use crate::cache::CatalogCache;
+
+/// Cache of the shards tables are pinned to, see the [module documentation](self).
+///
+/// A pin never changes once created, so pins are kept until the querier restarts. Tables without
+/// a pin are read from the catalog again on every query, since the router pins a table the first
+/// time it writes to it.
+#[derive(Debug)]
+pub struct ShardPins {
+    catalog_cache: Arc<CatalogCache>,
+
+    /// The topic and index of each shard of the catalog.
+    shard_indexes: RwLock<HashMap<ShardId, (TopicId, ShardIndex)>>,
+
+    /// The topic and index of the shard of each pinned table.
+    pins: RwLock<HashMap<TableId, (TopicId, ShardIndex)>>,
+}
+
+impl ShardPins {
+    /// Create a new cache, reading the pins from the catalog of `catalog_cache`.
+    pub fn new(catalog_cache: Arc<CatalogCache>) -> Self {
+        Self {
+            catalog_cache,
+            shard_indexes: Default::default(),
+            pins: Default::default(),
+        }
+    }
+
+    /// The index of the shard the table is pinned to, or `None` if it is not pinned to a shard of
+    /// `topic_id`, the topic of the namespace of the table.
+    pub(crate) async fn pinned_shard(
+        &self,
+        table_id: TableId,
+        topic_id: TopicId,
+    ) -> Result<Option<ShardIndex>, CatalogError> {
+        let pin = self.pins.read().get(&table_id).copied();
+        let pin = match pin {
+            Some(pin) => pin,
+            None => match self.load(table_id).await? {
+                Some(pin) => pin,
+                None => return Ok(None),
+            },
+        };
+
+        let (pin_topic_id, shard_index) = pin;
+        if pin_topic_id != topic_id {
+            debug!(
+                ?table_id,
+                ?pin_topic_id,
+                ?topic_id,
+                "ignoring shard pin of other topic"
+            );
+            return Ok(None);
+        }
+        Ok(Some(shard_index))
+    }
+
+    /// Read the pin of the table from the catalog.
+    async fn load(&self, table_id: TableId) -> Result<Option<(TopicId, ShardIndex)>, CatalogError> {
+        let mut repos = self.catalog_cache.catalog().repositories().await;
+        let shard_id = match repos.tables().get_shard_pin(table_id).await? {
+            Some(shard_id) => shard_id,
+            None => return Ok(None),
+        };
+
+        let known = self.shard_indexes.read().get(&shard_id).copied();
+        let pin = match known {
+            Some(pin) => pin,
+            None => {
+                // the shard was created since the shards were last read
+                let shards = repos.shards().list().await?;
+                let mut shard_indexes = self.shard_indexes.write();
+                *shard_indexes = shards
+                    .into_iter()
+                    .map(|s| (s.id, (s.topic_id, s.shard_index)))
+                    .collect();
+                match shard_indexes.get(&shard_id) {
+                    Some(pin) => *pin,
+                    None => {
+                        warn!(?table_id, ?shard_id, "table is pinned to an unknown shard");
+                        return Ok(None);
+                    }
+                }
+            }
+        };
+
+        debug!(?table_id, topic_id=?pin.0, shard_index=?pin.1, "resolved shard pin");
+        self.pins.write().insert(table_id, pin);
+        Ok(Some(pin))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iox_tests::util::TestCatalog;
+    use tokio::runtime::Handle;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pinned_shard() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let shard = ns.create_shard(3).await;
+        let table = ns.create_table("table").await;
+        let table_id = table.table.id;
+        let topic_id = ns.topic.id;
+
+        let catalog_cache = Arc::new(CatalogCache::new_testing(
+            catalog.catalog(),
+            catalog.time_provider(),
+            catalog.metric_registry(),
+            catalog.object_store(),
+            &Handle::current(),
+        ));
+        let pins = ShardPins::new(catalog_cache);
+
+        assert_eq!(pins.pinned_shard(table_id, topic_id).await.unwrap(), None);
+
+        catalog
+            .catalog()
+            .repositories()
+            .await
+            .tables()
+            .create_or_get_shard_pin(table_id, shard.shard.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            pins.pinned_shard(table_id, topic_id).await.unwrap(),
+            Some(ShardIndex::new(3))
+        );
+
+        // The pin is ignored once the namespace is assigned to another topic.
+        assert_eq!(
+            pins.pinned_shard(table_id, TopicId::new(topic_id.get() + 1))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use arrow::{
+    array::{ArrayRef, Int16Array, Int64Array, StringArray},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    error::Result,
+    record_batch::RecordBatch,
+};
+use async_trait::async_trait;
+use data_types::{NamespaceId, ParquetFileLineage, TableId};
+use datafusion::{
+    datasource::TableProvider,
+    error::{DataFusionError, Result as DataFusionResult},
+    execution::context::SessionState,
+    logical_expr::TableType,
+    physical_plan::{memory::MemoryExec, ExecutionPlan},
+    prelude::Expr,
+};
+use iox_catalog::interface::Catalog;
+use std::{any::Any, collections::HashMap, sync::Arc};
+
+/// Implementation of system.file_lineage table
+///
+/// Lists the parquet files of the namespace that were compacted from other files, one row per
+/// input file. Unlike the other system tables, the rows are read from the catalog on every scan.
+#[derive(Debug)]
+pub(super) struct FileLineageTable {
+    schema: SchemaRef,
+    catalog: Arc<dyn Catalog>,
+    namespace_id: NamespaceId,
+    table_names: HashMap<TableId, Arc<str>>,
+}
+
+impl FileLineageTable {
+    pub(super) fn new(
+        catalog: Arc<dyn Catalog>,
+        namespace_id: NamespaceId,
+        table_names: HashMap<TableId, Arc<str>>,
+    ) -> Self {
+        Self {
+            schema: file_lineage_schema(),
+            catalog,
+            namespace_id,
+            table_names,
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for FileLineageTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    async fn scan(
+        &self,
+        _ctx: &SessionState,
+        projection: &Option<Vec<usize>>,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let mut lineage = self
+            .catalog
+            .repositories()
+            .await
+            .parquet_files()
+            .list_lineage_by_namespace(self.namespace_id)
+            .await
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        lineage.sort_unstable_by_key(|l| (l.parquet_file_id, l.input_parquet_file_id));
+
+        let batch = from_file_lineage(self.schema(), &lineage, &self.table_names)?;
+
+        Ok(Arc::new(MemoryExec::try_new(
+            &[vec![batch]],
+            self.schema(),
+            projection.clone(),
+        )?))
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+}
+
+fn file_lineage_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("table_name", DataType::Utf8, true),
+        Field::new("parquet_file_id", DataType::Int64, false),
+        Field::new("compaction_level", DataType::Int16, false),
+        Field::new("input_parquet_file_id", DataType::Int64, false),
+        Field::new("input_compaction_level", DataType::Int16, false),
+    ]))
+}
+
+fn from_file_lineage(
+    schema: SchemaRef,
+    lineage: &[ParquetFileLineage],
+    table_names: &HashMap<TableId, Arc<str>>,
+) -> Result<RecordBatch> {
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            lineage
+                .iter()
+                .map(|l| table_names.get(&l.table_id).map(|name| name.as_ref()))
+                .collect::<StringArray>(),
+        ),
+        Arc::new(
+            lineage
+                .iter()
+                .map(|l| Some(l.parquet_file_id.get()))
+                .collect::<Int64Array>(),
+        ),
+        Arc::new(
+            lineage
+                .iter()
+                .map(|l| Some(l.compaction_level as i16))
+                .collect::<Int16Array>(),
+        ),
+        Arc::new(
+            lineage
+                .iter()
+                .map(|l| Some(l.input_parquet_file_id.get()))
+                .collect::<Int64Array>(),
+        ),
+        Arc::new(
+            lineage
+                .iter()
+                .map(|l| Some(l.input_compaction_level as i16))
+                .collect::<Int16Array>(),
+        ),
+    ];
+
+    RecordBatch::try_new(schema, columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_util::assert_batches_eq;
+    use data_types::{CompactionLevel, ParquetFileId};
+
+    #[test]
+    fn test_from_file_lineage() {
+        let lineage =
+            |parquet_file_id, input_parquet_file_id, input_compaction_level| ParquetFileLineage {
+                parquet_file_id: ParquetFileId::new(parquet_file_id),
+                compaction_level: CompactionLevel::FileNonOverlapped,
+                input_parquet_file_id: ParquetFileId::new(input_parquet_file_id),
+                input_compaction_level,
+                namespace_id: NamespaceId::new(1),
+                table_id: TableId::new(parquet_file_id),
+            };
+        let lineage = [
+            lineage(3, 1, CompactionLevel::Initial),
+            lineage(3, 2, CompactionLevel::FileNonOverlapped),
+            lineage(4, 3, CompactionLevel::FileNonOverlapped),
+        ];
+        let table_names = HashMap::from([(TableId::new(3), Arc::from("cpu"))]);
+
+        let expected = vec![
+            "+------------+-----------------+------------------+-----------------------+------------------------+",
+            "| table_name | parquet_file_id | compaction_level | input_parquet_file_id | input_compaction_level |",
+            "+------------+-----------------+------------------+-----------------------+------------------------+",
+            "| cpu        | 3               | 1                | 1                     | 0                      |",
+            "| cpu        | 3               | 1                | 2                     | 1                      |",
+            "|            | 4               | 1                | 3                     | 1                      |",
+            "+------------+-----------------+------------------+-----------------------+------------------------+",
+        ];
+
+        let batch = from_file_lineage(file_lineage_schema(), &lineage, &table_names).unwrap();
+        assert_batches_eq!(&expected, &[batch]);
+    }
+}
@@ -1,7 +1,7 @@
-use crate::query_log::QueryLog;
+use crate::{query_log::QueryLog, write_slo::WriteSloLog};
 use arrow::{datatypes::SchemaRef, error::Result as ArrowResult, record_batch::RecordBatch};
 use async_trait::async_trait;
-use data_types::NamespaceId;
+use data_types::{NamespaceId, TableId};
 use datafusion::{
     catalog::schema::SchemaProvider,
     datasource::TableProvider,
@@ -14,32 +14,77 @@ use datafusion::{
     },
     prelude::Expr,
 };
+use iox_catalog::{interface::Catalog, usage::UsageAccumulator};
 use std::{
     any::Any,
+    collections::HashMap,
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
 };
 
+mod file_lineage;
 mod queries;
+mod usage;
+mod write_slo;
 
 pub const SYSTEM_SCHEMA: &str = "system";
 
 const QUERIES_TABLE: &str = "queries";
 
-const ALL_SYSTEM_TABLES: &[&str] = &[QUERIES_TABLE];
+const WRITE_SLO_TABLE: &str = "write_slo";
+
+const USAGE_TABLE: &str = "usage";
+
+const FILE_LINEAGE_TABLE: &str = "file_lineage";
+
+const ALL_SYSTEM_TABLES: &[&str] = &[
+    QUERIES_TABLE,
+    WRITE_SLO_TABLE,
+    USAGE_TABLE,
+    FILE_LINEAGE_TABLE,
+];
 
 pub struct SystemSchemaProvider {
     queries: Arc<dyn TableProvider>,
+    write_slo: Arc<dyn TableProvider>,
+    usage: Arc<dyn TableProvider>,
+    file_lineage: Arc<dyn TableProvider>,
 }
 
 impl SystemSchemaProvider {
-    pub fn new(query_log: Arc<QueryLog>, namespace_id: NamespaceId) -> Self {
+    pub fn new(
+        query_log: Arc<QueryLog>,
+        write_slo_log: Arc<WriteSloLog>,
+        usage: Option<Arc<UsageAccumulator>>,
+        catalog: Arc<dyn Catalog>,
+        namespace_id: NamespaceId,
+        namespace_name: Arc<str>,
+        table_names: HashMap<TableId, Arc<str>>,
+    ) -> Self {
         let queries = Arc::new(SystemTableProvider {
             table: Arc::new(queries::QueriesTable::new(query_log, Some(namespace_id))),
         });
+        let write_slo = Arc::new(SystemTableProvider {
+            table: Arc::new(write_slo::WriteSloTable::new(write_slo_log, namespace_name)),
+        });
 
-        Self { queries }
+        let usage = Arc::new(SystemTableProvider {
+            table: Arc::new(usage::UsageTable::new(usage, namespace_id)),
+        });
+
+        let file_lineage = Arc::new(file_lineage::FileLineageTable::new(
+            catalog,
+            namespace_id,
+            table_names,
+        ));
+
+        Self {
+            queries,
+            write_slo,
+            usage,
+            file_lineage,
+        }
     }
 }
 
@@ -58,6 +103,9 @@ impl SchemaProvider for SystemSchemaProvider {
     fn table(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
         match name {
             QUERIES_TABLE => Some(Arc::clone(&self.queries)),
+            WRITE_SLO_TABLE => Some(Arc::clone(&self.write_slo)),
+            USAGE_TABLE => Some(Arc::clone(&self.usage)),
+            FILE_LINEAGE_TABLE => Some(Arc::clone(&self.file_lineage)),
             _ => None,
         }
     }
@@ -0,0 +1,126 @@
+use crate::system_tables::{BatchIterator, IoxSystemTable};
+use arrow::{
+    array::{ArrayRef, Int64Array},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    error::Result,
+    record_batch::RecordBatch,
+};
+use data_types::{NamespaceId, NamespaceUsage};
+use iox_catalog::usage::UsageAccumulator;
+use std::sync::Arc;
+
+/// Implementation of system.usage table
+///
+/// Contains the cumulative usage of the namespace in the catalog as of the last flush, and no rows
+/// if usage accounting is disabled.
+#[derive(Debug)]
+pub(super) struct UsageTable {
+    schema: SchemaRef,
+    usage: Option<Arc<UsageAccumulator>>,
+    namespace_id: NamespaceId,
+}
+
+impl UsageTable {
+    pub(super) fn new(usage: Option<Arc<UsageAccumulator>>, namespace_id: NamespaceId) -> Self {
+        Self {
+            schema: usage_schema(),
+            usage,
+            namespace_id,
+        }
+    }
+}
+
+impl IoxSystemTable for UsageTable {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    fn scan(&self, _batch_size: usize) -> Result<BatchIterator> {
+        let usage: Vec<_> = self
+            .usage
+            .as_ref()
+            .and_then(|usage| usage.total(self.namespace_id))
+            .into_iter()
+            .collect();
+        let batch = from_namespace_usage(self.schema(), &usage);
+
+        Ok(Box::new(std::iter::once(batch)))
+    }
+}
+
+fn usage_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("rows_written", DataType::Int64, false),
+        Field::new("bytes_written", DataType::Int64, false),
+        Field::new("queries_executed", DataType::Int64, false),
+        Field::new("bytes_scanned", DataType::Int64, false),
+    ]))
+}
+
+fn from_namespace_usage(schema: SchemaRef, usage: &[NamespaceUsage]) -> Result<RecordBatch> {
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            usage
+                .iter()
+                .map(|u| Some(u.rows_written))
+                .collect::<Int64Array>(),
+        ),
+        Arc::new(
+            usage
+                .iter()
+                .map(|u| Some(u.bytes_written))
+                .collect::<Int64Array>(),
+        ),
+        Arc::new(
+            usage
+                .iter()
+                .map(|u| Some(u.queries_executed))
+                .collect::<Int64Array>(),
+        ),
+        Arc::new(
+            usage
+                .iter()
+                .map(|u| Some(u.bytes_scanned))
+                .collect::<Int64Array>(),
+        ),
+    ];
+
+    RecordBatch::try_new(schema, columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_util::assert_batches_eq;
+    use iox_tests::util::TestCatalog;
+
+    #[tokio::test]
+    async fn test_usage_table() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let namespace_id = ns.namespace.id;
+
+        let usage = Arc::new(UsageAccumulator::default());
+        usage.record_write(namespace_id, 2, 100);
+        usage.record_query(namespace_id);
+        usage.record_scan(namespace_id, 1000);
+        usage.record_query(NamespaceId::new(namespace_id.get() + 1));
+        usage.flush(catalog.catalog.as_ref()).await.unwrap();
+
+        let table = UsageTable::new(Some(usage), namespace_id);
+        let expected = vec![
+            "+--------------+---------------+------------------+---------------+",
+            "| rows_written | bytes_written | queries_executed | bytes_scanned |",
+            "+--------------+---------------+------------------+---------------+",
+            "| 2            | 100           | 1                | 1000          |",
+            "+--------------+---------------+------------------+---------------+",
+        ];
+        let batches = table.scan(1).unwrap().collect::<Result<Vec<_>>>().unwrap();
+        assert_batches_eq!(&expected, &batches);
+
+        // Usage accounting is disabled.
+        let table = UsageTable::new(None, namespace_id);
+        let batches = table.scan(1).unwrap().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{
+    system_tables::{BatchIterator, IoxSystemTable},
+    write_slo::{WriteSloEntry, WriteSloLog},
+};
+use arrow::{
+    array::{ArrayRef, DurationNanosecondArray, StringArray, TimestampNanosecondArray},
+    datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit},
+    error::Result,
+    record_batch::RecordBatch,
+};
+use observability_deps::tracing::error;
+use std::sync::Arc;
+
+/// Implementation of system.write_slo table
+#[derive(Debug)]
+pub(super) struct WriteSloTable {
+    schema: SchemaRef,
+    write_slo_log: Arc<WriteSloLog>,
+    namespace_name: Arc<str>,
+}
+
+impl WriteSloTable {
+    pub(super) fn new(write_slo_log: Arc<WriteSloLog>, namespace_name: Arc<str>) -> Self {
+        Self {
+            schema: write_slo_schema(),
+            write_slo_log,
+            namespace_name,
+        }
+    }
+}
+
+impl IoxSystemTable for WriteSloTable {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    fn scan(&self, batch_size: usize) -> Result<BatchIterator> {
+        let schema = self.schema();
+
+        let mut entries: Vec<_> = self.write_slo_log.entries().into();
+        entries.retain(|entry| entry.namespace == self.namespace_name);
+
+        let mut offset = 0;
+        Ok(Box::new(std::iter::from_fn(move || {
+            if offset >= entries.len() {
+                return None;
+            }
+
+            let len = batch_size.min(entries.len() - offset);
+            match from_write_slo_entries(Arc::clone(&schema), &entries[offset..offset + len]) {
+                Ok(batch) => {
+                    offset += len;
+                    Some(Ok(batch))
+                }
+                Err(e) => {
+                    error!("Error system.write_slo table: {:?}", e);
+                    Some(Err(e))
+                }
+            }
+        })))
+    }
+}
+
+fn write_slo_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new(
+            "write_time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        ),
+        Field::new("probe", DataType::Utf8, false),
+        Field::new(
+            "queryable_duration",
+            DataType::Duration(TimeUnit::Nanosecond),
+            true,
+        ),
+        Field::new(
+            "persisted_duration",
+            DataType::Duration(TimeUnit::Nanosecond),
+            true,
+        ),
+        Field::new("error", DataType::Utf8, true),
+    ]))
+}
+
+fn from_write_slo_entries(
+    schema: SchemaRef,
+    entries: &[Arc<WriteSloEntry>],
+) -> Result<RecordBatch> {
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(
+            entries
+                .iter()
+                .map(|e| Some(e.write_time.timestamp_nanos()))
+                .collect::<TimestampNanosecondArray>(),
+        ),
+        Arc::new(
+            entries
+                .iter()
+                .map(|e| Some(e.probe.to_string()))
+                .collect::<StringArray>(),
+        ),
+        Arc::new(
+            entries
+                .iter()
+                .map(|e| e.queryable_duration.map(|d| d.as_nanos() as i64))
+                .collect::<DurationNanosecondArray>(),
+        ),
+        Arc::new(
+            entries
+                .iter()
+                .map(|e| e.persisted_duration.map(|d| d.as_nanos() as i64))
+                .collect::<DurationNanosecondArray>(),
+        ),
+        Arc::new(
+            entries
+                .iter()
+                .map(|e| e.error.as_deref())
+                .collect::<StringArray>(),
+        ),
+    ];
+
+    RecordBatch::try_new(schema, columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_util::assert_batches_eq;
+    use iox_time::Time;
+    use std::time::Duration;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_from_write_slo_log() {
+        let write_time = Time::from_rfc3339("1996-12-19T16:39:57+00:00").unwrap();
+        let write_slo_log = Arc::new(WriteSloLog::new(10));
+        write_slo_log.push(WriteSloEntry {
+            namespace: Arc::from("canary"),
+            probe: Uuid::from_u128(1),
+            write_time,
+            queryable_duration: Some(Duration::from_millis(1500)),
+            persisted_duration: Some(Duration::from_secs(90)),
+            error: None,
+        });
+        write_slo_log.push(WriteSloEntry {
+            namespace: Arc::from("other"),
+            probe: Uuid::from_u128(2),
+            write_time,
+            queryable_duration: None,
+            persisted_duration: None,
+            error: Some("nope".to_string()),
+        });
+        write_slo_log.push(WriteSloEntry {
+            namespace: Arc::from("canary"),
+            probe: Uuid::from_u128(3),
+            write_time: write_time + Duration::from_secs(60),
+            queryable_duration: Some(Duration::from_secs(2)),
+            persisted_duration: None,
+            error: Some("canary point not persisted after 600s".to_string()),
+        });
+
+        let table = WriteSloTable::new(write_slo_log, Arc::from("canary"));
+
+        let expected = vec![
+            "+----------------------+--------------------------------------+--------------------+--------------------+---------------------------------------+",
+            "| write_time           | probe                                | queryable_duration | persisted_duration | error                                 |",
+            "+----------------------+--------------------------------------+--------------------+--------------------+---------------------------------------+",
+            "| 1996-12-19T16:39:57Z | 00000000-0000-0000-0000-000000000001 | 1.5s               | 90s                |                                       |",
+            "| 1996-12-19T16:40:57Z | 00000000-0000-0000-0000-000000000003 | 2s                 |                    | canary point not persisted after 600s |",
+            "+----------------------+--------------------------------------+--------------------+--------------------+---------------------------------------+",
+        ];
+
+        let entries = table.scan(1).unwrap().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_batches_eq!(&expected, &entries);
+    }
+}
@@ -1,18 +1,35 @@
-use self::query_access::QuerierTableChunkPruner;
+use self::query_access::{chunk_estimate_size, QuerierTableChunkPruner};
 use self::state_reconciler::Reconciler;
+use self::tag_probes::TagProbes;
 use crate::table::query_access::MetricPruningObserver;
 use crate::{
     chunk::ChunkAdapter,
+    cold_tables::ColdTables,
     ingester::{self, IngesterPartition},
+    read_policy::{AccessDenied, NamespaceReadPolicy, RowFilter},
+    shard_pins::ShardPins,
     IngesterConnection,
 };
-use data_types::{ColumnId, NamespaceId, PartitionId, ShardIndex, TableId, TimestampMinMax};
-use datafusion::error::DataFusionError;
+use data_types::{
+    ColumnId, NamespaceId, ParquetFile, ParquetFileId, PartitionId, ShardIndex, TableId,
+    TimestampMinMax, TopicId,
+};
+use datafusion::{error::DataFusionError, prelude::Expr};
 use futures::{join, StreamExt};
+use iox_catalog::usage::UsageAccumulator;
 use iox_query::pruning::prune_summaries;
+use iox_query::query_range::{QueryRangeError, QueryRanges};
+use iox_query::row_ttl::RowTtl;
 use iox_query::util::create_basic_summary;
-use iox_query::{exec::Executor, provider, provider::ChunkPruner, QueryChunk};
+use iox_query::{
+    exec::{Executor, PersistedWatermarks},
+    provider,
+    provider::ChunkPruner,
+    QueryChunk,
+};
+use object_store::ObjectMeta;
 use observability_deps::tracing::{debug, trace};
+use parquet_file::{tag_index::TagIndexPath, ParquetFilePath};
 use predicate::Predicate;
 use schema::Schema;
 use sharder::JumpHash;
@@ -21,6 +38,7 @@ use std::collections::HashSet;
 use std::{
     collections::{hash_map::Entry, HashMap},
     sync::Arc,
+    time::Duration,
 };
 use trace::span::{Span, SpanRecorder};
 
@@ -28,6 +46,7 @@ pub use self::query_access::metrics::PruneMetrics;
 
 mod query_access;
 mod state_reconciler;
+mod tag_probes;
 
 #[cfg(test)]
 mod test_util;
@@ -62,6 +81,11 @@ pub enum Error {
 
     #[snafu(display("Chunk pruning failed: {}", source))]
     ChunkPruning { source: provider::Error },
+
+    #[snafu(display("Cannot resolve the shard the table is pinned to: {}", source))]
+    ShardPin {
+        source: iox_catalog::interface::Error,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -82,6 +106,7 @@ pub struct QuerierTableArgs {
     pub sharder: Arc<JumpHash<Arc<ShardIndex>>>,
     pub namespace_id: NamespaceId,
     pub namespace_name: Arc<str>,
+    pub topic_id: TopicId,
     pub table_id: TableId,
     pub table_name: Arc<str>,
     pub schema: Arc<Schema>,
@@ -90,6 +115,12 @@ pub struct QuerierTableArgs {
     pub exec: Arc<Executor>,
     pub max_query_bytes: usize,
     pub prune_metrics: Arc<PruneMetrics>,
+    pub read_policy: Option<Arc<NamespaceReadPolicy>>,
+    pub query_ranges: QueryRanges,
+    pub row_ttl: RowTtl,
+    pub usage: Option<Arc<UsageAccumulator>>,
+    pub cold_tables: Option<Arc<ColdTables>>,
+    pub shard_pins: Option<Arc<ShardPins>>,
 }
 
 /// Table representation for the querier.
@@ -104,6 +135,9 @@ pub struct QuerierTable {
     /// Namespace ID for this table.
     namespace_id: NamespaceId,
 
+    /// Write buffer topic of the namespace.
+    topic_id: TopicId,
+
     /// Table name.
     table_name: Arc<str>,
 
@@ -130,6 +164,24 @@ pub struct QuerierTable {
 
     /// Metrics for chunk pruning.
     prune_metrics: Arc<PruneMetrics>,
+
+    /// Read policy of the namespace, if reads are restricted.
+    read_policy: Option<Arc<NamespaceReadPolicy>>,
+
+    /// Time ranges the queries of the namespace may cover.
+    query_ranges: QueryRanges,
+
+    /// Expiry of the rows of the namespace.
+    row_ttl: RowTtl,
+
+    /// Accumulator of the bytes scanned from the namespace, if usage accounting is enabled.
+    usage: Option<Arc<UsageAccumulator>>,
+
+    /// Cold tables, if the ingesters are skipped for them.
+    cold_tables: Option<Arc<ColdTables>>,
+
+    /// Shards the tables are pinned to, if the router pins tables.
+    shard_pins: Option<Arc<ShardPins>>,
 }
 
 impl QuerierTable {
@@ -139,6 +191,7 @@ impl QuerierTable {
             sharder,
             namespace_id,
             namespace_name,
+            topic_id,
             table_id,
             table_name,
             schema,
@@ -147,6 +200,12 @@ impl QuerierTable {
             exec,
             max_query_bytes,
             prune_metrics,
+            read_policy,
+            query_ranges,
+            row_ttl,
+            usage,
+            cold_tables,
+            shard_pins,
         } = args;
 
         let reconciler = Reconciler::new(
@@ -159,6 +218,7 @@ impl QuerierTable {
             sharder,
             namespace_name,
             namespace_id,
+            topic_id,
             table_name,
             table_id,
             schema,
@@ -168,6 +228,12 @@ impl QuerierTable {
             exec,
             max_query_bytes,
             prune_metrics,
+            read_policy,
+            query_ranges,
+            row_ttl,
+            usage,
+            cold_tables,
+            shard_pins,
         }
     }
 
@@ -187,22 +253,74 @@ impl QuerierTable {
         &self.schema
     }
 
+    /// Check the time range selected by `filters` against the query ranges of the namespace, see
+    /// [`QueryRanges::check`].
+    pub(crate) fn query_range_filter(
+        &self,
+        filters: &[Expr],
+    ) -> Result<Option<Expr>, QueryRangeError> {
+        let now = self.chunk_adapter.catalog_cache().time_provider().now();
+        self.query_ranges
+            .check(&self.table_name, filters, now.timestamp_nanos())
+    }
+
+    /// Returns the filter dropping the expired rows of the table and the columns it reads, see
+    /// [`RowTtl::filter`].
+    pub(crate) fn row_ttl_filter(&self) -> Option<(Expr, Vec<&str>)> {
+        let now = self.chunk_adapter.catalog_cache().time_provider().now();
+        let filter = self.row_ttl.filter(&self.schema, now.timestamp_nanos())?;
+        Some((filter, self.row_ttl.columns(&self.schema)))
+    }
+
+    /// Returns the filter for the rows a query on behalf of `identity` may read, or [`None`] if
+    /// the query may read all rows.
+    pub(crate) fn row_filter(
+        &self,
+        identity: Option<Arc<str>>,
+    ) -> Result<Option<&RowFilter>, AccessDenied> {
+        match &self.read_policy {
+            Some(policy) => policy.row_filter(&self.namespace_name, identity),
+            None => Ok(None),
+        }
+    }
+
     /// Query all chunks within this table.
     ///
     /// This currently contains all parquet files linked to their unprocessed tombstones.
+    ///
+    /// If `max_unpersisted_staleness` is set and the predicate cannot match any data newer than
+    /// `now - max_unpersisted_staleness`, the ingesters are not contacted.
+    ///
+    /// If no ingesters are configured, the newest timestamp of the persisted data in the time
+    /// range of the predicate is recorded in `persisted_watermarks`.
     pub async fn chunks(
         &self,
         predicate: &Predicate,
         span: Option<Span>,
         projection: &Option<Vec<usize>>,
+        max_unpersisted_staleness: Option<Duration>,
+        persisted_watermarks: Option<&PersistedWatermarks>,
     ) -> Result<Vec<Arc<dyn QueryChunk>>> {
         let mut span_recorder = SpanRecorder::new(span);
         match self
-            .chunks_inner(predicate, &span_recorder, projection)
+            .chunks_inner(
+                predicate,
+                &span_recorder,
+                projection,
+                max_unpersisted_staleness,
+                persisted_watermarks,
+            )
             .await
         {
             Ok(chunks) => {
                 span_recorder.ok("got chunks");
+                if let Some(usage) = &self.usage {
+                    let bytes: usize = chunks
+                        .iter()
+                        .map(|chunk| chunk_estimate_size(chunk.as_ref()))
+                        .sum();
+                    usage.record_scan(self.namespace_id, bytes as _);
+                }
                 Ok(chunks)
             }
             Err(e) => {
@@ -212,11 +330,77 @@ impl QuerierTable {
         }
     }
 
+    /// Chunks of the parquet files of this table with the IDs `files`, for a debug scan.
+    ///
+    /// Unlike [`chunks`](Self::chunks), the ingesters are not contacted, the files are not pruned
+    /// and deletes are not applied, so the chunks hold the raw rows of the files. IDs of files of
+    /// other tables, or of files that are deleted or not cached yet, are ignored.
+    pub async fn debug_chunks(
+        &self,
+        files: &HashSet<ParquetFileId>,
+        span: Option<Span>,
+    ) -> Vec<Arc<dyn QueryChunk>> {
+        let span_recorder = SpanRecorder::new(span);
+        let catalog_cache = self.chunk_adapter.catalog_cache();
+
+        let parquet_files = catalog_cache
+            .parquet_file()
+            .get(
+                self.id(),
+                None,
+                None,
+                span_recorder.child_span("cache GET parquet_file"),
+            )
+            .await;
+        let parquet_files: Vec<_> = parquet_files
+            .files
+            .iter()
+            .filter(|file| files.contains(&file.id))
+            .cloned()
+            .collect();
+        if parquet_files.is_empty() {
+            return vec![];
+        }
+
+        let columns: HashSet<ColumnId> = parquet_files
+            .iter()
+            .flat_map(|file| file.column_set.iter().copied())
+            .collect();
+        let cached_namespace = catalog_cache
+            .namespace()
+            .get(
+                Arc::clone(&self.namespace_name),
+                &[(&self.table_name, &columns)],
+                span_recorder.child_span("cache GET namespace schema"),
+            )
+            .await;
+        let cached_table = match cached_namespace
+            .as_ref()
+            .and_then(|ns| ns.tables.get(self.table_name.as_ref()))
+        {
+            Some(cached_table) => cached_table,
+            None => return vec![],
+        };
+
+        futures::stream::iter(parquet_files)
+            .map(|file| {
+                let span = span_recorder.child_span("new_chunk");
+                self.chunk_adapter
+                    .new_chunk(Arc::clone(cached_table), file, span)
+            })
+            .buffer_unordered(CONCURRENT_CHUNK_CREATION_JOBS)
+            .filter_map(|chunk| async { chunk.map(|chunk| Arc::new(chunk) as Arc<dyn QueryChunk>) })
+            .collect()
+            .await
+    }
+
     async fn chunks_inner(
         &self,
         predicate: &Predicate,
         span_recorder: &SpanRecorder,
         projection: &Option<Vec<usize>>,
+        max_unpersisted_staleness: Option<Duration>,
+        persisted_watermarks: Option<&PersistedWatermarks>,
     ) -> Result<Vec<Arc<dyn QueryChunk>>> {
         debug!(
             ?predicate,
@@ -233,10 +417,12 @@ impl QuerierTable {
             self.ingester_partitions(
                 predicate,
                 span_recorder.child_span("ingester partitions"),
-                projection
+                projection,
+                max_unpersisted_staleness,
             ),
             catalog_cache.parquet_file().get(
                 self.id(),
+                predicate.range,
                 None,
                 span_recorder.child_span("cache GET parquet_file (pre-warm")
             ),
@@ -273,6 +459,7 @@ impl QuerierTable {
         let (parquet_files, tombstones) = join!(
             catalog_cache.parquet_file().get(
                 self.id(),
+                predicate.range,
                 max_parquet_sequence_number,
                 span_recorder.child_span("cache GET parquet_file")
             ),
@@ -283,6 +470,19 @@ impl QuerierTable {
             )
         );
 
+        // drop cached metadata of files that were deleted in the meantime
+        catalog_cache
+            .parquet_metadata()
+            .expire_deleted(self.id(), &parquet_files);
+
+        // without ingesters, the results reach up to the newest persisted data
+        if let Some(persisted_watermarks) = persisted_watermarks {
+            let max_time = parquet_files.files.iter().map(|f| f.max_time.get()).max();
+            if let (None, Some(max_time)) = (&self.ingester_connection, max_time) {
+                persisted_watermarks.record(&self.table_name, max_time);
+            }
+        }
+
         let columns: HashSet<ColumnId> = parquet_files
             .files
             .iter()
@@ -338,6 +538,7 @@ impl QuerierTable {
 
                 let early_pruning_observer =
                     &MetricPruningObserver::new(Arc::clone(&self.prune_metrics));
+                let tag_probes = &TagProbes::new(predicate, &cached_table.schema);
 
                 futures::stream::iter(parquet_files.files.iter().cloned().zip(keeps))
                     .filter(|(cached_parquet_file, keep)| {
@@ -352,6 +553,33 @@ impl QuerierTable {
                         async move { keep }
                     })
                     .map(|(cached_parquet_file, _keep)| async move {
+                        if !tag_probes.is_empty() {
+                            let span = span_recorder.child_span("check tag index");
+                            if self
+                                .excluded_by_tag_index(&cached_parquet_file, tag_probes, span)
+                                .await
+                            {
+                                early_pruning_observer.was_pruned_by_tag_index(
+                                    cached_parquet_file.row_count as u64,
+                                    cached_parquet_file.file_size_bytes as u64,
+                                );
+                                return None;
+                            }
+
+                            let span = span_recorder.child_span("check bloom filters");
+                            let excluded = self
+                                .excluded_by_bloom_filters(&cached_parquet_file, tag_probes, span)
+                                .await;
+                            early_pruning_observer.checked_bloom_filters();
+                            if excluded {
+                                early_pruning_observer.was_pruned_by_bloom_filters(
+                                    cached_parquet_file.row_count as u64,
+                                    cached_parquet_file.file_size_bytes as u64,
+                                );
+                                return None;
+                            }
+                        }
+
                         let span = span_recorder.child_span("new_chunk");
                         self.chunk_adapter
                             .new_chunk(Arc::clone(cached_table), cached_parquet_file, span)
@@ -391,6 +619,51 @@ impl QuerierTable {
         Ok(chunks)
     }
 
+    /// Whether the [tag index](parquet_file::tag_index) of the partition of `file` shows that it
+    /// holds no row with the tag values of `probes`.
+    ///
+    /// Partitions without an index keep all their files.
+    async fn excluded_by_tag_index(
+        &self,
+        file: &ParquetFile,
+        probes: &TagProbes,
+        span: Option<Span>,
+    ) -> bool {
+        self.chunk_adapter
+            .catalog_cache()
+            .tag_index()
+            .get(file.partition_id, TagIndexPath::from(file), span)
+            .await
+            .map(|index| probes.excluded_by_tag_index(&index, file.id))
+            .unwrap_or_default()
+    }
+
+    /// Whether the [bloom filters](parquet_file::bloom) of `file` show that it holds no row with
+    /// the tag values of `probes`.
+    ///
+    /// Reads the footer of the file through the metadata cache, which the scan of the file uses as
+    /// well.
+    async fn excluded_by_bloom_filters(
+        &self,
+        file: &ParquetFile,
+        probes: &TagProbes,
+        span: Option<Span>,
+    ) -> bool {
+        let object_meta = ObjectMeta {
+            location: ParquetFilePath::from(file).object_store_path(),
+            last_modified: Default::default(),
+            size: file.file_size_bytes as usize,
+        };
+
+        self.chunk_adapter
+            .catalog_cache()
+            .parquet_metadata()
+            .get(file.id, object_meta, span)
+            .await
+            .map(|metadata| probes.excluded_by_bloom_filters(metadata.file_metadata()))
+            .unwrap_or_default()
+    }
+
     /// Get a chunk pruner that can be used to prune chunks retrieved via [`chunks`](Self::chunks)
     pub fn chunk_pruner(&self) -> Arc<dyn ChunkPruner> {
         Arc::new(QuerierTableChunkPruner::new(
@@ -405,10 +678,36 @@ impl QuerierTable {
         predicate: &Predicate,
         span: Option<Span>,
         projection: &Option<Vec<usize>>,
+        max_unpersisted_staleness: Option<Duration>,
     ) -> Result<Vec<IngesterPartition>> {
         let mut span_recorder = SpanRecorder::new(span);
 
+        if let Some(staleness) = max_unpersisted_staleness {
+            if !self.may_need_unpersisted_data(predicate, staleness) {
+                debug!(
+                    namespace=%self.namespace_name,
+                    table_name=%self.table_name(),
+                    ?staleness,
+                    "Skipping ingesters, predicate only covers data outside staleness window"
+                );
+                span_recorder.ok("Ingesters skipped due to staleness tolerance");
+                return Ok(vec![]);
+            }
+        }
+
         if let Some(ingester_connection) = &self.ingester_connection {
+            if let Some(cold_tables) = &self.cold_tables {
+                if cold_tables.is_cold(self.table_id).await {
+                    debug!(
+                        namespace=%self.namespace_name,
+                        table_name=%self.table_name(),
+                        "Skipping ingesters, all partitions of the table are cold"
+                    );
+                    span_recorder.ok("Ingesters skipped for cold table");
+                    return Ok(vec![]);
+                }
+            }
+
             match self
                 .ingester_partitions_inner(
                     Arc::clone(ingester_connection),
@@ -434,6 +733,28 @@ impl QuerierTable {
         }
     }
 
+    /// Returns `false` if `predicate` provably excludes all rows with a timestamp in
+    /// `[now - staleness, MAX]`, i.e. the query does not care about recently written data.
+    fn may_need_unpersisted_data(&self, predicate: &Predicate, staleness: Duration) -> bool {
+        let now = self.chunk_adapter.catalog_cache().time_provider().now();
+        let min = match now.checked_sub(staleness) {
+            Some(t) => t.timestamp_nanos(),
+            None => return true,
+        };
+
+        let summary = Arc::new(create_basic_summary(
+            1,
+            &self.schema,
+            TimestampMinMax { min, max: i64::MAX },
+        ));
+
+        match prune_summaries(Arc::clone(&self.schema), &vec![summary], predicate) {
+            Ok(keeps) => keeps.into_iter().any(|keep| keep),
+            // Pruning not possible (e.g. no predicate) - be conservative.
+            Err(_) => true,
+        }
+    }
+
     async fn ingester_partitions_inner(
         &self,
         ingester_connection: Arc<dyn IngesterConnection>,
@@ -445,17 +766,27 @@ impl QuerierTable {
         // The provided projection should include all columns needed by the query
         let columns = self.schema.select_given_and_pk_columns(projection);
 
-        // Get the shard indexes responsible for this table's data from the sharder to
-        // determine which ingester(s) to query.
+        // Get the shard indexes responsible for this table's data from the shard pin or the
+        // sharder to determine which ingester(s) to query.
         // Currently, the sharder will only return one shard index per table, but in the
         // near future, the sharder might return more than one shard index for one table.
-        let shard_indexes = vec![**self
-            .sharder
-            .shard_for_query(&self.table_name, &self.namespace_name)];
+        let pinned = match &self.shard_pins {
+            Some(shard_pins) => shard_pins
+                .pinned_shard(self.table_id, self.topic_id)
+                .await
+                .context(ShardPinSnafu)?,
+            None => None,
+        };
+        let shard_indexes = vec![pinned.unwrap_or_else(|| {
+            **self
+                .sharder
+                .shard_for_query(&self.table_name, &self.namespace_name)
+        })];
 
         // get any chunks from the ingester(s)
         let partitions_result = ingester_connection
             .partitions(
+                self.topic_id,
                 &shard_indexes,
                 self.namespace_id,
                 self.table_id,
@@ -518,11 +849,15 @@ mod tests {
     use arrow_util::assert_batches_eq;
     use assert_matches::assert_matches;
     use data_types::{ChunkId, ColumnType, CompactionLevel, SequenceNumber};
+    use datafusion::prelude::{col, lit, lit_timestamp_nano};
     use iox_query::exec::IOxSessionContext;
     use iox_tests::util::{TestCatalog, TestParquetFileBuilder, TestTable};
+    use iox_time::Time;
+    use metric::{Attributes, Metric, U64Counter};
+    use parquet_file::tag_index::{TagIndex, TagValues};
     use predicate::Predicate;
     use schema::{builder::SchemaBuilder, InfluxFieldType};
-    use std::sync::Arc;
+    use std::{collections::BTreeMap, sync::Arc};
     use test_helpers::maybe_start_logging;
     use trace::{span::SpanStatus, RingBufferTraceCollector};
 
@@ -1005,6 +1340,256 @@ mod tests {
         assert_matches!(err, Error::IngestersOverlap { .. });
     }
 
+    #[tokio::test]
+    async fn test_max_unpersisted_staleness() {
+        maybe_start_logging();
+        let catalog = TestCatalog::new();
+        catalog
+            .mock_time_provider()
+            .set(Time::from_timestamp_nanos(1_000));
+
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let table = ns.create_table("table").await;
+        let shard = ns.create_shard(1).await;
+        let partition = table.with_shard(&shard).create_partition("k").await;
+        let schema = make_schema(&table).await;
+
+        // The ingester reports overlapping partitions, so any query that actually contacts the
+        // ingester fails.
+        let builder = IngesterPartitionBuilder::new(&schema, &shard, &partition);
+        let querier_table = TestQuerierTable::new(&catalog, &table)
+            .await
+            .with_ingester_partition(
+                builder
+                    .clone()
+                    .with_ingester_chunk_id(1)
+                    .with_lp(["table foo=1 1"])
+                    .build(None, None),
+            )
+            .with_ingester_partition(
+                builder
+                    .with_ingester_chunk_id(2)
+                    .with_lp(["table foo=2 2"])
+                    .build(None, None),
+            );
+        let staleness = Duration::from_nanos(500);
+
+        // query only covers data older than `now - staleness`, ingester is skipped
+        let pred = Predicate::new().with_expr(col("time").lt(lit_timestamp_nano(100)));
+        let chunks = querier_table
+            .chunks_with_predicate_and_staleness(&pred, staleness)
+            .await
+            .unwrap();
+        assert!(chunks.is_empty());
+
+        // query covers recent data, ingester is consulted
+        let pred = Predicate::new().with_expr(col("time").gt(lit_timestamp_nano(900)));
+        let err = querier_table
+            .chunks_with_predicate_and_staleness(&pred, staleness)
+            .await
+            .unwrap_err();
+        assert_matches!(err, Error::IngestersOverlap { .. });
+
+        // no time restriction, ingester is consulted
+        let err = querier_table
+            .chunks_with_predicate_and_staleness(&Predicate::new(), staleness)
+            .await
+            .unwrap_err();
+        assert_matches!(err, Error::IngestersOverlap { .. });
+    }
+
+    #[tokio::test]
+    async fn test_persisted_watermarks() {
+        maybe_start_logging();
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let table = ns.create_table("table").await;
+        let shard = ns.create_shard(1).await;
+        let partition = table.with_shard(&shard).create_partition("k").await;
+        table.create_column("time", ColumnType::Time).await;
+        table.create_column("foo", ColumnType::F64).await;
+
+        // a read replica does not contact any ingester
+        let mut querier_table = querier_table(&catalog, &table).await;
+        querier_table.ingester_connection = None;
+
+        // no watermark without persisted data
+        let watermarks = PersistedWatermarks::default();
+        querier_table
+            .chunks(&Predicate::default(), None, &None, None, Some(&watermarks))
+            .await
+            .unwrap();
+        assert!(watermarks.watermarks().is_empty());
+
+        for max_time in [22, 11] {
+            let builder = TestParquetFileBuilder::default()
+                .with_line_protocol(&format!("table foo=1 {max_time}"))
+                .with_min_time(max_time)
+                .with_max_time(max_time);
+            partition.create_parquet_file(builder).await;
+        }
+        querier_table.clear_parquet_cache();
+
+        let watermarks = PersistedWatermarks::default();
+        let chunks = querier_table
+            .chunks(&Predicate::default(), None, &None, None, Some(&watermarks))
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            watermarks.watermarks(),
+            BTreeMap::from([("table".to_string(), 22)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_bloom_filters() {
+        maybe_start_logging();
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let table = ns.create_table("table").await;
+        let shard = ns.create_shard(1).await;
+        let partition = table.with_shard(&shard).create_partition("k").await;
+        table.create_column("host", ColumnType::Tag).await;
+        table.create_column("time", ColumnType::Time).await;
+        table.create_column("foo", ColumnType::F64).await;
+
+        // the statistics of the first file do not exclude `b`
+        for hosts in [["a", "c"], ["b", "d"]] {
+            let builder = TestParquetFileBuilder::default()
+                .with_line_protocol(&format!(
+                    "table,host={} foo=1 11\ntable,host={} foo=2 11",
+                    hosts[0], hosts[1]
+                ))
+                .with_bloom_filter_columns(&["host"]);
+            partition.create_parquet_file(builder).await;
+        }
+
+        // a file without bloom filters is kept
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("table,host=a foo=1 11\ntable,host=c foo=2 11");
+        partition.create_parquet_file(builder).await;
+
+        let mut querier_table = querier_table(&catalog, &table).await;
+        querier_table.ingester_connection = None;
+
+        let predicate = Predicate::new().with_expr(col("host").eq(lit("b")));
+        let chunks = querier_table
+            .chunks(&predicate, None, &None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(bloom_filter_metrics(&catalog), (3, 1));
+
+        let predicate =
+            Predicate::new().with_expr(col("host").in_list(vec![lit("a"), lit("b")], false));
+        let chunks = querier_table
+            .chunks(&predicate, None, &None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(bloom_filter_metrics(&catalog), (6, 1));
+
+        // files are not checked without equality predicates on tags
+        let chunks = querier_table
+            .chunks(&Predicate::default(), None, &None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(bloom_filter_metrics(&catalog), (6, 1));
+    }
+
+    #[tokio::test]
+    async fn test_tag_index() {
+        maybe_start_logging();
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace_1hr_retention("ns").await;
+        let table = ns.create_table("table").await;
+        let shard = ns.create_shard(1).await;
+        let partition = table.with_shard(&shard).create_partition("k").await;
+        table.create_column("host", ColumnType::Tag).await;
+        table.create_column("time", ColumnType::Time).await;
+        table.create_column("foo", ColumnType::F64).await;
+
+        // the statistics of the first and last file do not exclude `b`
+        let mut files = vec![];
+        for hosts in [["a", "c"], ["b", "d"], ["a", "c"]] {
+            let builder = TestParquetFileBuilder::default().with_line_protocol(&format!(
+                "table,host={} foo=1 11\ntable,host={} foo=2 11",
+                hosts[0], hosts[1]
+            ));
+            let file = partition.create_parquet_file(builder).await.parquet_file;
+            files.push((file, hosts));
+        }
+
+        // the last file is not indexed and therefore kept
+        let mut index = TagIndex::default();
+        for (file, hosts) in &files[..2] {
+            index.add_file(
+                file.id,
+                TagValues::from([(
+                    "host".to_string(),
+                    hosts.iter().map(|host| host.to_string()).collect(),
+                )]),
+            );
+        }
+        index
+            .store(
+                catalog.object_store.as_ref(),
+                &TagIndexPath::from(&files[0].0),
+            )
+            .await
+            .unwrap();
+
+        let mut querier_table = querier_table(&catalog, &table).await;
+        querier_table.ingester_connection = None;
+
+        let predicate = Predicate::new().with_expr(col("host").eq(lit("b")));
+        let chunks = querier_table
+            .chunks(&predicate, None, &None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(tag_index_pruned(&catalog), 1);
+
+        // files are not checked without equality predicates on tags
+        let chunks = querier_table
+            .chunks(&Predicate::default(), None, &None, None, None)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(tag_index_pruned(&catalog), 1);
+    }
+
+    /// Returns the number of files pruned by the tag index of their partition.
+    fn tag_index_pruned(catalog: &TestCatalog) -> u64 {
+        catalog
+            .metric_registry()
+            .get_instrument::<Metric<U64Counter>>("query_pruner_chunks")
+            .unwrap()
+            .get_observer(&Attributes::from(&[("result", "pruned_tag_index")]))
+            .unwrap()
+            .fetch()
+    }
+
+    /// Returns the number of files checked against and pruned by their bloom filters.
+    fn bloom_filter_metrics(catalog: &TestCatalog) -> (u64, u64) {
+        let registry = catalog.metric_registry();
+        let checked = registry
+            .get_instrument::<Metric<U64Counter>>("query_pruner_bloom_filter_checked_files")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch();
+        let pruned = registry
+            .get_instrument::<Metric<U64Counter>>("query_pruner_chunks")
+            .unwrap()
+            .get_observer(&Attributes::from(&[("result", "pruned_bloom_filter")]))
+            .unwrap()
+            .fetch();
+        (checked, pruned)
+    }
+
     #[tokio::test]
     async fn test_parquet_cache_refresh() {
         maybe_start_logging();
@@ -1217,6 +1802,25 @@ mod tests {
             &self,
             pred: &Predicate,
             projection: &Option<Vec<usize>>,
+        ) -> Result<Vec<Arc<dyn QueryChunk>>> {
+            self.chunks_inner(pred, projection, None).await
+        }
+
+        /// Invokes querier_table.chunks modeling the ingester sending the partitions in this table
+        async fn chunks_with_predicate_and_staleness(
+            &self,
+            pred: &Predicate,
+            max_unpersisted_staleness: Duration,
+        ) -> Result<Vec<Arc<dyn QueryChunk>>> {
+            self.chunks_inner(pred, &None, Some(max_unpersisted_staleness))
+                .await
+        }
+
+        async fn chunks_inner(
+            &self,
+            pred: &Predicate,
+            projection: &Option<Vec<usize>>,
+            max_unpersisted_staleness: Option<Duration>,
         ) -> Result<Vec<Arc<dyn QueryChunk>>> {
             self.querier_table
                 .ingester_connection
@@ -1228,7 +1832,9 @@ mod tests {
                 .next_response(Ok(self.ingester_partitions.clone()));
 
             let span = Some(Span::root("root", Arc::clone(&self.traces) as _));
-            self.querier_table.chunks(pred, span, projection).await
+            self.querier_table
+                .chunks(pred, span, projection, max_unpersisted_staleness, None)
+                .await
         }
     }
 
@@ -62,6 +62,25 @@ pub struct PruneMetrics {
     /// At the moment we can prune chunks early only based on "time".
     pub pruned_early: PruneMetricsGroup,
 
+    /// Parquet files that have been pruned because the bloom filters of their tag columns do not
+    /// hold the values of an equality predicate.
+    ///
+    /// This was also done before the actual [`QueryChunk`](iox_query::QueryChunk) was created,
+    /// for the files that were not pruned early.
+    pub pruned_bloom_filter: PruneMetricsGroup,
+
+    /// Number of parquet files checked against the bloom filters of their tag columns.
+    ///
+    /// Together with [`pruned_bloom_filter`](Self::pruned_bloom_filter), this tells how many of
+    /// the checked files the bloom filters skip.
+    pub bloom_filter_checked: U64Counter,
+
+    /// Parquet files that have been pruned because the tag index of their partition shows that
+    /// they do not hold the values of an equality predicate.
+    ///
+    /// This was done before the bloom filters of the files were checked.
+    pub pruned_tag_index: PruneMetricsGroup,
+
     /// Chunks that have been pruned after they have been created. At this stage we likely had better/more statistics available.
     pub pruned_late: PruneMetricsGroup,
 
@@ -85,6 +104,16 @@ pub struct PruneMetrics {
 impl PruneMetrics {
     pub fn new(metric_registry: &metric::Registry) -> Self {
         let pruned_early = PruneMetricsGroup::new(metric_registry, &[("result", "pruned_early")]);
+        let pruned_bloom_filter =
+            PruneMetricsGroup::new(metric_registry, &[("result", "pruned_bloom_filter")]);
+        let bloom_filter_checked = metric_registry
+            .register_metric::<U64Counter>(
+                "query_pruner_bloom_filter_checked_files",
+                "Number of parquet files checked against the bloom filters of their tag columns",
+            )
+            .recorder(&[]);
+        let pruned_tag_index =
+            PruneMetricsGroup::new(metric_registry, &[("result", "pruned_tag_index")]);
         let pruned_late = PruneMetricsGroup::new(metric_registry, &[("result", "pruned_late")]);
         let not_pruned = PruneMetricsGroup::new(metric_registry, &[("result", "not_pruned")]);
         let could_not_prune_no_expression = PruneMetricsGroup::new(
@@ -117,6 +146,9 @@ impl PruneMetrics {
 
         Self {
             pruned_early,
+            pruned_bloom_filter,
+            bloom_filter_checked,
+            pruned_tag_index,
             pruned_late,
             not_pruned,
             could_not_prune_no_expression,
@@ -1,4 +1,4 @@
-use std::{any::Any, sync::Arc};
+use std::{any::Any, collections::HashSet, sync::Arc};
 
 use arrow::datatypes::SchemaRef;
 use async_trait::async_trait;
@@ -7,17 +7,21 @@ use datafusion::{
     error::DataFusionError,
     execution::context::SessionState,
     logical_expr::TableProviderFilterPushDown,
-    physical_plan::ExecutionPlan,
+    physical_expr::expressions::col as physical_col,
+    physical_plan::{
+        empty::EmptyExec, filter::FilterExec, projection::ProjectionExec, ExecutionPlan,
+    },
     prelude::Expr,
 };
 use iox_query::{
     exec::{ExecutorType, SessionContextIOxExt},
     provider::{ChunkPruner, Error as ProviderError, ProviderBuilder},
     pruning::{prune_chunks, NotPrunedReason, PruningObserver},
+    util::df_physical_expr,
     QueryChunk,
 };
 use predicate::Predicate;
-use schema::Schema;
+use schema::{Schema, TIME_COLUMN_NAME};
 
 use crate::{chunk::QuerierChunk, ingester::IngesterChunk};
 
@@ -47,6 +51,102 @@ impl TableProvider for QuerierTable {
         projection: &Option<Vec<usize>>,
         filters: &[Expr],
         limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>, DataFusionError> {
+        let row_filter = self
+            .row_filter(ctx.identity())
+            .map_err(|e| DataFusionError::Plan(e.to_string()))?;
+        let range_filter = self
+            .query_range_filter(filters)
+            .map_err(|e| DataFusionError::External(Box::new(e)))?;
+
+        let (ttl_filter, ttl_columns) = match self.row_ttl_filter() {
+            Some((filter, columns)) => (Some(filter), columns),
+            None => (None, vec![]),
+        };
+
+        let filter = match row_filter
+            .map(|f| f.expr())
+            .into_iter()
+            .chain(range_filter.clone())
+            .chain(ttl_filter)
+            .reduce(Expr::and)
+        {
+            Some(filter) => filter,
+            None => return self.scan_chunks(ctx, projection, filters, limit).await,
+        };
+
+        let schema = self.schema().as_arrow();
+        let output_schema = match projection {
+            Some(projection) => Arc::new(schema.project(projection)?),
+            None => Arc::clone(&schema),
+        };
+
+        // The row filter needs its tags, the default query range the time and the row expiry its
+        // columns even if the query does not select them. A table without one of the tags has no
+        // rows the identity may read.
+        let filter_columns = row_filter
+            .into_iter()
+            .flat_map(|f| f.tags())
+            .chain(range_filter.map(|_| TIME_COLUMN_NAME))
+            .chain(ttl_columns);
+        let mut scan_projection = projection.clone();
+        for column in filter_columns {
+            let idx = match schema.index_of(column) {
+                Ok(idx) => idx,
+                Err(_) => return Ok(Arc::new(EmptyExec::new(false, output_schema))),
+            };
+            if let Some(scan_projection) = &mut scan_projection {
+                if !scan_projection.contains(&idx) {
+                    scan_projection.push(idx);
+                }
+            }
+        }
+
+        // The filter also prunes chunks. The limit is not pushed down as it would be applied
+        // before the filter.
+        let scan_filters: Vec<_> = filters
+            .iter()
+            .cloned()
+            .chain(std::iter::once(filter.clone()))
+            .collect();
+        let input = self
+            .scan_chunks(ctx, &scan_projection, &scan_filters, None)
+            .await?;
+
+        let predicate = df_physical_expr(input.as_ref(), filter)?;
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(FilterExec::try_new(predicate, input)?);
+
+        if scan_projection == *projection {
+            return Ok(plan);
+        }
+
+        // drop the columns only scanned for the filter
+        let select_exprs = output_schema
+            .fields()
+            .iter()
+            .map(|f| Ok((physical_col(f.name(), &plan.schema())?, f.name().clone())))
+            .collect::<Result<Vec<_>, DataFusionError>>()?;
+        Ok(Arc::new(ProjectionExec::try_new(select_exprs, plan)?))
+    }
+
+    fn supports_filter_pushdown(
+        &self,
+        _filter: &Expr,
+    ) -> Result<TableProviderFilterPushDown, DataFusionError> {
+        // we may apply filtering (via pruning) but can not guarantee
+        // that the filter catches all row during scan
+        Ok(TableProviderFilterPushDown::Inexact)
+    }
+}
+
+impl QuerierTable {
+    /// Scan the chunks of the table, ignoring the read policy.
+    async fn scan_chunks(
+        &self,
+        ctx: &SessionState,
+        projection: &Option<Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>, DataFusionError> {
         // build provider out of all chunks
         // TODO: push down some predicates to catalog
@@ -58,22 +158,43 @@ impl TableProvider for QuerierTable {
             iox_ctx,
         );
 
-        let pruning_predicate = filters
-            .iter()
-            .cloned()
-            .fold(Predicate::default(), Predicate::with_expr);
-
-        let chunks = self
-            .chunks(
-                &pruning_predicate,
-                ctx.child_span("querier table chunks"),
-                projection,
-            )
-            .await?;
+        let debug_files = ctx.debug_parquet_files();
+        let chunks = match &debug_files {
+            // a debug scan reads the raw rows of the requested files
+            Some(files) => {
+                self.debug_chunks(files, ctx.child_span("querier table debug chunks"))
+                    .await
+            }
+            None => {
+                let pruning_predicate = filters
+                    .iter()
+                    .cloned()
+                    .fold(Predicate::default(), Predicate::with_expr);
+
+                self.chunks(
+                    &pruning_predicate,
+                    ctx.child_span("querier table chunks"),
+                    projection,
+                    ctx.max_unpersisted_staleness(),
+                    ctx.persisted_watermarks().as_deref(),
+                )
+                .await?
+            }
+        };
+
+        if let Some(statistics) = ctx.statistics() {
+            let ingester_partitions: HashSet<_> = chunks
+                .iter()
+                .filter_map(|chunk| chunk.as_any().downcast_ref::<IngesterChunk>())
+                .map(|chunk| chunk.partition_id())
+                .collect();
+            statistics.record_ingester_partitions(ingester_partitions.len() as u64);
+        }
 
         for chunk in chunks {
             builder = builder.add_chunk(chunk);
         }
+        builder = builder.with_enable_deduplication(debug_files.is_none());
 
         let provider = match builder.build() {
             Ok(provider) => provider,
@@ -82,15 +203,6 @@ impl TableProvider for QuerierTable {
 
         provider.scan(ctx, projection, filters, limit).await
     }
-
-    fn supports_filter_pushdown(
-        &self,
-        _filter: &Expr,
-    ) -> Result<TableProviderFilterPushDown, DataFusionError> {
-        // we may apply filtering (via pruning) but can not guarantee
-        // that the filter catches all row during scan
-        Ok(TableProviderFilterPushDown::Inexact)
-    }
 }
 
 #[derive(Debug)]
@@ -168,6 +280,26 @@ impl MetricPruningObserver {
     pub(crate) fn was_pruned_early(&self, row_count: u64, size_estimate: u64) {
         self.metrics.pruned_early.inc(1, row_count, size_estimate);
     }
+
+    /// Called when checking a parquet file against its bloom filters before creating the chunk
+    pub(crate) fn checked_bloom_filters(&self) {
+        self.metrics.bloom_filter_checked.inc(1);
+    }
+
+    /// Called when pruning a parquet file by its bloom filters before creating the chunk
+    pub(crate) fn was_pruned_by_bloom_filters(&self, row_count: u64, size_estimate: u64) {
+        self.metrics
+            .pruned_bloom_filter
+            .inc(1, row_count, size_estimate);
+    }
+
+    /// Called when pruning a parquet file by the tag index of its partition before creating the
+    /// chunk
+    pub(crate) fn was_pruned_by_tag_index(&self, row_count: u64, size_estimate: u64) {
+        self.metrics
+            .pruned_tag_index
+            .inc(1, row_count, size_estimate);
+    }
 }
 
 impl PruningObserver for MetricPruningObserver {
@@ -204,7 +336,7 @@ impl PruningObserver for MetricPruningObserver {
     }
 }
 
-fn chunk_estimate_size(chunk: &dyn QueryChunk) -> usize {
+pub(crate) fn chunk_estimate_size(chunk: &dyn QueryChunk) -> usize {
     let chunk = chunk.as_any();
 
     if let Some(chunk) = chunk.downcast_ref::<IngesterChunk>() {
@@ -0,0 +1,203 @@
+//! Skipping of parquet files via the [tag index](parquet_file::tag_index) of their partition and
+//! the [bloom filters](parquet_file::bloom) of their tag columns.
+
+use arrow::datatypes::DataType;
+use data_types::ParquetFileId;
+use datafusion::{
+    logical_expr::{BinaryExpr, Operator},
+    optimizer::utils::split_conjunction,
+    prelude::Expr,
+    scalar::ScalarValue,
+};
+use parquet::file::metadata::FileMetaData;
+use parquet_file::{bloom::BloomFilter, tag_index::TagIndex};
+use predicate::Predicate;
+use schema::{InfluxColumnType, Schema};
+
+/// The values that the tags of the rows matching a predicate must have, taken from the
+/// `tag = 'value'` and `tag IN ('value', ...)` conjuncts of the predicate.
+#[derive(Debug, Default)]
+pub(crate) struct TagProbes {
+    tags: Vec<(String, Vec<String>)>,
+}
+
+impl TagProbes {
+    /// Collect the tag values of `predicate` over a table with `schema`.
+    pub(crate) fn new(predicate: &Predicate, schema: &Schema) -> Self {
+        let tags = predicate
+            .exprs
+            .iter()
+            .flat_map(split_conjunction)
+            .filter_map(tag_values)
+            .filter(|(name, _)| {
+                schema
+                    .find_index_of(name)
+                    .map(|idx| schema.field(idx).0 == InfluxColumnType::Tag)
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        Self { tags }
+    }
+
+    /// Whether there are no tag values to check.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Whether the tag `index` of its partition shows that the parquet `file` holds none of the
+    /// values of a tag, i.e. no row matching the predicate.
+    ///
+    /// Tags that the index does not cover for the file may hold any value.
+    pub(crate) fn excluded_by_tag_index(&self, index: &TagIndex, file: ParquetFileId) -> bool {
+        self.tags
+            .iter()
+            .any(|(name, values)| index.may_contain(file, name, values) == Some(false))
+    }
+
+    /// Whether the bloom filters in the footer `metadata` of a parquet file show that the file
+    /// holds none of the values of a tag, i.e. no row matching the predicate.
+    ///
+    /// Tags without a filter in the file may hold any value.
+    pub(crate) fn excluded_by_bloom_filters(&self, metadata: &FileMetaData) -> bool {
+        self.tags.iter().any(|(name, values)| {
+            BloomFilter::from_metadata(metadata, name)
+                .map(|filter| !values.iter().any(|value| filter.contains(value)))
+                .unwrap_or_default()
+        })
+    }
+}
+
+/// Returns the column and the values it is compared to for `col = 'value'` and
+/// `col IN ('value', ...)`.
+fn tag_values(expr: &Expr) -> Option<(String, Vec<String>)> {
+    match expr {
+        Expr::BinaryExpr(BinaryExpr {
+            left,
+            op: Operator::Eq,
+            right,
+        }) => match (left.as_ref(), right.as_ref()) {
+            (column, Expr::Literal(value)) | (Expr::Literal(value), column) => {
+                Some((column_name(column)?, vec![string_value(value)?]))
+            }
+            _ => None,
+        },
+        Expr::InList {
+            expr,
+            list,
+            negated: false,
+        } => {
+            let values = list
+                .iter()
+                .map(|expr| match expr {
+                    Expr::Literal(value) => string_value(value),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some((column_name(expr)?, values))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the name of a column, which may be cast to a string.
+fn column_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Column(column) => Some(column.name.clone()),
+        Expr::Cast {
+            expr,
+            data_type: DataType::Utf8,
+        } => column_name(expr),
+        _ => None,
+    }
+}
+
+fn string_value(value: &ScalarValue) -> Option<String> {
+    match value {
+        ScalarValue::Utf8(Some(value)) => Some(value.clone()),
+        ScalarValue::Dictionary(_, value) => string_value(value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::prelude::{col, lit};
+    use datafusion_util::lit_dict;
+    use parquet_file::tag_index::TagValues;
+    use schema::builder::SchemaBuilder;
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    #[test]
+    fn test_probes() {
+        let schema = SchemaBuilder::new()
+            .tag("host")
+            .tag("region")
+            .influx_field("name", schema::InfluxFieldType::String)
+            .timestamp()
+            .build()
+            .unwrap();
+
+        let predicate = Predicate::new().with_expr(
+            col("host")
+                .eq(lit("a"))
+                .and(lit_dict("west").eq(col("region")))
+                .and(col("name").eq(lit("x"))),
+        );
+        let probes = TagProbes::new(&predicate, &schema);
+        assert_eq!(
+            probes.tags,
+            vec![
+                ("host".to_string(), vec!["a".to_string()]),
+                ("region".to_string(), vec!["west".to_string()]),
+            ]
+        );
+
+        let predicate = Predicate::new()
+            .with_expr(col("host").in_list(vec![lit("a"), lit("b")], false))
+            .with_expr(col("region").in_list(vec![lit("west")], true))
+            .with_expr(col("region").not_eq(lit("east")))
+            .with_expr(col("host").eq(lit("a")).or(col("host").eq(lit("b"))));
+        let probes = TagProbes::new(&predicate, &schema);
+        assert_eq!(
+            probes.tags,
+            vec![("host".to_string(), vec!["a".to_string(), "b".to_string()])]
+        );
+
+        assert!(TagProbes::new(&Predicate::new(), &schema).is_empty());
+    }
+
+    #[test]
+    fn test_excluded_by_tag_index() {
+        let schema = SchemaBuilder::new()
+            .tag("host")
+            .tag("region")
+            .timestamp()
+            .build()
+            .unwrap();
+        let f1 = ParquetFileId::new(1);
+        let f2 = ParquetFileId::new(2);
+        let f3 = ParquetFileId::new(3);
+
+        let tag_values = |host: &str| {
+            TagValues::from([("host".to_string(), BTreeSet::from([host.to_string()]))])
+        };
+        let mut index = TagIndex::default();
+        index.add_file(f1, tag_values("a"));
+        index.add_file(f2, tag_values("b"));
+
+        let probes = TagProbes::new(
+            &Predicate::new()
+                .with_expr(col("host").in_list(vec![lit("a"), lit("c")], false))
+                .with_expr(col("region").eq(lit("west"))),
+            &schema,
+        );
+        assert!(!probes.excluded_by_tag_index(&index, f1));
+        assert!(probes.excluded_by_tag_index(&index, f2));
+
+        // files that are not indexed are kept
+        assert!(!probes.excluded_by_tag_index(&index, f3));
+    }
+}
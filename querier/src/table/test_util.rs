@@ -6,6 +6,7 @@ use crate::{
 use arrow::record_batch::RecordBatch;
 use data_types::{ChunkId, SequenceNumber, ShardIndex};
 use iox_catalog::interface::get_schema_by_name;
+use iox_query::{query_range::QueryRanges, row_ttl::RowTtl};
 use iox_tests::util::{TestCatalog, TestPartition, TestShard, TestTable};
 use mutable_batch_lp::test_helpers::lp_to_mutable_batch;
 use schema::{sort::SortKey, Projection, Schema};
@@ -28,6 +29,14 @@ pub async fn querier_table(catalog: &Arc<TestCatalog>, table: &Arc<TestTable>) -
     let mut catalog_schema = get_schema_by_name(&table.namespace.namespace.name, repos.as_mut())
         .await
         .unwrap();
+    let query_ranges = QueryRanges {
+        default_range_ns: catalog_schema.default_query_range_ns,
+        max_range_ns: catalog_schema.max_query_range_ns,
+    };
+    let row_ttl = RowTtl {
+        ttl_ns: catalog_schema.row_ttl_ns,
+        column: catalog_schema.row_ttl_column.clone().map(Arc::from),
+    };
     let schema = catalog_schema.tables.remove(&table.table.name).unwrap();
     let schema = Arc::new(Schema::try_from(schema).unwrap());
 
@@ -37,6 +46,7 @@ pub async fn querier_table(catalog: &Arc<TestCatalog>, table: &Arc<TestTable>) -
         sharder: Arc::new(JumpHash::new((0..1).map(ShardIndex::new).map(Arc::new))),
         namespace_id: table.namespace.namespace.id,
         namespace_name,
+        topic_id: table.namespace.topic.id,
         table_id: table.table.id,
         table_name: table.table.name.clone().into(),
         schema,
@@ -45,6 +55,12 @@ pub async fn querier_table(catalog: &Arc<TestCatalog>, table: &Arc<TestTable>) -
         exec: catalog.exec(),
         max_query_bytes: usize::MAX,
         prune_metrics: Arc::new(PruneMetrics::new(&catalog.metric_registry())),
+        read_policy: None,
+        query_ranges,
+        row_ttl,
+        usage: None,
+        cold_tables: None,
+        shard_pins: None,
     })
 }
 
@@ -0,0 +1,411 @@
+//! Writes the results of `CREATE TABLE ... AS SELECT` and `INSERT INTO ... SELECT` back into IOx.
+
+use arrow::{
+    datatypes::{DataType, Schema as ArrowSchema, TimeUnit},
+    record_batch::RecordBatch,
+};
+use async_trait::async_trait;
+use client_util::connection;
+use datafusion::{
+    error::DataFusionError,
+    physical_plan::SendableRecordBatchStream,
+    sql::sqlparser::{
+        ast::ObjectName,
+        dialect::GenericDialect,
+        parser::{Parser, ParserError},
+        tokenizer::Token,
+    },
+};
+use datafusion_util::config::DEFAULT_SCHEMA;
+use futures::TryStreamExt;
+use http::{header::AUTHORIZATION, HeaderValue};
+use influxdb_iox_client::write::Client;
+use iox_query::exec::TableWriter;
+use observability_deps::tracing::debug;
+use schema::{builder::SchemaBuilder, InfluxFieldType, Schema, TIME_COLUMN_NAME};
+use snafu::{ResultExt, Snafu};
+use std::{mem, sync::Arc};
+
+/// Default maximum size of a write request, the default maximum HTTP request size of the router.
+pub(crate) const DEFAULT_MAX_REQUEST_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Snafu)]
+#[allow(missing_copy_implementations, missing_docs)]
+pub enum Error {
+    #[snafu(display(
+        "Cannot write to table '{}': only tables of the '{}' schema can be written",
+        table_name,
+        DEFAULT_SCHEMA
+    ))]
+    UnsupportedTableName { table_name: String },
+
+    #[snafu(display("Invalid table name '{}': {}", table_name, source))]
+    InvalidTableName {
+        table_name: String,
+        source: ParserError,
+    },
+
+    #[snafu(display(
+        "Cannot write column '{}' of type {}: only dictionary encoded tags, fields and a '{}' timestamp column are supported",
+        column_name,
+        data_type,
+        TIME_COLUMN_NAME
+    ))]
+    UnsupportedColumn {
+        column_name: String,
+        data_type: DataType,
+    },
+
+    #[snafu(display("Query results have no '{}' column", TIME_COLUMN_NAME))]
+    MissingTimeColumn,
+
+    #[snafu(display("Invalid schema: {}", source))]
+    InvalidSchema { source: schema::builder::Error },
+
+    #[snafu(display("Cannot convert query results to line protocol: {}", source))]
+    Conversion {
+        source: parquet_to_line_protocol::Error,
+    },
+
+    #[snafu(display("Invalid API token: {}", source))]
+    InvalidApiToken {
+        source: http::header::InvalidHeaderValue,
+    },
+
+    #[snafu(display("Failed to connect to router '{}': {}", router_address, source))]
+    Connecting {
+        router_address: String,
+        source: connection::Error,
+    },
+
+    #[snafu(display("Failed to write to router '{}': {}", router_address, source))]
+    Writing {
+        router_address: String,
+        source: influxdb_iox_client::error::Error,
+    },
+}
+
+impl From<Error> for DataFusionError {
+    fn from(e: Error) -> Self {
+        Self::External(Box::new(e))
+    }
+}
+
+/// [`TableWriter`] that converts query results to line protocol and writes them to a namespace
+/// through a router.
+///
+/// The results are written while the query runs, in requests of at most `max_request_size`
+/// bytes. If a request fails, the rows of the previous requests remain written.
+///
+/// The requests carry the API token of the query, so that the router only accepts the writes if
+/// the caller may write to the namespace.
+#[derive(Debug)]
+pub(crate) struct RouterTableWriter {
+    /// HTTP API address of the router.
+    router_address: Arc<str>,
+
+    /// Namespace the tables belong to.
+    namespace_name: Arc<str>,
+
+    /// Maximum size of a write request, which must not exceed the maximum HTTP request size of
+    /// the router.
+    max_request_size: usize,
+}
+
+impl RouterTableWriter {
+    /// Create writer for the given namespace.
+    pub(crate) fn new(
+        router_address: Arc<str>,
+        namespace_name: Arc<str>,
+        max_request_size: usize,
+    ) -> Self {
+        Self {
+            router_address,
+            namespace_name,
+            max_request_size,
+        }
+    }
+
+    /// Convert `batch` to line protocol for measurement `table_name`.
+    fn to_line_protocol(table_name: &str, batch: &RecordBatch) -> Result<Vec<u8>, Error> {
+        let schema = infer_schema(&batch.schema())?;
+        parquet_to_line_protocol::convert_batch(table_name, &schema, batch).context(ConversionSnafu)
+    }
+
+    /// Write the line protocol `lp` through `client`, connecting to the router with `api_token`
+    /// first if required.
+    async fn send(
+        &self,
+        client: &mut Option<Client>,
+        api_token: Option<&str>,
+        lp: String,
+    ) -> Result<(), Error> {
+        let router_address = self.router_address.as_ref();
+        let client = match client {
+            Some(client) => client,
+            None => {
+                let mut builder = connection::Builder::new();
+                if let Some(api_token) = api_token {
+                    let value = HeaderValue::from_str(&format!("Bearer {api_token}"))
+                        .context(InvalidApiTokenSnafu)?;
+                    builder = builder.header(AUTHORIZATION, value);
+                }
+                let connection = builder
+                    .build(router_address)
+                    .await
+                    .context(ConnectingSnafu { router_address })?;
+                // requests are already split to the maximum request size
+                client.insert(Client::new(connection).with_max_request_payload_size_bytes(None))
+            }
+        };
+
+        debug!(
+            %router_address,
+            namespace=%self.namespace_name,
+            bytes=lp.len(),
+            "writing query results"
+        );
+        client
+            .write_lp(self.namespace_name.as_ref(), lp)
+            .await
+            .context(WritingSnafu { router_address })?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TableWriter for RouterTableWriter {
+    async fn write(
+        &self,
+        table_name: &str,
+        mut batches: SendableRecordBatchStream,
+        api_token: Option<&str>,
+    ) -> Result<u64, DataFusionError> {
+        let table_name = measurement_name(table_name)?;
+
+        let mut client = None;
+        let mut requests = RequestBuffer::new(self.max_request_size);
+        let mut rows = 0;
+        while let Some(batch) = batches.try_next().await? {
+            if batch.num_rows() == 0 {
+                continue;
+            }
+
+            let lp = Self::to_line_protocol(&table_name, &batch)?;
+            rows += batch.num_rows() as u64;
+            for request in requests.push(&lp) {
+                self.send(&mut client, api_token, request).await?;
+            }
+        }
+        if let Some(request) = requests.finish() {
+            self.send(&mut client, api_token, request).await?;
+        }
+
+        debug!(namespace=%self.namespace_name, %table_name, rows, "wrote query results");
+        Ok(rows)
+    }
+}
+
+/// Groups lines of line protocol into requests of at most `max_size` bytes.
+///
+/// A single line longer than `max_size` becomes a request of its own, which the router rejects.
+#[derive(Debug)]
+struct RequestBuffer {
+    max_size: usize,
+    buffer: Vec<u8>,
+}
+
+impl RequestBuffer {
+    fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            buffer: vec![],
+        }
+    }
+
+    /// Append the newline terminated lines of `lp`, returning the requests that are full.
+    fn push(&mut self, lp: &[u8]) -> Vec<String> {
+        let mut full = vec![];
+        for line in lp.split_inclusive(|b| *b == b'\n') {
+            if !self.buffer.is_empty() && self.buffer.len() + line.len() > self.max_size {
+                full.push(Self::to_request(mem::take(&mut self.buffer)));
+            }
+            self.buffer.extend_from_slice(line);
+        }
+        full
+    }
+
+    /// Return the remaining lines as a request, if any.
+    fn finish(self) -> Option<String> {
+        (!self.buffer.is_empty()).then(|| Self::to_request(self.buffer))
+    }
+
+    fn to_request(lp: Vec<u8>) -> String {
+        String::from_utf8(lp).expect("line protocol builder produces UTF-8")
+    }
+}
+
+/// Resolve the table name of a statement (`<table>` or `iox.<table>`) to a measurement name.
+///
+/// The name is parsed as an SQL object name, so quoted identifiers may contain dots.
+fn measurement_name(table_name: &str) -> Result<String, Error> {
+    let ObjectName(idents) = Parser::new(&GenericDialect {})
+        .try_with_sql(table_name)
+        .and_then(|mut parser| {
+            let name = parser.parse_object_name()?;
+            parser.expect_token(&Token::EOF)?;
+            Ok(name)
+        })
+        .context(InvalidTableNameSnafu { table_name })?;
+
+    match idents.as_slice() {
+        [table] => Ok(table.value.clone()),
+        [schema, table] if schema.value == DEFAULT_SCHEMA => Ok(table.value.clone()),
+        _ => UnsupportedTableNameSnafu { table_name }.fail(),
+    }
+}
+
+/// Map the columns of query results to IOx column types.
+///
+/// The `time` column becomes the timestamp, string dictionaries become tags and all other
+/// supported types become fields.
+fn infer_schema(arrow_schema: &ArrowSchema) -> Result<Schema, Error> {
+    let mut builder = SchemaBuilder::new();
+    let mut has_time = false;
+
+    for field in arrow_schema.fields() {
+        let column_name = field.name();
+        match field.data_type() {
+            DataType::Timestamp(TimeUnit::Nanosecond, None) if column_name == TIME_COLUMN_NAME => {
+                builder.timestamp();
+                has_time = true;
+            }
+            DataType::Dictionary(key, value)
+                if key.as_ref() == &DataType::Int32 && value.as_ref() == &DataType::Utf8 =>
+            {
+                builder.tag(column_name);
+            }
+            DataType::Float64 => {
+                builder.influx_field(column_name, InfluxFieldType::Float);
+            }
+            DataType::Int64 => {
+                builder.influx_field(column_name, InfluxFieldType::Integer);
+            }
+            DataType::UInt64 => {
+                builder.influx_field(column_name, InfluxFieldType::UInteger);
+            }
+            DataType::Utf8 => {
+                builder.influx_field(column_name, InfluxFieldType::String);
+            }
+            DataType::Boolean => {
+                builder.influx_field(column_name, InfluxFieldType::Boolean);
+            }
+            data_type => {
+                return UnsupportedColumnSnafu {
+                    column_name,
+                    data_type: data_type.clone(),
+                }
+                .fail()
+            }
+        }
+    }
+
+    if !has_time {
+        return MissingTimeColumnSnafu.fail();
+    }
+
+    builder.build().context(InvalidSchemaSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::{
+        array::{ArrayRef, DictionaryArray, Float64Array, TimestampNanosecondArray},
+        datatypes::Int32Type,
+    };
+
+    #[test]
+    fn test_measurement_name() {
+        assert_eq!(measurement_name("cpu").unwrap(), "cpu");
+        assert_eq!(measurement_name("iox.cpu").unwrap(), "cpu");
+        assert_eq!(measurement_name(r#""iox"."my cpu""#).unwrap(), "my cpu");
+        assert_eq!(measurement_name(r#""cpu.load""#).unwrap(), "cpu.load");
+        assert_eq!(measurement_name(r#"iox."cpu.load""#).unwrap(), "cpu.load");
+        assert!(matches!(
+            measurement_name(r#""iox.cpu"."load""#).unwrap_err(),
+            Error::UnsupportedTableName { .. }
+        ));
+        assert!(matches!(
+            measurement_name("cpu load").unwrap_err(),
+            Error::InvalidTableName { .. }
+        ));
+        assert_eq!(
+            measurement_name("system.queries").unwrap_err().to_string(),
+            "Cannot write to table 'system.queries': only tables of the 'iox' schema can be written"
+        );
+    }
+
+    #[test]
+    fn test_to_line_protocol() {
+        let tag: DictionaryArray<Int32Type> = vec![Some("a"), None].into_iter().collect();
+        let batch = RecordBatch::try_from_iter(vec![
+            ("host", Arc::new(tag) as ArrayRef),
+            (
+                "usage",
+                Arc::new(Float64Array::from(vec![Some(1.5), Some(2.0)])) as ArrayRef,
+            ),
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from(vec![10, 20])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let lp = RouterTableWriter::to_line_protocol("cpu", &batch).unwrap();
+        assert_eq!(lp, b"cpu,host=a usage=1.5 10\ncpu usage=2 20\n");
+    }
+
+    #[test]
+    fn test_request_buffer() {
+        let mut requests = RequestBuffer::new(10);
+        assert_eq!(requests.push(b"a v=1 1\n"), Vec::<String>::new());
+        assert_eq!(
+            requests.push(b"b v=1 1\nc v=1 1\n"),
+            vec!["a v=1 1\n", "b v=1 1\n"]
+        );
+        assert_eq!(
+            requests.push(b"long_line v=1 1\nd v=1 1\n"),
+            vec!["c v=1 1\n", "long_line v=1 1\n"]
+        );
+        assert_eq!(requests.finish().unwrap(), "d v=1 1\n");
+
+        let mut requests = RequestBuffer::new(10);
+        assert_eq!(requests.push(b""), Vec::<String>::new());
+        assert_eq!(requests.finish(), None);
+    }
+
+    #[test]
+    fn test_infer_schema_errors() {
+        let no_time = ArrowSchema::new(vec![arrow::datatypes::Field::new(
+            "usage",
+            DataType::Float64,
+            true,
+        )]);
+        assert!(matches!(
+            infer_schema(&no_time).unwrap_err(),
+            Error::MissingTimeColumn
+        ));
+
+        let unsupported = ArrowSchema::new(vec![arrow::datatypes::Field::new(
+            "d",
+            DataType::Date32,
+            true,
+        )]);
+        assert!(matches!(
+            infer_schema(&unsupported).unwrap_err(),
+            Error::UnsupportedColumn { .. }
+        ));
+    }
+}
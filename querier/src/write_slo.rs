@@ -0,0 +1,475 @@
+//! End-to-end latency of the write path, measured with canary points.
+//!
+//! A querier configured with a [`WriteSloProbe`] periodically writes a canary point to a
+//! namespace through a router, and measures the time until the point is returned by queries
+//! against this querier and until it is persisted to a parquet file. The latencies are recorded
+//! in histograms and in the `system.write_slo` table of the canary namespace.
+
+use crate::database::QuerierDatabase;
+use client_util::connection;
+use datafusion::error::DataFusionError;
+use futures::{stream::FuturesUnordered, StreamExt};
+use iox_catalog::interface::Catalog;
+use iox_query::{exec::ExecutionContextProvider, frontend::sql::SqlQueryPlanner};
+use iox_time::{Time, TimeProvider};
+use metric::{DurationHistogram, Metric, U64Counter};
+use observability_deps::tracing::*;
+use parking_lot::Mutex;
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+use thiserror::Error;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// The table of the canary namespace the canary points are written to.
+const WRITE_SLO_TABLE: &str = "write_slo";
+
+/// The number of completed probes kept for the `system.write_slo` table.
+const WRITE_SLO_LOG_SIZE: usize = 1_000;
+
+/// How often a probe checks whether its canary point became visible or persisted.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Errors of a single probe.
+#[derive(Debug, Error)]
+#[allow(missing_docs)]
+pub enum ProbeError {
+    #[error("failed to connect to router: {0}")]
+    Connect(#[from] connection::Error),
+
+    #[error("failed to write canary point: {0}")]
+    Write(#[from] influxdb_iox_client::error::Error),
+
+    #[error("canary point not queryable after {0:?}")]
+    QueryableTimeout(Duration),
+
+    #[error("canary point not persisted after {0:?}")]
+    PersistedTimeout(Duration),
+}
+
+impl ProbeError {
+    /// The `result` attribute of the probe metrics.
+    fn result(&self) -> &'static str {
+        match self {
+            Self::Connect(_) | Self::Write(_) => "write_error",
+            Self::QueryableTimeout(_) => "queryable_timeout",
+            Self::PersistedTimeout(_) => "persisted_timeout",
+        }
+    }
+}
+
+/// A completed probe.
+#[derive(Debug)]
+pub struct WriteSloEntry {
+    /// Namespace the canary point was written to.
+    pub namespace: Arc<str>,
+
+    /// Value of the `probe` field of the canary point.
+    pub probe: Uuid,
+
+    /// Time at which the canary point was written.
+    pub write_time: Time,
+
+    /// Time until the canary point was returned by a query, if it was.
+    pub queryable_duration: Option<Duration>,
+
+    /// Time until the canary point was persisted, if it was.
+    pub persisted_duration: Option<Duration>,
+
+    /// Why the probe failed, if it did.
+    pub error: Option<String>,
+}
+
+/// Ring buffer of the most recently completed probes.
+#[derive(Debug)]
+pub struct WriteSloLog {
+    log: Mutex<VecDeque<Arc<WriteSloEntry>>>,
+    max_size: usize,
+}
+
+impl Default for WriteSloLog {
+    fn default() -> Self {
+        Self::new(WRITE_SLO_LOG_SIZE)
+    }
+}
+
+impl WriteSloLog {
+    /// Create a new log that holds at most `max_size` probes, evicting the oldest ones.
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            log: Mutex::new(VecDeque::with_capacity(max_size)),
+            max_size,
+        }
+    }
+
+    /// Add a completed probe.
+    pub fn push(&self, entry: WriteSloEntry) {
+        if self.max_size == 0 {
+            return;
+        }
+
+        let mut log = self.log.lock();
+        if log.len() == self.max_size {
+            log.pop_front();
+        }
+        log.push_back(Arc::new(entry));
+    }
+
+    /// The completed probes, oldest first.
+    pub fn entries(&self) -> VecDeque<Arc<WriteSloEntry>> {
+        self.log.lock().clone()
+    }
+}
+
+/// Metrics of the completed probes.
+#[derive(Debug)]
+struct WriteSloMetrics {
+    queryable_duration: DurationHistogram,
+    persisted_duration: DurationHistogram,
+    probes: Metric<U64Counter>,
+}
+
+impl WriteSloMetrics {
+    fn new(metric_registry: &metric::Registry) -> Self {
+        let queryable_duration = metric_registry
+            .register_metric::<DurationHistogram>(
+                "write_slo_queryable_duration",
+                "Time from writing a canary point through the router until it is returned by a query",
+            )
+            .recorder(&[]);
+        let persisted_duration = metric_registry
+            .register_metric::<DurationHistogram>(
+                "write_slo_persisted_duration",
+                "Time from writing a canary point through the router until it is persisted",
+            )
+            .recorder(&[]);
+        let probes = metric_registry.register_metric("write_slo_probes", "Completed write probes");
+
+        Self {
+            queryable_duration,
+            persisted_duration,
+            probes,
+        }
+    }
+
+    fn record(&self, entry: &WriteSloEntry, result: &'static str) {
+        if let Some(duration) = entry.queryable_duration {
+            self.queryable_duration.record(duration);
+        }
+        if let Some(duration) = entry.persisted_duration {
+            self.persisted_duration.record(duration);
+        }
+        self.probes.recorder(&[("result", result)]).inc(1);
+    }
+}
+
+/// Periodically measures the latency of the write path with canary points, see the
+/// [module docs](self).
+#[derive(Debug)]
+pub struct WriteSloProbe {
+    namespace: Arc<str>,
+    router_http_address: String,
+    interval: Duration,
+    timeout: Duration,
+    metrics: WriteSloMetrics,
+}
+
+impl WriteSloProbe {
+    /// Write a canary point to `namespace` through the router at `router_http_address` every
+    /// `interval`, giving up on each point if it is not persisted within `timeout`.
+    pub fn new(
+        namespace: impl Into<Arc<str>>,
+        router_http_address: impl Into<String>,
+        interval: Duration,
+        timeout: Duration,
+        metric_registry: &metric::Registry,
+    ) -> Self {
+        Self {
+            namespace: namespace.into(),
+            router_http_address: router_http_address.into(),
+            interval,
+            timeout,
+            metrics: WriteSloMetrics::new(metric_registry),
+        }
+    }
+
+    /// Run probes until `shutdown` is cancelled, recording the completed ones in the
+    /// [`WriteSloLog`] of `database`. Probes still running on shutdown are abandoned.
+    pub(crate) async fn run(
+        self,
+        database: Arc<QuerierDatabase>,
+        catalog: Arc<dyn Catalog>,
+        shutdown: CancellationToken,
+    ) {
+        info!(
+            namespace=%self.namespace,
+            router_http_address=%self.router_http_address,
+            interval=?self.interval,
+            "probing write path latency"
+        );
+
+        let mut interval = tokio::time::interval(self.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        // Persisting a point takes much longer than the probe interval, so probes overlap.
+        let mut probes = FuturesUnordered::new();
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = interval.tick() => probes.push(self.probe(&database, catalog.as_ref())),
+                Some(entry) = probes.next() => database.write_slo_log().push(entry),
+            }
+        }
+    }
+
+    /// Write a canary point and wait until it is queryable and persisted.
+    async fn probe(&self, database: &QuerierDatabase, catalog: &dyn Catalog) -> WriteSloEntry {
+        let time_provider = catalog.time_provider();
+        let probe = Uuid::new_v4();
+        let write_time = time_provider.now();
+
+        let mut entry = WriteSloEntry {
+            namespace: Arc::clone(&self.namespace),
+            probe,
+            write_time,
+            queryable_duration: None,
+            persisted_duration: None,
+            error: None,
+        };
+
+        let result = self
+            .probe_inner(database, catalog, time_provider.as_ref(), &mut entry)
+            .await;
+        let result = match result {
+            Ok(()) => "success",
+            Err(e) => {
+                warn!(%e, namespace=%self.namespace, %probe, "write probe failed");
+                entry.error = Some(e.to_string());
+                e.result()
+            }
+        };
+        self.metrics.record(&entry, result);
+
+        entry
+    }
+
+    async fn probe_inner(
+        &self,
+        database: &QuerierDatabase,
+        catalog: &dyn Catalog,
+        time_provider: &dyn TimeProvider,
+        entry: &mut WriteSloEntry,
+    ) -> Result<(), ProbeError> {
+        let probe = entry.probe;
+        let write_time = entry.write_time;
+
+        let lp = format!(
+            "{} probe=\"{}\" {}",
+            WRITE_SLO_TABLE,
+            probe,
+            write_time.timestamp_nanos()
+        );
+        let connection = connection::Builder::new()
+            .build(&self.router_http_address)
+            .await?;
+        influxdb_iox_client::write::Client::new(connection)
+            .write_lp(self.namespace.as_ref(), lp)
+            .await?;
+
+        let deadline = write_time + self.timeout;
+        entry.queryable_duration = Some(
+            poll_until(time_provider, write_time, deadline, || {
+                self.is_queryable(database, probe)
+            })
+            .await
+            .ok_or(ProbeError::QueryableTimeout(self.timeout))?,
+        );
+        entry.persisted_duration = Some(
+            poll_until(time_provider, write_time, deadline, || {
+                self.is_persisted(catalog, write_time)
+            })
+            .await
+            .ok_or(ProbeError::PersistedTimeout(self.timeout))?,
+        );
+
+        Ok(())
+    }
+
+    /// Returns true if a query against this querier returns the canary point of `probe`.
+    async fn is_queryable(&self, database: &QuerierDatabase, probe: Uuid) -> bool {
+        let namespace = match database.namespace(&self.namespace, None).await {
+            Some(namespace) => namespace,
+            None => return false,
+        };
+
+        let sql = format!("SELECT probe FROM {WRITE_SLO_TABLE} WHERE probe = '{probe}'");
+        let ctx = namespace.new_query_context(None);
+        let result: Result<_, DataFusionError> = async {
+            let plan = SqlQueryPlanner::default().query(&sql, &ctx).await?;
+            ctx.collect(plan).await
+        }
+        .await;
+
+        match result {
+            Ok(batches) => batches.iter().any(|batch| batch.num_rows() > 0),
+            Err(e) => {
+                // The table does not exist until the namespace schema cache picked up the first
+                // canary point.
+                debug!(%e, namespace=%self.namespace, %probe, "write probe query failed");
+                false
+            }
+        }
+    }
+
+    /// Returns true if a parquet file created after `write_time` covers `write_time`, i.e.
+    /// contains the canary point written at that time.
+    async fn is_persisted(&self, catalog: &dyn Catalog, write_time: Time) -> bool {
+        let result = async {
+            let mut repos = catalog.repositories().await;
+            let namespace = match repos.namespaces().get_by_name(&self.namespace).await? {
+                Some(namespace) => namespace,
+                None => return Ok(false),
+            };
+            let table = match repos
+                .tables()
+                .get_by_namespace_and_name(namespace.id, WRITE_SLO_TABLE)
+                .await?
+            {
+                Some(table) => table,
+                None => return Ok(false),
+            };
+
+            let time = write_time.timestamp_nanos();
+            let files = repos
+                .parquet_files()
+                .list_by_table_not_to_delete(table.id)
+                .await?;
+            Ok::<_, iox_catalog::interface::Error>(files.iter().any(|file| {
+                file.min_time.get() <= time
+                    && time <= file.max_time.get()
+                    && file.created_at.get() >= time
+            }))
+        }
+        .await;
+
+        result.unwrap_or_else(|e| {
+            warn!(%e, namespace=%self.namespace, "write probe catalog lookup failed");
+            false
+        })
+    }
+}
+
+/// Evaluate `condition` every [`POLL_INTERVAL`] until it holds, returning the time since `start`,
+/// or `None` if it does not hold by `deadline`.
+async fn poll_until<F, Fut>(
+    time_provider: &dyn TimeProvider,
+    start: Time,
+    deadline: Time,
+    mut condition: F,
+) -> Option<Duration>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = bool>,
+{
+    loop {
+        if condition().await {
+            return Some(time_provider.now() - start);
+        }
+        if time_provider.now() >= deadline {
+            return None;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(n: u128) -> WriteSloEntry {
+        WriteSloEntry {
+            namespace: Arc::from("canary"),
+            probe: Uuid::from_u128(n),
+            write_time: Time::from_timestamp_nanos(n as i64),
+            queryable_duration: None,
+            persisted_duration: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_log_evicts_oldest() {
+        let log = WriteSloLog::new(2);
+        log.push(entry(1));
+        log.push(entry(2));
+        log.push(entry(3));
+
+        let probes: Vec<_> = log.entries().iter().map(|e| e.probe).collect();
+        assert_eq!(probes, vec![Uuid::from_u128(2), Uuid::from_u128(3)]);
+
+        let log = WriteSloLog::new(0);
+        log.push(entry(1));
+        assert!(log.entries().is_empty());
+    }
+
+    #[test]
+    fn test_metrics() {
+        let metric_registry = metric::Registry::default();
+        let metrics = WriteSloMetrics::new(&metric_registry);
+
+        let mut success = entry(1);
+        success.queryable_duration = Some(Duration::from_millis(100));
+        success.persisted_duration = Some(Duration::from_secs(60));
+        metrics.record(&success, "success");
+        metrics.record(
+            &entry(2),
+            ProbeError::QueryableTimeout(Duration::from_secs(1)).result(),
+        );
+
+        let histogram = |name| {
+            metric_registry
+                .get_instrument::<Metric<DurationHistogram>>(name)
+                .unwrap()
+                .get_observer(&metric::Attributes::from(&[]))
+                .unwrap()
+                .fetch()
+                .sample_count()
+        };
+        assert_eq!(histogram("write_slo_queryable_duration"), 1);
+        assert_eq!(histogram("write_slo_persisted_duration"), 1);
+
+        let probes = |result| {
+            metric_registry
+                .get_instrument::<Metric<U64Counter>>("write_slo_probes")
+                .unwrap()
+                .get_observer(&metric::Attributes::from(&[("result", result)]))
+                .unwrap()
+                .fetch()
+        };
+        assert_eq!(probes("success"), 1);
+        assert_eq!(probes("queryable_timeout"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_poll_until() {
+        let time_provider = iox_time::SystemProvider::new();
+        let start = time_provider.now();
+
+        let mut calls = 0;
+        let duration = poll_until(
+            &time_provider,
+            start,
+            start + Duration::from_secs(60),
+            || {
+                calls += 1;
+                let done = calls == 2;
+                async move { done }
+            },
+        )
+        .await;
+        assert!(duration.unwrap() >= POLL_INTERVAL);
+
+        let duration = poll_until(&time_provider, start, start, || async { false }).await;
+        assert_eq!(duration, None);
+    }
+}
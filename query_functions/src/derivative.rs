@@ -0,0 +1,242 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{as_primitive_array, ArrayRef},
+    compute::cast,
+    datatypes::{DataType, Float64Type, TimestampNanosecondType},
+};
+use datafusion::{
+    error::{DataFusionError, Result as DataFusionResult},
+    logical_expr::{
+        AccumulatorFunctionImplementation, AggregateState, ReturnTypeFunction, Signature,
+        StateTypeFunction, TypeSignature, Volatility,
+    },
+    physical_plan::{udaf::AggregateUDF, Accumulator},
+    scalar::ScalarValue,
+};
+use once_cell::sync::Lazy;
+use schema::TIME_DATA_TYPE;
+
+/// The name of the derivative UDAF given to DataFusion.
+pub const DERIVATIVE_UDAF_NAME: &str = "derivative";
+
+/// Implementation of `derivative(value, time)`.
+///
+/// Returns the rate of change per second between the earliest and the latest point of each
+/// group, or NULL if the group has fewer than two distinct timestamps. Combined with
+/// `GROUP BY date_bin(...)` this computes a per-window rate.
+pub(crate) static DERIVATIVE_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    let signature = Signature::one_of(
+        [DataType::Float64, DataType::Int64, DataType::UInt64]
+            .into_iter()
+            .map(|value_type| TypeSignature::Exact(vec![value_type, TIME_DATA_TYPE()]))
+            .collect(),
+        Volatility::Stable,
+    );
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Float64)));
+    let accumulator: AccumulatorFunctionImplementation =
+        Arc::new(|_| Ok(Box::new(DerivativeAccumulator::default())));
+    let state_type: StateTypeFunction = Arc::new(|_| {
+        Ok(Arc::new(vec![
+            TIME_DATA_TYPE(),
+            DataType::Float64,
+            TIME_DATA_TYPE(),
+            DataType::Float64,
+        ]))
+    });
+
+    Arc::new(AggregateUDF::new(
+        DERIVATIVE_UDAF_NAME,
+        &signature,
+        &return_type,
+        &accumulator,
+        &state_type,
+    ))
+});
+
+/// Tracks the earliest and latest `(time, value)` points seen.
+#[derive(Debug, Default)]
+struct DerivativeAccumulator {
+    first: Option<(i64, f64)>,
+    last: Option<(i64, f64)>,
+}
+
+impl DerivativeAccumulator {
+    fn observe(&mut self, time: i64, value: f64) {
+        if self.first.map_or(true, |(t, _)| time < t) {
+            self.first = Some((time, value));
+        }
+        if self.last.map_or(true, |(t, _)| time > t) {
+            self.last = Some((time, value));
+        }
+    }
+
+    fn observe_arrays(&mut self, times: &ArrayRef, values: &ArrayRef) -> DataFusionResult<()> {
+        let values = cast(values, &DataType::Float64)?;
+        let values = as_primitive_array::<Float64Type>(values.as_ref());
+        let times = as_primitive_array::<TimestampNanosecondType>(times.as_ref());
+
+        for (time, value) in times.iter().zip(values.iter()) {
+            if let (Some(time), Some(value)) = (time, value) {
+                self.observe(time, value);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Accumulator for DerivativeAccumulator {
+    // state is (first time, first value, last time, last value)
+    fn state(&self) -> DataFusionResult<Vec<AggregateState>> {
+        let time = |point: Option<(i64, f64)>| {
+            AggregateState::Scalar(ScalarValue::TimestampNanosecond(
+                point.map(|(t, _)| t),
+                None,
+            ))
+        };
+        let value = |point: Option<(i64, f64)>| {
+            AggregateState::Scalar(ScalarValue::Float64(point.map(|(_, v)| v)))
+        };
+
+        Ok(vec![
+            time(self.first),
+            value(self.first),
+            time(self.last),
+            value(self.last),
+        ])
+    }
+
+    fn evaluate(&self) -> DataFusionResult<ScalarValue> {
+        let rate = match (self.first, self.last) {
+            (Some((t0, v0)), Some((t1, v1))) if t1 > t0 => {
+                Some((v1 - v0) / ((t1 - t0) as f64 / 1_000_000_000.0))
+            }
+            _ => None,
+        };
+        Ok(ScalarValue::Float64(rate))
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> DataFusionResult<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        if values.len() != 2 {
+            return Err(DataFusionError::Internal(format!(
+                "Internal error: Expected 2 arguments passed to derivative but got {}",
+                values.len()
+            )));
+        }
+
+        self.observe_arrays(&values[1], &values[0])
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> DataFusionResult<()> {
+        if states.len() != 4 {
+            return Err(DataFusionError::Internal(format!(
+                "Internal error: Expected 4 state columns for derivative but got {}",
+                states.len()
+            )));
+        }
+
+        self.observe_arrays(&states[0], &states[1])?;
+        self.observe_arrays(&states[2], &states[3])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::{
+        array::{Int64Array, StringArray, TimestampNanosecondArray},
+        record_batch::RecordBatch,
+    };
+    use datafusion::assert_batches_sorted_eq;
+    use datafusion_util::context_with_table;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_derivative() {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "host",
+                Arc::new(StringArray::from(vec!["a", "a", "a", "b", "c", "c"])) as ArrayRef,
+            ),
+            (
+                "value",
+                Arc::new(Int64Array::from(vec![
+                    Some(10),
+                    Some(40),
+                    Some(20),
+                    Some(5),
+                    Some(1),
+                    None,
+                ])) as ArrayRef,
+            ),
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from(vec![
+                    0,
+                    20_000_000_000,
+                    10_000_000_000,
+                    0,
+                    0,
+                    10_000_000_000,
+                ])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let ctx = context_with_table(batch);
+        ctx.register_udaf(DERIVATIVE_UDAF.as_ref().clone());
+
+        let result = ctx
+            .sql("SELECT host, derivative(value, time) AS rate FROM t GROUP BY host")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+
+        let expected = vec![
+            "+------+------+",
+            "| host | rate |",
+            "+------+------+",
+            "| a    | 1.5  |",
+            "| b    |      |",
+            "| c    |      |",
+            "+------+------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &result);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut acc1 = DerivativeAccumulator::default();
+        acc1.observe(10_000_000_000, 3.0);
+        let mut acc2 = DerivativeAccumulator::default();
+        acc2.observe(0, 1.0);
+
+        let state: Vec<ArrayRef> = acc1
+            .state()
+            .unwrap()
+            .into_iter()
+            .map(|s| match s {
+                AggregateState::Scalar(s) => s.to_array(),
+                AggregateState::Array(a) => a,
+            })
+            .collect();
+        acc2.merge_batch(&state).unwrap();
+
+        assert_eq!(acc2.evaluate().unwrap(), ScalarValue::Float64(Some(0.2)));
+
+        // non-float input is cast
+        let mut acc = DerivativeAccumulator::default();
+        acc.update_batch(&[
+            Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef,
+            Arc::new(TimestampNanosecondArray::from(vec![0, 1_000_000_000])) as ArrayRef,
+        ])
+        .unwrap();
+        assert_eq!(acc.evaluate().unwrap(), ScalarValue::Float64(Some(1.0)));
+    }
+}
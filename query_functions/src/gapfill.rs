@@ -0,0 +1,256 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{as_primitive_array, Array, ArrayRef, Float64Array, UInt32Array},
+    compute::take,
+    datatypes::{DataType, Float64Type, IntervalUnit},
+};
+use datafusion::{
+    error::DataFusionError,
+    logical_expr::{
+        ReturnTypeFunction, ScalarFunctionImplementation, ScalarUDF, Signature, TypeSignature,
+        Volatility,
+    },
+    physical_expr::datetime_expressions::date_bin,
+    physical_plan::ColumnarValue,
+    scalar::ScalarValue,
+};
+use once_cell::sync::Lazy;
+use schema::TIME_DATA_TYPE;
+
+/// The name of the date_bin_gapfill UDF given to DataFusion.
+pub const DATE_BIN_GAPFILL_UDF_NAME: &str = "date_bin_gapfill";
+
+/// The name of the locf UDF given to DataFusion.
+pub const LOCF_UDF_NAME: &str = "locf";
+
+/// The name of the interpolate UDF given to DataFusion.
+pub const INTERPOLATE_UDF_NAME: &str = "interpolate";
+
+/// Implementation of `date_bin_gapfill(stride, time[, origin])`.
+///
+/// Bins `time` exactly like DataFusion's `date_bin` (the origin defaults to the epoch). Using it
+/// instead of `date_bin` in the `GROUP BY` of an aggregation marks the column whose missing
+/// buckets should be filled: the IOx planner rewrites the aggregation to insert a row with NULL
+/// aggregates for every bucket between the time bounds of the `WHERE` clause that a group has no
+/// row for. Combine it with [`LOCF_UDF`] / [`INTERPOLATE_UDF`] to fill these NULLs.
+pub(crate) static DATE_BIN_GAPFILL_UDF: Lazy<Arc<ScalarUDF>> = Lazy::new(|| {
+    let signatures = [IntervalUnit::DayTime, IntervalUnit::MonthDayNano]
+        .into_iter()
+        .flat_map(|unit| {
+            [
+                TypeSignature::Exact(vec![DataType::Interval(unit), TIME_DATA_TYPE()]),
+                TypeSignature::Exact(vec![
+                    DataType::Interval(unit),
+                    TIME_DATA_TYPE(),
+                    TIME_DATA_TYPE(),
+                ]),
+            ]
+        })
+        .collect();
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(TIME_DATA_TYPE())));
+    let implementation: ScalarFunctionImplementation = Arc::new(date_bin_gapfill);
+
+    Arc::new(ScalarUDF::new(
+        DATE_BIN_GAPFILL_UDF_NAME,
+        &Signature::one_of(signatures, Volatility::Immutable),
+        &return_type,
+        &implementation,
+    ))
+});
+
+/// Implementation of `locf(value)`: "last observation carried forward".
+///
+/// Replaces each NULL with the closest preceding non-NULL value. Rows are processed in the order
+/// they reach the projection, so the IOx planner only accepts `locf` around an aggregate in the
+/// projection of an aggregation grouping by `date_bin_gapfill`, whose output is sorted by time and
+/// has each group in its own batch, so values are never carried over to another group.
+pub(crate) static LOCF_UDF: Lazy<Arc<ScalarUDF>> = Lazy::new(|| {
+    let return_type: ReturnTypeFunction = Arc::new(|arg_types| Ok(Arc::new(arg_types[0].clone())));
+    let implementation: ScalarFunctionImplementation = Arc::new(locf);
+
+    Arc::new(ScalarUDF::new(
+        LOCF_UDF_NAME,
+        &Signature::any(1, Volatility::Volatile),
+        &return_type,
+        &implementation,
+    ))
+});
+
+/// Implementation of `interpolate(value)`.
+///
+/// Replaces NULLs between two non-NULL values by linear interpolation over the row position, which
+/// matches interpolation over time for evenly spaced (e.g. gap-filled) rows. Leading and trailing
+/// NULLs are kept. It is accepted by the IOx planner at the same places as [`LOCF_UDF`].
+pub(crate) static INTERPOLATE_UDF: Lazy<Arc<ScalarUDF>> = Lazy::new(|| {
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Float64)));
+    let implementation: ScalarFunctionImplementation = Arc::new(interpolate);
+
+    Arc::new(ScalarUDF::new(
+        INTERPOLATE_UDF_NAME,
+        &Signature::uniform(1, vec![DataType::Float64], Volatility::Volatile),
+        &return_type,
+        &implementation,
+    ))
+});
+
+fn date_bin_gapfill(args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+    match args.len() {
+        2 => date_bin(&[
+            args[0].clone(),
+            args[1].clone(),
+            ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(Some(0), None)),
+        ]),
+        3 => date_bin(args),
+        n => Err(DataFusionError::Internal(format!(
+            "{} expects 2 or 3 arguments, got {}",
+            DATE_BIN_GAPFILL_UDF_NAME, n
+        ))),
+    }
+}
+
+fn locf(args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+    assert_eq!(args.len(), 1);
+
+    match &args[0] {
+        ColumnarValue::Scalar(_) => Ok(args[0].clone()),
+        ColumnarValue::Array(array) => {
+            // index of the last non-null value up to and including each row
+            let mut last_valid = None;
+            let indices: UInt32Array = (0..array.len())
+                .map(|i| {
+                    if array.is_valid(i) {
+                        last_valid = Some(i as u32);
+                    }
+                    last_valid
+                })
+                .collect();
+
+            Ok(ColumnarValue::Array(take(array.as_ref(), &indices, None)?))
+        }
+    }
+}
+
+fn interpolate(args: &[ColumnarValue]) -> Result<ColumnarValue, DataFusionError> {
+    assert_eq!(args.len(), 1);
+
+    match &args[0] {
+        ColumnarValue::Scalar(_) => Ok(args[0].clone()),
+        ColumnarValue::Array(array) => {
+            let mut values: Vec<Option<f64>> =
+                as_primitive_array::<Float64Type>(array).iter().collect();
+
+            let valid: Vec<usize> = values
+                .iter()
+                .enumerate()
+                .filter_map(|(i, v)| v.map(|_| i))
+                .collect();
+
+            for window in valid.windows(2) {
+                let (start, end) = (window[0], window[1]);
+                let (a, b) = (values[start].unwrap(), values[end].unwrap());
+                let steps = (end - start) as f64;
+
+                for (i, value) in values.iter_mut().enumerate().take(end).skip(start + 1) {
+                    *value = Some(a + (b - a) * (i - start) as f64 / steps);
+                }
+            }
+
+            Ok(ColumnarValue::Array(
+                Arc::new(Float64Array::from(values)) as ArrayRef
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::array::{StringArray, TimestampNanosecondArray};
+
+    use super::*;
+
+    fn eval(udf: &ScalarUDF, args: &[ColumnarValue]) -> ArrayRef {
+        match (udf.fun)(args).unwrap() {
+            ColumnarValue::Array(array) => array,
+            ColumnarValue::Scalar(_) => panic!("expected array result"),
+        }
+    }
+
+    #[test]
+    fn test_date_bin_gapfill() {
+        let one_minute = ColumnarValue::Scalar(ScalarValue::IntervalDayTime(Some(60_000)));
+        let times = ColumnarValue::Array(Arc::new(TimestampNanosecondArray::from(vec![
+            Some(0),
+            Some(90_000_000_000),
+            None,
+            Some(150_000_000_000),
+        ])));
+
+        let result = eval(&DATE_BIN_GAPFILL_UDF, &[one_minute.clone(), times.clone()]);
+        let expected: ArrayRef = Arc::new(TimestampNanosecondArray::from(vec![
+            Some(0),
+            Some(60_000_000_000),
+            None,
+            Some(120_000_000_000),
+        ]));
+        assert_eq!(&result, &expected);
+
+        // explicit origin
+        let origin =
+            ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(Some(30_000_000_000), None));
+        let result = eval(&DATE_BIN_GAPFILL_UDF, &[one_minute, times, origin]);
+        let expected: ArrayRef = Arc::new(TimestampNanosecondArray::from(vec![
+            Some(-30_000_000_000),
+            Some(90_000_000_000),
+            None,
+            Some(150_000_000_000),
+        ]));
+        assert_eq!(&result, &expected);
+    }
+
+    #[test]
+    fn test_locf() {
+        let input: ArrayRef = Arc::new(StringArray::from(vec![
+            None,
+            Some("a"),
+            None,
+            None,
+            Some("b"),
+            None,
+        ]));
+        let result = eval(&LOCF_UDF, &[ColumnarValue::Array(input)]);
+        let expected: ArrayRef = Arc::new(StringArray::from(vec![
+            None,
+            Some("a"),
+            Some("a"),
+            Some("a"),
+            Some("b"),
+            Some("b"),
+        ]));
+        assert_eq!(&result, &expected);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let input: ArrayRef = Arc::new(Float64Array::from(vec![
+            None,
+            Some(1.0),
+            None,
+            None,
+            Some(4.0),
+            Some(2.0),
+            None,
+        ]));
+        let result = eval(&INTERPOLATE_UDF, &[ColumnarValue::Array(input)]);
+        let expected: ArrayRef = Arc::new(Float64Array::from(vec![
+            None,
+            Some(1.0),
+            Some(2.0),
+            Some(3.0),
+            Some(4.0),
+            Some(2.0),
+            None,
+        ]));
+        assert_eq!(&result, &expected);
+    }
+}
@@ -12,10 +12,11 @@
 )]
 
 use datafusion::{
-    execution::FunctionRegistry,
+    execution::{context::SessionState, FunctionRegistry},
     prelude::{lit, Expr},
 };
 use group_by::WindowDuration;
+use std::sync::Arc;
 use window::EncodedWindowDuration;
 
 /// Grouping by structs
@@ -24,18 +25,31 @@ pub mod group_by;
 /// Regular Expressions
 mod regex;
 
+/// Gap filling expressions
+mod gapfill;
+
+/// Rate of change aggregate
+mod derivative;
+
 /// Flux selector expressions
 pub mod selectors;
 
 /// window_bounds expressions
 mod window;
 
+/// Approximate aggregates backed by sketches
+pub mod sketches;
+
 /// Function registry
 mod registry;
 
 pub use crate::regex::REGEX_MATCH_UDF_NAME;
 pub use crate::regex::REGEX_NOT_MATCH_UDF_NAME;
 
+pub use crate::derivative::DERIVATIVE_UDAF_NAME;
+pub use crate::gapfill::{DATE_BIN_GAPFILL_UDF_NAME, INTERPOLATE_UDF_NAME, LOCF_UDF_NAME};
+pub use crate::sketches::{APPROX_COUNT_DISTINCT_UDAF_NAME, APPROX_PERCENTILE_UDAF_NAME};
+
 /// Return an Expr that invokes a InfluxRPC compatible regex match to
 /// determine which values satisfy the pattern. Equivalent to:
 ///
@@ -62,6 +76,32 @@ pub fn regex_not_match_expr(input: Expr, pattern: String) -> Expr {
         .call(vec![input, lit(pattern)])
 }
 
+/// Return an Expr that estimates the number of distinct values of `input`, as used for the
+/// InfluxQL `COUNT(DISTINCT(field))`. Equivalent to:
+///
+/// ```text
+/// approx_count_distinct(input)
+/// ```
+pub fn approx_count_distinct_expr(input: Expr) -> Expr {
+    registry()
+        .udaf(sketches::APPROX_COUNT_DISTINCT_UDAF_NAME)
+        .expect("ApproxCountDistinct function not registered")
+        .call(vec![input])
+}
+
+/// Return an Expr that estimates the value of `input` at `percentile` in `[0, 1]`. The InfluxQL
+/// `PERCENTILE(field, N)` is `percentile = N / 100`. Equivalent to:
+///
+/// ```text
+/// approx_percentile(input, percentile)
+/// ```
+pub fn approx_percentile_expr(input: Expr, percentile: f64) -> Expr {
+    registry()
+        .udaf(sketches::APPROX_PERCENTILE_UDAF_NAME)
+        .expect("ApproxPercentile function not registered")
+        .call(vec![input, lit(percentile)])
+}
+
 /// Create a DataFusion `Expr` that invokes `window_bounds` with the
 /// appropriate every and offset arguments at runtime
 pub fn make_window_bound_expr(
@@ -86,6 +126,24 @@ pub fn make_window_bound_expr(
         ])
 }
 
+/// Registers the time series functions `date_bin_gapfill`, `locf`, `interpolate` and
+/// `derivative` so they can be invoked via SQL
+pub fn register_timeseries_functions(mut state: SessionState) -> SessionState {
+    for udf in [
+        &gapfill::DATE_BIN_GAPFILL_UDF,
+        &gapfill::LOCF_UDF,
+        &gapfill::INTERPOLATE_UDF,
+    ] {
+        let udf = Arc::clone(udf);
+        state.scalar_functions.insert(udf.name.clone(), udf);
+    }
+
+    let udaf = Arc::clone(&derivative::DERIVATIVE_UDAF);
+    state.aggregate_functions.insert(udaf.name.clone(), udaf);
+
+    state
+}
+
 /// Return an [`FunctionRegistry`] with the implementations of IOx UDFs
 pub fn registry() -> &'static dyn FunctionRegistry {
     registry::instance()
@@ -94,7 +152,7 @@ pub fn registry() -> &'static dyn FunctionRegistry {
 #[cfg(test)]
 mod test {
     use arrow::{
-        array::{ArrayRef, StringArray, TimestampNanosecondArray},
+        array::{ArrayRef, Int64Array, StringArray, TimestampNanosecondArray},
         record_batch::RecordBatch,
     };
     use datafusion::{assert_batches_eq, prelude::col};
@@ -197,4 +255,41 @@ mod test {
 
         assert_batches_eq!(&expected, &result);
     }
+
+    /// plumbing test to validate registry is connected. functions are
+    /// tested more thoroughly in their own modules
+    #[tokio::test]
+    async fn test_sketch_exprs() {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "data",
+            Arc::new(Int64Array::from(vec![1, 2, 2, 3, 5])) as ArrayRef,
+        )])
+        .unwrap();
+
+        let ctx = context_with_table(batch);
+        let result = ctx
+            .table("t")
+            .unwrap()
+            .aggregate(
+                vec![],
+                vec![
+                    approx_count_distinct_expr(col("data")).alias("distinct"),
+                    approx_percentile_expr(col("data"), 0.5).alias("median"),
+                ],
+            )
+            .unwrap()
+            .collect()
+            .await
+            .unwrap();
+
+        let expected = vec![
+            "+----------+--------+",
+            "| distinct | median |",
+            "+----------+--------+",
+            "| 4        | 2      |",
+            "+----------+--------+",
+        ];
+
+        assert_batches_eq!(&expected, &result);
+    }
 }
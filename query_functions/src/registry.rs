@@ -7,7 +7,7 @@ use datafusion::{
 };
 use once_cell::sync::Lazy;
 
-use crate::{regex, window};
+use crate::{derivative, gapfill, regex, sketches, window};
 
 static REGISTRY: Lazy<IOxFunctionRegistry> = Lazy::new(IOxFunctionRegistry::new);
 
@@ -23,10 +23,16 @@ impl IOxFunctionRegistry {
 
 impl FunctionRegistry for IOxFunctionRegistry {
     fn udfs(&self) -> HashSet<String> {
-        [regex::REGEX_MATCH_UDF_NAME, regex::REGEX_NOT_MATCH_UDF_NAME]
-            .into_iter()
-            .map(|s| s.to_string())
-            .collect()
+        [
+            regex::REGEX_MATCH_UDF_NAME,
+            regex::REGEX_NOT_MATCH_UDF_NAME,
+            gapfill::DATE_BIN_GAPFILL_UDF_NAME,
+            gapfill::LOCF_UDF_NAME,
+            gapfill::INTERPOLATE_UDF_NAME,
+        ]
+        .into_iter()
+        .map(|s| s.to_string())
+        .collect()
     }
 
     fn udf(&self, name: &str) -> DataFusionResult<Arc<ScalarUDF>> {
@@ -34,6 +40,9 @@ impl FunctionRegistry for IOxFunctionRegistry {
             regex::REGEX_MATCH_UDF_NAME => Ok(regex::REGEX_MATCH_UDF.clone()),
             regex::REGEX_NOT_MATCH_UDF_NAME => Ok(regex::REGEX_NOT_MATCH_UDF.clone()),
             window::WINDOW_BOUNDS_UDF_NAME => Ok(window::WINDOW_BOUNDS_UDF.clone()),
+            gapfill::DATE_BIN_GAPFILL_UDF_NAME => Ok(gapfill::DATE_BIN_GAPFILL_UDF.clone()),
+            gapfill::LOCF_UDF_NAME => Ok(gapfill::LOCF_UDF.clone()),
+            gapfill::INTERPOLATE_UDF_NAME => Ok(gapfill::INTERPOLATE_UDF.clone()),
             _ => Err(DataFusionError::Plan(format!(
                 "IOx FunctionRegistry does not contain function '{}'",
                 name
@@ -42,10 +51,23 @@ impl FunctionRegistry for IOxFunctionRegistry {
     }
 
     fn udaf(&self, name: &str) -> DataFusionResult<Arc<AggregateUDF>> {
-        Err(DataFusionError::Plan(format!(
-            "IOx FunctionRegistry does not contain user defined aggregate function '{}'",
-            name
-        )))
+        match name {
+            derivative::DERIVATIVE_UDAF_NAME => Ok(derivative::DERIVATIVE_UDAF.clone()),
+            sketches::APPROX_COUNT_DISTINCT_UDAF_NAME => {
+                Ok(sketches::APPROX_COUNT_DISTINCT_UDAF.clone())
+            }
+            sketches::HLL_SKETCH_UDAF_NAME => Ok(sketches::HLL_SKETCH_UDAF.clone()),
+            sketches::HLL_MERGE_UDAF_NAME => Ok(sketches::HLL_MERGE_UDAF.clone()),
+            sketches::HLL_COUNT_UDAF_NAME => Ok(sketches::HLL_COUNT_UDAF.clone()),
+            sketches::APPROX_PERCENTILE_UDAF_NAME => Ok(sketches::APPROX_PERCENTILE_UDAF.clone()),
+            sketches::TDIGEST_SKETCH_UDAF_NAME => Ok(sketches::TDIGEST_SKETCH_UDAF.clone()),
+            sketches::TDIGEST_MERGE_UDAF_NAME => Ok(sketches::TDIGEST_MERGE_UDAF.clone()),
+            sketches::TDIGEST_PERCENTILE_UDAF_NAME => Ok(sketches::TDIGEST_PERCENTILE_UDAF.clone()),
+            _ => Err(DataFusionError::Plan(format!(
+                "IOx FunctionRegistry does not contain user defined aggregate function '{}'",
+                name
+            ))),
+        }
     }
 }
 
@@ -0,0 +1,616 @@
+//! Approximate aggregates backed by mergeable sketches.
+//!
+//! | function                          | result                                             |
+//! |-----------------------------------|----------------------------------------------------|
+//! | `approx_count_distinct(value)`    | estimated number of distinct values                |
+//! | `hll_sketch(value)`               | HyperLogLog sketch of the values                   |
+//! | `hll_merge(sketch)`               | union of HyperLogLog sketches                      |
+//! | `hll_count(sketch)`               | estimated number of distinct values of sketches    |
+//! | `approx_percentile(value, p)`     | estimated value at percentile `p` in `[0, 1]`      |
+//! | `tdigest_sketch(value)`           | t-digest sketch of the values                      |
+//! | `tdigest_merge(sketch)`           | union of t-digest sketches                         |
+//! | `tdigest_percentile(sketch, p)`   | estimated value at percentile `p` of sketches      |
+//!
+//! The sketches are returned as binary values, so partial results can be stored or computed
+//! per group and re-aggregated later, e.g. the distinct hosts over all regions:
+//!
+//! ```sql
+//! SELECT hll_count(hosts) FROM (SELECT region, hll_sketch(host) AS hosts FROM cpu GROUP BY region)
+//! ```
+//!
+//! Null values are ignored. Distinct counts have a standard error of about 1.6%.
+use std::sync::Arc;
+
+use arrow::{
+    array::{as_primitive_array, Array, ArrayRef, BinaryArray},
+    compute::cast,
+    datatypes::{DataType, Float64Type},
+};
+use datafusion::{
+    error::{DataFusionError, Result as DataFusionResult},
+    execution::context::SessionState,
+    logical_expr::{
+        AccumulatorFunctionImplementation, AggregateState, ReturnTypeFunction, Signature,
+        StateTypeFunction, TypeSignature, Volatility,
+    },
+    physical_plan::{udaf::AggregateUDF, Accumulator},
+    scalar::ScalarValue,
+};
+use once_cell::sync::Lazy;
+
+mod hll;
+mod tdigest;
+use hll::{hash_values, HyperLogLog};
+use tdigest::TDigest;
+
+/// The name of the `approx_count_distinct` UDAF given to DataFusion.
+pub const APPROX_COUNT_DISTINCT_UDAF_NAME: &str = "approx_count_distinct";
+
+/// The name of the `hll_sketch` UDAF given to DataFusion.
+pub const HLL_SKETCH_UDAF_NAME: &str = "hll_sketch";
+
+/// The name of the `hll_merge` UDAF given to DataFusion.
+pub const HLL_MERGE_UDAF_NAME: &str = "hll_merge";
+
+/// The name of the `hll_count` UDAF given to DataFusion.
+pub const HLL_COUNT_UDAF_NAME: &str = "hll_count";
+
+/// The name of the `approx_percentile` UDAF given to DataFusion.
+pub const APPROX_PERCENTILE_UDAF_NAME: &str = "approx_percentile";
+
+/// The name of the `tdigest_sketch` UDAF given to DataFusion.
+pub const TDIGEST_SKETCH_UDAF_NAME: &str = "tdigest_sketch";
+
+/// The name of the `tdigest_merge` UDAF given to DataFusion.
+pub const TDIGEST_MERGE_UDAF_NAME: &str = "tdigest_merge";
+
+/// The name of the `tdigest_percentile` UDAF given to DataFusion.
+pub const TDIGEST_PERCENTILE_UDAF_NAME: &str = "tdigest_percentile";
+
+/// Numeric types accepted by the t-digest aggregates, which compute over `Float64`.
+const NUMERIC_TYPES: [DataType; 3] = [DataType::Float64, DataType::Int64, DataType::UInt64];
+
+pub(crate) static APPROX_COUNT_DISTINCT_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    hll_udaf(
+        APPROX_COUNT_DISTINCT_UDAF_NAME,
+        Signature::any(1, Volatility::Stable),
+        Input::Values,
+        Output::Estimate,
+    )
+});
+
+pub(crate) static HLL_SKETCH_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    hll_udaf(
+        HLL_SKETCH_UDAF_NAME,
+        Signature::any(1, Volatility::Stable),
+        Input::Values,
+        Output::Sketch,
+    )
+});
+
+pub(crate) static HLL_MERGE_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    hll_udaf(
+        HLL_MERGE_UDAF_NAME,
+        Signature::exact(vec![DataType::Binary], Volatility::Stable),
+        Input::Sketches,
+        Output::Sketch,
+    )
+});
+
+pub(crate) static HLL_COUNT_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    hll_udaf(
+        HLL_COUNT_UDAF_NAME,
+        Signature::exact(vec![DataType::Binary], Volatility::Stable),
+        Input::Sketches,
+        Output::Estimate,
+    )
+});
+
+pub(crate) static APPROX_PERCENTILE_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    let signature = Signature::one_of(
+        NUMERIC_TYPES
+            .into_iter()
+            .map(|value_type| TypeSignature::Exact(vec![value_type, DataType::Float64]))
+            .collect(),
+        Volatility::Stable,
+    );
+    tdigest_udaf(
+        APPROX_PERCENTILE_UDAF_NAME,
+        signature,
+        Input::Values,
+        Output::Estimate,
+    )
+});
+
+pub(crate) static TDIGEST_SKETCH_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    let signature = Signature::one_of(
+        NUMERIC_TYPES
+            .into_iter()
+            .map(|value_type| TypeSignature::Exact(vec![value_type]))
+            .collect(),
+        Volatility::Stable,
+    );
+    tdigest_udaf(
+        TDIGEST_SKETCH_UDAF_NAME,
+        signature,
+        Input::Values,
+        Output::Sketch,
+    )
+});
+
+pub(crate) static TDIGEST_MERGE_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    tdigest_udaf(
+        TDIGEST_MERGE_UDAF_NAME,
+        Signature::exact(vec![DataType::Binary], Volatility::Stable),
+        Input::Sketches,
+        Output::Sketch,
+    )
+});
+
+pub(crate) static TDIGEST_PERCENTILE_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    tdigest_udaf(
+        TDIGEST_PERCENTILE_UDAF_NAME,
+        Signature::exact(
+            vec![DataType::Binary, DataType::Float64],
+            Volatility::Stable,
+        ),
+        Input::Sketches,
+        Output::Estimate,
+    )
+});
+
+/// registers the sketch functions so they can be invoked via SQL
+pub fn register_sketch_aggregates(mut state: SessionState) -> SessionState {
+    for udaf in [
+        &APPROX_COUNT_DISTINCT_UDAF,
+        &HLL_SKETCH_UDAF,
+        &HLL_MERGE_UDAF,
+        &HLL_COUNT_UDAF,
+        &APPROX_PERCENTILE_UDAF,
+        &TDIGEST_SKETCH_UDAF,
+        &TDIGEST_MERGE_UDAF,
+        &TDIGEST_PERCENTILE_UDAF,
+    ] {
+        let udaf = Arc::clone(udaf);
+        state.aggregate_functions.insert(udaf.name.clone(), udaf);
+    }
+
+    state
+}
+
+/// What the first argument of a sketch aggregate holds.
+#[derive(Debug, Clone, Copy)]
+enum Input {
+    /// Values to add to the sketch.
+    Values,
+
+    /// Serialized sketches to merge.
+    Sketches,
+}
+
+/// What a sketch aggregate returns.
+#[derive(Debug, Clone, Copy)]
+enum Output {
+    /// The serialized sketch.
+    Sketch,
+
+    /// The estimate of the sketch.
+    Estimate,
+}
+
+fn hll_udaf(name: &str, signature: Signature, input: Input, output: Output) -> Arc<AggregateUDF> {
+    let return_type = match output {
+        Output::Sketch => DataType::Binary,
+        Output::Estimate => DataType::UInt64,
+    };
+    let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(Arc::new(return_type.clone())));
+    let accumulator: AccumulatorFunctionImplementation =
+        Arc::new(move |_| Ok(Box::new(HllAccumulator::new(input, output))));
+    // state is the serialized sketch
+    let state_type: StateTypeFunction = Arc::new(|_| Ok(Arc::new(vec![DataType::Binary])));
+
+    Arc::new(AggregateUDF::new(
+        name,
+        &signature,
+        &return_type,
+        &accumulator,
+        &state_type,
+    ))
+}
+
+fn tdigest_udaf(
+    name: &str,
+    signature: Signature,
+    input: Input,
+    output: Output,
+) -> Arc<AggregateUDF> {
+    let return_type = match output {
+        Output::Sketch => DataType::Binary,
+        Output::Estimate => DataType::Float64,
+    };
+    let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(Arc::new(return_type.clone())));
+    let accumulator: AccumulatorFunctionImplementation =
+        Arc::new(move |_| Ok(Box::new(TDigestAccumulator::new(input, output))));
+    // state is the serialized sketch and the requested percentile, since the final aggregation
+    // only sees the state
+    let state_type: StateTypeFunction =
+        Arc::new(|_| Ok(Arc::new(vec![DataType::Binary, DataType::Float64])));
+
+    Arc::new(AggregateUDF::new(
+        name,
+        &signature,
+        &return_type,
+        &accumulator,
+        &state_type,
+    ))
+}
+
+/// Calls `f` with each non-null sketch of a binary `array`.
+fn for_each_sketch(
+    array: &ArrayRef,
+    f: impl FnMut(&[u8]) -> DataFusionResult<()>,
+) -> DataFusionResult<()> {
+    let array = array
+        .as_any()
+        .downcast_ref::<BinaryArray>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "Internal error: Expected binary sketches but got {:?}",
+                array.data_type()
+            ))
+        })?;
+
+    array.iter().flatten().try_for_each(f)
+}
+
+#[derive(Debug)]
+struct HllAccumulator {
+    hll: HyperLogLog,
+    input: Input,
+    output: Output,
+}
+
+impl HllAccumulator {
+    fn new(input: Input, output: Output) -> Self {
+        Self {
+            hll: HyperLogLog::default(),
+            input,
+            output,
+        }
+    }
+
+    fn merge_sketches(&mut self, sketches: &ArrayRef) -> DataFusionResult<()> {
+        for_each_sketch(sketches, |sketch| {
+            self.hll.merge(&HyperLogLog::from_bytes(sketch)?);
+            Ok(())
+        })
+    }
+}
+
+impl Accumulator for HllAccumulator {
+    fn state(&self) -> DataFusionResult<Vec<AggregateState>> {
+        Ok(vec![AggregateState::Scalar(ScalarValue::Binary(Some(
+            self.hll.to_bytes(),
+        )))])
+    }
+
+    fn evaluate(&self) -> DataFusionResult<ScalarValue> {
+        Ok(match self.output {
+            Output::Sketch => ScalarValue::Binary(Some(self.hll.to_bytes())),
+            Output::Estimate => ScalarValue::UInt64(Some(self.hll.estimate())),
+        })
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> DataFusionResult<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        match self.input {
+            Input::Values => hash_values(&values[0], |hash| self.hll.add_hash(hash)),
+            Input::Sketches => self.merge_sketches(&values[0]),
+        }
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> DataFusionResult<()> {
+        if states.len() != 1 {
+            return Err(DataFusionError::Internal(format!(
+                "Internal error: Expected 1 state column for HyperLogLog but got {}",
+                states.len()
+            )));
+        }
+
+        self.merge_sketches(&states[0])
+    }
+}
+
+#[derive(Debug)]
+struct TDigestAccumulator {
+    digest: TDigest,
+    input: Input,
+    output: Output,
+    percentile: Option<f64>,
+}
+
+impl TDigestAccumulator {
+    fn new(input: Input, output: Output) -> Self {
+        Self {
+            digest: TDigest::default(),
+            input,
+            output,
+            percentile: None,
+        }
+    }
+
+    fn merge_sketches(&mut self, sketches: &ArrayRef) -> DataFusionResult<()> {
+        for_each_sketch(sketches, |sketch| {
+            self.digest.merge(&TDigest::from_bytes(sketch)?);
+            Ok(())
+        })
+    }
+
+    /// Take the percentile from the first non-null value of `percentiles`, if not known yet.
+    fn update_percentile(&mut self, percentiles: &ArrayRef) -> DataFusionResult<()> {
+        if self.percentile.is_some() {
+            return Ok(());
+        }
+
+        let percentiles = as_primitive_array::<Float64Type>(percentiles.as_ref());
+        if let Some(percentile) = percentiles.iter().flatten().next() {
+            if !(0.0..=1.0).contains(&percentile) {
+                return Err(DataFusionError::Execution(format!(
+                    "percentile must be between 0 and 1, got {}",
+                    percentile
+                )));
+            }
+            self.percentile = Some(percentile);
+        }
+        Ok(())
+    }
+}
+
+impl Accumulator for TDigestAccumulator {
+    // state is (sketch, percentile)
+    fn state(&self) -> DataFusionResult<Vec<AggregateState>> {
+        Ok(vec![
+            AggregateState::Scalar(ScalarValue::Binary(Some(self.digest.to_bytes()))),
+            AggregateState::Scalar(ScalarValue::Float64(self.percentile)),
+        ])
+    }
+
+    fn evaluate(&self) -> DataFusionResult<ScalarValue> {
+        Ok(match self.output {
+            Output::Sketch => ScalarValue::Binary(Some(self.digest.to_bytes())),
+            Output::Estimate => ScalarValue::Float64(
+                self.percentile
+                    .and_then(|percentile| self.digest.quantile(percentile)),
+            ),
+        })
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> DataFusionResult<()> {
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(percentiles) = values.get(1) {
+            self.update_percentile(percentiles)?;
+        }
+
+        match self.input {
+            Input::Values => {
+                let values = cast(&values[0], &DataType::Float64)?;
+                as_primitive_array::<Float64Type>(values.as_ref())
+                    .iter()
+                    .flatten()
+                    .for_each(|value| self.digest.add(value));
+                Ok(())
+            }
+            Input::Sketches => self.merge_sketches(&values[0]),
+        }
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> DataFusionResult<()> {
+        if states.len() != 2 {
+            return Err(DataFusionError::Internal(format!(
+                "Internal error: Expected 2 state columns for t-digest but got {}",
+                states.len()
+            )));
+        }
+
+        self.update_percentile(&states[1])?;
+        self.merge_sketches(&states[0])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::{
+        array::{DictionaryArray, Float64Array, Int64Array, StringArray},
+        datatypes::Int32Type,
+        record_batch::RecordBatch,
+    };
+    use datafusion::{assert_batches_sorted_eq, prelude::SessionContext};
+    use datafusion_util::context_with_table;
+
+    use super::*;
+
+    /// 1000 rows over 4 regions of 10 hosts each, with values 0 to 99 and usages 0 to 249 per
+    /// region.
+    fn context() -> SessionContext {
+        let region: DictionaryArray<Int32Type> = (0..1_000)
+            .map(|i| ["eu", "us", "ap", "sa"][i % 4])
+            .collect();
+        let batch = RecordBatch::try_from_iter(vec![
+            ("region", Arc::new(region) as ArrayRef),
+            (
+                "host",
+                Arc::new(StringArray::from_iter_values(
+                    (0..1_000).map(|i| format!("host-{}", i % 40)),
+                )) as ArrayRef,
+            ),
+            (
+                "value",
+                Arc::new(Int64Array::from_iter(
+                    (0..1_000).map(|i| (i < 800).then_some(i / 4 % 100)),
+                )) as ArrayRef,
+            ),
+            (
+                "usage",
+                Arc::new(Float64Array::from_iter_values(
+                    (0..1_000).map(|i| (i / 4 % 250) as f64),
+                )) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let ctx = context_with_table(batch);
+        for udaf in [
+            &APPROX_COUNT_DISTINCT_UDAF,
+            &HLL_SKETCH_UDAF,
+            &HLL_MERGE_UDAF,
+            &HLL_COUNT_UDAF,
+            &APPROX_PERCENTILE_UDAF,
+            &TDIGEST_SKETCH_UDAF,
+            &TDIGEST_MERGE_UDAF,
+            &TDIGEST_PERCENTILE_UDAF,
+        ] {
+            ctx.register_udaf(udaf.as_ref().clone());
+        }
+        ctx
+    }
+
+    async fn run(ctx: &SessionContext, sql: &str) -> Vec<RecordBatch> {
+        ctx.sql(sql).await.unwrap().collect().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_approx_count_distinct() {
+        let ctx = context();
+
+        let result = run(
+            &ctx,
+            "SELECT region, approx_count_distinct(host) AS hosts, \
+             approx_count_distinct(value) AS vals \
+             FROM t GROUP BY region",
+        )
+        .await;
+        let expected = vec![
+            "+--------+-------+------+",
+            "| region | hosts | vals |",
+            "+--------+-------+------+",
+            "| ap     | 10    | 100  |",
+            "| eu     | 10    | 100  |",
+            "| sa     | 10    | 100  |",
+            "| us     | 10    | 100  |",
+            "+--------+-------+------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &result);
+
+        // the dictionary encoded region
+        let result = run(
+            &ctx,
+            "SELECT approx_count_distinct(region) AS regions FROM t",
+        )
+        .await;
+        let expected = vec![
+            "+---------+",
+            "| regions |",
+            "+---------+",
+            "| 4       |",
+            "+---------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &result);
+    }
+
+    #[tokio::test]
+    async fn test_hll_sketches() {
+        let ctx = context();
+
+        // sketches per region re-aggregate into the count over all regions
+        let result = run(
+            &ctx,
+            "SELECT hll_count(sketch) AS hosts FROM (\
+             SELECT region, hll_sketch(host) AS sketch FROM t GROUP BY region)",
+        )
+        .await;
+        let expected = vec![
+            "+-------+",
+            "| hosts |",
+            "+-------+",
+            "| 40    |",
+            "+-------+",
+        ];
+        assert_batches_sorted_eq!(&expected, &result);
+
+        // ... also after merging them
+        let result = run(
+            &ctx,
+            "SELECT hll_count(merged) AS hosts FROM (\
+             SELECT hll_merge(sketch) AS merged FROM (\
+             SELECT region, hll_sketch(host) AS sketch FROM t GROUP BY region))",
+        )
+        .await;
+        assert_batches_sorted_eq!(&expected, &result);
+    }
+
+    #[tokio::test]
+    async fn test_approx_percentile() {
+        let ctx = context();
+
+        let result = run(
+            &ctx,
+            "SELECT region, approx_percentile(value, 0.5) AS median, \
+             approx_percentile(value, 1.0) AS max, approx_percentile(usage, 0.0) AS min \
+             FROM t GROUP BY region",
+        )
+        .await;
+        let expected = vec![
+            "+--------+--------+------+-----+",
+            "| region | median | max  | min |",
+            "+--------+--------+------+-----+",
+            "| ap     | 49.5   | 99   | 0   |",
+            "| eu     | 49.5   | 99   | 0   |",
+            "| sa     | 49.5   | 99   | 0   |",
+            "| us     | 49.5   | 99   | 0   |",
+            "+--------+--------+------+-----+",
+        ];
+        assert_batches_sorted_eq!(&expected, &result);
+
+        let err = ctx
+            .sql("SELECT approx_percentile(value, 50.0) FROM t")
+            .await
+            .unwrap()
+            .collect()
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("percentile must be between 0 and 1, got 50"),
+            "{}",
+            err
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tdigest_sketches() {
+        let ctx = context();
+
+        let result = run(
+            &ctx,
+            "SELECT tdigest_percentile(merged, 0.5) AS median, \
+             tdigest_percentile(merged, 0.0) AS min FROM (\
+             SELECT tdigest_merge(sketch) AS merged FROM (\
+             SELECT region, tdigest_sketch(usage) AS sketch FROM t GROUP BY region))",
+        )
+        .await;
+        let expected = vec![
+            "+--------+-----+",
+            "| median | min |",
+            "+--------+-----+",
+            "| 124.5  | 0   |",
+            "+--------+-----+",
+        ];
+        assert_batches_sorted_eq!(&expected, &result);
+    }
+}
@@ -0,0 +1,238 @@
+//! HyperLogLog sketch estimating the number of distinct values.
+
+use arrow::{
+    array::{as_boolean_array, as_primitive_array, as_string_array, Array, ArrayRef},
+    compute::cast,
+    datatypes::{DataType, Float64Type, Int64Type, TimeUnit, TimestampNanosecondType, UInt64Type},
+};
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+
+/// Number of bits of the hash that select the register.
+///
+/// 2^12 registers of one byte keep serialized sketches small, with a standard error of about
+/// 1.6%.
+const PRECISION: u32 = 12;
+
+const NUM_REGISTERS: usize = 1 << PRECISION;
+
+/// Version of the serialized format, written as the first byte.
+const FORMAT_VERSION: u8 = 1;
+
+/// A HyperLogLog sketch with [`PRECISION`] bits of precision.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self {
+            registers: vec![0; NUM_REGISTERS],
+        }
+    }
+}
+
+impl HyperLogLog {
+    /// Add a value by its [`hash`](hash_bytes).
+    pub(crate) fn add_hash(&mut self, hash: u64) {
+        let idx = (hash >> (64 - PRECISION)) as usize;
+        let rank = ((hash << PRECISION).leading_zeros() + 1).min(64 - PRECISION + 1) as u8;
+        self.registers[idx] = self.registers[idx].max(rank);
+    }
+
+    /// Add the values of `other`.
+    pub(crate) fn merge(&mut self, other: &Self) {
+        for (register, other) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    /// Estimated number of distinct values added.
+    pub(crate) fn estimate(&self) -> u64 {
+        let m = NUM_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self
+            .registers
+            .iter()
+            .map(|register| 2f64.powi(-(*register as i32)))
+            .sum();
+        let raw = alpha * m * m / sum;
+
+        // small cardinalities are estimated better by linear counting
+        let zeros = self
+            .registers
+            .iter()
+            .filter(|register| **register == 0)
+            .count();
+        let estimate = if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        };
+
+        estimate.round() as u64
+    }
+
+    /// Serialize the sketch.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(2 + NUM_REGISTERS);
+        data.push(FORMAT_VERSION);
+        data.push(PRECISION as u8);
+        data.extend_from_slice(&self.registers);
+        data
+    }
+
+    /// Deserialize a sketch written by [`to_bytes`](Self::to_bytes).
+    pub(crate) fn from_bytes(data: &[u8]) -> DataFusionResult<Self> {
+        match data {
+            [FORMAT_VERSION, precision, registers @ ..]
+                if *precision as u32 == PRECISION && registers.len() == NUM_REGISTERS =>
+            {
+                Ok(Self {
+                    registers: registers.to_vec(),
+                })
+            }
+            _ => Err(DataFusionError::Execution(format!(
+                "invalid HyperLogLog sketch of {} bytes",
+                data.len()
+            ))),
+        }
+    }
+}
+
+/// Calls `f` with the hash of each non-null value of `array`.
+///
+/// Dictionaries are hashed by their values. The hashes are stable across releases, since
+/// sketches may be stored.
+pub(crate) fn hash_values(array: &ArrayRef, mut f: impl FnMut(u64)) -> DataFusionResult<()> {
+    match array.data_type() {
+        DataType::Dictionary(_, value_type) => {
+            let array = cast(array, value_type)?;
+            hash_values(&array, f)?;
+        }
+        DataType::Utf8 => as_string_array(array)
+            .iter()
+            .flatten()
+            .for_each(|v| f(hash_bytes(v.as_bytes()))),
+        DataType::Int64 => as_primitive_array::<Int64Type>(array)
+            .iter()
+            .flatten()
+            .for_each(|v| f(hash_bytes(&v.to_le_bytes()))),
+        DataType::UInt64 => as_primitive_array::<UInt64Type>(array)
+            .iter()
+            .flatten()
+            .for_each(|v| f(hash_bytes(&v.to_le_bytes()))),
+        DataType::Float64 => as_primitive_array::<Float64Type>(array)
+            .iter()
+            .flatten()
+            .for_each(|v| f(hash_bytes(&v.to_bits().to_le_bytes()))),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            as_primitive_array::<TimestampNanosecondType>(array)
+                .iter()
+                .flatten()
+                .for_each(|v| f(hash_bytes(&v.to_le_bytes())))
+        }
+        DataType::Boolean => as_boolean_array(array)
+            .iter()
+            .flatten()
+            .for_each(|v| f(hash_bytes(&[v as u8]))),
+        other => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "HyperLogLog sketches of {other:?} values"
+            )))
+        }
+    }
+
+    Ok(())
+}
+
+/// 64-bit FNV-1a hash of `data`, finalized with the MurmurHash3 mixer so that all bits of the
+/// result depend on all bits of the input.
+fn hash_bytes(data: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for byte in data {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::array::{DictionaryArray, Int64Array};
+    use arrow::datatypes::Int32Type;
+
+    use super::*;
+
+    fn hll_of(values: impl IntoIterator<Item = i64>) -> HyperLogLog {
+        let mut hll = HyperLogLog::default();
+        for value in values {
+            hll.add_hash(hash_bytes(&value.to_le_bytes()));
+        }
+        hll
+    }
+
+    fn assert_close(estimate: u64, expected: u64) {
+        let error = (estimate as f64 - expected as f64).abs() / expected as f64;
+        assert!(error < 0.05, "estimate {estimate} for {expected}");
+    }
+
+    #[test]
+    fn test_estimate() {
+        assert_eq!(HyperLogLog::default().estimate(), 0);
+        assert_eq!(hll_of([1, 2, 3, 2, 1]).estimate(), 3);
+
+        for n in [1_000, 10_000, 100_000] {
+            assert_close(hll_of(0..n).estimate(), n as u64);
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut hll = hll_of(0..50_000);
+        hll.merge(&hll_of(25_000..100_000));
+        assert_eq!(hll, hll_of(0..100_000));
+        assert_close(hll.estimate(), 100_000);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let hll = hll_of(0..1_000);
+        assert_eq!(HyperLogLog::from_bytes(&hll.to_bytes()).unwrap(), hll);
+
+        assert!(HyperLogLog::from_bytes(&[]).is_err());
+        assert!(HyperLogLog::from_bytes(&hll.to_bytes()[..100]).is_err());
+    }
+
+    #[test]
+    fn test_hash_values() {
+        let hashes = |array: ArrayRef| {
+            let mut hashes = vec![];
+            hash_values(&array, |hash| hashes.push(hash)).unwrap();
+            hashes
+        };
+
+        let dictionary: DictionaryArray<Int32Type> = vec![Some("a"), None, Some("b"), Some("a")]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            hashes(Arc::new(dictionary)),
+            vec![hash_bytes(b"a"), hash_bytes(b"b"), hash_bytes(b"a")]
+        );
+
+        assert_eq!(
+            hashes(Arc::new(Int64Array::from(vec![Some(1), None]))),
+            vec![hash_bytes(&1i64.to_le_bytes())]
+        );
+
+        // the hash must not change, since sketches may be stored
+        assert_eq!(hash_bytes(b""), 0xefd0_1f60_ba99_2926);
+    }
+}
@@ -0,0 +1,304 @@
+//! Merging t-digest sketch estimating the quantiles of values.
+//!
+//! See Dunning & Ertl, "Computing Extremely Accurate Quantiles Using t-Digests".
+
+use std::cmp::Ordering;
+
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+
+/// Compression parameter δ, bounding the number of centroids to about δ.
+const COMPRESSION: f64 = 100.0;
+
+/// Number of values buffered before they are merged into the centroids.
+const BUFFER_SIZE: usize = 500;
+
+/// Version of the serialized format, written as the first byte.
+const FORMAT_VERSION: u8 = 1;
+
+/// Size of the serialized header: version, count, min, max and number of centroids.
+const HEADER_SIZE: usize = 1 + 3 * 8 + 4;
+
+/// Size of a serialized centroid: mean and weight.
+const CENTROID_SIZE: usize = 2 * 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// A t-digest with a compression of [`COMPRESSION`].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TDigest {
+    /// Centroids ordered by mean.
+    centroids: Vec<Centroid>,
+
+    /// Values and centroids not yet merged into `centroids`.
+    buffer: Vec<Centroid>,
+
+    /// Total weight of the digest, including the buffer.
+    count: f64,
+
+    min: f64,
+    max: f64,
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        Self {
+            centroids: vec![],
+            buffer: vec![],
+            count: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl TDigest {
+    /// Add `value`, ignoring NaN.
+    pub(crate) fn add(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+
+        self.push(Centroid {
+            mean: value,
+            weight: 1.0,
+        });
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Add the values of `other`.
+    pub(crate) fn merge(&mut self, other: &Self) {
+        for centroid in other.centroids.iter().chain(&other.buffer) {
+            self.push(*centroid);
+        }
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Estimated value at quantile `q` in `[0, 1]`, or `None` if the digest is empty.
+    ///
+    /// The estimate interpolates between the means of neighbouring centroids, and towards the
+    /// minimum and maximum at the ends.
+    pub(crate) fn quantile(&self, q: f64) -> Option<f64> {
+        let digest = self.compressed();
+        if digest.centroids.is_empty() {
+            return None;
+        }
+
+        let target = q * digest.count;
+        let mut previous = (0.0, digest.min);
+        let mut cumulative = 0.0;
+        for centroid in &digest.centroids {
+            let position = cumulative + centroid.weight / 2.0;
+            if target <= position {
+                return Some(interpolate(previous, (position, centroid.mean), target));
+            }
+            previous = (position, centroid.mean);
+            cumulative += centroid.weight;
+        }
+
+        Some(interpolate(previous, (digest.count, digest.max), target))
+    }
+
+    /// Serialize the sketch.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let digest = self.compressed();
+
+        let mut data = Vec::with_capacity(HEADER_SIZE + digest.centroids.len() * CENTROID_SIZE);
+        data.push(FORMAT_VERSION);
+        data.extend_from_slice(&digest.count.to_le_bytes());
+        data.extend_from_slice(&digest.min.to_le_bytes());
+        data.extend_from_slice(&digest.max.to_le_bytes());
+        data.extend_from_slice(&(digest.centroids.len() as u32).to_le_bytes());
+        for centroid in &digest.centroids {
+            data.extend_from_slice(&centroid.mean.to_le_bytes());
+            data.extend_from_slice(&centroid.weight.to_le_bytes());
+        }
+        data
+    }
+
+    /// Deserialize a sketch written by [`to_bytes`](Self::to_bytes).
+    pub(crate) fn from_bytes(data: &[u8]) -> DataFusionResult<Self> {
+        let invalid = || {
+            DataFusionError::Execution(format!("invalid t-digest sketch of {} bytes", data.len()))
+        };
+
+        if data.len() < HEADER_SIZE || data[0] != FORMAT_VERSION {
+            return Err(invalid());
+        }
+        let read_f64 = |offset: usize| {
+            f64::from_le_bytes(data[offset..offset + 8].try_into().expect("8 bytes"))
+        };
+        let num_centroids =
+            u32::from_le_bytes(data[25..HEADER_SIZE].try_into().expect("4 bytes")) as usize;
+        if data.len() != HEADER_SIZE + num_centroids * CENTROID_SIZE {
+            return Err(invalid());
+        }
+
+        let centroids = (0..num_centroids)
+            .map(|i| {
+                let offset = HEADER_SIZE + i * CENTROID_SIZE;
+                Centroid {
+                    mean: read_f64(offset),
+                    weight: read_f64(offset + 8),
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            centroids,
+            buffer: vec![],
+            count: read_f64(1),
+            min: read_f64(9),
+            max: read_f64(17),
+        })
+    }
+
+    fn push(&mut self, centroid: Centroid) {
+        self.buffer.push(centroid);
+        self.count += centroid.weight;
+        if self.buffer.len() >= BUFFER_SIZE {
+            self.compress();
+        }
+    }
+
+    /// Returns a copy of the digest with the buffer merged into the centroids.
+    fn compressed(&self) -> Self {
+        let mut digest = self.clone();
+        digest.compress();
+        digest
+    }
+
+    /// Merge the buffer into the centroids.
+    fn compress(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let mut centroids = std::mem::take(&mut self.centroids);
+        centroids.append(&mut self.buffer);
+        centroids.sort_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap_or(Ordering::Equal));
+
+        let total = self.count;
+        let limit = |weight_before: f64| {
+            let next_k = k(weight_before / total) + 1.0;
+            if next_k >= COMPRESSION / 4.0 {
+                total
+            } else {
+                k_inv(next_k) * total
+            }
+        };
+
+        let mut centroids = centroids.into_iter();
+        let mut current = centroids.next().expect("buffer is not empty");
+        let mut weight_before = 0.0;
+        let mut weight_limit = limit(weight_before);
+        for centroid in centroids {
+            if weight_before + current.weight + centroid.weight <= weight_limit {
+                current.weight += centroid.weight;
+                current.mean += (centroid.mean - current.mean) * centroid.weight / current.weight;
+            } else {
+                weight_before += current.weight;
+                self.centroids.push(current);
+                current = centroid;
+                weight_limit = limit(weight_before);
+            }
+        }
+        self.centroids.push(current);
+    }
+}
+
+/// Scale function k1, which keeps centroids near the ends of the distribution small.
+fn k(q: f64) -> f64 {
+    COMPRESSION / (2.0 * std::f64::consts::PI) * (2.0 * q - 1.0).asin()
+}
+
+/// Inverse of [`k`].
+fn k_inv(k: f64) -> f64 {
+    ((k * 2.0 * std::f64::consts::PI / COMPRESSION).sin() + 1.0) / 2.0
+}
+
+/// Linear interpolation of the value at `position` between the `(position, value)` points `a`
+/// and `b`.
+fn interpolate(a: (f64, f64), b: (f64, f64), position: f64) -> f64 {
+    if b.0 <= a.0 {
+        b.1
+    } else {
+        a.1 + (b.1 - a.1) * (position - a.0) / (b.0 - a.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest_of(values: impl IntoIterator<Item = i64>) -> TDigest {
+        let mut digest = TDigest::default();
+        for value in values {
+            digest.add(value as f64);
+        }
+        digest
+    }
+
+    fn assert_close(estimate: Option<f64>, expected: f64) {
+        let estimate = estimate.unwrap();
+        assert!(
+            (estimate - expected).abs() < 20.0,
+            "estimate {estimate} for {expected}"
+        );
+    }
+
+    #[test]
+    fn test_quantile() {
+        assert_eq!(TDigest::default().quantile(0.5), None);
+
+        let digest = digest_of([5, 1, 4, 2, 3]);
+        assert_eq!(digest.quantile(0.0), Some(1.0));
+        assert_eq!(digest.quantile(0.5), Some(3.0));
+        assert_eq!(digest.quantile(1.0), Some(5.0));
+
+        // the order of values barely matters
+        let digest = digest_of((0..10_000).map(|i| (i * 7_919) % 10_000));
+        assert!(digest.compressed().centroids.len() <= COMPRESSION as usize);
+        for q in [0.01, 0.5, 0.9, 0.99] {
+            assert_close(digest.quantile(q), q * 10_000.0);
+        }
+        assert_eq!(digest.quantile(0.0), Some(0.0));
+        assert_eq!(digest.quantile(1.0), Some(9_999.0));
+
+        // NaN is ignored
+        let mut digest = digest_of([1]);
+        digest.add(f64::NAN);
+        assert_eq!(digest.quantile(0.5), Some(1.0));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut digest = digest_of(5_000..10_000);
+        digest.merge(&digest_of(0..5_000));
+        for q in [0.01, 0.5, 0.9, 0.99] {
+            assert_close(digest.quantile(q), q * 10_000.0);
+        }
+
+        let mut empty = TDigest::default();
+        empty.merge(&TDigest::default());
+        assert_eq!(empty.quantile(0.5), None);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let digest = digest_of(0..1_000);
+        let decoded = TDigest::from_bytes(&digest.to_bytes()).unwrap();
+        assert_eq!(decoded, digest.compressed());
+
+        let empty = TDigest::from_bytes(&TDigest::default().to_bytes()).unwrap();
+        assert_eq!(empty, TDigest::default());
+
+        assert!(TDigest::from_bytes(&[]).is_err());
+        assert!(TDigest::from_bytes(&digest.to_bytes()[..100]).is_err());
+    }
+}
@@ -646,7 +646,7 @@ struct MockIngester {
 /// Query-test specific executor with static properties that may be relevant for the query optimizer and therefore may
 /// change `EXPLAIN` plans.
 static GLOBAL_EXEC: Lazy<Arc<DedicatedExecutors>> =
-    Lazy::new(|| Arc::new(DedicatedExecutors::new(1)));
+    Lazy::new(|| Arc::new(DedicatedExecutors::new(1, 1)));
 
 impl MockIngester {
     /// Create new empty ingester.
@@ -1019,6 +1019,9 @@ impl QueryDataAdapter {
                                     parquet_max_sequence_number: status
                                         .parquet_max_sequence_number
                                         .map(|x| x.get()),
+                                    max_sequence_number: status
+                                        .max_sequence_number
+                                        .map(|x| x.get()),
                                 }),
                             },
                         ),
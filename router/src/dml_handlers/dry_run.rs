@@ -0,0 +1,529 @@
+//! Validation-only ("dry run") processing of writes.
+
+use std::{borrow::Cow, collections::BTreeMap, ops::DerefMut, sync::Arc};
+
+use data_types::{NamespaceName, PartitionTemplate, TopicId};
+use hashbrown::HashMap;
+use iox_catalog::{
+    interface::{get_schema_by_name, Catalog, Error as CatalogError},
+    validate_schema,
+};
+use iox_time::{SystemProvider, TimeProvider};
+use mutable_batch::{MutableBatch, PartitionWrite, WritePayload};
+use observability_deps::tracing::*;
+use serde::Serialize;
+use sharder::Sharder;
+
+use super::{
+    retention_validator::validate_retention,
+    schema_validation::{resolve_conflicts, validate_column_limits, SchemaConflictPolicy},
+    DmlError, PartitionError, SchemaError, TopicRouterError, Transformer,
+};
+use crate::shard::Shard;
+
+/// The outcome of a dry-run write: what the write would have done had it been
+/// applied.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct DryRunReport {
+    /// One entry per table in the write, ordered by table name.
+    pub tables: Vec<TableReport>,
+    /// The number of rows the conflict policy of the namespace would drop
+    /// from the write.
+    pub dropped_rows: usize,
+}
+
+/// The effect of a dry-run write on a single table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableReport {
+    /// The table name.
+    pub table: String,
+    /// True if the write would create the table.
+    pub new_table: bool,
+    /// The columns the write would create, and their types.
+    pub new_columns: BTreeMap<String, String>,
+    /// The partitions the write would be split into, ordered by key.
+    pub partitions: Vec<PartitionReport>,
+}
+
+/// A single partition of a dry-run write.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PartitionReport {
+    /// The partition key.
+    pub partition_key: String,
+    /// The write buffer shard index (Kafka partition) the data would be
+    /// written to.
+    pub shard_index: i32,
+    /// The number of rows in this partition.
+    pub rows: usize,
+}
+
+/// Runs the retention, schema validation, partitioning and sharding steps of
+/// the write path without applying any of them.
+///
+/// Unlike the [`DmlHandler`] stack, the namespace schema is always read
+/// directly from the catalog (rather than the namespace cache) and nothing is
+/// ever written: unknown namespaces are not created, new tables and columns
+/// are not added to the catalog, and no data reaches the write buffer.
+///
+/// Because the schema is not changed, a dry run cannot detect a concurrent
+/// write creating the same column with a different type, nor the
+/// catalog-enforced limit on the number of tables.
+///
+/// [`DmlHandler`]: super::DmlHandler
+#[derive(Debug)]
+pub struct DryRunValidator {
+    catalog: Arc<dyn Catalog>,
+    partition_template: PartitionTemplate,
+    sharder: Arc<dyn Sharder<MutableBatch, Item = Arc<Shard>>>,
+    topic_sharders: Option<HashMap<TopicId, Arc<dyn Sharder<MutableBatch, Item = Arc<Shard>>>>>,
+    time_provider: Arc<dyn TimeProvider>,
+    retention_validation: bool,
+    transformer: Transformer,
+    conflict_policy: SchemaConflictPolicy,
+    namespace_conflict_policies: HashMap<NamespaceName<'static>, SchemaConflictPolicy>,
+}
+
+impl DryRunValidator {
+    /// Initialise a [`DryRunValidator`] reading schemas from `catalog`,
+    /// partitioning writes according to `partition_template` (unless the
+    /// namespace has its own) and assigning them to shards with `sharder`.
+    ///
+    /// These should match the configuration of the real write path.
+    pub fn new(
+        catalog: Arc<dyn Catalog>,
+        partition_template: PartitionTemplate,
+        sharder: Arc<dyn Sharder<MutableBatch, Item = Arc<Shard>>>,
+    ) -> Self {
+        Self {
+            catalog,
+            partition_template,
+            sharder,
+            topic_sharders: None,
+            time_provider: Arc::new(SystemProvider::default()),
+            retention_validation: true,
+            transformer: Transformer::default(),
+            conflict_policy: SchemaConflictPolicy::default(),
+            namespace_conflict_policies: HashMap::default(),
+        }
+    }
+
+    /// Assign writes to the shards of the sharder for the topic of their
+    /// namespace, matching the [`TopicRouter`] of the write path, rather than
+    /// to the shards of the sharder passed to [`DryRunValidator::new`].
+    ///
+    /// Writes to a namespace assigned to any other topic are rejected.
+    ///
+    /// [`TopicRouter`]: super::TopicRouter
+    pub fn with_topic_sharders(
+        self,
+        sharders: impl IntoIterator<Item = (TopicId, Arc<dyn Sharder<MutableBatch, Item = Arc<Shard>>>)>,
+    ) -> Self {
+        Self {
+            topic_sharders: Some(sharders.into_iter().collect()),
+            ..self
+        }
+    }
+
+    /// Enable or disable the validation of writes against the retention
+    /// period of the namespace, matching the [`RetentionValidator`] of the
+    /// write path.
+    ///
+    /// [`RetentionValidator`]: super::RetentionValidator
+    pub fn with_retention_validation(self, enabled: bool) -> Self {
+        Self {
+            retention_validation: enabled,
+            ..self
+        }
+    }
+
+    /// Apply the transform rules of `transformer` to the writes before
+    /// validating them, matching the [`Transformer`] of the write path.
+    pub fn with_transformer(self, transformer: Transformer) -> Self {
+        Self {
+            transformer,
+            ..self
+        }
+    }
+
+    /// Resolve schema conflicts according to `policy` in namespaces without
+    /// their own policy, matching the [`SchemaValidator`] of the write path.
+    ///
+    /// [`SchemaValidator`]: super::SchemaValidator
+    pub fn with_conflict_policy(mut self, policy: SchemaConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    /// Resolve schema conflicts in `namespace` according to `policy`.
+    pub fn with_namespace_conflict_policy(
+        mut self,
+        namespace: NamespaceName<'static>,
+        policy: SchemaConflictPolicy,
+    ) -> Self {
+        self.namespace_conflict_policies.insert(namespace, policy);
+        self
+    }
+
+    /// Validate `batches` for `namespace`, returning the changes the write
+    /// would make, or the error it would be rejected with.
+    pub async fn validate(
+        &self,
+        namespace: &NamespaceName<'static>,
+        batches: &HashMap<String, MutableBatch>,
+    ) -> Result<DryRunReport, DmlError> {
+        let mut batches = match self.transformer.rules(namespace) {
+            Some(rules) => Cow::Owned(rules.apply(batches.clone())?),
+            None => Cow::Borrowed(batches),
+        };
+
+        let mut repos = self.catalog.repositories().await;
+
+        let schema = match get_schema_by_name(namespace, repos.deref_mut()).await {
+            Ok(v) => v,
+            Err(CatalogError::NamespaceNotFoundByName { .. }) => {
+                return Err(DmlError::NamespaceNotFound(namespace.to_string()))
+            }
+            Err(e) => {
+                warn!(error=%e, %namespace, "failed to retrieve namespace schema");
+                return Err(SchemaError::NamespaceLookup(e).into());
+            }
+        };
+
+        if self.retention_validation {
+            validate_retention(&batches, &schema, self.time_provider.now())?;
+        }
+
+        let policy = self
+            .namespace_conflict_policies
+            .get(namespace)
+            .copied()
+            .unwrap_or(self.conflict_policy);
+        let dropped_rows = match policy {
+            SchemaConflictPolicy::Reject => 0,
+            _ => resolve_conflicts(namespace, &schema, policy, batches.to_mut()).dropped_rows,
+        };
+
+        validate_column_limits(&batches, &schema)
+            .map_err(|e| SchemaError::ServiceLimit(Box::new(e)))?;
+
+        let mut changes = validate_schema(batches.iter().map(|(k, v)| (k.as_str(), v)), &schema)
+            .map_err(SchemaError::Conflict)?;
+
+        // Writes to a namespace with its own partition template are
+        // partitioned by it, as the write path does.
+        let partition_template = schema
+            .partition_template
+            .as_ref()
+            .unwrap_or(&self.partition_template);
+
+        let sharder =
+            match &self.topic_sharders {
+                Some(sharders) => sharders.get(&schema.topic_id).ok_or_else(|| {
+                    TopicRouterError::UnservedTopic {
+                        namespace: namespace.to_string(),
+                        topic_id: schema.topic_id,
+                    }
+                })?,
+                None => &self.sharder,
+            };
+
+        let mut tables = Vec::with_capacity(batches.len());
+        for (table_name, batch) in batches.iter() {
+            let mut partitions = Vec::new();
+            for (partition_key, payload) in
+                PartitionWrite::partition(table_name, batch, partition_template)
+            {
+                // Shard on the partitioned batch, as the write path does.
+                let mut partition_batch = MutableBatch::default();
+                payload
+                    .write_to_batch(&mut partition_batch)
+                    .map_err(PartitionError::BatchWrite)?;
+                let shard = sharder.shard(table_name, namespace, &partition_batch);
+
+                partitions.push(PartitionReport {
+                    partition_key: partition_key.to_string(),
+                    shard_index: shard.shard_index().get(),
+                    rows: partition_batch.rows(),
+                });
+            }
+            partitions.sort_unstable_by(|a, b| a.partition_key.cmp(&b.partition_key));
+
+            let table_changes = changes.remove(table_name.as_str()).unwrap_or_default();
+            tables.push(TableReport {
+                table: table_name.clone(),
+                new_table: table_changes.new_table,
+                new_columns: table_changes
+                    .new_columns
+                    .into_iter()
+                    .map(|(name, column_type)| (name, column_type.to_string()))
+                    .collect(),
+                partitions,
+            });
+        }
+        tables.sort_unstable_by(|a, b| a.table.cmp(&b.table));
+
+        debug!(%namespace, tables=tables.len(), "dry run write validated");
+
+        Ok(DryRunReport {
+            tables,
+            dropped_rows,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use data_types::{ColumnType, ShardIndex, TemplatePart};
+    use iox_tests::util::{TestCatalog, TestNamespace};
+    use once_cell::sync::Lazy;
+    use sharder::JumpHash;
+    use write_buffer::mock::{MockBufferForWriting, MockBufferSharedState};
+
+    use super::*;
+    use crate::dml_handlers::TransformRules;
+
+    static NAMESPACE: Lazy<NamespaceName<'static>> = Lazy::new(|| "bananas".try_into().unwrap());
+
+    fn lp_to_writes(lp: &str) -> HashMap<String, MutableBatch> {
+        let (writes, _) = mutable_batch_lp::lines_to_batches_stats(lp, 42)
+            .expect("failed to build test writes from LP");
+        writes
+    }
+
+    async fn test_setup() -> (Arc<TestCatalog>, Arc<TestNamespace>, DryRunValidator) {
+        let catalog = TestCatalog::new();
+        let namespace = catalog.create_namespace_1hr_retention(&NAMESPACE).await;
+
+        let write_buffer = MockBufferForWriting::new(
+            MockBufferSharedState::empty_with_n_shards(1.try_into().unwrap()),
+            None,
+            catalog.time_provider(),
+        )
+        .expect("failed to init mock write buffer");
+        let shard = Shard::new(
+            ShardIndex::new(7),
+            Arc::new(write_buffer),
+            &Default::default(),
+        );
+
+        let validator = DryRunValidator::new(
+            catalog.catalog(),
+            PartitionTemplate {
+                parts: vec![TemplatePart::TimeFormat("%Y-%m-%d".to_owned())],
+            },
+            Arc::new(JumpHash::new([Arc::new(shard)])),
+        );
+
+        (catalog, namespace, validator)
+    }
+
+    /// Returns `lp` with a timestamp of `now` plus `offset_days`.
+    fn lp_at(lp: &str, offset_days: i64) -> String {
+        let now = SystemProvider::default().now().timestamp_nanos();
+        format!("{} {}", lp, now + offset_days * 24 * 3_600 * 1_000_000_000)
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_changes() {
+        let (catalog, namespace, validator) = test_setup().await;
+
+        let table = namespace.create_table("bananas").await;
+        table.create_column("tag1", ColumnType::Tag).await;
+        table.create_column("time", ColumnType::Time).await;
+
+        let writes = lp_to_writes(&format!(
+            "{}\n{}\n{}",
+            lp_at("bananas,tag1=A val=42i", 0),
+            lp_at("bananas,tag1=B val=42i", 1),
+            lp_at("platanos,tag1=A val=4.2", 0),
+        ));
+
+        let report = validator
+            .validate(&NAMESPACE, &writes)
+            .await
+            .expect("dry run should succeed");
+
+        assert_eq!(report.tables.len(), 2);
+
+        let bananas = &report.tables[0];
+        assert_eq!(bananas.table, "bananas");
+        assert!(!bananas.new_table);
+        assert_eq!(
+            bananas.new_columns,
+            BTreeMap::from([("val".to_string(), "i64".to_string())])
+        );
+        assert_eq!(bananas.partitions.len(), 2);
+        assert!(bananas
+            .partitions
+            .iter()
+            .all(|p| p.rows == 1 && p.shard_index == 7));
+
+        let platanos = &report.tables[1];
+        assert_eq!(platanos.table, "platanos");
+        assert!(platanos.new_table);
+        assert_eq!(
+            platanos.new_columns.keys().collect::<Vec<_>>(),
+            ["tag1", "time", "val"]
+        );
+        assert_eq!(platanos.partitions.len(), 1);
+
+        // Nothing was written to the catalog.
+        let mut repos = catalog.catalog.repositories().await;
+        let schema = get_schema_by_name(&NAMESPACE, repos.deref_mut())
+            .await
+            .unwrap();
+        assert_eq!(schema.tables.len(), 1);
+        assert!(!schema.tables["bananas"].columns.contains_key("val"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_conflict() {
+        let (_catalog, namespace, validator) = test_setup().await;
+
+        let table = namespace.create_table("bananas").await;
+        table.create_column("val", ColumnType::F64).await;
+
+        let writes = lp_to_writes(&lp_at("bananas,tag1=A val=42i", 0));
+        let err = validator
+            .validate(&NAMESPACE, &writes)
+            .await
+            .expect_err("dry run should fail");
+        assert_matches!(err, DmlError::Schema(SchemaError::Conflict(e)) => {
+            assert_eq!(e.table(), "bananas");
+        });
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_topic_sharders() {
+        let (catalog, namespace, validator) = test_setup().await;
+
+        let write_buffer = MockBufferForWriting::new(
+            MockBufferSharedState::empty_with_n_shards(1.try_into().unwrap()),
+            None,
+            catalog.time_provider(),
+        )
+        .expect("failed to init mock write buffer");
+        let shard = Shard::new(
+            ShardIndex::new(3),
+            Arc::new(write_buffer),
+            &Default::default(),
+        );
+        let noisy = catalog
+            .catalog()
+            .repositories()
+            .await
+            .topics()
+            .create_or_get("noisy")
+            .await
+            .unwrap();
+        let sharder: Arc<dyn Sharder<MutableBatch, Item = Arc<Shard>>> =
+            Arc::new(JumpHash::new([Arc::new(shard)]));
+        let validator = validator.with_topic_sharders([(noisy.id, sharder)]);
+
+        // The namespace is assigned to a topic without a sharder.
+        let writes = lp_to_writes(&lp_at("bananas,tag1=A val=42i", 0));
+        let err = validator
+            .validate(&NAMESPACE, &writes)
+            .await
+            .expect_err("dry run should fail");
+        assert_matches!(
+            err,
+            DmlError::TopicRouting(TopicRouterError::UnservedTopic { topic_id, .. }) => {
+                assert_eq!(topic_id, namespace.topic.id);
+            }
+        );
+
+        catalog
+            .catalog()
+            .repositories()
+            .await
+            .namespaces()
+            .update_topic(&NAMESPACE, noisy.id)
+            .await
+            .unwrap();
+        let report = validator
+            .validate(&NAMESPACE, &writes)
+            .await
+            .expect("dry run should succeed");
+        assert_eq!(report.tables[0].partitions[0].shard_index, 3);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_conflict_policy() {
+        let (_catalog, namespace, validator) = test_setup().await;
+        let validator = validator.with_conflict_policy(SchemaConflictPolicy::DropConflictingLines);
+
+        let table = namespace.create_table("bananas").await;
+        table.create_column("val", ColumnType::F64).await;
+
+        let writes = lp_to_writes(&format!(
+            "{}\n{}",
+            lp_at("bananas,tag1=A val=42i", 0),
+            lp_at("bananas,tag1=B other=42i", 0),
+        ));
+        let report = validator
+            .validate(&NAMESPACE, &writes)
+            .await
+            .expect("dry run should succeed");
+
+        assert_eq!(report.dropped_rows, 1);
+        assert_eq!(report.tables.len(), 1);
+        assert_eq!(report.tables[0].partitions.len(), 1);
+        assert_eq!(report.tables[0].partitions[0].rows, 1);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_outside_retention() {
+        let (_catalog, _namespace, validator) = test_setup().await;
+
+        let writes = lp_to_writes(&lp_at("bananas,tag1=A val=42i", -1));
+        let err = validator
+            .validate(&NAMESPACE, &writes)
+            .await
+            .expect_err("dry run should fail");
+        assert_matches!(err, DmlError::Retention(_));
+
+        let validator = validator.with_retention_validation(false);
+        validator
+            .validate(&NAMESPACE, &writes)
+            .await
+            .expect("retention is not validated");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_transform() {
+        let (_catalog, _namespace, validator) = test_setup().await;
+        let validator = validator.with_transformer(Transformer::default().with_namespace_rules(
+            NAMESPACE.clone(),
+            TransformRules {
+                rename_measurements: BTreeMap::from([(
+                    "bananas".to_string(),
+                    "platanos".to_string(),
+                )]),
+                ..Default::default()
+            },
+        ));
+
+        let writes = lp_to_writes(&lp_at("bananas,tag1=A val=42i", 0));
+        let report = validator
+            .validate(&NAMESPACE, &writes)
+            .await
+            .expect("dry run should succeed");
+        assert_eq!(report.tables.len(), 1);
+        assert_eq!(report.tables[0].table, "platanos");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_namespace_not_found() {
+        let (_catalog, _namespace, validator) = test_setup().await;
+
+        let ns = NamespaceName::try_from("platanos").unwrap();
+        let writes = lp_to_writes(&lp_at("bananas,tag1=A val=42i", 0));
+        let err = validator
+            .validate(&ns, &writes)
+            .await
+            .expect_err("dry run should fail");
+        assert_matches!(err, DmlError::NamespaceNotFound(_));
+    }
+}
@@ -1,7 +1,9 @@
+use std::{sync::Arc, time::Duration};
+
 use async_trait::async_trait;
 use data_types::{DeletePredicate, NamespaceId, NamespaceName};
 use iox_time::{SystemProvider, TimeProvider};
-use metric::{DurationHistogram, Metric};
+use metric::{CardinalityLimiter, DurationHistogram, Metric};
 use trace::{
     ctx::SpanContext,
     span::{SpanExt, SpanRecorder},
@@ -11,18 +13,26 @@ use super::DmlHandler;
 
 /// An instrumentation decorator recording call latencies for [`DmlHandler`] implementations.
 ///
-/// Metrics are broken down by operation (write/delete) and result (success/error).
+/// Metrics are broken down by operation (write/delete) and result (success/error), and optionally
+/// by namespace, see [`InstrumentationDecorator::with_namespace_labels()`].
 #[derive(Debug)]
 pub struct InstrumentationDecorator<T, P = SystemProvider> {
     name: &'static str,
     inner: T,
     time_provider: P,
 
+    write: Metric<DurationHistogram>,
+    delete: Metric<DurationHistogram>,
+
     write_success: DurationHistogram,
     write_error: DurationHistogram,
 
     delete_success: DurationHistogram,
     delete_error: DurationHistogram,
+
+    /// Bounds the distinct namespaces the metrics are labelled with, if
+    /// enabled.
+    namespace_labels: Option<Arc<CardinalityLimiter>>,
 }
 
 impl<T> InstrumentationDecorator<T> {
@@ -46,10 +56,59 @@ impl<T> InstrumentationDecorator<T> {
             name,
             inner,
             time_provider: Default::default(),
+            write,
+            delete,
             write_success,
             write_error,
             delete_success,
             delete_error,
+            namespace_labels: None,
+        }
+    }
+}
+
+impl<T, P> InstrumentationDecorator<T, P> {
+    /// Additionally label the metrics with the `namespace` of each call.
+    ///
+    /// Namespaces beyond the distinct values allowed by `limiter` are labelled
+    /// as `other`, bounding the number of recorded series. The `limiter` may
+    /// be shared by decorators so they agree on the labelled namespaces.
+    pub fn with_namespace_labels(self, limiter: Arc<CardinalityLimiter>) -> Self {
+        Self {
+            namespace_labels: Some(limiter),
+            ..self
+        }
+    }
+
+    fn record_write(&self, namespace: &NamespaceName<'_>, success: bool, delta: Duration) {
+        let result = if success { "success" } else { "error" };
+        match &self.namespace_labels {
+            Some(limiter) => self
+                .write
+                .recorder([
+                    ("handler", self.name.into()),
+                    ("result", result.into()),
+                    ("namespace", limiter.value(namespace.as_str())),
+                ])
+                .record(delta),
+            None if success => self.write_success.record(delta),
+            None => self.write_error.record(delta),
+        }
+    }
+
+    fn record_delete(&self, namespace: &NamespaceName<'_>, success: bool, delta: Duration) {
+        let result = if success { "success" } else { "error" };
+        match &self.namespace_labels {
+            Some(limiter) => self
+                .delete
+                .recorder([
+                    ("handler", self.name.into()),
+                    ("result", result.into()),
+                    ("namespace", limiter.value(namespace.as_str())),
+                ])
+                .record(delta),
+            None if success => self.delete_success.record(delta),
+            None => self.delete_error.record(delta),
         }
     }
 }
@@ -89,11 +148,11 @@ where
             match &res {
                 Ok(_) => {
                     span_recorder.ok("success");
-                    self.write_success.record(delta)
+                    self.record_write(namespace, true, delta)
                 }
                 Err(e) => {
                     span_recorder.error(e.to_string());
-                    self.write_error.record(delta)
+                    self.record_write(namespace, false, delta)
                 }
             };
         }
@@ -126,11 +185,11 @@ where
             match &res {
                 Ok(_) => {
                     span_recorder.ok("success");
-                    self.delete_success.record(delta)
+                    self.record_delete(namespace, true, delta)
                 }
                 Err(e) => {
                     span_recorder.error(e.to_string());
-                    self.delete_error.record(delta)
+                    self.record_delete(namespace, false, delta)
                 }
             };
         }
@@ -240,6 +299,38 @@ mod tests {
         assert_trace(traces, SpanStatus::Err);
     }
 
+    #[tokio::test]
+    async fn test_write_namespace_labels() {
+        let handler =
+            Arc::new(MockDmlHandler::default().with_write_return([Ok(summary()), Ok(summary())]));
+
+        let metrics = Arc::new(metric::Registry::default());
+        let decorator = InstrumentationDecorator::new(HANDLER_NAME, &metrics, handler)
+            .with_namespace_labels(Arc::new(CardinalityLimiter::new(1)));
+
+        for ns in ["platanos", "bananas"] {
+            decorator
+                .write(&ns.try_into().unwrap(), NamespaceId::new(42), (), None)
+                .await
+                .expect("inner handler configured to succeed");
+        }
+
+        // The second namespace exceeds the limit and is aggregated.
+        for ns in ["platanos", metric::OTHER_ATTRIBUTE_VALUE] {
+            let histogram = metrics
+                .get_instrument::<Metric<DurationHistogram>>("dml_handler_write_duration")
+                .expect("failed to read metric")
+                .get_observer(&Attributes::from(&[
+                    ("handler", HANDLER_NAME),
+                    ("result", "success"),
+                    ("namespace", ns),
+                ]))
+                .expect("failed to get observer")
+                .fetch();
+            assert_eq!(histogram.sample_count(), 1);
+        }
+    }
+
     #[tokio::test]
     async fn test_delete_ok() {
         let ns = "platanos".try_into().unwrap();
@@ -54,8 +54,29 @@
 //! The [`ShardedWriteBuffer`] uses a sharder implementation to direct the DML
 //! operations into a fixed set of shards.
 //!
+//! When writing to more than one write buffer topic, the [`TopicRouter`]
+//! dispatches the operations of each namespace to the [`ShardedWriteBuffer`]
+//! (and [`ShardPinner`]) of the topic the namespace is assigned to, rejecting
+//! the operations of namespaces assigned to a topic the router does not serve.
+//!
+//! The [`DryRunValidator`] sits outside of the stack, and applies the same
+//! validation, partitioning and sharding to a write without any side effects.
+//!
+//! The optional [`ShardPinner`] sits between schema validation and
+//! partitioning, lazily loading (or creating) the catalog pin of each table
+//! written to and applying it to the [`PinnedSharder`] used by the
+//! [`ShardedWriteBuffer`].
+//!
+//! The [`Transformer`] sits at the head of the stack, rewriting the writes to
+//! namespaces configured with [`TransformRules`] (renaming measurements and
+//! tag keys, and dropping fields) before they are validated.
+//!
+//! The optional [`WriteAuditor`] wraps the stack, recording a sample of the
+//! accepted writes to a [`WriteAuditSink`].
+//!
 //! [`NamespaceCache`]: crate::namespace_cache::NamespaceCache
 //! [`NamespaceSchema`]: data_types::NamespaceSchema
+//! [`PinnedSharder`]: sharder::PinnedSharder
 
 mod r#trait;
 pub use r#trait::*;
@@ -86,5 +107,20 @@ pub use fan_out::*;
 mod write_summary;
 pub use self::write_summary::*;
 
+mod dry_run;
+pub use dry_run::*;
+
+mod write_audit;
+pub use write_audit::*;
+
+mod shard_pinner;
+pub use shard_pinner::*;
+
+mod topic_router;
+pub use topic_router::*;
+
+mod transform;
+pub use transform::*;
+
 #[cfg(test)]
 pub mod mock;
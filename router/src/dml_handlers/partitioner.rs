@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use data_types::{
     DeletePredicate, NamespaceId, NamespaceName, PartitionKey, PartitionTemplate, TableId,
@@ -9,6 +11,7 @@ use thiserror::Error;
 use trace::ctx::SpanContext;
 
 use super::DmlHandler;
+use crate::namespace_cache::{MemoryNamespaceCache, NamespaceCache};
 
 /// An error raised by the [`Partitioner`] handler.
 #[derive(Debug, Error)]
@@ -46,23 +49,50 @@ impl<T> Partitioned<T> {
 /// partitioned per-table [`MutableBatch`] instances according to a configured
 /// [`PartitionTemplate`]. Deletes pass through unmodified.
 ///
+/// If configured with a [`NamespaceCache`], the writes to a namespace with a
+/// [`NamespaceSchema::partition_template`] in the cache are split according to
+/// that template instead.
+///
 /// A vector of partitions are returned to the caller, or the first error that
 /// occurs during partitioning.
+///
+/// [`NamespaceSchema::partition_template`]: data_types::NamespaceSchema::partition_template
 #[derive(Debug)]
-pub struct Partitioner {
+pub struct Partitioner<C = Arc<MemoryNamespaceCache>> {
     partition_template: PartitionTemplate,
+    namespace_cache: Option<C>,
 }
 
 impl Partitioner {
     /// Initialise a new [`Partitioner`], splitting writes according to the
     /// specified [`PartitionTemplate`].
     pub fn new(partition_template: PartitionTemplate) -> Self {
-        Self { partition_template }
+        Self {
+            partition_template,
+            namespace_cache: None,
+        }
+    }
+}
+
+impl<C> Partitioner<C> {
+    /// Split the writes to the namespaces with a partition template in
+    /// `namespace_cache` according to their template.
+    ///
+    /// The schema validator, which runs before this handler, populates the
+    /// cache with the schema of every namespace written to.
+    pub fn with_namespace_cache<T>(self, namespace_cache: T) -> Partitioner<T> {
+        Partitioner {
+            partition_template: self.partition_template,
+            namespace_cache: Some(namespace_cache),
+        }
     }
 }
 
 #[async_trait]
-impl DmlHandler for Partitioner {
+impl<C> DmlHandler for Partitioner<C>
+where
+    C: NamespaceCache,
+{
     type WriteError = PartitionError;
     type DeleteError = PartitionError;
 
@@ -72,20 +102,29 @@ impl DmlHandler for Partitioner {
     /// Partition the per-table [`MutableBatch`].
     async fn write(
         &self,
-        _namespace: &NamespaceName<'static>,
+        namespace: &NamespaceName<'static>,
         _namespace_id: NamespaceId,
         batch: Self::WriteInput,
         _span_ctx: Option<SpanContext>,
     ) -> Result<Self::WriteOutput, Self::WriteError> {
+        let schema = self
+            .namespace_cache
+            .as_ref()
+            .and_then(|cache| cache.get_schema(namespace));
+        let partition_template = schema
+            .as_ref()
+            .and_then(|schema| schema.partition_template.as_ref())
+            .unwrap_or(&self.partition_template);
+
         // A collection of partition-keyed, per-table MutableBatch instances.
         let mut partitions: HashMap<PartitionKey, HashMap<_, (String, MutableBatch)>> =
             HashMap::default();
 
         for (table_id, (table_name, batch)) in batch {
-            // Partition the table batch according to the configured partition
-            // template and write it into the partition-keyed map.
+            // Partition the table batch according to the partition template
+            // and write it into the partition-keyed map.
             for (partition_key, partition_payload) in
-                PartitionWrite::partition(&table_name, &batch, &self.partition_template)
+                PartitionWrite::partition(&table_name, &batch, partition_template)
             {
                 let partition = partitions.entry(partition_key).or_default();
                 let table_batch = partition
@@ -121,7 +160,7 @@ impl DmlHandler for Partitioner {
 #[cfg(test)]
 mod tests {
     use assert_matches::assert_matches;
-    use data_types::TemplatePart;
+    use data_types::{NamespaceSchema, QueryPoolId, TemplatePart, TopicId};
 
     use super::*;
 
@@ -300,4 +339,56 @@ mod tests {
         ],
         want_handler_ret = Ok(_)
     );
+
+    #[tokio::test]
+    async fn test_write_namespace_partition_template() {
+        let cache = Arc::new(MemoryNamespaceCache::default());
+        let partitioner = Partitioner::new(PartitionTemplate {
+            parts: vec![TemplatePart::TimeFormat("%Y-%m-%d".to_owned())],
+        })
+        .with_namespace_cache(Arc::clone(&cache));
+
+        let templated = NamespaceName::new("templated").expect("valid db name");
+        cache.put_schema(
+            templated.clone(),
+            NamespaceSchema {
+                partition_template: Some(PartitionTemplate {
+                    parts: vec![
+                        TemplatePart::TimeFormat("%Y".to_owned()),
+                        TemplatePart::TagValue("tag1".to_owned()),
+                    ],
+                }),
+                ..NamespaceSchema::new(
+                    NamespaceId::new(1),
+                    TopicId::new(2),
+                    QueryPoolId::new(3),
+                    4,
+                    None,
+                )
+            },
+        );
+        let other = NamespaceName::new("other").expect("valid db name");
+
+        let lp = "bananas,tag1=A val=42i 1465839830100400200";
+        let keys = |partitions: Vec<Partitioned<_>>| {
+            partitions
+                .into_iter()
+                .map(|p| p.key.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // The namespace with a template is partitioned by it...
+        let got = partitioner
+            .write(&templated, NamespaceId::new(1), lp_to_writes(lp), None)
+            .await
+            .expect("partitioning should succeed");
+        assert_eq!(keys(got), ["2016-A"]);
+
+        // ...and all other namespaces by the configured template.
+        let got = partitioner
+            .write(&other, NamespaceId::new(2), lp_to_writes(lp), None)
+            .await
+            .expect("partitioning should succeed");
+        assert_eq!(keys(got), ["2016-06-13"]);
+    }
 }
@@ -1,10 +1,10 @@
 use std::{ops::DerefMut, sync::Arc};
 
 use async_trait::async_trait;
-use data_types::{DeletePredicate, NamespaceId, NamespaceName};
+use data_types::{DeletePredicate, NamespaceId, NamespaceName, NamespaceSchema};
 use hashbrown::HashMap;
 use iox_catalog::interface::{get_schema_by_name, Catalog};
-use iox_time::{SystemProvider, TimeProvider};
+use iox_time::{SystemProvider, Time, TimeProvider};
 use mutable_batch::MutableBatch;
 use observability_deps::tracing::*;
 use thiserror::Error;
@@ -33,6 +33,7 @@ pub struct RetentionValidator<C = Arc<InstrumentedCache<MemoryNamespaceCache>>,
     catalog: Arc<dyn Catalog>,
     cache: C,
     time_provider: P,
+    enabled: bool,
 }
 
 impl<C> RetentionValidator<C> {
@@ -42,8 +43,16 @@ impl<C> RetentionValidator<C> {
             catalog,
             cache,
             time_provider: Default::default(),
+            enabled: true,
         }
     }
+
+    /// Enable or disable retention validation.
+    ///
+    /// When disabled, this handler is a NOP.
+    pub fn with_enabled(self, enabled: bool) -> Self {
+        Self { enabled, ..self }
+    }
 }
 
 #[async_trait]
@@ -65,6 +74,10 @@ where
         batch: Self::WriteInput,
         _span_ctx: Option<SpanContext>,
     ) -> Result<Self::WriteOutput, Self::WriteError> {
+        if !self.enabled {
+            return Ok(batch);
+        }
+
         let mut repos = self.catalog.repositories().await;
 
         // Load the namespace schema from the cache, falling back to pulling it
@@ -96,18 +109,7 @@ where
             }
         };
 
-        // retention is not infinte, validate all lines of a write are within the retention period
-        if let Some(retention_period_ns) = schema.retention_period_ns {
-            let min_retention = self.time_provider.now().timestamp_nanos() - retention_period_ns;
-            // batch is a HashMap<tring, MutableBatch>
-            for (table_name, batch) in &batch {
-                if let Some(min) = batch.timestamp_summary().and_then(|v| v.stats.min) {
-                    if min < min_retention {
-                        return Err(RetentionError::OutsideRetention(table_name.clone()));
-                    }
-                }
-            }
-        };
+        validate_retention(&batch, &schema, self.time_provider.now())?;
 
         Ok(batch)
     }
@@ -125,6 +127,29 @@ where
     }
 }
 
+/// Reject `batch` if it contains data older than the retention period of
+/// `schema`, as of `now`.
+pub(super) fn validate_retention(
+    batch: &HashMap<String, MutableBatch>,
+    schema: &NamespaceSchema,
+    now: Time,
+) -> Result<(), RetentionError> {
+    // retention is not infinte, validate all lines of a write are within the retention period
+    if let Some(retention_period_ns) = schema.retention_period_ns {
+        let min_retention = now.timestamp_nanos() - retention_period_ns;
+        // batch is a HashMap<tring, MutableBatch>
+        for (table_name, batch) in batch {
+            if let Some(min) = batch.timestamp_summary().and_then(|v| v.stats.min) {
+                if min < min_retention {
+                    return Err(RetentionError::OutsideRetention(table_name.clone()));
+                }
+            }
+        }
+    };
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use iox_tests::util::{TestCatalog, TestNamespace};
@@ -188,6 +213,14 @@ mod tests {
         assert!(result.is_err());
         let message = result.unwrap_err().to_string();
         assert!(message.contains("data in table bananas is outside of the retention period"));
+
+        // unless retention validation is disabled
+        let handler = handler.with_enabled(false);
+        let writes = lp_to_writes(&line);
+        let result = handler
+            .write(&NAMESPACE, NamespaceId::new(42), writes, None)
+            .await;
+        assert!(result.is_ok());
     }
 
     #[tokio::test]
@@ -1,11 +1,16 @@
-use std::{ops::DerefMut, sync::Arc};
+use std::{
+    ops::{DerefMut, Range},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
-use data_types::{DeletePredicate, NamespaceId, NamespaceName, NamespaceSchema, TableId};
+use data_types::{
+    ColumnType, DeletePredicate, NamespaceId, NamespaceName, NamespaceSchema, TableId,
+};
 use hashbrown::HashMap;
 use iox_catalog::{
-    interface::{get_schema_by_name, Catalog, Error as CatalogError},
-    validate_or_insert_schema,
+    interface::{get_schema_by_name, Catalog, Error as CatalogError, RepoCollection},
+    validate_or_insert_schema, TableScopedError,
 };
 use metric::U64Counter;
 use mutable_batch::MutableBatch;
@@ -40,6 +45,24 @@ pub enum SchemaError {
     UnexpectedCatalogError(iox_catalog::interface::Error),
 }
 
+/// The action taken by the [`SchemaValidator`] when a write contains a column
+/// whose type conflicts with the existing column in the namespace schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SchemaConflictPolicy {
+    /// Reject the write as a whole.
+    #[default]
+    Reject,
+
+    /// Convert integer fields written to existing float columns into floats.
+    ///
+    /// Writes containing any other conflict are rejected.
+    CoerceToWiderType,
+
+    /// Drop the lines containing a value for a conflicting column, and apply
+    /// the rest of the write.
+    DropConflictingLines,
+}
+
 /// A [`SchemaValidator`] checks the schema of incoming writes against a
 /// centralised schema store, maintaining an in-memory cache of all observed
 /// schemas.
@@ -60,6 +83,22 @@ pub enum SchemaError {
 /// Any successful write that adds new columns causes the new schema to be
 /// cached.
 ///
+/// # Schema Conflicts
+///
+/// By default, a write containing a column with a type that conflicts with the
+/// existing column is rejected as a whole. A different
+/// [`SchemaConflictPolicy`] can be configured for all namespaces, or for
+/// individual namespaces, to instead coerce integer fields written to float
+/// columns, or drop the conflicting lines and apply the rest of the write.
+///
+/// Conflicts are resolved against the cached schema. If the catalog reports a
+/// conflict for a column missing from the (stale) cached schema, the schema is
+/// reloaded from the catalog and the write is resolved and validated once more.
+///
+/// Dropped lines are counted in the `schema_validation_dropped_rows` metric and
+/// logged. The HTTP write API reports the number of lines dropped from a write
+/// in the `X-IOx-Dropped-Rows` response header.
+///
 /// To minimise locking, this cache is designed to allow (and tolerate) spurious
 /// cache "updates" racing with each other and overwriting newer schemas with
 /// older schemas. This is acceptable due to the incremental, additive schema
@@ -69,6 +108,13 @@ pub enum SchemaError {
 /// relatively rare - it results in additional requests being made to the
 /// catalog until the cached schema converges to match the catalog schema.
 ///
+/// Each schema change is published by incrementing the namespace's schema
+/// generation in the catalog, conditional on it still matching the generation
+/// of the schema the change was built on. If another writer changed the schema
+/// in the meantime, the locally built schema is missing those changes and is
+/// discarded - the full schema is reloaded from the catalog, published and
+/// cached instead.
+///
 /// Note that the namespace-wide limit of the number of columns allowed per table
 /// is also cached, which has two implications:
 ///
@@ -106,8 +152,14 @@ pub struct SchemaValidator<C = Arc<InstrumentedCache<MemoryNamespaceCache>>> {
     catalog: Arc<dyn Catalog>,
     cache: C,
 
+    conflict_policy: SchemaConflictPolicy,
+    namespace_conflict_policies: HashMap<NamespaceName<'static>, SchemaConflictPolicy>,
+
     service_limit_hit: U64Counter,
     schema_conflict: U64Counter,
+    generation_conflict: U64Counter,
+    coerced_columns: U64Counter,
+    dropped_rows: U64Counter,
 }
 
 impl<C> SchemaValidator<C> {
@@ -128,12 +180,184 @@ impl<C> SchemaValidator<C> {
                 "number of requests that fail due to a schema conflict",
             )
             .recorder(&[]);
+        let generation_conflict = metrics
+            .register_metric::<U64Counter>(
+                "schema_validation_generation_conflict",
+                "number of schema changes that raced with a concurrent change to the same namespace",
+            )
+            .recorder(&[]);
+        let coerced_columns = metrics
+            .register_metric::<U64Counter>(
+                "schema_validation_coerced_columns",
+                "number of conflicting write columns coerced to the type of the existing column",
+            )
+            .recorder(&[]);
+        let dropped_rows = metrics
+            .register_metric::<U64Counter>(
+                "schema_validation_dropped_rows",
+                "number of rows dropped from writes due to a schema conflict",
+            )
+            .recorder(&[]);
 
         Self {
             catalog,
             cache: ns_cache,
+            conflict_policy: SchemaConflictPolicy::default(),
+            namespace_conflict_policies: Default::default(),
             service_limit_hit,
             schema_conflict,
+            generation_conflict,
+            coerced_columns,
+            dropped_rows,
+        }
+    }
+
+    /// Resolve schema conflicts according to `policy` in namespaces without a
+    /// namespace-specific policy, instead of rejecting the write.
+    pub fn with_conflict_policy(mut self, policy: SchemaConflictPolicy) -> Self {
+        self.conflict_policy = policy;
+        self
+    }
+
+    /// Resolve schema conflicts in `namespace` according to `policy`.
+    pub fn with_namespace_conflict_policy(
+        mut self,
+        namespace: NamespaceName<'static>,
+        policy: SchemaConflictPolicy,
+    ) -> Self {
+        self.namespace_conflict_policies.insert(namespace, policy);
+        self
+    }
+
+    fn conflict_policy(&self, namespace: &NamespaceName<'static>) -> SchemaConflictPolicy {
+        self.namespace_conflict_policies
+            .get(namespace)
+            .copied()
+            .unwrap_or(self.conflict_policy)
+    }
+
+    /// Apply `policy` to the columns in `batches` conflicting with `schema`
+    /// (see [`resolve_conflicts()`]), updating the relevant metrics.
+    fn apply_conflict_policy(
+        &self,
+        namespace: &NamespaceName<'static>,
+        schema: &NamespaceSchema,
+        policy: SchemaConflictPolicy,
+        batches: &mut HashMap<String, MutableBatch>,
+    ) {
+        let resolution = resolve_conflicts(namespace, schema, policy, batches);
+        self.coerced_columns.inc(resolution.coerced_columns as u64);
+        self.dropped_rows.inc(resolution.dropped_rows as u64);
+    }
+
+    /// Convert a catalog validation error into a [`SchemaError`], updating
+    /// the relevant metrics.
+    fn validation_error(
+        &self,
+        namespace: &NamespaceName<'static>,
+        namespace_id: NamespaceId,
+        e: TableScopedError,
+    ) -> SchemaError {
+        match e.err() {
+            // Schema conflicts
+            CatalogError::ColumnTypeMismatch {
+                ref name,
+                ref existing,
+                ref new,
+            } => {
+                warn!(
+                    %namespace,
+                    %namespace_id,
+                    column_name=%name,
+                    existing_column_type=%existing,
+                    request_column_type=%new,
+                    table_name=%e.table(),
+                    "schema conflict"
+                );
+                self.schema_conflict.inc(1);
+                SchemaError::Conflict(e)
+            }
+            // Service limits
+            CatalogError::ColumnCreateLimitError { .. }
+            | CatalogError::TableCreateLimitError { .. } => {
+                warn!(
+                    %namespace,
+                    %namespace_id,
+                    error=%e,
+                    "service protection limit reached"
+                );
+                self.service_limit_hit.inc(1);
+                SchemaError::ServiceLimit(Box::new(e.into_err()))
+            }
+            _ => {
+                error!(
+                    %namespace,
+                    %namespace_id,
+                    error=%e,
+                    "schema validation failed"
+                );
+                SchemaError::UnexpectedCatalogError(e.into_err())
+            }
+        }
+    }
+
+    /// Publish `new_schema`, built on top of a schema at `base_generation`,
+    /// by incrementing the namespace's schema generation in the catalog.
+    ///
+    /// If another writer changed the schema after `base_generation` was read,
+    /// `new_schema` is missing their changes and the schema is reloaded from
+    /// the catalog (which contains both) and published again. Each conflict
+    /// means another writer made progress, so this eventually succeeds.
+    async fn publish_schema_change(
+        &self,
+        namespace: &NamespaceName<'static>,
+        namespace_id: NamespaceId,
+        base_generation: i64,
+        mut new_schema: NamespaceSchema,
+        repos: &mut dyn RepoCollection,
+    ) -> Result<NamespaceSchema, SchemaError> {
+        let mut expected = base_generation;
+        loop {
+            match repos
+                .namespaces()
+                .increment_schema_generation(new_schema.id, expected)
+                .await
+            {
+                Ok(ns) => {
+                    new_schema.generation = ns.schema_generation;
+                    return Ok(new_schema);
+                }
+                Err(CatalogError::PreconditionViolation { actual, .. }) => {
+                    debug!(
+                        %namespace,
+                        %namespace_id,
+                        expected,
+                        actual,
+                        "concurrent schema change, reloading schema"
+                    );
+                    self.generation_conflict.inc(1);
+
+                    new_schema = get_schema_by_name(namespace, repos).await.map_err(|e| {
+                        error!(
+                            %namespace,
+                            %namespace_id,
+                            error=%e,
+                            "failed to reload namespace schema"
+                        );
+                        SchemaError::UnexpectedCatalogError(e)
+                    })?;
+                    expected = new_schema.generation;
+                }
+                Err(e) => {
+                    error!(
+                        %namespace,
+                        %namespace_id,
+                        error=%e,
+                        "failed to publish schema change"
+                    );
+                    return Err(SchemaError::UnexpectedCatalogError(e));
+                }
+            }
         }
     }
 }
@@ -164,8 +388,10 @@ where
     /// If the schema validation fails due to a service limit being reached,
     /// [`SchemaError::ServiceLimit`] is returned.
     ///
-    /// A request that fails validation on one or more tables fails the request
-    /// as a whole - calling this method has "all or nothing" semantics.
+    /// Unless the [`SchemaConflictPolicy`] for `namespace` resolves the
+    /// conflicts, a request that fails validation on one or more tables fails
+    /// the request as a whole - calling this method has "all or nothing"
+    /// semantics.
     async fn write(
         &self,
         namespace: &NamespaceName<'static>,
@@ -178,7 +404,7 @@ where
         // Load the namespace schema from the cache, falling back to pulling it
         // from the global catalog (if it exists).
         let schema = self.cache.get_schema(namespace);
-        let schema = match schema {
+        let mut schema = match schema {
             Some(v) => v,
             None => {
                 // Pull the schema from the global catalog or error if it does
@@ -204,6 +430,10 @@ where
             }
         };
 
+        let policy = self.conflict_policy(namespace);
+        let mut batches = batches;
+        self.apply_conflict_policy(namespace, &schema, policy, &mut batches);
+
         validate_column_limits(&batches, &schema).map_err(|e| {
             warn!(
                 %namespace,
@@ -215,56 +445,43 @@ where
             SchemaError::ServiceLimit(Box::new(e))
         })?;
 
-        let maybe_new_schema = validate_or_insert_schema(
+        let mut result = validate_or_insert_schema(
             batches.iter().map(|(k, v)| (k.as_str(), v)),
             &schema,
             repos.deref_mut(),
         )
-        .await
-        .map_err(|e| {
-            match e.err() {
-                // Schema conflicts
-                CatalogError::ColumnTypeMismatch {
-                    ref name,
-                    ref existing,
-                    ref new,
-                } => {
-                    warn!(
-                        %namespace,
-                        %namespace_id,
-                        column_name=%name,
-                        existing_column_type=%existing,
-                        request_column_type=%new,
-                        table_name=%e.table(),
-                        "schema conflict"
-                    );
-                    self.schema_conflict.inc(1);
-                    SchemaError::Conflict(e)
-                }
-                // Service limits
-                CatalogError::ColumnCreateLimitError { .. }
-                | CatalogError::TableCreateLimitError { .. } => {
-                    warn!(
-                        %namespace,
-                        %namespace_id,
-                        error=%e,
-                        "service protection limit reached"
-                    );
-                    self.service_limit_hit.inc(1);
-                    SchemaError::ServiceLimit(Box::new(e.into_err()))
-                }
-                _ => {
-                    error!(
-                        %namespace,
-                        %namespace_id,
-                        error=%e,
-                        "schema validation failed"
-                    );
-                    SchemaError::UnexpectedCatalogError(e.into_err())
-                }
-            }
-        })?
-        .map(Arc::new);
+        .await;
+
+        // If the conflicting column is missing from the cached schema, the
+        // conflict could not have been resolved - reload the schema from the
+        // catalog and try again.
+        if policy != SchemaConflictPolicy::Reject
+            && matches!(&result, Err(e) if is_uncached_conflict(e, &schema))
+        {
+            debug!(
+                %namespace,
+                %namespace_id,
+                "schema conflict with uncached column, reloading schema"
+            );
+
+            schema = get_schema_by_name(namespace, repos.deref_mut())
+                .await
+                .map_err(SchemaError::UnexpectedCatalogError)
+                .map(Arc::new)?;
+            self.cache
+                .put_schema(namespace.clone(), Arc::clone(&schema));
+
+            self.apply_conflict_policy(namespace, &schema, policy, &mut batches);
+            result = validate_or_insert_schema(
+                batches.iter().map(|(k, v)| (k.as_str(), v)),
+                &schema,
+                repos.deref_mut(),
+            )
+            .await;
+        }
+
+        let maybe_new_schema =
+            result.map_err(|e| self.validation_error(namespace, namespace_id, e))?;
 
         trace!(%namespace, "schema validation complete");
 
@@ -274,6 +491,17 @@ where
         // complete.
         let latest_schema = match maybe_new_schema {
             Some(v) => {
+                let v = self
+                    .publish_schema_change(
+                        namespace,
+                        namespace_id,
+                        schema.generation,
+                        v,
+                        repos.deref_mut(),
+                    )
+                    .await
+                    .map(Arc::new)?;
+
                 // This call MAY overwrite a more-up-to-date cache entry if
                 // racing with another request for the same namespace, but the
                 // cache will eventually converge in subsequent requests.
@@ -315,6 +543,146 @@ where
     }
 }
 
+/// The changes [`resolve_conflicts()`] made to a write.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ConflictResolution {
+    /// The number of columns coerced to the type of the existing column.
+    pub(super) coerced_columns: usize,
+    /// The number of rows dropped.
+    pub(super) dropped_rows: usize,
+}
+
+/// Apply `policy` to the columns in `batches` conflicting with `schema`,
+/// removing tables with no remaining rows.
+///
+/// Conflicts that cannot be resolved are left in place, to be rejected by the
+/// catalog validation.
+pub(super) fn resolve_conflicts(
+    namespace: &NamespaceName<'static>,
+    schema: &NamespaceSchema,
+    policy: SchemaConflictPolicy,
+    batches: &mut HashMap<String, MutableBatch>,
+) -> ConflictResolution {
+    let mut resolution = ConflictResolution::default();
+    if policy == SchemaConflictPolicy::Reject {
+        return resolution;
+    }
+
+    batches.retain(|table_name, batch| {
+        let table = match schema.tables.get(table_name) {
+            Some(v) => v,
+            None => return true,
+        };
+
+        let conflicts = batch
+            .columns()
+            .filter_map(|(name, column)| {
+                let existing = table.columns.get(name)?;
+                (!existing.matches_type(column.influx_type()))
+                    .then(|| (name.clone(), existing.column_type))
+            })
+            .collect::<Vec<_>>();
+        if conflicts.is_empty() {
+            return true;
+        }
+
+        match policy {
+            SchemaConflictPolicy::Reject => unreachable!(),
+            SchemaConflictPolicy::CoerceToWiderType => {
+                for (column, existing) in conflicts {
+                    if existing == ColumnType::F64 && batch.coerce_column_to_f64(&column).is_ok() {
+                        debug!(
+                            %namespace,
+                            %table_name,
+                            column_name=%column,
+                            "coerced conflicting column to float"
+                        );
+                        resolution.coerced_columns += 1;
+                    }
+                }
+            }
+            SchemaConflictPolicy::DropConflictingLines => {
+                let columns = conflicts
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>();
+                let dropped = drop_conflicting_rows(batch, &columns);
+                warn!(
+                    %namespace,
+                    %table_name,
+                    column_names=?columns,
+                    dropped_rows=dropped,
+                    "dropped lines with conflicting column types"
+                );
+                resolution.dropped_rows += dropped;
+            }
+        }
+
+        batch.rows() > 0
+    });
+
+    resolution
+}
+
+/// Returns true if `e` is a column type conflict for a column that does not
+/// exist in `schema`.
+fn is_uncached_conflict(e: &TableScopedError, schema: &NamespaceSchema) -> bool {
+    match e.err() {
+        CatalogError::ColumnTypeMismatch { name, .. } => schema
+            .tables
+            .get(e.table())
+            .map_or(true, |t| !t.columns.contains_key(name)),
+        _ => false,
+    }
+}
+
+/// Remove `columns` from `batch`, along with all rows that have a value for
+/// any of them, returning the number of rows removed.
+fn drop_conflicting_rows(batch: &mut MutableBatch, columns: &[String]) -> usize {
+    let dropped_columns = columns
+        .iter()
+        .map(|name| {
+            batch
+                .drop_column(name)
+                .expect("conflicting column must exist")
+        })
+        .collect::<Vec<_>>();
+
+    // Find the ranges of rows with no value for any of the dropped columns.
+    let rows = batch.rows();
+    let mut ranges: Vec<Range<usize>> = vec![];
+    let mut start = None;
+    for row in 0..rows {
+        let keep = dropped_columns.iter().all(|c| !c.valid_mask().get(row));
+        match (keep, start) {
+            (true, None) => start = Some(row),
+            (false, Some(s)) => {
+                ranges.push(s..row);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        ranges.push(s..rows);
+    }
+
+    let kept = ranges.iter().map(|r| r.len()).sum::<usize>();
+    if kept == rows {
+        return 0;
+    }
+
+    let mut filtered = MutableBatch::new();
+    if kept > 0 {
+        filtered
+            .extend_from_ranges(batch, &ranges)
+            .expect("extending an empty batch cannot conflict");
+    }
+    *batch = filtered;
+
+    rows - kept
+}
+
 #[derive(Debug, Error)]
 #[error(
     "couldn't create columns in table `{table_name}`; table contains \
@@ -331,7 +699,7 @@ struct OverColumnLimit {
     max_columns_per_table: usize,
 }
 
-fn validate_column_limits(
+pub(super) fn validate_column_limits(
     batches: &HashMap<String, MutableBatch>,
     schema: &NamespaceSchema,
 ) -> Result<(), OverColumnLimit> {
@@ -371,9 +739,10 @@ mod tests {
     use std::sync::Arc;
 
     use assert_matches::assert_matches;
-    use data_types::{ColumnType, TimestampRange};
+    use data_types::TimestampRange;
     use iox_tests::util::{TestCatalog, TestNamespace};
     use once_cell::sync::Lazy;
+    use schema::{InfluxColumnType, InfluxFieldType};
 
     use super::*;
 
@@ -599,6 +968,62 @@ mod tests {
         assert_eq!(name, "bananas");
     }
 
+    #[tokio::test]
+    async fn test_write_concurrent_schema_change() {
+        let (catalog, _namespace) = test_setup().await;
+        let metrics = Arc::new(metric::Registry::default());
+
+        // Two routers with their own caches.
+        let handler_a = SchemaValidator::new(
+            catalog.catalog(),
+            Arc::new(MemoryNamespaceCache::default()),
+            &metrics,
+        );
+        let handler_b = SchemaValidator::new(
+            catalog.catalog(),
+            Arc::new(MemoryNamespaceCache::default()),
+            &metric::Registry::default(),
+        );
+
+        let writes = lp_to_writes("bananas,tag1=A val=42i 123456");
+        handler_a
+            .write(&NAMESPACE, NamespaceId::new(42), writes, None)
+            .await
+            .expect("request should succeed");
+        assert_eq!(
+            handler_a.cache.get_schema(&NAMESPACE).unwrap().generation,
+            1
+        );
+
+        // B changes the schema behind A's back.
+        let writes = lp_to_writes("bananas,tag2=B val=42i 123456");
+        handler_b
+            .write(&NAMESPACE, NamespaceId::new(42), writes, None)
+            .await
+            .expect("request should succeed");
+        assert_eq!(
+            handler_b.cache.get_schema(&NAMESPACE).unwrap().generation,
+            2
+        );
+        assert_eq!(handler_a.generation_conflict.fetch(), 0);
+
+        // A builds on its stale cached schema, detects the conflict and picks
+        // up B's column rather than dropping it from its cache.
+        let writes = lp_to_writes("bananas,tag3=C val=42i 123456");
+        handler_a
+            .write(&NAMESPACE, NamespaceId::new(42), writes, None)
+            .await
+            .expect("request should succeed");
+        assert_eq!(handler_a.generation_conflict.fetch(), 1);
+        assert_cache(&handler_a, "bananas", "tag1", ColumnType::Tag);
+        assert_cache(&handler_a, "bananas", "tag2", ColumnType::Tag);
+        assert_cache(&handler_a, "bananas", "tag3", ColumnType::Tag);
+        assert_eq!(
+            handler_a.cache.get_schema(&NAMESPACE).unwrap().generation,
+            3
+        );
+    }
+
     #[tokio::test]
     async fn test_write_schema_not_found() {
         let (catalog, _namespace) = test_setup().await;
@@ -661,6 +1086,134 @@ mod tests {
         assert_eq!(1, handler.schema_conflict.fetch());
     }
 
+    #[tokio::test]
+    async fn test_write_conflict_coerce() {
+        let (catalog, namespace) = test_setup().await;
+        let table = namespace.create_table("bananas").await;
+        table.create_column("val", ColumnType::F64).await;
+        table.create_column("str", ColumnType::String).await;
+
+        let metrics = Arc::new(metric::Registry::default());
+        let handler = SchemaValidator::new(
+            catalog.catalog(),
+            Arc::new(MemoryNamespaceCache::default()),
+            &metrics,
+        )
+        .with_conflict_policy(SchemaConflictPolicy::CoerceToWiderType);
+
+        let writes = lp_to_writes("bananas,tag1=A val=42i 123456");
+        let got = handler
+            .write(&NAMESPACE, NamespaceId::new(42), writes, None)
+            .await
+            .expect("request should succeed");
+
+        let (_name, batch) = got.get(&table.table.id).expect("table not in output");
+        assert_eq!(
+            batch.column("val").unwrap().influx_type(),
+            InfluxColumnType::Field(InfluxFieldType::Float)
+        );
+        assert_cache(&handler, "bananas", "val", ColumnType::F64);
+        assert_eq!(handler.coerced_columns.fetch(), 1);
+
+        // Conflicts other than integer -> float are still rejected.
+        let writes = lp_to_writes("bananas,tag1=A str=42i 123456");
+        let err = handler
+            .write(&NAMESPACE, NamespaceId::new(42), writes, None)
+            .await
+            .expect_err("request should fail");
+        assert_matches!(err, SchemaError::Conflict(_));
+        assert_eq!(handler.coerced_columns.fetch(), 1);
+        assert_eq!(handler.schema_conflict.fetch(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_write_conflict_drop_lines() {
+        let (catalog, namespace) = test_setup().await;
+        let bananas = namespace.create_table("bananas").await;
+        bananas.create_column("val", ColumnType::F64).await;
+        let platanos = namespace.create_table("platanos").await;
+        platanos.create_column("val", ColumnType::F64).await;
+
+        // The policy only applies to the configured namespace.
+        let metrics = Arc::new(metric::Registry::default());
+        let handler = SchemaValidator::new(
+            catalog.catalog(),
+            Arc::new(MemoryNamespaceCache::default()),
+            &metrics,
+        )
+        .with_namespace_conflict_policy(
+            NAMESPACE.clone(),
+            SchemaConflictPolicy::DropConflictingLines,
+        );
+
+        let writes = lp_to_writes(
+            "\
+            bananas,tag1=A val=1i 1\n\
+            bananas,tag1=B other=2i 2\n\
+            bananas,tag1=C val=3i 3\n\
+            platanos val=4i 4\n\
+            ",
+        );
+        let got = handler
+            .write(&NAMESPACE, NamespaceId::new(42), writes, None)
+            .await
+            .expect("request should succeed");
+
+        // All the platanos lines conflict.
+        assert_eq!(got.len(), 1);
+        let (_name, batch) = got.get(&bananas.table.id).expect("table not in output");
+        assert_eq!(batch.rows(), 1);
+        assert!(batch.column("val").is_err());
+        assert_cache(&handler, "bananas", "other", ColumnType::I64);
+        assert_eq!(handler.dropped_rows.fetch(), 3);
+
+        let ns = NamespaceName::try_from("A_DIFFERENT_NAMESPACE").unwrap();
+        assert_eq!(handler.conflict_policy(&ns), SchemaConflictPolicy::Reject);
+    }
+
+    #[tokio::test]
+    async fn test_write_conflict_uncached_column() {
+        let (catalog, _namespace) = test_setup().await;
+        let metrics = Arc::new(metric::Registry::default());
+
+        // Two routers with their own caches.
+        let handler_a = SchemaValidator::new(
+            catalog.catalog(),
+            Arc::new(MemoryNamespaceCache::default()),
+            &metrics,
+        )
+        .with_conflict_policy(SchemaConflictPolicy::CoerceToWiderType);
+        let handler_b = SchemaValidator::new(
+            catalog.catalog(),
+            Arc::new(MemoryNamespaceCache::default()),
+            &metric::Registry::default(),
+        );
+
+        let writes = lp_to_writes("bananas,tag1=A other=1i 123456");
+        handler_a
+            .write(&NAMESPACE, NamespaceId::new(42), writes, None)
+            .await
+            .expect("request should succeed");
+
+        // B creates the float column behind A's back.
+        let writes = lp_to_writes("bananas,tag1=A val=4.2 123456");
+        handler_b
+            .write(&NAMESPACE, NamespaceId::new(42), writes, None)
+            .await
+            .expect("request should succeed");
+
+        // A only observes the conflict in the catalog, reloads the schema and
+        // coerces the write.
+        let writes = lp_to_writes("bananas,tag1=A val=42i 123456");
+        handler_a
+            .write(&NAMESPACE, NamespaceId::new(42), writes, None)
+            .await
+            .expect("request should succeed");
+        assert_cache(&handler_a, "bananas", "val", ColumnType::F64);
+        assert_eq!(handler_a.coerced_columns.fetch(), 1);
+        assert_eq!(handler_a.schema_conflict.fetch(), 0);
+    }
+
     #[tokio::test]
     async fn test_write_table_service_limit() {
         let (catalog, _namespace) = test_setup().await;
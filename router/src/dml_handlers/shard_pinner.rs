@@ -0,0 +1,465 @@
+//! Lazy, catalog-backed pinning of tables to write buffer shards.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use data_types::{
+    DeletePredicate, NamespaceId, NamespaceName, Shard as CatalogShard, ShardId, ShardIndex,
+    TableId,
+};
+use hashbrown::HashMap;
+use iox_catalog::interface::Catalog;
+use mutable_batch::MutableBatch;
+use observability_deps::tracing::*;
+use sharder::{PinnedSharder, Sharder};
+use thiserror::Error;
+use trace::ctx::SpanContext;
+
+use super::DmlHandler;
+use crate::shard::Shard;
+
+/// Errors emitted while resolving the shard a table is pinned to.
+#[derive(Debug, Error)]
+pub enum ShardPinError {
+    /// An error reading or writing the pin of a table in the catalog.
+    #[error("failed to resolve table shard pin: {0}")]
+    Catalog(iox_catalog::interface::Error),
+}
+
+/// A [`DmlHandler`] that pins each table written to (or deleted from) to a
+/// single write buffer shard in a shared [`PinnedSharder`], before passing the
+/// request through unmodified.
+///
+/// The pin of a table is read from the catalog the first time the table is
+/// seen by this router instance. Tables without a pin are pinned to (in order
+/// of preference):
+///
+///   * the shard of the most recently created partition of the table, so
+///     tables with existing data stay on the shard they were written to before
+///     the number of shards changed, or
+///   * the shard the unpinned [`PinnedSharder`] maps the table to, spreading
+///     new tables across all shards.
+///
+/// The pin is then persisted, so existing pins are backfilled lazily and never
+/// changed once created, irrespective of changes to the number of shards.
+///
+/// The [`PinnedSharder`] must be the same instance used to shard writes
+/// further down the handler stack, and tables can only be pinned to shards it
+/// is configured with. When writing to more than one topic, each topic has its
+/// own [`ShardPinner`], dispatched to by a [`TopicRouter`].
+///
+/// [`TopicRouter`]: super::TopicRouter
+#[derive(Debug)]
+pub struct ShardPinner {
+    catalog: Arc<dyn Catalog>,
+    sharder: Arc<PinnedSharder<Arc<Shard>>>,
+
+    /// The catalog ID of each shard in the write buffer topic.
+    shard_ids: HashMap<ShardIndex, ShardId>,
+
+    enabled: bool,
+}
+
+impl ShardPinner {
+    /// Initialise a [`ShardPinner`] pinning tables to the shards of `sharder`,
+    /// where `shards` are the catalog records of the write buffer topic shards.
+    pub fn new(
+        catalog: Arc<dyn Catalog>,
+        sharder: Arc<PinnedSharder<Arc<Shard>>>,
+        shards: impl IntoIterator<Item = CatalogShard>,
+    ) -> Self {
+        Self {
+            catalog,
+            sharder,
+            shard_ids: shards.into_iter().map(|s| (s.shard_index, s.id)).collect(),
+            enabled: true,
+        }
+    }
+
+    /// Enable or disable pinning.
+    ///
+    /// When disabled, this handler is a NOP and the [`PinnedSharder`] behaves
+    /// exactly as a [`JumpHash`] over the same shards.
+    ///
+    /// [`JumpHash`]: sharder::JumpHash
+    pub fn with_enabled(self, enabled: bool) -> Self {
+        Self { enabled, ..self }
+    }
+
+    /// Load (or create) the pin of `table_id` and apply it to the sharder.
+    async fn pin(
+        &self,
+        namespace: &NamespaceName<'static>,
+        table_id: TableId,
+        table_name: &str,
+    ) -> Result<(), ShardPinError> {
+        let mut repos = self.catalog.repositories().await;
+
+        let pinned = match repos
+            .tables()
+            .get_shard_pin(table_id)
+            .await
+            .map_err(ShardPinError::Catalog)?
+        {
+            Some(v) => v,
+            None => {
+                // Prefer the shard existing data for this table was written
+                // to, ignoring partitions in shards of other topics.
+                let existing = repos
+                    .partitions()
+                    .list_by_table_id(table_id)
+                    .await
+                    .map_err(ShardPinError::Catalog)?
+                    .into_iter()
+                    .filter(|p| self.shard_ids.values().any(|id| *id == p.shard_id))
+                    .max_by_key(|p| p.id)
+                    .map(|p| p.shard_id);
+
+                let shard_id = match existing {
+                    Some(v) => v,
+                    None => {
+                        let shard = self.sharder.shard(table_name, namespace, &());
+                        match self.shard_ids.get(&shard.shard_index()) {
+                            Some(v) => *v,
+                            None => {
+                                warn!(
+                                    %namespace,
+                                    %table_name,
+                                    shard_index=%shard.shard_index(),
+                                    "no catalog shard for write buffer shard, not pinning table"
+                                );
+                                return Ok(());
+                            }
+                        }
+                    }
+                };
+
+                let pinned = repos
+                    .tables()
+                    .create_or_get_shard_pin(table_id, shard_id)
+                    .await
+                    .map_err(ShardPinError::Catalog)?;
+                info!(
+                    %namespace,
+                    %table_name,
+                    %table_id,
+                    shard_id=%pinned,
+                    from_existing_data=existing.is_some(),
+                    "pinned table to shard"
+                );
+                pinned
+            }
+        };
+
+        let bucket = self
+            .shard_ids
+            .iter()
+            .find(|(_, id)| **id == pinned)
+            .and_then(|(index, _)| {
+                self.sharder
+                    .shards()
+                    .iter()
+                    .position(|s| s.shard_index() == *index)
+            });
+
+        match bucket {
+            Some(bucket) => self.sharder.pin(table_name, namespace, bucket),
+            None => {
+                // The pinned shard has been removed, so the table has to move.
+                let shard = self.sharder.shard(table_name, namespace, &());
+                warn!(
+                    %namespace,
+                    %table_name,
+                    shard_id=%pinned,
+                    fallback_shard_index=%shard.shard_index(),
+                    "table pinned to unknown shard"
+                );
+                let bucket = self
+                    .sharder
+                    .shards()
+                    .iter()
+                    .position(|s| Arc::ptr_eq(s, &shard))
+                    .expect("sharder returned unknown shard");
+                self.sharder.pin(table_name, namespace, bucket);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DmlHandler for ShardPinner {
+    type WriteError = ShardPinError;
+    type DeleteError = ShardPinError;
+
+    type WriteInput = HashMap<TableId, (String, MutableBatch)>;
+    type WriteOutput = Self::WriteInput;
+
+    /// Pin the tables in `batches` that are not yet pinned, returning the
+    /// batches unmodified.
+    async fn write(
+        &self,
+        namespace: &NamespaceName<'static>,
+        _namespace_id: NamespaceId,
+        batches: Self::WriteInput,
+        _span_ctx: Option<SpanContext>,
+    ) -> Result<Self::WriteOutput, Self::WriteError> {
+        if !self.enabled {
+            return Ok(batches);
+        }
+
+        for (table_id, (table_name, _)) in &batches {
+            if !self.sharder.is_pinned(table_name, namespace) {
+                self.pin(namespace, *table_id, table_name).await?;
+            }
+        }
+
+        Ok(batches)
+    }
+
+    /// Pin the table of a table-scoped delete if it exists and is not yet
+    /// pinned, so the delete is sharded to the same shard as its writes.
+    async fn delete(
+        &self,
+        namespace: &NamespaceName<'static>,
+        namespace_id: NamespaceId,
+        table_name: &str,
+        _predicate: &DeletePredicate,
+        _span_ctx: Option<SpanContext>,
+    ) -> Result<(), Self::DeleteError> {
+        if !self.enabled || table_name.is_empty() || self.sharder.is_pinned(table_name, namespace) {
+            return Ok(());
+        }
+
+        let table = self
+            .catalog
+            .repositories()
+            .await
+            .tables()
+            .get_by_namespace_and_name(namespace_id, table_name)
+            .await
+            .map_err(ShardPinError::Catalog)?;
+
+        if let Some(table) = table {
+            self.pin(namespace, table.id, table_name).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use data_types::TimestampRange;
+    use iox_tests::util::{TestCatalog, TestNamespace};
+    use once_cell::sync::Lazy;
+    use write_buffer::mock::{MockBufferForWriting, MockBufferSharedState};
+
+    use super::*;
+
+    static NAMESPACE: Lazy<NamespaceName<'static>> = Lazy::new(|| "bananas".try_into().unwrap());
+
+    const N_SHARDS: i32 = 10;
+
+    fn lp_to_writes(
+        lp: &str,
+        tables: &[(&str, TableId)],
+    ) -> HashMap<TableId, (String, MutableBatch)> {
+        let (mut writes, _) = mutable_batch_lp::lines_to_batches_stats(lp, 42)
+            .expect("failed to build test writes from LP");
+        tables
+            .iter()
+            .map(|(name, id)| (*id, (name.to_string(), writes.remove(*name).unwrap())))
+            .collect()
+    }
+
+    async fn test_setup() -> (
+        Arc<TestCatalog>,
+        Arc<TestNamespace>,
+        Arc<PinnedSharder<Arc<Shard>>>,
+        ShardPinner,
+    ) {
+        let catalog = TestCatalog::new();
+        let namespace = catalog.create_namespace_1hr_retention(&NAMESPACE).await;
+
+        let write_buffer = Arc::new(
+            MockBufferForWriting::new(
+                MockBufferSharedState::empty_with_n_shards((N_SHARDS as u32).try_into().unwrap()),
+                None,
+                catalog.time_provider(),
+            )
+            .expect("failed to init mock write buffer"),
+        );
+
+        let mut catalog_shards = Vec::new();
+        let mut shards = Vec::new();
+        for i in 0..N_SHARDS {
+            catalog_shards.push(namespace.create_shard(i).await.shard.clone());
+            shards.push(Arc::new(Shard::new(
+                ShardIndex::new(i),
+                Arc::clone(&write_buffer) as _,
+                &Default::default(),
+            )));
+        }
+
+        let sharder = Arc::new(PinnedSharder::new(shards));
+        let pinner = ShardPinner::new(catalog.catalog(), Arc::clone(&sharder), catalog_shards);
+
+        (catalog, namespace, sharder, pinner)
+    }
+
+    async fn shard_index_of(catalog: &TestCatalog, shard_id: ShardId) -> ShardIndex {
+        catalog
+            .catalog()
+            .repositories()
+            .await
+            .shards()
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|s| s.id == shard_id)
+            .unwrap()
+            .shard_index
+    }
+
+    #[tokio::test]
+    async fn test_new_table_pinned_to_hashed_shard() {
+        let (catalog, namespace, sharder, pinner) = test_setup().await;
+        let table = namespace.create_table("bananas").await;
+
+        let want = sharder.shard("bananas", &NAMESPACE, &()).shard_index();
+
+        let writes = lp_to_writes("bananas val=42i 1", &[("bananas", table.table.id)]);
+        let got = pinner
+            .write(&NAMESPACE, namespace.namespace.id, writes, None)
+            .await
+            .expect("write should succeed");
+        assert_eq!(got.len(), 1);
+
+        assert!(sharder.is_pinned("bananas", &NAMESPACE));
+        assert_eq!(
+            sharder.shard("bananas", &NAMESPACE, &()).shard_index(),
+            want
+        );
+
+        let pin = catalog
+            .catalog()
+            .repositories()
+            .await
+            .tables()
+            .get_shard_pin(table.table.id)
+            .await
+            .unwrap()
+            .expect("pin should be persisted");
+        assert_eq!(shard_index_of(&catalog, pin).await, want);
+    }
+
+    #[tokio::test]
+    async fn test_existing_table_pinned_to_partition_shard() {
+        let (catalog, namespace, sharder, pinner) = test_setup().await;
+        let table = namespace.create_table("bananas").await;
+
+        // Place the existing data in a shard other than the hashed shard.
+        let hashed = sharder.shard("bananas", &NAMESPACE, &()).shard_index();
+        let existing = (hashed.get() + 1) % N_SHARDS;
+        let shard = namespace.create_shard(existing).await;
+        table.with_shard(&shard).create_partition("platanos").await;
+
+        let writes = lp_to_writes("bananas val=42i 1", &[("bananas", table.table.id)]);
+        pinner
+            .write(&NAMESPACE, namespace.namespace.id, writes, None)
+            .await
+            .expect("write should succeed");
+
+        assert_eq!(
+            sharder.shard("bananas", &NAMESPACE, &()).shard_index(),
+            ShardIndex::new(existing)
+        );
+        let pin = catalog
+            .catalog()
+            .repositories()
+            .await
+            .tables()
+            .get_shard_pin(table.table.id)
+            .await
+            .unwrap();
+        assert_eq!(pin, Some(shard.shard.id));
+    }
+
+    #[tokio::test]
+    async fn test_persisted_pin_loaded() {
+        let (catalog, namespace, sharder, pinner) = test_setup().await;
+        let table = namespace.create_table("bananas").await;
+
+        let hashed = sharder.shard("bananas", &NAMESPACE, &()).shard_index();
+        let pinned = namespace.create_shard((hashed.get() + 1) % N_SHARDS).await;
+        catalog
+            .catalog()
+            .repositories()
+            .await
+            .tables()
+            .create_or_get_shard_pin(table.table.id, pinned.shard.id)
+            .await
+            .unwrap();
+
+        // A delete for the table loads the pin.
+        let predicate = DeletePredicate {
+            range: TimestampRange::new(1, 2),
+            exprs: vec![],
+        };
+        pinner
+            .delete(
+                &NAMESPACE,
+                namespace.namespace.id,
+                "bananas",
+                &predicate,
+                None,
+            )
+            .await
+            .expect("delete should succeed");
+
+        assert_eq!(
+            sharder.shard("bananas", &NAMESPACE, &()).shard_index(),
+            pinned.shard.shard_index
+        );
+
+        // Unknown tables are not pinned.
+        pinner
+            .delete(
+                &NAMESPACE,
+                namespace.namespace.id,
+                "platanos",
+                &predicate,
+                None,
+            )
+            .await
+            .expect("delete should succeed");
+        assert!(!sharder.is_pinned("platanos", &NAMESPACE));
+    }
+
+    #[tokio::test]
+    async fn test_disabled() {
+        let (catalog, namespace, sharder, pinner) = test_setup().await;
+        let pinner = pinner.with_enabled(false);
+        let table = namespace.create_table("bananas").await;
+
+        let writes = lp_to_writes("bananas val=42i 1", &[("bananas", table.table.id)]);
+        pinner
+            .write(&NAMESPACE, namespace.namespace.id, writes, None)
+            .await
+            .expect("write should succeed");
+
+        assert!(!sharder.is_pinned("bananas", &NAMESPACE));
+        let pin = catalog
+            .catalog()
+            .repositories()
+            .await
+            .tables()
+            .get_shard_pin(table.table.id)
+            .await
+            .unwrap();
+        assert_eq!(pin, None);
+    }
+}
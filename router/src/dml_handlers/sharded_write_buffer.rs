@@ -16,6 +16,7 @@ use sharder::Sharder;
 use thiserror::Error;
 use trace::ctx::SpanContext;
 use write_buffer::core::WriteBufferError;
+use write_summary::PartitionWrite;
 
 use super::Partitioned;
 use crate::{dml_handlers::DmlHandler, shard::Shard};
@@ -54,7 +55,8 @@ where
 /// instances and dispatching them to the write buffer.
 ///
 /// Writes are batched per-shard, producing one op per shard, per write. For a
-/// single write, all shards are wrote to in parallel.
+/// single write, all shards are wrote to in parallel, and a [`PartitionWrite`]
+/// describing the rows enqueued into each shard is returned.
 ///
 /// The buffering / async return behaviour of the methods on this type are
 /// defined by the behaviour of the underlying [write buffer] implementation.
@@ -88,7 +90,7 @@ where
     type DeleteError = ShardError;
 
     type WriteInput = Partitioned<HashMap<TableId, (String, MutableBatch)>>;
-    type WriteOutput = Vec<DmlMeta>;
+    type WriteOutput = Vec<PartitionWrite>;
 
     /// Shard `writes` and dispatch the resultant DML operations.
     async fn write(
@@ -119,6 +121,7 @@ where
         }
 
         let iter = collated.into_iter().map(|(shard, batch)| {
+            let row_count = batch.values().map(|b| b.rows()).sum::<usize>();
             let dml = DmlWrite::new(
                 namespace_id,
                 batch,
@@ -130,16 +133,21 @@ where
                 %partition_key,
                 kafka_partition=%shard.shard_index(),
                 tables=%dml.table_count(),
+                %row_count,
                 %namespace,
                 %namespace_id,
                 approx_size=%dml.size(),
                 "routing writes to shard"
             );
 
-            (shard, DmlOperation::from(dml))
+            (shard, DmlOperation::from(dml), row_count)
         });
 
-        parallel_enqueue(iter).await
+        Ok(parallel_enqueue(iter)
+            .await?
+            .into_iter()
+            .map(|(row_count, meta)| PartitionWrite::new(partition_key.clone(), row_count, meta))
+            .collect())
     }
 
     /// Shard `predicate` and dispatch it to the appropriate shard.
@@ -170,7 +178,7 @@ where
                 "routing delete to shard"
             );
 
-            (s, DmlOperation::from(dml.clone()))
+            (s, DmlOperation::from(dml.clone()), ())
         });
 
         // TODO: return shard metadata
@@ -184,16 +192,18 @@ where
 /// the [`DmlOperation`] to its paired [`Shard`], executes all the futures
 /// in parallel and gathers any errors.
 ///
-/// Returns a list of the sequences that were written.
-async fn parallel_enqueue<T>(v: T) -> Result<Vec<DmlMeta>, ShardError>
+/// Returns a list of the sequences that were written, each paired with the
+/// `U` of the operation.
+async fn parallel_enqueue<T, U>(v: T) -> Result<Vec<(U, DmlMeta)>, ShardError>
 where
-    T: Iterator<Item = (Arc<Shard>, DmlOperation)> + Send,
+    T: Iterator<Item = (Arc<Shard>, DmlOperation, U)> + Send,
+    U: Send + 'static,
 {
     let mut successes = vec![];
     let mut errs = vec![];
 
-    v.map(|(shard, op)| async move {
-        tokio::spawn(async move { shard.enqueue(op).await })
+    v.map(|(shard, op, tag)| async move {
+        tokio::spawn(async move { shard.enqueue(op).await.map(|meta| (tag, meta)) })
             .await
             .expect("shard enqueue panic")
     })
@@ -204,7 +214,7 @@ where
     // Sort the result into successes/failures upon completion
     .into_iter()
     .for_each(|v| match v {
-        Ok(meta) => successes.push(meta),
+        Ok(write) => successes.push(write),
         Err(e) => errs.push(e),
     });
 
@@ -288,10 +298,18 @@ mod tests {
 
         // Call the ShardedWriteBuffer and drive the test
         let ns = NamespaceName::new("bananas").unwrap();
-        w.write(&ns, NamespaceId::new(42), writes, None)
+        let got = w
+            .write(&ns, NamespaceId::new(42), writes, None)
             .await
             .expect("write failed");
 
+        // A single op containing all the rows of the partition was enqueued.
+        assert_matches!(got.as_slice(), [w] => {
+            assert_eq!(w.partition_key().to_string(), "key");
+            assert_eq!(w.row_count(), 4);
+            assert_eq!(w.meta().sequence().unwrap().shard_index, ShardIndex::new(0));
+        });
+
         // Assert the sharder saw all the tables
         let calls = sharder.calls();
         assert_eq!(calls.len(), 3);
@@ -0,0 +1,258 @@
+//! Routing of DML operations to the write buffer topic of their namespace.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use data_types::{DeletePredicate, NamespaceId, NamespaceName, TopicId};
+use hashbrown::HashMap;
+use thiserror::Error;
+use trace::ctx::SpanContext;
+
+use super::{DmlError, DmlHandler};
+use crate::namespace_topics::NamespaceTopics;
+
+/// Errors emitted while routing an operation to the topic of its namespace.
+#[derive(Debug, Error)]
+pub enum TopicRouterError {
+    /// The namespace is assigned to a topic this router does not write to.
+    #[error(
+        "namespace {namespace} is assigned to topic {topic_id}, which is not served by this router"
+    )]
+    UnservedTopic {
+        /// The namespace of the operation.
+        namespace: String,
+        /// The topic the namespace is assigned to.
+        topic_id: TopicId,
+    },
+}
+
+/// A [`DmlHandler`] that dispatches each operation to the inner handler for
+/// the write buffer topic of the operation's namespace, as mapped by a
+/// [`NamespaceTopics`].
+///
+/// Typically each inner handler is a [`ShardedWriteBuffer`] writing to a
+/// different topic, allowing the writes of some namespaces to be isolated
+/// onto a dedicated topic (and therefore, a dedicated set of ingesters).
+///
+/// Operations for a namespace assigned to a topic without a handler are
+/// rejected with [`TopicRouterError::UnservedTopic`], as the ingesters of any
+/// other topic would never be queried for the namespace's data.
+///
+/// [`ShardedWriteBuffer`]: super::ShardedWriteBuffer
+#[derive(Debug)]
+pub struct TopicRouter<T> {
+    topics: Arc<NamespaceTopics>,
+    handlers: HashMap<TopicId, T>,
+}
+
+impl<T> TopicRouter<T> {
+    /// Initialise a [`TopicRouter`] dispatching operations to the handler of
+    /// their namespace's topic in `handlers`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `handlers` has no handler for the default topic of `topics`.
+    pub fn new(
+        topics: Arc<NamespaceTopics>,
+        handlers: impl IntoIterator<Item = (TopicId, T)>,
+    ) -> Self {
+        let handlers: HashMap<_, _> = handlers.into_iter().collect();
+        assert!(
+            handlers.contains_key(&topics.default_topic()),
+            "no handler for default topic {}",
+            topics.default_topic()
+        );
+
+        Self { topics, handlers }
+    }
+
+    fn handler(&self, namespace: &NamespaceName<'_>) -> Result<&T, TopicRouterError> {
+        let topic_id = self.topics.topic_for(namespace);
+        self.handlers
+            .get(&topic_id)
+            .ok_or_else(|| TopicRouterError::UnservedTopic {
+                namespace: namespace.to_string(),
+                topic_id,
+            })
+    }
+}
+
+#[async_trait]
+impl<T> DmlHandler for TopicRouter<T>
+where
+    T: DmlHandler,
+{
+    type WriteInput = T::WriteInput;
+    type WriteOutput = T::WriteOutput;
+    type WriteError = DmlError;
+    type DeleteError = DmlError;
+
+    /// Pass `input` to the handler for the topic of `namespace`.
+    async fn write(
+        &self,
+        namespace: &NamespaceName<'static>,
+        namespace_id: NamespaceId,
+        input: Self::WriteInput,
+        span_ctx: Option<SpanContext>,
+    ) -> Result<Self::WriteOutput, Self::WriteError> {
+        self.handler(namespace)?
+            .write(namespace, namespace_id, input, span_ctx)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Pass the delete to the handler for the topic of `namespace`.
+    async fn delete(
+        &self,
+        namespace: &NamespaceName<'static>,
+        namespace_id: NamespaceId,
+        table_name: &str,
+        predicate: &DeletePredicate,
+        span_ctx: Option<SpanContext>,
+    ) -> Result<(), Self::DeleteError> {
+        self.handler(namespace)?
+            .delete(namespace, namespace_id, table_name, predicate, span_ctx)
+            .await
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use data_types::TimestampRange;
+    use iox_tests::util::TestCatalog;
+    use mutable_batch::MutableBatch;
+    use write_summary::WriteSummary;
+
+    use super::*;
+    use crate::dml_handlers::mock::{MockDmlHandler, MockDmlHandlerCall};
+
+    #[tokio::test]
+    async fn test_routes_by_namespace_topic() {
+        let catalog = TestCatalog::new();
+        let bananas = catalog.create_namespace_1hr_retention("bananas").await;
+        catalog.create_namespace_1hr_retention("platanos").await;
+        let default = bananas.topic.id;
+
+        let noisy = {
+            let mut repos = catalog.catalog().repositories().await;
+            let noisy = repos.topics().create_or_get("noisy").await.unwrap();
+            repos
+                .namespaces()
+                .update_topic("bananas", noisy.id)
+                .await
+                .unwrap();
+            noisy.id
+        };
+
+        let topics = Arc::new(NamespaceTopics::new(default));
+        topics.refresh(&*catalog.catalog()).await.unwrap();
+
+        let default_handler = Arc::new(
+            MockDmlHandler::<HashMap<String, MutableBatch>>::default()
+                .with_write_return([Ok(WriteSummary::default())]),
+        );
+        let noisy_handler = Arc::new(
+            MockDmlHandler::<HashMap<String, MutableBatch>>::default()
+                .with_write_return([Ok(WriteSummary::default())])
+                .with_delete_return([Ok(())]),
+        );
+        let router = TopicRouter::new(
+            topics,
+            [
+                (default, Arc::clone(&default_handler)),
+                (noisy, Arc::clone(&noisy_handler)),
+            ],
+        );
+
+        let bananas = NamespaceName::try_from("bananas").unwrap();
+        let platanos = NamespaceName::try_from("platanos").unwrap();
+
+        router
+            .write(&bananas, NamespaceId::new(1), HashMap::new(), None)
+            .await
+            .expect("write should succeed");
+        router
+            .write(&platanos, NamespaceId::new(2), HashMap::new(), None)
+            .await
+            .expect("write should succeed");
+        router
+            .delete(
+                &bananas,
+                NamespaceId::new(1),
+                "cpu",
+                &DeletePredicate {
+                    range: TimestampRange::new(1, 2),
+                    exprs: vec![],
+                },
+                None,
+            )
+            .await
+            .expect("delete should succeed");
+
+        assert_matches!(noisy_handler.calls().as_slice(), [
+            MockDmlHandlerCall::Write { namespace: w, .. },
+            MockDmlHandlerCall::Delete { namespace: d, .. },
+        ] => {
+            assert_eq!(w, "bananas");
+            assert_eq!(d, "bananas");
+        });
+        assert_matches!(default_handler.calls().as_slice(), [
+            MockDmlHandlerCall::Write { namespace, .. },
+        ] => {
+            assert_eq!(namespace, "platanos");
+        });
+    }
+
+    #[tokio::test]
+    async fn test_unserved_topic() {
+        let catalog = TestCatalog::new();
+        let bananas = catalog.create_namespace_1hr_retention("bananas").await;
+        let default = bananas.topic.id;
+
+        let unserved = {
+            let mut repos = catalog.catalog().repositories().await;
+            let unserved = repos.topics().create_or_get("unserved").await.unwrap();
+            repos
+                .namespaces()
+                .update_topic("bananas", unserved.id)
+                .await
+                .unwrap();
+            unserved.id
+        };
+
+        let topics = Arc::new(NamespaceTopics::new(default));
+        topics.refresh(&*catalog.catalog()).await.unwrap();
+
+        let default_handler = Arc::new(MockDmlHandler::<HashMap<String, MutableBatch>>::default());
+        let router = TopicRouter::new(topics, [(default, Arc::clone(&default_handler))]);
+
+        let err = router
+            .write(
+                &NamespaceName::try_from("bananas").unwrap(),
+                NamespaceId::new(1),
+                HashMap::new(),
+                None,
+            )
+            .await
+            .expect_err("write to unserved topic should fail");
+        assert_matches!(
+            err,
+            DmlError::TopicRouting(TopicRouterError::UnservedTopic { namespace, topic_id }) => {
+                assert_eq!(namespace, "bananas");
+                assert_eq!(topic_id, unserved);
+            }
+        );
+
+        // The write is not sent to the default topic.
+        assert!(default_handler.calls().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "no handler for default topic")]
+    fn test_no_default_handler() {
+        let topics = Arc::new(NamespaceTopics::new(TopicId::new(1)));
+        TopicRouter::<MockDmlHandler<()>>::new(topics, std::iter::empty());
+    }
+}
@@ -7,6 +7,7 @@ use trace::ctx::SpanContext;
 
 use super::{
     partitioner::PartitionError, retention_validator::RetentionError, SchemaError, ShardError,
+    ShardPinError, TopicRouterError, TransformError,
 };
 
 /// Errors emitted by a [`DmlHandler`] implementation during DML request
@@ -33,6 +34,18 @@ pub enum DmlError {
     #[error(transparent)]
     Retention(#[from] RetentionError),
 
+    /// An error resolving the shard a table is pinned to.
+    #[error(transparent)]
+    ShardPin(#[from] ShardPinError),
+
+    /// An error routing the request to the topic of the namespace.
+    #[error(transparent)]
+    TopicRouting(#[from] TopicRouterError),
+
+    /// An error applying the transform rules of the namespace.
+    #[error(transparent)]
+    Transform(#[from] TransformError),
+
     /// An unknown error occured while processing the DML request.
     #[error("internal dml handler error: {0}")]
     Internal(Box<dyn Error + Send + Sync>),
@@ -0,0 +1,300 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use async_trait::async_trait;
+use data_types::{DeletePredicate, NamespaceId, NamespaceName};
+use hashbrown::HashMap;
+use mutable_batch::MutableBatch;
+use observability_deps::tracing::*;
+use schema::InfluxColumnType;
+use thiserror::Error;
+use trace::ctx::SpanContext;
+
+use super::DmlHandler;
+
+/// Errors emitted when applying the [`TransformRules`] of a namespace to a
+/// write.
+#[derive(Debug, Error)]
+pub enum TransformError {
+    /// A renamed measurement could not be merged into the measurement of the
+    /// same name in the write, e.g. because a column has a different type.
+    #[error("cannot rename table {from} to {to}: {source}")]
+    RenameTable {
+        /// The table name in the write.
+        from: String,
+        /// The name the table is renamed to.
+        to: String,
+        /// The error merging the tables.
+        source: mutable_batch::Error,
+    },
+
+    /// A tag key is renamed to the name of another column of the table.
+    #[error("cannot rename tag {from} of table {table} to {to}: {source}")]
+    RenameTag {
+        /// The table of the tag.
+        table: String,
+        /// The tag key in the write.
+        from: String,
+        /// The key the tag is renamed to.
+        to: String,
+        /// The error renaming the column.
+        source: mutable_batch::Error,
+    },
+}
+
+/// Declarative rules rewriting the writes to a namespace before they are
+/// validated against its schema.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransformRules {
+    /// New names of measurements, keyed by their name in the write.
+    pub rename_measurements: BTreeMap<String, String>,
+
+    /// New keys of tags, keyed by their key in the write. Applies to the tags
+    /// of all measurements.
+    pub rename_tags: BTreeMap<String, String>,
+
+    /// Fields to remove from all measurements.
+    pub drop_fields: BTreeSet<String>,
+}
+
+impl TransformRules {
+    /// Apply the rules to `batches`.
+    ///
+    /// The named fields are dropped and the tags renamed in every measurement
+    /// first. The measurements are then renamed, a renamed measurement being
+    /// merged into the measurement of the same name if the write contains
+    /// both. Measurements left without any field are removed from the write.
+    pub fn apply(
+        &self,
+        mut batches: HashMap<String, MutableBatch>,
+    ) -> Result<HashMap<String, MutableBatch>, TransformError> {
+        for (table, batch) in batches.iter_mut() {
+            for field in &self.drop_fields {
+                if matches!(
+                    batch.column(field).map(|c| c.influx_type()),
+                    Ok(InfluxColumnType::Field(_))
+                ) {
+                    batch.drop_column(field).expect("column exists");
+                }
+            }
+
+            for (from, to) in &self.rename_tags {
+                if matches!(
+                    batch.column(from).map(|c| c.influx_type()),
+                    Ok(InfluxColumnType::Tag)
+                ) {
+                    batch
+                        .rename_column(from, to)
+                        .map_err(|source| TransformError::RenameTag {
+                            table: table.clone(),
+                            from: from.clone(),
+                            to: to.clone(),
+                            source,
+                        })?;
+                }
+            }
+        }
+
+        // Remove all the renamed measurements before adding them back, so that
+        // a measurement is never renamed twice.
+        let renamed: Vec<_> = self
+            .rename_measurements
+            .iter()
+            .filter_map(|(from, to)| Some((from, to, batches.remove(from)?)))
+            .collect();
+        for (from, to, batch) in renamed {
+            match batches.get_mut(to) {
+                Some(existing) => {
+                    existing
+                        .extend_from(&batch)
+                        .map_err(|source| TransformError::RenameTable {
+                            from: from.clone(),
+                            to: to.clone(),
+                            source,
+                        })?;
+                }
+                None => {
+                    batches.insert(to.clone(), batch);
+                }
+            }
+        }
+
+        batches.retain(|_, batch| {
+            batch
+                .columns()
+                .any(|(_, c)| matches!(c.influx_type(), InfluxColumnType::Field(_)))
+        });
+
+        Ok(batches)
+    }
+}
+
+/// A [`DmlHandler`] implementation that rewrites the writes to namespaces
+/// with [`TransformRules`], passing all other writes through unmodified.
+///
+/// Deletes are passed through unmodified, so a delete must name the table
+/// and columns as they are stored, i.e. after renaming.
+#[derive(Debug, Clone, Default)]
+pub struct Transformer {
+    rules: HashMap<NamespaceName<'static>, TransformRules>,
+}
+
+impl Transformer {
+    /// Rewrite the writes to `namespace` according to `rules`.
+    pub fn with_namespace_rules(
+        mut self,
+        namespace: NamespaceName<'static>,
+        rules: TransformRules,
+    ) -> Self {
+        self.rules.insert(namespace, rules);
+        self
+    }
+
+    /// The rules applied to the writes to `namespace`, if any.
+    pub fn rules(&self, namespace: &NamespaceName<'static>) -> Option<&TransformRules> {
+        self.rules.get(namespace)
+    }
+}
+
+#[async_trait]
+impl DmlHandler for Transformer {
+    type WriteError = TransformError;
+    type DeleteError = TransformError;
+
+    type WriteInput = HashMap<String, MutableBatch>;
+    type WriteOutput = Self::WriteInput;
+
+    /// Apply the [`TransformRules`] of `namespace` to `batch`.
+    async fn write(
+        &self,
+        namespace: &NamespaceName<'static>,
+        _namespace_id: NamespaceId,
+        batch: Self::WriteInput,
+        _span_ctx: Option<SpanContext>,
+    ) -> Result<Self::WriteOutput, Self::WriteError> {
+        match self.rules(namespace) {
+            Some(rules) => {
+                trace!(%namespace, "applying transform rules");
+                rules.apply(batch)
+            }
+            None => Ok(batch),
+        }
+    }
+
+    /// Pass the delete request through unmodified to the next handler.
+    async fn delete(
+        &self,
+        _namespace: &NamespaceName<'static>,
+        _namespace_id: NamespaceId,
+        _table_name: &str,
+        _predicate: &DeletePredicate,
+        _span_ctx: Option<SpanContext>,
+    ) -> Result<(), Self::DeleteError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assert_matches::assert_matches;
+    use once_cell::sync::Lazy;
+
+    use super::*;
+
+    static NAMESPACE: Lazy<NamespaceName<'static>> = Lazy::new(|| "bananas".try_into().unwrap());
+
+    fn lp_to_writes(lp: &str) -> HashMap<String, MutableBatch> {
+        let (writes, _) = mutable_batch_lp::lines_to_batches_stats(lp, 42)
+            .expect("failed to build test writes from LP");
+        writes
+    }
+
+    fn rules() -> TransformRules {
+        TransformRules {
+            rename_measurements: BTreeMap::from([("cpu_total".to_string(), "cpu".to_string())]),
+            rename_tags: BTreeMap::from([("hostname".to_string(), "host".to_string())]),
+            drop_fields: BTreeSet::from(["debug".to_string()]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transform() {
+        let transformer = Transformer::default().with_namespace_rules(NAMESPACE.clone(), rules());
+
+        let writes = lp_to_writes(
+            "cpu_total,hostname=a usage=1,debug=\"x\" 1\n\
+             cpu,host=b usage=2 2\n\
+             mem,hostname=a free=3i 3\n\
+             trace debug=\"y\" 4",
+        );
+        let got = transformer
+            .write(&NAMESPACE, NamespaceId::new(42), writes, None)
+            .await
+            .expect("transform should succeed");
+
+        let mut tables: Vec<_> = got.keys().cloned().collect();
+        tables.sort_unstable();
+        assert_eq!(tables, ["cpu", "mem"]);
+
+        let cpu = &got["cpu"];
+        assert_eq!(cpu.rows(), 2);
+        assert_eq!(
+            cpu.column_names().into_iter().collect::<Vec<_>>(),
+            ["host", "time", "usage"]
+        );
+        assert_eq!(
+            cpu.column("host").unwrap().influx_type(),
+            InfluxColumnType::Tag
+        );
+
+        let mem = &got["mem"];
+        assert_eq!(mem.rows(), 1);
+        assert_eq!(
+            mem.column_names().into_iter().collect::<Vec<_>>(),
+            ["free", "host", "time"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transform_other_namespace() {
+        let transformer = Transformer::default().with_namespace_rules(NAMESPACE.clone(), rules());
+
+        let ns = NamespaceName::try_from("platanos").unwrap();
+        let writes = lp_to_writes("cpu_total,hostname=a usage=1,debug=\"x\" 1");
+        let got = transformer
+            .write(&ns, NamespaceId::new(42), writes, None)
+            .await
+            .expect("write should pass through");
+
+        let columns = got["cpu_total"].column_names();
+        assert_eq!(
+            columns.into_iter().collect::<Vec<_>>(),
+            ["debug", "hostname", "time", "usage"]
+        );
+    }
+
+    #[test]
+    fn test_transform_conflicts() {
+        // The renamed measurement has a column of a different type.
+        let writes = lp_to_writes("cpu_total usage=1i 1\ncpu usage=2 2");
+        let err = rules().apply(writes).expect_err("rename should fail");
+        assert_matches!(err, TransformError::RenameTable { from, to, .. } => {
+            assert_eq!(from, "cpu_total");
+            assert_eq!(to, "cpu");
+        });
+
+        // The renamed tag key is already used.
+        let writes = lp_to_writes("disk,hostname=a,host=b free=1i 1");
+        let err = rules().apply(writes).expect_err("rename should fail");
+        assert_matches!(err, TransformError::RenameTag { table, .. } => {
+            assert_eq!(table, "disk");
+        });
+
+        // Only tags are renamed, and only fields dropped.
+        let writes = lp_to_writes("disk,debug=a hostname=1i 1");
+        let got = rules().apply(writes).expect("no rule applies");
+        assert_eq!(
+            got["disk"].column_names().into_iter().collect::<Vec<_>>(),
+            ["debug", "hostname", "time"]
+        );
+    }
+}
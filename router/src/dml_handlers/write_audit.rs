@@ -0,0 +1,306 @@
+//! Sampled audit logging of accepted writes.
+
+use std::{fmt::Debug, sync::Arc};
+
+use async_trait::async_trait;
+use data_types::{DeletePredicate, NamespaceId, NamespaceName};
+use hashbrown::HashMap;
+use iox_time::{SystemProvider, Time, TimeProvider};
+use mutable_batch::MutableBatch;
+use observability_deps::tracing::*;
+use rand::Rng;
+use trace::ctx::{SpanContext, TraceId};
+
+use super::DmlHandler;
+
+/// A single audited write to one table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteAuditRecord {
+    /// The namespace the write was applied to.
+    pub namespace: String,
+    /// The table the write was applied to.
+    pub table: String,
+    /// The number of rows written to `table`.
+    pub rows: usize,
+    /// The approximate in-memory size of the data written to `table`, in bytes.
+    pub bytes: usize,
+    /// The trace ID of the request that performed the write, if any.
+    ///
+    /// This identifies the client request, and can be used to correlate the
+    /// record with the request logs & traces of the caller.
+    pub trace_id: Option<TraceId>,
+    /// The time at which the write was accepted.
+    pub timestamp: Time,
+}
+
+/// A destination for [`WriteAuditRecord`] entries.
+pub trait WriteAuditSink: Debug + Send + Sync {
+    /// Record `record`.
+    ///
+    /// Called on the write path - implementations should not block.
+    fn record(&self, record: WriteAuditRecord);
+}
+
+impl<T> WriteAuditSink for Arc<T>
+where
+    T: WriteAuditSink,
+{
+    fn record(&self, record: WriteAuditRecord) {
+        (**self).record(record)
+    }
+}
+
+/// A [`WriteAuditSink`] emitting one log line per record to the
+/// `write_audit` tracing target.
+#[derive(Debug, Default)]
+pub struct LogWriteAuditSink;
+
+impl WriteAuditSink for LogWriteAuditSink {
+    fn record(&self, record: WriteAuditRecord) {
+        info!(
+            target: "write_audit",
+            namespace=%record.namespace,
+            table=%record.table,
+            rows=record.rows,
+            bytes=record.bytes,
+            trace_id=?record.trace_id.map(|v| format!("{:x}", v.get())),
+            timestamp=%record.timestamp,
+            "write audit"
+        );
+    }
+}
+
+/// A [`DmlHandler`] decorator that records a [`WriteAuditRecord`] per table
+/// for a randomly sampled fraction of the writes successfully applied by the
+/// inner handler.
+///
+/// Rejected writes are never recorded, and deletes are passed through
+/// unaudited.
+#[derive(Debug)]
+pub struct WriteAuditor<T, S, P = SystemProvider> {
+    inner: T,
+    sink: S,
+    sample_rate: f64,
+    time_provider: P,
+}
+
+impl<T, S> WriteAuditor<T, S> {
+    /// Wrap `inner`, recording a `sample_rate` fraction of accepted writes to
+    /// `sink`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_rate` is not within the range `[0.0, 1.0]`.
+    pub fn new(inner: T, sink: S, sample_rate: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&sample_rate),
+            "write audit sample rate must be within [0.0, 1.0], got {}",
+            sample_rate
+        );
+
+        Self {
+            inner,
+            sink,
+            sample_rate,
+            time_provider: Default::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl<T, S, P> DmlHandler for WriteAuditor<T, S, P>
+where
+    T: DmlHandler<WriteInput = HashMap<String, MutableBatch>>,
+    S: WriteAuditSink,
+    P: TimeProvider,
+{
+    type WriteInput = T::WriteInput;
+    type WriteOutput = T::WriteOutput;
+    type WriteError = T::WriteError;
+    type DeleteError = T::DeleteError;
+
+    /// Pass `batches` to the inner handler, recording an audit entry per
+    /// table if the write is sampled and accepted.
+    async fn write(
+        &self,
+        namespace: &NamespaceName<'static>,
+        namespace_id: NamespaceId,
+        batches: Self::WriteInput,
+        span_ctx: Option<SpanContext>,
+    ) -> Result<Self::WriteOutput, Self::WriteError> {
+        // The batches are consumed by the inner handler, so the sampling
+        // decision is made (and the per-table stats captured) up front.
+        let sampled = self.sample_rate > 0.0 && rand::thread_rng().gen_bool(self.sample_rate);
+        let stats = sampled.then(|| {
+            batches
+                .iter()
+                .map(|(table, batch)| (table.clone(), batch.rows(), batch.size()))
+                .collect::<Vec<_>>()
+        });
+        let trace_id = span_ctx.as_ref().map(|v| v.trace_id);
+
+        let res = self
+            .inner
+            .write(namespace, namespace_id, batches, span_ctx)
+            .await?;
+
+        if let Some(stats) = stats {
+            let timestamp = self.time_provider.now();
+            for (table, rows, bytes) in stats {
+                self.sink.record(WriteAuditRecord {
+                    namespace: namespace.to_string(),
+                    table,
+                    rows,
+                    bytes,
+                    trace_id,
+                    timestamp,
+                });
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Pass the delete request through to the inner handler.
+    async fn delete(
+        &self,
+        namespace: &NamespaceName<'static>,
+        namespace_id: NamespaceId,
+        table_name: &str,
+        predicate: &DeletePredicate,
+        span_ctx: Option<SpanContext>,
+    ) -> Result<(), Self::DeleteError> {
+        self.inner
+            .delete(namespace, namespace_id, table_name, predicate, span_ctx)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::{NonZeroU128, NonZeroU64};
+
+    use assert_matches::assert_matches;
+    use parking_lot::Mutex;
+    use trace::{ctx::SpanId, RingBufferTraceCollector};
+    use write_summary::WriteSummary;
+
+    use super::*;
+    use crate::dml_handlers::{mock::MockDmlHandler, DmlError};
+
+    const NAMESPACE: &str = "bananas";
+
+    #[derive(Debug, Default)]
+    struct MockSink(Mutex<Vec<WriteAuditRecord>>);
+
+    impl WriteAuditSink for MockSink {
+        fn record(&self, record: WriteAuditRecord) {
+            self.0.lock().push(record)
+        }
+    }
+
+    fn lp_to_writes(lp: &str) -> HashMap<String, MutableBatch> {
+        let (writes, _) = mutable_batch_lp::lines_to_batches_stats(lp, 42)
+            .expect("failed to build test writes from LP");
+        writes
+    }
+
+    fn span_ctx() -> SpanContext {
+        SpanContext {
+            trace_id: TraceId(NonZeroU128::new(42).unwrap()),
+            parent_span_id: None,
+            span_id: SpanId(NonZeroU64::new(1).unwrap()),
+            links: vec![],
+            collector: Some(Arc::new(RingBufferTraceCollector::new(5))),
+            sampled: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_write_sampled() {
+        let inner =
+            Arc::new(MockDmlHandler::default().with_write_return([Ok(WriteSummary::default())]));
+        let sink = Arc::new(MockSink::default());
+        let handler = WriteAuditor::new(Arc::clone(&inner), Arc::clone(&sink), 1.0);
+
+        let ns = NamespaceName::try_from(NAMESPACE).unwrap();
+        let writes =
+            lp_to_writes("bananas,tag1=A val=42i 1\nbananas,tag1=B val=4i 2\nplatanos val=1i 3");
+        handler
+            .write(&ns, NamespaceId::new(42), writes, Some(span_ctx()))
+            .await
+            .expect("write should succeed");
+
+        assert_eq!(inner.calls().len(), 1);
+
+        let mut records = sink.0.lock().clone();
+        records.sort_unstable_by(|a, b| a.table.cmp(&b.table));
+        assert_matches!(records.as_slice(), [bananas, platanos] => {
+            assert_eq!(bananas.namespace, NAMESPACE);
+            assert_eq!(bananas.table, "bananas");
+            assert_eq!(bananas.rows, 2);
+            assert!(bananas.bytes > 0);
+            assert_eq!(bananas.trace_id, Some(TraceId(NonZeroU128::new(42).unwrap())));
+
+            assert_eq!(platanos.table, "platanos");
+            assert_eq!(platanos.rows, 1);
+            assert_eq!(platanos.timestamp, bananas.timestamp);
+        });
+    }
+
+    #[tokio::test]
+    async fn test_write_not_sampled() {
+        let inner =
+            Arc::new(MockDmlHandler::default().with_write_return([Ok(WriteSummary::default())]));
+        let sink = Arc::new(MockSink::default());
+        let handler = WriteAuditor::new(Arc::clone(&inner), Arc::clone(&sink), 0.0);
+
+        let ns = NamespaceName::try_from(NAMESPACE).unwrap();
+        handler
+            .write(
+                &ns,
+                NamespaceId::new(42),
+                lp_to_writes("bananas val=42i 1"),
+                None,
+            )
+            .await
+            .expect("write should succeed");
+
+        assert_eq!(inner.calls().len(), 1);
+        assert!(sink.0.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_write_rejected_not_recorded() {
+        let inner = Arc::new(
+            MockDmlHandler::default()
+                .with_write_return([Err(DmlError::NamespaceNotFound(NAMESPACE.to_string()))]),
+        );
+        let sink = Arc::new(MockSink::default());
+        let handler = WriteAuditor::new(Arc::clone(&inner), Arc::clone(&sink), 1.0);
+
+        let ns = NamespaceName::try_from(NAMESPACE).unwrap();
+        let err = handler
+            .write(
+                &ns,
+                NamespaceId::new(42),
+                lp_to_writes("bananas val=42i 1"),
+                None,
+            )
+            .await
+            .expect_err("write should fail");
+        assert_matches!(err, DmlError::NamespaceNotFound(_));
+
+        assert!(sink.0.lock().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "write audit sample rate must be within")]
+    fn test_invalid_sample_rate() {
+        WriteAuditor::new(
+            MockDmlHandler::<HashMap<String, MutableBatch>>::default(),
+            LogWriteAuditSink,
+            1.5,
+        );
+    }
+}
@@ -2,16 +2,18 @@ use std::fmt::Debug;
 
 use async_trait::async_trait;
 use data_types::{DeletePredicate, NamespaceId, NamespaceName};
-use dml::DmlMeta;
 use trace::ctx::SpanContext;
-use write_summary::WriteSummary;
+use write_summary::{PartitionWrite, WriteSummary};
 
 use super::DmlHandler;
 
 /// A [`WriteSummaryAdapter`] wraps DML Handler that produces
-///  `Vec<Vec<DmlMeta>>` for each write, and produces a WriteSummary,
+///  `Vec<Vec<PartitionWrite>>` for each write, and produces a WriteSummary,
 ///  suitable for
-/// sending back to a client
+/// sending back to a client.
+///
+/// The summary records the sequence numbers and per-partition row counts of
+/// the write in each shard it was sequenced into.
 #[derive(Debug, Default)]
 pub struct WriteSummaryAdapter<T> {
     inner: T,
@@ -28,7 +30,7 @@ impl<T> WriteSummaryAdapter<T> {
 #[async_trait]
 impl<T> DmlHandler for WriteSummaryAdapter<T>
 where
-    T: DmlHandler<WriteOutput = Vec<Vec<DmlMeta>>>,
+    T: DmlHandler<WriteOutput = Vec<Vec<PartitionWrite>>>,
 {
     type WriteInput = T::WriteInput;
     type WriteOutput = WriteSummary;
@@ -36,7 +38,7 @@ where
     type DeleteError = T::DeleteError;
 
     /// Sends `input` to the inner handler, which returns a
-    /// `Vec<Vec<DmlMeta>>`, creating a `WriteSummary`
+    /// `Vec<Vec<PartitionWrite>>`, creating a `WriteSummary`
     async fn write(
         &self,
         namespace: &NamespaceName<'static>,
@@ -44,11 +46,11 @@ where
         input: Self::WriteInput,
         span_ctx: Option<SpanContext>,
     ) -> Result<Self::WriteOutput, Self::WriteError> {
-        let metas = self
+        let writes = self
             .inner
             .write(namespace, namespace_id, input, span_ctx)
             .await?;
-        Ok(WriteSummary::new(metas))
+        Ok(WriteSummary::new(writes))
     }
 
     /// Pass the delete through to the inner handler.
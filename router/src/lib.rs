@@ -111,5 +111,7 @@
 pub mod dml_handlers;
 pub mod namespace_cache;
 pub mod namespace_resolver;
+pub mod namespace_topics;
+pub mod producer_sequence;
 pub mod server;
 pub mod shard;
@@ -47,6 +47,12 @@ mod tests {
             tables: Default::default(),
             max_columns_per_table: 50,
             retention_period_ns: Some(876),
+            default_query_range_ns: None,
+            max_query_range_ns: None,
+            row_ttl_ns: None,
+            row_ttl_column: None,
+            partition_template: None,
+            generation: 0,
         };
         assert!(cache.put_schema(ns.clone(), schema1.clone()).is_none());
         assert_eq!(*cache.get_schema(&ns).expect("lookup failure"), schema1);
@@ -58,6 +64,12 @@ mod tests {
             tables: Default::default(),
             max_columns_per_table: 10,
             retention_period_ns: Some(876),
+            default_query_range_ns: None,
+            max_query_range_ns: None,
+            row_ttl_ns: None,
+            row_ttl_column: None,
+            partition_template: None,
+            generation: 0,
         };
 
         assert_eq!(
@@ -66,6 +66,12 @@ mod tests {
             tables: Default::default(),
             max_columns_per_table: 7,
             retention_period_ns: None,
+            default_query_range_ns: None,
+            max_query_range_ns: None,
+            row_ttl_ns: None,
+            row_ttl_column: None,
+            partition_template: None,
+            generation: 0,
         }
     }
 
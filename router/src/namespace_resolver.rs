@@ -5,7 +5,7 @@ use std::{ops::DerefMut, sync::Arc};
 
 use async_trait::async_trait;
 use data_types::{NamespaceId, NamespaceName};
-use iox_catalog::interface::{get_schema_by_name, Catalog};
+use iox_catalog::interface::{get_schema_by_name, Catalog, Error as CatalogError};
 use observability_deps::tracing::*;
 use thiserror::Error;
 
@@ -18,6 +18,10 @@ pub use ns_autocreation::*;
 /// Error states encountered during [`NamespaceId`] lookup.
 #[derive(Debug, Error)]
 pub enum Error {
+    /// The requested namespace does not exist.
+    #[error("namespace {0} does not exist")]
+    NotFound(String),
+
     /// An error occured when attempting to fetch the namespace ID.
     #[error("failed to resolve namespace ID: {0}")]
     Lookup(iox_catalog::interface::Error),
@@ -75,6 +79,9 @@ where
                 let schema = get_schema_by_name(namespace, repos.deref_mut())
                     .await
                     .map_err(|e| {
+                        if let CatalogError::NamespaceNotFoundByName { .. } = e {
+                            return Error::NotFound(namespace.to_string());
+                        }
                         warn!(
                             error=%e,
                             %namespace,
@@ -123,6 +130,12 @@ mod tests {
                 tables: Default::default(),
                 max_columns_per_table: 4,
                 retention_period_ns: None,
+                default_query_range_ns: None,
+                max_query_range_ns: None,
+                row_ttl_ns: None,
+                row_ttl_column: None,
+                partition_template: None,
+                generation: 0,
             },
         );
 
@@ -199,7 +212,7 @@ mod tests {
             .await
             .expect_err("lookup should error");
 
-        assert_matches!(err, Error::Lookup(_));
+        assert_matches!(err, Error::NotFound(_));
         assert!(cache.get_schema(&ns).is_none());
     }
 }
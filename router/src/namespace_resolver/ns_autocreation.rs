@@ -1,7 +1,7 @@
 use std::{fmt::Debug, sync::Arc};
 
 use async_trait::async_trait;
-use data_types::{NamespaceId, NamespaceName, QueryPoolId, TopicId};
+use data_types::{NamespaceId, NamespaceName, PartitionTemplate, QueryPoolId, TopicId};
 use iox_catalog::interface::Catalog;
 use observability_deps::tracing::*;
 use thiserror::Error;
@@ -17,8 +17,37 @@ pub enum NamespaceCreationError {
     Create(iox_catalog::interface::Error),
 }
 
+/// The settings applied to namespaces created by [`NamespaceAutocreation`].
+///
+/// Unset values use the catalog defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamespaceTemplate {
+    /// The retention period of the namespace, or [`None`] for infinite
+    /// retention.
+    pub retention_period_ns: Option<i64>,
+    /// The maximum number of tables in the namespace.
+    pub max_tables: Option<i32>,
+    /// The maximum number of columns per table in the namespace.
+    pub max_columns_per_table: Option<i32>,
+    /// The template the writes to the namespace are partitioned by, or
+    /// [`None`] to partition them by the template the router is configured
+    /// with.
+    pub partition_template: Option<PartitionTemplate>,
+}
+
+/// What [`NamespaceAutocreation`] does when a request references a namespace
+/// that does not exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MissingNamespaceAction {
+    /// Do not create the namespace - the request fails with a "not found"
+    /// error.
+    Reject,
+    /// Create the namespace with the settings in the [`NamespaceTemplate`].
+    AutoCreate(NamespaceTemplate),
+}
+
 /// A layer to populate the [`Catalog`] with all the namespaces the router
-/// observes.
+/// observes, according to the configured [`MissingNamespaceAction`].
 ///
 /// Uses a [`NamespaceCache`] to limit issuing create requests to namespaces the
 /// router has not yet observed a schema for.
@@ -30,15 +59,15 @@ pub struct NamespaceAutocreation<C, T> {
 
     topic_id: TopicId,
     query_id: QueryPoolId,
-    retention_period_ns: Option<i64>,
+    action: MissingNamespaceAction,
 }
 
 impl<C, T> NamespaceAutocreation<C, T> {
-    /// Return a new [`NamespaceAutocreation`] layer that ensures a requested
-    /// namespace exists in `catalog`.
+    /// Return a new [`NamespaceAutocreation`] layer that applies `action` to
+    /// requested namespaces that do not exist in `catalog`.
     ///
-    /// If the namespace does not exist, it is created with the specified
-    /// `topic_id`, `query_id` and `retention` policy.
+    /// Namespaces created by [`MissingNamespaceAction::AutoCreate`] use the
+    /// specified `topic_id` and `query_id`.
     ///
     /// Namespaces are looked up in `cache`, skipping the creation request to
     /// the catalog if there's a hit.
@@ -48,7 +77,7 @@ impl<C, T> NamespaceAutocreation<C, T> {
         catalog: Arc<dyn Catalog>,
         topic_id: TopicId,
         query_id: QueryPoolId,
-        retention_period_ns: Option<i64>,
+        action: MissingNamespaceAction,
     ) -> Self {
         Self {
             inner,
@@ -56,7 +85,55 @@ impl<C, T> NamespaceAutocreation<C, T> {
             catalog,
             topic_id,
             query_id,
-            retention_period_ns,
+            action,
+        }
+    }
+
+    /// Create `namespace` with the settings in `template`, returning
+    /// [`iox_catalog::interface::Error::NameExists`] if it already exists.
+    async fn create(
+        &self,
+        namespace: &NamespaceName<'static>,
+        template: &NamespaceTemplate,
+    ) -> Result<(), iox_catalog::interface::Error> {
+        // The limits and partition template are set in the same transaction,
+        // so the namespace is never visible without them.
+        let mut txn = self.catalog.start_transaction().await?;
+
+        let res = async {
+            txn.namespaces()
+                .create(
+                    namespace.as_str(),
+                    template.retention_period_ns,
+                    self.topic_id,
+                    self.query_id,
+                )
+                .await?;
+            if let Some(max) = template.max_tables {
+                txn.namespaces()
+                    .update_table_limit(namespace.as_str(), max)
+                    .await?;
+            }
+            if let Some(max) = template.max_columns_per_table {
+                txn.namespaces()
+                    .update_column_limit(namespace.as_str(), max)
+                    .await?;
+            }
+            if let Some(partition_template) = &template.partition_template {
+                txn.namespaces()
+                    .update_partition_template(namespace.as_str(), Some(partition_template))
+                    .await?;
+            }
+            Ok::<_, iox_catalog::interface::Error>(())
+        }
+        .await;
+
+        match res {
+            Ok(()) => txn.commit().await,
+            Err(e) => {
+                txn.abort().await?;
+                Err(e)
+            }
         }
     }
 }
@@ -68,26 +145,23 @@ where
     T: NamespaceResolver,
 {
     /// Force the creation of `namespace` if it does not already exist in the
-    /// cache, before passing the request through to the inner delegate.
+    /// cache and auto-creation is enabled, before passing the request through
+    /// to the inner delegate.
     async fn get_namespace_id(
         &self,
         namespace: &NamespaceName<'static>,
     ) -> Result<NamespaceId, super::Error> {
+        let template = match &self.action {
+            MissingNamespaceAction::AutoCreate(v) => v,
+            // The inner resolver returns an error if the namespace does not
+            // exist.
+            MissingNamespaceAction::Reject => return self.inner.get_namespace_id(namespace).await,
+        };
+
         if self.cache.get_schema(namespace).is_none() {
             trace!(%namespace, "namespace auto-create cache miss");
 
-            let mut repos = self.catalog.repositories().await;
-
-            match repos
-                .namespaces()
-                .create(
-                    namespace.as_str(),
-                    self.retention_period_ns,
-                    self.topic_id,
-                    self.query_id,
-                )
-                .await
-            {
+            match self.create(namespace, template).await {
                 Ok(_) => {
                     debug!(%namespace, "created namespace");
                 }
@@ -112,12 +186,14 @@ where
 mod tests {
     use std::sync::Arc;
 
-    use data_types::{Namespace, NamespaceId, NamespaceSchema};
+    use assert_matches::assert_matches;
+    use data_types::{Namespace, NamespaceId, NamespaceSchema, TemplatePart};
     use iox_catalog::mem::MemCatalog;
 
     use super::*;
     use crate::{
-        namespace_cache::MemoryNamespaceCache, namespace_resolver::mock::MockNamespaceResolver,
+        namespace_cache::MemoryNamespaceCache,
+        namespace_resolver::{mock::MockNamespaceResolver, NamespaceSchemaResolver},
     };
 
     /// Common retention period value we'll use in tests
@@ -140,6 +216,12 @@ mod tests {
                 tables: Default::default(),
                 max_columns_per_table: 4,
                 retention_period_ns: None,
+                default_query_range_ns: None,
+                max_query_range_ns: None,
+                row_ttl_ns: None,
+                row_ttl_column: None,
+                partition_template: None,
+                generation: 0,
             },
         );
 
@@ -152,7 +234,10 @@ mod tests {
             Arc::clone(&catalog),
             TopicId::new(42),
             QueryPoolId::new(42),
-            TEST_RETENTION_PERIOD_NS,
+            MissingNamespaceAction::AutoCreate(NamespaceTemplate {
+                retention_period_ns: TEST_RETENTION_PERIOD_NS,
+                ..Default::default()
+            }),
         );
 
         // Drive the code under test
@@ -190,7 +275,10 @@ mod tests {
             Arc::clone(&catalog),
             TopicId::new(42),
             QueryPoolId::new(42),
-            TEST_RETENTION_PERIOD_NS,
+            MissingNamespaceAction::AutoCreate(NamespaceTemplate {
+                retention_period_ns: TEST_RETENTION_PERIOD_NS,
+                ..Default::default()
+            }),
         );
 
         let created_id = creator
@@ -219,7 +307,89 @@ mod tests {
                 max_tables: iox_catalog::DEFAULT_MAX_TABLES,
                 max_columns_per_table: iox_catalog::DEFAULT_MAX_COLUMNS_PER_TABLE,
                 retention_period_ns: TEST_RETENTION_PERIOD_NS,
+                schema_generation: 0,
+                default_query_range_ns: None,
+                max_query_range_ns: None,
+                row_ttl_ns: None,
+                row_ttl_column: None,
+                partition_template: None,
             }
         );
     }
+
+    #[tokio::test]
+    async fn test_cache_miss_template() {
+        let ns = NamespaceName::try_from("bananas").unwrap();
+
+        let cache = Arc::new(MemoryNamespaceCache::default());
+        let metrics = Arc::new(metric::Registry::new());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+
+        let creator = NamespaceAutocreation::new(
+            MockNamespaceResolver::default().with_mapping(ns.clone(), NamespaceId::new(1)),
+            cache,
+            Arc::clone(&catalog),
+            TopicId::new(42),
+            QueryPoolId::new(42),
+            MissingNamespaceAction::AutoCreate(NamespaceTemplate {
+                retention_period_ns: TEST_RETENTION_PERIOD_NS,
+                max_tables: Some(10),
+                max_columns_per_table: Some(20),
+                partition_template: Some(PartitionTemplate {
+                    parts: vec![TemplatePart::TimeFormat("%Y".to_string())],
+                }),
+            }),
+        );
+
+        creator
+            .get_namespace_id(&ns)
+            .await
+            .expect("handler should succeed");
+
+        let mut repos = catalog.repositories().await;
+        let got = repos
+            .namespaces()
+            .get_by_name(ns.as_str())
+            .await
+            .expect("lookup should not error")
+            .expect("creation request should be sent to catalog");
+
+        assert_eq!(got.retention_period_ns, TEST_RETENTION_PERIOD_NS);
+        assert_eq!(got.max_tables, 10);
+        assert_eq!(got.max_columns_per_table, 20);
+        assert_eq!(got.partition_template.as_deref(), Some("time(%Y)"));
+    }
+
+    #[tokio::test]
+    async fn test_reject() {
+        let ns = NamespaceName::try_from("bananas").unwrap();
+
+        let cache = Arc::new(MemoryNamespaceCache::default());
+        let metrics = Arc::new(metric::Registry::new());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+
+        let creator = NamespaceAutocreation::new(
+            NamespaceSchemaResolver::new(Arc::clone(&catalog), Arc::clone(&cache)),
+            cache,
+            Arc::clone(&catalog),
+            TopicId::new(42),
+            QueryPoolId::new(42),
+            MissingNamespaceAction::Reject,
+        );
+
+        let err = creator
+            .get_namespace_id(&ns)
+            .await
+            .expect_err("unknown namespace should be rejected");
+        assert_matches!(err, crate::namespace_resolver::Error::NotFound(_));
+
+        // The namespace MUST NOT have been created.
+        let mut repos = catalog.repositories().await;
+        assert!(repos
+            .namespaces()
+            .get_by_name(ns.as_str())
+            .await
+            .expect("lookup should not error")
+            .is_none());
+    }
 }
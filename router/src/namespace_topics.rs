@@ -0,0 +1,162 @@
+//! A periodically refreshed mapping of namespaces to the write buffer topic
+//! their writes are routed to.
+
+use std::{sync::Arc, time::Duration};
+
+use data_types::{NamespaceName, TopicId};
+use hashbrown::HashMap;
+use iox_catalog::interface::Catalog;
+use observability_deps::tracing::*;
+use parking_lot::RwLock;
+use tokio::task::JoinHandle;
+
+/// The write buffer topic of each namespace, as assigned in the catalog.
+///
+/// Namespaces that have not been observed (such as those created after the
+/// last refresh) are mapped to the default topic. A namespace assigned to a
+/// topic this router does not write to is still mapped to that topic, so that
+/// its writes can be rejected rather than sent to the wrong topic.
+#[derive(Debug)]
+pub struct NamespaceTopics {
+    default: TopicId,
+
+    /// The topic of each namespace not assigned to the default topic.
+    topics: RwLock<HashMap<String, TopicId>>,
+}
+
+impl NamespaceTopics {
+    /// Initialise an empty [`NamespaceTopics`], mapping all namespaces to
+    /// `default` until refreshed, and then to the topic they are assigned to.
+    pub fn new(default: TopicId) -> Self {
+        Self {
+            default,
+            topics: Default::default(),
+        }
+    }
+
+    /// The topic of namespaces not assigned to any other topic.
+    pub fn default_topic(&self) -> TopicId {
+        self.default
+    }
+
+    /// The topic writes for `namespace` are routed to.
+    pub fn topic_for(&self, namespace: &NamespaceName<'_>) -> TopicId {
+        self.topics
+            .read()
+            .get(namespace.as_str())
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Reload the topic of each namespace from `catalog`.
+    pub async fn refresh(
+        &self,
+        catalog: &dyn Catalog,
+    ) -> Result<(), iox_catalog::interface::Error> {
+        let namespaces = catalog.repositories().await.namespaces().list().await?;
+
+        let mut topics = HashMap::new();
+        for ns in namespaces {
+            if ns.topic_id == self.default {
+                continue;
+            }
+            topics.insert(ns.name, ns.topic_id);
+        }
+
+        debug!(n_routed = topics.len(), "refreshed namespace topics");
+        *self.topics.write() = topics;
+
+        Ok(())
+    }
+}
+
+/// Refreshes a [`NamespaceTopics`] from the catalog in the background, once
+/// every `interval`, until dropped.
+#[derive(Debug)]
+pub struct NamespaceTopicsRefresher {
+    handle: JoinHandle<()>,
+}
+
+impl NamespaceTopicsRefresher {
+    /// Start refreshing `topics` from `catalog`, starting immediately.
+    pub fn new(
+        topics: Arc<NamespaceTopics>,
+        catalog: Arc<dyn Catalog>,
+        interval: Duration,
+    ) -> Self {
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = topics.refresh(&*catalog).await {
+                    warn!(error=%e, "failed to refresh namespace topics");
+                }
+            }
+        });
+
+        Self { handle }
+    }
+}
+
+impl Drop for NamespaceTopicsRefresher {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iox_tests::util::TestCatalog;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_refresh() {
+        let catalog = TestCatalog::new();
+        let bananas = catalog.create_namespace_1hr_retention("bananas").await;
+        let platanos = catalog.create_namespace_1hr_retention("platanos").await;
+        let default = bananas.topic.id;
+
+        let mut repos = catalog.catalog().repositories().await;
+        let noisy = repos.topics().create_or_get("noisy").await.unwrap();
+        let unserved = repos.topics().create_or_get("unserved").await.unwrap();
+        repos
+            .namespaces()
+            .update_topic("bananas", noisy.id)
+            .await
+            .unwrap();
+        repos
+            .namespaces()
+            .update_topic("platanos", unserved.id)
+            .await
+            .unwrap();
+        drop(repos);
+
+        let topics = NamespaceTopics::new(default);
+        let bananas = NamespaceName::try_from(bananas.namespace.name.clone()).unwrap();
+        let platanos = NamespaceName::try_from(platanos.namespace.name.clone()).unwrap();
+
+        // All namespaces map to the default topic until refreshed.
+        assert_eq!(topics.topic_for(&bananas), default);
+
+        topics.refresh(&*catalog.catalog()).await.unwrap();
+        assert_eq!(topics.topic_for(&bananas), noisy.id);
+        assert_eq!(topics.topic_for(&platanos), unserved.id);
+        assert_eq!(
+            topics.topic_for(&NamespaceName::try_from("unknown").unwrap()),
+            default
+        );
+
+        // Moving the namespace back is observed by the next refresh.
+        catalog
+            .catalog()
+            .repositories()
+            .await
+            .namespaces()
+            .update_topic("bananas", default)
+            .await
+            .unwrap();
+        topics.refresh(&*catalog.catalog()).await.unwrap();
+        assert_eq!(topics.topic_for(&bananas), default);
+    }
+}
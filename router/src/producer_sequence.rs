@@ -0,0 +1,206 @@
+//! Deduplication of retried writes by the sequence numbers assigned by their
+//! producers.
+//!
+//! A producer, such as an edge gateway, that may retry a write it already sent
+//! tags each of its writes with its producer ID and an increasing sequence
+//! number. Before a write is applied, its sequence number is reserved in the
+//! catalog by a compare-and-swap of the highest sequence number of the producer
+//! in the namespace. A write with a sequence number no higher than the reserved
+//! one is skipped instead of being applied again, whichever router it is sent
+//! to and across restarts of the routers.
+//!
+//! If applying the write fails, the reservation is released so that a retry
+//! is applied. Deduplication is therefore best-effort in two cases:
+//!
+//! * A retry arriving while the write it repeats is still being applied is
+//!   skipped, and lost if that write then fails.
+//! * A write that fails after being applied to some shards, or whose router
+//!   stops before releasing its reservation, is applied again, respectively
+//!   lost, on retry.
+
+use std::sync::Arc;
+
+use data_types::NamespaceId;
+use iox_catalog::interface::{Catalog, Error};
+use observability_deps::tracing::*;
+
+/// Reserves the sequence numbers of the writes of producers in the catalog,
+/// see the [module documentation](self).
+#[derive(Debug)]
+pub struct ProducerSequences {
+    catalog: Arc<dyn Catalog>,
+}
+
+impl ProducerSequences {
+    /// Reserve the sequence numbers of writes in `catalog`.
+    pub fn new(catalog: Arc<dyn Catalog>) -> Self {
+        Self { catalog }
+    }
+
+    /// Reserve `sequence_number` for a write of `producer_id` to
+    /// `namespace_id`, or return `None` if a write with the same or a higher
+    /// sequence number was already reserved, i.e. the write is a duplicate.
+    pub async fn reserve(
+        &self,
+        namespace_id: NamespaceId,
+        producer_id: &str,
+        sequence_number: i64,
+    ) -> Result<Option<ProducerSequenceReservation>, Error> {
+        let mut repos = self.catalog.repositories().await;
+        loop {
+            let previous = repos
+                .namespaces()
+                .get_producer_sequence(namespace_id, producer_id)
+                .await?;
+            if matches!(previous, Some(previous) if previous >= sequence_number) {
+                return Ok(None);
+            }
+
+            // Fails if another write of the producer was reserved concurrently.
+            let reserved = repos
+                .namespaces()
+                .compare_and_swap_producer_sequence(
+                    namespace_id,
+                    producer_id,
+                    previous,
+                    Some(sequence_number),
+                )
+                .await?;
+            if reserved {
+                return Ok(Some(ProducerSequenceReservation {
+                    catalog: Arc::clone(&self.catalog),
+                    namespace_id,
+                    producer_id: producer_id.to_string(),
+                    sequence_number,
+                    previous,
+                }));
+            }
+        }
+    }
+}
+
+/// The reservation of the sequence number of a write, returned by
+/// [`ProducerSequences::reserve()`].
+///
+/// The reservation is kept once the write is applied, and must be
+/// [released](Self::release) if applying it fails.
+#[derive(Debug)]
+#[must_use = "a reservation must be released if the write fails"]
+pub struct ProducerSequenceReservation {
+    catalog: Arc<dyn Catalog>,
+    namespace_id: NamespaceId,
+    producer_id: String,
+    sequence_number: i64,
+
+    /// The sequence number reserved before this one.
+    previous: Option<i64>,
+}
+
+impl ProducerSequenceReservation {
+    /// Release the reservation of a write that failed, so that its retry is
+    /// applied.
+    ///
+    /// Nothing is released if a later write of the producer was reserved in
+    /// the meantime. Failing to release the reservation is logged, not
+    /// returned, as the error of the write is returned instead.
+    pub async fn release(self) {
+        let res = self
+            .catalog
+            .repositories()
+            .await
+            .namespaces()
+            .compare_and_swap_producer_sequence(
+                self.namespace_id,
+                &self.producer_id,
+                Some(self.sequence_number),
+                self.previous,
+            )
+            .await;
+        match res {
+            Ok(true) => {}
+            Ok(false) => debug!(
+                namespace_id=%self.namespace_id,
+                producer_id=%self.producer_id,
+                sequence_number=self.sequence_number,
+                "not releasing producer sequence number superseded by a later write"
+            ),
+            Err(e) => warn!(
+                error=%e,
+                namespace_id=%self.namespace_id,
+                producer_id=%self.producer_id,
+                sequence_number=self.sequence_number,
+                "failed to release producer sequence number"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iox_catalog::mem::MemCatalog;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_producer_sequences() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+        let namespace_id = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("foo").await.unwrap();
+            let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+            repos
+                .namespaces()
+                .create("bananas", None, topic.id, pool.id)
+                .await
+                .unwrap()
+                .id
+        };
+
+        let sequences = ProducerSequences::new(Arc::clone(&catalog));
+
+        // A reserved write is a duplicate, for any router sharing the catalog.
+        let reservation = sequences.reserve(namespace_id, "gateway", 1).await.unwrap();
+        assert!(reservation.is_some());
+        let other_router = ProducerSequences::new(Arc::clone(&catalog));
+        for sequences in [&sequences, &other_router] {
+            assert!(sequences
+                .reserve(namespace_id, "gateway", 1)
+                .await
+                .unwrap()
+                .is_none());
+        }
+
+        // Other producers are independent.
+        assert!(sequences
+            .reserve(namespace_id, "other", 1)
+            .await
+            .unwrap()
+            .is_some());
+
+        // A released write is applied on retry.
+        let reservation = sequences.reserve(namespace_id, "gateway", 2).await.unwrap();
+        reservation.unwrap().release().await;
+        let reservation = sequences.reserve(namespace_id, "gateway", 2).await.unwrap();
+        assert!(reservation.is_some());
+
+        // A release does not undo the reservation of a later write.
+        let reservation = sequences.reserve(namespace_id, "gateway", 3).await.unwrap();
+        assert!(sequences
+            .reserve(namespace_id, "gateway", 4)
+            .await
+            .unwrap()
+            .is_some());
+        reservation.unwrap().release().await;
+        assert_eq!(
+            catalog
+                .repositories()
+                .await
+                .namespaces()
+                .get_producer_sequence(namespace_id, "gateway")
+                .await
+                .unwrap(),
+            Some(4)
+        );
+    }
+}
@@ -5,16 +5,27 @@ pub mod sharder;
 use std::sync::Arc;
 
 use ::sharder::Sharder;
-use data_types::{QueryPoolId, TopicId};
+use data_types::{NamespaceNameRules, QueryPoolId, TopicId};
 use generated_types::influxdata::iox::{
-    catalog::v1::*, namespace::v1::*, object_store::v1::*, schema::v1::*, sharder::v1::*,
+    authz::v1::*, catalog::v1::*, namespace::v1::*, object_store::v1::*, schema::v1::*,
+    sharder::v1::*, usage::v1::*,
 };
-use iox_catalog::interface::Catalog;
+use iox_catalog::{authz::Authorizer, interface::Catalog};
 use object_store::DynObjectStore;
+use service_grpc_authz::{
+    rpc::{
+        AuthorizedService, RpcPermissions, CATALOG_SERVICE_PERMISSIONS,
+        NAMESPACE_SERVICE_PERMISSIONS, OBJECT_STORE_SERVICE_PERMISSIONS,
+        SCHEMA_SERVICE_PERMISSIONS, SHARD_SERVICE_PERMISSIONS, USAGE_SERVICE_PERMISSIONS,
+    },
+    AuthzService,
+};
 use service_grpc_catalog::CatalogService;
 use service_grpc_namespace::NamespaceService;
 use service_grpc_object_store::ObjectStoreService;
 use service_grpc_schema::SchemaService;
+use service_grpc_usage::UsageService;
+use tonic::transport::NamedService;
 
 use self::sharder::ShardService;
 use crate::shard::Shard;
@@ -27,6 +38,8 @@ pub struct GrpcDelegate<S> {
     catalog: Arc<dyn Catalog>,
     object_store: Arc<DynObjectStore>,
     shard_service: ShardService<S>,
+    namespace_name_rules: NamespaceNameRules,
+    authorizer: Option<Arc<Authorizer>>,
 }
 
 impl<S> GrpcDelegate<S> {
@@ -44,8 +57,24 @@ impl<S> GrpcDelegate<S> {
             catalog,
             object_store,
             shard_service,
+            namespace_name_rules: Default::default(),
+            authorizer: None,
         }
     }
+
+    /// Reject the creation of namespaces that violate `rules` through the
+    /// [`NamespaceService`].
+    pub fn with_namespace_name_rules(mut self, rules: NamespaceNameRules) -> Self {
+        self.namespace_name_rules = rules;
+        self
+    }
+
+    /// Require an API token for the requests to the gRPC services, allowing the permission of
+    /// each RPC (an admin token for the requests to the [`AuthzService`]).
+    pub fn with_authorizer(mut self, authorizer: Arc<Authorizer>) -> Self {
+        self.authorizer = Some(authorizer);
+        self
+    }
 }
 
 impl<S> GrpcDelegate<S>
@@ -55,10 +84,15 @@ where
     /// Acquire a [`SchemaService`] gRPC service implementation.
     ///
     /// [`SchemaService`]: generated_types::influxdata::iox::schema::v1::schema_service_server::SchemaService.
-    pub fn schema_service(&self) -> schema_service_server::SchemaServiceServer<SchemaService> {
-        schema_service_server::SchemaServiceServer::new(SchemaService::new(Arc::clone(
-            &self.catalog,
-        )))
+    pub fn schema_service(
+        &self,
+    ) -> AuthorizedService<schema_service_server::SchemaServiceServer<SchemaService>> {
+        self.authorized(
+            schema_service_server::SchemaServiceServer::new(SchemaService::new(Arc::clone(
+                &self.catalog,
+            ))),
+            SCHEMA_SERVICE_PERMISSIONS,
+        )
     }
 
     /// Acquire a [`CatalogService`] gRPC service implementation.
@@ -66,11 +100,15 @@ where
     /// [`CatalogService`]: generated_types::influxdata::iox::catalog::v1::catalog_service_server::CatalogService.
     pub fn catalog_service(
         &self,
-    ) -> catalog_service_server::CatalogServiceServer<impl catalog_service_server::CatalogService>
-    {
-        catalog_service_server::CatalogServiceServer::new(CatalogService::new(Arc::clone(
-            &self.catalog,
-        )))
+    ) -> AuthorizedService<
+        catalog_service_server::CatalogServiceServer<impl catalog_service_server::CatalogService>,
+    > {
+        self.authorized(
+            catalog_service_server::CatalogServiceServer::new(CatalogService::new(Arc::clone(
+                &self.catalog,
+            ))),
+            CATALOG_SERVICE_PERMISSIONS,
+        )
     }
 
     /// Acquire a [`ObjectStoreService`] gRPC service implementation.
@@ -78,13 +116,18 @@ where
     /// [`ObjectStoreService`]: generated_types::influxdata::iox::object_store::v1::object_store_service_server::ObjectStoreService.
     pub fn object_store_service(
         &self,
-    ) -> object_store_service_server::ObjectStoreServiceServer<
-        impl object_store_service_server::ObjectStoreService,
+    ) -> AuthorizedService<
+        object_store_service_server::ObjectStoreServiceServer<
+            impl object_store_service_server::ObjectStoreService,
+        >,
     > {
-        object_store_service_server::ObjectStoreServiceServer::new(ObjectStoreService::new(
-            Arc::clone(&self.catalog),
-            Arc::clone(&self.object_store),
-        ))
+        self.authorized(
+            object_store_service_server::ObjectStoreServiceServer::new(ObjectStoreService::new(
+                Arc::clone(&self.catalog),
+                Arc::clone(&self.object_store),
+            )),
+            OBJECT_STORE_SERVICE_PERMISSIONS,
+        )
     }
 
     /// Return a gRPC [`ShardService`] handler.
@@ -92,8 +135,13 @@ where
     /// [`ShardService`]: generated_types::influxdata::iox::sharder::v1::shard_service_server::ShardService
     pub fn shard_service(
         &self,
-    ) -> shard_service_server::ShardServiceServer<impl shard_service_server::ShardService> {
-        shard_service_server::ShardServiceServer::new(self.shard_service.clone())
+    ) -> AuthorizedService<
+        shard_service_server::ShardServiceServer<impl shard_service_server::ShardService>,
+    > {
+        self.authorized(
+            shard_service_server::ShardServiceServer::new(self.shard_service.clone()),
+            SHARD_SERVICE_PERMISSIONS,
+        )
     }
 
     /// Acquire a [`NamespaceService`] gRPC service implementation.
@@ -101,11 +149,50 @@ where
     /// [`NamespaceService`]: generated_types::influxdata::iox::namespace::v1::namespace_service_server::NamespaceService.
     pub fn namespace_service(
         &self,
-    ) -> namespace_service_server::NamespaceServiceServer<NamespaceService> {
-        namespace_service_server::NamespaceServiceServer::new(NamespaceService::new(
+    ) -> AuthorizedService<namespace_service_server::NamespaceServiceServer<NamespaceService>> {
+        self.authorized(
+            namespace_service_server::NamespaceServiceServer::new(
+                NamespaceService::new(
+                    Arc::clone(&self.catalog),
+                    Some(self.topic_id),
+                    Some(self.query_pool_id),
+                )
+                .with_name_rules(self.namespace_name_rules.clone()),
+            ),
+            NAMESPACE_SERVICE_PERMISSIONS,
+        )
+    }
+
+    /// Acquire a [`UsageService`] gRPC service implementation.
+    ///
+    /// [`UsageService`]: generated_types::influxdata::iox::usage::v1::usage_service_server::UsageService.
+    pub fn usage_service(
+        &self,
+    ) -> AuthorizedService<usage_service_server::UsageServiceServer<UsageService>> {
+        self.authorized(
+            usage_service_server::UsageServiceServer::new(UsageService::new(Arc::clone(
+                &self.catalog,
+            ))),
+            USAGE_SERVICE_PERMISSIONS,
+        )
+    }
+
+    /// Acquire an [`AuthzService`] gRPC service implementation.
+    ///
+    /// [`AuthzService`]: generated_types::influxdata::iox::authz::v1::authz_service_server::AuthzService.
+    pub fn authz_service(&self) -> authz_service_server::AuthzServiceServer<AuthzService> {
+        authz_service_server::AuthzServiceServer::new(AuthzService::new(
             Arc::clone(&self.catalog),
-            Some(self.topic_id),
-            Some(self.query_pool_id),
+            self.authorizer.clone(),
         ))
     }
+
+    /// Wrap `service`, requiring the API token of each RPC to allow the permission of its method
+    /// in `permissions` if authorization is enabled.
+    fn authorized<T>(&self, service: T, permissions: RpcPermissions) -> AuthorizedService<T>
+    where
+        T: NamedService,
+    {
+        AuthorizedService::new(service, self.authorizer.clone(), permissions)
+    }
 }
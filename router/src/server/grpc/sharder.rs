@@ -1,17 +1,23 @@
 //! A gRPC service to provide shard mappings to external clients.
 
-use std::sync::Arc;
+use std::{collections::BTreeSet, sync::Arc};
 
-use data_types::{NamespaceName, ShardId, ShardIndex, TopicMetadata};
-use generated_types::influxdata::iox::sharder::v1::{
-    shard_service_server, MapToShardRequest, MapToShardResponse,
+use data_types::{NamespaceName, SequenceNumber, ShardId, ShardIndex, TopicId, TopicMetadata};
+use generated_types::{
+    google::{NotFound, ResourceType},
+    influxdata::iox::sharder::v1::{
+        shard_service_server, GetShardProgressRequest, GetShardProgressResponse, MapToShardRequest,
+        MapToShardResponse, ShardProgress,
+    },
 };
 use hashbrown::HashMap;
 use iox_catalog::interface::Catalog;
+use observability_deps::tracing::*;
 use sharder::Sharder;
 use tonic::{Request, Response};
+use write_buffer::core::WriteBufferReading;
 
-use crate::shard::Shard;
+use crate::{namespace_topics::NamespaceTopics, shard::Shard};
 
 /// A [`ShardService`] exposes a [gRPC endpoint] for external systems to discover the shard mapping
 /// for specific tables.
@@ -21,31 +27,108 @@ use crate::shard::Shard;
 /// queries. This mapping is expected to be unchanged over the lifetime of a router instance.
 ///
 /// This service MUST be initialised with the same sharder instance as the
-/// [`ShardedWriteBuffer`] for the outputs to be correct.
+/// [`ShardedWriteBuffer`] for the outputs to be correct. When writing to more
+/// than one topic, the sharder of each topic is added with
+/// [`ShardService::with_topic`], and tables are mapped to the shards of the
+/// topic of their namespace.
+///
+/// The service also reports the write progress of each shard, combining the
+/// high watermark of the write buffer with the persisted sequence number the
+/// ingesters record in the [`Catalog`]. Clients can compare these against the
+/// sequence numbers in a write token to check if a write has been persisted.
 ///
 /// [gRPC endpoint]: generated_types::influxdata::iox::sharder::v1::shard_service_server::ShardService
 /// [`ShardedWriteBuffer`]: crate::dml_handlers::ShardedWriteBuffer
 #[derive(Debug, Clone)]
 pub struct ShardService<S> {
+    /// The topic of namespaces without an entry in `namespace_topics`.
+    default_topic: TopicId,
+    topics: HashMap<TopicId, TopicShards<S>>,
+    namespace_topics: Option<Arc<NamespaceTopics>>,
+
+    catalog: Arc<dyn Catalog>,
+}
+
+/// The sharder and shards of a single write buffer topic.
+#[derive(Debug, Clone)]
+struct TopicShards<S> {
     sharder: S,
 
-    // A pre-loaded mapping of all Kafka partition (shard) indexes for the in-use Kafka
-    // topic, to their respective catalog row shard ID.
+    // A pre-loaded mapping of all Kafka partition (shard) indexes for the Kafka topic, to their
+    // respective catalog row shard ID.
     mapping: HashMap<ShardIndex, ShardId>,
+
+    topic: TopicMetadata,
+    write_buffer: Arc<dyn WriteBufferReading>,
 }
 
 impl<S> ShardService<S>
 where
     S: Send + Sync,
 {
-    /// Initialise a gRPC [`ShardService`] handler, building a cached mapping
-    /// from the catalog.
+    /// Initialise a gRPC [`ShardService`] handler for the default `topic`,
+    /// building a cached mapping from the catalog.
+    ///
+    /// `write_buffer` must read from the same topic as `topic`.
     ///
     /// [`ShardService`]: generated_types::influxdata::iox::sharder::v1::shard_service_server::ShardService
     pub async fn new(
         sharder: S,
         topic: TopicMetadata,
         catalog: Arc<dyn Catalog>,
+        write_buffer: Arc<dyn WriteBufferReading>,
+    ) -> Result<Self, iox_catalog::interface::Error> {
+        let default_topic = topic.id;
+        let shards = TopicShards::new(sharder, topic, &*catalog, write_buffer).await?;
+
+        Ok(Self {
+            default_topic,
+            topics: HashMap::from([(default_topic, shards)]),
+            namespace_topics: None,
+            catalog,
+        })
+    }
+
+    /// Map the tables of namespaces assigned to `topic` to the shards of
+    /// `sharder`, building a cached mapping from the catalog.
+    ///
+    /// `write_buffer` must read from the same topic as `topic`.
+    pub async fn with_topic(
+        mut self,
+        sharder: S,
+        topic: TopicMetadata,
+        write_buffer: Arc<dyn WriteBufferReading>,
+    ) -> Result<Self, iox_catalog::interface::Error> {
+        let shards = TopicShards::new(sharder, topic, &*self.catalog, write_buffer).await?;
+        self.topics.insert(shards.topic.id, shards);
+        Ok(self)
+    }
+
+    /// Map the tables of each namespace to the shards of the topic `topics`
+    /// maps it to, rather than to the shards of the default topic.
+    pub fn with_namespace_topics(self, topics: Arc<NamespaceTopics>) -> Self {
+        Self {
+            namespace_topics: Some(topics),
+            ..self
+        }
+    }
+
+    /// The shards of the topic named `name`, or of the default topic if
+    /// `name` is empty.
+    fn topic_by_name(&self, name: &str) -> Option<&TopicShards<S>> {
+        if name.is_empty() {
+            return self.topics.get(&self.default_topic);
+        }
+        self.topics.values().find(|t| t.topic.name == name)
+    }
+}
+
+impl<S> TopicShards<S> {
+    async fn new(
+        sharder: S,
+        topic: TopicMetadata,
+        catalog: &dyn Catalog,
+        write_buffer: Arc<dyn WriteBufferReading>,
     ) -> Result<Self, iox_catalog::interface::Error> {
         // Build the mapping of Kafka partition (shard) index -> Catalog shard ID
         let mapping = catalog
@@ -58,7 +141,12 @@ where
             .map(|s| (s.shard_index, s.id))
             .collect();
 
-        Ok(Self { sharder, mapping })
+        Ok(Self {
+            sharder,
+            mapping,
+            topic,
+            write_buffer,
+        })
     }
 }
 
@@ -77,12 +165,25 @@ where
         let ns = NamespaceName::try_from(req.namespace_name)
             .map_err(|e| tonic::Status::invalid_argument(e.to_string()))?;
 
+        // Writes to a namespace assigned to a topic this router does not
+        // write to are rejected, so the namespace has no shard here.
+        let topic_id = self
+            .namespace_topics
+            .as_ref()
+            .map_or(self.default_topic, |t| t.topic_for(&ns));
+        let topic = self.topics.get(&topic_id).ok_or_else(|| {
+            tonic::Status::unavailable(format!(
+                "namespace {} is assigned to topic {}, which is not served by this router",
+                ns, topic_id
+            ))
+        })?;
+
         // Map the (table, namespace) tuple to the Shard for it.
-        let shard = self.sharder.shard(&req.table_name, &ns, &());
+        let shard = topic.sharder.shard(&req.table_name, &ns, &());
 
         // Look up the shard index in the cached mapping, to extract the catalog ID associated with
         // the Shard.
-        let shard_id = self
+        let shard_id = topic
             .mapping
             .get(&shard.shard_index())
             .expect("in-use shard maps to non-existant catalog entry");
@@ -90,21 +191,88 @@ where
         Ok(Response::new(MapToShardResponse {
             shard_id: shard_id.get(),
             shard_index: shard.shard_index().get(),
+            topic: topic.topic.name.clone(),
         }))
     }
+
+    async fn get_shard_progress(
+        &self,
+        request: Request<GetShardProgressRequest>,
+    ) -> Result<Response<GetShardProgressResponse>, tonic::Status> {
+        let req = request.into_inner();
+
+        let topic = self.topic_by_name(&req.topic).ok_or_else(|| {
+            tonic::Status::not_found(format!("topic {} is not served by this router", req.topic))
+        })?;
+
+        let wanted = req
+            .shard_indexes
+            .into_iter()
+            .map(ShardIndex::new)
+            .collect::<BTreeSet<_>>();
+        if let Some(unknown) = wanted.iter().find(|v| !topic.mapping.contains_key(v)) {
+            return Err(NotFound::new(ResourceType::Shard, unknown.get().to_string()).into());
+        }
+
+        // The persisted progress is read from the catalog rather than the
+        // cached mapping, as it is updated by the ingesters.
+        let mut shards = self
+            .catalog
+            .repositories()
+            .await
+            .shards()
+            .list_by_topic(&topic.topic)
+            .await
+            .map_err(|e| {
+                warn!(error=%e, "failed to list shards");
+                tonic::Status::internal(e.to_string())
+            })?;
+        shards.retain(|s| wanted.is_empty() || wanted.contains(&s.shard_index));
+        shards.sort_unstable_by_key(|s| s.shard_index);
+
+        let mut progress = Vec::with_capacity(shards.len());
+        for shard in shards {
+            let watermark = topic
+                .write_buffer
+                .fetch_high_watermark(shard.shard_index)
+                .await
+                .map_err(|e| {
+                    warn!(error=%e, shard_index=%shard.shard_index, "failed to fetch high watermark");
+                    tonic::Status::unavailable(e.to_string())
+                })?;
+
+            progress.push(ShardProgress {
+                shard_id: shard.id.get(),
+                shard_index: shard.shard_index.get(),
+                // The watermark is the next sequence number to be written.
+                max_durable_sequence_number: previous(watermark),
+                // All sequence numbers below the minimum unpersisted sequence
+                // number are persisted.
+                max_persisted_sequence_number: previous(shard.min_unpersisted_sequence_number),
+            });
+        }
+
+        Ok(Response::new(GetShardProgressResponse { shards: progress }))
+    }
+}
+
+/// Return the sequence number before `v`, if any.
+fn previous(v: SequenceNumber) -> Option<i64> {
+    v.get().checked_sub(1).filter(|v| *v >= 0)
 }
 
 #[cfg(test)]
 mod tests {
     use std::{num::NonZeroU32, sync::Arc};
 
+    use data_types::Sequence;
     use futures::stream::{FuturesUnordered, StreamExt};
     use generated_types::influxdata::iox::sharder::v1::shard_service_server::ShardService as _;
     use iox_catalog::mem::MemCatalog;
     use sharder::JumpHash;
     use write_buffer::{
         core::WriteBufferWriting,
-        mock::{MockBufferForWriting, MockBufferSharedState},
+        mock::{MockBufferForReading, MockBufferForWriting, MockBufferSharedState},
     };
 
     use super::*;
@@ -115,7 +283,8 @@ mod tests {
     async fn test_mapping() {
         let metrics = Arc::new(metric::Registry::default());
         let catalog = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
-        let write_buffer: Arc<dyn WriteBufferWriting> = Arc::new(init_write_buffer());
+        let state = init_state();
+        let write_buffer: Arc<dyn WriteBufferWriting> = Arc::new(init_write_buffer(state.clone()));
 
         let topic = catalog
             .repositories()
@@ -153,12 +322,12 @@ mod tests {
                 .map(Arc::new),
         );
 
-        let svc = ShardService::new(sharder, topic, catalog)
+        let svc = ShardService::new(sharder, topic, catalog, init_reader(state))
             .await
             .expect("failed to init service");
 
         // Validate the correct mapping was constructed.
-        assert_eq!(svc.mapping, actual_mapping);
+        assert_eq!(svc.topics[&svc.default_topic].mapping, actual_mapping);
 
         // Validate calling the RPC service returns correct mapping data.
         for i in 0..100 {
@@ -178,16 +347,223 @@ mod tests {
         }
     }
 
-    // Init a mock write buffer with the given number of shards.
-    fn init_write_buffer() -> MockBufferForWriting {
+    #[tokio::test]
+    async fn test_progress() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+        let state = init_state();
+
+        let mut repos = catalog.repositories().await;
+        let topic = repos
+            .topics()
+            .create_or_get("test")
+            .await
+            .expect("topic create");
+        let mut shards = Vec::new();
+        for idx in 0..N_SHARDS {
+            shards.push(
+                repos
+                    .shards()
+                    .create_or_get(&topic, ShardIndex::new(idx))
+                    .await
+                    .expect("failed to create shard"),
+            );
+        }
+
+        // Shard 1 has three writes durable, and the first two persisted.
+        for n in 0..3 {
+            state.push_lp(
+                Sequence::new(ShardIndex::new(1), SequenceNumber::new(n)),
+                "bananas,tag=A val=42i 1",
+            );
+        }
+        repos
+            .shards()
+            .update_min_unpersisted_sequence_number(shards[1].id, SequenceNumber::new(2))
+            .await
+            .expect("failed to update shard");
+        drop(repos);
+
+        let sharder = JumpHash::new(
+            (0..N_SHARDS)
+                .map(|idx| {
+                    Shard::new(
+                        ShardIndex::new(idx),
+                        Arc::new(init_write_buffer(state.clone())),
+                        &metrics,
+                    )
+                })
+                .map(Arc::new),
+        );
+        let svc = ShardService::new(sharder, topic, catalog, init_reader(state))
+            .await
+            .expect("failed to init service");
+
+        // All shards are returned when no shard indexes are specified.
+        let resp = svc
+            .get_shard_progress(Request::new(GetShardProgressRequest {
+                shard_indexes: vec![],
+                topic: String::new(),
+            }))
+            .await
+            .expect("rpc call should succeed")
+            .into_inner();
+        assert_eq!(
+            resp.shards
+                .iter()
+                .map(|s| s.shard_index)
+                .collect::<Vec<_>>(),
+            (0..N_SHARDS).collect::<Vec<_>>()
+        );
+
+        let resp = svc
+            .get_shard_progress(Request::new(GetShardProgressRequest {
+                shard_indexes: vec![1, 0],
+                topic: "test".to_string(),
+            }))
+            .await
+            .expect("rpc call should succeed")
+            .into_inner();
+        assert_eq!(
+            resp.shards,
+            [
+                ShardProgress {
+                    shard_id: shards[0].id.get(),
+                    shard_index: 0,
+                    max_durable_sequence_number: None,
+                    max_persisted_sequence_number: None,
+                },
+                ShardProgress {
+                    shard_id: shards[1].id.get(),
+                    shard_index: 1,
+                    max_durable_sequence_number: Some(2),
+                    max_persisted_sequence_number: Some(1),
+                },
+            ]
+        );
+
+        let err = svc
+            .get_shard_progress(Request::new(GetShardProgressRequest {
+                shard_indexes: vec![N_SHARDS],
+                topic: String::new(),
+            }))
+            .await
+            .expect_err("unknown shard index should fail");
+        assert_eq!(err.code(), tonic::Code::NotFound);
+        let not_found = generated_types::google::decode_not_found(&err)
+            .next()
+            .unwrap();
+        assert_eq!(not_found.resource_type, ResourceType::Shard);
+        assert_eq!(not_found.resource_name, N_SHARDS.to_string());
+
+        let err = svc
+            .get_shard_progress(Request::new(GetShardProgressRequest {
+                shard_indexes: vec![],
+                topic: "unserved".to_string(),
+            }))
+            .await
+            .expect_err("unserved topic should fail");
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_namespace_topics() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+        let state = init_state();
+        let write_buffer: Arc<dyn WriteBufferWriting> = Arc::new(init_write_buffer(state.clone()));
+
+        let mut repos = catalog.repositories().await;
+        let default = repos.topics().create_or_get("default").await.unwrap();
+        let noisy = repos.topics().create_or_get("noisy").await.unwrap();
+        let unserved = repos.topics().create_or_get("unserved").await.unwrap();
+        let query_pool = repos.query_pools().create_or_get("pool").await.unwrap();
+        for (topic, shard_index) in [(&default, 0), (&noisy, 1)] {
+            repos
+                .shards()
+                .create_or_get(topic, ShardIndex::new(shard_index))
+                .await
+                .unwrap();
+        }
+        for (name, topic) in [("bananas", &noisy), ("platanos", &unserved)] {
+            repos
+                .namespaces()
+                .create(name, None, topic.id, query_pool.id)
+                .await
+                .unwrap();
+        }
+        drop(repos);
+
+        let sharder = |shard_index| {
+            JumpHash::new([Arc::new(Shard::new(
+                ShardIndex::new(shard_index),
+                Arc::clone(&write_buffer),
+                &metrics,
+            ))])
+        };
+        let namespace_topics = Arc::new(NamespaceTopics::new(default.id));
+        namespace_topics.refresh(&*catalog).await.unwrap();
+
+        let svc = ShardService::new(
+            sharder(0),
+            default,
+            Arc::clone(&catalog),
+            init_reader(state.clone()),
+        )
+        .await
+        .expect("failed to init service")
+        .with_topic(sharder(1), noisy, init_reader(state))
+        .await
+        .expect("failed to add topic")
+        .with_namespace_topics(namespace_topics);
+
+        let request = |namespace: &str| {
+            Request::new(MapToShardRequest {
+                table_name: "cpu".to_string(),
+                namespace_name: namespace.to_string(),
+            })
+        };
+
+        let resp = svc
+            .map_to_shard(request("bananas"))
+            .await
+            .expect("rpc call should succeed")
+            .into_inner();
+        assert_eq!(resp.shard_index, 1);
+        assert_eq!(resp.topic, "noisy");
+
+        // Namespaces not assigned to another topic map to the default topic.
+        let resp = svc
+            .map_to_shard(request("unknown"))
+            .await
+            .expect("rpc call should succeed")
+            .into_inner();
+        assert_eq!(resp.shard_index, 0);
+        assert_eq!(resp.topic, "default");
+
+        let err = svc
+            .map_to_shard(request("platanos"))
+            .await
+            .expect_err("unserved topic should fail");
+        assert_eq!(err.code(), tonic::Code::Unavailable);
+    }
+
+    // Init the shared state of a mock write buffer with N_SHARDS shards.
+    fn init_state() -> MockBufferSharedState {
+        MockBufferSharedState::empty_with_n_shards(NonZeroU32::new(N_SHARDS as _).unwrap())
+    }
+
+    // Init a mock write buffer writing to `state`.
+    fn init_write_buffer(state: MockBufferSharedState) -> MockBufferForWriting {
         let time = iox_time::MockProvider::new(
             iox_time::Time::from_timestamp_millis(668563200000).unwrap(),
         );
-        MockBufferForWriting::new(
-            MockBufferSharedState::empty_with_n_shards(NonZeroU32::new(N_SHARDS as _).unwrap()),
-            None,
-            Arc::new(time),
-        )
-        .expect("failed to init mock write buffer")
+        MockBufferForWriting::new(state, None, Arc::new(time))
+            .expect("failed to init mock write buffer")
+    }
+
+    // Init a mock write buffer reader for `state`.
+    fn init_reader(state: MockBufferSharedState) -> Arc<dyn WriteBufferReading> {
+        Arc::new(MockBufferForReading::new(state, None).expect("failed to init mock reader"))
     }
 }
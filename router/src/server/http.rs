@@ -3,18 +3,33 @@
 mod delete_predicate;
 
 use bytes::{Bytes, BytesMut};
-use data_types::{org_and_bucket_to_namespace, OrgBucketMappingError};
+use data_types::{
+    org_and_bucket_to_namespace, ApiTokenPermission, NamespaceName, NamespaceNameRuleViolation,
+    NamespaceNameRules, OrgBucketMappingError,
+};
 use futures::StreamExt;
 use hashbrown::HashMap;
-use hyper::{header::CONTENT_ENCODING, Body, Method, Request, Response, StatusCode};
+use hyper::{
+    header::{HeaderValue, AUTHORIZATION, CONTENT_ENCODING, CONTENT_TYPE},
+    Body, Method, Request, Response, StatusCode,
+};
+use iox_catalog::{
+    authz::{token_from_header, Authorizer, AuthzError},
+    usage::UsageAccumulator,
+};
 use iox_time::{SystemProvider, TimeProvider};
 use metric::{DurationHistogram, U64Counter};
 use mutable_batch::MutableBatch;
-use mutable_batch_lp::LinesConverter;
+use mutable_batch_lp::{LinesConverter, PayloadStatistics, StreamingLinesConverter};
 use observability_deps::tracing::*;
 use predicate::delete_predicate::parse_delete_predicate;
 use serde::Deserialize;
-use std::{str::Utf8Error, time::Instant};
+use std::{
+    io::Write,
+    str::Utf8Error,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use thiserror::Error;
 use tokio::sync::{Semaphore, TryAcquireError};
 use trace::ctx::SpanContext;
@@ -22,12 +37,25 @@ use write_summary::WriteSummary;
 
 use self::delete_predicate::parse_http_delete_request;
 use crate::{
-    dml_handlers::{DmlError, DmlHandler, PartitionError, RetentionError, SchemaError},
+    dml_handlers::{
+        DmlError, DmlHandler, DryRunReport, DryRunValidator, PartitionError, RetentionError,
+        SchemaError, ShardPinError, TopicRouterError,
+    },
     namespace_resolver::NamespaceResolver,
+    producer_sequence::ProducerSequences,
 };
 
 const WRITE_TOKEN_HTTP_HEADER: &str = "X-IOx-Write-Token";
 
+/// The response header reporting the number of lines of a write dropped by
+/// the schema conflict policy of the namespace, if any.
+const DROPPED_ROWS_HTTP_HEADER: &str = "X-IOx-Dropped-Rows";
+
+/// The size of the slices of a gzip-compressed request body chunk that are
+/// decompressed at a time, bounding the amount of decompressed data held in
+/// memory before the request size limit is enforced.
+const GZIP_INPUT_SLICE_BYTES: usize = 1024;
+
 /// Errors returned by the `router` HTTP request handler.
 #[derive(Debug, Error)]
 pub enum Error {
@@ -90,6 +118,24 @@ pub enum Error {
     /// simultaneous requests.
     #[error("this service is overloaded, please try again later")]
     RequestLimit,
+
+    /// A dry-run write was requested, but this router is not configured to
+    /// serve them.
+    #[error("dry-run writes are not supported by this service")]
+    DryRunUnsupported,
+
+    /// The API token of the request does not allow it.
+    #[error(transparent)]
+    Unauthorized(#[from] AuthzError),
+
+    /// A write with a producer sequence number was requested, but this router
+    /// is not configured to deduplicate them.
+    #[error("producer sequence numbers are not supported by this service")]
+    ProducerSequenceUnsupported,
+
+    /// The sequence number of a write could not be reserved in the catalog.
+    #[error("failed to reserve producer sequence number: {0}")]
+    ProducerSequence(iox_catalog::interface::Error),
 }
 
 impl Error {
@@ -112,8 +158,169 @@ impl Error {
                 StatusCode::UNSUPPORTED_MEDIA_TYPE
             }
             Error::DmlHandler(err) => StatusCode::from(err),
+            Error::NamespaceResolver(crate::namespace_resolver::Error::NotFound(_)) => {
+                StatusCode::NOT_FOUND
+            }
             Error::NamespaceResolver(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Error::RequestLimit => StatusCode::SERVICE_UNAVAILABLE,
+            Error::DryRunUnsupported => StatusCode::NOT_IMPLEMENTED,
+            Error::Unauthorized(AuthzError::MissingToken | AuthzError::InvalidToken) => {
+                StatusCode::UNAUTHORIZED
+            }
+            Error::Unauthorized(AuthzError::PermissionDenied { .. }) => StatusCode::FORBIDDEN,
+            Error::Unauthorized(AuthzError::Catalog(_)) => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::ProducerSequenceUnsupported => StatusCode::NOT_IMPLEMENTED,
+            Error::ProducerSequence(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// The stable, machine-readable [`ErrorCode`] of the error, returned to
+    /// the end user alongside the human-readable message.
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            Error::NoHandler => ErrorCode::NotFound,
+            Error::InvalidOrgBucket(_)
+            | Error::NonUtf8Body(_)
+            | Error::NonUtf8ContentHeader(_)
+            | Error::InvalidContentEncoding(_)
+            | Error::ClientHangup(_)
+            | Error::InvalidGzip(_) => ErrorCode::InvalidRequest,
+            Error::RequestSizeExceeded(_) => ErrorCode::RequestTooLarge,
+            Error::ParseLineProtocol(_) => ErrorCode::LineProtocolParse,
+            Error::ParseDelete(_) | Error::ParseHttpDelete(_) => ErrorCode::DeletePredicateParse,
+            Error::DmlHandler(err) => ErrorCode::from(err),
+            Error::NamespaceResolver(crate::namespace_resolver::Error::NotFound(_)) => {
+                ErrorCode::NamespaceNotFound
+            }
+            Error::NamespaceResolver(_) => ErrorCode::Internal,
+            Error::RequestLimit => ErrorCode::Unavailable,
+            Error::DryRunUnsupported => ErrorCode::NotImplemented,
+            Error::Unauthorized(AuthzError::MissingToken | AuthzError::InvalidToken) => {
+                ErrorCode::Unauthenticated
+            }
+            Error::Unauthorized(AuthzError::PermissionDenied { .. }) => ErrorCode::PermissionDenied,
+            Error::Unauthorized(AuthzError::Catalog(_)) => ErrorCode::Internal,
+            Error::ProducerSequenceUnsupported => ErrorCode::NotImplemented,
+            Error::ProducerSequence(_) => ErrorCode::Internal,
+        }
+    }
+
+    /// Machine-readable details of the failure, such as the line that failed
+    /// to parse or the table and column with a conflicting schema.
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            Error::ParseLineProtocol(
+                mutable_batch_lp::Error::LineProtocol { line, .. }
+                | mutable_batch_lp::Error::Write { line, .. },
+            ) => Some(serde_json::json!({ "line": line })),
+            Error::DmlHandler(DmlError::NamespaceNotFound(namespace))
+            | Error::NamespaceResolver(crate::namespace_resolver::Error::NotFound(namespace)) => {
+                Some(serde_json::json!({ "namespace": namespace }))
+            }
+            Error::DmlHandler(DmlError::Schema(SchemaError::Conflict(e))) => match e.err() {
+                iox_catalog::interface::Error::ColumnTypeMismatch {
+                    name,
+                    existing,
+                    new,
+                } => Some(serde_json::json!({
+                    "table": e.table(),
+                    "column": name,
+                    "existing_type": existing.to_string(),
+                    "write_type": new.to_string(),
+                })),
+                _ => Some(serde_json::json!({ "table": e.table() })),
+            },
+            Error::DmlHandler(DmlError::Retention(RetentionError::OutsideRetention(table))) => {
+                Some(serde_json::json!({ "table": table }))
+            }
+            Error::RequestSizeExceeded(max_bytes) => {
+                Some(serde_json::json!({ "max_bytes": max_bytes }))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Stable, machine-readable codes of the errors returned by the `router` HTTP
+/// API.
+///
+/// Unlike the error messages, these codes do not change between releases, so
+/// clients can branch on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// The requested path has no registered handler.
+    NotFound,
+    /// The request is malformed, e.g. it specifies no org/bucket or its body
+    /// cannot be decoded.
+    InvalidRequest,
+    /// The request body exceeds the configured maximum size.
+    RequestTooLarge,
+    /// The line protocol in the request body is invalid.
+    LineProtocolParse,
+    /// The delete predicate in the request body is invalid.
+    DeletePredicateParse,
+    /// The write conflicts with the schema of the namespace, e.g. a column is
+    /// written with a different type than it has.
+    SchemaConflict,
+    /// The write contains data outside of the retention period of the
+    /// namespace.
+    RetentionViolation,
+    /// The namespace of the request does not exist.
+    NamespaceNotFound,
+    /// The write would exceed the table or column limit of the namespace.
+    OverQuota,
+    /// The router is overloaded, the request can be retried later.
+    Unavailable,
+    /// The requested feature is not supported by this router.
+    NotImplemented,
+    /// The request has no valid API token.
+    Unauthenticated,
+    /// The API token of the request does not allow writing to the namespace.
+    PermissionDenied,
+    /// The router failed to process the request.
+    Internal,
+}
+
+impl ErrorCode {
+    /// The text representation of the code returned to the end user.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NotFound => "not_found",
+            Self::InvalidRequest => "invalid_request",
+            Self::RequestTooLarge => "request_too_large",
+            Self::LineProtocolParse => "line_protocol_parse",
+            Self::DeletePredicateParse => "delete_predicate_parse",
+            Self::SchemaConflict => "schema_conflict",
+            Self::RetentionViolation => "retention_violation",
+            Self::NamespaceNotFound => "namespace_not_found",
+            Self::OverQuota => "over_quota",
+            Self::Unavailable => "unavailable",
+            Self::NotImplemented => "not_implemented",
+            Self::Unauthenticated => "unauthenticated",
+            Self::PermissionDenied => "permission_denied",
+            Self::Internal => "internal",
+        }
+    }
+}
+
+impl From<&DmlError> for ErrorCode {
+    fn from(e: &DmlError) -> Self {
+        match e {
+            DmlError::NamespaceNotFound(_) => Self::NamespaceNotFound,
+            DmlError::Schema(SchemaError::ServiceLimit(_)) => Self::OverQuota,
+            DmlError::Schema(SchemaError::Conflict(_)) | DmlError::Transform(_) => {
+                Self::SchemaConflict
+            }
+            DmlError::Schema(
+                SchemaError::NamespaceLookup(_) | SchemaError::UnexpectedCatalogError(_),
+            ) => Self::Internal,
+            DmlError::Retention(RetentionError::OutsideRetention(_)) => Self::RetentionViolation,
+            DmlError::Retention(RetentionError::NamespaceLookup(_))
+            | DmlError::Internal(_)
+            | DmlError::WriteBuffer(_)
+            | DmlError::Partition(PartitionError::BatchWrite(_))
+            | DmlError::ShardPin(ShardPinError::Catalog(_)) => Self::Internal,
+            DmlError::TopicRouting(TopicRouterError::UnservedTopic { .. }) => Self::Unavailable,
         }
     }
 }
@@ -144,6 +351,11 @@ impl From<&DmlError> for StatusCode {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
             DmlError::Retention(RetentionError::OutsideRetention(_)) => StatusCode::FORBIDDEN,
+            DmlError::ShardPin(ShardPinError::Catalog(_)) => StatusCode::INTERNAL_SERVER_ERROR,
+            DmlError::TopicRouting(TopicRouterError::UnservedTopic { .. }) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            DmlError::Transform(_) => StatusCode::BAD_REQUEST,
         }
     }
 }
@@ -163,6 +375,15 @@ pub enum OrgBucketError {
     /// The provided org/bucket could not be converted into a namespace name.
     #[error(transparent)]
     MappingFail(#[from] OrgBucketMappingError),
+
+    /// The namespace name violates the configured [`NamespaceNameRules`].
+    #[error(transparent)]
+    NameRule(#[from] NamespaceNameRuleViolation),
+
+    /// The request specifies only one of a producer ID and a sequence number,
+    /// or an invalid one.
+    #[error("invalid producer sequence: {0}")]
+    InvalidProducerSequence(&'static str),
 }
 
 #[derive(Debug, Deserialize)]
@@ -203,6 +424,32 @@ pub struct WriteInfo {
 
     #[serde(default)]
     precision: Precision,
+
+    /// Validate the write and report its effects without applying it.
+    ///
+    /// Only meaningful for writes.
+    #[serde(default)]
+    dry_run: bool,
+
+    /// The ID of the producer of the write, which assigns increasing
+    /// `sequence_number`s to its writes so that retries of a write already
+    /// applied are skipped.
+    ///
+    /// Only meaningful for writes.
+    producer_id: Option<String>,
+
+    /// The sequence number assigned to the write by its producer.
+    sequence_number: Option<u64>,
+}
+
+impl WriteInfo {
+    /// The producer ID and sequence number of the write, if specified.
+    fn producer_sequence(&self) -> Option<(&str, i64)> {
+        self.producer_id
+            .as_deref()
+            .zip(self.sequence_number)
+            .map(|(producer_id, sequence_number)| (producer_id, sequence_number as i64))
+    }
 }
 
 impl<T> TryFrom<&Request<T>> for WriteInfo {
@@ -217,6 +464,25 @@ impl<T> TryFrom<&Request<T>> for WriteInfo {
             return Err(OrgBucketError::NotSpecified);
         }
 
+        match (&got.producer_id, got.sequence_number) {
+            (Some(_), None) | (None, Some(_)) => {
+                return Err(OrgBucketError::InvalidProducerSequence(
+                    "producer_id and sequence_number must be specified together",
+                ))
+            }
+            (Some(producer_id), _) if producer_id.is_empty() => {
+                return Err(OrgBucketError::InvalidProducerSequence(
+                    "producer_id must not be empty",
+                ))
+            }
+            (_, Some(sequence_number)) if sequence_number > i64::MAX as u64 => {
+                return Err(OrgBucketError::InvalidProducerSequence(
+                    "sequence_number is out of range",
+                ))
+            }
+            _ => {}
+        }
+
         Ok(got)
     }
 }
@@ -233,6 +499,11 @@ pub struct HttpDelegate<D, N, T = SystemProvider> {
     time_provider: T,
     namespace_resolver: N,
     dml_handler: D,
+    dry_run: Option<DryRunValidator>,
+    namespace_name_rules: NamespaceNameRules,
+    usage: Option<Arc<UsageAccumulator>>,
+    authorizer: Option<Arc<Authorizer>>,
+    producer_sequences: Option<ProducerSequences>,
 
     // A request limiter to restrict the number of simultaneous requests this
     // router services.
@@ -313,6 +584,11 @@ impl<D, N> HttpDelegate<D, N, SystemProvider> {
             time_provider: SystemProvider::default(),
             namespace_resolver,
             dml_handler,
+            dry_run: None,
+            namespace_name_rules: Default::default(),
+            usage: None,
+            authorizer: None,
+            producer_sequences: None,
             request_sem: Semaphore::new(max_requests),
             write_metric_lines,
             http_line_protocol_parse_duration,
@@ -325,6 +601,77 @@ impl<D, N> HttpDelegate<D, N, SystemProvider> {
     }
 }
 
+impl<D, N, T> HttpDelegate<D, N, T> {
+    /// Serve `?dry_run=true` write requests with `validator`, which should be
+    /// configured to match the `dml_handler` stack.
+    ///
+    /// Without a [`DryRunValidator`], dry-run writes are rejected.
+    pub fn with_dry_run(mut self, validator: DryRunValidator) -> Self {
+        self.dry_run = Some(validator);
+        self
+    }
+
+    /// Reject requests for namespaces that violate `rules`.
+    pub fn with_namespace_name_rules(mut self, rules: NamespaceNameRules) -> Self {
+        self.namespace_name_rules = rules;
+        self
+    }
+
+    /// Record the rows and bytes written to each namespace in `usage`.
+    pub fn with_usage(mut self, usage: Arc<UsageAccumulator>) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Reject the requests whose API token does not allow writing to their
+    /// namespace.
+    pub fn with_authorizer(mut self, authorizer: Arc<Authorizer>) -> Self {
+        self.authorizer = Some(authorizer);
+        self
+    }
+
+    /// Skip the writes whose producer sequence number was already reserved,
+    /// as recorded in the catalog by `sequences`.
+    ///
+    /// Without [`ProducerSequences`], writes with a producer sequence number
+    /// are rejected.
+    pub fn with_producer_sequences(mut self, sequences: ProducerSequences) -> Self {
+        self.producer_sequences = Some(sequences);
+        self
+    }
+
+    /// Return an error unless the API token of `req` allows writing to
+    /// `namespace`, if authorization is enabled.
+    async fn authorize(
+        &self,
+        req: &Request<Body>,
+        namespace: &NamespaceName<'static>,
+    ) -> Result<(), Error> {
+        let authorizer = match &self.authorizer {
+            Some(authorizer) => authorizer,
+            None => return Ok(()),
+        };
+
+        let token = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(token_from_header);
+        authorizer
+            .authorize(token, Some(namespace.as_str()), ApiTokenPermission::Write)
+            .await?;
+        Ok(())
+    }
+
+    /// Map the org & bucket of a request to its namespace, enforcing the
+    /// configured [`NamespaceNameRules`].
+    fn namespace(&self, info: &WriteInfo) -> Result<NamespaceName<'static>, OrgBucketError> {
+        let namespace = org_and_bucket_to_namespace(&info.org, &info.bucket)?;
+        self.namespace_name_rules.validate(&namespace)?;
+        Ok(namespace)
+    }
+}
+
 impl<D, N, T> HttpDelegate<D, N, T>
 where
     D: DmlHandler<WriteInput = HashMap<String, MutableBatch>, WriteOutput = WriteSummary>,
@@ -354,61 +701,72 @@ where
         // Route the request to a handler.
         match (req.method(), req.uri().path()) {
             (&Method::POST, "/api/v2/write") => self.write_handler(req).await,
-            (&Method::POST, "/api/v2/delete") => self.delete_handler(req).await,
-            _ => return Err(Error::NoHandler),
+            (&Method::POST, "/api/v2/delete") => {
+                self.delete_handler(req).await.map(summary_response)
+            }
+            _ => Err(Error::NoHandler),
         }
-        .map(|summary| {
-            Response::builder()
-                .status(StatusCode::NO_CONTENT)
-                .header(WRITE_TOKEN_HTTP_HEADER, summary.to_token())
-                .body(Body::empty())
-                .unwrap()
-        })
     }
 
-    async fn write_handler(&self, req: Request<Body>) -> Result<WriteSummary, Error> {
+    async fn write_handler(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
         let span_ctx: Option<SpanContext> = req.extensions().get().cloned();
 
         let write_info = WriteInfo::try_from(&req)?;
-        let namespace = org_and_bucket_to_namespace(&write_info.org, &write_info.bucket)
-            .map_err(OrgBucketError::MappingFail)?;
+        let namespace = self.namespace(&write_info)?;
+        self.authorize(&req, &namespace).await?;
+
+        // Reject unservable dry runs before reading the body.
+        if write_info.dry_run && self.dry_run.is_none() {
+            return Err(Error::DryRunUnsupported);
+        }
+        if write_info.producer_sequence().is_some() && self.producer_sequences.is_none() {
+            return Err(Error::ProducerSequenceUnsupported);
+        }
 
         trace!(
             org=%write_info.org,
             bucket=%write_info.bucket,
             %namespace,
+            dry_run=write_info.dry_run,
             "processing write request"
         );
 
-        // Read the HTTP body and convert it to a str.
-        let body = self.read_body(req).await?;
-        let body = std::str::from_utf8(&body).map_err(Error::NonUtf8Body)?;
-
         // The time, in nanoseconds since the epoch, to assign to any points that don't
         // contain a timestamp
         let default_time = self.time_provider.now().timestamp_nanos();
-        let start_instant = Instant::now();
 
         let mut converter = LinesConverter::new(default_time);
         converter.set_timestamp_base(write_info.precision.timestamp_base());
-        let (batches, stats) = match converter.write_lp(body).and_then(|_| converter.finish()) {
+
+        // Convert the HTTP body to batches as it is received.
+        let ParsedBody {
+            batches,
+            stats,
+            body_size,
+            parse_duration: duration,
+        } = match self.read_lines(req, converter).await {
             Ok(v) => v,
-            Err(mutable_batch_lp::Error::EmptyPayload) => {
+            Err(Error::ParseLineProtocol(mutable_batch_lp::Error::EmptyPayload))
+                if write_info.dry_run =>
+            {
+                debug!("nothing to validate");
+                return Ok(dry_run_response(&DryRunReport::default()));
+            }
+            Err(Error::ParseLineProtocol(mutable_batch_lp::Error::EmptyPayload)) => {
                 debug!("nothing to write");
-                return Ok(WriteSummary::default());
+                return Ok(summary_response(WriteSummary::default()));
             }
-            Err(e) => return Err(Error::ParseLineProtocol(e)),
+            Err(e) => return Err(e),
         };
 
         let num_tables = batches.len();
-        let duration = start_instant.elapsed();
         self.http_line_protocol_parse_duration.record(duration);
         debug!(
             num_lines=stats.num_lines,
             num_fields=stats.num_fields,
             num_tables,
             precision=?write_info.precision,
-            body_size=body.len(),
+            body_size,
             %namespace,
             org=%write_info.org,
             bucket=%write_info.bucket,
@@ -416,29 +774,79 @@ where
             "routing write",
         );
 
+        if let Some(validator) = self.dry_run.as_ref().filter(|_| write_info.dry_run) {
+            // The namespace is resolved by the validator, without the side
+            // effects of the namespace resolver (such as auto-creation).
+            let report = validator.validate(&namespace, &batches).await?;
+            return Ok(dry_run_response(&report));
+        }
+
         // Retrieve the namespace ID for this namespace.
         let namespace_id = self.namespace_resolver.get_namespace_id(&namespace).await?;
 
-        let summary = self
+        // Reserve the sequence number of the write in the catalog before
+        // applying it, skipping writes whose sequence number was already
+        // reserved by any router.
+        let reservation = match (write_info.producer_sequence(), &self.producer_sequences) {
+            (Some((producer_id, sequence_number)), Some(sequences)) => {
+                match sequences
+                    .reserve(namespace_id, producer_id, sequence_number)
+                    .await
+                    .map_err(Error::ProducerSequence)?
+                {
+                    Some(reservation) => Some(reservation),
+                    None => {
+                        debug!(%namespace, producer_id, sequence_number, "skipping duplicate write");
+                        return Ok(summary_response(WriteSummary::default()));
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        let summary = match self
             .dml_handler
             .write(&namespace, namespace_id, batches, span_ctx)
             .await
-            .map_err(Into::into)?;
+        {
+            Ok(summary) => summary,
+            Err(e) => {
+                // Let the producer retry the failed write.
+                if let Some(reservation) = reservation {
+                    reservation.release().await;
+                }
+                return Err(e.into());
+            }
+        };
 
         self.write_metric_lines.inc(stats.num_lines as _);
         self.write_metric_fields.inc(stats.num_fields as _);
         self.write_metric_tables.inc(num_tables as _);
-        self.write_metric_body_size.inc(body.len() as _);
+        self.write_metric_body_size.inc(body_size as _);
+        if let Some(usage) = &self.usage {
+            usage.record_write(namespace_id, stats.num_lines as _, body_size as _);
+        }
 
-        Ok(summary)
+        // Each line is a single row, so any line missing from the summary was
+        // dropped by the conflict policy of the namespace.
+        let dropped_rows = (stats.num_lines as u64).saturating_sub(summary.row_count());
+        let mut response = summary_response(summary);
+        if dropped_rows > 0 {
+            debug!(%namespace, dropped_rows, "dropped conflicting rows from write");
+            response
+                .headers_mut()
+                .insert(DROPPED_ROWS_HTTP_HEADER, HeaderValue::from(dropped_rows));
+        }
+
+        Ok(response)
     }
 
     async fn delete_handler(&self, req: Request<Body>) -> Result<WriteSummary, Error> {
         let span_ctx: Option<SpanContext> = req.extensions().get().cloned();
 
         let account = WriteInfo::try_from(&req)?;
-        let namespace = org_and_bucket_to_namespace(&account.org, &account.bucket)
-            .map_err(OrgBucketError::MappingFail)?;
+        let namespace = self.namespace(&account)?;
+        self.authorize(&req, &namespace).await?;
 
         trace!(org=%account.org, bucket=%account.bucket, %namespace, "processing delete request");
 
@@ -486,20 +894,74 @@ where
         Ok(WriteSummary::default())
     }
 
+    /// Convert the line protocol in the request's body to batches with
+    /// `converter` as it is received, applying the configured size limits and
+    /// decoding any content encoding.
+    ///
+    /// Unlike [`Self::read_body()`], only the incomplete last line of each
+    /// received chunk is buffered, rather than the whole body.
+    async fn read_lines(
+        &self,
+        req: hyper::Request<Body>,
+        converter: LinesConverter,
+    ) -> Result<ParsedBody, Error> {
+        let ungzip = is_gzip(&req)?;
+        let mut payload = req.into_body();
+
+        let mut converter = StreamingLinesConverter::new(converter, self.max_request_bytes);
+        let mut decoder = ungzip.then(|| flate2::write::GzDecoder::new(Vec::new()));
+        let mut received = 0;
+        let mut parse_duration = Duration::ZERO;
+
+        while let Some(chunk) = payload.next().await {
+            let chunk = chunk.map_err(Error::ClientHangup)?;
+            // limit max size of the (possibly compressed) payload
+            received += chunk.len();
+            if received > self.max_request_bytes {
+                return Err(Error::RequestSizeExceeded(self.max_request_bytes));
+            }
+
+            let start_instant = Instant::now();
+            match decoder.as_mut() {
+                Some(decoder) => {
+                    // The converter limits the size of the decompressed
+                    // payload, preventing a decompression bomb based DoS.
+                    for input in chunk.chunks(GZIP_INPUT_SLICE_BYTES) {
+                        decoder.write_all(input).map_err(Error::InvalidGzip)?;
+                        converter
+                            .write_chunk(decoder.get_ref())
+                            .map_err(line_protocol_error)?;
+                        decoder.get_mut().clear();
+                    }
+                }
+                None => converter.write_chunk(&chunk).map_err(line_protocol_error)?,
+            }
+            parse_duration += start_instant.elapsed();
+        }
+
+        let start_instant = Instant::now();
+        if let Some(decoder) = decoder {
+            let remaining = decoder.finish().map_err(Error::InvalidGzip)?;
+            converter
+                .write_chunk(&remaining)
+                .map_err(line_protocol_error)?;
+        }
+        let body_size = converter.bytes();
+        let (batches, stats) = converter.finish().map_err(line_protocol_error)?;
+        parse_duration += start_instant.elapsed();
+
+        Ok(ParsedBody {
+            batches,
+            stats,
+            body_size,
+            parse_duration,
+        })
+    }
+
     /// Parse the request's body into raw bytes, applying the configured size
     /// limits and decoding any content encoding.
     async fn read_body(&self, req: hyper::Request<Body>) -> Result<Bytes, Error> {
-        let encoding = req
-            .headers()
-            .get(&CONTENT_ENCODING)
-            .map(|v| v.to_str().map_err(Error::NonUtf8ContentHeader))
-            .transpose()?;
-        let ungzip = match encoding {
-            None => false,
-            Some("gzip") => true,
-            Some(v) => return Err(Error::InvalidContentEncoding(v.to_string())),
-        };
-
+        let ungzip = is_gzip(&req)?;
         let mut payload = req.into_body();
 
         let mut body = BytesMut::new();
@@ -544,6 +1006,65 @@ where
     }
 }
 
+/// The line protocol of a write request body, converted to batches.
+#[derive(Debug)]
+struct ParsedBody {
+    batches: HashMap<String, MutableBatch>,
+    stats: PayloadStatistics,
+    /// The size of the (decompressed) body, in bytes.
+    body_size: usize,
+    /// The time spent decompressing and converting the body, excluding the
+    /// time spent waiting for it to be received.
+    parse_duration: Duration,
+}
+
+/// Returns true if the request body is gzip-encoded, or an error if the
+/// content encoding is not supported.
+fn is_gzip(req: &Request<Body>) -> Result<bool, Error> {
+    let encoding = req
+        .headers()
+        .get(&CONTENT_ENCODING)
+        .map(|v| v.to_str().map_err(Error::NonUtf8ContentHeader))
+        .transpose()?;
+    match encoding {
+        None => Ok(false),
+        Some("gzip") => Ok(true),
+        Some(v) => Err(Error::InvalidContentEncoding(v.to_string())),
+    }
+}
+
+/// Map a line protocol conversion error to the equivalent body read error,
+/// if any.
+fn line_protocol_error(e: mutable_batch_lp::Error) -> Error {
+    match e {
+        mutable_batch_lp::Error::SizeExceeded { max_bytes } => {
+            Error::RequestSizeExceeded(max_bytes)
+        }
+        mutable_batch_lp::Error::NonUtf8 { source } => Error::NonUtf8Body(source),
+        e => Error::ParseLineProtocol(e),
+    }
+}
+
+/// The response to a successfully applied write or delete.
+fn summary_response(summary: WriteSummary) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header(WRITE_TOKEN_HTTP_HEADER, summary.to_token())
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// The response to a successfully validated dry-run write.
+fn dry_run_response(report: &DryRunReport) -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            serde_json::to_vec(report).expect("dry run report is always serializable"),
+        ))
+        .unwrap()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -552,9 +1073,13 @@ mod tests {
         namespace_resolver::mock::MockNamespaceResolver,
     };
     use assert_matches::assert_matches;
-    use data_types::{NamespaceId, NamespaceNameError};
+    use data_types::{NamespaceId, NamespaceNameError, Timestamp};
     use flate2::{write::GzEncoder, Compression};
-    use hyper::header::HeaderValue;
+    use iox_catalog::{
+        authz::{generate_token, hash_token},
+        interface::Catalog,
+        mem::MemCatalog,
+    };
     use metric::{Attributes, Metric};
     use mutable_batch::column::ColumnData;
     use mutable_batch_lp::LineWriteError;
@@ -765,6 +1290,58 @@ mod tests {
         }
     );
 
+    test_write_handler!(
+        dry_run_unsupported,
+        query_string = "?org=bananas&bucket=test&dry_run=true",
+        body = "platanos,tag1=A,tag2=B val=42i 123456".as_bytes(),
+        dml_handler = [],
+        want_result = Err(Error::DryRunUnsupported),
+        want_dml_calls = []
+    );
+
+    test_write_handler!(
+        producer_sequence_unsupported,
+        query_string = "?org=bananas&bucket=test&producer_id=gateway&sequence_number=1",
+        body = "platanos,tag1=A,tag2=B val=42i 123456".as_bytes(),
+        dml_handler = [],
+        want_result = Err(Error::ProducerSequenceUnsupported),
+        want_dml_calls = []
+    );
+
+    test_write_handler!(
+        producer_id_without_sequence_number,
+        query_string = "?org=bananas&bucket=test&producer_id=gateway",
+        body = "platanos,tag1=A,tag2=B val=42i 123456".as_bytes(),
+        dml_handler = [],
+        want_result = Err(Error::InvalidOrgBucket(
+            OrgBucketError::InvalidProducerSequence(_)
+        )),
+        want_dml_calls = []
+    );
+
+    test_write_handler!(
+        sequence_number_out_of_range,
+        query_string =
+            "?org=bananas&bucket=test&producer_id=gateway&sequence_number=18446744073709551615",
+        body = "platanos,tag1=A,tag2=B val=42i 123456".as_bytes(),
+        dml_handler = [],
+        want_result = Err(Error::InvalidOrgBucket(
+            OrgBucketError::InvalidProducerSequence(_)
+        )),
+        want_dml_calls = []
+    );
+
+    test_write_handler!(
+        ok_dry_run_false,
+        query_string = "?org=bananas&bucket=test&dry_run=false",
+        body = "platanos,tag1=A,tag2=B val=42i 123456".as_bytes(),
+        dml_handler = [Ok(summary())],
+        want_result = Ok(_),
+        want_dml_calls = [MockDmlHandlerCall::Write{namespace, ..}] => {
+            assert_eq!(namespace, "bananas_test");
+        }
+    );
+
     test_write_handler!(
         ok_precision_s,
         query_string = "?org=bananas&bucket=test&precision=s",
@@ -1197,6 +1774,439 @@ mod tests {
         Terrible,
     }
 
+    #[test]
+    fn test_error_codes() {
+        let e = Error::ParseLineProtocol(mutable_batch_lp::Error::Write {
+            source: LineWriteError::ConflictedFieldTypes {
+                name: "bananas".into(),
+            },
+            line: 42,
+        });
+        assert_eq!(e.error_code().as_str(), "line_protocol_parse");
+        assert_eq!(e.details(), Some(serde_json::json!({ "line": 42 })));
+
+        let e = Error::DmlHandler(DmlError::NamespaceNotFound("bananas_test".into()));
+        assert_eq!(e.as_status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(e.error_code().as_str(), "namespace_not_found");
+        assert_eq!(
+            e.details(),
+            Some(serde_json::json!({ "namespace": "bananas_test" }))
+        );
+
+        let e = Error::DmlHandler(DmlError::Retention(RetentionError::OutsideRetention(
+            "cpu".into(),
+        )));
+        assert_eq!(e.error_code().as_str(), "retention_violation");
+        assert_eq!(e.details(), Some(serde_json::json!({ "table": "cpu" })));
+
+        let e = Error::DmlHandler(DmlError::Schema(SchemaError::ServiceLimit(Box::new(
+            MockError::Terrible,
+        ))));
+        assert_eq!(e.error_code().as_str(), "over_quota");
+        assert_eq!(e.details(), None);
+
+        let e = Error::DmlHandler(DmlError::Internal(Box::new(MockError::Terrible)));
+        assert_eq!(e.error_code(), ErrorCode::Internal);
+    }
+
+    // A dry-run write is validated and reported, but never reaches the DML
+    // handlers.
+    #[tokio::test]
+    async fn test_dry_run_write() {
+        let catalog = iox_tests::util::TestCatalog::new();
+        catalog.create_namespace_1hr_retention("bananas_test").await;
+
+        let write_buffer = write_buffer::mock::MockBufferForWriting::new(
+            write_buffer::mock::MockBufferSharedState::empty_with_n_shards(1.try_into().unwrap()),
+            None,
+            catalog.time_provider(),
+        )
+        .unwrap();
+        let shard = crate::shard::Shard::new(
+            data_types::ShardIndex::new(0),
+            Arc::new(write_buffer),
+            &Default::default(),
+        );
+        let validator = DryRunValidator::new(
+            catalog.catalog(),
+            data_types::PartitionTemplate {
+                parts: vec![data_types::TemplatePart::TimeFormat("%Y-%m-%d".to_owned())],
+            },
+            Arc::new(sharder::JumpHash::new([Arc::new(shard)])),
+        );
+
+        let dml_handler = Arc::new(MockDmlHandler::default());
+        let metrics = Arc::new(metric::Registry::default());
+        let delegate = HttpDelegate::new(
+            MAX_BYTES,
+            100,
+            MockNamespaceResolver::default(),
+            Arc::clone(&dml_handler),
+            &metrics,
+        )
+        .with_dry_run(validator);
+
+        // Omit the timestamp so the write is within the retention period.
+        let request = Request::builder()
+            .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test&dry_run=true")
+            .method("POST")
+            .body(Body::from("platanos,tag1=A val=42i"))
+            .unwrap();
+
+        let response = delegate
+            .route(request)
+            .await
+            .expect("dry run should succeed");
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["tables"][0]["table"], "platanos");
+        assert_eq!(report["tables"][0]["new_table"], true);
+        assert_eq!(report["tables"][0]["new_columns"]["val"], "i64");
+        assert_eq!(report["tables"][0]["partitions"][0]["shard_index"], 0);
+        assert_eq!(report["tables"][0]["partitions"][0]["rows"], 1);
+
+        assert!(dml_handler.calls().is_empty());
+        assert_metric_hit(&metrics, "http_write_lines", Some(0));
+    }
+
+    // Writes and deletes for namespaces that violate the configured naming
+    // rules are rejected before reaching the DML handlers.
+    #[tokio::test]
+    async fn test_namespace_name_rules() {
+        let dml_handler = Arc::new(MockDmlHandler::default());
+        let metrics = Arc::new(metric::Registry::default());
+        let delegate = HttpDelegate::new(
+            MAX_BYTES,
+            100,
+            MockNamespaceResolver::default(),
+            Arc::clone(&dml_handler),
+            &metrics,
+        )
+        .with_namespace_name_rules(NamespaceNameRules {
+            reserved_prefixes: vec!["system".to_string()],
+            ..Default::default()
+        });
+
+        for path in ["write", "delete"] {
+            let request = Request::builder()
+                .uri(format!(
+                    "https://bananas.example/api/v2/{}?org=system&bucket=test",
+                    path
+                ))
+                .method("POST")
+                .body(Body::from("platanos,tag1=A val=42i"))
+                .unwrap();
+
+            let err = delegate
+                .route(request)
+                .await
+                .expect_err("reserved namespace should be rejected");
+            assert_matches!(
+                err,
+                Error::InvalidOrgBucket(OrgBucketError::NameRule(
+                    NamespaceNameRuleViolation::ReservedPrefix { .. }
+                ))
+            );
+            assert_eq!(err.as_status_code(), StatusCode::BAD_REQUEST);
+        }
+
+        assert!(dml_handler.calls().is_empty());
+    }
+
+    // Lines, quoted newlines and multi-byte characters split across body
+    // chunks are reassembled.
+    #[tokio::test]
+    async fn test_write_streamed_body() {
+        let mock_namespace_resolver =
+            MockNamespaceResolver::default().with_mapping("bananas_test", NAMESPACE_ID);
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(summary())]));
+        let metrics = Arc::new(metric::Registry::default());
+        let delegate = HttpDelegate::new(
+            MAX_BYTES,
+            100,
+            mock_namespace_resolver,
+            Arc::clone(&dml_handler),
+            &metrics,
+        );
+
+        let body = "platanos,tag1=\u{1F34C} str=\"a\nb\" 1\nplatanos,tag1=B val=42i 2\n";
+        let chunks = body
+            .as_bytes()
+            .chunks(3)
+            .map(|v| Ok::<_, MockError>(v.to_vec()))
+            .collect::<Vec<_>>();
+        let request = Request::builder()
+            .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+            .method("POST")
+            .body(Body::wrap_stream(futures::stream::iter(chunks)))
+            .unwrap();
+
+        delegate.route(request).await.expect("write should succeed");
+
+        assert_matches!(
+            dml_handler.calls().as_slice(),
+            [MockDmlHandlerCall::Write { write_input, .. }] => {
+                assert_eq!(write_input["platanos"].rows(), 2);
+            }
+        );
+        assert_metric_hit(&metrics, "http_write_body_bytes", Some(body.len() as _));
+    }
+
+    // Lines dropped from a write by the schema conflict policy are reported
+    // in the response headers.
+    #[tokio::test]
+    async fn test_write_dropped_rows() {
+        let written = WriteSummary::new(vec![vec![write_summary::PartitionWrite::new(
+            "platanos".into(),
+            1,
+            dml::DmlMeta::sequenced(
+                data_types::Sequence::new(
+                    data_types::ShardIndex::new(1),
+                    data_types::SequenceNumber::new(2),
+                ),
+                iox_time::Time::from_timestamp_millis(42).unwrap(),
+                None,
+                42,
+            ),
+        )]]);
+
+        let mock_namespace_resolver =
+            MockNamespaceResolver::default().with_mapping("bananas_test", NAMESPACE_ID);
+        let dml_handler = Arc::new(
+            MockDmlHandler::default().with_write_return([Ok(written.clone()), Ok(written)]),
+        );
+        let metrics = Arc::new(metric::Registry::default());
+        let delegate = HttpDelegate::new(
+            MAX_BYTES,
+            100,
+            mock_namespace_resolver,
+            Arc::clone(&dml_handler),
+            &metrics,
+        );
+
+        let write = |body: &'static str| {
+            Request::builder()
+                .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+                .method("POST")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = delegate
+            .route(write(
+                "platanos,tag1=A val=42i 1\nplatanos,tag1=B val=42.0 2\n",
+            ))
+            .await
+            .expect("write should succeed");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.headers()[DROPPED_ROWS_HTTP_HEADER], "1");
+
+        let response = delegate
+            .route(write("platanos,tag1=A val=42i 1\n"))
+            .await
+            .expect("write should succeed");
+        assert!(!response.headers().contains_key(DROPPED_ROWS_HTTP_HEADER));
+    }
+
+    // Successful writes are recorded in the usage of their namespace.
+    #[tokio::test]
+    async fn test_write_usage() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+        let namespace_id = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("foo").await.unwrap();
+            let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+            repos
+                .namespaces()
+                .create("bananas_test", None, topic.id, pool.id)
+                .await
+                .unwrap()
+                .id
+        };
+
+        let mock_namespace_resolver =
+            MockNamespaceResolver::default().with_mapping("bananas_test", namespace_id);
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(summary())]));
+        let usage = Arc::new(UsageAccumulator::default());
+        let delegate = HttpDelegate::new(
+            MAX_BYTES,
+            100,
+            mock_namespace_resolver,
+            Arc::clone(&dml_handler),
+            &metrics,
+        )
+        .with_usage(Arc::clone(&usage));
+
+        let body = "platanos,tag1=A val=42i 1\nplatanos,tag1=B val=42i 2\n";
+        let request = Request::builder()
+            .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+            .method("POST")
+            .body(Body::from(body))
+            .unwrap();
+        delegate.route(request).await.expect("write should succeed");
+
+        usage.flush(catalog.as_ref()).await.unwrap();
+        let got = usage.total(namespace_id).expect("usage not recorded");
+        assert_eq!(got.rows_written, 2);
+        assert_eq!(got.bytes_written, body.len() as i64);
+        assert_eq!(got.queries_executed, 0);
+    }
+
+    // With an authorizer, writes require a token allowing writes to their
+    // namespace.
+    #[tokio::test]
+    async fn test_write_authz() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+        let writer = generate_token();
+        let reader = generate_token();
+        {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("foo").await.unwrap();
+            let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+            let namespace = repos
+                .namespaces()
+                .create("bananas_test", None, topic.id, pool.id)
+                .await
+                .unwrap();
+            for (token, permission) in [
+                (&writer, ApiTokenPermission::Write),
+                (&reader, ApiTokenPermission::Read),
+            ] {
+                repos
+                    .api_tokens()
+                    .create(
+                        &hash_token(token),
+                        Some(namespace.id),
+                        permission,
+                        "test",
+                        Timestamp::new(0),
+                    )
+                    .await
+                    .unwrap();
+            }
+        }
+
+        let mock_namespace_resolver =
+            MockNamespaceResolver::default().with_mapping("bananas_test", NamespaceId::new(42));
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([Ok(summary())]));
+        let authorizer = Arc::new(Authorizer::new(
+            Arc::clone(&catalog),
+            catalog.time_provider(),
+            Duration::from_secs(60),
+        ));
+        let delegate = HttpDelegate::new(
+            MAX_BYTES,
+            100,
+            mock_namespace_resolver,
+            Arc::clone(&dml_handler),
+            &metrics,
+        )
+        .with_authorizer(authorizer);
+
+        let write = |authorization: Option<String>| {
+            let mut request = Request::builder()
+                .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+                .method("POST");
+            if let Some(v) = authorization {
+                request = request.header(AUTHORIZATION, v);
+            }
+            delegate.route(request.body(Body::from("platanos val=42i 1")).unwrap())
+        };
+
+        let err = write(None).await.expect_err("write should be rejected");
+        assert_matches!(err, Error::Unauthorized(AuthzError::MissingToken));
+        assert_eq!(err.as_status_code(), StatusCode::UNAUTHORIZED);
+
+        let err = write(Some(format!("Token {reader}")))
+            .await
+            .expect_err("write should be rejected");
+        assert_matches!(
+            err,
+            Error::Unauthorized(AuthzError::PermissionDenied { .. })
+        );
+        assert_eq!(err.as_status_code(), StatusCode::FORBIDDEN);
+        assert!(dml_handler.calls().is_empty());
+
+        write(Some(format!("Token {writer}")))
+            .await
+            .expect("write should succeed");
+        assert_eq!(dml_handler.calls().len(), 1);
+    }
+
+    // Retries of a write already applied are skipped, and retries of a failed
+    // write are applied.
+    #[tokio::test]
+    async fn test_write_producer_sequence() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+        let namespace_id = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("foo").await.unwrap();
+            let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+            repos
+                .namespaces()
+                .create("bananas_test", None, topic.id, pool.id)
+                .await
+                .unwrap()
+                .id
+        };
+
+        let mock_namespace_resolver =
+            MockNamespaceResolver::default().with_mapping("bananas_test", namespace_id);
+        let dml_handler = Arc::new(MockDmlHandler::default().with_write_return([
+            Ok(summary()),
+            Err(DmlError::NamespaceNotFound("bananas_test".to_string())),
+            Ok(summary()),
+        ]));
+        let delegate = HttpDelegate::new(
+            MAX_BYTES,
+            100,
+            mock_namespace_resolver,
+            Arc::clone(&dml_handler),
+            &metrics,
+        )
+        .with_producer_sequences(ProducerSequences::new(Arc::clone(&catalog)));
+
+        let write = |sequence_number: u64| {
+            let request = Request::builder()
+                .uri(format!(
+                    "https://bananas.example/api/v2/write?org=bananas&bucket=test\
+                     &producer_id=gateway&sequence_number={sequence_number}"
+                ))
+                .method("POST")
+                .body(Body::from("platanos val=42i 1"))
+                .unwrap();
+            delegate.route(request)
+        };
+
+        write(1).await.expect("write should succeed");
+        assert_eq!(dml_handler.calls().len(), 1);
+
+        // A retry is acknowledged without being applied.
+        let response = write(1).await.expect("retry should succeed");
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(dml_handler.calls().len(), 1);
+
+        // A failed write is applied on retry.
+        write(2).await.expect_err("write should fail");
+        assert_eq!(dml_handler.calls().len(), 2);
+        write(2).await.expect("retry should succeed");
+        assert_eq!(dml_handler.calls().len(), 3);
+
+        let reserved = catalog
+            .repositories()
+            .await
+            .namespaces()
+            .get_producer_sequence(namespace_id, "gateway")
+            .await
+            .unwrap();
+        assert_eq!(reserved, Some(2));
+    }
+
     // This test ensures the request limiter drops requests once the configured
     // number of simultaneous requests are being serviced.
     #[tokio::test]
@@ -1505,9 +2515,19 @@ mod tests {
              name [name] already exists",
         ),
 
+        (
+            NamespaceResolver(crate::namespace_resolver::Error::NotFound("[name]".into())),
+            "failed to resolve namespace ID: namespace [name] does not exist",
+        ),
+
         (
             RequestLimit,
             "this service is overloaded, please try again later",
         ),
+
+        (
+            DryRunUnsupported,
+            "dry-run writes are not supported by this service",
+        ),
     }
 }
@@ -19,7 +19,9 @@ use router::{
         ShardedWriteBuffer, WriteSummaryAdapter,
     },
     namespace_cache::{MemoryNamespaceCache, ShardedCache},
-    namespace_resolver::{NamespaceAutocreation, NamespaceSchemaResolver},
+    namespace_resolver::{
+        MissingNamespaceAction, NamespaceAutocreation, NamespaceSchemaResolver, NamespaceTemplate,
+    },
     server::http::HttpDelegate,
     shard::Shard,
 };
@@ -78,7 +80,7 @@ type HttpDelegateStack = HttpDelegate<
 /// A [`router`] stack configured with the various DML handlers using mock
 /// catalog / write buffer backends.
 impl TestContext {
-    pub fn new(ns_autocreate_retention_period_ns: Option<i64>) -> Self {
+    pub fn new(missing_namespace_action: MissingNamespaceAction) -> Self {
         let metrics = Arc::new(metric::Registry::default());
         let time = iox_time::MockProvider::new(
             iox_time::Time::from_timestamp_millis(668563200000).unwrap(),
@@ -131,7 +133,7 @@ impl TestContext {
             Arc::clone(&catalog),
             TopicId::new(TEST_TOPIC_ID),
             QueryPoolId::new(TEST_QUERY_POOL_ID),
-            ns_autocreate_retention_period_ns,
+            missing_namespace_action,
         );
 
         let delegate = HttpDelegate::new(1024, 100, namespace_resolver, handler_stack, &metrics);
@@ -187,13 +189,13 @@ impl TestContext {
 
 impl Default for TestContext {
     fn default() -> Self {
-        Self::new(None)
+        Self::new(MissingNamespaceAction::AutoCreate(Default::default()))
     }
 }
 
 #[tokio::test]
 async fn test_write_ok() {
-    let ctx = TestContext::new(None);
+    let ctx = TestContext::new(MissingNamespaceAction::AutoCreate(Default::default()));
 
     // Write data inside retention period
     let now = SystemProvider::default()
@@ -279,7 +281,10 @@ async fn test_write_ok() {
 
 #[tokio::test]
 async fn test_write_outside_retention_period() {
-    let ctx = TestContext::new(TEST_RETENTION_PERIOD_NS);
+    let ctx = TestContext::new(MissingNamespaceAction::AutoCreate(NamespaceTemplate {
+        retention_period_ns: TEST_RETENTION_PERIOD_NS,
+        ..Default::default()
+    }));
 
     // Write data outside retention period into a new table
     let two_hours_ago =
@@ -310,9 +315,55 @@ async fn test_write_outside_retention_period() {
     assert_eq!(err.as_status_code(), StatusCode::FORBIDDEN);
 }
 
+#[tokio::test]
+async fn test_write_namespace_not_found() {
+    let ctx = TestContext::new(MissingNamespaceAction::Reject);
+
+    let now = SystemProvider::default()
+        .now()
+        .timestamp_nanos()
+        .to_string();
+    let lp = "platanos,tag1=A,tag2=B val=42i ".to_string() + &now;
+
+    let request = Request::builder()
+        .uri("https://bananas.example/api/v2/write?org=bananas&bucket=test")
+        .method("POST")
+        .body(Body::from(lp))
+        .expect("failed to construct HTTP request");
+
+    let err = ctx
+        .delegate()
+        .route(request)
+        .await
+        .expect_err("write to unknown namespace should fail");
+
+    assert_matches!(
+        err,
+        router::server::http::Error::NamespaceResolver(
+            router::namespace_resolver::Error::NotFound(_)
+        )
+    );
+    assert_eq!(err.as_status_code(), StatusCode::NOT_FOUND);
+
+    // The namespace MUST NOT have been created.
+    let mut repos = ctx.catalog().repositories().await;
+    assert!(repos
+        .namespaces()
+        .get_by_name("bananas_test")
+        .await
+        .expect("lookup should not error")
+        .is_none());
+
+    // And nothing was written.
+    assert!(ctx
+        .write_buffer_state()
+        .get_messages(ShardIndex::new(0))
+        .is_empty());
+}
+
 #[tokio::test]
 async fn test_schema_conflict() {
-    let ctx = TestContext::new(None);
+    let ctx = TestContext::new(MissingNamespaceAction::AutoCreate(Default::default()));
 
     // data inside the retention period
     let now = SystemProvider::default()
@@ -378,7 +429,7 @@ async fn test_schema_conflict() {
 
 #[tokio::test]
 async fn test_schema_limit() {
-    let ctx = TestContext::new(None);
+    let ctx = TestContext::new(MissingNamespaceAction::AutoCreate(Default::default()));
 
     let now = SystemProvider::default()
         .now()
@@ -444,7 +495,7 @@ async fn test_schema_limit() {
 
 #[tokio::test]
 async fn test_write_propagate_ids() {
-    let ctx = TestContext::new(None);
+    let ctx = TestContext::new(MissingNamespaceAction::AutoCreate(Default::default()));
 
     // Create the namespace and a set of tables.
     let ns = ctx
@@ -525,7 +576,7 @@ async fn test_write_propagate_ids() {
 
 #[tokio::test]
 async fn test_delete_propagate_ids() {
-    let ctx = TestContext::new(None);
+    let ctx = TestContext::new(MissingNamespaceAction::AutoCreate(Default::default()));
 
     // Create the namespace and a set of tables.
     let ns = ctx
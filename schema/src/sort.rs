@@ -1,4 +1,4 @@
-use crate::{Schema, TIME_COLUMN_NAME};
+use crate::{InfluxColumnType, Schema, TIME_COLUMN_NAME};
 use arrow::compute::SortOptions;
 use arrow::{
     array::{Array, DictionaryArray, StringArray},
@@ -8,7 +8,7 @@ use arrow::{
 use indexmap::{map::Iter, IndexMap};
 use itertools::Itertools;
 use observability_deps::tracing::debug;
-use snafu::Snafu;
+use snafu::{ensure, OptionExt, Snafu};
 use std::{
     collections::{HashMap, HashSet},
     fmt::Display,
@@ -29,6 +29,19 @@ pub enum Error {
 
     #[snafu(display("invalid nulls first value: {}", value))]
     InvalidNullsFirst { value: String },
+
+    #[snafu(display("sort key column '{}' not found in schema", column))]
+    ColumnNotFound { column: String },
+
+    #[snafu(display(
+        "sort key column '{}' has type {} but must be a tag or time",
+        column,
+        column_type
+    ))]
+    InvalidColumnType {
+        column: String,
+        column_type: InfluxColumnType,
+    },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -113,6 +126,9 @@ impl SortKeyBuilder {
         )
     }
 
+    /// Insert col with specified sort options into sort key.
+    ///
+    /// A column that is already in the sort key keeps its position, with its options replaced.
     pub fn with_col_sort_opts(mut self, col: impl Into<Arc<str>>, options: SortOptions) -> Self {
         self.columns.insert(col.into(), options);
         self
@@ -123,6 +139,13 @@ impl SortKeyBuilder {
             columns: Arc::new(self.columns),
         }
     }
+
+    /// Build the sort key, validating it against `schema`, see [`SortKey::validate`].
+    pub fn build_for_schema(self, schema: &Schema) -> Result<SortKey> {
+        let sort_key = self.build();
+        sort_key.validate(schema)?;
+        Ok(sort_key)
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -131,6 +154,27 @@ pub struct SortKey {
 }
 
 impl SortKey {
+    /// Validate that all columns of this sort key are tags or the time column of `schema`.
+    pub fn validate(&self, schema: &Schema) -> Result<()> {
+        for column in self.columns.keys() {
+            let idx = schema.find_index_of(column).context(ColumnNotFoundSnafu {
+                column: column.as_ref(),
+            })?;
+            let (column_type, _) = schema.field(idx);
+            ensure!(
+                matches!(
+                    column_type,
+                    InfluxColumnType::Tag | InfluxColumnType::Timestamp
+                ),
+                InvalidColumnTypeSnafu {
+                    column: column.as_ref(),
+                    column_type,
+                }
+            );
+        }
+        Ok(())
+    }
+
     /// Create a new empty sort key
     pub fn empty() -> Self {
         SortKey {
@@ -527,7 +571,7 @@ pub fn adjust_sort_key_columns(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::builder::SchemaBuilder;
+    use crate::{builder::SchemaBuilder, InfluxFieldType};
     use arrow::array::ArrayRef;
 
     #[test]
@@ -595,6 +639,50 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_build_for_schema() {
+        let schema = SchemaBuilder::new()
+            .tag("host")
+            .tag("region")
+            .influx_field("usage", InfluxFieldType::Float)
+            .timestamp()
+            .build()
+            .unwrap();
+
+        // Duplicate columns keep their first position.
+        let key = SortKeyBuilder::new()
+            .with_col("region")
+            .with_col("host")
+            .with_col_opts("region", true, false)
+            .with_col(TIME_COLUMN_NAME)
+            .build_for_schema(&schema)
+            .unwrap();
+        assert_eq!(
+            key.to_columns().collect::<Vec<_>>(),
+            vec!["region", "host", TIME_COLUMN_NAME]
+        );
+        assert!(key.get("region").unwrap().options.descending);
+
+        let err = SortKeyBuilder::new()
+            .with_col("host")
+            .with_col("zone")
+            .build_for_schema(&schema)
+            .unwrap_err();
+        assert!(matches!(err, Error::ColumnNotFound { column } if column == "zone"));
+
+        let err = SortKeyBuilder::new()
+            .with_col("usage")
+            .with_col(TIME_COLUMN_NAME)
+            .build_for_schema(&schema)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "sort key column 'usage' has type iox::column_type::field::float but must be a tag or time"
+        );
+
+        SortKey::empty().validate(&schema).unwrap();
+    }
+
     #[test]
     fn test_sort_key_eq() {
         let key1 = SortKey::from_columns(vec!["a"]);
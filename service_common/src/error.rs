@@ -1,6 +1,7 @@
 //! Routines for error handling
 
 use datafusion::{arrow::error::ArrowError, error::DataFusionError};
+use iox_query::query_range::QueryRangeError;
 
 /// Converts a [`DataFusionError`] into the appropriate [`tonic::Code`]
 ///
@@ -45,6 +46,10 @@ pub fn datafusion_error_to_tonic_code(mut e: &DataFusionError) -> tonic::Code {
 
     match e {
         DataFusionError::ResourcesExhausted(_) => tonic::Code::ResourceExhausted,
+        // Queries violating the query ranges of the namespace must be changed by the user
+        DataFusionError::External(inner) if inner.is::<QueryRangeError>() => {
+            tonic::Code::InvalidArgument
+        }
         // Map as many as possible back into user visible (non internal) errors
         DataFusionError::SQL(_)
         | DataFusionError::SchemaError(_)
@@ -121,6 +126,14 @@ mod test {
             tonic::Code::InvalidArgument,
         );
 
+        do_test(
+            DataFusionError::External(Box::new(QueryRangeError::Unbounded {
+                table: s.clone(),
+                max_range_ns: 1,
+            })),
+            tonic::Code::InvalidArgument,
+        );
+
         do_test(DataFusionError::Internal(s), tonic::Code::Internal);
     }
 
@@ -1,7 +1,7 @@
 //! Query planner wrapper for use in IOx services
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use datafusion::physical_plan::ExecutionPlan;
+use datafusion::{logical_expr::LogicalPlan, physical_plan::ExecutionPlan, scalar::ScalarValue};
 use iox_query::{
     exec::IOxSessionContext,
     frontend::{influxrpc::InfluxRpcPlanner, sql::SqlQueryPlanner},
@@ -43,6 +43,23 @@ impl Planner {
             .await
     }
 
+    /// Plan a SQL query that references the named parameters `$<name>`,
+    /// binding them to the values in `params`, and return a DataFusion
+    /// physical execution plan.
+    pub async fn sql_with_params(
+        &self,
+        query: impl Into<String> + Send,
+        params: HashMap<String, ScalarValue>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let planner = SqlQueryPlanner::new();
+        let query = query.into();
+        let ctx = self.ctx.child_ctx("planner sql_with_params");
+
+        self.ctx
+            .run(async move { planner.query_with_params(&query, &params, &ctx).await })
+            .await
+    }
+
     /// Creates a plan as described on
     /// [`InfluxRpcPlanner::table_names`], on a separate threadpool
     pub async fn table_names<N>(
@@ -155,6 +172,28 @@ impl Planner {
             .await
     }
 
+    /// Creates plans as described on
+    /// [`InfluxRpcPlanner::read_series_cardinality`], on a separate threadpool
+    pub async fn read_series_cardinality<N>(
+        &self,
+        namespace: Arc<N>,
+        predicate: InfluxRpcPredicate,
+    ) -> Result<Vec<LogicalPlan>>
+    where
+        N: QueryNamespace + 'static,
+    {
+        let planner = InfluxRpcPlanner::new(self.ctx.child_ctx("planner read_series_cardinality"));
+
+        self.ctx
+            .run(async move {
+                planner
+                    .read_series_cardinality(namespace, predicate)
+                    .await
+                    .map_err(|e| e.to_df_error("read_series_cardinality"))
+            })
+            .await
+    }
+
     /// Creates a plan as described on
     /// [`InfluxRpcPlanner::read_group`], on a separate threadpool
     pub async fn read_group<N>(
@@ -0,0 +1,396 @@
+//! Implementation of the authz gRPC service, managing the API tokens that authorize requests to
+//! routers and queriers.
+
+use std::{collections::HashMap, sync::Arc};
+
+use data_types::{ApiToken as CatalogApiToken, ApiTokenId, ApiTokenPermission, Timestamp};
+use generated_types::{
+    google::{FieldViolation, NotFound, ResourceType},
+    influxdata::iox::authz::v1::*,
+};
+use iox_catalog::{
+    authz::{generate_token, hash_token, token_from_header, Authorizer, AuthzError},
+    interface::Catalog,
+};
+use observability_deps::tracing::{info, warn};
+use tonic::{metadata::MetadataMap, Request, Response, Status};
+
+pub mod rpc;
+
+/// gRPC metadata key carrying the API token of a request.
+const AUTHORIZATION_HEADER: &str = "authorization";
+
+/// The API token of the request with `metadata`, if any.
+pub fn token_from_metadata(metadata: &MetadataMap) -> Option<&str> {
+    metadata
+        .get(AUTHORIZATION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(token_from_header)
+}
+
+/// Convert an [`AuthzError`] to the [`Status`] returned to the client.
+pub fn authz_error_to_status(e: AuthzError) -> Status {
+    match e {
+        AuthzError::MissingToken | AuthzError::InvalidToken => {
+            Status::unauthenticated(e.to_string())
+        }
+        AuthzError::PermissionDenied { .. } => Status::permission_denied(e.to_string()),
+        AuthzError::Catalog(e) => {
+            warn!(error=%e, "failed to authorize request");
+            Status::internal(e.to_string())
+        }
+    }
+}
+
+/// Implementation of the gRPC authz service
+#[derive(Debug)]
+pub struct AuthzService {
+    /// Catalog.
+    catalog: Arc<dyn Catalog>,
+
+    /// Authorizes the requests to this service, if authorization is enabled.
+    authorizer: Option<Arc<Authorizer>>,
+}
+
+impl AuthzService {
+    pub fn new(catalog: Arc<dyn Catalog>, authorizer: Option<Arc<Authorizer>>) -> Self {
+        Self {
+            catalog,
+            authorizer,
+        }
+    }
+
+    /// Return an error unless the request with `metadata` may manage tokens.
+    async fn authorize(&self, metadata: &MetadataMap) -> Result<(), Status> {
+        match &self.authorizer {
+            Some(authorizer) => authorizer
+                .authorize(
+                    token_from_metadata(metadata),
+                    None,
+                    ApiTokenPermission::Admin,
+                )
+                .await
+                .map_err(authz_error_to_status),
+            None => Ok(()),
+        }
+    }
+
+    /// The names of all namespaces, by ID.
+    async fn namespace_names(&self) -> Result<HashMap<data_types::NamespaceId, String>, Status> {
+        let mut repos = self.catalog.repositories().await;
+        Ok(repos
+            .namespaces()
+            .list()
+            .await
+            .map_err(|e| {
+                warn!(error=%e, "failed to retrieve namespaces from catalog");
+                Status::internal(e.to_string())
+            })?
+            .into_iter()
+            .map(|namespace| (namespace.id, namespace.name))
+            .collect())
+    }
+}
+
+#[tonic::async_trait]
+impl authz_service_server::AuthzService for AuthzService {
+    async fn create_token(
+        &self,
+        request: Request<CreateTokenRequest>,
+    ) -> Result<Response<CreateTokenResponse>, Status> {
+        self.authorize(request.metadata()).await?;
+        let req = request.into_inner();
+
+        let permission = permission_from_proto(req.permission())?;
+
+        let mut repos = self.catalog.repositories().await;
+        let namespace = match &req.namespace {
+            Some(name) => Some(
+                repos
+                    .namespaces()
+                    .get_by_name(name)
+                    .await
+                    .map_err(|e| {
+                        warn!(error=%e, namespace=%name, "failed to retrieve namespace from catalog");
+                        Status::internal(e.to_string())
+                    })?
+                    .ok_or_else(|| NotFound::new(ResourceType::Namespace, name.clone()))?,
+            ),
+            None => None,
+        };
+
+        let token = generate_token();
+        let api_token = repos
+            .api_tokens()
+            .create(
+                &hash_token(&token),
+                namespace.as_ref().map(|n| n.id),
+                permission,
+                &req.description,
+                Timestamp::from(self.catalog.time_provider().now()),
+            )
+            .await
+            .map_err(|e| {
+                warn!(error=%e, "failed to create API token");
+                Status::internal(e.to_string())
+            })?;
+
+        info!(
+            id=%api_token.id,
+            namespace=?req.namespace,
+            %permission,
+            description=%req.description,
+            "created API token"
+        );
+
+        Ok(Response::new(CreateTokenResponse {
+            api_token: Some(token_to_proto(api_token, namespace.map(|n| n.name))),
+            token,
+        }))
+    }
+
+    async fn list_tokens(
+        &self,
+        request: Request<ListTokensRequest>,
+    ) -> Result<Response<ListTokensResponse>, Status> {
+        self.authorize(request.metadata()).await?;
+
+        let names = self.namespace_names().await?;
+        let mut repos = self.catalog.repositories().await;
+        let api_tokens = repos
+            .api_tokens()
+            .list()
+            .await
+            .map_err(|e| {
+                warn!(error=%e, "failed to retrieve API tokens from catalog");
+                Status::internal(e.to_string())
+            })?
+            .into_iter()
+            .map(|token| {
+                let namespace = token.namespace_id.and_then(|id| names.get(&id).cloned());
+                token_to_proto(token, namespace)
+            })
+            .collect();
+
+        Ok(Response::new(ListTokensResponse { api_tokens }))
+    }
+
+    async fn revoke_token(
+        &self,
+        request: Request<RevokeTokenRequest>,
+    ) -> Result<Response<RevokeTokenResponse>, Status> {
+        self.authorize(request.metadata()).await?;
+        let req = request.into_inner();
+
+        let names = self.namespace_names().await?;
+        let mut repos = self.catalog.repositories().await;
+        let token = repos
+            .api_tokens()
+            .revoke(
+                ApiTokenId::new(req.id),
+                Timestamp::from(self.catalog.time_provider().now()),
+            )
+            .await
+            .map_err(|e| {
+                warn!(error=%e, id=%req.id, "failed to revoke API token");
+                Status::internal(e.to_string())
+            })?
+            .ok_or_else(|| {
+                NotFound::new(
+                    ResourceType::Unknown("api_token".to_string()),
+                    req.id.to_string(),
+                )
+            })?;
+
+        info!(id=%token.id, "revoked API token");
+
+        let namespace = token.namespace_id.and_then(|id| names.get(&id).cloned());
+        Ok(Response::new(RevokeTokenResponse {
+            api_token: Some(token_to_proto(token, namespace)),
+        }))
+    }
+}
+
+fn permission_from_proto(permission: Permission) -> Result<ApiTokenPermission, FieldViolation> {
+    match permission {
+        Permission::Read => Ok(ApiTokenPermission::Read),
+        Permission::Write => Ok(ApiTokenPermission::Write),
+        Permission::Admin => Ok(ApiTokenPermission::Admin),
+        Permission::Unspecified => Err(FieldViolation::required("permission")),
+    }
+}
+
+fn permission_to_proto(permission: ApiTokenPermission) -> Permission {
+    match permission {
+        ApiTokenPermission::Read => Permission::Read,
+        ApiTokenPermission::Write => Permission::Write,
+        ApiTokenPermission::Admin => Permission::Admin,
+    }
+}
+
+fn token_to_proto(token: CatalogApiToken, namespace: Option<String>) -> ApiToken {
+    ApiToken {
+        id: token.id.get(),
+        namespace,
+        permission: permission_to_proto(token.permission).into(),
+        description: token.description,
+        created_at: token.created_at.get(),
+        revoked_at: token.revoked_at.map(|t| t.get()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use generated_types::influxdata::iox::authz::v1::authz_service_server::AuthzService as _;
+    use iox_catalog::mem::MemCatalog;
+
+    use super::*;
+
+    fn request<T>(message: T, token: Option<&str>) -> Request<T> {
+        let mut request = Request::new(message);
+        if let Some(token) = token {
+            request.metadata_mut().insert(
+                AUTHORIZATION_HEADER,
+                format!("Bearer {token}").parse().unwrap(),
+            );
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn test_tokens() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+        {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("iox-shared").await.unwrap();
+            let pool = repos
+                .query_pools()
+                .create_or_get("iox-shared")
+                .await
+                .unwrap();
+            repos
+                .namespaces()
+                .create("bananas", None, topic.id, pool.id)
+                .await
+                .unwrap();
+        }
+
+        // Bootstrap an admin token without authorization.
+        let service = AuthzService::new(Arc::clone(&catalog), None);
+        let admin = service
+            .create_token(request(
+                CreateTokenRequest {
+                    namespace: None,
+                    permission: Permission::Admin.into(),
+                    description: "admin".to_string(),
+                },
+                None,
+            ))
+            .await
+            .unwrap()
+            .into_inner()
+            .token;
+
+        let authorizer = Arc::new(Authorizer::new(
+            Arc::clone(&catalog),
+            catalog.time_provider(),
+            Duration::ZERO,
+        ));
+        let service = AuthzService::new(Arc::clone(&catalog), Some(Arc::clone(&authorizer)));
+
+        let err = service
+            .list_tokens(request(ListTokensRequest {}, None))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+
+        let created = service
+            .create_token(request(
+                CreateTokenRequest {
+                    namespace: Some("bananas".to_string()),
+                    permission: Permission::Write.into(),
+                    description: "writer".to_string(),
+                },
+                Some(&admin),
+            ))
+            .await
+            .unwrap()
+            .into_inner();
+        let writer = created.api_token.unwrap();
+        assert_eq!(writer.namespace.as_deref(), Some("bananas"));
+        assert_eq!(writer.permission(), Permission::Write);
+        assert_eq!(writer.revoked_at, None);
+        authorizer
+            .authorize(
+                Some(&created.token),
+                Some("bananas"),
+                ApiTokenPermission::Write,
+            )
+            .await
+            .unwrap();
+
+        // Only admins may manage tokens.
+        let err = service
+            .list_tokens(request(ListTokensRequest {}, Some(&created.token)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+        for (namespace, permission, code) in [
+            (Some("platanos"), Permission::Read, tonic::Code::NotFound),
+            (None, Permission::Unspecified, tonic::Code::InvalidArgument),
+        ] {
+            let err = service
+                .create_token(request(
+                    CreateTokenRequest {
+                        namespace: namespace.map(ToString::to_string),
+                        permission: permission.into(),
+                        description: String::new(),
+                    },
+                    Some(&admin),
+                ))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code(), code);
+        }
+
+        let tokens = service
+            .list_tokens(request(ListTokensRequest {}, Some(&admin)))
+            .await
+            .unwrap()
+            .into_inner()
+            .api_tokens;
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[1], writer);
+
+        let revoked = service
+            .revoke_token(request(RevokeTokenRequest { id: writer.id }, Some(&admin)))
+            .await
+            .unwrap()
+            .into_inner()
+            .api_token
+            .unwrap();
+        assert!(revoked.revoked_at.is_some());
+        let err = authorizer
+            .authorize(
+                Some(&created.token),
+                Some("bananas"),
+                ApiTokenPermission::Read,
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(
+            authz_error_to_status(err).code(),
+            tonic::Code::Unauthenticated
+        );
+
+        let err = service
+            .revoke_token(request(RevokeTokenRequest { id: 42 }, Some(&admin)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+}
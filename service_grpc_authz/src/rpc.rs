@@ -0,0 +1,295 @@
+//! Authorization of the RPCs of gRPC services that do not authorize their requests themselves.
+//!
+//! An [`AuthorizedService`] wraps a gRPC service and requires the API token of each RPC to allow
+//! the permission listed for its method in the [`RpcPermissions`] of the service. These services
+//! are not scoped to a namespace, so the token must allow the permission on all namespaces.
+//!
+//! RPCs whose method is not listed are rejected, so that methods added to a service are not
+//! served without authorization before their permission is decided.
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use data_types::ApiTokenPermission;
+use futures::future::BoxFuture;
+use http::{Request, Response};
+use iox_catalog::authz::{token_from_header, Authorizer};
+use observability_deps::tracing::warn;
+use tonic::{body::BoxBody, transport::NamedService, Status};
+use tower::Service;
+
+use crate::{authz_error_to_status, AUTHORIZATION_HEADER};
+
+/// The permission required by each method of a gRPC service, by method name.
+pub type RpcPermissions = &'static [(&'static str, ApiTokenPermission)];
+
+/// The permissions of the `influxdata.iox.namespace.v1.NamespaceService` RPCs.
+pub const NAMESPACE_SERVICE_PERMISSIONS: RpcPermissions = &[
+    ("GetNamespaces", ApiTokenPermission::Read),
+    ("ListNamespaces", ApiTokenPermission::Read),
+    ("CreateNamespace", ApiTokenPermission::Admin),
+    ("UpdateNamespaceRetention", ApiTokenPermission::Admin),
+    ("UpdateNamespaceQueryRanges", ApiTokenPermission::Admin),
+    ("UpdateNamespaceRowTtl", ApiTokenPermission::Admin),
+    ("UpdateNamespaceTopic", ApiTokenPermission::Admin),
+];
+
+/// The permissions of the `influxdata.iox.catalog.v1.CatalogService` RPCs.
+pub const CATALOG_SERVICE_PERMISSIONS: RpcPermissions = &[
+    ("GetParquetFilesByPartitionId", ApiTokenPermission::Read),
+    ("GetPartitionsByTableId", ApiTokenPermission::Read),
+    ("GetParquetFilesByNamespaceTable", ApiTokenPermission::Read),
+    ("GetDeleteStatus", ApiTokenPermission::Read),
+];
+
+/// The permissions of the `influxdata.iox.schema.v1.SchemaService` RPCs.
+pub const SCHEMA_SERVICE_PERMISSIONS: RpcPermissions = &[
+    ("GetSchema", ApiTokenPermission::Read),
+    ("GetTableSchema", ApiTokenPermission::Read),
+];
+
+/// The permissions of the `influxdata.iox.object_store.v1.ObjectStoreService` RPCs.
+pub const OBJECT_STORE_SERVICE_PERMISSIONS: RpcPermissions =
+    &[("GetParquetFileByObjectStoreId", ApiTokenPermission::Read)];
+
+/// The permissions of the `influxdata.iox.sharder.v1.ShardService` RPCs.
+pub const SHARD_SERVICE_PERMISSIONS: RpcPermissions = &[
+    ("MapToShard", ApiTokenPermission::Read),
+    ("GetShardProgress", ApiTokenPermission::Read),
+];
+
+/// The permissions of the `influxdata.iox.usage.v1.UsageService` RPCs.
+pub const USAGE_SERVICE_PERMISSIONS: RpcPermissions = &[
+    ("GetNamespaceUsage", ApiTokenPermission::Read),
+    ("ListNamespaceUsage", ApiTokenPermission::Read),
+];
+
+/// The permissions of the `influxdata.iox.ingester.v1.WriteInfoService` RPCs.
+pub const WRITE_INFO_SERVICE_PERMISSIONS: RpcPermissions =
+    &[("GetWriteInfo", ApiTokenPermission::Read)];
+
+/// The permissions of the `influxdata.platform.storage.Storage` (InfluxRPC) RPCs.
+pub const STORAGE_SERVICE_PERMISSIONS: RpcPermissions = &[
+    ("ReadFilter", ApiTokenPermission::Read),
+    ("ReadGroup", ApiTokenPermission::Read),
+    ("ReadWindowAggregate", ApiTokenPermission::Read),
+    ("TagKeys", ApiTokenPermission::Read),
+    ("TagValues", ApiTokenPermission::Read),
+    (
+        "TagValuesGroupedByMeasurementAndTagKey",
+        ApiTokenPermission::Read,
+    ),
+    ("ReadSeriesCardinality", ApiTokenPermission::Read),
+    ("Capabilities", ApiTokenPermission::Read),
+    ("MeasurementNames", ApiTokenPermission::Read),
+    ("MeasurementTagKeys", ApiTokenPermission::Read),
+    ("MeasurementTagValues", ApiTokenPermission::Read),
+    ("MeasurementFields", ApiTokenPermission::Read),
+    ("Offsets", ApiTokenPermission::Read),
+];
+
+/// A gRPC service whose RPCs are authorized by their API token, see the
+/// [module documentation](self).
+#[derive(Debug, Clone)]
+pub struct AuthorizedService<S> {
+    inner: S,
+
+    /// Authorizes the RPCs, if authorization is enabled.
+    authorizer: Option<Arc<Authorizer>>,
+
+    /// The permission required by each RPC, by path.
+    permissions: Arc<HashMap<String, ApiTokenPermission>>,
+}
+
+impl<S> AuthorizedService<S>
+where
+    S: NamedService,
+{
+    /// Authorize the RPCs to `inner` with `authorizer`, requiring the `permissions` of their
+    /// methods. RPCs are not authorized if `authorizer` is `None`.
+    pub fn new(inner: S, authorizer: Option<Arc<Authorizer>>, permissions: RpcPermissions) -> Self {
+        let permissions = permissions
+            .iter()
+            .map(|(method, permission)| (format!("/{}/{}", S::NAME, method), *permission))
+            .collect();
+
+        Self {
+            inner,
+            authorizer,
+            permissions: Arc::new(permissions),
+        }
+    }
+}
+
+impl<S> NamedService for AuthorizedService<S>
+where
+    S: NamedService,
+{
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, B> Service<Request<B>> for AuthorizedService<S>
+where
+    S: Service<Request<B>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    B: Send + 'static,
+{
+    type Response = Response<BoxBody>;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        // `self.inner` was polled ready, so it must serve this request rather than its clone
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let authorizer = match &self.authorizer {
+            Some(authorizer) => Arc::clone(authorizer),
+            None => return Box::pin(inner.call(request)),
+        };
+
+        let path = request.uri().path().to_string();
+        let permission = self.permissions.get(&path).copied();
+        let token = request
+            .headers()
+            .get(AUTHORIZATION_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(token_from_header)
+            .map(ToString::to_string);
+
+        Box::pin(async move {
+            let authorized = match permission {
+                Some(permission) => authorizer
+                    .authorize(token.as_deref(), None, permission)
+                    .await
+                    .map_err(authz_error_to_status),
+                None => {
+                    warn!(%path, "rejecting RPC without a required permission");
+                    Err(Status::permission_denied(format!(
+                        "RPC {path} is not allowed with authorization enabled"
+                    )))
+                }
+            };
+
+            match authorized {
+                Ok(()) => inner.call(request).await,
+                Err(status) => Ok(status.to_http()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, time::Duration};
+
+    use data_types::Timestamp;
+    use iox_catalog::{
+        authz::{generate_token, hash_token},
+        interface::Catalog,
+        mem::MemCatalog,
+    };
+
+    use super::*;
+
+    /// A service answering every RPC with an OK status.
+    #[derive(Debug, Clone)]
+    struct OkService;
+
+    impl NamedService for OkService {
+        const NAME: &'static str = "test.OkService";
+    }
+
+    impl Service<Request<()>> for OkService {
+        type Response = Response<BoxBody>;
+        type Error = Infallible;
+        type Future = futures::future::Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, _request: Request<()>) -> Self::Future {
+            futures::future::ready(Ok(Status::ok("").to_http()))
+        }
+    }
+
+    const PERMISSIONS: RpcPermissions = &[
+        ("Read", ApiTokenPermission::Read),
+        ("Admin", ApiTokenPermission::Admin),
+    ];
+
+    async fn call<S>(service: &mut S, method: &str, token: Option<&str>) -> tonic::Code
+    where
+        S: Service<Request<()>, Response = Response<BoxBody>, Error = Infallible>,
+    {
+        let mut request = Request::builder().uri(format!("/test.OkService/{method}"));
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION_HEADER, format!("Bearer {token}"));
+        }
+        let response = service.call(request.body(()).unwrap()).await.unwrap();
+        Status::from_header_map(response.headers()).unwrap().code()
+    }
+
+    #[tokio::test]
+    async fn test_authorized_service() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+        let (reader, admin) = (generate_token(), generate_token());
+        {
+            let mut repos = catalog.repositories().await;
+            for (token, permission) in [
+                (&reader, ApiTokenPermission::Read),
+                (&admin, ApiTokenPermission::Admin),
+            ] {
+                repos
+                    .api_tokens()
+                    .create(&hash_token(token), None, permission, "", Timestamp::new(0))
+                    .await
+                    .unwrap();
+            }
+        }
+
+        // Without an authorizer, all RPCs are served.
+        let mut service = AuthorizedService::new(OkService, None, PERMISSIONS);
+        assert_eq!(call(&mut service, "Admin", None).await, tonic::Code::Ok);
+        assert_eq!(call(&mut service, "Other", None).await, tonic::Code::Ok);
+
+        let authorizer = Arc::new(Authorizer::new(
+            Arc::clone(&catalog),
+            catalog.time_provider(),
+            Duration::from_secs(60),
+        ));
+        let mut service = AuthorizedService::new(OkService, Some(authorizer), PERMISSIONS);
+
+        assert_eq!(
+            call(&mut service, "Read", None).await,
+            tonic::Code::Unauthenticated
+        );
+        assert_eq!(
+            call(&mut service, "Read", Some(&reader)).await,
+            tonic::Code::Ok
+        );
+        assert_eq!(
+            call(&mut service, "Admin", Some(&reader)).await,
+            tonic::Code::PermissionDenied
+        );
+        assert_eq!(
+            call(&mut service, "Admin", Some(&admin)).await,
+            tonic::Code::Ok
+        );
+
+        // RPCs without a permission are rejected.
+        assert_eq!(
+            call(&mut service, "Other", Some(&admin)).await,
+            tonic::Code::PermissionDenied
+        );
+    }
+}
@@ -15,7 +15,7 @@
 
 use data_types::{PartitionId, TableId};
 use generated_types::influxdata::iox::catalog::v1::*;
-use iox_catalog::interface::Catalog;
+use iox_catalog::interface::{Catalog, RepoCollection};
 use observability_deps::tracing::*;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
@@ -126,6 +126,109 @@ impl catalog_service_server::CatalogService for CatalogService {
 
         Ok(Response::new(response))
     }
+
+    async fn get_delete_status(
+        &self,
+        request: Request<GetDeleteStatusRequest>,
+    ) -> Result<Response<GetDeleteStatusResponse>, Status> {
+        let mut repos = self.catalog.repositories().await;
+        let req = request.into_inner();
+
+        let namespace = repos
+            .namespaces()
+            .get_by_name(&req.namespace_name)
+            .await
+            .map_err(|e| Status::unknown(e.to_string()))?
+            .ok_or_else(|| {
+                Status::not_found(format!("Namespace {} not found", req.namespace_name))
+            })?;
+
+        let table = repos
+            .tables()
+            .get_by_namespace_and_name(namespace.id, &req.table_name)
+            .await
+            .map_err(|e| Status::unknown(e.to_string()))?
+            .ok_or_else(|| Status::not_found(format!("Table {} not found", req.table_name)))?;
+
+        let deletes = delete_status(repos.as_mut(), table.id).await.map_err(|e| {
+            warn!(
+                error=%e,
+                %req.namespace_name,
+                %req.table_name,
+                "failed to get the delete status for table"
+            );
+            Status::unknown(e.to_string())
+        })?;
+
+        let response = GetDeleteStatusResponse { deletes };
+
+        Ok(Response::new(response))
+    }
+}
+
+/// Determine how far each tombstone of the table was applied.
+///
+/// A tombstone applies to the data of its shard written before it. That data is either still
+/// buffered by the ingester, until the shard is persisted past the tombstone, or in the parquet
+/// files of the shard with a smaller max sequence number that overlap the time range of the
+/// tombstone. The compactor records the files it applied the tombstone to as processed.
+async fn delete_status(
+    repos: &mut dyn RepoCollection,
+    table_id: TableId,
+) -> iox_catalog::interface::Result<Vec<DeleteStatus>> {
+    let tombstones = repos.tombstones().list_by_table(table_id).await?;
+    if tombstones.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let shards = repos.shards().list().await?;
+    let parquet_files = repos
+        .parquet_files()
+        .list_by_table_not_to_delete(table_id)
+        .await?;
+
+    let mut deletes = Vec::with_capacity(tombstones.len());
+    for tombstone in tombstones {
+        let shard_persisted = shards
+            .iter()
+            .find(|s| s.id == tombstone.shard_id)
+            .map(|s| s.min_unpersisted_sequence_number > tombstone.sequence_number)
+            .unwrap_or_default();
+
+        let mut applied_parquet_file_ids = vec![];
+        let mut pending_parquet_file_ids = vec![];
+        for file in parquet_files.iter().filter(|f| {
+            f.shard_id == tombstone.shard_id
+                && f.max_sequence_number < tombstone.sequence_number
+                && f.min_time <= tombstone.max_time
+                && f.max_time >= tombstone.min_time
+        }) {
+            if repos
+                .processed_tombstones()
+                .exist(file.id, tombstone.id)
+                .await?
+            {
+                applied_parquet_file_ids.push(file.id.get());
+            } else {
+                pending_parquet_file_ids.push(file.id.get());
+            }
+        }
+
+        deletes.push(DeleteStatus {
+            tombstone_id: tombstone.id.get(),
+            shard_id: tombstone.shard_id.get(),
+            sequence_number: tombstone.sequence_number.get(),
+            min_time: tombstone.min_time.get(),
+            max_time: tombstone.max_time.get(),
+            serialized_predicate: tombstone.serialized_predicate,
+            shard_persisted,
+            fully_applied: shard_persisted && pending_parquet_file_ids.is_empty(),
+            applied_parquet_file_ids,
+            pending_parquet_file_ids,
+        });
+    }
+
+    Ok(deletes)
 }
 
 // converts the catalog ParquetFile to protobuf
@@ -320,4 +423,123 @@ mod tests {
             .collect();
         assert_eq!(expect, response.partitions);
     }
+
+    #[tokio::test]
+    async fn get_delete_status() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog = Arc::new(MemCatalog::new(metrics));
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("iox-shared").await.unwrap();
+        let pool = repos
+            .query_pools()
+            .create_or_get("iox-shared")
+            .await
+            .unwrap();
+        let shard = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(1))
+            .await
+            .unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("catalog_delete_test", None, topic.id, pool.id)
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("delete_test_table", namespace.id)
+            .await
+            .unwrap();
+        let partition = repos
+            .partitions()
+            .create_or_get("foo".into(), shard.id, table.id)
+            .await
+            .unwrap();
+        let p1params = ParquetFileParams {
+            shard_id: shard.id,
+            namespace_id: namespace.id,
+            table_id: table.id,
+            partition_id: partition.id,
+            object_store_id: Uuid::new_v4(),
+            max_sequence_number: SequenceNumber::new(40),
+            min_time: Timestamp::new(1),
+            max_time: Timestamp::new(5),
+            file_size_bytes: 2343,
+            row_count: 29,
+            compaction_level: CompactionLevel::Initial,
+            created_at: Timestamp::new(2343),
+            column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+        };
+        // written after the delete
+        let p2params = ParquetFileParams {
+            object_store_id: Uuid::new_v4(),
+            max_sequence_number: SequenceNumber::new(70),
+            ..p1params.clone()
+        };
+        // outside of the time range of the delete
+        let p3params = ParquetFileParams {
+            object_store_id: Uuid::new_v4(),
+            min_time: Timestamp::new(20),
+            max_time: Timestamp::new(30),
+            ..p1params.clone()
+        };
+        let p1 = repos.parquet_files().create(p1params).await.unwrap();
+        repos.parquet_files().create(p2params).await.unwrap();
+        repos.parquet_files().create(p3params).await.unwrap();
+        let tombstone = repos
+            .tombstones()
+            .create_or_get(
+                table.id,
+                shard.id,
+                SequenceNumber::new(50),
+                Timestamp::new(1),
+                Timestamp::new(10),
+                "tag=\"a\"",
+            )
+            .await
+            .unwrap();
+        drop(repos);
+
+        let grpc = super::CatalogService::new(Arc::clone(&catalog) as _);
+        let status = || async {
+            let request = GetDeleteStatusRequest {
+                namespace_name: "catalog_delete_test".into(),
+                table_name: "delete_test_table".into(),
+            };
+            let mut deletes = grpc
+                .get_delete_status(Request::new(request))
+                .await
+                .expect("rpc request should succeed")
+                .into_inner()
+                .deletes;
+            assert_eq!(deletes.len(), 1);
+            deletes.pop().unwrap()
+        };
+
+        let got = status().await;
+        assert_eq!(got.tombstone_id, tombstone.id.get());
+        assert!(!got.shard_persisted);
+        assert!(got.applied_parquet_file_ids.is_empty());
+        assert_eq!(got.pending_parquet_file_ids, vec![p1.id.get()]);
+        assert!(!got.fully_applied);
+
+        let mut repos = catalog.repositories().await;
+        repos
+            .shards()
+            .update_min_unpersisted_sequence_number(shard.id, SequenceNumber::new(51))
+            .await
+            .unwrap();
+        repos
+            .processed_tombstones()
+            .create(p1.id, tombstone.id)
+            .await
+            .unwrap();
+        drop(repos);
+
+        let got = status().await;
+        assert!(got.shard_persisted);
+        assert_eq!(got.applied_parquet_file_ids, vec![p1.id.get()]);
+        assert!(got.pending_parquet_file_ids.is_empty());
+        assert!(got.fully_applied);
+    }
 }
@@ -1,19 +1,26 @@
 //! Implements the native gRPC IOx query API using Arrow Flight
 
+use arrow::datatypes::Schema as ArrowSchema;
 use arrow::error::ArrowError;
 use arrow_flight::{
+    flight_descriptor::DescriptorType,
     flight_service_server::{FlightService as Flight, FlightServiceServer as FlightServer},
-    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
-    HandshakeRequest, HandshakeResponse, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, IpcMessage, PutResult, SchemaAsIpc, SchemaResult, Ticket,
 };
 use arrow_util::optimize::{optimize_record_batch, optimize_schema};
 use bytes::{Bytes, BytesMut};
-use data_types::NamespaceNameError;
-use datafusion::{error::DataFusionError, physical_plan::ExecutionPlan};
+use data_types::{ApiTokenPermission, NamespaceNameError, ParquetFileId};
+use datafusion::{error::DataFusionError, physical_plan::ExecutionPlan, scalar::ScalarValue};
+use flatbuffers::FlatBufferBuilder;
 use futures::{SinkExt, Stream, StreamExt};
 use generated_types::influxdata::iox::querier::v1 as proto;
+use iox_catalog::authz::Authorizer;
 use iox_query::{
-    exec::{ExecutionContextProvider, IOxSessionContext},
+    exec::{
+        sql_permission, ExecutionContextProvider, IOxSessionContext, PersistedWatermarks,
+        QueryStatistics, QueryStatisticsSummary,
+    },
     QueryCompletedToken, QueryNamespace,
 };
 use observability_deps::tracing::{debug, info, warn};
@@ -21,14 +28,29 @@ use pin_project::{pin_project, pinned_drop};
 use prost::Message;
 use serde::Deserialize;
 use service_common::{datafusion_error_to_tonic_code, planner::Planner, QueryNamespaceProvider};
-use snafu::{ResultExt, Snafu};
-use std::{fmt::Debug, pin::Pin, sync::Arc, task::Poll, time::Instant};
+use service_grpc_authz::{authz_error_to_status, token_from_metadata};
+use snafu::{OptionExt, ResultExt, Snafu};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    pin::Pin,
+    sync::Arc,
+    task::Poll,
+    time::{Duration, Instant},
+};
 use tokio::task::JoinHandle;
-use tonic::{Request, Response, Streaming};
+use tonic::{metadata::MetadataMap, Request, Response, Streaming};
 use trace::{ctx::SpanContext, span::SpanExt};
 use trace_http::ctx::{RequestLogContext, RequestLogContextExt};
 use tracker::InstrumentedAsyncOwnedSemaphorePermit;
 
+/// gRPC metadata key carrying the identity a query runs on behalf of.
+///
+/// IOx does not authenticate this identity: it must be set by a gateway that authenticates the
+/// caller and strips the header from client requests. When authorization is enabled, the header
+/// is ignored and queries run on behalf of the owner of their API token instead.
+pub const IDENTITY_HEADER: &str = "iox-identity";
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -47,6 +69,21 @@ pub enum Error {
     #[snafu(display("Namespace {} not found", namespace_name))]
     NamespaceNotFound { namespace_name: String },
 
+    #[snafu(display("Table {} not found in namespace {}", table_name, namespace_name))]
+    TableNotFound {
+        namespace_name: String,
+        table_name: String,
+    },
+
+    #[snafu(display("Invalid flight descriptor: {}", reason))]
+    InvalidDescriptor { reason: String },
+
+    #[snafu(display("Invalid query parameter {}: {}", name, reason))]
+    InvalidQueryParam { name: String, reason: String },
+
+    #[snafu(display("Invalid list flights criteria: {}", source))]
+    InvalidCriteria { source: std::string::FromUtf8Error },
+
     #[snafu(display(
         "Internal error reading points from namespace {}: {}",
         namespace_name,
@@ -82,6 +119,10 @@ impl From<Error> for tonic::Status {
         let msg = "Error handling Flight gRPC request";
         match err {
             Error::NamespaceNotFound { .. }
+            | Error::TableNotFound { .. }
+            | Error::InvalidDescriptor { .. }
+            | Error::InvalidCriteria { .. }
+            | Error::InvalidQueryParam { .. }
             | Error::InvalidTicket { .. }
             | Error::InvalidJsonTicket { .. }
             | Error::InvalidQuery { .. }
@@ -102,8 +143,11 @@ impl Error {
         let msg = self.to_string();
 
         let code = match self {
-            Self::NamespaceNotFound { .. } => tonic::Code::NotFound,
-            Self::InvalidTicket { .. }
+            Self::NamespaceNotFound { .. } | Self::TableNotFound { .. } => tonic::Code::NotFound,
+            Self::InvalidDescriptor { .. }
+            | Self::InvalidCriteria { .. }
+            | Self::InvalidQueryParam { .. }
+            | Self::InvalidTicket { .. }
             | Self::InvalidJsonTicket { .. }
             | Self::InvalidQuery { .. }
             | Self::InvalidNamespaceName { .. } => tonic::Code::InvalidArgument,
@@ -124,6 +168,16 @@ type TonicStream<T> = Pin<Box<dyn Stream<Item = Result<T, tonic::Status>> + Send
 struct ReadInfo {
     namespace_name: String,
     sql_query: String,
+    #[serde(default)]
+    max_unpersisted_staleness_ns: Option<u64>,
+    #[serde(default)]
+    additional_namespaces: Vec<String>,
+    #[serde(default)]
+    params: Vec<proto::QueryParam>,
+    #[serde(default)]
+    include_statistics: bool,
+    #[serde(default)]
+    debug_parquet_file_ids: Vec<i64>,
 }
 
 impl ReadInfo {
@@ -147,6 +201,107 @@ impl ReadInfo {
         Ok(Self {
             namespace_name: read_info.namespace_name,
             sql_query: read_info.sql_query,
+            max_unpersisted_staleness_ns: read_info.max_unpersisted_staleness_ns,
+            additional_namespaces: read_info.additional_namespaces,
+            params: read_info.params,
+            include_statistics: read_info.include_statistics,
+            debug_parquet_file_ids: read_info.debug_parquet_file_ids,
+        })
+    }
+}
+
+/// Convert the query parameters of a ticket into the values bound to the query.
+fn query_params(params: Vec<proto::QueryParam>) -> Result<HashMap<String, ScalarValue>> {
+    use proto::query_param::Value;
+
+    let mut values = HashMap::with_capacity(params.len());
+    for proto::QueryParam { name, value } in params {
+        let value = match value.context(InvalidQueryParamSnafu {
+            name: &name,
+            reason: "no value",
+        })? {
+            Value::BoolValue(v) => ScalarValue::Boolean(Some(v)),
+            Value::Int64Value(v) => ScalarValue::Int64(Some(v)),
+            Value::Uint64Value(v) => ScalarValue::UInt64(Some(v)),
+            Value::DoubleValue(v) => ScalarValue::Float64(Some(v)),
+            Value::StringValue(v) => ScalarValue::Utf8(Some(v)),
+            Value::TimestampValue(v) => ScalarValue::TimestampNanosecond(Some(v), None),
+        };
+
+        if values.insert(name.clone(), value).is_some() {
+            return InvalidQueryParamSnafu {
+                name,
+                reason: "duplicate parameter",
+            }
+            .fail();
+        }
+    }
+
+    Ok(values)
+}
+
+/// The table addressed by the path `[namespace, table]` of a [`FlightDescriptor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TablePath {
+    namespace_name: String,
+    table_name: String,
+}
+
+impl TablePath {
+    fn try_from_descriptor(descriptor: &FlightDescriptor) -> Result<Self> {
+        if descriptor.r#type() != DescriptorType::Path {
+            return InvalidDescriptorSnafu {
+                reason: "only path descriptors are supported",
+            }
+            .fail();
+        }
+
+        match descriptor.path.as_slice() {
+            [namespace_name, table_name] => Ok(Self {
+                namespace_name: namespace_name.clone(),
+                table_name: table_name.clone(),
+            }),
+            path => InvalidDescriptorSnafu {
+                reason: format!("expected path [namespace, table], got {:?}", path),
+            }
+            .fail(),
+        }
+    }
+
+    /// Build the [`FlightInfo`] of this table with the given `schema`.
+    ///
+    /// The single endpoint carries a ticket that selects all rows of the table.
+    fn flight_info(self, schema: &ArrowSchema) -> Result<FlightInfo> {
+        let options = arrow::ipc::writer::IpcWriteOptions::default();
+        let SchemaResult { schema } = SchemaAsIpc::new(schema, &options).into();
+
+        let mut ticket = BytesMut::new();
+        proto::ReadInfo {
+            namespace_name: self.namespace_name.clone(),
+            sql_query: format!("SELECT * FROM \"{}\"", self.table_name.replace('"', "\"\"")),
+            max_unpersisted_staleness_ns: None,
+            additional_namespaces: vec![],
+            params: vec![],
+            include_statistics: false,
+            debug_parquet_file_ids: vec![],
+        }
+        .encode(&mut ticket)
+        .context(SerializationSnafu)?;
+
+        Ok(FlightInfo {
+            schema,
+            flight_descriptor: Some(FlightDescriptor::new_path(vec![
+                self.namespace_name,
+                self.table_name,
+            ])),
+            endpoint: vec![FlightEndpoint {
+                ticket: Some(Ticket {
+                    ticket: ticket.to_vec(),
+                }),
+                location: vec![],
+            }],
+            total_records: -1,
+            total_bytes: -1,
         })
     }
 }
@@ -158,37 +313,121 @@ where
     S: QueryNamespaceProvider,
 {
     server: Arc<S>,
+
+    /// Authorizes the queries, if authorization is enabled.
+    authorizer: Option<Arc<Authorizer>>,
 }
 
-pub fn make_server<S>(server: Arc<S>) -> FlightServer<impl Flight>
+/// Create the Flight service, requiring an API token allowing to read the queried namespaces if
+/// `authorizer` is set. Statements writing to the namespace, or creating external tables, need
+/// a token allowing to write to it, or to administer it.
+pub fn make_server<S>(
+    server: Arc<S>,
+    authorizer: Option<Arc<Authorizer>>,
+) -> FlightServer<impl Flight>
 where
     S: QueryNamespaceProvider,
 {
-    FlightServer::new(FlightService { server })
+    FlightServer::new(FlightService { server, authorizer })
 }
 
 impl<S> FlightService<S>
 where
     S: QueryNamespaceProvider,
 {
+    /// Return an error unless the request with `metadata` has `permission` on all of
+    /// `namespaces`.
+    async fn authorize<'a>(
+        &self,
+        metadata: &MetadataMap,
+        namespaces: impl IntoIterator<Item = &'a str>,
+        permission: ApiTokenPermission,
+    ) -> Result<(), tonic::Status> {
+        let authorizer = match &self.authorizer {
+            Some(authorizer) => authorizer,
+            None => return Ok(()),
+        };
+
+        let token = token_from_metadata(metadata);
+        for namespace in namespaces {
+            authorizer
+                .authorize(token, Some(namespace), permission)
+                .await
+                .map_err(authz_error_to_status)?;
+        }
+        Ok(())
+    }
+
+    /// The identity the request with `metadata` runs on behalf of.
+    ///
+    /// If authorization is enabled, this is the owner of the API token of the request, otherwise
+    /// the identity set by the gateway in the [`IDENTITY_HEADER`].
+    async fn identity(&self, metadata: &MetadataMap) -> Result<Option<String>, tonic::Status> {
+        match &self.authorizer {
+            Some(authorizer) => authorizer
+                .identity(token_from_metadata(metadata))
+                .await
+                .map(Some)
+                .map_err(authz_error_to_status),
+            None => Ok(metadata
+                .get(IDENTITY_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(ToString::to_string)),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn run_query(
         &self,
         span_ctx: Option<SpanContext>,
         permit: InstrumentedAsyncOwnedSemaphorePermit,
         sql_query: String,
         namespace: String,
+        max_unpersisted_staleness: Option<Duration>,
+        additional_namespaces: Vec<String>,
+        params: Vec<proto::QueryParam>,
+        identity: Option<String>,
+        api_token: Option<String>,
+        include_statistics: bool,
+        debug_parquet_files: Option<HashSet<ParquetFileId>>,
     ) -> Result<Response<TonicStream<FlightData>>, tonic::Status> {
+        let params = query_params(params)?;
+
         let db = self
             .server
             .db(&namespace, span_ctx.child_span("get namespace"))
             .await
             .ok_or_else(|| tonic::Status::not_found(format!("Unknown namespace: {namespace}")))?;
 
-        let ctx = db.new_query_context(span_ctx);
+        let statistics = include_statistics.then(|| Arc::new(QueryStatistics::default()));
+        let ctx = db
+            .new_query_context(span_ctx.clone())
+            .with_max_unpersisted_staleness(max_unpersisted_staleness)
+            .with_identity(identity.as_deref())
+            .with_api_token(api_token.as_deref())
+            .with_statistics(statistics)
+            .with_debug_parquet_files(debug_parquet_files)
+            .with_persisted_watermarks(Some(Arc::new(PersistedWatermarks::default())));
+
+        for other in additional_namespaces {
+            let other_db = self
+                .server
+                .db(&other, span_ctx.child_span("get additional namespace"))
+                .await
+                .ok_or_else(|| tonic::Status::not_found(format!("Unknown namespace: {other}")))?;
+            let schema = other_db.user_schema_provider().ok_or_else(|| {
+                tonic::Status::unimplemented("Cross-namespace queries are not supported")
+            })?;
+            ctx.register_namespace_schema(&other, schema)
+                .context(QuerySnafu {
+                    namespace_name: &namespace,
+                })?;
+        }
+
         let query_completed_token = db.record_query(&ctx, "sql", Box::new(sql_query.clone()));
 
         let physical_plan = Planner::new(&ctx)
-            .sql(sql_query)
+            .sql_with_params(sql_query, params)
             .await
             .context(PlanningSnafu)?;
 
@@ -197,6 +436,32 @@ where
 
         Ok(Response::new(Box::pin(output) as TonicStream<FlightData>))
     }
+
+    /// Return the schema of the table at `path`, as it is sent by `do_get`.
+    ///
+    /// The schema is derived from the cached schema of the namespace, no query is planned.
+    async fn table_schema(
+        &self,
+        span_ctx: Option<SpanContext>,
+        path: &TablePath,
+    ) -> Result<ArrowSchema> {
+        let db = self
+            .server
+            .db(&path.namespace_name, span_ctx.child_span("get namespace"))
+            .await
+            .context(NamespaceNotFoundSnafu {
+                namespace_name: &path.namespace_name,
+            })?;
+
+        let schema = db
+            .table_schema(&path.table_name)
+            .context(TableNotFoundSnafu {
+                namespace_name: &path.namespace_name,
+                table_name: &path.table_name,
+            })?;
+
+        Ok(optimize_schema(&schema.as_arrow()))
+    }
 }
 
 #[tonic::async_trait]
@@ -214,9 +479,21 @@ where
 
     async fn get_schema(
         &self,
-        _request: Request<FlightDescriptor>,
+        request: Request<FlightDescriptor>,
     ) -> Result<Response<SchemaResult>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let span_ctx: Option<SpanContext> = request.extensions().get().cloned();
+        let path = TablePath::try_from_descriptor(request.get_ref())?;
+        self.authorize(
+            request.metadata(),
+            [path.namespace_name.as_str()],
+            ApiTokenPermission::Read,
+        )
+        .await?;
+
+        let schema = self.table_schema(span_ctx, &path).await?;
+        let options = arrow::ipc::writer::IpcWriteOptions::default();
+
+        Ok(Response::new(SchemaAsIpc::new(&schema, &options).into()))
     }
 
     async fn do_get(
@@ -226,7 +503,7 @@ where
         let external_span_ctx: Option<RequestLogContext> = request.extensions().get().cloned();
         let trace = external_span_ctx.format_jaeger();
         let span_ctx: Option<SpanContext> = request.extensions().get().cloned();
-        let ticket = request.into_inner();
+        let (metadata, _, ticket) = request.into_parts();
 
         // decode ticket
         let read_info = ReadInfo::decode_protobuf(&ticket.ticket).or_else(|_e| {
@@ -240,7 +517,35 @@ where
         let ReadInfo {
             namespace_name,
             sql_query,
+            max_unpersisted_staleness_ns,
+            additional_namespaces,
+            params,
+            include_statistics,
+            debug_parquet_file_ids,
         } = read_info?;
+        let max_unpersisted_staleness = max_unpersisted_staleness_ns.map(Duration::from_nanos);
+        let debug_parquet_files = (!debug_parquet_file_ids.is_empty()).then(|| {
+            debug_parquet_file_ids
+                .into_iter()
+                .map(ParquetFileId::new)
+                .collect::<HashSet<_>>()
+        });
+
+        // The statement may write to the queried namespace, the additional ones are only read.
+        self.authorize(
+            &metadata,
+            [namespace_name.as_str()],
+            sql_permission(&sql_query),
+        )
+        .await?;
+        self.authorize(
+            &metadata,
+            additional_namespaces.iter().map(String::as_str),
+            ApiTokenPermission::Read,
+        )
+        .await?;
+        let identity = self.identity(&metadata).await?;
+        let api_token = token_from_metadata(&metadata).map(ToString::to_string);
 
         let permit = self
             .server
@@ -249,10 +554,22 @@ where
 
         // Log after we acquire the permit and are about to start execution
         let start = Instant::now();
-        info!(%namespace_name, %sql_query, %trace, "Running SQL via flight do_get");
+        info!(%namespace_name, %sql_query, %trace, ?max_unpersisted_staleness, ?identity, ?debug_parquet_files, "Running SQL via flight do_get");
 
         let response = self
-            .run_query(span_ctx, permit, sql_query.clone(), namespace_name.clone())
+            .run_query(
+                span_ctx,
+                permit,
+                sql_query.clone(),
+                namespace_name.clone(),
+                max_unpersisted_staleness,
+                additional_namespaces,
+                params,
+                identity,
+                api_token,
+                include_statistics,
+                debug_parquet_files,
+            )
             .await;
 
         if let Err(e) = &response {
@@ -277,18 +594,69 @@ where
         Ok(Response::new(Box::pin(output) as Self::HandshakeStream))
     }
 
+    /// List a flight for each table of the namespace named by the criteria expression.
     async fn list_flights(
         &self,
-        _request: Request<Criteria>,
+        request: Request<Criteria>,
     ) -> Result<Response<Self::ListFlightsStream>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let span_ctx: Option<SpanContext> = request.extensions().get().cloned();
+        let (metadata, _, criteria) = request.into_parts();
+        let namespace_name =
+            String::from_utf8(criteria.expression).context(InvalidCriteriaSnafu)?;
+        self.authorize(
+            &metadata,
+            [namespace_name.as_str()],
+            ApiTokenPermission::Read,
+        )
+        .await?;
+
+        let db = self
+            .server
+            .db(&namespace_name, span_ctx.child_span("get namespace"))
+            .await
+            .context(NamespaceNotFoundSnafu {
+                namespace_name: &namespace_name,
+            })?;
+
+        let mut table_names = db.table_names();
+        table_names.sort();
+
+        let flights = table_names
+            .into_iter()
+            .filter_map(|table_name| {
+                // tables may be dropped from the cached schema concurrently
+                let schema = db.table_schema(&table_name)?;
+                let path = TablePath {
+                    namespace_name: namespace_name.clone(),
+                    table_name,
+                };
+                Some(
+                    path.flight_info(&optimize_schema(&schema.as_arrow()))
+                        .map_err(tonic::Status::from),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let output = futures::stream::iter(flights);
+        Ok(Response::new(Box::pin(output) as Self::ListFlightsStream))
     }
 
     async fn get_flight_info(
         &self,
-        _request: Request<FlightDescriptor>,
+        request: Request<FlightDescriptor>,
     ) -> Result<Response<FlightInfo>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let span_ctx: Option<SpanContext> = request.extensions().get().cloned();
+        let path = TablePath::try_from_descriptor(request.get_ref())?;
+        self.authorize(
+            request.metadata(),
+            [path.namespace_name.as_str()],
+            ApiTokenPermission::Read,
+        )
+        .await?;
+
+        let schema = self.table_schema(span_ctx, &path).await?;
+
+        Ok(Response::new(path.flight_info(&schema)?))
     }
 
     async fn do_put(
@@ -348,12 +716,19 @@ impl GetStream {
         let options = arrow::ipc::writer::IpcWriteOptions::default();
         let mut schema_flight_data: FlightData = SchemaAsIpc::new(&schema, &options).into();
 
-        // Add response metadata
+        // Add response metadata, the watermarks were recorded while planning the query
         let mut bytes = BytesMut::new();
-        let app_metadata = proto::AppMetadata {};
+        let app_metadata = proto::AppMetadata {
+            persisted_watermarks: ctx
+                .persisted_watermarks()
+                .map(|watermarks| watermarks.watermarks())
+                .unwrap_or_default(),
+            ..Default::default()
+        };
         prost::Message::encode(&app_metadata, &mut bytes).context(SerializationSnafu)?;
         schema_flight_data.app_metadata = bytes.to_vec();
 
+        let statistics = ctx.statistics();
         let mut stream_record_batches = ctx
             .execute_stream(Arc::clone(&physical_plan))
             .await
@@ -370,6 +745,10 @@ impl GetStream {
             while let Some(batch_or_err) = stream_record_batches.next().await {
                 match batch_or_err {
                     Ok(batch) => {
+                        if let Some(statistics) = &statistics {
+                            statistics.record_rows_returned(batch.num_rows() as u64);
+                        }
+
                         match optimize_record_batch(&batch, Arc::clone(&schema)) {
                             Ok(batch) => {
                                 let (flight_dictionaries, flight_batch) =
@@ -416,6 +795,15 @@ impl GetStream {
                 }
             }
 
+            if let Some(statistics) = statistics {
+                let summary = statistics.summary(physical_plan.as_ref());
+                let data = statistics_flight_data(summary).map_err(tonic::Status::from);
+                if tx.send(data).await.is_err() {
+                    // receiver is gone
+                    return;
+                }
+            }
+
             // if we get here, all is good
             query_completed_token.set_success()
         });
@@ -429,6 +817,54 @@ impl GetStream {
     }
 }
 
+/// Build the message without data that ends the response with the [`QueryStatisticsSummary`] of
+/// the query as its app metadata.
+fn statistics_flight_data(summary: QueryStatisticsSummary) -> Result<FlightData> {
+    let QueryStatisticsSummary {
+        parquet_bytes_read,
+        rows_scanned,
+        rows_returned,
+        ingester_partitions,
+        cache_hits,
+        cache_misses,
+    } = summary;
+    let app_metadata = proto::AppMetadata {
+        statistics: Some(proto::QueryStatistics {
+            parquet_bytes_read,
+            rows_scanned,
+            rows_returned,
+            ingester_partitions,
+            cache_hits,
+            cache_misses,
+        }),
+        ..Default::default()
+    };
+
+    let mut bytes = BytesMut::new();
+    prost::Message::encode(&app_metadata, &mut bytes).context(SerializationSnafu)?;
+
+    Ok(FlightData::new(
+        None,
+        IpcMessage(build_none_flight_msg()),
+        bytes.to_vec(),
+        vec![],
+    ))
+}
+
+fn build_none_flight_msg() -> Vec<u8> {
+    let mut fbb = FlatBufferBuilder::new();
+
+    let mut message = arrow::ipc::MessageBuilder::new(&mut fbb);
+    message.add_version(arrow::ipc::MetadataVersion::V5);
+    message.add_header_type(arrow::ipc::MessageHeader::NONE);
+    message.add_bodyLength(0);
+
+    let data = message.finish();
+    fbb.finish(data, None);
+
+    fbb.finished_data().to_vec()
+}
+
 #[pinned_drop]
 impl PinnedDrop for GetStream {
     fn drop(self: Pin<&mut Self>) {
@@ -464,7 +900,16 @@ impl Stream for GetStream {
 
 #[cfg(test)]
 mod tests {
+    use arrow::datatypes::{DataType, TimeUnit};
+    use data_types::Timestamp;
     use futures::Future;
+    use iox_catalog::{
+        authz::{generate_token, hash_token},
+        interface::Catalog,
+        mem::MemCatalog,
+    };
+    use iox_query::test::TestChunk;
+    use iox_time::SystemProvider;
     use metric::{Attributes, Metric, U64Gauge};
     use service_common::test_util::TestDatabaseStore;
     use tokio::pin;
@@ -487,6 +932,375 @@ mod tests {
 
         assert_eq!(read_info.namespace_name, "my_db");
         assert_eq!(read_info.sql_query, "SELECT 1;");
+        assert_eq!(read_info.max_unpersisted_staleness_ns, None);
+    }
+
+    #[test]
+    fn json_ticket_decoding_with_staleness() {
+        let ticket = Ticket {
+            ticket: br#"{"namespace_name": "my_db", "sql_query": "SELECT 1;", "max_unpersisted_staleness_ns": 60000000000}"#.to_vec(),
+        };
+
+        let read_info = ReadInfo::decode_json(&ticket.ticket).unwrap();
+
+        assert_eq!(read_info.namespace_name, "my_db");
+        assert_eq!(read_info.sql_query, "SELECT 1;");
+        assert_eq!(read_info.max_unpersisted_staleness_ns, Some(60_000_000_000));
+    }
+
+    #[test]
+    fn protobuf_ticket_decoding_with_staleness() {
+        let mut buf = BytesMut::new();
+        proto::ReadInfo {
+            namespace_name: "my_db".to_string(),
+            sql_query: "SELECT 1;".to_string(),
+            max_unpersisted_staleness_ns: Some(1_000),
+            additional_namespaces: vec!["other_db".to_string()],
+            params: vec![proto::QueryParam {
+                name: "start".to_string(),
+                value: Some(proto::query_param::Value::TimestampValue(1)),
+            }],
+            include_statistics: true,
+            debug_parquet_file_ids: vec![42],
+        }
+        .encode(&mut buf)
+        .unwrap();
+
+        let read_info = ReadInfo::decode_protobuf(&buf).unwrap();
+
+        assert_eq!(read_info.namespace_name, "my_db");
+        assert_eq!(read_info.sql_query, "SELECT 1;");
+        assert_eq!(read_info.max_unpersisted_staleness_ns, Some(1_000));
+        assert_eq!(
+            read_info.additional_namespaces,
+            vec!["other_db".to_string()]
+        );
+        assert_eq!(
+            query_params(read_info.params).unwrap(),
+            HashMap::from([(
+                "start".to_string(),
+                ScalarValue::TimestampNanosecond(Some(1), None)
+            )])
+        );
+        assert!(read_info.include_statistics);
+        assert_eq!(read_info.debug_parquet_file_ids, vec![42]);
+    }
+
+    #[test]
+    fn json_ticket_decoding_with_params() {
+        let ticket = Ticket {
+            ticket: br#"{"namespace_name": "my_db", "sql_query": "SELECT $x, $y;", "params": [{"name": "x", "int64Value": "1"}, {"name": "y", "stringValue": "a"}]}"#.to_vec(),
+        };
+
+        let read_info = ReadInfo::decode_json(&ticket.ticket).unwrap();
+
+        assert_eq!(
+            query_params(read_info.params).unwrap(),
+            HashMap::from([
+                ("x".to_string(), ScalarValue::Int64(Some(1))),
+                ("y".to_string(), ScalarValue::Utf8(Some("a".to_string()))),
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_query_params() {
+        let err = query_params(vec![proto::QueryParam {
+            name: "x".to_string(),
+            value: None,
+        }])
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid query parameter x: no value");
+
+        let param = proto::QueryParam {
+            name: "x".to_string(),
+            value: Some(proto::query_param::Value::BoolValue(true)),
+        };
+        let err = query_params(vec![param.clone(), param]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid query parameter x: duplicate parameter"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_schema() {
+        let service = test_service().await;
+
+        let descriptor = FlightDescriptor::new_path(vec!["my_db".into(), "h2o".into()]);
+        let schema_result = service
+            .get_schema(tonic::Request::new(descriptor))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_h2o_schema(&schema_result);
+
+        let descriptor = FlightDescriptor::new_path(vec!["my_db".into(), "o2".into()]);
+        let err = service
+            .get_schema(tonic::Request::new(descriptor))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+        assert_eq!(err.message(), "Table o2 not found in namespace my_db");
+
+        let descriptor = FlightDescriptor::new_path(vec!["other_db".into(), "h2o".into()]);
+        let err = service
+            .get_schema(tonic::Request::new(descriptor))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+
+        let descriptor = FlightDescriptor::new_path(vec!["my_db".into()]);
+        let err = service
+            .get_schema(tonic::Request::new(descriptor))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        let descriptor = FlightDescriptor::new_cmd(b"SELECT 1".to_vec());
+        let err = service
+            .get_schema(tonic::Request::new(descriptor))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_get_flight_info() {
+        let service = test_service().await;
+
+        let descriptor = FlightDescriptor::new_path(vec!["my_db".into(), "h2o".into()]);
+        let info = service
+            .get_flight_info(tonic::Request::new(descriptor.clone()))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_h2o_info(&info);
+        assert_eq!(info.flight_descriptor, Some(descriptor));
+
+        let descriptor = FlightDescriptor::new_path(vec!["my_db".into(), "o2".into()]);
+        let err = service
+            .get_flight_info(tonic::Request::new(descriptor))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_list_flights() {
+        let service = test_service().await;
+
+        let criteria = Criteria {
+            expression: b"my_db".to_vec(),
+        };
+        let flights = service
+            .list_flights(tonic::Request::new(criteria))
+            .await
+            .unwrap()
+            .into_inner()
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(flights.len(), 1);
+        assert_h2o_info(flights[0].as_ref().unwrap());
+
+        let criteria = Criteria {
+            expression: b"other_db".to_vec(),
+        };
+        let err = service
+            .list_flights(tonic::Request::new(criteria))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_do_get_statistics() {
+        let service = test_service().await;
+
+        let do_get = |include_statistics: bool| {
+            let ticket = Ticket {
+                ticket: format!(
+                    r#"{{"namespace_name": "my_db", "sql_query": "SELECT 1 UNION ALL SELECT 2;", "include_statistics": {include_statistics}}}"#
+                )
+                .into_bytes(),
+            };
+            service.do_get(tonic::Request::new(ticket))
+        };
+
+        let data = do_get(false)
+            .await
+            .unwrap()
+            .into_inner()
+            .collect::<Vec<_>>()
+            .await;
+        let last = data.last().unwrap().as_ref().unwrap();
+        let header = arrow::ipc::root_as_message(&last.data_header).unwrap();
+        assert_eq!(header.header_type(), arrow::ipc::MessageHeader::RecordBatch);
+
+        // the statistics are sent after the data
+        let data = do_get(true)
+            .await
+            .unwrap()
+            .into_inner()
+            .collect::<Vec<_>>()
+            .await;
+        let last = data.last().unwrap().as_ref().unwrap();
+        let header = arrow::ipc::root_as_message(&last.data_header).unwrap();
+        assert_eq!(header.header_type(), arrow::ipc::MessageHeader::NONE);
+        let app_metadata = proto::AppMetadata::decode(last.app_metadata.as_slice()).unwrap();
+        let statistics = app_metadata.statistics.unwrap();
+        assert_eq!(statistics.rows_returned, 2);
+        assert_eq!(statistics.parquet_bytes_read, 0);
+        assert_eq!(statistics.ingester_partitions, 0);
+    }
+
+    /// Create a service with a namespace `my_db`, containing the table `h2o`.
+    async fn test_service() -> FlightService<TestDatabaseStore> {
+        let test_storage = Arc::new(TestDatabaseStore::new());
+        test_storage.db_or_create("my_db").await.add_chunk(
+            "my_partition_key",
+            Arc::new(
+                TestChunk::new("h2o")
+                    .with_tag_column("state")
+                    .with_i64_field_column("temp")
+                    .with_time_column(),
+            ),
+        );
+
+        FlightService {
+            server: test_storage,
+            authorizer: None,
+        }
+    }
+
+    fn assert_h2o_schema(schema_result: &SchemaResult) {
+        let schema = ArrowSchema::try_from(schema_result).unwrap();
+
+        let mut fields = schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().as_str(), f.data_type().clone()))
+            .collect::<Vec<_>>();
+        fields.sort_by(|a, b| a.0.cmp(b.0));
+
+        // tags are sent as plain strings, as by do_get
+        assert_eq!(
+            fields,
+            vec![
+                ("state", DataType::Utf8),
+                ("temp", DataType::Int64),
+                ("time", DataType::Timestamp(TimeUnit::Nanosecond, None)),
+            ]
+        );
+    }
+
+    fn assert_h2o_info(info: &FlightInfo) {
+        assert_h2o_schema(&SchemaResult {
+            schema: info.schema.clone(),
+        });
+
+        assert_eq!(info.endpoint.len(), 1);
+        let ticket = info.endpoint[0].ticket.as_ref().unwrap();
+        let read_info = ReadInfo::decode_protobuf(&ticket.ticket).unwrap();
+        assert_eq!(read_info.namespace_name, "my_db");
+        assert_eq!(read_info.sql_query, r#"SELECT * FROM "h2o""#);
+    }
+
+    #[tokio::test]
+    async fn test_authz() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+        let token = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("foo").await.unwrap();
+            let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+            let namespace = repos
+                .namespaces()
+                .create("my_db", None, topic.id, pool.id)
+                .await
+                .unwrap();
+
+            let token = generate_token();
+            repos
+                .api_tokens()
+                .create(
+                    &hash_token(&token),
+                    Some(namespace.id),
+                    ApiTokenPermission::Read,
+                    "reader",
+                    Timestamp::new(0),
+                )
+                .await
+                .unwrap();
+            token
+        };
+
+        let mut service = test_service().await;
+        service.authorizer = Some(Arc::new(Authorizer::new(
+            catalog,
+            Arc::new(SystemProvider::new()),
+            Duration::from_secs(60),
+        )));
+
+        let request = |namespace: &str, token: Option<&str>| {
+            let mut request = tonic::Request::new(Criteria {
+                expression: namespace.as_bytes().to_vec(),
+            });
+            if let Some(token) = token {
+                request
+                    .metadata_mut()
+                    .insert("authorization", format!("Bearer {token}").parse().unwrap());
+            }
+            request
+        };
+
+        let err = service
+            .list_flights(request("my_db", None))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+
+        let err = service
+            .list_flights(request("my_db", Some("bananas")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+
+        let err = service
+            .list_flights(request("other_db", Some(&token)))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::PermissionDenied);
+
+        service
+            .list_flights(request("my_db", Some(&token)))
+            .await
+            .unwrap();
+
+        // A read token may not write to the namespace or create external tables.
+        for sql in [
+            "INSERT INTO h2o SELECT * FROM h2o",
+            "CREATE EXTERNAL TABLE t STORED AS CSV LOCATION '/etc/passwd'",
+        ] {
+            let mut request = tonic::Request::new(Ticket {
+                ticket: format!(r#"{{"namespace_name": "my_db", "sql_query": "{sql}"}}"#)
+                    .into_bytes(),
+            });
+            request
+                .metadata_mut()
+                .insert("authorization", format!("Bearer {token}").parse().unwrap());
+            let err = service.do_get(request).await.unwrap_err();
+            assert_eq!(err.code(), tonic::Code::PermissionDenied, "{sql}");
+        }
+
+        // The identity is that of the token owner, the identity header is ignored.
+        let mut metadata = request("my_db", Some(&token)).into_parts().0;
+        metadata.insert(IDENTITY_HEADER, "admin".parse().unwrap());
+        assert_eq!(
+            service.identity(&metadata).await.unwrap().as_deref(),
+            Some("reader")
+        );
     }
 
     #[tokio::test]
@@ -515,6 +1329,7 @@ mod tests {
 
         let service = FlightService {
             server: Arc::clone(&test_storage),
+            authorizer: None,
         };
         let ticket = Ticket {
             ticket: br#"{"namespace_name": "my_db", "sql_query": "SELECT 1;"}"#.to_vec(),
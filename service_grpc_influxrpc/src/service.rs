@@ -11,6 +11,7 @@ use crate::{
     input::GrpcInputs,
     StorageService,
 };
+use arrow::array::Int64Array;
 use data_types::{org_and_bucket_to_namespace, NamespaceName};
 use datafusion::error::DataFusionError;
 use futures::Stream;
@@ -771,13 +772,62 @@ where
         make_response(futures::stream::iter(results), permit)
     }
 
-    type ReadSeriesCardinalityStream = ReceiverStream<Result<Int64ValuesResponse, Status>>;
+    type ReadSeriesCardinalityStream =
+        StreamWithPermit<ReceiverStream<Result<Int64ValuesResponse, Status>>>;
 
     async fn read_series_cardinality(
         &self,
-        _req: tonic::Request<ReadSeriesCardinalityRequest>,
+        req: tonic::Request<ReadSeriesCardinalityRequest>,
     ) -> Result<tonic::Response<Self::ReadSeriesCardinalityStream>, Status> {
-        unimplemented!("read_series_cardinality not yet implemented. https://github.com/influxdata/influxdb_iox/issues/447");
+        let external_span_ctx: Option<RequestLogContext> = req.extensions().get().cloned();
+        let span_ctx: Option<SpanContext> = req.extensions().get().cloned();
+        let (tx, rx) = mpsc::channel(4);
+
+        let req = req.into_inner();
+        let permit = self
+            .db_store
+            .acquire_semaphore(span_ctx.child_span("query rate limit semaphore"))
+            .await;
+
+        let db_name = get_namespace_name(&req)?;
+        info!(
+            %db_name,
+            ?req.range,
+            predicate=%req.predicate.loggable(),
+            trace=%external_span_ctx.format_jaeger(),
+            "read_series_cardinality",
+        );
+
+        let db = self
+            .db_store
+            .db(&db_name, span_ctx.child_span("get namespace"))
+            .await
+            .context(NamespaceNotFoundSnafu { db_name: &db_name })?;
+
+        let ctx = db.new_query_context(span_ctx);
+        let mut query_completed_token =
+            db.record_query(&ctx, "read_series_cardinality", defer_json(&req));
+
+        let ReadSeriesCardinalityRequest {
+            read_series_cardinality_source: _source,
+            range,
+            predicate,
+        } = req;
+
+        let response =
+            read_series_cardinality_impl(Arc::clone(&db), db_name, range, predicate, &ctx)
+                .await
+                .map_err(|e| e.into_status());
+
+        if response.is_ok() {
+            query_completed_token.set_success();
+        }
+
+        tx.send(response)
+            .await
+            .expect("sending read_series_cardinality response to server");
+
+        make_response(ReceiverStream::new(rx), permit)
     }
 
     async fn capabilities(
@@ -1367,6 +1417,59 @@ where
     Ok(vec![response])
 }
 
+/// Count the distinct series (measurement and tag set, regardless of the field) that match the
+/// predicate.
+async fn read_series_cardinality_impl<N>(
+    db: Arc<N>,
+    db_name: NamespaceName<'static>,
+    range: Option<TimestampRange>,
+    rpc_predicate: Option<Predicate>,
+    ctx: &IOxSessionContext,
+) -> Result<Int64ValuesResponse, Error>
+where
+    N: QueryNamespace + ExecutionContextProvider + 'static,
+{
+    let db_name = db_name.as_str();
+
+    let rpc_predicate_string = format!("{:?}", rpc_predicate);
+
+    let predicate = InfluxRpcPredicateBuilder::default()
+        .set_range(range)
+        .rpc_predicate(rpc_predicate)
+        .context(ConvertingPredicateSnafu {
+            rpc_predicate_string,
+        })?
+        .build();
+
+    let plans = Planner::new(ctx)
+        .read_series_cardinality(db, predicate)
+        .await
+        .context(PlanningFilteringSeriesSnafu { db_name })?;
+
+    // Each plan counts the series of a single table.
+    let mut count = 0;
+    for plan in plans {
+        let batches = ctx
+            .run_logical_plan(plan)
+            .await
+            .context(FilteringSeriesSnafu { db_name })
+            .log_if_error("Running series cardinality plan")?;
+
+        for batch in batches {
+            let counts = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .expect("series count is an Int64 column");
+            count += counts.iter().flatten().sum::<i64>();
+        }
+    }
+
+    Ok(Int64ValuesResponse {
+        values: vec![count],
+    })
+}
+
 /// Launch async tasks that send the result of executing read_group to `tx`
 async fn query_group_impl<N>(
     db: Arc<N>,
@@ -1787,31 +1890,29 @@ mod tests {
     // correctly updated.
     fn grpc_request_metric_has_count(
         fixture: &Fixture,
-        path: &'static str,
-        status: &'static str,
+        method: &'static str,
+        code: &'static str,
         expected: u64,
     ) {
         let metrics = fixture
             .test_storage
             .metric_registry
-            .get_instrument::<Metric<U64Counter>>("grpc_requests")
+            .get_instrument::<Metric<U64Counter>>("grpc_rpc_requests")
             .unwrap();
 
         let observation = metrics
-            .get_observer(&Attributes::from([
-                (
-                    "path",
-                    format!("/influxdata.platform.storage.Storage/{}", path).into(),
-                ),
-                ("status", status.into()),
+            .get_observer(&Attributes::from(&[
+                ("service", "influxdata.platform.storage.Storage"),
+                ("method", method),
+                ("code", code),
             ]))
             .unwrap()
             .fetch();
 
         assert_eq!(
             observation, expected,
-            "\n\npath: {}\nstatus:{}\nobservation:{}\nexpected:{}\n\nAll metrics:\n\n{:#?}",
-            path, status, observation, expected, metrics
+            "\n\nmethod: {}\ncode:{}\nobservation:{}\nexpected:{}\n\nAll metrics:\n\n{:#?}",
+            method, code, observation, expected, metrics
         );
     }
 
@@ -1934,7 +2035,7 @@ mod tests {
         let expected_measurements = to_string_vec(&["h2o", "o2"]);
         assert_eq!(actual_measurements, expected_measurements);
 
-        grpc_request_metric_has_count(&fixture, "MeasurementNames", "ok", 3);
+        grpc_request_metric_has_count(&fixture, "MeasurementNames", "Ok", 3);
     }
 
     /// test the plumbing of the RPC layer for tag_keys -- specifically that
@@ -1996,7 +2097,7 @@ mod tests {
             )
             .await;
 
-        grpc_request_metric_has_count(&fixture, "TagKeys", "ok", 1);
+        grpc_request_metric_has_count(&fixture, "TagKeys", "Ok", 1);
     }
 
     #[tokio::test]
@@ -2030,7 +2131,7 @@ mod tests {
         let response = fixture.storage_client.tag_keys(request).await;
         assert_contains!(response.unwrap_err().to_string(), "Sugar we are going down");
 
-        grpc_request_metric_has_count(&fixture, "TagKeys", "server_error", 1);
+        grpc_request_metric_has_count(&fixture, "TagKeys", "Internal", 1);
     }
 
     /// test the plumbing of the RPC layer for measurement_tag_keys--
@@ -2101,7 +2202,7 @@ mod tests {
             )
             .await;
 
-        grpc_request_metric_has_count(&fixture, "MeasurementTagKeys", "ok", 1);
+        grpc_request_metric_has_count(&fixture, "MeasurementTagKeys", "Ok", 1);
     }
 
     #[tokio::test]
@@ -2137,7 +2238,7 @@ mod tests {
         let response = fixture.storage_client.measurement_tag_keys(request).await;
         assert_contains!(response.unwrap_err().to_string(), "This is an error");
 
-        grpc_request_metric_has_count(&fixture, "MeasurementTagKeys", "server_error", 1);
+        grpc_request_metric_has_count(&fixture, "MeasurementTagKeys", "Internal", 1);
     }
 
     /// test the plumbing of the RPC layer for tag_values -- specifically that
@@ -2175,7 +2276,7 @@ mod tests {
         let actual_tag_values = fixture.storage_client.tag_values(request).await.unwrap();
         assert_eq!(actual_tag_values, vec!["MA"]);
 
-        grpc_request_metric_has_count(&fixture, "TagValues", "ok", 1);
+        grpc_request_metric_has_count(&fixture, "TagValues", "Ok", 1);
     }
 
     /// test the plumbing of the RPC layer for tag_values
@@ -2219,7 +2320,7 @@ mod tests {
             "unexpected tag values while getting tag values for measurement names"
         );
 
-        grpc_request_metric_has_count(&fixture, "TagValues", "ok", 1);
+        grpc_request_metric_has_count(&fixture, "TagValues", "Ok", 1);
     }
 
     #[tokio::test]
@@ -2262,7 +2363,7 @@ mod tests {
             "unexpected tag values while getting tag values for field names"
         );
 
-        grpc_request_metric_has_count(&fixture, "TagValues", "ok", 1);
+        grpc_request_metric_has_count(&fixture, "TagValues", "Ok", 1);
     }
 
     #[tokio::test]
@@ -2327,10 +2428,10 @@ mod tests {
         );
 
         // error from backend error
-        grpc_request_metric_has_count(&fixture, "TagValues", "server_error", 1);
+        grpc_request_metric_has_count(&fixture, "TagValues", "Internal", 1);
 
         // error from bad utf8
-        grpc_request_metric_has_count(&fixture, "TagValues", "client_error", 1);
+        grpc_request_metric_has_count(&fixture, "TagValues", "InvalidArgument", 1);
     }
 
     #[tokio::test]
@@ -2637,7 +2738,7 @@ mod tests {
             "unexpected tag values while getting tag values",
         );
 
-        grpc_request_metric_has_count(&fixture, "MeasurementTagValues", "ok", 1);
+        grpc_request_metric_has_count(&fixture, "MeasurementTagValues", "Ok", 1);
     }
 
     #[tokio::test]
@@ -2681,7 +2782,7 @@ mod tests {
 
         assert_contains!(response_string, "Sugar we are going down");
 
-        grpc_request_metric_has_count(&fixture, "MeasurementTagValues", "server_error", 1);
+        grpc_request_metric_has_count(&fixture, "MeasurementTagValues", "Internal", 1);
     }
 
     #[tokio::test]
@@ -2798,7 +2899,7 @@ mod tests {
             "unexpected frames returned by query_series"
         );
 
-        grpc_request_metric_has_count(&fixture, "ReadFilter", "ok", 1);
+        grpc_request_metric_has_count(&fixture, "ReadFilter", "Ok", 1);
     }
 
     #[tokio::test]
@@ -2887,7 +2988,45 @@ mod tests {
         let response = fixture.storage_client.read_filter(request).await;
         assert_contains!(response.unwrap_err().to_string(), "Sugar we are going down");
 
-        grpc_request_metric_has_count(&fixture, "ReadFilter", "server_error", 1);
+        grpc_request_metric_has_count(&fixture, "ReadFilter", "Internal", 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_series_cardinality() {
+        test_helpers::maybe_start_logging();
+        // Start a test gRPC server on a randomally allocated port
+        let mut fixture = Fixture::new().await.expect("Connecting to test server");
+
+        let db_info = org_and_bucket();
+
+        // two fields per series should not be counted twice
+        let chunk = TestChunk::new("h2o")
+            .with_time_column()
+            .with_tag_column("tag1")
+            .with_i64_field_column("field_int")
+            .with_u64_column("field_uint")
+            .with_three_rows_of_data();
+
+        fixture
+            .test_storage
+            .db_or_create(db_info.db_name())
+            .await
+            .add_chunk("my_partition_key", Arc::new(chunk));
+
+        let request = ReadSeriesCardinalityRequest {
+            read_series_cardinality_source: Some(StorageClient::read_source(&db_info, 1)),
+            range: Some(make_timestamp_range(0, 100_000)),
+            predicate: None,
+        };
+
+        let values = fixture
+            .storage_client
+            .read_series_cardinality(request)
+            .await
+            .unwrap();
+        assert_eq!(values, vec![3]);
+
+        grpc_request_metric_has_count(&fixture, "ReadSeriesCardinality", "Ok", 1);
     }
 
     #[tokio::test]
@@ -2933,7 +3072,7 @@ mod tests {
         // DataFrame
         assert_eq!(frames.len(), 3);
 
-        grpc_request_metric_has_count(&fixture, "ReadGroup", "ok", 1);
+        grpc_request_metric_has_count(&fixture, "ReadGroup", "Ok", 1);
     }
 
     #[tokio::test]
@@ -2979,7 +3118,7 @@ mod tests {
             .to_string();
         assert_contains!(response_string, "Sugar we are going down");
 
-        grpc_request_metric_has_count(&fixture, "ReadGroup", "server_error", 1);
+        grpc_request_metric_has_count(&fixture, "ReadGroup", "Internal", 1);
     }
 
     #[tokio::test]
@@ -3033,7 +3172,7 @@ mod tests {
             "unexpected frames returned by query_groups"
         );
 
-        grpc_request_metric_has_count(&fixture, "ReadWindowAggregate", "ok", 1);
+        grpc_request_metric_has_count(&fixture, "ReadWindowAggregate", "Ok", 1);
     }
 
     #[tokio::test]
@@ -3145,7 +3284,7 @@ mod tests {
 
         assert_contains!(response_string, "Sugar we are going down");
 
-        grpc_request_metric_has_count(&fixture, "ReadWindowAggregate", "server_error", 1);
+        grpc_request_metric_has_count(&fixture, "ReadWindowAggregate", "Internal", 1);
     }
 
     #[tokio::test]
@@ -3191,7 +3330,7 @@ mod tests {
             "unexpected frames returned by measurement_fields"
         );
 
-        grpc_request_metric_has_count(&fixture, "MeasurementFields", "ok", 1);
+        grpc_request_metric_has_count(&fixture, "MeasurementFields", "Ok", 1);
     }
 
     #[tokio::test]
@@ -3941,9 +4080,11 @@ mod tests {
             let router = tonic::transport::Server::builder()
                 .layer(trace_http::tower::TraceLayer::new(
                     trace_header_parser,
-                    Arc::clone(&test_storage.metric_registry),
                     None,
-                    true,
+                    None,
+                ))
+                .layer(ioxd_common::rpc::metrics::RpcMetricsLayer::new(
+                    &test_storage.metric_registry,
                 ))
                 .add_service(service_grpc_testing::make_server())
                 .add_service(crate::make_server(Arc::clone(&test_storage)));
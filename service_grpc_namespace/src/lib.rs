@@ -2,12 +2,21 @@
 
 use std::sync::Arc;
 
-use data_types::{Namespace as CatalogNamespace, QueryPoolId, TopicId};
-use generated_types::influxdata::iox::namespace::v1::*;
-use iox_catalog::interface::Catalog;
+use data_types::{
+    Namespace as CatalogNamespace, NamespaceName, NamespaceNameRules, QueryPoolId, TopicId,
+};
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use generated_types::{
+    google::{AlreadyExists, FieldViolation, NotFound, ResourceType},
+    influxdata::iox::namespace::v1::*,
+};
+use iox_catalog::interface::{Catalog, Error as CatalogError};
 use observability_deps::tracing::warn;
 use tonic::{Request, Response, Status};
 
+/// The number of namespaces fetched from the catalog per query when listing them all.
+const LIST_PAGE_SIZE: usize = 1_000;
+
 /// Implementation of the gRPC namespace service
 #[derive(Debug)]
 pub struct NamespaceService {
@@ -15,6 +24,7 @@ pub struct NamespaceService {
     catalog: Arc<dyn Catalog>,
     topic_id: Option<TopicId>,
     query_id: Option<QueryPoolId>,
+    name_rules: NamespaceNameRules,
 }
 
 impl NamespaceService {
@@ -27,8 +37,52 @@ impl NamespaceService {
             catalog,
             topic_id,
             query_id,
+            name_rules: Default::default(),
         }
     }
+
+    /// Reject the creation of namespaces that violate `rules`.
+    pub fn with_name_rules(mut self, rules: NamespaceNameRules) -> Self {
+        self.name_rules = rules;
+        self
+    }
+
+    /// Stream all namespaces from the catalog in pages of at most `page_size`, in ascending ID
+    /// order.
+    ///
+    /// The catalog is queried lazily, one page at a time, and no connection is held between
+    /// pages.
+    fn namespace_pages(
+        &self,
+        page_size: usize,
+    ) -> BoxStream<'static, Result<Vec<CatalogNamespace>, Status>> {
+        let catalog = Arc::clone(&self.catalog);
+
+        futures::stream::try_unfold(Some(None), move |state| {
+            let catalog = Arc::clone(&catalog);
+            async move {
+                let after = match state {
+                    Some(v) => v,
+                    None => return Ok(None),
+                };
+
+                let mut repos = catalog.repositories().await;
+                let page = repos
+                    .namespaces()
+                    .list_paged(after, page_size)
+                    .await
+                    .map_err(|e| {
+                        warn!(error=%e, "failed to retrieve namespaces from catalog");
+                        Status::not_found(e.to_string())
+                    })?;
+
+                let next = (page.len() == page_size).then(|| page.last().map(|ns| ns.id));
+                Ok(Some((page, next)))
+            }
+        })
+        .try_filter(|page| futures::future::ready(!page.is_empty()))
+        .boxed()
+    }
 }
 
 #[tonic::async_trait]
@@ -37,17 +91,30 @@ impl namespace_service_server::NamespaceService for NamespaceService {
         &self,
         _request: Request<GetNamespacesRequest>,
     ) -> Result<Response<GetNamespacesResponse>, Status> {
-        let mut repos = self.catalog.repositories().await;
+        // The response holds every namespace; ListNamespaces streams them instead.
+        let namespaces = self.namespace_pages(LIST_PAGE_SIZE).try_concat().await?;
 
-        let namespaces = repos.namespaces().list().await.map_err(|e| {
-            warn!(error=%e, "failed to retrieve namespaces from catalog");
-            Status::not_found(e.to_string())
-        })?;
         Ok(Response::new(GetNamespacesResponse {
             namespaces: namespaces.into_iter().map(namespace_to_proto).collect(),
         }))
     }
 
+    type ListNamespacesStream = BoxStream<'static, Result<ListNamespacesResponse, Status>>;
+
+    async fn list_namespaces(
+        &self,
+        _request: Request<ListNamespacesRequest>,
+    ) -> Result<Response<Self::ListNamespacesStream>, Status> {
+        let pages = self
+            .namespace_pages(LIST_PAGE_SIZE)
+            .map_ok(|page| ListNamespacesResponse {
+                namespaces: page.into_iter().map(namespace_to_proto).collect(),
+            })
+            .boxed();
+
+        Ok(Response::new(pages))
+    }
+
     // create a namespace
     async fn create_namespace(
         &self,
@@ -57,9 +124,20 @@ impl namespace_service_server::NamespaceService for NamespaceService {
             return Err(Status::invalid_argument("topic_id or query_id not set"));
         }
 
-        let mut repos = self.catalog.repositories().await;
-
         let req = request.into_inner();
+
+        let name = NamespaceName::try_from(req.name.as_str()).map_err(|e| FieldViolation {
+            field: "name".to_string(),
+            description: e.to_string(),
+        })?;
+        self.name_rules
+            .validate(&name)
+            .map_err(|e| FieldViolation {
+                field: "name".to_string(),
+                description: e.to_string(),
+            })?;
+
+        let mut repos = self.catalog.repositories().await;
         let namespace = repos
             .namespaces()
             .create(
@@ -71,7 +149,12 @@ impl namespace_service_server::NamespaceService for NamespaceService {
             .await
             .map_err(|e| {
                 warn!(error=%e, %req.name, "failed to create namespace");
-                Status::internal(e.to_string())
+                match e {
+                    CatalogError::NameExists { name } => {
+                        Status::from(AlreadyExists::new(ResourceType::Namespace, name))
+                    }
+                    e => Status::internal(e.to_string()),
+                }
             })?;
 
         Ok(Response::new(create_namespace_to_proto(namespace)))
@@ -90,12 +173,164 @@ impl namespace_service_server::NamespaceService for NamespaceService {
             .await
             .map_err(|e| {
                 warn!(error=%e, %req.name, "failed to update namespace retention");
-                Status::not_found(e.to_string())
+                match e {
+                    CatalogError::NamespaceNotFoundByName { name } => {
+                        Status::from(NotFound::new(ResourceType::Namespace, name))
+                    }
+                    e => Status::internal(e.to_string()),
+                }
             })?;
         Ok(Response::new(UpdateNamespaceRetentionResponse {
             namespace: Some(namespace_to_proto(namespace)),
         }))
     }
+
+    async fn update_namespace_query_ranges(
+        &self,
+        request: Request<UpdateNamespaceQueryRangesRequest>,
+    ) -> Result<Response<UpdateNamespaceQueryRangesResponse>, Status> {
+        let req = request.into_inner();
+        validate_query_ranges(req.default_query_range_ns, req.max_query_range_ns)?;
+
+        let mut repos = self.catalog.repositories().await;
+        let namespace = repos
+            .namespaces()
+            .update_query_ranges(
+                &req.name,
+                req.default_query_range_ns,
+                req.max_query_range_ns,
+            )
+            .await
+            .map_err(|e| {
+                warn!(error=%e, %req.name, "failed to update namespace query ranges");
+                match e {
+                    CatalogError::NamespaceNotFoundByName { name } => {
+                        Status::from(NotFound::new(ResourceType::Namespace, name))
+                    }
+                    e => Status::internal(e.to_string()),
+                }
+            })?;
+        Ok(Response::new(UpdateNamespaceQueryRangesResponse {
+            namespace: Some(namespace_to_proto(namespace)),
+        }))
+    }
+
+    async fn update_namespace_row_ttl(
+        &self,
+        request: Request<UpdateNamespaceRowTtlRequest>,
+    ) -> Result<Response<UpdateNamespaceRowTtlResponse>, Status> {
+        let req = request.into_inner();
+        validate_row_ttl(req.row_ttl_ns, req.row_ttl_column.as_deref())?;
+
+        let mut repos = self.catalog.repositories().await;
+        let namespace = repos
+            .namespaces()
+            .update_row_ttl(&req.name, req.row_ttl_ns, req.row_ttl_column)
+            .await
+            .map_err(|e| {
+                warn!(error=%e, %req.name, "failed to update namespace row ttl");
+                match e {
+                    CatalogError::NamespaceNotFoundByName { name } => {
+                        Status::from(NotFound::new(ResourceType::Namespace, name))
+                    }
+                    e => Status::internal(e.to_string()),
+                }
+            })?;
+        Ok(Response::new(UpdateNamespaceRowTtlResponse {
+            namespace: Some(namespace_to_proto(namespace)),
+        }))
+    }
+
+    async fn update_namespace_topic(
+        &self,
+        request: Request<UpdateNamespaceTopicRequest>,
+    ) -> Result<Response<UpdateNamespaceTopicResponse>, Status> {
+        let req = request.into_inner();
+
+        let mut repos = self.catalog.repositories().await;
+        let topic = repos
+            .topics()
+            .get_by_name(&req.topic)
+            .await
+            .map_err(|e| {
+                warn!(error=%e, %req.topic, "failed to look up topic");
+                Status::internal(e.to_string())
+            })?
+            .ok_or_else(|| FieldViolation {
+                field: "topic".to_string(),
+                description: format!("no topic named {}", req.topic),
+            })?;
+
+        let namespace = repos
+            .namespaces()
+            .update_topic(&req.name, topic.id)
+            .await
+            .map_err(|e| {
+                warn!(error=%e, %req.name, "failed to update namespace topic");
+                match e {
+                    CatalogError::NamespaceNotFoundByName { name } => {
+                        Status::from(NotFound::new(ResourceType::Namespace, name))
+                    }
+                    e => Status::internal(e.to_string()),
+                }
+            })?;
+        Ok(Response::new(UpdateNamespaceTopicResponse {
+            namespace: Some(namespace_to_proto(namespace)),
+        }))
+    }
+}
+
+/// Reject query ranges that are not positive, and a default range longer than the maximum, as
+/// it would fail every query that does not restrict the time.
+fn validate_query_ranges(
+    default_query_range_ns: Option<i64>,
+    max_query_range_ns: Option<i64>,
+) -> Result<(), FieldViolation> {
+    for (field, range) in [
+        ("default_query_range_ns", default_query_range_ns),
+        ("max_query_range_ns", max_query_range_ns),
+    ] {
+        if matches!(range, Some(range) if range <= 0) {
+            return Err(FieldViolation {
+                field: field.to_string(),
+                description: "query range must be positive".to_string(),
+            });
+        }
+    }
+
+    if let (Some(default), Some(max)) = (default_query_range_ns, max_query_range_ns) {
+        if default > max {
+            return Err(FieldViolation {
+                field: "default_query_range_ns".to_string(),
+                description: "default query range exceeds the maximum query range".to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Reject a row expiry that is not positive, and an expiry field that can not hold the expiry
+/// time of a row.
+fn validate_row_ttl(
+    row_ttl_ns: Option<i64>,
+    row_ttl_column: Option<&str>,
+) -> Result<(), FieldViolation> {
+    if matches!(row_ttl_ns, Some(ttl) if ttl <= 0) {
+        return Err(FieldViolation {
+            field: "row_ttl_ns".to_string(),
+            description: "row ttl must be positive".to_string(),
+        });
+    }
+
+    if matches!(row_ttl_column, Some(column) if column.is_empty() || column == "time") {
+        return Err(FieldViolation {
+            field: "row_ttl_column".to_string(),
+            description: "row ttl column must name a field".to_string(),
+        });
+    }
+
+    Ok(())
 }
 
 fn namespace_to_proto(namespace: CatalogNamespace) -> Namespace {
@@ -103,15 +338,333 @@ fn namespace_to_proto(namespace: CatalogNamespace) -> Namespace {
         id: namespace.id.get(),
         name: namespace.name.clone(),
         retention_period_ns: namespace.retention_period_ns,
+        default_query_range_ns: namespace.default_query_range_ns,
+        max_query_range_ns: namespace.max_query_range_ns,
+        row_ttl_ns: namespace.row_ttl_ns,
+        row_ttl_column: namespace.row_ttl_column,
     }
 }
 
 fn create_namespace_to_proto(namespace: CatalogNamespace) -> CreateNamespaceResponse {
     CreateNamespaceResponse {
-        namespace: Some(Namespace {
-            id: namespace.id.get(),
-            name: namespace.name.clone(),
-            retention_period_ns: namespace.retention_period_ns,
-        }),
+        namespace: Some(namespace_to_proto(namespace)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use generated_types::influxdata::iox::namespace::v1::namespace_service_server::NamespaceService as _;
+    use iox_catalog::mem::MemCatalog;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_list_namespaces() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+
+        let service = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("iox-shared").await.unwrap();
+            let query_pool = repos
+                .query_pools()
+                .create_or_get("iox-shared")
+                .await
+                .unwrap();
+            for name in ["bananas", "platanos", "apples", "pears"] {
+                repos
+                    .namespaces()
+                    .create(name, None, topic.id, query_pool.id)
+                    .await
+                    .unwrap();
+            }
+            NamespaceService::new(Arc::clone(&catalog), Some(topic.id), Some(query_pool.id))
+        };
+
+        // A full last page is followed by no empty page.
+        let pages = service
+            .namespace_pages(2)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            pages
+                .iter()
+                .map(|page| page.iter().map(|ns| ns.name.as_str()).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            [vec!["bananas", "platanos"], vec!["apples", "pears"]]
+        );
+
+        let pages = service
+            .namespace_pages(3)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(pages.iter().map(Vec::len).collect::<Vec<_>>(), [3, 1]);
+
+        let streamed = service
+            .list_namespaces(Request::new(ListNamespacesRequest {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .map_ok(|page| page.namespaces)
+            .try_concat()
+            .await
+            .unwrap();
+        let all = service
+            .get_namespaces(Request::new(GetNamespacesRequest {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .namespaces;
+        assert_eq!(streamed.len(), 4);
+        assert_eq!(streamed, all);
+    }
+
+    #[tokio::test]
+    async fn test_create_namespace_name_rules() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+
+        let (topic, query_pool) = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("iox-shared").await.unwrap();
+            let query_pool = repos
+                .query_pools()
+                .create_or_get("iox-shared")
+                .await
+                .unwrap();
+            (topic, query_pool)
+        };
+
+        let service =
+            NamespaceService::new(Arc::clone(&catalog), Some(topic.id), Some(query_pool.id))
+                .with_name_rules(NamespaceNameRules {
+                    reserved_prefixes: vec!["_internal".to_string()],
+                    ..Default::default()
+                });
+
+        for name in ["_internal_bananas", "bananas\n", ""] {
+            let err = service
+                .create_namespace(Request::new(CreateNamespaceRequest {
+                    name: name.to_string(),
+                    retention_period_ns: None,
+                }))
+                .await
+                .expect_err("invalid namespace name should be rejected");
+            assert_eq!(err.code(), tonic::Code::InvalidArgument, "{}", name);
+        }
+
+        let resp = service
+            .create_namespace(Request::new(CreateNamespaceRequest {
+                name: "bananas".to_string(),
+                retention_period_ns: None,
+            }))
+            .await
+            .expect("valid namespace should be created")
+            .into_inner();
+        assert_eq!(resp.namespace.unwrap().name, "bananas");
+
+        let err = service
+            .create_namespace(Request::new(CreateNamespaceRequest {
+                name: "bananas".to_string(),
+                retention_period_ns: None,
+            }))
+            .await
+            .expect_err("duplicate namespace should be rejected");
+        assert_eq!(err.code(), tonic::Code::AlreadyExists);
+
+        // Only the valid namespace was created.
+        let namespaces = catalog
+            .repositories()
+            .await
+            .namespaces()
+            .list()
+            .await
+            .unwrap();
+        assert_eq!(namespaces.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_update_namespace_query_ranges() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+
+        let (topic, query_pool) = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("iox-shared").await.unwrap();
+            let query_pool = repos
+                .query_pools()
+                .create_or_get("iox-shared")
+                .await
+                .unwrap();
+            repos
+                .namespaces()
+                .create("bananas", None, topic.id, query_pool.id)
+                .await
+                .unwrap();
+            (topic, query_pool)
+        };
+
+        let service =
+            NamespaceService::new(Arc::clone(&catalog), Some(topic.id), Some(query_pool.id));
+
+        let request = |name: &str, default: Option<i64>, max: Option<i64>| {
+            Request::new(UpdateNamespaceQueryRangesRequest {
+                name: name.to_string(),
+                default_query_range_ns: default,
+                max_query_range_ns: max,
+            })
+        };
+
+        let namespace = service
+            .update_namespace_query_ranges(request("bananas", Some(10), Some(20)))
+            .await
+            .expect("valid query ranges should be set")
+            .into_inner()
+            .namespace
+            .unwrap();
+        assert_eq!(namespace.default_query_range_ns, Some(10));
+        assert_eq!(namespace.max_query_range_ns, Some(20));
+
+        for (default, max) in [(Some(0), None), (None, Some(-1)), (Some(30), Some(20))] {
+            let err = service
+                .update_namespace_query_ranges(request("bananas", default, max))
+                .await
+                .expect_err("invalid query ranges should be rejected");
+            assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        }
+
+        let err = service
+            .update_namespace_query_ranges(request("platanos", None, None))
+            .await
+            .expect_err("unknown namespace should be rejected");
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_update_namespace_row_ttl() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+
+        let (topic, query_pool) = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("iox-shared").await.unwrap();
+            let query_pool = repos
+                .query_pools()
+                .create_or_get("iox-shared")
+                .await
+                .unwrap();
+            repos
+                .namespaces()
+                .create("bananas", None, topic.id, query_pool.id)
+                .await
+                .unwrap();
+            (topic, query_pool)
+        };
+
+        let service =
+            NamespaceService::new(Arc::clone(&catalog), Some(topic.id), Some(query_pool.id));
+
+        let request = |name: &str, ttl: Option<i64>, column: Option<&str>| {
+            Request::new(UpdateNamespaceRowTtlRequest {
+                name: name.to_string(),
+                row_ttl_ns: ttl,
+                row_ttl_column: column.map(ToString::to_string),
+            })
+        };
+
+        let namespace = service
+            .update_namespace_row_ttl(request("bananas", Some(10), Some("expires")))
+            .await
+            .expect("valid row ttl should be set")
+            .into_inner()
+            .namespace
+            .unwrap();
+        assert_eq!(namespace.row_ttl_ns, Some(10));
+        assert_eq!(namespace.row_ttl_column.as_deref(), Some("expires"));
+
+        for (ttl, column) in [(Some(0), None), (None, Some("")), (None, Some("time"))] {
+            let err = service
+                .update_namespace_row_ttl(request("bananas", ttl, column))
+                .await
+                .expect_err("invalid row ttl should be rejected");
+            assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        }
+
+        let err = service
+            .update_namespace_row_ttl(request("platanos", None, None))
+            .await
+            .expect_err("unknown namespace should be rejected");
+        assert_eq!(err.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_update_namespace_topic() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+
+        let (topic, query_pool) = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("iox-shared").await.unwrap();
+            let query_pool = repos
+                .query_pools()
+                .create_or_get("iox-shared")
+                .await
+                .unwrap();
+            repos
+                .namespaces()
+                .create("bananas", None, topic.id, query_pool.id)
+                .await
+                .unwrap();
+            (topic, query_pool)
+        };
+        let noisy = catalog
+            .repositories()
+            .await
+            .topics()
+            .create_or_get("noisy")
+            .await
+            .unwrap();
+
+        let service =
+            NamespaceService::new(Arc::clone(&catalog), Some(topic.id), Some(query_pool.id));
+
+        let request = |name: &str, topic: &str| {
+            Request::new(UpdateNamespaceTopicRequest {
+                name: name.to_string(),
+                topic: topic.to_string(),
+            })
+        };
+
+        let namespace = service
+            .update_namespace_topic(request("bananas", "noisy"))
+            .await
+            .expect("known topic should be set")
+            .into_inner()
+            .namespace
+            .unwrap();
+        assert_eq!(namespace.name, "bananas");
+        let got = catalog
+            .repositories()
+            .await
+            .namespaces()
+            .get_by_name("bananas")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(got.topic_id, noisy.id);
+
+        let err = service
+            .update_namespace_topic(request("bananas", "unknown"))
+            .await
+            .expect_err("unknown topic should be rejected");
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+
+        let err = service
+            .update_namespace_topic(request("platanos", "noisy"))
+            .await
+            .expect_err("unknown namespace should be rejected");
+        assert_eq!(err.code(), tonic::Code::NotFound);
     }
 }
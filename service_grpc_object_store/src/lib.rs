@@ -15,7 +15,10 @@
 )]
 
 use futures::{stream::BoxStream, StreamExt};
-use generated_types::influxdata::iox::object_store::v1::*;
+use generated_types::{
+    google::{NotFound, PreconditionViolation, ResourceType},
+    influxdata::iox::object_store::v1::*,
+};
 use iox_catalog::interface::Catalog;
 use object_store::DynObjectStore;
 use observability_deps::tracing::*;
@@ -65,7 +68,7 @@ impl object_store_service_server::ObjectStoreService for ObjectStoreService {
                 warn!(error=%e, %req.uuid, "failed to get parquet_file by object store id");
                 Status::unknown(e.to_string())
             })?
-            .ok_or_else(|| Status::not_found(req.uuid))?;
+            .ok_or_else(|| NotFound::new(ResourceType::ParquetFile, req.uuid.clone()))?;
 
         let path = ParquetFilePath::new(
             parquet_file.namespace_id,
@@ -76,11 +79,16 @@ impl object_store_service_server::ObjectStoreService for ObjectStoreService {
         );
         let path = path.object_store_path();
 
-        let res = self
-            .object_store
-            .get(&path)
-            .await
-            .map_err(|e| Status::unknown(e.to_string()))?;
+        let res = self.object_store.get(&path).await.map_err(|e| match e {
+            // the file is still in the catalog, but was already deleted from the object store
+            object_store::Error::NotFound { .. } => {
+                Status::from(PreconditionViolation::ParquetFileInvalidState(format!(
+                    "parquet file {} is not in the object store",
+                    req.uuid
+                )))
+            }
+            e => Status::unknown(e.to_string()),
+        })?;
 
         let rx = Box::pin(res.into_stream().map(|next| match next {
             Ok(data) => Ok(GetParquetFileByObjectStoreIdResponse {
@@ -160,7 +168,7 @@ mod tests {
             Arc::clone(&catalog)
         };
 
-        let object_store = Arc::new(InMemory::new());
+        let object_store: Arc<DynObjectStore> = Arc::new(InMemory::new());
 
         let path = ParquetFilePath::new(
             p1.namespace_id,
@@ -175,7 +183,7 @@ mod tests {
 
         object_store.put(&path, data.clone()).await.unwrap();
 
-        let grpc = super::ObjectStoreService::new(catalog, object_store);
+        let grpc = super::ObjectStoreService::new(catalog, Arc::clone(&object_store));
         let request = GetParquetFileByObjectStoreIdRequest {
             uuid: p1.object_store_id.to_string(),
         };
@@ -188,5 +196,37 @@ mod tests {
         let response = response.next().await.unwrap().unwrap();
 
         assert_eq!(response.data, data);
+
+        // a file that is not in the catalog is not found
+        let request = GetParquetFileByObjectStoreIdRequest {
+            uuid: Uuid::new_v4().to_string(),
+        };
+        let err = grpc
+            .get_parquet_file_by_object_store_id(Request::new(request.clone()))
+            .await
+            .err()
+            .expect("rpc request should fail");
+        assert_eq!(err.code(), tonic::Code::NotFound);
+        let not_found = generated_types::google::decode_not_found(&err)
+            .next()
+            .unwrap();
+        assert_eq!(not_found.resource_type, ResourceType::ParquetFile);
+        assert_eq!(not_found.resource_name, request.uuid);
+
+        // a file that is in the catalog but was deleted from the object store
+        object_store.delete(&path).await.unwrap();
+        let request = GetParquetFileByObjectStoreIdRequest {
+            uuid: p1.object_store_id.to_string(),
+        };
+        let err = grpc
+            .get_parquet_file_by_object_store_id(Request::new(request))
+            .await
+            .err()
+            .expect("rpc request should fail");
+        assert_eq!(err.code(), tonic::Code::FailedPrecondition);
+        assert!(matches!(
+            generated_types::google::decode_precondition_violation(&err).next(),
+            Some(PreconditionViolation::ParquetFileInvalidState(_))
+        ));
     }
 }
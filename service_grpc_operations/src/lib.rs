@@ -0,0 +1,454 @@
+//! gRPC service for the long-running operations recorded in the catalog.
+
+#![deny(rustdoc::broken_intra_doc_links, rustdoc::bare_urls, rust_2018_idioms)]
+#![warn(
+    missing_copy_implementations,
+    missing_debug_implementations,
+    missing_docs,
+    clippy::explicit_iter_loop,
+    clippy::future_not_send,
+    clippy::use_self,
+    clippy::clone_on_ref_ptr,
+    clippy::todo,
+    clippy::dbg_macro
+)]
+
+use data_types::{Operation, OperationId, OperationStatus};
+use generated_types::{
+    google::{
+        longrunning::{self as proto, operation, operations_server},
+        protobuf::{Any, Empty},
+        rpc,
+    },
+    influxdata::iox::operations::v1::{OperationMetadata, OPERATION_METADATA},
+    protobuf_type_url,
+};
+use iox_catalog::interface::Catalog;
+use iox_time::{Time, TimeProvider};
+use observability_deps::tracing::*;
+use prost::Message;
+use std::{sync::Arc, time::Duration};
+use tonic::{Code, Request, Response, Status};
+
+/// How long `WaitOperation` waits if the request has no timeout.
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often `WaitOperation` checks whether the operation completed.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Implementation of the `google.longrunning.Operations` gRPC service for the operations recorded
+/// in the catalog.
+///
+/// The operations of all nodes are served. They are executed elsewhere and can therefore neither
+/// be cancelled nor deleted through this service.
+#[derive(Debug)]
+pub struct OperationsService {
+    catalog: Arc<dyn Catalog>,
+    time_provider: Arc<dyn TimeProvider>,
+}
+
+impl OperationsService {
+    /// Create a new operations service serving the operations in `catalog`
+    pub fn new(catalog: Arc<dyn Catalog>) -> Self {
+        let time_provider = catalog.time_provider();
+        Self {
+            catalog,
+            time_provider,
+        }
+    }
+
+    async fn get(&self, name: &str) -> Result<Operation, Status> {
+        let id = name
+            .parse()
+            .map(OperationId::new)
+            .map_err(|_| Status::invalid_argument(format!("invalid operation name: {name}")))?;
+
+        self.catalog
+            .repositories()
+            .await
+            .operations()
+            .get_by_id(id)
+            .await
+            .map_err(|e| {
+                warn!(error=%e, %id, "failed to get operation");
+                Status::internal(e.to_string())
+            })?
+            .ok_or_else(|| Status::not_found(format!("operation not found: {id}")))
+    }
+}
+
+#[tonic::async_trait]
+impl operations_server::Operations for OperationsService {
+    async fn list_operations(
+        &self,
+        request: Request<proto::ListOperationsRequest>,
+    ) -> Result<Response<proto::ListOperationsResponse>, Status> {
+        let filter = Filter::parse(&request.into_inner().filter)?;
+
+        let operations = self
+            .catalog
+            .repositories()
+            .await
+            .operations()
+            .list()
+            .await
+            .map_err(|e| {
+                warn!(error=%e, "failed to list operations");
+                Status::internal(e.to_string())
+            })?;
+
+        let now = self.time_provider.now();
+        let operations = operations
+            .into_iter()
+            .filter(|o| filter.matches(o))
+            .map(|o| to_proto(o, now))
+            .collect();
+
+        Ok(Response::new(proto::ListOperationsResponse {
+            operations,
+            next_page_token: String::new(),
+        }))
+    }
+
+    async fn get_operation(
+        &self,
+        request: Request<proto::GetOperationRequest>,
+    ) -> Result<Response<proto::Operation>, Status> {
+        let operation = self.get(&request.into_inner().name).await?;
+
+        Ok(Response::new(to_proto(operation, self.time_provider.now())))
+    }
+
+    async fn delete_operation(
+        &self,
+        _request: Request<proto::DeleteOperationRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        Err(Status::unimplemented(
+            "completed operations are deleted by retention",
+        ))
+    }
+
+    async fn cancel_operation(
+        &self,
+        _request: Request<proto::CancelOperationRequest>,
+    ) -> Result<Response<Empty>, Status> {
+        Err(Status::unimplemented(
+            "operations recorded in the catalog cannot be cancelled",
+        ))
+    }
+
+    async fn wait_operation(
+        &self,
+        request: Request<proto::WaitOperationRequest>,
+    ) -> Result<Response<proto::Operation>, Status> {
+        let request = request.into_inner();
+        let timeout = match request.timeout {
+            Some(timeout) => {
+                let seconds = u64::try_from(timeout.seconds)
+                    .map_err(|_| Status::invalid_argument("negative timeout"))?;
+                let nanos = u32::try_from(timeout.nanos)
+                    .map_err(|_| Status::invalid_argument("negative timeout"))?;
+                Duration::new(seconds, nanos)
+            }
+            None => DEFAULT_WAIT_TIMEOUT,
+        };
+
+        let wait = async {
+            loop {
+                let operation = self.get(&request.name).await?;
+                if operation.status.is_done() {
+                    return Ok(operation);
+                }
+                tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+            }
+        };
+
+        let operation = match tokio::time::timeout(timeout, wait).await {
+            Ok(res) => res?,
+            // Return the latest state once the timeout passed.
+            Err(_) => self.get(&request.name).await?,
+        };
+
+        Ok(Response::new(to_proto(operation, self.time_provider.now())))
+    }
+}
+
+/// The conditions of a `ListOperations` filter.
+///
+/// A filter is a whitespace separated list of `kind=<kind>`, `status=<status>` and
+/// `node=<node>` terms that must all match, e.g. `kind=persist status=running`.
+#[derive(Debug, Default)]
+struct Filter {
+    kind: Option<String>,
+    status: Option<OperationStatus>,
+    node: Option<String>,
+}
+
+impl Filter {
+    fn parse(filter: &str) -> Result<Self, Status> {
+        let mut parsed = Self::default();
+
+        for term in filter.split_whitespace() {
+            let (key, value) = term
+                .split_once('=')
+                .ok_or_else(|| Status::invalid_argument(format!("invalid filter term: {term}")))?;
+            match key {
+                "kind" => parsed.kind = Some(value.to_string()),
+                "status" => parsed.status = Some(value.parse().map_err(Status::invalid_argument)?),
+                "node" => parsed.node = Some(value.to_string()),
+                _ => {
+                    return Err(Status::invalid_argument(format!(
+                        "invalid filter key: {key}"
+                    )))
+                }
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    fn matches(&self, operation: &Operation) -> bool {
+        self.kind
+            .as_ref()
+            .map_or(true, |kind| &operation.kind == kind)
+            && self
+                .status
+                .map_or(true, |status| operation.status == status)
+            && self
+                .node
+                .as_ref()
+                .map_or(true, |node| &operation.node == node)
+    }
+}
+
+/// Convert a catalog [`Operation`] into its protobuf representation at time `now`.
+///
+/// An operation is a single task, so its metadata counts exactly one task.
+fn to_proto(operation: Operation, now: Time) -> proto::Operation {
+    let done = operation.status.is_done();
+
+    let started = Time::from_timestamp_nanos(operation.created_at.get());
+    let ended = operation
+        .completed_at
+        .map(|t| Time::from_timestamp_nanos(t.get()))
+        .unwrap_or(now);
+    let wall_nanos = ended
+        .checked_duration_since(started)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+
+    let metadata = OperationMetadata {
+        description: operation.description,
+        cpu_nanos: 0,
+        wall_nanos,
+        total_count: 1,
+        pending_count: (!done).into(),
+        success_count: (operation.status == OperationStatus::Success).into(),
+        error_count: matches!(
+            operation.status,
+            OperationStatus::Error | OperationStatus::Interrupted
+        )
+        .into(),
+        cancelled_count: (operation.status == OperationStatus::Cancelled).into(),
+    };
+
+    let error = |code: Code, message: String| {
+        Some(operation::Result::Error(rpc::Status {
+            code: code as i32,
+            message,
+            details: vec![],
+        }))
+    };
+    let result = match operation.status {
+        OperationStatus::Running | OperationStatus::Success => None,
+        OperationStatus::Error => error(Code::Internal, "operation failed".to_string()),
+        OperationStatus::Cancelled => error(Code::Cancelled, "operation cancelled".to_string()),
+        OperationStatus::Interrupted => error(
+            Code::Aborted,
+            format!("operation interrupted by a restart of {}", operation.node),
+        ),
+    };
+
+    proto::Operation {
+        name: operation.id.to_string(),
+        metadata: Some(Any {
+            type_url: protobuf_type_url(OPERATION_METADATA),
+            value: metadata.encode_to_vec().into(),
+        }),
+        done,
+        result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_types::Timestamp;
+    use generated_types::google::longrunning::{
+        operations_server::Operations, IoxOperation, ListOperationsRequest,
+    };
+    use iox_catalog::mem::MemCatalog;
+
+    async fn list(service: &OperationsService, filter: &str) -> Result<Vec<IoxOperation>, Status> {
+        let response = service
+            .list_operations(Request::new(ListOperationsRequest {
+                filter: filter.to_string(),
+                ..Default::default()
+            }))
+            .await?;
+
+        Ok(response
+            .into_inner()
+            .operations
+            .into_iter()
+            .map(|o| o.try_into().unwrap())
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn test_operations() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+
+        let mut repos = catalog.repositories().await;
+        let operations = repos.operations();
+        let running = operations
+            .create(
+                "persist",
+                "persist partition 1",
+                "ingester-1",
+                Timestamp::new(0),
+            )
+            .await
+            .unwrap();
+        let failed = operations
+            .create(
+                "persist",
+                "persist partition 2",
+                "ingester-1",
+                Timestamp::new(0),
+            )
+            .await
+            .unwrap();
+        operations
+            .complete(failed.id, OperationStatus::Error, Timestamp::new(10))
+            .await
+            .unwrap();
+        let interrupted = operations
+            .create(
+                "persist",
+                "persist partition 3",
+                "ingester-2",
+                Timestamp::new(0),
+            )
+            .await
+            .unwrap();
+        operations
+            .interrupt_running("ingester-2", Timestamp::new(20))
+            .await
+            .unwrap();
+        // release the lock of the in-memory catalog
+        drop(repos);
+
+        let service = OperationsService::new(Arc::clone(&catalog));
+
+        let listed = list(&service, "").await.unwrap();
+        assert_eq!(listed.len(), 3);
+
+        assert_eq!(listed[0].id, running.id.get() as usize);
+        assert!(!listed[0].is_done());
+        assert!(listed[0].error().is_none());
+        assert_eq!(listed[0].metadata.description, "persist partition 1");
+        assert_eq!(listed[0].metadata.pending_count, 1);
+
+        assert!(listed[1].is_done());
+        assert_eq!(listed[1].error().unwrap().code, Code::Internal as i32);
+        assert_eq!(listed[1].metadata.error_count, 1);
+        assert_eq!(listed[1].metadata.wall_nanos, 10);
+
+        assert!(listed[2].is_done());
+        assert_eq!(listed[2].error().unwrap().code, Code::Aborted as i32);
+        assert_eq!(listed[2].id, interrupted.id.get() as usize);
+
+        let listed = list(&service, "node=ingester-1 status=running")
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, running.id.get() as usize);
+        assert!(list(&service, "kind=compact").await.unwrap().is_empty());
+
+        let err = list(&service, "status=unknown").await.unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+        let err = list(&service, "partition=1").await.unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+
+        let operation = service
+            .get_operation(Request::new(proto::GetOperationRequest {
+                name: failed.id.to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(operation.done);
+        let err = service
+            .get_operation(Request::new(proto::GetOperationRequest {
+                name: "42".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_wait_operation() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+        let running = catalog
+            .repositories()
+            .await
+            .operations()
+            .create(
+                "persist",
+                "persist partition 1",
+                "ingester-1",
+                Timestamp::new(0),
+            )
+            .await
+            .unwrap();
+
+        let service = Arc::new(OperationsService::new(Arc::clone(&catalog)));
+        let wait = |timeout: Option<Duration>| {
+            let service = Arc::clone(&service);
+            let name = running.id.to_string();
+            async move {
+                service
+                    .wait_operation(Request::new(proto::WaitOperationRequest {
+                        name,
+                        timeout: timeout.map(|t| generated_types::google::protobuf::Duration {
+                            seconds: t.as_secs() as i64,
+                            nanos: t.subsec_nanos() as i32,
+                        }),
+                    }))
+                    .await
+                    .unwrap()
+                    .into_inner()
+            }
+        };
+
+        // The latest state is returned after the timeout.
+        let operation = wait(Some(Duration::from_millis(10))).await;
+        assert!(!operation.done);
+
+        let waiting = tokio::spawn(wait(None));
+        catalog
+            .repositories()
+            .await
+            .operations()
+            .complete(running.id, OperationStatus::Success, Timestamp::new(10))
+            .await
+            .unwrap();
+        let operation = waiting.await.unwrap();
+        assert!(operation.done);
+        assert!(operation.result.is_none());
+    }
+}
@@ -38,6 +38,69 @@ impl schema_service_server::SchemaService for SchemaService {
             .map(Arc::new)?;
         Ok(Response::new(schema_to_proto(schema)))
     }
+
+    async fn get_table_schema(
+        &self,
+        request: Request<GetTableSchemaRequest>,
+    ) -> Result<Response<GetTableSchemaResponse>, Status> {
+        let mut repos = self.catalog.repositories().await;
+
+        let req = request.into_inner();
+        let catalog_error = |e: iox_catalog::interface::Error| {
+            warn!(error=%e, %req.namespace, %req.table, "failed to retrieve table schema");
+            Status::internal(e.to_string())
+        };
+
+        let namespace = repos
+            .namespaces()
+            .get_by_name(&req.namespace)
+            .await
+            .map_err(catalog_error)?
+            .ok_or_else(|| Status::not_found(format!("namespace {} not found", req.namespace)))?;
+        let table = repos
+            .tables()
+            .get_by_namespace_and_name(namespace.id, &req.table)
+            .await
+            .map_err(catalog_error)?
+            .ok_or_else(|| {
+                Status::not_found(format!(
+                    "table {} not found in namespace {}",
+                    req.table, req.namespace
+                ))
+            })?;
+        let mut columns = repos
+            .columns()
+            .list_by_table_id(table.id)
+            .await
+            .map_err(catalog_error)?;
+        columns.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(Response::new(GetTableSchemaResponse {
+            table_id: table.id.get(),
+            columns: columns.iter().map(column_to_proto).collect(),
+        }))
+    }
+}
+
+fn column_to_proto(c: &data_types::Column) -> ColumnMetadata {
+    let influx_type = match c.column_type {
+        data_types::ColumnType::Tag => column_metadata::InfluxType::Tag,
+        data_types::ColumnType::Time => column_metadata::InfluxType::Time,
+        data_types::ColumnType::I64
+        | data_types::ColumnType::U64
+        | data_types::ColumnType::F64
+        | data_types::ColumnType::Bool
+        | data_types::ColumnType::String => column_metadata::InfluxType::Field,
+    };
+
+    ColumnMetadata {
+        id: c.id.get(),
+        name: c.name.clone(),
+        column_type: c.column_type as i32,
+        influx_type: influx_type as i32,
+        nullable: c.column_type != data_types::ColumnType::Time,
+        created_at: c.created_at.map(|t| t.get()),
+    }
 }
 
 fn schema_to_proto(schema: Arc<data_types::NamespaceSchema>) -> GetSchemaResponse {
@@ -138,4 +201,92 @@ mod tests {
             vec![&"schema_test_column".to_string()]
         );
     }
+
+    #[tokio::test]
+    async fn test_table_schema() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog = Arc::new(MemCatalog::new(metrics));
+        let table_id = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("franz").await.unwrap();
+            let pool = repos.query_pools().create_or_get("franz").await.unwrap();
+            let namespace = repos
+                .namespaces()
+                .create("namespace_schema_test", None, topic.id, pool.id)
+                .await
+                .unwrap();
+            let table = repos
+                .tables()
+                .create_or_get("schema_test_table", namespace.id)
+                .await
+                .unwrap();
+            for (name, column_type) in [
+                ("time", ColumnType::Time),
+                ("host", ColumnType::Tag),
+                ("load", ColumnType::F64),
+            ] {
+                repos
+                    .columns()
+                    .create_or_get(name, table.id, column_type)
+                    .await
+                    .unwrap();
+            }
+            repos
+                .tables()
+                .create_or_get("other_table", namespace.id)
+                .await
+                .unwrap();
+            table.id
+        };
+
+        let grpc = super::SchemaService::new(Arc::clone(&catalog) as _);
+        let response = grpc
+            .get_table_schema(Request::new(GetTableSchemaRequest {
+                namespace: "namespace_schema_test".to_string(),
+                table: "schema_test_table".to_string(),
+            }))
+            .await
+            .expect("rpc request should succeed")
+            .into_inner();
+
+        assert_eq!(response.table_id, table_id.get());
+        let columns = response
+            .columns
+            .iter()
+            .map(|c| {
+                (
+                    c.name.as_str(),
+                    column_metadata::InfluxType::from_i32(c.influx_type).unwrap(),
+                    c.nullable,
+                    c.created_at.is_some(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            columns,
+            [
+                ("host", column_metadata::InfluxType::Tag, true, true),
+                ("load", column_metadata::InfluxType::Field, true, true),
+                ("time", column_metadata::InfluxType::Time, false, true),
+            ]
+        );
+        assert_eq!(
+            response.columns[1].column_type,
+            column_schema::ColumnType::F64 as i32
+        );
+
+        for (namespace, table) in [
+            ("namespace_schema_test", "missing_table"),
+            ("missing_namespace", "schema_test_table"),
+        ] {
+            let status = grpc
+                .get_table_schema(Request::new(GetTableSchemaRequest {
+                    namespace: namespace.to_string(),
+                    table: table.to_string(),
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(status.code(), tonic::Code::NotFound);
+        }
+    }
 }
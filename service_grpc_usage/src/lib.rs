@@ -0,0 +1,204 @@
+//! Implementation of the usage gRPC service
+
+use std::{collections::HashMap, sync::Arc};
+
+use data_types::NamespaceUsage as CatalogNamespaceUsage;
+use generated_types::{
+    google::{NotFound, ResourceType},
+    influxdata::iox::usage::v1::*,
+};
+use iox_catalog::interface::Catalog;
+use observability_deps::tracing::warn;
+use tonic::{Request, Response, Status};
+
+/// Implementation of the gRPC usage service
+#[derive(Debug)]
+pub struct UsageService {
+    /// Catalog.
+    catalog: Arc<dyn Catalog>,
+}
+
+impl UsageService {
+    pub fn new(catalog: Arc<dyn Catalog>) -> Self {
+        Self { catalog }
+    }
+}
+
+#[tonic::async_trait]
+impl usage_service_server::UsageService for UsageService {
+    async fn get_namespace_usage(
+        &self,
+        request: Request<GetNamespaceUsageRequest>,
+    ) -> Result<Response<GetNamespaceUsageResponse>, Status> {
+        let mut repos = self.catalog.repositories().await;
+
+        let req = request.into_inner();
+        let namespace = repos
+            .namespaces()
+            .get_by_name(&req.namespace)
+            .await
+            .map_err(|e| {
+                warn!(error=%e, %req.namespace, "failed to retrieve namespace from catalog");
+                Status::internal(e.to_string())
+            })?
+            .ok_or_else(|| NotFound::new(ResourceType::Namespace, req.namespace.clone()))?;
+
+        let usage = repos
+            .namespaces()
+            .get_usage(namespace.id)
+            .await
+            .map_err(|e| {
+                warn!(error=%e, %req.namespace, "failed to retrieve namespace usage from catalog");
+                Status::internal(e.to_string())
+            })?
+            .unwrap_or_else(|| CatalogNamespaceUsage::new(namespace.id));
+
+        Ok(Response::new(GetNamespaceUsageResponse {
+            usage: Some(usage_to_proto(usage, namespace.name)),
+        }))
+    }
+
+    async fn list_namespace_usage(
+        &self,
+        _request: Request<ListNamespaceUsageRequest>,
+    ) -> Result<Response<ListNamespaceUsageResponse>, Status> {
+        let mut repos = self.catalog.repositories().await;
+
+        let mut names: HashMap<_, _> = repos
+            .namespaces()
+            .list()
+            .await
+            .map_err(|e| {
+                warn!(error=%e, "failed to retrieve namespaces from catalog");
+                Status::internal(e.to_string())
+            })?
+            .into_iter()
+            .map(|namespace| (namespace.id, namespace.name))
+            .collect();
+
+        let usage = repos
+            .namespaces()
+            .list_usage()
+            .await
+            .map_err(|e| {
+                warn!(error=%e, "failed to retrieve namespace usage from catalog");
+                Status::internal(e.to_string())
+            })?
+            .into_iter()
+            .filter_map(|usage| {
+                let name = names.remove(&usage.namespace_id)?;
+                Some(usage_to_proto(usage, name))
+            })
+            .collect();
+
+        Ok(Response::new(ListNamespaceUsageResponse { usage }))
+    }
+}
+
+fn usage_to_proto(usage: CatalogNamespaceUsage, namespace: String) -> NamespaceUsage {
+    NamespaceUsage {
+        namespace_id: usage.namespace_id.get(),
+        namespace,
+        rows_written: usage.rows_written,
+        bytes_written: usage.bytes_written,
+        queries_executed: usage.queries_executed,
+        bytes_scanned: usage.bytes_scanned,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use generated_types::influxdata::iox::usage::v1::usage_service_server::UsageService as _;
+    use iox_catalog::mem::MemCatalog;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_namespace_usage() {
+        let metrics = Arc::new(metric::Registry::default());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+
+        let (bananas, platanos) = {
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("iox-shared").await.unwrap();
+            let pool = repos
+                .query_pools()
+                .create_or_get("iox-shared")
+                .await
+                .unwrap();
+            let bananas = repos
+                .namespaces()
+                .create("bananas", None, topic.id, pool.id)
+                .await
+                .unwrap();
+            let platanos = repos
+                .namespaces()
+                .create("platanos", None, topic.id, pool.id)
+                .await
+                .unwrap();
+            repos
+                .namespaces()
+                .add_usage(&CatalogNamespaceUsage {
+                    rows_written: 10,
+                    bytes_written: 100,
+                    queries_executed: 2,
+                    bytes_scanned: 1000,
+                    ..CatalogNamespaceUsage::new(bananas.id)
+                })
+                .await
+                .unwrap();
+            (bananas, platanos)
+        };
+
+        let service = UsageService::new(Arc::clone(&catalog));
+
+        let want = NamespaceUsage {
+            namespace_id: bananas.id.get(),
+            namespace: "bananas".to_string(),
+            rows_written: 10,
+            bytes_written: 100,
+            queries_executed: 2,
+            bytes_scanned: 1000,
+        };
+        let got = service
+            .get_namespace_usage(Request::new(GetNamespaceUsageRequest {
+                namespace: "bananas".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(got.usage, Some(want.clone()));
+
+        // A namespace without recorded usage has none.
+        let got = service
+            .get_namespace_usage(Request::new(GetNamespaceUsageRequest {
+                namespace: "platanos".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            got.usage,
+            Some(NamespaceUsage {
+                namespace_id: platanos.id.get(),
+                namespace: "platanos".to_string(),
+                ..Default::default()
+            })
+        );
+
+        let err = service
+            .get_namespace_usage(Request::new(GetNamespaceUsageRequest {
+                namespace: "missing".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+
+        let got = service
+            .list_namespace_usage(Request::new(ListNamespaceUsageRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(got.usage, vec![want]);
+    }
+}
@@ -76,6 +76,16 @@ impl<T> JumpHash<T> {
 
     /// Consistently hash `key` to a `T`.
     pub fn hash<H>(&self, key: H) -> &T
+    where
+        H: Hash,
+    {
+        self.shards
+            .get(self.bucket(key))
+            .expect("sharder mapped input to non-existant bucket")
+    }
+
+    /// Consistently hash `key` to the index of a `T` in [`Self::shards()`].
+    pub(crate) fn bucket<H>(&self, key: H) -> usize
     where
         H: Hash,
     {
@@ -93,17 +103,21 @@ impl<T> JumpHash<T> {
         }
 
         assert!(b >= 0);
-        self.shards
-            .get(b as usize)
-            .expect("sharder mapped input to non-existant bucket")
+        b as usize
     }
 
     /// Consistently hash a table and namespace to a `T`. For use in a situation where you don't
     /// have a payload.
     pub fn shard_for_query(&self, table: &str, namespace: &str) -> &T {
+        &self.shards[self.bucket_for_query(table, namespace)]
+    }
+
+    /// Consistently hash a table and namespace to the index of a `T` in
+    /// [`Self::shards()`].
+    pub(crate) fn bucket_for_query(&self, table: &str, namespace: &str) -> usize {
         // The derived hash impl for HashKey is hardened against prefix
         // collisions when combining the two fields.
-        self.hash(&HashKey { table, namespace })
+        self.bucket(&HashKey { table, namespace })
     }
 }
 
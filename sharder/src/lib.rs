@@ -26,5 +26,11 @@ pub use round_robin::*;
 mod jumphash;
 pub use jumphash::*;
 
+mod tag_value;
+pub use tag_value::*;
+
+mod pinned;
+pub use pinned::*;
+
 #[allow(missing_docs)]
 pub mod mock;
@@ -0,0 +1,205 @@
+use super::{JumpHash, Sharder};
+use data_types::{DeletePredicate, NamespaceName};
+use mutable_batch::MutableBatch;
+use parking_lot::RwLock;
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
+/// A [`PinnedSharder`] maps operations for a given table in a given namespace
+/// to the shard the table has been pinned to, falling back to a [`JumpHash`]
+/// over all shards for tables that are not pinned.
+///
+/// Changing the number of shards a [`JumpHash`] is configured with remaps a
+/// fraction of all (namespace, table) pairs to a different shard. Pinning each
+/// table to the shard it was previously mapped to (and has data buffered in)
+/// allows the shard count to change without moving existing tables, while new
+/// tables are spread across all shards.
+///
+/// Pins are added at runtime with [`PinnedSharder::pin()`], typically as the
+/// persisted pin of each table is lazily loaded by the caller.
+#[derive(Debug)]
+pub struct PinnedSharder<T> {
+    hasher: JumpHash<T>,
+
+    /// The index in `hasher`'s shards of each pinned table, keyed by namespace
+    /// and then table name.
+    pins: RwLock<HashMap<String, HashMap<String, usize>>>,
+}
+
+impl<T> PinnedSharder<T> {
+    /// Initialise a [`PinnedSharder`] with no pinned tables, mapping all
+    /// tables to one of `shards` exactly as a [`JumpHash`] would.
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if the number of elements in `shards` is 0.
+    pub fn new(shards: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            hasher: JumpHash::new(shards),
+            pins: Default::default(),
+        }
+    }
+
+    /// Return a slice of all the shards this instance is configured with.
+    pub fn shards(&self) -> &[T] {
+        self.hasher.shards()
+    }
+
+    /// Pin `table` in `namespace` to the shard at index `shard` in
+    /// [`Self::shards()`], replacing any existing pin.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shard` is not a valid index into [`Self::shards()`].
+    pub fn pin(&self, table: &str, namespace: &NamespaceName<'_>, shard: usize) {
+        assert!(
+            shard < self.shards().len(),
+            "pinned shard index {} out of range for {} shards",
+            shard,
+            self.shards().len()
+        );
+
+        self.pins
+            .write()
+            .entry(namespace.to_string())
+            .or_default()
+            .insert(table.to_string(), shard);
+    }
+
+    /// Returns true if `table` in `namespace` has been pinned to a shard.
+    pub fn is_pinned(&self, table: &str, namespace: &NamespaceName<'_>) -> bool {
+        self.pinned_bucket(table, namespace).is_some()
+    }
+
+    fn pinned_bucket(&self, table: &str, namespace: &NamespaceName<'_>) -> Option<usize> {
+        self.pins
+            .read()
+            .get(namespace.as_ref())
+            .and_then(|tables| tables.get(table))
+            .copied()
+    }
+
+    fn bucket(&self, table: &str, namespace: &NamespaceName<'_>) -> usize {
+        self.pinned_bucket(table, namespace)
+            .unwrap_or_else(|| self.hasher.bucket_for_query(table, namespace.as_ref()))
+    }
+}
+
+/// A [`PinnedSharder`] mapping a [`MutableBatch`] to the shard of the table it
+/// is destined for.
+impl<T> Sharder<MutableBatch> for PinnedSharder<Arc<T>>
+where
+    T: Debug + Send + Sync,
+{
+    type Item = Arc<T>;
+
+    fn shard(
+        &self,
+        table: &str,
+        namespace: &NamespaceName<'_>,
+        _payload: &MutableBatch,
+    ) -> Self::Item {
+        Self::shard(self, table, namespace, &())
+    }
+}
+
+/// A [`PinnedSharder`] mapping a [`DeletePredicate`] to all shards unless a
+/// table is specified, in which case it is mapped to the same shard as a write
+/// to the same table would be.
+impl<T> Sharder<DeletePredicate> for PinnedSharder<Arc<T>>
+where
+    T: Debug + Send + Sync,
+{
+    type Item = Vec<Arc<T>>;
+
+    fn shard(
+        &self,
+        table: &str,
+        namespace: &NamespaceName<'_>,
+        _payload: &DeletePredicate,
+    ) -> Self::Item {
+        if table.is_empty() {
+            return self.shards().iter().map(Arc::clone).collect();
+        }
+
+        vec![Self::shard(self, table, namespace, &())]
+    }
+}
+
+impl<T> Sharder<()> for PinnedSharder<Arc<T>>
+where
+    T: Debug + Send + Sync,
+{
+    type Item = Arc<T>;
+
+    fn shard(&self, table: &str, namespace: &NamespaceName<'_>, _payload: &()) -> Self::Item {
+        Arc::clone(&self.shards()[self.bucket(table, namespace)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_types::TimestampRange;
+
+    #[test]
+    fn test_unpinned_matches_jumphash() {
+        let sharder = PinnedSharder::new((0..100).map(Arc::new));
+        let hasher = JumpHash::new((0..100).map(Arc::new));
+        let namespace = NamespaceName::try_from("bananas").unwrap();
+
+        for i in 0..100 {
+            let table = i.to_string();
+            assert!(!sharder.is_pinned(&table, &namespace));
+            assert_eq!(
+                sharder.shard(&table, &namespace, &()),
+                hasher.shard(&table, &namespace, &())
+            );
+        }
+    }
+
+    #[test]
+    fn test_pinned() {
+        let sharder = PinnedSharder::new((0..100).map(Arc::new));
+        let namespace = NamespaceName::try_from("bananas").unwrap();
+        let other = NamespaceName::try_from("platanos").unwrap();
+
+        let unpinned = sharder.shard("cpu", &namespace, &());
+        let pinned = (*unpinned + 1) % 100;
+        sharder.pin("cpu", &namespace, pinned as usize);
+
+        assert!(sharder.is_pinned("cpu", &namespace));
+        assert_eq!(*sharder.shard("cpu", &namespace, &()), pinned);
+        assert_eq!(
+            *sharder.shard("cpu", &namespace, &MutableBatch::new()),
+            pinned
+        );
+
+        // The pin applies to the table in one namespace only.
+        assert!(!sharder.is_pinned("cpu", &other));
+        assert!(!sharder.is_pinned("mem", &namespace));
+
+        // A delete for the pinned table follows the writes, while a delete
+        // for all tables maps to all shards.
+        let predicate = DeletePredicate {
+            range: TimestampRange::new(1, 2),
+            exprs: vec![],
+        };
+        let got = sharder.shard("cpu", &namespace, &predicate);
+        assert_eq!(got.len(), 1);
+        assert_eq!(*got[0], pinned);
+        assert_eq!(sharder.shard("", &namespace, &predicate).len(), 100);
+    }
+
+    #[test]
+    #[should_panic = "pinned shard index 10 out of range for 10 shards"]
+    fn test_pin_out_of_range() {
+        let sharder = PinnedSharder::new((0..10).map(Arc::new));
+        sharder.pin("cpu", &NamespaceName::try_from("bananas").unwrap(), 10);
+    }
+
+    #[test]
+    #[should_panic = "empty shard set given to sharder"]
+    fn no_shards() {
+        PinnedSharder::<i32>::new(std::iter::empty());
+    }
+}
@@ -0,0 +1,264 @@
+use super::{JumpHash, Sharder};
+use data_types::{DeletePredicate, NamespaceName};
+use mutable_batch::{column::ColumnData, MutableBatch};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    ops::Range,
+    sync::Arc,
+};
+
+/// A [`TagValueSharder`] maps each row of a write to a shard according to the
+/// value of a configured tag (such as `region` or `device_id`), rather than
+/// the namespace & table of the write.
+///
+/// All rows with the same tag value are consistently mapped to the same shard,
+/// irrespective of the namespace or table they are written to, placing all the
+/// data of (for example) a single device on the same ingester.
+///
+/// Rows without a value for the tag are mapped by their table & namespace,
+/// exactly as a [`JumpHash`] with the same set of shards would map them.
+///
+/// Because the rows of a single table may be written to any shard, a delete
+/// is always mapped to all shards.
+#[derive(Debug)]
+pub struct TagValueSharder<T> {
+    tag: String,
+    hasher: JumpHash<T>,
+}
+
+impl<T> TagValueSharder<T> {
+    /// Initialise a [`TagValueSharder`] that consistently maps rows to one of
+    /// `shards` by the value of the tag named `tag`.
+    ///
+    /// # Correctness
+    ///
+    /// Changing the number of, or order of, the elements in `shards` when
+    /// constructing two instances changes the mapping produced.
+    ///
+    /// # Panics
+    ///
+    /// This constructor panics if the number of elements in `shards` is 0.
+    pub fn new(tag: impl Into<String>, shards: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            tag: tag.into(),
+            hasher: JumpHash::new(shards),
+        }
+    }
+
+    /// Return the name of the tag rows are sharded by.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Return a slice of all the shards this instance is configured with.
+    pub fn shards(&self) -> &[T] {
+        self.hasher.shards()
+    }
+
+    /// Return the row ranges of `payload` mapped to each shard, keyed by the
+    /// index of the shard.
+    fn row_ranges(
+        &self,
+        table: &str,
+        namespace: &NamespaceName<'_>,
+        payload: &MutableBatch,
+    ) -> BTreeMap<usize, Vec<Range<usize>>> {
+        let fallback = self.hasher.bucket_for_query(table, namespace.as_ref());
+        let all_rows = || BTreeMap::from([(fallback, vec![0..payload.rows()])]);
+
+        let column = match payload.column(&self.tag) {
+            Ok(v) => v,
+            Err(_) => return all_rows(),
+        };
+        let (keys, dictionary) = match column.data() {
+            ColumnData::Tag(keys, dictionary, _) => (keys, dictionary),
+            // A field with the same name as the tag is ignored.
+            _ => return all_rows(),
+        };
+        let valid = column.valid_mask();
+
+        // The bucket of each distinct tag value, keyed by dictionary ID.
+        let mut buckets = HashMap::new();
+        let mut ranges: BTreeMap<usize, Vec<Range<usize>>> = BTreeMap::new();
+        for (row, key) in keys.iter().enumerate() {
+            let bucket = if valid.get(row) {
+                *buckets.entry(*key).or_insert_with(|| {
+                    let value = dictionary
+                        .lookup_id(*key)
+                        .expect("tag value missing from dictionary");
+                    self.hasher.bucket(value)
+                })
+            } else {
+                fallback
+            };
+
+            // Extend the previous range of the shard if it ends at this row.
+            let shard_ranges = ranges.entry(bucket).or_default();
+            match shard_ranges.last_mut() {
+                Some(range) if range.end == row => range.end += 1,
+                _ => shard_ranges.push(row..row + 1),
+            }
+        }
+
+        ranges
+    }
+}
+
+/// A [`TagValueSharder`] splitting a [`MutableBatch`] into the subsets of rows
+/// destined for each shard.
+impl<T> Sharder<MutableBatch> for TagValueSharder<Arc<T>>
+where
+    T: Debug + Send + Sync,
+{
+    type Item = Vec<(Arc<T>, MutableBatch)>;
+
+    fn shard(
+        &self,
+        table: &str,
+        namespace: &NamespaceName<'_>,
+        payload: &MutableBatch,
+    ) -> Self::Item {
+        let ranges = self.row_ranges(table, namespace, payload);
+
+        // Avoid copying the rows of a write mapped to a single shard.
+        if ranges.len() == 1 {
+            let (bucket, _) = ranges.into_iter().next().unwrap();
+            return vec![(Arc::clone(&self.shards()[bucket]), payload.clone())];
+        }
+
+        ranges
+            .into_iter()
+            .map(|(bucket, ranges)| {
+                let mut batch = MutableBatch::new();
+                batch
+                    .extend_from_ranges(payload, &ranges)
+                    .expect("extending an empty batch cannot fail");
+                (Arc::clone(&self.shards()[bucket]), batch)
+            })
+            .collect()
+    }
+}
+
+/// A [`TagValueSharder`] mapping a [`DeletePredicate`] to all shards, as the
+/// rows of any table may have been written to any shard.
+impl<T> Sharder<DeletePredicate> for TagValueSharder<Arc<T>>
+where
+    T: Debug + Send + Sync,
+{
+    type Item = Vec<Arc<T>>;
+
+    fn shard(
+        &self,
+        _table: &str,
+        _namespace: &NamespaceName<'_>,
+        _payload: &DeletePredicate,
+    ) -> Self::Item {
+        self.shards().iter().map(Arc::clone).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_types::TimestampRange;
+
+    fn lp_to_batch(lp: &str) -> MutableBatch {
+        let mut batches = mutable_batch_lp::lines_to_batches(lp, 42).unwrap();
+        assert_eq!(batches.len(), 1);
+        batches.drain().next().unwrap().1
+    }
+
+    #[test]
+    fn test_tag_value_locality() {
+        let sharder = TagValueSharder::new("device", (0..1_000).map(Arc::new));
+        let namespace = NamespaceName::try_from("bananas").unwrap();
+
+        // The same device maps to the same shard in any table & namespace.
+        let a = sharder.shard(
+            "cpu",
+            &namespace,
+            &lp_to_batch("cpu,device=A,host=X val=1i 1\ncpu,device=A,host=Y val=2i 2"),
+        );
+        let b = sharder.shard(
+            "mem",
+            &NamespaceName::try_from("platanos").unwrap(),
+            &lp_to_batch("mem,device=A val=3i 3"),
+        );
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].1.rows(), 2);
+        assert_eq!(b.len(), 1);
+        assert_eq!(a[0].0, b[0].0);
+    }
+
+    #[test]
+    fn test_split_by_tag_value() {
+        let sharder = TagValueSharder::new("device", (0..10_000).map(Arc::new));
+        let namespace = NamespaceName::try_from("bananas").unwrap();
+
+        let lp = "\
+            cpu,device=A val=1i 1\n\
+            cpu,device=B val=2i 2\n\
+            cpu val=3i 3\n\
+            cpu,device=A val=4i 4\n\
+            cpu,device=C val=5i 5\n\
+        ";
+        let mut got = sharder.shard("cpu", &namespace, &lp_to_batch(lp));
+        assert_eq!(got.len(), 4);
+        assert_eq!(got.iter().map(|(_, b)| b.rows()).sum::<usize>(), 5);
+
+        // Rows without the tag map to the same shard as a JumpHash would.
+        let fallback = JumpHash::new((0..10_000).map(Arc::new));
+        let want = Sharder::<()>::shard(&fallback, "cpu", &namespace, &());
+
+        got.sort_unstable_by_key(|(_, b)| b.rows());
+        for (shard, batch) in &got[..3] {
+            assert_eq!(batch.rows(), 1);
+            let is_fallback = batch.column("device").is_err()
+                || !batch.column("device").unwrap().valid_mask().get(0);
+            assert_eq!(is_fallback, *shard == want);
+        }
+        let (shard, batch) = &got[3];
+        assert_eq!(batch.rows(), 2);
+        let single = sharder.shard("cpu", &namespace, &lp_to_batch("cpu,device=A val=1i 1"));
+        assert_eq!(single[0].0, *shard);
+    }
+
+    #[test]
+    fn test_tag_absent_falls_back_to_jumphash() {
+        let sharder = TagValueSharder::new("device", (0..1_000).map(Arc::new));
+        let fallback = JumpHash::new((0..1_000).map(Arc::new));
+        let namespace = NamespaceName::try_from("bananas").unwrap();
+
+        for i in 0..100 {
+            let table = i.to_string();
+            let batch = lp_to_batch(&format!("{},host=A val=1i 1", table));
+
+            let got = sharder.shard(&table, &namespace, &batch);
+            assert_eq!(got.len(), 1);
+            assert_eq!(got[0].1.rows(), 1);
+            assert_eq!(got[0].0, fallback.shard(&table, &namespace, &batch));
+        }
+    }
+
+    #[test]
+    fn test_delete_shards_to_all() {
+        let shards = (0..10).map(Arc::new).collect::<Vec<_>>();
+        let sharder = TagValueSharder::new("device", shards.clone());
+
+        let predicate = DeletePredicate {
+            range: TimestampRange::new(1, 2),
+            exprs: vec![],
+        };
+
+        let namespace = NamespaceName::try_from("bananas").unwrap();
+        assert_eq!(sharder.shard("cpu", &namespace, &predicate), shards);
+        assert_eq!(sharder.shard("", &namespace, &predicate), shards);
+    }
+
+    #[test]
+    #[should_panic = "empty shard set given to sharder"]
+    fn no_shards() {
+        TagValueSharder::<i32>::new("device", std::iter::empty());
+    }
+}
@@ -209,6 +209,11 @@ pub async fn try_run_query(
         .perform_query(ReadInfo {
             namespace_name: namespace,
             sql_query: sql,
+            max_unpersisted_staleness_ns: None,
+            additional_namespaces: vec![],
+            params: vec![],
+            include_statistics: false,
+            debug_parquet_file_ids: vec![],
         })
         .await?;
 
@@ -0,0 +1,517 @@
+//! An in-process IOx cluster for write path integration tests.
+//!
+//! Unlike the [`MiniCluster`](test_helpers_end_to_end::MiniCluster), which
+//! spawns `influxdb_iox` processes configured through the CLI, an
+//! [`InProcessCluster`] runs the router, ingester and querier server types
+//! inside the test process. All three share a [`MemCatalog`], an in-memory
+//! object store and a file-based write buffer, so tests can inspect the
+//! catalog directly alongside the typed clients connected to each service.
+
+#![deny(rustdoc::broken_intra_doc_links, rust_2018_idioms)]
+#![warn(
+    missing_copy_implementations,
+    missing_debug_implementations,
+    missing_docs,
+    clippy::explicit_iter_loop,
+    clippy::future_not_send,
+    clippy::use_self,
+    clippy::clone_on_ref_ptr
+)]
+
+use arrow::record_batch::RecordBatch;
+use clap::Parser;
+use clap_blocks::{
+    ingester::IngesterConfig,
+    querier::{IngesterAddresses, QuerierConfig},
+    router::{
+        DmlHandlerConfig, NamespaceAutocreationConfig, NamespaceNameRulesConfig,
+        SchemaConflictConfig, TopicRoutingConfig,
+    },
+    write_buffer::WriteBufferConfig,
+};
+use data_types::{IngesterMapping, ShardIndex};
+use http::Response;
+use hyper::Body;
+use influxdb_iox_client::{
+    connection::{Builder, Connection},
+    flight, namespace, schema, write, write_info,
+};
+use iox_catalog::{
+    fault::{FaultConfig as CatalogFaultConfig, FaultInjectingCatalog},
+    interface::Catalog,
+    mem::MemCatalog,
+};
+use iox_query::exec::{Executor, ExecutorConfig};
+use iox_time::{SystemProvider, TimeProvider};
+use ioxd_common::server_type::{CommonServerState, ServerType};
+use ioxd_ingester::create_ingester_server_type;
+use ioxd_querier::{create_querier_server_type, QuerierServerTypeArgs};
+use ioxd_router::create_router_server_type;
+use object_store::{memory::InMemory, DynObjectStore};
+use object_store_metrics::fault::{
+    FaultConfig as ObjectStoreFaultConfig, FaultInjectingObjectStore,
+};
+use observability_deps::tracing::*;
+use parquet_file::storage::{ParquetStorage, StorageId};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use tempfile::TempDir;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// The name of the write buffer topic and query pool used by the cluster.
+const TOPIC: &str = "iox-shared";
+
+/// Errors that can occur while starting an [`InProcessCluster`].
+#[derive(Debug, Error)]
+pub enum Error {
+    /// A default service configuration failed to parse.
+    #[error("invalid configuration: {0}")]
+    Config(#[from] clap::Error),
+
+    /// The write buffer topic could not be created.
+    #[error("catalog error: {0}")]
+    Catalog(#[from] iox_catalog::interface::Error),
+
+    /// The file write buffer directory could not be created.
+    #[error("error creating write buffer directory: {0}")]
+    WriteBufferDir(#[source] std::io::Error),
+
+    /// A service listener could not be bound.
+    #[error("error binding listener: {0}")]
+    Listener(#[from] ioxd_common::Error),
+
+    /// The address of a bound listener could not be read.
+    #[error("error reading listener address: {0}")]
+    ListenerAddr(#[source] std::io::Error),
+
+    /// The router failed to start.
+    #[error("router error: {0}")]
+    Router(#[from] ioxd_router::Error),
+
+    /// The ingester failed to start.
+    #[error("ingester error: {0}")]
+    Ingester(#[from] ioxd_ingester::Error),
+
+    /// The querier failed to start.
+    #[error("querier error: {0}")]
+    Querier(#[from] ioxd_querier::Error),
+
+    /// A client connection to a service could not be established.
+    #[error("error connecting to {addr}: {source}")]
+    Connect {
+        /// The address that was connected to.
+        addr: String,
+        /// The connection error.
+        source: influxdb_iox_client::connection::Error,
+    },
+}
+
+/// Configures and starts an [`InProcessCluster`].
+#[derive(Debug)]
+pub struct InProcessClusterBuilder {
+    ingester_config: IngesterConfig,
+    querier_config: QuerierConfig,
+    catalog_faults: CatalogFaultConfig,
+    object_store_faults: ObjectStoreFaultConfig,
+}
+
+impl Default for InProcessClusterBuilder {
+    fn default() -> Self {
+        Self {
+            ingester_config: IngesterConfig::try_parse_from([
+                "ingester",
+                "--shard-index-range-start",
+                "0",
+                "--shard-index-range-end",
+                "0",
+                "--pause-ingest-size-bytes",
+                "2000000",
+                "--persist-memory-threshold-bytes",
+                "1000000",
+                "--skip-to-oldest-available",
+            ])
+            .expect("default ingester config should parse"),
+            querier_config: QuerierConfig::try_parse_from(["querier"])
+                .expect("default querier config should parse"),
+            catalog_faults: Default::default(),
+            object_store_faults: Default::default(),
+        }
+    }
+}
+
+impl InProcessClusterBuilder {
+    /// Use `ingester_config` instead of the default ingester configuration.
+    ///
+    /// The ingester consumes the shard indexes in its configured range, and
+    /// the querier is pointed at it for each of them.
+    pub fn with_ingester_config(self, ingester_config: IngesterConfig) -> Self {
+        Self {
+            ingester_config,
+            ..self
+        }
+    }
+
+    /// Use `querier_config` instead of the default querier configuration.
+    pub fn with_querier_config(self, querier_config: QuerierConfig) -> Self {
+        Self {
+            querier_config,
+            ..self
+        }
+    }
+
+    /// Inject `faults` into the operations of the catalog shared by all
+    /// services.
+    ///
+    /// Faults are injected from the moment the services start, so a high
+    /// error rate may cause [`build()`](Self::build) to fail.
+    pub fn with_catalog_faults(self, catalog_faults: CatalogFaultConfig) -> Self {
+        Self {
+            catalog_faults,
+            ..self
+        }
+    }
+
+    /// Inject `faults` into the operations of the object store shared by all
+    /// services.
+    pub fn with_object_store_faults(self, object_store_faults: ObjectStoreFaultConfig) -> Self {
+        Self {
+            object_store_faults,
+            ..self
+        }
+    }
+
+    /// Start the router, ingester and querier and connect to each of them.
+    pub async fn build(self) -> Result<InProcessCluster, Error> {
+        let common_state = CommonServerState::for_testing();
+        let metrics = Arc::new(metric::Registry::default());
+        let time_provider: Arc<dyn TimeProvider> = Arc::new(SystemProvider::new());
+
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+        catalog
+            .repositories()
+            .await
+            .topics()
+            .create_or_get(TOPIC)
+            .await?;
+        let catalog: Arc<dyn Catalog> = if self.catalog_faults.is_enabled() {
+            Arc::new(FaultInjectingCatalog::new(catalog, self.catalog_faults))
+        } else {
+            catalog
+        };
+
+        let object_store: Arc<DynObjectStore> = Arc::new(InMemory::new());
+        let object_store: Arc<DynObjectStore> = if self.object_store_faults.is_enabled() {
+            Arc::new(FaultInjectingObjectStore::new(
+                object_store,
+                self.object_store_faults,
+            ))
+        } else {
+            object_store
+        };
+        let parquet_store = ParquetStorage::new(Arc::clone(&object_store), StorageId::from("iox"));
+        let exec = Arc::new(Executor::new_with_config(ExecutorConfig {
+            num_query_threads: 1,
+            num_reorg_threads: 1,
+            target_query_partitions: 1,
+            object_stores: HashMap::from([(
+                parquet_store.id(),
+                Arc::clone(parquet_store.object_store()),
+            )]),
+        }));
+
+        // The file write buffer creates its shards on first use, rooted in a
+        // directory that lives as long as the cluster.
+        let write_buffer_dir = TempDir::new().map_err(Error::WriteBufferDir)?;
+        let write_buffer_config =
+            WriteBufferConfig::new(TOPIC, Some(write_buffer_dir.path().to_path_buf()));
+
+        let shard_indexes = self.ingester_config.shard_index_range_start
+            ..=self.ingester_config.shard_index_range_end;
+
+        info!("starting router");
+        let router = create_router_server_type(
+            &common_state,
+            Arc::clone(&metrics),
+            Arc::clone(&catalog),
+            Arc::clone(&object_store),
+            &write_buffer_config,
+            TOPIC,
+            1_000,
+            0.0,
+            false,
+            None,
+            None,
+            None,
+            &NamespaceAutocreationConfig::new_enabled(),
+            &NamespaceNameRulesConfig::default(),
+            &SchemaConflictConfig::default(),
+            &TopicRoutingConfig::default(),
+            &DmlHandlerConfig::default(),
+        )
+        .await?;
+
+        info!("starting ingester");
+        let ingester = create_ingester_server_type(
+            &common_state,
+            Arc::clone(&metrics),
+            Arc::clone(&catalog),
+            Arc::clone(&object_store),
+            Arc::clone(&exec),
+            &write_buffer_config,
+            self.ingester_config,
+        )
+        .await?;
+
+        // Bind the ingester listener up front so its address can be handed to
+        // the querier.
+        let ingester_grpc = ioxd_common::grpc_listener(loopback()).await?;
+        let ingester_addr = ingester_grpc.local_addr().map_err(Error::ListenerAddr)?;
+        let ingester_mapping =
+            IngesterMapping::Addr(Arc::from(format!("http://{ingester_addr}").as_str()));
+        let ingester_addresses = IngesterAddresses::ByShardIndex(
+            shard_indexes
+                .map(|index| (ShardIndex::new(index), ingester_mapping.clone()))
+                .collect(),
+        );
+
+        info!(?ingester_addresses, "starting querier");
+        let querier = create_querier_server_type(QuerierServerTypeArgs {
+            common_state: &common_state,
+            metric_registry: Arc::clone(&metrics),
+            catalog: Arc::clone(&catalog),
+            object_store,
+            exec,
+            time_provider,
+            ingester_addresses,
+            querier_config: self.querier_config,
+            authz_cache_ttl: None,
+        })
+        .await?;
+
+        let router_grpc = ioxd_common::grpc_listener(loopback()).await?;
+        let router_grpc_addr = router_grpc.local_addr().map_err(Error::ListenerAddr)?;
+        let router_http = ioxd_common::http_listener(loopback()).await?;
+        let router_http_addr = router_http.local_addr();
+        let querier_grpc = ioxd_common::grpc_listener(loopback()).await?;
+        let querier_addr = querier_grpc.local_addr().map_err(Error::ListenerAddr)?;
+
+        let shutdown = CancellationToken::new();
+        let tasks = vec![
+            serve(
+                &common_state,
+                &shutdown,
+                router_grpc,
+                Some(router_http),
+                router,
+            ),
+            serve(&common_state, &shutdown, ingester_grpc, None, ingester),
+            serve(&common_state, &shutdown, querier_grpc, None, querier),
+        ];
+
+        // The listeners are already bound, so connecting does not race with
+        // the servers starting up.
+        let router_grpc_connection = connect(router_grpc_addr).await?;
+        let router_http_connection = connect(router_http_addr).await?;
+        let ingester_grpc_connection = connect(ingester_addr).await?;
+        let querier_grpc_connection = connect(querier_addr).await?;
+
+        let org_id = test_helpers_end_to_end::rand_id();
+        let bucket_id = test_helpers_end_to_end::rand_id();
+        let namespace = format!("{org_id}_{bucket_id}");
+
+        Ok(InProcessCluster {
+            org_id,
+            bucket_id,
+            namespace,
+            catalog,
+            metrics,
+            router_http_base: format!("http://{router_http_addr}"),
+            router_grpc_connection,
+            router_http_connection,
+            ingester_grpc_connection,
+            querier_grpc_connection,
+            shutdown,
+            tasks,
+            _write_buffer_dir: write_buffer_dir,
+        })
+    }
+}
+
+/// A router, ingester and querier running within the current process.
+///
+/// The servers are stopped when the cluster is dropped; use
+/// [`InProcessCluster::shutdown`] to also wait for them to exit.
+#[derive(Debug)]
+pub struct InProcessCluster {
+    org_id: String,
+    bucket_id: String,
+    namespace: String,
+
+    catalog: Arc<dyn Catalog>,
+    metrics: Arc<metric::Registry>,
+
+    router_http_base: String,
+    router_grpc_connection: Connection,
+    router_http_connection: Connection,
+    ingester_grpc_connection: Connection,
+    querier_grpc_connection: Connection,
+
+    shutdown: CancellationToken,
+    tasks: Vec<JoinHandle<Result<(), ioxd_common::Error>>>,
+
+    /// Holds the file write buffer, removed when the cluster is dropped.
+    _write_buffer_dir: TempDir,
+}
+
+impl InProcessCluster {
+    /// Start a cluster with the default configuration.
+    pub async fn new() -> Result<Self, Error> {
+        InProcessClusterBuilder::default().build().await
+    }
+
+    /// Configure a cluster before starting it.
+    pub fn builder() -> InProcessClusterBuilder {
+        InProcessClusterBuilder::default()
+    }
+
+    /// The org ID used for writes.
+    pub fn org_id(&self) -> &str {
+        &self.org_id
+    }
+
+    /// The bucket ID used for writes.
+    pub fn bucket_id(&self) -> &str {
+        &self.bucket_id
+    }
+
+    /// The namespace name derived from the org and bucket IDs.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// The catalog shared by all services.
+    pub fn catalog(&self) -> &Arc<dyn Catalog> {
+        &self.catalog
+    }
+
+    /// The metric registry shared by all services.
+    pub fn metrics(&self) -> &Arc<metric::Registry> {
+        &self.metrics
+    }
+
+    /// The base URL of the router HTTP API.
+    pub fn router_http_base(&self) -> &str {
+        &self.router_http_base
+    }
+
+    /// A connection to the router gRPC API.
+    pub fn router_grpc_connection(&self) -> Connection {
+        self.router_grpc_connection.clone()
+    }
+
+    /// A connection to the ingester gRPC API.
+    pub fn ingester_grpc_connection(&self) -> Connection {
+        self.ingester_grpc_connection.clone()
+    }
+
+    /// A connection to the querier gRPC API.
+    pub fn querier_grpc_connection(&self) -> Connection {
+        self.querier_grpc_connection.clone()
+    }
+
+    /// A client for the router write API.
+    pub fn write_client(&self) -> write::Client {
+        write::Client::new(self.router_http_connection.clone())
+    }
+
+    /// A client for the router schema API.
+    pub fn schema_client(&self) -> schema::Client {
+        schema::Client::new(self.router_grpc_connection())
+    }
+
+    /// A client for the router namespace API.
+    pub fn namespace_client(&self) -> namespace::Client {
+        namespace::Client::new(self.router_grpc_connection())
+    }
+
+    /// A client for the querier write info API.
+    pub fn write_info_client(&self) -> write_info::Client {
+        write_info::Client::new(self.querier_grpc_connection())
+    }
+
+    /// A client for the querier Flight API.
+    pub fn flight_client(&self) -> flight::Client {
+        flight::Client::new(self.querier_grpc_connection())
+    }
+
+    /// Write `line_protocol` to the cluster's namespace through the router.
+    pub async fn write_to_router(&self, line_protocol: impl Into<String>) -> Response<Body> {
+        test_helpers_end_to_end::write_to_router(
+            line_protocol,
+            &self.org_id,
+            &self.bucket_id,
+            &self.router_http_base,
+        )
+        .await
+    }
+
+    /// Wait until the write identified by `write_token` is readable through
+    /// the querier.
+    pub async fn wait_for_readable(&self, write_token: impl Into<String>) {
+        test_helpers_end_to_end::wait_for_readable(write_token, self.querier_grpc_connection())
+            .await
+    }
+
+    /// Run `sql` against the cluster's namespace through the querier.
+    pub async fn query(&self, sql: impl Into<String>) -> Vec<RecordBatch> {
+        test_helpers_end_to_end::run_query(sql, &self.namespace, self.querier_grpc_connection())
+            .await
+    }
+
+    /// Stop all services and wait for them to exit.
+    pub async fn shutdown(mut self) {
+        self.shutdown.cancel();
+        for task in self.tasks.drain(..) {
+            match task.await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => error!(%e, "in-process server exited with error"),
+                Err(e) => error!(%e, "in-process server panicked"),
+            }
+        }
+    }
+}
+
+impl Drop for InProcessCluster {
+    fn drop(&mut self) {
+        self.shutdown.cancel();
+    }
+}
+
+fn loopback() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 0))
+}
+
+fn serve(
+    common_state: &CommonServerState,
+    shutdown: &CancellationToken,
+    grpc_listener: tokio::net::TcpListener,
+    http_listener: Option<hyper::server::conn::AddrIncoming>,
+    server_type: Arc<dyn ServerType>,
+) -> JoinHandle<Result<(), ioxd_common::Error>> {
+    tokio::spawn(ioxd_common::serve(
+        common_state.clone(),
+        shutdown.clone(),
+        grpc_listener,
+        http_listener,
+        server_type,
+    ))
+}
+
+async fn connect(addr: SocketAddr) -> Result<Connection, Error> {
+    let addr = format!("http://{addr}");
+    Builder::default()
+        .build(addr.as_str())
+        .await
+        .map_err(|source| Error::Connect { addr, source })
+}
@@ -0,0 +1,38 @@
+use arrow_util::assert_batches_sorted_eq;
+use http::StatusCode;
+use test_helpers_end_to_end::get_write_token;
+use test_helpers_in_process::InProcessCluster;
+
+#[tokio::test]
+async fn write_then_query() {
+    let cluster = InProcessCluster::new().await.unwrap();
+
+    let response = cluster.write_to_router("cpu,tag1=A val=42i 123456").await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let write_token = get_write_token(&response);
+
+    // The router autocreates the namespace in the shared catalog.
+    let namespace = cluster
+        .catalog()
+        .repositories()
+        .await
+        .namespaces()
+        .get_by_name(cluster.namespace())
+        .await
+        .unwrap();
+    assert!(namespace.is_some());
+
+    cluster.wait_for_readable(write_token).await;
+
+    let batches = cluster.query("select * from cpu").await;
+    let expected = [
+        "+------+--------------------------------+-----+",
+        "| tag1 | time                           | val |",
+        "+------+--------------------------------+-----+",
+        "| A    | 1970-01-01T00:00:00.000123456Z | 42  |",
+        "+------+--------------------------------+-----+",
+    ];
+    assert_batches_sorted_eq!(&expected, &batches);
+
+    cluster.shutdown().await;
+}
@@ -14,6 +14,8 @@ const B3_SAMPLED_HEADER: &str = "X-B3-Sampled";
 const B3_TRACE_ID_HEADER: &str = "X-B3-TraceId";
 const B3_PARENT_SPAN_ID_HEADER: &str = "X-B3-ParentSpanId";
 const B3_SPAN_ID_HEADER: &str = "X-B3-SpanId";
+const B3_SINGLE_HEADER: &str = "b3";
+const W3C_TRACEPARENT_HEADER: &str = "traceparent";
 
 /// Error decoding SpanContext from transport representation
 #[derive(Debug, Snafu)]
@@ -40,6 +42,12 @@ pub enum DecodeError {
     #[snafu(display("Expected \"trace-id:span-id:parent-span-id:flags\""))]
     InvalidJaegerTrace,
 
+    #[snafu(display("Expected \"version-trace-id-parent-id-flags\""))]
+    InvalidW3cTraceparent,
+
+    #[snafu(display("Expected \"trace-id-span-id[-sampled[-parent-span-id]]\""))]
+    InvalidB3Single,
+
     #[snafu(display("value cannot be 0"))]
     ZeroError,
 }
@@ -99,9 +107,11 @@ impl TraceHeaderParser {
     /// Create a SpanContext for the trace described in the request's
     /// headers, if any
     ///
-    /// Currently support the following formats:
-    /// * <https://github.com/openzipkin/b3-propagation#multiple-headers>
+    /// Currently support the following formats, in order of precedence:
     /// * <https://www.jaegertracing.io/docs/1.21/client-libraries/#propagation-format>
+    /// * <https://www.w3.org/TR/trace-context/#traceparent-header>
+    /// * <https://github.com/openzipkin/b3-propagation#single-header>
+    /// * <https://github.com/openzipkin/b3-propagation#multiple-headers>
     pub fn parse(
         &self,
         collector: Option<&Arc<dyn TraceCollector>>,
@@ -113,6 +123,18 @@ impl TraceHeaderParser {
             }
         }
 
+        if headers.contains_key(W3C_TRACEPARENT_HEADER) {
+            return decode_w3c(collector, headers).map(Some);
+        }
+
+        if headers.contains_key(B3_SINGLE_HEADER) {
+            // A single header with only a sampling decision carries no trace
+            // context to join.
+            if let Some(ctx) = decode_b3_single(collector, headers)? {
+                return Ok(Some(ctx));
+            }
+        }
+
         if headers.contains_key(B3_TRACE_ID_HEADER) {
             return decode_b3(collector, headers).map(Some);
         }
@@ -164,6 +186,128 @@ fn decode_b3(
     })
 }
 
+/// Decodes the single `b3` header, returning `None` if it only contains a
+/// sampling decision.
+fn decode_b3_single(
+    collector: Option<&Arc<dyn TraceCollector>>,
+    headers: &HeaderMap,
+) -> Result<Option<SpanContext>, ContextError> {
+    let decoded: Option<B3SingleCtx> = required_header(headers, B3_SINGLE_HEADER, |s| match s {
+        "0" | "1" | "d" => Ok(None),
+        _ => s.parse::<B3SingleCtx>().map(Some),
+    })?;
+
+    // Links cannot be specified via the HTTP header
+    let links = vec![];
+
+    Ok(decoded.map(|decoded| SpanContext {
+        trace_id: decoded.trace_id,
+        parent_span_id: decoded.parent_span_id,
+        span_id: decoded.span_id,
+        links,
+        collector: collector.cloned(),
+        sampled: decoded.sampled,
+    }))
+}
+
+struct B3SingleCtx {
+    trace_id: TraceId,
+    span_id: SpanId,
+    parent_span_id: Option<SpanId>,
+    sampled: bool,
+}
+
+impl FromStr for B3SingleCtx {
+    type Err = DecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split('-');
+        let (trace_id, span_id) = match (parts.next(), parts.next()) {
+            (Some(trace_id), Some(span_id))
+                if matches!(trace_id.len(), 16 | 32) && span_id.len() == 16 =>
+            {
+                (parse_trace(trace_id)?, parse_span(span_id)?)
+            }
+            _ => return Err(DecodeError::InvalidB3Single),
+        };
+        let sampled = match parts.next() {
+            None | Some("0") => false,
+            Some("1" | "d") => true,
+            Some(_) => return Err(DecodeError::InvalidB3Single),
+        };
+        let parent_span_id = match parts.next() {
+            None => None,
+            Some(parent_span_id) if parent_span_id.len() == 16 => Some(parse_span(parent_span_id)?),
+            Some(_) => return Err(DecodeError::InvalidB3Single),
+        };
+        if parts.next().is_some() {
+            return Err(DecodeError::InvalidB3Single);
+        }
+
+        Ok(Self {
+            trace_id,
+            span_id,
+            parent_span_id,
+            sampled,
+        })
+    }
+}
+
+/// Decodes the W3C `traceparent` header
+fn decode_w3c(
+    collector: Option<&Arc<dyn TraceCollector>>,
+    headers: &HeaderMap,
+) -> Result<SpanContext, ContextError> {
+    let decoded: W3cCtx = required_header(headers, W3C_TRACEPARENT_HEADER, FromStr::from_str)?;
+
+    // Links cannot be specified via the HTTP header
+    let links = vec![];
+
+    Ok(SpanContext {
+        trace_id: decoded.trace_id,
+        parent_span_id: None,
+        span_id: decoded.parent_id,
+        links,
+        collector: collector.cloned(),
+        sampled: decoded.flags & 0x01 == 1,
+    })
+}
+
+struct W3cCtx {
+    trace_id: TraceId,
+    parent_id: SpanId,
+    flags: u8,
+}
+
+impl FromStr for W3cCtx {
+    type Err = DecodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<_> = s.trim().split('-').collect();
+        let (version, trace_id, parent_id, flags) = match parts.as_slice() {
+            // Versions after 00 may append fields, which are ignored.
+            [version, trace_id, parent_id, flags, rest @ ..]
+                if (rest.is_empty() || *version != "00")
+                    && version.len() == 2
+                    && *version != "ff"
+                    && trace_id.len() == 32
+                    && parent_id.len() == 16
+                    && flags.len() == 2 =>
+            {
+                (version, trace_id, parent_id, flags)
+            }
+            _ => return Err(DecodeError::InvalidW3cTraceparent),
+        };
+        u8::from_str_radix(version, 16)?;
+
+        Ok(Self {
+            trace_id: parse_trace(trace_id)?,
+            parent_id: parse_span(parent_id)?,
+            flags: u8::from_str_radix(flags, 16)?,
+        })
+    }
+}
+
 struct JaegerCtx {
     trace_id: TraceId,
     span_id: SpanId,
@@ -405,6 +549,152 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_decode_w3c() {
+        let parser = TraceHeaderParser::new();
+        let collector: Arc<dyn TraceCollector> = Arc::new(trace::LogTraceCollector::new());
+        let mut headers = HeaderMap::new();
+
+        // Sampled
+        headers.insert(
+            W3C_TRACEPARENT_HEADER,
+            HeaderValue::from_static("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01"),
+        );
+        let span = parser.parse(Some(&collector), &headers).unwrap().unwrap();
+        assert_eq!(span.trace_id.0.get(), 0x0af7651916cd43dd8448eb211c80319c);
+        assert_eq!(span.span_id.0.get(), 0xb7ad6b7169203331);
+        assert!(span.parent_span_id.is_none());
+        assert!(span.sampled);
+
+        // Not sampled
+        headers.insert(
+            W3C_TRACEPARENT_HEADER,
+            HeaderValue::from_static("00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-00"),
+        );
+        let span = parser.parse(Some(&collector), &headers).unwrap().unwrap();
+        assert!(!span.sampled);
+
+        // Future versions may append fields
+        headers.insert(
+            W3C_TRACEPARENT_HEADER,
+            HeaderValue::from_static(
+                "01-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-what-the-future",
+            ),
+        );
+        let span = parser.parse(Some(&collector), &headers).unwrap().unwrap();
+        assert_eq!(span.span_id.0.get(), 0xb7ad6b7169203331);
+
+        for invalid in [
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01-extra",
+            "ff-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd-b7ad6b7169203331-01",
+            "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331",
+        ] {
+            headers.insert(W3C_TRACEPARENT_HEADER, HeaderValue::from_static(invalid));
+            assert_eq!(
+                parser
+                    .parse(Some(&collector), &headers)
+                    .unwrap_err()
+                    .to_string(),
+                "error decoding header 'traceparent': Expected \"version-trace-id-parent-id-flags\"",
+                "{invalid}"
+            );
+        }
+
+        headers.insert(
+            W3C_TRACEPARENT_HEADER,
+            HeaderValue::from_static("00-00000000000000000000000000000000-b7ad6b7169203331-01"),
+        );
+        assert_eq!(
+            parser
+                .parse(Some(&collector), &headers)
+                .unwrap_err()
+                .to_string(),
+            "error decoding header 'traceparent': value cannot be 0"
+        );
+    }
+
+    #[test]
+    fn test_decode_b3_single() {
+        let parser = TraceHeaderParser::new();
+        let collector: Arc<dyn TraceCollector> = Arc::new(trace::LogTraceCollector::new());
+        let mut headers = HeaderMap::new();
+
+        // Not sampled, 128-bit trace ID
+        headers.insert(
+            B3_SINGLE_HEADER,
+            HeaderValue::from_static("80f198ee56343ba864fe8b2a57d3eff7-e457b5a2e4d86bd1"),
+        );
+        let span = parser.parse(Some(&collector), &headers).unwrap().unwrap();
+        assert_eq!(span.trace_id.0.get(), 0x80f198ee56343ba864fe8b2a57d3eff7);
+        assert_eq!(span.span_id.0.get(), 0xe457b5a2e4d86bd1);
+        assert!(span.parent_span_id.is_none());
+        assert!(!span.sampled);
+
+        // Debug, with parent span, 64-bit trace ID
+        headers.insert(
+            B3_SINGLE_HEADER,
+            HeaderValue::from_static("64fe8b2a57d3eff7-e457b5a2e4d86bd1-d-05e3ac9a4f6e3b90"),
+        );
+        let span = parser.parse(Some(&collector), &headers).unwrap().unwrap();
+        assert_eq!(span.trace_id.0.get(), 0x64fe8b2a57d3eff7);
+        assert_eq!(span.parent_span_id.unwrap().0.get(), 0x05e3ac9a4f6e3b90);
+        assert!(span.sampled);
+
+        // A sampling decision only falls back to the other formats
+        headers.insert(B3_SINGLE_HEADER, HeaderValue::from_static("1"));
+        assert!(parser.parse(Some(&collector), &headers).unwrap().is_none());
+
+        headers.insert(B3_TRACE_ID_HEADER, HeaderValue::from_static("ee25f"));
+        headers.insert(B3_SPAN_ID_HEADER, HeaderValue::from_static("34e"));
+        let span = parser.parse(Some(&collector), &headers).unwrap().unwrap();
+        assert_eq!(span.trace_id.0.get(), 0xee25f);
+
+        headers.insert(
+            B3_SINGLE_HEADER,
+            HeaderValue::from_static("64fe8b2a57d3eff7-e457b5a2e4d86bd1-x"),
+        );
+        assert_eq!(
+            parser
+                .parse(Some(&collector), &headers)
+                .unwrap_err()
+                .to_string(),
+            "error decoding header 'b3': Expected \"trace-id-span-id[-sampled[-parent-span-id]]\""
+        );
+    }
+
+    #[test]
+    fn test_decode_precedence() {
+        let parser =
+            TraceHeaderParser::new().with_jaeger_trace_context_header_name("uber-trace-id");
+        let collector: Arc<dyn TraceCollector> = Arc::new(trace::LogTraceCollector::new());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(B3_TRACE_ID_HEADER, HeaderValue::from_static("4"));
+        headers.insert(B3_SPAN_ID_HEADER, HeaderValue::from_static("4"));
+        headers.insert(
+            B3_SINGLE_HEADER,
+            HeaderValue::from_static("0000000000000003-0000000000000003"),
+        );
+        headers.insert(
+            W3C_TRACEPARENT_HEADER,
+            HeaderValue::from_static("00-00000000000000000000000000000002-0000000000000002-01"),
+        );
+        headers.insert("uber-trace-id", HeaderValue::from_static("1:1:0:1"));
+
+        for want in 1..=4 {
+            let span = parser.parse(Some(&collector), &headers).unwrap().unwrap();
+            assert_eq!(span.trace_id.0.get(), want);
+
+            match want {
+                1 => headers.remove("uber-trace-id"),
+                2 => headers.remove(W3C_TRACEPARENT_HEADER),
+                3 => headers.remove(B3_SINGLE_HEADER),
+                _ => None,
+            };
+        }
+    }
+
     #[test]
     fn test_decode_jaeger() {
         const TRACE_HEADER: &str = "uber-trace-id";
@@ -8,9 +8,6 @@ use std::time::Instant;
 /// `MetricsCollection` is used to retrieve `MetricsRecorder` for instrumenting http requests
 #[derive(Debug)]
 pub struct MetricsCollection {
-    /// Whether this `MetricCollection` should publish to grpc_request* or http_request*
-    is_grpc: bool,
-
     /// Metric registry for registering new metrics
     metric_registry: Arc<metric::Registry>,
 
@@ -19,9 +16,8 @@ pub struct MetricsCollection {
 }
 
 impl MetricsCollection {
-    pub fn new(metric_registry: Arc<metric::Registry>, is_grpc: bool) -> Self {
+    pub fn new(metric_registry: Arc<metric::Registry>) -> Self {
         Self {
-            is_grpc,
             metric_registry,
             metrics: Default::default(),
         }
@@ -30,7 +26,7 @@ impl MetricsCollection {
     /// Gets the `MetricsRecorder` for a given http request
     pub fn recorder<B>(self: &Arc<Self>, request: &http::Request<B>) -> MetricsRecorder {
         MetricsRecorder {
-            metrics: Arc::clone(self),
+            metrics: Some(Arc::clone(self)),
             start_instant: Instant::now(),
             path: Some(request.uri().path().to_string()),
             classification: None,
@@ -46,8 +42,7 @@ impl MetricsCollection {
                         None => Attributes::from([]),
                     };
 
-                    let metrics =
-                        Metrics::new(self.metric_registry.as_ref(), attributes, self.is_grpc);
+                    let metrics = Metrics::new(self.metric_registry.as_ref(), attributes);
                     (path, metrics)
                 });
             request_metrics
@@ -69,17 +64,12 @@ struct Metrics {
 }
 
 impl Metrics {
-    fn new(registry: &metric::Registry, attributes: impl Into<Attributes>, is_grpc: bool) -> Self {
-        let (counter, duration) = match is_grpc {
-            true => ("grpc_requests", "grpc_request_duration"),
-            false => ("http_requests", "http_request_duration"),
-        };
-
+    fn new(registry: &metric::Registry, attributes: impl Into<Attributes>) -> Self {
         let counter: Metric<U64Counter> =
-            registry.register_metric(counter, "accumulated total requests");
+            registry.register_metric("http_requests", "accumulated total requests");
 
         let duration: Metric<DurationHistogram> =
-            registry.register_metric(duration, "distribution of request latencies");
+            registry.register_metric("http_request_duration", "distribution of request latencies");
 
         let mut attributes = attributes.into();
         let count = ResultMetric::new(&counter, attributes.clone());
@@ -99,13 +89,24 @@ impl Metrics {
 /// A `MetricsRecorder` is used to record metrics for a given http request
 #[derive(Debug)]
 pub struct MetricsRecorder {
-    metrics: Arc<MetricsCollection>,
+    /// The metrics to record to, or None if the request is not recorded
+    metrics: Option<Arc<MetricsCollection>>,
     start_instant: Instant,
     path: Option<String>,
     classification: Option<Classification>,
 }
 
 impl MetricsRecorder {
+    /// Returns a `MetricsRecorder` that does not record anything
+    pub fn disabled() -> Self {
+        Self {
+            metrics: None,
+            start_instant: Instant::now(),
+            path: None,
+            classification: None,
+        }
+    }
+
     /// Sets the classification of this request if not already set
     pub fn set_classification(&mut self, classification: Classification) {
         if matches!(classification, Classification::PathNotFound) {
@@ -122,7 +123,10 @@ impl MetricsRecorder {
 
 impl Drop for MetricsRecorder {
     fn drop(&mut self) {
-        let metrics = self.metrics.request_metrics(self.path.take());
+        let metrics = match &self.metrics {
+            Some(metrics) => metrics.request_metrics(self.path.take()),
+            None => return,
+        };
 
         let duration = self.start_instant.elapsed();
         match self.classification {
@@ -37,26 +37,29 @@ use crate::metrics::{MetricsCollection, MetricsRecorder};
 /// Including:
 ///
 /// - Extracting distributed trace context and attaching span context
-/// - Collecting count and duration metrics - [RED metrics][1]
+/// - Collecting count and duration metrics - [RED metrics][1] - if a metric registry is provided
+///
+/// gRPC services should not provide a metric registry, as per-path metrics cannot tell RPC
+/// status codes apart and ignore message sizes. They are instrumented per RPC by the gRPC server
+/// instead.
 ///
 /// [1]: https://www.weave.works/blog/the-red-method-key-metrics-for-microservices-architecture/
 #[derive(Debug, Clone)]
 pub struct TraceLayer {
     trace_header_parser: TraceHeaderParser,
-    metrics: Arc<MetricsCollection>,
+    metrics: Option<Arc<MetricsCollection>>,
     collector: Option<Arc<dyn TraceCollector>>,
 }
 
 impl TraceLayer {
     pub fn new(
         trace_header_parser: TraceHeaderParser,
-        metric_registry: Arc<metric::Registry>,
+        metric_registry: Option<Arc<metric::Registry>>,
         collector: Option<Arc<dyn TraceCollector>>,
-        is_grpc: bool,
     ) -> Self {
         Self {
             trace_header_parser,
-            metrics: Arc::new(MetricsCollection::new(metric_registry, is_grpc)),
+            metrics: metric_registry.map(|registry| Arc::new(MetricsCollection::new(registry))),
             collector,
         }
     }
@@ -69,7 +72,7 @@ impl<S> Layer<S> for TraceLayer {
         TraceService {
             service,
             collector: self.collector.clone(),
-            metrics: Arc::clone(&self.metrics),
+            metrics: self.metrics.clone(),
             trace_header_parser: self.trace_header_parser.clone(),
         }
     }
@@ -81,7 +84,7 @@ pub struct TraceService<S> {
     service: S,
     trace_header_parser: TraceHeaderParser,
     collector: Option<Arc<dyn TraceCollector>>,
-    metrics: Arc<MetricsCollection>,
+    metrics: Option<Arc<MetricsCollection>>,
 }
 
 impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for TraceService<S>
@@ -98,7 +101,10 @@ where
     }
 
     fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
-        let metrics_recorder = Some(self.metrics.recorder(&request));
+        let metrics_recorder = Some(match &self.metrics {
+            Some(metrics) => metrics.recorder(&request),
+            None => MetricsRecorder::disabled(),
+        });
 
         let request_ctx = match self
             .trace_header_parser
@@ -205,11 +211,17 @@ where
 
         match result {
             Ok(mut response) => {
-                // add trace-id header to the response, if we have one
+                // add trace-id header to the response, if we have one, so that
+                // clients can find the trace they propagated, even if it is
+                // not sampled
                 let projected = self.as_mut().project();
                 let request_ctx = projected.request_ctx.take();
                 let span_recorder = projected.span_recorder.take();
-                if let Some(trace_id) = span_recorder.span().map(|span| span.ctx.trace_id) {
+                let trace_id = span_recorder
+                    .span()
+                    .map(|span| span.ctx.trace_id)
+                    .or_else(|| request_ctx.as_ref().map(|ctx| ctx.ctx().trace_id));
+                if let Some(trace_id) = trace_id {
                     // format as hex
                     let trace_id = HeaderValue::from_str(&format!("{:x}", trace_id.get())).unwrap();
                     response.headers_mut().insert("trace-id", trace_id);
@@ -183,6 +183,13 @@ impl InstrumentedAsyncSemaphore {
         self.acquire_impl(n, span).await
     }
 
+    /// Number of permits that are currently available.
+    ///
+    /// See [`tokio::sync::Semaphore::available_permits`] for details.
+    pub fn available_permits(&self) -> usize {
+        self.inner.available_permits()
+    }
+
     fn acquire_impl(
         &self,
         n: u32,
@@ -230,9 +230,11 @@ impl WriteBufferConfigFactory {
                     cfg.connection.clone(),
                     db_name.to_owned(),
                     &cfg.connection_config,
+                    Arc::clone(&self.time_provider),
                     cfg.creation_config.as_ref(),
                     partitions,
                     trace_collector.map(Arc::clone),
+                    &self.metric_registry,
                 )
                 .await?;
                 Arc::new(rskafka_buffer) as _
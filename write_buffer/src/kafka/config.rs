@@ -82,6 +82,12 @@ pub struct ConsumerConfig {
     ///
     /// Extracted from `consumer_max_batch_size`. Defaults to `None` (rskafka default).
     pub max_batch_size: Option<i32>,
+
+    /// The maximum number of records of a shard decoded concurrently, ahead of the consumer of
+    /// the shard's stream.
+    ///
+    /// Extracted from `consumer_decode_workers`. Defaults to `10`, must not be `0`.
+    pub decode_workers: usize,
 }
 
 impl TryFrom<&BTreeMap<String, String>> for ConsumerConfig {
@@ -96,6 +102,15 @@ impl TryFrom<&BTreeMap<String, String>> for ConsumerConfig {
             //
             //       max_batch_size: parse_key(cfg, "consumer_max_batch_size")?,
             max_batch_size: Some(parse_key(cfg, "consumer_max_batch_size")?.unwrap_or(5242880)),
+            decode_workers: match parse_key(cfg, "consumer_decode_workers")? {
+                Some(0) => {
+                    return Err(WriteBufferError::invalid_input(
+                        "`consumer_decode_workers` must not be 0",
+                    ))
+                }
+                Some(n) => n,
+                None => 10,
+            },
         })
     }
 }
@@ -257,6 +272,7 @@ mod tests {
             max_wait_ms: None,
             min_batch_size: None,
             max_batch_size: Some(5242880),
+            decode_workers: 10,
         };
         assert_eq!(actual, expected);
     }
@@ -267,6 +283,7 @@ mod tests {
             (String::from("consumer_max_wait_ms"), String::from("11")),
             (String::from("consumer_min_batch_size"), String::from("22")),
             (String::from("consumer_max_batch_size"), String::from("33")),
+            (String::from("consumer_decode_workers"), String::from("4")),
             (String::from("foo"), String::from("bar")),
         ]))
         .unwrap();
@@ -274,6 +291,7 @@ mod tests {
             max_wait_ms: Some(11),
             min_batch_size: Some(22),
             max_batch_size: Some(33),
+            decode_workers: 4,
         };
         assert_eq!(actual, expected);
     }
@@ -309,6 +327,13 @@ mod tests {
             err.to_string(),
             "Cannot parse `consumer_max_batch_size` from 'xyz': invalid digit found in string"
         );
+
+        let err = ConsumerConfig::try_from(&BTreeMap::from([(
+            String::from("consumer_decode_workers"),
+            String::from("0"),
+        )]))
+        .unwrap_err();
+        assert_contains!(err.to_string(), "`consumer_decode_workers` must not be 0");
     }
 
     #[test]
@@ -1,9 +1,9 @@
-use std::result::Result;
+use std::{result::Result, sync::Arc};
 
 use data_types::ShardIndex;
 use futures::future::BoxFuture;
-use iox_time::{SystemProvider, TimeProvider};
-use metric::{Attributes, DurationHistogram, U64Histogram, U64HistogramOptions};
+use iox_time::{SystemProvider, Time, TimeProvider};
+use metric::{Attributes, DurationHistogram, U64Gauge, U64Histogram, U64HistogramOptions};
 use rskafka::{
     client::{partition::Compression, producer::ProducerClient},
     record::Record,
@@ -145,6 +145,122 @@ where
     }
 }
 
+/// Metrics of the decode worker pool of a shard's consumer stream.
+///
+/// Records are read from Kafka, decoded concurrently by up to
+/// `consumer_decode_workers` tasks and handed over to the consumer of the
+/// stream in order. Reading stops while the pool is full, so an in-flight
+/// count at the number of workers means the decoding or the consumer of the
+/// stream applies backpressure to reading from Kafka.
+///
+/// The metrics created by this instrumentation are labelled with the kafka
+/// topic & partition specified at initialisation.
+#[derive(Debug)]
+pub struct KafkaDecodeMetrics {
+    time_provider: Arc<dyn TimeProvider>,
+
+    in_flight: U64Gauge,
+    decode_duration: DurationHistogram,
+    handover_lag: DurationHistogram,
+}
+
+impl KafkaDecodeMetrics {
+    pub fn new(
+        kafka_topic_name: String,
+        shard_index: ShardIndex,
+        time_provider: Arc<dyn TimeProvider>,
+        metrics: &metric::Registry,
+    ) -> Self {
+        let attr = Attributes::from([
+            ("kafka_partition", shard_index.to_string().into()),
+            ("kafka_topic", kafka_topic_name.into()),
+        ]);
+
+        let in_flight = metrics
+            .register_metric::<U64Gauge>(
+                "write_buffer_consumer_decode_in_flight",
+                "number of records read from kafka that were not yet handed \
+                 over to the consumer of the stream",
+            )
+            .recorder(attr.clone());
+        let decode_duration = metrics
+            .register_metric::<DurationHistogram>(
+                "write_buffer_consumer_decode_duration",
+                "duration of time taken to decode a record read from kafka",
+            )
+            .recorder(attr.clone());
+        let handover_lag = metrics
+            .register_metric::<DurationHistogram>(
+                "write_buffer_consumer_decode_lag",
+                "duration of time between reading a record from kafka and \
+                 handing it over to the consumer of the stream, including \
+                 waiting for the records read before it",
+            )
+            .recorder(attr);
+
+        Self {
+            time_provider,
+            in_flight,
+            decode_duration,
+            handover_lag,
+        }
+    }
+
+    /// Record that a record was read, returning the [`DecodeJob`] tracking it
+    /// until it is handed over.
+    pub fn read(self: &Arc<Self>) -> DecodeJob {
+        self.in_flight.inc(1);
+        DecodeJob {
+            metrics: Arc::clone(self),
+            read_at: self.time_provider.now(),
+        }
+    }
+}
+
+/// A record in the decode worker pool, see [`KafkaDecodeMetrics`].
+///
+/// The record leaves the pool when this is dropped.
+#[derive(Debug)]
+pub struct DecodeJob {
+    metrics: Arc<KafkaDecodeMetrics>,
+    read_at: Time,
+}
+
+impl DecodeJob {
+    /// Decode the record with `f`, recording the time it takes.
+    pub fn decode<T>(&self, f: impl FnOnce() -> T) -> T {
+        let started_at = self.metrics.time_provider.now();
+        let res = f();
+        if let Some(delta) = self
+            .metrics
+            .time_provider
+            .now()
+            .checked_duration_since(started_at)
+        {
+            self.metrics.decode_duration.record(delta);
+        }
+        res
+    }
+
+    /// Record that the record was handed over to the consumer of the stream.
+    pub fn handed_over(self) {
+        if let Some(delta) = self
+            .metrics
+            .time_provider
+            .now()
+            .checked_duration_since(self.read_at)
+        {
+            self.metrics.handover_lag.record(delta);
+        }
+    }
+}
+
+impl Drop for DecodeJob {
+    fn drop(&mut self) {
+        self.metrics.in_flight.dec(1);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{sync::Arc, time::Duration};
@@ -294,4 +410,62 @@ mod tests {
         assert_eq!(histogram.sample_count(), 1);
         assert_eq!(histogram.total, 0);
     }
+
+    #[tokio::test]
+    async fn test_decode_instrumentation() {
+        let clock = Arc::new(iox_time::MockProvider::new(Time::MIN));
+        let metrics = metric::Registry::default();
+        let decode_metrics = Arc::new(KafkaDecodeMetrics::new(
+            KAFKA_TOPIC.to_string(),
+            SHARD_INDEX,
+            Arc::clone(&clock) as _,
+            &metrics,
+        ));
+        let attr = Attributes::from(&[("kafka_topic", KAFKA_TOPIC), ("kafka_partition", "42")]);
+        let in_flight = || {
+            metrics
+                .get_instrument::<Metric<U64Gauge>>("write_buffer_consumer_decode_in_flight")
+                .expect("failed to read metric")
+                .get_observer(&attr)
+                .expect("failed to get observer")
+                .fetch()
+        };
+        let histogram = |name: &'static str| {
+            metrics
+                .get_instrument::<Metric<DurationHistogram>>(name)
+                .expect("failed to read metric")
+                .get_observer(&attr)
+                .expect("failed to get observer")
+                .fetch()
+        };
+
+        let job_1 = decode_metrics.read();
+        let job_2 = decode_metrics.read();
+        assert_eq!(in_flight(), 2);
+
+        clock.inc(CALL_LATENCY);
+        let decoded = job_1.decode(|| {
+            clock.inc(CALL_LATENCY);
+            42
+        });
+        assert_eq!(decoded, 42);
+        job_1.handed_over();
+        assert_eq!(in_flight(), 1);
+
+        let decode = histogram("write_buffer_consumer_decode_duration");
+        assert_eq!(decode.sample_count(), 1);
+        assert_eq!(decode.total, CALL_LATENCY);
+        let lag = histogram("write_buffer_consumer_decode_lag");
+        assert_eq!(lag.sample_count(), 1);
+        assert_eq!(lag.total, 2 * CALL_LATENCY);
+
+        // A job that is never handed over, e.g. because the stream is
+        // dropped, still leaves the pool.
+        drop(job_2);
+        assert_eq!(in_flight(), 0);
+        assert_eq!(
+            histogram("write_buffer_consumer_decode_lag").sample_count(),
+            1
+        );
+    }
 }
@@ -1,6 +1,6 @@
 use self::{
     config::{ClientConfig, ConsumerConfig, ProducerConfig, TopicCreationConfig},
-    instrumentation::KafkaProducerMetrics,
+    instrumentation::{DecodeJob, KafkaDecodeMetrics, KafkaProducerMetrics},
     record_aggregator::RecordAggregator,
 };
 use crate::{
@@ -45,9 +45,6 @@ mod config;
 mod instrumentation;
 mod record_aggregator;
 
-/// Maximum number of jobs buffered and decoded concurrently.
-const CONCURRENT_DECODE_JOBS: usize = 10;
-
 type Result<T, E = WriteBufferError> = std::result::Result<T, E>;
 
 #[derive(Debug)]
@@ -150,20 +147,27 @@ pub struct RSKafkaStreamHandler {
     trace_collector: Option<Arc<dyn TraceCollector>>,
     consumer_config: ConsumerConfig,
     shard_index: ShardIndex,
+    decode_metrics: Arc<KafkaDecodeMetrics>,
 }
 
 /// Launch a tokio task that attempts to decode a DmlOperation from a
 /// record.
 ///
-/// Returns the offset (if a record was read successfully) and the
-/// result of decoding. Note that `Some(offset)` is returned even if
-/// there is an error decoding the data in the record, but not if
-/// there was an error reading the record in the first place.
+/// Returns the offset (if a record was read successfully), the result
+/// of decoding and the decode `job`, unless the task panicked. Note that
+/// `Some(offset)` is returned even if there is an error decoding the data
+/// in the record, but not if there was an error reading the record in the
+/// first place.
 async fn try_decode(
     record: Result<RecordAndOffset, WriteBufferError>,
     shard_index: ShardIndex,
     trace_collector: Option<Arc<dyn TraceCollector>>,
-) -> (Option<i64>, Result<DmlOperation, WriteBufferError>) {
+    job: DecodeJob,
+) -> (
+    Option<i64>,
+    Result<DmlOperation, WriteBufferError>,
+    Option<DecodeJob>,
+) {
     let offset = match &record {
         Ok(record) => Some(record.offset),
         Err(_) => None,
@@ -172,38 +176,40 @@ async fn try_decode(
     // launch a task to try and do the decode (which is CPU intensive)
     // in parallel
     let result = tokio::task::spawn(async move {
-        let record = record?;
-        let kafka_read_size = record.record.approximate_size();
+        let res = job.decode(|| {
+            let record = record?;
+            let kafka_read_size = record.record.approximate_size();
 
-        let headers = IoxHeaders::from_headers(record.record.headers, trace_collector.as_ref())?;
+            let headers =
+                IoxHeaders::from_headers(record.record.headers, trace_collector.as_ref())?;
 
-        let sequence = Sequence {
-            shard_index,
-            sequence_number: SequenceNumber::new(record.offset),
-        };
+            let sequence = Sequence {
+                shard_index,
+                sequence_number: SequenceNumber::new(record.offset),
+            };
 
-        let timestamp = Time::from_date_time(record.record.timestamp);
+            let timestamp = Time::from_date_time(record.record.timestamp);
 
-        let value = record
-            .record
-            .value
-            .ok_or_else::<WriteBufferError, _>(|| "Value missing".to_string().into())?;
-        crate::codec::decode(&value, headers, sequence, timestamp, kafka_read_size)
+            let value = record
+                .record
+                .value
+                .ok_or_else::<WriteBufferError, _>(|| "Value missing".to_string().into())?;
+            crate::codec::decode(&value, headers, sequence, timestamp, kafka_read_size)
+        });
+        (res, job)
     })
     .await;
 
     // Convert panics in the task to WriteBufferErrors
-    let dml_result = match result {
+    match result {
         Err(e) => {
             warn!(%e, "Decode panic");
             // Was a join error (aka the task panic'd()
-            Err(WriteBufferError::unknown(e))
+            (offset, Err(WriteBufferError::unknown(e)), None)
         }
         // normal error in the task, use that
-        Ok(res) => res,
-    };
-
-    (offset, dml_result)
+        Ok((res, job)) => (offset, res, Some(job)),
+    }
 }
 
 #[async_trait]
@@ -240,6 +246,8 @@ impl WriteBufferStreamHandler for RSKafkaStreamHandler {
         let stream = stream_builder.build();
 
         let shard_index = self.shard_index;
+        let decode_workers = self.consumer_config.decode_workers;
+        let decode_metrics = Arc::clone(&self.decode_metrics);
 
         // Use buffered streams to pipeline the reading of a message from kafka from with its
         // decoding.
@@ -255,7 +263,7 @@ impl WriteBufferStreamHandler for RSKafkaStreamHandler {
         // │      Decode      │
         // │                  │
         // └──────────────────┘
-        //  ... up to 10 ..
+        //  ... up to `decode_workers` ..
         //    ┌──────────────────┐
         //    │                  │
         //    │      Decode      │
@@ -292,12 +300,12 @@ impl WriteBufferStreamHandler for RSKafkaStreamHandler {
             .map(move |record| {
                 // appease borrow checker
                 let trace_collector = trace_collector.clone();
-                try_decode(record, shard_index, trace_collector)
+                try_decode(record, shard_index, trace_collector, decode_metrics.read())
             })
             // the decode jobs in parallel
             // (`buffered` does NOT reorder, so the API user still gets an ordered stream)
-            .buffered(CONCURRENT_DECODE_JOBS)
-            .map(move |(offset, dml_result)| {
+            .buffered(decode_workers)
+            .map(move |(offset, dml_result, job)| {
                 // but only update the offset when a decoded recorded
                 // is actually returned to the consumer of the stream
                 // (not when it was decoded or when it was read from
@@ -311,6 +319,9 @@ impl WriteBufferStreamHandler for RSKafkaStreamHandler {
                 if let Some(offset) = offset {
                     *next_offset.lock() = Some(offset + 1);
                 }
+                if let Some(job) = job {
+                    job.handed_over();
+                }
                 dml_result
             });
         stream.boxed()
@@ -340,19 +351,22 @@ impl WriteBufferStreamHandler for RSKafkaStreamHandler {
 
 #[derive(Debug)]
 pub struct RSKafkaConsumer {
-    partition_clients: BTreeMap<ShardIndex, Arc<PartitionClient>>,
+    partition_clients: BTreeMap<ShardIndex, (Arc<PartitionClient>, Arc<KafkaDecodeMetrics>)>,
     trace_collector: Option<Arc<dyn TraceCollector>>,
     consumer_config: ConsumerConfig,
 }
 
 impl RSKafkaConsumer {
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         conn: String,
         topic_name: String,
         connection_config: &BTreeMap<String, String>,
+        time_provider: Arc<dyn TimeProvider>,
         creation_config: Option<&WriteBufferCreationConfig>,
         partitions: Option<Range<i32>>,
         trace_collector: Option<Arc<dyn TraceCollector>>,
+        metric_registry: &metric::Registry,
     ) -> Result<Self> {
         let partition_clients = setup_topic(
             conn,
@@ -365,7 +379,18 @@ impl RSKafkaConsumer {
 
         let partition_clients = partition_clients
             .into_iter()
-            .map(|(k, v)| (k, Arc::new(v)))
+            .map(|(shard_index, partition_client)| {
+                let decode_metrics = KafkaDecodeMetrics::new(
+                    topic_name.clone(),
+                    shard_index,
+                    Arc::clone(&time_provider),
+                    metric_registry,
+                );
+                (
+                    shard_index,
+                    (Arc::new(partition_client), Arc::new(decode_metrics)),
+                )
+            })
             .collect();
 
         Ok(Self {
@@ -386,12 +411,12 @@ impl WriteBufferReading for RSKafkaConsumer {
         &self,
         shard_index: ShardIndex,
     ) -> Result<Box<dyn WriteBufferStreamHandler>, WriteBufferError> {
-        let partition_client = self
+        let (partition_client, decode_metrics) = self
             .partition_clients
             .get(&shard_index)
             .ok_or_else::<WriteBufferError, _>(|| {
-                format!("Unknown shard index: {}", shard_index).into()
-            })?;
+            format!("Unknown shard index: {}", shard_index).into()
+        })?;
 
         Ok(Box::new(RSKafkaStreamHandler {
             partition_client: Arc::clone(partition_client),
@@ -400,6 +425,7 @@ impl WriteBufferReading for RSKafkaConsumer {
             trace_collector: self.trace_collector.clone(),
             consumer_config: self.consumer_config.clone(),
             shard_index,
+            decode_metrics: Arc::clone(decode_metrics),
         }))
     }
 
@@ -407,12 +433,12 @@ impl WriteBufferReading for RSKafkaConsumer {
         &self,
         shard_index: ShardIndex,
     ) -> Result<SequenceNumber, WriteBufferError> {
-        let partition_client = self
+        let (partition_client, _decode_metrics) = self
             .partition_clients
             .get(&shard_index)
             .ok_or_else::<WriteBufferError, _>(|| {
-                format!("Unknown shard index: {}", shard_index).into()
-            })?;
+            format!("Unknown shard index: {}", shard_index).into()
+        })?;
 
         let watermark = partition_client.get_offset(OffsetAt::Latest).await?;
         Ok(SequenceNumber::new(watermark))
@@ -615,9 +641,11 @@ mod tests {
                 self.conn.clone(),
                 self.topic_name.clone(),
                 &BTreeMap::default(),
+                Arc::clone(&self.time_provider),
                 self.creation_config(creation_config).as_ref(),
                 None,
                 Some(self.trace_collector() as Arc<_>),
+                &self.metrics,
             )
             .await
         }
@@ -1,7 +1,8 @@
-use data_types::{SequenceNumber, ShardIndex, ShardWriteStatus};
+use data_types::{PartitionKey, SequenceNumber, ShardIndex, ShardWriteStatus};
 use dml::DmlMeta;
 /// Protobuf to/from conversion
 use generated_types::influxdata::iox::write_summary::v1 as proto;
+use generated_types::write_summary::{decode_write_token, encode_write_token, WRITE_TOKEN_VERSION};
 use observability_deps::tracing::debug;
 use snafu::{OptionExt, Snafu};
 use std::collections::BTreeMap;
@@ -17,6 +18,57 @@ pub enum Error {
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// The rows of a single partition of a write, as enqueued into a shard.
+///
+/// The [`DmlMeta`] is the metadata returned by the write buffer for the
+/// operation containing the rows.
+#[derive(Debug, Clone)]
+pub struct PartitionWrite {
+    partition_key: PartitionKey,
+    row_count: usize,
+    meta: DmlMeta,
+}
+
+impl PartitionWrite {
+    pub fn new(partition_key: PartitionKey, row_count: usize, meta: DmlMeta) -> Self {
+        Self {
+            partition_key,
+            row_count,
+            meta,
+        }
+    }
+
+    /// The partition the rows belong to.
+    pub fn partition_key(&self) -> &PartitionKey {
+        &self.partition_key
+    }
+
+    /// The number of rows written to the partition.
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    /// The write buffer metadata of the operation containing the rows.
+    pub fn meta(&self) -> &DmlMeta {
+        &self.meta
+    }
+}
+
+/// The rows of a single partition of a write, sequenced into a shard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionSummary {
+    pub partition_key: PartitionKey,
+    pub sequence_number: SequenceNumber,
+    pub row_count: u64,
+}
+
+/// The parts of a write that were sequenced into a single shard.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct ShardSummary {
+    sequence_numbers: Vec<SequenceNumber>,
+    partitions: Vec<PartitionSummary>,
+}
+
 /// Contains information about a single write.
 ///
 /// A single write consisting of multiple lines of line protocol
@@ -25,59 +77,54 @@ pub type Result<T, E = Error> = std::result::Result<T, E>;
 /// become readable at potentially different times.
 ///
 /// This struct contains sufficient information to determine the
-/// current state of the write as a whole
+/// current state of the write as a whole, as well as the state of the
+/// write in each individual shard.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
-/// Summary of a Vec<Vec<DmlMeta>>
+/// Summary of a Vec<Vec<PartitionWrite>>
 pub struct WriteSummary {
     /// Key is the shard index from the DmlMeta structure (aka kafka
-    /// partition id), value is the sequence numbers from that
-    /// shard.
+    /// partition id), value is the sequence numbers and partitions
+    /// from that shard.
     ///
     /// Note: BTreeMap to ensure the output is in a consistent order
-    shards: BTreeMap<ShardIndex, Vec<SequenceNumber>>,
+    shards: BTreeMap<ShardIndex, ShardSummary>,
 }
 
 impl WriteSummary {
-    pub fn new(metas: Vec<Vec<DmlMeta>>) -> Self {
-        debug!(?metas, "Creating write summary");
-        let sequences = metas
-            .iter()
-            .flat_map(|v| v.iter())
-            .filter_map(|meta| meta.sequence());
+    pub fn new(writes: Vec<Vec<PartitionWrite>>) -> Self {
+        debug!(?writes, "Creating write summary");
+        let sequenced = writes
+            .into_iter()
+            .flatten()
+            .filter_map(|write| write.meta.sequence().copied().map(|s| (s, write)));
 
         let mut shards = BTreeMap::new();
-        for s in sequences {
-            let shard_index = s.shard_index;
-            let sequence_number = s.sequence_number;
-
-            shards
-                .entry(shard_index)
-                .or_insert_with(Vec::new)
-                .push(sequence_number)
+        for (s, write) in sequenced {
+            let shard: &mut ShardSummary = shards.entry(s.shard_index).or_default();
+
+            shard.sequence_numbers.push(s.sequence_number);
+            shard.partitions.push(PartitionSummary {
+                partition_key: write.partition_key,
+                sequence_number: s.sequence_number,
+                row_count: write.row_count as u64,
+            });
         }
 
         Self { shards }
     }
 
-    /// Return an opaque summary "token" of this summary
+    /// Return an opaque summary "token" of this summary.
+    ///
+    /// The token can be decoded into the [`proto::WriteSummary`] with
+    /// [`generated_types::write_summary::decode_write_token`].
     pub fn to_token(self) -> String {
         let proto_write_summary: proto::WriteSummary = self.into();
-        base64::encode(
-            serde_json::to_string(&proto_write_summary)
-                .expect("unexpected error serializing token to json"),
-        )
+        encode_write_token(&proto_write_summary)
     }
 
     /// Return a WriteSummary from the "token" (created with [Self::to_token]), or error if not possible
     pub fn try_from_token(token: &str) -> Result<Self, String> {
-        let data = base64::decode(token)
-            .map_err(|e| format!("Invalid write token, invalid base64: {}", e))?;
-
-        let json = String::from_utf8(data)
-            .map_err(|e| format!("Invalid write token, non utf8 data in write token: {}", e))?;
-
-        let proto = serde_json::from_str::<proto::WriteSummary>(&json)
-            .map_err(|e| format!("Invalid write token, protobuf decode error: {}", e))?;
+        let proto = decode_write_token(token).map_err(|e| format!("Invalid write token, {}", e))?;
 
         proto
             .try_into()
@@ -89,6 +136,32 @@ impl WriteSummary {
         self.shards.keys().cloned().collect()
     }
 
+    /// Return the sequence numbers of the write in the shard with
+    /// `shard_index`, or `None` if the write has no data in that shard.
+    pub fn sequence_numbers(&self, shard_index: ShardIndex) -> Option<&[SequenceNumber]> {
+        self.shards
+            .get(&shard_index)
+            .map(|shard| shard.sequence_numbers.as_slice())
+    }
+
+    /// Return the partitions of the write that were sequenced into the
+    /// shard with `shard_index`, or `None` if the write has no data in that
+    /// shard.
+    pub fn partitions(&self, shard_index: ShardIndex) -> Option<&[PartitionSummary]> {
+        self.shards
+            .get(&shard_index)
+            .map(|shard| shard.partitions.as_slice())
+    }
+
+    /// Return the total number of rows of the write, across all shards.
+    pub fn row_count(&self) -> u64 {
+        self.shards
+            .values()
+            .flat_map(|shard| shard.partitions.iter())
+            .map(|partition| partition.row_count)
+            .sum()
+    }
+
     /// Given the write described by this summary, and the shard's progress for a particular
     /// shard index, returns the status of that write in this write summary
     pub fn write_status(
@@ -97,8 +170,7 @@ impl WriteSummary {
         progress: &ShardProgress,
     ) -> Result<ShardWriteStatus> {
         let sequence_numbers = self
-            .shards
-            .get(&shard_index)
+            .sequence_numbers(shard_index)
             .context(UnknownShardSnafu { shard_index })?;
 
         debug!(?shard_index, ?progress, ?sequence_numbers, "write_status");
@@ -132,13 +204,29 @@ impl From<WriteSummary> for proto::WriteSummary {
         let shards = summary
             .shards
             .into_iter()
-            .map(|(shard_index, sequence_numbers)| proto::ShardWrite {
+            .map(|(shard_index, shard)| proto::ShardWrite {
                 shard_index: shard_index.get(),
-                sequence_numbers: sequence_numbers.into_iter().map(|v| v.get()).collect(),
+                sequence_numbers: shard
+                    .sequence_numbers
+                    .into_iter()
+                    .map(|v| v.get())
+                    .collect(),
+                partitions: shard
+                    .partitions
+                    .into_iter()
+                    .map(|p| proto::PartitionWrite {
+                        partition_key: p.partition_key.to_string(),
+                        sequence_number: p.sequence_number.get(),
+                        row_count: p.row_count,
+                    })
+                    .collect(),
             })
             .collect();
 
-        Self { shards }
+        Self {
+            shards,
+            version: WRITE_TOKEN_VERSION,
+        }
     }
 }
 
@@ -153,13 +241,38 @@ impl TryFrom<proto::WriteSummary> for WriteSummary {
                 |proto::ShardWrite {
                      shard_index,
                      sequence_numbers,
+                     partitions,
                  }| {
                     let sequence_numbers = sequence_numbers
                         .into_iter()
                         .map(SequenceNumber::new)
                         .collect::<Vec<_>>();
 
-                    Ok((ShardIndex::new(shard_index), sequence_numbers))
+                    let partitions = partitions
+                        .into_iter()
+                        .map(|p| {
+                            if p.partition_key.is_empty() {
+                                return Err(format!(
+                                    "empty partition key in shard index {}",
+                                    shard_index
+                                ));
+                            }
+
+                            Ok(PartitionSummary {
+                                partition_key: p.partition_key.into(),
+                                sequence_number: SequenceNumber::new(p.sequence_number),
+                                row_count: p.row_count,
+                            })
+                        })
+                        .collect::<Result<Vec<_>, String>>()?;
+
+                    Ok((
+                        ShardIndex::new(shard_index),
+                        ShardSummary {
+                            sequence_numbers,
+                            partitions,
+                        },
+                    ))
                 },
             )
             .collect::<Result<BTreeMap<_, _>, String>>()?;
@@ -178,14 +291,17 @@ mod tests {
         let metas = vec![];
         let summary: proto::WriteSummary = WriteSummary::new(metas).into();
 
-        let expected = proto::WriteSummary { shards: vec![] };
+        let expected = proto::WriteSummary {
+            shards: vec![],
+            version: WRITE_TOKEN_VERSION,
+        };
 
         assert_eq!(summary, expected);
     }
 
     #[test]
     fn one() {
-        let metas = vec![vec![make_meta(Sequence::new(
+        let metas = vec![vec![make_write(Sequence::new(
             ShardIndex::new(1),
             SequenceNumber::new(2),
         ))]];
@@ -195,7 +311,9 @@ mod tests {
             shards: vec![proto::ShardWrite {
                 shard_index: 1,
                 sequence_numbers: vec![2],
+                partitions: vec![proto_partition(2)],
             }],
+            version: WRITE_TOKEN_VERSION,
         };
 
         assert_eq!(summary, expected);
@@ -205,10 +323,10 @@ mod tests {
     fn many() {
         let metas = vec![
             vec![
-                make_meta(Sequence::new(ShardIndex::new(1), SequenceNumber::new(2))),
-                make_meta(Sequence::new(ShardIndex::new(10), SequenceNumber::new(20))),
+                make_write(Sequence::new(ShardIndex::new(1), SequenceNumber::new(2))),
+                make_write(Sequence::new(ShardIndex::new(10), SequenceNumber::new(20))),
             ],
-            vec![make_meta(Sequence::new(
+            vec![make_write(Sequence::new(
                 ShardIndex::new(1),
                 SequenceNumber::new(3),
             ))],
@@ -220,29 +338,80 @@ mod tests {
                 proto::ShardWrite {
                     shard_index: 1,
                     sequence_numbers: vec![2, 3],
+                    partitions: vec![proto_partition(2), proto_partition(3)],
                 },
                 proto::ShardWrite {
                     shard_index: 10,
                     sequence_numbers: vec![20],
+                    partitions: vec![proto_partition(20)],
                 },
             ],
+            version: WRITE_TOKEN_VERSION,
         };
 
         assert_eq!(summary, expected);
     }
 
+    #[test]
+    fn per_shard_detail() {
+        let metas = vec![
+            vec![
+                make_write(Sequence::new(ShardIndex::new(1), SequenceNumber::new(2))),
+                make_write(Sequence::new(ShardIndex::new(10), SequenceNumber::new(20))),
+            ],
+            vec![PartitionWrite::new(
+                PartitionKey::from("2022-12-15"),
+                5,
+                make_write(Sequence::new(ShardIndex::new(1), SequenceNumber::new(3)))
+                    .meta()
+                    .clone(),
+            )],
+        ];
+        let summary = WriteSummary::new(metas);
+
+        assert_eq!(
+            summary.sequence_numbers(ShardIndex::new(1)),
+            Some([SequenceNumber::new(2), SequenceNumber::new(3)].as_slice())
+        );
+        assert_eq!(
+            summary.partitions(ShardIndex::new(1)),
+            Some(
+                [
+                    PartitionSummary {
+                        partition_key: PartitionKey::from("2022-12-14"),
+                        sequence_number: SequenceNumber::new(2),
+                        row_count: 10,
+                    },
+                    PartitionSummary {
+                        partition_key: PartitionKey::from("2022-12-15"),
+                        sequence_number: SequenceNumber::new(3),
+                        row_count: 5,
+                    },
+                ]
+                .as_slice()
+            )
+        );
+        assert_eq!(summary.sequence_numbers(ShardIndex::new(2)), None);
+        assert_eq!(summary.partitions(ShardIndex::new(2)), None);
+        assert_eq!(summary.row_count(), 25);
+
+        // the detail survives the token round trip
+        let new_summary = WriteSummary::try_from_token(&summary.clone().to_token()).unwrap();
+        assert_eq!(summary, new_summary);
+    }
+
     #[test]
     fn different_order() {
         // order in sequences shouldn't matter
         let metas1 = vec![vec![
-            make_meta(Sequence::new(ShardIndex::new(1), SequenceNumber::new(2))),
-            make_meta(Sequence::new(ShardIndex::new(2), SequenceNumber::new(3))),
+            make_write(Sequence::new(ShardIndex::new(1), SequenceNumber::new(2))),
+            make_write(Sequence::new(ShardIndex::new(2), SequenceNumber::new(3))),
         ]];
 
         // order in sequences shouldn't matter
         let metas2 = vec![vec![
-            make_meta(Sequence::new(ShardIndex::new(2), SequenceNumber::new(3))),
-            make_meta(Sequence::new(ShardIndex::new(1), SequenceNumber::new(2))),
+            make_write(Sequence::new(ShardIndex::new(2), SequenceNumber::new(3))),
+            make_write(Sequence::new(ShardIndex::new(1), SequenceNumber::new(2))),
         ]];
 
         let summary1: proto::WriteSummary = WriteSummary::new(metas1).into();
@@ -253,12 +422,15 @@ mod tests {
                 proto::ShardWrite {
                     shard_index: 1,
                     sequence_numbers: vec![2],
+                    partitions: vec![proto_partition(2)],
                 },
                 proto::ShardWrite {
                     shard_index: 2,
                     sequence_numbers: vec![3],
+                    partitions: vec![proto_partition(3)],
                 },
             ],
+            version: WRITE_TOKEN_VERSION,
         };
 
         assert_eq!(summary1, expected);
@@ -267,14 +439,14 @@ mod tests {
 
     #[test]
     fn token_creation() {
-        let metas = vec![vec![make_meta(Sequence::new(
+        let metas = vec![vec![make_write(Sequence::new(
             ShardIndex::new(1),
             SequenceNumber::new(2),
         ))]];
         let summary = WriteSummary::new(metas.clone());
         let summary_copy = WriteSummary::new(metas);
 
-        let metas2 = vec![vec![make_meta(Sequence::new(
+        let metas2 = vec![vec![make_write(Sequence::new(
             ShardIndex::new(2),
             SequenceNumber::new(3),
         ))]];
@@ -301,7 +473,7 @@ mod tests {
 
     #[test]
     fn token_parsing() {
-        let metas = vec![vec![make_meta(Sequence::new(
+        let metas = vec![vec![make_write(Sequence::new(
             ShardIndex::new(1),
             SequenceNumber::new(2),
         ))]];
@@ -322,19 +494,36 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Invalid write token, non utf8 data in write token")]
-    fn token_parsing_bad_utf8() {
+    #[should_panic(expected = "Invalid write token, protobuf decode error")]
+    fn token_parsing_bad_proto() {
         let token = base64::encode(vec![0xa0, 0xa1]);
         WriteSummary::try_from_token(&token).unwrap();
     }
 
     #[test]
-    #[should_panic(expected = "Invalid write token, protobuf decode error: key must be a string")]
-    fn token_parsing_bad_proto() {
+    #[should_panic(expected = "Invalid write token, json decode error: key must be a string")]
+    fn token_parsing_bad_json() {
         let token = base64::encode("{not_valid_json}");
         WriteSummary::try_from_token(&token).unwrap();
     }
 
+    #[test]
+    #[should_panic(expected = "Invalid write token, unsupported write token version")]
+    fn token_parsing_unsupported_version() {
+        let token = base64::encode(r#"{"shards":[],"version":1000}"#);
+        WriteSummary::try_from_token(&token).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid write token, invalid content: empty partition key")]
+    fn token_parsing_empty_partition_key() {
+        let mut summary: proto::WriteSummary = test_summary().into();
+        summary.shards[0].partitions[0].partition_key = String::new();
+
+        let token = generated_types::write_summary::encode_write_token(&summary);
+        WriteSummary::try_from_token(&token).unwrap();
+    }
+
     #[test]
     fn no_progress() {
         let summary = test_summary();
@@ -426,19 +615,28 @@ mod tests {
     /// shard 2 --> sequence 1
     fn test_summary() -> WriteSummary {
         let metas = vec![vec![
-            make_meta(Sequence::new(ShardIndex::new(1), SequenceNumber::new(2))),
-            make_meta(Sequence::new(ShardIndex::new(1), SequenceNumber::new(3))),
-            make_meta(Sequence::new(ShardIndex::new(2), SequenceNumber::new(1))),
+            make_write(Sequence::new(ShardIndex::new(1), SequenceNumber::new(2))),
+            make_write(Sequence::new(ShardIndex::new(1), SequenceNumber::new(3))),
+            make_write(Sequence::new(ShardIndex::new(2), SequenceNumber::new(1))),
         ]];
         WriteSummary::new(metas)
     }
 
-    fn make_meta(s: Sequence) -> DmlMeta {
+    fn make_write(s: Sequence) -> PartitionWrite {
         use iox_time::TimeProvider;
         let time_provider = iox_time::SystemProvider::new();
 
         let span_context = None;
         let bytes_read = 132;
-        DmlMeta::sequenced(s, time_provider.now(), span_context, bytes_read)
+        let meta = DmlMeta::sequenced(s, time_provider.now(), span_context, bytes_read);
+        PartitionWrite::new(PartitionKey::from("2022-12-14"), 10, meta)
+    }
+
+    fn proto_partition(sequence_number: i64) -> proto::PartitionWrite {
+        proto::PartitionWrite {
+            partition_key: "2022-12-14".to_string(),
+            sequence_number,
+            row_count: 10,
+        }
     }
 }